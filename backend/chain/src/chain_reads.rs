@@ -0,0 +1,171 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use web3::types::{Address, H256, U256};
+
+/// A chain read the client caches, with its arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReadKey {
+    LatestBatchId,
+    BatchRoots(u64),
+    UsdcBalance(Address),
+}
+
+impl ReadKey {
+    /// Client method the read comes from, used to label the metrics
+    pub fn method(&self) -> &'static str {
+        match self {
+            ReadKey::LatestBatchId => "get_latest_batch_id",
+            ReadKey::BatchRoots(_) => "get_batch_roots",
+            ReadKey::UsdcBalance(_) => "get_usdc_balance",
+        }
+    }
+}
+
+/// Result of a cached read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadValue {
+    BatchId(u64),
+    BatchRoots(H256, H256),
+    Balance(U256),
+}
+
+/// Hit and miss counts for one client method
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MethodCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped because one of our own submissions changed them
+    pub invalidations: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReadCacheMetrics {
+    pub ttl_seconds: u64,
+    pub entries: usize,
+    pub methods: BTreeMap<String, MethodCacheStats>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<ReadKey, (Instant, ReadValue)>,
+    methods: BTreeMap<String, MethodCacheStats>,
+}
+
+/// Shared TTL cache for repeated on-chain reads, written by the chain client and read by the API
+///
+/// A TTL of 0 disables caching; reads are still counted as misses.
+#[derive(Clone, Default)]
+pub struct ChainReadCache {
+    ttl: Duration,
+    inner: Arc<RwLock<CacheState>>,
+}
+
+impl ChainReadCache {
+    pub fn new(ttl_seconds: u64) -> Self {
+        Self { ttl: Duration::from_secs(ttl_seconds), inner: Arc::default() }
+    }
+
+    /// Cached value for a read, if still fresh
+    pub fn get(&self, key: ReadKey) -> Option<ReadValue> {
+        self.get_at(key, Instant::now())
+    }
+
+    /// Remember the value a read returned
+    pub fn put(&self, key: ReadKey, value: ReadValue) {
+        self.put_at(key, value, Instant::now())
+    }
+
+    /// Drop the cached entries matching `stale`, after a submission that changes them
+    pub fn invalidate(&self, stale: impl Fn(&ReadKey) -> bool) {
+        let mut state = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        let keys: Vec<ReadKey> = state.entries.keys().filter(|key| stale(key)).copied().collect();
+        for key in keys {
+            state.entries.remove(&key);
+            state.methods.entry(key.method().to_string()).or_default().invalidations += 1;
+        }
+    }
+
+    pub fn snapshot(&self) -> ReadCacheMetrics {
+        let state = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        ReadCacheMetrics {
+            ttl_seconds: self.ttl.as_secs(),
+            entries: state.entries.values().filter(|(expires_at, _)| *expires_at > now).count(),
+            methods: state.methods.clone(),
+        }
+    }
+
+    fn get_at(&self, key: ReadKey, now: Instant) -> Option<ReadValue> {
+        let mut state = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        let value = match state.entries.get(&key) {
+            Some((expires_at, value)) if *expires_at > now => Some(*value),
+            Some(_) => {
+                state.entries.remove(&key);
+                None
+            }
+            None => None,
+        };
+        let stats = state.methods.entry(key.method().to_string()).or_default();
+        match value {
+            Some(_) => stats.hits += 1,
+            None => stats.misses += 1,
+        }
+        value
+    }
+
+    fn put_at(&self, key: ReadKey, value: ReadValue, now: Instant) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut state = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        state.entries.insert(key, (now + self.ttl, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_are_cached_until_their_ttl() {
+        let cache = ChainReadCache::new(5);
+        let start = Instant::now();
+        assert_eq!(cache.get_at(ReadKey::LatestBatchId, start), None);
+        cache.put_at(ReadKey::LatestBatchId, ReadValue::BatchId(7), start);
+
+        assert_eq!(cache.get_at(ReadKey::LatestBatchId, start + Duration::from_secs(4)), Some(ReadValue::BatchId(7)));
+        assert_eq!(cache.get_at(ReadKey::BatchRoots(7), start), None);
+        assert_eq!(cache.get_at(ReadKey::LatestBatchId, start + Duration::from_secs(5)), None);
+
+        let metrics = cache.snapshot();
+        assert_eq!(metrics.entries, 0);
+        let latest = &metrics.methods["get_latest_batch_id"];
+        assert_eq!((latest.hits, latest.misses), (1, 2));
+        assert_eq!(metrics.methods["get_batch_roots"].misses, 1);
+    }
+
+    #[test]
+    fn test_invalidation_drops_only_matching_reads() {
+        let cache = ChainReadCache::new(60);
+        let holder = Address::from_low_u64_be(1);
+        cache.put(ReadKey::LatestBatchId, ReadValue::BatchId(1));
+        cache.put(ReadKey::BatchRoots(1), ReadValue::BatchRoots(H256::zero(), H256::zero()));
+        cache.put(ReadKey::UsdcBalance(holder), ReadValue::Balance(U256::from(10)));
+
+        cache.invalidate(|key| matches!(key, ReadKey::LatestBatchId | ReadKey::BatchRoots(2)));
+        assert_eq!(cache.get(ReadKey::LatestBatchId), None);
+        assert_eq!(cache.get(ReadKey::BatchRoots(1)), Some(ReadValue::BatchRoots(H256::zero(), H256::zero())));
+        assert_eq!(cache.get(ReadKey::UsdcBalance(holder)), Some(ReadValue::Balance(U256::from(10))));
+        assert_eq!(cache.snapshot().methods["get_latest_batch_id"].invalidations, 1);
+    }
+
+    #[test]
+    fn test_zero_ttl_disables_caching() {
+        let cache = ChainReadCache::new(0);
+        cache.put(ReadKey::LatestBatchId, ReadValue::BatchId(3));
+        assert_eq!(cache.get(ReadKey::LatestBatchId), None);
+        assert_eq!(cache.snapshot().methods["get_latest_batch_id"].misses, 1);
+    }
+}
@@ -1,13 +1,45 @@
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use tracing::{info, error};
+use std::sync::Arc;
+use tracing::{info, error, instrument};
 use web3::{
     contract::{Contract, Options},
     transports::Http,
-    types::{Address, U256, H256, Bytes, BlockNumber},
+    types::{Address, U256, H256, Bytes, BlockId, BlockNumber, FilterBuilder},
     Web3,
 };
 
+use vapor_core::fault_injection::{inject, FaultTarget};
+use crate::event_abi::{AbiVersionMetrics, DepositDecoder};
+use crate::chain_reads::{ChainReadCache, ReadKey, ReadValue};
+use crate::signer::{Signer, signature_to_hex};
+
+/// Errors talking to the chain or preparing data for it
+#[derive(Debug, thiserror::Error)]
+pub enum ChainError {
+    #[error("RPC transport error: {0}")]
+    Transport(#[from] web3::Error),
+    #[error("Contract call failed: {0}")]
+    Contract(#[from] web3::contract::Error),
+    #[error("Invalid contract ABI: {0}")]
+    Abi(#[from] web3::ethabi::Error),
+    #[error("No signer configured for blockchain submissions")]
+    NoSigner,
+    /// Sending would fail for lack of gas; refused before signing
+    #[error("Signer {signer:?} holds {balance} wei, less than the {required} wei a submission may cost")]
+    InsufficientFunds { signer: Address, balance: U256, required: U256 },
+    #[error("Failed to sign submission: {0}")]
+    Signing(anyhow::Error),
+    #[error("Invalid hex: {0}")]
+    InvalidHex(#[from] hex::FromHexError),
+    #[error("Invalid hex length for {0}")]
+    InvalidLength(&'static str),
+    /// Failure injected by the fault-injection harness
+    #[error("{0}")]
+    Injected(anyhow::Error),
+}
+
+type Result<T> = std::result::Result<T, ChainError>;
+
 /// Blockchain client for interacting with Vapor smart contracts
 pub struct BlockchainClient {
     /// Web3 instance for Ethereum interactions
@@ -20,6 +52,12 @@ pub struct BlockchainClient {
     pub addresses: ContractAddresses,
     /// Chain configuration
     pub chain_config: ChainConfig,
+    /// Signer used for outgoing transactions
+    pub signer: Option<Arc<dyn Signer>>,
+    /// Deposit decoding counters per bridge ABI version
+    pub event_metrics: AbiVersionMetrics,
+    /// Short-lived cache for reads polled by status endpoints
+    pub read_cache: ChainReadCache,
 }
 
 /// Contract addresses on the blockchain
@@ -40,11 +78,36 @@ pub struct ChainConfig {
     pub gas_limit: U256,
 }
 
+/// How far a submission got: signed by the operator, or sent with a transaction hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Submission {
+    /// Signed but not broadcast, so there is no transaction hash yet
+    Signed {
+        signer: Address,
+        /// keccak256 of the signed payload
+        payload_hash: H256,
+        /// 65-byte r || s || v hex
+        signature: String,
+    },
+    Sent { transaction_hash: H256 },
+}
+
+impl Submission {
+    /// Hash of the transaction, once it has been sent
+    pub fn transaction_hash(&self) -> Option<H256> {
+        match self {
+            Submission::Signed { .. } => None,
+            Submission::Sent { transaction_hash } => Some(*transaction_hash),
+        }
+    }
+}
+
 /// Result of submitting a proof to the blockchain
 #[derive(Debug, Serialize)]
 pub struct ProofSubmissionResult {
-    pub transaction_hash: H256,
-    pub batch_id: u32,
+    pub submission: Submission,
+    pub batch_id: u64,
     pub gas_used: Option<U256>,
     pub success: bool,
 }
@@ -64,7 +127,7 @@ pub struct DepositEvent {
 #[derive(Debug, Clone, Serialize)]
 pub struct ClaimEvent {
     pub user: Address,
-    pub batch_id: u32,
+    pub batch_id: u64,
     pub order_id: u32,
     pub amount: U256,
     pub block_number: u64,
@@ -124,14 +187,85 @@ impl BlockchainClient {
             proof_verifier_contract,
             addresses,
             chain_config,
+            signer: None,
+            event_metrics: AbiVersionMetrics::default(),
+            read_cache: ChainReadCache::default(),
+        })
+    }
+
+    /// Attach the signer used for proof and claim submissions
+    pub fn with_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Count decoded deposits per bridge ABI version on the given handle (shared with the API)
+    pub fn with_event_metrics(mut self, metrics: AbiVersionMetrics) -> Self {
+        self.event_metrics = metrics;
+        self
+    }
+
+    /// Cache repeated reads on the given handle (shared with the API)
+    pub fn with_read_cache(mut self, cache: ChainReadCache) -> Self {
+        self.read_cache = cache;
+        self
+    }
+
+    /// Sign a submission payload with the configured signer, without broadcasting it
+    async fn sign_submission(&self, payload: &[u8]) -> Result<Submission> {
+        let signer = self.signer.as_ref()
+            .ok_or(ChainError::NoSigner)?;
+
+        let digest = H256::from(web3::signing::keccak256(payload));
+        let signature = signer.sign_digest(digest, Some(self.chain_config.chain_id))
+            .await
+            .map_err(ChainError::Signing)?;
+
+        Ok(Submission::Signed {
+            signer: signer.address(),
+            payload_hash: digest,
+            signature: signature_to_hex(&signature).map_err(ChainError::Signing)?,
         })
     }
 
+    /// Gas price submissions are priced at: the configured one, else the network's
+    pub async fn submission_gas_price(&self) -> Result<U256> {
+        match self.chain_config.gas_price {
+            Some(gas_price) => Ok(gas_price),
+            None => self.get_gas_price().await,
+        }
+    }
+
+    /// Most one submission can cost: the gas limit at the submission gas price
+    pub async fn estimated_submission_cost(&self) -> Result<U256> {
+        Ok(self.chain_config.gas_limit.saturating_mul(self.submission_gas_price().await?))
+    }
+
+    /// ETH balance of an address in wei
+    pub async fn get_eth_balance(&self, address: Address) -> Result<U256> {
+        Ok(self.web3.eth().balance(address, None).await?)
+    }
+
+    /// Refuse to sign a submission the signer cannot pay gas for, rather than have it fail at send time
+    async fn ensure_signer_funded(&self) -> Result<()> {
+        let signer = self.signer.as_ref().ok_or(ChainError::NoSigner)?.address();
+        let balance = self.get_eth_balance(signer).await?;
+        let required = self.estimated_submission_cost().await?;
+        if balance < required {
+            error!(alert = "signer_funds_low", signer = ?signer, %balance, %required, "Refusing submission: signer cannot cover its gas");
+            return Err(ChainError::InsufficientFunds { signer, balance, required });
+        }
+        Ok(())
+    }
+
     /// Submit a batch proof to the proof verifier contract
+    // Mirrors the arguments of ProofVerifier.submitProof
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip_all, fields(batch_id = batch_id))]
     pub async fn submit_proof(
         &self,
-        batch_id: u32,
-        prev_batch_id: u32,
+        batch_id: u64,
+        prev_batch_id: u64,
         prev_state_root: H256,
         prev_orders_root: H256,
         new_state_root: H256,
@@ -139,6 +273,9 @@ impl BlockchainClient {
         proof: Bytes,
     ) -> Result<ProofSubmissionResult> {
         info!("Submitting proof for batch {} to proof verifier", batch_id);
+        inject(FaultTarget::Rpc).await.map_err(ChainError::Injected)?;
+        self.ensure_signer_funded().await?;
+
 
         // For MVP, return a mock result since web3 contract interaction is complex
         // In a real implementation, you'd call the proof_verifier_contract.call() method:
@@ -156,46 +293,95 @@ impl BlockchainClient {
             .await?;
         */
         
-        let mock_tx_hash = H256::from_low_u64_be(batch_id as u64);
-        
-        info!("Proof submitted! Transaction hash: {:?}", mock_tx_hash);
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&batch_id.to_be_bytes());
+        payload.extend_from_slice(&prev_batch_id.to_be_bytes());
+        payload.extend_from_slice(prev_state_root.as_bytes());
+        payload.extend_from_slice(prev_orders_root.as_bytes());
+        payload.extend_from_slice(new_state_root.as_bytes());
+        payload.extend_from_slice(new_orders_root.as_bytes());
+        payload.extend_from_slice(&proof.0);
+
+        // Transaction is signed but not broadcast yet, so there is no hash or receipt
+        let submission = self.sign_submission(&payload).await?;
+
+        info!("Proof for batch {} signed: {:?}", batch_id, submission);
+        self.read_cache.invalidate(|key| matches!(key, ReadKey::LatestBatchId) || *key == ReadKey::BatchRoots(batch_id));
 
         Ok(ProofSubmissionResult {
-            transaction_hash: mock_tx_hash,
+            submission,
             batch_id,
-            gas_used: Some(U256::from(200_000)),
+            gas_used: None,
             success: true,
         })
     }
 
+    /// Submit a batch claim to the bridge contract (batchClaim)
+    #[instrument(skip_all, fields(batch_id = batch_id, payload_bytes = claims_payload.len()))]
+    pub async fn submit_batch_claim(&self, batch_id: u64, claims_payload: &[u8]) -> Result<Submission> {
+        info!("Submitting batch claim for batch {} to bridge", batch_id);
+        inject(FaultTarget::Rpc).await.map_err(ChainError::Injected)?;
+        self.ensure_signer_funded().await?;
+
+
+        let mut payload = batch_id.to_be_bytes().to_vec();
+        payload.extend_from_slice(claims_payload);
+
+        let submission = self.sign_submission(&payload).await?;
+
+        info!("Batch claim for batch {} signed: {:?}", batch_id, submission);
+        // Claims pay out of the bridge, so any cached balance may be stale
+        self.read_cache.invalidate(|key| matches!(key, ReadKey::UsdcBalance(_)));
+        Ok(submission)
+    }
+
     /// Get the latest batch ID from the proof verifier contract
-    pub async fn get_latest_batch_id(&self) -> Result<u32> {
+    pub async fn get_latest_batch_id(&self) -> Result<u64> {
+        if let Some(ReadValue::BatchId(batch_id)) = self.read_cache.get(ReadKey::LatestBatchId) {
+            return Ok(batch_id);
+        }
         let result: U256 = self.proof_verifier_contract
             .query("getLatestBatchId", (), None, Options::default(), None)
             .await?;
 
-        Ok(result.as_u32())
+        self.read_cache.put(ReadKey::LatestBatchId, ReadValue::BatchId(result.as_u64()));
+        Ok(result.as_u64())
     }
 
     /// Get batch roots for a specific batch ID from proof verifier
-    pub async fn get_batch_roots(&self, batch_id: u32) -> Result<(H256, H256)> {
+    pub async fn get_batch_roots(&self, batch_id: u64) -> Result<(H256, H256)> {
+        if let Some(ReadValue::BatchRoots(state_root, orders_root)) = self.read_cache.get(ReadKey::BatchRoots(batch_id)) {
+            return Ok((state_root, orders_root));
+        }
         let result: (H256, H256) = self.proof_verifier_contract
             .query("getBatch", batch_id, None, Options::default(), None)
             .await?;
 
+        self.read_cache.put(ReadKey::BatchRoots(batch_id), ReadValue::BatchRoots(result.0, result.1));
         Ok(result)
     }
 
-    /// Listen for deposit events (simplified implementation)
-    pub async fn get_deposit_events(&self, from_block: u64, _to_block: Option<u64>) -> Result<Vec<DepositEvent>> {
+    /// Deposits made to the bridge in a block range, decoded whichever `Deposited` ABI version emitted them
+    pub async fn get_deposit_events(&self, from_block: u64, to_block: Option<u64>) -> Result<Vec<DepositEvent>> {
         info!("Getting deposit events from block {}", from_block);
-        
-        // For MVP, return mock events
-        // In production, you'd use proper event filtering with web3.eth().logs()
-        let mock_events = vec![];
-        
-        info!("Found {} deposit events from block {}", mock_events.len(), from_block);
-        Ok(mock_events)
+        inject(FaultTarget::Rpc).await.map_err(ChainError::Injected)?;
+
+        let filter = FilterBuilder::default()
+            .address(vec![self.addresses.bridge])
+            .from_block(BlockNumber::Number(from_block.into()))
+            .to_block(to_block.map_or(BlockNumber::Latest, |block| BlockNumber::Number(block.into())))
+            .build();
+        let logs = self.web3.eth().logs(filter).await?;
+
+        // Every bridge log is fetched so that deposits from an upgraded contract are
+        // reported as unknown signatures instead of silently filtered out
+        let other_events = self.bridge_contract.abi().events()
+            .filter(|event| event.name != "Deposited")
+            .map(|event| event.signature());
+        let events = DepositDecoder::new(other_events, self.event_metrics.clone()).decode(&logs);
+
+        info!("Found {} deposit events in {} bridge logs from block {}", events.len(), logs.len(), from_block);
+        Ok(events)
     }
 
     /// Listen for claim events (simplified implementation)
@@ -212,10 +398,33 @@ impl BlockchainClient {
 
     /// Get current block number
     pub async fn get_block_number(&self) -> Result<u64> {
+        inject(FaultTarget::Rpc).await.map_err(ChainError::Injected)?;
         let block_number = self.web3.eth().block_number().await?;
         Ok(block_number.as_u64())
     }
 
+    /// Hash of a block, or None if the chain does not have it (yet)
+    pub async fn get_block_hash(&self, block_number: u64) -> Result<Option<H256>> {
+        inject(FaultTarget::Rpc).await.map_err(ChainError::Injected)?;
+        let block = self.web3.eth().block(BlockId::Number(BlockNumber::Number(block_number.into()))).await?;
+        Ok(block.and_then(|block| block.hash))
+    }
+
+    /// Number of the block a tag such as `finalized` or `safe` points at, or None if the node
+    /// does not serve it
+    pub async fn get_tagged_block_number(&self, tag: BlockNumber) -> Result<Option<u64>> {
+        inject(FaultTarget::Rpc).await.map_err(ChainError::Injected)?;
+        let block = self.web3.eth().block(BlockId::Number(tag)).await?;
+        Ok(block.and_then(|block| block.number).map(|number| number.as_u64()))
+    }
+
+    /// Chain id and genesis hash of the chain behind the RPC endpoint
+    pub async fn get_chain_identity(&self) -> Result<ChainIdentity> {
+        let chain_id = self.web3.eth().chain_id().await?.as_u64();
+        let genesis_hash = self.get_block_hash(0).await?.unwrap_or_default();
+        Ok(ChainIdentity { chain_id, genesis_hash: format!("{:?}", genesis_hash) })
+    }
+
     /// Check if an order has been claimed
     pub async fn is_order_claimed(&self, order_id: u32) -> Result<bool> {
         let result: bool = self.bridge_contract
@@ -227,6 +436,10 @@ impl BlockchainClient {
 
     /// Get USDC balance of an address
     pub async fn get_usdc_balance(&self, address: Address) -> Result<U256> {
+        if let Some(ReadValue::Balance(balance)) = self.read_cache.get(ReadKey::UsdcBalance(address)) {
+            return Ok(balance);
+        }
+
         // Create USDC contract instance (ERC20)
         let usdc_abi = r#"[{"constant":true,"inputs":[{"name":"_owner","type":"address"}],"name":"balanceOf","outputs":[{"name":"balance","type":"uint256"}],"payable":false,"stateMutability":"view","type":"function"}]"#;
         
@@ -240,11 +453,17 @@ impl BlockchainClient {
             .query("balanceOf", address, None, Options::default(), None)
             .await?;
 
+        self.read_cache.put(ReadKey::UsdcBalance(address), ReadValue::Balance(balance));
         Ok(balance)
     }
 
 
 
+    /// Current network gas price in wei
+    pub async fn get_gas_price(&self) -> Result<U256> {
+        Ok(self.web3.eth().gas_price().await?)
+    }
+
     /// Get network statistics
     pub async fn get_network_stats(&self) -> Result<NetworkStats> {
         let block_number = self.get_block_number().await?;
@@ -261,12 +480,21 @@ impl BlockchainClient {
     }
 }
 
+/// Identifies the chain behind an RPC endpoint
+///
+/// A reset dev chain keeps its chain id but gets a new genesis block, so both are compared.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChainIdentity {
+    pub chain_id: u64,
+    pub genesis_hash: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct NetworkStats {
     pub chain_id: u64,
     pub block_number: u64,
     pub gas_price: U256,
-    pub latest_batch_id: u32,
+    pub latest_batch_id: u64,
     pub bridge_address: Address,
 }
 
@@ -275,7 +503,7 @@ pub fn hex_to_h256(hex: &str) -> Result<H256> {
     let clean_hex = hex.trim_start_matches("0x");
     let bytes = hex::decode(clean_hex)?;
     if bytes.len() != 32 {
-        return Err(anyhow::anyhow!("Invalid hex length for H256"));
+        return Err(ChainError::InvalidLength("H256"));
     }
     Ok(H256::from_slice(&bytes))
 }
@@ -285,11 +513,44 @@ pub fn hex_to_address(hex: &str) -> Result<Address> {
     let clean_hex = hex.trim_start_matches("0x");
     let bytes = hex::decode(clean_hex)?;
     if bytes.len() != 20 {
-        return Err(anyhow::anyhow!("Invalid hex length for Address"));
+        return Err(ChainError::InvalidLength("Address"));
     }
     Ok(Address::from_slice(&bytes))
 }
 
+/// Clients against a local JSON-RPC stub, for exercising submissions without a node
+#[cfg(any(test, feature = "testing"))]
+pub mod testing {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    // Anvil's first default account
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    /// A client whose signer holds plenty of ETH at a gas price of 1 wei, reading through `read_cache`
+    pub async fn funded_client(read_cache: ChainReadCache) -> BlockchainClient {
+        let rpc = Router::new().route("/", post(|Json(request): Json<Value>| async move {
+            let result = match request["method"].as_str() {
+                Some("eth_getBalance") => json!("0xde0b6b3a7640000"),
+                Some("eth_gasPrice") => json!("0x1"),
+                _ => Value::Null,
+            };
+            Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, rpc).await.unwrap() });
+
+        let signer = crate::signer::LocalKeySigner::from_hex(TEST_KEY).unwrap();
+        BlockchainClient::new(url, Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3), 31337)
+            .await
+            .unwrap()
+            .with_signer(Arc::new(signer))
+            .with_read_cache(read_cache)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -306,6 +567,36 @@ mod tests {
         H256::from_low_u64_be(value)
     }
 
+    #[tokio::test]
+    async fn test_submission_evicts_the_submitted_batch_roots() {
+        let read_cache = ChainReadCache::new(60);
+        let roots = ReadValue::BatchRoots(create_test_h256(1), create_test_h256(2));
+        read_cache.put(ReadKey::LatestBatchId, ReadValue::BatchId(6));
+        read_cache.put(ReadKey::BatchRoots(6), roots);
+        read_cache.put(ReadKey::BatchRoots(7), roots);
+        let client = testing::funded_client(read_cache.clone()).await;
+
+        let h = create_test_h256;
+        let result = client.submit_proof(7, 6, h(1), h(2), h(3), h(4), Bytes(vec![0xab; 32])).await.unwrap();
+        assert_eq!(result.batch_id, 7);
+        // Signed by the operator but not sent: no transaction hash and no gas used yet
+        assert!(matches!(&result.submission, Submission::Signed { signer, signature, .. }
+            if Some(*signer) == client.signer.as_ref().map(|s| s.address()) && signature.len() == 2 + 130));
+        assert_eq!(result.submission.transaction_hash(), None);
+        assert_eq!(result.gas_used, None);
+        assert_eq!(read_cache.get(ReadKey::BatchRoots(7)), None);
+        assert_eq!(read_cache.get(ReadKey::LatestBatchId), None);
+        // The previous batch's roots are final and stay cached
+        assert_eq!(read_cache.get(ReadKey::BatchRoots(6)), Some(roots));
+    }
+
+    #[test]
+    fn test_bridge_abi_deposit_is_a_known_version() {
+        let abi = web3::ethabi::Contract::load(&include_bytes!("abi/VaporBridge_abi.json")[..]).unwrap();
+        let deposited = abi.event("Deposited").unwrap().signature();
+        assert!(crate::event_abi::DEPOSIT_ABIS.iter().any(|version| version.topic() == deposited));
+    }
+
     #[test]
     fn test_contract_addresses_creation() {
         let bridge_addr = create_test_address(1);
@@ -343,7 +634,7 @@ mod tests {
     #[test]
     fn test_proof_submission_result_creation() {
         let result = ProofSubmissionResult {
-            transaction_hash: create_test_h256(123),
+            submission: Submission::Sent { transaction_hash: create_test_h256(123) },
             batch_id: 42,
             gas_used: Some(U256::from(180_000)),
             success: true,
@@ -359,14 +650,14 @@ mod tests {
         let deposit = DepositEvent {
             user: create_test_address(1),
             token: create_test_address(2),
-            amount: U256::from(1000_000_000), // 1000 USDC (6 decimals)
+            amount: U256::from(1_000_000_000), // 1000 USDC (6 decimals)
             banking_hash: create_test_h256(456),
             block_number: 18_500_000,
             transaction_hash: create_test_h256(789),
         };
 
         assert_eq!(deposit.user, create_test_address(1));
-        assert_eq!(deposit.amount, U256::from(1000_000_000));
+        assert_eq!(deposit.amount, U256::from(1_000_000_000));
         assert_eq!(deposit.block_number, 18_500_000);
     }
 
@@ -481,7 +772,7 @@ mod tests {
     pub struct MockBlockchainClient {
         pub addresses: ContractAddresses,
         pub chain_config: ChainConfig,
-        pub mock_batch_id: u32,
+        pub mock_batch_id: u64,
         pub mock_block_number: u64,
         pub mock_events: Vec<DepositEvent>,
     }
@@ -507,10 +798,11 @@ mod tests {
             }
         }
 
+        #[allow(clippy::too_many_arguments)]
         pub async fn submit_proof(
             &self,
-            batch_id: u32,
-            _prev_batch_id: u32,
+            batch_id: u64,
+            _prev_batch_id: u64,
             _prev_state_root: H256,
             _prev_orders_root: H256,
             _new_state_root: H256,
@@ -518,18 +810,18 @@ mod tests {
             _proof: Bytes,
         ) -> Result<ProofSubmissionResult> {
             Ok(ProofSubmissionResult {
-                transaction_hash: create_test_h256(batch_id as u64),
+                submission: Submission::Sent { transaction_hash: create_test_h256(batch_id) },
                 batch_id,
                 gas_used: Some(U256::from(200_000)),
                 success: true,
             })
         }
 
-        pub async fn get_latest_batch_id(&self) -> Result<u32> {
+        pub async fn get_latest_batch_id(&self) -> Result<u64> {
             Ok(self.mock_batch_id)
         }
 
-        pub async fn get_batch_roots(&self, _batch_id: u32) -> Result<(H256, H256)> {
+        pub async fn get_batch_roots(&self, _batch_id: u64) -> Result<(H256, H256)> {
             Ok((create_test_h256(111), create_test_h256(222)))
         }
 
@@ -539,11 +831,11 @@ mod tests {
 
         pub async fn is_order_claimed(&self, order_id: u32) -> Result<bool> {
             // Mock: even order IDs are claimed
-            Ok(order_id % 2 == 0)
+            Ok(order_id.is_multiple_of(2))
         }
 
         pub async fn get_usdc_balance(&self, _address: Address) -> Result<U256> {
-            Ok(U256::from(1000_000_000u64)) // 1000 USDC
+            Ok(U256::from(1_000_000_000u64)) // 1000 USDC
         }
 
         pub async fn get_deposit_events(&self, _from_block: u64, _to_block: Option<u64>) -> Result<Vec<DepositEvent>> {
@@ -591,7 +883,7 @@ mod tests {
         assert_eq!(result.batch_id, 42);
         assert!(result.success);
         assert_eq!(result.gas_used.unwrap(), U256::from(200_000));
-        assert_eq!(result.transaction_hash, create_test_h256(42));
+        assert_eq!(result.submission.transaction_hash(), Some(create_test_h256(42)));
     }
 
     #[tokio::test]
@@ -636,7 +928,7 @@ mod tests {
         let test_address = create_test_address(5);
         
         let balance = client.get_usdc_balance(test_address).await.unwrap();
-        assert_eq!(balance, U256::from(1000_000_000u64));
+        assert_eq!(balance, U256::from(1_000_000_000u64));
     }
 
     #[tokio::test]
@@ -694,13 +986,14 @@ mod tests {
     #[test]
     fn test_proof_submission_result_serialization() {
         let result = ProofSubmissionResult {
-            transaction_hash: create_test_h256(123),
+            submission: Submission::Sent { transaction_hash: create_test_h256(123) },
             batch_id: 42,
             gas_used: Some(U256::from(180_000)),
             success: true,
         };
 
         let json = serde_json::to_string(&result).unwrap();
+        assert!(json.contains("\"status\":\"sent\""));
         assert!(json.contains("transaction_hash"));
         assert!(json.contains("batch_id"));
         assert!(json.contains("gas_used"));
@@ -712,7 +1005,7 @@ mod tests {
         let deposit = DepositEvent {
             user: create_test_address(1),
             token: create_test_address(2),
-            amount: U256::from(1000_000_000),
+            amount: U256::from(1_000_000_000),
             banking_hash: create_test_h256(456),
             block_number: 18_500_000,
             transaction_hash: create_test_h256(789),
@@ -769,7 +1062,7 @@ mod tests {
         assert_eq!(result.batch_id, next_batch_id);
         assert!(result.success);
         assert!(result.gas_used.is_some());
-        assert_eq!(result.transaction_hash, create_test_h256(next_batch_id as u64));
+        assert_eq!(result.submission.transaction_hash(), Some(create_test_h256(next_batch_id as u64)));
         
         // 4. Check network stats
         let stats = client.get_network_stats().await.unwrap();
@@ -805,7 +1098,7 @@ mod tests {
         
         // 1. Check USDC balance
         let balance = client.get_usdc_balance(test_user).await.unwrap();
-        assert_eq!(balance, U256::from(1000_000_000u64)); // 1000 USDC
+        assert_eq!(balance, U256::from(1_000_000_000u64)); // 1000 USDC
         
         // 2. Get batch roots for verification
         let (state_root, orders_root) = client.get_batch_roots(42).await.unwrap();
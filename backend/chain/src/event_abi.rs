@@ -0,0 +1,300 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, warn};
+use web3::signing::keccak256;
+use web3::types::{Address, Log, H256, U256};
+
+use crate::client::DepositEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    Address,
+    Uint,
+    Bytes32,
+}
+
+#[derive(Debug)]
+struct AbiField {
+    name: &'static str,
+    kind: FieldKind,
+    indexed: bool,
+}
+
+/// One released layout of the bridge's `Deposited` event
+#[derive(Debug)]
+pub struct DepositAbi {
+    pub version: u32,
+    pub signature: &'static str,
+    fields: &'static [AbiField],
+}
+
+impl DepositAbi {
+    pub fn topic(&self) -> H256 {
+        H256::from(keccak256(self.signature.as_bytes()))
+    }
+}
+
+const fn field(name: &'static str, kind: FieldKind, indexed: bool) -> AbiField {
+    AbiField { name, kind, indexed }
+}
+
+/// Every `Deposited` layout the relayer can decode, oldest first
+///
+/// v2 is the upgraded bridge, which also emits the token address and a per-user deposit
+/// nonce; the relayer has no use for the nonce and ignores it.
+pub const DEPOSIT_ABIS: &[DepositAbi] = &[
+    DepositAbi {
+        version: 1,
+        signature: "Deposited(address,uint256,uint256,bytes32)",
+        fields: &[
+            field("from", FieldKind::Address, true),
+            field("tokenId", FieldKind::Uint, false),
+            field("amount", FieldKind::Uint, false),
+            field("bankingHash", FieldKind::Bytes32, true),
+        ],
+    },
+    DepositAbi {
+        version: 2,
+        signature: "Deposited(address,uint256,uint256,bytes32,address,uint256)",
+        fields: &[
+            field("from", FieldKind::Address, true),
+            field("tokenId", FieldKind::Uint, false),
+            field("amount", FieldKind::Uint, false),
+            field("bankingHash", FieldKind::Bytes32, true),
+            field("token", FieldKind::Address, false),
+            field("nonce", FieldKind::Uint, false),
+        ],
+    },
+];
+
+/// Decode counts for one ABI version; a new version appearing marks a contract upgrade
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AbiVersionStats {
+    pub signature: String,
+    pub events: u64,
+    pub first_block: Option<u64>,
+    pub last_block: Option<u64>,
+    /// Fields absent from the log and decoded as zero
+    pub defaulted_fields: u64,
+    /// Trailing data words beyond the fields this version declares
+    pub ignored_words: u64,
+}
+
+/// Deposit decoding counters, keyed by ABI version
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DepositAbiMetrics {
+    pub versions: BTreeMap<u32, AbiVersionStats>,
+    /// Version of the deposit seen at the highest block
+    pub latest_version: Option<u32>,
+    /// Bridge logs skipped because their signature is unknown, keyed by topic0
+    pub unknown_signatures: BTreeMap<String, u64>,
+}
+
+/// Shared handle to the deposit decoding counters, written by the chain client and read by the API
+#[derive(Clone, Default)]
+pub struct AbiVersionMetrics {
+    inner: Arc<RwLock<DepositAbiMetrics>>,
+}
+
+impl AbiVersionMetrics {
+    pub fn snapshot(&self) -> DepositAbiMetrics {
+        self.inner.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn record_decoded(&self, abi: &DepositAbi, block: u64, defaulted_fields: u64, ignored_words: u64) {
+        let mut metrics = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        let stats = metrics.versions.entry(abi.version).or_default();
+        stats.signature = abi.signature.to_string();
+        stats.events += 1;
+        stats.first_block = Some(stats.first_block.map_or(block, |first| first.min(block)));
+        stats.last_block = Some(stats.last_block.map_or(block, |last| last.max(block)));
+        stats.defaulted_fields += defaulted_fields;
+        stats.ignored_words += ignored_words;
+
+        let latest_block = metrics.latest_version
+            .and_then(|version| metrics.versions.get(&version))
+            .and_then(|stats| stats.last_block);
+        if latest_block.is_none_or(|latest| block >= latest) {
+            metrics.latest_version = Some(abi.version);
+        }
+    }
+
+    fn record_unknown(&self, topic: H256) {
+        let mut metrics = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        *metrics.unknown_signatures.entry(format!("{:?}", topic)).or_default() += 1;
+    }
+}
+
+/// Decodes bridge logs into deposits, whichever `Deposited` layout emitted them
+pub struct DepositDecoder {
+    abis: HashMap<H256, &'static DepositAbi>,
+    /// Other bridge events, skipped without being counted as unknown
+    other_events: HashSet<H256>,
+    metrics: AbiVersionMetrics,
+}
+
+impl DepositDecoder {
+    pub fn new(other_events: impl IntoIterator<Item = H256>, metrics: AbiVersionMetrics) -> Self {
+        let abis = DEPOSIT_ABIS.iter().map(|abi| (abi.topic(), abi)).collect();
+        Self { abis, other_events: other_events.into_iter().collect(), metrics }
+    }
+
+    /// Decode every deposit in `logs`, logging and skipping logs with an unknown signature
+    pub fn decode(&self, logs: &[Log]) -> Vec<DepositEvent> {
+        logs.iter().filter_map(|log| self.decode_log(log)).collect()
+    }
+
+    fn decode_log(&self, log: &Log) -> Option<DepositEvent> {
+        let topic = log.topics.first().copied().unwrap_or_default();
+        let Some(abi) = self.abis.get(&topic) else {
+            if self.other_events.contains(&topic) {
+                debug!("Skipping non-deposit bridge event {:?}", topic);
+            } else {
+                warn!(
+                    "Skipping bridge log with unknown event signature {:?} (tx {:?}, block {:?})",
+                    topic, log.transaction_hash, log.block_number
+                );
+                self.metrics.record_unknown(topic);
+            }
+            return None;
+        };
+
+        let mut topics = log.topics.iter().skip(1);
+        let mut words = log.data.0.chunks(32);
+        let mut values = HashMap::new();
+        let mut defaulted = 0;
+        for field in abi.fields {
+            let word = match field.indexed {
+                true => topics.next().copied(),
+                false => words.next().filter(|word| word.len() == 32).map(H256::from_slice),
+            };
+            if word.is_none() {
+                defaulted += 1;
+            }
+            values.insert(field.name, (field.kind, word.unwrap_or_default()));
+        }
+        let ignored = words.count() as u64;
+
+        let address = |name| match values.get(name) {
+            Some((FieldKind::Address, word)) => Some(Address::from(*word)),
+            _ => None,
+        };
+        let uint = |name| match values.get(name) {
+            Some((FieldKind::Uint, word)) => U256::from_big_endian(word.as_bytes()),
+            _ => U256::zero(),
+        };
+        let bytes32 = |name| match values.get(name) {
+            Some((FieldKind::Bytes32, word)) => *word,
+            _ => H256::zero(),
+        };
+
+        let block_number = log.block_number.map_or(0, |block| block.as_u64());
+        self.metrics.record_decoded(abi, block_number, defaulted, ignored);
+
+        Some(DepositEvent {
+            user: address("from").unwrap_or_default(),
+            // v1 only emits the token id; the relayer maps it back from the low bytes
+            token: address("token").unwrap_or_else(|| Address::from_low_u64_be(uint("tokenId").low_u64())),
+            amount: uint("amount"),
+            banking_hash: bytes32("bankingHash"),
+            block_number,
+            transaction_hash: log.transaction_hash.unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use web3::types::Bytes;
+
+    fn word(value: u64) -> Vec<u8> {
+        H256::from_low_u64_be(value).as_bytes().to_vec()
+    }
+
+    fn log(topics: Vec<H256>, words: &[u64], block: u64) -> Log {
+        Log {
+            address: Address::zero(),
+            topics,
+            data: Bytes(words.iter().flat_map(|value| word(*value)).collect()),
+            block_hash: None,
+            block_number: Some(block.into()),
+            transaction_hash: Some(H256::from_low_u64_be(block)),
+            transaction_index: None,
+            log_index: None,
+            transaction_log_index: None,
+            log_type: None,
+            removed: None,
+        }
+    }
+
+    fn v1_topics() -> Vec<H256> {
+        vec![DEPOSIT_ABIS[0].topic(), H256::from_low_u64_be(0xaa), H256::from_low_u64_be(0xbb)]
+    }
+
+    #[test]
+    fn test_decodes_each_abi_version() {
+        let metrics = AbiVersionMetrics::default();
+        let decoder = DepositDecoder::new([], metrics.clone());
+
+        let v2_topics = vec![DEPOSIT_ABIS[1].topic(), H256::from_low_u64_be(0xaa), H256::from_low_u64_be(0xbb)];
+        let deposits = decoder.decode(&[
+            log(v1_topics(), &[2, 500], 10),
+            log(v2_topics, &[2, 700, 0x7070, 9], 20),
+        ]);
+
+        assert_eq!(deposits.len(), 2);
+        assert_eq!(deposits[0].user, Address::from_low_u64_be(0xaa));
+        assert_eq!(deposits[0].token, Address::from_low_u64_be(2));
+        assert_eq!(deposits[0].amount, U256::from(500));
+        assert_eq!(deposits[0].banking_hash, H256::from_low_u64_be(0xbb));
+        assert_eq!(deposits[0].block_number, 10);
+        assert_eq!(deposits[1].token, Address::from_low_u64_be(0x7070));
+        assert_eq!(deposits[1].amount, U256::from(700));
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.versions[&1].events, 1);
+        assert_eq!(snapshot.versions[&2].first_block, Some(20));
+        assert_eq!(snapshot.latest_version, Some(2));
+    }
+
+    #[test]
+    fn test_missing_fields_default_and_extra_words_are_ignored() {
+        let metrics = AbiVersionMetrics::default();
+        let decoder = DepositDecoder::new([], metrics.clone());
+
+        let short = log(vec![DEPOSIT_ABIS[0].topic(), H256::from_low_u64_be(0xaa)], &[1], 5);
+        let long = log(v1_topics(), &[1, 300, 42, 43], 6);
+        let deposits = decoder.decode(&[short, long]);
+
+        assert_eq!(deposits[0].amount, U256::zero());
+        assert_eq!(deposits[0].banking_hash, H256::zero());
+        assert_eq!(deposits[1].amount, U256::from(300));
+
+        let stats = &metrics.snapshot().versions[&1];
+        assert_eq!(stats.events, 2);
+        assert_eq!(stats.defaulted_fields, 2);
+        assert_eq!(stats.ignored_words, 2);
+    }
+
+    #[test]
+    fn test_unknown_signatures_are_skipped_and_counted() {
+        let metrics = AbiVersionMetrics::default();
+        let claimed = H256::from(keccak256(b"Claimed(uint256,uint256,address,uint256,uint256)"));
+        let decoder = DepositDecoder::new([claimed], metrics.clone());
+
+        let unknown = H256::from(keccak256(b"Deposited(address,uint256,uint256,bytes32,bytes)"));
+        let deposits = decoder.decode(&[
+            log(vec![unknown], &[1, 2], 7),
+            log(vec![unknown], &[1, 2], 8),
+            log(vec![claimed], &[1, 2], 8),
+            log(v1_topics(), &[1, 100], 9),
+        ]);
+
+        assert_eq!(deposits.len(), 1);
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.unknown_signatures, BTreeMap::from([(format!("{:?}", unknown), 2)]));
+        assert_eq!(snapshot.latest_version, Some(1));
+    }
+}
@@ -0,0 +1,16 @@
+//! Everything the server needs to talk to the bridge and proof verifier contracts
+//!
+//! Holds the contract client the relayer, prover and claim endpoints submit through, the
+//! versioned `Deposited` event decoder, the cache in front of repeated chain reads, the
+//! signers submissions are signed with and the owner check for EIP-2612 permits. Models and
+//! proof formats come from `vapor-core`; nothing here touches HTTP or the database. The
+//! keystore and remote signers sit behind the `keystore` and `remote-signer` features so
+//! tools that only read the chain stay light.
+
+mod client;
+pub mod signer;
+pub mod event_abi;
+pub mod chain_reads;
+pub mod permit;
+
+pub use client::*;
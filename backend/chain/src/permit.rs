@@ -0,0 +1,83 @@
+//! Owner checks for EIP-2612 permits, which need secp256k1 recovery
+
+use vapor_core::types::PermitData;
+use web3::{signing, types::Address};
+
+use crate::hex_to_address;
+
+/// Recover the address that signed the permit under the token's domain separator
+pub fn recover_owner(permit: &PermitData, domain_separator: &[u8; 32]) -> Result<Address, String> {
+    let digest = permit.digest(domain_separator)?;
+    let (v, r, s) = permit.split_signature()?;
+
+    let mut compact = [0u8; 64];
+    compact[..32].copy_from_slice(&r);
+    compact[32..].copy_from_slice(&s);
+    signing::recover(&digest, &compact, (v - 27) as i32)
+        .map_err(|e| format!("Permit signature cannot be recovered: {}", e))
+}
+
+/// Check the permit was signed by its owner under the token's domain separator
+pub fn verify_signature(permit: &PermitData, domain_separator: &[u8; 32]) -> Result<(), String> {
+    let owner = hex_to_address(&permit.owner).map_err(|e| format!("Invalid permit owner: {}", e))?;
+    let signer = recover_owner(permit, domain_separator)?;
+    if signer != owner {
+        return Err(format!("Permit is signed by {:?}, not its owner {:?}", signer, owner));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::{signature_to_hex, LocalKeySigner, Signer};
+    use web3::types::H256;
+
+    // Anvil's first default account
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const DOMAIN: [u8; 32] = [7u8; 32];
+
+    async fn signed_permit(signer: &LocalKeySigner) -> PermitData {
+        let mut permit = PermitData {
+            owner: format!("{:?}", signer.address()),
+            spender: "0x0000000000000000000000000000000000000001".to_string(),
+            value: "1000000".to_string(),
+            nonce: Some("0".to_string()),
+            deadline: 1_900_000_000,
+            signature: String::new(),
+        };
+        let digest = H256::from(permit.digest(&DOMAIN).unwrap());
+        permit.signature = signature_to_hex(&signer.sign_digest(digest, None).await.unwrap()).unwrap();
+        permit
+    }
+
+    #[tokio::test]
+    async fn test_permit_signed_by_its_owner_verifies() {
+        let signer = LocalKeySigner::from_hex(TEST_KEY).unwrap();
+        let permit = signed_permit(&signer).await;
+
+        assert_eq!(recover_owner(&permit, &DOMAIN).unwrap(), signer.address());
+        assert!(verify_signature(&permit, &DOMAIN).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_permit_for_another_owner_or_domain_is_rejected() {
+        let signer = LocalKeySigner::from_hex(TEST_KEY).unwrap();
+        let permit = signed_permit(&signer).await;
+
+        // Any 65 bytes with a valid v recover to someone, just not the owner
+        let mut forged = permit.clone();
+        forged.signature = format!("0x{}1b", "11".repeat(64));
+        assert!(verify_signature(&forged, &DOMAIN).is_err());
+
+        let mut other_owner = permit.clone();
+        other_owner.owner = "0x1234567890123456789012345678901234567890".to_string();
+        assert!(verify_signature(&other_owner, &DOMAIN).unwrap_err().contains("not its owner"));
+
+        let mut raised = permit.clone();
+        raised.value = "2000000".to_string();
+        assert!(verify_signature(&raised, &DOMAIN).is_err());
+
+        assert!(verify_signature(&permit, &[8u8; 32]).is_err());
+    }
+}
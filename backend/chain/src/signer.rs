@@ -0,0 +1,344 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use serde::Serialize;
+use web3::{
+    signing::{Key, SecretKey, SecretKeyRef, Signature},
+    types::{Address, H256},
+};
+
+/// Signs transaction digests on behalf of the relayer
+/// Implementations decide where the key material lives; callers only see the address
+pub trait Signer: Send + Sync {
+    /// Short name of the backend, e.g. "env", "keystore" or "remote"
+    fn kind(&self) -> &'static str;
+
+    /// Address of the signing account
+    fn address(&self) -> Address;
+
+    /// Sign a 32-byte digest, applying EIP-155 replay protection when a chain id is given
+    fn sign_digest(&self, digest: H256, chain_id: Option<u64>) -> BoxFuture<'_, Result<Signature>>;
+}
+
+/// Signer backed by a raw private key held in memory (PRIVATE_KEY)
+pub struct LocalKeySigner {
+    key: SecretKey,
+    address: Address,
+    kind: &'static str,
+}
+
+impl LocalKeySigner {
+    pub fn from_hex(private_key: &str) -> Result<Self> {
+        let bytes = hex::decode(private_key.trim().trim_start_matches("0x"))
+            .map_err(|_| anyhow::anyhow!("Private key is not valid hex"))?;
+        Self::from_bytes(&bytes, "env")
+    }
+
+    fn from_bytes(bytes: &[u8], kind: &'static str) -> Result<Self> {
+        let key = SecretKey::from_slice(bytes)
+            .map_err(|e| anyhow::anyhow!("Invalid private key: {}", e))?;
+        let address = SecretKeyRef::new(&key).address();
+        Ok(Self { key, address, kind })
+    }
+}
+
+impl Signer for LocalKeySigner {
+    fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn sign_digest(&self, digest: H256, chain_id: Option<u64>) -> BoxFuture<'_, Result<Signature>> {
+        Box::pin(async move {
+            SecretKeyRef::new(&self.key)
+                .sign(digest.as_bytes(), chain_id)
+                .map_err(|e| anyhow::anyhow!("Failed to sign digest: {}", e))
+        })
+    }
+}
+
+/// Signer backed by an encrypted JSON keystore file (Web3 Secret Storage v3)
+#[cfg(feature = "keystore")]
+pub struct KeystoreSigner;
+
+#[cfg(feature = "keystore")]
+impl KeystoreSigner {
+    /// Decrypt the keystore and return a signer holding the recovered key
+    pub fn open(path: &str, password: &str) -> Result<LocalKeySigner> {
+        let wallet = ethers::signers::LocalWallet::decrypt_keystore(path, password)
+            .map_err(|e| anyhow::anyhow!("Failed to decrypt keystore {}: {}", path, e))?;
+        LocalKeySigner::from_bytes(&wallet.signer().to_bytes(), "keystore")
+    }
+}
+
+#[cfg(feature = "remote-signer")]
+#[derive(Debug, Serialize)]
+struct RemoteSignRequest<'a> {
+    key_id: &'a str,
+    digest: String,
+}
+
+#[cfg(feature = "remote-signer")]
+#[derive(Debug, serde::Deserialize)]
+struct RemoteSignResponse {
+    /// 65-byte r || s || v signature, hex encoded
+    signature: String,
+}
+
+/// Signer that delegates to an external KMS/HSM signing service over HTTP
+/// The service never reveals the key; every signature is checked against the expected address
+#[cfg(feature = "remote-signer")]
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: String,
+    key_id: String,
+    auth_token: Option<String>,
+    address: Address,
+}
+
+#[cfg(feature = "remote-signer")]
+impl RemoteSigner {
+    pub fn new(url: String, key_id: String, auth_token: Option<String>, address: Address) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            key_id,
+            auth_token,
+            address,
+        }
+    }
+
+    async fn request_signature(&self, digest: H256, chain_id: Option<u64>) -> Result<Signature> {
+        let request = RemoteSignRequest {
+            key_id: &self.key_id,
+            digest: format!("0x{}", hex::encode(digest.as_bytes())),
+        };
+
+        let mut builder = self.client.post(format!("{}/sign", self.url)).json(&request);
+        if let Some(token) = &self.auth_token {
+            builder = builder.bearer_auth(token);
+        }
+
+        let response = builder.send().await?.error_for_status()?;
+        let body: RemoteSignResponse = response.json().await?;
+
+        let bytes = hex::decode(body.signature.trim_start_matches("0x"))
+            .map_err(|_| anyhow::anyhow!("Remote signer returned non-hex signature"))?;
+        if bytes.len() != 65 {
+            return Err(anyhow::anyhow!("Remote signer returned {} byte signature, expected 65", bytes.len()));
+        }
+
+        // Normalise v to a recovery id (accepts 0/1 and 27/28)
+        let recovery_id = match bytes[64] {
+            0 | 27 => 0,
+            1 | 28 => 1,
+            v => return Err(anyhow::anyhow!("Remote signer returned invalid v value {}", v)),
+        };
+
+        let recovered = web3::signing::recover(digest.as_bytes(), &bytes[..64], recovery_id)
+            .map_err(|e| anyhow::anyhow!("Failed to recover remote signature: {}", e))?;
+        if recovered != self.address {
+            return Err(anyhow::anyhow!(
+                "Remote signer returned signature for {:?}, expected {:?}",
+                recovered, self.address
+            ));
+        }
+
+        let v = match chain_id {
+            Some(chain_id) => recovery_id as u64 + 35 + chain_id * 2,
+            None => recovery_id as u64 + 27,
+        };
+
+        Ok(Signature {
+            v,
+            r: H256::from_slice(&bytes[..32]),
+            s: H256::from_slice(&bytes[32..64]),
+        })
+    }
+}
+
+#[cfg(feature = "remote-signer")]
+impl Signer for RemoteSigner {
+    fn kind(&self) -> &'static str {
+        "remote"
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn sign_digest(&self, digest: H256, chain_id: Option<u64>) -> BoxFuture<'_, Result<Signature>> {
+        Box::pin(self.request_signature(digest, chain_id))
+    }
+}
+
+/// Encode a signature as 65-byte r || s || v hex, with v normalised to 27/28
+/// (an EIP-155 v of 35 + 2 * chain_id + recovery_id does not fit in a byte)
+///
+/// Any other v is rejected rather than guessed at, as a remote signer can return anything.
+pub fn signature_to_hex(signature: &Signature) -> Result<String> {
+    let recovery_id = match signature.v {
+        0 | 1 => signature.v,
+        27 | 28 => signature.v - 27,
+        v if v >= 35 => (v - 35) % 2,
+        v => return Err(anyhow::anyhow!("Signature has invalid v value {}", v)),
+    };
+    let mut bytes = Vec::with_capacity(65);
+    bytes.extend_from_slice(signature.r.as_bytes());
+    bytes.extend_from_slice(signature.s.as_bytes());
+    bytes.push(recovery_id as u8 + 27);
+    Ok(format!("0x{}", hex::encode(bytes)))
+}
+
+/// Left-pad a u64 into a 32-byte ABI word, as `abi.encodePacked(uint256(value))` does
+pub fn uint256(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Recover the address behind a 65-byte r || s || v hex signature (v = 27/28) over `digest`,
+/// as produced by `signature_to_hex` for operator-signed documents
+pub fn recover_signer(digest: &[u8; 32], signature: &str) -> Result<Address> {
+    let signature = hex::decode(signature.trim_start_matches("0x"))?;
+    if signature.len() != 65 || !matches!(signature[64], 27 | 28) {
+        return Err(anyhow::anyhow!("Signature is not a 65-byte r || s || v signature"));
+    }
+    web3::signing::recover(digest, &signature[..64], signature[64] as i32 - 27)
+        .map_err(|e| anyhow::anyhow!("Failed to recover signer: {}", e))
+}
+
+/// Outcome of checking an operator-signed document presented by a client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SignatureVerification {
+    pub valid: bool,
+    /// Address recovered from the signature, when it could be recovered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovered_signer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl SignatureVerification {
+    /// Compare the signer recovered from a document with the operator's address
+    pub fn against(operator: Address, recovered: Result<Address>) -> Self {
+        match recovered {
+            Ok(signer) if signer == operator => Self { valid: true, recovered_signer: Some(format!("{:?}", signer)), reason: None },
+            Ok(signer) => Self {
+                valid: false,
+                recovered_signer: Some(format!("{:?}", signer)),
+                reason: Some(format!("Not signed by the operator {:?}", operator)),
+            },
+            Err(e) => Self { valid: false, recovered_signer: None, reason: Some(e.to_string()) },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use web3::signing;
+
+    // Anvil's first default account
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const TEST_ADDRESS: &str = "f39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+
+    #[test]
+    fn test_local_signer_address() {
+        let signer = LocalKeySigner::from_hex(TEST_KEY).unwrap();
+
+        assert_eq!(signer.kind(), "env");
+        assert_eq!(hex::encode(signer.address().as_bytes()), TEST_ADDRESS);
+    }
+
+    #[test]
+    fn test_local_signer_rejects_invalid_key() {
+        assert!(LocalKeySigner::from_hex("0xnothex").is_err());
+        assert!(LocalKeySigner::from_hex("0x1234").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_local_signer_signature_recovers_address() {
+        let signer = LocalKeySigner::from_hex(TEST_KEY).unwrap();
+        let digest = H256::from(signing::keccak256(b"vapor"));
+
+        let signature = signer.sign_digest(digest, None).await.unwrap();
+        let recovery_id = signature.v as i32 - 27;
+
+        let mut compact = Vec::new();
+        compact.extend_from_slice(signature.r.as_bytes());
+        compact.extend_from_slice(signature.s.as_bytes());
+        let recovered = signing::recover(digest.as_bytes(), &compact, recovery_id).unwrap();
+
+        assert_eq!(recovered, signer.address());
+        assert_eq!(signature_to_hex(&signature).unwrap().len(), 2 + 130);
+    }
+
+    #[tokio::test]
+    async fn test_signature_hex_normalises_eip155_v() {
+        let signer = LocalKeySigner::from_hex(TEST_KEY).unwrap();
+        let digest = H256::from(signing::keccak256(b"vapor"));
+
+        let signature = signer.sign_digest(digest, Some(31337)).await.unwrap();
+        assert!(signature.v >= 35 + 2 * 31337);
+
+        let bytes = hex::decode(signature_to_hex(&signature).unwrap().trim_start_matches("0x")).unwrap();
+        assert!(matches!(bytes[64], 27 | 28));
+        let recovered = signing::recover(digest.as_bytes(), &bytes[..64], bytes[64] as i32 - 27).unwrap();
+        assert_eq!(recovered, signer.address());
+    }
+
+    #[test]
+    fn test_signature_hex_rejects_invalid_v() {
+        let signature = |v| Signature { v, r: H256::repeat_byte(1), s: H256::repeat_byte(2) };
+
+        for v in [0, 1, 27, 28, 35, 36, 35 + 2 * 31337] {
+            assert!(signature_to_hex(&signature(v)).is_ok(), "v = {}", v);
+        }
+        for v in [2, 26, 29, 34] {
+            assert!(signature_to_hex(&signature(v)).is_err(), "v = {}", v);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recover_signer_from_hex_signature() {
+        let signer = LocalKeySigner::from_hex(TEST_KEY).unwrap();
+        let digest = signing::keccak256(b"vapor");
+
+        let signature = signature_to_hex(&signer.sign_digest(H256::from(digest), None).await.unwrap()).unwrap();
+        assert_eq!(recover_signer(&digest, &signature).unwrap(), signer.address());
+
+        assert!(recover_signer(&digest, &signature[..signature.len() - 2]).is_err());
+        assert!(recover_signer(&digest, &format!("{}00", &signature[..signature.len() - 2])).is_err());
+        assert_ne!(recover_signer(&signing::keccak256(b"other"), &signature).ok(), Some(signer.address()));
+    }
+
+    #[test]
+    fn test_uint256_word() {
+        let word = uint256(0x0102);
+        assert_eq!(&word[..30], &[0u8; 30]);
+        assert_eq!(&word[30..], &[1, 2]);
+    }
+
+    #[cfg(feature = "keystore")]
+    #[test]
+    fn test_keystore_signer_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("vapor-keystore-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let (wallet, file_name) = ethers::signers::LocalWallet::new_keystore(
+            &dir, &mut rand::thread_rng(), "password", None,
+        ).unwrap();
+        let path = dir.join(file_name);
+        let path = path.to_str().unwrap();
+
+        let signer = KeystoreSigner::open(path, "password").unwrap();
+        assert_eq!(signer.kind(), "keystore");
+        assert_eq!(signer.address().as_bytes(), ethers::signers::Signer::address(&wallet).as_bytes());
+
+        assert!(KeystoreSigner::open(path, "wrong").is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,321 @@
+//! Typed async client for the Vapor backend
+//!
+//! Covers what a filler integration needs: finding and locking orders, submitting payment
+//! proofs, fetching order proofs and claiming earnings. Requests carry the filler's bearer
+//! token when one is set, and are retried with backoff where that is safe (see [`RetryPolicy`]).
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), vapor_client::ClientError> {
+//! use vapor_client::{types::{DiscoveryQuery, LockOrderRequest}, VaporClient};
+//!
+//! let client = VaporClient::new("http://localhost:8080").with_token("filler-token");
+//! let page = client.discovery_orders(&DiscoveryQuery { filler_id: Some("filler_1".into()), ..Default::default() }).await?;
+//! if let Some(order) = page.orders.first() {
+//!     let lock = LockOrderRequest { filler_id: "filler_1".into(), amount: order.amount.clone(), accepted_rate: None, fee_bps: None };
+//!     client.lock_order(&order.id, &lock).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod types;
+
+use reqwest::{Method, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+use types::{
+    AccountProofResponse, ClaimPreviewResponse, ClaimRecord, ClaimRequest, ClaimResponse, ClaimsListResponse, ClaimsQuery,
+    CreateOrderRequest, DiscoveryOrdersResponse, DiscoveryQuery, FillerBalance, LockOrderRequest, OrderResponse,
+    OrderStatusResponse, OrderStatusTransition, ProofResponse, SubmitPaymentProofRequest,
+};
+
+/// Error returned by the client
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// The backend answered with an error; `code` is its stable error code, e.g. "order_not_lockable"
+    #[error("{status} ({code}): {message}")]
+    Api { status: StatusCode, code: String, message: String, details: Option<Value> },
+    /// The request could not be sent or its response not read
+    #[error("request failed: {0}")]
+    Transport(#[from] reqwest::Error),
+    /// A successful response whose body is not what the endpoint returns
+    #[error("unexpected response body: {0}")]
+    Decode(#[from] serde_json::Error),
+}
+
+impl ClientError {
+    /// Backend error code, for errors the backend answered with
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            Self::Api { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Read an error response: the backend's `{"error", "message", "details"}` body, or the
+    /// status alone for handlers that answer without one
+    fn from_response(status: StatusCode, body: &[u8]) -> Self {
+        let body: Value = serde_json::from_slice(body).unwrap_or(Value::Null);
+        let reason = status.canonical_reason().unwrap_or("Unknown error");
+        Self::Api {
+            status,
+            code: body["error"].as_str().map_or_else(|| reason.to_lowercase().replace(' ', "_"), str::to_string),
+            message: body["message"].as_str().unwrap_or(reason).to_string(),
+            details: body.get("details").cloned(),
+        }
+    }
+
+    /// Wait the backend asks for before retrying a rate-limited request
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::Api { details: Some(details), .. } => details["retry_after_seconds"].as_u64().map(Duration::from_secs),
+            _ => None,
+        }
+    }
+}
+
+/// When and how often failed requests are retried
+///
+/// Rate-limited requests (429) and requests that never reached the backend are retried for
+/// every method. Timeouts and 5xx responses are retried for reads only, since a write may have
+/// been applied before it failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled on each one after
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 3, base_delay: Duration::from_millis(200), max_delay: Duration::from_secs(5) }
+    }
+}
+
+impl RetryPolicy {
+    /// Send every request once
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// Wait before retry number `retry` (1-based), at least what a rate limit asked for
+    pub fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        let backoff = self.base_delay.saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+        backoff.max(retry_after.unwrap_or_default()).min(self.max_delay)
+    }
+
+    fn retryable(error: &ClientError, idempotent: bool) -> bool {
+        match error {
+            ClientError::Api { status, .. } => {
+                *status == StatusCode::TOO_MANY_REQUESTS || (idempotent && status.is_server_error())
+            }
+            ClientError::Transport(e) => e.is_connect() || (idempotent && e.is_timeout()),
+            ClientError::Decode(_) => false,
+        }
+    }
+}
+
+/// Client of one Vapor backend
+#[derive(Debug, Clone)]
+pub struct VaporClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+    retry: RetryPolicy,
+}
+
+impl VaporClient {
+    /// Client of the backend at `base_url`, e.g. "https://api.vapor.example"
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Send `token` as a bearer token, as endpoints acting for a filler with a feed token require
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Send requests through `http`, e.g. one built with timeouts or a proxy
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    // Orders
+
+    /// POST /orders
+    pub async fn create_order(&self, request: &CreateOrderRequest) -> Result<OrderResponse, ClientError> {
+        self.send(Method::POST, "/api/v1/orders", &(), Some(request)).await
+    }
+
+    /// GET /orders/:order_id
+    pub async fn get_order(&self, order_id: &str) -> Result<OrderResponse, ClientError> {
+        self.get(&format!("/api/v1/orders/{}", order_id), &()).await
+    }
+
+    /// GET /orders/:order_id/status
+    pub async fn get_order_status(&self, order_id: &str) -> Result<OrderStatusResponse, ClientError> {
+        self.get(&format!("/api/v1/orders/{}/status", order_id), &()).await
+    }
+
+    /// GET /orders/:order_id/history
+    pub async fn get_order_history(&self, order_id: &str) -> Result<Vec<OrderStatusTransition>, ClientError> {
+        self.get(&format!("/api/v1/orders/{}/history", order_id), &()).await
+    }
+
+    // Fillers
+
+    /// GET /fillers/discovery
+    pub async fn discovery_orders(&self, query: &DiscoveryQuery) -> Result<DiscoveryOrdersResponse, ClientError> {
+        self.get("/api/v1/fillers/discovery", query).await
+    }
+
+    /// POST /fillers/orders/:order_id/lock
+    pub async fn lock_order(&self, order_id: &str, request: &LockOrderRequest) -> Result<OrderResponse, ClientError> {
+        self.send(Method::POST, &format!("/api/v1/fillers/orders/{}/lock", order_id), &(), Some(request)).await
+    }
+
+    /// POST /fillers/orders/:order_id/payment-proof
+    pub async fn submit_payment_proof(
+        &self,
+        order_id: &str,
+        request: &SubmitPaymentProofRequest,
+    ) -> Result<OrderResponse, ClientError> {
+        self.send(Method::POST, &format!("/api/v1/fillers/orders/{}/payment-proof", order_id), &(), Some(request)).await
+    }
+
+    /// GET /fillers/:filler_id/balance
+    pub async fn get_filler_balance(&self, filler_id: &str) -> Result<FillerBalance, ClientError> {
+        self.get(&format!("/api/v1/fillers/{}/balance", filler_id), &()).await
+    }
+
+    // Claims
+
+    /// POST /fillers/claim/preview
+    pub async fn preview_claim(&self, request: &ClaimRequest) -> Result<ClaimPreviewResponse, ClientError> {
+        self.send(Method::POST, "/api/v1/fillers/claim/preview", &(), Some(request)).await
+    }
+
+    /// POST /fillers/claim
+    pub async fn claim(&self, request: &ClaimRequest) -> Result<ClaimResponse, ClientError> {
+        self.send(Method::POST, "/api/v1/fillers/claim", &(), Some(request)).await
+    }
+
+    /// GET /fillers/claims
+    pub async fn list_claims(&self, query: &ClaimsQuery) -> Result<ClaimsListResponse, ClientError> {
+        self.get("/api/v1/fillers/claims", query).await
+    }
+
+    /// GET /fillers/claims/:claim_id
+    pub async fn get_claim(&self, claim_id: &str) -> Result<ClaimRecord, ClientError> {
+        self.get(&format!("/api/v1/fillers/claims/{}", claim_id), &()).await
+    }
+
+    // Proofs
+
+    /// GET /proofs/order/:batch_id/:order_id, in `format` ("siblings" when None)
+    pub async fn get_order_proof(&self, batch_id: u64, order_id: &str, format: Option<&str>) -> Result<ProofResponse, ClientError> {
+        self.get(&format!("/api/v1/proofs/order/{}/{}", batch_id, order_id), &[("format", format)]).await
+    }
+
+    /// GET /proofs/account/:address; `filler:<filler_id>` names a filler's settlement account
+    pub async fn get_account_proof(&self, address: &str, batch_id: Option<u64>) -> Result<AccountProofResponse, ClientError> {
+        self.get(&format!("/api/v1/proofs/account/{}", address), &[("batch_id", batch_id)]).await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &impl Serialize) -> Result<T, ClientError> {
+        self.send(Method::GET, path, query, None::<&()>).await
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &impl Serialize,
+        body: Option<&impl Serialize>,
+    ) -> Result<T, ClientError> {
+        let idempotent = method == Method::GET;
+        let mut attempt = 1;
+        loop {
+            let result = self.send_once(method.clone(), path, query, body).await;
+            match result {
+                Err(e) if attempt < self.retry.max_attempts && RetryPolicy::retryable(&e, idempotent) => {
+                    tokio::time::sleep(self.retry.delay(attempt, e.retry_after())).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn send_once<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &impl Serialize,
+        body: Option<&impl Serialize>,
+    ) -> Result<T, ClientError> {
+        let mut request = self.http.request(method, format!("{}{}", self.base_url, path)).query(query);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let bytes = response.bytes().await?;
+        if !status.is_success() {
+            return Err(ClientError::from_response(status, &bytes));
+        }
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delays() {
+        let retry = RetryPolicy::default();
+        assert_eq!(retry.delay(1, None), Duration::from_millis(200));
+        assert_eq!(retry.delay(3, None), Duration::from_millis(800));
+        assert_eq!(retry.delay(1, Some(Duration::from_secs(2))), Duration::from_secs(2));
+        assert_eq!(retry.delay(30, Some(Duration::from_secs(60))), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_error_bodies() {
+        let body = br#"{"error":"rate_limited","message":"slow down","details":{"limit":60,"retry_after_seconds":7}}"#;
+        let limited = ClientError::from_response(StatusCode::TOO_MANY_REQUESTS, body);
+        assert_eq!(limited.code(), Some("rate_limited"));
+        assert_eq!(limited.retry_after(), Some(Duration::from_secs(7)));
+        assert!(RetryPolicy::retryable(&limited, false));
+
+        // Handlers answering with a bare status have no body
+        let missing = ClientError::from_response(StatusCode::NOT_FOUND, b"");
+        assert_eq!(missing.code(), Some("not_found"));
+        assert!(!RetryPolicy::retryable(&missing, true));
+
+        let unavailable = ClientError::from_response(StatusCode::SERVICE_UNAVAILABLE, b"");
+        assert!(RetryPolicy::retryable(&unavailable, true));
+        assert!(!RetryPolicy::retryable(&unavailable, false));
+    }
+}
@@ -0,0 +1,188 @@
+//! Request and response bodies of the endpoints the client covers
+//!
+//! The server's own request types come from `vapor-core`, so a request the client serializes is
+//! exactly what the handler deserializes. Responses that embed server-side service types are
+//! mirrored here, with those nested objects kept as raw JSON.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub use vapor_core::types::*;
+
+// Mirrors of responses built from server-side service types
+
+/// An order as returned by the order and filler endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderResponse {
+    pub id: String,
+    pub order_type: OrderType,
+    pub status: OrderStatus,
+    pub amount: String,
+    pub bank_account: Option<String>,
+    pub bank_service: Option<String>,
+    pub filler_id: Option<String>,
+    pub locked_amount: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Reference to pass as bankingHash when depositing for a BridgeIn order
+    #[serde(default)]
+    pub deposit_reference: Option<String>,
+    /// Structured payment proof submitted by the filler
+    #[serde(default)]
+    pub payment_proof: Option<Value>,
+    /// USD price of the order's token when it was quoted, e.g. "0.999800"
+    #[serde(default)]
+    pub quoted_rate: Option<String>,
+    /// Set when the order looks like a resubmission of a recent order
+    #[serde(default)]
+    pub duplicate_warning: Option<Value>,
+    /// Share of the proof submission gas of the batch that settled the order
+    #[serde(default)]
+    pub settlement_cost: Option<Value>,
+}
+
+/// Order status tracking for seller
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderStatusResponse {
+    pub id: String,
+    pub status: OrderStatus,
+    pub phase: OrderPhase,
+    pub progress_percentage: u8,
+    pub estimated_completion: Option<DateTime<Utc>>,
+    pub filler_info: Option<FillerInfo>,
+    #[serde(default)]
+    pub parent_order_id: Option<String>,
+    #[serde(default)]
+    pub child_order_ids: Vec<String>,
+    /// How the time in the current phase compares with the corridor's recent orders
+    #[serde(default)]
+    pub slo_position: Option<Value>,
+}
+
+/// Filters of GET /fillers/discovery
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiscoveryQuery {
+    /// Only orders this filler's registered capabilities accept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filler_id: Option<String>,
+    /// Page size, 20 by default and at most 100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// `next_cursor` of the previous page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+/// A page of orders in discovery, oldest first
+#[derive(Debug, Deserialize)]
+pub struct DiscoveryOrdersResponse {
+    pub orders: Vec<OrderResponse>,
+    pub total: usize,
+    /// Pass as `after` to fetch the next page; unset on the last page
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Filters of GET /fillers/claims
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ClaimsQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filler_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+/// A page of claims, newest first
+#[derive(Debug, Deserialize)]
+pub struct ClaimsListResponse {
+    pub claims: Vec<ClaimRecord>,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Claim response
+#[derive(Debug, Deserialize)]
+pub struct ClaimResponse {
+    pub transaction_hash: Option<String>,
+    /// Batch the claims were made against; None before any batch is proven
+    pub batch_id: Option<u64>,
+    pub total_claimed: String,
+    pub payout_token_id: u32,
+    pub total_payout: String,
+    pub claims_processed: Vec<ProcessedClaim>,
+    /// Gas and fee of the claim transaction when the backend relayed it
+    #[serde(default)]
+    pub relay: Option<Value>,
+}
+
+/// Individual processed claim
+#[derive(Debug, Deserialize)]
+pub struct ProcessedClaim {
+    pub claim_id: String,
+    pub amount: String,
+    /// Fees taken out of `amount` and the net transferred
+    pub fee: Value,
+    pub destination_address: String,
+    pub payout_token_id: u32,
+    pub payout_amount: String,
+    pub conversion: Option<TokenConversion>,
+    pub merkle_proof: Vec<String>,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Fees and payouts a claim request would get, without submitting it
+#[derive(Debug, Deserialize)]
+pub struct ClaimPreviewResponse {
+    pub payout_token_id: u32,
+    pub total_gross: String,
+    pub total_fee: String,
+    pub total_net: String,
+    pub total_payout: String,
+    pub claims: Vec<ClaimPreview>,
+    #[serde(default)]
+    pub relay: Option<Value>,
+}
+
+/// One claim of a preview, in request order
+#[derive(Debug, Deserialize)]
+pub struct ClaimPreview {
+    pub destination_address: String,
+    pub fee: Value,
+    pub payout_amount: String,
+    pub conversion: Option<TokenConversion>,
+}
+
+/// Merkle proof of an order in a batch
+#[derive(Debug, Deserialize)]
+pub struct ProofResponse {
+    pub batch_id: u64,
+    pub order_id: String,
+    pub leaf_hash: String,
+    pub proof: Vec<String>,
+    pub root: String,
+    pub valid: bool,
+    /// "siblings", "raw" or "sorted_pairs"
+    pub format: String,
+    #[serde(default)]
+    pub path_bits: Option<Vec<u8>>,
+    #[serde(default)]
+    pub leaf_index: Option<usize>,
+}
+
+/// Merkle proof of an account in the state tree
+#[derive(Debug, Deserialize)]
+pub struct AccountProofResponse {
+    #[serde(default)]
+    pub batch_id: Option<u64>,
+    pub address: String,
+    pub leaf_hash: String,
+    pub proof: Vec<String>,
+    pub root: String,
+    pub valid: bool,
+    pub format: String,
+    #[serde(default)]
+    pub path_bits: Option<Vec<u8>>,
+}
@@ -0,0 +1,223 @@
+//! Fault injection for resilience testing
+//!
+//! Services call [`inject`] at their failure points. Without the `fault-injection`
+//! feature this is a no-op; with it, faults are driven by a runtime-configurable
+//! global [`FaultInjector`] (see the /admin/faults endpoints).
+
+use anyhow::Result;
+
+/// Services that can have faults injected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "fault-injection", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "fault-injection", serde(rename_all = "snake_case"))]
+pub enum FaultTarget {
+    Database,
+    Rpc,
+    Prover,
+}
+
+/// Possibly delay and/or fail the calling operation
+#[cfg(feature = "fault-injection")]
+pub async fn inject(target: FaultTarget) -> Result<()> {
+    enabled::global().inject(target).await
+}
+
+/// Possibly delay and/or fail the calling operation (no-op without `fault-injection`)
+#[cfg(not(feature = "fault-injection"))]
+#[inline(always)]
+pub async fn inject(_target: FaultTarget) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(feature = "fault-injection")]
+pub use enabled::*;
+
+#[cfg(feature = "fault-injection")]
+mod enabled {
+    use super::FaultTarget;
+    use anyhow::Result;
+    use serde::{Deserialize, Serialize};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{OnceLock, RwLock};
+    use std::time::Duration;
+    use tracing::warn;
+
+    /// Fault behaviour for a single target
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    pub struct FaultProfile {
+        /// Added delay when latency is injected
+        pub latency_ms: u64,
+        /// Probability (0.0 to 1.0) of injecting latency
+        pub latency_probability: f64,
+        /// Probability (0.0 to 1.0) of failing the operation
+        pub failure_probability: f64,
+    }
+
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    pub struct FaultConfig {
+        pub enabled: bool,
+        #[serde(default)]
+        pub database: FaultProfile,
+        #[serde(default)]
+        pub rpc: FaultProfile,
+        #[serde(default)]
+        pub prover: FaultProfile,
+    }
+
+    impl FaultConfig {
+        pub fn profile(&self, target: FaultTarget) -> &FaultProfile {
+            match target {
+                FaultTarget::Database => &self.database,
+                FaultTarget::Rpc => &self.rpc,
+                FaultTarget::Prover => &self.prover,
+            }
+        }
+
+        pub fn validate(&self) -> Result<(), String> {
+            for (name, profile) in [("database", &self.database), ("rpc", &self.rpc), ("prover", &self.prover)] {
+                let valid = |p: f64| (0.0..=1.0).contains(&p);
+                if !valid(profile.latency_probability) || !valid(profile.failure_probability) {
+                    return Err(format!("{} probabilities must be between 0.0 and 1.0", name));
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Counts of injected faults since the last reset
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct FaultStats {
+        pub database_failures: u64,
+        pub rpc_failures: u64,
+        pub prover_failures: u64,
+        pub latency_injections: u64,
+    }
+
+    #[derive(Default)]
+    pub struct FaultInjector {
+        config: RwLock<FaultConfig>,
+        database_failures: AtomicU64,
+        rpc_failures: AtomicU64,
+        prover_failures: AtomicU64,
+        latency_injections: AtomicU64,
+    }
+
+    impl FaultInjector {
+        pub fn config(&self) -> FaultConfig {
+            self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+        }
+
+        pub fn configure(&self, config: FaultConfig) {
+            *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        }
+
+        pub fn reset(&self) {
+            self.configure(FaultConfig::default());
+            for counter in [&self.database_failures, &self.rpc_failures, &self.prover_failures, &self.latency_injections] {
+                counter.store(0, Ordering::Relaxed);
+            }
+        }
+
+        pub fn stats(&self) -> FaultStats {
+            FaultStats {
+                database_failures: self.database_failures.load(Ordering::Relaxed),
+                rpc_failures: self.rpc_failures.load(Ordering::Relaxed),
+                prover_failures: self.prover_failures.load(Ordering::Relaxed),
+                latency_injections: self.latency_injections.load(Ordering::Relaxed),
+            }
+        }
+
+        pub async fn inject(&self, target: FaultTarget) -> Result<()> {
+            // Roll the dice before awaiting so the lock is not held across the sleep
+            let (delay, fail) = {
+                let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+                if !config.enabled {
+                    return Ok(());
+                }
+                let profile = config.profile(target);
+                let delay = (profile.latency_ms > 0 && rand::random::<f64>() < profile.latency_probability)
+                    .then(|| Duration::from_millis(profile.latency_ms));
+                (delay, rand::random::<f64>() < profile.failure_probability)
+            };
+
+            if let Some(delay) = delay {
+                self.latency_injections.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(delay).await;
+            }
+
+            if fail {
+                let counter = match target {
+                    FaultTarget::Database => &self.database_failures,
+                    FaultTarget::Rpc => &self.rpc_failures,
+                    FaultTarget::Prover => &self.prover_failures,
+                };
+                counter.fetch_add(1, Ordering::Relaxed);
+                warn!("Injected {:?} fault", target);
+                return Err(anyhow::anyhow!("Injected {:?} fault", target));
+            }
+
+            Ok(())
+        }
+    }
+
+    static INJECTOR: OnceLock<FaultInjector> = OnceLock::new();
+
+    /// Process-wide injector used by [`super::inject`]
+    pub fn global() -> &'static FaultInjector {
+        INJECTOR.get_or_init(FaultInjector::default)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn always_fail(target: FaultTarget) -> FaultConfig {
+            let mut config = FaultConfig { enabled: true, ..Default::default() };
+            let profile = FaultProfile { latency_ms: 1, latency_probability: 1.0, failure_probability: 1.0 };
+            match target {
+                FaultTarget::Database => config.database = profile,
+                FaultTarget::Rpc => config.rpc = profile,
+                FaultTarget::Prover => config.prover = profile,
+            }
+            config
+        }
+
+        #[tokio::test]
+        async fn test_disabled_injector_never_fails() {
+            let injector = FaultInjector::default();
+            let mut config = always_fail(FaultTarget::Database);
+            config.enabled = false;
+            injector.configure(config);
+
+            assert!(injector.inject(FaultTarget::Database).await.is_ok());
+            assert_eq!(injector.stats().database_failures, 0);
+        }
+
+        #[tokio::test]
+        async fn test_faults_only_hit_configured_target() {
+            let injector = FaultInjector::default();
+            injector.configure(always_fail(FaultTarget::Rpc));
+
+            assert!(injector.inject(FaultTarget::Rpc).await.is_err());
+            assert!(injector.inject(FaultTarget::Database).await.is_ok());
+            assert!(injector.inject(FaultTarget::Prover).await.is_ok());
+
+            let stats = injector.stats();
+            assert_eq!(stats.rpc_failures, 1);
+            assert_eq!(stats.latency_injections, 1);
+
+            injector.reset();
+            assert!(injector.inject(FaultTarget::Rpc).await.is_ok());
+            assert_eq!(injector.stats().rpc_failures, 0);
+        }
+
+        #[test]
+        fn test_config_validation() {
+            let mut config = always_fail(FaultTarget::Prover);
+            assert!(config.validate().is_ok());
+
+            config.prover.failure_probability = 1.5;
+            assert!(config.validate().is_err());
+        }
+    }
+}
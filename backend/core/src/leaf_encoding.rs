@@ -0,0 +1,6 @@
+//! How orders and accounts are encoded into tree leaves, and where those leaves sit
+//!
+//! Lives in the `vapor-merkle` crate, so browser and offline verifiers hash leaves exactly as
+//! the server does.
+
+pub use vapor_merkle::leaf::*;
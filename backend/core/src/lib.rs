@@ -0,0 +1,26 @@
+//! Domain code shared by the server and the tools around it
+//!
+//! Holds the order, batch and account models, the request types the server accepts, the
+//! sparse Merkle tree the server builds its order and state trees with, the leaf encoding,
+//! the proof formats the API serves and the traits the server's services plug into. Nothing
+//! here touches HTTP, the database or the chain, so `vapor-verify`, load generators and SDKs
+//! can depend on it without pulling in axum or web3. Build with `default-features = false`
+//! to leave the tree out and keep only the proof formats and leaf encoding.
+
+#[cfg(feature = "tree")]
+pub mod sparse_merkle_tree;
+pub mod proof_format;
+pub mod leaf_encoding;
+pub mod types;
+pub mod models;
+pub mod services;
+pub mod fault_injection;
+
+#[cfg(feature = "tree")]
+pub use sparse_merkle_tree::{
+    SparseMerkleTree,
+    SparseMerkleLeaf,
+    MerkleProof,
+    ethereum_address_to_path,
+    index_to_path
+};
@@ -0,0 +1,1011 @@
+//! Orders, batches and account balances as the server stores, batches and proves them
+//!
+//! Plain data and the rules on it, with no database or HTTP code, so tools that read orders
+//! or rebuild trees share the server's definitions.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::sync::Mutex;
+use ulid::{Generator, Ulid};
+
+use crate::leaf_encoding::{account_leaf_preimage, order_leaf_endpoints, order_leaf_preimage};
+#[cfg(feature = "tree")]
+use crate::leaf_encoding::{ethereum_address_to_path, index_to_path};
+#[cfg(feature = "tree")]
+use crate::sparse_merkle_tree::SparseMerkleLeaf;
+use crate::types::{CreateOrderRequest, OrderStatus, OrderType};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Order {
+    pub id: String,
+    pub order_type: OrderType,
+    pub from_address: Option<String>,
+    pub to_address: Option<String>,
+    pub token_id: u32,
+    pub amount: String,
+    pub bank_account: Option<String>,        // New: Bank account for off-ramp
+    pub bank_service: Option<String>,        // New: Bank service name (PayPal, ACH, etc.)
+    pub banking_hash: Option<String>,        // Payment proof/receipt hash
+    pub filler_id: Option<String>,           // New: ID of filler who locked this order
+    pub locked_amount: Option<String>,       // New: Amount locked by filler
+    pub status: OrderStatus,
+    pub batch_id: Option<u64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Batch {
+    pub id: u32,
+    pub prev_state_root: String,
+    pub prev_orders_root: String,
+    pub new_state_root: String,
+    pub new_orders_root: String,
+    pub proof_data: Option<String>,
+    pub status: BatchStatus,
+    pub created_at: DateTime<Utc>,
+    pub submitted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[repr(i32)]
+pub enum BatchStatus {
+    Building = 0,
+    Proving = 1,
+    Submitting = 2,
+    Submitted = 3,
+    Failed = 4,
+}
+
+impl From<i32> for BatchStatus {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => BatchStatus::Building,
+            1 => BatchStatus::Proving,
+            2 => BatchStatus::Submitting,
+            3 => BatchStatus::Submitted,
+            4 => BatchStatus::Failed,
+            _ => BatchStatus::Building, // Default fallback
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenBalance {
+    pub token_id: u32,
+    pub balance: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountState {
+    pub address: String,
+    pub balances: Vec<TokenBalance>, // Array-based dictionary of token balances
+    pub updated_at: DateTime<Utc>,
+}
+
+/// State root of the most recent batch whose proof was submitted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvenRoot {
+    pub batch_id: u64,
+    pub state_root: String,
+}
+
+/// Leading 4 bytes reserved for filler settlement accounts in the state tree
+pub const FILLER_SETTLEMENT_PREFIX: &str = "0xf111e700";
+
+/// Prefix of account proof targets that name a filler rather than an address
+const FILLER_ACCOUNT_ALIAS: &str = "filler:";
+
+/// State tree address of the account holding a filler's claimable balances
+///
+/// The reserved prefix followed by the first 16 bytes of keccak256(filler_id), so
+/// settlement accounts stay apart from user wallets and are derivable on-chain.
+pub fn filler_settlement_address(filler_id: &str) -> String {
+    let digest = Keccak256::digest(filler_id.as_bytes());
+    format!("{}{}", FILLER_SETTLEMENT_PREFIX, hex::encode(&digest[..16]))
+}
+
+/// Resolve an account proof target, where `filler:<filler_id>` names a filler's settlement account
+pub fn resolve_account_address(target: &str) -> String {
+    match target.strip_prefix(FILLER_ACCOUNT_ALIAS) {
+        Some(filler_id) => filler_settlement_address(filler_id),
+        None => target.to_string(),
+    }
+}
+
+/// Largest page a list endpoint returns, whatever `limit` asks for
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// Keyset position in a listing ordered by (created_at, id)
+///
+/// Handed to clients as an opaque `next_cursor` and passed back as `after`. Unlike an offset
+/// it keeps pointing at the same row while rows are inserted ahead of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid pagination cursor, pass next_cursor from the previous page as is")]
+pub struct InvalidCursor;
+
+impl Cursor {
+    pub fn new(created_at: DateTime<Utc>, id: impl Into<String>) -> Self {
+        Self { created_at, id: id.into() }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("cursors serialize"))
+    }
+
+    pub fn decode(raw: &str) -> Result<Self, InvalidCursor> {
+        let json = URL_SAFE_NO_PAD.decode(raw).map_err(|_| InvalidCursor)?;
+        serde_json::from_slice(&json).map_err(|_| InvalidCursor)
+    }
+}
+
+/// Rows to return for a requested `limit`: `default` when unset, at most [`MAX_PAGE_SIZE`]
+pub fn page_size(limit: Option<usize>, default: usize) -> usize {
+    limit.unwrap_or(default).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Trim rows fetched with `LIMIT size + 1` to a page, with the cursor of the next page if
+/// there is one
+pub fn paginate<T>(mut rows: Vec<T>, size: usize, cursor_of: impl Fn(&T) -> Cursor) -> (Vec<T>, Option<String>) {
+    if rows.len() <= size {
+        return (rows, None);
+    }
+    rows.truncate(size);
+    let next_cursor = rows.last().map(|row| cursor_of(row).encode());
+    (rows, next_cursor)
+}
+
+/// Shared so ids minted within the same millisecond still increase
+static ORDER_ID_GENERATOR: Mutex<Generator> = Mutex::new(Generator::new());
+
+/// Mint a new order id
+///
+/// Order ids are ULIDs: a millisecond timestamp followed by a random part that the
+/// generator increments within a millisecond, so ids sort in creation order.
+pub fn new_order_id() -> String {
+    let mut generator = ORDER_ID_GENERATOR.lock().unwrap_or_else(|e| e.into_inner());
+    generator.generate().unwrap_or_else(|_| Ulid::new()).to_string()
+}
+
+/// Creation time encoded in an order id, or None for ids minted before ULIDs (UUIDv4)
+pub fn order_id_timestamp(id: &str) -> Option<DateTime<Utc>> {
+    Ulid::from_string(id).ok().map(|ulid| DateTime::<Utc>::from(ulid.datetime()))
+}
+
+/// Sort orders by creation, the order they take in a batch's order tree
+pub fn sort_by_creation(orders: &mut [Order]) {
+    orders.sort_by(|a, b| a.creation_key().cmp(&b.creation_key()));
+}
+
+impl Order {
+    pub fn new(req: CreateOrderRequest) -> Self {
+        Self::new_at(req, Utc::now())
+    }
+
+    /// Create an order stamped with `now`, for callers holding a clock
+    pub fn new_at(req: CreateOrderRequest, now: DateTime<Utc>) -> Self {
+        Self {
+            id: new_order_id(),
+            order_type: req.order_type,
+            from_address: req.from_address,
+            to_address: req.to_address,
+            token_id: req.token_id,
+            amount: req.amount,
+            bank_account: req.bank_account,
+            bank_service: req.bank_service,
+            banking_hash: req.banking_hash,
+            filler_id: None,
+            locked_amount: None,
+            status: OrderStatus::Pending,
+            batch_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Creation-time ordering key
+    ///
+    /// ULID ids carry their own timestamp and sort monotonically; legacy UUID ids fall back to
+    /// `created_at`, with the id breaking ties.
+    pub fn creation_key(&self) -> (DateTime<Utc>, &str) {
+        (order_id_timestamp(&self.id).unwrap_or(self.created_at), &self.id)
+    }
+
+    /// Update order status, stamping the order with `now`
+    pub fn update_status(&mut self, status: OrderStatus, now: DateTime<Utc>) {
+        self.status = status;
+        self.updated_at = now;
+    }
+
+    /// Assign order to a batch
+    pub fn assign_to_batch(&mut self, batch_id: u64, now: DateTime<Utc>) {
+        self.batch_id = Some(batch_id);
+        self.updated_at = now;
+    }
+    
+    /// Lock order for a filler
+    pub fn lock_for_filler(&mut self, filler_id: String, amount: String, now: DateTime<Utc>) {
+        self.filler_id = Some(filler_id);
+        self.locked_amount = Some(amount);
+        self.status = OrderStatus::Locked;
+        self.updated_at = now;
+    }
+    
+    /// Mark order as discovered (available for fillers)
+    pub fn mark_discovered(&mut self, now: DateTime<Utc>) {
+        self.status = OrderStatus::Discovery;
+        self.updated_at = now;
+    }
+    
+    /// Submit payment proof
+    pub fn submit_payment_proof(&mut self, banking_hash: String, now: DateTime<Utc>) {
+        self.banking_hash = Some(banking_hash);
+        self.status = OrderStatus::MarkPaid;
+        self.updated_at = now;
+    }
+
+    /// Check if order is finalized (cannot be modified)
+    pub fn is_finalized(&self) -> bool {
+        matches!(self.status, OrderStatus::Settled | OrderStatus::Failed)
+    }
+
+    /// Check if order can be matched
+    pub fn can_be_matched(&self) -> bool {
+        self.status == OrderStatus::Pending
+    }
+
+    /// Validate order data
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.is_empty() {
+            return Err("Order ID cannot be empty".to_string());
+        }
+
+        if self.amount.is_empty() || self.amount.parse::<u64>().is_err() {
+            return Err("Amount must be a valid positive number".to_string());
+        }
+
+        if self.token_id == 0 {
+            return Err("Token ID must be greater than 0".to_string());
+        }
+
+        match self.order_type {
+            OrderType::BridgeIn => {
+                if self.from_address.is_none() {
+                    return Err("BridgeIn orders require from_address".to_string());
+                }
+                if self.banking_hash.is_none() {
+                    return Err("BridgeIn orders require banking_hash".to_string());
+                }
+            }
+            OrderType::BridgeOut => {
+                if self.to_address.is_none() {
+                    return Err("BridgeOut orders require to_address".to_string());
+                }
+            }
+            OrderType::Transfer => {
+                if self.from_address.is_none() || self.to_address.is_none() {
+                    return Err("Transfer orders require both from_address and to_address".to_string());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl AccountState {
+    /// Create new account state
+    pub fn new(address: String) -> Self {
+        Self {
+            address,
+            balances: Vec::new(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Get balance for a specific token
+    pub fn get_balance(&self, token_id: u32) -> Option<&str> {
+        self.balances
+            .iter()
+            .find(|b| b.token_id == token_id)
+            .map(|b| b.balance.as_str())
+    }
+
+    /// Set balance for a specific token
+    pub fn set_balance(&mut self, token_id: u32, balance: String) {
+        if let Some(existing) = self.balances.iter_mut().find(|b| b.token_id == token_id) {
+            existing.balance = balance;
+        } else {
+            self.balances.push(TokenBalance { token_id, balance });
+        }
+        self.updated_at = Utc::now();
+    }
+
+    /// Add to balance for a specific token
+    pub fn add_balance(&mut self, token_id: u32, amount: &str) -> Result<(), String> {
+        let amount_value = amount.parse::<u64>()
+            .map_err(|_| "Invalid amount format".to_string())?;
+
+        if let Some(existing) = self.balances.iter_mut().find(|b| b.token_id == token_id) {
+            let current_value = existing.balance.parse::<u64>()
+                .map_err(|_| "Invalid existing balance format".to_string())?;
+            existing.balance = (current_value + amount_value).to_string();
+        } else {
+            self.balances.push(TokenBalance { 
+                token_id, 
+                balance: amount.to_string() 
+            });
+        }
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Subtract from balance for a specific token
+    pub fn subtract_balance(&mut self, token_id: u32, amount: &str) -> Result<(), String> {
+        let amount_value = amount.parse::<u64>()
+            .map_err(|_| "Invalid amount format".to_string())?;
+
+        if let Some(existing) = self.balances.iter_mut().find(|b| b.token_id == token_id) {
+            let current_value = existing.balance.parse::<u64>()
+                .map_err(|_| "Invalid existing balance format".to_string())?;
+            
+            if current_value < amount_value {
+                return Err("Insufficient balance".to_string());
+            }
+            
+            existing.balance = (current_value - amount_value).to_string();
+            self.updated_at = Utc::now();
+            Ok(())
+        } else {
+            Err("Token balance not found".to_string())
+        }
+    }
+
+    /// Generate hash for Merkle tree leaf
+    pub fn hash_leaf(&self) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.address.as_bytes());
+        
+        // Sort balances by token_id for deterministic hashing
+        let mut sorted_balances = self.balances.clone();
+        sorted_balances.sort_by_key(|b| b.token_id);
+        
+        for balance in sorted_balances {
+            hasher.update(balance.token_id.to_le_bytes());
+            hasher.update(balance.balance.as_bytes());
+        }
+        
+        hasher.finalize().into()
+    }
+}
+
+impl TokenBalance {
+    /// Create new token balance
+    pub fn new(token_id: u32, balance: String) -> Self {
+        Self { token_id, balance }
+    }
+
+    /// Check if balance is zero
+    pub fn is_zero(&self) -> bool {
+        self.balance == "0" || self.balance.is_empty()
+    }
+
+    /// Parse balance as u64
+    pub fn as_u64(&self) -> Result<u64, String> {
+        self.balance.parse().map_err(|_| "Invalid balance format".to_string())
+    }
+}
+
+#[cfg(feature = "tree")]
+impl SparseMerkleLeaf for AccountState {
+    fn hash_leaf(&self, _key: &str) -> anyhow::Result<[u8; 32]> {
+        Ok(Keccak256::digest(self.leaf_preimage()).into())
+    }
+    
+    fn key_to_path(&self, key: &str, depth: usize) -> String {
+        ethereum_address_to_path(key, depth)
+    }
+}
+
+impl AccountState {
+    /// Bytes hashed into the account's leaf: the address, then each balance in token order
+    pub fn leaf_preimage(&self) -> Vec<u8> {
+        account_leaf_preimage(&self.address, self.balances.iter().map(|b| (b.token_id, b.balance.as_str())))
+    }
+}
+
+// Orders are hashed with their batch id, which the server's order tree supplies
+#[cfg(feature = "tree")]
+impl SparseMerkleLeaf for Order {
+    fn hash_leaf(&self, _key: &str) -> anyhow::Result<[u8; 32]> {
+        // This should not be called directly - use hash_leaf_with_batch_id instead
+        Err(anyhow::anyhow!("Order hash_leaf requires batch context - use OrderMerkleTree instead"))
+    }
+    
+    fn key_to_path(&self, key: &str, depth: usize) -> String {
+        index_to_path(key, depth)
+    }
+}
+
+impl Order {
+    /// Hash leaf with batch ID context
+    pub fn hash_leaf_with_batch_id(&self, batch_id: u64) -> anyhow::Result<[u8; 32]> {
+        Ok(Keccak256::digest(self.leaf_preimage_with_batch_id(batch_id)).into())
+    }
+
+    /// Bytes hashed into the order's leaf for a batch
+    pub fn leaf_preimage_with_batch_id(&self, batch_id: u64) -> Vec<u8> {
+        let order_type = self.order_type as u8;
+        let (source_addr, dest_addr) = order_leaf_endpoints(order_type, self.from_address.as_deref(), self.to_address.as_deref());
+
+        order_leaf_preimage(
+            batch_id,
+            &self.id,
+            order_type,
+            source_addr,
+            dest_addr,
+            self.token_id,
+            &self.amount,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{is_hex_address, PermitData};
+    use serde_json;
+
+    #[test]
+    fn test_order_ids_sort_in_creation_order() {
+        let ids: Vec<String> = (0..100).map(|_| new_order_id()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let before = Utc::now() - chrono::Duration::seconds(1);
+        let timestamp = order_id_timestamp(&ids[0]).unwrap();
+        assert!(timestamp > before && timestamp <= Utc::now());
+        assert_eq!(order_id_timestamp("2f1c1b3e-7a55-4c2b-9f0e-3d8a6b1c2d4e"), None);
+
+        // Legacy UUID orders sort by created_at among ULID orders
+        let order = |id: String, created_at: DateTime<Utc>| Order {
+            id,
+            order_type: OrderType::Transfer,
+            from_address: None,
+            to_address: None,
+            token_id: 1,
+            amount: "1".to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            status: OrderStatus::Pending,
+            batch_id: None,
+            created_at,
+            updated_at: created_at,
+        };
+        let mut orders = vec![
+            order(ids[1].clone(), Utc::now()),
+            order("ffffffff-0000-4000-8000-000000000000".to_string(), before),
+            order(ids[0].clone(), Utc::now()),
+        ];
+        sort_by_creation(&mut orders);
+        let sorted: Vec<&str> = orders.iter().map(|order| order.id.as_str()).collect();
+        assert_eq!(sorted, vec!["ffffffff-0000-4000-8000-000000000000", ids[0].as_str(), ids[1].as_str()]);
+    }
+
+    #[test]
+    fn test_filler_settlement_addresses() {
+        let address = filler_settlement_address("filler_1");
+        assert!(is_hex_address(&address));
+        assert!(address.starts_with(FILLER_SETTLEMENT_PREFIX));
+        assert_eq!(address, filler_settlement_address("filler_1"));
+        assert_ne!(address, filler_settlement_address("filler_2"));
+
+        assert_eq!(resolve_account_address("filler:filler_1"), address);
+        assert_eq!(
+            resolve_account_address("0x1234567890123456789012345678901234567890"),
+            "0x1234567890123456789012345678901234567890"
+        );
+    }
+
+    #[test]
+    fn test_order_type_serialization() {
+        // Test enum values match expected i32 representation
+        assert_eq!(OrderType::BridgeIn as i32, 0);
+        assert_eq!(OrderType::BridgeOut as i32, 1);
+        assert_eq!(OrderType::Transfer as i32, 2);
+
+        // Test JSON serialization (serde serializes enums as strings by default)
+        let bridge_in = OrderType::BridgeIn;
+        let json = serde_json::to_string(&bridge_in).unwrap();
+        assert_eq!(json, "\"BridgeIn\"");
+
+        // Test JSON deserialization from string
+        let deserialized: OrderType = serde_json::from_str("\"BridgeOut\"").unwrap();
+        assert_eq!(deserialized, OrderType::BridgeOut);
+    }
+
+    #[test]
+    fn test_order_type_from_i32() {
+        assert_eq!(OrderType::from(0), OrderType::BridgeIn);
+        assert_eq!(OrderType::from(1), OrderType::BridgeOut);
+        assert_eq!(OrderType::from(2), OrderType::Transfer);
+        assert_eq!(OrderType::from(999), OrderType::BridgeIn); // Default fallback
+    }
+
+    #[test]
+    fn test_order_status_serialization() {
+        // Test enum values
+        assert_eq!(OrderStatus::Pending as i32, 0);
+        assert_eq!(OrderStatus::Discovery as i32, 1);
+        assert_eq!(OrderStatus::Locked as i32, 2);
+        assert_eq!(OrderStatus::MarkPaid as i32, 3);
+        assert_eq!(OrderStatus::Settled as i32, 4);
+        assert_eq!(OrderStatus::Failed as i32, 5);
+        assert_eq!(OrderStatus::Disputed as i32, 6);
+        assert_eq!(OrderStatus::ReviewPending as i32, 7);
+
+        // Test serialization round-trip
+        let status = OrderStatus::MarkPaid;
+        let json = serde_json::to_string(&status).unwrap();
+        let deserialized: OrderStatus = serde_json::from_str(&json).unwrap();
+        assert_eq!(status, deserialized);
+    }
+
+    #[test]
+    fn test_order_status_from_i32() {
+        assert_eq!(OrderStatus::from(0), OrderStatus::Pending);
+        assert_eq!(OrderStatus::from(1), OrderStatus::Discovery);
+        assert_eq!(OrderStatus::from(2), OrderStatus::Locked);
+        assert_eq!(OrderStatus::from(3), OrderStatus::MarkPaid);
+        assert_eq!(OrderStatus::from(4), OrderStatus::Settled);
+        assert_eq!(OrderStatus::from(5), OrderStatus::Failed);
+        assert_eq!(OrderStatus::from(6), OrderStatus::Disputed);
+        assert_eq!(OrderStatus::from(7), OrderStatus::ReviewPending);
+        assert_eq!(OrderStatus::from(-1), OrderStatus::Pending); // Default fallback
+    }
+
+    #[test]
+    fn test_batch_status_enum() {
+        assert_eq!(BatchStatus::Building as i32, 0);
+        assert_eq!(BatchStatus::Proving as i32, 1);
+        assert_eq!(BatchStatus::Submitting as i32, 2);
+        assert_eq!(BatchStatus::Submitted as i32, 3);
+        assert_eq!(BatchStatus::Failed as i32, 4);
+
+        // Test conversion
+        assert_eq!(BatchStatus::from(2), BatchStatus::Submitting);
+        assert_eq!(BatchStatus::from(100), BatchStatus::Building); // Default
+    }
+
+    #[test]
+    fn test_order_creation() {
+        let create_req = CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: Some("0xabcdef1234567890".to_string()),
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        };
+
+        let order = Order::new(create_req);
+
+        assert_eq!(order.order_type, OrderType::BridgeIn);
+        assert_eq!(order.status, OrderStatus::Pending);
+        assert_eq!(order.token_id, 1);
+        assert_eq!(order.amount, "1000000");
+        assert!(order.from_address.is_some());
+        assert!(order.banking_hash.is_some());
+        assert!(order.batch_id.is_none());
+        assert!(!order.id.is_empty());
+    }
+
+    #[test]
+    fn test_order_validation() {
+        // Valid BridgeIn order
+        let mut order = Order {
+            id: "test-order".to_string(),
+            order_type: OrderType::BridgeIn,
+            status: OrderStatus::Pending,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: Some("0xhash".to_string()),
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        assert!(order.validate().is_ok());
+
+        // Invalid: missing from_address for BridgeIn
+        order.from_address = None;
+        assert!(order.validate().is_err());
+        assert!(order.validate().unwrap_err().contains("from_address"));
+
+        // Invalid: missing banking_hash for BridgeIn
+        order.from_address = Some("0x1234567890123456789012345678901234567890".to_string());
+        order.banking_hash = None;
+        assert!(order.validate().is_err());
+        assert!(order.validate().unwrap_err().contains("banking_hash"));
+
+        // Test BridgeOut validation
+        order.order_type = OrderType::BridgeOut;
+        order.banking_hash = Some("0xhash".to_string());
+        order.to_address = None;
+        assert!(order.validate().is_err());
+        assert!(order.validate().unwrap_err().contains("to_address"));
+
+        // Test Transfer validation
+        order.order_type = OrderType::Transfer;
+        order.to_address = Some("0x9876543210987654321098765432109876543210".to_string());
+        order.from_address = None;
+        assert!(order.validate().is_err());
+        assert!(order.validate().unwrap_err().contains("both from_address and to_address"));
+
+        // Test invalid amount
+        order.from_address = Some("0x1234567890123456789012345678901234567890".to_string());
+        order.amount = "invalid".to_string();
+        assert!(order.validate().is_err());
+        assert!(order.validate().unwrap_err().contains("Amount"));
+
+        // Test zero token_id
+        order.amount = "1000000".to_string();
+        order.token_id = 0;
+        assert!(order.validate().is_err());
+        assert!(order.validate().unwrap_err().contains("Token ID"));
+    }
+
+    #[test]
+    fn test_order_status_transitions() {
+        let mut order = Order {
+            id: "test-order".to_string(),
+            order_type: OrderType::BridgeIn,
+            status: OrderStatus::Pending,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: Some("0xhash".to_string()),
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        // Test status update
+        let locked_at = order.updated_at + chrono::Duration::minutes(1);
+        order.update_status(OrderStatus::Locked, locked_at);
+        assert_eq!(order.status, OrderStatus::Locked);
+        assert_eq!(order.updated_at, locked_at);
+
+        // Test batch assignment
+        let batched_at = locked_at + chrono::Duration::minutes(1);
+        order.assign_to_batch(123, batched_at);
+        assert_eq!(order.batch_id, Some(123));
+        assert_eq!(order.updated_at, batched_at);
+
+        // Test state checks
+        assert!(!order.can_be_matched()); // Not pending anymore
+        assert!(!order.is_finalized()); // Not settled or failed
+
+        order.update_status(OrderStatus::Settled, batched_at);
+        assert!(order.is_finalized());
+        assert!(!order.can_be_matched());
+    }
+
+    #[test]
+    fn test_order_hash_deterministic() {
+        let order = Order {
+            id: "test-order".to_string(),
+            order_type: OrderType::BridgeIn,
+            status: OrderStatus::Pending,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: Some("0xhash".to_string()),
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        // Note: hash_leaf_with_batch_id returns Result<[u8; 32]>
+        // For model tests, we test the data structure integrity instead
+        assert_eq!(order.id, "test-order");
+        assert_eq!(order.order_type, OrderType::BridgeIn);
+        assert_eq!(order.amount, "1000000");
+    }
+
+    #[test]
+    fn test_account_state_creation() {
+        let address = "0x1234567890123456789012345678901234567890".to_string();
+        let account = AccountState::new(address.clone());
+
+        assert_eq!(account.address, address);
+        assert!(account.balances.is_empty());
+        assert!(account.updated_at <= Utc::now());
+    }
+
+    #[test]
+    fn test_account_state_balance_operations() {
+        let mut account = AccountState::new("0x1234567890123456789012345678901234567890".to_string());
+
+        // Test setting initial balance
+        account.set_balance(1, "1000000".to_string());
+        assert_eq!(account.get_balance(1), Some("1000000"));
+        assert_eq!(account.get_balance(2), None);
+
+        // Test adding balance
+        account.add_balance(1, "500000").unwrap();
+        assert_eq!(account.get_balance(1), Some("1500000"));
+
+        // Test adding balance for new token
+        account.add_balance(2, "2000000").unwrap();
+        assert_eq!(account.get_balance(2), Some("2000000"));
+        assert_eq!(account.balances.len(), 2);
+
+        // Test subtracting balance
+        account.subtract_balance(1, "300000").unwrap();
+        assert_eq!(account.get_balance(1), Some("1200000"));
+
+        // Test insufficient balance error
+        let result = account.subtract_balance(1, "2000000");
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Insufficient balance"));
+
+        // Test subtracting from non-existent token
+        let result = account.subtract_balance(3, "100");
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Token balance not found"));
+    }
+
+    #[test]
+    fn test_account_state_hash_deterministic() {
+        let mut account1 = AccountState::new("0x1234567890123456789012345678901234567890".to_string());
+        account1.set_balance(1, "1000000".to_string());
+        account1.set_balance(2, "2000000".to_string());
+
+        let mut account2 = AccountState::new("0x1234567890123456789012345678901234567890".to_string());
+        // Add balances in different order
+        account2.set_balance(2, "2000000".to_string());
+        account2.set_balance(1, "1000000".to_string());
+
+        let hash1 = account1.hash_leaf();
+        let hash2 = account2.hash_leaf();
+        assert_eq!(hash1, hash2, "Hash should be deterministic regardless of insertion order");
+
+        // Different address should produce different hash
+        let mut account3 = AccountState::new("0x9876543210987654321098765432109876543210".to_string());
+        account3.set_balance(1, "1000000".to_string());
+        account3.set_balance(2, "2000000".to_string());
+        
+        let hash3 = account3.hash_leaf();
+        assert_ne!(hash1, hash3, "Different address should produce different hash");
+    }
+
+    #[test]
+    fn test_token_balance_operations() {
+        let balance = TokenBalance::new(1, "1000000".to_string());
+        assert_eq!(balance.token_id, 1);
+        assert_eq!(balance.balance, "1000000");
+        assert_eq!(balance.as_u64().unwrap(), 1000000);
+
+        let zero_balance = TokenBalance::new(1, "0".to_string());
+        assert!(zero_balance.is_zero());
+
+        let empty_balance = TokenBalance::new(1, "".to_string());
+        assert!(empty_balance.is_zero());
+
+        let invalid_balance = TokenBalance::new(1, "invalid".to_string());
+        assert!(invalid_balance.as_u64().is_err());
+    }
+
+    #[test]
+    fn test_json_serialization_roundtrip() {
+        let order = Order {
+            id: "test-order".to_string(),
+            order_type: OrderType::Transfer,
+            status: OrderStatus::MarkPaid,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: Some("0x9876543210987654321098765432109876543210".to_string()),
+            token_id: 1,
+            amount: "1000000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: Some("0xbankinghash".to_string()),
+            filler_id: None,
+            locked_amount: None,
+            batch_id: Some(123),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        // Test Order serialization
+        let json = serde_json::to_string(&order).unwrap();
+        let deserialized: Order = serde_json::from_str(&json).unwrap();
+        assert_eq!(order.id, deserialized.id);
+        assert_eq!(order.order_type, deserialized.order_type);
+        assert_eq!(order.status, deserialized.status);
+        assert_eq!(order.amount, deserialized.amount);
+
+        // Test AccountState serialization
+        let mut account = AccountState::new("0x1234567890123456789012345678901234567890".to_string());
+        account.set_balance(1, "1000000".to_string());
+        account.set_balance(2, "2000000".to_string());
+
+        let json = serde_json::to_string(&account).unwrap();
+        let deserialized: AccountState = serde_json::from_str(&json).unwrap();
+        assert_eq!(account.address, deserialized.address);
+        assert_eq!(account.balances.len(), deserialized.balances.len());
+    }
+
+    #[test]
+    fn test_large_amount_handling() {
+        let large_amount = "999999999999999999999999999999999999"; // 36 digits
+        
+        let mut account = AccountState::new("0x1234567890123456789012345678901234567890".to_string());
+        account.set_balance(1, large_amount.to_string());
+        
+        assert_eq!(account.get_balance(1), Some(large_amount));
+        
+        // Test that hash works with large amounts
+        let hash = account.hash_leaf();
+        assert_eq!(hash.len(), 32); // Should produce valid 32-byte hash
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        // Empty strings
+        let mut account = AccountState::new("".to_string());
+        account.set_balance(1, "0".to_string());
+        let hash = account.hash_leaf();
+        assert_eq!(hash.len(), 32);
+
+        // Unicode in addresses (should handle gracefully)
+        let unicode_address = "0x1234567890123456789012345678901234567890🎯";
+        let mut account = AccountState::new(unicode_address.to_string());
+        account.set_balance(1, "1000".to_string());
+        let hash = account.hash_leaf();
+        assert_eq!(hash.len(), 32);
+
+        // Zero balances
+        let balance = TokenBalance::new(1, "0".to_string());
+        assert!(balance.is_zero());
+        assert_eq!(balance.as_u64().unwrap(), 0);
+    }
+
+    fn test_permit() -> PermitData {
+        PermitData {
+            owner: "0x1234567890123456789012345678901234567890".to_string(),
+            spender: "0x0000000000000000000000000000000000000001".to_string(),
+            value: "1000000".to_string(),
+            nonce: None,
+            deadline: 1_900_000_000,
+            signature: format!("0x{}1c", "ab".repeat(64)),
+        }
+    }
+
+    #[test]
+    fn test_permit_validation() {
+        let bridge = "0x0000000000000000000000000000000000000001";
+        let owner = "0x1234567890123456789012345678901234567890";
+        let now = DateTime::from_timestamp(1_800_000_000, 0).unwrap();
+        let permit = test_permit();
+
+        assert!(permit.validate("1000000", Some(owner), bridge, now).is_ok());
+        assert!(permit.validate("999", None, bridge, now).is_ok());
+        assert!(permit.validate("1000001", Some(owner), bridge, now).is_err()); // Value too small
+        assert!(permit.validate("1000000", Some("0x9999999999999999999999999999999999999999"), bridge, now).is_err());
+        assert!(permit.validate("1000000", Some(owner), "0x0000000000000000000000000000000000000002", now).is_err());
+
+        let expired = permit.validate("1000000", Some(owner), bridge, now + chrono::Duration::days(10_000));
+        assert!(expired.unwrap_err().contains("deadline"));
+
+        let mut bad_signature = test_permit();
+        bad_signature.signature = "0x1234".to_string();
+        assert!(bad_signature.validate("1000000", Some(owner), bridge, now).is_err());
+
+        let mut bad_v = test_permit();
+        bad_v.signature = format!("0x{}05", "ab".repeat(64));
+        assert!(bad_v.validate("1000000", Some(owner), bridge, now).is_err());
+    }
+
+    #[test]
+    fn test_permit_digest() {
+        let mut permit = test_permit();
+        let domain = [7u8; 32];
+        assert!(permit.digest(&domain).unwrap_err().contains("nonce"));
+
+        permit.nonce = Some("0".to_string());
+        let digest = permit.digest(&domain).unwrap();
+        assert_eq!(permit.digest(&domain).unwrap(), digest);
+        assert_ne!(permit.digest(&[8u8; 32]).unwrap(), digest);
+
+        // Every signed field is part of the digest
+        let mut other = permit.clone();
+        other.nonce = Some("1".to_string());
+        assert_ne!(other.digest(&domain).unwrap(), digest);
+        let mut other = permit.clone();
+        other.deadline += 1;
+        assert_ne!(other.digest(&domain).unwrap(), digest);
+        let mut other = permit.clone();
+        other.value = "1000001".to_string();
+        assert_ne!(other.digest(&domain).unwrap(), digest);
+    }
+
+    #[test]
+    fn test_permit_signature_and_deadline() {
+        let mut permit = test_permit();
+        let (v, r, s) = permit.split_signature().unwrap();
+        assert_eq!(v, 28);
+        assert_eq!(r, [0xab; 32]);
+        assert_eq!(s, [0xab; 32]);
+
+        permit.signature = format!("0x{}00", "ab".repeat(64));
+        assert_eq!(permit.split_signature().unwrap().0, 27);
+
+        let deadline = DateTime::from_timestamp(1_900_000_000, 0).unwrap();
+        assert!(!permit.is_expired(deadline));
+        assert!(permit.is_expired(deadline + chrono::Duration::seconds(1)));
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = Cursor::new(Utc::now(), new_order_id());
+        let encoded = cursor.encode();
+        assert!(encoded.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+        assert_eq!(Cursor::decode(&encoded), Ok(cursor));
+
+        assert_eq!(Cursor::decode("not a cursor"), Err(InvalidCursor));
+        assert_eq!(Cursor::decode(&URL_SAFE_NO_PAD.encode(b"{\"id\":\"x\"}")), Err(InvalidCursor));
+    }
+
+    #[test]
+    fn test_paginate_sets_cursor_only_when_more_rows_exist() {
+        let now = Utc::now();
+        let rows: Vec<u32> = (1..=3).collect();
+        let cursor_of = |row: &u32| Cursor::new(now, row.to_string());
+
+        let (page, next_cursor) = paginate(rows.clone(), 2, cursor_of);
+        assert_eq!(page, vec![1, 2]);
+        assert_eq!(Cursor::decode(&next_cursor.unwrap()).unwrap().id, "2");
+
+        // A full last page has no next page
+        let (page, next_cursor) = paginate(rows, 3, cursor_of);
+        assert_eq!(page.len(), 3);
+        assert!(next_cursor.is_none());
+
+        assert_eq!(page_size(None, 20), 20);
+        assert_eq!(page_size(Some(1000), 20), MAX_PAGE_SIZE);
+        assert_eq!(page_size(Some(0), 20), 1);
+    }
+}
@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use vapor_merkle::proof::VerifyError;
+
+/// Errors building, parsing or checking Merkle proofs
+#[derive(Debug, thiserror::Error)]
+pub enum ProofError {
+    #[error("Unknown proof format '{0}', expected siblings, raw or sorted_pairs")]
+    UnknownFormat(String),
+    #[error("Proof has {siblings} siblings but {path_bits} path bits")]
+    PathLengthMismatch { siblings: usize, path_bits: usize },
+    #[error("Raw proofs need path_bits or index")]
+    MissingPathBits,
+    #[error("Cannot build a Merkle tree without leaves")]
+    EmptyTree,
+    #[error("Leaf index {0} out of range")]
+    LeafIndexOutOfRange(usize),
+    #[error("Hash is not valid hex: {0}")]
+    InvalidHex(String),
+    #[error("Hash must be 32 bytes: {0}")]
+    InvalidHashLength(String),
+    #[error("Malformed multiproof: {0}")]
+    InvalidMultiProof(&'static str),
+    #[error("Sorted-pair proofs are not available for account state")]
+    SortedPairsForAccount,
+    /// Failure inside the sparse Merkle tree manager
+    #[error("Merkle tree error: {0}")]
+    Tree(anyhow::Error),
+}
+
+impl From<VerifyError> for ProofError {
+    fn from(e: VerifyError) -> Self {
+        match e {
+            VerifyError::PathLengthMismatch { siblings, path_bits } => ProofError::PathLengthMismatch { siblings, path_bits },
+            VerifyError::InvalidHex(value) => ProofError::InvalidHex(value),
+            VerifyError::InvalidHashLength(value) => ProofError::InvalidHashLength(value),
+            VerifyError::InvalidMultiProof(reason) => ProofError::InvalidMultiProof(reason),
+        }
+    }
+}
+
+/// Output encodings for Merkle proofs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofFormat {
+    /// Plain sibling list from leaf to root (legacy output)
+    #[default]
+    Siblings,
+    /// Sibling list plus the leaf's path bits, for index-aware verifiers
+    Raw,
+    /// Sorted-pair proof accepted by OpenZeppelin's MerkleProof.verify and VaporBridge
+    SortedPairs,
+}
+
+impl ProofFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProofFormat::Siblings => "siblings",
+            ProofFormat::Raw => "raw",
+            ProofFormat::SortedPairs => "sorted_pairs",
+        }
+    }
+}
+
+impl FromStr for ProofFormat {
+    type Err = ProofError;
+
+    fn from_str(s: &str) -> Result<Self, ProofError> {
+        match s {
+            "siblings" => Ok(ProofFormat::Siblings),
+            "raw" => Ok(ProofFormat::Raw),
+            "sorted_pairs" | "oz" => Ok(ProofFormat::SortedPairs),
+            other => Err(ProofError::UnknownFormat(other.to_string())),
+        }
+    }
+}
+
+pub use vapor_merkle::proof::{bit_path_to_path_bits, hash_sorted_pair, index_to_path_bits, process_sorted_proof, to_hex32};
+
+/// Fold a positional proof back to its root
+/// `path_bits[i]` is 1 when the node at height i is a right child
+pub fn process_raw_proof(leaf: [u8; 32], siblings: &[[u8; 32]], path_bits: &[u8]) -> Result<[u8; 32], ProofError> {
+    Ok(vapor_merkle::proof::process_raw_proof(leaf, siblings, path_bits)?)
+}
+
+/// Fold a positional multiproof back to its root (see `OrderMultiProof` for the layout)
+///
+/// `leaf_indices` must be strictly ascending, as generated.
+pub fn process_multi_proof(leaves: &[[u8; 32]], leaf_indices: &[usize], proof: &[[u8; 32]], proof_flags: &[bool]) -> Result<[u8; 32], ProofError> {
+    Ok(vapor_merkle::proof::process_multi_proof(leaves, leaf_indices, proof, proof_flags)?)
+}
+
+/// Dense Merkle tree hashed with sorted pairs, matching OpenZeppelin's MerkleProof
+/// An unpaired node at the end of a layer is carried up unchanged
+pub struct SortedPairTree {
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl SortedPairTree {
+    pub fn from_leaves(leaves: Vec<[u8; 32]>) -> Result<Self, ProofError> {
+        if leaves.is_empty() {
+            return Err(ProofError::EmptyTree);
+        }
+
+        let mut layers = vec![leaves];
+        while layers.last().unwrap().len() > 1 {
+            let next = layers.last().unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_sorted_pair(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next);
+        }
+
+        Ok(Self { layers })
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.layers.last().unwrap()[0]
+    }
+
+    pub fn leaf(&self, index: usize) -> Option<[u8; 32]> {
+        self.layers[0].get(index).copied()
+    }
+
+    pub fn proof(&self, index: usize) -> Result<Vec<[u8; 32]>, ProofError> {
+        if index >= self.layers[0].len() {
+            return Err(ProofError::LeafIndexOutOfRange(index));
+        }
+
+        let mut proof = Vec::new();
+        let mut index = index;
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+
+        Ok(proof)
+    }
+}
+
+/// A Merkle proof rendered in one of the supported formats
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormattedProof {
+    pub format: ProofFormat,
+    pub leaf_hash: String,
+    pub proof: Vec<String>,
+    pub root: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_bits: Option<Vec<u8>>,
+}
+
+impl FormattedProof {
+    /// Render a positional proof; path bits are only emitted for the raw format
+    pub fn positional(format: ProofFormat, leaf: [u8; 32], siblings: &[[u8; 32]], path_bits: Vec<u8>, root: [u8; 32]) -> Self {
+        Self {
+            format,
+            leaf_hash: to_hex32(&leaf),
+            proof: siblings.iter().map(to_hex32).collect(),
+            root: to_hex32(&root),
+            path_bits: (format == ProofFormat::Raw).then_some(path_bits),
+        }
+    }
+
+    pub fn sorted_pairs(leaf: [u8; 32], proof: &[[u8; 32]], root: [u8; 32]) -> Self {
+        Self {
+            format: ProofFormat::SortedPairs,
+            leaf_hash: to_hex32(&leaf),
+            proof: proof.iter().map(to_hex32).collect(),
+            root: to_hex32(&root),
+            path_bits: None,
+        }
+    }
+}
+
+/// Parse a 32-byte hash with or without 0x prefix
+pub fn parse_hash32(value: &str) -> Result<[u8; 32], ProofError> {
+    Ok(vapor_merkle::proof::parse_hash32(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha3::{Digest, Keccak256};
+    use vapor_merkle::proof::hash_pair;
+
+    // Reference values computed with keccak256(abi.encodePacked(...)) as in Solidity,
+    // leaves are keccak256(abi.encodePacked(uint256(i))) for i = 1..=5
+    const LEAVES: [&str; 5] = [
+        "b10e2d527612073b26eecdfd717e6a320cf44b4afac2b0732d9fcbe2b7fa0cf6",
+        "405787fa12a823e0f2b7631cc41b3ba8828b3321ca811111fa75cd3aa3bb5ace",
+        "c2575a0e9e593c00f959f8c92f12db2869c3395a3b0502d05e2516446f71f85b",
+        "8a35acfbc15ff81a39ae7d344fd709f28e8600b4aa8c65c6b64bfe7fe36bd19b",
+        "036b6384b5eca791c62761152d0c79bb0604c104a5fb6f4eb0703f3154bb3db0",
+    ];
+    const SORTED_ROOT_2: &str = "2a171b5bcd1449348c3e09a5424946b5e6d6f5471221941d585131d673952ee4";
+    const SORTED_ROOT_5: &str = "9be4d908ee1467e12177bdda3d2712a12e7a2445350dccd4be9c218066530b19";
+    const SORTED_PROOF_5_INDEX_0: [&str; 3] = [
+        "405787fa12a823e0f2b7631cc41b3ba8828b3321ca811111fa75cd3aa3bb5ace",
+        "58dedfa8c8510aa7a44a262de0df204bc81f3b437741b6b63212d1173a876672",
+        "036b6384b5eca791c62761152d0c79bb0604c104a5fb6f4eb0703f3154bb3db0",
+    ];
+    const SORTED_PROOF_5_INDEX_4: [&str; 1] = [
+        "9cb86f87624f55e4956a62a87acdd72769cdb21f746c27d345ef90343a9b2316",
+    ];
+    const POSITIONAL_ROOT_4: &str = "1e8cc8511a4954df48a80e5f5b8da3419a99ba3e7697574234e10893022167fc";
+    const POSITIONAL_PROOF_4_INDEX_2: [&str; 2] = [
+        "8a35acfbc15ff81a39ae7d344fd709f28e8600b4aa8c65c6b64bfe7fe36bd19b",
+        "50387073e2d4f7060a3c02c3c5268d8a72700a28b5cbd7e23314ae0e1ebda895",
+    ];
+
+    fn h(value: &str) -> [u8; 32] {
+        parse_hash32(value).unwrap()
+    }
+
+    fn leaves(count: usize) -> Vec<[u8; 32]> {
+        LEAVES[..count].iter().map(|leaf| h(leaf)).collect()
+    }
+
+    #[test]
+    fn test_leaf_vectors_match_solidity_keccak() {
+        for (i, leaf) in LEAVES.iter().enumerate() {
+            let mut encoded = [0u8; 32];
+            encoded[31] = (i + 1) as u8;
+            let hash: [u8; 32] = Keccak256::digest(encoded).into();
+            assert_eq!(hex::encode(hash), *leaf);
+        }
+    }
+
+    #[test]
+    fn test_sorted_pair_tree_matches_reference() {
+        assert_eq!(hex::encode(SortedPairTree::from_leaves(leaves(1)).unwrap().root()), LEAVES[0]);
+        assert_eq!(hex::encode(SortedPairTree::from_leaves(leaves(2)).unwrap().root()), SORTED_ROOT_2);
+
+        let tree = SortedPairTree::from_leaves(leaves(5)).unwrap();
+        assert_eq!(hex::encode(tree.root()), SORTED_ROOT_5);
+
+        let proof: Vec<String> = tree.proof(0).unwrap().iter().map(hex::encode).collect();
+        assert_eq!(proof, SORTED_PROOF_5_INDEX_0);
+        let proof: Vec<String> = tree.proof(4).unwrap().iter().map(hex::encode).collect();
+        assert_eq!(proof, SORTED_PROOF_5_INDEX_4);
+
+        for index in 0..5 {
+            let proof = tree.proof(index).unwrap();
+            assert_eq!(process_sorted_proof(tree.leaf(index).unwrap(), &proof), tree.root());
+        }
+
+        assert!(matches!(tree.proof(5), Err(ProofError::LeafIndexOutOfRange(5))));
+        assert!(matches!(SortedPairTree::from_leaves(vec![]), Err(ProofError::EmptyTree)));
+    }
+
+    #[test]
+    fn test_sorted_pair_hash_is_commutative() {
+        let (a, b) = (h(LEAVES[0]), h(LEAVES[1]));
+        assert_eq!(hash_sorted_pair(&a, &b), hash_sorted_pair(&b, &a));
+        assert_eq!(hex::encode(hash_sorted_pair(&a, &b)), SORTED_ROOT_2);
+    }
+
+    #[test]
+    fn test_raw_proof_matches_reference() {
+        let siblings: Vec<[u8; 32]> = POSITIONAL_PROOF_4_INDEX_2.iter().map(|s| h(s)).collect();
+        let path_bits = index_to_path_bits(2, 2);
+        assert_eq!(path_bits, vec![0, 1]);
+
+        let root = process_raw_proof(h(LEAVES[2]), &siblings, &path_bits).unwrap();
+        assert_eq!(hex::encode(root), POSITIONAL_ROOT_4);
+
+        // Wrong index does not reproduce the root
+        let root = process_raw_proof(h(LEAVES[2]), &siblings, &index_to_path_bits(1, 2)).unwrap();
+        assert_ne!(hex::encode(root), POSITIONAL_ROOT_4);
+
+        assert!(process_raw_proof(h(LEAVES[2]), &siblings, &[0]).is_err());
+    }
+
+    #[test]
+    fn test_multi_proof_matches_reference() {
+        let leaves = leaves(4);
+        // Leaves 0 and 1 pair with each other, leaf 3 needs leaf 2, and the two parents pair up
+        let root = process_multi_proof(&[leaves[0], leaves[1], leaves[3]], &[0, 1, 3], &[leaves[2]], &[true, false, true]).unwrap();
+        assert_eq!(hex::encode(root), POSITIONAL_ROOT_4);
+
+        // A single leaf is a plain positional proof
+        let siblings: Vec<_> = POSITIONAL_PROOF_4_INDEX_2.iter().map(|sibling| h(sibling)).collect();
+        let root = process_multi_proof(&[leaves[2]], &[2], &siblings, &[false, false]).unwrap();
+        assert_eq!(hex::encode(root), POSITIONAL_ROOT_4);
+
+        assert!(process_multi_proof(&[leaves[1], leaves[0]], &[1, 0], &[], &[true]).is_err());
+        assert!(process_multi_proof(&[leaves[0], leaves[2]], &[0, 2], &[], &[true]).is_err());
+        assert!(process_multi_proof(&[leaves[0]], &[0], &[leaves[1]], &[false, false]).is_err());
+    }
+
+    #[test]
+    fn test_bit_path_conversion() {
+        assert_eq!(bit_path_to_path_bits("0010"), vec![0, 1, 0, 0]);
+        assert_eq!(bit_path_to_path_bits("0010"), index_to_path_bits(2, 4));
+    }
+
+    #[test]
+    fn test_formatted_proof_output() {
+        let leaf = h(LEAVES[0]);
+        let sibling = h(LEAVES[1]);
+
+        let proof = FormattedProof::positional(ProofFormat::Raw, leaf, &[sibling], vec![0], hash_pair(&leaf, &sibling));
+        assert_eq!(proof.path_bits, Some(vec![0]));
+        assert!(proof.proof[0].starts_with("0x"));
+
+        let proof = FormattedProof::positional(ProofFormat::Siblings, leaf, &[sibling], vec![0], hash_pair(&leaf, &sibling));
+        assert!(proof.path_bits.is_none());
+
+        assert_eq!("oz".parse::<ProofFormat>().unwrap(), ProofFormat::SortedPairs);
+        assert!("bogus".parse::<ProofFormat>().is_err());
+    }
+}
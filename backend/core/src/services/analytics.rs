@@ -0,0 +1,51 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures_core::future::BoxFuture;
+use serde::Serialize;
+
+use crate::types::{OrderStatus, OrderType};
+
+/// Token paid in and bank service paid out, as in the market summary
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Corridor {
+    pub token_id: u32,
+    pub bank_service: String,
+}
+
+/// An order event with everything that identifies a person hashed or bucketed away
+///
+/// Addresses, order ids and filler ids are salted hashes, so events of one order or address
+/// can still be joined; bank accounts, payment proofs and exact amounts are left out.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnalyticsEvent {
+    /// Kind of the event log event this was built from, e.g. `order_locked`
+    pub event: String,
+    pub order: String,
+    pub order_type: OrderType,
+    pub status: Option<OrderStatus>,
+    pub corridor: Corridor,
+    pub amount_bucket: &'static str,
+    pub from_address: Option<String>,
+    pub to_address: Option<String>,
+    pub filler: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+    /// Time since the order was created
+    pub order_age_ms: i64,
+}
+
+/// Where analytics events are delivered
+pub trait AnalyticsSink: Send + Sync {
+    fn send<'a>(&'a self, event: &'a AnalyticsEvent) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Writes each event as a line of JSON to stdout
+pub struct StdoutSink;
+
+impl AnalyticsSink for StdoutSink {
+    fn send<'a>(&'a self, event: &'a AnalyticsEvent) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            println!("{}", serde_json::to_string(event)?);
+            Ok(())
+        })
+    }
+}
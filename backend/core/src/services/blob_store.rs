@@ -0,0 +1,113 @@
+use anyhow::Result;
+use futures_core::future::BoxFuture;
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Location and content hash of a stored blob; only this is kept in the database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobRef {
+    /// Key relative to the store root, `<first byte>/<hash>`
+    pub reference: String,
+    /// `0x`-prefixed keccak256 of the content
+    pub checksum: String,
+    pub size: u64,
+}
+
+impl BlobRef {
+    pub fn for_content(data: &[u8]) -> Self {
+        let hash = hex::encode(Keccak256::digest(data));
+        Self {
+            reference: format!("{}/{}", &hash[..2], hash),
+            checksum: format!("0x{}", hash),
+            size: data.len() as u64,
+        }
+    }
+}
+
+/// Content-addressed storage for proof artifacts and state snapshots
+///
+/// Implementations only move bytes under a key; `put`, `get` and `delete` add the
+/// content hashing and the checksum check on read.
+pub trait BlobStore: Send + Sync {
+    /// Short name of the backend, e.g. "fs", "s3" or "memory"
+    fn kind(&self) -> &'static str;
+
+    fn write<'a>(&'a self, key: &'a str, data: &'a [u8]) -> BoxFuture<'a, Result<()>>;
+
+    /// None if there is no object under `key`
+    fn read<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>>>;
+
+    /// Removing a missing object is not an error
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<()>>;
+
+    /// Store `data` under its content hash
+    fn put<'a>(&'a self, data: &'a [u8]) -> BoxFuture<'a, Result<BlobRef>> {
+        Box::pin(async move {
+            let blob = BlobRef::for_content(data);
+            self.write(&blob.reference, data).await?;
+            Ok(blob)
+        })
+    }
+
+    /// Load a blob, failing if its content no longer matches `checksum`
+    fn get<'a>(&'a self, reference: &'a str, checksum: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>>> {
+        Box::pin(async move {
+            let Some(data) = self.read(reference).await? else {
+                return Ok(None);
+            };
+            let actual = BlobRef::for_content(&data).checksum;
+            if actual != checksum {
+                return Err(anyhow::anyhow!("Blob {} is corrupt: expected checksum {}, got {}", reference, checksum, actual));
+            }
+            Ok(Some(data))
+        })
+    }
+
+    fn delete<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<()>> {
+        self.remove(reference)
+    }
+}
+
+/// Blobs held in process memory, lost on restart
+#[derive(Default)]
+pub struct MemoryBlobStore {
+    objects: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl BlobStore for MemoryBlobStore {
+    fn kind(&self) -> &'static str {
+        "memory"
+    }
+
+    fn write<'a>(&'a self, key: &'a str, data: &'a [u8]) -> BoxFuture<'a, Result<()>> {
+        self.objects.lock().unwrap().insert(key.to_string(), data.to_vec());
+        Box::pin(async { Ok(()) })
+    }
+
+    fn read<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>>> {
+        let data = self.objects.lock().unwrap().get(key).cloned();
+        Box::pin(async move { Ok(data) })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<()>> {
+        self.objects.lock().unwrap().remove(key);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_identical_content_shares_a_blob() {
+        let store = MemoryBlobStore::default();
+        let first = store.put(b"proof").await.unwrap();
+        let second = store.put(b"proof").await.unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(store.objects.lock().unwrap().len(), 1);
+        assert_eq!(first.checksum, format!("0x{}", hex::encode(Keccak256::digest(b"proof"))));
+    }
+}
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use std::fmt::Debug;
+use std::sync::Arc;
+#[cfg(any(test, feature = "testing"))]
+use {chrono::Duration, std::sync::Mutex};
+
+/// Source of the current time for lock expiry, SLA timers, batch schedules and timestamps
+///
+/// Services take a `SharedClock` instead of calling `Utc::now()` so time-based behaviour
+/// can be tested by moving a `MockClock` instead of sleeping.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Wall-clock time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// The clock used outside tests
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// Time that only moves when told to; clones share the same time
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl MockClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self { now: Arc::new(Mutex::new(now)) }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        *now += by;
+    }
+
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(self.clone())
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_mock_clock_moves_only_when_told() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+        let clock = MockClock::new(start);
+        let shared = clock.shared();
+        assert_eq!(shared.now(), start);
+
+        clock.advance(Duration::minutes(30));
+        assert_eq!(shared.now(), start + Duration::minutes(30));
+
+        clock.set(start);
+        assert_eq!(shared.now(), start);
+        assert!(SystemClock.now() > start);
+    }
+}
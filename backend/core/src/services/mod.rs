@@ -0,0 +1,10 @@
+//! Extension points the server's services are built on
+//!
+//! Each trait comes with the data that crosses it; the implementations that talk to SMTP,
+//! S3, screening APIs and the like stay in the server.
+
+pub mod clock;
+pub mod blob_store;
+pub mod screening;
+pub mod analytics;
+pub mod notifications;
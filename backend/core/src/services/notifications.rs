@@ -0,0 +1,167 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures_core::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+/// Lifecycle events a user can be notified of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    /// A filler locked the order
+    OrderLocked,
+    /// The filler submitted proof of the fiat payment
+    PaymentReceived,
+    OrderSettled,
+}
+
+impl NotificationEvent {
+    pub const ALL: [NotificationEvent; 3] = [
+        NotificationEvent::OrderLocked,
+        NotificationEvent::PaymentReceived,
+        NotificationEvent::OrderSettled,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationEvent::OrderLocked => "order_locked",
+            NotificationEvent::PaymentReceived => "payment_received",
+            NotificationEvent::OrderSettled => "order_settled",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str() == text)
+    }
+
+    /// Subject and body templates; `{name}` placeholders are filled from the order
+    pub fn template(&self) -> (&'static str, &'static str) {
+        match self {
+            NotificationEvent::OrderLocked => (
+                "Order {order_id} was picked up",
+                "Filler {filler_id} locked your order {order_id} for {amount} of token {token_id}. \
+                 They will now pay out to your {bank_service} account.",
+            ),
+            NotificationEvent::PaymentReceived => (
+                "Payment sent for order {order_id}",
+                "Filler {filler_id} reports paying out order {order_id} to your {bank_service} account. \
+                 Check the payment arrived; the order settles once it is verified.",
+            ),
+            NotificationEvent::OrderSettled => (
+                "Order {order_id} settled",
+                "Your order {order_id} for {amount} of token {token_id} is settled.",
+            ),
+        }
+    }
+}
+
+/// How a notification reaches the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationChannel {
+    Email,
+    /// JSON POSTed to the user's URL
+    Webhook,
+    /// Written to the server's stdout, for development
+    Console,
+}
+
+impl NotificationChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationChannel::Email => "email",
+            NotificationChannel::Webhook => "webhook",
+            NotificationChannel::Console => "console",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "email" => Some(NotificationChannel::Email),
+            "webhook" => Some(NotificationChannel::Webhook),
+            "console" => Some(NotificationChannel::Console),
+            _ => None,
+        }
+    }
+
+    /// Whether `destination` is an address this channel can deliver to
+    pub fn accepts_destination(&self, destination: &str) -> bool {
+        match self {
+            NotificationChannel::Email => destination.split_once('@').is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.')),
+            NotificationChannel::Webhook => destination.starts_with("https://") || destination.starts_with("http://"),
+            NotificationChannel::Console => true,
+        }
+    }
+}
+
+/// A user's choice of events for one channel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationPreference {
+    pub channel: NotificationChannel,
+    /// Email address or webhook URL; ignored for the console
+    #[serde(default)]
+    pub destination: String,
+    pub events: Vec<NotificationEvent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Out of attempts
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "pending" => Some(DeliveryStatus::Pending),
+            "delivered" => Some(DeliveryStatus::Delivered),
+            "failed" => Some(DeliveryStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// A rendered notification and how its delivery went
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub id: String,
+    pub address: String,
+    pub order_id: String,
+    pub event: NotificationEvent,
+    pub channel: NotificationChannel,
+    pub destination: String,
+    pub subject: String,
+    pub body: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Sends notifications over one channel
+pub trait NotificationTransport: Send + Sync {
+    fn send<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Writes each notification as a line of JSON to stdout
+pub struct ConsoleTransport;
+
+impl NotificationTransport for ConsoleTransport {
+    fn send<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            println!("{}", serde_json::to_string(notification)?);
+            Ok(())
+        })
+    }
+}
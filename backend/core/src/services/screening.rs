@@ -0,0 +1,35 @@
+use anyhow::Result;
+use futures_core::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+/// Why a provider lists an address, e.g. category "sanctions" and the list's name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identification {
+    pub category: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// Checks addresses against sanctions lists; an empty answer means the address is clear
+pub trait ScreeningProvider: Send + Sync {
+    /// Short name of the provider, recorded with each result
+    fn kind(&self) -> &'static str;
+
+    fn screen<'a>(&'a self, address: &'a str) -> BoxFuture<'a, Result<Vec<Identification>>>;
+}
+
+/// Screens nothing
+pub struct NoScreening;
+
+impl ScreeningProvider for NoScreening {
+    fn kind(&self) -> &'static str {
+        "none"
+    }
+
+    fn screen<'a>(&'a self, _address: &'a str) -> BoxFuture<'a, Result<Vec<Identification>>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+}
@@ -1,7 +1,11 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
-use std::collections::HashMap;
+use lru::LruCache;
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+
+pub use super::leaf_encoding::{ethereum_address_to_path, index_to_path};
 
 /// Generic Sparse Merkle Tree with dynamic sizing
 /// Supports any data type that can be hashed and indexed by a key
@@ -10,8 +14,8 @@ pub struct SparseMerkleTree<T> {
     pub depth: usize,
     /// Data indexed by key
     pub data: HashMap<String, T>,
-    /// Cached intermediate nodes for efficiency (path -> hash)
-    pub cached_nodes: HashMap<String, [u8; 32]>,
+    /// Cached intermediate nodes for efficiency (path -> hash), least recently used evicted first
+    pub cached_nodes: NodeCache,
     /// Current root hash
     pub root: Option<[u8; 32]>,
     /// Zero hash for empty nodes at each level
@@ -20,6 +24,125 @@ pub struct SparseMerkleTree<T> {
     pub min_depth: usize,
     /// Maximum depth to prevent memory issues
     pub max_depth: usize,
+    /// Sorted leaf paths of the stored keys, rebuilt after the data or depth changes
+    leaf_paths: Option<Vec<String>>,
+}
+
+/// Nodes cached per tree unless configured otherwise (about 25 MiB)
+pub const DEFAULT_NODE_CACHE_CAPACITY: usize = 1 << 18;
+
+/// A node's bit path packed most significant bit first, with its length; paths of the
+/// 160-level account tree would otherwise take a heap string of up to 160 bytes each
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeKey {
+    len: u16,
+    bits: [u8; 32],
+}
+
+impl NodeKey {
+    pub fn from_path(path: &str) -> Self {
+        let mut bits = [0u8; 32];
+        for (i, bit) in path.bytes().enumerate() {
+            if bit == b'1' {
+                bits[i / 8] |= 0x80 >> (i % 8);
+            }
+        }
+        Self { len: path.len() as u16, bits }
+    }
+}
+
+/// Approximate bytes per cached node: key and hash, plus the LRU list links and hash table slot
+const NODE_CACHE_ENTRY_BYTES: usize = std::mem::size_of::<NodeKey>() + 32 + 4 * std::mem::size_of::<usize>();
+
+/// Size-bounded LRU of node hashes
+///
+/// Nodes are pure functions of the tree data, so an evicted node is simply recomputed from
+/// its children the next time it is needed.
+pub struct NodeCache {
+    entries: LruCache<NodeKey, [u8; 32]>,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+/// Node cache instrumentation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeCacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Approximate memory held by the cached nodes
+    pub memory_bytes: usize,
+}
+
+impl NodeCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: LruCache::new(NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN)),
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    pub fn get(&mut self, path: &str) -> Option<[u8; 32]> {
+        let hash = self.entries.get(&NodeKey::from_path(path)).copied();
+        if hash.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        hash
+    }
+
+    pub fn insert(&mut self, path: &str, hash: [u8; 32]) {
+        let key = NodeKey::from_path(path);
+        if let Some((evicted, _)) = self.entries.push(key, hash) {
+            if evicted != key {
+                self.evictions += 1;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.cap().get()
+    }
+
+    /// Change the bound, evicting least recently used nodes if the cache is over it
+    pub fn set_capacity(&mut self, capacity: usize) {
+        let before = self.entries.len();
+        self.entries.resize(NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN));
+        self.evictions += (before - self.entries.len()) as u64;
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.entries.len() * NODE_CACHE_ENTRY_BYTES
+    }
+
+    pub fn stats(&self) -> NodeCacheStats {
+        NodeCacheStats {
+            entries: self.entries.len(),
+            capacity: self.capacity(),
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            memory_bytes: self.memory_usage(),
+        }
+    }
 }
 
 /// Trait for data types that can be stored in sparse Merkle trees
@@ -48,6 +171,7 @@ pub struct TreeStats {
     pub cache_size: usize,
     pub optimal_depth: usize,
     pub memory_usage: usize,
+    pub cache: NodeCacheStats,
 }
 
 /// Batch proof generation result
@@ -83,21 +207,28 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
         Self {
             depth: actual_depth,
             data: HashMap::new(),
-            cached_nodes: HashMap::new(),
+            cached_nodes: NodeCache::new(DEFAULT_NODE_CACHE_CAPACITY),
             root: None,
             zero_hashes,
             min_depth,
             max_depth,
+            leaf_paths: None,
         }
     }
     
+    /// Bound the node cache to `capacity` nodes
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        self.cached_nodes.set_capacity(capacity);
+        self
+    }
+
     /// Create tree with optimal depth based on expected data size
     pub fn new_for_size(expected_items: usize) -> Self {
         let optimal_depth = if expected_items <= 1 {
             4 // Minimum depth
         } else {
             // Calculate depth needed: log2(expected_items) + 1 for safety margin
-            ((expected_items as f64).log2().ceil() as usize + 1).max(4).min(32)
+            ((expected_items as f64).log2().ceil() as usize + 1).clamp(4, 32)
         };
         Self::new_with_bounds(optimal_depth, 4, 32)
     }
@@ -113,8 +244,24 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
         if needed_depth != self.depth {
             self.resize(needed_depth)?;
         }
+        self.separate_colliding_keys()
+    }
+
+    /// Deepen the tree until every key has a leaf of its own
+    ///
+    /// Keys whose paths share a prefix as long as the depth would land on the same leaf, and
+    /// all but one of them would silently be left out of the root.
+    fn separate_colliding_keys(&mut self) -> Result<()> {
+        while self.depth < self.max_depth && self.has_colliding_keys() {
+            self.resize(self.depth + 1)?;
+        }
         Ok(())
     }
+
+    fn has_colliding_keys(&self) -> bool {
+        let mut paths = HashSet::with_capacity(self.data.len());
+        !self.data.iter().all(|(key, value)| paths.insert(value.key_to_path(key, self.depth)))
+    }
     
     /// Resize the tree to a new depth
     fn resize(&mut self, new_depth: usize) -> Result<()> {
@@ -140,6 +287,7 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
         self.depth = bounded_depth;
         self.zero_hashes = zero_hashes;
         self.cached_nodes.clear(); // Invalidate cache
+        self.leaf_paths = None;
         self.root = None;
         
         Ok(())
@@ -148,13 +296,14 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
     pub fn clear(&mut self) {
         self.data.clear();
         self.cached_nodes.clear();
+        self.leaf_paths = None;
         self.root = None;
     }
     
     pub fn insert(&mut self, key: String, value: T) -> Result<()> {
         self.data.insert(key, value);
         self.invalidate_cache();
-        Ok(())
+        self.separate_colliding_keys()
     }
     
     /// Batch insert multiple items efficiently
@@ -169,7 +318,7 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
         }
         
         self.invalidate_cache();
-        Ok(())
+        self.separate_colliding_keys()
     }
     
     /// Build tree from scratch with known items (most efficient)
@@ -180,6 +329,7 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
             tree.data.insert(key, value);
         }
         
+        tree.separate_colliding_keys()?;
         Ok(tree)
     }
     
@@ -187,6 +337,7 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
     fn invalidate_cache(&mut self) {
         // For now, clear all cache. Could be optimized to only clear affected paths
         self.cached_nodes.clear();
+        self.leaf_paths = None;
         self.root = None;
     }
     
@@ -199,15 +350,16 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
             optimal_depth: if self.data.len() <= 1 { 
                 4 
             } else { 
-                ((self.data.len() as f64).log2().ceil() as usize + 1).max(4).min(32)
+                ((self.data.len() as f64).log2().ceil() as usize + 1).clamp(4, 32)
             },
             memory_usage: self.estimate_memory_usage(),
+            cache: self.cached_nodes.stats(),
         }
     }
     
     fn estimate_memory_usage(&self) -> usize {
         let data_size = self.data.len() * (32 + 64); // rough estimate
-        let cache_size = self.cached_nodes.len() * 32;
+        let cache_size = self.cached_nodes.memory_usage();
         let zero_hashes_size = self.zero_hashes.len() * 32;
         data_size + cache_size + zero_hashes_size
     }
@@ -247,11 +399,11 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
             proof_hashes.push(hex::encode(sibling_hash));
         }
         
-        // Get leaf hash
+        // Get leaf hash; an absent key proves whatever holds its leaf (empty, or a key sharing its path)
         let leaf_hash = if let Some(data) = self.data.get(key) {
             data.hash_leaf(key)?
         } else {
-            self.zero_hashes[0]
+            self.compute_node_hash(path, self.depth)?
         };
         
         Ok(MerkleProof {
@@ -265,7 +417,12 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
     /// Recursively compute node hash for sparse tree
     fn compute_node_hash(&mut self, path: String, level: usize) -> Result<[u8; 32]> {
         if let Some(cached) = self.cached_nodes.get(&path) {
-            return Ok(*cached);
+            return Ok(cached);
+        }
+        
+        // Subtrees without any keys hash to the zero hash of their height
+        if !self.has_leaves_under(&path) {
+            return Ok(self.zero_hashes[self.depth - level]);
         }
         
         if level == self.depth {
@@ -276,7 +433,7 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
                 self.zero_hashes[0] // Empty leaf
             };
             
-            self.cached_nodes.insert(path, hash);
+            self.cached_nodes.insert(&path, hash);
             return Ok(hash);
         }
         
@@ -293,10 +450,23 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
         hasher.update(right_hash);
         let hash = hasher.finalize().into();
         
-        self.cached_nodes.insert(path, hash);
+        self.cached_nodes.insert(&path, hash);
         Ok(hash)
     }
     
+    /// Whether any stored key's path starts with `prefix`
+    fn has_leaves_under(&mut self, prefix: &str) -> bool {
+        let depth = self.depth;
+        let data = &self.data;
+        let paths = self.leaf_paths.get_or_insert_with(|| {
+            let mut paths: Vec<String> = data.iter().map(|(key, value)| value.key_to_path(key, depth)).collect();
+            paths.sort();
+            paths
+        });
+        let first = paths.partition_point(|path| path.as_str() < prefix);
+        paths.get(first).is_some_and(|path| path.starts_with(prefix))
+    }
+    
     /// Find data that matches the given bit path
     fn find_data_at_path(&self, path: &str) -> Option<&T> {
         for (key, data) in &self.data {
@@ -356,15 +526,15 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
                 format!("{}0", current_path)
             };
             
-            let sibling_hash = self.compute_node_hash(sibling_path, self.depth - level - 1)?;
+            let sibling_hash = self.compute_node_hash(sibling_path, level + 1)?;
             proof_hashes.push(hex::encode(sibling_hash));
         }
         
-        // Get leaf hash
+        // Get leaf hash; an absent key proves whatever holds its leaf (empty, or a key sharing its path)
         let leaf_hash = if let Some(data) = self.data.get(key) {
             data.hash_leaf(key)?
         } else {
-            self.zero_hashes[0]
+            self.compute_node_hash(path, self.depth)?
         };
         
         Ok(MerkleProof {
@@ -392,36 +562,6 @@ impl<T: SparseMerkleLeaf + Clone> SparseMerkleTree<T> {
     }
 }
 
-/// Utility functions for path conversion
-pub fn ethereum_address_to_path(address: &str, depth: usize) -> String {
-    // Remove 0x prefix if present
-    let clean_addr = if address.starts_with("0x") {
-        &address[2..]
-    } else {
-        address
-    };
-    
-    // Convert hex to binary string
-    let mut bit_path = String::new();
-    for hex_char in clean_addr.chars() {
-        let digit = u8::from_str_radix(&hex_char.to_string(), 16).unwrap_or(0);
-        bit_path.push_str(&format!("{:04b}", digit));
-    }
-    
-    // Ensure exactly the required depth
-    bit_path.truncate(depth);
-    while bit_path.len() < depth {
-        bit_path.push('0');
-    }
-    
-    bit_path
-}
-
-pub fn index_to_path(index_str: &str, depth: usize) -> String {
-    let index: usize = index_str.parse().unwrap_or(0);
-    format!("{:0width$b}", index, width = depth)
-}
-
 /// Solidity-compatible hashing utilities
 pub fn solidity_keccak256_hash(data: &[&[u8]]) -> [u8; 32] {
     let mut hasher = Keccak256::new();
@@ -479,4 +619,149 @@ mod tests {
         let index_path = index_to_path("5", 8);
         assert_eq!(index_path, "00000101");
     }
+
+    #[test]
+    fn test_node_cache_is_bounded() {
+        // Prefixes of one another must not collide once packed
+        let keys = ["", "0", "00", "1", "10", "010"].map(NodeKey::from_path);
+        assert_eq!(keys.iter().collect::<HashSet<_>>().len(), keys.len());
+
+        let mut tree = SparseMerkleTree::new(8).with_cache_capacity(16);
+        for index in 0..40 {
+            tree.insert(index.to_string(), TestData { value: format!("test{}", index) }).unwrap();
+        }
+        tree.compute_root().unwrap();
+        tree.generate_proof("7").unwrap();
+
+        let stats = tree.get_stats().cache;
+        assert_eq!((stats.entries, stats.capacity), (16, 16));
+        assert!(stats.evictions > 0);
+        assert!(stats.misses > 0);
+        assert_eq!(stats.memory_bytes, 16 * NODE_CACHE_ENTRY_BYTES);
+
+        tree.cached_nodes.set_capacity(4);
+        assert_eq!(tree.cached_nodes.len(), 4);
+        assert_eq!(tree.cached_nodes.stats().evictions, stats.evictions + 12);
+
+        // Reads refresh recency, so the node read last survives the next eviction
+        let mut cache = NodeCache::new(2);
+        cache.insert("0", [1; 32]);
+        cache.insert("1", [2; 32]);
+        assert_eq!(cache.get("0"), Some([1; 32]));
+        cache.insert("00", [3; 32]);
+        assert_eq!((cache.get("1"), cache.get("0")), (None, Some([1; 32])));
+        assert_eq!((cache.stats().hits, cache.stats().misses, cache.stats().evictions), (2, 1, 1));
+    }
+
+    mod properties {
+        use super::*;
+        use crate::proof_format::{bit_path_to_path_bits, process_raw_proof};
+        use proptest::collection::btree_map;
+        use proptest::prelude::*;
+        use std::collections::BTreeMap;
+
+        const DEPTH: usize = 6;
+
+        fn build(items: &[(u8, String)]) -> SparseMerkleTree<TestData> {
+            let mut tree = SparseMerkleTree::new(DEPTH);
+            for (index, value) in items {
+                tree.insert(index.to_string(), TestData { value: value.clone() }).unwrap();
+            }
+            tree
+        }
+
+        /// Fold the proof back up using the key's path and compare with the proof's root
+        fn verifies(proof: &MerkleProof) -> bool {
+            let to_hash = |value: &str| -> [u8; 32] { hex::decode(value).unwrap().try_into().unwrap() };
+            let siblings: Vec<[u8; 32]> = proof.proof.iter().map(|sibling| to_hash(sibling)).collect();
+            let path_bits = bit_path_to_path_bits(&index_to_path(&proof.key, siblings.len()));
+            process_raw_proof(to_hash(&proof.leaf_hash), &siblings, &path_bits).unwrap() == to_hash(&proof.root)
+        }
+
+        /// Up to 24 distinct leaf indices of the depth-6 tree with arbitrary values
+        fn leaves() -> impl Strategy<Value = BTreeMap<u8, String>> {
+            btree_map(0u8..64, "[a-z0-9]{1,12}", 1..24)
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(64))]
+
+            #[test]
+            fn root_is_independent_of_insertion_order(
+                (items, shuffled) in leaves()
+                    .prop_map(|leaves| leaves.into_iter().collect::<Vec<_>>())
+                    .prop_flat_map(|items| (Just(items.clone()), Just(items).prop_shuffle()))
+            ) {
+                prop_assert_eq!(build(&items).compute_root().unwrap(), build(&shuffled).compute_root().unwrap());
+            }
+
+            #[test]
+            fn every_key_has_a_verifying_proof(leaves in leaves()) {
+                let items: Vec<_> = leaves.into_iter().collect();
+                let mut tree = build(&items);
+                let root = hex::encode(tree.compute_root().unwrap());
+
+                for (index, value) in &items {
+                    let proof = tree.generate_proof(&index.to_string()).unwrap();
+                    prop_assert_eq!(&proof.root, &root);
+                    prop_assert_eq!(proof.proof.len(), DEPTH);
+                    prop_assert_eq!(&proof.leaf_hash, &hex::encode(TestData { value: value.clone() }.hash_leaf("").unwrap()));
+                    prop_assert!(verifies(&proof));
+                }
+
+                // Batch proofs agree with the individual ones
+                let keys: Vec<String> = items.iter().map(|(index, _)| index.to_string()).collect();
+                let batch = tree.generate_batch_proofs(&keys).unwrap();
+                for proof in &batch.proofs {
+                    prop_assert_eq!(&proof.proof, &tree.generate_proof(&proof.key).unwrap().proof);
+                }
+            }
+
+            #[test]
+            fn eviction_does_not_change_proofs(leaves in leaves(), capacity in 1usize..16) {
+                let items: Vec<_> = leaves.into_iter().collect();
+                let mut unbounded = build(&items);
+                let mut bounded = build(&items).with_cache_capacity(capacity);
+
+                prop_assert_eq!(bounded.compute_root().unwrap(), unbounded.compute_root().unwrap());
+                for index in 0u8..64 {
+                    let proof = bounded.generate_proof(&index.to_string()).unwrap();
+                    prop_assert_eq!(&proof.proof, &unbounded.generate_proof(&index.to_string()).unwrap().proof);
+                    prop_assert!(verifies(&proof));
+                }
+                prop_assert!(bounded.cached_nodes.len() <= capacity);
+            }
+
+            #[test]
+            fn absent_keys_prove_an_empty_leaf(leaves in leaves(), absent in 0u8..64) {
+                prop_assume!(!leaves.contains_key(&absent));
+                let items: Vec<_> = leaves.into_iter().collect();
+                let mut tree = build(&items);
+
+                let proof = tree.generate_proof(&absent.to_string()).unwrap();
+                prop_assert_eq!(&proof.leaf_hash, &hex::encode([0u8; 32]));
+                prop_assert!(verifies(&proof));
+            }
+
+            #[test]
+            fn cached_root_tracks_updates(
+                leaves in leaves(),
+                updates in btree_map(0u8..64, "[a-z0-9]{1,12}", 1..8),
+            ) {
+                let mut tree = build(&leaves.clone().into_iter().collect::<Vec<_>>());
+                tree.compute_root().unwrap();
+                tree.generate_proof(&leaves.keys().next().unwrap().to_string()).unwrap();
+
+                let mut expected = leaves;
+                for (index, value) in &updates {
+                    tree.insert(index.to_string(), TestData { value: value.clone() }).unwrap();
+                    expected.insert(*index, value.clone());
+                }
+
+                let fresh = build(&expected.into_iter().collect::<Vec<_>>()).compute_root().unwrap();
+                prop_assert_eq!(tree.compute_root().unwrap(), fresh);
+            }
+        }
+    }
 }
+
@@ -0,0 +1,354 @@
+//! Request and response bodies the server accepts and the client sends
+//!
+//! Handlers deserialize these and the filler client serializes them, so both sides share one
+//! definition and a request cannot drift from what the server accepts.
+
+use chrono::{DateTime, Utc};
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[repr(i32)]
+pub enum OrderType {
+    BridgeIn = 0,
+    BridgeOut = 1,
+    Transfer = 2,
+}
+
+impl From<i32> for OrderType {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => OrderType::BridgeIn,
+            1 => OrderType::BridgeOut,
+            2 => OrderType::Transfer,
+            _ => OrderType::BridgeIn, // Default fallback
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum OrderStatus {
+    Pending = 0,        // Order created, waiting for blockchain confirmation
+    Discovery = 1,      // New: In discovery phase, visible to fillers
+    Locked = 2,         // Locked by a filler, waiting for payment
+    MarkPaid = 3,       // Filler has submitted payment proof
+    Settled = 4,        // Order completed and settled
+    Failed = 5,         // Order failed or cancelled
+    Disputed = 6,       // Payment claimed but never verified, needs manual review
+    ReviewPending = 7,  // Above the token's review threshold, waiting for an admin's approval
+}
+
+impl From<i32> for OrderStatus {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => OrderStatus::Pending,
+            1 => OrderStatus::Discovery,
+            2 => OrderStatus::Locked,
+            3 => OrderStatus::MarkPaid,
+            4 => OrderStatus::Settled,
+            5 => OrderStatus::Failed,
+            6 => OrderStatus::Disputed,
+            7 => OrderStatus::ReviewPending,
+            _ => OrderStatus::Pending, // Default fallback
+        }
+    }
+}
+
+// API request/response types
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateOrderRequest {
+    pub order_type: OrderType,
+    pub from_address: Option<String>,
+    pub to_address: Option<String>,
+    pub token_id: u32,
+    pub amount: String,
+    pub bank_account: Option<String>,     // New: Bank account for off-ramp
+    pub bank_service: Option<String>,     // New: Bank service name
+    pub banking_hash: Option<String>,
+    #[serde(default)]
+    pub permit: Option<PermitData>,       // EIP-2612 permit when depositing without approve
+    /// Fee offered for earlier matching under the priority_fee match policy, in token base units
+    #[serde(default)]
+    pub priority_fee: Option<u64>,
+    /// Create the order even though it looks like a duplicate of a recent one
+    #[serde(default)]
+    pub confirm_duplicate: bool,
+    /// Quote from POST /orders/quote whose terms the order takes
+    #[serde(default)]
+    pub quote_id: Option<String>,
+}
+
+/// EIP-2612 permit metadata for deposits made with depositWithPermit
+/// The signature is recovered against the token's EIP-712 domain separator (see `digest`)
+/// before the order is accepted
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PermitData {
+    pub owner: String,
+    pub spender: String,
+    pub value: String,
+    pub nonce: Option<String>,
+    pub deadline: u64,            // Unix timestamp (seconds)
+    pub signature: String,        // 65-byte r || s || v, hex encoded
+}
+
+impl PermitData {
+    /// Check the permit is well formed, unexpired at `now` and covers the deposit
+    pub fn validate(&self, amount: &str, from_address: Option<&str>, bridge_address: &str, now: DateTime<Utc>) -> Result<(), String> {
+        if !is_hex_address(&self.owner) || !is_hex_address(&self.spender) {
+            return Err("Permit owner and spender must be 20-byte hex addresses".to_string());
+        }
+
+        if let Some(from_address) = from_address {
+            if !self.owner.eq_ignore_ascii_case(from_address) {
+                return Err("Permit owner must match from_address".to_string());
+            }
+        }
+
+        if !self.spender.eq_ignore_ascii_case(bridge_address) {
+            return Err("Permit spender must be the bridge contract".to_string());
+        }
+
+        let value = U256::from_dec_str(&self.value)
+            .map_err(|_| "Permit value must be a decimal number".to_string())?;
+        let amount = U256::from_dec_str(amount)
+            .map_err(|_| "Amount must be a decimal number".to_string())?;
+        if value < amount {
+            return Err("Permit value does not cover the deposit amount".to_string());
+        }
+
+        if self.is_expired(now) {
+            return Err(format!("Permit deadline {} has passed", self.deadline));
+        }
+
+        self.split_signature().map(|_| ())
+    }
+
+    /// EIP-712 digest of `Permit(owner,spender,value,nonce,deadline)` under the token's
+    /// domain separator, i.e. what the owner signed
+    pub fn digest(&self, domain_separator: &[u8; 32]) -> Result<[u8; 32], String> {
+        let nonce = self.nonce.as_deref()
+            .ok_or_else(|| "Permit nonce is required to check its signature".to_string())?;
+        let nonce = U256::from_dec_str(nonce)
+            .map_err(|_| "Permit nonce must be a decimal number".to_string())?;
+        let value = U256::from_dec_str(&self.value)
+            .map_err(|_| "Permit value must be a decimal number".to_string())?;
+
+        let mut encoded = Vec::with_capacity(6 * 32);
+        encoded.extend_from_slice(&Keccak256::digest(PERMIT_TYPE));
+        encoded.extend_from_slice(&address_word(&self.owner)?);
+        encoded.extend_from_slice(&address_word(&self.spender)?);
+        encoded.extend_from_slice(&uint_word(value));
+        encoded.extend_from_slice(&uint_word(nonce));
+        encoded.extend_from_slice(&uint_word(U256::from(self.deadline)));
+
+        let mut message = Vec::with_capacity(2 + 32 + 32);
+        message.extend_from_slice(b"\x19\x01");
+        message.extend_from_slice(domain_separator);
+        message.extend_from_slice(&Keccak256::digest(&encoded));
+        Ok(Keccak256::digest(&message).into())
+    }
+
+    /// Whether the permit deadline has passed
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        (self.deadline as i64) < now.timestamp()
+    }
+
+    /// Split the signature into (v, r, s) as expected by permit()
+    pub fn split_signature(&self) -> Result<(u8, [u8; 32], [u8; 32]), String> {
+        let bytes = hex::decode(self.signature.trim_start_matches("0x"))
+            .map_err(|_| "Permit signature must be hex encoded".to_string())?;
+        if bytes.len() != 65 {
+            return Err("Permit signature must be 65 bytes".to_string());
+        }
+
+        let v = match bytes[64] {
+            0 | 1 => bytes[64] + 27,
+            27 | 28 => bytes[64],
+            _ => return Err("Permit signature has invalid v value".to_string()),
+        };
+
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        r.copy_from_slice(&bytes[..32]);
+        s.copy_from_slice(&bytes[32..64]);
+        Ok((v, r, s))
+    }
+}
+
+/// EIP-2612 permit struct type, hashed into every permit digest
+const PERMIT_TYPE: &[u8] = b"Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)";
+
+/// An address ABI-encoded as a left-padded 32-byte word
+fn address_word(address: &str) -> Result<[u8; 32], String> {
+    if !is_hex_address(address) {
+        return Err(format!("{} is not a 20-byte hex address", address));
+    }
+    let mut word = [0u8; 32];
+    hex::decode_to_slice(&address[2..], &mut word[12..]).map_err(|e| e.to_string())?;
+    Ok(word)
+}
+
+/// A uint256 ABI-encoded as a big-endian 32-byte word
+fn uint_word(value: U256) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    word
+}
+
+/// Whether `address` is a 0x-prefixed 20-byte hex address
+pub fn is_hex_address(address: &str) -> bool {
+    let Some(hex_part) = address.strip_prefix("0x") else { return false };
+    hex_part.len() == 40 && hex_part.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Request to lock an order for filling
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LockOrderRequest {
+    pub filler_id: String,
+    pub amount: String,
+    /// `current_rate` of a re-quote, to lock at the moved rate
+    #[serde(default)]
+    pub accepted_rate: Option<String>,
+    /// Fee the filler charges in basis points, compared under the `best_fee` lock window policy
+    #[serde(default)]
+    pub fee_bps: Option<u32>,
+}
+
+/// Request to submit payment proof
+///
+/// `payment_proof` is validated against the schema of the order's bank service (see
+/// `GET /bank-services`); without a `banking_hash` the proof's digest is used as one.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubmitPaymentProofRequest {
+    #[serde(default)]
+    pub banking_hash: Option<String>,
+    #[serde(default)]
+    pub payment_proof: Option<serde_json::Value>,
+}
+
+/// What happens to the unfilled part of a partially settled order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemainderAction {
+    /// Offer the remainder to fillers again
+    #[default]
+    Rediscover,
+    /// Return the remainder to the depositor
+    Refund,
+}
+
+/// Request to settle the locked portion of an order and split off the remainder
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SettlePartialRequest {
+    #[serde(default)]
+    pub remainder: RemainderAction,
+}
+
+/// A single entry in an order's status history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderStatusTransition {
+    pub order_id: String,
+    pub from_status: OrderStatus,
+    pub to_status: OrderStatus,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Three phases of order processing
+#[derive(Debug, Serialize, Deserialize)]
+pub enum OrderPhase {
+    PrivateListing,    // Order created, waiting for blockchain confirmation
+    FindingFillers,    // In discovery, looking for fillers
+    SendingUSD,        // Locked by filler, processing payment
+}
+
+/// Filler information
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FillerInfo {
+    pub id: String,
+    pub locked_amount: String,
+}
+
+/// Filler balance information
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FillerBalance {
+    pub filler_id: String,
+    pub total_balance: String,
+    pub available_balance: String, // Total - locked amounts
+    pub locked_balance: String,
+    pub completed_jobs: u32,
+    pub wallets: Vec<FillerWallet>,
+}
+
+/// Individual wallet for a filler
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FillerWallet {
+    pub address: String,
+    pub balance: String,
+    pub percentage: f32, // What percentage of total balance
+}
+
+/// Claim request for multiple wallets
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClaimRequest {
+    pub filler_id: String,
+    pub claims: Vec<WalletClaim>,
+    /// Token to be paid out in, when different from the earned token (e.g. 2 for PYUSD)
+    #[serde(default)]
+    pub payout_token_id: Option<u32>,
+}
+
+/// Individual wallet claim
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WalletClaim {
+    pub amount: String,
+    pub destination_address: String, // Where to send the claimed tokens
+    /// Batch of the on-chain BridgeOut order this claim redeems; given together with `order_id`
+    #[serde(default)]
+    pub batch_id: Option<u64>,
+    /// On-chain order id being redeemed; each order can be claimed once
+    #[serde(default)]
+    pub order_id: Option<u32>,
+}
+
+/// Conversion applied to a claim paid out in another token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenConversion {
+    pub from_token_id: u32,
+    pub to_token_id: u32,
+    pub source_amount: String,
+    pub converted_amount: String,
+    /// Units of the payout token per unit of the earned token, e.g. "1.000200"
+    pub rate: String,
+    /// Where the rate came from, e.g. "config"
+    pub rate_source: String,
+    pub quoted_at: DateTime<Utc>,
+}
+
+/// A claim as recorded in the claims table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimRecord {
+    pub id: String,
+    pub filler_id: String,
+    pub wallet_address: String,
+    pub destination_address: String,
+    /// Claimed (gross) amount in the earned token (`token_id`)
+    pub amount: String,
+    /// Claim fees taken out of `amount`
+    pub fee_amount: String,
+    /// `amount` less fees, what is transferred before any payout conversion
+    pub net_amount: String,
+    pub token_id: u32,
+    pub payout_token_id: u32,
+    pub payout_amount: String,
+    pub conversion: Option<TokenConversion>,
+    pub batch_id: Option<u64>,
+    /// On-chain order redeemed by the claim, if it references one
+    pub order_id: Option<u32>,
+    /// "pending" until the claim is seen settled on-chain, then "confirmed"
+    pub status: String,
+    pub transaction_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
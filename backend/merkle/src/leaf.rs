@@ -0,0 +1,154 @@
+//! How orders and accounts are encoded into tree leaves, and where those leaves sit
+//!
+//! Kept free of the server's models so verifiers can recompute leaves from the fields the
+//! proofs API and fixtures publish.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::Deserialize;
+use sha3::{Digest, Keccak256};
+
+/// `order_type` values as hashed into order leaves
+pub const BRIDGE_IN: u8 = 0;
+pub const BRIDGE_OUT: u8 = 1;
+
+const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+
+/// Source and destination addresses hashed into an order leaf
+///
+/// Bridge-in orders move funds within the user's wallet, so both are the sender; bridge-out
+/// orders come from the zero address; transfers use the addresses as given.
+pub fn order_leaf_endpoints<'a>(order_type: u8, from: Option<&'a str>, to: Option<&'a str>) -> (&'a str, &'a str) {
+    match order_type {
+        BRIDGE_IN => (from.unwrap_or_default(), from.unwrap_or_default()),
+        BRIDGE_OUT => (ZERO_ADDRESS, to.unwrap_or_default()),
+        _ => (from.unwrap_or_default(), to.unwrap_or_default()),
+    }
+}
+
+/// Packed order fields hashed into an order leaf
+pub fn order_leaf_preimage(
+    batch_id: u64,
+    order_id: &str,
+    order_type: u8,
+    from: &str,
+    to: &str,
+    token_id: u32,
+    amount: &str,
+) -> Vec<u8> {
+    let mut preimage = Vec::new();
+
+    preimage.extend_from_slice(&batch_id.to_be_bytes()); // Solidity uses big-endian
+    preimage.extend_from_slice(order_id.as_bytes());
+    preimage.push(order_type);
+    preimage.extend_from_slice(from.as_bytes());
+    preimage.extend_from_slice(to.as_bytes());
+    preimage.extend_from_slice(&token_id.to_be_bytes());
+    preimage.extend_from_slice(amount.as_bytes());
+
+    preimage
+}
+
+/// Bytes hashed into an account's leaf: the address, then each balance in token order
+pub fn account_leaf_preimage<'a>(address: &str, balances: impl IntoIterator<Item = (u32, &'a str)>) -> Vec<u8> {
+    let mut balances: Vec<_> = balances.into_iter().collect();
+    balances.sort_by_key(|(token_id, _)| *token_id);
+
+    let mut preimage = address.as_bytes().to_vec();
+    for (token_id, balance) in balances {
+        preimage.extend_from_slice(&token_id.to_be_bytes());
+        preimage.extend_from_slice(balance.as_bytes());
+    }
+    preimage
+}
+
+/// An order as published by the fixtures endpoint, enough to recompute its leaf
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderLeafFields {
+    pub order_id: String,
+    pub order_type: u8,
+    pub from_address: Option<String>,
+    pub to_address: Option<String>,
+    pub token_id: u32,
+    pub amount: String,
+}
+
+impl OrderLeafFields {
+    pub fn leaf_hash(&self, batch_id: u64) -> [u8; 32] {
+        let (from, to) = order_leaf_endpoints(self.order_type, self.from_address.as_deref(), self.to_address.as_deref());
+        let preimage = order_leaf_preimage(batch_id, &self.order_id, self.order_type, from, to, self.token_id, &self.amount);
+        Keccak256::digest(preimage).into()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LeafBalance {
+    pub token_id: u32,
+    pub balance: String,
+}
+
+/// An account and its balances, as published by the fixtures and accounts endpoints
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountLeafFields {
+    pub address: String,
+    pub balances: Vec<LeafBalance>,
+}
+
+impl AccountLeafFields {
+    pub fn leaf_hash(&self) -> [u8; 32] {
+        let balances = self.balances.iter().map(|balance| (balance.token_id, balance.balance.as_str()));
+        Keccak256::digest(account_leaf_preimage(&self.address, balances)).into()
+    }
+}
+
+/// Root-to-leaf bit path of an account: the address bits, truncated or zero-padded to `depth`
+pub fn ethereum_address_to_path(address: &str, depth: usize) -> String {
+    let clean_addr = address.strip_prefix("0x").unwrap_or(address);
+
+    // Convert hex to binary string
+    let mut bit_path = String::new();
+    for hex_char in clean_addr.chars() {
+        let digit = hex_char.to_digit(16).unwrap_or(0);
+        bit_path.push_str(&alloc::format!("{:04b}", digit));
+    }
+
+    // Ensure exactly the required depth
+    bit_path.truncate(depth);
+    while bit_path.len() < depth {
+        bit_path.push('0');
+    }
+
+    bit_path
+}
+
+/// Root-to-leaf bit path of an order at a decimal tree index
+pub fn index_to_path(index_str: &str, depth: usize) -> String {
+    let index: usize = index_str.parse().unwrap_or(0);
+    alloc::format!("{:0width$b}", index, width = depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(ethereum_address_to_path("0xA0", 12), "101000000000");
+        assert_eq!(ethereum_address_to_path("0xff", 4), "1111");
+        assert_eq!(index_to_path("5", 4), "0101");
+    }
+
+    #[test]
+    fn test_order_leaf_endpoints() {
+        let (from, to) = (Some("0xfrom"), Some("0xto"));
+        assert_eq!(order_leaf_endpoints(BRIDGE_IN, from, to), ("0xfrom", "0xfrom"));
+        assert_eq!(order_leaf_endpoints(BRIDGE_OUT, from, to), (ZERO_ADDRESS, "0xto"));
+        assert_eq!(order_leaf_endpoints(2, from, to), ("0xfrom", "0xto"));
+    }
+
+    #[test]
+    fn test_account_balances_are_hashed_in_token_order() {
+        let preimage = account_leaf_preimage("0xab", [(2, "5"), (1, "7")]);
+        assert_eq!(preimage, b"0xab\0\0\0\x017\0\0\0\x025");
+    }
+}
@@ -0,0 +1,32 @@
+//! Leaf encoding and Merkle proof verification for Vapor's order and account trees
+//!
+//! The backend builds its trees with this crate's leaf encoding and checks proofs with its
+//! fold functions, so a verifier using the same crate hashes byte for byte what the server
+//! hashed. It is `no_std` (with `alloc`); the `wasm` feature adds a wasm-bindgen API for
+//! verifying proofs in the browser.
+//!
+//! ```
+//! use vapor_merkle::{leaf::OrderLeafFields, proof::{index_to_path_bits, process_raw_proof}};
+//!
+//! let order = OrderLeafFields {
+//!     order_id: "order_1".into(),
+//!     order_type: vapor_merkle::leaf::BRIDGE_IN,
+//!     from_address: Some("0x1234567890123456789012345678901234567890".into()),
+//!     to_address: None,
+//!     token_id: 1,
+//!     amount: "1000".into(),
+//! };
+//! let leaf = order.leaf_hash(7);
+//! // A one-level tree: the order is the left leaf, an empty slot the right one
+//! let root = process_raw_proof(leaf, &[[0u8; 32]], &index_to_path_bits(0, 1)).unwrap();
+//! assert_eq!(root, vapor_merkle::proof::hash_pair(&leaf, &[0u8; 32]));
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod leaf;
+pub mod proof;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,135 @@
+//! Folding proofs back to their roots, with the two hash schemes the backend emits
+//!
+//! Positional proofs (sibling lists and multiproofs) hash each pair in tree order; sorted-pair
+//! proofs hash the smaller value first, as OpenZeppelin's `MerkleProof` does.
+
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use sha3::{Digest, Keccak256};
+
+/// Why a proof or hash could not be processed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    PathLengthMismatch { siblings: usize, path_bits: usize },
+    InvalidHex(String),
+    InvalidHashLength(String),
+    InvalidMultiProof(&'static str),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::PathLengthMismatch { siblings, path_bits } => {
+                write!(f, "Proof has {} siblings but {} path bits", siblings, path_bits)
+            }
+            VerifyError::InvalidHex(value) => write!(f, "Hash is not valid hex: {}", value),
+            VerifyError::InvalidHashLength(value) => write!(f, "Hash must be 32 bytes: {}", value),
+            VerifyError::InvalidMultiProof(reason) => write!(f, "Malformed multiproof: {}", reason),
+        }
+    }
+}
+
+/// keccak256(abi.encodePacked(left, right))
+pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// OpenZeppelin's commutative pair hash: the smaller value is hashed first
+pub fn hash_sorted_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        hash_pair(a, b)
+    } else {
+        hash_pair(b, a)
+    }
+}
+
+/// Fold a positional proof back to its root
+/// `path_bits[i]` is 1 when the node at height i is a right child
+pub fn process_raw_proof(leaf: [u8; 32], siblings: &[[u8; 32]], path_bits: &[u8]) -> Result<[u8; 32], VerifyError> {
+    if siblings.len() != path_bits.len() {
+        return Err(VerifyError::PathLengthMismatch { siblings: siblings.len(), path_bits: path_bits.len() });
+    }
+
+    Ok(siblings.iter().zip(path_bits).fold(leaf, |node, (sibling, bit)| {
+        if *bit == 0 {
+            hash_pair(&node, sibling)
+        } else {
+            hash_pair(sibling, &node)
+        }
+    }))
+}
+
+/// Fold a sorted-pair proof back to its root (MerkleProof.processProof)
+pub fn process_sorted_proof(leaf: [u8; 32], proof: &[[u8; 32]]) -> [u8; 32] {
+    proof.iter().fold(leaf, |node, sibling| hash_sorted_pair(&node, sibling))
+}
+
+/// Fold a positional multiproof back to its root
+///
+/// Each flag combines the next queued node with the following queued node (its right sibling)
+/// when set, or with the next proof hash otherwise. `leaf_indices` must be strictly ascending,
+/// as generated.
+pub fn process_multi_proof(leaves: &[[u8; 32]], leaf_indices: &[usize], proof: &[[u8; 32]], proof_flags: &[bool]) -> Result<[u8; 32], VerifyError> {
+    if leaves.is_empty() || leaves.len() != leaf_indices.len() {
+        return Err(VerifyError::InvalidMultiProof("needs one index per leaf"));
+    }
+    if leaf_indices.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(VerifyError::InvalidMultiProof("leaf indices must be strictly ascending"));
+    }
+    if leaves.len() + proof.len() != proof_flags.len() + 1 {
+        return Err(VerifyError::InvalidMultiProof("leaves and proof hashes must be one more than the flags"));
+    }
+
+    let mut queue: VecDeque<(usize, [u8; 32])> = leaf_indices.iter().copied().zip(leaves.iter().copied()).collect();
+    let mut proof = proof.iter();
+    for flag in proof_flags {
+        let (index, node) = queue.pop_front().ok_or(VerifyError::InvalidMultiProof("ran out of nodes"))?;
+        let parent = if *flag {
+            match queue.pop_front() {
+                Some((right_index, right)) if index & 1 == 0 && right_index == index + 1 => hash_pair(&node, &right),
+                _ => return Err(VerifyError::InvalidMultiProof("flagged nodes are not siblings")),
+            }
+        } else {
+            let sibling = proof.next().ok_or(VerifyError::InvalidMultiProof("ran out of proof hashes"))?;
+            if index & 1 == 0 {
+                hash_pair(&node, sibling)
+            } else {
+                hash_pair(sibling, &node)
+            }
+        };
+        queue.push_back((index / 2, parent));
+    }
+
+    match (queue.pop_front(), queue.is_empty()) {
+        (Some((0, root)), true) => Ok(root),
+        _ => Err(VerifyError::InvalidMultiProof("does not fold to a single root")),
+    }
+}
+
+/// Path bits (leaf to root) of a leaf index in a tree of the given depth
+pub fn index_to_path_bits(index: usize, depth: usize) -> Vec<u8> {
+    (0..depth).map(|level| ((index >> level) & 1) as u8).collect()
+}
+
+/// Path bits (leaf to root) from a root-to-leaf bit path string, as used by the sparse trees
+pub fn bit_path_to_path_bits(path: &str) -> Vec<u8> {
+    path.chars().rev().map(|c| if c == '1' { 1 } else { 0 }).collect()
+}
+
+/// 0x-prefixed hex encoding of a 32-byte hash
+pub fn to_hex32(hash: &[u8; 32]) -> String {
+    alloc::format!("0x{}", hex::encode(hash))
+}
+
+/// Parse a 32-byte hash with or without 0x prefix
+pub fn parse_hash32(value: &str) -> Result<[u8; 32], VerifyError> {
+    let bytes = hex::decode(value.trim_start_matches("0x"))
+        .map_err(|_| VerifyError::InvalidHex(value.to_string()))?;
+    bytes.try_into()
+        .map_err(|_| VerifyError::InvalidHashLength(value.to_string()))
+}
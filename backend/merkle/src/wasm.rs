@@ -0,0 +1,81 @@
+//! JS-facing API, compiled in with the `wasm` feature (see README.md for the build)
+//!
+//! Hashes cross the boundary as 0x-prefixed hex strings; a malformed hash or proof rejects
+//! with the error message, a well-formed proof for another root resolves to `false`.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use wasm_bindgen::prelude::*;
+
+use crate::leaf::{self, ethereum_address_to_path};
+use crate::proof::{self, bit_path_to_path_bits, parse_hash32, to_hex32, VerifyError};
+
+fn js_error(e: VerifyError) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+fn parse_hashes(hashes: &[String]) -> Result<Vec<[u8; 32]>, JsValue> {
+    hashes.iter().map(|hash| parse_hash32(hash).map_err(js_error)).collect()
+}
+
+/// Leaf hash of an order in batch `batchId`, from the fields the proofs API publishes
+#[wasm_bindgen(js_name = orderLeafHash)]
+pub fn order_leaf_hash(
+    batch_id: u64,
+    order_id: &str,
+    order_type: u8,
+    from_address: Option<String>,
+    to_address: Option<String>,
+    token_id: u32,
+    amount: &str,
+) -> String {
+    let fields = leaf::OrderLeafFields {
+        order_id: order_id.to_string(),
+        order_type,
+        from_address,
+        to_address,
+        token_id,
+        amount: amount.to_string(),
+    };
+    to_hex32(&fields.leaf_hash(batch_id))
+}
+
+/// Leaf hash of an account; `balances[i]` is the balance of token `tokenIds[i]`
+#[wasm_bindgen(js_name = accountLeafHash)]
+pub fn account_leaf_hash(address: &str, token_ids: Vec<u32>, balances: Vec<String>) -> Result<String, JsValue> {
+    if token_ids.len() != balances.len() {
+        return Err(JsValue::from_str("tokenIds and balances must have the same length"));
+    }
+    let balances = token_ids.into_iter().zip(balances)
+        .map(|(token_id, balance)| leaf::LeafBalance { token_id, balance })
+        .collect();
+    Ok(to_hex32(&leaf::AccountLeafFields { address: address.to_string(), balances }.leaf_hash()))
+}
+
+/// Path bits (leaf to root) of the order at `index` in a tree of depth `depth`
+#[wasm_bindgen(js_name = indexPathBits)]
+pub fn index_path_bits(index: usize, depth: usize) -> Vec<u8> {
+    proof::index_to_path_bits(index, depth)
+}
+
+/// Path bits (leaf to root) of an account's leaf in a tree of depth `depth`
+#[wasm_bindgen(js_name = addressPathBits)]
+pub fn address_path_bits(address: &str, depth: usize) -> Vec<u8> {
+    bit_path_to_path_bits(&ethereum_address_to_path(address, depth))
+}
+
+/// Check a positional (`siblings` or `raw` format) proof against a trusted root
+#[wasm_bindgen(js_name = verifyPositionalProof)]
+pub fn verify_positional_proof(leaf_hash: &str, siblings: Vec<String>, path_bits: Vec<u8>, root: &str) -> Result<bool, JsValue> {
+    let leaf = parse_hash32(leaf_hash).map_err(js_error)?;
+    let computed = proof::process_raw_proof(leaf, &parse_hashes(&siblings)?, &path_bits).map_err(js_error)?;
+    Ok(computed == parse_hash32(root).map_err(js_error)?)
+}
+
+/// Check a `sorted_pairs` proof against a trusted root
+#[wasm_bindgen(js_name = verifySortedProof)]
+pub fn verify_sorted_proof(leaf_hash: &str, proof: Vec<String>, root: &str) -> Result<bool, JsValue> {
+    let leaf = parse_hash32(leaf_hash).map_err(js_error)?;
+    let computed = proof::process_sorted_proof(leaf, &parse_hashes(&proof)?);
+    Ok(computed == parse_hash32(root).map_err(js_error)?)
+}
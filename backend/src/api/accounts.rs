@@ -0,0 +1,245 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{info, warn, error};
+
+use super::{admin::require_admin, error::ApiError, AppState};
+use crate::database::helpers;
+use crate::models::{page_size, resolve_account_address, AccountState, ProvenRoot, TokenBalance, MAX_PAGE_SIZE};
+use crate::services::bulk_accounts::{self, BulkAccountEntry, BulkAccountError, BulkAccountSummary, CsvParser};
+use crate::services::system_accounts::{self, SystemAccount, TokenLedger};
+
+/// Balances of an account (GET /accounts/:address)
+#[derive(Debug, Serialize)]
+pub struct AccountResponse {
+    pub address: String,
+    /// As of the last finalized batch, plus accounts initialized since
+    pub balances: Vec<TokenBalance>,
+    /// Latest state root with a submitted proof, to check the balances against
+    pub proven_root: Option<ProvenRoot>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountsQuery {
+    /// Only accounts holding a balance of this token
+    pub token_id: Option<u32>,
+    /// Page size, 100 by default and at most
+    pub limit: Option<usize>,
+    /// `next_cursor` of the previous page
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountsListResponse {
+    pub accounts: Vec<AccountState>,
+    pub total: usize,
+    /// Pass as `after` to fetch the next page; unset on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Get the balances of an account; `filler:<filler_id>` reads a filler's settlement account
+pub async fn get_account(
+    State(app_state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<Json<AccountResponse>, ApiError> {
+    info!("Getting account {}", address);
+
+    let address = resolve_account_address(&address);
+    let balances = helpers::get_account_balances(&app_state.db, &address).await.map_err(|e| {
+        error!("Database error fetching balances of {}: {}", address, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if balances.is_empty() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "account_not_found", format!("No balances recorded for {}", address)));
+    }
+
+    let proven_root = helpers::latest_proven_root(&app_state.db).await.map_err(|e| {
+        error!("Database error fetching the latest proven root: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(AccountResponse { address, balances, proven_root }))
+}
+
+/// List accounts with their balances, by address
+pub async fn list_accounts(
+    State(app_state): State<AppState>,
+    Query(query): Query<AccountsQuery>,
+) -> Result<Json<AccountsListResponse>, ApiError> {
+    info!("Listing accounts with params: {:?}", query);
+
+    // Addresses never change, so the last one is a stable cursor
+    let limit = page_size(query.limit, MAX_PAGE_SIZE);
+    let mut accounts = helpers::list_accounts(&app_state.db, query.token_id, query.after.as_deref(), limit + 1)
+        .await
+        .map_err(|e| {
+            error!("Database error listing accounts: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let next_cursor = if accounts.len() > limit {
+        accounts.truncate(limit);
+        accounts.last().map(|account| account.address.clone())
+    } else {
+        None
+    };
+    let total = accounts.len();
+
+    Ok(Json(AccountsListResponse { accounts, total, next_cursor }))
+}
+
+/// A system account and its reserved address
+#[derive(Debug, Serialize)]
+pub struct SystemAccountEntry {
+    pub account: SystemAccount,
+    pub address: String,
+}
+
+/// The system accounts and the per-token ledger they keep (GET /accounts/system)
+#[derive(Debug, Serialize)]
+pub struct SystemLedgerResponse {
+    pub accounts: Vec<SystemAccountEntry>,
+    /// Including the open batch's orders; every token's `net` is "0" while the tree balances
+    pub tokens: Vec<TokenLedger>,
+}
+
+/// Get the system accounts and the per-token totals they balance against
+pub async fn get_system_ledger(State(app_state): State<AppState>) -> Json<SystemLedgerResponse> {
+    info!("Getting system account ledger");
+
+    let tokens = system_accounts::ledger(&app_state.batch_processor.lock().await.accounts);
+    if let Some(token) = tokens.iter().find(|token| token.net != "0") {
+        error!(alert = "ledger_unbalanced", token_id = token.token_id, "Token {} does not balance: net {}", token.token_id, token.net);
+    }
+    let accounts = SystemAccount::ALL.into_iter()
+        .map(|account| SystemAccountEntry { account, address: account.address() })
+        .collect();
+    Json(SystemLedgerResponse { accounts, tokens })
+}
+
+/// Initialize account for testing/demo purposes
+#[derive(Debug, Deserialize)]
+pub struct InitAccountRequest {
+    pub address: String,
+    pub token_id: u32,
+    pub initial_balance: String,
+}
+
+/// Give an account an initial balance (POST /accounts, admin only)
+pub async fn init_account(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<InitAccountRequest>,
+) -> Result<Json<Value>, ApiError> {
+    info!("Initializing account: {} with {} of token {}", req.address, req.initial_balance, req.token_id);
+    if !app_state.config.profile.allows_init_account() {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "disabled_in_profile",
+            format!("Account initialization is disabled in the {} profile", app_state.config.profile.as_str()),
+        ));
+    }
+    require_admin(&app_state, &headers)?;
+
+    let mut processor = app_state.batch_processor.lock().await;
+    processor.init_account(req.address.clone(), req.token_id, req.initial_balance.clone())?;
+
+    // Readable before the next batch is finalized, with the bridge account that mirrors it
+    let accounts: Vec<AccountState> = [req.address.clone(), SystemAccount::Bridge.address()].iter()
+        .filter_map(|address| processor.accounts.get(address).cloned())
+        .collect();
+    drop(processor);
+    if let Err(e) = helpers::store_account_balances(&app_state.db, &accounts).await {
+        error!("Failed to persist balances of {}: {}", req.address, e);
+    }
+
+    info!("Account initialized successfully: {}", req.address);
+    Ok(Json(json!({
+        "status": "success",
+        "address": req.address,
+        "token_id": req.token_id,
+        "initial_balance": req.initial_balance,
+        "message": "Account initialized successfully"
+    })))
+}
+
+/// Upper bound on a JSON upload's size per allowed entry, so the body is refused before it is parsed
+const MAX_JSON_BYTES_PER_ENTRY: usize = 256;
+
+/// Read bulk entries from a JSON array, or from CSV (`text/csv`) parsed as the body streams in
+async fn read_bulk_entries(headers: &HeaderMap, body: Body, max_entries: usize) -> Result<Vec<BulkAccountEntry>, ApiError> {
+    let is_csv = headers.get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/csv"));
+
+    if is_csv {
+        let mut parser = CsvParser::new(max_entries);
+        let mut stream = body.into_data_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, "invalid_body", e.to_string()))?;
+            parser.push(&chunk)?;
+        }
+        return Ok(parser.finish()?);
+    }
+
+    let body = axum::body::to_bytes(body, max_entries.saturating_mul(MAX_JSON_BYTES_PER_ENTRY))
+        .await
+        .map_err(|_| BulkAccountError::TooManyEntries { max: max_entries })?;
+    Ok(bulk_accounts::parse_json(&body, max_entries)?)
+}
+
+/// Set many balances at once from JSON or CSV (POST /admin/accounts/bulk, admin only)
+///
+/// Every entry is validated before anything is written; the database write is one
+/// transaction and the batch processor only changes once it has committed.
+pub async fn init_accounts_bulk(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Json<BulkAccountSummary>, ApiError> {
+    if !app_state.config.profile.allows_init_account() {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "disabled_in_profile",
+            format!("Account initialization is disabled in the {} profile", app_state.config.profile.as_str()),
+        ));
+    }
+    require_admin(&app_state, &headers)?;
+
+    let entries = read_bulk_entries(&headers, body, app_state.config.api.bulk_account_max_entries).await?;
+    bulk_accounts::validate(&entries)?;
+    info!("Bulk initializing {} balances", entries.len());
+
+    // Held across the write so no batch starts or finalizes between the two stores
+    let mut processor = app_state.batch_processor.lock().await;
+    bulk_accounts::store(&app_state.db, &entries, app_state.clock.now()).await.map_err(|e| {
+        error!("Failed to store bulk balances: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let summary = processor.set_balances(&entries);
+
+    Ok(Json(summary))
+}
+
+/// Deprecated alias of [`init_account`] (POST /batch/init-account)
+pub async fn init_account_deprecated(
+    state: State<AppState>,
+    headers: HeaderMap,
+    req: Json<InitAccountRequest>,
+) -> Response {
+    warn!("Deprecated POST /api/v1/batch/init-account called, use POST /api/v1/accounts");
+
+    let mut response = init_account(state, headers, req).await.into_response();
+    let response_headers = response.headers_mut();
+    response_headers.insert("deprecation", HeaderValue::from_static("true"));
+    response_headers.insert(header::LINK, HeaderValue::from_static("</api/v1/accounts>; rel=\"successor-version\""));
+    response
+}
@@ -0,0 +1,826 @@
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn, error};
+
+use super::{error::ApiError, fillers::bearer_token, limits::LONG_RUNNING_ROUTES, AppState};
+use vapor_chain::hex_to_address;
+use crate::config::{parse_usd_price, HttpCacheConfig, RequestLimitsConfig};
+use crate::services::backups::{BackupRecord, BackupTrigger, BackupVerification, RestoreReport};
+use crate::services::balance_alerts::AlertState;
+use crate::services::batch_caps::{DeferredOrder, TokenCapStatus};
+use crate::services::claims::ReconcileReport;
+use crate::services::fixtures::{self, VerificationFixtures, DEFAULT_FIXTURE_BATCH_ID};
+use crate::services::maintenance::MaintenanceStatus;
+use crate::services::order_reconciliation::{ReconciliationReport, ReconciliationTrigger, ReportSummary};
+use crate::services::order_review::{OrderReviewDecision, OrderReviewEntry};
+use crate::services::query_metrics::{self, QueryDiagnostics};
+use crate::services::rates::format_rate;
+use crate::services::registry::{self, BankServiceEntry, Registry, RegistryCacheStats, TokenEntry};
+use crate::services::retention::{self, ScrubRecord};
+use crate::services::scheduler::{JobStatus, ScheduledJob};
+use crate::services::screening::{ReviewDecision, ScreeningResult};
+use crate::services::settlement_saga::{SagaRecord, SagaState};
+use crate::services::withdrawal_limits::{self, AddressList, AddressListEntry, WithdrawalUsage};
+
+#[derive(Debug, Deserialize)]
+pub struct SetMaintenanceRequest {
+    pub enabled: bool,
+    pub message: Option<String>,
+}
+
+/// Get the current maintenance mode (GET /admin/maintenance)
+pub async fn get_maintenance(State(app_state): State<AppState>) -> Json<MaintenanceStatus> {
+    info!("Getting maintenance mode");
+
+    Json(app_state.maintenance.status())
+}
+
+/// Enable or disable maintenance mode (PUT /admin/maintenance)
+pub async fn set_maintenance(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SetMaintenanceRequest>,
+) -> Result<Json<MaintenanceStatus>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Setting maintenance mode: {:?}", request);
+
+    let status = app_state.maintenance
+        .set(request.enabled, request.message)
+        .await
+        .map_err(|e| {
+            error!("Failed to persist maintenance mode: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(status))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetAddressListRequest {
+    pub list: AddressList,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WithdrawalUsageQuery {
+    pub token_id: Option<u32>,
+}
+
+/// List the bridge-out allowlist and denylist (GET /admin/withdrawals/lists)
+pub async fn get_withdrawal_lists(State(app_state): State<AppState>) -> Result<Json<Vec<AddressListEntry>>, StatusCode> {
+    info!("Getting bridge-out address lists");
+
+    let entries = withdrawal_limits::list_entries(&app_state.db).await.map_err(|e| {
+        error!("Database error listing bridge-out address lists: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(entries))
+}
+
+/// Put an address on the bridge-out allowlist or denylist (PUT /admin/withdrawals/lists/:address)
+pub async fn set_withdrawal_list_entry(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Path(address): Path<String>,
+    Json(request): Json<SetAddressListRequest>,
+) -> Result<Json<AddressListEntry>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Adding {} to the bridge-out {}list", address, request.list.as_str());
+
+    if hex_to_address(&address).is_err() {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+
+    let entry = withdrawal_limits::set_list_entry(&app_state.db, &address, request.list, request.reason)
+        .await
+        .map_err(|e| {
+            error!("Database error updating bridge-out address list: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(entry))
+}
+
+/// Remove an address from the bridge-out lists (DELETE /admin/withdrawals/lists/:address)
+pub async fn remove_withdrawal_list_entry(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Path(address): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Removing {} from the bridge-out address lists", address);
+
+    let removed = withdrawal_limits::remove_list_entry(&app_state.db, &address).await.map_err(|e| {
+        error!("Database error updating bridge-out address list: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if removed {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND.into())
+    }
+}
+
+/// Today's bridge-out usage and limits for an address (GET /admin/withdrawals/usage/:address?token_id=)
+pub async fn get_withdrawal_usage(
+    State(app_state): State<AppState>,
+    Path(address): Path<String>,
+    Query(query): Query<WithdrawalUsageQuery>,
+) -> Result<Json<WithdrawalUsage>, StatusCode> {
+    info!("Getting bridge-out usage for {}", address);
+
+    let usage = withdrawal_limits::daily_usage(
+        &app_state.db,
+        &app_state.config.withdrawal,
+        &address,
+        query.token_id.unwrap_or(1),
+        app_state.clock.now(),
+    )
+    .await
+        .map_err(|e| {
+            error!("Database error reading bridge-out usage: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(usage))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FixturesQuery {
+    pub batch_id: Option<u64>,
+}
+
+/// Hash test vectors for the contracts' Foundry tests (GET /admin/fixtures?batch_id=)
+pub async fn get_verification_fixtures(Query(query): Query<FixturesQuery>) -> Result<Json<VerificationFixtures>, ApiError> {
+    info!("Generating verification fixtures: {:?}", query);
+
+    Ok(Json(fixtures::generate(query.batch_id.unwrap_or(DEFAULT_FIXTURE_BATCH_ID))?))
+}
+
+/// Runtime limits in effect, as read from the environment at startup
+#[derive(Debug, Serialize)]
+pub struct AdminConfigResponse {
+    pub request_limits: RequestLimitsConfig,
+    /// Routes whose POSTs get `request_limits.long_timeout_seconds`
+    pub long_running_routes: &'static [&'static str],
+    pub http_cache: HttpCacheConfig,
+}
+
+/// Get the request timeouts and body size limit (GET /admin/config)
+pub async fn get_config(State(app_state): State<AppState>) -> Json<AdminConfigResponse> {
+    info!("Getting admin config");
+
+    Json(AdminConfigResponse {
+        request_limits: app_state.config.request_limits.clone(),
+        long_running_routes: LONG_RUNNING_ROUTES,
+        http_cache: app_state.config.http_cache.clone(),
+    })
+}
+
+/// Require the admin bearer token (ADMIN_API_TOKEN)
+///
+/// Without a configured token the admin API is open in the dev profile and disabled (503)
+/// everywhere else.
+pub fn require_admin(app_state: &AppState, headers: &HeaderMap) -> Result<(), ApiError> {
+    let admin_token = app_state.config.api.admin_token.as_deref().filter(|token| !token.is_empty());
+    let Some(admin_token) = admin_token else {
+        if app_state.config.profile.allows_open_admin() {
+            return Ok(());
+        }
+        warn!("Rejected admin request: ADMIN_API_TOKEN is not configured");
+        return Err(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "admin_disabled",
+            "The admin API is disabled until ADMIN_API_TOKEN is configured",
+        ));
+    };
+    if bearer_token(headers).is_some_and(|token| constant_time_eq(token.as_bytes(), admin_token.as_bytes())) {
+        return Ok(());
+    }
+
+    warn!("Rejected admin request with missing or invalid token");
+    Err(ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", "A valid admin token is required"))
+}
+
+/// Compare secrets without returning early on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Require the admin token on every `/api/v1/admin/*` route, reads included
+pub async fn admin_guard(
+    State(app_state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.uri().path().starts_with("/api/v1/admin/") {
+        if let Err(e) = require_admin(&app_state, request.headers()) {
+            return e.into_response();
+        }
+    }
+    next.run(request).await
+}
+
+/// Reject writes with 503 while maintenance mode is enabled
+///
+/// Reads, admin endpoints, proof verification and batch dry runs (which do not change state) keep serving.
+pub async fn maintenance_guard(
+    State(app_state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if app_state.maintenance.is_enabled() && !allowed_during_maintenance(request.method(), request.uri().path()) {
+        let status = app_state.maintenance.status();
+        return ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "maintenance", status.message()).into_response();
+    }
+
+    next.run(request).await
+}
+
+fn allowed_during_maintenance(method: &Method, path: &str) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+        || path.starts_with("/api/v1/admin/")
+        || path == "/api/v1/proofs/verify"
+        || path == "/api/v1/batch/dry-run"
+        || path == "/api/v1/graphql"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_writes_are_blocked_during_maintenance() {
+        assert!(allowed_during_maintenance(&Method::GET, "/api/v1/orders"));
+        assert!(allowed_during_maintenance(&Method::PUT, "/api/v1/admin/maintenance"));
+        assert!(allowed_during_maintenance(&Method::POST, "/api/v1/proofs/verify"));
+        assert!(allowed_during_maintenance(&Method::POST, "/api/v1/batch/dry-run"));
+        assert!(allowed_during_maintenance(&Method::POST, "/api/v1/graphql"));
+        assert!(!allowed_during_maintenance(&Method::POST, "/api/v1/orders"));
+        assert!(!allowed_during_maintenance(&Method::POST, "/api/v1/fillers/orders/abc/lock"));
+        assert!(!allowed_during_maintenance(&Method::POST, "/api/v1/batch/start"));
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchCapsResponse {
+    /// Batch the volumes are counted for, if one is open
+    pub batch_id: Option<u64>,
+    pub caps: Vec<TokenCapStatus>,
+    pub deferred_orders: Vec<DeferredOrder>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetBatchCapRequest {
+    /// Max BridgeOut volume of the token per batch; 0 lifts the cap
+    pub max_bridge_out: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SetBatchCapResponse {
+    pub cap: TokenCapStatus,
+    /// Deferred orders moved into the open batch by the override
+    pub released_orders: Vec<String>,
+}
+
+/// Per-token BridgeOut caps, this batch's volumes and the deferred orders (GET /admin/batch-caps)
+pub async fn get_batch_caps(State(app_state): State<AppState>) -> Json<BatchCapsResponse> {
+    info!("Getting batch BridgeOut caps");
+
+    let processor = app_state.batch_processor.lock().await;
+    Json(BatchCapsResponse {
+        batch_id: processor.get_current_batch().map(|batch| batch.batch_id),
+        caps: processor.bridge_out_caps.status(),
+        deferred_orders: processor.deferred_orders.clone(),
+    })
+}
+
+/// Override a token's per-batch BridgeOut cap, releasing its deferred orders (PUT /admin/batch-caps/:token_id)
+pub async fn set_batch_cap(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Path(token_id): Path<u32>,
+    Json(request): Json<SetBatchCapRequest>,
+) -> Result<Json<SetBatchCapResponse>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Overriding BridgeOut cap of token {}: {:?}", token_id, request);
+
+    let mut processor = app_state.batch_processor.lock().await;
+    let released_orders = processor.override_bridge_out_cap(token_id, request.max_bridge_out);
+    Ok(Json(SetBatchCapResponse {
+        cap: processor.bridge_out_caps.status_for(token_id),
+        released_orders,
+    }))
+}
+
+/// Drop a cap override, going back to the configured cap (DELETE /admin/batch-caps/:token_id)
+pub async fn clear_batch_cap(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Path(token_id): Path<u32>,
+) -> Result<StatusCode, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Clearing BridgeOut cap override of token {}", token_id);
+
+    if app_state.batch_processor.lock().await.clear_bridge_out_cap_override(token_id) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND.into())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenRate {
+    /// Decimal dollars per token, e.g. "0.9998"
+    pub usd_price: String,
+}
+
+/// Move a token's USD price, e.g. to simulate slippage between quote and lock (PUT /admin/rates/:token_id)
+pub async fn set_token_rate(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Path(token_id): Path<u32>,
+    Json(request): Json<TokenRate>,
+) -> Result<Json<TokenRate>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Setting USD price of token {} to {}", token_id, request.usd_price);
+
+    let price = parse_usd_price(&request.usd_price).ok_or_else(|| {
+        ApiError::new(StatusCode::BAD_REQUEST, "invalid_rate", format!("Invalid USD price '{}'", request.usd_price))
+    })?;
+    app_state.rates.set_usd_price(token_id, price);
+    Ok(Json(TokenRate { usd_price: format_rate(price) }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetTokenRequest {
+    pub symbol: String,
+    pub decimals: u8,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetBankServiceRequest {
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegistryResponse {
+    pub tokens: Vec<TokenEntry>,
+    pub bank_services: Vec<BankServiceEntry>,
+}
+
+/// Registered tokens and bank services, as the cache serves them (GET /admin/registry)
+pub async fn get_registry(State(app_state): State<AppState>) -> Result<Json<RegistryResponse>, ApiError> {
+    info!("Getting token and bank service registries");
+
+    Ok(Json(RegistryResponse {
+        tokens: app_state.registry.tokens().await.map_err(registry::RegistryError::Storage)?,
+        bank_services: app_state.registry.bank_services().await.map_err(registry::RegistryError::Storage)?,
+    }))
+}
+
+/// Register, update or disable a token (PUT /admin/registry/tokens/:token_id)
+pub async fn set_registry_token(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Path(token_id): Path<u32>,
+    Json(request): Json<SetTokenRequest>,
+) -> Result<Json<TokenEntry>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Setting registry token {}: {:?}", token_id, request);
+
+    let symbol = request.symbol.trim();
+    if symbol.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "invalid_token", "symbol cannot be empty"));
+    }
+    let token = TokenEntry { token_id, symbol: symbol.to_string(), decimals: request.decimals, enabled: request.enabled };
+    registry::save_token(&app_state.db, &token).await.map_err(registry::RegistryError::Storage)?;
+    app_state.event_bus.publish_registry_change(Registry::Tokens);
+    Ok(Json(token))
+}
+
+/// Register, enable or disable a bank service (PUT /admin/registry/bank-services/:name)
+pub async fn set_registry_bank_service(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(request): Json<SetBankServiceRequest>,
+) -> Result<Json<BankServiceEntry>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Setting registry bank service '{}': {:?}", name, request);
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "invalid_bank_service", "name cannot be empty"));
+    }
+    let service = BankServiceEntry { name: name.to_string(), enabled: request.enabled };
+    registry::save_bank_service(&app_state.db, &service).await.map_err(registry::RegistryError::Storage)?;
+    app_state.event_bus.publish_registry_change(Registry::BankServices);
+    Ok(Json(service))
+}
+
+/// Hit, miss and invalidation counts of the registry cache (GET /admin/registry/metrics)
+pub async fn get_registry_metrics(State(app_state): State<AppState>) -> Json<RegistryCacheStats> {
+    info!("Getting registry cache metrics");
+
+    Json(app_state.registry.stats())
+}
+
+/// Balance alert rules with their firing/resolved state (GET /admin/balance-alerts)
+pub async fn get_balance_alerts(State(app_state): State<AppState>) -> Json<Vec<AlertState>> {
+    info!("Getting balance alerts");
+
+    Json(app_state.balance_alerts.states())
+}
+
+/// Report of the latest claim reconciliation run (GET /admin/claims/reconciliation)
+///
+/// 404 until the reconciler has run, which needs a blockchain client.
+pub async fn get_claim_reconciliation(State(app_state): State<AppState>) -> Result<Json<ReconcileReport>, StatusCode> {
+    info!("Getting claim reconciliation report");
+
+    app_state.claim_reconciler.last_report().map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SagaQuery {
+    /// Only sagas in this state; defaults to every in-flight saga
+    pub state: Option<SagaState>,
+}
+
+/// Settlement sagas, oldest first (GET /admin/sagas)
+pub async fn list_sagas(
+    State(app_state): State<AppState>,
+    Query(query): Query<SagaQuery>,
+) -> Result<Json<Vec<SagaRecord>>, ApiError> {
+    info!("Listing settlement sagas: {:?}", query);
+
+    Ok(Json(app_state.settlement_saga.list(query.state).await?))
+}
+
+/// One order's settlement saga (GET /admin/sagas/:order_id)
+pub async fn get_saga(
+    State(app_state): State<AppState>,
+    Path(order_id): Path<String>,
+) -> Result<Json<SagaRecord>, ApiError> {
+    info!("Getting settlement saga for order {}", order_id);
+
+    Ok(Json(app_state.settlement_saga.get(&order_id).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AbortSagaRequest {
+    pub reason: Option<String>,
+}
+
+/// Fail a saga and run its compensations (POST /admin/sagas/:order_id/abort)
+///
+/// Also retries the outstanding compensations of a saga whose compensation failed.
+pub async fn abort_saga(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Path(order_id): Path<String>,
+    Json(request): Json<AbortSagaRequest>,
+) -> Result<Json<SagaRecord>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    let reason = request.reason.as_deref().unwrap_or("admin_abort");
+    warn!("Aborting settlement saga for order {}: {}", order_id, reason);
+
+    Ok(Json(app_state.settlement_saga.fail(&order_id, reason).await?))
+}
+
+/// Lift a filler's collateral flag after repeated lock expiries (DELETE /admin/fillers/:filler_id/collateral/flag)
+pub async fn clear_collateral_flag(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Path(filler_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Clearing collateral flag of filler {}", filler_id);
+
+    let cleared = app_state.collateral.clear_flag(&filler_id).await.map_err(|e| {
+        error!("Database error clearing collateral flag of filler {}: {}", filler_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !cleared {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "filler_not_flagged", format!("Filler {} is not flagged", filler_id)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobQuery {
+    /// Only jobs in this status; defaults to every job
+    pub status: Option<JobStatus>,
+}
+
+/// Delayed jobs, most recently updated first (GET /admin/jobs)
+pub async fn list_jobs(
+    State(app_state): State<AppState>,
+    Query(query): Query<JobQuery>,
+) -> Result<Json<Vec<ScheduledJob>>, ApiError> {
+    info!("Listing scheduled jobs: {:?}", query);
+
+    let jobs = app_state.scheduler.list(query.status).await.map_err(|e| {
+        error!("Failed to list scheduled jobs: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(jobs))
+}
+
+/// Stored order book reconciliation reports, newest first (GET /admin/reconciliation/reports)
+pub async fn list_reconciliation_reports(State(app_state): State<AppState>) -> Result<Json<Vec<ReportSummary>>, StatusCode> {
+    info!("Listing order book reconciliation reports");
+
+    let reports = app_state.order_reconciler.list().await.map_err(|e| {
+        error!("Database error listing reconciliation reports: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(reports))
+}
+
+/// The most recent reconciliation report (GET /admin/reconciliation/reports/latest)
+pub async fn get_latest_reconciliation_report(State(app_state): State<AppState>) -> Result<Json<ReconciliationReport>, StatusCode> {
+    info!("Getting latest order book reconciliation report");
+
+    load_reconciliation_report(&app_state, None).await
+}
+
+/// One stored reconciliation report (GET /admin/reconciliation/reports/:id)
+pub async fn get_reconciliation_report(
+    State(app_state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<ReconciliationReport>, StatusCode> {
+    info!("Getting order book reconciliation report {}", id);
+
+    load_reconciliation_report(&app_state, Some(id)).await
+}
+
+async fn load_reconciliation_report(app_state: &AppState, id: Option<i64>) -> Result<Json<ReconciliationReport>, StatusCode> {
+    match app_state.order_reconciler.get(id).await {
+        Ok(Some(report)) => Ok(Json(report)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Database error loading reconciliation report: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Reconcile the order book now instead of waiting for the nightly run (POST /admin/reconciliation/run)
+pub async fn run_reconciliation(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ReconciliationReport>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Running order book reconciliation on demand");
+
+    let report = app_state.order_reconciler
+        .run(app_state.blockchain_client.as_deref(), ReconciliationTrigger::Manual)
+        .await
+        .map_err(|e| {
+            error!("Order book reconciliation failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(report))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RetentionAuditQuery {
+    pub order_id: Option<String>,
+    /// Defaults to 100
+    pub limit: Option<u32>,
+}
+
+/// Bank detail scrubs, newest first (GET /admin/retention/audit)
+pub async fn get_retention_audit(
+    State(app_state): State<AppState>,
+    Query(query): Query<RetentionAuditQuery>,
+) -> Result<Json<Vec<ScrubRecord>>, StatusCode> {
+    info!("Getting retention audit log: {:?}", query);
+
+    let records = retention::audit_log(&app_state.db, query.order_id.as_deref(), query.limit.unwrap_or(100).min(1000))
+        .await
+        .map_err(|e| {
+            error!("Database error loading retention audit log: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(records))
+}
+
+/// Scrub expired bank details now instead of waiting for the next sweep (POST /admin/retention/run)
+pub async fn run_retention(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ScrubRecord>>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Running bank details retention sweep on demand");
+
+    let scrubbed = retention::scrub_expired(&app_state.db, &app_state.config.retention, app_state.clock.now())
+        .await
+        .map_err(|e| {
+            error!("Bank details retention sweep failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(scrubbed))
+}
+
+/// Database backups, newest first (GET /admin/backups)
+pub async fn list_backups(State(app_state): State<AppState>) -> Result<Json<Vec<BackupRecord>>, StatusCode> {
+    info!("Listing database backups");
+
+    let backups = app_state.backups.list().await.map_err(|e| {
+        error!("Database error listing backups: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(backups))
+}
+
+/// Take a database backup now (POST /admin/backups)
+pub async fn take_backup(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<BackupRecord>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Taking database backup on demand");
+
+    let backup = app_state.backups.take(BackupTrigger::Manual, app_state.clock.now())
+        .await
+        .map_err(|e| {
+            error!("Database backup failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(backup))
+}
+
+/// Check a backup's checksum, integrity and schema without restoring it (POST /admin/backups/:id/verify)
+pub async fn verify_backup(
+    Path(id): Path<i64>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<BackupVerification>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Verifying database backup {}", id);
+
+    Ok(Json(app_state.backups.verify(id).await?))
+}
+
+/// Replace the database contents with a backup's (POST /admin/backups/:id/restore)
+///
+/// Maintenance mode has to be on, so nothing writes while rows are replaced; the server must
+/// be restarted afterwards to reload its in-memory state.
+pub async fn restore_backup(
+    Path(id): Path<i64>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<RestoreReport>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    if !app_state.maintenance.is_enabled() {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "maintenance_required",
+            "Enable maintenance mode before restoring a backup",
+        ));
+    }
+    warn!("Restoring database backup {}", id);
+
+    Ok(Json(app_state.backups.restore(id, app_state.clock.now()).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScreeningReviewsQuery {
+    /// Also list results that already have a decision
+    #[serde(default)]
+    pub include_reviewed: bool,
+    /// Defaults to 100
+    pub limit: Option<u32>,
+}
+
+/// Flagged screening results waiting for review, oldest first (GET /admin/screening/reviews)
+pub async fn list_screening_reviews(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ScreeningReviewsQuery>,
+) -> Result<Json<Vec<ScreeningResult>>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Listing screening reviews: {:?}", query);
+
+    let results = app_state
+        .screening
+        .review_queue(query.include_reviewed, query.limit.unwrap_or(100).min(1000))
+        .await
+        .map_err(|e| {
+            error!("Database error loading screening reviews: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(results))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewScreeningRequest {
+    pub decision: ReviewDecision,
+    pub note: Option<String>,
+}
+
+/// Clear or confirm a flagged screening result (POST /admin/screening/reviews/:id)
+pub async fn review_screening_result(
+    Path(id): Path<String>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<ReviewScreeningRequest>,
+) -> Result<Json<ScreeningResult>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Reviewing screening result {}: {:?}", id, req.decision);
+
+    Ok(Json(app_state.screening.review(&id, req.decision, req.note.as_deref()).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderReviewsQuery {
+    /// Also list orders that already have a decision
+    #[serde(default)]
+    pub include_decided: bool,
+    /// Defaults to 100
+    pub limit: Option<u32>,
+}
+
+/// High-value orders waiting in ReviewPending, oldest first (GET /admin/order-reviews)
+pub async fn list_order_reviews(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<OrderReviewsQuery>,
+) -> Result<Json<Vec<OrderReviewEntry>>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Listing order reviews: {:?}", query);
+
+    let reviews = app_state
+        .order_review
+        .queue(query.include_decided, query.limit.unwrap_or(100).min(1000))
+        .await
+        .map_err(|e| {
+            error!("Database error loading order reviews: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(reviews))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DecideOrderReviewRequest {
+    pub decision: OrderReviewDecision,
+    /// Recorded in the order's status history; required to reject
+    pub reason: Option<String>,
+}
+
+/// Approve a held order into Discovery or reject it (POST /admin/order-reviews/:order_id)
+pub async fn decide_order_review(
+    Path(order_id): Path<String>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<DecideOrderReviewRequest>,
+) -> Result<Json<OrderReviewEntry>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Reviewing order {}: {:?}", order_id, req.decision);
+
+    let review = app_state
+        .order_review
+        .decide(&order_id, req.decision, req.reason.as_deref(), &app_state.matching_engine, &app_state.event_bus)
+        .await?;
+    Ok(Json(review))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueryDiagnosticsQuery {
+    /// Operations to list; defaults to DB_DIAGNOSTICS_TOP_N
+    pub top: Option<usize>,
+}
+
+/// Slowest data-access operations with their latency histograms, and recent slow runs
+/// (GET /admin/diagnostics/queries)
+pub async fn get_query_diagnostics(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<QueryDiagnosticsQuery>,
+) -> Result<Json<QueryDiagnostics>, ApiError> {
+    require_admin(&app_state, &headers)?;
+
+    let top = query.top.unwrap_or(app_state.config.diagnostics.top_operations).min(100);
+    Ok(Json(query_metrics::global().diagnostics(top)))
+}
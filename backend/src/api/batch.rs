@@ -1,146 +1,400 @@
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{extract::{Path, Query, State}, http::{HeaderMap, StatusCode}, Json};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use tracing::{info, warn, error};
+use tracing::{info, warn, error, instrument, Span};
 
-use super::AppState;
+use super::{admin::require_admin, error::ApiError, AppState};
+use crate::merkle::MerkleCacheStats;
+use crate::database::helpers;
+use vapor_core::proof_format::parse_hash32;
+use crate::services::{
+    archival::{ArchiveStats, BatchSnapshot, RootMatch},
+    batch_caps::DeferredOrder,
+    batch_costs::{self, BatchCostSample, CostHistory, CostPeriod},
+    batch_journal::{self, AbortedBatch},
+    batch_processor::{BatchError, BatchProcessor, BatchResult, DryRunResult, FailedOrder},
+    batch_prover::ProvenBatch,
+    batch_sequence::{self, BatchLink},
+    event_bus::BatchEvent,
+    event_log::{self, DomainEvent},
+    invariants::InvariantMetrics,
+    mvp_prover::{FailureScenario, MvpProverConfig},
+    manifests,
+    order_costs::{self, BatchCostReport},
+    proof_compression::{self, PreparedSubmission, SubmissionSizes},
+    transfers,
+};
 
 #[derive(Debug, Serialize)]
 pub struct BatchResponse {
-    pub batch_id: u32,
+    pub batch_id: u64,
     pub orders_count: usize,
     pub prev_state_root: String,
     pub new_state_root: String,
     pub prev_orders_root: String,
     pub new_orders_root: String,
     pub status: String,
+    /// BridgeOut orders held back for the next batch by the per-token caps
+    pub deferred_orders: Vec<DeferredOrder>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct BatchStatsResponse {
-    pub next_batch_id: u32,
+    pub next_batch_id: u64,
     pub current_batch_orders: usize,
     pub total_accounts: usize,
     pub has_active_batch: bool,
+    pub queued_for_proof: Vec<u64>,
+    /// Node cache usage of the account and order trees
+    pub merkle_cache: MerkleCacheStats,
+    /// Invariant checks run on finalized batches and the violations they found
+    pub invariants: InvariantMetrics,
+}
+
+/// Persist the snapshot of a just-finalized batch, and precompute its proofs and issue its
+/// inclusion receipts in the background; the batch is already final, so failures are only logged
+async fn persist_snapshot(app_state: &AppState, processor: &mut BatchProcessor) {
+    let Some(snapshot) = processor.take_snapshot() else {
+        return;
+    };
+
+    if let Err(e) = app_state.archive.store_snapshot(&snapshot).await {
+        error!("Failed to persist state snapshot for batch {}: {}", snapshot.batch_id, e);
+    }
+    // Served by the accounts API
+    if let Err(e) = helpers::store_account_balances(&app_state.db, &snapshot.accounts).await {
+        error!("Failed to persist account balances for batch {}: {}", snapshot.batch_id, e);
+    }
+    // Signed transfers need no claim, so they are settled with the batch
+    if let Err(e) = transfers::settle_batch(&app_state.db, snapshot.batch_id, &snapshot.orders, app_state.clock.now()).await {
+        error!("Failed to settle transfers of batch {}: {}", snapshot.batch_id, e);
+    }
+    let order_ids: Vec<String> = snapshot.orders.iter().map(|order| order.id.clone()).collect();
+    if let Err(e) = app_state.settlement_saga.record_batched(snapshot.batch_id, &order_ids).await {
+        error!("Failed to advance settlement sagas for batch {}: {}", snapshot.batch_id, e);
+    }
+
+    if app_state.receipts.is_enabled() {
+        let receipts = app_state.receipts.clone();
+        let snapshot = snapshot.clone();
+        tokio::spawn(async move {
+            if let Err(e) = receipts.issue(&snapshot).await {
+                error!("Failed to issue inclusion receipts for batch {}: {}", snapshot.batch_id, e);
+            }
+        });
+    }
+
+    if app_state.proof_cache.is_enabled() {
+        let proof_cache = app_state.proof_cache.clone();
+        tokio::spawn(async move {
+            if let Err(e) = proof_cache.precompute(&snapshot).await {
+                warn!("Failed to precompute proofs for batch {}: {}", snapshot.batch_id, e);
+            }
+        });
+    }
+}
+
+/// Finalize the current batch if it continues the recorded batch sequence, then persist its snapshot
+///
+/// The batch is recorded in the sequence before it is finalized; if recording fails it stays open.
+pub(super) async fn finalize_in_sequence(app_state: &AppState, processor: &mut BatchProcessor) -> Result<BatchResult, BatchError> {
+    let batch = processor.get_current_batch().ok_or(BatchError::NothingToFinalize)?;
+    batch_sequence::check_next(&app_state.db, &BatchLink::from(batch)).await?;
+
+    let sealed = processor.seal_batch()?;
+    batch_sequence::record(&app_state.db, sealed.result(), app_state.clock.now()).await?;
+    let result = processor.commit_sealed(sealed)?;
+    persist_snapshot(app_state, processor).await;
+    Ok(result)
 }
 
 /// Start a new batch
+#[instrument(skip_all, fields(batch_id = tracing::field::Empty))]
 pub async fn start_batch(
     State(app_state): State<AppState>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     info!("Starting new batch");
     
     let mut processor = app_state.batch_processor.lock().await;
-    
-    match processor.start_batch() {
-        Ok(batch_id) => {
-            info!("Started batch {}", batch_id);
-            Ok(Json(json!({
-                "status": "success",
-                "batch_id": batch_id,
-                "message": "Batch started successfully"
-            })))
-        }
-        Err(e) => {
-            error!("Failed to start batch: {}", e);
-            Ok(Json(json!({
-                "status": "error",
-                "message": format!("Failed to start batch: {}", e)
-            })))
-        }
-    }
+    let batch_id = processor.start_batch()?;
+    Span::current().record("batch_id", batch_id);
+
+    info!("Started batch {}", batch_id);
+    Ok(Json(json!({
+        "status": "success",
+        "batch_id": batch_id,
+        "message": "Batch started successfully"
+    })))
 }
 
 /// Finalize current batch and generate Merkle trees
+#[instrument(skip_all, fields(batch_id = tracing::field::Empty))]
 pub async fn finalize_batch(
     State(app_state): State<AppState>,
-) -> Result<Json<BatchResponse>, StatusCode> {
+) -> Result<Json<BatchResponse>, ApiError> {
     info!("Finalizing current batch");
     
     let mut processor = app_state.batch_processor.lock().await;
+    let result = finalize_in_sequence(&app_state, &mut processor).await?;
+    Span::current().record("batch_id", result.batch_id);
+
+    info!("Batch {} finalized successfully", result.batch_id);
     
-    match processor.finalize_batch() {
-        Ok(result) => {
-            info!("Batch {} finalized successfully", result.batch_id);
-            
-            let response = BatchResponse {
-                batch_id: result.batch_id,
-                orders_count: result.orders_count,
-                prev_state_root: result.prev_state_root,
-                new_state_root: result.new_state_root,
-                prev_orders_root: result.prev_orders_root,
-                new_orders_root: result.new_orders_root,
-                status: "finalized".to_string(),
-            };
-            
-            Ok(Json(response))
-        }
-        Err(e) => {
-            error!("Failed to finalize batch: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+    let response = BatchResponse {
+        batch_id: result.batch_id,
+        orders_count: result.orders_count,
+        prev_state_root: result.prev_state_root,
+        new_state_root: result.new_state_root,
+        prev_orders_root: result.prev_orders_root,
+        new_orders_root: result.new_orders_root,
+        status: "finalized".to_string(),
+        deferred_orders: result.deferred_orders,
+    };
+    
+    Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AbortBatchRequest {
+    pub reason: Option<String>,
+}
+
+/// Abandon the open batch (POST /batch/abort, admin)
+///
+/// Account changes staged by the batch are reverted and its orders requeued for the next
+/// batch, which reuses its id. Order statuses are untouched, as batching does not change them.
+#[instrument(skip_all, fields(batch_id = tracing::field::Empty))]
+pub async fn abort_batch(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<AbortBatchRequest>,
+) -> Result<Json<AbortedBatch>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    let reason = request.reason.unwrap_or_else(|| "admin_abort".to_string());
+
+    let batch = app_state.batch_processor.lock().await.abort_batch()?;
+    Span::current().record("batch_id", batch.batch_id);
+    let aborted = AbortedBatch {
+        batch_id: batch.batch_id,
+        reason,
+        order_ids: batch.orders.iter().map(|order| order.id.clone()).collect(),
+        aborted_at: app_state.clock.now(),
+    };
+    warn!(
+        alert = "batch_aborted",
+        batch_id = aborted.batch_id,
+        "Batch {} aborted with {} orders requeued: {}",
+        aborted.batch_id, aborted.order_ids.len(), aborted.reason
+    );
+
+    // The batch is already aborted, so a failure to record it is only logged
+    if let Err(e) = batch_journal::record_aborted_batch(&app_state.db, &aborted).await {
+        error!("Failed to record aborted batch {}: {}", aborted.batch_id, e);
+    }
+    app_state.event_bus.publish_batch_event(BatchEvent::Aborted(aborted.clone()));
+
+    Ok(Json(aborted))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AbortedBatchesQuery {
+    /// Defaults to 50
+    pub limit: Option<u32>,
+}
+
+/// Aborted batches, most recent first (GET /batch/aborted)
+pub async fn list_aborted_batches(
+    State(app_state): State<AppState>,
+    Query(query): Query<AbortedBatchesQuery>,
+) -> Result<Json<Vec<AbortedBatch>>, StatusCode> {
+    let aborted = batch_journal::list_aborted_batches(&app_state.db, query.limit.unwrap_or(50).min(1000))
+        .await
+        .map_err(|e| {
+            error!("Database error loading aborted batches: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(aborted))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DryRunRequest {
+    /// Orders to preview on top of the open batch
+    pub order_ids: Option<Vec<String>>,
+}
+
+/// Preview the roots, balance changes and failing orders of finalizing the current batch
+/// with the given orders added, without changing the batch or the account state
+#[instrument(skip_all, fields(batch_id = tracing::field::Empty))]
+pub async fn dry_run_batch(
+    State(app_state): State<AppState>,
+    req: Option<Json<DryRunRequest>>,
+) -> Result<Json<DryRunResult>, ApiError> {
+    info!("Dry-running current batch");
+
+    let order_ids = req.map(|Json(req)| req).unwrap_or_default().order_ids.unwrap_or_default();
+    let mut candidates = Vec::with_capacity(order_ids.len());
+    let mut missing = Vec::new();
+    for order_id in order_ids {
+        match helpers::get_order_by_id(&app_state.db, &order_id).await {
+            Ok(Some(order)) => candidates.push(order),
+            Ok(None) => missing.push(FailedOrder { order_id, error: "Order not found".to_string() }),
+            Err(e) => {
+                error!("Database error fetching order {}: {}", order_id, e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+            }
         }
     }
+
+    let mut processor = app_state.batch_processor.lock().await;
+    let mut result = processor.dry_run(candidates)?;
+    Span::current().record("batch_id", result.batch_id);
+    result.failed_orders.extend(missing);
+
+    info!(
+        "Dry run of batch {}: {} orders, {} would fail",
+        result.batch_id, result.orders_count, result.failed_orders.len()
+    );
+    Ok(Json(result))
 }
 
-/// Generate SP1 proof for a batch and submit to blockchain
+/// Finalize the current batch, then generate its SP1 proof and submit it to the blockchain
+///
+/// Earlier batches still waiting in the proving queue are proven first. The batch processor
+/// is released before proving starts, so the next batch keeps taking orders meanwhile.
+#[instrument(skip_all, fields(batch_id = tracing::field::Empty))]
 pub async fn prove_batch(
     State(app_state): State<AppState>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     info!("Starting batch proving process");
     
-    // First finalize the current batch
+    // First finalize the current batch, which queues it for proving
     let mut processor = app_state.batch_processor.lock().await;
-    
-    let batch_result = match processor.finalize_batch() {
-        Ok(result) => result,
-        Err(e) => {
-            error!("Failed to finalize batch before proving: {}", e);
-            return Ok(Json(json!({
-                "status": "error",
-                "message": format!("Failed to finalize batch: {}", e)
-            })));
-        }
-    };
+    let batch_result = finalize_in_sequence(&app_state, &mut processor).await?;
+    Span::current().record("batch_id", batch_result.batch_id);
     
     info!("Batch {} finalized, starting MVP proof generation", batch_result.batch_id);
+    drop(processor);
     
-    // Generate proof using MVP prover and submit to blockchain
-    match processor.generate_and_submit_proof(batch_result.batch_id).await {
-        Ok(proof_result) => {
-            if proof_result.success {
-                info!("Proof generated and submitted successfully for batch {}", batch_result.batch_id);
-                Ok(Json(json!({
-                    "status": "success",
-                    "batch_id": batch_result.batch_id,
-                    "orders_count": batch_result.orders_count,
-                    "proof_generated": true,
-                    "generation_time_ms": proof_result.generation_time_ms,
-                    "submitted_to_blockchain": app_state.blockchain_client.is_some(),
-                    "proof_data": proof_result.proof,
-                    "message": "Batch proven and submitted successfully using MVP prover"
-                })))
-            } else {
-                warn!("Proof generation failed for batch {}: {:?}", batch_result.batch_id, proof_result.error_message);
-                Ok(Json(json!({
-                    "status": "error",
-                    "batch_id": batch_result.batch_id,
-                    "proof_generated": false,
-                    "error": proof_result.error_message.unwrap_or_else(|| "Unknown error".to_string()),
-                    "generation_time_ms": proof_result.generation_time_ms,
-                    "message": "Batch proof generation failed"
-                })))
-            }
+    // Generate proofs using MVP prover and submit to blockchain
+    let proven = app_state.batch_prover.lock().await
+        .prove_through(batch_result.batch_id)
+        .await?;
+
+    // Raw vs submitted sizes feed the calldata cost analysis; the proof is already out, so only log failures
+    for submission in proven.iter().filter_map(|proven| proven.submission.as_ref()) {
+        if let Err(e) = proof_compression::record(&app_state.db, app_state.blobs.as_ref(), submission).await {
+            error!("Failed to record proof submission sizes for batch {}: {}", submission.batch_id, e);
         }
+    }
+
+    for proven in proven.iter().filter(|proven| proven.result.success) {
+        let Some(submission) = &proven.submission else {
+            continue;
+        };
+        let submitted = DomainEvent::ProofSubmitted {
+            batch_id: submission.batch_id,
+            target: submission.target.clone(),
+            artifact_hash: submission.artifact_hash(),
+            gas_used: proven.gas_used,
+        };
+        if let Err(e) = event_log::append(&app_state.db, &submitted).await {
+            error!("Failed to log proof submission of batch {}: {}", submission.batch_id, e);
+        }
+        let snapshot = match app_state.archive.load_snapshot(submission.batch_id).await {
+            Ok(Some(snapshot)) => snapshot,
+            Ok(None) => {
+                warn!("No state snapshot for batch {}, skipping its manifest and costs", submission.batch_id);
+                continue;
+            }
+            Err(e) => {
+                error!("Failed to load state snapshot for batch {}: {}", submission.batch_id, e);
+                continue;
+            }
+        };
+        publish_manifest(&app_state, &snapshot, submission).await;
+        attribute_costs(&app_state, &snapshot, submission, proven).await;
+    }
+
+    // Ends with this batch, or with the earlier batch whose proof failed
+    let Some(ProvenBatch { batch_id, result: proof_result, submission, .. }) = proven.into_iter().last() else {
+        return Err(BatchError::NotQueued(batch_result.batch_id).into());
+    };
+
+    if proof_result.success {
+        info!("Proof generated and submitted successfully for batch {}", batch_result.batch_id);
+        Ok(Json(json!({
+            "status": "success",
+            "batch_id": batch_result.batch_id,
+            "orders_count": batch_result.orders_count,
+            "proof_generated": true,
+            "generation_time_ms": proof_result.generation_time_ms,
+            "submitted_to_blockchain": app_state.blockchain_client.is_some(),
+            "proof_data": proof_result.proof,
+            "calldata": submission.map(|submission| json!({
+                "compression": submission.compression,
+                "raw_size": submission.raw_size,
+                "calldata_size": submission.calldata.len(),
+                "artifact_hash": submission.artifact_hash(),
+            })),
+            "message": "Batch proven and submitted successfully using MVP prover"
+        })))
+    } else {
+        warn!("Proof generation failed for batch {}: {:?}", batch_id, proof_result.error_message);
+        Ok(Json(json!({
+            "status": "error",
+            "batch_id": batch_id,
+            "proof_generated": false,
+            "error": proof_result.error_message.unwrap_or_else(|| "Unknown error".to_string()),
+            "generation_time_ms": proof_result.generation_time_ms,
+            "message": "Batch proof generation failed"
+        })))
+    }
+}
+
+/// Sign and store the manifest of a submitted batch, then push it in the background
+async fn publish_manifest(app_state: &AppState, snapshot: &BatchSnapshot, submission: &PreparedSubmission) {
+    if !app_state.manifests.is_enabled() {
+        return;
+    }
+
+    let batch_id = submission.batch_id;
+    let manifest = match app_state.manifests.publish(snapshot, &manifests::proof_artifact_hash(submission)).await {
+        Ok(Some(manifest)) => manifest,
+        Ok(None) => return,
         Err(e) => {
-            error!("Failed to generate proof for batch {}: {}", batch_result.batch_id, e);
-            Ok(Json(json!({
-                "status": "error",
-                "batch_id": batch_result.batch_id,
-                "proof_generated": false,
-                "error": e.to_string(),
-                "message": "Failed to generate proof for batch"
-            })))
+            error!("Failed to publish manifest of batch {}: {}", batch_id, e);
+            return;
         }
+    };
+
+    if app_state.manifests.pushes() {
+        let publisher = app_state.manifests.clone();
+        tokio::spawn(async move {
+            if let Err(e) = publisher.push(&manifest).await {
+                warn!("Failed to push manifest of batch {}: {}", manifest.batch_id, e);
+            }
+        });
+    }
+}
+
+/// Split a submitted batch's gas cost across its orders, and add it to the cost history
+async fn attribute_costs(app_state: &AppState, snapshot: &BatchSnapshot, submission: &PreparedSubmission, proven: &ProvenBatch) {
+    let config = &app_state.config.proof_submission;
+    let cost = order_costs::submission_cost(config, app_state.blockchain_client.as_deref(), submission, proven.gas_used).await;
+    let sample = BatchCostSample::new(
+        snapshot.batch_id,
+        snapshot.orders.len() as u64,
+        proven.result.generation_time_ms,
+        cost,
+        config.eth_usd_price,
+        app_state.clock.now(),
+    );
+    if let Err(e) = batch_costs::record(&app_state.db, &sample).await {
+        error!("Failed to record cost history of batch {}: {}", snapshot.batch_id, e);
+    }
+    let costs = order_costs::apportion(snapshot.batch_id, &snapshot.orders, cost, config.cost_weighting);
+    if let Err(e) = order_costs::record(&app_state.db, &costs).await {
+        error!("Failed to record order costs of batch {}: {}", snapshot.batch_id, e);
     }
 }
 
@@ -158,11 +412,198 @@ pub async fn get_batch_stats(
         current_batch_orders: stats.current_batch_orders,
         total_accounts: stats.total_accounts,
         has_active_batch: stats.has_active_batch,
+        queued_for_proof: stats.queued_for_proof,
+        merkle_cache: stats.merkle_cache,
+        invariants: stats.invariants,
     };
     
     Ok(Json(response))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SubmissionSizesQuery {
+    pub limit: Option<u32>,
+}
+
+/// Raw vs submitted proof sizes per batch, most recent first
+pub async fn get_submission_sizes(
+    State(app_state): State<AppState>,
+    Query(query): Query<SubmissionSizesQuery>,
+) -> Result<Json<Vec<SubmissionSizes>>, StatusCode> {
+    info!("Getting proof submission sizes");
+
+    let sizes = proof_compression::list(&app_state.db, query.limit.unwrap_or(50).min(500))
+        .await
+        .map_err(|e| {
+            error!("Database error fetching proof submission sizes: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(sizes))
+}
+
+/// Gas cost of a submitted batch and each order's share of it (GET /batch/:batch_id/costs)
+pub async fn get_batch_costs(
+    State(app_state): State<AppState>,
+    Path(batch_id): Path<u64>,
+) -> Result<Json<BatchCostReport>, ApiError> {
+    info!("Getting order costs of batch {}", batch_id);
+
+    let report = order_costs::for_batch(&app_state.db, batch_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching order costs of batch {}: {}", batch_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| {
+            ApiError::new(StatusCode::NOT_FOUND, "costs_not_found", format!("No costs attributed for batch {}", batch_id))
+        })?;
+
+    Ok(Json(report))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CostHistoryQuery {
+    #[serde(default)]
+    pub period: CostPeriod,
+    /// Periods to cover, including the current one; 30 days or 12 weeks by default
+    pub periods: Option<u32>,
+}
+
+/// Proving time and submission cost per day or week, with the change between the latest two (GET /stats/costs)
+pub async fn get_cost_history(
+    State(app_state): State<AppState>,
+    Query(query): Query<CostHistoryQuery>,
+) -> Result<Json<CostHistory>, StatusCode> {
+    let periods = query.periods.unwrap_or(match query.period {
+        CostPeriod::Daily => 30,
+        CostPeriod::Weekly => 12,
+    }).clamp(1, 366);
+    info!("Getting {:?} cost history over {} periods", query.period, periods);
+
+    let history = batch_costs::history(&app_state.db, query.period, periods, app_state.clock.now())
+        .await
+        .map_err(|e| {
+            error!("Database error fetching cost history: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(history))
+}
+
+/// Get state snapshot retention statistics
+pub async fn get_archive_stats(
+    State(app_state): State<AppState>,
+) -> Result<Json<ArchiveStats>, StatusCode> {
+    info!("Getting snapshot archive statistics");
+
+    let stats = app_state.archive.stats().await.map_err(|e| {
+        error!("Database error fetching archive stats: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(stats))
+}
+
+/// Known roots listed when a looked-up root is unknown
+const NEAREST_ROOTS: usize = 5;
+
+#[derive(Debug, Serialize)]
+pub struct RootLookupResponse {
+    pub root: String,
+    /// Whether the root belongs to more than one batch, e.g. a state root left unchanged
+    pub ambiguous: bool,
+    pub matches: Vec<RootMatch>,
+}
+
+/// Resolve a state or orders root, e.g. one observed on-chain, to the batches it belongs to
+#[instrument(skip(app_state))]
+pub async fn get_batches_by_root(
+    State(app_state): State<AppState>,
+    Path(root): Path<String>,
+) -> Result<Json<RootLookupResponse>, ApiError> {
+    info!("Looking up batches by root {}", root);
+
+    let root = parse_hash32(&root)
+        .map(|bytes| format!("0x{}", hex::encode(bytes)))
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, "invalid_root", e.to_string()))?;
+
+    let matches = app_state.archive.batches_by_root(&root).await.map_err(|e| {
+        error!("Database error looking up root {}: {}", root, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if matches.is_empty() {
+        let nearest = app_state.archive.nearest_roots(&root, NEAREST_ROOTS).await.map_err(|e| {
+            error!("Database error listing roots near {}: {}", root, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "root_not_found", format!("No finalized batch has root {}", root))
+            .with_details(json!({ "nearest": nearest })));
+    }
+
+    Ok(Json(RootLookupResponse { root, ambiguous: matches.len() > 1, matches }))
+}
+
+/// Change the mock prover's delay, random failures and scripted failure scenarios
+///
+/// Omitted fields keep their value; `scenarios` replaces the whole list and restarts its
+/// attempt counts.
+#[derive(Debug, Deserialize)]
+pub struct UpdateProverConfigRequest {
+    pub generation_delay_ms: Option<u64>,
+    pub simulate_failures: Option<bool>,
+    pub failure_rate: Option<f64>,
+    pub scenarios: Option<Vec<FailureScenario>>,
+}
+
+/// Get the mock prover configuration
+pub async fn get_prover_config(
+    State(app_state): State<AppState>,
+) -> Json<MvpProverConfig> {
+    info!("Getting prover config");
+
+    Json(app_state.batch_prover.lock().await.prover_config().clone())
+}
+
+pub async fn update_prover_config(
+    State(app_state): State<AppState>,
+    Json(req): Json<UpdateProverConfigRequest>,
+) -> Result<Json<MvpProverConfig>, ApiError> {
+    info!("Updating prover config: {:?}", req);
+
+    if req.failure_rate.is_some_and(|rate| !(0.0..=1.0).contains(&rate)) {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "invalid_failure_rate", "failure_rate must be between 0.0 and 1.0"));
+    }
+    let simulates_failures = req.simulate_failures == Some(true)
+        || req.scenarios.as_ref().is_some_and(|scenarios| !scenarios.is_empty());
+    if simulates_failures && !app_state.config.profile.allows_simulated_failures() {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "disabled_in_profile",
+            format!("Simulated prover failures are disabled in the {} profile", app_state.config.profile.as_str()),
+        ));
+    }
+
+    let mut batch_prover = app_state.batch_prover.lock().await;
+    let mut config = batch_prover.prover_config().clone();
+    if let Some(generation_delay_ms) = req.generation_delay_ms {
+        config.generation_delay_ms = generation_delay_ms;
+    }
+    if let Some(simulate_failures) = req.simulate_failures {
+        config.simulate_failures = simulate_failures;
+    }
+    if let Some(failure_rate) = req.failure_rate {
+        config.failure_rate = failure_rate;
+    }
+    if let Some(scenarios) = req.scenarios {
+        config.scenarios = scenarios;
+    }
+    batch_prover.update_prover_config(config.clone());
+
+    Ok(Json(config))
+}
+
 /// Get current batch information
 pub async fn get_current_batch(
     State(app_state): State<AppState>,
@@ -190,40 +631,3 @@ pub async fn get_current_batch(
         }
     }
 }
-
-/// Initialize account for testing/demo purposes
-#[derive(Debug, Deserialize)]
-pub struct InitAccountRequest {
-    pub address: String,
-    pub token_id: u32,
-    pub initial_balance: String,
-}
-
-pub async fn init_account(
-    State(app_state): State<AppState>,
-    Json(req): Json<InitAccountRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    info!("Initializing account: {} with {} of token {}", req.address, req.initial_balance, req.token_id);
-    
-    let mut processor = app_state.batch_processor.lock().await;
-    
-    match processor.init_account(req.address.clone(), req.token_id, req.initial_balance.clone()) {
-        Ok(_) => {
-            info!("Account initialized successfully: {}", req.address);
-            Ok(Json(json!({
-                "status": "success",
-                "address": req.address,
-                "token_id": req.token_id,
-                "initial_balance": req.initial_balance,
-                "message": "Account initialized successfully"
-            })))
-        }
-        Err(e) => {
-            error!("Failed to initialize account: {}", e);
-            Ok(Json(json!({
-                "status": "error",
-                "message": format!("Failed to initialize account: {}", e)
-            })))
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1,210 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+use tracing::warn;
+
+use super::AppState;
+use crate::config::HttpCacheConfig;
+use vapor_core::sparse_merkle_tree::solidity_keccak256_hash;
+
+/// A polled read endpoint that answers conditional requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CachedRoute {
+    /// GET /orders/:id
+    Order(String),
+    /// GET /orders/:id/status
+    OrderStatus(String),
+    /// GET /proofs/order/:batch_id/:order_id
+    OrderProof { batch_id: u64, order_id: String },
+}
+
+impl CachedRoute {
+    pub fn of(path: &str) -> Option<Self> {
+        let segments: Vec<&str> = path.trim_start_matches("/api/v1/").split('/').collect();
+        match segments.as_slice() {
+            ["orders", order_id] if !order_id.is_empty() && *order_id != "quote" => Some(Self::Order(order_id.to_string())),
+            ["orders", order_id, "status"] => Some(Self::OrderStatus(order_id.to_string())),
+            ["proofs", "order", batch_id, order_id] => Some(Self::OrderProof {
+                batch_id: batch_id.parse().ok()?,
+                order_id: order_id.to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn cache_control(&self, config: &HttpCacheConfig) -> String {
+        match self {
+            // Order data is per user; shared caches must not keep it
+            Self::Order(_) | Self::OrderStatus(_) if config.order_max_age_seconds == 0 => "private, no-cache".to_string(),
+            Self::Order(_) | Self::OrderStatus(_) => format!("private, max-age={}", config.order_max_age_seconds),
+            Self::OrderProof { .. } => format!("public, max-age={}", config.proof_max_age_seconds),
+        }
+    }
+}
+
+/// What a response is validated against: the ETag and Last-Modified it is served with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validator {
+    pub etag: String,
+    pub last_modified: DateTime<Utc>,
+}
+
+impl Validator {
+    /// Weak ETag over `parts`: the same parts give a semantically equal response, though
+    /// fields such as an order's SLO position move on with the clock
+    fn from_parts(parts: &[&[u8]], last_modified: DateTime<Utc>) -> Self {
+        let digest = solidity_keccak256_hash(parts);
+        Self { etag: format!("W/\"{}\"", hex::encode(&digest[..12])), last_modified }
+    }
+
+    /// Whether a client holding this representation can be answered with 304
+    ///
+    /// If-None-Match wins over If-Modified-Since when both are sent, and ETags compare weakly.
+    pub fn matches(&self, headers: &HeaderMap) -> bool {
+        if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+            let Ok(if_none_match) = if_none_match.to_str() else {
+                return false;
+            };
+            let ours = self.etag.trim_start_matches("W/");
+            return if_none_match.split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == ours);
+        }
+        headers.get(header::IF_MODIFIED_SINCE)
+            .and_then(|since| since.to_str().ok())
+            .and_then(|since| DateTime::parse_from_rfc2822(since).ok())
+            .is_some_and(|since| self.last_modified.timestamp() <= since.timestamp())
+    }
+
+    fn apply(&self, headers: &mut HeaderMap, cache_control: &str) {
+        let values = [
+            (header::ETAG, self.etag.clone()),
+            (header::LAST_MODIFIED, http_date(self.last_modified)),
+            (header::CACHE_CONTROL, cache_control.to_string()),
+        ];
+        for (name, value) in values {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.insert(name, value);
+            }
+        }
+    }
+}
+
+/// IMF-fixdate, as HTTP dates are sent
+pub fn http_date(at: DateTime<Utc>) -> String {
+    at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Validator of the resource behind a route, from a single indexed lookup; None when it
+/// does not exist or is not cacheable yet (a proof of a batch not finalized)
+pub async fn validator(app_state: &AppState, route: &CachedRoute, query: &str) -> anyhow::Result<Option<Validator>> {
+    match route {
+        CachedRoute::Order(order_id) | CachedRoute::OrderStatus(order_id) => {
+            // A partial settlement touches the children listed in the parent's status
+            let row = sqlx::query("SELECT MAX(updated_at) AS updated_at, COUNT(*) AS orders FROM orders WHERE id = ?1 OR parent_order_id = ?1")
+                .bind(order_id)
+                .fetch_one(&app_state.db)
+                .await?;
+            let Some(updated_at) = row.try_get::<Option<DateTime<Utc>>, _>("updated_at")? else {
+                return Ok(None);
+            };
+            let orders: i64 = row.try_get("orders")?;
+            Ok(Some(Validator::from_parts(
+                &[order_id.as_bytes(), updated_at.to_rfc3339().as_bytes(), &orders.to_be_bytes()],
+                updated_at,
+            )))
+        }
+        CachedRoute::OrderProof { batch_id, order_id } => {
+            let Some((state_root, orders_root, finalized_at)) = app_state.archive.snapshot_roots(*batch_id).await? else {
+                return Ok(None);
+            };
+            Ok(Some(Validator::from_parts(
+                &[&batch_id.to_be_bytes(), state_root.as_bytes(), orders_root.as_bytes(), order_id.as_bytes(), query.as_bytes()],
+                finalized_at,
+            )))
+        }
+    }
+}
+
+/// Serve ETag, Last-Modified and Cache-Control on polled reads, and answer a client whose
+/// copy is still current with 304 before the handler runs
+///
+/// Responses other than 200 are passed through without validators. A validator read before a
+/// concurrent update can only be older than the body, so clients revalidate early rather than late.
+pub async fn conditional_get(
+    State(app_state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return next.run(request).await;
+    }
+    let Some(route) = CachedRoute::of(request.uri().path()) else {
+        return next.run(request).await;
+    };
+
+    let query = request.uri().query().unwrap_or_default().to_string();
+    let validator = match validator(&app_state, &route, &query).await {
+        Ok(validator) => validator,
+        Err(e) => {
+            warn!("Failed to read cache validator for {}: {}", request.uri().path(), e);
+            None
+        }
+    };
+    let Some(validator) = validator else {
+        return next.run(request).await;
+    };
+
+    let cache_control = route.cache_control(&app_state.config.http_cache);
+    if validator.matches(request.headers()) {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        validator.apply(response.headers_mut(), &cache_control);
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+    if response.status() == StatusCode::OK {
+        validator.apply(response.headers_mut(), &cache_control);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_routes() {
+        assert_eq!(CachedRoute::of("/api/v1/orders/order_1"), Some(CachedRoute::Order("order_1".to_string())));
+        assert_eq!(CachedRoute::of("/api/v1/orders/order_1/status"), Some(CachedRoute::OrderStatus("order_1".to_string())));
+        assert_eq!(
+            CachedRoute::of("/api/v1/proofs/order/3/order_1"),
+            Some(CachedRoute::OrderProof { batch_id: 3, order_id: "order_1".to_string() })
+        );
+        assert_eq!(CachedRoute::of("/api/v1/orders/order_1/history"), None);
+        assert_eq!(CachedRoute::of("/api/v1/proofs/order/latest/order_1"), None);
+        assert_eq!(CachedRoute::of("/api/v1/orders"), None);
+    }
+
+    #[test]
+    fn test_validator_matching() {
+        let last_modified = DateTime::parse_from_rfc3339("2026-01-02T03:04:05.600Z").unwrap().with_timezone(&Utc);
+        let validator = Validator::from_parts(&[b"order_1"], last_modified);
+        let headers = |name, value: &str| HeaderMap::from_iter([(name, HeaderValue::from_str(value).unwrap())]);
+        let strong = validator.etag.trim_start_matches("W/");
+
+        assert!(validator.matches(&headers(header::IF_NONE_MATCH, &validator.etag)));
+        assert!(validator.matches(&headers(header::IF_NONE_MATCH, &format!("\"other\", {}", strong))));
+        assert!(validator.matches(&headers(header::IF_NONE_MATCH, "*")));
+        assert!(!validator.matches(&headers(header::IF_NONE_MATCH, "W/\"other\"")));
+
+        assert!(validator.matches(&headers(header::IF_MODIFIED_SINCE, &http_date(last_modified))));
+        assert!(!validator.matches(&headers(header::IF_MODIFIED_SINCE, "Fri, 02 Jan 2026 03:04:04 GMT")));
+        assert!(!validator.matches(&HeaderMap::new()));
+    }
+}
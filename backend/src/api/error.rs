@@ -0,0 +1,568 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+use tracing::{error, warn};
+
+use vapor_chain::ChainError;
+use vapor_core::proof_format::ProofError;
+use crate::models::InvalidCursor;
+use crate::services::attestations::AttestationError;
+use crate::services::backups::BackupError;
+use crate::services::batch_processor::BatchError;
+use crate::services::bulk_accounts::BulkAccountError;
+use crate::services::claim_fees::ClaimFeeError;
+use crate::services::claim_relay::RelayError;
+use crate::services::claims::ClaimError;
+use crate::services::collateral::CollateralError;
+use crate::services::filler_capabilities::CapabilityError;
+use crate::services::matching_engine::{FillerLimitError, MatchError};
+use crate::services::duplicates::DuplicateOrderError;
+use crate::services::order_limits::OrderAmountError;
+use crate::services::payment_proofs::PaymentProofError;
+use crate::services::quotes::QuoteError;
+use crate::services::rates::RateError;
+use crate::services::registry::RegistryError;
+use crate::services::request_limiter::RateLimited;
+use crate::services::market::CorridorSaturated;
+use crate::services::batch_sequence::BatchSequenceError;
+use crate::services::handover::HandoverError;
+use crate::services::order_review::OrderReviewError;
+use crate::services::screening::ScreeningError;
+use crate::services::settlement::SettlementError;
+use crate::services::settlement_saga::SagaError;
+use crate::services::system_accounts::SystemAccountError;
+use crate::services::transfers::TransferError;
+use crate::services::notifications::NotificationError;
+use crate::services::withdrawal_limits::{WithdrawalError, WithdrawalLimitError};
+
+/// Error returned by API handlers
+///
+/// Serialized as `{"error": code, "message": ..., "details": ...}` so clients can branch
+/// on the stable `code` instead of parsing messages.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    pub details: Option<Value>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if self.status.is_server_error() {
+            error!("{} ({}): {}", self.status, self.code, self.message);
+        } else {
+            warn!("{} ({}): {}", self.status, self.code, self.message);
+        }
+
+        let mut body = json!({
+            "error": self.code,
+            "message": self.message,
+        });
+        if let Some(details) = self.details {
+            body["details"] = details;
+        }
+
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Plain status codes from handlers that have not been given a dedicated error code
+impl From<StatusCode> for ApiError {
+    fn from(status: StatusCode) -> Self {
+        let code = match status {
+            StatusCode::BAD_REQUEST => "bad_request",
+            StatusCode::NOT_FOUND => "not_found",
+            StatusCode::CONFLICT => "conflict",
+            StatusCode::SERVICE_UNAVAILABLE => "service_unavailable",
+            _ => "internal_error",
+        };
+        Self::new(status, code, status.canonical_reason().unwrap_or("Unknown error"))
+    }
+}
+
+impl From<BatchError> for ApiError {
+    fn from(e: BatchError) -> Self {
+        // Wrapped errors keep the code of the module they came from
+        let e = match e {
+            BatchError::Proof(e) => return e.into(),
+            BatchError::Chain(e) => return e.into(),
+            BatchError::WithdrawalLimit(e) => return e.into(),
+            BatchError::AmountOutOfRange(e) => return e.into(),
+            BatchError::SystemAccount(e) => return e.into(),
+            BatchError::Sequence(e) => return e.into(),
+            e => e,
+        };
+
+        let (status, code) = match &e {
+            BatchError::BatchInProgress => (StatusCode::CONFLICT, "batch_in_progress"),
+            BatchError::NoActiveBatch | BatchError::NothingToFinalize => (StatusCode::CONFLICT, "no_active_batch"),
+            BatchError::NotFinalized(_) | BatchError::NotQueued(_) => (StatusCode::CONFLICT, "batch_not_ready"),
+            BatchError::InvalidAmount(_) => (StatusCode::BAD_REQUEST, "invalid_amount"),
+            BatchError::AccountNotFound(_) => (StatusCode::NOT_FOUND, "account_not_found"),
+            BatchError::TokenBalanceNotFound { .. } => (StatusCode::NOT_FOUND, "token_balance_not_found"),
+            BatchError::InsufficientBalance { .. } => (StatusCode::UNPROCESSABLE_ENTITY, "insufficient_balance"),
+            BatchError::BalanceOverflow { .. } => (StatusCode::UNPROCESSABLE_ENTITY, "balance_overflow"),
+            BatchError::NoBlockchainClient => (StatusCode::SERVICE_UNAVAILABLE, "blockchain_unavailable"),
+            BatchError::Compression(_) => (StatusCode::INTERNAL_SERVER_ERROR, "proof_compression_failed"),
+            BatchError::Deferred(_) => (StatusCode::CONFLICT, "batch_cap_exceeded"),
+            BatchError::Tree(_)
+            | BatchError::Proof(_)
+            | BatchError::Chain(_)
+            | BatchError::WithdrawalLimit(_)
+            | BatchError::AmountOutOfRange(_)
+            | BatchError::SystemAccount(_)
+            | BatchError::Sequence(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "merkle_tree_error")
+            }
+        };
+        Self::new(status, code, e.to_string())
+    }
+}
+
+impl From<MatchError> for ApiError {
+    fn from(e: MatchError) -> Self {
+        match e {
+            MatchError::UnsupportedOrderType(_) => {
+                Self::new(StatusCode::BAD_REQUEST, "unsupported_order_type", e.to_string())
+            }
+            MatchError::LimitExceeded(e) => e.into(),
+            MatchError::Capability(e) => e.into(),
+        }
+    }
+}
+
+impl From<FillerLimitError> for ApiError {
+    fn from(e: FillerLimitError) -> Self {
+        let details = json!(e);
+        Self::new(StatusCode::TOO_MANY_REQUESTS, "filler_limit_exceeded", e.to_string()).with_details(details)
+    }
+}
+
+impl From<CapabilityError> for ApiError {
+    fn from(e: CapabilityError) -> Self {
+        let details = json!(e);
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, "outside_filler_capabilities", e.to_string()).with_details(details)
+    }
+}
+
+impl From<CollateralError> for ApiError {
+    fn from(e: CollateralError) -> Self {
+        let (status, code) = match &e {
+            CollateralError::Insufficient { .. } => (StatusCode::FORBIDDEN, "insufficient_collateral"),
+            CollateralError::Flagged { .. } => (StatusCode::FORBIDDEN, "filler_flagged"),
+            CollateralError::Other(_) => return Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", e.to_string()),
+        };
+        let details = json!(e);
+        Self::new(status, code, e.to_string()).with_details(details)
+    }
+}
+
+impl From<QuoteError> for ApiError {
+    fn from(e: QuoteError) -> Self {
+        let (status, code) = match &e {
+            QuoteError::NotFound { .. } => (StatusCode::NOT_FOUND, "quote_not_found"),
+            QuoteError::Expired { .. } => (StatusCode::GONE, "quote_expired"),
+            QuoteError::AlreadyUsed { .. } => (StatusCode::CONFLICT, "quote_used"),
+            QuoteError::Mismatch { .. } => (StatusCode::UNPROCESSABLE_ENTITY, "quote_mismatch"),
+            QuoteError::Other(_) => return Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", e.to_string()),
+        };
+        let details = json!(e);
+        Self::new(status, code, e.to_string()).with_details(details)
+    }
+}
+
+impl From<SystemAccountError> for ApiError {
+    fn from(e: SystemAccountError) -> Self {
+        let (status, code) = match &e {
+            SystemAccountError::ForbiddenTransition { .. } => (StatusCode::UNPROCESSABLE_ENTITY, "system_account_transition"),
+            SystemAccountError::Reserved { .. } => (StatusCode::BAD_REQUEST, "reserved_address"),
+            SystemAccountError::Unbalanced { .. } => (StatusCode::INTERNAL_SERVER_ERROR, "ledger_unbalanced"),
+        };
+        let details = json!(e);
+        Self::new(status, code, e.to_string()).with_details(details)
+    }
+}
+
+impl From<WithdrawalLimitError> for ApiError {
+    fn from(e: WithdrawalLimitError) -> Self {
+        let (status, code) = match &e {
+            WithdrawalLimitError::Denylisted { .. } | WithdrawalLimitError::NotAllowlisted { .. } => {
+                (StatusCode::FORBIDDEN, "withdrawal_not_permitted")
+            }
+            WithdrawalLimitError::PerAddressDaily { .. } | WithdrawalLimitError::GlobalDaily { .. } => {
+                (StatusCode::TOO_MANY_REQUESTS, "withdrawal_limit_exceeded")
+            }
+        };
+        let details = json!(e);
+        Self::new(status, code, e.to_string()).with_details(details)
+    }
+}
+
+impl From<OrderAmountError> for ApiError {
+    fn from(e: OrderAmountError) -> Self {
+        let details = json!(e);
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, "order_amount_out_of_range", e.to_string()).with_details(details)
+    }
+}
+
+impl From<WithdrawalError> for ApiError {
+    fn from(e: WithdrawalError) -> Self {
+        match e {
+            WithdrawalError::Limit(e) => e.into(),
+            WithdrawalError::Database(e) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", e.to_string()),
+        }
+    }
+}
+
+impl From<RateError> for ApiError {
+    fn from(e: RateError) -> Self {
+        let (status, code) = match &e {
+            RateError::UnknownToken(_) => (StatusCode::BAD_REQUEST, "unsupported_payout_token"),
+            RateError::Overflow => (StatusCode::BAD_REQUEST, "invalid_amount"),
+            RateError::RequoteRequired(requote) => {
+                let details = json!(requote);
+                return Self::new(StatusCode::CONFLICT, "requote_required", e.to_string()).with_details(details);
+            }
+        };
+        Self::new(status, code, e.to_string())
+    }
+}
+
+impl From<RelayError> for ApiError {
+    fn from(e: RelayError) -> Self {
+        let message = e.to_string();
+        match e {
+            RelayError::Unavailable => Self::new(StatusCode::CONFLICT, "claim_relay_unavailable", message),
+            RelayError::FeeExceedsClaim { fee, largest_claim } => {
+                Self::new(StatusCode::UNPROCESSABLE_ENTITY, "relay_fee_exceeds_claim", message)
+                    .with_details(json!({ "fee": fee.to_string(), "largest_claim": largest_claim.to_string() }))
+            }
+            RelayError::FeeAboveMaximum { fee, max_fee } => {
+                Self::new(StatusCode::UNPROCESSABLE_ENTITY, "relay_fee_above_maximum", message)
+                    .with_details(json!({ "fee": fee.to_string(), "max_fee": max_fee.to_string() }))
+            }
+            RelayError::Rate(e) => e.into(),
+            RelayError::Other(_) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message),
+        }
+    }
+}
+
+impl From<ClaimFeeError> for ApiError {
+    fn from(e: ClaimFeeError) -> Self {
+        let message = e.to_string();
+        match e {
+            ClaimFeeError::FeeExceedsAmount { gross, fee } => {
+                Self::new(StatusCode::UNPROCESSABLE_ENTITY, "claim_fee_exceeds_amount", message)
+                    .with_details(json!({ "amount": gross.to_string(), "fee": fee.to_string() }))
+            }
+            ClaimFeeError::InvalidAmount(_) => Self::new(StatusCode::BAD_REQUEST, "invalid_amount", message),
+        }
+    }
+}
+
+impl From<PaymentProofError> for ApiError {
+    fn from(e: PaymentProofError) -> Self {
+        let code = match &e {
+            PaymentProofError::Invalid { .. } => "invalid_payment_proof",
+            PaymentProofError::UnsupportedService(_) => "unsupported_bank_service",
+            PaymentProofError::Missing => "missing_payment_proof",
+        };
+        Self::new(StatusCode::BAD_REQUEST, code, e.to_string())
+    }
+}
+
+impl From<InvalidCursor> for ApiError {
+    fn from(e: InvalidCursor) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_cursor", e.to_string())
+    }
+}
+
+impl From<RateLimited> for ApiError {
+    fn from(e: RateLimited) -> Self {
+        let details = json!(e);
+        Self::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", e.to_string()).with_details(details)
+    }
+}
+
+impl From<CorridorSaturated> for ApiError {
+    fn from(e: CorridorSaturated) -> Self {
+        let details = json!(e);
+        Self::new(StatusCode::SERVICE_UNAVAILABLE, "corridor_saturated", e.to_string()).with_details(details)
+    }
+}
+
+impl From<ChainError> for ApiError {
+    fn from(e: ChainError) -> Self {
+        let (status, code) = match &e {
+            ChainError::InvalidHex(_) | ChainError::InvalidLength(_) => (StatusCode::BAD_REQUEST, "invalid_hex"),
+            ChainError::NoSigner => (StatusCode::SERVICE_UNAVAILABLE, "signer_unavailable"),
+            ChainError::InsufficientFunds { signer, balance, required } => {
+                let details = json!({
+                    "signer": format!("{:?}", signer),
+                    "balance_wei": balance.to_string(),
+                    "required_wei": required.to_string(),
+                });
+                return Self::new(StatusCode::SERVICE_UNAVAILABLE, "signer_insufficient_funds", e.to_string()).with_details(details);
+            }
+            ChainError::Injected(_) => (StatusCode::SERVICE_UNAVAILABLE, "chain_unavailable"),
+            ChainError::Transport(_) | ChainError::Contract(_) | ChainError::Signing(_) => {
+                (StatusCode::BAD_GATEWAY, "chain_error")
+            }
+            ChainError::Abi(_) => (StatusCode::INTERNAL_SERVER_ERROR, "chain_error"),
+        };
+        Self::new(status, code, e.to_string())
+    }
+}
+
+impl From<ClaimError> for ApiError {
+    fn from(e: ClaimError) -> Self {
+        match e {
+            ClaimError::IncompleteReference | ClaimError::DuplicateInRequest(_) => {
+                Self::new(StatusCode::BAD_REQUEST, "invalid_claim", e.to_string())
+            }
+            ClaimError::AlreadyClaimed(ref order_ids) => {
+                let details = json!({ "order_ids": order_ids });
+                Self::new(StatusCode::CONFLICT, "already_claimed", e.to_string()).with_details(details)
+            }
+            ClaimError::Chain(e) => e.into(),
+            ClaimError::Database(_) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", e.to_string()),
+        }
+    }
+}
+
+impl From<ProofError> for ApiError {
+    fn from(e: ProofError) -> Self {
+        let (status, code) = match &e {
+            ProofError::UnknownFormat(_) | ProofError::SortedPairsForAccount => {
+                (StatusCode::BAD_REQUEST, "unsupported_proof_format")
+            }
+            ProofError::PathLengthMismatch { .. }
+            | ProofError::MissingPathBits
+            | ProofError::InvalidHex(_)
+            | ProofError::InvalidHashLength(_)
+            | ProofError::InvalidMultiProof(_) => (StatusCode::BAD_REQUEST, "invalid_proof"),
+            ProofError::EmptyTree | ProofError::LeafIndexOutOfRange(_) => (StatusCode::NOT_FOUND, "leaf_not_found"),
+            ProofError::Tree(_) => (StatusCode::INTERNAL_SERVER_ERROR, "merkle_tree_error"),
+        };
+        Self::new(status, code, e.to_string())
+    }
+}
+
+impl From<RegistryError> for ApiError {
+    fn from(e: RegistryError) -> Self {
+        let (status, code) = match &e {
+            RegistryError::TokenDisabled(_) => (StatusCode::UNPROCESSABLE_ENTITY, "token_disabled"),
+            RegistryError::BankServiceDisabled(_) => (StatusCode::UNPROCESSABLE_ENTITY, "bank_service_disabled"),
+            RegistryError::Storage(_) => (StatusCode::INTERNAL_SERVER_ERROR, "registry_unavailable"),
+        };
+        Self::new(status, code, e.to_string())
+    }
+}
+
+impl From<BulkAccountError> for ApiError {
+    fn from(e: BulkAccountError) -> Self {
+        let (status, code) = match &e {
+            BulkAccountError::TooManyEntries { .. } => (StatusCode::PAYLOAD_TOO_LARGE, "too_many_entries"),
+            _ => (StatusCode::BAD_REQUEST, "invalid_bulk_accounts"),
+        };
+        let details = json!(e);
+        Self::new(status, code, e.to_string()).with_details(details)
+    }
+}
+
+impl From<SettlementError> for ApiError {
+    fn from(e: SettlementError) -> Self {
+        let (status, code) = match &e {
+            SettlementError::NotFound(_) => (StatusCode::NOT_FOUND, "order_not_found"),
+            SettlementError::InvalidStatus { .. } => (StatusCode::CONFLICT, "invalid_order_status"),
+            SettlementError::Conflict(_) => (StatusCode::CONFLICT, "conflict"),
+            SettlementError::NothingToSplit(_) => (StatusCode::BAD_REQUEST, "nothing_to_split"),
+            SettlementError::CannotRediscover { .. } => (StatusCode::BAD_REQUEST, "cannot_rediscover"),
+            SettlementError::Other(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        };
+        Self::new(status, code, e.to_string())
+    }
+}
+
+impl From<SagaError> for ApiError {
+    fn from(e: SagaError) -> Self {
+        let (status, code) = match &e {
+            SagaError::NotFound(_) => (StatusCode::NOT_FOUND, "saga_not_found"),
+            SagaError::AlreadyFinished { .. } => (StatusCode::CONFLICT, "saga_finished"),
+            SagaError::Other(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        };
+        Self::new(status, code, e.to_string())
+    }
+}
+
+impl From<DuplicateOrderError> for ApiError {
+    fn from(e: DuplicateOrderError) -> Self {
+        let details = json!(e.0);
+        Self::new(StatusCode::CONFLICT, "suspected_duplicate", e.to_string()).with_details(details)
+    }
+}
+
+impl From<BackupError> for ApiError {
+    fn from(e: BackupError) -> Self {
+        let (status, code) = match &e {
+            BackupError::NotFound(_) => (StatusCode::NOT_FOUND, "backup_not_found"),
+            BackupError::Corrupt { .. } => (StatusCode::UNPROCESSABLE_ENTITY, "backup_corrupt"),
+            BackupError::SchemaMismatch { expected, found, tables, .. } => {
+                let details = json!({ "expected": expected, "found": found, "tables": tables });
+                return Self::new(StatusCode::CONFLICT, "backup_schema_mismatch", e.to_string()).with_details(details);
+            }
+            BackupError::Other(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        };
+        Self::new(status, code, e.to_string())
+    }
+}
+
+impl From<AttestationError> for ApiError {
+    fn from(e: AttestationError) -> Self {
+        let (status, code) = match &e {
+            AttestationError::Disabled => (StatusCode::SERVICE_UNAVAILABLE, "attestations_disabled"),
+            AttestationError::Expired(_) => (StatusCode::UNPROCESSABLE_ENTITY, "attestation_expired"),
+            AttestationError::InvalidSignature(_) => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_attestation"),
+        };
+        Self::new(status, code, e.to_string())
+    }
+}
+
+impl From<BatchSequenceError> for ApiError {
+    fn from(e: BatchSequenceError) -> Self {
+        let message = e.to_string();
+        match e {
+            BatchSequenceError::Gap { expected, found } => {
+                Self::new(StatusCode::CONFLICT, "batch_sequence_gap", message)
+                    .with_details(json!({ "expected": expected, "found": found }))
+            }
+            BatchSequenceError::Fork { batch_id, prev_batch_id, root, expected, found } => {
+                Self::new(StatusCode::CONFLICT, "batch_sequence_fork", message).with_details(json!({
+                    "batch_id": batch_id,
+                    "prev_batch_id": prev_batch_id,
+                    "root": root,
+                    "expected": expected,
+                    "found": found,
+                }))
+            }
+            BatchSequenceError::Other(_) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message),
+        }
+    }
+}
+
+impl From<ScreeningError> for ApiError {
+    fn from(e: ScreeningError) -> Self {
+        let (status, code) = match &e {
+            ScreeningError::Blocked { .. } => (StatusCode::FORBIDDEN, "address_screened"),
+            ScreeningError::Unavailable { .. } => (StatusCode::SERVICE_UNAVAILABLE, "screening_unavailable"),
+            ScreeningError::NotFound { .. } => (StatusCode::NOT_FOUND, "screening_result_not_found"),
+            ScreeningError::NotPending { .. } => (StatusCode::CONFLICT, "screening_not_pending"),
+            ScreeningError::Other(_) => return Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", e.to_string()),
+        };
+        let details = json!(e);
+        Self::new(status, code, e.to_string()).with_details(details)
+    }
+}
+
+impl From<OrderReviewError> for ApiError {
+    fn from(e: OrderReviewError) -> Self {
+        let (status, code) = match &e {
+            OrderReviewError::NotFound { .. } => (StatusCode::NOT_FOUND, "order_review_not_found"),
+            OrderReviewError::NotPending { .. } => (StatusCode::CONFLICT, "order_review_not_pending"),
+            OrderReviewError::ReasonRequired { .. } => (StatusCode::BAD_REQUEST, "review_reason_required"),
+            OrderReviewError::Other(_) => return Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", e.to_string()),
+        };
+        let details = json!(e);
+        Self::new(status, code, e.to_string()).with_details(details)
+    }
+}
+
+impl From<HandoverError> for ApiError {
+    fn from(e: HandoverError) -> Self {
+        let (status, code) = match &e {
+            HandoverError::NotWriter(_) => (StatusCode::CONFLICT, "not_writer"),
+            HandoverError::NotStandby(_) => (StatusCode::CONFLICT, "not_standby"),
+            HandoverError::Version(_) | HandoverError::Digest => (StatusCode::BAD_REQUEST, "invalid_handover_manifest"),
+            HandoverError::NotPending(_) => (StatusCode::CONFLICT, "handover_not_pending"),
+            HandoverError::Diverged { field, manifest, current } => {
+                let details = json!({ "field": field, "manifest": manifest, "current": current });
+                return Self::new(StatusCode::CONFLICT, "handover_diverged", e.to_string()).with_details(details);
+            }
+            HandoverError::Other(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        };
+        Self::new(status, code, e.to_string())
+    }
+}
+
+impl From<TransferError> for ApiError {
+    fn from(e: TransferError) -> Self {
+        let (status, code) = match &e {
+            TransferError::Invalid(_) => (StatusCode::BAD_REQUEST, "invalid_transfer"),
+            TransferError::MalformedSignature | TransferError::WrongSigner { .. } => (StatusCode::UNAUTHORIZED, "invalid_signature"),
+            TransferError::NonceUsed { .. } => (StatusCode::CONFLICT, "nonce_used"),
+            TransferError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        };
+        Self::new(status, code, e.to_string())
+    }
+}
+
+impl From<NotificationError> for ApiError {
+    fn from(e: NotificationError) -> Self {
+        let (status, code) = match &e {
+            NotificationError::Invalid(_) => (StatusCode::BAD_REQUEST, "invalid_notification_preference"),
+            NotificationError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        };
+        Self::new(status, code, e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_errors_map_to_api_codes() {
+        let err = ApiError::from(BatchError::InsufficientBalance { available: 100, required: 500 });
+        assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(err.code, "insufficient_balance");
+        assert_eq!(err.message, "Insufficient balance: 100 < 500");
+
+        let err = ApiError::from(BatchError::Chain(ChainError::NoSigner));
+        assert_eq!(err.status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(err.code, "signer_unavailable");
+
+        let err = ApiError::from(MatchError::LimitExceeded(FillerLimitError::MaxConcurrentLocks {
+            filler_id: "filler_1".to_string(),
+            current: 2,
+            max: 2,
+        }));
+        assert_eq!(err.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(err.details.unwrap()["limit"], "max_concurrent_locks");
+
+        let err = ApiError::from(ProofError::UnknownFormat("bogus".to_string()));
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert_eq!(err.code, "unsupported_proof_format");
+
+        assert_eq!(ApiError::from(StatusCode::NOT_FOUND).code, "not_found");
+    }
+}
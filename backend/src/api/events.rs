@@ -0,0 +1,38 @@
+use axum::{extract::{Query, State}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use super::AppState;
+use crate::services::event_log::{self, LoggedEvent};
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// Sequence number of the last event already seen; 0 (the default) reads from the start
+    pub after_seq: Option<u64>,
+    /// Defaults to 100, at most 1000
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventsResponse {
+    pub events: Vec<LoggedEvent>,
+    /// Pass as `after_seq` to read on; equals the request's `after_seq` when nothing is new
+    pub next_seq: u64,
+}
+
+/// Tail the append-only event log (GET /api/v1/events?after_seq=…)
+pub async fn list_events(
+    State(app_state): State<AppState>,
+    Query(query): Query<EventsQuery>,
+) -> Result<Json<EventsResponse>, StatusCode> {
+    let after_seq = query.after_seq.unwrap_or(0);
+    let events = event_log::read(&app_state.db, after_seq, query.limit.unwrap_or(100).clamp(1, 1000))
+        .await
+        .map_err(|e| {
+            error!("Database error reading the event log: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let next_seq = events.last().map_or(after_seq, |event| event.seq);
+    Ok(Json(EventsResponse { events, next_seq }))
+}
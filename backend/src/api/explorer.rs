@@ -0,0 +1,213 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::Response,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use tracing::{info, error, warn};
+
+use super::{error::ApiError, market::client_key, AppState};
+use crate::models::{AccountState, TokenBalance};
+use crate::services::{archival::BatchSnapshot, manifests::{BatchManifest, ManifestRecord}};
+use vapor_chain::signer::SignatureVerification;
+
+/// Leaves serialized into each chunk of the response body
+const LEAVES_PER_CHUNK: usize = 256;
+
+#[derive(Debug, Deserialize)]
+pub struct LeavesQuery {
+    /// Only leaves that differ from this earlier batch's state
+    pub since: Option<u64>,
+}
+
+/// One NDJSON line of a state export
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LeafLine {
+    Leaf {
+        address: String,
+        balances: Vec<TokenBalance>,
+        leaf_hash: String,
+    },
+    /// In the `since` batch's state but not in this one
+    Removed { address: String, removed: bool },
+}
+
+/// Stream the state tree leaves of a batch as NDJSON (GET /explorer/state/:batch_id/leaves)
+///
+/// Read from the batch's persisted snapshot, one line per account in address order. With
+/// `since`, only accounts whose leaf changed after that batch are listed, plus removed
+/// accounts. The batch's state root is returned in the `x-state-root` header.
+pub async fn get_state_leaves(
+    Path(batch_id): Path<u64>,
+    Query(query): Query<LeavesQuery>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> Result<Response, ApiError> {
+    let client = client_key(&headers, connect_info.as_ref().map(|info| info.0));
+    info!("Exporting state leaves of batch {} (since {:?}) for {}", batch_id, query.since, client);
+
+    app_state.explorer_limiter.check(&client)?;
+
+    if let Some(since) = query.since {
+        if since >= batch_id {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "invalid_since",
+                format!("since ({}) must be an earlier batch than {}", since, batch_id),
+            ));
+        }
+    }
+
+    let snapshot = load_snapshot(&app_state, batch_id).await?;
+    let previous = match query.since {
+        Some(since) => Some(load_snapshot(&app_state, since).await?),
+        None => None,
+    };
+
+    let lines = leaf_lines(&snapshot.accounts, previous.as_ref().map(|previous| previous.accounts.as_slice()));
+    info!("Streaming {} state leaves of batch {}", lines.len(), batch_id);
+
+    let chunks: Vec<Result<Bytes, std::io::Error>> = lines
+        .chunks(LEAVES_PER_CHUNK)
+        .map(|chunk| {
+            let mut body = Vec::new();
+            for line in chunk {
+                serde_json::to_writer(&mut body, line)?;
+                body.push(b'\n');
+            }
+            Ok(Bytes::from(body))
+        })
+        .collect();
+
+    let mut response = Response::new(Body::from_stream(futures::stream::iter(chunks)));
+    let response_headers = response.headers_mut();
+    response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/x-ndjson"));
+    response_headers.insert("x-batch-id", HeaderValue::from(batch_id));
+    if let Ok(state_root) = HeaderValue::from_str(&snapshot.state_root) {
+        response_headers.insert("x-state-root", state_root);
+    }
+    Ok(response)
+}
+
+/// Get the signed manifest of a submitted batch (GET /explorer/batches/:batch_id/manifest)
+///
+/// Verifiers recompute `digest` from the other fields and recover the operator's address from
+/// `signature`; see `BatchManifest::recover_signer`.
+pub async fn get_batch_manifest(
+    Path(batch_id): Path<u64>,
+    State(app_state): State<AppState>,
+) -> Result<Json<ManifestRecord>, ApiError> {
+    info!("Getting manifest of batch {}", batch_id);
+
+    let manifest = app_state.manifests.get(batch_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching manifest of batch {}: {}", batch_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| {
+            ApiError::new(StatusCode::NOT_FOUND, "manifest_not_found", format!("No manifest published for batch {}", batch_id))
+        })?;
+
+    Ok(Json(manifest))
+}
+
+/// Check a manifest against the operator key, e.g. one mirrored from IPFS (POST /explorer/manifests/verify)
+pub async fn verify_manifest(
+    State(app_state): State<AppState>,
+    Json(manifest): Json<BatchManifest>,
+) -> Result<Json<SignatureVerification>, ApiError> {
+    info!("Verifying manifest of batch {}", manifest.batch_id);
+
+    app_state.manifests.verify(&manifest).map(Json).ok_or_else(|| {
+        ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "manifests_disabled", "No operator key is configured to sign manifests")
+    })
+}
+
+async fn load_snapshot(app_state: &AppState, batch_id: u64) -> Result<BatchSnapshot, ApiError> {
+    app_state.archive.load_snapshot(batch_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load state snapshot for batch {}: {}", batch_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| {
+            warn!("No state snapshot for batch {}", batch_id);
+            ApiError::new(StatusCode::NOT_FOUND, "snapshot_not_found", format!("No state snapshot for batch {}", batch_id))
+        })
+}
+
+/// Export lines for `accounts`, or only what changed since `previous`, sorted by address
+fn leaf_lines(accounts: &[AccountState], previous: Option<&[AccountState]>) -> Vec<LeafLine> {
+    // The state tree's leaf hash, not the legacy AccountState::hash_leaf
+    let leaf_hash = |account: &AccountState| hex::encode(Keccak256::digest(account.leaf_preimage()));
+    let previous_hashes: Option<HashMap<String, String>> = previous.map(|previous| {
+        previous.iter().map(|account| (account.address.to_lowercase(), leaf_hash(account))).collect()
+    });
+
+    let mut lines: Vec<(String, LeafLine)> = accounts.iter()
+        .filter_map(|account| {
+            let hash = leaf_hash(account);
+            let unchanged = previous_hashes.as_ref()
+                .and_then(|hashes| hashes.get(&account.address.to_lowercase()))
+                .is_some_and(|previous| *previous == hash);
+            (!unchanged).then(|| {
+                (account.address.to_lowercase(), LeafLine::Leaf {
+                    address: account.address.clone(),
+                    balances: account.balances.clone(),
+                    leaf_hash: hash,
+                })
+            })
+        })
+        .collect();
+
+    if let Some(previous) = previous {
+        let current: HashSet<String> = accounts.iter().map(|account| account.address.to_lowercase()).collect();
+        lines.extend(previous.iter()
+            .filter(|account| !current.contains(&account.address.to_lowercase()))
+            .map(|account| (account.address.to_lowercase(), LeafLine::Removed { address: account.address.clone(), removed: true })));
+    }
+
+    lines.sort_by(|a, b| a.0.cmp(&b.0));
+    lines.into_iter().map(|(_, line)| line).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn account(address: &str, balance: &str) -> AccountState {
+        AccountState {
+            address: address.to_string(),
+            balances: vec![TokenBalance { token_id: 1, balance: balance.to_string() }],
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_changed_leaves_since_an_earlier_state() {
+        let before = vec![account("0xbbb", "10"), account("0xaaa", "5"), account("0xddd", "1")];
+        let after = vec![account("0xccc", "7"), account("0xaaa", "5"), account("0xBBB", "12")];
+
+        let addresses = |lines: &[LeafLine]| -> Vec<String> {
+            lines.iter().map(|line| serde_json::to_value(line).unwrap()["address"].as_str().unwrap().to_string()).collect()
+        };
+        assert_eq!(addresses(&leaf_lines(&after, None)), vec!["0xaaa", "0xBBB", "0xccc"]);
+
+        // The unchanged account is left out and the dropped one is marked removed
+        let changed = leaf_lines(&after, Some(&before));
+        assert_eq!(addresses(&changed), vec!["0xBBB", "0xccc", "0xddd"]);
+        assert!(matches!(changed[2], LeafLine::Removed { removed: true, .. }));
+        let LeafLine::Leaf { leaf_hash, .. } = &changed[0] else { panic!("expected a leaf") };
+        let tree_leaf = vapor_core::SparseMerkleLeaf::hash_leaf(&after[2], "0xBBB").unwrap();
+        assert_eq!(*leaf_hash, hex::encode(tree_leaf));
+    }
+}
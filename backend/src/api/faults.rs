@@ -0,0 +1,59 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use super::{admin::require_admin, error::ApiError, AppState};
+use vapor_core::fault_injection::{self, FaultConfig};
+
+/// Get the active fault injection config and injected fault counts
+pub async fn get_faults() -> Result<Json<Value>, StatusCode> {
+    info!("Getting fault injection config");
+
+    let injector = fault_injection::global();
+    Ok(Json(json!({
+        "config": injector.config(),
+        "stats": injector.stats(),
+    })))
+}
+
+/// Replace the fault injection config (PUT /admin/faults)
+pub async fn set_faults(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(config): Json<FaultConfig>,
+) -> Result<Json<Value>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Updating fault injection config: {:?}", config);
+
+    if let Err(e) = config.validate() {
+        warn!("Invalid fault injection config: {}", e);
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+
+    fault_injection::global().configure(config.clone());
+
+    Ok(Json(json!({
+        "status": "success",
+        "config": config,
+    })))
+}
+
+/// Disable fault injection and clear counters (DELETE /admin/faults)
+pub async fn reset_faults(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    info!("Resetting fault injection");
+
+    fault_injection::global().reset();
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": "Fault injection disabled"
+    })))
+}
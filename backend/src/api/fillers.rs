@@ -1,17 +1,43 @@
 use axum::{
-    extract::{Path, State, Query},
-    http::StatusCode,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State, Query,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
-use tracing::{info, warn, error};
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{info, warn, error, debug, instrument};
 use sqlx::Row;
+use web3::types::U256;
 
-use super::AppState;
+use super::{error::ApiError, AppState};
+use crate::services::{
+    claim_fees::{self, ClaimFee},
+    claim_relay::{self, RelayAccounting, RelayQuote, RelaySettings},
+    claims,
+    collateral::CollateralStatus,
+    event_bus::{FillerSubscription, OrderEvent},
+    event_log::{self, DomainEvent},
+    filler_capabilities::{self, FillerCapabilities},
+    matching_engine::{check_filler_limits, MatchingEngine},
+    payment_proofs::{BankServiceSchema, FieldDiagnostic, PaymentProof, PaymentProofError, PaymentRail},
+    query_metrics::QueryTimer,
+    rates::format_rate,
+    scheduler::JobKind,
+    screening::ScreeningContext,
+    settlement_saga::SagaError,
+};
+use crate::config::parse_usd_price;
 use crate::models::{
     Order, OrderResponse, OrderType, OrderStatus, 
     LockOrderRequest, SubmitPaymentProofRequest,
-    FillerBalance, ClaimRequest, ClaimResponse, ProcessedClaim, WalletClaim,
+    FillerBalance, ClaimRequest, ClaimResponse, ClaimRecord, ProcessedClaim, WalletClaim,
+    ClaimPreview, ClaimPreviewResponse, TokenConversion,
+    page_size, paginate, Cursor,
 };
 // TODO: Fix database helpers import issue
 // use crate::database::helpers::{get_filler_balance, upsert_filler_balance, add_filler_wallet, insert_claim};
@@ -19,73 +45,245 @@ use crate::models::{
 #[derive(Debug, Deserialize)]
 pub struct FillerQuery {
     pub status: Option<String>,
+    /// Page size, 20 by default and at most 100
     pub limit: Option<usize>,
+    /// `next_cursor` of the previous page
+    pub after: Option<String>,
+    /// Only orders this filler's registered capabilities accept
+    pub filler_id: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct DiscoveryOrdersResponse {
     pub orders: Vec<OrderResponse>,
     pub total: usize,
+    /// Pass as `after` to fetch the next page; unset on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 /// Get orders in discovery phase for fillers (GET /fillers/discovery)
 pub async fn get_discovery_orders(
     Query(query): Query<FillerQuery>,
     State(app_state): State<AppState>,
-) -> Result<Json<DiscoveryOrdersResponse>, StatusCode> {
+) -> Result<Json<DiscoveryOrdersResponse>, ApiError> {
     info!("Getting discovery orders for fillers");
 
-    let mut sql_query = "SELECT * FROM orders WHERE status = $1".to_string();
-    let mut params = vec![OrderStatus::Discovery as i32];
-    
-    if let Some(limit) = query.limit {
-        sql_query.push_str(&format!(" LIMIT {}", limit.min(100))); // Cap at 100
-    } else {
-        sql_query.push_str(" LIMIT 20"); // Default limit
-    }
+    let mut after = query.after.as_deref().map(Cursor::decode).transpose()?;
+    let limit = page_size(query.limit, 20);
 
-    let rows = sqlx::query(&sql_query)
-        .bind(params[0])
-        .fetch_all(&app_state.db)
-        .await
-        .map_err(|e| {
-            error!("Database error fetching discovery orders: {}", e);
+    let capabilities = match &query.filler_id {
+        Some(filler_id) => filler_capabilities::load(&app_state.db, filler_id).await.map_err(|e| {
+            error!("Database error fetching capabilities of filler {}: {}", filler_id, e);
             StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        })?,
+        None => None,
+    };
 
-    let orders: Vec<OrderResponse> = rows.iter()
-        .map(|row| OrderResponse {
-            id: row.try_get("id").unwrap_or_default(),
-            order_type: OrderType::from(row.try_get::<i32, _>("order_type").unwrap_or(0)),
-            status: OrderStatus::from(row.try_get::<i32, _>("status").unwrap_or(0)),
-            amount: row.try_get("amount").unwrap_or_default(),
-            bank_account: row.try_get("bank_account").ok(),
-            bank_service: row.try_get("bank_service").ok(),
-            filler_id: row.try_get("filler_id").ok(),
-            locked_amount: row.try_get("locked_amount").ok(),
-            created_at: row.try_get("created_at").unwrap_or_default(),
-        })
-        .collect();
+    // Oldest first, in creation order; one extra row tells whether there is a next page.
+    // Orders outside the filler's capabilities are skipped, reading on until the page is full
+    let mut orders = Vec::new();
+    loop {
+        let sql_query = format!(
+            "SELECT * FROM orders WHERE status = ?1{} ORDER BY created_at, id LIMIT {}",
+            if after.is_some() { " AND (created_at, id) > (?2, ?3)" } else { "" },
+            limit + 1
+        );
+
+        let _timer = QueryTimer::start("list_discovery_orders");
+        let mut rows_query = sqlx::query(&sql_query).bind(OrderStatus::Discovery as i32);
+        if let Some(after) = &after {
+            rows_query = rows_query.bind(after.created_at).bind(&after.id);
+        }
+        let rows = rows_query
+            .fetch_all(&app_state.db)
+            .await
+            .map_err(|e| {
+                error!("Database error fetching discovery orders: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        let exhausted = rows.len() <= limit;
+
+        for row in &rows {
+            let order = OrderResponse {
+                id: row.try_get("id").unwrap_or_default(),
+                order_type: OrderType::from(row.try_get::<i32, _>("order_type").unwrap_or(0)),
+                status: OrderStatus::from(row.try_get::<i32, _>("status").unwrap_or(0)),
+                amount: row.try_get("amount").unwrap_or_default(),
+                bank_account: row.try_get("bank_account").ok(),
+                bank_service: row.try_get("bank_service").ok(),
+                filler_id: row.try_get("filler_id").ok(),
+                locked_amount: row.try_get("locked_amount").ok(),
+                created_at: row.try_get("created_at").unwrap_or_default(),
+                deposit_reference: None,
+                payment_proof: None,
+                quoted_rate: row.try_get::<Option<i64>, _>("quoted_usd_price").ok().flatten()
+                    .map(|price| format_rate(price as u64)),
+                duplicate_warning: None,
+                settlement_cost: None,
+            };
+            after = Some(Cursor::new(order.created_at, &order.id));
+
+            let accepted = match (&capabilities, &query.filler_id) {
+                (Some(capabilities), Some(filler_id)) => {
+                    let token_id = row.try_get::<i64, _>("token_id").unwrap_or_default() as u32;
+                    order.amount.parse().is_ok_and(|amount| {
+                        capabilities.check_order(filler_id, token_id, amount, order.bank_service.as_deref()).is_ok()
+                    })
+                }
+                _ => true,
+            };
+            if accepted {
+                orders.push(order);
+            }
+        }
+
+        if exhausted || orders.len() > limit {
+            break;
+        }
+    }
 
+    let (orders, next_cursor) = paginate(orders, limit, |order| Cursor::new(order.created_at, &order.id));
     let total = orders.len();
     
     info!("Found {} orders in discovery phase", total);
-    Ok(Json(DiscoveryOrdersResponse { orders, total }))
+    Ok(Json(DiscoveryOrdersResponse { orders, total, next_cursor }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FillerFeedQuery {
+    /// Feed token, for clients that cannot set an Authorization header
+    pub token: Option<String>,
+    pub bank_service: Option<String>,
+    pub max_amount: Option<u64>,
+}
+
+/// Real-time feed of discovery and lock-expiry events (GET /fillers/ws)
+pub async fn filler_feed(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    Query(query): Query<FillerFeedQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Response, StatusCode> {
+    info!("Filler websocket feed requested");
+
+    let token = bearer_token(&headers).or(query.token.as_deref());
+    let Some(filler_id) = token.and_then(|token| app_state.config.filler.filler_for_token(token)) else {
+        warn!("Rejected filler websocket connection with missing or unknown token");
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    let subscription = FillerSubscription {
+        filler_id: filler_id.to_string(),
+        bank_service: query.bank_service,
+        max_amount: query.max_amount,
+    };
+
+    // Subscribe before upgrading so no events are missed during the handshake
+    let receiver = app_state.event_bus.subscribe();
+    let matching_engine = app_state.matching_engine.clone();
+
+    Ok(ws.on_upgrade(move |socket| run_filler_feed(socket, receiver, subscription, matching_engine)))
+}
+
+pub(super) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+async fn run_filler_feed(
+    mut socket: WebSocket,
+    mut receiver: broadcast::Receiver<OrderEvent>,
+    subscription: FillerSubscription,
+    matching_engine: Arc<Mutex<MatchingEngine>>,
+) {
+    info!("Filler {} connected to websocket feed", subscription.filler_id);
+
+    let hello = serde_json::json!({
+        "type": "subscribed",
+        "filler_id": subscription.filler_id,
+    });
+    if socket.send(Message::Text(hello.to_string())).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            event = receiver.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Filler {} feed lagged, skipped {} events", subscription.filler_id, skipped);
+                        // Tell the filler to re-sync from GET /fillers/discovery
+                        let lagged = serde_json::json!({ "type": "lagged", "skipped": skipped });
+                        if socket.send(Message::Text(lagged.to_string())).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                // Registered fillers only see orders within their remaining capacity
+                let capacity = {
+                    let engine = matching_engine.lock().await;
+                    match engine.fillers.get(&subscription.filler_id) {
+                        Some(filler) if filler.is_active => Some(filler.capacity_usd),
+                        Some(_) => Some(0),
+                        None => None,
+                    }
+                };
+
+                if !subscription.accepts(&event, capacity) {
+                    continue;
+                }
+
+                let payload = match serde_json::to_string(&event) {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        error!("Failed to serialize order event: {}", e);
+                        continue;
+                    }
+                };
+
+                if socket.send(Message::Text(payload)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => debug!("Ignoring inbound message from filler {}", subscription.filler_id),
+                }
+            }
+        }
+    }
+
+    info!("Filler {} disconnected from websocket feed", subscription.filler_id);
 }
 
 /// Lock an order for filling (POST /fillers/orders/:id/lock)
+///
+/// If the token's rate moved past the slippage tolerance since the order was quoted, the lock
+/// is refused with 409 `requote_required`; locking again with `accepted_rate` set to the
+/// re-quoted rate locks the order and records that rate as its quote.
+///
+/// With a lock window configured the attempt is checked the same way but only recorded as a
+/// bid, answered with 202; when the window closes the winning bid locks the order.
+#[instrument(skip_all, fields(order_id = %order_id, filler_id = %req.filler_id))]
 pub async fn lock_order(
     Path(order_id): Path<String>,
     State(app_state): State<AppState>,
     Json(req): Json<LockOrderRequest>,
-) -> Result<Json<OrderResponse>, StatusCode> {
+) -> Result<Response, ApiError> {
     info!("Locking order {} for filler {}", order_id, req.filler_id);
 
     // Verify order exists and is in discovery phase
-    let order_query = "SELECT id, order_type, status, from_address, to_address, token_id, amount, bank_account, bank_service, banking_hash, filler_id, locked_amount, batch_id, created_at, updated_at FROM orders WHERE id = $1 AND status = $2";
+    let order_query = "SELECT id, order_type, status, from_address, to_address, token_id, amount, bank_account, bank_service, banking_hash, filler_id, locked_amount, batch_id, quoted_usd_price, created_at, updated_at FROM orders WHERE id = $1";
     let row = sqlx::query(order_query)
         .bind(&order_id)
-        .bind(OrderStatus::Discovery as i32)
         .fetch_optional(&app_state.db)
         .await
         .map_err(|e| {
@@ -94,9 +292,15 @@ pub async fn lock_order(
         })?;
 
     let Some(row) = row else {
-        warn!("Order not found or not available for locking: {}", order_id);
-        return Err(StatusCode::NOT_FOUND);
+        warn!("Order not found: {}", order_id);
+        return Err(StatusCode::NOT_FOUND.into());
     };
+    let status = OrderStatus::from(row.try_get::<i32, _>("status").unwrap_or_default());
+    let holder: Option<String> = row.try_get("filler_id").unwrap_or(None);
+    if status != OrderStatus::Discovery || holder.is_some() {
+        warn!("Order {} is not available for locking ({:?})", order_id, status);
+        return Err(order_not_lockable(&order_id, status));
+    }
 
     // Parse order amount to validate lock amount
     let order_amount: u64 = row.try_get::<String, _>("amount")
@@ -115,23 +319,153 @@ pub async fn lock_order(
 
     if lock_amount > order_amount {
         warn!("Lock amount {} exceeds order amount {}", lock_amount, order_amount);
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into());
     }
 
-    // Update order to locked status
+    // The rate may have moved since the order was quoted; beyond the slippage tolerance the
+    // filler has to accept the current rate rather than lock at a stale one
+    let quoted_usd_price = row.try_get::<Option<i64>, _>("quoted_usd_price").ok().flatten().map(|price| price as u64);
+    let accepted_rate = match req.accepted_rate.as_deref() {
+        Some(rate) => Some(parse_usd_price(rate).ok_or_else(|| {
+            ApiError::new(StatusCode::BAD_REQUEST, "invalid_rate", format!("Invalid accepted_rate '{}'", rate))
+        })?),
+        None => None,
+    };
+    let requoted = match quoted_usd_price {
+        Some(quoted) => {
+            let token_id = row.try_get::<i64, _>("token_id").unwrap_or_default() as u32;
+            app_state.rates.check_slippage(token_id, quoted, accepted_rate).inspect_err(|e| {
+                warn!("Rejecting lock on order {}: {}", order_id, e);
+            })?
+        }
+        None => None,
+    };
+
+    // Enforce the filler's lock limits against the locks it currently holds
+    let limits = app_state.config.filler.limits_for(&req.filler_id);
+    let (open_locks, locked_value) = crate::database::helpers::get_filler_lock_usage(&app_state.db, &req.filler_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching filler lock usage: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if let Err(e) = check_filler_limits(&req.filler_id, &limits, open_locks, locked_value, lock_amount) {
+        warn!("Rejecting lock on order {}: {}", order_id, e);
+        return Err(e.into());
+    }
+
+    // Registered fillers only lock orders their capabilities cover, within their operating hours
+    let capabilities = filler_capabilities::load(&app_state.db, &req.filler_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching filler capabilities: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if let Some(capabilities) = capabilities {
+        let token_id = row.try_get::<i64, _>("token_id").unwrap_or_default() as u32;
+        let bank_service: Option<String> = row.try_get("bank_service").unwrap_or(None);
+        capabilities.check_order(&req.filler_id, token_id, order_amount, bank_service.as_deref())
+            .and_then(|_| capabilities.check_hours(&req.filler_id, app_state.clock.now()))
+            .inspect_err(|e| warn!("Rejecting lock on order {}: {}", order_id, e))?;
+    }
+
+    // Fillers paying out from sanctioned wallets cannot take orders
+    app_state.screening.screen_lock(&order_id, &req.filler_id)
+        .await
+        .inspect_err(|e| warn!("Rejecting lock on order {}: {}", order_id, e))?;
+
+    // Larger locks need bridge-held collateral covering the filler's open locks
+    let token_id = row.try_get::<i64, _>("token_id").unwrap_or_default() as u32;
+    let bank_service: Option<String> = row.try_get("bank_service").unwrap_or(None);
+    app_state.collateral
+        .check_lock(&req.filler_id, app_state.blockchain_client.as_deref(), token_id, bank_service.as_deref(), lock_amount)
+        .await
+        .inspect_err(|e| warn!("Rejecting lock on order {}: {}", order_id, e))?;
+
+    if app_state.lock_window.is_enabled() {
+        let (bid, opened) = app_state.lock_window
+            .bid(&order_id, &req.filler_id, &req.amount, req.fee_bps, requoted)
+            .await
+            .map_err(|e| {
+                error!("Database error recording lock bid: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        if opened {
+            let delay = bid.window_closes_at - app_state.clock.now();
+            if let Err(e) = app_state.scheduler.schedule(JobKind::CloseLockWindow, &order_id, None, delay).await {
+                error!("Failed to schedule the lock window close for order {}: {}", order_id, e);
+            }
+        }
+        info!("Filler {} bid on order {} until {}", req.filler_id, order_id, bid.window_closes_at);
+        return Ok((StatusCode::ACCEPTED, Json(bid)).into_response());
+    }
+
+    let locked_order = commit_lock(&app_state, &order_id, &req.filler_id, &req.amount, requoted).await?;
+    let mut order_response = OrderResponse::from(&locked_order);
+    order_response.quoted_rate = requoted.or(quoted_usd_price).map(format_rate);
+
+    info!("Order {} successfully locked for filler {}", order_id, req.filler_id);
+    Ok(Json(order_response).into_response())
+}
+
+/// Lock an order in discovery for a filler that passed the lock checks, then log the lock,
+/// start its settlement saga and schedule its expiry
+///
+/// 409 `order_not_lockable` if another lock got there first, plain 409 if one of the
+/// filler's own concurrent locks took its limit.
+pub(super) async fn commit_lock(
+    app_state: &AppState,
+    order_id: &str,
+    filler_id: &str,
+    amount: &str,
+    requoted: Option<u64>,
+) -> Result<Order, ApiError> {
+    let limits = app_state.config.filler.limits_for(filler_id);
+    let lock_amount: u64 = amount.parse().map_err(|_| {
+        error!("Invalid lock amount format");
+        StatusCode::BAD_REQUEST
+    })?;
+
+    // The locked value is summed here as U256, as SQLite's integer SUM overflows past i64. The
+    // update below only goes ahead if the filler still holds exactly these locks.
+    let held = crate::database::helpers::get_filler_locks(&app_state.db, filler_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching filler locks: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let locked_value = crate::database::helpers::sum_locked_amounts(held.iter().map(|(_, amount)| amount.as_str()));
+    if limits.max_locked_value > 0 && locked_value.saturating_add(U256::from(lock_amount)) > U256::from(limits.max_locked_value) {
+        warn!("Order {} would take filler {} past its locked value limit", order_id, filler_id);
+        return Err(StatusCode::CONFLICT.into());
+    }
+    let held_ids = held.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>().join(",");
+
+    // Compare-and-set the order to locked: of concurrent lock attempts only the one that still
+    // finds it unclaimed in discovery wins. The limit conditions are re-checked here so
+    // concurrent locks by the same filler cannot both slip under a limit.
     let update_query = r#"
         UPDATE orders 
-        SET status = $1, filler_id = $2, locked_amount = $3, updated_at = $4
-        WHERE id = $5 AND status = $6
+        SET status = ?1, filler_id = ?2, locked_amount = ?3, updated_at = ?4,
+            quoted_usd_price = COALESCE(?10, quoted_usd_price),
+            rate_quoted_at = CASE WHEN ?10 IS NULL THEN rate_quoted_at ELSE ?4 END
+        WHERE id = ?5 AND status = ?6 AND filler_id IS NULL
+          AND (?7 = 0 OR (SELECT COUNT(*) FROM orders WHERE filler_id = ?2 AND status = ?1) < ?7)
+          AND (?8 = 0 OR (SELECT COALESCE(GROUP_CONCAT(id, ','), '') FROM (SELECT id FROM orders WHERE filler_id = ?2 AND status = ?1 ORDER BY id)) = ?9)
     "#;
     
     let result = sqlx::query(update_query)
         .bind(OrderStatus::Locked as i32)
-        .bind(&req.filler_id)
-        .bind(&req.amount)
-        .bind(chrono::Utc::now())
-        .bind(&order_id)
+        .bind(filler_id)
+        .bind(amount)
+        .bind(app_state.clock.now())
+        .bind(order_id)
         .bind(OrderStatus::Discovery as i32) // Ensure it's still in discovery
+        .bind(limits.max_concurrent_locks as i64)
+        .bind(i64::try_from(limits.max_locked_value).unwrap_or(i64::MAX))
+        .bind(&held_ids)
+        .bind(requoted.map(|price| price as i64))
         .execute(&app_state.db)
         .await
         .map_err(|e| {
@@ -140,14 +474,28 @@ pub async fn lock_order(
         })?;
 
 
-
     if result.rows_affected() == 0 {
-        warn!("Order {} was already locked or changed status", order_id);
-        return Err(StatusCode::CONFLICT);
+        // Either another lock won the race or one of the filler's own locks took its limit
+        let current = crate::database::helpers::get_order_by_id(&app_state.db, order_id)
+            .await
+            .map_err(|e| {
+                error!("Database error re-reading order: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        return Err(match current {
+            Some(order) if order.status != OrderStatus::Discovery || order.filler_id.is_some() => {
+                warn!("Lock on order {} by filler {} lost the race", order_id, filler_id);
+                order_not_lockable(order_id, order.status)
+            }
+            _ => {
+                warn!("Order {} hit a concurrent lock limit for filler {}", order_id, filler_id);
+                StatusCode::CONFLICT.into()
+            }
+        });
     }
 
     // Fetch updated order using the database helper
-    let updated_order = crate::database::helpers::get_order_by_id(&app_state.db, &order_id)
+    let updated_order = crate::database::helpers::get_order_by_id(&app_state.db, order_id)
         .await
         .map_err(|e| {
             error!("Database error fetching updated order: {}", e);
@@ -158,31 +506,91 @@ pub async fn lock_order(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    let order_response = OrderResponse::from(&updated_order);
+    let locked = DomainEvent::OrderLocked {
+        order_id: order_id.to_string(),
+        filler_id: filler_id.to_string(),
+        locked_amount: amount.to_string(),
+    };
+    if let Err(e) = event_log::append(&app_state.db, &locked).await {
+        error!("Failed to log lock of order {}: {}", order_id, e);
+    }
+    app_state.matching_engine.lock().await.record_lock(order_id, filler_id);
+    if let Err(e) = app_state.settlement_saga.begin(&updated_order).await {
+        error!("Failed to start settlement saga for order {}: {}", order_id, e);
+    }
+    if app_state.config.filler.lock_ttl_seconds > 0 {
+        let ttl = chrono::Duration::seconds(app_state.config.filler.lock_ttl_seconds as i64);
+        if let Err(e) = app_state.scheduler.schedule(JobKind::ExpireLock, order_id, None, ttl).await {
+            error!("Failed to schedule lock expiry for order {}: {}", order_id, e);
+        }
+    }
 
-    info!("Order {} successfully locked for filler {}", order_id, req.filler_id);
-    Ok(Json(order_response))
+    Ok(updated_order)
+}
+
+/// 409 for a lock on an order that is no longer unclaimed in discovery
+fn order_not_lockable(order_id: &str, status: OrderStatus) -> ApiError {
+    ApiError::new(StatusCode::CONFLICT, "order_not_lockable", format!("Order {} is {:?} and cannot be locked", order_id, status))
+        .with_details(serde_json::json!({ "order_id": order_id, "status": status }))
 }
 
 /// Submit payment proof (POST /fillers/orders/:id/payment-proof)
+#[instrument(skip_all, fields(order_id = %order_id))]
 pub async fn submit_payment_proof(
     Path(order_id): Path<String>,
     State(app_state): State<AppState>,
     Json(req): Json<SubmitPaymentProofRequest>,
-) -> Result<Json<OrderResponse>, StatusCode> {
+) -> Result<Json<OrderResponse>, ApiError> {
     info!("Submitting payment proof for order {}", order_id);
 
+    let bank_service: Option<String> = sqlx::query("SELECT bank_service FROM orders WHERE id = $1")
+        .bind(&order_id)
+        .fetch_optional(&app_state.db)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching order: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| {
+            warn!("Order {} not found", order_id);
+            StatusCode::NOT_FOUND
+        })?
+        .try_get("bank_service")
+        .unwrap_or(None);
+
+    // Structured proofs must match the schema of the order's rail
+    let payment_proof = match req.payment_proof {
+        Some(payload) => {
+            let rail = bank_service.as_deref()
+                .and_then(PaymentRail::for_bank_service)
+                .ok_or_else(|| PaymentProofError::UnsupportedService(bank_service.clone().unwrap_or_default()))?;
+            Some(rail.parse_proof(payload)?)
+        }
+        None => None,
+    };
+    let banking_hash = req.banking_hash
+        .or_else(|| payment_proof.as_ref().map(PaymentProof::digest))
+        .ok_or(PaymentProofError::Missing)?;
+    let stored_proof = payment_proof.as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| {
+            error!("Failed to serialize payment proof: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
     // Update order with payment proof
     let update_query = r#"
         UPDATE orders 
-        SET status = $1, banking_hash = $2, updated_at = $3
-        WHERE id = $4 AND status = $5
+        SET status = $1, banking_hash = $2, payment_proof = $3, updated_at = $4
+        WHERE id = $5 AND status = $6
     "#;
     
     let result = sqlx::query(update_query)
         .bind(OrderStatus::MarkPaid as i32)
-        .bind(&req.banking_hash)
-        .bind(chrono::Utc::now())
+        .bind(&banking_hash)
+        .bind(&stored_proof)
+        .bind(app_state.clock.now())
         .bind(&order_id)
         .bind(OrderStatus::Locked as i32) // Must be locked to submit proof
         .execute(&app_state.db)
@@ -193,8 +601,28 @@ pub async fn submit_payment_proof(
         })?;
 
     if result.rows_affected() == 0 {
-        warn!("Order {} not found or not in locked status", order_id);
-        return Err(StatusCode::NOT_FOUND);
+        warn!("Order {} not in locked status", order_id);
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+
+    let paid = DomainEvent::OrderPaid { order_id: order_id.clone(), banking_hash: Some(banking_hash.clone()) };
+    if let Err(e) = event_log::append(&app_state.db, &paid).await {
+        error!("Failed to log payment of order {}: {}", order_id, e);
+    }
+    app_state.matching_engine.lock().await.clear_lock(&order_id);
+
+    // Orders locked before sagas were tracked have none to advance
+    match app_state.settlement_saga.record_payment(&order_id, None).await {
+        Ok(()) | Err(SagaError::NotFound(_)) => {}
+        Err(e) => error!("Failed to record payment in settlement saga for order {}: {}", order_id, e),
+    }
+
+    // The escrow transfer is created once the payment provider has verified the proof
+    if let Err(e) = app_state.scheduler.cancel(JobKind::ExpireLock, &order_id).await {
+        error!("Failed to cancel lock expiry for order {}: {}", order_id, e);
+    }
+    if let Err(e) = app_state.scheduler.schedule(JobKind::VerifyPayment, &order_id, None, chrono::Duration::zero()).await {
+        error!("Failed to schedule payment verification for order {}: {}", order_id, e);
     }
 
     // Fetch updated order
@@ -216,6 +644,11 @@ pub async fn submit_payment_proof(
         bank_service: updated_row.try_get("bank_service").ok(),
         filler_id: updated_row.try_get("filler_id").ok(),
         locked_amount: updated_row.try_get("locked_amount").ok(),
+        deposit_reference: None,
+        payment_proof,
+        quoted_rate: None,
+        duplicate_warning: None,
+        settlement_cost: None,
         created_at: updated_row.try_get("created_at").unwrap_or_default(),
     };
 
@@ -223,6 +656,212 @@ pub async fn submit_payment_proof(
     Ok(Json(order_response))
 }
 
+/// Payment proof to check before submitting it; the bank service is the order's when `order_id` is given
+#[derive(Debug, Deserialize)]
+pub struct ValidatePaymentProofRequest {
+    pub order_id: Option<String>,
+    pub bank_service: Option<String>,
+    #[serde(default)]
+    pub banking_hash: Option<String>,
+    #[serde(default)]
+    pub payment_proof: Option<serde_json::Value>,
+    /// Amount paid, checked against the order's locked amount
+    pub amount: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentProofValidation {
+    /// Whether submitting the proof now would be accepted
+    pub valid: bool,
+    pub rail: Option<PaymentRail>,
+    /// Banking hash the submission would record
+    pub banking_hash: Option<String>,
+    pub diagnostics: Vec<FieldDiagnostic>,
+}
+
+/// Check a payment proof the way submitting it would, without touching the order
+/// (POST /fillers/payment-proof/validate)
+pub async fn validate_payment_proof(
+    State(app_state): State<AppState>,
+    Json(req): Json<ValidatePaymentProofRequest>,
+) -> Result<Json<PaymentProofValidation>, ApiError> {
+    info!("Validating payment proof for order {:?}", req.order_id);
+
+    let mut diagnostics = Vec::new();
+    let mut bank_service = req.bank_service.clone();
+    let mut locked_amount: Option<String> = None;
+    if let Some(order_id) = &req.order_id {
+        let row = sqlx::query("SELECT status, bank_service, locked_amount FROM orders WHERE id = $1")
+            .bind(order_id)
+            .fetch_optional(&app_state.db)
+            .await
+            .map_err(|e| {
+                error!("Database error fetching order: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "order_not_found", format!("Order {} not found", order_id)))?;
+        let status = OrderStatus::from(row.try_get::<i32, _>("status").unwrap_or_default());
+        diagnostics.push(match status {
+            OrderStatus::Locked => FieldDiagnostic::valid("order_id"),
+            status => FieldDiagnostic::invalid("order_id", format!("Order is {:?}, proofs are only accepted for locked orders", status)),
+        });
+        bank_service = row.try_get("bank_service").unwrap_or(None);
+        locked_amount = row.try_get("locked_amount").unwrap_or(None);
+    }
+
+    let rail = bank_service.as_deref().and_then(PaymentRail::for_bank_service);
+    let mut banking_hash = req.banking_hash.clone();
+    if let Some(payload) = &req.payment_proof {
+        match rail {
+            Some(rail) => {
+                let fields = rail.diagnose(payload);
+                if fields.iter().all(|field| field.valid) {
+                    let proof = rail.parse_proof(payload.clone())?;
+                    banking_hash = banking_hash.or_else(|| Some(proof.digest()));
+                }
+                diagnostics.extend(fields);
+            }
+            None => {
+                let unsupported = PaymentProofError::UnsupportedService(bank_service.clone().unwrap_or_default());
+                diagnostics.push(FieldDiagnostic::invalid("bank_service", unsupported.to_string()));
+            }
+        }
+    } else if banking_hash.is_none() {
+        diagnostics.push(FieldDiagnostic::invalid("banking_hash", PaymentProofError::Missing.to_string()));
+    }
+
+    if let Some(amount) = &req.amount {
+        diagnostics.push(match (amount.parse::<u128>(), &locked_amount) {
+            (Ok(0) | Err(_), _) => FieldDiagnostic::invalid("amount", format!("'{}' is not a positive integer amount", amount)),
+            (Ok(paid), Some(locked)) if locked.parse::<u128>().ok() != Some(paid) => {
+                FieldDiagnostic::invalid("amount", format!("Paid {} but the order is locked for {}", paid, locked))
+            }
+            _ => FieldDiagnostic::valid("amount"),
+        });
+    }
+
+    Ok(Json(PaymentProofValidation {
+        valid: diagnostics.iter().all(|diagnostic| diagnostic.valid),
+        rail,
+        banking_hash,
+        diagnostics,
+    }))
+}
+
+/// A filler's collateral and what its open locks require (GET /fillers/:filler_id/collateral)
+pub async fn get_collateral(
+    Path(filler_id): Path<String>,
+    State(app_state): State<AppState>,
+) -> Result<Json<CollateralStatus>, StatusCode> {
+    info!("Getting collateral of filler {}", filler_id);
+
+    let status = app_state.collateral.status(&filler_id).await.map_err(|e| {
+        error!("Database error fetching collateral of filler {}: {}", filler_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(status))
+}
+
+/// Payment rails with the proof payload each requires (GET /bank-services)
+pub async fn get_bank_services() -> Json<Vec<BankServiceSchema>> {
+    info!("Getting bank services registry");
+    Json(crate::services::payment_proofs::registry())
+}
+
+/// Register or replace a filler's capabilities (PUT /fillers/:filler_id/capabilities)
+///
+/// A filler with a feed token configured must present it.
+#[instrument(skip_all, fields(filler_id = %filler_id))]
+pub async fn register_capabilities(
+    Path(filler_id): Path<String>,
+    headers: HeaderMap,
+    State(app_state): State<AppState>,
+    Json(capabilities): Json<FillerCapabilities>,
+) -> Result<Json<FillerCapabilities>, ApiError> {
+    info!("Registering capabilities for filler {}", filler_id);
+    require_filler_token(&app_state, &headers, &filler_id)?;
+
+    capabilities.validate()
+        .map_err(|message| ApiError::new(StatusCode::BAD_REQUEST, "invalid_capabilities", message))?;
+
+    filler_capabilities::save(&app_state.db, &filler_id, &capabilities)
+        .await
+        .map_err(|e| {
+            error!("Database error saving filler capabilities: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    app_state.matching_engine.lock().await.set_capabilities(&filler_id, capabilities.clone());
+
+    Ok(Json(capabilities))
+}
+
+/// Fillers with a websocket token must present it to change their settings
+fn require_filler_token(app_state: &AppState, headers: &HeaderMap, filler_id: &str) -> Result<(), ApiError> {
+    let has_token = app_state.config.filler.ws_tokens.values().any(|id| id == filler_id);
+    let presented = bearer_token(headers).and_then(|token| app_state.config.filler.filler_for_token(token));
+    if has_token && presented != Some(filler_id) {
+        return Err(ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", format!("Missing or wrong token for filler {}", filler_id)));
+    }
+    Ok(())
+}
+
+/// A filler's claim relay settings, gas spent and fees charged (GET /fillers/:filler_id/claim-relay)
+pub async fn get_claim_relay(
+    Path(filler_id): Path<String>,
+    State(app_state): State<AppState>,
+) -> Result<Json<RelayAccounting>, StatusCode> {
+    info!("Getting claim relay accounting for filler {}", filler_id);
+
+    claim_relay::accounting(&app_state.db, &app_state.config.claims, &filler_id)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Database error fetching claim relay accounting: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Opt in to or out of claim relaying (PUT /fillers/:filler_id/claim-relay)
+pub async fn set_claim_relay(
+    Path(filler_id): Path<String>,
+    headers: HeaderMap,
+    State(app_state): State<AppState>,
+    Json(settings): Json<RelaySettings>,
+) -> Result<Json<RelaySettings>, ApiError> {
+    info!("Setting claim relay for filler {}: {:?}", filler_id, settings);
+    require_filler_token(&app_state, &headers, &filler_id)?;
+
+    if settings.enabled && !app_state.config.claims.relay_enabled {
+        return Err(claim_relay::RelayError::Unavailable.into());
+    }
+    if settings.max_fee.as_deref().is_some_and(|max| max.parse::<u64>().is_err()) {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "invalid_amount", "max_fee must be an amount in the earned token"));
+    }
+
+    claim_relay::save_settings(&app_state.db, &filler_id, &settings)
+        .await
+        .map_err(|e| {
+            error!("Database error saving claim relay settings: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(settings))
+}
+
+/// Get a filler's registered capabilities (GET /fillers/:filler_id/capabilities)
+pub async fn get_capabilities(
+    Path(filler_id): Path<String>,
+    State(app_state): State<AppState>,
+) -> Result<Json<FillerCapabilities>, ApiError> {
+    filler_capabilities::load(&app_state.db, &filler_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching filler capabilities: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(Json)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "filler_not_registered", format!("Filler {} has not registered capabilities", filler_id)))
+}
+
 /// Get filler balance (GET /fillers/:filler_id/balance)
 pub async fn get_filler_balance_api(
     Path(filler_id): Path<String>,
@@ -292,71 +931,275 @@ pub async fn add_wallet_to_filler(
     Ok(Json(updated_balance))
 }
 
+/// Token fillers earn for the orders they fill (USDC)
+const EARNED_TOKEN_ID: u32 = 1;
+
+/// Fees, relay quote and payouts of a claim request
+struct PricedClaims {
+    fees: Vec<ClaimFee>,
+    payouts: Vec<(String, Option<TokenConversion>)>,
+    relay: Option<RelayQuote>,
+}
+
+/// Price a claim request the way submitting it would: claim fees on each gross amount, the
+/// relay fee out of the largest net amount for opted-in fillers, then any payout conversion
+async fn price_claims(app_state: &AppState, req: &ClaimRequest, payout_token_id: u32) -> Result<PricedClaims, ApiError> {
+    let amounts: Vec<&str> = req.claims.iter().map(|claim| claim.amount.as_str()).collect();
+    let mut fees = claim_fees::compute_all(&app_state.config.claims, &amounts)?;
+
+    // Fillers that opted in to relaying have the claim submitted for them, with the gas
+    // charged out of their largest claim
+    let relay_settings = claim_relay::load_settings(&app_state.db, &req.filler_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching relay settings: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let relay = match relay_settings {
+        Some(settings) if settings.enabled && app_state.config.claims.relay_enabled => {
+            let quote = claim_relay::quote(
+                &app_state.config.claims,
+                &app_state.rates,
+                app_state.blockchain_client.as_deref(),
+                EARNED_TOKEN_ID,
+                req.claims.len(),
+            ).await?;
+            settings.check_fee(quote.fee())?;
+            let mut net_amounts: Vec<u64> = fees.iter().map(ClaimFee::net).collect();
+            let charged = claim_relay::deduct_fee(&mut net_amounts, quote.fee())?;
+            fees[charged].charge_relay_fee(quote.fee());
+            Some(quote)
+        }
+        _ => None,
+    };
+
+    let payouts = fees.iter()
+        .map(|fee| {
+            if payout_token_id == EARNED_TOKEN_ID {
+                return Ok((fee.net_amount.clone(), None));
+            }
+            let conversion = app_state.rates.convert(EARNED_TOKEN_ID, payout_token_id, fee.net())?;
+            Ok((conversion.converted_amount.clone(), Some(conversion)))
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    Ok(PricedClaims { fees, payouts, relay })
+}
+
+/// Preview the fees and net amounts of a claim request (POST /fillers/claim/preview)
+///
+/// Takes the same body as POST /fillers/claim and prices it at current rates and gas price
+/// without recording anything, so a filler sees exactly what would be transferred.
+pub async fn preview_claim(
+    State(app_state): State<AppState>,
+    Json(req): Json<ClaimRequest>,
+) -> Result<Json<ClaimPreviewResponse>, ApiError> {
+    info!("Previewing claim fees for filler {} with {} claims", req.filler_id, req.claims.len());
+
+    let payout_token_id = req.payout_token_id.unwrap_or(EARNED_TOKEN_ID);
+    let PricedClaims { fees, payouts, relay } = price_claims(&app_state, &req, payout_token_id).await?;
+
+    let total = |amount: fn(&ClaimFee) -> &str| -> String {
+        fees.iter().map(|fee| amount(fee).parse::<u64>().unwrap_or(0)).sum::<u64>().to_string()
+    };
+    let response = ClaimPreviewResponse {
+        payout_token_id,
+        total_gross: total(|fee| &fee.gross_amount),
+        total_fee: total(|fee| &fee.fee_amount),
+        total_net: total(|fee| &fee.net_amount),
+        total_payout: payouts.iter().map(|(amount, _)| amount.parse::<u64>().unwrap_or(0)).sum::<u64>().to_string(),
+        claims: req.claims.iter().zip(fees.iter().cloned().zip(payouts))
+            .map(|(claim, (fee, (payout_amount, conversion)))| ClaimPreview {
+                destination_address: claim.destination_address.clone(),
+                fee,
+                payout_amount,
+                conversion,
+            })
+            .collect(),
+        relay,
+    };
+
+    Ok(Json(response))
+}
+
 /// Claim tokens from multiple wallets (POST /fillers/claim)
+///
+/// Claim amounts are in the earned token. Each claim pays the claim fee (`fee_bps` of its
+/// amount plus the gas surcharge) and only the net amount is transferred; gross, fee and net
+/// are recorded on the claim row. With a `payout_token_id` preference the net is converted at
+/// the current rate and the conversion is recorded too. Claims that name an on-chain order
+/// (`batch_id` and `order_id`) are rejected with 409 if it is already claimed; the claims of a
+/// request are recorded together or not at all. For fillers opted in to relaying, the relay
+/// fee is taken out of the largest claim before conversion.
 pub async fn claim_tokens(
-    State(_app_state): State<AppState>,
+    State(app_state): State<AppState>,
     Json(req): Json<ClaimRequest>,
-) -> Result<Json<ClaimResponse>, StatusCode> {
+) -> Result<Json<ClaimResponse>, ApiError> {
     info!("Processing claim request for filler {} with {} claims", 
           req.filler_id, req.claims.len());
 
-    // TODO: Implement actual validation and database operations once import issue is resolved
+    // Nothing is paid out to a sanctioned address
+    let destinations: Vec<&str> = req.claims.iter().map(|claim| claim.destination_address.as_str()).collect();
+    app_state.screening.screen(ScreeningContext::Claim, &req.filler_id, &destinations).await?;
+
+    let batch_id = claim_batch_id(&app_state, &req.claims).await?;
+    let payout_token_id = req.payout_token_id.unwrap_or(EARNED_TOKEN_ID);
     let mut processed_claims = Vec::new();
+    let mut records = Vec::new();
     let mut total_claimed = 0u64;
+    let mut total_payout = 0u64;
 
-            for claim in &req.claims {
-            let claim_amount: u64 = claim.amount.parse().map_err(|_| {
-                error!("Invalid claim amount: {}", claim.amount);
-                StatusCode::BAD_REQUEST
-            })?;
+    let PricedClaims { fees, payouts, relay } = price_claims(&app_state, &req, payout_token_id).await?;
 
-            // Create bridge-out order for this claim (anyone can claim, no source wallet needed)
-            let bridge_out_order = create_bridge_out_order(
-                &claim.destination_address,
-                &claim.amount,
-            );
+    for ((claim, fee), (payout_amount, conversion)) in req.claims.iter().zip(fees).zip(payouts) {
+        let claim_amount: u64 = claim.amount.parse().unwrap_or_default();
 
-            // Generate merkle proof (this would integrate with the actual merkle tree)
-            let merkle_proof = generate_mock_merkle_proof(&bridge_out_order);
+        // Create bridge-out order for this claim (anyone can claim, no source wallet needed)
+        let bridge_out_order = create_bridge_out_order(
+            &claim.destination_address,
+            &payout_amount,
+            payout_token_id,
+        );
 
-            processed_claims.push(ProcessedClaim {
-                amount: claim.amount.clone(),
-                destination_address: claim.destination_address.clone(),
-                merkle_proof,
-                success: true,
-                error: None,
-            });
+        let record = ClaimRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            filler_id: req.filler_id.clone(),
+            wallet_address: bridge_out_order.from_address.clone(),
+            destination_address: claim.destination_address.clone(),
+            amount: claim.amount.clone(),
+            fee_amount: fee.fee_amount.clone(),
+            net_amount: fee.net_amount.clone(),
+            token_id: EARNED_TOKEN_ID,
+            payout_token_id,
+            payout_amount: payout_amount.clone(),
+            conversion: conversion.clone(),
+            batch_id: claim.batch_id,
+            order_id: claim.order_id,
+            status: "pending".to_string(),
+            transaction_hash: None,
+            created_at: chrono::Utc::now(),
+        };
 
-            total_claimed += claim_amount;
-        }
+        // Generate merkle proof (this would integrate with the actual merkle tree)
+        let merkle_proof = generate_mock_merkle_proof(&bridge_out_order);
+
+        processed_claims.push(ProcessedClaim {
+            claim_id: record.id.clone(),
+            amount: claim.amount.clone(),
+            fee,
+            destination_address: claim.destination_address.clone(),
+            payout_token_id,
+            payout_amount: payout_amount.clone(),
+            conversion,
+            merkle_proof,
+            success: true,
+            error: None,
+        });
+
+        records.push(record);
+        let overflow = || ApiError::new(StatusCode::BAD_REQUEST, "claim_total_overflow", "The claimed amounts add up to more than a u64 can hold");
+        total_claimed = total_claimed.checked_add(claim_amount).ok_or_else(overflow)?;
+        total_payout = total_payout.checked_add(payout_amount.parse::<u64>().unwrap_or(0)).ok_or_else(overflow)?;
+    }
+
+    // Claims redeeming an on-chain order are refused if that order was claimed before, in any batch
+    claims::consume(&app_state.db, app_state.blockchain_client.as_deref(), &records).await?;
+    if let Err(e) = app_state.settlement_saga.record_claims(&req.filler_id, total_claimed).await {
+        error!("Failed to complete settlement sagas for filler {}: {}", req.filler_id, e);
+    }
 
     // TODO: Submit batch claim to smart contract
     // This would involve calling the smart contract's batch claim function
-    let transaction_hash = submit_batch_claim_to_contract(&processed_claims).await;
+    let transaction_hash = submit_batch_claim_to_contract(app_state.blockchain_client.as_deref(), batch_id, &processed_claims).await;
+    if let Some(quote) = &relay {
+        let claim_ids: Vec<String> = records.iter().map(|record| record.id.clone()).collect();
+        if let Err(e) = claim_relay::record(&app_state.db, &req.filler_id, transaction_hash.as_deref(), quote, &claim_ids).await {
+            error!("Failed to record relayed claim for filler {}: {}", req.filler_id, e);
+        }
+    }
 
     let response = ClaimResponse {
         transaction_hash,
-        batch_id: 1, // TODO: Use actual batch ID from blockchain
+        batch_id,
         total_claimed: total_claimed.to_string(),
+        payout_token_id,
+        total_payout: total_payout.to_string(),
         claims_processed: processed_claims,
+        relay,
     };
 
-    info!("Mock: Processed {} claims for filler {}, total claimed: {}", 
-          req.claims.len(), req.filler_id, total_claimed);
+    info!("Mock: Processed {} claims for filler {}, total claimed: {}, paid out {} of token {}", 
+          req.claims.len(), req.filler_id, total_claimed, total_payout, payout_token_id);
 
     Ok(Json(response))
 }
 
+/// Get a recorded claim with its payout conversion (GET /fillers/claims/:claim_id)
+pub async fn get_claim(
+    State(app_state): State<AppState>,
+    Path(claim_id): Path<String>,
+) -> Result<Json<ClaimRecord>, StatusCode> {
+    info!("Getting claim {}", claim_id);
+
+    let claim = crate::database::helpers::get_claim(&app_state.db, &claim_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching claim {}: {}", claim_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(claim))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClaimsQuery {
+    pub filler_id: Option<String>,
+    /// Page size, 20 by default and at most 100
+    pub limit: Option<usize>,
+    /// `next_cursor` of the previous page
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClaimsListResponse {
+    pub claims: Vec<ClaimRecord>,
+    /// Pass as `after` to fetch the next page; unset on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// List claims, newest first (GET /fillers/claims)
+pub async fn list_claims(
+    State(app_state): State<AppState>,
+    Query(query): Query<ClaimsQuery>,
+) -> Result<Json<ClaimsListResponse>, ApiError> {
+    info!("Listing claims with params: {:?}", query);
+
+    let after = query.after.as_deref().map(Cursor::decode).transpose()?;
+    let limit = page_size(query.limit, 20);
+    let claims = crate::database::helpers::list_claims(&app_state.db, query.filler_id.as_deref(), after.as_ref(), limit + 1)
+        .await
+        .map_err(|e| {
+            error!("Database error listing claims: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let (claims, next_cursor) = paginate(claims, limit, |claim| Cursor::new(claim.created_at, &claim.id));
+    Ok(Json(ClaimsListResponse { claims, next_cursor }))
+}
+
 /// Helper function to create a bridge-out order
 fn create_bridge_out_order(
     destination_address: &str, // Where tokens should be sent 
-    amount: &str
+    amount: &str,
+    token_id: u32,
 ) -> BridgeOutOrder {
     BridgeOutOrder {
         from_address: "0x0000000000000000000000000000000000000000".to_string(), // zero address - anyone can claim
         to_address: destination_address.to_string(),  // destination
         amount: amount.to_string(),
-        token_id: 1, // USDC
+        token_id,
     }
 }
 
@@ -380,8 +1223,31 @@ fn generate_mock_merkle_proof(order: &BridgeOutOrder) -> Vec<String> {
     ]
 }
 
+/// Batch a claim request is claimed against: the one its claims name, else the latest proven batch
+async fn claim_batch_id(app_state: &AppState, claims: &[WalletClaim]) -> Result<Option<u64>, ApiError> {
+    let mut named = claims.iter().filter_map(|claim| claim.batch_id);
+    if let Some(batch_id) = named.next() {
+        if named.any(|other| other != batch_id) {
+            return Err(ApiError::new(StatusCode::BAD_REQUEST, "mixed_claim_batches", "All claims of a request must be against the same batch"));
+        }
+        return Ok(Some(batch_id));
+    }
+
+    let proven_root = crate::database::helpers::latest_proven_root(&app_state.db).await.map_err(|e| {
+        error!("Database error fetching the latest proven root: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(proven_root.map(|root| root.batch_id))
+}
+
 /// Submit batch claim to smart contract (mock implementation)
-async fn submit_batch_claim_to_contract(claims: &[ProcessedClaim]) -> Option<String> {
+///
+/// Returns the transaction hash once the claim is sent; a claim that is only signed has none.
+async fn submit_batch_claim_to_contract(
+    blockchain_client: Option<&vapor_chain::BlockchainClient>,
+    batch_id: Option<u64>,
+    claims: &[ProcessedClaim],
+) -> Option<String> {
     // This is a mock implementation
     // In reality, this would:
     // 1. Create ClaimData array for the smart contract
@@ -399,6 +1265,30 @@ async fn submit_batch_claim_to_contract(claims: &[ProcessedClaim]) -> Option<Str
     info!("Mock: Submitting batch claim with {} claims to VaporBridge.batchClaim()", claims.len());
     info!("Mock: Each claim will recreate leaf: keccak256(abi.encode(batchId, orderId, ORDER_TYPE_BRIDGE_OUT, address(0), destinationAddress, tokenId, amount))");
     
-    // Simulate successful batch claim transaction
-    Some("0x1234567890abcdef1234567890abcdef12345678".to_string())
+    let Some(blockchain_client) = blockchain_client else {
+        // Simulate successful batch claim transaction
+        return Some("0x1234567890abcdef1234567890abcdef12345678".to_string());
+    };
+    let Some(batch_id) = batch_id else {
+        warn!("Not submitting batch claim: no batch has been proven yet");
+        return None;
+    };
+
+    let mut payload = Vec::new();
+    for claim in claims {
+        payload.extend_from_slice(claim.destination_address.as_bytes());
+        payload.extend_from_slice(&claim.payout_token_id.to_be_bytes());
+        payload.extend_from_slice(claim.payout_amount.as_bytes());
+        for node in &claim.merkle_proof {
+            payload.extend_from_slice(node.as_bytes());
+        }
+    }
+
+    match blockchain_client.submit_batch_claim(batch_id, &payload).await {
+        Ok(submission) => submission.transaction_hash().map(|tx_hash| format!("{:?}", tx_hash)),
+        Err(e) => {
+            error!("Failed to submit batch claim: {}", e);
+            None
+        }
+    }
 }
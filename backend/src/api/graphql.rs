@@ -0,0 +1,585 @@
+use async_graphql::{
+    dataloader::{DataLoader, Loader},
+    Context, EmptyMutation, EmptySubscription, Enum, Object, Schema, SimpleObject,
+};
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use super::AppState;
+use crate::database::helpers::{self, OrderFilter};
+use vapor_core::proof_format::{FormattedProof, ProofError};
+use crate::merkle::MerkleTreeManager;
+use crate::models::{self, ClaimRecord, FillerBalance, TokenBalance};
+use crate::services::archival::{ArchiveService, BatchSnapshot};
+use crate::services::proof_cache::{build_account_proof, build_order_proofs};
+
+/// Read-only schema served at `/api/v1/graphql`
+pub type VaporSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Deepest selection accepted, enough for order → batch → orders → filler → claims
+const MAX_QUERY_DEPTH: usize = 10;
+/// Largest page the order lists return, whatever `limit` asks for
+const MAX_ORDERS_LIMIT: u32 = 500;
+
+pub fn build_schema() -> VaporSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .finish()
+}
+
+/// Execute a GraphQL query, with dataloaders scoped to the request
+pub async fn graphql_handler(
+    State(app_state): State<AppState>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    info!("Executing GraphQL query {}", request.operation_name.as_deref().unwrap_or("(anonymous)"));
+
+    let request = request
+        .data(DataLoader::new(OrderLoader(app_state.db.clone()), tokio::spawn))
+        .data(DataLoader::new(SnapshotLoader(app_state.archive.clone()), tokio::spawn))
+        .data(DataLoader::new(FillerLoader(app_state.db.clone()), tokio::spawn))
+        .data(DataLoader::new(ClaimsLoader(app_state.db.clone()), tokio::spawn))
+        .data(app_state.clone());
+
+    Json(app_state.graphql.execute(request).await)
+}
+
+/// Log the underlying error and hide it from the client, as the REST handlers do
+fn internal(what: &str, e: impl std::fmt::Display) -> async_graphql::Error {
+    error!("GraphQL: failed to load {}: {}", what, e);
+    async_graphql::Error::new("Internal server error")
+}
+
+fn proof_error(e: ProofError) -> async_graphql::Error {
+    match e {
+        ProofError::Tree(e) => internal("proof", e),
+        e => async_graphql::Error::new(e.to_string()),
+    }
+}
+
+// Loaders batch the lookups of one request; errors are shared between the keys of a batch
+
+pub struct OrderLoader(SqlitePool);
+
+impl Loader<String> for OrderLoader {
+    type Value = models::Order;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        let orders = helpers::get_orders_by_ids(&self.0, keys).await.map_err(Arc::new)?;
+        Ok(orders.into_iter().map(|order| (order.id.clone(), order)).collect())
+    }
+}
+
+pub struct SnapshotLoader(ArchiveService);
+
+impl Loader<u64> for SnapshotLoader {
+    type Value = Arc<BatchSnapshot>;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[u64]) -> Result<HashMap<u64, Self::Value>, Self::Error> {
+        let mut snapshots = HashMap::new();
+        for &batch_id in keys {
+            if let Some(snapshot) = self.0.load_snapshot(batch_id).await.map_err(Arc::new)? {
+                snapshots.insert(batch_id, Arc::new(snapshot));
+            }
+        }
+        Ok(snapshots)
+    }
+}
+
+pub struct FillerLoader(SqlitePool);
+
+impl Loader<String> for FillerLoader {
+    type Value = Arc<FillerBalance>;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        let mut fillers = HashMap::new();
+        for filler_id in keys {
+            if let Some(balance) = helpers::get_filler_balance(&self.0, filler_id).await.map_err(Arc::new)? {
+                fillers.insert(filler_id.clone(), Arc::new(balance));
+            }
+        }
+        Ok(fillers)
+    }
+}
+
+pub struct ClaimsLoader(SqlitePool);
+
+impl Loader<String> for ClaimsLoader {
+    type Value = Vec<ClaimRecord>;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        let mut claims: HashMap<String, Vec<ClaimRecord>> = HashMap::new();
+        for claim in helpers::get_claims_by_fillers(&self.0, keys).await.map_err(Arc::new)? {
+            claims.entry(claim.filler_id.clone()).or_default().push(claim);
+        }
+        Ok(claims)
+    }
+}
+
+async fn load_snapshot(ctx: &Context<'_>, batch_id: u64) -> async_graphql::Result<Option<Arc<BatchSnapshot>>> {
+    ctx.data_unchecked::<DataLoader<SnapshotLoader>>()
+        .load_one(batch_id)
+        .await
+        .map_err(|e| internal("batch snapshot", e))
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "OrderType", remote = "crate::models::OrderType")]
+pub enum GqlOrderType {
+    BridgeIn,
+    BridgeOut,
+    Transfer,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "OrderStatus", remote = "crate::models::OrderStatus")]
+pub enum GqlOrderStatus {
+    Pending,
+    Discovery,
+    Locked,
+    MarkPaid,
+    Settled,
+    Failed,
+    Disputed,
+    ReviewPending,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[graphql(name = "ProofFormat", remote = "vapor_core::proof_format::ProofFormat")]
+pub enum GqlProofFormat {
+    #[default]
+    Siblings,
+    Raw,
+    SortedPairs,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "TokenBalance")]
+pub struct GqlTokenBalance {
+    token_id: u32,
+    balance: String,
+}
+
+impl From<&TokenBalance> for GqlTokenBalance {
+    fn from(balance: &TokenBalance) -> Self {
+        Self { token_id: balance.token_id, balance: balance.balance.clone() }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Proof {
+    format: GqlProofFormat,
+    leaf_hash: String,
+    proof: Vec<String>,
+    root: String,
+    path_bits: Option<Vec<u8>>,
+    /// Leaf position in the batch's orders tree, for order proofs
+    leaf_index: Option<usize>,
+}
+
+impl Proof {
+    fn new(proof: FormattedProof, leaf_index: Option<usize>) -> Self {
+        Self {
+            format: proof.format.into(),
+            leaf_hash: proof.leaf_hash,
+            proof: proof.proof,
+            root: proof.root,
+            path_bits: proof.path_bits,
+            leaf_index,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Claim {
+    id: String,
+    filler_id: String,
+    wallet_address: String,
+    destination_address: String,
+    amount: String,
+    /// Claim fees taken out of `amount`
+    fee_amount: String,
+    net_amount: String,
+    token_id: u32,
+    payout_token_id: u32,
+    payout_amount: String,
+    /// Payout token units per earned token unit, when converted
+    conversion_rate: Option<String>,
+    batch_id: Option<u64>,
+}
+
+impl From<ClaimRecord> for Claim {
+    fn from(claim: ClaimRecord) -> Self {
+        Self {
+            id: claim.id,
+            filler_id: claim.filler_id,
+            wallet_address: claim.wallet_address,
+            destination_address: claim.destination_address,
+            amount: claim.amount,
+            fee_amount: claim.fee_amount,
+            net_amount: claim.net_amount,
+            token_id: claim.token_id,
+            payout_token_id: claim.payout_token_id,
+            payout_amount: claim.payout_amount,
+            conversion_rate: claim.conversion.map(|conversion| conversion.rate),
+            batch_id: claim.batch_id,
+        }
+    }
+}
+
+pub struct Order(models::Order);
+
+#[Object]
+impl Order {
+    async fn id(&self) -> &str {
+        &self.0.id
+    }
+
+    async fn order_type(&self) -> GqlOrderType {
+        self.0.order_type.into()
+    }
+
+    async fn status(&self) -> GqlOrderStatus {
+        self.0.status.into()
+    }
+
+    async fn from_address(&self) -> Option<&str> {
+        self.0.from_address.as_deref()
+    }
+
+    async fn to_address(&self) -> Option<&str> {
+        self.0.to_address.as_deref()
+    }
+
+    async fn token_id(&self) -> u32 {
+        self.0.token_id
+    }
+
+    async fn amount(&self) -> &str {
+        &self.0.amount
+    }
+
+    async fn bank_service(&self) -> Option<&str> {
+        self.0.bank_service.as_deref()
+    }
+
+    async fn banking_hash(&self) -> Option<&str> {
+        self.0.banking_hash.as_deref()
+    }
+
+    async fn locked_amount(&self) -> Option<&str> {
+        self.0.locked_amount.as_deref()
+    }
+
+    async fn batch_id(&self) -> Option<u64> {
+        self.0.batch_id
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    /// Batch the order was included in, once its state snapshot exists
+    async fn batch(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Batch>> {
+        let Some(batch_id) = self.0.batch_id else {
+            return Ok(None);
+        };
+        Ok(load_snapshot(ctx, batch_id).await?.map(Batch))
+    }
+
+    /// Inclusion proof against the orders root of the order's batch
+    async fn proof(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] format: GqlProofFormat,
+    ) -> async_graphql::Result<Option<Proof>> {
+        let Some(batch_id) = self.0.batch_id else {
+            return Ok(None);
+        };
+        let Some(snapshot) = load_snapshot(ctx, batch_id).await? else {
+            return Ok(None);
+        };
+        let Some(index) = snapshot.orders.iter().position(|order| order.id == self.0.id) else {
+            return Ok(None);
+        };
+
+        let proof = build_order_proofs(&snapshot.orders, batch_id, &[index], format.into())
+            .map_err(proof_error)?
+            .remove(0);
+        Ok(Some(Proof::new(proof, Some(index))))
+    }
+
+    /// Filler that locked the order
+    async fn filler(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Filler>> {
+        let Some(filler_id) = &self.0.filler_id else {
+            return Ok(None);
+        };
+        load_filler(ctx, filler_id).await
+    }
+}
+
+/// A finalized batch, read from its state snapshot
+pub struct Batch(Arc<BatchSnapshot>);
+
+#[Object]
+impl Batch {
+    async fn batch_id(&self) -> u64 {
+        self.0.batch_id
+    }
+
+    async fn state_root(&self) -> &str {
+        &self.0.state_root
+    }
+
+    async fn orders_root(&self) -> &str {
+        &self.0.orders_root
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn orders_count(&self) -> usize {
+        self.0.orders.len()
+    }
+
+    /// Orders in tree order
+    async fn orders(&self) -> Vec<Order> {
+        self.0.orders.iter().cloned().map(Order).collect()
+    }
+
+    /// Accounts in the batch's state tree
+    async fn accounts(&self) -> Vec<Account> {
+        self.0.accounts.iter()
+            .map(|account| Account {
+                address: account.address.clone(),
+                balances: account.balances.clone(),
+                batch: Some(self.0.clone()),
+            })
+            .collect()
+    }
+}
+
+/// Balances of an address, either live or as of a batch
+pub struct Account {
+    address: String,
+    balances: Vec<TokenBalance>,
+    batch: Option<Arc<BatchSnapshot>>,
+}
+
+#[Object]
+impl Account {
+    async fn address(&self) -> &str {
+        &self.address
+    }
+
+    async fn balances(&self) -> Vec<GqlTokenBalance> {
+        self.balances.iter().map(GqlTokenBalance::from).collect()
+    }
+
+    async fn batch_id(&self) -> Option<u64> {
+        self.batch.as_ref().map(|snapshot| snapshot.batch_id)
+    }
+
+    /// State tree proof, only available for accounts read as of a batch
+    async fn proof(
+        &self,
+        #[graphql(default)] format: GqlProofFormat,
+    ) -> async_graphql::Result<Option<Proof>> {
+        let Some(snapshot) = &self.batch else {
+            return Ok(None);
+        };
+
+        let mut manager = MerkleTreeManager::new();
+        manager.build_state_tree(&snapshot.accounts).map_err(|e| internal("state tree", e))?;
+        let proof = build_account_proof(&mut manager, &self.address, format.into()).map_err(proof_error)?;
+        Ok(Some(Proof::new(proof, None)))
+    }
+
+    /// Newest orders sent from or to the address
+    async fn orders(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 50)] limit: u32,
+    ) -> async_graphql::Result<Vec<Order>> {
+        let filter = OrderFilter { address: Some(self.address.clone()), ..Default::default() };
+        find_orders(ctx, &filter, limit).await
+    }
+}
+
+pub struct Filler(Arc<FillerBalance>);
+
+#[Object]
+impl Filler {
+    async fn filler_id(&self) -> &str {
+        &self.0.filler_id
+    }
+
+    /// Address of the filler's settlement account in the state tree
+    async fn settlement_address(&self) -> String {
+        models::filler_settlement_address(&self.0.filler_id)
+    }
+
+    async fn total_balance(&self) -> &str {
+        &self.0.total_balance
+    }
+
+    async fn available_balance(&self) -> &str {
+        &self.0.available_balance
+    }
+
+    async fn locked_balance(&self) -> &str {
+        &self.0.locked_balance
+    }
+
+    async fn completed_jobs(&self) -> u32 {
+        self.0.completed_jobs
+    }
+
+    async fn wallets(&self) -> Vec<FillerWallet> {
+        self.0.wallets.iter()
+            .map(|wallet| FillerWallet {
+                address: wallet.address.clone(),
+                balance: wallet.balance.clone(),
+                percentage: wallet.percentage,
+            })
+            .collect()
+    }
+
+    /// Claims made by the filler, newest first
+    async fn claims(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Claim>> {
+        let claims = ctx.data_unchecked::<DataLoader<ClaimsLoader>>()
+            .load_one(self.0.filler_id.clone())
+            .await
+            .map_err(|e| internal("claims", e))?
+            .unwrap_or_default();
+        Ok(claims.into_iter().map(Claim::from).collect())
+    }
+
+    /// Newest orders locked by the filler
+    async fn orders(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 50)] limit: u32,
+    ) -> async_graphql::Result<Vec<Order>> {
+        let filter = OrderFilter { filler_id: Some(self.0.filler_id.clone()), ..Default::default() };
+        find_orders(ctx, &filter, limit).await
+    }
+
+    /// Claimable balances, as proven by the settlement account in a batch's state tree
+    async fn settlement_account(&self, ctx: &Context<'_>, batch_id: u64) -> async_graphql::Result<Option<Account>> {
+        let address = models::filler_settlement_address(&self.0.filler_id);
+        historical_account(ctx, &address, batch_id).await
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct FillerWallet {
+    address: String,
+    balance: String,
+    percentage: f32,
+}
+
+async fn load_filler(ctx: &Context<'_>, filler_id: &str) -> async_graphql::Result<Option<Filler>> {
+    let balance = ctx.data_unchecked::<DataLoader<FillerLoader>>()
+        .load_one(filler_id.to_string())
+        .await
+        .map_err(|e| internal("filler", e))?;
+    Ok(balance.map(Filler))
+}
+
+async fn find_orders(ctx: &Context<'_>, filter: &OrderFilter, limit: u32) -> async_graphql::Result<Vec<Order>> {
+    let app_state = ctx.data_unchecked::<AppState>();
+    let orders = helpers::find_orders(&app_state.db, filter, limit.min(MAX_ORDERS_LIMIT))
+        .await
+        .map_err(|e| internal("orders", e))?;
+    Ok(orders.into_iter().map(Order).collect())
+}
+
+async fn historical_account(ctx: &Context<'_>, address: &str, batch_id: u64) -> async_graphql::Result<Option<Account>> {
+    let Some(snapshot) = load_snapshot(ctx, batch_id).await? else {
+        return Ok(None);
+    };
+    let Some(account) = snapshot.accounts.iter().find(|account| account.address.eq_ignore_ascii_case(address)) else {
+        return Ok(None);
+    };
+
+    Ok(Some(Account {
+        address: account.address.clone(),
+        balances: account.balances.clone(),
+        batch: Some(snapshot.clone()),
+    }))
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn order(&self, ctx: &Context<'_>, id: String) -> async_graphql::Result<Option<Order>> {
+        let order = ctx.data_unchecked::<DataLoader<OrderLoader>>()
+            .load_one(id)
+            .await
+            .map_err(|e| internal("order", e))?;
+        Ok(order.map(Order))
+    }
+
+    /// Newest orders matching every given condition
+    async fn orders(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<GqlOrderStatus>,
+        order_type: Option<GqlOrderType>,
+        address: Option<String>,
+        filler_id: Option<String>,
+        #[graphql(default = 50)] limit: u32,
+    ) -> async_graphql::Result<Vec<Order>> {
+        let filter = OrderFilter {
+            status: status.map(Into::into),
+            order_type: order_type.map(Into::into),
+            address,
+            filler_id,
+        };
+        find_orders(ctx, &filter, limit).await
+    }
+
+    async fn batch(&self, ctx: &Context<'_>, batch_id: u64) -> async_graphql::Result<Option<Batch>> {
+        Ok(load_snapshot(ctx, batch_id).await?.map(Batch))
+    }
+
+    /// Current balances of an address, or its state as of `batchId`
+    ///
+    /// `filler:<filler_id>` reads the filler's settlement account.
+    async fn account(
+        &self,
+        ctx: &Context<'_>,
+        address: String,
+        batch_id: Option<u64>,
+    ) -> async_graphql::Result<Option<Account>> {
+        let address = models::resolve_account_address(&address);
+        if let Some(batch_id) = batch_id {
+            return historical_account(ctx, &address, batch_id).await;
+        }
+
+        let app_state = ctx.data_unchecked::<AppState>();
+        let balances = helpers::get_account_balances(&app_state.db, &address)
+            .await
+            .map_err(|e| internal("account balances", e))?;
+        Ok(Some(Account { address, balances, batch: None }))
+    }
+
+    async fn filler(&self, ctx: &Context<'_>, filler_id: String) -> async_graphql::Result<Option<Filler>> {
+        load_filler(ctx, &filler_id).await
+    }
+}
@@ -0,0 +1,218 @@
+use axum::{extract::State, http::HeaderMap, Json};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use super::{admin::require_admin, batch::finalize_in_sequence, error::ApiError, AppState};
+use crate::merkle::MerkleTreeManager;
+use crate::services::{
+    batch_journal::{self, RecoveryReport},
+    batch_processor::ProcessingBatch,
+    batch_sequence,
+    filler_capabilities,
+    handover::{self, HandoverError, HandoverManifest, WriterRole},
+    order_reconciliation,
+};
+
+#[derive(Debug, Serialize)]
+pub struct HandoverStatus {
+    pub instance_id: String,
+    #[serde(flatten)]
+    pub role: WriterRole,
+    /// Manifest exported by the writer and waiting to be imported
+    pub pending: Option<HandoverManifest>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportHandoverRequest {
+    /// Finalize the open batch before exporting (the default); otherwise it is left open and
+    /// recovered from the batch journal by the new instance
+    pub finalize_open_batch: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    /// Last finalized batch the instance continues from
+    pub batch_id: u64,
+    /// Finalized batches put back in the proving queue
+    pub queued_for_proof: Vec<u64>,
+    /// The open batch recovered from the journal
+    pub recovered_batch: Option<RecoveryReport>,
+}
+
+/// This instance's writer role and any pending handover (GET /admin/handover)
+pub async fn get_handover(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<HandoverStatus>, ApiError> {
+    require_admin(&app_state, &headers)?;
+
+    let pending = app_state.writer_lease.pending_manifest().await.map_err(HandoverError::from)?;
+    Ok(Json(HandoverStatus {
+        instance_id: app_state.writer_lease.instance_id().to_string(),
+        role: app_state.writer_lease.role(),
+        pending,
+    }))
+}
+
+/// Freeze this writer and export the manifest a new instance imports to take over
+/// (POST /admin/handover/export)
+///
+/// Writes are refused from the start; if the export fails they are accepted again. Once the
+/// manifest is recorded this instance never writes again and keeps serving reads.
+pub async fn export_handover(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    request: Option<Json<ExportHandoverRequest>>,
+) -> Result<Json<HandoverManifest>, ApiError> {
+    require_admin(&app_state, &headers)?;
+    let finalize = request.and_then(|Json(request)| request.finalize_open_batch).unwrap_or(true);
+
+    let lease = &app_state.writer_lease;
+    let role = lease.role();
+    if role != WriterRole::Writer {
+        return Err(HandoverError::NotWriter(role).into());
+    }
+
+    lease.freeze();
+    match freeze_and_export(&app_state, finalize).await {
+        Ok(manifest) => {
+            warn!("Handed over at batch {} with manifest {}", manifest.batch_id, manifest.digest);
+            Ok(Json(manifest))
+        }
+        Err(e) => {
+            lease.thaw();
+            Err(e.into())
+        }
+    }
+}
+
+async fn freeze_and_export(app_state: &AppState, finalize: bool) -> Result<HandoverManifest, HandoverError> {
+    // Held until the handover is recorded, so no batch operation slips in after the capture
+    let mut processor = app_state.batch_processor.lock().await;
+    if finalize && processor.current_batch.is_some() {
+        let result = finalize_in_sequence(app_state, &mut processor).await.map_err(anyhow::Error::from)?;
+        info!("Finalized batch {} for the handover", result.batch_id);
+    }
+    if let Some(journal) = &processor.journal {
+        journal.flush().await;
+    }
+
+    let manifest = HandoverManifest::capture(
+        &app_state.db,
+        app_state.writer_lease.instance_id(),
+        processor.get_current_batch().map(|batch| batch.batch_id),
+        processor.proving_queue.batch_ids(),
+        app_state.clock.now(),
+    ).await?;
+    app_state.writer_lease.hand_over(&manifest, app_state.clock.now()).await?;
+    Ok(manifest)
+}
+
+/// Validate a manifest exported by the writer and take over from it (POST /admin/handover/import)
+///
+/// Only a standby instance imports, and only if the database still matches the manifest.
+pub async fn import_handover(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(manifest): Json<HandoverManifest>,
+) -> Result<Json<ImportReport>, ApiError> {
+    require_admin(&app_state, &headers)?;
+
+    let lease = &app_state.writer_lease;
+    let _transition = lease.lock_transitions().await;
+    let role = lease.role();
+    if !matches!(role, WriterRole::Standby { .. }) {
+        return Err(HandoverError::NotStandby(role).into());
+    }
+
+    manifest.validate(&app_state.db).await?;
+    lease.take_over(&manifest, app_state.clock.now()).await?;
+    match assume_writer(&app_state, &manifest.queued_for_proof).await {
+        Ok(report) => {
+            info!("Took over from {} at batch {}", manifest.from_instance, report.batch_id);
+            Ok(Json(report))
+        }
+        Err(e) => {
+            // The lease is ours now; stay frozen rather than write from a half-loaded state
+            error!("Failed to load the handed over state, writes stay paused: {}", e);
+            lease.freeze();
+            Err(e.into())
+        }
+    }
+}
+
+/// Bring this instance's memory up to the database before it writes: resume from the last
+/// finalized batch, requeue `queued_for_proof` and reload the writer state
+pub async fn assume_writer(app_state: &AppState, queued_for_proof: &[u64]) -> Result<ImportReport, HandoverError> {
+    let mut processor = app_state.batch_processor.lock().await;
+    let batch_id = match handover::latest_batch(&app_state.db).await? {
+        Some((batch_id, _, _)) => {
+            let snapshot = app_state.archive.load_snapshot(batch_id).await?
+                .ok_or_else(|| anyhow::anyhow!("snapshot of batch {} is missing", batch_id))?;
+            processor.resume_from(&snapshot).map_err(anyhow::Error::from)?;
+            batch_id
+        }
+        None => 0,
+    };
+    for &queued in queued_for_proof {
+        processor.proving_queue.push(finalized_batch(app_state, queued).await?).map_err(anyhow::Error::from)?;
+    }
+    drop(processor);
+
+    let recovered_batch = load_writer_state(app_state).await?;
+    Ok(ImportReport { batch_id, queued_for_proof: queued_for_proof.to_vec(), recovered_batch })
+}
+
+/// A finalized batch as the proving stage expects it, rebuilt from its and its parent's snapshots
+async fn finalized_batch(app_state: &AppState, batch_id: u64) -> anyhow::Result<ProcessingBatch> {
+    let snapshot = app_state.archive.load_snapshot(batch_id).await?
+        .ok_or_else(|| anyhow::anyhow!("snapshot of batch {} is missing", batch_id))?;
+    let (prev_batch_id, prev_state_root, prev_orders_root) = match batch_sequence::get(&app_state.db, batch_id).await? {
+        Some(recorded) => (recorded.prev_batch_id, recorded.prev_state_root, recorded.prev_orders_root),
+        // Finalized before the batch sequence was recorded
+        None if batch_id == 1 => (0, MerkleTreeManager::empty_state_root(), MerkleTreeManager::empty_orders_root()),
+        None => {
+            let (state_root, orders_root, _) = app_state.archive.snapshot_roots(batch_id - 1).await?
+                .ok_or_else(|| anyhow::anyhow!("snapshot of batch {} is missing", batch_id - 1))?;
+            (batch_id - 1, state_root, orders_root)
+        }
+    };
+
+    Ok(ProcessingBatch {
+        batch_id,
+        prev_batch_id,
+        prev_state_root,
+        prev_orders_root,
+        orders: snapshot.orders,
+        new_state_root: snapshot.state_root,
+        new_orders_root: snapshot.orders_root,
+        created_at: snapshot.created_at,
+        is_finalized: true,
+    })
+}
+
+/// Load what the writer keeps in memory besides the batch state: registered filler
+/// capabilities, held locks and the batch left open by the previous writer
+async fn load_writer_state(app_state: &AppState) -> anyhow::Result<Option<RecoveryReport>> {
+    // Registered filler capabilities feed the matching engine's ranking
+    let capabilities = filler_capabilities::load_all(&app_state.db).await?;
+    let mut engine = app_state.matching_engine.lock().await;
+    for (filler_id, filler_capabilities) in capabilities {
+        engine.set_capabilities(&filler_id, filler_capabilities);
+    }
+    // Locks taken before the restart are still held
+    engine.locked_orders.clear();
+    for lock in order_reconciliation::locked_orders(&app_state.db).await? {
+        if let Some(filler_id) = &lock.filler_id {
+            engine.record_lock(&lock.order_id, filler_id);
+        }
+    }
+    drop(engine);
+
+    // Reopen a batch left unfinalized by the previous run
+    batch_journal::recover_open_batch(
+        &app_state.db,
+        &mut *app_state.batch_processor.lock().await,
+        app_state.config.batch.recovery_policy,
+    ).await
+}
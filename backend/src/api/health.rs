@@ -5,16 +5,21 @@ use tracing::info;
 use chrono::Utc;
 
 use super::AppState;
+use crate::config::DeploymentProfile;
+use crate::services::handover::{LeaseStatus, WriterRole};
 
 #[derive(Debug, Serialize)]
 pub struct HealthResponse {
     pub status: String,
     pub service: String,
     pub version: String,
+    pub profile: DeploymentProfile,
     pub timestamp: String,
     pub database: DatabaseHealth,
     pub services: ServicesHealth,
     pub blockchain: Option<BlockchainHealth>,
+    /// Writer election: which instance runs the batch pipeline and background processing
+    pub writer: Option<LeaseStatus>,
 }
 
 #[derive(Debug, Serialize)]
@@ -54,9 +59,18 @@ pub async fn health_check(State(app_state): State<AppState>) -> Json<HealthRespo
     
     // Check blockchain connectivity if available
     let blockchain_health = check_blockchain_health(&app_state).await;
+
+    let writer = match app_state.writer_lease.status().await {
+        Ok(status) => Some(status),
+        Err(e) => {
+            tracing::error!("Writer lease health check failed: {}", e);
+            None
+        }
+    };
     
-    // Determine overall status
-    let overall_status = if database_health.connected {
+    // Determine overall status; a writer that lost its lease has stopped processing
+    let lapsed = writer.as_ref().is_some_and(|writer| writer.role == WriterRole::Lapsed);
+    let overall_status = if database_health.connected && !lapsed {
         "healthy"
     } else {
         "degraded"
@@ -66,10 +80,12 @@ pub async fn health_check(State(app_state): State<AppState>) -> Json<HealthRespo
         status: overall_status.to_string(),
         service: "cashlink-backend".to_string(),
         version: "0.1.0".to_string(),
+        profile: app_state.config.profile,
         timestamp: Utc::now().to_rfc3339(),
         database: database_health,
         services: services_health,
         blockchain: blockchain_health,
+        writer,
     };
 
     Json(response)
@@ -0,0 +1,186 @@
+use anyhow::Result;
+use axum::extract::{Path, State};
+use chrono::Duration;
+use sqlx::Row;
+use tracing::{info, warn, error};
+
+use super::{fillers, orders, AppState};
+use crate::database::helpers;
+use crate::models::OrderStatus;
+use crate::services::{
+    payment_proofs::Verification,
+    scheduler::{JobKind, JobOutcome, ScheduledJob},
+    settlement_saga::SagaError,
+    sla::{self, LockExpiry, SlaPolicy},
+};
+
+/// Jobs taken per pass; the rest wait for the next poll
+const JOBS_PER_PASS: u32 = 100;
+
+/// Run every due job once, returning how many ran
+///
+/// A job that errors is retried with backoff like one whose attempt failed.
+pub async fn run_due_jobs(app_state: &AppState) -> Result<usize> {
+    let jobs = app_state.scheduler.due(JOBS_PER_PASS).await?;
+    for job in &jobs {
+        let outcome = match run_job(app_state, job).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                warn!("Job {} failed: {:#}", job.id, e);
+                JobOutcome::Retry(e.to_string())
+            }
+        };
+        app_state.scheduler.finish(job, outcome).await?;
+    }
+    Ok(jobs.len())
+}
+
+async fn run_job(app_state: &AppState, job: &ScheduledJob) -> Result<JobOutcome> {
+    match job.kind {
+        JobKind::VerifyPayment => verify_payment(app_state, &job.order_id).await,
+        JobKind::CheckSettlement => check_settlement(app_state, &job.order_id, job.payload.as_deref()).await,
+        JobKind::ExpireLock => expire_lock(app_state, &job.order_id).await,
+        JobKind::CloseLockWindow => close_lock_window(app_state, &job.order_id).await,
+        JobKind::DeliverNotification => deliver_notification(app_state, &job.order_id).await,
+    }
+}
+
+/// Send a queued notification; a failed send is retried until the notification is given up
+async fn deliver_notification(app_state: &AppState, notification_id: &str) -> Result<JobOutcome> {
+    match app_state.notifications.deliver(notification_id, app_state.config.jobs.max_attempts).await? {
+        Some(_) => Ok(JobOutcome::Done),
+        None => Ok(JobOutcome::Fail(format!("notification {} not found", notification_id))),
+    }
+}
+
+/// Ask the payment provider about a proof, creating the escrow transfer once it is verified
+async fn verify_payment(app_state: &AppState, order_id: &str) -> Result<JobOutcome> {
+    let Some(row) = sqlx::query("SELECT status, bank_service, banking_hash, payment_proof FROM orders WHERE id = ?")
+        .bind(order_id)
+        .fetch_optional(&app_state.db)
+        .await?
+    else {
+        return Ok(JobOutcome::Fail(format!("order {} not found", order_id)));
+    };
+
+    // Disputed, expired, or already marked paid by an operator
+    if OrderStatus::from(row.try_get::<i32, _>("status")?) != OrderStatus::MarkPaid {
+        return Ok(JobOutcome::Done);
+    }
+    match app_state.settlement_saga.get(order_id).await {
+        Ok(saga) if saga.escrow_order_id.is_some() => return Ok(JobOutcome::Done),
+        Ok(_) | Err(SagaError::NotFound(_)) => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let payment_proof = row.try_get::<Option<String>, _>("payment_proof")?
+        .map(|proof| serde_json::from_str(&proof))
+        .transpose()?;
+    let verification = app_state.payment_verifier.verify(
+        order_id,
+        row.try_get::<Option<String>, _>("bank_service")?.as_deref(),
+        row.try_get::<Option<String>, _>("banking_hash")?.as_deref(),
+        payment_proof.as_ref(),
+    ).await?;
+
+    match verification {
+        Verification::Verified => match orders::mark_paid(State(app_state.clone()), Path(order_id.to_string())).await {
+            Ok(_) => Ok(JobOutcome::Done),
+            Err(status) => Ok(JobOutcome::Retry(format!("marking order paid failed with {}", status))),
+        },
+        Verification::Pending => Ok(JobOutcome::Retry("payment not yet visible to the verifier".to_string())),
+        Verification::Rejected { reason } => {
+            let reason = reason.unwrap_or_else(|| "payment_rejected".to_string());
+            let result = sqlx::query("UPDATE orders SET status = ?1, failure_reason = 'payment_rejected', updated_at = ?2 WHERE id = ?3 AND status = ?4")
+                .bind(OrderStatus::Disputed as i32)
+                .bind(app_state.clock.now())
+                .bind(order_id)
+                .bind(OrderStatus::MarkPaid as i32)
+                .execute(&app_state.db)
+                .await?;
+            if result.rows_affected() > 0 {
+                helpers::record_status_transition(&app_state.db, order_id, OrderStatus::MarkPaid, OrderStatus::Disputed, Some("payment_rejected")).await?;
+                warn!("Payment for order {} rejected, order disputed: {}", order_id, reason);
+            }
+            Ok(JobOutcome::Fail(reason))
+        }
+    }
+}
+
+/// Settle a paid order once the batch holding its escrow transfer has had its proof submitted
+async fn check_settlement(app_state: &AppState, order_id: &str, escrow_order_id: Option<&str>) -> Result<JobOutcome> {
+    let Some(escrow_order_id) = escrow_order_id else {
+        return Ok(JobOutcome::Fail("no escrow transfer to check".to_string()));
+    };
+    let Some(escrow) = helpers::get_order_by_id(&app_state.db, escrow_order_id).await? else {
+        return Ok(JobOutcome::Fail(format!("escrow transfer {} not found", escrow_order_id)));
+    };
+    if escrow.status == OrderStatus::Failed {
+        return Ok(JobOutcome::Fail(format!("escrow transfer {} failed", escrow_order_id)));
+    }
+
+    let submitted = match escrow.batch_id {
+        Some(batch_id) => sqlx::query("SELECT 1 FROM proof_submissions WHERE batch_id = ? LIMIT 1")
+            .bind(batch_id as i64)
+            .fetch_optional(&app_state.db)
+            .await?
+            .is_some(),
+        None => false,
+    };
+    let now = app_state.clock.now();
+    if !submitted {
+        return Ok(JobOutcome::RunAt(now + Duration::seconds(app_state.config.jobs.settlement_check_seconds as i64)));
+    }
+
+    let result = sqlx::query("UPDATE orders SET status = ?1, updated_at = ?2 WHERE id = ?3 AND status = ?4")
+        .bind(OrderStatus::Settled as i32)
+        .bind(now)
+        .bind(order_id)
+        .bind(OrderStatus::MarkPaid as i32)
+        .execute(&app_state.db)
+        .await?;
+    if result.rows_affected() > 0 {
+        helpers::record_status_transition(&app_state.db, order_id, OrderStatus::MarkPaid, OrderStatus::Settled, Some("settlement_final")).await?;
+        info!("Order {} settled with batch {:?}", order_id, escrow.batch_id);
+    }
+    Ok(JobOutcome::Done)
+}
+
+/// Fail a lock past its TTL and compensate its saga, as the SLA sweeper would
+async fn expire_lock(app_state: &AppState, order_id: &str) -> Result<JobOutcome> {
+    let policy = SlaPolicy::from_config(&app_state.config).with_clock(app_state.clock.clone());
+    match sla::expire_lock(&app_state.db, &policy, &app_state.matching_engine, &app_state.sla_metrics, order_id).await? {
+        LockExpiry::Until(expires_at) => Ok(JobOutcome::RunAt(expires_at)),
+        LockExpiry::NotLocked => Ok(JobOutcome::Done),
+        LockExpiry::Expired(breach) => {
+            if let Err(e) = app_state.collateral.record_expiry(&breach.order_id).await {
+                error!("Failed to record lock expiry against filler collateral for order {}: {}", breach.order_id, e);
+            }
+            match app_state.settlement_saga.fail(&breach.order_id, breach.reason.as_str()).await {
+                Ok(_) | Err(SagaError::NotFound(_)) => {}
+                Err(e) => error!("Failed to compensate settlement saga for order {}: {}", breach.order_id, e),
+            }
+            Ok(JobOutcome::Done)
+        }
+    }
+}
+
+/// Lock the order for the best-ranked bid of its window that still can; the others lose
+async fn close_lock_window(app_state: &AppState, order_id: &str) -> Result<JobOutcome> {
+    for bid in app_state.lock_window.ranked_bids(order_id).await? {
+        match fillers::commit_lock(app_state, order_id, &bid.filler_id, &bid.amount, bid.requoted_usd_price).await {
+            Ok(_) => {
+                info!("Order {} locked for filler {}, winner of its lock window", order_id, bid.filler_id);
+                app_state.lock_window.decide(order_id, Some(&bid.filler_id)).await?;
+                return Ok(JobOutcome::Done);
+            }
+            Err(e) if e.status.is_server_error() => return Err(anyhow::anyhow!(e.message)),
+            // The order left discovery while the window was open, so no bid can take it
+            Err(e) if e.code == "order_not_lockable" => break,
+            // The filler's other locks took its limit in the meantime; the next bid may still lock
+            Err(e) => warn!("Lock window bid of filler {} on order {} fell through: {}", bid.filler_id, order_id, e.message),
+        }
+    }
+    app_state.lock_window.decide(order_id, None).await?;
+    Ok(JobOutcome::Done)
+}
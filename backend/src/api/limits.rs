@@ -0,0 +1,120 @@
+use axum::{
+    body::HttpBody,
+    extract::{Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::json;
+use std::time::Duration;
+
+use super::{error::ApiError, AppState};
+use crate::config::RequestLimitsConfig;
+
+/// Routes whose POSTs may legitimately run for minutes, given the long timeout; a trailing
+/// `/` covers every path under it
+pub const LONG_RUNNING_ROUTES: &[&str] = &[
+    "/api/v1/batch/prove",
+    "/api/v1/admin/backups",
+    "/api/v1/admin/backups/",
+    "/api/v1/admin/reconciliation/run",
+    "/api/v1/admin/retention/run",
+];
+
+/// Which timeout a request gets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteClass {
+    Read,
+    Write,
+    Long,
+}
+
+impl RouteClass {
+    pub fn of(method: &Method, path: &str) -> Self {
+        let long_running = LONG_RUNNING_ROUTES.iter()
+            .any(|route| *route == path || (route.ends_with('/') && path.starts_with(route)));
+        if long_running && *method == Method::POST {
+            Self::Long
+        } else if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+            Self::Read
+        } else {
+            Self::Write
+        }
+    }
+
+    pub fn timeout(&self, limits: &RequestLimitsConfig) -> Option<Duration> {
+        let seconds = match self {
+            Self::Read => limits.read_timeout_seconds,
+            Self::Write => limits.write_timeout_seconds,
+            Self::Long => limits.long_timeout_seconds,
+        };
+        (seconds > 0).then(|| Duration::from_secs(seconds))
+    }
+}
+
+/// Reject bodies over `max_body_bytes` with 413 and requests running past their timeout with 504
+///
+/// Declared lengths are checked up front; streamed bodies without one are capped by the
+/// `DefaultBodyLimit` layer set to the same size, which answers with a plain 413. A timed-out request is dropped where it
+/// stands, so a timed-out prove leaves its batch queued for the next one.
+pub async fn enforce_request_limits(
+    State(app_state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limits = &app_state.config.request_limits;
+    let path = request.uri().path().to_string();
+
+    let length = request.headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<u64>().ok())
+        .unwrap_or_default()
+        .max(request.body().size_hint().lower());
+    if length > limits.max_body_bytes as u64 {
+        return ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "payload_too_large",
+            format!("Request body of {} bytes for {} is over the {} byte limit", length, path, limits.max_body_bytes),
+        )
+        .with_details(json!({ "max_body_bytes": limits.max_body_bytes }))
+        .into_response();
+    }
+
+    let class = RouteClass::of(request.method(), &path);
+    let Some(timeout) = class.timeout(limits) else {
+        return next.run(request).await;
+    };
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => ApiError::new(
+            StatusCode::GATEWAY_TIMEOUT,
+            "request_timeout",
+            format!("{} did not complete within {} seconds", path, timeout.as_secs()),
+        )
+        .with_details(json!({ "route_class": class, "timeout_seconds": timeout.as_secs() }))
+        .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_classes() {
+        assert_eq!(RouteClass::of(&Method::POST, "/api/v1/batch/prove"), RouteClass::Long);
+        assert_eq!(RouteClass::of(&Method::POST, "/api/v1/admin/backups/42/restore"), RouteClass::Long);
+        assert_eq!(RouteClass::of(&Method::GET, "/api/v1/admin/backups"), RouteClass::Read);
+        assert_eq!(RouteClass::of(&Method::POST, "/api/v1/admin/backups/42/verify"), RouteClass::Long);
+        assert_eq!(RouteClass::of(&Method::POST, "/api/v1/batch/prove/extra"), RouteClass::Write);
+        assert_eq!(RouteClass::of(&Method::POST, "/api/v1/orders"), RouteClass::Write);
+        assert_eq!(RouteClass::of(&Method::GET, "/api/v1/orders"), RouteClass::Read);
+
+        let limits = RequestLimitsConfig { read_timeout_seconds: 0, ..RequestLimitsConfig::default() };
+        assert_eq!(RouteClass::Read.timeout(&limits), None);
+        assert_eq!(RouteClass::Long.timeout(&limits), Some(Duration::from_secs(600)));
+    }
+}
@@ -0,0 +1,60 @@
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use std::net::SocketAddr;
+use tracing::{info, error};
+
+use super::{error::ApiError, AppState};
+use crate::services::market::MarketSummary;
+
+/// Anonymized order book aggregates for market data consumers (GET /market/summary)
+///
+/// Served from the periodically refreshed cache and rate limited per client.
+pub async fn get_market_summary(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> Result<Json<MarketSummary>, ApiError> {
+    let client = client_key(&headers, connect_info.as_ref().map(|info| info.0));
+    info!("Getting market summary for {}", client);
+
+    app_state.market_limiter.check(&client)?;
+
+    let summary = app_state.market_summary.get().await.map_err(|e| {
+        error!("Failed to compute market summary: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(summary))
+}
+
+/// Identify the caller for rate limiting: the first X-Forwarded-For hop when running
+/// behind a proxy, otherwise the peer address
+pub(super) fn client_key(headers: &HeaderMap, peer: Option<SocketAddr>) -> String {
+    headers.get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .map(str::to_string)
+        .or_else(|| peer.map(|peer| peer.ip().to_string()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_key_prefers_forwarded_for() {
+        let peer: SocketAddr = "10.0.0.1:5000".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        assert_eq!(client_key(&headers, Some(peer)), "10.0.0.1");
+        assert_eq!(client_key(&headers, None), "unknown");
+
+        headers.insert("x-forwarded-for", "203.0.113.7, 10.0.0.1".parse().unwrap());
+        assert_eq!(client_key(&headers, Some(peer)), "203.0.113.7");
+    }
+}
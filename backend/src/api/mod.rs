@@ -1,20 +1,77 @@
+use axum::http::HeaderValue;
 use sqlx::SqlitePool;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
+use tower_http::cors::{Any, CorsLayer};
+use tracing::warn;
 use crate::config::Config;
 use crate::services::{
     matching_engine::MatchingEngine,
+    match_policy::policy_for,
     batch_processor::BatchProcessor,
-    relayer::{RelayerService, RelayerConfig},
+    batch_prover::BatchProver,
+    batch_journal::BatchJournal,
+    relayer::{RelayerService, RelayerConfig, RelayerMetrics},
+    event_bus::EventBus,
+    sla::SlaMetrics,
+    archival::ArchiveService,
+    backups::BackupService,
+    proof_cache::ProofCache,
+    verification_cache::VerificationCache,
+    invariants::InvariantMode,
+    maintenance::MaintenanceMode,
+    handover::WriterLease,
+    rates::RateService,
+    market::MarketSummaryCache,
+    request_limiter::ClientRateLimiter,
+    balance_alerts::BalanceAlerts,
+    claims::ClaimReconciler,
+    receipts::ReceiptIssuer,
+    attestations::BalanceAttester,
+    manifests::ManifestPublisher,
+    blob_store::blob_store_from_config,
+    registry::RegistryCache,
+    slo::SloTracker,
+    settlement_saga::SettlementSaga,
+    order_reconciliation::OrderReconciler,
+    payment_proofs::PaymentVerifier,
+    scheduler::Scheduler,
+    collateral::CollateralService,
+    quotes::QuoteService,
+    screening::Screener,
+    order_review::OrderReview,
+    lock_window::LockWindow,
+    notifications::{notification_transports_from_config, NotificationService},
 };
-use crate::blockchain::BlockchainClient;
+use vapor_core::services::blob_store::BlobStore;
+use vapor_core::services::clock::{system_clock, SharedClock};
+use vapor_chain::{chain_reads::ChainReadCache, BlockchainClient};
+use vapor_chain::signer::Signer;
 
+pub mod error;
 pub mod health;
+pub mod admin;
 pub mod orders;
 pub mod batch;
 pub mod proofs;
 pub mod relayer;
 pub mod fillers;
+pub mod market;
+pub mod graphql;
+pub mod order_queue;
+pub mod accounts;
+pub mod overview;
+pub mod explorer;
+pub mod limits;
+pub mod caching;
+pub mod jobs;
+pub mod events;
+pub mod handover;
+pub mod sync;
+pub mod notifications;
+#[cfg(feature = "fault-injection")]
+pub mod faults;
 
 #[cfg(test)]
 pub mod tests;
@@ -25,19 +82,169 @@ pub struct AppState {
     pub db: SqlitePool,
     pub matching_engine: Arc<Mutex<MatchingEngine>>,
     pub batch_processor: Arc<Mutex<BatchProcessor>>,
+    pub batch_prover: Arc<Mutex<BatchProver>>,
     pub blockchain_client: Option<Arc<BlockchainClient>>,
     pub relayer_service: Option<Arc<Mutex<RelayerService>>>,
+    pub relayer_metrics: RelayerMetrics,
+    /// Cached chain reads and their hit/miss counts, shared with the blockchain client
+    pub chain_reads: ChainReadCache,
+    pub event_bus: EventBus,
+    pub sla_metrics: Arc<Mutex<SlaMetrics>>,
+    pub archive: ArchiveService,
+    pub backups: BackupService,
+    pub proof_cache: ProofCache,
+    /// Proof verification results and batch public inputs reused across verify requests
+    pub verification_cache: VerificationCache,
+    pub maintenance: MaintenanceMode,
+    /// Whether this instance is the one allowed to write, and its handovers
+    pub writer_lease: WriterLease,
+    pub rates: RateService,
+    pub market_summary: MarketSummaryCache,
+    pub market_limiter: ClientRateLimiter,
+    pub explorer_limiter: ClientRateLimiter,
+    pub graphql: graphql::VaporSchema,
+    pub balance_alerts: BalanceAlerts,
+    pub claim_reconciler: ClaimReconciler,
+    pub receipts: ReceiptIssuer,
+    /// Operator-signed balance attestations served alongside account proofs
+    pub attestations: BalanceAttester,
+    pub manifests: ManifestPublisher,
+    pub blobs: Arc<dyn BlobStore>,
+    pub registry: RegistryCache,
+    pub slo: SloTracker,
+    pub settlement_saga: SettlementSaga,
+    pub order_reconciler: OrderReconciler,
+    /// Delayed payment verification, settlement checks and lock expiry
+    pub scheduler: Scheduler,
+    pub payment_verifier: PaymentVerifier,
+    pub collateral: CollateralService,
+    /// Quotes handed out by POST /orders/quote and held until an order commits to them
+    pub quotes: QuoteService,
+    /// Sanctions screening of order, lock and claim addresses
+    pub screening: Screener,
+    /// High-value orders held for an admin's approval before Discovery
+    pub order_review: OrderReview,
+    /// Lock attempts collected per order before one wins, when a lock window is configured
+    pub lock_window: LockWindow,
+    /// Order lifecycle notifications by each user's channels, and their delivery
+    pub notifications: NotificationService,
+    /// Time source shared by the matching engine, batch processor and timers
+    pub clock: SharedClock,
 }
 
 impl AppState {
     pub fn new(config: Config, db: SqlitePool) -> Self {
+        Self::new_with_clock(config, db, system_clock())
+    }
+
+    /// Build the state with every time-dependent service reading `clock`
+    pub fn new_with_clock(config: Config, db: SqlitePool, clock: SharedClock) -> Self {
+        let matching_engine = MatchingEngine::new()
+            .with_clock(clock.clone())
+            .with_filler_limits(config.filler.default_limits, config.filler.limits.clone())
+            .with_policy(policy_for(config.filler.match_policy, &config.filler.priority_fee_tiers))
+            .with_max_boosts(config.filler.match_max_boosts);
+        let batch_processor = BatchProcessor::new()
+            .with_clock(clock.clone())
+            .with_withdrawal_limits(config.withdrawal.clone())
+            .with_order_amounts(config.order_amounts.clone())
+            .with_batch_caps(config.batch.bridge_out_caps.clone())
+            .with_node_cache_capacity(config.batch.merkle_node_cache_capacity)
+            .with_invariant_checks(InvariantMode::for_profile(config.batch.invariant_checks, config.profile))
+            .with_journal(BatchJournal::spawn(db.clone()));
+        let batch_prover = BatchProver::new(batch_processor.proving_queue.clone())
+            .with_proof_submission(&config.proof_submission, &config.blockchain.proof_verifier_address)
+            .with_stats(db.clone());
+        let matching_engine = Arc::new(Mutex::new(matching_engine));
+        let batch_processor = Arc::new(Mutex::new(batch_processor));
+        let blobs = blob_store_from_config(&config.blob_store);
+        let archive = ArchiveService::new(db.clone(), blobs.clone(), &config.archive);
+        let backups = BackupService::new(db.clone(), blobs.clone(), &config.backup);
+        let proof_cache = ProofCache::new(db.clone(), config.batch.precompute_proof_batches);
+        let maintenance = MaintenanceMode::new(db.clone());
+        let writer_lease = WriterLease::new(db.clone(), &config.handover, maintenance.clone());
+        let rates = RateService::new(&config.rates);
+        let market_summary = MarketSummaryCache::new(db.clone(), config.filler.clone());
+        let market_limiter = ClientRateLimiter::new(config.market.rate_limit_per_minute);
+        let explorer_limiter = ClientRateLimiter::new(config.explorer.rate_limit_per_minute);
+        let balance_alerts = BalanceAlerts::new(&config.balance_alerts);
+        let claim_reconciler = ClaimReconciler::new(config.claims.reconcile_from_block);
+        let receipts = ReceiptIssuer::new(db.clone(), clock.clone());
+        let attestations = BalanceAttester::new(&config.attestations);
+        let manifests = ManifestPublisher::new(db.clone(), &config.manifests);
+        let event_bus = EventBus::default();
+        let registry = RegistryCache::new(db.clone(), Duration::from_secs(config.registry.cache_ttl_seconds), &event_bus);
+        let slo = SloTracker::new(db.clone(), config.slo.clone());
+        let settlement_saga = SettlementSaga::new(
+            db.clone(),
+            matching_engine.clone(),
+            batch_processor.clone(),
+            event_bus.clone(),
+            clock.clone(),
+        );
+        let order_reconciler = OrderReconciler::new(
+            db.clone(),
+            matching_engine.clone(),
+            clock.clone(),
+            config.reconciliation.clone(),
+            config.claims.reconcile_from_block,
+        );
+        let scheduler = Scheduler::new(db.clone(), clock.clone(), &config.jobs);
+        let payment_verifier = PaymentVerifier::new(config.jobs.payment_verifier_url.clone());
+        let collateral = CollateralService::new(db.clone(), &config.collateral, clock.clone());
+        let quotes = QuoteService::new(db.clone(), &config.quotes, clock.clone());
+        let screening = Screener::new(db.clone(), &config.screening, clock.clone());
+        let order_review = OrderReview::new(db.clone(), &config.order_review, clock.clone());
+        let lock_window = LockWindow::new(db.clone(), &config.filler, clock.clone());
+        let notifications = notification_transports_from_config(&config.notifications)
+            .into_iter()
+            .fold(NotificationService::new(db.clone(), &config.notifications, clock.clone()), |service, (channel, transport)| {
+                service.with_transport(channel, transport)
+            });
+        let chain_reads = ChainReadCache::new(config.blockchain.read_cache_seconds);
+        let verification_cache = VerificationCache::new(config.batch.verify_cache_seconds, config.batch.verify_cache_capacity);
         Self { 
             config, 
             db,
-            matching_engine: Arc::new(Mutex::new(MatchingEngine::new())),
-            batch_processor: Arc::new(Mutex::new(BatchProcessor::new())),
+            matching_engine,
+            batch_processor,
+            batch_prover: Arc::new(Mutex::new(batch_prover)),
             blockchain_client: None, // Initialize later with proper config
             relayer_service: None, // Initialize later with blockchain client
+            relayer_metrics: RelayerMetrics::default(),
+            chain_reads,
+            event_bus,
+            sla_metrics: Arc::new(Mutex::new(SlaMetrics::default())),
+            archive,
+            backups,
+            proof_cache,
+            verification_cache,
+            maintenance,
+            writer_lease,
+            rates,
+            market_summary,
+            market_limiter,
+            explorer_limiter,
+            graphql: graphql::build_schema(),
+            balance_alerts,
+            claim_reconciler,
+            receipts,
+            attestations,
+            manifests,
+            blobs,
+            registry,
+            slo,
+            settlement_saga,
+            order_reconciler,
+            scheduler,
+            payment_verifier,
+            collateral,
+            quotes,
+            screening,
+            order_review,
+            lock_window,
+            notifications,
+            clock,
         }
     }
     
@@ -46,8 +253,51 @@ impl AppState {
         self
     }
     
+    /// Sign inclusion receipts with the operator key
+    pub fn with_receipt_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.receipts = self.receipts.with_signer(signer);
+        self
+    }
+
+    /// Sign balance attestations with the operator key
+    pub fn with_attestation_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.attestations = self.attestations.with_signer(signer);
+        self
+    }
+
+    /// Sign batch manifests with the operator key
+    pub fn with_manifest_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.manifests = self.manifests.with_signer(signer);
+        self
+    }
+
+    /// Sign order quotes with the operator key
+    pub fn with_quote_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.quotes = self.quotes.with_signer(signer);
+        self
+    }
+    
     pub async fn with_relayer_service(mut self, relayer: RelayerService) -> Self {
         self.relayer_service = Some(Arc::new(Mutex::new(relayer)));
         self
     }
 }
+
+/// Any origin in the dev profile, otherwise only `CORS_ALLOWED_ORIGINS`
+pub fn cors_layer(config: &Config) -> CorsLayer {
+    if config.profile.allows_permissive_cors() {
+        return CorsLayer::permissive();
+    }
+
+    let origins: Vec<HeaderValue> = config.api.cors_allowed_origins.iter()
+        .filter_map(|origin| {
+            HeaderValue::from_str(origin)
+                .inspect_err(|_| warn!("Ignoring invalid CORS origin '{}'", origin))
+                .ok()
+        })
+        .collect();
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(Any)
+        .allow_headers(Any)
+}
@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use super::{error::ApiError, AppState};
+use vapor_chain::hex_to_address;
+use vapor_core::services::notifications::{Notification, NotificationPreference};
+
+/// Notifications listed unless the request asks for another count
+const DEFAULT_HISTORY_LIMIT: u32 = 50;
+const MAX_HISTORY_LIMIT: u32 = 500;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotificationPreferences {
+    pub preferences: Vec<NotificationPreference>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationHistoryQuery {
+    pub limit: Option<u32>,
+}
+
+fn parse_address(address: &str) -> Result<String, ApiError> {
+    hex_to_address(address)
+        .map(|_| address.to_lowercase())
+        .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, "invalid_address", format!("'{}' is not an address", address)))
+}
+
+/// Channels and events an address is notified on
+pub async fn get_notification_preferences(
+    State(app_state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<Json<NotificationPreferences>, ApiError> {
+    let address = parse_address(&address)?;
+    let preferences = app_state.notifications.preferences(&address).await?;
+    Ok(Json(NotificationPreferences { preferences }))
+}
+
+/// Replace an address's notification preferences; a channel without events is removed
+pub async fn set_notification_preferences(
+    State(app_state): State<AppState>,
+    Path(address): Path<String>,
+    Json(req): Json<NotificationPreferences>,
+) -> Result<Json<NotificationPreferences>, ApiError> {
+    let address = parse_address(&address)?;
+    info!("Setting {} notification channels for {}", req.preferences.len(), address);
+    let preferences = app_state.notifications.set_preferences(&address, &req.preferences).await?;
+    Ok(Json(NotificationPreferences { preferences }))
+}
+
+/// Notifications sent to an address, newest first, with their delivery status and attempts
+pub async fn get_notifications(
+    State(app_state): State<AppState>,
+    Path(address): Path<String>,
+    Query(query): Query<NotificationHistoryQuery>,
+) -> Result<Json<Vec<Notification>>, ApiError> {
+    let address = parse_address(&address)?;
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).min(MAX_HISTORY_LIMIT);
+    let notifications = app_state.notifications.history(&address, limit).await.map_err(|e| {
+        error!("Failed to list notifications of {}: {}", address, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(notifications))
+}
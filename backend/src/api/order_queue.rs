@@ -0,0 +1,314 @@
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use redis::{
+    aio::MultiplexedConnection,
+    streams::{StreamId, StreamReadOptions, StreamReadReply},
+    AsyncCommands, RedisResult,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::time::Duration;
+use tracing::{info, warn, error};
+
+use super::{error::ApiError, orders, AppState};
+use crate::config::OrderQueueConfig;
+use crate::models::CreateOrderRequest;
+
+/// How long a read waits for new messages before checking maintenance mode again
+const BLOCK_MS: usize = 5000;
+/// Pause before retrying a message that hit a transient error
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// An order pushed to the stream as `XADD <stream> * message_id <id> order <CreateOrderRequest JSON>`
+#[derive(Debug, Deserialize)]
+pub struct QueuedOrder {
+    /// Producer-chosen idempotency key; a retried XADD must reuse it
+    pub message_id: String,
+    pub order: CreateOrderRequest,
+}
+
+impl QueuedOrder {
+    fn from_entry(entry: &StreamId) -> Result<Self> {
+        let message_id: String = entry.get("message_id").ok_or_else(|| anyhow!("missing message_id field"))?;
+        let order: String = entry.get("order").ok_or_else(|| anyhow!("missing order field"))?;
+        Ok(Self {
+            message_id,
+            order: serde_json::from_str(&order).context("invalid order JSON")?,
+        })
+    }
+}
+
+/// What became of a consumed message
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Created(String),
+    /// Consumed before, e.g. redelivered because the server stopped before acknowledging it
+    Duplicate,
+    /// Failed validation; acknowledged so it is not redelivered
+    Rejected(String),
+}
+
+/// A consumed message and the order it created (GET /order-queue/messages/:message_id)
+#[derive(Debug, Serialize)]
+pub struct QueuedMessage {
+    pub message_id: String,
+    pub order_id: Option<String>,
+    /// Why the order was rejected
+    pub error: Option<String>,
+    pub received_at: DateTime<Utc>,
+}
+
+/// Turn a queued message into an order through the same path as POST /orders, at most once
+/// per message id
+///
+/// Errors are transient (database, matching engine); the message stays unacknowledged and is
+/// processed again.
+pub async fn process(app_state: &AppState, message: QueuedOrder) -> Result<Outcome> {
+    if get_message(&app_state.db, &message.message_id).await?.is_some() {
+        return Ok(Outcome::Duplicate);
+    }
+
+    match orders::submit_order(app_state, message.order).await {
+        Ok(order) => {
+            record_message(&app_state.db, &message.message_id, Some(&order.id), None).await?;
+            Ok(Outcome::Created(order.id))
+        }
+        Err(e) if e.status.is_client_error() => {
+            record_message(&app_state.db, &message.message_id, None, Some(&e.message)).await?;
+            Ok(Outcome::Rejected(e.message))
+        }
+        Err(e) => Err(anyhow!("{} ({})", e.message, e.code)),
+    }
+}
+
+async fn record_message(db: &SqlitePool, message_id: &str, order_id: Option<&str>, error: Option<&str>) -> Result<()> {
+    sqlx::query("INSERT OR IGNORE INTO queued_order_messages (message_id, order_id, error, received_at) VALUES (?1, ?2, ?3, ?4)")
+        .bind(message_id)
+        .bind(order_id)
+        .bind(error)
+        .bind(Utc::now())
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+async fn get_message(db: &SqlitePool, message_id: &str) -> Result<Option<QueuedMessage>> {
+    let row = sqlx::query("SELECT message_id, order_id, error, received_at FROM queued_order_messages WHERE message_id = ?1")
+        .bind(message_id)
+        .fetch_optional(db)
+        .await?;
+
+    row.map(|row| {
+        Ok(QueuedMessage {
+            message_id: row.try_get("message_id")?,
+            order_id: row.try_get("order_id")?,
+            error: row.try_get("error")?,
+            received_at: row.try_get("received_at")?,
+        })
+    })
+    .transpose()
+}
+
+/// Look up the order created from a queued message
+pub async fn get_queued_message(
+    State(app_state): State<AppState>,
+    Path(message_id): Path<String>,
+) -> Result<Json<QueuedMessage>, ApiError> {
+    info!("Getting queued order message {}", message_id);
+
+    match get_message(&app_state.db, &message_id).await {
+        Ok(Some(message)) => Ok(Json(message)),
+        Ok(None) => Err(StatusCode::NOT_FOUND.into()),
+        Err(e) => {
+            error!("Database error fetching queued message {}: {}", message_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into())
+        }
+    }
+}
+
+/// Consume the order stream until the Redis connection fails
+///
+/// Messages are acknowledged once their order is stored or rejected, so a crash in between
+/// redelivers them (at least once); the message id makes the redelivery a no-op.
+pub async fn run(app_state: &AppState) -> Result<()> {
+    let config = &app_state.config.order_queue;
+    let url = config.redis_url.as_deref().ok_or_else(|| anyhow!("ORDER_QUEUE_REDIS_URL is not set"))?;
+    let mut conn = redis::Client::open(url)?.get_multiplexed_tokio_connection().await?;
+    ensure_group(&mut conn, config).await?;
+    info!("Consuming orders from stream {} as {}/{}", config.stream, config.group, config.consumer);
+
+    // Start with messages delivered to this consumer but never acknowledged
+    let mut read_pending = true;
+    loop {
+        // Same as the REST intake, which rejects writes during maintenance
+        if app_state.maintenance.is_enabled() {
+            tokio::time::sleep(Duration::from_millis(BLOCK_MS as u64)).await;
+            continue;
+        }
+
+        let mut options = StreamReadOptions::default()
+            .group(&config.group, &config.consumer)
+            .count(config.batch_size.max(1));
+        if !read_pending {
+            options = options.block(BLOCK_MS);
+        }
+        let start = if read_pending { "0" } else { ">" };
+        let reply: StreamReadReply = conn.xread_options(&[&config.stream], &[start], &options).await?;
+        let entries: Vec<StreamId> = reply.keys.into_iter().flat_map(|key| key.ids).collect();
+
+        if read_pending && entries.is_empty() {
+            read_pending = false;
+            continue;
+        }
+
+        for entry in entries {
+            if handle_entry(app_state, &entry).await {
+                let _: i64 = conn.xack(&config.stream, &config.group, &[&entry.id]).await?;
+            } else {
+                // Leave it pending and pick it up again before reading new messages
+                read_pending = true;
+                tokio::time::sleep(RETRY_DELAY).await;
+                break;
+            }
+        }
+    }
+}
+
+/// Create the consumer group, and the stream, on first start; the group reads from the beginning
+async fn ensure_group(conn: &mut MultiplexedConnection, config: &OrderQueueConfig) -> Result<()> {
+    let created: RedisResult<()> = conn.xgroup_create_mkstream(&config.stream, &config.group, "0").await;
+    match created {
+        Ok(()) => {
+            info!("Created consumer group {} on stream {}", config.group, config.stream);
+            Ok(())
+        }
+        Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Process one stream entry; returns whether it can be acknowledged
+async fn handle_entry(app_state: &AppState, entry: &StreamId) -> bool {
+    let message = match QueuedOrder::from_entry(entry) {
+        Ok(message) => message,
+        Err(e) => {
+            warn!("Dropping malformed order queue entry {}: {:#}", entry.id, e);
+            return true;
+        }
+    };
+
+    let message_id = message.message_id.clone();
+    match process(app_state, message).await {
+        Ok(Outcome::Created(order_id)) => {
+            info!("Queued message {} created order {}", message_id, order_id);
+            true
+        }
+        Ok(Outcome::Duplicate) => {
+            info!("Queued message {} was already consumed", message_id);
+            true
+        }
+        Ok(Outcome::Rejected(reason)) => {
+            warn!("Queued message {} rejected: {}", message_id, reason);
+            true
+        }
+        Err(e) => {
+            error!("Failed to process queued message {}, will retry: {:#}", message_id, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::models::OrderType;
+    use redis::Value;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    async fn app_state() -> AppState {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        AppState::new(Config::default(), db)
+    }
+
+    fn bridge_in() -> CreateOrderRequest {
+        serde_json::from_value(json!({
+            "order_type": "BridgeIn",
+            "from_address": "0x1234567890123456789012345678901234567890",
+            "token_id": 1,
+            "amount": "1000000",
+            "bank_account": "12345678",
+            "bank_service": "PayPal Hong Kong",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_stream_entry_parsing() {
+        let field = |value: &str| Value::Data(value.as_bytes().to_vec());
+        let entry = StreamId {
+            id: "1-0".to_string(),
+            map: HashMap::from([
+                ("message_id".to_string(), field("mm-bot-42")),
+                ("order".to_string(), field(&serde_json::to_string(&bridge_in()).unwrap())),
+            ]),
+        };
+        let message = QueuedOrder::from_entry(&entry).unwrap();
+        assert_eq!(message.message_id, "mm-bot-42");
+        assert_eq!(message.order.order_type, OrderType::BridgeIn);
+
+        let missing_order = StreamId {
+            id: "2-0".to_string(),
+            map: HashMap::from([("message_id".to_string(), field("mm-bot-43"))]),
+        };
+        assert!(QueuedOrder::from_entry(&missing_order).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_redelivered_messages_create_one_order() {
+        let app_state = app_state().await;
+        let message = || QueuedOrder { message_id: "mm-bot-1".to_string(), order: bridge_in() };
+
+        let Outcome::Created(order_id) = process(&app_state, message()).await.unwrap() else {
+            panic!("expected an order to be created");
+        };
+        assert_eq!(process(&app_state, message()).await.unwrap(), Outcome::Duplicate);
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM orders").fetch_one(&app_state.db).await.unwrap();
+        assert_eq!(count, 1);
+        let recorded = get_message(&app_state.db, "mm-bot-1").await.unwrap().unwrap();
+        assert_eq!(recorded.order_id, Some(order_id));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_orders_are_rejected_once() {
+        let app_state = app_state().await;
+        let mut order = bridge_in();
+        order.order_type = OrderType::Transfer;
+        order.permit = Some(serde_json::from_value(json!({
+            "owner": "0x1234567890123456789012345678901234567890",
+            "spender": "0x0000000000000000000000000000000000000000",
+            "value": "1000000",
+            "nonce": null,
+            "deadline": 4102444800u64,
+            "signature": format!("0x{}", "11".repeat(65)),
+        })).unwrap());
+
+        // Permits are only valid on BridgeIn orders, as on the REST endpoint
+        let outcome = process(&app_state, QueuedOrder { message_id: "mm-bot-2".to_string(), order }).await.unwrap();
+        assert!(matches!(outcome, Outcome::Rejected(_)));
+        let recorded = get_message(&app_state.db, "mm-bot-2").await.unwrap().unwrap();
+        assert!(recorded.order_id.is_none());
+        assert!(recorded.error.is_some());
+
+        let redelivered = QueuedOrder { message_id: "mm-bot-2".to_string(), order: bridge_in() };
+        assert_eq!(process(&app_state, redelivered).await.unwrap(), Outcome::Duplicate);
+    }
+}
@@ -4,25 +4,36 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
-use tracing::{info, warn, error};
-use uuid::Uuid;
+use tracing::{info, warn, error, instrument, Span};
 use chrono::Utc;
 use sqlx::Row;
 
-use super::AppState;
-use crate::models::{CreateOrderRequest, OrderResponse, OrderStatusResponse, Order, OrderType, OrderStatus};
+use super::{error::ApiError, AppState};
+use crate::models::{filler_settlement_address, new_order_id, page_size, paginate, Cursor, MAX_PAGE_SIZE, CreateOrderRequest, OrderResponse, OrderStatusResponse, Order, OrderType, OrderStatus, SettlePartialRequest};
+use vapor_core::fault_injection::{self, FaultTarget};
+use crate::services::{batch_processor::BatchError, claim_fees, deposit_reference, duplicates::{self, DuplicateOrderError}, event_log::{self, DomainEvent}, order_costs, order_limits, quotes::{Quote, QuoteTerms}, rates::format_rate, receipts::InclusionReceipt, scheduler::JobKind, screening::ScreeningContext, settlement, market::UNSPECIFIED_BANK_SERVICE, slo::{SloPhase, SloReport}, settlement_saga::SagaError, system_accounts, transfers::{self, TransferRequest}, withdrawal_limits};
+use crate::config::DuplicateMode;
+use vapor_chain::signer::SignatureVerification;
 
 #[derive(Debug, Deserialize)]
 pub struct OrderQuery {
     pub status: Option<String>,
     pub order_type: Option<String>,
+    /// Page size, 100 by default and at most
     pub limit: Option<usize>,
+    /// "asc" for oldest first; newest first by default
+    pub sort: Option<String>,
+    /// `next_cursor` of the previous page, in the same sort
+    pub after: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct OrdersListResponse {
     pub orders: Vec<OrderResponse>,
     pub total: usize,
+    /// Pass as `after` to fetch the next page; unset on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -34,19 +45,122 @@ pub struct MatchResponse {
 }
 
 /// Create a new order (BridgeIn/Transfer/BridgeOut)
+#[instrument(skip_all, fields(order_id = tracing::field::Empty, order_type = ?req.order_type))]
 pub async fn create_order(
     State(app_state): State<AppState>,
     Json(req): Json<CreateOrderRequest>,
-) -> Result<Json<OrderResponse>, StatusCode> {
+) -> Result<Json<OrderResponse>, ApiError> {
+    submit_order(&app_state, req).await.map(Json)
+}
+
+/// Validate, store and route a new order; shared by the REST handler and the order queue
+pub(crate) async fn submit_order(app_state: &AppState, req: CreateOrderRequest) -> Result<OrderResponse, ApiError> {
     info!("Creating order: {:?}", req);
     
+    // Permits only make sense for deposits into the bridge
+    let permit = req.permit.clone();
+    if let Some(permit) = &permit {
+        if req.order_type != OrderType::BridgeIn {
+            warn!("Permit supplied for non-BridgeIn order");
+            return Err(ApiError::new(StatusCode::BAD_REQUEST, "invalid_permit", "Permits are only accepted on BridgeIn orders"));
+        }
+        let checked = permit
+            .validate(&req.amount, req.from_address.as_deref(), &app_state.config.blockchain.contract_address, app_state.clock.now())
+            .and_then(|_| {
+                let domain_separator = app_state.config.blockchain.permit_domain_separators.get(&req.token_id)
+                    .ok_or_else(|| format!("Permits are not accepted for token {}", req.token_id))?;
+                vapor_chain::permit::verify_signature(permit, domain_separator)
+            });
+        if let Err(e) = checked {
+            warn!("Invalid permit: {}", e);
+            return Err(ApiError::new(StatusCode::BAD_REQUEST, "invalid_permit", e));
+        }
+    }
+    
+    // Priority fees only order the matching engine's queue, which takes BridgeIn orders
+    let priority_fee = req.priority_fee.unwrap_or(0);
+    if priority_fee > 0 && req.order_type != OrderType::BridgeIn {
+        warn!("Priority fee supplied for non-BridgeIn order");
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    
+    // System accounts are moved by the operator, never on a user's order
+    if let Some(from_address) = &req.from_address {
+        system_accounts::check_user_address(from_address)?;
+    }
+
+    let confirm_duplicate = req.confirm_duplicate;
+    let quote_id = req.quote_id.clone();
+    let quote_terms = QuoteTerms::of(&req);
+
     // Create new order
-    let order = Order::new(req);
+    let mut order = Order::new_at(req, app_state.clock.now());
+    Span::current().record("order_id", order.id.as_str());
+
+    // Tokens and bank services switched off in the registries take no new orders
+    app_state.registry.check_order(order.token_id, order.bank_service.as_deref()).await?;
+
+    order_limits::check(&app_state.config.order_amounts, &order)?;
+
+    // Deposits into a corridor whose fillers are already spoken for would only sit in Discovery
+    if order.order_type == OrderType::BridgeIn && app_state.config.market.throttle_saturated_corridors {
+        app_state.market_summary.check_intake(order.token_id, order.bank_service.as_deref())?;
+    }
+
+    // Sanctioned addresses neither send nor receive through the bridge
+    let addresses: Vec<&str> = [order.from_address.as_deref(), order.to_address.as_deref()].into_iter().flatten().collect();
+    app_state.screening.screen(ScreeningContext::Order, &order.id, &addresses).await?;
+
+    // Risk controls: allow/deny lists and daily limits on withdrawals
+    if order.order_type == OrderType::BridgeOut {
+        withdrawal_limits::check_bridge_out(&app_state.db, &app_state.config.withdrawal, &order, app_state.clock.now()).await?;
+    }
+
+    // Sellers double-submit the same off-ramp; flag it, or hold it back until confirmed
+    let duplicate = duplicates::find_suspected_duplicate(&app_state.db, &app_state.config.order_duplicates, &order)
+        .await
+        .map_err(|e| {
+            error!("Database error checking for duplicate orders: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if let Some(duplicate) = &duplicate {
+        if app_state.config.order_duplicates.mode == DuplicateMode::Block && !confirm_duplicate {
+            return Err(DuplicateOrderError(duplicate.clone()).into());
+        }
+        warn!("Order {} looks like a duplicate of order {}", order.id, duplicate.original_order_id);
+    }
+
+    // BridgeIn deposits carry this reference so the relayer can attribute them to the order
+    let deposit_reference = (order.order_type == OrderType::BridgeIn).then(|| {
+        deposit_reference::format_reference(&deposit_reference::derive_deposit_reference(
+            &app_state.config.blockchain.deposit_reference_salt,
+            &order.id,
+        ))
+    });
+    
+    // The token's current price is the order's quote; locks re-validate it against slippage.
+    // Tokens without a configured price are not quoted. An order committing to a quote from
+    // POST /orders/quote takes the price it was quoted instead.
+    let quote = match &quote_id {
+        Some(quote_id) => Some(app_state.quotes.commit(quote_id, &quote_terms, &order.id).await?),
+        None => None,
+    };
+    let (quoted_usd_price, rate_quoted_at) = match &quote {
+        Some(quote) => (quote.usd_price, quote.created_at),
+        None => (app_state.rates.usd_price(order.token_id).ok(), order.created_at),
+    };
+
+    if let Err(e) = fault_injection::inject(FaultTarget::Database).await {
+        error!("Database error creating order: {}", e);
+        release_quote(app_state, quote_id.as_deref(), &order.id).await;
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+    }
     
     // Save to database (simplified for MVP)
     let query = r#"
-        INSERT INTO orders (id, order_type, status, from_address, to_address, token_id, amount, banking_hash, created_at, updated_at)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+        INSERT INTO orders (id, order_type, status, from_address, to_address, token_id, amount, banking_hash, created_at, updated_at, deposit_reference, bank_account, bank_service,
+                            quoted_usd_price, rate_quoted_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
     "#;
     
     let result = sqlx::query(query)
@@ -60,19 +174,46 @@ pub async fn create_order(
         .bind(&order.banking_hash)
         .bind(order.created_at)
         .bind(order.updated_at)
+        .bind(&deposit_reference)
+        .bind(&order.bank_account)
+        .bind(&order.bank_service)
+        .bind(quoted_usd_price.map(|price| price as i64))
+        .bind(quoted_usd_price.map(|_| rate_quoted_at))
         .execute(&app_state.db)
         .await;
 
     match result {
         Ok(_) => {
             info!("Order saved to database: {}", order.id);
+            if let Err(e) = event_log::append(&app_state.db, &DomainEvent::order_created(&order)).await {
+                error!("Failed to log creation of order {}: {}", order.id, e);
+            }
+            
+            if let Some(permit) = &permit {
+                crate::database::helpers::insert_order_permit(&app_state.db, &order.id, permit)
+                    .await
+                    .map_err(|e| {
+                        error!("Database error storing permit: {}", e);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?;
+            }
             
+            // High-value orders wait for an admin's approval before matching
+            let held = app_state.order_review.hold_if_required(&order).await.map_err(|e| {
+                error!("Database error holding order {} for review: {}", order.id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+            if held {
+                order.status = OrderStatus::ReviewPending;
+            }
+
             // Process order based on type
             match order.order_type {
+                OrderType::BridgeIn if held => info!("Order {} held for review", order.id),
                 OrderType::BridgeIn => {
                     // Add to matching engine for P2P matching
                     let mut engine = app_state.matching_engine.lock().await;
-                    if let Err(e) = engine.add_order(order.clone()) {
+                    if let Err(e) = engine.add_order_with_priority_fee(order.clone(), priority_fee) {
                         error!("Failed to add order to matching engine: {}", e);
                     } else {
                         info!("Order added to matching engine: {}", order.id);
@@ -89,27 +230,190 @@ pub async fn create_order(
                         }
                     }
                     
-                    if let Err(e) = processor.add_order_to_batch(order.clone()) {
-                        error!("Failed to add order to batch: {}", e);
-                    } else {
-                        info!("Order added to batch: {}", order.id);
+                    match processor.add_order_to_batch(order.clone()) {
+                        Err(BatchError::WithdrawalLimit(e)) => {
+                            drop(processor);
+                            reject_over_limit_order(app_state, &order).await?;
+                            return Err(e.into());
+                        }
+                        Err(BatchError::Deferred(e)) => warn!("Order {} held for a later batch: {}", order.id, e),
+                        Err(e) => error!("Failed to add order to batch: {}", e),
+                        Ok(()) => info!("Order added to batch: {}", order.id),
                     }
                 }
             }
             
-            let response = OrderResponse::from(&order);
+            let mut response = OrderResponse::from(&order);
+            response.deposit_reference = deposit_reference;
+            response.quoted_rate = quoted_usd_price.map(format_rate);
+            response.duplicate_warning = duplicate;
             
             info!("Order created successfully: {}", order.id);
-            Ok(Json(response))
+            Ok(response)
         }
         Err(e) => {
             error!("Database error creating order: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            release_quote(app_state, quote_id.as_deref(), &order.id).await;
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into())
         }
     }
 }
 
+/// Free the quote an order committed to when the order itself could not be stored
+async fn release_quote(app_state: &AppState, quote_id: Option<&str>, order_id: &str) {
+    let Some(quote_id) = quote_id else {
+        return;
+    };
+    if let Err(e) = app_state.quotes.release(quote_id, order_id).await {
+        error!("Failed to release quote {} after order {} was not stored: {}", quote_id, order_id, e);
+    }
+}
+
+/// Quote an order before committing to it (POST /orders/quote)
+///
+/// The quote holds the token's current price, the claim fee and the corridor's expected time
+/// to settlement until it expires; passing its `quote_id` to POST /orders creates the order
+/// on those terms, provided the order's type, token, amount and bank service match.
+#[instrument(skip_all, fields(order_type = ?terms.order_type, token_id = terms.token_id))]
+pub async fn quote_order(
+    State(app_state): State<AppState>,
+    Json(terms): Json<QuoteTerms>,
+) -> Result<Json<Quote>, ApiError> {
+    info!("Quoting order: {:?}", terms);
+
+    app_state.registry.check_order(terms.token_id, terms.bank_service.as_deref()).await?;
+    let probe = Order::new_at(CreateOrderRequest {
+        order_type: terms.order_type,
+        from_address: None,
+        to_address: None,
+        token_id: terms.token_id,
+        amount: terms.amount.clone(),
+        bank_account: None,
+        bank_service: terms.bank_service.clone(),
+        banking_hash: None,
+        permit: None,
+        priority_fee: None,
+        confirm_duplicate: false,
+        quote_id: None,
+    }, app_state.clock.now());
+    order_limits::check(&app_state.config.order_amounts, &probe)?;
+
+    let usd_price = app_state.rates.usd_price(terms.token_id).ok();
+    // Only BridgeIn orders are claimed by the filler that takes them
+    let claim_fee = match (terms.order_type, terms.amount.parse::<u64>()) {
+        (OrderType::BridgeIn, Ok(gross)) => claim_fees::compute(&app_state.config.claims, gross).ok(),
+        _ => None,
+    };
+    let eta_seconds = match app_state.slo.report().await {
+        Ok(report) => expected_settle_seconds(&report, &probe),
+        Err(e) => {
+            warn!("Failed to read SLO report for quote ETA: {}", e);
+            None
+        }
+    };
+
+    let quote = app_state.quotes.issue(terms, usd_price, claim_fee, eta_seconds).await.map_err(|e| {
+        error!("Failed to issue quote: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(quote))
+}
+
+/// Median creation-to-settlement time of the order's corridor, or of all orders when the
+/// corridor has no recent settlements
+fn expected_settle_seconds(report: &SloReport, order: &Order) -> Option<u64> {
+    let bank_service = order.bank_service.as_deref().filter(|service| !service.is_empty()).unwrap_or(UNSPECIFIED_BANK_SERVICE);
+    report.corridors.iter()
+        .find(|corridor| corridor.token_id == order.token_id && corridor.bank_service == bank_service)
+        .and_then(|corridor| corridor.phases.get(&SloPhase::Settle))
+        .or_else(|| report.overall.get(&SloPhase::Settle))
+        .map(|stats| stats.p50_seconds)
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransferResponse {
+    #[serde(flatten)]
+    pub order: OrderResponse,
+    /// The open batch the transfer was added to; it settles when that batch is finalized
+    pub batch_id: u64,
+}
+
+/// Submit a signed transfer between two accounts (POST /transfers)
+///
+/// Transfers skip matching and fillers: once the signature and nonce check out the transfer
+/// goes straight into the open batch, which rejects it if the sender cannot cover it.
+#[instrument(skip_all, fields(order_id = tracing::field::Empty))]
+pub async fn create_transfer(
+    State(app_state): State<AppState>,
+    Json(req): Json<TransferRequest>,
+) -> Result<Json<TransferResponse>, ApiError> {
+    info!("Creating transfer of {} token {} from {} to {}", req.amount, req.token_id, req.from_address, req.to_address);
+
+    req.verify(&app_state.config.blockchain.contract_address)?;
+    app_state.registry.check_order(req.token_id, None).await?;
+
+    let order = req.to_order(app_state.clock.now());
+    Span::current().record("order_id", order.id.as_str());
+    order_limits::check(&app_state.config.order_amounts, &order)?;
+    app_state.screening.screen(ScreeningContext::Order, &order.id, &[&req.from_address, &req.to_address]).await?;
+    transfers::reserve_nonce(&app_state.db, &req.from_address, req.nonce, &order.id, order.created_at).await?;
+    crate::database::helpers::insert_order(&app_state.db, &order).await.map_err(|e| {
+        error!("Database error creating transfer: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let batched = {
+        let mut processor = app_state.batch_processor.lock().await;
+        let started = match processor.get_current_batch() {
+            Some(batch) => Ok(batch.batch_id),
+            None => processor.start_batch(),
+        };
+        started.and_then(|batch_id| processor.add_order_to_batch(order.clone()).map(|_| batch_id))
+    };
+    let batch_id = match batched {
+        Ok(batch_id) => batch_id,
+        Err(e) => {
+            warn!("Batch rejected transfer {}: {}", order.id, e);
+            fail_order(&app_state, &order, "batch_rejected").await?;
+            return Err(e.into());
+        }
+    };
+
+    info!("Transfer {} added to batch {}", order.id, batch_id);
+    Ok(Json(TransferResponse { order: OrderResponse::from(&order), batch_id }))
+}
+
+/// Fail a saved order the batch would not take
+async fn fail_order(app_state: &AppState, order: &Order, reason: &str) -> Result<(), StatusCode> {
+    sqlx::query("UPDATE orders SET status = ?1, failure_reason = ?2, updated_at = ?3 WHERE id = ?4")
+        .bind(OrderStatus::Failed as i32)
+        .bind(reason)
+        .bind(app_state.clock.now())
+        .bind(&order.id)
+        .execute(&app_state.db)
+        .await
+        .map_err(|e| {
+            error!("Database error failing order {}: {}", order.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    crate::database::helpers::record_status_transition(&app_state.db, &order.id, order.status, OrderStatus::Failed, Some(reason))
+        .await
+        .map_err(|e| {
+            error!("Database error recording status history: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Fail a saved BridgeOut order that the batch rejected for exceeding the withdrawal limits
+async fn reject_over_limit_order(app_state: &AppState, order: &Order) -> Result<(), StatusCode> {
+    warn!("BridgeOut order {} exceeds the withdrawal limits at batch inclusion", order.id);
+
+    fail_order(app_state, order, "withdrawal_limit_exceeded").await
+}
+
 /// Get order status for tracking (GET /orders/:id/status)
+#[instrument(skip_all, fields(order_id = %order_id))]
 pub async fn get_order_status(
     Path(order_id): Path<String>,
     State(app_state): State<AppState>,
@@ -141,12 +445,33 @@ pub async fn get_order_status(
                 banking_hash: row.try_get("banking_hash").ok(),
                 filler_id: row.try_get("filler_id").ok(),
                 locked_amount: row.try_get("locked_amount").ok(),
-                batch_id: row.try_get::<Option<i32>, _>("batch_id").unwrap_or(None).map(|id| id as u32),
+                batch_id: row.try_get::<Option<i64>, _>("batch_id").unwrap_or(None).map(|id| id as u64),
                 created_at: row.try_get("created_at").unwrap_or_default(),
                 updated_at: row.try_get("updated_at").unwrap_or_default(),
             };
             
-            let status_response = OrderStatusResponse::from(order);
+            let mut status_response = OrderStatusResponse::from(order.clone());
+
+            // Link partially settled orders and the orders they were split into
+            status_response.parent_order_id = crate::database::helpers::get_parent_order_id(&app_state.db, &order_id)
+                .await
+                .map_err(|e| {
+                    error!("Database error fetching parent order: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            status_response.child_order_ids = crate::database::helpers::get_child_order_ids(&app_state.db, &order_id)
+                .await
+                .map_err(|e| {
+                    error!("Database error fetching child orders: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+
+            // The SLO position is informational, so a failure to compute it does not fail the request
+            match slo_position(&app_state, &order).await {
+                Ok(position) => status_response.slo_position = position,
+                Err(e) => warn!("Could not compute SLO position for {}: {}", order_id, e),
+            }
+
             Ok(Json(status_response))
         }
         None => {
@@ -157,6 +482,7 @@ pub async fn get_order_status(
 }
 
 /// Mark an order as paid (triggers Transfer order creation)
+#[instrument(skip_all, fields(order_id = %order_id))]
 pub async fn mark_paid(
     State(app_state): State<AppState>,
     Path(order_id): Path<String>,
@@ -184,16 +510,27 @@ pub async fn mark_paid(
                     error!("Failed to update order status: {}", e);
                     StatusCode::INTERNAL_SERVER_ERROR
                 })?;
+            let paid = DomainEvent::OrderPaid { order_id: order_id.clone(), banking_hash: row.try_get("banking_hash").unwrap_or(None) };
+            if let Err(e) = event_log::append(&app_state.db, &paid).await {
+                error!("Failed to log payment of order {}: {}", order_id, e);
+            }
+            app_state.matching_engine.lock().await.clear_lock(&order_id);
 
-            // Create Transfer order (seller → filler)
+            // Create Transfer order (seller → filler settlement account), crediting the filler
+            // with what it locked once the batch settles
+            let filler_id: Option<String> = row.try_get("filler_id").unwrap_or(None);
+            let locked_amount: Option<String> = row.try_get("locked_amount").unwrap_or(None);
             let transfer_order = Order {
-                id: Uuid::new_v4().to_string(),
+                id: new_order_id(),
                 order_type: OrderType::Transfer,
                 status: OrderStatus::Pending,
                 from_address: row.try_get("to_address").ok(),
-                to_address: Some("filler_address".to_string()), // TODO: Get from matching
+                to_address: Some(filler_id.as_deref()
+                    .map_or_else(|| "filler_address".to_string(), filler_settlement_address)),
                 token_id: row.try_get::<i32, _>("token_id").unwrap_or(1) as u32,
-                amount: row.try_get("amount").unwrap_or_default(),
+                amount: locked_amount
+                    .filter(|locked| locked.parse::<u64>().is_ok_and(|locked| locked > 0))
+                    .unwrap_or_else(|| row.try_get("amount").unwrap_or_default()),
                 bank_account: None,
                 bank_service: None,
                 banking_hash: None,
@@ -227,20 +564,52 @@ pub async fn mark_paid(
                     error!("Failed to save transfer order to database: {}", e);
                     StatusCode::INTERNAL_SERVER_ERROR
                 })?;
+            if let Err(e) = event_log::append(&app_state.db, &DomainEvent::order_created(&transfer_order)).await {
+                error!("Failed to log creation of transfer order {}: {}", transfer_order.id, e);
+            }
 
             // Add Transfer order to batch
-            let mut processor = app_state.batch_processor.lock().await;
-            if processor.get_current_batch().is_none() {
-                processor.start_batch().map_err(|e| {
-                    error!("Failed to start batch: {}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
+            let batched = {
+                let mut processor = app_state.batch_processor.lock().await;
+                let started = match processor.get_current_batch() {
+                    Some(_) => Ok(()),
+                    None => processor.start_batch().map(|_| ()),
+                };
+                started.and_then(|_| processor.add_order_to_batch(transfer_order.clone()))
+            };
+
+            match app_state.settlement_saga.record_payment(&order_id, Some(&transfer_order.id)).await {
+                Ok(()) | Err(SagaError::NotFound(_)) => {}
+                Err(e) => error!("Failed to record escrow in settlement saga for order {}: {}", order_id, e),
             }
-            
-            processor.add_order_to_batch(transfer_order.clone()).map_err(|e| {
+
+            // A refused escrow fails the saga, which puts the order back into discovery
+            if let Err(e) = batched {
                 error!("Failed to add transfer order to batch: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
+                sqlx::query("UPDATE orders SET status = ?, failure_reason = 'batch_rejected' WHERE id = ?")
+                    .bind(OrderStatus::Failed as i32)
+                    .bind(&transfer_order.id)
+                    .execute(&app_state.db)
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to mark transfer order {} failed: {}", transfer_order.id, e);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?;
+                let failed = DomainEvent::status_change(&transfer_order.id, transfer_order.status, OrderStatus::Failed, Some("batch_rejected"));
+                if let Err(e) = event_log::append(&app_state.db, &failed).await {
+                    error!("Failed to log failure of transfer order {}: {}", transfer_order.id, e);
+                }
+                match app_state.settlement_saga.fail(&order_id, "escrow_rejected").await {
+                    Ok(_) | Err(SagaError::NotFound(_)) => {}
+                    Err(e) => error!("Failed to compensate settlement saga for order {}: {}", order_id, e),
+                }
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+
+            let check_after = chrono::Duration::seconds(app_state.config.jobs.settlement_check_seconds as i64);
+            if let Err(e) = app_state.scheduler.schedule(JobKind::CheckSettlement, &order_id, Some(&transfer_order.id), check_after).await {
+                error!("Failed to schedule settlement check for order {}: {}", order_id, e);
+            }
 
             info!("Order marked as paid and transfer order created: {}", order_id);
             Ok(Json(serde_json::json!({
@@ -265,9 +634,10 @@ pub async fn mark_paid(
 pub async fn list_orders(
     State(app_state): State<AppState>,
     Query(params): Query<OrderQuery>,
-) -> Result<Json<OrdersListResponse>, StatusCode> {
+) -> Result<Json<OrdersListResponse>, ApiError> {
     info!("Listing orders with params: {:?}", params);
     
+    let after = params.after.as_deref().map(Cursor::decode).transpose()?;
     let mut query = "SELECT id, order_type, status, amount, created_at FROM orders".to_string();
     let mut conditions = Vec::new();
     
@@ -293,17 +663,27 @@ pub async fn list_orders(
         }
     }
     
+    // Creation order: created_at, then the id (ULIDs increase within a millisecond)
+    let ascending = params.sort.as_deref() == Some("asc");
+    if after.is_some() {
+        conditions.push(if ascending { "(created_at, id) > (?1, ?2)" } else { "(created_at, id) < (?1, ?2)" });
+    }
+
     if !conditions.is_empty() {
         query.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
     }
     
-    query.push_str(" ORDER BY created_at DESC");
+    query.push_str(if ascending { " ORDER BY created_at, id" } else { " ORDER BY created_at DESC, id DESC" });
     
-    if let Some(limit) = params.limit {
-        query.push_str(&format!(" LIMIT {}", limit.min(100))); // Cap at 100
-    }
+    // One extra row tells whether there is a next page
+    let limit = page_size(params.limit, MAX_PAGE_SIZE);
+    query.push_str(&format!(" LIMIT {}", limit + 1));
     
-    let rows = sqlx::query(&query)
+    let mut rows_query = sqlx::query(&query);
+    if let Some(after) = &after {
+        rows_query = rows_query.bind(after.created_at).bind(&after.id);
+    }
+    let rows = rows_query
         .fetch_all(&app_state.db)
         .await
         .map_err(|e| {
@@ -322,23 +702,30 @@ pub async fn list_orders(
             filler_id: row.try_get("filler_id").ok(),
             locked_amount: row.try_get("locked_amount").ok(),
             created_at: row.try_get("created_at").unwrap_or_default(),
+            deposit_reference: None,
+            payment_proof: None,
+            quoted_rate: None,
+            duplicate_warning: None,
+            settlement_cost: None,
         })
         .collect();
 
+    let (orders, next_cursor) = paginate(orders, limit, |order| Cursor::new(order.created_at, &order.id));
     let total = orders.len();
     
     info!("Found {} orders", total);
-    Ok(Json(OrdersListResponse { orders, total }))
+    Ok(Json(OrdersListResponse { orders, total, next_cursor }))
 }
 
 /// Get specific order by ID
+#[instrument(skip_all, fields(order_id = %order_id))]
 pub async fn get_order(
     State(app_state): State<AppState>,
     Path(order_id): Path<String>,
 ) -> Result<Json<OrderResponse>, StatusCode> {
     info!("Getting order: {}", order_id);
     
-    let query = "SELECT id, order_type, status, amount, created_at FROM orders WHERE id = ?";
+    let query = "SELECT id, order_type, status, amount, created_at, payment_proof, quoted_usd_price FROM orders WHERE id = ?";
     let row = sqlx::query(query)
         .bind(&order_id)
         .fetch_optional(&app_state.db)
@@ -350,6 +737,12 @@ pub async fn get_order(
 
     match row {
         Some(row) => {
+            let settlement_cost = order_costs::for_order(&app_state.db, &order_id)
+                .await
+                .map_err(|e| {
+                    error!("Database error fetching cost of order {}: {}", order_id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
             let order = OrderResponse {
                 id: row.try_get("id").unwrap_or_default(),
                 order_type: OrderType::from(row.try_get::<i32, _>("order_type").unwrap_or(0)),
@@ -360,6 +753,13 @@ pub async fn get_order(
                 filler_id: row.try_get("filler_id").ok(),
                 locked_amount: row.try_get("locked_amount").ok(),
                 created_at: row.try_get("created_at").unwrap_or_default(),
+                deposit_reference: None,
+                payment_proof: row.try_get::<Option<String>, _>("payment_proof").ok().flatten()
+                    .and_then(|proof| serde_json::from_str(&proof).ok()),
+                quoted_rate: row.try_get::<Option<i64>, _>("quoted_usd_price").ok().flatten()
+                    .map(|price| format_rate(price as u64)),
+                duplicate_warning: None,
+                settlement_cost,
             };
             
             Ok(Json(order))
@@ -372,16 +772,14 @@ pub async fn get_order(
 }
 
 /// Trigger order matching manually
+#[instrument(skip_all)]
 pub async fn match_orders(
     State(app_state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     info!("Triggering order matching");
     
     let mut engine = app_state.matching_engine.lock().await;
-    let matches = engine.match_orders().map_err(|e| {
-        error!("Failed to match orders: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    let matches = engine.match_orders()?;
 
     let match_responses: Vec<MatchResponse> = matches.iter()
         .map(|m| MatchResponse {
@@ -401,12 +799,26 @@ pub async fn match_orders(
 }
 
 /// Mark order as in discovery phase (for testing/simulation)
+#[instrument(skip_all, fields(order_id = %order_id))]
 pub async fn mark_discovery(
     State(app_state): State<AppState>,
     Path(order_id): Path<String>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     info!("Marking order as discovery: {}", order_id);
     
+    // Remember the previous status for the order's history
+    let previous_status = crate::database::helpers::get_order_by_id(&app_state.db, &order_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching order {}: {}", order_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(|order| order.status)
+        .ok_or_else(|| {
+            warn!("Order {} not found for discovery update", order_id);
+            StatusCode::NOT_FOUND
+        })?;
+    
     // Update order status to Discovery
     let update_query = "UPDATE orders SET status = ?, updated_at = ? WHERE id = ?";
     match sqlx::query(update_query)
@@ -422,6 +834,14 @@ pub async fn mark_discovery(
                 Err(StatusCode::NOT_FOUND)
             } else {
                 info!("Order {} marked as discovery", order_id);
+                if let Err(e) = crate::database::helpers::record_status_transition(
+                    &app_state.db, &order_id, previous_status, OrderStatus::Discovery, Some("manual"),
+                ).await {
+                    warn!("Failed to record status history for order {}: {}", order_id, e);
+                }
+                if let Err(e) = crate::services::discovery::publish_discovered(&app_state.db, &app_state.event_bus, &order_id).await {
+                    warn!("Failed to publish discovery event for order {}: {}", order_id, e);
+                }
                 Ok(Json(serde_json::json!({
                     "success": true,
                     "message": "Order marked as discovery",
@@ -434,4 +854,141 @@ pub async fn mark_discovery(
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
-}
\ No newline at end of file
+}
+
+/// Settle the locked portion of an order and split off the remainder (POST /orders/:id/settle-partial)
+#[instrument(skip_all, fields(order_id = %order_id))]
+pub async fn settle_partial(
+    State(app_state): State<AppState>,
+    Path(order_id): Path<String>,
+    Json(req): Json<SettlePartialRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    info!("Partially settling order {} ({:?} remainder)", order_id, req.remainder);
+
+    let split = settlement::settle_partial(&app_state.db, &app_state.event_bus, &order_id, req.remainder).await?;
+
+    Ok(Json(serde_json::json!({
+        "parent_order_id": split.parent_order_id,
+        "settled_order": OrderResponse::from(&split.settled),
+        "remainder_order": OrderResponse::from(&split.remainder),
+        "remainder_action": split.remainder_action,
+    })))
+}
+
+/// Get an order's status transitions (GET /orders/:id/history)
+#[instrument(skip_all, fields(order_id = %order_id))]
+pub async fn get_order_history(
+    State(app_state): State<AppState>,
+    Path(order_id): Path<String>,
+) -> Result<Json<Vec<crate::models::OrderStatusTransition>>, StatusCode> {
+    info!("Getting status history for order: {}", order_id);
+
+    crate::database::helpers::get_order_history(&app_state.db, &order_id)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Database error fetching order history: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Get the operator-signed receipt of an order's inclusion in a finalized batch (GET /orders/:id/receipt)
+#[instrument(skip_all, fields(order_id = %order_id))]
+pub async fn get_order_receipt(
+    State(app_state): State<AppState>,
+    Path(order_id): Path<String>,
+) -> Result<Json<InclusionReceipt>, ApiError> {
+    info!("Getting inclusion receipt for order: {}", order_id);
+
+    if !app_state.receipts.is_enabled() {
+        return Err(ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "receipts_disabled", "No operator key is configured to sign receipts"));
+    }
+
+    let receipt = app_state.receipts.get(&order_id).await.map_err(|e| {
+        error!("Database error fetching receipt: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    receipt.map(Json).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            "receipt_not_found",
+            format!("Order {} has no receipt yet; receipts are issued once its batch is finalized", order_id),
+        )
+    })
+}
+
+/// Check a receipt against the operator key, e.g. one kept by a user since it was issued (POST /receipts/verify)
+pub async fn verify_receipt(
+    State(app_state): State<AppState>,
+    Json(receipt): Json<InclusionReceipt>,
+) -> Result<Json<SignatureVerification>, ApiError> {
+    info!("Verifying inclusion receipt for order: {}", receipt.order_id);
+
+    app_state.receipts.verify(&receipt).map(Json).ok_or_else(|| {
+        ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "receipts_disabled", "No operator key is configured to sign receipts")
+    })
+}
+
+/// Get SLA breach counters per bank service (GET /orders/sla-metrics)
+pub async fn get_sla_metrics(
+    State(app_state): State<AppState>,
+) -> Result<Json<crate::services::sla::SlaMetrics>, StatusCode> {
+    info!("Getting order SLA metrics");
+
+    let metrics = app_state.sla_metrics.lock().await;
+    Ok(Json(metrics.clone()))
+}
+
+/// Get rolling p50/p95/p99 phase latencies per corridor (GET /stats/slo)
+pub async fn get_slo_report(
+    State(app_state): State<AppState>,
+) -> Result<Json<crate::services::slo::SloReport>, StatusCode> {
+    info!("Getting SLO report");
+
+    app_state.slo.report().await.map(Json).map_err(|e| {
+        error!("Failed to compute SLO report: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Matching engine queue and how long fresh and re-queued orders waited for a match (GET /stats/matching)
+pub async fn get_matching_stats(
+    State(app_state): State<AppState>,
+) -> Json<crate::services::matching_engine::MatchingStats> {
+    Json(app_state.matching_engine.lock().await.get_stats())
+}
+
+/// Where an order's time in its current phase falls among its corridor's recent orders
+async fn slo_position(app_state: &AppState, order: &Order) -> anyhow::Result<Option<crate::services::slo::SloPosition>> {
+    let history = crate::database::helpers::get_order_history(&app_state.db, &order.id).await?;
+    let entered_at = history.iter()
+        .rev()
+        .find(|transition| transition.to_status == order.status)
+        .map_or(order.updated_at, |transition| transition.created_at);
+    app_state.slo.position(order, entered_at, Utc::now()).await
+}
+
+/// Get the permit an order's deposit was made with (GET /orders/:id/permit)
+#[instrument(skip_all, fields(order_id = %order_id))]
+pub async fn get_order_permit(
+    State(app_state): State<AppState>,
+    Path(order_id): Path<String>,
+) -> Result<Json<crate::models::PermitData>, StatusCode> {
+    info!("Getting permit for order: {}", order_id);
+
+    let permit = crate::database::helpers::get_order_permit(&app_state.db, &order_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching permit: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    match permit {
+        Some(permit) => Ok(Json(permit)),
+        None => {
+            warn!("No permit found for order: {}", order_id);
+            Err(StatusCode::NOT_FOUND)
+        }
+    }
+}
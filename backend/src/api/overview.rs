@@ -0,0 +1,270 @@
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::Row;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::AppState;
+use crate::models::OrderStatus;
+use crate::services::balance_alerts::AlertStatus;
+use crate::services::chain_checkpoint;
+use crate::services::proof_compression::{self, SubmissionSizes};
+use crate::services::relayer::ScanThroughput;
+use crate::services::settlement_saga::SagaState;
+use crate::services::stats::{self, Counter};
+
+/// How long one section may take before it is reported as failed
+const SECTION_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Proof submissions listed in the overview
+const RECENT_SUBMISSIONS: u32 = 10;
+
+/// Statuses an order can still leave, counted in the overview
+const OPEN_STATUSES: [OrderStatus; 6] = [
+    OrderStatus::Pending,
+    OrderStatus::ReviewPending,
+    OrderStatus::Discovery,
+    OrderStatus::Locked,
+    OrderStatus::MarkPaid,
+    OrderStatus::Disputed,
+];
+
+/// One dashboard section: its data, or why it could not be read
+///
+/// Sections are read independently, so a slow RPC or a busy batch processor only blanks
+/// its own section.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Section<T> {
+    Ok { data: T },
+    Error { error: String },
+}
+
+/// Everything an operator dashboard shows (GET /admin/overview)
+#[derive(Debug, Serialize)]
+pub struct Overview {
+    pub generated_at: DateTime<Utc>,
+    pub batch: Section<BatchOverview>,
+    pub orders: Section<OrderCounts>,
+    pub relayer: Section<RelayerLag>,
+    pub prover: Section<ProverQueue>,
+    pub proof_submissions: Section<Vec<SubmissionSizes>>,
+    pub filler_liquidity: Section<FillerLiquidity>,
+    pub alerts: Section<Vec<ActiveAlert>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchOverview {
+    pub next_batch_id: u64,
+    pub has_active_batch: bool,
+    pub current_batch_id: Option<u64>,
+    pub current_batch_orders: usize,
+    pub current_batch_created_at: Option<DateTime<Utc>>,
+    pub total_accounts: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrderCounts {
+    /// Orders per open status, zero included
+    pub by_status: BTreeMap<String, i64>,
+    pub total_open: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelayerLag {
+    /// Last block the relayer applied, from its checkpoint
+    pub last_processed_block: Option<u64>,
+    pub checkpoint_updated_at: Option<DateTime<Utc>>,
+    /// None without a blockchain client
+    pub chain_head: Option<u64>,
+    pub lag_blocks: Option<u64>,
+    pub last_scan: Option<ScanThroughput>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProverQueue {
+    pub depth: usize,
+    pub queued_batch_ids: Vec<u64>,
+    pub proofs_generated: u64,
+    pub proof_failures: u64,
+    pub last_proof_at: Option<DateTime<Utc>>,
+}
+
+/// Filler balances summed over every filler, in token base units
+#[derive(Debug, Serialize)]
+pub struct FillerLiquidity {
+    pub fillers: usize,
+    pub total_balance: String,
+    pub locked_balance: String,
+    pub available_balance: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActiveAlert {
+    /// balance, maintenance or saga
+    pub source: &'static str,
+    pub message: String,
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// System overview for operator dashboards (GET /admin/overview)
+///
+/// Sections are assembled concurrently, each with its own timeout and error.
+pub async fn get_overview(State(app_state): State<AppState>) -> Json<Overview> {
+    info!("Getting operator overview");
+
+    let (batch, orders, relayer, prover, proof_submissions, filler_liquidity, alerts) = tokio::join!(
+        section("batch", batch_overview(&app_state)),
+        section("orders", order_counts(&app_state)),
+        section("relayer", relayer_lag(&app_state)),
+        section("prover", prover_queue(&app_state)),
+        section("proof_submissions", proof_compression::list(&app_state.db, RECENT_SUBMISSIONS)),
+        section("filler_liquidity", filler_liquidity(&app_state)),
+        section("alerts", active_alerts(&app_state)),
+    );
+
+    Json(Overview {
+        generated_at: app_state.clock.now(),
+        batch,
+        orders,
+        relayer,
+        prover,
+        proof_submissions,
+        filler_liquidity,
+        alerts,
+    })
+}
+
+async fn section<T>(name: &str, read: impl Future<Output = anyhow::Result<T>>) -> Section<T> {
+    match tokio::time::timeout(SECTION_TIMEOUT, read).await {
+        Ok(Ok(data)) => Section::Ok { data },
+        Ok(Err(e)) => {
+            warn!("Overview section {} failed: {}", name, e);
+            Section::Error { error: e.to_string() }
+        }
+        Err(_) => {
+            warn!("Overview section {} timed out", name);
+            Section::Error { error: format!("timed out after {}s", SECTION_TIMEOUT.as_secs()) }
+        }
+    }
+}
+
+async fn batch_overview(app_state: &AppState) -> anyhow::Result<BatchOverview> {
+    let processor = app_state.batch_processor.lock().await;
+    let stats = processor.get_stats();
+    let current = processor.get_current_batch();
+    Ok(BatchOverview {
+        next_batch_id: stats.next_batch_id,
+        has_active_batch: stats.has_active_batch,
+        current_batch_id: current.map(|batch| batch.batch_id),
+        current_batch_orders: stats.current_batch_orders,
+        current_batch_created_at: current.map(|batch| batch.created_at),
+        total_accounts: stats.total_accounts,
+    })
+}
+
+async fn order_counts(app_state: &AppState) -> anyhow::Result<OrderCounts> {
+    let rows = sqlx::query("SELECT status, COUNT(*) AS count FROM orders GROUP BY status")
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let mut by_status: BTreeMap<String, i64> = OPEN_STATUSES.iter().map(|status| (format!("{:?}", status), 0)).collect();
+    for row in rows {
+        let status = OrderStatus::from(row.try_get::<i32, _>("status")?);
+        if OPEN_STATUSES.contains(&status) {
+            by_status.insert(format!("{:?}", status), row.try_get("count")?);
+        }
+    }
+    Ok(OrderCounts { total_open: by_status.values().sum(), by_status })
+}
+
+async fn relayer_lag(app_state: &AppState) -> anyhow::Result<RelayerLag> {
+    let checkpoint = chain_checkpoint::load(&app_state.db).await?;
+    let chain_head = match &app_state.blockchain_client {
+        Some(client) => Some(client.get_block_number().await?),
+        None => None,
+    };
+    let last_processed_block = checkpoint.as_ref().map(|checkpoint| checkpoint.block_number);
+    Ok(RelayerLag {
+        last_processed_block,
+        checkpoint_updated_at: checkpoint.map(|checkpoint| checkpoint.updated_at),
+        chain_head,
+        lag_blocks: chain_head.zip(last_processed_block).map(|(head, processed)| head.saturating_sub(processed)),
+        last_scan: app_state.relayer_metrics.snapshot().last_scan,
+    })
+}
+
+async fn prover_queue(app_state: &AppState) -> anyhow::Result<ProverQueue> {
+    let queued_batch_ids = app_state.batch_processor.lock().await.proving_queue.batch_ids();
+    let (proofs_generated, last_proof_at) = stats::get(&app_state.db, Counter::ProofsGenerated).await?;
+    let (proof_failures, _) = stats::get(&app_state.db, Counter::ProofFailures).await?;
+    Ok(ProverQueue {
+        depth: queued_batch_ids.len(),
+        queued_batch_ids,
+        proofs_generated,
+        proof_failures,
+        last_proof_at,
+    })
+}
+
+async fn filler_liquidity(app_state: &AppState) -> anyhow::Result<FillerLiquidity> {
+    let rows = sqlx::query("SELECT filler_id, total_balance, locked_balance FROM filler_balances")
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let (mut total, mut locked) = (0u128, 0u128);
+    for row in &rows {
+        let parse = |column: &str| -> anyhow::Result<u128> {
+            let value: String = row.try_get(column)?;
+            value.parse().map_err(|_| anyhow::anyhow!("filler {} has a malformed {}", row.get::<String, _>("filler_id"), column))
+        };
+        total += parse("total_balance")?;
+        locked += parse("locked_balance")?;
+    }
+    Ok(FillerLiquidity {
+        fillers: rows.len(),
+        total_balance: total.to_string(),
+        locked_balance: locked.to_string(),
+        available_balance: total.saturating_sub(locked).to_string(),
+    })
+}
+
+async fn active_alerts(app_state: &AppState) -> anyhow::Result<Vec<ActiveAlert>> {
+    let mut alerts = Vec::new();
+
+    let maintenance = app_state.maintenance.status();
+    if maintenance.enabled {
+        alerts.push(ActiveAlert { source: "maintenance", message: maintenance.message().to_string(), since: maintenance.updated_at });
+    }
+    for state in app_state.balance_alerts.states() {
+        if state.status == AlertStatus::Firing {
+            alerts.push(ActiveAlert {
+                source: "balance",
+                message: format!(
+                    "{:?} balance of {} for token {} is {} (threshold {})",
+                    state.rule.source,
+                    state.rule.target,
+                    state.rule.token_id,
+                    state.balance.as_deref().unwrap_or("unknown"),
+                    state.rule.threshold,
+                ),
+                since: state.since,
+            });
+        }
+    }
+    for saga in app_state.settlement_saga.list(Some(SagaState::CompensationFailed)).await? {
+        alerts.push(ActiveAlert {
+            source: "saga",
+            message: format!(
+                "Settlement saga for order {} could not be compensated: {}",
+                saga.order_id,
+                saga.last_error.as_deref().unwrap_or("unknown error"),
+            ),
+            since: Some(saga.updated_at),
+        });
+    }
+    Ok(alerts)
+}
@@ -6,43 +6,101 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tracing::{info, warn, error};
+use chrono::{DateTime, Utc};
 use sqlx::Row;
 
-use super::AppState;
+use super::{error::ApiError, AppState};
+use vapor_core::ethereum_address_to_path;
+use vapor_core::proof_format::{
+    self, ProofError, ProofFormat, bit_path_to_path_bits, index_to_path_bits, parse_hash32,
+};
+use crate::merkle::MerkleTreeManager;
+use crate::models::{resolve_account_address, TokenBalance};
+use crate::database::helpers;
+use crate::services::attestations::BalanceAttestation;
+use crate::services::proof_cache::{build_account_proof, build_order_proofs};
+use crate::services::stats;
+use crate::services::verification_cache::{self, BatchPublicInputs, VerificationCacheMetrics, VerificationKey};
 
 #[derive(Debug, Deserialize)]
 pub struct ProofQuery {
     pub proof_type: Option<String>, // "order" or "account"
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ProofFormatQuery {
+    pub format: Option<String>, // "siblings" (default), "raw" or "sorted_pairs"
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountProofQuery {
+    pub format: Option<String>,
+    /// Prove against the state snapshot of this batch instead of the current state
+    pub batch_id: Option<u64>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ProofResponse {
-    pub batch_id: u32,
+    pub batch_id: u64,
     pub order_id: String,
     pub leaf_hash: String,
     pub proof: Vec<String>,
     pub root: String,
     pub valid: bool,
+    pub format: ProofFormat,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_bits: Option<Vec<u8>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaf_index: Option<usize>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct AccountProofResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<u64>,
     pub address: String,
     pub leaf_hash: String,
     pub proof: Vec<String>,
     pub root: String,
     pub valid: bool,
+    pub format: ProofFormat,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_bits: Option<Vec<u8>>,
+}
+
+fn parse_proof_format(format: Option<&str>) -> Result<ProofFormat, ProofError> {
+    format.map_or(Ok(ProofFormat::default()), str::parse)
 }
 
 /// Get Merkle proof for a specific order in a batch
 pub async fn get_order_proof(
     State(app_state): State<AppState>,
-    Path((batch_id, order_id)): Path<(u32, String)>,
-) -> Result<Json<ProofResponse>, StatusCode> {
+    Path((batch_id, order_id)): Path<(u64, String)>,
+    Query(query): Query<ProofFormatQuery>,
+) -> Result<Json<ProofResponse>, ApiError> {
     info!("Getting Merkle proof for batch {} order {}", batch_id, order_id);
     
-    // For MVP, we'll generate a mock proof since we don't have persistent batch storage
-    // In production, you'd retrieve the actual batch and generate the real proof
+    let format = parse_proof_format(query.format.as_deref())?;
+
+    // Recent batches have their proofs precomputed at finalization
+    match app_state.proof_cache.get_order_proof(batch_id, &order_id, format).await {
+        Ok(Some((proof, leaf_index))) => {
+            info!("Serving precomputed {:?} proof for order {} in batch {}", format, order_id, batch_id);
+            return Ok(Json(ProofResponse {
+                batch_id,
+                order_id,
+                leaf_hash: proof.leaf_hash,
+                proof: proof.proof,
+                root: proof.root,
+                valid: true,
+                format,
+                path_bits: proof.path_bits,
+                leaf_index,
+            }));
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Failed to read precomputed proof for order {}: {}", order_id, e),
+    }
     
     // Check if order exists in database
     let query = "SELECT id FROM orders WHERE id = ?";
@@ -57,35 +115,83 @@ pub async fn get_order_proof(
 
     if order_exists.is_none() {
         warn!("Order not found: {}", order_id);
-        return Err(StatusCode::NOT_FOUND);
+        return Err(StatusCode::NOT_FOUND.into());
     }
 
-    // Generate mock proof for MVP
-    let mock_proof = ProofResponse {
-        batch_id,
-        order_id: order_id.clone(),
-        leaf_hash: format!("0x{:064x}", batch_id as u64),
-        proof: vec![
-            "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef".to_string(),
-            "0xfedcba0987654321fedcba0987654321fedcba0987654321fedcba0987654321".to_string(),
-        ],
-        root: format!("0x{:064x}", (batch_id * 1000) as u64),
-        valid: true,
+    let batch_orders = crate::database::helpers::get_orders_by_batch(&app_state.db, batch_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching batch orders: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let Some(order_index) = batch_orders.iter().position(|order| order.id == order_id) else {
+        // Contract-facing formats need the real batch tree
+        if format != ProofFormat::Siblings {
+            warn!("Order {} is not in batch {}", order_id, batch_id);
+            return Err(StatusCode::NOT_FOUND.into());
+        }
+
+        // Generate mock proof for MVP
+        let mock_proof = ProofResponse {
+            batch_id,
+            order_id: order_id.clone(),
+            leaf_hash: format!("0x{:064x}", batch_id),
+            proof: vec![
+                "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef".to_string(),
+                "0xfedcba0987654321fedcba0987654321fedcba0987654321fedcba0987654321".to_string(),
+            ],
+            root: format!("0x{:064x}", batch_id * 1000),
+            valid: true,
+            format,
+            path_bits: None,
+            leaf_index: None,
+        };
+
+        info!("Generated mock proof for order {} in batch {}", order_id, batch_id);
+        return Ok(Json(mock_proof));
     };
 
-    info!("Generated proof for order {} in batch {}", order_id, batch_id);
-    Ok(Json(mock_proof))
+    let proof = build_order_proofs(&batch_orders, batch_id, &[order_index], format)?.remove(0);
+
+    info!("Generated {:?} proof for order {} in batch {}", format, order_id, batch_id);
+    Ok(Json(ProofResponse {
+        batch_id,
+        order_id,
+        leaf_hash: proof.leaf_hash,
+        proof: proof.proof,
+        root: proof.root,
+        valid: true,
+        format,
+        path_bits: proof.path_bits,
+        leaf_index: Some(order_index),
+    }))
 }
 
 /// Get Merkle proof for an account state
+///
+/// `filler:<filler_id>` proves the filler's settlement account, i.e. its claimable balances.
 pub async fn get_account_proof(
     State(app_state): State<AppState>,
-    Path(address): Path<String>,
-) -> Result<Json<AccountProofResponse>, StatusCode> {
+    Path(target): Path<String>,
+    Query(query): Query<AccountProofQuery>,
+) -> Result<Json<AccountProofResponse>, ApiError> {
+    let address = resolve_account_address(&target);
     info!("Getting account state proof for address: {}", address);
     
+    // The account tree is a positional sparse tree keyed by address bits, sorted pairs cannot express it
+    let format = parse_proof_format(query.format.as_deref())?;
+    if format == ProofFormat::SortedPairs {
+        return Err(ProofError::SortedPairsForAccount.into());
+    }
+
+    if let Some(batch_id) = query.batch_id {
+        return get_historical_account_proof(&app_state, address, batch_id, format).await.map(Json);
+    }
+    
     // For MVP, generate a mock account proof
-    let mock_proof = AccountProofResponse {
+    let mut mock_proof = AccountProofResponse {
+        batch_id: None,
         address: address.clone(),
         leaf_hash: format!("0x{:064x}", address.len() as u64),
         proof: vec![
@@ -94,12 +200,186 @@ pub async fn get_account_proof(
         ],
         root: "0x1111111111111111111111111111111111111111111111111111111111111111".to_string(),
         valid: true,
+        format,
+        path_bits: None,
     };
 
+    if format == ProofFormat::Raw {
+        let path = ethereum_address_to_path(&address, mock_proof.proof.len());
+        mock_proof.path_bits = Some(bit_path_to_path_bits(&path));
+    }
+
     info!("Generated account proof for address: {}", address);
     Ok(Json(mock_proof))
 }
 
+/// Prove an account against the state tree of a past batch, restoring its snapshot from the archive if needed
+async fn get_historical_account_proof(
+    app_state: &AppState,
+    address: String,
+    batch_id: u64,
+    format: ProofFormat,
+) -> Result<AccountProofResponse, ApiError> {
+    let precomputed = app_state.proof_cache.get_account_proof(batch_id, &address, format)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to read precomputed proof for account {}: {}", address, e);
+            None
+        });
+
+    let proof = match precomputed {
+        Some(proof) => {
+            info!("Serving precomputed proof for account {} at batch {}", address, batch_id);
+            proof
+        }
+        None => {
+            let snapshot = app_state.archive.load_snapshot(batch_id)
+                .await
+                .map_err(|e| {
+                    error!("Failed to load state snapshot for batch {}: {}", batch_id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?
+                .ok_or_else(|| {
+                    warn!("No state snapshot for batch {}", batch_id);
+                    StatusCode::NOT_FOUND
+                })?;
+
+            if !snapshot.accounts.iter().any(|account| account.address.eq_ignore_ascii_case(&address)) {
+                warn!("Account {} is not in the state of batch {}", address, batch_id);
+                return Err(StatusCode::NOT_FOUND.into());
+            }
+
+            let mut manager = MerkleTreeManager::new();
+            manager.build_state_tree(&snapshot.accounts).map_err(ProofError::Tree)?;
+            build_account_proof(&mut manager, &address, format)?
+        }
+    };
+
+    info!("Generated account proof for address {} at batch {}", address, batch_id);
+    Ok(AccountProofResponse {
+        batch_id: Some(batch_id),
+        address,
+        leaf_hash: proof.leaf_hash,
+        proof: proof.proof,
+        root: proof.root,
+        valid: true,
+        format,
+        path_bits: proof.path_bits,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttestationQuery {
+    pub token_id: u32,
+    /// Attest against this proven batch instead of the latest one
+    pub batch_id: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BalanceAttestationResponse {
+    pub attestation: BalanceAttestation,
+    /// Every balance of the account's leaf, in token order, so the leaf hash can be recomputed
+    pub balances: Vec<TokenBalance>,
+    /// Raw proof of the leaf against `attestation.state_root`
+    pub proof: AccountProofResponse,
+}
+
+/// Get an operator-signed attestation of an account's balance of a token, with the Merkle proof
+/// backing it
+///
+/// The attestation covers a proven batch, the latest one unless `batch_id` is given, so its
+/// state root can be checked against the verifier contract's `getBatch(batch_id)`.
+pub async fn get_balance_attestation(
+    State(app_state): State<AppState>,
+    Path(target): Path<String>,
+    Query(query): Query<AttestationQuery>,
+) -> Result<Json<BalanceAttestationResponse>, ApiError> {
+    let address = resolve_account_address(&target);
+    info!("Getting balance attestation for address {} and token {}", address, query.token_id);
+
+    if !app_state.attestations.is_enabled() {
+        return Err(ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "attestations_disabled", "No operator key is configured to sign attestations"));
+    }
+
+    let batch_id = match query.batch_id {
+        Some(batch_id) => batch_id,
+        None => helpers::latest_proven_root(&app_state.db)
+            .await
+            .map_err(|e| {
+                error!("Database error fetching the latest proven root: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .map(|root| root.batch_id)
+            .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "no_proven_batch", "No batch has been proven yet"))?,
+    };
+
+    let snapshot = app_state.archive.load_snapshot(batch_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load state snapshot for batch {}: {}", batch_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "batch_not_found", format!("No state snapshot for batch {}", batch_id)))?;
+    let account = snapshot.accounts.iter()
+        .find(|account| account.address.eq_ignore_ascii_case(&address))
+        .ok_or_else(|| ApiError::new(
+            StatusCode::NOT_FOUND,
+            "account_not_found",
+            format!("Account {} is not in the state of batch {}", address, batch_id),
+        ))?;
+    // A token missing from the leaf is a zero balance, which the proof shows just as well
+    let balance = account.balances.iter()
+        .find(|balance| balance.token_id == query.token_id)
+        .map_or_else(|| "0".to_string(), |balance| balance.balance.clone());
+
+    let mut manager = MerkleTreeManager::new();
+    manager.build_state_tree(&snapshot.accounts).map_err(ProofError::Tree)?;
+    let proof = build_account_proof(&mut manager, &account.address, ProofFormat::Raw)?;
+
+    let attestation = app_state.attestations
+        .attest(&account.address, query.token_id, &balance, batch_id, &snapshot.state_root, app_state.clock.now())
+        .await
+        .map_err(|e| {
+            error!("Failed to sign balance attestation for {}: {}", address, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Issued balance attestation for address {} at batch {}", address, batch_id);
+    Ok(Json(BalanceAttestationResponse {
+        attestation,
+        balances: account.balances.clone(),
+        proof: AccountProofResponse {
+            batch_id: Some(batch_id),
+            address: account.address.clone(),
+            leaf_hash: proof.leaf_hash,
+            proof: proof.proof,
+            root: proof.root,
+            valid: true,
+            format: ProofFormat::Raw,
+            path_bits: proof.path_bits,
+        },
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct AcceptedAttestation {
+    pub valid: bool,
+    pub signer: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Check an attestation presented by a third party: unaltered, signed by the operator and
+/// not expired (POST /proofs/attestations/verify)
+pub async fn verify_balance_attestation(
+    State(app_state): State<AppState>,
+    Json(attestation): Json<BalanceAttestation>,
+) -> Result<Json<AcceptedAttestation>, ApiError> {
+    info!("Verifying balance attestation for address {} at batch {}", attestation.address, attestation.batch_id);
+
+    let signer = app_state.attestations.accept(&attestation, app_state.clock.now())?;
+    Ok(Json(AcceptedAttestation { valid: true, signer: format!("{:?}", signer), expires_at: attestation.expires_at }))
+}
+
 /// Verify a Merkle proof
 #[derive(Debug, Deserialize)]
 pub struct VerifyProofRequest {
@@ -107,37 +387,201 @@ pub struct VerifyProofRequest {
     pub proof: Vec<String>,
     pub root: String,
     pub index: Option<u32>,
+    #[serde(default)]
+    pub format: ProofFormat,
+    pub path_bits: Option<Vec<u8>>,
+    /// Finalized batch the root must belong to, as its state or orders root
+    pub batch_id: Option<u64>,
+}
+
+impl VerifyProofRequest {
+    /// Leaf position of a positional proof, from its path bits or index
+    fn resolved_path_bits(&self) -> Option<Vec<u8>> {
+        match (&self.path_bits, self.index) {
+            (Some(path_bits), _) => Some(path_bits.clone()),
+            (None, Some(index)) => Some(index_to_path_bits(index as usize, self.proof.len())),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Recompute the root from a raw or sorted-pair proof and compare it to the claimed root
+fn verify_formatted_proof(req: &VerifyProofRequest) -> Result<bool, ProofError> {
+    let leaf = parse_hash32(&req.leaf_hash)?;
+    let root = parse_hash32(&req.root)?;
+    let siblings = req.proof.iter()
+        .map(|sibling| parse_hash32(sibling))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let computed = match req.format {
+        ProofFormat::SortedPairs => proof_format::process_sorted_proof(leaf, &siblings),
+        _ => {
+            let path_bits = req.resolved_path_bits().ok_or(ProofError::MissingPathBits)?;
+            proof_format::process_raw_proof(leaf, &siblings, &path_bits)?
+        }
+    };
+
+    Ok(computed == root)
+}
+
+/// Public inputs of a finalized batch, encoded once and memoized
+async fn batch_public_inputs(app_state: &AppState, batch_id: u64) -> Result<BatchPublicInputs, ApiError> {
+    if let Some(inputs) = app_state.verification_cache.batch_inputs(batch_id) {
+        return Ok(inputs);
+    }
+    let (state_root, orders_root, _) = app_state.archive.snapshot_roots(batch_id).await
+        .map_err(|e| {
+            error!("Failed to read the roots of batch {}: {}", batch_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "batch_not_found", format!("Batch {} has not been finalized", batch_id)))?;
+    let inputs = BatchPublicInputs::encode(batch_id, &state_root, &orders_root)?;
+    app_state.verification_cache.remember_batch_inputs(inputs);
+    Ok(inputs)
 }
 
 pub async fn verify_proof(
-    State(_app_state): State<AppState>,
+    State(app_state): State<AppState>,
     Json(req): Json<VerifyProofRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    info!("Verifying Merkle proof");
-    
-    // For MVP, we'll do a simple validation
-    // In production, you'd implement proper Merkle proof verification
-    
-    let is_valid = !req.leaf_hash.is_empty() 
-        && !req.proof.is_empty() 
-        && !req.root.is_empty()
-        && req.leaf_hash.starts_with("0x")
-        && req.root.starts_with("0x");
+) -> Result<Json<Value>, ApiError> {
+    info!("Verifying {:?} Merkle proof", req.format);
+
+    let batch = match req.batch_id {
+        Some(batch_id) => Some(batch_public_inputs(&app_state, batch_id).await?),
+        None => None,
+    };
+    let path_bits = (req.format != ProofFormat::SortedPairs).then(|| req.resolved_path_bits()).flatten();
+    let key = VerificationKey {
+        proof_hash: verification_cache::proof_hash(&req.proof),
+        public_inputs_hash: verification_cache::public_inputs_hash(&req.leaf_hash, &req.root, req.format, path_bits.as_deref(), batch.as_ref()),
+    };
 
-    info!("Proof verification result: {}", is_valid);
+    let cached = app_state.verification_cache.get(&key);
+    let is_valid = match cached {
+        Some(valid) => valid,
+        None => {
+            let proof_valid = match req.format {
+                // For MVP, legacy sibling lists only get a simple validation
+                ProofFormat::Siblings => !req.leaf_hash.is_empty() 
+                    && !req.proof.is_empty() 
+                    && !req.root.is_empty()
+                    && req.leaf_hash.starts_with("0x")
+                    && req.root.starts_with("0x"),
+                ProofFormat::Raw | ProofFormat::SortedPairs => verify_formatted_proof(&req).unwrap_or_else(|e| {
+                    warn!("Malformed proof: {}", e);
+                    false
+                }),
+            };
+            let in_batch = batch.is_none_or(|batch| parse_hash32(&req.root).is_ok_and(|root| batch.commits_to(&root)));
+            let valid = proof_valid && in_batch;
+            app_state.verification_cache.put(key, valid);
+            valid
+        }
+    };
+
+    info!("Proof verification result: {} (cached: {})", is_valid, cached.is_some());
     
     Ok(Json(json!({
         "valid": is_valid,
         "leaf_hash": req.leaf_hash,
         "root": req.root,
-        "proof_length": req.proof.len()
+        "format": req.format,
+        "proof_length": req.proof.len(),
+        "batch_id": req.batch_id,
+        "cached": cached.is_some()
     })))
 }
 
+/// Hit rates of the verification cache and the memoized batch public inputs
+pub async fn get_verification_cache_metrics(State(app_state): State<AppState>) -> Json<VerificationCacheMetrics> {
+    Json(app_state.verification_cache.snapshot())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MultiProofRequest {
+    pub batch_id: u64,
+    /// Positions of the orders in the batch, as `leaf_index` in single proofs
+    pub order_indices: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MultiProofResponse {
+    pub batch_id: u64,
+    /// Order ids of `leaves`, in the same order
+    pub order_ids: Vec<String>,
+    pub leaf_indices: Vec<usize>,
+    pub leaves: Vec<String>,
+    pub proof: Vec<String>,
+    pub proof_flags: Vec<bool>,
+    pub root: String,
+    pub valid: bool,
+    /// Sibling hashes the same orders would need as separate proofs
+    pub separate_proof_hashes: usize,
+}
+
+fn prefixed(hash: String) -> String {
+    format!("0x{}", hash)
+}
+
+/// One proof for several orders of a batch, so they can be claimed in a single call
+pub async fn get_order_multiproof(
+    State(app_state): State<AppState>,
+    Json(req): Json<MultiProofRequest>,
+) -> Result<Json<MultiProofResponse>, ApiError> {
+    info!("Generating multiproof for {} orders in batch {}", req.order_indices.len(), req.batch_id);
+
+    if req.order_indices.is_empty() {
+        return Err(ProofError::InvalidMultiProof("order_indices is empty").into());
+    }
+
+    let batch_orders = crate::database::helpers::get_orders_by_batch(&app_state.db, req.batch_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching batch orders: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if batch_orders.is_empty() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "batch_not_found", format!("Batch {} has no orders", req.batch_id)));
+    }
+    if let Some(index) = req.order_indices.iter().find(|index| **index >= batch_orders.len()) {
+        return Err(ProofError::LeafIndexOutOfRange(*index).into());
+    }
+
+    let mut manager = MerkleTreeManager::new();
+    manager.build_orders_tree(&batch_orders, req.batch_id).map_err(ProofError::Tree)?;
+    let multiproof = manager.generate_order_multiproof(&req.order_indices).map_err(ProofError::Tree)?;
+    let separate_proof_hashes = multiproof.leaf_indices.len() * manager.order_tree.depth();
+
+    let hashes = |values: &[String]| values.iter().map(|value| parse_hash32(value)).collect::<Result<Vec<_>, _>>();
+    let computed = proof_format::process_multi_proof(
+        &hashes(&multiproof.leaves)?,
+        &multiproof.leaf_indices,
+        &hashes(&multiproof.proof)?,
+        &multiproof.proof_flags,
+    )?;
+
+    info!(
+        "Generated multiproof for {} orders in batch {}: {} hashes instead of {}",
+        multiproof.leaf_indices.len(), req.batch_id, multiproof.proof.len(), separate_proof_hashes
+    );
+    Ok(Json(MultiProofResponse {
+        batch_id: req.batch_id,
+        order_ids: multiproof.leaf_indices.iter().map(|index| batch_orders[*index].id.clone()).collect(),
+        valid: computed == parse_hash32(&multiproof.root)?,
+        leaf_indices: multiproof.leaf_indices,
+        leaves: multiproof.leaves.into_iter().map(prefixed).collect(),
+        proof: multiproof.proof.into_iter().map(prefixed).collect(),
+        proof_flags: multiproof.proof_flags,
+        root: prefixed(multiproof.root),
+        separate_proof_hashes,
+    }))
+}
+
 /// Get all available proofs for a batch
 pub async fn get_batch_proofs(
     State(app_state): State<AppState>,
-    Path(batch_id): Path<u32>,
+    Path(batch_id): Path<u64>,
     Query(query): Query<ProofQuery>,
 ) -> Result<Json<Value>, StatusCode> {
     info!("Getting all proofs for batch {}", batch_id);
@@ -162,7 +606,7 @@ pub async fn get_batch_proofs(
                     let order_id: String = row.try_get("id").unwrap_or_default();
                     json!({
                         "order_id": order_id,
-                        "leaf_hash": format!("0x{:064x}", (batch_id * 100 + i as u32) as u64),
+                        "leaf_hash": format!("0x{:064x}", batch_id * 100 + i as u64),
                         "available": true
                     })
                 })
@@ -223,12 +667,21 @@ pub async fn get_proof_stats(
         .try_get("count")
         .unwrap_or(0);
 
+    // Read from the stats table rather than the prover, which stays locked while proving
+    let proofs = stats::proof_totals(&app_state.db).await.map_err(|e| {
+        error!("Database error getting proof totals: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
     // Get batch processor stats
     let processor = app_state.batch_processor.lock().await;
     let batch_stats = processor.get_stats();
     
     Ok(Json(json!({
         "total_orders": orders_count,
+        "total_proofs_generated": proofs.generated,
+        "total_proof_failures": proofs.failures,
+        "average_generation_time_ms": proofs.average_generation_time_ms(),
         "current_batch_id": batch_stats.next_batch_id - 1,
         "current_batch_orders": batch_stats.current_batch_orders,
         "total_accounts": batch_stats.total_accounts,
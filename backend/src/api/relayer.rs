@@ -8,6 +8,8 @@ use serde_json::{json, Value};
 use tracing::{info, warn, error};
 
 use super::AppState;
+use crate::services::{relayer::ScanMetrics, signer_funds::SignerFunds};
+use vapor_chain::chain_reads::ReadCacheMetrics;
 
 #[derive(Debug, Deserialize)]
 pub struct ProcessEventsQuery {
@@ -23,6 +25,10 @@ pub struct RelayerStatsResponse {
     pub total_orders_created: u64,
     pub last_poll_time: Option<String>,
     pub current_block: Option<u64>,
+    pub signer_type: Option<String>,
+    pub signer_address: Option<String>,
+    /// The signer's ETH balance, submissions it covers and runway; None if it could not be read
+    pub signer_funds: Option<SignerFunds>,
 }
 
 /// Get relayer service status and statistics
@@ -33,7 +39,10 @@ pub async fn get_relayer_status(
 
     if let Some(relayer_service) = &app_state.relayer_service {
         let relayer = relayer_service.lock().await;
-        let stats = relayer.get_stats();
+        let stats = relayer.get_stats().await.map_err(|e| {
+            error!("Failed to read relayer stats: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
         
         // Try to get current block number
         let current_block = if let Some(blockchain_client) = &app_state.blockchain_client {
@@ -42,6 +51,13 @@ pub async fn get_relayer_status(
             None
         };
 
+        let signer = app_state.blockchain_client.as_ref()
+            .and_then(|client| client.signer.as_ref());
+        let signer_funds = match &app_state.blockchain_client {
+            Some(client) => app_state.balance_alerts.evaluate_signer(client, &app_state.db).await,
+            None => None,
+        };
+
         let response = RelayerStatsResponse {
             is_running: stats.is_running,
             last_processed_block: stats.last_processed_block,
@@ -49,6 +65,9 @@ pub async fn get_relayer_status(
             total_orders_created: stats.total_orders_created,
             last_poll_time: stats.last_poll_time.map(|t| t.to_rfc3339()),
             current_block,
+            signer_type: signer.map(|signer| signer.kind().to_string()),
+            signer_address: signer.map(|signer| format!("{:?}", signer.address())),
+            signer_funds,
         };
 
         Ok(Json(response))
@@ -124,7 +143,24 @@ pub async fn update_relayer_config(
     }
 }
 
+/// Catch-up scan throughput (GET /relayer/metrics)
+///
+/// Served from a shared handle, so it answers even while the relayer loop is busy.
+pub async fn get_relayer_metrics(State(app_state): State<AppState>) -> Json<ScanMetrics> {
+    info!("Getting relayer scan metrics");
+
+    Json(app_state.relayer_metrics.snapshot())
+}
+
+/// Hits and misses of the cached chain reads (GET /relayer/read-cache)
+pub async fn get_read_cache_metrics(State(app_state): State<AppState>) -> Json<ReadCacheMetrics> {
+    Json(app_state.chain_reads.snapshot())
+}
+
 /// Get current blockchain status as seen by relayer
+///
+/// `finality` reports the configured and active finality mode; `blocks_behind` counts from
+/// the final block rather than the chain head.
 pub async fn get_blockchain_status(
     State(app_state): State<AppState>,
 ) -> Result<Json<Value>, StatusCode> {
@@ -133,18 +169,24 @@ pub async fn get_blockchain_status(
     if let Some(relayer_service) = &app_state.relayer_service {
         let relayer = relayer_service.lock().await;
         
+        // Which finality mode is in use, and the final block the relayer scans up to
+        let finality = app_state.relayer_metrics.finality();
+
         match relayer.get_current_block().await {
             Ok(current_block) => {
-                let stats = relayer.get_stats();
-                let blocks_behind = current_block.saturating_sub(stats.last_processed_block);
+                let last_processed_block = relayer.last_processed_block();
+                // Blocks past the final block are not processed yet by design
+                let final_block = finality.as_ref().map_or(current_block, |finality| finality.final_block);
+                let blocks_behind = final_block.saturating_sub(last_processed_block);
 
                 Ok(Json(json!({
                     "status": "connected",
                     "current_block": current_block,
-                    "last_processed_block": stats.last_processed_block,
+                    "last_processed_block": last_processed_block,
                     "blocks_behind": blocks_behind,
                     "is_synced": blocks_behind <= 5, // Consider synced if within 5 blocks
-                    "relayer_running": stats.is_running
+                    "relayer_running": relayer.is_running(),
+                    "finality": finality
                 })))
             }
             Err(e) => {
@@ -152,7 +194,8 @@ pub async fn get_blockchain_status(
                 Ok(Json(json!({
                     "status": "error",
                     "message": format!("Failed to get blockchain status: {}", e),
-                    "relayer_running": relayer.get_stats().is_running
+                    "relayer_running": relayer.is_running(),
+                    "finality": finality
                 })))
             }
         }
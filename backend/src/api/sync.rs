@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use super::{error::ApiError, AppState};
+use crate::services::archival::BatchSnapshot;
+use crate::services::state_sync::{self, StateDiff};
+
+#[derive(Debug, Deserialize)]
+pub struct StateDiffQuery {
+    /// Batch the client's mirror is at, 0 for an empty mirror
+    pub from_batch: u64,
+    /// Include inclusion proofs of every changed account against the resulting root
+    #[serde(default)]
+    pub proofs: bool,
+}
+
+/// Balance changes from `from_batch` up to the latest finalized batch
+///
+/// Deltas are ordered by address, then token, and the checksum binds them to the resulting
+/// state root, so a client that applies them can confirm it arrived at `to_root`.
+pub async fn get_state_diff(
+    State(app_state): State<AppState>,
+    Query(query): Query<StateDiffQuery>,
+) -> Result<Json<StateDiff>, ApiError> {
+    info!("Getting state diff from batch {}", query.from_batch);
+
+    let latest = app_state.archive.latest_batch_id()
+        .await
+        .map_err(|e| {
+            error!("Failed to find the latest state snapshot: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "no_finalized_batch", "No batch has been finalized yet"))?;
+    if query.from_batch > latest {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "batch_not_found",
+            format!("Batch {} is past the latest finalized batch {}", query.from_batch, latest),
+        ));
+    }
+
+    let to = load_snapshot(&app_state, latest).await?;
+    let from = match query.from_batch {
+        0 => None,
+        batch_id => Some(load_snapshot(&app_state, batch_id).await?),
+    };
+
+    let diff = state_sync::diff(from.as_ref(), &to, query.proofs).map_err(|e| {
+        error!("Failed to diff batch {} against batch {}: {}", query.from_batch, latest, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(diff))
+}
+
+async fn load_snapshot(app_state: &AppState, batch_id: u64) -> Result<BatchSnapshot, ApiError> {
+    app_state.archive.load_snapshot(batch_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load state snapshot for batch {}: {}", batch_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "batch_not_found", format!("No state snapshot for batch {}", batch_id)))
+}
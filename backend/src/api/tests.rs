@@ -7,35 +7,39 @@ mod integration_tests {
         Router,
     };
     use serde_json::{json, Value};
-    use sqlx::SqlitePool;
+    use sqlx::{Row, SqlitePool};
     use std::sync::Arc;
     use tokio::sync::Mutex;
     use tower::util::ServiceExt;
     use crate::{
-        api::{AppState, health, orders, fillers, batch, proofs, relayer},
-        config::Config,
-        models::{CreateOrderRequest, OrderType, OrderStatus, OrderResponse, LockOrderRequest, SubmitPaymentProofRequest, OrderStatusResponse},
+        api::{AppState, accounts, admin, health, orders, order_queue, fillers, batch, proofs, relayer, market, graphql, overview, explorer, events, handover, sync, notifications},
+        config::{Config, DeploymentProfile},
+        models::{CreateOrderRequest, OrderType, OrderStatus, OrderResponse, LockOrderRequest, SubmitPaymentProofRequest, OrderStatusResponse, ClaimRecord, ClaimResponse},
         services::{
             matching_engine::MatchingEngine,
             batch_processor::BatchProcessor,
         },
-        blockchain::BlockchainClient,
     };
     use axum::routing::{get, post};
 
     async fn create_test_app() -> (Router, SqlitePool) {
+        create_test_app_with_config(Config::default()).await
+    }
+
+    async fn create_test_app_with_config(config: Config) -> (Router, SqlitePool) {
         // Create in-memory database for testing
         let db = SqlitePool::connect(":memory:").await.unwrap();
         
         // Run migrations
         crate::database::run_migrations(&db).await.unwrap();
         
-        // Create mock config
-        let config = Config::default();
-        
         // Create app state
-        let app_state = AppState::new(config, db.clone());
-        
+        create_test_app_with_state(AppState::new(config, db)).await
+    }
+
+    async fn create_test_app_with_state(app_state: AppState) -> (Router, SqlitePool) {
+        let db = app_state.db.clone();
+
         // Build test router with all routes
         let app = Router::new()
             // Health endpoints
@@ -45,36 +49,143 @@ mod integration_tests {
             // Order management endpoints
             .route("/api/v1/orders", post(orders::create_order))
             .route("/api/v1/orders", get(orders::list_orders))
+            .route("/api/v1/orders/quote", post(orders::quote_order))
+            .route("/api/v1/transfers", post(orders::create_transfer))
             .route("/api/v1/orders/:order_id", get(orders::get_order))
             .route("/api/v1/orders/:order_id/status", get(orders::get_order_status))
+            .route("/api/v1/orders/:order_id/permit", get(orders::get_order_permit))
+            .route("/api/v1/orders/:order_id/history", get(orders::get_order_history))
+            .route("/api/v1/orders/:order_id/receipt", get(orders::get_order_receipt))
+            .route("/api/v1/receipts/verify", post(orders::verify_receipt))
+            .route("/api/v1/orders/:order_id/mark-discovery", post(orders::mark_discovery))
+            .route("/api/v1/orders/:order_id/settle-partial", post(orders::settle_partial))
             .route("/api/v1/orders/:order_id/mark-paid", post(orders::mark_paid))
             .route("/api/v1/orders/match", post(orders::match_orders))
+            .route("/api/v1/orders/sla-metrics", get(orders::get_sla_metrics))
+            .route("/api/v1/stats/slo", get(orders::get_slo_report))
+            .route("/api/v1/stats/matching", get(orders::get_matching_stats))
+            .route("/api/v1/events", get(events::list_events))
+            .route("/api/v1/order-queue/messages/:message_id", get(order_queue::get_queued_message))
+            .route("/api/v1/market/summary", get(market::get_market_summary))
+            .route("/api/v1/explorer/state/:batch_id/leaves", get(explorer::get_state_leaves))
+            .route("/api/v1/explorer/batches/:batch_id/manifest", get(explorer::get_batch_manifest))
+            .route("/api/v1/explorer/manifests/verify", post(explorer::verify_manifest))
+            .route("/api/v1/graphql", post(graphql::graphql_handler))
             
             // Filler endpoints
             .route("/api/v1/fillers/discovery", get(fillers::get_discovery_orders))
+            .route("/api/v1/fillers/:filler_id/capabilities", get(fillers::get_capabilities).put(fillers::register_capabilities))
+            .route("/api/v1/fillers/:filler_id/claim-relay", get(fillers::get_claim_relay).put(fillers::set_claim_relay))
+            .route("/api/v1/fillers/:filler_id/collateral", get(fillers::get_collateral))
+            .route("/api/v1/bank-services", get(fillers::get_bank_services))
+            .route("/api/v1/fillers/ws", get(fillers::filler_feed))
             .route("/api/v1/fillers/orders/:order_id/lock", post(fillers::lock_order))
             .route("/api/v1/fillers/orders/:order_id/payment-proof", post(fillers::submit_payment_proof))
+            .route("/api/v1/fillers/payment-proof/validate", post(fillers::validate_payment_proof))
+            .route("/api/v1/fillers/claim", post(fillers::claim_tokens))
+            .route("/api/v1/fillers/claim/preview", post(fillers::preview_claim))
+            .route("/api/v1/fillers/claims", get(fillers::list_claims))
+            .route("/api/v1/fillers/claims/:claim_id", get(fillers::get_claim))
             
             // Batch processing endpoints
             .route("/api/v1/batch/start", post(batch::start_batch))
             .route("/api/v1/batch/finalize", post(batch::finalize_batch))
+            .route("/api/v1/batch/abort", post(batch::abort_batch))
+            .route("/api/v1/batch/aborted", get(batch::list_aborted_batches))
+            .route("/api/v1/batch/dry-run", post(batch::dry_run_batch))
             .route("/api/v1/batch/prove", post(batch::prove_batch))
             .route("/api/v1/batch/stats", get(batch::get_batch_stats))
+            .route("/api/v1/batch/archive", get(batch::get_archive_stats))
+            .route("/api/v1/batches/by-root/:root", get(batch::get_batches_by_root))
+            .route("/api/v1/batch/submissions", get(batch::get_submission_sizes))
+            .route("/api/v1/batch/:batch_id/costs", get(batch::get_batch_costs))
+            .route("/api/v1/stats/costs", get(batch::get_cost_history))
             .route("/api/v1/batch/current", get(batch::get_current_batch))
-            .route("/api/v1/batch/init-account", post(batch::init_account))
+            // Deprecated alias of POST /api/v1/accounts
+            .route("/api/v1/batch/init-account", post(accounts::init_account_deprecated))
+            
+            // Account endpoints
+            .route("/api/v1/accounts", get(accounts::list_accounts).post(accounts::init_account))
+            .route("/api/v1/accounts/system", get(accounts::get_system_ledger))
+            .route("/api/v1/accounts/:address", get(accounts::get_account))
+            .route("/api/v1/admin/accounts/bulk", post(accounts::init_accounts_bulk))
             
             // Proof endpoints
             .route("/api/v1/proofs/order/:batch_id/:order_id", get(proofs::get_order_proof))
             .route("/api/v1/proofs/account/:address", get(proofs::get_account_proof))
+            .route("/api/v1/proofs/account/:address/attestation", get(proofs::get_balance_attestation))
+            .route("/api/v1/proofs/attestations/verify", post(proofs::verify_balance_attestation))
+            .route("/api/v1/sync/diff", get(sync::get_state_diff))
+            .route("/api/v1/notifications/:address", get(notifications::get_notifications))
+            .route("/api/v1/notifications/:address/preferences", get(notifications::get_notification_preferences).put(notifications::set_notification_preferences))
             .route("/api/v1/proofs/verify", post(proofs::verify_proof))
+            .route("/api/v1/proofs/verify/cache", get(proofs::get_verification_cache_metrics))
+            .route("/api/v1/proofs/multiproof", post(proofs::get_order_multiproof))
             .route("/api/v1/proofs/batch/:batch_id", get(proofs::get_batch_proofs))
             .route("/api/v1/proofs/stats", get(proofs::get_proof_stats))
             
             // Relayer endpoints
             .route("/api/v1/relayer/status", get(relayer::get_relayer_status))
+            .route("/api/v1/relayer/metrics", get(relayer::get_relayer_metrics))
+            .route("/api/v1/relayer/read-cache", get(relayer::get_read_cache_metrics))
             .route("/api/v1/relayer/process-events", post(relayer::process_events_manually))
             .route("/api/v1/relayer/config", post(relayer::update_relayer_config))
             .route("/api/v1/relayer/blockchain", get(relayer::get_blockchain_status))
+            .route("/api/v1/prover/config", get(batch::get_prover_config).post(batch::update_prover_config))
+            
+            // Admin endpoints
+            .route("/api/v1/admin/overview", get(overview::get_overview))
+            .route("/api/v1/admin/config", get(admin::get_config))
+            .route("/api/v1/admin/maintenance", get(admin::get_maintenance).put(admin::set_maintenance))
+            .route("/api/v1/admin/withdrawals/lists", get(admin::get_withdrawal_lists))
+            .route("/api/v1/admin/withdrawals/lists/:address", axum::routing::put(admin::set_withdrawal_list_entry)
+                .delete(admin::remove_withdrawal_list_entry))
+            .route("/api/v1/admin/withdrawals/usage/:address", get(admin::get_withdrawal_usage))
+            .route("/api/v1/admin/fixtures", get(admin::get_verification_fixtures))
+            .route("/api/v1/admin/batch-caps", get(admin::get_batch_caps))
+            .route("/api/v1/admin/batch-caps/:token_id", axum::routing::put(admin::set_batch_cap).delete(admin::clear_batch_cap))
+            .route("/api/v1/admin/rates/:token_id", axum::routing::put(admin::set_token_rate))
+            .route("/api/v1/admin/registry", get(admin::get_registry))
+            .route("/api/v1/admin/registry/metrics", get(admin::get_registry_metrics))
+            .route("/api/v1/admin/registry/tokens/:token_id", axum::routing::put(admin::set_registry_token))
+            .route("/api/v1/admin/registry/bank-services/:name", axum::routing::put(admin::set_registry_bank_service))
+            .route("/api/v1/admin/balance-alerts", get(admin::get_balance_alerts))
+            .route("/api/v1/admin/claims/reconciliation", get(admin::get_claim_reconciliation))
+            .route("/api/v1/admin/sagas", get(admin::list_sagas))
+            .route("/api/v1/admin/sagas/:order_id", get(admin::get_saga))
+            .route("/api/v1/admin/sagas/:order_id/abort", post(admin::abort_saga))
+            .route("/api/v1/admin/jobs", get(admin::list_jobs))
+            .route("/api/v1/admin/fillers/:filler_id/collateral/flag", axum::routing::delete(admin::clear_collateral_flag))
+            .route("/api/v1/admin/reconciliation/reports", get(admin::list_reconciliation_reports))
+            .route("/api/v1/admin/reconciliation/reports/latest", get(admin::get_latest_reconciliation_report))
+            .route("/api/v1/admin/reconciliation/reports/:id", get(admin::get_reconciliation_report))
+            .route("/api/v1/admin/reconciliation/run", post(admin::run_reconciliation))
+            .route("/api/v1/admin/retention/audit", get(admin::get_retention_audit))
+            .route("/api/v1/admin/retention/run", post(admin::run_retention))
+            .route("/api/v1/admin/screening/reviews", get(admin::list_screening_reviews))
+            .route("/api/v1/admin/screening/reviews/:id", post(admin::review_screening_result))
+            .route("/api/v1/admin/order-reviews", get(admin::list_order_reviews))
+            .route("/api/v1/admin/order-reviews/:order_id", post(admin::decide_order_review))
+            .route("/api/v1/admin/diagnostics/queries", get(admin::get_query_diagnostics))
+            .route("/api/v1/admin/backups", get(admin::list_backups).post(admin::take_backup))
+            .route("/api/v1/admin/backups/:id/verify", post(admin::verify_backup))
+            .route("/api/v1/admin/backups/:id/restore", post(admin::restore_backup))
+            .route("/api/v1/admin/handover", get(handover::get_handover))
+            .route("/api/v1/admin/handover/export", post(handover::export_handover))
+            .route("/api/v1/admin/handover/import", post(handover::import_handover));
+
+        #[cfg(feature = "fault-injection")]
+        let app = app
+            .route("/api/v1/admin/faults", get(crate::api::faults::get_faults)
+                .put(crate::api::faults::set_faults)
+                .delete(crate::api::faults::reset_faults));
+
+        let app = app
+            .layer(axum::middleware::from_fn_with_state(app_state.clone(), crate::api::caching::conditional_get))
+            .layer(axum::middleware::from_fn_with_state(app_state.clone(), admin::maintenance_guard))
+            .layer(axum::middleware::from_fn_with_state(app_state.clone(), admin::admin_guard))
+            .layer(axum::middleware::from_fn_with_state(app_state.clone(), crate::api::limits::enforce_request_limits))
+            .layer(axum::extract::DefaultBodyLimit::max(app_state.config.request_limits.max_body_bytes))
             .with_state(app_state);
         
         (app, db)
@@ -98,6 +209,9 @@ mod integration_tests {
             .await
             .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let health: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(health["profile"], "dev");
     }
 
     #[tokio::test]
@@ -114,6 +228,10 @@ mod integration_tests {
             bank_account: Some("12345678".to_string()),
             bank_service: Some("PayPal Hong Kong".to_string()),
             banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
         };
 
         let response = app
@@ -173,6 +291,10 @@ mod integration_tests {
             bank_account: Some("12345678".to_string()),
             bank_service: Some("PayPal Hong Kong".to_string()),
             banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
         };
 
         let response = app
@@ -227,6 +349,10 @@ mod integration_tests {
                 bank_account: Some(format!("1234567{}", i)),
                 bank_service: Some("PayPal Hong Kong".to_string()),
                 banking_hash: None,
+                permit: None,
+                priority_fee: None,
+                confirm_duplicate: false,
+                quote_id: None,
             };
 
             let _ = app
@@ -278,6 +404,10 @@ mod integration_tests {
             bank_account: Some("12345678".to_string()),
             bank_service: Some("PayPal Hong Kong".to_string()),
             banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
         };
 
         let response = app
@@ -340,6 +470,10 @@ mod integration_tests {
             bank_account: Some("12345678".to_string()),
             bank_service: Some("PayPal Hong Kong".to_string()),
             banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
         };
 
         let response = app
@@ -370,6 +504,8 @@ mod integration_tests {
         let lock_request = LockOrderRequest {
             filler_id: "filler_123".to_string(),
             amount: "500000000000000000".to_string(), // 0.5 ETH
+            accepted_rate: None,
+            fee_bps: None,
         };
 
         let response = app
@@ -419,6 +555,10 @@ mod integration_tests {
             bank_account: Some("12345678".to_string()),
             bank_service: Some("PayPal Hong Kong".to_string()),
             banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
         };
 
         let response = app
@@ -449,7 +589,8 @@ mod integration_tests {
 
         // Test submitting payment proof
         let payment_proof_request = SubmitPaymentProofRequest {
-            banking_hash: "0xabcdef123456789".to_string(),
+            banking_hash: Some("0xabcdef123456789".to_string()),
+            payment_proof: None,
         };
 
         let response = app
@@ -570,6 +711,7 @@ mod integration_tests {
 
         // Test getting blockchain status
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
                     .uri("/api/v1/relayer/blockchain")
@@ -581,6 +723,38 @@ mod integration_tests {
 
         // Should return 503 since no blockchain client is configured in tests
         assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // Read cache counters too
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/relayer/read-cache")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let cache: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!((cache["ttl_seconds"].as_u64(), cache["entries"].as_u64()), (Some(5), Some(0)));
+
+        // Scan metrics are served without a relayer
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/relayer/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let metrics: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(metrics["blocks_scanned"], 0);
+        assert!(metrics["last_scan"].is_null());
     }
 
     #[tokio::test]
@@ -621,6 +795,8 @@ mod integration_tests {
         let lock_request = LockOrderRequest {
             filler_id: "filler_123".to_string(),
             amount: "500000000000000000".to_string(),
+            accepted_rate: None,
+            fee_bps: None,
         };
 
         let response = app
@@ -637,4 +813,5211 @@ mod integration_tests {
 
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
+
+    #[tokio::test]
+    async fn test_sla_metrics_endpoint() {
+        let (app, _db) = create_test_app().await;
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/orders/sla-metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let metrics: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(metrics["total_breaches"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_slo_report_and_order_position() {
+        let mut config = Config::default();
+        config.slo.p95_targets.discovery_seconds = Some(600);
+        let (app, db) = create_test_app_with_config(config).await;
+
+        // Orders that waited 5, 10, 20 and 40 minutes for a filler, then one still waiting
+        let mut waiting_id = String::new();
+        for minutes in [5, 10, 20, 40, 0] {
+            let mut order = crate::models::Order::new(CreateOrderRequest {
+                order_type: OrderType::BridgeIn,
+                from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+                to_address: None,
+                token_id: 1,
+                amount: "1000".to_string(),
+                bank_account: Some("12345678".to_string()),
+                bank_service: Some("Wise".to_string()),
+                banking_hash: None,
+                permit: None,
+                priority_fee: None,
+                confirm_duplicate: false,
+                quote_id: None,
+            });
+            let discovered_at = chrono::Utc::now() - chrono::Duration::minutes(if minutes == 0 { 15 } else { 60 });
+            order.status = if minutes == 0 { OrderStatus::Discovery } else { OrderStatus::Locked };
+            crate::database::helpers::insert_order(&db, &order).await.unwrap();
+            let mut transitions = vec![(OrderStatus::Pending, OrderStatus::Discovery, discovered_at)];
+            if minutes > 0 {
+                transitions.push((OrderStatus::Discovery, OrderStatus::Locked, discovered_at + chrono::Duration::minutes(minutes)));
+            }
+            for (from, to, at) in transitions {
+                sqlx::query("INSERT INTO order_status_history (order_id, from_status, to_status, created_at) VALUES (?1, ?2, ?3, ?4)")
+                    .bind(&order.id)
+                    .bind(from as i32)
+                    .bind(to as i32)
+                    .bind(at)
+                    .execute(&db)
+                    .await
+                    .unwrap();
+            }
+            if minutes == 0 {
+                waiting_id = order.id;
+            }
+        }
+
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/stats/slo").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: Value = serde_json::from_slice(&body).unwrap();
+        let wise = &report["corridors"][0];
+        assert_eq!(wise["bank_service"], "Wise");
+        assert_eq!(wise["phases"]["discovery"]["samples"], 4);
+        assert_eq!(wise["phases"]["discovery"]["p95_seconds"], 2400);
+        assert_eq!(wise["phases"]["discovery"]["met"], false);
+        assert_eq!(report["overall"]["discovery"]["p50_seconds"], 600);
+
+        let response = app
+            .oneshot(Request::builder().uri(format!("/api/v1/orders/{}/status", waiting_id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let status: OrderStatusResponse = serde_json::from_slice(&body).unwrap();
+        let position = status.slo_position.unwrap();
+        assert_eq!(position.phase, crate::services::slo::SloPhase::Discovery);
+        assert_eq!(position.percentile, 50);
+    }
+
+    #[tokio::test]
+    async fn test_create_order_with_permit() {
+        use vapor_chain::signer::{signature_to_hex, LocalKeySigner, Signer};
+
+        let domain_separator = [7u8; 32];
+        let mut config = Config::default();
+        config.blockchain.permit_domain_separators.insert(1, domain_separator);
+        let (app, _db) = create_test_app_with_config(config).await;
+
+        let signer = LocalKeySigner::from_hex("0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80").unwrap();
+        let owner = format!("{:?}", signer.address());
+        let mut permit = crate::models::PermitData {
+            owner: owner.clone(),
+            spender: Config::default().blockchain.contract_address,
+            value: "1000000".to_string(),
+            nonce: Some("0".to_string()),
+            deadline: (chrono::Utc::now().timestamp() + 3600) as u64,
+            signature: String::new(),
+        };
+        let digest = web3::types::H256::from(permit.digest(&domain_separator).unwrap());
+        permit.signature = signature_to_hex(&signer.sign_digest(digest, None).await.unwrap()).unwrap();
+
+        let mut create_request = CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: Some(owner),
+            to_address: None,
+            token_id: 1,
+            amount: "1000000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            permit: Some(permit.clone()),
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        };
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&create_request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/orders/{}/permit", order.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let stored: crate::models::PermitData = serde_json::from_slice(&body).unwrap();
+        assert_eq!(stored, permit);
+
+        // Expired permits, and signatures that do not recover to the owner, are rejected
+        let mut expired = permit.clone();
+        expired.deadline = 1;
+        let mut forged = permit.clone();
+        forged.signature = format!("0x{}1b", "11".repeat(64));
+        for rejected in [expired, forged] {
+            create_request.permit = Some(rejected);
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/orders")
+                        .header("content-type", "application/json")
+                        .body(Body::from(serde_json::to_string(&create_request).unwrap()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let error: Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(error["error"], "invalid_permit");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mark_discovery_records_history() {
+        let (app, _db) = create_test_app().await;
+
+        let create_request = CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        };
+
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&create_request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/orders/{}/mark-discovery", order.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/orders/{}/history", order.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let history: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(history.as_array().unwrap().len(), 1);
+        assert_eq!(history[0]["from_status"], "Pending");
+        assert_eq!(history[0]["to_status"], "Discovery");
+        assert_eq!(history[0]["reason"], "manual");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders/missing/mark-discovery")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_lock_order_enforces_filler_limits() {
+        let mut config = Config::default();
+        config.filler.default_limits.max_concurrent_locks = 5;
+        let (app, db) = create_test_app_with_config(config).await;
+
+        for i in 0..6 {
+            let order = crate::models::Order {
+                id: format!("limit_order_{}", i),
+                order_type: OrderType::BridgeIn,
+                status: OrderStatus::Discovery,
+                from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+                to_address: None,
+                token_id: 1,
+                amount: "1000000".to_string(),
+                bank_account: Some("12345678".to_string()),
+                bank_service: Some("PayPal Hong Kong".to_string()),
+                banking_hash: None,
+                filler_id: None,
+                locked_amount: None,
+                batch_id: None,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            };
+            crate::database::helpers::insert_order(&db, &order).await.unwrap();
+        }
+
+        let lock_request = LockOrderRequest {
+            filler_id: "filler_limited".to_string(),
+            amount: "1000000".to_string(),
+            accepted_rate: None,
+            fee_bps: None,
+        };
+
+        for i in 0..6 {
+            let response = app.clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/api/v1/fillers/orders/limit_order_{}/lock", i))
+                        .header("content-type", "application/json")
+                        .body(Body::from(serde_json::to_string(&lock_request).unwrap()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            if i < 5 {
+                assert_eq!(response.status(), StatusCode::OK);
+                continue;
+            }
+
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let error: Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(error["error"], "filler_limit_exceeded");
+            assert_eq!(error["details"]["limit"], "max_concurrent_locks");
+            assert_eq!(error["details"]["max"], 5);
+        }
+
+        let (open_locks, locked_value) = crate::database::helpers::get_filler_lock_usage(&db, "filler_limited").await.unwrap();
+        assert_eq!(open_locks, 5);
+        assert_eq!(locked_value, 5_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_historical_account_proof_from_snapshot() {
+        let (app, _db) = create_test_app().await;
+        let address = "0x1234567890123456789012345678901234567890";
+
+        let init_request = serde_json::json!({
+            "address": address,
+            "token_id": 1,
+            "initial_balance": "1000",
+        });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/batch/init-account")
+                    .header("content-type", "application/json")
+                    .body(Body::from(init_request.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for uri in ["/api/v1/batch/start", "/api/v1/batch/finalize"] {
+            let response = app.clone()
+                .oneshot(Request::builder().method("POST").uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/proofs/account/{}?batch_id=1&format=raw", address))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let proof: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(proof["batch_id"], 1);
+        assert!(proof["path_bits"].is_array());
+
+        // Unknown batches and accounts missing from the snapshot have nothing to prove
+        for uri in [
+            format!("/api/v1/proofs/account/{}?batch_id=9", address),
+            "/api/v1/proofs/account/0x9999999999999999999999999999999999999999?batch_id=1".to_string(),
+        ] {
+            let response = app.clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/batch/archive").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let stats: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(stats["hot_snapshots"], 1);
+        assert_eq!(stats["archived_snapshots"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_partial_settlement_links_child_orders() {
+        let (app, db) = create_test_app().await;
+
+        let order = crate::models::Order {
+            id: "partial_order".to_string(),
+            order_type: OrderType::BridgeIn,
+            status: OrderStatus::MarkPaid,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: Some("0xreceipt".to_string()),
+            filler_id: Some("filler_123".to_string()),
+            locked_amount: Some("500".to_string()),
+            batch_id: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        crate::database::helpers::insert_order(&db, &order).await.unwrap();
+
+        let settle = |app: Router| async move {
+            app.oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders/partial_order/settle-partial")
+                    .header("content-type", "application/json")
+                    .body(Body::from("{}"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+        };
+
+        let response = settle(app.clone()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let split: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(split["settled_order"]["amount"], "500");
+        assert_eq!(split["settled_order"]["status"], "Settled");
+        assert_eq!(split["remainder_order"]["amount"], "500");
+        assert_eq!(split["remainder_order"]["status"], "Discovery");
+        assert_eq!(split["remainder_action"], "rediscover");
+
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/orders/partial_order/status").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let status: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status["status"], "Settled");
+        assert_eq!(status["child_order_ids"].as_array().unwrap().len(), 2);
+
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/orders/partial_order-remainder/status").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let status: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status["parent_order_id"], "partial_order");
+
+        // The parent is settled now, so it cannot be split again
+        let response = settle(app).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_proof_submission_sizes_are_recorded() {
+        let mut config = Config::default();
+        config.proof_submission.default_compression = crate::config::CalldataCompression::Zlib;
+        let (app, _db) = create_test_app_with_config(config).await;
+
+        for uri in ["/api/v1/batch/start", "/api/v1/batch/prove"] {
+            let response = app.clone()
+                .oneshot(Request::builder().method("POST").uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/batch/submissions").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let sizes: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(sizes.as_array().unwrap().len(), 1);
+        assert_eq!(sizes[0]["batch_id"], 1);
+        assert_eq!(sizes[0]["compression"], "zlib");
+        assert_eq!(sizes[0]["target"], Config::default().blockchain.proof_verifier_address);
+        assert!(sizes[0]["raw_size"].as_i64().unwrap() > 0);
+        assert!(sizes[0]["calldata_size"].as_i64().unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_submitted_batches_get_signed_manifests() {
+        use crate::services::manifests::BatchManifest;
+
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let mut config = Config::default();
+        config.proof_submission.default_compression = crate::config::CalldataCompression::ZstdArtifact;
+        // Anvil's first default account
+        let signer = vapor_chain::signer::LocalKeySigner::from_hex("0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80").unwrap();
+        let operator = vapor_chain::signer::Signer::address(&signer);
+        let app_state = AppState::new(config, db).with_manifest_signer(Arc::new(signer));
+        let (app, _db) = create_test_app_with_state(app_state).await;
+
+        let get = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap())
+            }
+        };
+        let (status, error) = get("/api/v1/explorer/batches/1/manifest").await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::NOT_FOUND, Some("manifest_not_found")));
+
+        for uri in ["/api/v1/batch/start", "/api/v1/batch/prove"] {
+            let response = app.clone()
+                .oneshot(Request::builder().method("POST").uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let (status, manifest) = get("/api/v1/explorer/batches/1/manifest").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(manifest["batch_id"], 1);
+        assert_eq!(manifest["order_count"], 0);
+        assert_eq!(manifest["orders_root"], crate::merkle::MerkleTreeManager::empty_orders_root());
+        let (_, sizes) = get("/api/v1/batch/submissions").await;
+        assert_eq!(manifest["proof_artifact_hash"], sizes[0]["artifact_hash"]);
+
+        // Anyone holding the manifest can check it was signed by the operator
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/explorer/manifests/verify")
+                    .header("content-type", "application/json")
+                    .body(Body::from(manifest.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let verification: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(verification["valid"], true);
+        let manifest: BatchManifest = serde_json::from_value(manifest).unwrap();
+        assert_eq!(manifest.recover_signer().unwrap(), operator);
+    }
+
+    #[tokio::test]
+    async fn test_submitted_batch_gas_is_attributed_to_its_orders() {
+        let mut config = Config::default();
+        config.proof_submission.cost_weighting = crate::config::CostWeighting::Value;
+        let (app, _db) = create_test_app_with_config(config).await;
+        let alice = "0x1111111111111111111111111111111111111111";
+        let bob = "0x2222222222222222222222222222222222222222";
+
+        let send = |method: &'static str, uri: String, body: Option<Value>| {
+            let app = app.clone();
+            async move {
+                let request = Request::builder().method(method).uri(uri).header("content-type", "application/json");
+                let body = body.map(|body| Body::from(body.to_string())).unwrap_or_else(Body::empty);
+                let response = app.oneshot(request.body(body).unwrap()).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap())
+            }
+        };
+
+        let account = json!({ "address": alice, "token_id": 1, "initial_balance": "1000" });
+        assert_eq!(send("POST", "/api/v1/accounts".to_string(), Some(account)).await.0, StatusCode::OK);
+        let mut order_ids = Vec::new();
+        for amount in ["100", "300"] {
+            let transfer = json!({ "order_type": "Transfer", "from_address": alice, "to_address": bob, "token_id": 1, "amount": amount });
+            let (status, order) = send("POST", "/api/v1/orders".to_string(), Some(transfer)).await;
+            assert_eq!(status, StatusCode::OK);
+            order_ids.push(order["id"].as_str().unwrap().to_string());
+        }
+
+        let (status, error) = send("GET", "/api/v1/batch/1/costs".to_string(), None).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::NOT_FOUND, Some("costs_not_found")));
+        let (status, _) = send("POST", "/api/v1/batch/prove".to_string(), None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Not sent on-chain in tests, so the calldata estimate is split by amount
+        let (status, report) = send("GET", "/api/v1/batch/1/costs".to_string(), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["estimated"], true);
+        let orders = report["orders"].as_array().unwrap();
+        assert_eq!(orders.len(), 2);
+        let gas_used = report["gas_used"].as_u64().unwrap();
+        let share = |order_id: &str| orders.iter().find(|cost| cost["order_id"] == order_id).unwrap()["gas_used"].as_u64().unwrap();
+        assert_eq!(share(&order_ids[0]) + share(&order_ids[1]), gas_used);
+        assert!(share(&order_ids[1]) >= 3 * share(&order_ids[0]));
+        let wei: u128 = report["gas_cost_wei"].as_str().unwrap().parse().unwrap();
+        assert_eq!(wei, gas_used as u128 * 20_000_000_000);
+
+        let (_, order) = send("GET", format!("/api/v1/orders/{}", order_ids[1]), None).await;
+        assert_eq!(order["settlement_cost"]["batch_id"], 1);
+        assert_eq!(order["settlement_cost"]["weighting"], "value");
+        assert_eq!(order["settlement_cost"]["gas_used"].as_u64(), Some(share(&order_ids[1])));
+
+        // The batch also lands in the cost history, priced at PROOF_ETH_USD_PRICE
+        let (status, history) = send("GET", "/api/v1/stats/costs?period=weekly&periods=4".to_string(), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(history["period"], "weekly");
+        let bucket = &history["buckets"][0];
+        assert_eq!((bucket["batches"].as_u64(), bucket["orders"].as_u64(), bucket["gas_used"].as_u64()), (Some(1), Some(2), Some(gas_used)));
+        assert_eq!(bucket["estimated_batches"], 1);
+        assert_eq!(bucket["avg_gas_price_gwei"], 20.0);
+        assert_eq!(bucket["usd_cost"], crate::services::rates::format_rate((wei * 3_000 / 1_000_000_000_000) as u64));
+        assert!(history["trend"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_proof_stats_count_generated_proofs() {
+        let (app, _db) = create_test_app().await;
+
+        for uri in ["/api/v1/batch/start", "/api/v1/batch/prove"] {
+            let response = app.clone()
+                .oneshot(Request::builder().method("POST").uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/proofs/stats").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let stats: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(stats["total_proofs_generated"], 1);
+        assert_eq!(stats["total_proof_failures"], 0);
+        assert!(stats["average_generation_time_ms"].is_u64());
+    }
+
+    #[tokio::test]
+    async fn test_finalized_batch_proofs_are_precomputed() {
+        let (app, db) = create_test_app().await;
+        let address = "0x1234567890123456789012345678901234567890";
+
+        let init_request = json!({ "address": address, "token_id": 1, "initial_balance": "1000" });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/batch/init-account")
+                    .header("content-type", "application/json")
+                    .body(Body::from(init_request.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let create_request = CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: None,
+            to_address: Some(address.to_string()),
+            token_id: 1,
+            amount: "100".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        };
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&create_request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+
+        // Marking the order paid adds a Transfer order to the current batch
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/orders/{}/mark-paid", order.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let paid: Value = serde_json::from_slice(&body).unwrap();
+        let transfer_id = paid["transfer_order_id"].as_str().unwrap().to_string();
+
+        let response = app.clone()
+            .oneshot(Request::builder().method("POST").uri("/api/v1/batch/finalize").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Proofs are written by a background task after finalization
+        let mut precomputed = 0;
+        for _ in 0..100 {
+            precomputed = sqlx::query("SELECT COUNT(*) AS count FROM precomputed_proofs WHERE batch_id = 1")
+                .fetch_one(&db)
+                .await
+                .unwrap()
+                .get::<i64, _>("count");
+            if precomputed > 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(precomputed > 0);
+
+        // The transfer order has no batch assigned in the database, so only the precomputed proof can serve this
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/proofs/order/1/{}?format=raw", transfer_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let proof: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(proof["leaf_index"], 0);
+        assert!(proof["path_bits"].is_array());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/proofs/account/{}?batch_id=1", address))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_filler_claimable_balance_is_provable() {
+        let (app, db) = create_test_app().await;
+        let address = "0x1234567890123456789012345678901234567890";
+
+        let init_request = json!({ "address": address, "token_id": 1, "initial_balance": "1000" });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/batch/init-account")
+                    .header("content-type", "application/json")
+                    .body(Body::from(init_request.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let create_request = CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: None,
+            to_address: Some(address.to_string()),
+            token_id: 1,
+            amount: "100".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        };
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&create_request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+
+        // filler_1 locked part of the order
+        sqlx::query("UPDATE orders SET status = ?1, filler_id = 'filler_1', locked_amount = '60' WHERE id = ?2")
+            .bind(OrderStatus::Locked as i32)
+            .bind(&order.id)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        for uri in [format!("/api/v1/orders/{}/mark-paid", order.id), "/api/v1/batch/finalize".to_string()] {
+            let response = app.clone()
+                .oneshot(Request::builder().method("POST").uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/proofs/account/filler:filler_1?batch_id=1&format=raw")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let proof: Value = serde_json::from_slice(&body).unwrap();
+
+        // The settlement account holds exactly the locked amount
+        let settlement_address = crate::models::filler_settlement_address("filler_1");
+        assert_eq!(proof["address"], settlement_address.as_str());
+        let mut expected = crate::models::AccountState::new(settlement_address);
+        expected.set_balance(1, "60".to_string());
+        let expected_leaf: [u8; 32] = <sha3::Keccak256 as sha3::Digest>::digest(expected.leaf_preimage()).into();
+        let leaf = vapor_core::proof_format::parse_hash32(proof["leaf_hash"].as_str().unwrap()).unwrap();
+        assert_eq!(leaf, expected_leaf);
+        assert!(proof["path_bits"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_order_proof_formats_verify() {
+        let (app, db) = create_test_app().await;
+
+        for i in 0..3 {
+            let order = crate::models::Order {
+                id: format!("proof_order_{}", i),
+                order_type: OrderType::Transfer,
+                status: OrderStatus::Settled,
+                from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+                to_address: Some("0x0987654321098765432109876543210987654321".to_string()),
+                token_id: 1,
+                amount: format!("{}000000", i + 1),
+                bank_account: None,
+                bank_service: None,
+                banking_hash: None,
+                filler_id: None,
+                locked_amount: None,
+                batch_id: Some(7),
+                created_at: chrono::Utc::now() + chrono::Duration::seconds(i),
+                updated_at: chrono::Utc::now(),
+            };
+            crate::database::helpers::insert_order(&db, &order).await.unwrap();
+        }
+
+        for format in ["raw", "sorted_pairs"] {
+            let response = app.clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("/api/v1/proofs/order/7/proof_order_2?format={}", format))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let proof: Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(proof["format"], format);
+            assert_eq!(proof["leaf_index"], 2);
+            assert_eq!(proof.get("path_bits").is_some(), format == "raw");
+
+            let verify_request = json!({
+                "leaf_hash": proof["leaf_hash"],
+                "proof": proof["proof"],
+                "root": proof["root"],
+                "format": format,
+                "path_bits": proof.get("path_bits"),
+            });
+            let response = app.clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/proofs/verify")
+                        .header("content-type", "application/json")
+                        .body(Body::from(verify_request.to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let result: Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(result["valid"], true, "{} proof did not verify", format);
+        }
+
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/proofs/order/7/proof_order_0?format=bogus").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Orders outside the batch have no contract-facing proof
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/proofs/order/8/proof_order_0?format=sorted_pairs").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_order_multiproof() {
+        let (app, db) = create_test_app().await;
+
+        for i in 0..4 {
+            let order = crate::models::Order {
+                id: format!("multi_order_{}", i),
+                order_type: OrderType::BridgeOut,
+                status: OrderStatus::Settled,
+                from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+                to_address: Some("0x0987654321098765432109876543210987654321".to_string()),
+                token_id: 1,
+                amount: format!("{}000000", i + 1),
+                bank_account: None,
+                bank_service: None,
+                banking_hash: None,
+                filler_id: None,
+                locked_amount: None,
+                batch_id: Some(9),
+                created_at: chrono::Utc::now() + chrono::Duration::seconds(i),
+                updated_at: chrono::Utc::now(),
+            };
+            crate::database::helpers::insert_order(&db, &order).await.unwrap();
+        }
+
+        let multiproof = |body: Value| {
+            app.clone().oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/proofs/multiproof")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+        };
+
+        let response = multiproof(json!({ "batch_id": 9, "order_indices": [3, 0, 1] })).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let proof: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(proof["valid"], true);
+        assert_eq!(proof["leaf_indices"], json!([0, 1, 3]));
+        assert_eq!(proof["order_ids"], json!(["multi_order_0", "multi_order_1", "multi_order_3"]));
+        let proof_len = proof["proof"].as_array().unwrap().len();
+        assert!(proof_len < proof["separate_proof_hashes"].as_u64().unwrap() as usize);
+        assert_eq!(proof["proof_flags"].as_array().unwrap().len() + 1, proof_len + 3);
+
+        // Same root as the single-order proofs
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/proofs/order/9/multi_order_3?format=raw").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let single: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(proof["root"], single["root"]);
+        assert_eq!(proof["leaves"][2], single["leaf_hash"]);
+
+        let response = multiproof(json!({ "batch_id": 9, "order_indices": [4] })).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = multiproof(json!({ "batch_id": 9, "order_indices": [] })).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = multiproof(json!({ "batch_id": 10, "order_indices": [0] })).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_bridge_in_order_gets_deposit_reference() {
+        use vapor_chain::DepositEvent;
+        use crate::services::deposit_reference::{claim_referenced_order, derive_deposit_reference, format_reference};
+
+        let (app, db) = create_test_app().await;
+        let depositor = "0x1234567890123456789012345678901234567890";
+
+        let create_request = CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: Some(depositor.to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        };
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&create_request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+        let reference = derive_deposit_reference(&Config::default().blockchain.deposit_reference_salt, &order.id);
+        assert_eq!(order.deposit_reference, Some(format_reference(&reference)));
+
+        // A deposit made with the reference as its bankingHash is attributed to the order
+        let deposit = DepositEvent {
+            user: depositor.parse().unwrap(),
+            token: web3::types::Address::from_low_u64_be(3),
+            amount: 1_000_000u64.into(),
+            banking_hash: reference,
+            block_number: 100,
+            transaction_hash: web3::types::H256::from_low_u64_be(1),
+        };
+        let claimed = claim_referenced_order(&db, &deposit).await.unwrap().unwrap();
+        assert_eq!(claimed.id, order.id);
+    }
+
+    #[tokio::test]
+    async fn test_domain_errors_map_to_api_error_codes() {
+        let (app, _db) = create_test_app().await;
+
+        let post = |uri: &'static str| Request::builder().method("POST").uri(uri).body(Body::empty()).unwrap();
+
+        // Nothing to finalize before a batch is started
+        let response = app.clone().oneshot(post("/api/v1/batch/finalize")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "no_active_batch");
+
+        let response = app.clone().oneshot(post("/api/v1/batch/start")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone().oneshot(post("/api/v1/batch/start")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "batch_in_progress");
+        assert_eq!(error["message"], "Batch already in progress");
+
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/proofs/account/0x1234567890123456789012345678901234567890?format=sorted_pairs")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "unsupported_proof_format");
+    }
+
+    #[cfg(feature = "fault-injection")]
+    #[tokio::test]
+    async fn test_fault_injection_admin_endpoints() {
+        let (app, _db) = create_test_app().await;
+
+        // Keep injection disabled so concurrently running tests are unaffected
+        let config = json!({
+            "enabled": false,
+            "database": { "latency_ms": 50, "latency_probability": 0.5, "failure_probability": 0.1 }
+        });
+
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri("/api/v1/admin/faults")
+                    .header("content-type", "application/json")
+                    .body(Body::from(config.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/admin/faults").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let faults: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(faults["config"]["database"]["latency_ms"], 50);
+        assert_eq!(faults["config"]["rpc"]["failure_probability"], 0.0);
+
+        let invalid = json!({ "enabled": true, "prover": { "latency_ms": 0, "latency_probability": 0.0, "failure_probability": 2.0 } });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri("/api/v1/admin/faults")
+                    .header("content-type", "application/json")
+                    .body(Body::from(invalid.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .oneshot(Request::builder().method("DELETE").uri("/api/v1/admin/faults").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_bodies_and_slow_requests_get_structured_errors() {
+        let mut config = Config::default();
+        config.request_limits.long_timeout_seconds = 1;
+        config.request_limits.max_body_bytes = 512;
+        let (app, _db) = create_test_app_with_config(config).await;
+
+        let send = |method: &'static str, uri: &'static str, body: Option<String>| {
+            let app = app.clone();
+            async move {
+                let request = Request::builder().method(method).uri(uri).header("content-type", "application/json");
+                let response = app.oneshot(request.body(body.map(Body::from).unwrap_or_else(Body::empty)).unwrap()).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let order = json!({ "order_type": "BridgeIn", "to_address": "0x1234", "token_id": 1, "amount": "1", "bank_account": "x".repeat(600) });
+        let (status, error) = send("POST", "/api/v1/orders", Some(order.to_string())).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(error["error"], "payload_too_large");
+        assert_eq!(error["details"]["max_body_bytes"], 512);
+
+        // A prover slower than the long timeout
+        let prover = json!({ "generation_delay_ms": 3000, "failure_rate": 0.0 });
+        assert_eq!(send("POST", "/api/v1/prover/config", Some(prover.to_string())).await.0, StatusCode::OK);
+        assert_eq!(send("POST", "/api/v1/batch/start", None).await.0, StatusCode::OK);
+        let (status, error) = send("POST", "/api/v1/batch/prove", None).await;
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(error["error"], "request_timeout");
+        assert_eq!(error["details"], json!({ "route_class": "long", "timeout_seconds": 1 }));
+
+        let (status, config) = send("GET", "/api/v1/admin/config", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(config["request_limits"]["max_body_bytes"], 512);
+        assert_eq!(config["request_limits"]["read_timeout_seconds"], 10);
+        assert!(config["long_running_routes"].as_array().unwrap().contains(&json!("/api/v1/batch/prove")));
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_mode_blocks_writes_only() {
+        let (app, _db) = create_test_app().await;
+
+        let set_maintenance = |body: Value| {
+            Request::builder()
+                .method("PUT")
+                .uri("/api/v1/admin/maintenance")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let create_order = || {
+            let request = json!({
+                "order_type": "BridgeOut",
+                "to_address": "0x1234567890123456789012345678901234567890",
+                "token_id": 1,
+                "amount": "1000000",
+                "bank_account": "12345678",
+                "bank_service": "PayPal Hong Kong",
+            });
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/orders")
+                .header("content-type", "application/json")
+                .body(Body::from(request.to_string()))
+                .unwrap()
+        };
+
+        let response = app.clone()
+            .oneshot(set_maintenance(json!({ "enabled": true, "message": "Upgrading contracts" })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Writes are rejected with the maintenance message
+        let response = app.clone().oneshot(create_order()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "maintenance");
+        assert_eq!(error["message"], "Upgrading contracts");
+
+        // Reads keep serving
+        for uri in ["/health", "/api/v1/orders", "/api/v1/proofs/stats", "/api/v1/admin/maintenance"] {
+            let response = app.clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "GET {} during maintenance", uri);
+        }
+
+        let response = app.clone().oneshot(set_maintenance(json!({ "enabled": false }))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.oneshot(create_order()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_bridge_out_lists_and_daily_limits() {
+        let mut config = Config::default();
+        config.withdrawal.default_limits.per_address_daily = 1_500_000;
+        let (app, _db) = create_test_app_with_config(config).await;
+
+        let bridge_out = |to: &str, amount: &str| {
+            let request = json!({
+                "order_type": "BridgeOut",
+                "to_address": to,
+                "token_id": 1,
+                "amount": amount,
+            });
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/orders")
+                .header("content-type", "application/json")
+                .body(Body::from(request.to_string()))
+                .unwrap()
+        };
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        let denied = "0xdead000000000000000000000000000000000000";
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/api/v1/admin/withdrawals/lists/{}", denied))
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({ "list": "deny", "reason": "sanctions" }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone().oneshot(bridge_out(denied, "1000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let error = json_body(response).await;
+        assert_eq!(error["error"], "withdrawal_not_permitted");
+        assert_eq!(error["details"]["reason"], "sanctions");
+
+        let recipient = "0xaaaa000000000000000000000000000000000000";
+        let response = app.clone().oneshot(bridge_out(recipient, "1000000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The second withdrawal would take the recipient over its daily limit
+        let response = app.clone().oneshot(bridge_out(recipient, "1000000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let error = json_body(response).await;
+        assert_eq!(error["error"], "withdrawal_limit_exceeded");
+        assert_eq!(error["details"]["limit"], "per_address_daily");
+        assert_eq!(error["details"]["remaining"], 500_000);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/admin/withdrawals/usage/{}?token_id=1", recipient))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let usage = json_body(response).await;
+        assert_eq!(usage["address_used"], 1_000_000);
+        assert_eq!(usage["limits"]["per_address_daily"], 1_500_000);
+    }
+
+    #[tokio::test]
+    async fn test_batch_cap_defers_bridge_outs_until_override() {
+        let mut config = Config::default();
+        config.batch.bridge_out_caps = std::collections::HashMap::from([(1, 1_500_000)]);
+        let (app, _db) = create_test_app_with_config(config).await;
+
+        let request = |method: &str, uri: &str, body: Option<Value>| {
+            let builder = Request::builder().method(method).uri(uri);
+            match body {
+                Some(body) => builder.header("content-type", "application/json").body(Body::from(body.to_string())).unwrap(),
+                None => builder.body(Body::empty()).unwrap(),
+            }
+        };
+        let bridge_out = |amount: &str| {
+            request("POST", "/api/v1/orders", Some(json!({
+                "order_type": "BridgeOut",
+                "to_address": "0xaaaa000000000000000000000000000000000000",
+                "token_id": 1,
+                "amount": amount,
+            })))
+        };
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        // The second order would take the batch over the cap; it is accepted but deferred
+        assert_eq!(app.clone().oneshot(bridge_out("1000000")).await.unwrap().status(), StatusCode::OK);
+        let response = app.clone().oneshot(bridge_out("1000000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let deferred_id = json_body(response).await["id"].clone();
+
+        let caps = json_body(app.clone().oneshot(request("GET", "/api/v1/admin/batch-caps", None)).await.unwrap()).await;
+        assert_eq!(caps["caps"][0]["batch_volume"], 1_000_000);
+        assert_eq!(caps["caps"][0]["tripped"], true);
+        assert_eq!(caps["deferred_orders"][0]["order"]["id"], deferred_id);
+        assert_eq!(caps["deferred_orders"][0]["cap"]["max"], 1_500_000);
+
+        let finalized = json_body(app.clone().oneshot(request("POST", "/api/v1/batch/finalize", None)).await.unwrap()).await;
+        assert_eq!(finalized["orders_count"], 1);
+        assert_eq!(finalized["deferred_orders"][0]["order"]["id"], deferred_id);
+
+        // The next batch includes it; a third order trips the cap again until the override lifts it
+        app.clone().oneshot(request("POST", "/api/v1/batch/start", None)).await.unwrap();
+        let current = json_body(app.clone().oneshot(request("GET", "/api/v1/batch/current", None)).await.unwrap()).await;
+        assert_eq!(current["orders_count"], 1);
+        let response = app.clone().oneshot(bridge_out("1000000")).await.unwrap();
+        let third_id = json_body(response).await["id"].clone();
+
+        let response = app.clone()
+            .oneshot(request("PUT", "/api/v1/admin/batch-caps/1", Some(json!({ "max_bridge_out": 0 }))))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let overridden = json_body(response).await;
+        assert_eq!(overridden["released_orders"], json!([third_id]));
+        assert_eq!(overridden["cap"]["effective_max"], Value::Null);
+        assert_eq!(overridden["cap"]["batch_volume"], 2_000_000);
+
+        let response = app.clone().oneshot(request("DELETE", "/api/v1/admin/batch-caps/1", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = app.oneshot(request("DELETE", "/api/v1/admin/batch-caps/1", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_batch_dry_run_does_not_commit() {
+        let (app, _db) = create_test_app().await;
+        let alice = "0x1111111111111111111111111111111111111111";
+        let bob = "0x2222222222222222222222222222222222222222";
+
+        let post_json = |uri: &str, body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        let response = app.clone()
+            .oneshot(post_json("/api/v1/batch/init-account", json!({ "address": alice, "token_id": 1, "initial_balance": "1000" })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Transfers go straight into the open batch
+        let transfer = json!({ "order_type": "Transfer", "from_address": alice, "to_address": bob, "token_id": 1, "amount": "300" });
+        let response = app.clone().oneshot(post_json("/api/v1/orders", transfer)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // A pending BridgeIn is not batched until it is paid, so it makes a candidate
+        let bridge_in = json!({
+            "order_type": "BridgeIn",
+            "to_address": bob,
+            "token_id": 1,
+            "amount": "50",
+            "bank_account": "12345678",
+            "bank_service": "PayPal Hong Kong",
+        });
+        let response = app.clone().oneshot(post_json("/api/v1/orders", bridge_in)).await.unwrap();
+        let candidate = json_body(response).await["id"].as_str().unwrap().to_string();
+
+        let response = app.clone()
+            .oneshot(post_json("/api/v1/batch/dry-run", json!({ "order_ids": [candidate, "missing-order"] })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let result = json_body(response).await;
+        assert_eq!(result["batch_id"], 1);
+        assert_eq!(result["orders_count"], 2);
+        assert_eq!(result["failed_orders"].as_array().unwrap().len(), 1);
+        assert_eq!(result["failed_orders"][0]["order_id"], "missing-order");
+        let deltas = result["balance_deltas"].as_array().unwrap();
+        assert_eq!(deltas.len(), 3);
+        // The deposit is mirrored by the bridge account, which sorts first
+        assert_eq!(deltas[0]["address"], crate::services::system_accounts::SystemAccount::Bridge.address());
+        assert_eq!(deltas[0]["delta"], "50");
+        assert_eq!(deltas[1]["address"], alice);
+        assert_eq!(deltas[1]["delta"], "-300");
+        assert_eq!(deltas[2]["address"], bob);
+        assert_eq!(deltas[2]["delta"], "350");
+
+        // The candidate was not added to the real batch
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/batch/current").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await["orders_count"], 1);
+
+        // Without a body the open batch alone is previewed
+        let response = app.clone()
+            .oneshot(Request::builder().method("POST").uri("/api/v1/batch/dry-run").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["orders_count"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_claim_paid_out_in_preferred_token() {
+        let mut config = Config::default();
+        config.rates.usd_prices.insert(2, 999_800);
+        let (app, _db) = create_test_app_with_config(config).await;
+
+        let claim = |payout_token_id: Option<u32>| {
+            let request = json!({
+                "filler_id": "filler_1",
+                "payout_token_id": payout_token_id,
+                "claims": [{ "amount": "1000000", "destination_address": "0x1111111111111111111111111111111111111111" }],
+            });
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/fillers/claim")
+                .header("content-type", "application/json")
+                .body(Body::from(request.to_string()))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(claim(Some(2))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let claimed: ClaimResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(claimed.total_claimed, "1000000");
+        assert_eq!(claimed.payout_token_id, 2);
+        assert_eq!(claimed.total_payout, "1000200");
+
+        // The conversion is kept on the claim row for auditing
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/fillers/claims/{}", claimed.claims_processed[0].claim_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let record: ClaimRecord = serde_json::from_slice(&body).unwrap();
+        assert_eq!(record.amount, "1000000");
+        assert_eq!(record.token_id, 1);
+        assert_eq!(record.payout_token_id, 2);
+        assert_eq!(record.payout_amount, "1000200");
+        let conversion = record.conversion.unwrap();
+        assert_eq!(conversion.rate, "1.000200");
+        assert_eq!(conversion.rate_source, "config");
+
+        // Without a preference the claim is paid out as earned, with no conversion
+        let response = app.clone().oneshot(claim(None)).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let claimed: ClaimResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(claimed.total_payout, "1000000");
+        assert!(claimed.claims_processed[0].conversion.is_none());
+
+        let response = app.clone().oneshot(claim(Some(9))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "unsupported_payout_token");
+    }
+
+    #[tokio::test]
+    async fn test_claim_is_made_against_the_batch_its_claims_name() {
+        let (app, _db) = create_test_app().await;
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        let claim = |batch_ids: &[Option<u64>]| {
+            let claims: Vec<Value> = batch_ids.iter().enumerate().map(|(i, batch_id)| json!({
+                "amount": "1000000",
+                "destination_address": "0x1111111111111111111111111111111111111111",
+                "batch_id": batch_id,
+                "order_id": batch_id.map(|_| i as u64 + 1),
+            })).collect();
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/fillers/claim")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({ "filler_id": "filler_1", "claims": claims }).to_string()))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(claim(&[Some(4), Some(4)])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let claimed: ClaimResponse = serde_json::from_value(json_body(response).await).unwrap();
+        assert_eq!(claimed.batch_id, Some(4));
+
+        // Nothing is proven yet, so a claim naming no batch is against none
+        let response = app.clone().oneshot(claim(&[None])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let claimed: ClaimResponse = serde_json::from_value(json_body(response).await).unwrap();
+        assert_eq!(claimed.batch_id, None);
+
+        let response = app.clone().oneshot(claim(&[Some(4), Some(5)])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json_body(response).await["error"], "mixed_claim_batches");
+    }
+
+    #[tokio::test]
+    async fn test_lock_requires_requote_after_slippage() {
+        let (app, db) = create_test_app().await;
+
+        let request = json!({
+            "order_type": "BridgeIn",
+            "from_address": "0x1234567890123456789012345678901234567890",
+            "token_id": 1,
+            "amount": "1000000",
+            "bank_account": "12345678",
+            "bank_service": "PayPal Hong Kong",
+        });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders")
+                    .header("content-type", "application/json")
+                    .body(Body::from(request.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(order.quoted_rate.as_deref(), Some("1.000000"));
+        sqlx::query("UPDATE orders SET status = ? WHERE id = ?")
+            .bind(OrderStatus::Discovery as i32)
+            .bind(&order.id)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        // USDC slips 2% between quote and lock
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri("/api/v1/admin/rates/1")
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({ "usd_price": "0.98" }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let lock = |accepted_rate: Option<&str>| {
+            let request = json!({ "filler_id": "filler_1", "amount": "1000000", "accepted_rate": accepted_rate });
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/fillers/orders/{}/lock", order.id))
+                .header("content-type", "application/json")
+                .body(Body::from(request.to_string()))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(lock(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "requote_required");
+        assert_eq!(error["details"]["quoted_rate"], "1.000000");
+        assert_eq!(error["details"]["current_rate"], "0.980000");
+        assert_eq!(error["details"]["slippage_bps"], 200);
+
+        // The order stays available until the filler takes the new rate
+        let response = app.clone().oneshot(lock(Some("0.98"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let locked: OrderResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(locked.filler_id.as_deref(), Some("filler_1"));
+        assert_eq!(locked.quoted_rate.as_deref(), Some("0.980000"));
+    }
+
+    #[tokio::test]
+    async fn test_on_chain_orders_cannot_be_claimed_twice() {
+        let (app, db) = create_test_app().await;
+
+        let claim = |claims: Value| {
+            let request = json!({ "filler_id": "filler_1", "claims": claims });
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/fillers/claim")
+                .header("content-type", "application/json")
+                .body(Body::from(request.to_string()))
+                .unwrap()
+        };
+        let order = |batch_id: u64, order_id: u32| json!({
+            "amount": "1000000",
+            "destination_address": "0x1111111111111111111111111111111111111111",
+            "batch_id": batch_id,
+            "order_id": order_id,
+        });
+
+        let response = app.clone().oneshot(claim(json!([order(1, 41)]))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Claiming the same order again, even under another batch, is refused and records nothing
+        let response = app.clone().oneshot(claim(json!([order(2, 42), order(2, 41)]))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "already_claimed");
+        assert_eq!(error["details"]["order_ids"], json!([41]));
+
+        let response = app.clone().oneshot(claim(json!([order(2, 42), order(2, 42)]))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "invalid_claim");
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM claims").fetch_one(&db).await.unwrap();
+        assert_eq!(count, 1);
+
+        // Without a blockchain client nothing has been reconciled yet
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/admin/claims/reconciliation").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_order_listing_pages_in_creation_order() {
+        let (app, db) = create_test_app().await;
+
+        // A row minted before ULIDs keeps sorting by created_at
+        let mut legacy = crate::models::Order::new(CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1".to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        });
+        legacy.id = uuid::Uuid::new_v4().to_string();
+        legacy.created_at = chrono::Utc::now() - chrono::Duration::minutes(5);
+        crate::database::helpers::insert_order(&db, &legacy).await.unwrap();
+
+        let mut created = vec![legacy.id.clone()];
+        for i in 0..4 {
+            let body = json!({ "order_type": "BridgeIn", "from_address": "0x1234567890123456789012345678901234567890", "token_id": 1, "amount": format!("{}", i + 2) });
+            let response = app.clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/orders")
+                        .header("content-type", "application/json")
+                        .body(Body::from(body.to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+            created.push(order.id);
+        }
+
+        // New ids are ULIDs, increasing even when minted within the same millisecond
+        assert!(created[1..].windows(2).all(|pair| pair[0] < pair[1]));
+
+        let mut listed = Vec::new();
+        let mut uri = "/api/v1/orders?sort=asc&limit=2".to_string();
+        loop {
+            let response = app.clone()
+                .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let page: Value = serde_json::from_slice(&body).unwrap();
+            listed.extend(page["orders"].as_array().unwrap().iter().map(|order| order["id"].as_str().unwrap().to_string()));
+
+            match page["next_cursor"].as_str() {
+                Some(cursor) => uri = format!("/api/v1/orders?sort=asc&limit=2&after={}", cursor),
+                None => break,
+            }
+        }
+        assert_eq!(listed, created);
+
+        // Newest first by default
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/orders").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let page: Value = serde_json::from_slice(&body).unwrap();
+        let newest_first: Vec<String> = page["orders"].as_array().unwrap().iter()
+            .map(|order| order["id"].as_str().unwrap().to_string())
+            .collect();
+        created.reverse();
+        assert_eq!(newest_first, created);
+    }
+
+    #[tokio::test]
+    async fn test_verification_fixtures_endpoint() {
+        let (app, _db) = create_test_app().await;
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/admin/fixtures?batch_id=42").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let fixtures: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(fixtures["batch_id"], 42);
+
+        let orders = fixtures["order_tree"]["orders"].as_array().unwrap();
+        assert!(!orders.is_empty());
+        assert_eq!(orders[0]["proof"]["root"], fixtures["order_tree"]["root"]);
+        assert_eq!(orders[0]["proof"]["format"], "raw");
+        assert_eq!(orders[0]["sorted_pairs_proof"]["root"], fixtures["order_tree"]["sorted_pairs_root"]);
+
+        let accounts = fixtures["account_tree"]["accounts"].as_array().unwrap();
+        assert!(!accounts.is_empty());
+        assert_eq!(accounts[0]["proof"]["root"], fixtures["account_tree"]["root"]);
+        assert!(accounts[0]["proof"]["path_bits"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_market_summary_is_anonymized_and_rate_limited() {
+        let mut config = Config::default();
+        config.market.rate_limit_per_minute = 2;
+        let (app, _db) = create_test_app_with_config(config).await;
+
+        let create = json!({
+            "order_type": "BridgeIn",
+            "from_address": "0x1234567890123456789012345678901234567890",
+            "token_id": 1,
+            "amount": "1000",
+            "bank_account": "84127312",
+            "bank_service": "PayPal Hong Kong"
+        });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders")
+                    .header("content-type", "application/json")
+                    .body(Body::from(create.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+        let response = app.clone()
+            .oneshot(Request::builder().method("POST").uri(format!("/api/v1/orders/{}/mark-discovery", order.id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let summary_request = |client: &str| {
+            Request::builder()
+                .uri("/api/v1/market/summary")
+                .header("x-forwarded-for", client)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(summary_request("203.0.113.7")).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+        let summary: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(summary["totals"]["open_orders"], 1);
+        assert_eq!(summary["corridors"][0]["bank_service"], "PayPal Hong Kong");
+        assert_eq!(summary["corridors"][0]["open_volume"], "1000");
+        let raw = String::from_utf8(body.to_vec()).unwrap();
+        assert!(!raw.contains("0x1234") && !raw.contains("84127312") && !raw.contains(&order.id));
+
+        assert_eq!(app.clone().oneshot(summary_request("203.0.113.7")).await.unwrap().status(), StatusCode::OK);
+        let response = app.clone().oneshot(summary_request("203.0.113.7")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "rate_limited");
+        assert_eq!(error["details"]["limit"], 2);
+
+        // Other clients are unaffected
+        assert_eq!(app.oneshot(summary_request("198.51.100.1")).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_graphql_nested_order_query() {
+        let (app, db) = create_test_app().await;
+        let address = "0x1234567890123456789012345678901234567890";
+
+        let init_request = json!({ "address": address, "token_id": 1, "initial_balance": "1000" });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/batch/init-account")
+                    .header("content-type", "application/json")
+                    .body(Body::from(init_request.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let create_request = CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: None,
+            to_address: Some(address.to_string()),
+            token_id: 1,
+            amount: "100".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        };
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&create_request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+
+        sqlx::query("UPDATE orders SET status = ?1, filler_id = 'filler_1', locked_amount = '100' WHERE id = ?2")
+            .bind(OrderStatus::Locked as i32)
+            .bind(&order.id)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let mut transfer_order_id = String::new();
+        for uri in [format!("/api/v1/orders/{}/mark-paid", order.id), "/api/v1/batch/finalize".to_string()] {
+            let response = app.clone()
+                .oneshot(Request::builder().method("POST").uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            if let Some(id) = serde_json::from_slice::<Value>(&body).unwrap()["transfer_order_id"].as_str() {
+                transfer_order_id = id.to_string();
+            }
+        }
+        // Batch assignment is not persisted by the pipeline yet
+        sqlx::query("UPDATE orders SET batch_id = 1 WHERE id = ?1").bind(&transfer_order_id).execute(&db).await.unwrap();
+
+        let claim_request = json!({
+            "filler_id": "filler_1",
+            "claims": [{ "amount": "100", "destination_address": "0x1111111111111111111111111111111111111111" }],
+        });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/fillers/claim")
+                    .header("content-type", "application/json")
+                    .body(Body::from(claim_request.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let graphql = |query: String| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/graphql")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({ "query": query }).to_string()))
+                .unwrap()
+        };
+
+        let query = format!(
+            r#"{{
+                order(id: "{}") {{
+                    status
+                    filler {{ fillerId claims {{ amount destinationAddress }} }}
+                }}
+                transfer: order(id: "{}") {{
+                    batch {{ batchId ordersRoot orders {{ id }} }}
+                    proof(format: SORTED_PAIRS) {{ format root leafIndex }}
+                }}
+                account(address: "filler:filler_1", batchId: 1) {{ balances {{ tokenId balance }} proof {{ leafHash }} }}
+            }}"#,
+            order.id, transfer_order_id
+        );
+        let response = app.clone().oneshot(graphql(query)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let result: Value = serde_json::from_slice(&body).unwrap();
+        assert!(result.get("errors").is_none(), "unexpected errors: {}", result);
+
+        let data = &result["data"]["order"];
+        assert_eq!(data["status"], "MARK_PAID");
+        assert_eq!(data["filler"]["fillerId"], "filler_1");
+        assert_eq!(data["filler"]["claims"][0]["amount"], "100");
+
+        let transfer = &result["data"]["transfer"];
+        assert_eq!(transfer["batch"]["batchId"], 1);
+        assert_eq!(transfer["batch"]["orders"][0]["id"], transfer_order_id.as_str());
+        assert_eq!(transfer["proof"]["format"], "SORTED_PAIRS");
+        assert_eq!(transfer["proof"]["leafIndex"], 0);
+        assert!(transfer["proof"]["root"].is_string());
+
+        // The filler's settlement account is proven the same way as over REST
+        let account = &result["data"]["account"];
+        assert_eq!(account["balances"][0]["balance"], "100");
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/proofs/account/filler:filler_1?batch_id=1").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let rest_proof: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(account["proof"]["leafHash"], rest_proof["leaf_hash"]);
+
+        // Filters apply together; unknown ids resolve to null rather than an error
+        let query = r#"{ orders(status: MARK_PAID, fillerId: "filler_1") { id } missing: order(id: "nope") { id } }"#;
+        let response = app.oneshot(graphql(query.to_string())).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let result: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(result["data"]["orders"].as_array().unwrap().len(), 1);
+        assert!(result["data"]["missing"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_structured_payment_proof_per_bank_service() {
+        let (app, db) = create_test_app().await;
+
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/bank-services").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let registry: Value = serde_json::from_slice(&body).unwrap();
+        let paypal = registry.as_array().unwrap().iter().find(|entry| entry["rail"] == "paypal").unwrap();
+        assert_eq!(paypal["proof_schema"]["required"], json!(["transaction_id", "payer_email_hash"]));
+
+        let locked_order = |bank_service: &'static str| {
+            let db = db.clone();
+            async move {
+                let mut order = crate::models::Order::new(CreateOrderRequest {
+                    order_type: OrderType::BridgeIn,
+                    from_address: None,
+                    to_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+                    token_id: 1,
+                    amount: "100".to_string(),
+                    bank_account: Some("12345678".to_string()),
+                    bank_service: Some(bank_service.to_string()),
+                    banking_hash: None,
+                    permit: None,
+                    priority_fee: None,
+                    confirm_duplicate: false,
+                    quote_id: None,
+                });
+                order.lock_for_filler("filler_1".to_string(), "100".to_string(), chrono::Utc::now());
+                crate::database::helpers::insert_order(&db, &order).await.unwrap();
+                order.id
+            }
+        };
+        let submit = |order_id: &str, body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/fillers/orders/{}/payment-proof", order_id))
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let email_hash = "0x1c8aff950685c2ed4bc3174f3472287b56d9517b9c948127319a09a7a36deac8";
+
+        // A proof for the wrong rail, or with a malformed field, is rejected
+        let order_id = locked_order("PayPal Hong Kong").await;
+        for proof in [
+            json!({ "trace_number": "091000019876543" }),
+            json!({ "transaction_id": "not-a-paypal-id", "payer_email_hash": email_hash }),
+        ] {
+            let response = app.clone().oneshot(submit(&order_id, json!({ "payment_proof": proof }))).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert_eq!(serde_json::from_slice::<Value>(&body).unwrap()["error"], "invalid_payment_proof");
+        }
+
+        let proof = json!({ "transaction_id": "8MC585209K746392H", "payer_email_hash": email_hash });
+        let response = app.clone().oneshot(submit(&order_id, json!({ "payment_proof": proof }))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Stored as typed JSON, with its digest standing in for the banking hash
+        let response = app.clone()
+            .oneshot(Request::builder().uri(format!("/api/v1/orders/{}", order_id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+        let stored = order.payment_proof.unwrap();
+        assert!(matches!(stored, crate::services::payment_proofs::PaymentProof::Paypal(_)));
+        let banking_hash: String = sqlx::query("SELECT banking_hash FROM orders WHERE id = ?")
+            .bind(&order_id)
+            .fetch_one(&db)
+            .await
+            .unwrap()
+            .get("banking_hash");
+        assert_eq!(banking_hash, stored.digest());
+
+        // Services without a schema keep taking an opaque banking hash only
+        let order_id = locked_order("Venmo").await;
+        let response = app.clone()
+            .oneshot(submit(&order_id, json!({ "payment_proof": { "transfer_id": "123" } })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app.clone().oneshot(submit(&order_id, json!({}))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app.oneshot(submit(&order_id, json!({ "banking_hash": "0xreceipt" }))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_balance_alerts_admin_endpoint() {
+        let mut config = Config::default();
+        config.balance_alerts.rules = vec![crate::config::BalanceAlertRule {
+            source: crate::config::BalanceSource::Ledger,
+            target: "filler:filler_1".to_string(),
+            token_id: 1,
+            threshold: 1_000_000,
+        }];
+        let (app, _db) = create_test_app_with_config(config).await;
+
+        // Rules are listed before their first evaluation, with an unknown state
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/admin/balance-alerts").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let alerts: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(alerts[0]["rule"]["source"], "ledger");
+        assert_eq!(alerts[0]["rule"]["threshold"], 1_000_000);
+        assert_eq!(alerts[0]["status"], "unknown");
+    }
+
+    #[tokio::test]
+    async fn test_prover_config_endpoint() {
+        let (app, _db) = create_test_app().await;
+
+        let update = json!({
+            "generation_delay_ms": 1,
+            "scenarios": [{ "kind": "fail_attempts", "batch_id": 1, "attempts": 1 }],
+        });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/prover/config")
+                    .header("content-type", "application/json")
+                    .body(Body::from(update.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/prover/config").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let config: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(config["scenarios"], json!([{ "kind": "fail_attempts", "batch_id": 1, "attempts": 1 }]));
+        assert_eq!(config["generation_delay_ms"], 1);
+        // Fields left out of the update keep their value
+        assert_eq!(config["failure_rate"], 0.1);
+
+        // The scripted failure leaves batch 1 queued for the next attempt
+        for uri in ["/api/v1/batch/start", "/api/v1/batch/prove"] {
+            let response = app.clone()
+                .oneshot(Request::builder().method("POST").uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            if uri.ends_with("prove") {
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let result: Value = serde_json::from_slice(&body).unwrap();
+                assert_eq!(result["status"], "error");
+                assert_eq!(result["batch_id"], 1);
+            }
+        }
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/prover/config")
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({ "failure_rate": 1.5 }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_order_cursors_are_stable_under_inserts() {
+        let (app, db) = create_test_app().await;
+        let created_at = chrono::Utc::now() - chrono::Duration::minutes(1);
+        let order = |id: String, status: OrderStatus, created_at| crate::models::Order {
+            id,
+            order_type: OrderType::BridgeIn,
+            status,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000000".to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at,
+            updated_at: created_at,
+        };
+        // More orders than the largest page, all created at the same instant
+        for i in 0..105 {
+            let status = if i < 5 { OrderStatus::Discovery } else { OrderStatus::Pending };
+            crate::database::helpers::insert_order(&db, &order(format!("order_{:03}", i), status, created_at)).await.unwrap();
+        }
+
+        let get_page = |uri: String| {
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<Value>(&body).unwrap()
+            }
+        };
+        let ids = |page: &Value, key: &str| -> Vec<String> {
+            page[key].as_array().unwrap().iter().map(|item| item["id"].as_str().unwrap().to_string()).collect()
+        };
+
+        // The page size is capped
+        let page = get_page("/api/v1/orders?limit=1000".to_string()).await;
+        assert_eq!(page["orders"].as_array().unwrap().len(), 100);
+        assert!(page["next_cursor"].is_string());
+
+        // Orders created between pages neither shift nor repeat the rest of the listing
+        let mut listed = Vec::new();
+        let mut page = get_page("/api/v1/orders?limit=40".to_string()).await;
+        loop {
+            listed.extend(ids(&page, "orders"));
+            let newer = order(crate::models::new_order_id(), OrderStatus::Pending, chrono::Utc::now());
+            crate::database::helpers::insert_order(&db, &newer).await.unwrap();
+            match page["next_cursor"].as_str() {
+                Some(cursor) => page = get_page(format!("/api/v1/orders?limit=40&after={}", cursor)).await,
+                None => break,
+            }
+        }
+        let expected: Vec<String> = (0..105).rev().map(|i| format!("order_{:03}", i)).collect();
+        assert_eq!(listed, expected);
+
+        // Discovery orders page oldest first
+        let page = get_page("/api/v1/fillers/discovery?limit=3".to_string()).await;
+        assert_eq!(ids(&page, "orders"), vec!["order_000", "order_001", "order_002"]);
+        let cursor = page["next_cursor"].as_str().unwrap();
+        let page = get_page(format!("/api/v1/fillers/discovery?limit=3&after={}", cursor)).await;
+        assert_eq!(ids(&page, "orders"), vec!["order_003", "order_004"]);
+        assert!(page["next_cursor"].is_null());
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/orders?after=order_001").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "invalid_cursor");
+    }
+
+    #[tokio::test]
+    async fn test_bulk_account_initialization() {
+        let mut config = Config::default();
+        config.api.bulk_account_max_entries = 3;
+        let (app, _db) = create_test_app_with_config(config).await;
+        let alice = "0x1111111111111111111111111111111111111111";
+        let bob = "0x2222222222222222222222222222222222222222";
+        let upload = |content_type: &str, body: String| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/admin/accounts/bulk")
+                .header("content-type", content_type)
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        let entries = json!([
+            { "address": alice, "token_id": 1, "balance": "1000" },
+            { "address": alice, "token_id": 2, "balance": "50" },
+        ]);
+        let response = app.clone().oneshot(upload("application/json", entries.to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let summary = json_body(response).await;
+        assert_eq!(summary["accounts_created"], 1);
+        assert_eq!(summary["totals"]["1"], "1000");
+
+        // CSV replaces existing balances and creates new accounts
+        let csv = format!("address,token_id,balance\n{},1,700\n{},1,300\n", alice, bob);
+        let summary = json_body(app.clone().oneshot(upload("text/csv", csv)).await.unwrap()).await;
+        assert_eq!((summary["accounts_created"].as_u64(), summary["accounts_updated"].as_u64()), (Some(1), Some(1)));
+        assert_eq!(summary["totals"]["1"], "1000");
+
+        // A bad entry rejects the whole upload
+        let csv = format!("{},1,5\n{},1,lots\n", bob, alice);
+        let response = app.clone().oneshot(upload("text/csv", csv)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json_body(response).await["details"]["entry"], 2);
+
+        let csv = (0..4).map(|i| format!("0x{:040},1,1\n", i)).collect::<String>();
+        let response = app.clone().oneshot(upload("text/csv", csv)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let account = Request::builder().uri(format!("/api/v1/accounts/{}", alice)).body(Body::empty()).unwrap();
+        let account = json_body(app.clone().oneshot(account).await.unwrap()).await;
+        assert_eq!(account["balances"], json!([{ "token_id": 1, "balance": "700" }, { "token_id": 2, "balance": "50" }]));
+        let account = Request::builder().uri(format!("/api/v1/accounts/{}", bob)).body(Body::empty()).unwrap();
+        assert_eq!(json_body(app.oneshot(account).await.unwrap()).await["balances"][0]["balance"], "300");
+    }
+
+    #[tokio::test]
+    async fn test_accounts_api() {
+        let (app, _db) = create_test_app().await;
+        let alice = "0x1234567890123456789012345678901234567890";
+        let bob = "0x0987654321098765432109876543210987654321";
+        let carol = "0xcccccccccccccccccccccccccccccccccccccccc";
+        let request = |method: &str, uri: &str, body: Option<Value>| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+                .unwrap()
+        };
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        for (address, token_id) in [(alice, 1), (bob, 1), (carol, 2)] {
+            let init = json!({ "address": address, "token_id": token_id, "initial_balance": "1000" });
+            let response = app.clone().oneshot(request("POST", "/api/v1/accounts", Some(init))).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        // Balances are readable straight away; nothing is proven yet
+        let response = app.clone().oneshot(request("GET", &format!("/api/v1/accounts/{}", alice), None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let account = json_body(response).await;
+        assert_eq!(account["balances"], json!([{ "token_id": 1, "balance": "1000" }]));
+        assert!(account["proven_root"].is_null());
+
+        // Addresses match case-insensitively
+        let checksummed = format!("0x{}", "C".repeat(40));
+        let account = json_body(app.clone().oneshot(request("GET", &format!("/api/v1/accounts/{}", checksummed), None)).await.unwrap()).await;
+        assert_eq!(account["balances"], json!([{ "token_id": 2, "balance": "1000" }]));
+
+        let response = app.clone().oneshot(request("GET", "/api/v1/accounts/0x0000000000000000000000000000000000000001", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Listed by address, optionally only holders of a token
+        let page = json_body(app.clone().oneshot(request("GET", "/api/v1/accounts?limit=2", None)).await.unwrap()).await;
+        let addresses: Vec<&str> = page["accounts"].as_array().unwrap().iter().map(|a| a["address"].as_str().unwrap()).collect();
+        assert_eq!(addresses, vec![bob, alice]);
+        let cursor = page["next_cursor"].as_str().unwrap();
+        let page = json_body(app.clone().oneshot(request("GET", &format!("/api/v1/accounts?limit=2&after={}", cursor), None)).await.unwrap()).await;
+        assert_eq!(page["accounts"][0]["address"], carol);
+        assert!(page["next_cursor"].is_null());
+        let page = json_body(app.clone().oneshot(request("GET", "/api/v1/accounts?token_id=2", None)).await.unwrap()).await;
+        assert_eq!(page["total"], 1);
+
+        // Proving a batch makes its root the proven one
+        for uri in ["/api/v1/batch/start", "/api/v1/batch/prove"] {
+            let response = app.clone().oneshot(request("POST", uri, None)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let account = json_body(app.clone().oneshot(request("GET", &format!("/api/v1/accounts/{}", alice), None)).await.unwrap()).await;
+        assert_eq!(account["proven_root"]["batch_id"], 1);
+        assert!(!account["proven_root"]["state_root"].as_str().unwrap().is_empty());
+
+        // The old route still works, flagged as deprecated
+        let init = json!({ "address": alice, "token_id": 2, "initial_balance": "5" });
+        let response = app.oneshot(request("POST", "/api/v1/batch/init-account", Some(init))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["deprecation"], "true");
+    }
+
+    #[tokio::test]
+    async fn test_init_account_requires_admin_token() {
+        let mut config = Config::default();
+        config.api.admin_token = Some("admin-secret".to_string());
+        let (app, _db) = create_test_app_with_config(config).await;
+        let init = json!({ "address": "0x1234567890123456789012345678901234567890", "token_id": 1, "initial_balance": "1000" });
+        let request = |uri: &str, token: Option<&str>| {
+            let mut builder = Request::builder().method("POST").uri(uri).header("content-type", "application/json");
+            if let Some(token) = token {
+                builder = builder.header("authorization", format!("Bearer {}", token));
+            }
+            builder.body(Body::from(init.to_string())).unwrap()
+        };
+
+        for (uri, token, status) in [
+            ("/api/v1/accounts", None, StatusCode::UNAUTHORIZED),
+            ("/api/v1/accounts", Some("wrong"), StatusCode::UNAUTHORIZED),
+            ("/api/v1/batch/init-account", None, StatusCode::UNAUTHORIZED),
+            ("/api/v1/accounts", Some("admin-secret"), StatusCode::OK),
+            ("/api/v1/batch/init-account", Some("admin-secret"), StatusCode::OK),
+        ] {
+            let response = app.clone().oneshot(request(uri, token)).await.unwrap();
+            assert_eq!(response.status(), status, "{} with token {:?}", uri, token);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_changes_apply_to_new_orders() {
+        let mut config = Config::default();
+        config.api.admin_token = Some("admin-secret".to_string());
+        // Long enough that only the invalidation can make a change visible
+        config.registry.cache_ttl_seconds = 3600;
+        let (app, _db) = create_test_app_with_config(config).await;
+        let request = |method: &str, uri: &str, body: Option<Value>| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", "Bearer admin-secret")
+                .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+                .unwrap()
+        };
+        let order = |token_id: u32, bank_service: &str| {
+            request("POST", "/api/v1/orders", Some(json!({
+                "order_type": "BridgeIn",
+                "from_address": "0x1234567890123456789012345678901234567890",
+                "token_id": token_id,
+                "amount": "1000",
+                "bank_account": "12345678",
+                "bank_service": bank_service,
+            })))
+        };
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        // Loads both registries into the cache while they are empty
+        let response = app.clone().oneshot(order(1, "Wise")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let unauthorized = Request::builder()
+            .method("PUT")
+            .uri("/api/v1/admin/registry/tokens/1")
+            .header("content-type", "application/json")
+            .body(Body::from(json!({ "symbol": "USDC", "decimals": 6, "enabled": false }).to_string()))
+            .unwrap();
+        assert_eq!(app.clone().oneshot(unauthorized).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+        let token = json!({ "symbol": "USDC", "decimals": 6, "enabled": false });
+        let response = app.clone().oneshot(request("PUT", "/api/v1/admin/registry/tokens/1", Some(token))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(request("PUT", "/api/v1/admin/registry/bank-services/Wise", Some(json!({ "enabled": false })))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone().oneshot(order(1, "PayPal Hong Kong")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json_body(response).await["error"], "token_disabled");
+        let response = app.clone().oneshot(order(2, "wise")).await.unwrap();
+        assert_eq!(json_body(response).await["error"], "bank_service_disabled");
+        let response = app.clone().oneshot(order(2, "PayPal Hong Kong")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let registry = json_body(app.clone().oneshot(request("GET", "/api/v1/admin/registry", None)).await.unwrap()).await;
+        assert_eq!(registry["tokens"][0]["symbol"], "USDC");
+        assert_eq!(registry["bank_services"][0]["enabled"], false);
+
+        let metrics = json_body(app.oneshot(request("GET", "/api/v1/admin/registry/metrics", None)).await.unwrap()).await;
+        assert_eq!(metrics["ttl_seconds"], 3600);
+        assert_eq!(metrics["tokens"]["invalidations"], 1);
+        assert_eq!(metrics["tokens"]["misses"], 2);
+        assert!(metrics["tokens"]["hits"].as_u64().unwrap() >= 3);
+        assert_eq!(metrics["bank_services"]["entries"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_prod_profile_disables_dangerous_endpoints() {
+        let config = Config { profile: DeploymentProfile::Prod, ..Config::default() };
+        let (app, _db) = create_test_app_with_config(config).await;
+        let post = |uri: &str, body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let init = json!({ "address": "0x1234567890123456789012345678901234567890", "token_id": 1, "initial_balance": "1000" });
+        for request in [
+            post("/api/v1/accounts", init.clone()),
+            post("/api/v1/batch/init-account", init),
+            post("/api/v1/prover/config", json!({ "simulate_failures": true })),
+        ] {
+            let uri = request.uri().to_string();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", uri);
+        }
+
+        // Turning simulated failures off stays allowed
+        let response = app.clone()
+            .oneshot(post("/api/v1/prover/config", json!({ "simulate_failures": false })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .oneshot(Request::builder().uri("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let health: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(health["profile"], "prod");
+    }
+
+    #[tokio::test]
+    async fn test_finalized_orders_get_signed_receipts() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        // Anvil's first default account
+        let signer = vapor_chain::signer::LocalKeySigner::from_hex("0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80").unwrap();
+        let operator = vapor_chain::signer::Signer::address(&signer);
+        let app_state = AppState::new(Config::default(), db).with_receipt_signer(Arc::new(signer));
+        let (app, db) = create_test_app_with_state(app_state).await;
+
+        let init_request = json!({ "address": "0x1234567890123456789012345678901234567890", "token_id": 1, "initial_balance": "1000" });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/accounts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(init_request.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let create_request = CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: None,
+            to_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            token_id: 1,
+            amount: "100".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        };
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&create_request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+
+        let receipt_request = || Request::builder()
+            .uri(format!("/api/v1/orders/{}/receipt", order.id))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(receipt_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app.clone()
+            .oneshot(Request::builder().method("POST").uri(format!("/api/v1/orders/{}/mark-paid", order.id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let paid: Value = serde_json::from_slice(&body).unwrap();
+        let transfer_id = paid["transfer_order_id"].as_str().unwrap().to_string();
+
+        let response = app.clone()
+            .oneshot(Request::builder().method("POST").uri("/api/v1/batch/finalize").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let batch: Value = serde_json::from_slice(&body).unwrap();
+
+        // Receipts are issued by a background task after finalization
+        for _ in 0..100 {
+            let issued: i64 = sqlx::query("SELECT COUNT(*) AS count FROM inclusion_receipts")
+                .fetch_one(&db)
+                .await
+                .unwrap()
+                .get("count");
+            if issued > 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/orders/{}/receipt", transfer_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let receipt: crate::services::receipts::InclusionReceipt = serde_json::from_slice(&body).unwrap();
+        assert_eq!(receipt.batch_id, batch["batch_id"].as_u64().unwrap());
+        assert_eq!(receipt.orders_root, batch["new_orders_root"].as_str().unwrap());
+        assert_eq!(receipt.recover_signer().unwrap(), operator);
+
+        let verify = |receipt: &crate::services::receipts::InclusionReceipt| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/receipts/verify")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(receipt).unwrap()))
+                .unwrap()
+        };
+        let response = app.clone().oneshot(verify(&receipt)).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let verification: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(verification["valid"], true);
+        let tampered = crate::services::receipts::InclusionReceipt { leaf_index: receipt.leaf_index + 1, ..receipt };
+        let response = app.oneshot(verify(&tampered)).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let verification: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(verification["valid"], false);
+    }
+
+    #[tokio::test]
+    async fn test_filler_capabilities_api() {
+        let mut config = Config::default();
+        config.filler.ws_tokens = std::collections::HashMap::from([("secret".to_string(), "filler_1".to_string())]);
+        let (app, db) = create_test_app_with_config(config).await;
+
+        let created_at = chrono::Utc::now() - chrono::Duration::minutes(1);
+        let orders = [("order_0", "Wise", "100"), ("order_1", "PayPal Hong Kong", "1000"), ("order_2", "PayPal Hong Kong", "100"), ("order_3", "PayPal US", "200")];
+        for (i, (id, bank_service, amount)) in orders.into_iter().enumerate() {
+            let order = crate::models::Order {
+                id: id.to_string(),
+                order_type: OrderType::BridgeIn,
+                status: OrderStatus::Discovery,
+                from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+                to_address: None,
+                token_id: 1,
+                amount: amount.to_string(),
+                bank_account: Some("12345678".to_string()),
+                bank_service: Some(bank_service.to_string()),
+                banking_hash: None,
+                filler_id: None,
+                locked_amount: None,
+                batch_id: None,
+                created_at: created_at + chrono::Duration::seconds(i as i64),
+                updated_at: created_at,
+            };
+            crate::database::helpers::insert_order(&db, &order).await.unwrap();
+        }
+
+        let send = |method: &str, uri: &str, token: Option<&str>, body: Option<Value>| {
+            let mut request = Request::builder().method(method).uri(uri).header("content-type", "application/json");
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
+            }
+            let request = request.body(body.map(|body| Body::from(body.to_string())).unwrap_or_else(Body::empty)).unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let capabilities = json!({
+            "corridors": [{ "token_id": 1, "rail": "paypal" }],
+            "max_order_amount": 500,
+            "auto_accept": [{ "max_amount": 100 }]
+        });
+        let (status, _) = send("GET", "/api/v1/fillers/filler_1/capabilities", None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send("PUT", "/api/v1/fillers/filler_1/capabilities", None, Some(capabilities.clone())).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, error) = send("PUT", "/api/v1/fillers/filler_1/capabilities", Some("secret"), Some(json!({ "min_order_amount": 5, "max_order_amount": 1 }))).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_capabilities")));
+        let (status, _) = send("PUT", "/api/v1/fillers/filler_1/capabilities", Some("secret"), Some(capabilities)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, stored) = send("GET", "/api/v1/fillers/filler_1/capabilities", None, None).await;
+        assert_eq!(stored["max_order_amount"], 500);
+
+        // Discovery skips orders outside the filler's capabilities, across pages
+        let ids = |page: &Value| -> Vec<String> {
+            page["orders"].as_array().unwrap().iter().map(|order| order["id"].as_str().unwrap().to_string()).collect()
+        };
+        let (_, page) = send("GET", "/api/v1/fillers/discovery?filler_id=filler_1&limit=1", None, None).await;
+        assert_eq!(ids(&page), vec!["order_2"]);
+        let cursor = page["next_cursor"].as_str().unwrap().to_string();
+        let (_, page) = send("GET", &format!("/api/v1/fillers/discovery?filler_id=filler_1&limit=1&after={}", cursor), None, None).await;
+        assert_eq!(ids(&page), vec!["order_3"]);
+        assert!(page["next_cursor"].is_null());
+        let (_, page) = send("GET", "/api/v1/fillers/discovery", None, None).await;
+        assert_eq!(page["total"], 4);
+
+        // Locks outside the capabilities are refused
+        let lock = |amount: &str| Some(json!({ "filler_id": "filler_1", "amount": amount }));
+        let (status, error) = send("POST", "/api/v1/fillers/orders/order_0/lock", None, lock("100")).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::UNPROCESSABLE_ENTITY, Some("outside_filler_capabilities")));
+        assert_eq!(error["details"]["capability"], "corridor");
+        let (status, error) = send("POST", "/api/v1/fillers/orders/order_1/lock", None, lock("100")).await;
+        assert_eq!((status, error["details"]["capability"].as_str()), (StatusCode::UNPROCESSABLE_ENTITY, Some("above_maximum")));
+        let (status, order) = send("POST", "/api/v1/fillers/orders/order_2/lock", None, lock("100")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(order["filler_id"], "filler_1");
+    }
+
+    #[tokio::test]
+    async fn test_locks_above_threshold_need_collateral() {
+        let mut config = Config::default();
+        config.api.admin_token = Some("admin-secret".to_string());
+        config.collateral.lock_threshold = 100;
+        config.collateral.ratio_bps = 5_000;
+        let (app, db) = create_test_app_with_config(config).await;
+
+        for (id, amount) in [("small_order", "100"), ("large_order", "1000"), ("another_order", "1000")] {
+            let order = crate::models::Order {
+                id: id.to_string(),
+                order_type: OrderType::BridgeIn,
+                status: OrderStatus::Discovery,
+                from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+                to_address: None,
+                token_id: 1,
+                amount: amount.to_string(),
+                bank_account: Some("12345678".to_string()),
+                bank_service: Some("PayPal Hong Kong".to_string()),
+                banking_hash: None,
+                filler_id: None,
+                locked_amount: None,
+                batch_id: None,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            };
+            crate::database::helpers::insert_order(&db, &order).await.unwrap();
+        }
+
+        let send = |method: &str, uri: &str, token: Option<&str>, body: Option<Value>| {
+            let mut request = Request::builder().method(method).uri(uri).header("content-type", "application/json");
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
+            }
+            let request = request.body(body.map(|body| Body::from(body.to_string())).unwrap_or_else(Body::empty)).unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+        let lock = |amount: &str| Some(json!({ "filler_id": "filler_1", "amount": amount }));
+
+        // Up to the threshold no collateral is needed, but the lock still counts towards utilization
+        let (status, _) = send("POST", "/api/v1/fillers/orders/small_order/lock", None, lock("100")).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, error) = send("POST", "/api/v1/fillers/orders/large_order/lock", None, lock("1000")).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::FORBIDDEN, Some("insufficient_collateral")));
+        assert_eq!((error["details"]["required"].as_str(), error["details"]["available"].as_str()), (Some("550"), Some("0")));
+
+        sqlx::query("INSERT INTO filler_collateral (filler_id, deposited, refreshed_at, updated_at) VALUES ('filler_1', '600', ?1, ?1)")
+            .bind(chrono::Utc::now())
+            .execute(&db)
+            .await
+            .unwrap();
+        let (status, _) = send("POST", "/api/v1/fillers/orders/large_order/lock", None, lock("1000")).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, collateral) = send("GET", "/api/v1/fillers/filler_1/collateral", None, None).await;
+        assert_eq!((collateral["required"].as_str(), collateral["open_locks"].as_u64()), (Some("550"), Some(2)));
+
+        // A flagged filler keeps to the threshold until an admin lifts the flag
+        sqlx::query("UPDATE filler_collateral SET deposited = '10000', expiries = 3, flagged_at = ?1 WHERE filler_id = 'filler_1'")
+            .bind(chrono::Utc::now())
+            .execute(&db)
+            .await
+            .unwrap();
+        let (status, error) = send("POST", "/api/v1/fillers/orders/another_order/lock", None, lock("1000")).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::FORBIDDEN, Some("filler_flagged")));
+        let (status, _) = send("DELETE", "/api/v1/admin/fillers/filler_1/collateral/flag", None, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send("DELETE", "/api/v1/admin/fillers/filler_1/collateral/flag", Some("admin-secret"), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send("DELETE", "/api/v1/admin/fillers/filler_1/collateral/flag", Some("admin-secret"), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send("POST", "/api/v1/fillers/orders/another_order/lock", None, lock("1000")).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_settlement_saga_admin_view_and_abort() {
+        let mut config = Config::default();
+        config.api.admin_token = Some("admin-secret".to_string());
+        let (app, db) = create_test_app_with_config(config).await;
+
+        let order = crate::models::Order {
+            id: "saga_order".to_string(),
+            order_type: OrderType::BridgeIn,
+            status: OrderStatus::Discovery,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        crate::database::helpers::insert_order(&db, &order).await.unwrap();
+
+        let send = |method: &str, uri: &str, token: Option<&str>, body: Option<Value>| {
+            let mut request = Request::builder().method(method).uri(uri).header("content-type", "application/json");
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
+            }
+            let request = request.body(body.map(|body| Body::from(body.to_string())).unwrap_or_else(Body::empty)).unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let (status, _) = send("POST", "/api/v1/fillers/orders/saga_order/lock", None, Some(json!({ "filler_id": "filler_1", "amount": "1000" }))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, sagas) = send("GET", "/api/v1/admin/sagas", Some("admin-secret"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(sagas.as_array().unwrap().len(), 1);
+        assert_eq!(sagas[0]["order_id"], "saga_order");
+        assert_eq!((sagas[0]["step"].as_str(), sagas[0]["state"].as_str()), (Some("matched"), Some("running")));
+
+        let abort = Some(json!({ "reason": "bank_outage" }));
+        let (status, _) = send("POST", "/api/v1/admin/sagas/saga_order/abort", None, abort.clone()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, saga) = send("POST", "/api/v1/admin/sagas/saga_order/abort", Some("admin-secret"), abort.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(saga["state"], "compensated");
+        assert_eq!(saga["compensations"], json!(["release_lock"]));
+        assert_eq!(saga["failure_reason"], "bank_outage");
+
+        // The order is available to other fillers again
+        let released = crate::database::helpers::get_order_by_id(&db, "saga_order").await.unwrap().unwrap();
+        assert_eq!((released.status, released.filler_id), (OrderStatus::Discovery, None));
+
+        let (status, error) = send("POST", "/api/v1/admin/sagas/saga_order/abort", Some("admin-secret"), abort).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::CONFLICT, Some("saga_finished")));
+        let (_, sagas) = send("GET", "/api/v1/admin/sagas", Some("admin-secret"), None).await;
+        assert_eq!(sagas, json!([]));
+        let (_, sagas) = send("GET", "/api/v1/admin/sagas?state=compensated", Some("admin-secret"), None).await;
+        assert_eq!(sagas.as_array().unwrap().len(), 1);
+        let (status, error) = send("GET", "/api/v1/admin/sagas/unknown", Some("admin-secret"), None).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::NOT_FOUND, Some("saga_not_found")));
+    }
+
+    #[tokio::test]
+    async fn test_order_book_reconciliation_reports() {
+        let mut config = Config::default();
+        config.api.admin_token = Some("admin-secret".to_string());
+        let (app, db) = create_test_app_with_config(config).await;
+
+        // Locked behind the matching engine's back
+        let order = crate::models::Order {
+            id: "orphan_lock".to_string(),
+            order_type: OrderType::BridgeIn,
+            status: OrderStatus::Locked,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            filler_id: Some("filler_1".to_string()),
+            locked_amount: Some("1000".to_string()),
+            batch_id: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        crate::database::helpers::insert_order(&db, &order).await.unwrap();
+
+        let send = |method: &str, uri: &str, token: Option<&str>| {
+            let mut request = Request::builder().method(method).uri(uri);
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
+            }
+            let request = request.body(Body::empty()).unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let (status, _) = send("GET", "/api/v1/admin/reconciliation/reports/latest", Some("admin-secret")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send("POST", "/api/v1/admin/reconciliation/run", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, report) = send("POST", "/api/v1/admin/reconciliation/run", Some("admin-secret")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["trigger"], "manual");
+        assert_eq!(report["locked_unknown_to_engine"], json!([{ "order_id": "orphan_lock", "filler_id": "filler_1" }]));
+        assert_eq!(report["chain_claims_scanned"], Value::Null);
+
+        let (status, reports) = send("GET", "/api/v1/admin/reconciliation/reports", Some("admin-secret")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(reports.as_array().unwrap().len(), 1);
+        assert_eq!((reports[0]["id"].clone(), reports[0]["mismatches"].clone()), (report["id"].clone(), json!(1)));
+
+        let (_, latest) = send("GET", "/api/v1/admin/reconciliation/reports/latest", Some("admin-secret")).await;
+        assert_eq!(latest, report);
+        let (status, stored) = send("GET", &format!("/api/v1/admin/reconciliation/reports/{}", report["id"]), Some("admin-secret")).await;
+        assert_eq!((status, stored), (StatusCode::OK, report));
+        let (status, _) = send("GET", "/api/v1/admin/reconciliation/reports/999", Some("admin-secret")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_signed_transfers_settle_with_the_batch() {
+        use crate::services::transfers::TransferRequest;
+        use vapor_chain::signer::{LocalKeySigner, Signer};
+
+        let (app, db) = create_test_app().await;
+        let bridge = Config::default().blockchain.contract_address;
+        let signer = LocalKeySigner::from_hex("0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80").unwrap();
+        let alice = format!("{:?}", signer.address());
+        let bob = "0x2222222222222222222222222222222222222222";
+
+        let sign = |amount: &str, nonce: u64| {
+            let mut transfer = TransferRequest {
+                from_address: alice.clone(),
+                to_address: bob.to_string(),
+                token_id: 1,
+                amount: amount.to_string(),
+                nonce,
+                signature: String::new(),
+            };
+            let digest = web3::signing::hash_message(transfer.message(&bridge).as_bytes());
+            let signer = &signer;
+            async move {
+                let signature = signer.sign_digest(digest, None).await.unwrap();
+                transfer.signature = format!("0x{}{}{:02x}", hex::encode(signature.r.as_bytes()), hex::encode(signature.s.as_bytes()), signature.v);
+                serde_json::to_value(transfer).unwrap()
+            }
+        };
+        let send = |method: &str, uri: &str, body: Option<Value>| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(body.map(|body| Body::from(body.to_string())).unwrap_or_else(Body::empty))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let (status, _) = send("POST", "/api/v1/batch/init-account", Some(json!({ "address": alice, "token_id": 1, "initial_balance": "1000" }))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let transfer = sign("300", 1).await;
+        let (status, created) = send("POST", "/api/v1/transfers", Some(transfer.clone())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((created["order_type"].as_str(), created["status"].as_str()), (Some("Transfer"), Some("Pending")));
+        let batch_id = created["batch_id"].as_u64().unwrap();
+        let transfer_id = created["id"].as_str().unwrap().to_string();
+
+        // Replays, forgeries and overdrafts are refused
+        let (status, error) = send("POST", "/api/v1/transfers", Some(transfer.clone())).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::CONFLICT, Some("nonce_used")));
+        let mut forged = transfer.clone();
+        forged["amount"] = json!("900");
+        forged["nonce"] = json!(2);
+        let (status, error) = send("POST", "/api/v1/transfers", Some(forged)).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::UNAUTHORIZED, Some("invalid_signature")));
+        let (status, error) = send("POST", "/api/v1/transfers", Some(sign("5000", 3).await)).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::UNPROCESSABLE_ENTITY, Some("insufficient_balance")));
+        let failed: i64 = sqlx::query("SELECT COUNT(*) AS count FROM orders WHERE status = ? AND failure_reason = 'batch_rejected'")
+            .bind(OrderStatus::Failed as i32)
+            .fetch_one(&db)
+            .await
+            .unwrap()
+            .get("count");
+        assert_eq!(failed, 1);
+
+        // Finalizing the batch settles the transfer without a claim, and it can be proven
+        let (status, _) = send("POST", "/api/v1/batch/finalize", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let settled = crate::database::helpers::get_order_by_id(&db, &transfer_id).await.unwrap().unwrap();
+        assert_eq!((settled.status, settled.batch_id), (OrderStatus::Settled, Some(batch_id)));
+        let (status, proof) = send("GET", &format!("/api/v1/proofs/order/{}/{}", batch_id, transfer_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(proof["order_id"], transfer_id.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_bank_details_are_scrubbed_after_retention() {
+        let mut config = Config::default();
+        config.api.admin_token = Some("admin-secret".to_string());
+        config.retention.bank_details_days = 10;
+        let (app, db) = create_test_app_with_config(config).await;
+
+        let settled_at = chrono::Utc::now() - chrono::Duration::days(11);
+        let order = crate::models::Order {
+            id: "old_settled".to_string(),
+            order_type: OrderType::BridgeOut,
+            status: OrderStatus::Settled,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: Some("0xabcdef".to_string()),
+            filler_id: Some("filler_1".to_string()),
+            locked_amount: Some("1000".to_string()),
+            batch_id: None,
+            created_at: settled_at,
+            updated_at: settled_at,
+        };
+        crate::database::helpers::insert_order(&db, &order).await.unwrap();
+
+        let send = |method: &str, uri: &str, token: Option<&str>| {
+            let mut request = Request::builder().method(method).uri(uri);
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
+            }
+            let request = request.body(Body::empty()).unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let (status, _) = send("POST", "/api/v1/admin/retention/run", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, scrubbed) = send("POST", "/api/v1/admin/retention/run", Some("admin-secret")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(scrubbed[0]["order_id"], "old_settled");
+        assert_eq!(scrubbed[0]["fields"], json!(["bank_account", "bank_service"]));
+
+        let order = crate::database::helpers::get_order_by_id(&db, "old_settled").await.unwrap().unwrap();
+        assert_eq!((order.bank_account, order.banking_hash.as_deref()), (None, Some("0xabcdef")));
+
+        let (status, audit) = send("GET", "/api/v1/admin/retention/audit?order_id=old_settled", Some("admin-secret")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((audit[0]["retention_days"].clone(), audit[0]["bank_service"].clone()), (json!(10), json!("PayPal Hong Kong")));
+        let (_, audit) = send("GET", "/api/v1/admin/retention/audit?order_id=unknown", Some("admin-secret")).await;
+        assert_eq!(audit, json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_operator_overview_isolates_failing_sections() {
+        let mut config = Config::default();
+        config.api.admin_token = Some("admin-secret".to_string());
+        let (app, db) = create_test_app_with_config(config).await;
+
+        for (id, status) in [("open_1", OrderStatus::Discovery), ("open_2", OrderStatus::Discovery), ("done", OrderStatus::Settled)] {
+            let order = crate::models::Order {
+                id: id.to_string(),
+                order_type: OrderType::BridgeOut,
+                status,
+                from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+                to_address: None,
+                token_id: 1,
+                amount: "1000".to_string(),
+                bank_account: None,
+                bank_service: None,
+                banking_hash: None,
+                filler_id: None,
+                locked_amount: None,
+                batch_id: None,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            };
+            crate::database::helpers::insert_order(&db, &order).await.unwrap();
+        }
+        crate::database::helpers::upsert_filler_balance(&db, "filler_1", "5000").await.unwrap();
+        sqlx::query("UPDATE filler_balances SET locked_balance = '1200'").execute(&db).await.unwrap();
+
+        let send = |method: &str, uri: &str, body: Option<Value>| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", "Bearer admin-secret")
+                .body(body.map(|body| Body::from(body.to_string())).unwrap_or_else(Body::empty))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let (status, _) = send("PUT", "/api/v1/admin/maintenance", Some(json!({ "enabled": true, "message": "Upgrading" }))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, overview) = send("GET", "/api/v1/admin/overview", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(overview["orders"]["status"], "ok");
+        assert_eq!(overview["orders"]["data"]["by_status"]["Discovery"], 2);
+        assert_eq!(overview["orders"]["data"]["by_status"]["Locked"], 0);
+        assert_eq!(overview["orders"]["data"]["total_open"], 2);
+        assert_eq!(overview["batch"]["data"]["has_active_batch"], false);
+        assert_eq!(overview["prover"]["data"]["depth"], 0);
+        assert_eq!(overview["relayer"]["data"]["chain_head"], Value::Null);
+        assert_eq!(overview["proof_submissions"]["data"], json!([]));
+        let liquidity = &overview["filler_liquidity"]["data"];
+        assert_eq!((liquidity["fillers"].clone(), liquidity["available_balance"].clone()), (json!(1), json!("3800")));
+        assert_eq!(overview["alerts"]["data"][0]["source"], "maintenance");
+        assert_eq!(overview["alerts"]["data"][0]["message"], "Upgrading");
+
+        // A section that cannot be read reports its error without taking the others down
+        sqlx::query("UPDATE filler_balances SET total_balance = 'lots'").execute(&db).await.unwrap();
+        let (status, overview) = send("GET", "/api/v1/admin/overview", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(overview["filler_liquidity"]["status"], "error");
+        assert!(overview["filler_liquidity"]["error"].as_str().unwrap().contains("filler_1"));
+        assert_eq!(overview["orders"]["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_order_amounts_outside_the_configured_range_are_rejected() {
+        let mut config = Config::default();
+        config.order_amounts.default_limits.bridge_in = crate::config::AmountRange { min: 1_000, max: 0 };
+        config.order_amounts.token_limits.insert(1, crate::config::OrderTypeAmounts {
+            bridge_in: crate::config::AmountRange { min: 1_000, max: 10_000_000 },
+            ..Default::default()
+        });
+        let (app, db) = create_test_app_with_config(config).await;
+
+        let create = |token_id: u32, amount: &str| {
+            let body = json!({
+                "order_type": "BridgeIn",
+                "from_address": "0x1234567890123456789012345678901234567890",
+                "token_id": token_id,
+                "amount": amount,
+                "bank_account": "12345678",
+                "bank_service": "PayPal Hong Kong",
+            });
+            let request = Request::builder()
+                .method("POST")
+                .uri("/api/v1/orders")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let (status, error) = create(1, "1").await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::UNPROCESSABLE_ENTITY, Some("order_amount_out_of_range")));
+        assert_eq!(error["details"], json!({
+            "limit": "below_minimum",
+            "token_id": 1,
+            "order_type": "BridgeIn",
+            "amount": "1",
+            "min": 1000,
+            "max": 10000000,
+        }));
+
+        let (status, error) = create(1, "10000000000000").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["details"]["limit"], "above_maximum");
+
+        // Tokens without an override use the default range, which has no maximum
+        let (status, _) = create(2, "10000000000000").await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = create(1, "5000").await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Rejected orders are never stored
+        let stored: i64 = sqlx::query("SELECT COUNT(*) AS count FROM orders").fetch_one(&db).await.unwrap().get("count");
+        assert_eq!(stored, 2);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_locks_have_exactly_one_winner() {
+        let (app, db) = create_test_app().await;
+        let order = crate::models::Order {
+            id: "contested_order".to_string(),
+            order_type: OrderType::BridgeIn,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            status: OrderStatus::Discovery,
+            batch_id: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        crate::database::helpers::insert_order(&db, &order).await.unwrap();
+
+        let attempts: Vec<_> = (0..32)
+            .map(|i| {
+                let request = Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/fillers/orders/contested_order/lock")
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({ "filler_id": format!("filler_{}", i), "amount": "1000000" }).to_string()))
+                    .unwrap();
+                let app = app.clone();
+                tokio::spawn(async move {
+                    let response = app.oneshot(request).await.unwrap();
+                    let status = response.status();
+                    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                    (status, serde_json::from_slice::<Value>(&body).unwrap())
+                })
+            })
+            .collect();
+
+        let mut winners = Vec::new();
+        for attempt in attempts {
+            let (status, body) = attempt.await.unwrap();
+            match status {
+                StatusCode::OK => winners.push(body["filler_id"].as_str().unwrap().to_string()),
+                status => {
+                    // Every loser is told the order is taken, whichever check caught it
+                    assert_eq!((status, body["error"].as_str()), (StatusCode::CONFLICT, Some("order_not_lockable")));
+                    assert_eq!(body["details"]["status"], "Locked");
+                }
+            }
+        }
+        assert_eq!(winners.len(), 1);
+
+        let stored = crate::database::helpers::get_order_by_id(&db, "contested_order").await.unwrap().unwrap();
+        assert_eq!((stored.status, stored.filler_id), (OrderStatus::Locked, Some(winners[0].clone())));
+        let sagas: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM settlement_sagas WHERE order_id = 'contested_order'").fetch_one(&db).await.unwrap();
+        assert_eq!(sagas, 1);
+    }
+
+    #[tokio::test]
+    async fn test_backups_restore_only_under_maintenance() {
+        let mut config = Config::default();
+        config.api.admin_token = Some("admin-secret".to_string());
+        // Backups need a database on disk
+        let path = std::env::temp_dir().join(format!("vapor-api-backups-{}.db", uuid::Uuid::new_v4()));
+        let db = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let (app, db) = create_test_app_with_state(AppState::new(config, db)).await;
+
+        let send = |method: &str, uri: &str, body: Option<Value>| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", "Bearer admin-secret")
+                .header("content-type", "application/json")
+                .body(body.map(|body| Body::from(body.to_string())).unwrap_or_else(Body::empty))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+        let count_accounts = || async {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM account_balances").fetch_one(&db).await.unwrap()
+        };
+
+        let (status, backup) = send("POST", "/api/v1/admin/backups", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(backup["trigger"], "manual");
+        let backup_id = backup["id"].as_i64().unwrap();
+        sqlx::query("INSERT INTO account_balances (address, token_id, balance) VALUES ('0xaaa', 1, '100')").execute(&db).await.unwrap();
+
+        let (status, verified) = send("POST", &format!("/api/v1/admin/backups/{}/verify", backup_id), None).await;
+        assert_eq!((status, verified["tables"]["account_balances"].clone()), (StatusCode::OK, json!(0)));
+
+        // Restores replace rows under live traffic only once intake is paused
+        let restore = format!("/api/v1/admin/backups/{}/restore", backup_id);
+        let (status, error) = send("POST", &restore, None).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::CONFLICT, Some("maintenance_required")));
+        assert_eq!(count_accounts().await, 1);
+
+        let (status, _) = send("PUT", "/api/v1/admin/maintenance", Some(json!({ "enabled": true }))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, report) = send("POST", &restore, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["restart_required"], true);
+        assert_eq!(count_accounts().await, 0);
+
+        let (_, backups) = send("GET", "/api/v1/admin/backups", None).await;
+        assert_eq!(backups[0]["trigger"], "pre_restore");
+        assert_eq!(backups.as_array().unwrap().len(), 2);
+        let (status, error) = send("POST", "/api/v1/admin/backups/999/restore", None).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::NOT_FOUND, Some("backup_not_found")));
+    }
+
+    #[tokio::test]
+    async fn test_near_duplicate_orders_are_flagged_or_held_back() {
+        let mut config = Config::default();
+        config.order_duplicates.window_seconds = 300;
+        let (app, db) = create_test_app_with_config(config).await;
+
+        let create = |app: Router, amount: &str, bank_account: &str, confirm_duplicate: bool| {
+            let body = json!({
+                "order_type": "BridgeIn",
+                "from_address": "0x1234567890123456789012345678901234567890",
+                "token_id": 1,
+                "amount": amount,
+                "bank_account": bank_account,
+                "bank_service": "PayPal Hong Kong",
+                "confirm_duplicate": confirm_duplicate,
+            });
+            let request = Request::builder()
+                .method("POST")
+                .uri("/api/v1/orders")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let (status, original) = create(app.clone(), "1000", "12345678", false).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(original.get("duplicate_warning").is_none());
+
+        // Flagged by default, and still created
+        let (status, flagged) = create(app.clone(), "1000", "12345678", false).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(flagged["duplicate_warning"]["original_order_id"], original["id"]);
+        assert_eq!(flagged["duplicate_warning"]["window_seconds"], 300);
+
+        // Another amount or bank account is a different off-ramp
+        let (_, other) = create(app.clone(), "1001", "12345678", false).await;
+        assert!(other.get("duplicate_warning").is_none());
+        let (_, other) = create(app.clone(), "1000", "87654321", false).await;
+        assert!(other.get("duplicate_warning").is_none());
+
+        // Blocking mode holds the order back until the seller confirms it
+        let mut config = Config::default();
+        config.order_duplicates.mode = crate::config::DuplicateMode::Block;
+        let (app, _) = create_test_app_with_state(AppState::new(config, db.clone())).await;
+        let (status, error) = create(app.clone(), "1000", "87654321", false).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::CONFLICT, Some("suspected_duplicate")));
+        assert_eq!(error["details"]["original_order_id"], other["id"]);
+        let (status, confirmed) = create(app.clone(), "1000", "87654321", true).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(confirmed["duplicate_warning"]["original_order_id"], other["id"]);
+
+        // Outside the window, or once the original failed, it is a fresh order
+        sqlx::query("UPDATE orders SET created_at = ? WHERE amount = '1001'")
+            .bind(chrono::Utc::now() - chrono::Duration::seconds(900))
+            .execute(&db)
+            .await
+            .unwrap();
+        let (status, _) = create(app.clone(), "1001", "12345678", false).await;
+        assert_eq!(status, StatusCode::OK);
+        sqlx::query("UPDATE orders SET status = ? WHERE bank_account = '87654321'")
+            .bind(OrderStatus::Failed as i32)
+            .execute(&db)
+            .await
+            .unwrap();
+        let (status, fresh) = create(app.clone(), "1000", "87654321", false).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(fresh.get("duplicate_warning").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_state_leaves_export_streams_ndjson() {
+        use crate::models::{AccountState, TokenBalance};
+        use crate::services::archival::BatchSnapshot;
+
+        let mut config = Config::default();
+        config.explorer.rate_limit_per_minute = 3;
+        let app_state = AppState::new(config, {
+            let db = SqlitePool::connect(":memory:").await.unwrap();
+            crate::database::run_migrations(&db).await.unwrap();
+            db
+        });
+        let account = |address: &str, balance: &str| AccountState {
+            address: address.to_string(),
+            balances: vec![TokenBalance { token_id: 1, balance: balance.to_string() }],
+            updated_at: chrono::Utc::now(),
+        };
+        let snapshot = |batch_id: u64, accounts: Vec<AccountState>| BatchSnapshot {
+            batch_id,
+            state_root: format!("root_{}", batch_id),
+            orders_root: "0x".to_string(),
+            accounts,
+            orders: vec![],
+            created_at: chrono::Utc::now(),
+        };
+        app_state.archive.store_snapshot(&snapshot(1, vec![account("0xbbb", "10"), account("0xaaa", "5")])).await.unwrap();
+        app_state.archive.store_snapshot(&snapshot(2, vec![account("0xaaa", "5"), account("0xbbb", "3"), account("0xccc", "9")])).await.unwrap();
+        let (app, _) = create_test_app_with_state(app_state).await;
+
+        let export = |uri: &str| {
+            let request = Request::builder().uri(uri).header("x-forwarded-for", "203.0.113.7").body(Body::empty()).unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let (status, headers) = (response.status(), response.headers().clone());
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, headers, String::from_utf8(body.to_vec()).unwrap())
+            }
+        };
+        let lines = |body: &str| -> Vec<Value> { body.lines().map(|line| serde_json::from_str(line).unwrap()).collect() };
+
+        let (status, headers, body) = export("/api/v1/explorer/state/2/leaves").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers["content-type"], "application/x-ndjson");
+        assert_eq!(headers["x-state-root"], "root_2");
+        let leaves = lines(&body);
+        let addresses: Vec<_> = leaves.iter().map(|leaf| leaf["address"].as_str().unwrap()).collect();
+        assert_eq!(addresses, vec!["0xaaa", "0xbbb", "0xccc"]);
+        assert_eq!(leaves[1]["balances"], json!([{ "token_id": 1, "balance": "3" }]));
+        assert_eq!(leaves[1]["leaf_hash"].as_str().unwrap().len(), 64);
+
+        // Only the leaves that changed after batch 1
+        let (_, _, body) = export("/api/v1/explorer/state/2/leaves?since=1").await;
+        let changed: Vec<_> = lines(&body).iter().map(|leaf| leaf["address"].as_str().unwrap().to_string()).collect();
+        assert_eq!(changed, vec!["0xbbb", "0xccc"]);
+
+        let (status, _, body) = export("/api/v1/explorer/state/7/leaves").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["error"], "snapshot_not_found");
+
+        // Three exports a minute per client
+        let (status, _, _) = export("/api/v1/explorer/state/2/leaves").await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_claims_transfer_the_net_of_fees_shown_in_the_preview() {
+        let mut config = Config::default();
+        config.claims.fee_bps = 50;
+        config.claims.fee_gas_surcharge = 100_000;
+        config.claims.relay_enabled = true;
+        let (app, _) = create_test_app_with_config(config).await;
+        let send = |method: &str, uri: &str, body: Value| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+        let claim = json!({
+            "filler_id": "filler_1",
+            "claims": [
+                { "amount": "10000000", "destination_address": "0x1111111111111111111111111111111111111111" },
+                { "amount": "20000000", "destination_address": "0x2222222222222222222222222222222222222222" }
+            ]
+        });
+
+        // 0.5% plus the 0.1 surcharge on each claim
+        let (status, preview) = send("POST", "/api/v1/fillers/claim/preview", claim.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(preview["claims"][0]["fee"]["protocol_fee"], "50000");
+        assert_eq!(preview["claims"][0]["fee"]["net_amount"], "9850000");
+        assert_eq!(preview["claims"][1]["fee"]["net_amount"], "19800000");
+        assert_eq!((preview["total_gross"].as_str(), preview["total_fee"].as_str()), (Some("30000000"), Some("350000")));
+        assert!(preview.get("relay").is_none());
+
+        // Relaying adds its fee to the largest claim
+        let (status, _) = send("PUT", "/api/v1/fillers/filler_1/claim-relay", json!({ "enabled": true })).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, preview) = send("POST", "/api/v1/fillers/claim/preview", claim.clone()).await;
+        assert_eq!(preview["claims"][1]["fee"]["relay_fee"], "9900000");
+        assert_eq!(preview["claims"][1]["fee"]["fee_amount"], "10100000");
+        assert_eq!(preview["total_net"], "19750000");
+
+        let (status, response) = send("POST", "/api/v1/fillers/claim", claim).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response["total_claimed"], "30000000");
+        assert_eq!(response["total_payout"], preview["total_payout"]);
+        for (processed, previewed) in response["claims_processed"].as_array().unwrap().iter().zip(preview["claims"].as_array().unwrap()) {
+            assert_eq!(processed["fee"], previewed["fee"]);
+            assert_eq!(processed["payout_amount"], previewed["payout_amount"]);
+        }
+
+        // The claim row keeps gross, fee and net
+        let claim_uri = format!("/api/v1/fillers/claims/{}", response["claims_processed"][1]["claim_id"].as_str().unwrap());
+        let (_, stored) = send("GET", &claim_uri, Value::Null).await;
+        assert_eq!((stored["amount"].as_str(), stored["fee_amount"].as_str(), stored["net_amount"].as_str()), (Some("20000000"), Some("10100000"), Some("9900000")));
+
+        // A fee that would swallow the claim is refused
+        let (status, error) = send("POST", "/api/v1/fillers/claim/preview", json!({
+            "filler_id": "filler_2",
+            "claims": [{ "amount": "100000", "destination_address": "0x1111111111111111111111111111111111111111" }]
+        })).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::UNPROCESSABLE_ENTITY, Some("claim_fee_exceeds_amount")));
+        assert_eq!(error["details"]["fee"], "100500");
+    }
+
+    #[tokio::test]
+    async fn test_relayed_claims_pay_gas_from_the_claim() {
+        async fn send(app: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(body.map(|body| Body::from(body.to_string())).unwrap_or_else(Body::empty))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+        }
+
+        // Relaying is off unless the backend offers it
+        let (app, _) = create_test_app().await;
+        let (status, error) = send(&app, "PUT", "/api/v1/fillers/filler_1/claim-relay", Some(json!({ "enabled": true }))).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::CONFLICT, Some("claim_relay_unavailable")));
+
+        let mut config = Config::default();
+        config.claims.relay_enabled = true;
+        let (app, _) = create_test_app_with_config(config).await;
+        let claim = json!({
+            "filler_id": "filler_1",
+            "claims": [
+                { "amount": "5000000", "destination_address": "0x1111111111111111111111111111111111111111" },
+                { "amount": "20000000", "destination_address": "0x2222222222222222222222222222222222222222" }
+            ]
+        });
+
+        // A fee above the filler's maximum is refused rather than charged
+        let (status, _) = send(&app, "PUT", "/api/v1/fillers/filler_1/claim-relay", Some(json!({ "enabled": true, "max_fee": "1000000" }))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, error) = send(&app, "POST", "/api/v1/fillers/claim", Some(claim.clone())).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::UNPROCESSABLE_ENTITY, Some("relay_fee_above_maximum")));
+        assert_eq!(error["details"]["fee"], "9900000");
+
+        let (status, _) = send(&app, "PUT", "/api/v1/fillers/filler_1/claim-relay", Some(json!({ "enabled": true }))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, response) = send(&app, "POST", "/api/v1/fillers/claim", Some(claim)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response["relay"]["fee_amount"], "9900000");
+        assert_eq!(response["total_claimed"], "25000000");
+        assert_eq!(response["total_payout"], "15100000");
+        assert_eq!(response["claims_processed"][0]["payout_amount"], "5000000");
+        assert_eq!(response["claims_processed"][1]["payout_amount"], "10100000");
+
+        let (status, accounting) = send(&app, "GET", "/api/v1/fillers/filler_1/claim-relay", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(accounting["settings"]["enabled"], true);
+        assert_eq!((accounting["transactions"].as_u64(), accounting["claims_relayed"].as_u64()), (Some(1), Some(2)));
+        assert_eq!(accounting["gas_used"], "150000");
+        assert_eq!(accounting["fees_charged"], "9900000");
+        assert_eq!(accounting["relays"][0]["transaction_hash"], response["transaction_hash"]);
+    }
+
+    /// A PayPal order of a funded seller locked by filler_1, inserted directly, with the
+    /// request submitting its proof
+    async fn locked_paypal_order(app: &Router, db: &SqlitePool) -> (String, Request<Body>) {
+        let init_request = json!({ "address": "0x1234567890123456789012345678901234567890", "token_id": 1, "initial_balance": "1000" });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/batch/init-account")
+                    .header("content-type", "application/json")
+                    .body(Body::from(init_request.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut order = crate::models::Order::new(CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: None,
+            to_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            token_id: 1,
+            amount: "100".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        });
+        order.lock_for_filler("filler_1".to_string(), "100".to_string(), chrono::Utc::now());
+        crate::database::helpers::insert_order(db, &order).await.unwrap();
+
+        let proof = json!({
+            "transaction_id": "8MC585209K746392H",
+            "payer_email_hash": "0x1c8aff950685c2ed4bc3174f3472287b56d9517b9c948127319a09a7a36deac8",
+        });
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/api/v1/fillers/orders/{}/payment-proof", order.id))
+            .header("content-type", "application/json")
+            .body(Body::from(json!({ "payment_proof": proof }).to_string()))
+            .unwrap();
+        (order.id, request)
+    }
+
+    async fn order_status(db: &SqlitePool, order_id: &str) -> OrderStatus {
+        crate::database::helpers::get_order_by_id(db, order_id).await.unwrap().unwrap().status
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_jobs_verify_payment_and_settle() {
+        use vapor_core::services::clock::MockClock;
+
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let clock = MockClock::new(chrono::Utc::now());
+        let app_state = AppState::new_with_clock(Config::default(), db, clock.shared());
+        let (app, db) = create_test_app_with_state(app_state.clone()).await;
+
+        let (order_id, submit) = locked_paypal_order(&app, &db).await;
+        assert_eq!(app.clone().oneshot(submit).await.unwrap().status(), StatusCode::OK);
+
+        // Without a verifier configured the proof is accepted on the first run
+        assert_eq!(crate::api::jobs::run_due_jobs(&app_state).await.unwrap(), 1);
+        let transfer_order_id: String = sqlx::query("SELECT payload FROM scheduled_jobs WHERE kind = 'check_settlement'")
+            .fetch_one(&db)
+            .await
+            .unwrap()
+            .get("payload");
+        assert_eq!(order_status(&db, &transfer_order_id).await, OrderStatus::Pending);
+
+        // The settlement check waits for the transfer's batch proof
+        let check_after = chrono::Duration::seconds(Config::default().jobs.settlement_check_seconds as i64);
+        clock.advance(check_after);
+        assert_eq!(crate::api::jobs::run_due_jobs(&app_state).await.unwrap(), 1);
+        assert_eq!(order_status(&db, &order_id).await, OrderStatus::MarkPaid);
+
+        let response = app.clone()
+            .oneshot(Request::builder().method("POST").uri("/api/v1/batch/prove").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(crate::api::jobs::run_due_jobs(&app_state).await.unwrap(), 0);
+        clock.advance(check_after);
+        assert_eq!(crate::api::jobs::run_due_jobs(&app_state).await.unwrap(), 1);
+        assert_eq!(order_status(&db, &order_id).await, OrderStatus::Settled);
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/admin/jobs?status=done").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let jobs: Value = serde_json::from_slice(&body).unwrap();
+        let mut kinds: Vec<&str> = jobs.as_array().unwrap().iter().map(|job| job["kind"].as_str().unwrap()).collect();
+        kinds.sort();
+        assert_eq!(kinds, vec!["check_settlement", "verify_payment"]);
+    }
+
+    #[tokio::test]
+    async fn test_pending_payment_is_retried_until_rejected() {
+        use vapor_core::services::clock::{Clock, MockClock};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Pending on the first check, rejected on the next
+        let checks = Arc::new(AtomicUsize::new(0));
+        let counter = checks.clone();
+        let verifier = Router::new().route("/verify", post(move || async move {
+            match counter.fetch_add(1, Ordering::SeqCst) {
+                0 => axum::Json(json!({ "status": "pending" })),
+                _ => axum::Json(json!({ "status": "rejected", "reason": "no such transaction" })),
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/verify", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, verifier).await.unwrap() });
+
+        let mut config = Config::default();
+        config.jobs.payment_verifier_url = Some(url);
+        config.jobs.retry_base_seconds = 30;
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let clock = MockClock::new(chrono::Utc::now());
+        let app_state = AppState::new_with_clock(config, db, clock.shared());
+        let (app, db) = create_test_app_with_state(app_state.clone()).await;
+
+        let (order_id, submit) = locked_paypal_order(&app, &db).await;
+        assert_eq!(app.oneshot(submit).await.unwrap().status(), StatusCode::OK);
+
+        assert_eq!(crate::api::jobs::run_due_jobs(&app_state).await.unwrap(), 1);
+        assert_eq!(order_status(&db, &order_id).await, OrderStatus::MarkPaid);
+        let job = &app_state.scheduler.list(None).await.unwrap()[0];
+        assert_eq!((job.attempts, job.run_at - clock.now()), (1, chrono::Duration::seconds(30)));
+
+        // Not retried before the backoff is up
+        clock.advance(chrono::Duration::seconds(29));
+        assert_eq!(crate::api::jobs::run_due_jobs(&app_state).await.unwrap(), 0);
+        clock.advance(chrono::Duration::seconds(1));
+        assert_eq!(crate::api::jobs::run_due_jobs(&app_state).await.unwrap(), 1);
+        assert_eq!(checks.load(Ordering::SeqCst), 2);
+        assert_eq!(order_status(&db, &order_id).await, OrderStatus::Disputed);
+
+        let job = &app_state.scheduler.list(None).await.unwrap()[0];
+        assert_eq!(job.status, crate::services::scheduler::JobStatus::Failed);
+        assert_eq!(job.last_error.as_deref(), Some("no such transaction"));
+    }
+
+    #[tokio::test]
+    async fn test_abort_batch_reverts_state_and_requeues_orders() {
+        let mut config = Config::default();
+        config.api.admin_token = Some("admin-secret".to_string());
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let app_state = AppState::new(config, db);
+        let (app, _db) = create_test_app_with_state(app_state.clone()).await;
+        let mut batch_events = app_state.event_bus.subscribe_batch_events();
+
+        let alice = "0x1111111111111111111111111111111111111111";
+        let bob = "0x2222222222222222222222222222222222222222";
+        {
+            let mut processor = app_state.batch_processor.lock().await;
+            processor.init_account(alice.to_string(), 1, "1000".to_string()).unwrap();
+            processor.start_batch().unwrap();
+            processor.add_order_to_batch(crate::models::Order {
+                id: "transfer_1".to_string(),
+                order_type: OrderType::Transfer,
+                status: OrderStatus::Pending,
+                from_address: Some(alice.to_string()),
+                to_address: Some(bob.to_string()),
+                token_id: 1,
+                amount: "300".to_string(),
+                bank_account: None,
+                bank_service: None,
+                banking_hash: None,
+                filler_id: None,
+                locked_amount: None,
+                batch_id: None,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            }).unwrap();
+        }
+
+        let abort = |token: Option<&str>| {
+            let mut request = Request::builder().method("POST").uri("/api/v1/batch/abort").header("content-type", "application/json");
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
+            }
+            let request = request.body(Body::from(json!({ "reason": "started by mistake" }).to_string())).unwrap();
+            app.clone().oneshot(request)
+        };
+
+        assert_eq!(abort(None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        let response = abort(Some("admin-secret")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let aborted: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!((aborted["batch_id"].as_u64(), aborted["order_ids"].clone()), (Some(1), json!(["transfer_1"])));
+
+        let event = serde_json::to_value(batch_events.try_recv().unwrap()).unwrap();
+        assert_eq!((event["type"].as_str(), event["reason"].as_str()), (Some("aborted"), Some("started by mistake")));
+        {
+            let processor = app_state.batch_processor.lock().await;
+            assert!(processor.get_current_batch().is_none());
+            assert_eq!(processor.accounts[alice].balances[0].balance, "1000");
+            assert!(!processor.accounts.contains_key(bob));
+        }
+
+        // Nothing left to abort
+        let response = abort(Some("admin-secret")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/batch/aborted").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let history: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(history.as_array().unwrap().len(), 1);
+        assert_eq!(history[0]["reason"], "started by mistake");
+
+        // The next batch takes the aborted batch's id and its orders
+        let response = app.clone()
+            .oneshot(Request::builder().method("POST").uri("/api/v1/batch/start").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&body).unwrap()["batch_id"], 1);
+        let processor = app_state.batch_processor.lock().await;
+        assert_eq!(processor.get_current_batch().unwrap().orders[0].id, "transfer_1");
+        assert_eq!(processor.accounts[bob].balances[0].balance, "300");
+    }
+
+    #[tokio::test]
+    async fn test_order_commits_to_quote_terms() {
+        use vapor_core::services::clock::MockClock;
+
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let clock = MockClock::new(chrono::Utc::now());
+        let app_state = AppState::new_with_clock(Config::default(), db, clock.shared());
+        let (app, _db) = create_test_app_with_state(app_state.clone()).await;
+
+        let send = |uri: &'static str, body: Value| {
+            let request = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap())
+            }
+        };
+        let terms = json!({ "order_type": "BridgeIn", "token_id": 1, "amount": "1000000", "bank_service": "PayPal Hong Kong" });
+        let order = |quote_id: &str, amount: &str| json!({
+            "order_type": "BridgeIn",
+            "from_address": "0x1111111111111111111111111111111111111111",
+            "token_id": 1,
+            "amount": amount,
+            "bank_account": "HK-123",
+            "bank_service": "PayPal Hong Kong",
+            "quote_id": quote_id,
+        });
+
+        let (status, quote) = send("/api/v1/orders/quote", terms.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(quote["rate"], "1.000000");
+        assert!(quote["quote_id"].as_str().unwrap().starts_with("quote_"));
+        assert!(quote["claim_fee"]["net_amount"].is_string());
+        let quote_id = quote["quote_id"].as_str().unwrap().to_string();
+
+        // The order keeps the quoted price even though the token has moved since
+        app_state.rates.set_usd_price(1, 1_050_000);
+        let (status, error) = send("/api/v1/orders", order(&quote_id, "2000000")).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::UNPROCESSABLE_ENTITY, Some("quote_mismatch")));
+        assert_eq!(error["details"]["field"], "amount");
+        let (status, created) = send("/api/v1/orders", order(&quote_id, "1000000")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(created["quoted_rate"], "1.000000");
+
+        let (status, error) = send("/api/v1/orders", order(&quote_id, "1000000")).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::CONFLICT, Some("quote_used")));
+        assert_eq!(error["details"]["order_id"], created["id"]);
+        let (status, error) = send("/api/v1/orders", order("quote_unknown", "1000000")).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::NOT_FOUND, Some("quote_not_found")));
+
+        let (_, quote) = send("/api/v1/orders/quote", terms).await;
+        clock.advance(chrono::Duration::seconds(app_state.config.quotes.ttl_seconds as i64));
+        let (status, error) = send("/api/v1/orders", order(quote["quote_id"].as_str().unwrap(), "1000000")).await;
+        assert_eq!((status, error["error"].as_str()), (StatusCode::GONE, Some("quote_expired")));
+    }
+
+    #[tokio::test]
+    async fn test_polled_reads_answer_conditional_requests() {
+        use crate::services::archival::BatchSnapshot;
+
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let app_state = AppState::new(Config::default(), db);
+        let (app, db) = create_test_app_with_state(app_state.clone()).await;
+
+        let order = crate::models::Order {
+            id: "cached_order".to_string(),
+            order_type: OrderType::Transfer,
+            status: OrderStatus::Pending,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: Some("0x0987654321098765432109876543210987654321".to_string()),
+            token_id: 1,
+            amount: "1000000".to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: Some(4),
+            created_at: chrono::Utc::now() - chrono::Duration::minutes(5),
+            updated_at: chrono::Utc::now() - chrono::Duration::minutes(5),
+        };
+        crate::database::helpers::insert_order(&db, &order).await.unwrap();
+
+        let get = |uri: &str, header: Option<(&str, String)>| {
+            let mut request = Request::builder().uri(uri);
+            if let Some((name, value)) = header {
+                request = request.header(name, value);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+        let status_uri = "/api/v1/orders/cached_order/status";
+
+        let response = get(status_uri, None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["cache-control"], "private, max-age=2");
+        let etag = response.headers()["etag"].to_str().unwrap().to_string();
+        let last_modified = response.headers()["last-modified"].to_str().unwrap().to_string();
+        assert!(etag.starts_with("W/\""));
+
+        let response = get(status_uri, Some(("if-none-match", etag.clone()))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()["etag"], etag.as_str());
+        assert!(axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().is_empty());
+        let response = get(status_uri, Some(("if-modified-since", last_modified))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        // The order and its status share a validator
+        let response = get("/api/v1/orders/cached_order", Some(("if-none-match", etag.clone()))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        sqlx::query("UPDATE orders SET status = ?1, updated_at = ?2 WHERE id = 'cached_order'")
+            .bind(OrderStatus::Settled as i32)
+            .bind(chrono::Utc::now())
+            .execute(&db)
+            .await
+            .unwrap();
+        let response = get(status_uri, Some(("if-none-match", etag.clone()))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers()["etag"], etag.as_str());
+
+        // Missing orders and proofs of batches not finalized carry no validators
+        let response = get("/api/v1/orders/missing_order/status", Some(("if-none-match", "*".to_string()))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let proof_uri = "/api/v1/proofs/order/4/cached_order?format=raw";
+        let response = get(proof_uri, None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get("etag").is_none());
+
+        app_state.archive.store_snapshot(&BatchSnapshot {
+            batch_id: 4,
+            state_root: format!("0x{}", "11".repeat(32)),
+            orders_root: format!("0x{}", "22".repeat(32)),
+            accounts: vec![],
+            orders: vec![order],
+            created_at: chrono::Utc::now(),
+        }).await.unwrap();
+        let response = get(proof_uri, None).await.unwrap();
+        assert_eq!(response.headers()["cache-control"], "public, max-age=3600");
+        let proof_etag = response.headers()["etag"].to_str().unwrap().to_string();
+        let response = get(proof_uri, Some(("if-none-match", proof_etag.clone()))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        // Another proof format is another representation
+        let response = get("/api/v1/proofs/order/4/cached_order?format=sorted_pairs", Some(("if-none-match", proof_etag))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_system_ledger_mirrors_user_balances() {
+        use crate::services::system_accounts::SystemAccount;
+
+        let (app, _db) = create_test_app().await;
+        let alice = "0x1111111111111111111111111111111111111111";
+        let escrow = SystemAccount::Escrow.address();
+        let post_json = |uri: &str, body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        let response = app.clone()
+            .oneshot(post_json("/api/v1/accounts", json!({ "address": alice, "token_id": 1, "initial_balance": "1000" })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone()
+            .oneshot(post_json("/api/v1/accounts", json!({ "address": escrow, "token_id": 1, "initial_balance": "1000" })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json_body(response).await["error"], "reserved_address");
+
+        // Orders cannot spend from a system account
+        let response = app.clone()
+            .oneshot(post_json("/api/v1/orders", json!({
+                "order_type": "Transfer",
+                "from_address": escrow,
+                "to_address": alice,
+                "token_id": 1,
+                "amount": "10",
+            })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/accounts/system").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let ledger = json_body(response).await;
+        assert_eq!(ledger["accounts"].as_array().unwrap().len(), 3);
+        assert_eq!(ledger["accounts"][2], json!({ "account": "escrow", "address": escrow }));
+        assert_eq!(ledger["tokens"], json!([{
+            "token_id": 1, "bridge": "1000", "users": "1000", "fee_pool": "0", "escrow": "0", "net": "0"
+        }]));
+
+        // The bridge account is readable by address, but not listed with user accounts
+        let bridge = SystemAccount::Bridge.address();
+        let response = app.clone()
+            .oneshot(Request::builder().uri(format!("/api/v1/accounts/{}", bridge)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await["balances"], json!([{ "token_id": 1, "balance": "1000" }]));
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/accounts").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await["total"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_filler_client_round_trip() {
+        use vapor_client::{types::{ClaimsQuery, DiscoveryQuery}, ClientError, RetryPolicy, VaporClient};
+
+        let mut config = Config::default();
+        config.filler.ws_tokens = std::collections::HashMap::from([("secret".to_string(), "filler_1".to_string())]);
+        let (app, _db) = create_test_app_with_config(config).await;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let client = VaporClient::new(format!("{}/", base_url))
+            .with_token("secret")
+            .with_retry(RetryPolicy::none());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let order = client.create_order(&CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        }).await.unwrap();
+        assert_eq!(order.status, OrderStatus::Pending);
+        let response = reqwest::Client::new()
+            .post(format!("{}/api/v1/orders/{}/mark-discovery", base_url, order.id))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        let page = client.discovery_orders(&DiscoveryQuery { filler_id: Some("filler_1".to_string()), ..Default::default() }).await.unwrap();
+        assert_eq!(page.orders.iter().map(|order| order.id.as_str()).collect::<Vec<_>>(), vec![order.id.as_str()]);
+
+        let locked = client.lock_order(&order.id, &LockOrderRequest {
+            filler_id: "filler_1".to_string(),
+            amount: "1000000".to_string(),
+            accepted_rate: None,
+            fee_bps: None,
+        }).await.unwrap();
+        assert_eq!(locked.filler_id.as_deref(), Some("filler_1"));
+
+        let proof = json!({
+            "transaction_id": "8MC585209K746392H",
+            "payer_email_hash": "0x1c8aff950685c2ed4bc3174f3472287b56d9517b9c948127319a09a7a36deac8",
+        });
+        let paid = client.submit_payment_proof(&order.id, &SubmitPaymentProofRequest { banking_hash: None, payment_proof: Some(proof) }).await.unwrap();
+        assert_eq!(paid.status, OrderStatus::MarkPaid);
+        assert!(paid.payment_proof.is_some());
+
+        let status = client.get_order_status(&order.id).await.unwrap();
+        assert_eq!(status.status, OrderStatus::MarkPaid);
+        assert_eq!(status.filler_info.unwrap().id, "filler_1");
+        let history = client.get_order_history(&order.id).await.unwrap();
+        assert_eq!(history[0].to_status, OrderStatus::Discovery);
+        let claims = client.list_claims(&ClaimsQuery { filler_id: Some("filler_1".to_string()), ..Default::default() }).await.unwrap();
+        assert!(claims.claims.is_empty());
+
+        // Error bodies keep the backend's code; bare statuses get one from the status
+        let error = client.lock_order(&order.id, &LockOrderRequest {
+            filler_id: "filler_2".to_string(),
+            amount: "1000000".to_string(),
+            accepted_rate: None,
+            fee_bps: None,
+        }).await.unwrap_err();
+        assert!(matches!(&error, ClientError::Api { status, .. } if status.is_client_error()), "{}", error);
+        let error = client.get_order("missing").await.unwrap_err();
+        assert_eq!(error.code(), Some("not_found"));
+    }
+
+    #[tokio::test]
+    async fn test_event_log_can_be_tailed() {
+        let (app, _db) = create_test_app().await;
+
+        let create_request = CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: Some("0x9876543210987654321098765432109876543210".to_string()),
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        };
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&create_request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+
+        let response = app.clone()
+            .oneshot(Request::builder().method("POST").uri(format!("/api/v1/orders/{}/mark-discovery", order.id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let lock_request = LockOrderRequest { filler_id: "filler_1".to_string(), amount: "1000".to_string(), accepted_rate: None, fee_bps: None };
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/fillers/orders/{}/lock", order.id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&lock_request).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/events").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let page: Value = serde_json::from_slice(&body).unwrap();
+        let events = page["events"].as_array().unwrap();
+        let kinds: Vec<&str> = events.iter().map(|event| event["kind"].as_str().unwrap()).collect();
+        assert_eq!(kinds, ["order_created", "order_status_changed", "order_locked"]);
+        assert!(events.iter().all(|event| event["subject"] == order.id.as_str()));
+        assert_eq!(events[1]["payload"]["to_status"], "Discovery");
+        assert_eq!(events[2]["payload"]["filler_id"], "filler_1");
+        for event in events {
+            let payload_hash = crate::services::event_log::payload_hash(&event["payload"].to_string());
+            assert_eq!(event["payload_hash"], payload_hash.as_str());
+        }
+
+        // Tailing from the last event returns nothing new, and the same position
+        let next_seq = page["next_seq"].as_u64().unwrap();
+        assert_eq!(next_seq, events[2]["seq"].as_u64().unwrap());
+        let response = app.clone()
+            .oneshot(Request::builder().uri(format!("/api/v1/events?after_seq={}", events[0]["seq"])).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let page: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(page["events"].as_array().unwrap().len(), 2);
+
+        let response = app
+            .oneshot(Request::builder().uri(format!("/api/v1/events?after_seq={}", next_seq)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let page: Value = serde_json::from_slice(&body).unwrap();
+        assert!(page["events"].as_array().unwrap().is_empty());
+        assert_eq!(page["next_seq"], next_seq);
+    }
+
+    #[tokio::test]
+    async fn test_saturated_corridor_throttles_bridge_in_intake() {
+        let mut config = Config::default();
+        config.market.throttle_saturated_corridors = true;
+        let (app, _db) = create_test_app_with_config(config).await;
+
+        let create = json!({
+            "order_type": "BridgeIn",
+            "from_address": "0x1234567890123456789012345678901234567890",
+            "token_id": 1,
+            "amount": "1000",
+            "bank_account": "84127312",
+            "bank_service": "PayPal Hong Kong"
+        });
+        let create_request = || {
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/orders")
+                .header("content-type", "application/json")
+                .body(Body::from(create.to_string()))
+                .unwrap()
+        };
+
+        // Nothing is known about the corridor yet
+        let response = app.clone().oneshot(create_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+        let response = app.clone()
+            .oneshot(Request::builder().method("POST").uri(format!("/api/v1/orders/{}/mark-discovery", order.id)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // No filler serves it, so the open order saturates it
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/market/summary").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let summary: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(summary["corridors"][0]["fillers"], 0);
+        assert_eq!(summary["corridors"][0]["saturated"], true);
+        assert_eq!(summary["totals"]["saturated_corridors"], 1);
+
+        let response = app.clone().oneshot(create_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "corridor_saturated");
+        assert_eq!(error["details"]["open_volume"], "1000");
+        assert_eq!(error["details"]["retry_after_seconds"], 1800);
+    }
+
+    #[tokio::test]
+    async fn test_handover_moves_writes_to_the_new_instance() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let instance = |instance_id: &str| {
+            let mut config = Config::default();
+            config.handover.instance_id = instance_id.to_string();
+            AppState::new(config, db.clone())
+        };
+        let (blue_state, mut green_state) = (instance("blue"), instance("green"));
+        // Deployments share the blob store holding the snapshots, like they share the database
+        green_state.blobs = blue_state.blobs.clone();
+        green_state.archive = blue_state.archive.clone();
+        let blue_processor = blue_state.batch_processor.clone();
+        assert!(blue_state.writer_lease.refresh(blue_state.clock.now()).await.unwrap().accepts_writes());
+        assert!(!green_state.writer_lease.refresh(green_state.clock.now()).await.unwrap().accepts_writes());
+        let (blue, _) = create_test_app_with_state(blue_state).await;
+        let (green, _) = create_test_app_with_state(green_state).await;
+
+        let post = |uri: &str, body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let create = json!({
+            "order_type": "BridgeIn",
+            "from_address": "0x1234567890123456789012345678901234567890",
+            "token_id": 1,
+            "amount": "1000",
+            "bank_account": "84127312",
+            "bank_service": "PayPal Hong Kong"
+        });
+
+        // Only the lease holder writes
+        let response = blue.clone().oneshot(post("/api/v1/orders", create.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: OrderResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(green.clone().oneshot(post("/api/v1/orders", create.clone())).await.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(blue.clone().oneshot(post("/api/v1/batch/start", json!({}))).await.unwrap().status(), StatusCode::OK);
+        let mut order = crate::database::helpers::get_order_by_id(&db, &order.id).await.unwrap().unwrap();
+        order.to_address = Some("0x9876543210987654321098765432109876543210".to_string());
+        blue_processor.lock().await.add_order_to_batch(order).unwrap();
+
+        // The open batch is finalized into the manifest
+        let response = blue.clone().oneshot(post("/api/v1/admin/handover/export", json!({}))).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+        let manifest: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(manifest["from_instance"], "blue");
+        assert_eq!(manifest["batch_id"], 1);
+        assert_eq!(manifest["open_batch_id"], Value::Null);
+        assert_eq!(manifest["open_orders"].as_array().unwrap().len(), 1);
+        assert_ne!(manifest["orders_root"], "0x");
+        assert_eq!(blue.clone().oneshot(post("/api/v1/orders", create.clone())).await.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let response = green.clone()
+            .oneshot(Request::builder().uri("/api/v1/admin/handover").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let status: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!((status["instance_id"].as_str(), status["role"].as_str(), status["holder"].as_str()), (Some("green"), Some("standby"), Some("blue")));
+        assert_eq!(status["pending"]["digest"], manifest["digest"]);
+
+        // A tampered manifest is refused
+        let mut tampered = manifest.clone();
+        tampered["batch_id"] = json!(0);
+        let response = green.clone().oneshot(post("/api/v1/admin/handover/import", tampered)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = green.clone().oneshot(post("/api/v1/admin/handover/import", manifest.clone())).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+        let report: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["batch_id"], 1);
+
+        // Green continues the batch chain where blue stopped
+        assert_eq!(green.clone().oneshot(post("/api/v1/orders", create.clone())).await.unwrap().status(), StatusCode::OK);
+        let response = green.clone().oneshot(post("/api/v1/batch/start", json!({}))).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&body).unwrap()["batch_id"], 2, "{}", String::from_utf8_lossy(&body));
+
+        let response = green.clone().oneshot(post("/api/v1/admin/handover/import", manifest)).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&body).unwrap()["error"], "not_standby");
+    }
+
+    #[tokio::test]
+    async fn test_finalize_refuses_a_batch_out_of_sequence() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let app_state = AppState::new(Config::default(), db.clone());
+        let processor = app_state.batch_processor.clone();
+        let (app, _) = create_test_app_with_state(app_state).await;
+        let post = |uri: &'static str| Request::builder().method("POST").uri(uri).body(Body::empty()).unwrap();
+
+        assert_eq!(app.clone().oneshot(post("/api/v1/batch/start")).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(app.clone().oneshot(post("/api/v1/batch/finalize")).await.unwrap().status(), StatusCode::OK);
+        let recorded = crate::services::batch_sequence::get(&db, 1).await.unwrap().unwrap();
+        assert_eq!(recorded.prev_batch_id, 0);
+
+        // A processor that lost its place numbers batches from 1 again
+        *processor.lock().await = BatchProcessor::new();
+        assert_eq!(app.clone().oneshot(post("/api/v1/batch/start")).await.unwrap().status(), StatusCode::OK);
+        let response = app.clone().oneshot(post("/api/v1/batch/finalize")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "batch_sequence_gap");
+        assert_eq!((error["details"]["expected"].as_u64(), error["details"]["found"].as_u64()), (Some(2), Some(1)));
+        // Nothing was finalized
+        assert!(processor.lock().await.current_batch.is_some());
+        assert_eq!(crate::services::batch_sequence::tip(&db).await.unwrap().batch_id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_screened_addresses_are_blocked_or_flagged_for_review() {
+        use crate::config::{ScreeningAction, ScreeningProviderConfig};
+
+        let denied = "0x9876543210987654321098765432109876543210";
+        let mut config = Config::default();
+        config.screening.provider = ScreeningProviderConfig::Static { addresses: vec![denied.to_string()] };
+        let post = |uri: &str, body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let create = json!({
+            "order_type": "BridgeIn",
+            "from_address": "0x1234567890123456789012345678901234567890",
+            "to_address": denied,
+            "token_id": 1,
+            "amount": "1000",
+            "bank_account": "84127312",
+            "bank_service": "PayPal Hong Kong"
+        });
+
+        // Blocking refuses the order outright
+        let (app, _) = create_test_app_with_config(config.clone()).await;
+        let response = app.clone().oneshot(post("/api/v1/orders", create.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"], "address_screened");
+        assert_eq!(error["details"]["context"], "order");
+
+        // Flagging lets it through and queues the hit for review
+        config.screening.action = ScreeningAction::Flag;
+        let (app, _) = create_test_app_with_config(config).await;
+        let response = app.clone().oneshot(post("/api/v1/orders", create)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let order: Value = serde_json::from_slice(&body).unwrap();
+
+        let reviews = |query: &str| Request::builder().uri(format!("/api/v1/admin/screening/reviews{}", query)).body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(reviews("")).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let queue: Vec<Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!((queue[0]["subject_id"].as_str(), queue[0]["outcome"].as_str()), (order["id"].as_str(), Some("flagged")));
+        let id = queue[0]["id"].as_str().unwrap().to_string();
+
+        let uri = format!("/api/v1/admin/screening/reviews/{}", id);
+        let response = app.clone().oneshot(post(&uri, json!({ "decision": "cleared", "note": "name match only" }))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(post(&uri, json!({ "decision": "confirmed" }))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let body = axum::body::to_bytes(app.clone().oneshot(reviews("")).await.unwrap().into_body(), usize::MAX).await.unwrap();
+        assert!(serde_json::from_slice::<Vec<Value>>(&body).unwrap().is_empty());
+        let body = axum::body::to_bytes(app.clone().oneshot(reviews("?include_reviewed=true")).await.unwrap().into_body(), usize::MAX).await.unwrap();
+        let reviewed: Vec<Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!((reviewed[0]["decision"].as_str(), reviewed[0]["review_note"].as_str()), (Some("cleared"), Some("name match only")));
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_the_writer_lease() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let instance = |instance_id: &str| {
+            let mut config = Config::default();
+            config.handover.instance_id = instance_id.to_string();
+            AppState::new(config, db.clone())
+        };
+        let (blue_state, green_state) = (instance("blue"), instance("green"));
+        let now = blue_state.clock.now();
+        blue_state.writer_lease.refresh(now).await.unwrap();
+        green_state.writer_lease.refresh(now).await.unwrap();
+        let blue_lease = blue_state.writer_lease.clone();
+        let (blue, _) = create_test_app_with_state(blue_state).await;
+        let (green, _) = create_test_app_with_state(green_state).await;
+
+        let health = |app: Router| async move {
+            let response = app.oneshot(Request::builder().uri("/health").body(Body::empty()).unwrap()).await.unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+        let status = health(blue.clone()).await;
+        assert_eq!(status["status"], "healthy");
+        assert_eq!((status["writer"]["role"].as_str(), status["writer"]["leader"].as_str()), (Some("writer"), Some("blue")));
+        assert!(status["writer"]["heartbeat_at"].is_string());
+        let status = health(green.clone()).await;
+        assert_eq!((status["writer"]["instance_id"].as_str(), status["writer"]["role"].as_str()), (Some("green"), Some("standby")));
+
+        // A writer that could not renew in time stops writing and reports it
+        assert!(blue_lease.check_lapsed(now + chrono::Duration::seconds(30)));
+        let status = health(blue).await;
+        assert_eq!(status["status"], "degraded");
+        assert_eq!(status["writer"]["role"], "lapsed");
+    }
+
+    #[tokio::test]
+    async fn test_balance_attestation_verifies_against_the_proven_root() {
+        use vapor_core::proof_format::{parse_hash32, process_raw_proof};
+        use crate::models::{AccountState, TokenBalance};
+        use chrono::Utc;
+        use sha3::{Digest, Keccak256};
+
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        // Anvil's first default account
+        let signer = vapor_chain::signer::LocalKeySigner::from_hex("0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80").unwrap();
+        let operator = vapor_chain::signer::Signer::address(&signer);
+        let (unsigned, _) = create_test_app_with_state(AppState::new(Config::default(), db.clone())).await;
+        let (app, _) = create_test_app_with_state(AppState::new(Config::default(), db).with_attestation_signer(Arc::new(signer))).await;
+        let alice = "0x1234567890123456789012345678901234567890";
+        let get = |uri: String| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        let init = json!({ "address": alice, "token_id": 1, "initial_balance": "1000" });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/accounts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(init.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Nothing is attested before a batch is proven
+        let response = app.clone().oneshot(get(format!("/api/v1/proofs/account/{}/attestation?token_id=1", alice))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        for uri in ["/api/v1/batch/start", "/api/v1/batch/prove"] {
+            let response = app.clone()
+                .oneshot(Request::builder().method("POST").uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app.clone().oneshot(get(format!("/api/v1/proofs/account/{}/attestation?token_id=1", alice))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_body(response).await;
+        let attestation: crate::services::attestations::BalanceAttestation = serde_json::from_value(body["attestation"].clone()).unwrap();
+        assert_eq!(attestation.batch_id, 1);
+        assert_eq!(attestation.balance, "1000");
+
+        // The documented verification path: signature, then leaf, then proof up to the signed root
+        assert_eq!(attestation.recover_signer().unwrap(), operator);
+        assert!(!attestation.is_expired(Utc::now()));
+        let balances: Vec<TokenBalance> = serde_json::from_value(body["balances"].clone()).unwrap();
+        let leaf: [u8; 32] = Keccak256::digest(AccountState { address: attestation.address.clone(), balances, updated_at: Utc::now() }.leaf_preimage()).into();
+        assert_eq!(leaf, parse_hash32(body["proof"]["leaf_hash"].as_str().unwrap()).unwrap());
+        let siblings: Vec<[u8; 32]> = body["proof"]["proof"].as_array().unwrap().iter()
+            .map(|sibling| parse_hash32(sibling.as_str().unwrap()).unwrap())
+            .collect();
+        let path_bits: Vec<u8> = serde_json::from_value(body["proof"]["path_bits"].clone()).unwrap();
+        assert_eq!(process_raw_proof(leaf, &siblings, &path_bits).unwrap(), parse_hash32(&attestation.state_root).unwrap());
+
+        // Tokens the account never held are attested as zero
+        let body = json_body(app.clone().oneshot(get(format!("/api/v1/proofs/account/{}/attestation?token_id=7", alice))).await.unwrap()).await;
+        assert_eq!(body["attestation"]["balance"], "0");
+
+        for uri in [
+            "/api/v1/proofs/account/0x9999999999999999999999999999999999999999/attestation?token_id=1".to_string(),
+            format!("/api/v1/proofs/account/{}/attestation?token_id=1&batch_id=9", alice),
+        ] {
+            let response = app.clone().oneshot(get(uri)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+
+        // An attestation handed back to the operator is accepted only as signed
+        let verify = |attestation: &crate::services::attestations::BalanceAttestation| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/proofs/attestations/verify")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(attestation).unwrap()))
+                .unwrap()
+        };
+        let response = app.clone().oneshot(verify(&attestation)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["signer"], format!("{:?}", operator));
+        let inflated = crate::services::attestations::BalanceAttestation { balance: "5000".to_string(), ..attestation.clone() };
+        let response = app.clone().oneshot(verify(&inflated)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json_body(response).await["error"], "invalid_attestation");
+
+        let response = unsigned.clone().oneshot(verify(&attestation)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let response = unsigned.oneshot(get(format!("/api/v1/proofs/account/{}/attestation?token_id=1", alice))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_high_value_orders_wait_for_review_before_discovery() {
+        let mut config = Config::default();
+        config.order_review.thresholds.insert(1, 5000);
+        let (app, db) = create_test_app_with_config(config).await;
+        let post = |uri: &str, body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+        let create = |amount: &str| json!({
+            "order_type": "BridgeIn",
+            "from_address": "0x1234567890123456789012345678901234567890",
+            "to_address": "0x1234567890123456789012345678901234567890",
+            "token_id": 1,
+            "amount": amount,
+            "bank_account": "84127312",
+            "bank_service": "PayPal Hong Kong"
+        });
+
+        let small = json_body(app.clone().oneshot(post("/api/v1/orders", create("5000"))).await.unwrap()).await;
+        assert_eq!(small["status"], "Pending");
+        let mut held = Vec::new();
+        for _ in 0..2 {
+            let order = json_body(app.clone().oneshot(post("/api/v1/orders", create("7500"))).await.unwrap()).await;
+            assert_eq!(order["status"], "ReviewPending");
+            held.push(order["id"].as_str().unwrap().to_string());
+        }
+
+        // Held orders are not matched, nor promoted by auto-discovery
+        crate::services::discovery::promote_pending_orders(&db, &crate::services::event_bus::EventBus::default()).await.unwrap();
+        assert_eq!(order_status(&db, &held[0]).await, OrderStatus::ReviewPending);
+
+        let queue = json_body(app.clone().oneshot(Request::builder().uri("/api/v1/admin/order-reviews").body(Body::empty()).unwrap()).await.unwrap()).await;
+        assert_eq!(queue.as_array().unwrap().len(), 2);
+        assert_eq!((queue[0]["amount"].as_str(), queue[0]["threshold"].as_u64()), (Some("7500"), Some(5000)));
+
+        let uri = |order_id: &str| format!("/api/v1/admin/order-reviews/{}", order_id);
+        let response = app.clone().oneshot(post(&uri(&held[0]), json!({ "decision": "approved", "reason": "known customer" }))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(order_status(&db, &held[0]).await, OrderStatus::Discovery);
+        let response = app.clone().oneshot(post(&uri(&held[1]), json!({ "decision": "rejected" }))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app.clone().oneshot(post(&uri(&held[1]), json!({ "decision": "rejected", "reason": "over daily exposure" }))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(order_status(&db, &held[1]).await, OrderStatus::Failed);
+        let response = app.clone().oneshot(post(&uri(&held[1]), json!({ "decision": "approved" }))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        // Every step is in the status history
+        let history = crate::database::helpers::get_order_history(&db, &held[0]).await.unwrap();
+        let steps: Vec<(OrderStatus, Option<&str>)> = history.iter().map(|t| (t.to_status, t.reason.as_deref())).collect();
+        assert_eq!(steps, vec![
+            (OrderStatus::ReviewPending, Some("review_required")),
+            (OrderStatus::Pending, Some("review_approved: known customer")),
+            (OrderStatus::Discovery, Some("review_approved")),
+        ]);
+        let history = crate::database::helpers::get_order_history(&db, &held[1]).await.unwrap();
+        assert_eq!(history.last().unwrap().reason.as_deref(), Some("review_rejected: over daily exposure"));
+
+        let queue = json_body(app.oneshot(Request::builder().uri("/api/v1/admin/order-reviews").body(Body::empty()).unwrap()).await.unwrap()).await;
+        assert!(queue.as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_query_diagnostics_list_timed_operations() {
+        let (app, _db) = create_test_app().await;
+        let create = json!({
+            "order_type": "BridgeIn",
+            "from_address": "0x1234567890123456789012345678901234567890",
+            "to_address": "0x1234567890123456789012345678901234567890",
+            "token_id": 1,
+            "amount": "1000",
+            "bank_account": "84127312",
+            "bank_service": "PayPal Hong Kong"
+        });
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/orders")
+                    .header("content-type", "application/json")
+                    .body(Body::from(create.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let order: Value = serde_json::from_slice(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert!(order["id"].is_string());
+        let response = app.clone()
+            .oneshot(Request::builder().uri("/api/v1/accounts/0x1234567890123456789012345678901234567890").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // The registry is process-wide, so other tests' operations may be listed too
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/admin/diagnostics/queries?top=100").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let diagnostics: Value = serde_json::from_slice(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(diagnostics["slow_query_ms"], 250);
+        let operations = diagnostics["operations"].as_array().unwrap();
+        let lookup = operations.iter().find(|stats| stats["operation"] == "get_account_balances").unwrap();
+        assert!(lookup["count"].as_u64().unwrap() >= 1);
+        assert_eq!(lookup["buckets"].as_array().unwrap().len(), crate::services::query_metrics::BUCKET_BOUNDS_MS.len() + 1);
+        assert!(operations.iter().any(|stats| stats["operation"] == "append_event"));
+    }
+
+    #[tokio::test]
+    async fn test_state_diff_carries_only_changed_balances_since_a_batch() {
+        use crate::merkle::MerkleTreeManager;
+        use crate::models::{AccountState, TokenBalance};
+        use crate::services::archival::BatchSnapshot;
+        use chrono::Utc;
+
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let state = AppState::new(Config::default(), db);
+        let archive = state.archive.clone();
+        let (app, _) = create_test_app_with_state(state).await;
+        let alice = "0x1234567890123456789012345678901234567890";
+        let bob = "0x00000000000000000000000000000000000000bb";
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        // Nothing to sync to before the first batch
+        let response = app.clone().oneshot(get("/api/v1/sync/diff?from_batch=0")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let account = |address: &str, token_id: u32, balance: &str| AccountState {
+            address: address.to_string(),
+            balances: vec![TokenBalance { token_id, balance: balance.to_string() }],
+            updated_at: Utc::now(),
+        };
+        let batches = [vec![account(alice, 1, "1000")], vec![account(alice, 1, "1000"), account(bob, 2, "25")]];
+        for (batch_id, accounts) in (1..).zip(batches) {
+            let state_root = MerkleTreeManager::new().build_state_tree(&accounts).unwrap();
+            let snapshot = BatchSnapshot { batch_id, state_root, orders_root: String::new(), accounts, orders: Vec::new(), created_at: Utc::now() };
+            archive.store_snapshot(&snapshot).await.unwrap();
+        }
+
+        let response = app.clone().oneshot(get("/api/v1/sync/diff?from_batch=1&proofs=true")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_body(response).await;
+        assert_eq!((body["from_batch"].as_u64(), body["to_batch"].as_u64()), (Some(1), Some(2)));
+        assert_eq!(body["deltas"], json!([[bob, 2, "0", "25"]]));
+        let proofs = body["proofs"].as_array().unwrap();
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(proofs[0]["address"], bob);
+
+        // From an empty mirror every balance arrives, ordered by address
+        let response = app.clone().oneshot(get("/api/v1/sync/diff?from_batch=0")).await.unwrap();
+        let body = json_body(response).await;
+        assert_eq!(body["deltas"], json!([[bob, 2, "0", "25"], [alice, 1, "0", "1000"]]));
+        assert!(body.get("proofs").is_none());
+
+        let response = app.clone().oneshot(get("/api/v1/sync/diff?from_batch=3")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_lock_window_collects_bids_and_locks_for_the_best_fee() {
+        use crate::config::LockWindowPolicy;
+        use vapor_core::services::clock::MockClock;
+        use crate::services::lock_window::LockBidStatus;
+
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let mut config = Config::default();
+        config.filler.lock_window_seconds = 30;
+        config.filler.lock_window_policy = LockWindowPolicy::BestFee;
+        let clock = MockClock::new(chrono::Utc::now());
+        let app_state = AppState::new_with_clock(config, db, clock.shared());
+        let (app, db) = create_test_app_with_state(app_state.clone()).await;
+
+        let mut order = crate::models::Order::new(CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: None,
+            to_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            token_id: 1,
+            amount: "100".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        });
+        order.status = OrderStatus::Discovery;
+        crate::database::helpers::insert_order(&db, &order).await.unwrap();
+        let lock = |filler_id: &str, fee_bps: u32| {
+            let request = LockOrderRequest { filler_id: filler_id.to_string(), amount: "100".to_string(), accepted_rate: None, fee_bps: Some(fee_bps) };
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/fillers/orders/{}/lock", order.id))
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&request).unwrap()))
+                .unwrap()
+        };
+
+        // The fastest filler only opens the window
+        let response = app.clone().oneshot(lock("fast_bot", 50)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let bid: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(bid["status"], "pending");
+        clock.advance(chrono::Duration::seconds(10));
+        assert_eq!(app.clone().oneshot(lock("patient", 20)).await.unwrap().status(), StatusCode::ACCEPTED);
+        assert_eq!(order_status(&db, &order.id).await, OrderStatus::Discovery);
+        assert_eq!(crate::api::jobs::run_due_jobs(&app_state).await.unwrap(), 0);
+
+        clock.advance(chrono::Duration::seconds(20));
+        assert_eq!(crate::api::jobs::run_due_jobs(&app_state).await.unwrap(), 1);
+        let locked = crate::database::helpers::get_order_by_id(&db, &order.id).await.unwrap().unwrap();
+        assert_eq!((locked.status, locked.filler_id.as_deref()), (OrderStatus::Locked, Some("patient")));
+
+        let outcomes: Vec<(String, LockBidStatus)> = app_state.lock_window.bids(&order.id).await.unwrap()
+            .into_iter()
+            .map(|bid| (bid.filler_id, bid.status))
+            .collect();
+        assert_eq!(outcomes, vec![("fast_bot".to_string(), LockBidStatus::Lost), ("patient".to_string(), LockBidStatus::Won)]);
+
+        // Once locked, late bids are refused as before
+        assert_eq!(app.clone().oneshot(lock("late", 1)).await.unwrap().status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_verify_reuses_results_and_binds_proofs_to_a_batch() {
+        use vapor_core::proof_format::ProofFormat;
+        use crate::merkle::MerkleTreeManager;
+        use crate::models::{AccountState, TokenBalance};
+        use crate::services::archival::BatchSnapshot;
+        use crate::services::proof_cache::build_account_proof;
+        use chrono::Utc;
+
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let state = AppState::new(Config::default(), db);
+        let archive = state.archive.clone();
+        let (app, _) = create_test_app_with_state(state).await;
+        let alice = "0x1234567890123456789012345678901234567890";
+        let account = |balance: &str| AccountState {
+            address: alice.to_string(),
+            balances: vec![TokenBalance { token_id: 1, balance: balance.to_string() }],
+            updated_at: Utc::now(),
+        };
+
+        let mut proven = None;
+        for (batch_id, balance) in [(1, "1000"), (2, "400")] {
+            let accounts = vec![account(balance)];
+            let mut manager = MerkleTreeManager::new();
+            let state_root = manager.build_state_tree(&accounts).unwrap();
+            if batch_id == 1 {
+                proven = Some(build_account_proof(&mut manager, alice, ProofFormat::Raw).unwrap());
+            }
+            let snapshot = BatchSnapshot { batch_id, state_root, orders_root: format!("0x{}", "00".repeat(32)), accounts, orders: Vec::new(), created_at: Utc::now() };
+            archive.store_snapshot(&snapshot).await.unwrap();
+        }
+        let proof = proven.unwrap();
+
+        let verify = |batch_id: u64| {
+            let request = json!({
+                "leaf_hash": proof.leaf_hash,
+                "proof": proof.proof,
+                "root": proof.root,
+                "format": "raw",
+                "path_bits": proof.path_bits,
+                "batch_id": batch_id,
+            });
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/proofs/verify")
+                .header("content-type", "application/json")
+                .body(Body::from(request.to_string()))
+                .unwrap()
+        };
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        let first = json_body(app.clone().oneshot(verify(1)).await.unwrap()).await;
+        assert_eq!((first["valid"].as_bool(), first["cached"].as_bool()), (Some(true), Some(false)));
+        let again = json_body(app.clone().oneshot(verify(1)).await.unwrap()).await;
+        assert_eq!((again["valid"].as_bool(), again["cached"].as_bool()), (Some(true), Some(true)));
+
+        // The proof folds to batch 1's root, which batch 2 does not commit to
+        let other = json_body(app.clone().oneshot(verify(2)).await.unwrap()).await;
+        assert_eq!((other["valid"].as_bool(), other["cached"].as_bool()), (Some(false), Some(false)));
+
+        let response = app.clone().oneshot(verify(9)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(json_body(response).await["error"], "batch_not_found");
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/proofs/verify/cache").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let metrics = json_body(response).await;
+        assert_eq!((metrics["hits"].as_u64(), metrics["misses"].as_u64(), metrics["entries"].as_u64()), (Some(1), Some(2), Some(2)));
+        assert_eq!(metrics["memoized_batches"], 2);
+        assert_eq!((metrics["batch_input_hits"].as_u64(), metrics["batch_input_misses"].as_u64()), (Some(1), Some(3)));
+    }
+
+    #[tokio::test]
+    async fn test_notification_preferences_and_delivery_tracking() {
+        use crate::database::helpers;
+        use crate::services::event_log::{self, DomainEvent};
+        use crate::services::scheduler::JobKind;
+
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let app_state = AppState::new(Config::default(), db);
+        let (app, db) = create_test_app_with_state(app_state.clone()).await;
+        let seller = "0x1234567890abcdef1234567890abcdef12345678";
+        let put = |body: Value| {
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/api/v1/notifications/{}/preferences", seller))
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        let response = app.clone()
+            .oneshot(put(json!({ "preferences": [{ "channel": "webhook", "destination": "ftp://nope", "events": ["order_locked"] }] })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json_body(response).await["error"], "invalid_notification_preference");
+
+        let response = app.clone()
+            .oneshot(put(json!({ "preferences": [
+                { "channel": "console", "events": ["order_locked", "order_settled"] },
+                { "channel": "email", "destination": "seller@example.com", "events": [] },
+            ] })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        // A channel without events is not kept
+        assert_eq!(json_body(response).await["preferences"], json!([{ "channel": "console", "destination": "", "events": ["order_locked", "order_settled"] }]));
+
+        let order = crate::models::Order {
+            id: "notify_order".to_string(),
+            order_type: OrderType::BridgeOut,
+            from_address: Some(seller.to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "500".to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: Some("filler_1".to_string()),
+            locked_amount: None,
+            status: OrderStatus::Locked,
+            batch_id: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        helpers::insert_order(&db, &order).await.unwrap();
+        event_log::append(&db, &DomainEvent::OrderLocked {
+            order_id: order.id.clone(),
+            filler_id: "filler_1".to_string(),
+            locked_amount: "500".to_string(),
+        }).await.unwrap();
+        for logged in event_log::read(&db, 0, 10).await.unwrap() {
+            for notification in app_state.notifications.enqueue(&logged).await.unwrap() {
+                app_state.scheduler.schedule(JobKind::DeliverNotification, &notification.id, None, chrono::Duration::zero()).await.unwrap();
+            }
+        }
+        assert_eq!(crate::api::jobs::run_due_jobs(&app_state).await.unwrap(), 1);
+
+        let response = app.clone()
+            .oneshot(Request::builder().uri(format!("/api/v1/notifications/{}", seller.to_uppercase().replace("0X", "0x"))).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let history = json_body(response).await;
+        assert_eq!(history.as_array().unwrap().len(), 1);
+        assert_eq!((history[0]["event"].as_str(), history[0]["status"].as_str(), history[0]["attempts"].as_u64()), (Some("order_locked"), Some("delivered"), Some(1)));
+        assert_eq!(history[0]["subject"], "Order notify_order was picked up");
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/notifications/not-an-address").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_batches_resolve_by_state_or_orders_root() {
+        use crate::services::archival::BatchSnapshot;
+
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let app_state = AppState::new(Config::default(), db);
+        let (app, _db) = create_test_app_with_state(app_state.clone()).await;
+        let root = |byte: &str| format!("0x{}", byte.repeat(32));
+        for (batch_id, state_root, orders_root) in [(1, "aa", "b1"), (2, "aa", "b2"), (3, "ab", "b3")] {
+            app_state.archive.store_snapshot(&BatchSnapshot {
+                batch_id,
+                state_root: root(state_root),
+                orders_root: root(orders_root),
+                accounts: vec![],
+                orders: vec![],
+                created_at: chrono::Utc::now(),
+            }).await.unwrap();
+        }
+        let get = |uri: String| app.clone().oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap());
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        // An unchanged state root belongs to both batches
+        let response = get(format!("/api/v1/batches/by-root/{}", root("aa").to_uppercase().replace("0X", ""))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let lookup = json_body(response).await;
+        assert_eq!(lookup["root"], root("aa"));
+        assert_eq!(lookup["ambiguous"], true);
+        let matches = lookup["matches"].as_array().unwrap();
+        assert_eq!(matches.iter().map(|m| m["batch_id"].as_u64().unwrap()).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!((matches[0]["root_kind"].as_str(), matches[0]["order_count"].as_u64()), (Some("state"), Some(0)));
+
+        let response = get(format!("/api/v1/batches/by-root/{}", root("b3"))).await.unwrap();
+        let lookup = json_body(response).await;
+        assert_eq!(lookup["ambiguous"], false);
+        assert_eq!((lookup["matches"][0]["batch_id"].as_u64(), lookup["matches"][0]["root_kind"].as_str()), (Some(3), Some("orders")));
+
+        // Unknown roots list the closest known ones
+        let response = get(format!("/api/v1/batches/by-root/0xab{}", "00".repeat(31))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = json_body(response).await;
+        assert_eq!(body["error"], "root_not_found");
+        let nearest = body["details"]["nearest"].as_array().unwrap();
+        assert_eq!(nearest.len(), 5);
+        assert_eq!((nearest[0]["root"].as_str(), nearest[0]["common_prefix"].as_u64()), (Some(root("ab").as_str()), Some(2)));
+
+        let response = get("/api/v1/batches/by-root/0x1234".to_string()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_payment_proofs_are_validated_without_touching_the_order() {
+        use crate::database::helpers;
+
+        let (app, db) = create_test_app().await;
+        let order = crate::models::Order {
+            id: "proof_order".to_string(),
+            order_type: OrderType::BridgeOut,
+            from_address: Some("0x1111111111111111111111111111111111111111".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "500".to_string(),
+            bank_account: Some("seller@example.com".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            filler_id: Some("filler_1".to_string()),
+            locked_amount: Some("500".to_string()),
+            status: OrderStatus::Locked,
+            batch_id: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        helpers::insert_order(&db, &order).await.unwrap();
+        let validate = |body: Value| {
+            app.clone().oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/fillers/payment-proof/validate")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+        };
+        let json_body = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+        let email_hash = format!("0x{}", "ab".repeat(32));
+
+        // Every problem is reported, per field
+        let response = validate(json!({
+            "order_id": "proof_order",
+            "payment_proof": { "transaction_id": "8mc585209k746392h", "memo": "rent" },
+            "amount": "450",
+        })).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let validation = json_body(response).await;
+        assert_eq!(validation["valid"], false);
+        assert_eq!(validation["rail"], "paypal");
+        let invalid: Vec<_> = validation["diagnostics"].as_array().unwrap().iter()
+            .filter(|d| d["valid"] == false)
+            .map(|d| d["field"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(invalid, vec!["payment_proof.transaction_id", "payment_proof.payer_email_hash", "payment_proof.memo", "amount"]);
+
+        let response = validate(json!({
+            "order_id": "proof_order",
+            "payment_proof": { "transaction_id": "8MC585209K746392H", "payer_email_hash": email_hash },
+            "amount": "500",
+        })).await.unwrap();
+        let validation = json_body(response).await;
+        assert_eq!(validation["valid"], true);
+        assert_eq!(validation["banking_hash"].as_str().unwrap().len(), 66);
+
+        // The order is still waiting for its proof
+        let stored = helpers::get_order_by_id(&db, "proof_order").await.unwrap().unwrap();
+        assert_eq!((stored.status, stored.banking_hash), (OrderStatus::Locked, None));
+
+        let response = validate(json!({ "bank_service": "Venmo", "payment_proof": { "id": "1" } })).await.unwrap();
+        let validation = json_body(response).await;
+        assert_eq!((validation["valid"].as_bool(), validation["diagnostics"][0]["field"].as_str()), (Some(false), Some("bank_service")));
+
+        let response = validate(json!({ "order_id": "missing_order", "banking_hash": "0x1234" })).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_batch_stays_open_when_its_sequence_cannot_be_recorded() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let app_state = AppState::new(Config::default(), db.clone());
+        let batch_id = app_state.batch_processor.lock().await.start_batch().unwrap();
+
+        sqlx::query("CREATE TRIGGER refuse_sequence BEFORE INSERT ON batch_sequence BEGIN SELECT RAISE(ABORT, 'disk full'); END")
+            .execute(&db)
+            .await
+            .unwrap();
+        {
+            let mut processor = app_state.batch_processor.lock().await;
+            assert!(crate::api::batch::finalize_in_sequence(&app_state, &mut processor).await.is_err());
+            assert_eq!(processor.get_current_batch().map(|batch| batch.batch_id), Some(batch_id));
+            assert!(processor.last_snapshot.is_none());
+            assert!(processor.proving_queue.batch_ids().is_empty());
+        }
+        assert_eq!(app_state.archive.latest_batch_id().await.unwrap(), None);
+
+        sqlx::query("DROP TRIGGER refuse_sequence").execute(&db).await.unwrap();
+        let mut processor = app_state.batch_processor.lock().await;
+        let result = crate::api::batch::finalize_in_sequence(&app_state, &mut processor).await.unwrap();
+        assert_eq!(result.batch_id, batch_id);
+        assert!(processor.get_current_batch().is_none());
+        drop(processor);
+        assert_eq!(app_state.archive.latest_batch_id().await.unwrap(), Some(batch_id));
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_require_the_admin_token() {
+        let mut config = Config::default();
+        config.api.admin_token = Some("admin-secret".to_string());
+        let (app, _db) = create_test_app_with_config(config).await;
+        let request = |method: &str, uri: &str, token: Option<&str>| {
+            let mut builder = Request::builder().method(method).uri(uri).header("content-type", "application/json");
+            if let Some(token) = token {
+                builder = builder.header("authorization", format!("Bearer {}", token));
+            }
+            builder.body(Body::from("{}")).unwrap()
+        };
+
+        let mut mutations = vec![
+            ("PUT", "/api/v1/admin/maintenance"),
+            ("PUT", "/api/v1/admin/withdrawals/lists/0x1234567890123456789012345678901234567890"),
+            ("DELETE", "/api/v1/admin/withdrawals/lists/0x1234567890123456789012345678901234567890"),
+            ("PUT", "/api/v1/admin/batch-caps/1"),
+            ("DELETE", "/api/v1/admin/batch-caps/1"),
+            ("PUT", "/api/v1/admin/rates/1"),
+            ("PUT", "/api/v1/admin/registry/tokens/1"),
+            ("PUT", "/api/v1/admin/registry/bank-services/Wise"),
+            ("POST", "/api/v1/admin/sagas/order_1/abort"),
+            ("DELETE", "/api/v1/admin/fillers/filler_1/collateral/flag"),
+            ("POST", "/api/v1/admin/reconciliation/run"),
+            ("POST", "/api/v1/admin/retention/run"),
+            ("POST", "/api/v1/admin/screening/reviews/1"),
+            ("POST", "/api/v1/admin/order-reviews/order_1"),
+            ("POST", "/api/v1/admin/backups"),
+            ("POST", "/api/v1/admin/backups/1/verify"),
+            ("POST", "/api/v1/admin/backups/1/restore"),
+            ("POST", "/api/v1/admin/handover/export"),
+            ("POST", "/api/v1/admin/handover/import"),
+            ("POST", "/api/v1/admin/accounts/bulk"),
+        ];
+        if cfg!(feature = "fault-injection") {
+            mutations.extend([("PUT", "/api/v1/admin/faults"), ("DELETE", "/api/v1/admin/faults")]);
+        }
+        for (method, uri) in mutations {
+            for token in [None, Some("wrong")] {
+                let response = app.clone().oneshot(request(method, uri, token)).await.unwrap();
+                assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{} {} with token {:?}", method, uri, token);
+            }
+        }
+
+        // Reads are guarded too
+        let response = app.clone().oneshot(request("GET", "/api/v1/admin/config", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.oneshot(request("GET", "/api/v1/admin/config", Some("admin-secret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_are_disabled_without_a_token_outside_dev() {
+        let request = || Request::builder().uri("/api/v1/admin/config").body(Body::empty()).unwrap();
+
+        let (app, _db) = create_test_app().await;
+        assert_eq!(app.oneshot(request()).await.unwrap().status(), StatusCode::OK);
+
+        let config = Config { profile: DeploymentProfile::Staging, ..Config::default() };
+        let (app, _db) = create_test_app_with_config(config).await;
+        let response = app.oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "admin_disabled");
+    }
 }
@@ -0,0 +1,234 @@
+//! Offline verification of order and account proofs
+//!
+//! Recomputes the leaf hash from the order or account fields, folds the proof with the hash
+//! scheme its format uses and compares the result with a root the filler trusts (e.g. the one
+//! the bridge recorded), without talking to the backend.
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// Shared with the server through vapor-core, so leaves and proofs are hashed exactly as they
+// were generated; only the verification half is used here
+use vapor_core::leaf_encoding::{ethereum_address_to_path, AccountLeafFields, OrderLeafFields};
+use vapor_core::proof_format::{
+    bit_path_to_path_bits, index_to_path_bits, parse_hash32, process_raw_proof, process_sorted_proof, to_hex32,
+    ProofFormat,
+};
+
+#[derive(Parser)]
+#[command(name = "vapor-verify", about = "Verify Vapor order and account proofs offline")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Verify that an order is in a batch's orders tree
+    Order {
+        #[command(flatten)]
+        args: ProofArgs,
+        /// Batch the proof is for; defaults to the proof's `batch_id`
+        #[arg(long)]
+        batch_id: Option<u64>,
+        /// Position of the order in the batch; defaults to the proof's `leaf_index`
+        #[arg(long)]
+        index: Option<usize>,
+    },
+    /// Verify an account's balances against a state root
+    Account {
+        #[command(flatten)]
+        args: ProofArgs,
+    },
+}
+
+#[derive(clap::Args)]
+struct ProofArgs {
+    /// Proof JSON as returned by the proofs API (`leaf_hash`, `proof`, `format`, `path_bits`, ...)
+    #[arg(long)]
+    proof: PathBuf,
+    /// Leaf data JSON: order fields (`order_id`, `order_type`, `from_address`, `to_address`,
+    /// `token_id`, `amount`) or account fields (`address`, `balances`)
+    #[arg(long)]
+    leaf: PathBuf,
+    /// Root to verify against, 0x-prefixed hex
+    #[arg(long)]
+    root: String,
+}
+
+/// The fields of a proofs API response that verification needs
+#[derive(Debug, Deserialize)]
+struct ProofFile {
+    #[serde(default)]
+    format: ProofFormat,
+    proof: Vec<String>,
+    leaf_hash: Option<String>,
+    path_bits: Option<Vec<u8>>,
+    leaf_index: Option<usize>,
+    batch_id: Option<u64>,
+}
+
+/// Where the leaf sits, for positional proofs that do not carry their path bits
+enum LeafPosition<'a> {
+    Index(Option<usize>),
+    Address(&'a str),
+}
+
+#[derive(Debug)]
+struct Verification {
+    format: ProofFormat,
+    leaf_hash: [u8; 32],
+    /// False when the proof names a different leaf hash than the one recomputed
+    leaf_matches: bool,
+    computed_root: [u8; 32],
+    expected_root: [u8; 32],
+}
+
+impl Verification {
+    fn valid(&self) -> bool {
+        self.leaf_matches && self.computed_root == self.expected_root
+    }
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = match self.format {
+            ProofFormat::SortedPairs => "sorted pairs",
+            ProofFormat::Siblings | ProofFormat::Raw => "positional",
+        };
+        writeln!(f, "hash scheme:   {} ({})", scheme, self.format.as_str())?;
+        writeln!(f, "leaf hash:     {}{}", to_hex32(&self.leaf_hash), if self.leaf_matches { "" } else { " (differs from the proof's leaf_hash)" })?;
+        writeln!(f, "computed root: {}", to_hex32(&self.computed_root))?;
+        writeln!(f, "expected root: {}", to_hex32(&self.expected_root))?;
+        write!(f, "{}", if self.valid() { "VALID" } else { "INVALID" })
+    }
+}
+
+fn verify(proof: &ProofFile, leaf_hash: [u8; 32], position: LeafPosition, expected_root: [u8; 32]) -> Result<Verification> {
+    let siblings = proof.proof.iter()
+        .map(|sibling| parse_hash32(sibling))
+        .collect::<Result<Vec<_>, _>>()?;
+    let leaf_matches = match &proof.leaf_hash {
+        Some(claimed) => parse_hash32(claimed)? == leaf_hash,
+        None => true,
+    };
+
+    let computed_root = match proof.format {
+        ProofFormat::SortedPairs => process_sorted_proof(leaf_hash, &siblings),
+        ProofFormat::Siblings | ProofFormat::Raw => {
+            let path_bits = match (&proof.path_bits, position) {
+                (Some(path_bits), _) => path_bits.clone(),
+                (None, LeafPosition::Index(Some(index))) => index_to_path_bits(index, siblings.len()),
+                (None, LeafPosition::Index(None)) => bail!("positional order proofs need --index, leaf_index or path_bits"),
+                (None, LeafPosition::Address(address)) => {
+                    bit_path_to_path_bits(&ethereum_address_to_path(address, siblings.len()))
+                }
+            };
+            process_raw_proof(leaf_hash, &siblings, &path_bits)?
+        }
+    };
+
+    Ok(Verification { format: proof.format, leaf_hash, leaf_matches, computed_root, expected_root })
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T> {
+    let raw = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    serde_json::from_slice(&raw).with_context(|| format!("parsing {}", path.display()))
+}
+
+fn run(cli: Cli) -> Result<Verification> {
+    match cli.command {
+        Command::Order { args, batch_id, index } => {
+            let proof: ProofFile = read_json(&args.proof)?;
+            let order: OrderLeafFields = read_json(&args.leaf)?;
+            let batch_id = batch_id.or(proof.batch_id).context("the proof has no batch_id; pass --batch-id")?;
+            let position = LeafPosition::Index(index.or(proof.leaf_index));
+            verify(&proof, order.leaf_hash(batch_id), position, parse_hash32(&args.root)?)
+        }
+        Command::Account { args } => {
+            let proof: ProofFile = read_json(&args.proof)?;
+            let account: AccountLeafFields = read_json(&args.leaf)?;
+            let position = LeafPosition::Address(&account.address);
+            verify(&proof, account.leaf_hash(), position, parse_hash32(&args.root)?)
+        }
+    }
+}
+
+fn main() {
+    match run(Cli::parse()) {
+        Ok(verification) => {
+            println!("{}", verification);
+            std::process::exit(if verification.valid() { 0 } else { 1 });
+        }
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vapor_core::proof_format::SortedPairTree;
+    use vapor_merkle::proof::hash_pair;
+
+    fn order(order_id: &str) -> OrderLeafFields {
+        OrderLeafFields {
+            order_id: order_id.to_string(),
+            order_type: vapor_core::leaf_encoding::BRIDGE_IN,
+            from_address: Some("0x70997970c51812dc3a010c7d01b50e0d17dc79c8".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+        }
+    }
+
+    fn proof_file(format: ProofFormat, siblings: &[[u8; 32]]) -> ProofFile {
+        ProofFile {
+            format,
+            proof: siblings.iter().map(to_hex32).collect(),
+            leaf_hash: None,
+            path_bits: None,
+            leaf_index: None,
+            batch_id: Some(3),
+        }
+    }
+
+    #[test]
+    fn test_verifies_both_hash_schemes() {
+        let leaves: Vec<[u8; 32]> = ["a", "b", "c"].iter().map(|id| order(id).leaf_hash(3)).collect();
+
+        // Positional: leaf 1 is the right child of the first pair, with an empty right subtree
+        let empty = [0u8; 32];
+        let positional_root = hash_pair(&hash_pair(&leaves[0], &leaves[1]), &hash_pair(&leaves[2], &empty));
+        let proof = proof_file(ProofFormat::Siblings, &[leaves[0], hash_pair(&leaves[2], &empty)]);
+        assert!(verify(&proof, leaves[1], LeafPosition::Index(Some(1)), positional_root).unwrap().valid());
+        assert!(!verify(&proof, leaves[1], LeafPosition::Index(Some(0)), positional_root).unwrap().valid());
+        assert!(verify(&proof, leaves[1], LeafPosition::Index(None), positional_root).is_err());
+
+        let tree = SortedPairTree::from_leaves(leaves.clone()).unwrap();
+        let proof = proof_file(ProofFormat::SortedPairs, &tree.proof(2).unwrap());
+        assert!(verify(&proof, leaves[2], LeafPosition::Index(None), tree.root()).unwrap().valid());
+        assert!(!verify(&proof, order("x").leaf_hash(3), LeafPosition::Index(None), tree.root()).unwrap().valid());
+    }
+
+    #[test]
+    fn test_leaf_must_match_the_proof() {
+        let account = AccountLeafFields { address: "0x80".to_string(), balances: vec![] };
+        let sibling = order("a").leaf_hash(1);
+        // Address 0x80 starts with a set bit, so the account is the right child
+        let root = hash_pair(&sibling, &account.leaf_hash());
+
+        let mut proof = proof_file(ProofFormat::Raw, &[sibling]);
+        assert!(verify(&proof, account.leaf_hash(), LeafPosition::Address(&account.address), root).unwrap().valid());
+
+        proof.leaf_hash = Some(to_hex32(&sibling));
+        let verification = verify(&proof, account.leaf_hash(), LeafPosition::Address(&account.address), root).unwrap();
+        assert!(!verification.leaf_matches);
+        assert!(!verification.valid());
+    }
+}
@@ -1,48 +1,1471 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 
+use crate::models::OrderType;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    pub profile: DeploymentProfile,
     pub api: ApiConfig,
     pub database: DatabaseConfig,
     pub blockchain: BlockchainConfig,
     pub batch: BatchConfig,
+    pub filler: FillerConfig,
+    pub signer: SignerConfig,
+    pub sla: SlaConfig,
+    pub archive: ArchiveConfig,
+    pub blob_store: BlobStoreConfig,
+    pub telemetry: TelemetryConfig,
+    pub withdrawal: WithdrawalConfig,
+    pub order_amounts: OrderAmountConfig,
+    pub order_duplicates: DuplicateConfig,
+    pub rates: RateConfig,
+    pub relayer: RelayerScanConfig,
+    pub market: MarketConfig,
+    pub explorer: ExplorerConfig,
+    pub manifests: ManifestConfig,
+    pub request_limits: RequestLimitsConfig,
+    pub http_cache: HttpCacheConfig,
+    pub jobs: JobsConfig,
+    pub collateral: CollateralConfig,
+    pub quotes: QuoteConfig,
+    pub proof_submission: ProofSubmissionConfig,
+    pub order_queue: OrderQueueConfig,
+    pub balance_alerts: BalanceAlertConfig,
+    pub claims: ClaimConfig,
+    pub registry: RegistryConfig,
+    pub slo: SloConfig,
+    pub reconciliation: ReconciliationConfig,
+    pub retention: RetentionConfig,
+    pub backup: BackupConfig,
+    pub handover: HandoverConfig,
+    pub screening: ScreeningConfig,
+    pub analytics: AnalyticsConfig,
+    pub attestations: AttestationConfig,
+    pub order_review: OrderReviewConfig,
+    pub diagnostics: DiagnosticsConfig,
+    pub notifications: NotificationConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiConfig {
+    pub port: u16,
+    /// Bearer token required by admin-only endpoints; unset leaves them open
+    pub admin_token: Option<String>,
+    /// Origins allowed cross-origin requests outside the dev profile, which allows any
+    pub cors_allowed_origins: Vec<String>,
+    /// Most balances one bulk account initialization may set
+    pub bulk_account_max_entries: usize,
+}
+
+/// Environment the server is deployed to, which decides the features that are only safe
+/// away from real funds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeploymentProfile {
+    #[default]
+    Dev,
+    Staging,
+    Prod,
+}
+
+impl DeploymentProfile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dev => "dev",
+            Self::Staging => "staging",
+            Self::Prod => "prod",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "dev" | "development" => Some(Self::Dev),
+            "staging" => Some(Self::Staging),
+            "prod" | "production" => Some(Self::Prod),
+            _ => None,
+        }
+    }
+
+    pub fn allows_mock_prover(&self) -> bool {
+        *self != Self::Prod
+    }
+
+    pub fn allows_permissive_cors(&self) -> bool {
+        *self == Self::Dev
+    }
+
+    pub fn allows_init_account(&self) -> bool {
+        *self != Self::Prod
+    }
+
+    /// Admin routes without ADMIN_API_TOKEN
+    pub fn allows_open_admin(&self) -> bool {
+        *self == Self::Dev
+    }
+
+    /// Mock prover failures and fault injection
+    pub fn allows_simulated_failures(&self) -> bool {
+        *self != Self::Prod
+    }
+
+    /// Blob storage that is lost on restart
+    pub fn allows_memory_blob_store(&self) -> bool {
+        *self == Self::Dev
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockchainConfig {
+    pub rpc_url: String,
+    pub contract_address: String,
+    pub proof_verifier_address: String,
+    pub usdc_address: String,
+    pub private_key: String,
+    /// Salt mixed into per-order deposit references so they cannot be guessed from order IDs
+    #[serde(skip_serializing)]
+    pub deposit_reference_salt: String,
+    /// How long latest batch id, batch roots and USDC balances are cached (0 disables)
+    pub read_cache_seconds: u64,
+    /// EIP-712 domain separators (the token's DOMAIN_SEPARATOR()) keyed by token id; permits
+    /// for tokens without one are rejected since their signature cannot be checked
+    pub permit_domain_separators: HashMap<u32, [u8; 32]>,
+}
+
+/// Per-state order SLAs in seconds (0 disables the timer)
+/// Locked orders use the filler lock TTL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaConfig {
+    pub discovery_seconds: u64,
+    pub mark_paid_seconds: u64,
+    pub sweep_interval_seconds: u64,
+    /// How long an API-created BridgeIn order waits for its deposit before it fails as
+    /// `intent_expired`
+    pub intent_seconds: u64,
+    /// How long after expiring such an order is revived if its deposit still arrives
+    pub intent_revival_seconds: u64,
+    /// Receives each expired intent as JSON
+    pub intent_webhook_url: Option<String>,
+}
+
+/// State snapshot retention: the last `retain_batches` snapshots stay hot,
+/// older ones are compressed into the archive table (0 keeps everything hot)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveConfig {
+    pub retain_batches: u32,
+}
+
+/// Where proof artifacts and state snapshots are stored (BLOB_STORE = fs | s3 | memory)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum BlobStoreConfig {
+    /// Held in process memory and lost on restart
+    Memory,
+    #[serde(rename = "fs")]
+    Filesystem { path: String },
+    S3(S3Config),
+}
+
+/// S3-compatible bucket (AWS, MinIO, R2, ...), addressed path-style as `{endpoint}/{bucket}/{key}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    /// Prepended to every object key, e.g. "vapor/"
+    pub prefix: String,
+    pub access_key_id: String,
+    #[serde(skip_serializing)]
+    pub secret_access_key: String,
+}
+
+/// OpenTelemetry trace export; spans are only exported when an OTLP endpoint is set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+}
+
+/// Transaction signer selection (SIGNER_TYPE = env | keystore | remote)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignerConfig {
+    pub kind: String,
+    pub keystore_path: Option<String>,
+    #[serde(skip_serializing)]
+    pub keystore_password: Option<String>,
+    pub remote_url: Option<String>,
+    pub remote_key_id: Option<String>,
+    #[serde(skip_serializing)]
+    pub remote_auth_token: Option<String>,
+    pub remote_address: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchConfig {
+    pub interval_seconds: u64,
+    pub max_orders_per_batch: usize,
+    /// Recent batches whose proofs are precomputed at finalization (0 disables)
+    pub precompute_proof_batches: u32,
+    /// What to do on startup with a batch that was still open when the server stopped
+    pub recovery_policy: BatchRecoveryPolicy,
+    /// Max total BridgeOut volume per token in a single batch (tokens without a cap are unlimited)
+    pub bridge_out_caps: HashMap<u32, u64>,
+    /// Max Merkle nodes cached per tree; least recently used nodes are evicted past it
+    pub merkle_node_cache_capacity: usize,
+    /// How long a proof verification result is reused for the same proof and public inputs (0 disables)
+    pub verify_cache_seconds: u64,
+    /// Most verification results kept; the ones closest to expiring are dropped past it
+    pub verify_cache_capacity: usize,
+    /// Assert token-balance invariants after every batch (see services::invariants)
+    pub invariant_checks: bool,
+}
+
+/// Relayer catch-up scanning: log queries cover `range_blocks` blocks each, with up to
+/// `max_concurrent_ranges` in flight. Blocks are scanned up to the chain's finality mode
+/// (`finality`, keyed by chain id); chains without one wait `confirmation_depth` blocks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayerScanConfig {
+    pub range_blocks: u64,
+    pub max_concurrent_ranges: usize,
+    pub finality: HashMap<u64, FinalityMode>,
+    /// Blocks behind latest that count as final in `depth` mode, or when a tag is unsupported
+    pub confirmation_depth: u64,
+}
+
+/// How the relayer decides which blocks are final enough to process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinalityMode {
+    /// The node's `finalized` block
+    Finalized,
+    /// The node's `safe` block
+    Safe,
+    /// `confirmation_depth` blocks behind the latest block
+    #[default]
+    Depth,
+}
+
+impl FinalityMode {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "finalized" => Some(Self::Finalized),
+            "safe" => Some(Self::Safe),
+            "depth" => Some(Self::Depth),
+            _ => None,
+        }
+    }
+}
+
+/// Public market summary: recomputed every `refresh_seconds`, each client may fetch it
+/// `rate_limit_per_minute` times a minute (0 = unlimited). With `throttle_saturated_corridors`
+/// set, BridgeIn orders are refused in corridors the last refresh found saturated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketConfig {
+    pub refresh_seconds: u64,
+    pub rate_limit_per_minute: u32,
+    pub throttle_saturated_corridors: bool,
+}
+
+/// State tree exports for external indexers; each client may start `rate_limit_per_minute`
+/// exports a minute (0 = unlimited)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplorerConfig {
+    pub rate_limit_per_minute: u32,
+}
+
+/// Where signed batch manifests are pushed after each proof submission, besides being served
+/// by the explorer API; nothing is pushed unless `push_url` is set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ManifestConfig {
+    pub push_url: Option<String>,
+    pub push_kind: ManifestPushKind,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestPushKind {
+    /// POST the manifest as JSON to `push_url`
+    #[default]
+    Http,
+    /// Add the manifest to the IPFS node whose HTTP API is at `push_url`
+    Ipfs,
+}
+
+impl ManifestPushKind {
+    fn parse(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "ipfs" => Self::Ipfs,
+            _ => Self::Http,
+        }
+    }
+}
+
+/// Timeouts and body size limit applied to every request; a timeout of 0 disables it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestLimitsConfig {
+    /// GET, HEAD and OPTIONS requests
+    pub read_timeout_seconds: u64,
+    /// Other requests
+    pub write_timeout_seconds: u64,
+    /// Proving, backups and the other long-running jobs started over the API
+    pub long_timeout_seconds: u64,
+    pub max_body_bytes: usize,
+}
+
+impl Default for RequestLimitsConfig {
+    fn default() -> Self {
+        Self {
+            read_timeout_seconds: 10,
+            write_timeout_seconds: 30,
+            long_timeout_seconds: 600,
+            max_body_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// Cache-Control lifetimes of the polled read endpoints that answer conditional requests (see api::caching)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpCacheConfig {
+    /// GET /orders/:id and /orders/:id/status; 0 makes clients revalidate on every poll
+    pub order_max_age_seconds: u64,
+    /// Order proofs of finalized batches, which do not change once built
+    pub proof_max_age_seconds: u64,
+}
+
+impl Default for HttpCacheConfig {
+    fn default() -> Self {
+        Self {
+            order_max_age_seconds: 2,
+            proof_max_age_seconds: 3600,
+        }
+    }
+}
+
+/// Delayed jobs run by the in-process scheduler (see services::scheduler)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobsConfig {
+    /// How often due jobs are picked up
+    pub poll_interval_seconds: u64,
+    /// First retry delay of a failed attempt, doubled on each further one up to `retry_max_seconds`
+    pub retry_base_seconds: u64,
+    pub retry_max_seconds: u64,
+    /// Attempts before a job is given up as failed
+    pub max_attempts: u32,
+    /// Payment verification provider; without it a proof that passed its schema is accepted
+    pub payment_verifier_url: Option<String>,
+    /// How often a paid order's settlement is checked until its batch proof is submitted
+    pub settlement_check_seconds: u64,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_seconds: 5,
+            retry_base_seconds: 30,
+            retry_max_seconds: 1800,
+            max_attempts: 10,
+            payment_verifier_url: None,
+            settlement_check_seconds: 60,
+        }
+    }
+}
+
+/// Collateral fillers hold with the bridge before locking larger orders (see services::collateral)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollateralConfig {
+    /// Locks of up to this amount never need collateral
+    pub lock_threshold: u64,
+    /// Collateral required across a filler's open locks, in basis points of the locked amounts;
+    /// 0 turns the requirement off for corridors without their own ratio
+    pub ratio_bps: u32,
+    /// Ratio overrides for single corridors
+    pub corridor_ratios: Vec<CorridorRatio>,
+    /// First block scanned for the fillers' bridge deposits
+    pub from_block: u64,
+    /// How long a collateral balance read from the chain is reused
+    pub cache_seconds: u64,
+    /// Share of an expired lock's amount slashed from the filler's collateral, in basis points
+    pub slash_bps: u32,
+    /// Lock expiries after which the filler may only take locks up to the threshold; 0 never flags
+    pub flag_after_expiries: u32,
+}
+
+/// Collateral ratio of the orders of one token paid out over one bank service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorridorRatio {
+    pub token_id: u32,
+    /// Matched case-insensitively
+    pub bank_service: String,
+    pub ratio_bps: u32,
+}
+
+impl Default for CollateralConfig {
+    fn default() -> Self {
+        Self {
+            lock_threshold: 0,
+            ratio_bps: 0,
+            corridor_ratios: Vec::new(),
+            from_block: 0,
+            cache_seconds: 300,
+            slash_bps: 0,
+            flag_after_expiries: 3,
+        }
+    }
+}
+
+impl CollateralConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.ratio_bps > 0 || self.corridor_ratios.iter().any(|corridor| corridor.ratio_bps > 0)
+    }
+
+    /// Ratio for orders of `token_id` paid out over `bank_service`
+    pub fn ratio_for(&self, token_id: u32, bank_service: Option<&str>) -> u32 {
+        self.corridor_ratios.iter()
+            .find(|corridor| {
+                corridor.token_id == token_id
+                    && bank_service.is_some_and(|service| service.eq_ignore_ascii_case(&corridor.bank_service))
+            })
+            .map_or(self.ratio_bps, |corridor| corridor.ratio_bps)
+    }
+}
+
+/// Binding quotes handed out before an order is committed (see services::quotes)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuoteConfig {
+    /// How long a quote can be committed to
+    pub ttl_seconds: u64,
+}
+
+impl Default for QuoteConfig {
+    fn default() -> Self {
+        Self { ttl_seconds: 120 }
+    }
+}
+
+/// Order intake from a Redis stream, read through a consumer group; disabled unless `redis_url` is set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderQueueConfig {
+    pub redis_url: Option<String>,
+    pub stream: String,
+    pub group: String,
+    /// Consumer name within the group; each server instance needs its own
+    pub consumer: String,
+    /// Messages read per round trip
+    pub batch_size: usize,
+}
+
+impl Default for OrderQueueConfig {
+    fn default() -> Self {
+        Self {
+            redis_url: None,
+            stream: "vapor:orders".to_string(),
+            group: "vapor-backend".to_string(),
+            consumer: "vapor-backend-1".to_string(),
+            batch_size: 100,
+        }
+    }
+}
+
+/// Low balance alerts, checked every `interval_seconds`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceAlertConfig {
+    pub rules: Vec<BalanceAlertRule>,
+    pub interval_seconds: u64,
+    /// Alerts are POSTed here as JSON when they fire or resolve
+    pub webhook_url: Option<String>,
+    /// Recipient of alert emails (stub: the email is only logged)
+    pub email_to: Option<String>,
+    /// Alert while the submission signer's ETH covers fewer submissions than this (0 = off)
+    pub signer_min_submissions: u64,
+}
+
+impl Default for BalanceAlertConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            interval_seconds: 60,
+            webhook_url: None,
+            email_to: None,
+            signer_min_submissions: 10,
+        }
+    }
+}
+
+/// Claim reconciliation: on-chain ClaimEvents are compared with the claims table every
+/// `reconcile_interval_seconds`, starting at `reconcile_from_block`. Claim fees: every claim
+/// pays `fee_bps` of its gross amount plus `fee_gas_surcharge`, and only the net is transferred.
+/// Claim relaying: the backend submits opted-in fillers' claims and deducts the gas cost from
+/// what they claim.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimConfig {
+    pub reconcile_interval_seconds: u64,
+    pub reconcile_from_block: u64,
+    /// Protocol fee on each claim, in basis points of the gross amount
+    pub fee_bps: u32,
+    /// Fixed amount of the earned token added to each claim's fee to cover gas
+    pub fee_gas_surcharge: u64,
+    /// Whether fillers may opt in to having the backend submit (and pay gas for) their claims
+    pub relay_enabled: bool,
+    /// Gas estimate of a relayed batchClaim: a base cost plus a cost per claim
+    pub relay_base_gas: u64,
+    pub relay_gas_per_claim: u64,
+    /// Gas price used when no blockchain client can report the network's
+    pub relay_gas_price_gwei: u64,
+    /// ETH price in micro-dollars, to turn the gas cost into a fee in the claimed token
+    pub relay_eth_usd_price: u64,
+    /// Charged on top of the gas cost, in basis points
+    pub relay_fee_markup_bps: u32,
+}
+
+impl Default for ClaimConfig {
+    fn default() -> Self {
+        Self {
+            reconcile_interval_seconds: 300,
+            reconcile_from_block: 0,
+            fee_bps: 0,
+            fee_gas_surcharge: 0,
+            relay_enabled: false,
+            relay_base_gas: 60_000,
+            relay_gas_per_claim: 45_000,
+            relay_gas_price_gwei: 20,
+            relay_eth_usd_price: 3_000_000_000,
+            relay_fee_markup_bps: 1_000,
+        }
+    }
+}
+
+/// Token and bank-service registries are reloaded from the database at most every `cache_ttl_seconds`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryConfig {
+    pub cache_ttl_seconds: u64,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self { cache_ttl_seconds: 60 }
+    }
+}
+
+/// Order book reconciliation runs daily at `hour_utc` and keeps the latest `retain_reports` reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationConfig {
+    pub hour_utc: u32,
+    pub retain_reports: u32,
+}
+
+impl Default for ReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            hour_utc: 3,
+            retain_reports: 30,
+        }
+    }
+}
+
+/// How long bank details outlive their order; bank services are the tenants, each with an
+/// optional override of `bank_details_days`. The sweep runs every `sweep_interval_seconds`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// Days bank details are kept after an order settles or fails (0 keeps them)
+    pub bank_details_days: u32,
+    /// Per-bank-service overrides, keyed by lowercased bank service name
+    pub bank_service_days: HashMap<String, u32>,
+    pub sweep_interval_seconds: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            bank_details_days: 30,
+            bank_service_days: HashMap::new(),
+            sweep_interval_seconds: 3600,
+        }
+    }
+}
+
+/// Scheduled database backups into the blob store; the newest `retain` are kept (0 keeps all)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// Seconds between scheduled backups (0 disables them)
+    pub interval_seconds: u64,
+    pub retain: u32,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            interval_seconds: 0,
+            retain: 7,
+        }
+    }
+}
+
+/// Writer lease for blue/green deployments: only the instance holding it writes, and it must be
+/// renewed within `lease_seconds` (0 disables the lease). Instances on one host need distinct ids
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoverConfig {
+    pub lease_seconds: u64,
+    pub instance_id: String,
+}
+
+impl Default for HandoverConfig {
+    fn default() -> Self {
+        Self {
+            lease_seconds: 30,
+            instance_id: "vapor".to_string(),
+        }
+    }
+}
+
+/// Sanctions screening of the addresses on new orders, filler locks and claims
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScreeningConfig {
+    pub provider: ScreeningProviderConfig,
+    pub action: ScreeningAction,
+}
+
+/// Who screens addresses (SCREENING_PROVIDER = none | static | http)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ScreeningProviderConfig {
+    /// Every address passes
+    #[default]
+    None,
+    /// Addresses on a fixed denylist are hits
+    Static { addresses: Vec<String> },
+    /// Chainalysis-style sanctions API: `GET {url}/api/v1/address/{address}` with an `X-API-Key`
+    Http {
+        url: String,
+        #[serde(skip_serializing)]
+        api_key: String,
+    },
+}
+
+/// What happens to a request with a screening hit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreeningAction {
+    /// Refuse the request
+    #[default]
+    Block,
+    /// Let it through and queue the hit for an admin to review
+    Flag,
+}
+
+impl ScreeningAction {
+    fn parse(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "flag" => Self::Flag,
+            _ => Self::Block,
+        }
+    }
+}
+
+/// Anonymized product analytics events, built from the event log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    /// Kill switch; nothing is emitted unless set
+    pub enabled: bool,
+    pub sink: AnalyticsSinkConfig,
+    /// Share of orders whose events are emitted, 0.0 to 1.0; an order is sampled in or out whole
+    pub sample_rate: f64,
+    /// Secret mixed into hashed addresses and ids, so they cannot be matched against public addresses
+    #[serde(skip_serializing)]
+    pub hash_salt: String,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sink: AnalyticsSinkConfig::default(),
+            sample_rate: 1.0,
+            hash_salt: String::new(),
+        }
+    }
+}
+
+/// Manual review of high-value orders before they enter Discovery (see services::order_review)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderReviewConfig {
+    /// Amount per token, in base units, above which an order waits for an admin's approval
+    pub thresholds: HashMap<u32, u64>,
+}
+
+/// Off-platform order lifecycle notifications (see services::notifications)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    /// Kill switch; no notification is queued unless set
+    pub enabled: bool,
+    /// Sender address of notification emails
+    pub email_from: String,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self { enabled: false, email_from: "notifications@vapor.local".to_string() }
+    }
+}
+
+/// Database latency instrumentation (see services::query_metrics)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsConfig {
+    /// Data-access operations slower than this are logged; 0 turns the log off
+    pub slow_query_ms: u64,
+    /// Operations listed by GET /admin/diagnostics/queries unless the request asks for another count
+    pub top_operations: usize,
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> Self {
+        Self { slow_query_ms: 250, top_operations: 10 }
+    }
+}
+
+/// Operator-signed balance attestations for third parties (see services::attestations)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationConfig {
+    /// How long an attestation may be relied on after it is issued
+    pub ttl_seconds: u64,
+}
+
+impl Default for AttestationConfig {
+    fn default() -> Self {
+        Self { ttl_seconds: 3600 }
+    }
+}
+
+/// Where analytics events go (ANALYTICS_SINK = stdout | http)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum AnalyticsSinkConfig {
+    /// One JSON object per line on stdout
+    #[default]
+    Stdout,
+    /// POSTed one by one as JSON to a collector
+    Http { url: String },
+}
+
+impl RetentionConfig {
+    /// Retention that applies to an order with this bank service
+    pub fn days_for(&self, bank_service: Option<&str>) -> u32 {
+        bank_service
+            .and_then(|service| self.bank_service_days.get(&service.to_lowercase()))
+            .copied()
+            .unwrap_or(self.bank_details_days)
+    }
+}
+
+/// Per-phase latency objectives, recomputed every `refresh_seconds` over orders that left
+/// a phase in the last `window_hours`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SloConfig {
+    pub window_hours: i64,
+    pub refresh_seconds: u64,
+    pub p95_targets: SloTargets,
+}
+
+/// p95 targets in seconds; phases without one are reported but never missed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SloTargets {
+    pub discovery_seconds: Option<u64>,
+    pub locked_seconds: Option<u64>,
+    pub mark_paid_seconds: Option<u64>,
+    /// Creation to settlement
+    pub settle_seconds: Option<u64>,
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        Self {
+            window_hours: 24,
+            refresh_seconds: 60,
+            p95_targets: SloTargets::default(),
+        }
+    }
+}
+
+/// Where an alert rule reads its balance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceSource {
+    /// ERC20 balance on-chain (USDC only)
+    Chain,
+    /// Balance in the rollup state kept by the batch processor
+    Ledger,
+}
+
+/// Fires while a balance is below `threshold` (token base units)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceAlertRule {
+    pub source: BalanceSource,
+    /// An address, `bridge` for the bridge contract, or `filler:<filler_id>` for a filler's settlement account
+    pub target: String,
+    pub token_id: u32,
+    pub threshold: u64,
+}
+
+/// Encoding of proof bytes in submission calldata
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalldataCompression {
+    /// Raw proof bytes
+    #[default]
+    None,
+    /// zlib-compressed proof bytes, for verifiers that inflate them on-chain
+    Zlib,
+    /// zstd-compressed proof kept as an off-chain artifact; calldata carries its keccak256 hash
+    ZstdArtifact,
+}
+
+impl CalldataCompression {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Zlib => "zlib",
+            Self::ZstdArtifact => "zstd_artifact",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "none" | "raw" => Some(Self::None),
+            "zlib" => Some(Self::Zlib),
+            "zstd" | "zstd_artifact" => Some(Self::ZstdArtifact),
+            _ => None,
+        }
+    }
+}
+
+/// How a batch's submission gas is split across its orders
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CostWeighting {
+    /// Every order pays the same share
+    #[default]
+    Count,
+    /// Shares follow the orders' amounts
+    Value,
+}
+
+impl CostWeighting {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Count => "count",
+            Self::Value => "value",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "value" | "amount" => Self::Value,
+            _ => Self::Count,
+        }
+    }
+}
+
+/// Proof calldata compression per submission target, i.e. per verifier contract address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofSubmissionConfig {
+    pub default_compression: CalldataCompression,
+    /// Overrides keyed by lowercase verifier address
+    pub target_compression: HashMap<String, CalldataCompression>,
+    /// How submission gas is attributed to a batch's orders
+    pub cost_weighting: CostWeighting,
+    /// Gas price used for the attribution when the network's price is unavailable
+    pub gas_price_gwei: u64,
+    /// ETH price in micro-dollars, to record each batch's submission cost in USD
+    pub eth_usd_price: u64,
+}
+
+impl Default for ProofSubmissionConfig {
+    fn default() -> Self {
+        Self {
+            default_compression: CalldataCompression::default(),
+            target_compression: HashMap::new(),
+            cost_weighting: CostWeighting::default(),
+            gas_price_gwei: 20,
+            eth_usd_price: 3_000_000_000,
+        }
+    }
+}
+
+impl ProofSubmissionConfig {
+    pub fn compression_for(&self, target: &str) -> CalldataCompression {
+        self.target_compression.get(&target.to_ascii_lowercase())
+            .copied()
+            .unwrap_or(self.default_compression)
+    }
+}
+
+/// Handling of an unfinalized batch found on startup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchRecoveryPolicy {
+    /// Reopen the batch with its orders reapplied
+    #[default]
+    Resume,
+    /// Reopen the batch empty and mark its orders Failed
+    Rollback,
+}
+
+impl BatchRecoveryPolicy {
+    fn parse(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "rollback" => Self::Rollback,
+            _ => Self::Resume,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillerConfig {
+    /// Websocket feed tokens, keyed by token with the filler id as value
+    pub ws_tokens: HashMap<String, String>,
+    /// How long a filler lock is held before it expires
+    pub lock_ttl_seconds: u64,
+    /// How long before expiry the lock holder is notified
+    pub lock_expiry_warning_seconds: u64,
+    /// Lock limits for fillers without an override
+    pub default_limits: FillerLimits,
+    /// Per-filler lock limit overrides, keyed by filler id
+    pub limits: HashMap<String, FillerLimits>,
+    /// Order in which the matching engine offers pending orders to fillers
+    pub match_policy: MatchPolicyKind,
+    /// Ascending minimum priority fees of each tier above the base tier (priority_fee policy)
+    pub priority_fee_tiers: Vec<u64>,
+    /// Re-queues of one order (after its lock fell through) offered ahead of fresh orders
+    pub match_max_boosts: u32,
+    /// How long lock attempts on an order are collected before one wins (0 = first come, first served)
+    pub lock_window_seconds: u64,
+    /// How the winner of a lock window is picked
+    pub lock_window_policy: LockWindowPolicy,
+}
+
+/// Matching engine ordering policy, see services::match_policy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchPolicyKind {
+    /// Oldest order first; an order no filler can take holds up the rest
+    #[default]
+    Fifo,
+    /// Smallest amount first, skipping orders no filler can take
+    SmallestFirst,
+    /// Oldest first, spreading matched volume across fillers in proportion to their capacity
+    ProRata,
+    /// Highest priority fee tier first, oldest first within a tier
+    PriorityFee,
+}
+
+impl MatchPolicyKind {
+    fn parse(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "smallest_first" => Self::SmallestFirst,
+            "pro_rata" => Self::ProRata,
+            "priority_fee" => Self::PriorityFee,
+            _ => Self::Fifo,
+        }
+    }
+}
+
+/// How the winning lock attempt of a lock window is picked, see services::lock_window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockWindowPolicy {
+    /// Drawn at random, weighted by each filler's settlement record
+    #[default]
+    Reputation,
+    /// Lowest `fee_bps`, earliest attempt among equal fees
+    BestFee,
+}
+
+impl LockWindowPolicy {
+    fn parse(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "best_fee" => Self::BestFee,
+            _ => Self::Reputation,
+        }
+    }
+}
+
+/// Caps on how much a single filler can hold locked at once (0 means unlimited)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FillerLimits {
+    pub max_concurrent_locks: u32,
+    pub max_locked_value: u64,
+}
+
+impl FillerConfig {
+    /// Resolve the filler id that owns a websocket feed token
+    pub fn filler_for_token(&self, token: &str) -> Option<&str> {
+        self.ws_tokens.get(token).map(|filler_id| filler_id.as_str())
+    }
+
+    /// Lock limits that apply to a filler
+    pub fn limits_for(&self, filler_id: &str) -> FillerLimits {
+        self.limits.get(filler_id).copied().unwrap_or(self.default_limits)
+    }
+}
+
+/// BridgeOut controls: daily caps per token and whether only allowlisted addresses may withdraw
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WithdrawalConfig {
+    /// Daily limits for tokens without an override
+    pub default_limits: WithdrawalLimits,
+    /// Per-token daily limit overrides, keyed by token id
+    pub token_limits: HashMap<u32, WithdrawalLimits>,
+    /// Reject BridgeOut orders to addresses that are not on the allowlist
+    pub allowlist_only: bool,
+}
+
+/// Daily BridgeOut caps for a token, per UTC day (0 means unlimited)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalLimits {
+    pub per_address_daily: u64,
+    pub global_daily: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ApiConfig {
-    pub port: u16,
+impl WithdrawalConfig {
+    /// Daily limits that apply to a token
+    pub fn limits_for(&self, token_id: u32) -> WithdrawalLimits {
+        self.token_limits.get(&token_id).copied().unwrap_or(self.default_limits)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DatabaseConfig {
-    pub url: String,
+/// Minimum and maximum order amounts per token and order type, see services::order_limits
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderAmountConfig {
+    /// Ranges for tokens without an override
+    pub default_limits: OrderTypeAmounts,
+    /// Per-token overrides, keyed by token id
+    pub token_limits: HashMap<u32, OrderTypeAmounts>,
+}
+
+/// Amount range of each order type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderTypeAmounts {
+    pub bridge_in: AmountRange,
+    pub bridge_out: AmountRange,
+    pub transfer: AmountRange,
+}
+
+/// Allowed order amounts in token base units (0 leaves that bound open)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmountRange {
+    pub min: u64,
+    pub max: u64,
+}
+
+impl OrderTypeAmounts {
+    pub fn get(&self, order_type: OrderType) -> AmountRange {
+        match order_type {
+            OrderType::BridgeIn => self.bridge_in,
+            OrderType::BridgeOut => self.bridge_out,
+            OrderType::Transfer => self.transfer,
+        }
+    }
+
+    fn get_mut(&mut self, order_type: OrderType) -> &mut AmountRange {
+        match order_type {
+            OrderType::BridgeIn => &mut self.bridge_in,
+            OrderType::BridgeOut => &mut self.bridge_out,
+            OrderType::Transfer => &mut self.transfer,
+        }
+    }
+}
+
+impl OrderAmountConfig {
+    /// Amount range that applies to an order of `order_type` in `token_id`
+    pub fn range_for(&self, token_id: u32, order_type: OrderType) -> AmountRange {
+        self.token_limits.get(&token_id).unwrap_or(&self.default_limits).get(order_type)
+    }
+}
+
+/// What create_order does with a near-duplicate of a recent order, see services::duplicates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateMode {
+    Off,
+    /// Create the order and return a warning naming the suspected original
+    #[default]
+    Flag,
+    /// Refuse the order until it is resubmitted with `confirm_duplicate`
+    Block,
+}
+
+impl DuplicateMode {
+    fn parse(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "off" => Self::Off,
+            "block" => Self::Block,
+            _ => Self::Flag,
+        }
+    }
 }
 
+/// Orders from the same address for the same amount and bank account within `window_seconds`
+/// of each other are suspected duplicates
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BlockchainConfig {
-    pub rpc_url: String,
-    pub contract_address: String,
-    pub proof_verifier_address: String,
-    pub usdc_address: String,
-    pub private_key: String,
+pub struct DuplicateConfig {
+    pub mode: DuplicateMode,
+    pub window_seconds: u64,
+}
+
+impl Default for DuplicateConfig {
+    fn default() -> Self {
+        Self {
+            mode: DuplicateMode::Flag,
+            window_seconds: 600,
+        }
+    }
+}
+
+/// Parse `token_id:order_type:min:max` entries separated by commas (ORDER_AMOUNT_LIMITS); `*` as
+/// the token sets the default, and tokens with an entry start from the default's other ranges
+fn parse_order_amounts(raw: &str) -> OrderAmountConfig {
+    let entries: Vec<(Option<u32>, OrderType, AmountRange)> = raw.split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.trim().split(':');
+            let token_id = match parts.next()?.trim() {
+                "*" => None,
+                token_id => Some(token_id.parse().ok()?),
+            };
+            let order_type = match parts.next()?.trim().to_ascii_lowercase().as_str() {
+                "bridge_in" => OrderType::BridgeIn,
+                "bridge_out" => OrderType::BridgeOut,
+                "transfer" => OrderType::Transfer,
+                _ => return None,
+            };
+            let min = parts.next()?.trim().parse().ok()?;
+            let max = parts.next()?.trim().parse().ok()?;
+            if parts.next().is_some() || (max > 0 && min > max) {
+                return None;
+            }
+            Some((token_id, order_type, AmountRange { min, max }))
+        })
+        .collect();
+
+    let mut config = OrderAmountConfig::default();
+    for (token_id, order_type, range) in &entries {
+        if token_id.is_none() {
+            *config.default_limits.get_mut(*order_type) = *range;
+        }
+    }
+    for (token_id, order_type, range) in entries {
+        if let Some(token_id) = token_id {
+            let defaults = config.default_limits;
+            *config.token_limits.entry(token_id).or_insert(defaults).get_mut(order_type) = range;
+        }
+    }
+    config
+}
+
+/// Parse `verifier_address:compression` pairs separated by commas (PROOF_CALLDATA_COMPRESSION_TARGETS)
+fn parse_target_compression(raw: &str) -> HashMap<String, CalldataCompression> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let (target, compression) = entry.trim().split_once(':')?;
+            let target = target.trim();
+            if target.is_empty() {
+                return None;
+            }
+            Some((target.to_ascii_lowercase(), CalldataCompression::parse(compression)?))
+        })
+        .collect()
+}
+
+/// Parse `source:target:token_id:threshold` entries separated by commas (BALANCE_ALERT_RULES)
+///
+/// The target may itself contain a colon (`filler:<filler_id>`), so the entry is split from both ends.
+fn parse_balance_alert_rules(raw: &str) -> Vec<BalanceAlertRule> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let mut tail = entry.trim().rsplitn(3, ':');
+            let threshold = tail.next()?.trim().parse().ok()?;
+            let token_id = tail.next()?.trim().parse().ok()?;
+            let (source, target) = tail.next()?.split_once(':')?;
+            let source = match source.trim().to_ascii_lowercase().as_str() {
+                "chain" => BalanceSource::Chain,
+                "ledger" => BalanceSource::Ledger,
+                _ => return None,
+            };
+            let target = target.trim();
+            if target.is_empty() {
+                return None;
+            }
+            Some(BalanceAlertRule { source, target: target.to_string(), token_id, threshold })
+        })
+        .collect()
+}
+
+/// Parse `filler_id:token` pairs separated by commas (FILLER_WS_TOKENS)
+fn parse_filler_tokens(raw: &str) -> HashMap<String, String> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let (filler_id, token) = entry.trim().split_once(':')?;
+            let (filler_id, token) = (filler_id.trim(), token.trim());
+            if filler_id.is_empty() || token.is_empty() {
+                return None;
+            }
+            Some((token.to_string(), filler_id.to_string()))
+        })
+        .collect()
+}
+
+/// Parse `filler_id:max_locks:max_value` entries separated by commas (FILLER_LIMITS)
+fn parse_filler_limits(raw: &str) -> HashMap<String, FillerLimits> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.trim().split(':');
+            let filler_id = parts.next()?.trim();
+            let max_concurrent_locks = parts.next()?.trim().parse().ok()?;
+            let max_locked_value = parts.next()?.trim().parse().ok()?;
+            if filler_id.is_empty() || parts.next().is_some() {
+                return None;
+            }
+            Some((filler_id.to_string(), FillerLimits { max_concurrent_locks, max_locked_value }))
+        })
+        .collect()
+}
+
+/// Parse `token_id:bank service:ratio_bps` entries separated by commas (COLLATERAL_CORRIDOR_RATIOS)
+fn parse_corridor_ratios(raw: &str) -> Vec<CorridorRatio> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let (token_id, rest) = entry.trim().split_once(':')?;
+            let (bank_service, ratio_bps) = rest.rsplit_once(':')?;
+            let bank_service = bank_service.trim();
+            (!bank_service.is_empty()).then_some(CorridorRatio {
+                token_id: token_id.trim().parse().ok()?,
+                bank_service: bank_service.to_string(),
+                ratio_bps: ratio_bps.trim().parse().ok()?,
+            })
+        })
+        .collect()
 }
 
+/// Token prices used to convert filler payouts between tokens
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BatchConfig {
-    pub interval_seconds: u64,
-    pub max_orders_per_batch: usize,
+pub struct RateConfig {
+    /// USD price per token, in micro-dollars (1_000_000 = $1.00), keyed by token id
+    pub usd_prices: HashMap<u32, u64>,
+    /// How far, in basis points, a token's price may move between an order's quote and its
+    /// lock before the filler has to accept a re-quote
+    pub max_slippage_bps: u32,
+}
+
+impl Default for RateConfig {
+    fn default() -> Self {
+        // USDC and PYUSD, both pegged to the dollar
+        Self {
+            usd_prices: HashMap::from([(1, 1_000_000), (2, 1_000_000)]),
+            max_slippage_bps: 50,
+        }
+    }
+}
+
+/// Parse `token_id:price` pairs, prices as decimal dollars (e.g. `1:1.0,2:0.9998`)
+fn parse_token_prices(raw: &str) -> HashMap<u32, u64> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let (token_id, price) = entry.trim().split_once(':')?;
+            Some((token_id.trim().parse().ok()?, parse_usd_price(price)?))
+        })
+        .collect()
+}
+
+/// Parse a positive decimal price with up to 6 decimals into micro-dollars (`0.9998` -> 999_800)
+pub fn parse_usd_price(raw: &str) -> Option<u64> {
+    let (whole, fraction) = raw.trim().split_once('.').unwrap_or((raw.trim(), ""));
+    if fraction.len() > 6 {
+        return None;
+    }
+    let whole: u64 = whole.parse().ok()?;
+    let fraction: u64 = if fraction.is_empty() { 0 } else { format!("{:0<6}", fraction).parse().ok()? };
+    let price = whole.checked_mul(1_000_000)?.checked_add(fraction)?;
+    (price > 0).then_some(price)
+}
+
+/// Parse `token_id:0x...` entries separated by commas (PERMIT_DOMAIN_SEPARATORS); entries
+/// that are not 32 bytes of hex are dropped
+fn parse_domain_separators(raw: &str) -> HashMap<u32, [u8; 32]> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let (token_id, separator) = entry.trim().split_once(':')?;
+            let mut bytes = [0u8; 32];
+            hex::decode_to_slice(separator.trim().trim_start_matches("0x"), &mut bytes).ok()?;
+            Some((token_id.trim().parse().ok()?, bytes))
+        })
+        .collect()
+}
+
+/// Parse `chain_id:mode` entries separated by commas (RELAYER_FINALITY), e.g. `1:finalized,10:safe`
+fn parse_finality(raw: &str) -> HashMap<u64, FinalityMode> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let (chain_id, mode) = entry.trim().split_once(':')?;
+            Some((chain_id.trim().parse().ok()?, FinalityMode::parse(mode)?))
+        })
+        .collect()
+}
+
+/// Parse `token_id:amount` entries separated by commas (BATCH_BRIDGE_OUT_CAPS, ORDER_REVIEW_THRESHOLDS); 0 amounts are dropped
+fn parse_batch_caps(raw: &str) -> HashMap<u32, u64> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let (token_id, max) = entry.trim().split_once(':')?;
+            let max: u64 = max.trim().parse().ok()?;
+            (max > 0).then_some((token_id.trim().parse().ok()?, max))
+        })
+        .collect()
+}
+
+/// Parse `bank service:days` entries separated by commas (RETENTION_BANK_SERVICE_DAYS); names are lowercased
+fn parse_retention_days(raw: &str) -> HashMap<String, u32> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let (service, days) = entry.rsplit_once(':')?;
+            let service = service.trim();
+            (!service.is_empty()).then_some((service.to_lowercase(), days.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Parse comma-separated tier thresholds (MATCH_PRIORITY_FEE_TIERS), sorted and deduplicated
+fn parse_priority_fee_tiers(raw: &str) -> Vec<u64> {
+    let mut tiers: Vec<u64> = raw.split(',').filter_map(|tier| tier.trim().parse().ok()).filter(|tier| *tier > 0).collect();
+    tiers.sort_unstable();
+    tiers.dedup();
+    tiers
+}
+
+/// Parse `phase:seconds` entries separated by commas (SLO_P95_TARGETS), e.g. `discovery:600,settle:3600`
+fn parse_slo_targets(raw: &str) -> SloTargets {
+    let mut targets = SloTargets::default();
+    for entry in raw.split(',') {
+        let Some((phase, seconds)) = entry.trim().split_once(':') else { continue };
+        let Ok(seconds) = seconds.trim().parse() else { continue };
+        match phase.trim().to_ascii_lowercase().as_str() {
+            "discovery" => targets.discovery_seconds = Some(seconds),
+            "locked" => targets.locked_seconds = Some(seconds),
+            "mark_paid" | "markpaid" => targets.mark_paid_seconds = Some(seconds),
+            "settle" => targets.settle_seconds = Some(seconds),
+            _ => {}
+        }
+    }
+    targets
+}
+
+/// Parse `token_id:per_address_daily:global_daily` entries separated by commas (WITHDRAWAL_TOKEN_LIMITS)
+fn parse_withdrawal_limits(raw: &str) -> HashMap<u32, WithdrawalLimits> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.trim().split(':');
+            let token_id = parts.next()?.trim().parse().ok()?;
+            let per_address_daily = parts.next()?.trim().parse().ok()?;
+            let global_daily = parts.next()?.trim().parse().ok()?;
+            if parts.next().is_some() {
+                return None;
+            }
+            Some((token_id, WithdrawalLimits { per_address_daily, global_daily }))
+        })
+        .collect()
 }
 
 impl Config {
     pub fn from_env() -> anyhow::Result<Self> {
+        let profile = match env::var("DEPLOYMENT_PROFILE") {
+            Ok(raw) => DeploymentProfile::parse(&raw)
+                .ok_or_else(|| anyhow::anyhow!("DEPLOYMENT_PROFILE must be dev, staging or prod, got '{}'", raw))?,
+            Err(_) => DeploymentProfile::default(),
+        };
+
+        let blob_store = match env::var("BLOB_STORE").unwrap_or_else(|_| "fs".to_string()).trim() {
+            "fs" => BlobStoreConfig::Filesystem {
+                path: env::var("BLOB_STORE_PATH").unwrap_or_else(|_| "./blobs".to_string()),
+            },
+            "s3" => {
+                let required = |name: &str| env::var(name)
+                    .ok()
+                    .filter(|value| !value.is_empty())
+                    .ok_or_else(|| anyhow::anyhow!("{} required for the s3 blob store", name));
+                BlobStoreConfig::S3(S3Config {
+                    endpoint: required("S3_ENDPOINT")?,
+                    bucket: required("S3_BUCKET")?,
+                    region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+                    prefix: env::var("S3_PREFIX").unwrap_or_default(),
+                    access_key_id: required("S3_ACCESS_KEY_ID")?,
+                    secret_access_key: required("S3_SECRET_ACCESS_KEY")?,
+                })
+            }
+            "memory" => BlobStoreConfig::Memory,
+            other => return Err(anyhow::anyhow!("BLOB_STORE must be fs, s3 or memory, got '{}'", other)),
+        };
+
+        let screening_provider = match env::var("SCREENING_PROVIDER").unwrap_or_else(|_| "none".to_string()).trim() {
+            "none" | "" => ScreeningProviderConfig::None,
+            "static" => ScreeningProviderConfig::Static {
+                addresses: env::var("SCREENING_DENYLIST")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|address| address.trim().to_lowercase())
+                    .filter(|address| !address.is_empty())
+                    .collect(),
+            },
+            "http" => ScreeningProviderConfig::Http {
+                url: env::var("SCREENING_API_URL")
+                    .ok()
+                    .filter(|url| !url.is_empty())
+                    .ok_or_else(|| anyhow::anyhow!("SCREENING_API_URL required for the http screening provider"))?,
+                api_key: env::var("SCREENING_API_KEY").unwrap_or_default(),
+            },
+            other => return Err(anyhow::anyhow!("SCREENING_PROVIDER must be none, static or http, got '{}'", other)),
+        };
+
+        let analytics_enabled = env::var("ANALYTICS_ENABLED")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse()
+            .unwrap_or(false);
+        let analytics_sink = match env::var("ANALYTICS_SINK").unwrap_or_else(|_| "stdout".to_string()).trim() {
+            "stdout" | "" => AnalyticsSinkConfig::Stdout,
+            "http" => AnalyticsSinkConfig::Http {
+                url: env::var("ANALYTICS_COLLECTOR_URL")
+                    .ok()
+                    .filter(|url| !url.is_empty())
+                    .ok_or_else(|| anyhow::anyhow!("ANALYTICS_COLLECTOR_URL required for the http analytics sink"))?,
+            },
+            other => return Err(anyhow::anyhow!("ANALYTICS_SINK must be stdout or http, got '{}'", other)),
+        };
+        let analytics_hash_salt = env::var("ANALYTICS_HASH_SALT").unwrap_or_default();
+        if analytics_enabled && analytics_hash_salt.is_empty() {
+            return Err(anyhow::anyhow!("ANALYTICS_HASH_SALT required when ANALYTICS_ENABLED is set"));
+        }
+
         Ok(Config {
+            profile,
             api: ApiConfig {
                 port: env::var("SERVER_PORT")
                     .or_else(|_| env::var("PORT"))
                     .unwrap_or_else(|_| "8080".to_string())
                     .parse()
                     .unwrap_or(8080),
+                admin_token: env::var("ADMIN_API_TOKEN").ok().filter(|token| !token.is_empty()),
+                cors_allowed_origins: env::var("CORS_ALLOWED_ORIGINS")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|origin| origin.trim().to_string())
+                    .filter(|origin| !origin.is_empty())
+                    .collect(),
+                bulk_account_max_entries: env::var("ACCOUNTS_BULK_MAX_ENTRIES")
+                    .unwrap_or_else(|_| "1000".to_string())
+                    .parse()
+                    .unwrap_or(1000),
             },
             database: DatabaseConfig {
                 url: env::var("DATABASE_URL")
@@ -59,8 +1482,15 @@ impl Config {
                     .map_err(|_| anyhow::anyhow!("PROOF_VERIFIER_CONTRACT environment variable required"))?,
                 usdc_address: env::var("USDC_CONTRACT")
                     .map_err(|_| anyhow::anyhow!("USDC_CONTRACT environment variable required"))?,
-                private_key: env::var("PRIVATE_KEY")
-                    .map_err(|_| anyhow::anyhow!("PRIVATE_KEY environment variable required"))?,
+                // Only required by the env signer, see SignerConfig
+                private_key: env::var("PRIVATE_KEY").unwrap_or_default(),
+                deposit_reference_salt: env::var("DEPOSIT_REFERENCE_SALT")
+                    .unwrap_or_else(|_| "vapor-deposit".to_string()),
+                read_cache_seconds: env::var("CHAIN_READ_CACHE_SECONDS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(5),
+                permit_domain_separators: parse_domain_separators(&env::var("PERMIT_DOMAIN_SEPARATORS").unwrap_or_default()),
             },
             batch: BatchConfig {
                 interval_seconds: env::var("BATCH_INTERVAL_SECONDS")
@@ -71,15 +1501,501 @@ impl Config {
                     .unwrap_or_else(|_| "100".to_string())
                     .parse()
                     .unwrap_or(100),
+                precompute_proof_batches: env::var("PRECOMPUTE_PROOF_BATCHES")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()
+                    .unwrap_or(10),
+                recovery_policy: BatchRecoveryPolicy::parse(&env::var("BATCH_RECOVERY_POLICY").unwrap_or_default()),
+                bridge_out_caps: parse_batch_caps(&env::var("BATCH_BRIDGE_OUT_CAPS").unwrap_or_default()),
+                merkle_node_cache_capacity: env::var("MERKLE_NODE_CACHE_CAPACITY")
+                    .unwrap_or_else(|_| "262144".to_string())
+                    .parse()
+                    .unwrap_or(262144),
+                verify_cache_seconds: env::var("PROOF_VERIFY_CACHE_SECONDS")
+                    .unwrap_or_else(|_| "300".to_string())
+                    .parse()
+                    .unwrap_or(300),
+                verify_cache_capacity: env::var("PROOF_VERIFY_CACHE_CAPACITY")
+                    .unwrap_or_else(|_| "10000".to_string())
+                    .parse()
+                    .unwrap_or(10000),
+                invariant_checks: env::var("INVARIANT_CHECKS")
+                    .map(|value| value == "true")
+                    .unwrap_or(cfg!(debug_assertions) || profile == DeploymentProfile::Staging),
+            },
+            filler: FillerConfig {
+                ws_tokens: parse_filler_tokens(&env::var("FILLER_WS_TOKENS").unwrap_or_default()),
+                lock_ttl_seconds: env::var("FILLER_LOCK_TTL_SECONDS")
+                    .unwrap_or_else(|_| "1800".to_string())
+                    .parse()
+                    .unwrap_or(1800),
+                lock_expiry_warning_seconds: env::var("FILLER_LOCK_EXPIRY_WARNING_SECONDS")
+                    .unwrap_or_else(|_| "300".to_string())
+                    .parse()
+                    .unwrap_or(300),
+                default_limits: FillerLimits {
+                    max_concurrent_locks: env::var("FILLER_MAX_CONCURRENT_LOCKS")
+                        .unwrap_or_else(|_| "0".to_string())
+                        .parse()
+                        .unwrap_or(0),
+                    max_locked_value: env::var("FILLER_MAX_LOCKED_VALUE")
+                        .unwrap_or_else(|_| "0".to_string())
+                        .parse()
+                        .unwrap_or(0),
+                },
+                limits: parse_filler_limits(&env::var("FILLER_LIMITS").unwrap_or_default()),
+                match_policy: MatchPolicyKind::parse(&env::var("MATCH_POLICY").unwrap_or_default()),
+                priority_fee_tiers: parse_priority_fee_tiers(&env::var("MATCH_PRIORITY_FEE_TIERS").unwrap_or_default()),
+                match_max_boosts: env::var("MATCH_REQUEUE_MAX_BOOSTS")
+                    .unwrap_or_else(|_| "3".to_string())
+                    .parse()
+                    .unwrap_or(3),
+                lock_window_seconds: env::var("FILLER_LOCK_WINDOW_SECONDS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                lock_window_policy: LockWindowPolicy::parse(&env::var("FILLER_LOCK_WINDOW_POLICY").unwrap_or_default()),
+            },
+            signer: SignerConfig {
+                kind: env::var("SIGNER_TYPE").unwrap_or_else(|_| "env".to_string()),
+                keystore_path: env::var("SIGNER_KEYSTORE_PATH").ok(),
+                keystore_password: env::var("SIGNER_KEYSTORE_PASSWORD").ok(),
+                remote_url: env::var("SIGNER_REMOTE_URL").ok(),
+                remote_key_id: env::var("SIGNER_REMOTE_KEY_ID").ok(),
+                remote_auth_token: env::var("SIGNER_REMOTE_TOKEN").ok(),
+                remote_address: env::var("SIGNER_REMOTE_ADDRESS").ok(),
+            },
+            sla: SlaConfig {
+                discovery_seconds: env::var("SLA_DISCOVERY_SECONDS")
+                    .unwrap_or_else(|_| "86400".to_string())
+                    .parse()
+                    .unwrap_or(86400),
+                mark_paid_seconds: env::var("SLA_MARK_PAID_SECONDS")
+                    .unwrap_or_else(|_| "7200".to_string())
+                    .parse()
+                    .unwrap_or(7200),
+                sweep_interval_seconds: env::var("SLA_SWEEP_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .unwrap_or(30),
+                intent_seconds: env::var("SLA_INTENT_SECONDS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .unwrap_or(3600),
+                intent_revival_seconds: env::var("SLA_INTENT_REVIVAL_SECONDS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .unwrap_or(3600),
+                intent_webhook_url: env::var("SLA_INTENT_WEBHOOK_URL").ok().filter(|url| !url.is_empty()),
+            },
+            archive: ArchiveConfig {
+                retain_batches: env::var("ARCHIVE_RETAIN_BATCHES")
+                    .unwrap_or_else(|_| "50".to_string())
+                    .parse()
+                    .unwrap_or(50),
+            },
+            blob_store,
+            telemetry: TelemetryConfig {
+                otlp_endpoint: env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().filter(|endpoint| !endpoint.is_empty()),
+                service_name: env::var("OTEL_SERVICE_NAME")
+                    .unwrap_or_else(|_| "vapor-backend".to_string()),
+            },
+            withdrawal: WithdrawalConfig {
+                default_limits: WithdrawalLimits {
+                    per_address_daily: env::var("WITHDRAWAL_DAILY_LIMIT_PER_ADDRESS")
+                        .unwrap_or_else(|_| "0".to_string())
+                        .parse()
+                        .unwrap_or(0),
+                    global_daily: env::var("WITHDRAWAL_DAILY_LIMIT_GLOBAL")
+                        .unwrap_or_else(|_| "0".to_string())
+                        .parse()
+                        .unwrap_or(0),
+                },
+                token_limits: parse_withdrawal_limits(&env::var("WITHDRAWAL_TOKEN_LIMITS").unwrap_or_default()),
+                allowlist_only: env::var("WITHDRAWAL_ALLOWLIST_ONLY")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .unwrap_or(false),
+            },
+            order_amounts: parse_order_amounts(&env::var("ORDER_AMOUNT_LIMITS").unwrap_or_default()),
+            order_duplicates: DuplicateConfig {
+                mode: DuplicateMode::parse(&env::var("ORDER_DUPLICATE_MODE").unwrap_or_default()),
+                window_seconds: env::var("ORDER_DUPLICATE_WINDOW_SECONDS")
+                    .unwrap_or_else(|_| "600".to_string())
+                    .parse()
+                    .unwrap_or(600),
+            },
+            rates: {
+                let defaults = RateConfig::default();
+                RateConfig {
+                    usd_prices: env::var("TOKEN_USD_PRICES")
+                        .map(|raw| parse_token_prices(&raw))
+                        .unwrap_or(defaults.usd_prices),
+                    max_slippage_bps: env::var("RATE_MAX_SLIPPAGE_BPS")
+                        .ok()
+                        .and_then(|bps| bps.parse().ok())
+                        .unwrap_or(defaults.max_slippage_bps),
+                }
+            },
+            relayer: RelayerScanConfig {
+                range_blocks: env::var("RELAYER_SCAN_RANGE_BLOCKS")
+                    .unwrap_or_else(|_| "500".to_string())
+                    .parse()
+                    .unwrap_or(500),
+                max_concurrent_ranges: env::var("RELAYER_MAX_CONCURRENT_RANGES")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()
+                    .unwrap_or(10),
+                finality: env::var("RELAYER_FINALITY")
+                    .map(|raw| parse_finality(&raw))
+                    .unwrap_or_default(),
+                confirmation_depth: env::var("RELAYER_CONFIRMATION_DEPTH")
+                    .ok()
+                    .and_then(|depth| depth.parse().ok())
+                    .unwrap_or(0),
+            },
+            market: MarketConfig {
+                refresh_seconds: env::var("MARKET_SUMMARY_REFRESH_SECONDS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .unwrap_or(30),
+                rate_limit_per_minute: env::var("MARKET_SUMMARY_RATE_LIMIT_PER_MINUTE")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .unwrap_or(60),
+                throttle_saturated_corridors: env::var("MARKET_THROTTLE_SATURATED_CORRIDORS")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .unwrap_or(false),
+            },
+            explorer: ExplorerConfig {
+                rate_limit_per_minute: env::var("EXPLORER_RATE_LIMIT_PER_MINUTE")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()
+                    .unwrap_or(10),
+            },
+            manifests: ManifestConfig {
+                push_url: env::var("MANIFEST_PUSH_URL").ok().filter(|url| !url.is_empty()),
+                push_kind: ManifestPushKind::parse(&env::var("MANIFEST_PUSH_KIND").unwrap_or_default()),
+            },
+            collateral: {
+                let defaults = CollateralConfig::default();
+                CollateralConfig {
+                    lock_threshold: env::var("COLLATERAL_LOCK_THRESHOLD")
+                        .ok()
+                        .and_then(|amount| amount.parse().ok())
+                        .unwrap_or(defaults.lock_threshold),
+                    ratio_bps: env::var("COLLATERAL_RATIO_BPS")
+                        .ok()
+                        .and_then(|bps| bps.parse().ok())
+                        .unwrap_or(defaults.ratio_bps),
+                    corridor_ratios: parse_corridor_ratios(&env::var("COLLATERAL_CORRIDOR_RATIOS").unwrap_or_default()),
+                    from_block: env::var("COLLATERAL_FROM_BLOCK")
+                        .ok()
+                        .and_then(|block| block.parse().ok())
+                        .unwrap_or(defaults.from_block),
+                    cache_seconds: env::var("COLLATERAL_CACHE_SECONDS")
+                        .ok()
+                        .and_then(|seconds| seconds.parse().ok())
+                        .unwrap_or(defaults.cache_seconds),
+                    slash_bps: env::var("COLLATERAL_SLASH_BPS")
+                        .ok()
+                        .and_then(|bps| bps.parse().ok())
+                        .unwrap_or(defaults.slash_bps),
+                    flag_after_expiries: env::var("COLLATERAL_FLAG_AFTER_EXPIRIES")
+                        .ok()
+                        .and_then(|expiries| expiries.parse().ok())
+                        .unwrap_or(defaults.flag_after_expiries),
+                }
+            },
+            quotes: QuoteConfig {
+                ttl_seconds: env::var("QUOTE_TTL_SECONDS")
+                    .ok()
+                    .and_then(|seconds| seconds.parse().ok())
+                    .unwrap_or(QuoteConfig::default().ttl_seconds),
+            },
+            jobs: {
+                let defaults = JobsConfig::default();
+                JobsConfig {
+                    poll_interval_seconds: env::var("JOBS_POLL_INTERVAL_SECONDS")
+                        .ok()
+                        .and_then(|seconds| seconds.parse().ok())
+                        .unwrap_or(defaults.poll_interval_seconds),
+                    retry_base_seconds: env::var("JOBS_RETRY_BASE_SECONDS")
+                        .ok()
+                        .and_then(|seconds| seconds.parse().ok())
+                        .unwrap_or(defaults.retry_base_seconds),
+                    retry_max_seconds: env::var("JOBS_RETRY_MAX_SECONDS")
+                        .ok()
+                        .and_then(|seconds| seconds.parse().ok())
+                        .unwrap_or(defaults.retry_max_seconds),
+                    max_attempts: env::var("JOBS_MAX_ATTEMPTS")
+                        .ok()
+                        .and_then(|attempts| attempts.parse().ok())
+                        .unwrap_or(defaults.max_attempts),
+                    payment_verifier_url: env::var("PAYMENT_VERIFIER_URL").ok().filter(|url| !url.is_empty()),
+                    settlement_check_seconds: env::var("SETTLEMENT_CHECK_SECONDS")
+                        .ok()
+                        .and_then(|seconds| seconds.parse().ok())
+                        .unwrap_or(defaults.settlement_check_seconds),
+                }
+            },
+            request_limits: {
+                let defaults = RequestLimitsConfig::default();
+                RequestLimitsConfig {
+                    read_timeout_seconds: env::var("REQUEST_READ_TIMEOUT_SECONDS")
+                        .ok()
+                        .and_then(|seconds| seconds.parse().ok())
+                        .unwrap_or(defaults.read_timeout_seconds),
+                    write_timeout_seconds: env::var("REQUEST_WRITE_TIMEOUT_SECONDS")
+                        .ok()
+                        .and_then(|seconds| seconds.parse().ok())
+                        .unwrap_or(defaults.write_timeout_seconds),
+                    long_timeout_seconds: env::var("REQUEST_LONG_TIMEOUT_SECONDS")
+                        .ok()
+                        .and_then(|seconds| seconds.parse().ok())
+                        .unwrap_or(defaults.long_timeout_seconds),
+                    max_body_bytes: env::var("REQUEST_MAX_BODY_BYTES")
+                        .ok()
+                        .and_then(|bytes| bytes.parse().ok())
+                        .unwrap_or(defaults.max_body_bytes),
+                }
+            },
+            http_cache: {
+                let defaults = HttpCacheConfig::default();
+                HttpCacheConfig {
+                    order_max_age_seconds: env::var("HTTP_CACHE_ORDER_MAX_AGE_SECONDS")
+                        .ok()
+                        .and_then(|seconds| seconds.parse().ok())
+                        .unwrap_or(defaults.order_max_age_seconds),
+                    proof_max_age_seconds: env::var("HTTP_CACHE_PROOF_MAX_AGE_SECONDS")
+                        .ok()
+                        .and_then(|seconds| seconds.parse().ok())
+                        .unwrap_or(defaults.proof_max_age_seconds),
+                }
+            },
+            proof_submission: ProofSubmissionConfig {
+                default_compression: CalldataCompression::parse(&env::var("PROOF_CALLDATA_COMPRESSION").unwrap_or_default())
+                    .unwrap_or_default(),
+                target_compression: parse_target_compression(&env::var("PROOF_CALLDATA_COMPRESSION_TARGETS").unwrap_or_default()),
+                cost_weighting: CostWeighting::parse(&env::var("PROOF_COST_WEIGHTING").unwrap_or_default()),
+                gas_price_gwei: env::var("PROOF_GAS_PRICE_GWEI")
+                    .ok()
+                    .and_then(|price| price.parse().ok())
+                    .unwrap_or(20),
+                eth_usd_price: env::var("PROOF_ETH_USD_PRICE")
+                    .ok()
+                    .and_then(|price| price.parse().ok())
+                    .unwrap_or(3_000_000_000),
+            },
+            order_queue: {
+                let defaults = OrderQueueConfig::default();
+                OrderQueueConfig {
+                    redis_url: env::var("ORDER_QUEUE_REDIS_URL").ok().filter(|url| !url.is_empty()),
+                    stream: env::var("ORDER_QUEUE_STREAM").unwrap_or(defaults.stream),
+                    group: env::var("ORDER_QUEUE_GROUP").unwrap_or(defaults.group),
+                    consumer: env::var("ORDER_QUEUE_CONSUMER").unwrap_or(defaults.consumer),
+                    batch_size: env::var("ORDER_QUEUE_BATCH_SIZE")
+                        .ok()
+                        .and_then(|size| size.parse().ok())
+                        .unwrap_or(defaults.batch_size),
+                }
+            },
+            balance_alerts: BalanceAlertConfig {
+                rules: parse_balance_alert_rules(&env::var("BALANCE_ALERT_RULES").unwrap_or_default()),
+                interval_seconds: env::var("BALANCE_ALERT_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .unwrap_or(60),
+                webhook_url: env::var("BALANCE_ALERT_WEBHOOK_URL").ok().filter(|url| !url.is_empty()),
+                email_to: env::var("BALANCE_ALERT_EMAIL").ok().filter(|email| !email.is_empty()),
+                signer_min_submissions: env::var("SIGNER_MIN_SUBMISSIONS")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()
+                    .unwrap_or(10),
+            },
+            claims: ClaimConfig {
+                reconcile_interval_seconds: env::var("CLAIM_RECONCILE_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "300".to_string())
+                    .parse()
+                    .unwrap_or(300),
+                reconcile_from_block: env::var("CLAIM_RECONCILE_FROM_BLOCK")
+                    .ok()
+                    .and_then(|block| block.parse().ok())
+                    .unwrap_or(0),
+                fee_bps: env::var("CLAIM_FEE_BPS")
+                    .ok()
+                    .and_then(|bps| bps.parse().ok())
+                    .unwrap_or(0),
+                fee_gas_surcharge: env::var("CLAIM_FEE_GAS_SURCHARGE")
+                    .ok()
+                    .and_then(|amount| amount.parse().ok())
+                    .unwrap_or(0),
+                relay_enabled: env::var("CLAIM_RELAY_ENABLED")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .unwrap_or(false),
+                relay_base_gas: env::var("CLAIM_RELAY_BASE_GAS")
+                    .ok()
+                    .and_then(|gas| gas.parse().ok())
+                    .unwrap_or(60_000),
+                relay_gas_per_claim: env::var("CLAIM_RELAY_GAS_PER_CLAIM")
+                    .ok()
+                    .and_then(|gas| gas.parse().ok())
+                    .unwrap_or(45_000),
+                relay_gas_price_gwei: env::var("CLAIM_RELAY_GAS_PRICE_GWEI")
+                    .ok()
+                    .and_then(|price| price.parse().ok())
+                    .unwrap_or(20),
+                relay_eth_usd_price: env::var("CLAIM_RELAY_ETH_USD_PRICE")
+                    .ok()
+                    .and_then(|price| parse_usd_price(&price))
+                    .unwrap_or(3_000_000_000),
+                relay_fee_markup_bps: env::var("CLAIM_RELAY_FEE_MARKUP_BPS")
+                    .ok()
+                    .and_then(|bps| bps.parse().ok())
+                    .unwrap_or(1_000),
+            },
+            registry: RegistryConfig {
+                cache_ttl_seconds: env::var("REGISTRY_CACHE_TTL_SECONDS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .unwrap_or(60),
+            },
+            slo: SloConfig {
+                window_hours: env::var("SLO_WINDOW_HOURS")
+                    .unwrap_or_else(|_| "24".to_string())
+                    .parse()
+                    .unwrap_or(24),
+                refresh_seconds: env::var("SLO_REFRESH_SECONDS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .unwrap_or(60),
+                p95_targets: parse_slo_targets(&env::var("SLO_P95_TARGETS").unwrap_or_default()),
+            },
+            reconciliation: ReconciliationConfig {
+                hour_utc: env::var("RECONCILIATION_HOUR_UTC")
+                    .ok()
+                    .and_then(|hour| hour.parse().ok())
+                    .filter(|hour| *hour < 24)
+                    .unwrap_or(3),
+                retain_reports: env::var("RECONCILIATION_RETAIN_REPORTS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .unwrap_or(30),
+            },
+            retention: RetentionConfig {
+                bank_details_days: env::var("RETENTION_BANK_DETAILS_DAYS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .unwrap_or(30),
+                bank_service_days: parse_retention_days(&env::var("RETENTION_BANK_SERVICE_DAYS").unwrap_or_default()),
+                sweep_interval_seconds: env::var("RETENTION_SWEEP_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .unwrap_or(3600),
+            },
+            backup: BackupConfig {
+                interval_seconds: env::var("BACKUP_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                retain: env::var("BACKUP_RETAIN")
+                    .unwrap_or_else(|_| "7".to_string())
+                    .parse()
+                    .unwrap_or(7),
+            },
+            handover: HandoverConfig {
+                lease_seconds: env::var("WRITER_LEASE_SECONDS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .unwrap_or(30),
+                instance_id: ["INSTANCE_ID", "HOSTNAME"].iter()
+                    .filter_map(|name| env::var(name).ok())
+                    .find(|id| !id.trim().is_empty())
+                    .unwrap_or_else(|| "vapor".to_string()),
+            },
+            screening: ScreeningConfig {
+                provider: screening_provider,
+                action: ScreeningAction::parse(&env::var("SCREENING_ACTION").unwrap_or_default()),
+            },
+            analytics: AnalyticsConfig {
+                enabled: analytics_enabled,
+                sink: analytics_sink,
+                sample_rate: env::var("ANALYTICS_SAMPLE_RATE")
+                    .unwrap_or_else(|_| "1.0".to_string())
+                    .parse::<f64>()
+                    .unwrap_or(1.0)
+                    .clamp(0.0, 1.0),
+                hash_salt: analytics_hash_salt,
+            },
+            diagnostics: DiagnosticsConfig {
+                slow_query_ms: env::var("DB_SLOW_QUERY_MS")
+                    .ok()
+                    .and_then(|ms| ms.parse().ok())
+                    .unwrap_or(DiagnosticsConfig::default().slow_query_ms),
+                top_operations: env::var("DB_DIAGNOSTICS_TOP_N")
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(DiagnosticsConfig::default().top_operations),
+            },
+            order_review: OrderReviewConfig {
+                thresholds: parse_batch_caps(&env::var("ORDER_REVIEW_THRESHOLDS").unwrap_or_default()),
+            },
+            attestations: AttestationConfig {
+                ttl_seconds: env::var("ATTESTATION_TTL_SECONDS")
+                    .ok()
+                    .and_then(|seconds| seconds.parse().ok())
+                    .unwrap_or(AttestationConfig::default().ttl_seconds),
+            },
+            notifications: NotificationConfig {
+                enabled: env::var("NOTIFICATIONS_ENABLED")
+                    .map(|value| value == "true")
+                    .unwrap_or(false),
+                email_from: env::var("NOTIFICATIONS_EMAIL_FROM")
+                    .ok()
+                    .filter(|from| !from.is_empty())
+                    .unwrap_or_else(|| NotificationConfig::default().email_from),
             },
         })
     }
+
+    /// Refuse to start with features the profile does not allow
+    ///
+    /// All violations are reported together so a deploy can be fixed in one go.
+    pub fn check_profile(&self) -> anyhow::Result<()> {
+        let profile = self.profile;
+        let mut violations = Vec::new();
+        // The mock prover is the only prover so far
+        if !profile.allows_mock_prover() {
+            violations.push("the mock prover is not allowed".to_string());
+        }
+        if !profile.allows_simulated_failures() && cfg!(feature = "fault-injection") {
+            violations.push("fault-injection builds are not allowed".to_string());
+        }
+        if !profile.allows_permissive_cors() && self.api.cors_allowed_origins.iter().any(|origin| origin == "*") {
+            violations.push("CORS_ALLOWED_ORIGINS cannot allow any origin".to_string());
+        }
+        if !profile.allows_memory_blob_store() && self.blob_store == BlobStoreConfig::Memory {
+            violations.push("BLOB_STORE=memory loses proof artifacts and snapshots on restart".to_string());
+        }
+        if !profile.allows_open_admin() && self.api.admin_token.as_deref().is_none_or(str::is_empty) {
+            violations.push("ADMIN_API_TOKEN is required".to_string());
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Invalid configuration for the {} profile: {}", profile.as_str(), violations.join("; ")))
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
-            api: ApiConfig { port: 8080 },
+            profile: DeploymentProfile::Dev,
+            api: ApiConfig { port: 8080, admin_token: None, cors_allowed_origins: Vec::new(), bulk_account_max_entries: 1000 },
             database: DatabaseConfig { 
                 url: ":memory:".to_string() 
             },
@@ -89,11 +2005,310 @@ impl Default for Config {
                 proof_verifier_address: "0x0000000000000000000000000000000000000001".to_string(),
                 usdc_address: "0x0000000000000000000000000000000000000002".to_string(),
                 private_key: "0x0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+                deposit_reference_salt: "vapor-deposit".to_string(),
+                read_cache_seconds: 5,
+                permit_domain_separators: HashMap::new(),
             },
             batch: BatchConfig {
                 interval_seconds: 60,
                 max_orders_per_batch: 100,
+                precompute_proof_batches: 10,
+                recovery_policy: BatchRecoveryPolicy::Resume,
+                bridge_out_caps: HashMap::new(),
+                merkle_node_cache_capacity: 262144,
+                verify_cache_seconds: 300,
+                verify_cache_capacity: 10000,
+                invariant_checks: cfg!(debug_assertions),
+            },
+            filler: FillerConfig {
+                ws_tokens: HashMap::new(),
+                lock_ttl_seconds: 1800,
+                lock_expiry_warning_seconds: 300,
+                default_limits: FillerLimits {
+                    max_concurrent_locks: 0,
+                    max_locked_value: 0,
+                },
+                limits: HashMap::new(),
+                match_policy: MatchPolicyKind::Fifo,
+                priority_fee_tiers: Vec::new(),
+                match_max_boosts: 3,
+                lock_window_seconds: 0,
+                lock_window_policy: LockWindowPolicy::Reputation,
             },
+            signer: SignerConfig {
+                kind: "env".to_string(),
+                keystore_path: None,
+                keystore_password: None,
+                remote_url: None,
+                remote_key_id: None,
+                remote_auth_token: None,
+                remote_address: None,
+            },
+            sla: SlaConfig {
+                discovery_seconds: 86400,
+                mark_paid_seconds: 7200,
+                sweep_interval_seconds: 30,
+                intent_seconds: 3600,
+                intent_revival_seconds: 3600,
+                intent_webhook_url: None,
+            },
+            archive: ArchiveConfig {
+                retain_batches: 50,
+            },
+            blob_store: BlobStoreConfig::Memory,
+            telemetry: TelemetryConfig {
+                otlp_endpoint: None,
+                service_name: "vapor-backend".to_string(),
+            },
+            withdrawal: WithdrawalConfig::default(),
+            order_amounts: OrderAmountConfig::default(),
+            order_duplicates: DuplicateConfig::default(),
+            rates: RateConfig::default(),
+            relayer: RelayerScanConfig {
+                range_blocks: 500,
+                max_concurrent_ranges: 10,
+                finality: HashMap::new(),
+                confirmation_depth: 0,
+            },
+            market: MarketConfig {
+                refresh_seconds: 30,
+                rate_limit_per_minute: 60,
+                throttle_saturated_corridors: false,
+            },
+            explorer: ExplorerConfig {
+                rate_limit_per_minute: 10,
+            },
+            manifests: ManifestConfig::default(),
+            request_limits: RequestLimitsConfig::default(),
+            http_cache: HttpCacheConfig::default(),
+            jobs: JobsConfig::default(),
+            collateral: CollateralConfig::default(),
+            quotes: QuoteConfig::default(),
+            proof_submission: ProofSubmissionConfig::default(),
+            order_queue: OrderQueueConfig::default(),
+            balance_alerts: BalanceAlertConfig::default(),
+            claims: ClaimConfig::default(),
+            registry: RegistryConfig::default(),
+            slo: SloConfig::default(),
+            reconciliation: ReconciliationConfig::default(),
+            retention: RetentionConfig::default(),
+            backup: BackupConfig::default(),
+            handover: HandoverConfig::default(),
+            screening: ScreeningConfig::default(),
+            analytics: AnalyticsConfig::default(),
+            attestations: AttestationConfig::default(),
+            order_review: OrderReviewConfig::default(),
+            diagnostics: DiagnosticsConfig::default(),
+            notifications: NotificationConfig::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_filler_tokens() {
+        let tokens = parse_filler_tokens("filler_1:abc, filler_2:def,broken,:nofiller,nofiller:");
+
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens.get("abc"), Some(&"filler_1".to_string()));
+        assert_eq!(tokens.get("def"), Some(&"filler_2".to_string()));
+        assert!(parse_filler_tokens("").is_empty());
+    }
+
+    #[test]
+    fn test_filler_for_token() {
+        let mut config = Config::default();
+        config.filler.ws_tokens = parse_filler_tokens("filler_1:secret");
+
+        assert_eq!(config.filler.filler_for_token("secret"), Some("filler_1"));
+        assert_eq!(config.filler.filler_for_token("wrong"), None);
+    }
+
+    #[test]
+    fn test_parse_filler_limits() {
+        let limits = parse_filler_limits("filler_1:3:5000000, filler_2:0:100,broken,filler_3:x:1,filler_4:1:2:3");
+
+        assert_eq!(limits.len(), 2);
+        assert_eq!(limits["filler_1"], FillerLimits { max_concurrent_locks: 3, max_locked_value: 5_000_000 });
+        assert_eq!(limits["filler_2"], FillerLimits { max_concurrent_locks: 0, max_locked_value: 100 });
+
+        let mut config = Config::default();
+        config.filler.limits = limits;
+        assert_eq!(config.filler.limits_for("filler_1").max_concurrent_locks, 3);
+        assert_eq!(config.filler.limits_for("unknown"), config.filler.default_limits);
+    }
+
+    #[test]
+    fn test_parse_corridor_ratios() {
+        let ratios = parse_corridor_ratios("1:PayPal Hong Kong:5000, 2:Wise:2500,broken,1::100,x:Wise:1");
+        assert_eq!(ratios.len(), 2);
+        assert_eq!(ratios[0], CorridorRatio { token_id: 1, bank_service: "PayPal Hong Kong".to_string(), ratio_bps: 5000 });
+
+        let config = CollateralConfig { ratio_bps: 10_000, corridor_ratios: ratios, ..CollateralConfig::default() };
+        assert_eq!(config.ratio_for(1, Some("paypal hong kong")), 5000);
+        assert_eq!(config.ratio_for(2, Some("PayPal Hong Kong")), 10_000);
+        assert_eq!(config.ratio_for(2, None), 10_000);
+        assert!(!CollateralConfig::default().is_enabled());
+    }
+
+    #[test]
+    fn test_parse_withdrawal_limits() {
+        let limits = parse_withdrawal_limits("1:1000:50000, 2:0:100,broken,x:1:2,3:1:2:3");
+
+        assert_eq!(limits.len(), 2);
+        assert_eq!(limits[&1], WithdrawalLimits { per_address_daily: 1000, global_daily: 50000 });
+        assert_eq!(limits[&2], WithdrawalLimits { per_address_daily: 0, global_daily: 100 });
+
+        let mut config = Config::default();
+        config.withdrawal.token_limits = limits;
+        assert_eq!(config.withdrawal.limits_for(1).per_address_daily, 1000);
+        assert_eq!(config.withdrawal.limits_for(9), WithdrawalLimits::default());
+    }
+
+    #[test]
+    fn test_parse_retention_days() {
+        let days = parse_retention_days("PayPal Hong Kong:7, Wise:0,broken,:5,ACH:x");
+
+        assert_eq!(days.len(), 2);
+        let config = RetentionConfig { bank_service_days: days, ..RetentionConfig::default() };
+        assert_eq!(config.days_for(Some("paypal hong kong")), 7);
+        assert_eq!(config.days_for(Some("Wise")), 0);
+        assert_eq!(config.days_for(Some("ACH")), 30);
+        assert_eq!(config.days_for(None), 30);
+    }
+
+    #[test]
+    fn test_parse_match_policy() {
+        assert_eq!(MatchPolicyKind::parse("pro-rata"), MatchPolicyKind::ProRata);
+        assert_eq!(MatchPolicyKind::parse(" Smallest_First"), MatchPolicyKind::SmallestFirst);
+        assert_eq!(MatchPolicyKind::parse("priority_fee"), MatchPolicyKind::PriorityFee);
+        assert_eq!(MatchPolicyKind::parse("lifo"), MatchPolicyKind::Fifo);
+        assert_eq!(parse_priority_fee_tiers("1000, 100,x,0,100"), vec![100, 1000]);
+        assert_eq!(LockWindowPolicy::parse("Best-Fee"), LockWindowPolicy::BestFee);
+        assert_eq!(LockWindowPolicy::parse(""), LockWindowPolicy::Reputation);
+    }
+
+    #[test]
+    fn test_parse_duplicate_mode() {
+        assert_eq!(DuplicateMode::parse(" Block"), DuplicateMode::Block);
+        assert_eq!(DuplicateMode::parse("off"), DuplicateMode::Off);
+        assert_eq!(DuplicateMode::parse("warn"), DuplicateMode::Flag);
+        assert_eq!(DuplicateMode::parse(""), DuplicateMode::Flag);
+    }
+
+    #[test]
+    fn test_parse_finality() {
+        let finality = parse_finality("1:finalized, 10:Safe,31337:depth,5:latest,bad");
+        assert_eq!(finality.len(), 3);
+        assert_eq!(finality[&1], FinalityMode::Finalized);
+        assert_eq!(finality[&10], FinalityMode::Safe);
+        assert_eq!(finality[&31337], FinalityMode::Depth);
+    }
+
+    #[test]
+    fn test_parse_order_amounts() {
+        let config = parse_order_amounts("*:bridge_in:100:0, 1:bridge_out:10:5000,1:transfer:1:0,2:swap:1:2,3:transfer:9:5,broken");
+
+        assert_eq!(config.range_for(7, OrderType::BridgeIn), AmountRange { min: 100, max: 0 });
+        assert_eq!(config.range_for(7, OrderType::BridgeOut), AmountRange::default());
+        // Token overrides keep the default for the types they do not set
+        assert_eq!(config.range_for(1, OrderType::BridgeIn), AmountRange { min: 100, max: 0 });
+        assert_eq!(config.range_for(1, OrderType::BridgeOut), AmountRange { min: 10, max: 5000 });
+        assert_eq!(config.range_for(1, OrderType::Transfer), AmountRange { min: 1, max: 0 });
+        assert_eq!(config.token_limits.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_batch_caps() {
+        let caps = parse_batch_caps("1:1000000, 2:500,3:0,broken,x:1,4:-1");
+
+        assert_eq!(caps, HashMap::from([(1, 1_000_000), (2, 500)]));
+    }
+
+    #[test]
+    fn test_parse_domain_separators() {
+        let separators = parse_domain_separators(&format!("1:0x{}, 2:{},3:0x1234,x:0x{}", "ab".repeat(32), "cd".repeat(32), "ef".repeat(32)));
+
+        assert_eq!(separators, HashMap::from([(1, [0xab; 32]), (2, [0xcd; 32])]));
+    }
+
+    #[test]
+    fn test_parse_token_prices() {
+        let prices = parse_token_prices("1:1.0, 2:0.9998,3:2,broken,4:x,5:0.1234567,6:0");
+
+        assert_eq!(prices.len(), 3);
+        assert_eq!(prices[&1], 1_000_000);
+        assert_eq!(prices[&2], 999_800);
+        assert_eq!(prices[&3], 2_000_000);
+    }
+
+    #[test]
+    fn test_parse_slo_targets() {
+        let targets = parse_slo_targets("discovery:600, MARK_PAID:900,settle:3600,locked:x,queued:5,broken");
+
+        assert_eq!(targets, SloTargets {
+            discovery_seconds: Some(600),
+            locked_seconds: None,
+            mark_paid_seconds: Some(900),
+            settle_seconds: Some(3600),
+        });
+    }
+
+    #[test]
+    fn test_parse_balance_alert_rules() {
+        let rules = parse_balance_alert_rules("chain:bridge:1:5000000, ledger:filler:filler_1:2:100,ledger:0xabc:x:1,wallet:0xabc:1:1,chain::1:1,broken");
+
+        assert_eq!(rules, vec![
+            BalanceAlertRule { source: BalanceSource::Chain, target: "bridge".to_string(), token_id: 1, threshold: 5_000_000 },
+            BalanceAlertRule { source: BalanceSource::Ledger, target: "filler:filler_1".to_string(), token_id: 2, threshold: 100 },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_target_compression() {
+        let targets = parse_target_compression("0xAbC:zlib, 0xdef:zstd-artifact,0x123:brotli,broken");
+
+        assert_eq!(targets.len(), 2);
+        let config = ProofSubmissionConfig { default_compression: CalldataCompression::None, target_compression: targets, ..ProofSubmissionConfig::default() };
+        assert_eq!(config.compression_for("0xabc"), CalldataCompression::Zlib);
+        assert_eq!(config.compression_for("0xDEF"), CalldataCompression::ZstdArtifact);
+        assert_eq!(config.compression_for("0x123"), CalldataCompression::None);
+    }
+
+    #[test]
+    fn test_parse_deployment_profile() {
+        assert_eq!(DeploymentProfile::parse("dev"), Some(DeploymentProfile::Dev));
+        assert_eq!(DeploymentProfile::parse(" Staging "), Some(DeploymentProfile::Staging));
+        assert_eq!(DeploymentProfile::parse("production"), Some(DeploymentProfile::Prod));
+        assert_eq!(DeploymentProfile::parse("qa"), None);
+    }
+
+    #[test]
+    fn test_check_profile() {
+        let mut config = Config::default();
+        config.api.cors_allowed_origins = vec!["*".to_string()];
+        assert!(config.check_profile().is_ok());
+
+        config.profile = DeploymentProfile::Staging;
+        let err = config.check_profile().unwrap_err().to_string();
+        assert!(err.contains("CORS_ALLOWED_ORIGINS"));
+        assert!(err.contains("ADMIN_API_TOKEN"));
+        assert!(!err.contains("mock prover"));
+
+        config.api.cors_allowed_origins = vec!["https://app.vapor.example".to_string()];
+        config.api.admin_token = Some(String::new());
+        let err = config.check_profile().unwrap_err().to_string();
+        assert!(err.contains("BLOB_STORE"));
+        assert!(err.contains("ADMIN_API_TOKEN"));
+
+        config.blob_store = BlobStoreConfig::Filesystem { path: "./blobs".to_string() };
+        config.api.admin_token = Some("admin-secret".to_string());
+        assert!(config.check_profile().is_ok());
+
+        config.profile = DeploymentProfile::Prod;
+        assert!(config.check_profile().unwrap_err().to_string().contains("mock prover"));
+    }
+}
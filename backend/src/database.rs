@@ -33,6 +33,12 @@ pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
             locked_amount TEXT,
             status INTEGER NOT NULL DEFAULT 0,
             batch_id INTEGER,
+            failure_reason TEXT,
+            parent_order_id TEXT,
+            deposit_reference TEXT,
+            payment_proof TEXT, -- typed JSON, see services::payment_proofs
+            quoted_usd_price INTEGER, -- token price in micro-dollars when quoted, see services::rates
+            rate_quoted_at DATETIME,
             created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
             updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
         )
@@ -41,6 +47,30 @@ pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
     .execute(pool)
     .await?;
 
+    // Columns added after the initial schema
+    add_column_if_missing(pool, "orders", "failure_reason", "TEXT").await?;
+    add_column_if_missing(pool, "orders", "parent_order_id", "TEXT").await?;
+    add_column_if_missing(pool, "orders", "deposit_reference", "TEXT").await?;
+    add_column_if_missing(pool, "orders", "payment_proof", "TEXT").await?;
+    add_column_if_missing(pool, "orders", "quoted_usd_price", "INTEGER").await?;
+    add_column_if_missing(pool, "orders", "rate_quoted_at", "DATETIME").await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_orders_parent ON orders(parent_order_id)")
+        .execute(pool)
+        .await?;
+
+    // Keyset pagination of order listings, optionally filtered by status
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_orders_created_at ON orders(created_at, id)")
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_orders_status_created_at ON orders(status, created_at, id)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_orders_deposit_reference ON orders(deposit_reference) WHERE deposit_reference IS NOT NULL")
+        .execute(pool)
+        .await?;
+
     // Create batches table
     sqlx::query(
         r#"
@@ -110,40 +140,884 @@ pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
     .execute(pool)
     .await?;
 
-    // Create claims table to track claim history
+    // Create claims table to track claim history
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS claims (
+            id TEXT PRIMARY KEY,
+            filler_id TEXT NOT NULL,
+            wallet_address TEXT NOT NULL,
+            destination_address TEXT NOT NULL,
+            amount TEXT NOT NULL,
+            token_id INTEGER NOT NULL DEFAULT 1,
+            payout_token_id INTEGER,
+            payout_amount TEXT,
+            conversion_rate TEXT, -- payout token units per earned token unit, when converted
+            rate_source TEXT,
+            rate_quoted_at DATETIME,
+            batch_id INTEGER,
+            transaction_hash TEXT,
+            status TEXT NOT NULL DEFAULT 'pending',
+            merkle_proof TEXT, -- JSON array of proof elements
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (filler_id) REFERENCES filler_balances(filler_id)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Payout token and conversion bookkeeping, added after the initial schema
+    add_column_if_missing(pool, "claims", "token_id", "INTEGER NOT NULL DEFAULT 1").await?;
+    add_column_if_missing(pool, "claims", "payout_token_id", "INTEGER").await?;
+    add_column_if_missing(pool, "claims", "payout_amount", "TEXT").await?;
+    add_column_if_missing(pool, "claims", "conversion_rate", "TEXT").await?;
+    add_column_if_missing(pool, "claims", "rate_source", "TEXT").await?;
+    add_column_if_missing(pool, "claims", "rate_quoted_at", "DATETIME").await?;
+
+    // Claims used to take CURRENT_TIMESTAMP ("YYYY-MM-DD HH:MM:SS"); rewrite those in the
+    // RFC 3339 form claims are now stored with, so created_at compares correctly as text
+    sqlx::query("UPDATE claims SET created_at = strftime('%Y-%m-%dT%H:%M:%S+00:00', created_at) WHERE created_at NOT LIKE '%T%'")
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_claims_created_at ON claims(created_at, id)")
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_claims_filler_created_at ON claims(filler_id, created_at, id)")
+        .execute(pool)
+        .await?;
+
+    // On-chain BridgeOut order a claim redeems; the contract tracks claimed orders globally, so an
+    // order may back at most one claim whatever its batch
+    add_column_if_missing(pool, "claims", "order_id", "INTEGER").await?;
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_claims_order_id ON claims(order_id) WHERE order_id IS NOT NULL")
+        .execute(pool)
+        .await?;
+
+    // Fee taken out of each claim's gross `amount` and the net transferred; unset before claim fees
+    add_column_if_missing(pool, "claims", "fee_amount", "TEXT").await?;
+    add_column_if_missing(pool, "claims", "net_amount", "TEXT").await?;
+
+    // Create order_permits table for deposits made with an EIP-2612 permit
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS order_permits (
+            order_id TEXT PRIMARY KEY,
+            owner TEXT NOT NULL,
+            spender TEXT NOT NULL,
+            value TEXT NOT NULL,
+            nonce TEXT,
+            deadline INTEGER NOT NULL,
+            signature TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (order_id) REFERENCES orders(id)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Create order_status_history table recording every status transition
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS order_status_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            order_id TEXT NOT NULL,
+            from_status INTEGER NOT NULL,
+            to_status INTEGER NOT NULL,
+            reason TEXT,
+            created_at DATETIME NOT NULL,
+            FOREIGN KEY (order_id) REFERENCES orders(id)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_order_status_history_order ON order_status_history(order_id)")
+        .execute(pool)
+        .await?;
+
+    // Create batch_snapshots table holding the most recent state snapshots (see services::archival)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS batch_snapshots (
+            batch_id INTEGER PRIMARY KEY,
+            state_root TEXT NOT NULL,
+            orders_root TEXT NOT NULL,
+            data TEXT NOT NULL,
+            created_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Create batch_snapshot_archive table holding gzip-compressed snapshots past the retention window
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS batch_snapshot_archive (
+            batch_id INTEGER PRIMARY KEY,
+            state_root TEXT NOT NULL,
+            orders_root TEXT NOT NULL,
+            data BLOB NOT NULL,
+            raw_size INTEGER NOT NULL,
+            compressed_size INTEGER NOT NULL,
+            created_at DATETIME NOT NULL,
+            archived_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Create proof_submissions table: calldata sizes per batch and target for cost analysis (see services::proof_compression)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS proof_submissions (
+            batch_id INTEGER NOT NULL,
+            target TEXT NOT NULL,
+            compression TEXT NOT NULL,
+            raw_size INTEGER NOT NULL,
+            calldata_size INTEGER NOT NULL,
+            raw_calldata_gas INTEGER NOT NULL,
+            calldata_gas INTEGER NOT NULL,
+            artifact BLOB, -- zstd proof kept off-chain when only its hash is submitted (inline only for rows from before artifact_ref)
+            artifact_hash TEXT,
+            created_at DATETIME NOT NULL,
+            PRIMARY KEY (batch_id, target)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Snapshots and proof artifacts now live in the blob store (see services::blob_store); rows keep
+    // a reference and checksum, while inline data stays readable for rows written before the move
+    add_column_if_missing(pool, "batch_snapshots", "blob_ref", "TEXT").await?;
+    add_column_if_missing(pool, "batch_snapshots", "checksum", "TEXT").await?;
+    add_column_if_missing(pool, "batch_snapshot_archive", "blob_ref", "TEXT").await?;
+    add_column_if_missing(pool, "batch_snapshot_archive", "checksum", "TEXT").await?;
+    add_column_if_missing(pool, "proof_submissions", "artifact_ref", "TEXT").await?;
+    add_column_if_missing(pool, "proof_submissions", "artifact_size", "INTEGER").await?;
+
+    // Create withdrawal_address_lists table: admin-managed BridgeOut allowlist/denylist (see services::withdrawal_limits)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS withdrawal_address_lists (
+            address TEXT PRIMARY KEY,
+            list TEXT NOT NULL CHECK (list IN ('allow', 'deny')),
+            reason TEXT,
+            created_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Create maintenance_mode table holding the persisted admin pause switch (see services::maintenance)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS maintenance_mode (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            enabled BOOLEAN NOT NULL,
+            message TEXT,
+            updated_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Create open_batch tables journaling the unfinalized batch for restart recovery (see services::batch_journal)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS open_batch (
+            batch_id INTEGER PRIMARY KEY,
+            prev_batch_id INTEGER NOT NULL,
+            prev_state_root TEXT NOT NULL,
+            prev_orders_root TEXT NOT NULL,
+            start_accounts TEXT NOT NULL,
+            created_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS open_batch_orders (
+            batch_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            order_data TEXT NOT NULL,
+            PRIMARY KEY (batch_id, position)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // BridgeOut orders held back by the per-batch volume caps, waiting for a later batch
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS deferred_batch_orders (
+            order_id TEXT PRIMARY KEY,
+            deferred_data TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Orders of an aborted batch, waiting to be re-added when the next batch starts
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS requeued_batch_orders (
+            order_id TEXT PRIMARY KEY,
+            order_data TEXT NOT NULL,
+            requeued_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Batches abandoned by an admin before finalization, with why (see services::batch_journal)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS aborted_batches (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            batch_id INTEGER NOT NULL,
+            reason TEXT NOT NULL,
+            order_ids TEXT NOT NULL,
+            aborted_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Create precomputed_proofs table holding proofs generated when a batch is finalized (see services::proof_cache)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS precomputed_proofs (
+            batch_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            proof_key TEXT NOT NULL,
+            format TEXT NOT NULL,
+            leaf_index INTEGER,
+            proof TEXT NOT NULL,
+            created_at DATETIME NOT NULL,
+            PRIMARY KEY (batch_id, kind, proof_key, format)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Messages consumed from the order queue, so redelivered messages are not turned into orders twice (see api::order_queue)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS queued_order_messages (
+            message_id TEXT PRIMARY KEY,
+            order_id TEXT, -- NULL when the order was rejected
+            error TEXT,
+            received_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Operator-signed inclusion receipts for orders of finalized batches (see services::receipts)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS inclusion_receipts (
+            order_id TEXT PRIMARY KEY,
+            batch_id INTEGER NOT NULL,
+            orders_root TEXT NOT NULL,
+            leaf_index INTEGER NOT NULL,
+            digest TEXT NOT NULL,
+            signer TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            issued_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Single-row relayer checkpoint with the chain it was taken on (see services::chain_checkpoint)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS relayer_checkpoint (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            chain_id INTEGER NOT NULL,
+            genesis_hash TEXT NOT NULL,
+            block_number INTEGER NOT NULL,
+            block_hash TEXT NOT NULL,
+            updated_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Prover and relayer totals, kept across restarts (see services::stats)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS service_stats (
+            name TEXT PRIMARY KEY,
+            value INTEGER NOT NULL DEFAULT 0,
+            updated_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Filler capabilities declared at registration (see services::filler_capabilities)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS filler_capabilities (
+            filler_id TEXT PRIMARY KEY,
+            capabilities TEXT NOT NULL,
+            updated_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Token and bank-service registries, served through services::registry::RegistryCache
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS token_registry (
+            token_id INTEGER PRIMARY KEY,
+            symbol TEXT NOT NULL,
+            decimals INTEGER NOT NULL,
+            enabled BOOLEAN NOT NULL,
+            updated_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS bank_service_registry (
+            name TEXT PRIMARY KEY COLLATE NOCASE,
+            enabled BOOLEAN NOT NULL,
+            updated_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // One settlement saga per locked order, driven by services::settlement_saga
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS settlement_sagas (
+            order_id TEXT PRIMARY KEY,
+            filler_id TEXT NOT NULL,
+            locked_amount TEXT NOT NULL,
+            step TEXT NOT NULL,
+            state TEXT NOT NULL,
+            escrow_order_id TEXT,
+            batch_id INTEGER,
+            refund_order_id TEXT,
+            compensations TEXT NOT NULL DEFAULT '[]',
+            failure_reason TEXT,
+            last_error TEXT,
+            started_at DATETIME NOT NULL,
+            updated_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_settlement_sagas_state ON settlement_sagas(state, started_at)")
+        .execute(pool)
+        .await?;
+
+    // Per-filler opt-in to claim relaying, and the gas spent and fees charged per relayed claim
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS filler_relay_settings (
+            filler_id TEXT PRIMARY KEY,
+            enabled BOOLEAN NOT NULL,
+            max_fee TEXT,
+            updated_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS claim_relays (
+            id TEXT PRIMARY KEY,
+            filler_id TEXT NOT NULL,
+            transaction_hash TEXT,
+            claim_ids TEXT NOT NULL,
+            gas_used TEXT NOT NULL,
+            gas_price_wei TEXT NOT NULL,
+            gas_cost_wei TEXT NOT NULL,
+            fee_amount TEXT NOT NULL,
+            fee_token_id INTEGER NOT NULL,
+            created_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_claim_relays_filler ON claim_relays(filler_id, created_at)")
+        .execute(pool)
+        .await?;
+
+    // Order book reconciliation reports, as JSON, newest kept per RECONCILIATION_RETAIN_REPORTS
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS reconciliation_reports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            trigger TEXT NOT NULL,
+            mismatches INTEGER NOT NULL,
+            report TEXT NOT NULL,
+            created_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Nonces of signed transfers, one use per sender
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS transfer_nonces (
+            from_address TEXT NOT NULL,
+            nonce INTEGER NOT NULL,
+            order_id TEXT NOT NULL,
+            created_at DATETIME NOT NULL,
+            PRIMARY KEY (from_address, nonce)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Bank details removed after their retention ran out
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS retention_audit (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            order_id TEXT NOT NULL,
+            bank_service TEXT,
+            fields TEXT NOT NULL,
+            retention_days INTEGER NOT NULL,
+            terminal_at DATETIME NOT NULL,
+            scrubbed_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Database snapshots kept in the blob store
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS db_backups (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            blob_ref TEXT NOT NULL,
+            checksum TEXT NOT NULL,
+            raw_size INTEGER NOT NULL,
+            compressed_size INTEGER NOT NULL,
+            schema_fingerprint TEXT NOT NULL,
+            trigger TEXT NOT NULL,
+            created_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Operator-signed manifests of submitted batches, for external verifiers (see services::manifests)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS batch_manifests (
+            batch_id INTEGER PRIMARY KEY,
+            state_root TEXT NOT NULL,
+            orders_root TEXT NOT NULL,
+            order_count INTEGER NOT NULL,
+            timestamp DATETIME NOT NULL,
+            proof_artifact_hash TEXT NOT NULL,
+            digest TEXT NOT NULL,
+            signer TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            pushed_to TEXT,
+            push_reference TEXT, -- e.g. the IPFS CID
+            push_error TEXT
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Create order_costs table: each order's share of its batch's proof submission gas (see services::order_costs)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS order_costs (
+            batch_id INTEGER NOT NULL,
+            order_id TEXT NOT NULL,
+            weighting TEXT NOT NULL,
+            gas_used INTEGER NOT NULL,
+            gas_price_wei TEXT NOT NULL,
+            gas_cost_wei TEXT NOT NULL,
+            estimated BOOLEAN NOT NULL, -- no on-chain receipt, gas estimated from the calldata
+            created_at DATETIME NOT NULL,
+            PRIMARY KEY (batch_id, order_id)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_order_costs_order ON order_costs(order_id)")
+        .execute(pool)
+        .await?;
+
+    // Create batch_costs table: proving time and submission cost of each proven batch (see services::batch_costs)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS batch_costs (
+            batch_id INTEGER PRIMARY KEY,
+            orders_count INTEGER NOT NULL,
+            proof_time_ms INTEGER NOT NULL,
+            gas_used INTEGER NOT NULL,
+            gas_price_wei TEXT NOT NULL,
+            gas_cost_wei TEXT NOT NULL,
+            usd_cost_micros INTEGER NOT NULL,
+            estimated BOOLEAN NOT NULL, -- no on-chain receipt, gas estimated from the calldata
+            recorded_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_batch_costs_recorded ON batch_costs(recorded_at)")
+        .execute(pool)
+        .await?;
+
+    // Create scheduled_jobs table: delayed order transitions, one per kind and order (see services::scheduler)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS scheduled_jobs (
+            id TEXT PRIMARY KEY, -- kind:order_id
+            kind TEXT NOT NULL,
+            order_id TEXT NOT NULL,
+            payload TEXT,
+            run_at DATETIME NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL,
+            last_error TEXT,
+            created_at DATETIME NOT NULL,
+            updated_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_scheduled_jobs_due ON scheduled_jobs(status, run_at)")
+        .execute(pool)
+        .await?;
+
+    // Create filler_collateral table: bridge deposits, slashing and flags per filler (see services::collateral)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS filler_collateral (
+            filler_id TEXT PRIMARY KEY,
+            deposited TEXT NOT NULL DEFAULT '0',
+            slashed TEXT NOT NULL DEFAULT '0',
+            expiries INTEGER NOT NULL DEFAULT 0,
+            flagged_at DATETIME,
+            refreshed_at DATETIME, -- when deposited was last read from the chain
+            updated_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Create order_quotes table: terms held for an order until it commits or the quote expires (see services::quotes)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS order_quotes (
+            quote_id TEXT PRIMARY KEY,
+            order_type INTEGER NOT NULL,
+            token_id INTEGER NOT NULL,
+            amount TEXT NOT NULL,
+            bank_service TEXT,
+            usd_price INTEGER,
+            claim_fee TEXT, -- JSON ClaimFee
+            eta_seconds INTEGER,
+            digest TEXT NOT NULL,
+            signer TEXT,
+            signature TEXT,
+            order_id TEXT, -- set once an order commits to the quote
+            created_at DATETIME NOT NULL,
+            expires_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Create events table: the append-only domain event log (see services::event_log)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS events (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            payload TEXT NOT NULL, -- JSON DomainEvent, keys sorted
+            payload_hash TEXT NOT NULL,
+            recorded_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Events are never rewritten, not even by a backup restore (which leaves the table alone)
+    sqlx::query("CREATE TRIGGER IF NOT EXISTS events_no_update BEFORE UPDATE ON events BEGIN SELECT RAISE(ABORT, 'events are append-only'); END")
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE TRIGGER IF NOT EXISTS events_no_delete BEFORE DELETE ON events BEGIN SELECT RAISE(ABORT, 'events are append-only'); END")
+        .execute(pool)
+        .await?;
+
+    // Create writer_lease table holding the single writer role and any pending handover (see services::handover)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS writer_lease (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            holder TEXT NOT NULL,
+            expires_at DATETIME NOT NULL,
+            handover_digest TEXT,
+            handover_manifest TEXT,
+            updated_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Create batch_sequence table: every finalized batch and the batch it continues (see services::batch_sequence)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS batch_sequence (
+            batch_id INTEGER PRIMARY KEY CHECK (batch_id >= 1),
+            prev_batch_id INTEGER NOT NULL UNIQUE CHECK (prev_batch_id = batch_id - 1),
+            prev_state_root TEXT NOT NULL,
+            prev_orders_root TEXT NOT NULL,
+            state_root TEXT NOT NULL,
+            orders_root TEXT NOT NULL,
+            recorded_at DATETIME NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Once started, the sequence only grows by the batch that continues its last one
+    sqlx::query(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS batch_sequence_contiguous BEFORE INSERT ON batch_sequence
+        WHEN EXISTS (SELECT 1 FROM batch_sequence) AND NOT EXISTS (
+            SELECT 1 FROM batch_sequence
+            WHERE batch_id = NEW.prev_batch_id
+              AND batch_id = (SELECT MAX(batch_id) FROM batch_sequence)
+              AND state_root = NEW.prev_state_root
+              AND orders_root = NEW.prev_orders_root
+        )
+        BEGIN SELECT RAISE(ABORT, 'batch does not continue the batch sequence'); END
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Create screening_results table: every sanctions screening and the review of flagged hits (see services::screening)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS screening_results (
+            id TEXT PRIMARY KEY,
+            context TEXT NOT NULL, -- order | lock | claim
+            subject_id TEXT NOT NULL,
+            address TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            outcome TEXT NOT NULL, -- clear | blocked | flagged
+            identifications TEXT NOT NULL, -- JSON array
+            screened_at DATETIME NOT NULL,
+            decision TEXT, -- cleared | confirmed, once a flagged hit is reviewed
+            review_note TEXT,
+            reviewed_at DATETIME
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_screening_results_review ON screening_results(outcome, decision, screened_at)")
+        .execute(pool)
+        .await?;
+
+    // Create order_reviews table: high-value orders held for manual approval and the decision on each (see services::order_review)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS order_reviews (
+            order_id TEXT PRIMARY KEY,
+            token_id INTEGER NOT NULL,
+            amount TEXT NOT NULL,
+            threshold INTEGER NOT NULL,
+            requested_at DATETIME NOT NULL,
+            decision TEXT, -- approved | rejected
+            reason TEXT,
+            decided_at DATETIME,
+            FOREIGN KEY (order_id) REFERENCES orders(id)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_order_reviews_queue ON order_reviews(decision, requested_at)")
+        .execute(pool)
+        .await?;
+
+    // Create lock_bids table: lock attempts collected during an order's lock window and which one won (see services::lock_window)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS lock_bids (
+            order_id TEXT NOT NULL,
+            filler_id TEXT NOT NULL,
+            amount TEXT NOT NULL,
+            fee_bps INTEGER,
+            reputation_bps INTEGER NOT NULL,
+            requoted_usd_price INTEGER,
+            status TEXT NOT NULL, -- pending | won | lost
+            window_closes_at DATETIME NOT NULL,
+            placed_at DATETIME NOT NULL,
+            decided_at DATETIME,
+            PRIMARY KEY (order_id, filler_id),
+            FOREIGN KEY (order_id) REFERENCES orders(id)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Index finalized roots for reverse lookups of a root seen on-chain (see services::archival)
+    for index in [
+        "CREATE INDEX IF NOT EXISTS idx_batch_snapshots_state_root ON batch_snapshots(state_root)",
+        "CREATE INDEX IF NOT EXISTS idx_batch_snapshots_orders_root ON batch_snapshots(orders_root)",
+        "CREATE INDEX IF NOT EXISTS idx_batch_snapshot_archive_state_root ON batch_snapshot_archive(state_root)",
+        "CREATE INDEX IF NOT EXISTS idx_batch_snapshot_archive_orders_root ON batch_snapshot_archive(orders_root)",
+    ] {
+        sqlx::query(index).execute(pool).await?;
+    }
+
+    // Create notification_preferences table: the channels a user is notified on, and for which events (see services::notifications)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS notification_preferences (
+            address TEXT NOT NULL, -- lowercase
+            channel TEXT NOT NULL, -- email | webhook | console
+            destination TEXT NOT NULL,
+            events TEXT NOT NULL, -- comma-separated event kinds
+            updated_at DATETIME NOT NULL,
+            PRIMARY KEY (address, channel)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Create notifications table: rendered notifications and their delivery (see services::notifications)
     sqlx::query(
         r#"
-        CREATE TABLE IF NOT EXISTS claims (
+        CREATE TABLE IF NOT EXISTS notifications (
             id TEXT PRIMARY KEY,
-            filler_id TEXT NOT NULL,
-            wallet_address TEXT NOT NULL,
-            destination_address TEXT NOT NULL,
-            amount TEXT NOT NULL,
-            batch_id INTEGER,
-            transaction_hash TEXT,
-            status TEXT NOT NULL DEFAULT 'pending',
-            merkle_proof TEXT, -- JSON array of proof elements
-            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-            FOREIGN KEY (filler_id) REFERENCES filler_balances(filler_id)
+            address TEXT NOT NULL,
+            order_id TEXT NOT NULL,
+            event TEXT NOT NULL,
+            channel TEXT NOT NULL,
+            destination TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            body TEXT NOT NULL,
+            status TEXT NOT NULL, -- pending | delivered | failed
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            created_at DATETIME NOT NULL,
+            delivered_at DATETIME,
+            UNIQUE (order_id, event, channel, address)
         )
         "#,
     )
     .execute(pool)
     .await?;
 
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_notifications_address ON notifications(address, created_at)")
+        .execute(pool)
+        .await?;
+
     info!("Database migrations completed");
     Ok(())
 }
 
+/// Add a column to an existing table (CREATE TABLE IF NOT EXISTS does not upgrade old databases)
+async fn add_column_if_missing(pool: &SqlitePool, table: &str, column: &str, definition: &str) -> Result<()> {
+    let columns = sqlx::query(&format!("PRAGMA table_info({})", table))
+        .fetch_all(pool)
+        .await?;
+
+    let exists = columns.iter()
+        .any(|row| row.try_get::<String, _>("name").map(|name| name == column).unwrap_or(false));
+
+    if !exists {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(pool)
+            .await?;
+        info!("Added column {}.{}", table, column);
+    }
+
+    Ok(())
+}
+
 /// Database helper functions for testing and operations
 pub mod helpers {
     use super::*;
     use chrono::Utc;
-    use crate::models::{Order, OrderType, OrderStatus, TokenBalance, FillerBalance, FillerWallet, ClaimRequest, ClaimResponse};
+    use crate::models::{sort_by_creation, Order, OrderType, OrderStatus, TokenBalance, FillerBalance, FillerWallet, ClaimRequest, ClaimResponse, ClaimRecord, TokenConversion, PermitData, OrderStatusTransition, Cursor, AccountState, ProvenRoot};
+    use crate::services::event_log::{self, DomainEvent};
+    use vapor_core::fault_injection::{inject, FaultTarget};
+    use crate::services::query_metrics::QueryTimer;
+    use tracing::instrument;
+    use web3::types::U256;
     
     /// Insert an order into the database
+    #[instrument(skip_all, fields(db.system = "sqlite", order_id = %order.id))]
     pub async fn insert_order(pool: &SqlitePool, order: &Order) -> Result<()> {
+        let _timer = QueryTimer::start("insert_order");
+        inject(FaultTarget::Database).await?;
+
         sqlx::query(
             r#"
             INSERT INTO orders (id, order_type, status, from_address, to_address, token_id, amount, bank_account, bank_service, banking_hash, filler_id, locked_amount, batch_id, created_at, updated_at)
@@ -167,12 +1041,105 @@ pub mod helpers {
         .bind(order.updated_at)
         .execute(pool)
         .await?;
+
+        event_log::append(pool, &DomainEvent::order_created(order)).await?;
         
         Ok(())
     }
     
+    /// Store the permit an order's deposit was made with
+    #[instrument(skip_all, fields(db.system = "sqlite", order_id = %order_id))]
+    pub async fn insert_order_permit(pool: &SqlitePool, order_id: &str, permit: &PermitData) -> Result<()> {
+        let _timer = QueryTimer::start("insert_order_permit");
+        sqlx::query(
+            r#"
+            INSERT INTO order_permits (order_id, owner, spender, value, nonce, deadline, signature)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+        )
+        .bind(order_id)
+        .bind(&permit.owner)
+        .bind(&permit.spender)
+        .bind(&permit.value)
+        .bind(&permit.nonce)
+        .bind(permit.deadline as i64)
+        .bind(&permit.signature)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get the permit an order's deposit was made with, if any
+    pub async fn get_order_permit(pool: &SqlitePool, order_id: &str) -> Result<Option<PermitData>> {
+        let _timer = QueryTimer::start("get_order_permit");
+        let row = sqlx::query(
+            "SELECT owner, spender, value, nonce, deadline, signature FROM order_permits WHERE order_id = ?"
+        )
+        .bind(order_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(|row| PermitData {
+            owner: row.try_get("owner").unwrap_or_default(),
+            spender: row.try_get("spender").unwrap_or_default(),
+            value: row.try_get("value").unwrap_or_default(),
+            nonce: row.try_get("nonce").unwrap_or_default(),
+            deadline: row.try_get::<i64, _>("deadline").unwrap_or_default() as u64,
+            signature: row.try_get("signature").unwrap_or_default(),
+        }))
+    }
+
+    /// Record a status transition in the order's history
+    #[instrument(skip_all, fields(db.system = "sqlite", order_id = %order_id))]
+    pub async fn record_status_transition(
+        pool: &SqlitePool,
+        order_id: &str,
+        from_status: OrderStatus,
+        to_status: OrderStatus,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        let _timer = QueryTimer::start("record_status_transition");
+        sqlx::query(
+            "INSERT INTO order_status_history (order_id, from_status, to_status, reason, created_at) VALUES (?1, ?2, ?3, ?4, ?5)"
+        )
+        .bind(order_id)
+        .bind(from_status as i32)
+        .bind(to_status as i32)
+        .bind(reason)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+        event_log::append(pool, &DomainEvent::status_change(order_id, from_status, to_status, reason)).await?;
+
+        Ok(())
+    }
+
+    /// Get an order's status transitions, oldest first
+    pub async fn get_order_history(pool: &SqlitePool, order_id: &str) -> Result<Vec<OrderStatusTransition>> {
+        let _timer = QueryTimer::start("get_order_history");
+        let rows = sqlx::query(
+            "SELECT order_id, from_status, to_status, reason, created_at FROM order_status_history WHERE order_id = ? ORDER BY id"
+        )
+        .bind(order_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| OrderStatusTransition {
+            order_id: row.try_get("order_id").unwrap_or_default(),
+            from_status: OrderStatus::from(row.try_get::<i32, _>("from_status").unwrap_or(0)),
+            to_status: OrderStatus::from(row.try_get::<i32, _>("to_status").unwrap_or(0)),
+            reason: row.try_get("reason").unwrap_or_default(),
+            created_at: row.try_get("created_at").unwrap_or_default(),
+        }).collect())
+    }
+
     /// Get an order by ID
+    #[instrument(skip_all, fields(db.system = "sqlite", order_id = %order_id))]
     pub async fn get_order_by_id(pool: &SqlitePool, order_id: &str) -> Result<Option<Order>> {
+        let _timer = QueryTimer::start("get_order_by_id");
+        inject(FaultTarget::Database).await?;
+
         let row = sqlx::query(
             "SELECT id, order_type, status, from_address, to_address, token_id, amount, bank_account, bank_service, banking_hash, filler_id, locked_amount, batch_id, created_at, updated_at FROM orders WHERE id = ?"
         )
@@ -180,43 +1147,230 @@ pub mod helpers {
         .fetch_optional(pool)
         .await?;
         
-        if let Some(row) = row {
-            let order = Order {
-                id: row.try_get("id")?,
-                order_type: match row.try_get::<i32, _>("order_type")? {
-                    0 => OrderType::BridgeIn,
-                    1 => OrderType::BridgeOut,
-                    2 => OrderType::Transfer,
-                    _ => return Err(anyhow::anyhow!("Invalid order type")),
-                },
-                status: match row.try_get::<i32, _>("status")? {
-                    0 => OrderStatus::Pending,
-                    1 => OrderStatus::Discovery,
-                    2 => OrderStatus::Locked,
-                    3 => OrderStatus::MarkPaid,
-                    4 => OrderStatus::Settled,
-                    5 => OrderStatus::Failed,
-                    _ => return Err(anyhow::anyhow!("Invalid order status")),
-                },
-                from_address: row.try_get("from_address")?,
-                to_address: row.try_get("to_address")?,
-                token_id: row.try_get::<i32, _>("token_id")? as u32,
-                amount: row.try_get("amount")?,
-                bank_account: row.try_get("bank_account")?,
-                bank_service: row.try_get("bank_service")?,
-                banking_hash: row.try_get("banking_hash")?,
-                filler_id: row.try_get("filler_id")?,
-                locked_amount: row.try_get("locked_amount")?,
-                batch_id: row.try_get::<Option<i32>, _>("batch_id")?.map(|id| id as u32),
-                created_at: row.try_get("created_at")?,
-                updated_at: row.try_get("updated_at")?,
-            };
-            Ok(Some(order))
-        } else {
-            Ok(None)
+        row.map(|row| row_to_order(&row)).transpose()
+    }
+
+    /// Get all orders assigned to a batch, in creation order (their order tree indices)
+    /// Orders split by a partial settlement are represented by their child orders instead
+    #[instrument(skip_all, fields(db.system = "sqlite", batch_id = batch_id))]
+    pub async fn get_orders_by_batch(pool: &SqlitePool, batch_id: u64) -> Result<Vec<Order>> {
+        let _timer = QueryTimer::start("get_orders_by_batch");
+        inject(FaultTarget::Database).await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, order_type, status, from_address, to_address, token_id, amount, bank_account, bank_service, banking_hash, filler_id, locked_amount, batch_id, created_at, updated_at
+            FROM orders
+            WHERE batch_id = ? AND id NOT IN (SELECT parent_order_id FROM orders WHERE parent_order_id IS NOT NULL)
+            ORDER BY created_at, id
+            "#
+        )
+        .bind(batch_id as i32)
+        .fetch_all(pool)
+        .await?;
+
+        let mut orders = rows.iter().map(row_to_order).collect::<Result<Vec<_>>>()?;
+        sort_by_creation(&mut orders);
+        Ok(orders)
+    }
+
+    /// Get orders by id; ids without an order are skipped
+    #[instrument(skip_all, fields(db.system = "sqlite", count = order_ids.len()))]
+    pub async fn get_orders_by_ids(pool: &SqlitePool, order_ids: &[String]) -> Result<Vec<Order>> {
+        let _timer = QueryTimer::start("get_orders_by_ids");
+        inject(FaultTarget::Database).await?;
+        if order_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query = format!(
+            "SELECT id, order_type, status, from_address, to_address, token_id, amount, bank_account, bank_service, banking_hash, filler_id, locked_amount, batch_id, created_at, updated_at FROM orders WHERE id IN ({})",
+            placeholders(order_ids.len())
+        );
+        let mut query = sqlx::query(&query);
+        for order_id in order_ids {
+            query = query.bind(order_id);
+        }
+
+        query.fetch_all(pool).await?.iter().map(row_to_order).collect()
+    }
+
+    /// Conditions for [`find_orders`]; unset fields match any order
+    #[derive(Debug, Clone, Default)]
+    pub struct OrderFilter {
+        pub status: Option<OrderStatus>,
+        pub order_type: Option<OrderType>,
+        /// Matches either side of the order, case-insensitively
+        pub address: Option<String>,
+        pub filler_id: Option<String>,
+    }
+
+    /// Get the newest orders matching all of the given conditions
+    #[instrument(skip_all, fields(db.system = "sqlite"))]
+    pub async fn find_orders(pool: &SqlitePool, filter: &OrderFilter, limit: u32) -> Result<Vec<Order>> {
+        let _timer = QueryTimer::start("find_orders");
+        inject(FaultTarget::Database).await?;
+
+        let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+            "SELECT id, order_type, status, from_address, to_address, token_id, amount, bank_account, bank_service, banking_hash, filler_id, locked_amount, batch_id, created_at, updated_at FROM orders WHERE 1 = 1",
+        );
+        if let Some(status) = filter.status {
+            query.push(" AND status = ").push_bind(status as i32);
         }
+        if let Some(order_type) = filter.order_type {
+            query.push(" AND order_type = ").push_bind(order_type as i32);
+        }
+        if let Some(address) = &filter.address {
+            query.push(" AND (LOWER(from_address) = ").push_bind(address.to_lowercase());
+            query.push(" OR LOWER(to_address) = ").push_bind(address.to_lowercase()).push(")");
+        }
+        if let Some(filler_id) = &filter.filler_id {
+            query.push(" AND filler_id = ").push_bind(filler_id.clone());
+        }
+        query.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(limit as i64);
+
+        query.build().fetch_all(pool).await?.iter().map(row_to_order).collect()
+    }
+
+    fn placeholders(count: usize) -> String {
+        vec!["?"; count].join(", ")
+    }
+
+    fn row_to_order(row: &sqlx::sqlite::SqliteRow) -> Result<Order> {
+        Ok(Order {
+            id: row.try_get("id")?,
+            order_type: match row.try_get::<i32, _>("order_type")? {
+                0 => OrderType::BridgeIn,
+                1 => OrderType::BridgeOut,
+                2 => OrderType::Transfer,
+                _ => return Err(anyhow::anyhow!("Invalid order type")),
+            },
+            status: match row.try_get::<i32, _>("status")? {
+                0 => OrderStatus::Pending,
+                1 => OrderStatus::Discovery,
+                2 => OrderStatus::Locked,
+                3 => OrderStatus::MarkPaid,
+                4 => OrderStatus::Settled,
+                5 => OrderStatus::Failed,
+                6 => OrderStatus::Disputed,
+                7 => OrderStatus::ReviewPending,
+                _ => return Err(anyhow::anyhow!("Invalid order status")),
+            },
+            from_address: row.try_get("from_address")?,
+            to_address: row.try_get("to_address")?,
+            token_id: row.try_get::<i32, _>("token_id")? as u32,
+            amount: row.try_get("amount")?,
+            bank_account: row.try_get("bank_account")?,
+            bank_service: row.try_get("bank_service")?,
+            banking_hash: row.try_get("banking_hash")?,
+            filler_id: row.try_get("filler_id")?,
+            locked_amount: row.try_get("locked_amount")?,
+            batch_id: row.try_get::<Option<i64>, _>("batch_id")?.map(|id| id as u64),
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
     }
     
+    /// Insert an order split off from `parent_order_id`
+    #[instrument(skip_all, fields(db.system = "sqlite", order_id = %order.id, parent_order_id = %parent_order_id))]
+    pub async fn insert_child_order(pool: &SqlitePool, order: &Order, parent_order_id: &str) -> Result<()> {
+        let _timer = QueryTimer::start("insert_child_order");
+        insert_order(pool, order).await?;
+
+        sqlx::query("UPDATE orders SET parent_order_id = ? WHERE id = ?")
+            .bind(parent_order_id)
+            .bind(&order.id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The order an order was split off from, if any
+    pub async fn get_parent_order_id(pool: &SqlitePool, order_id: &str) -> Result<Option<String>> {
+        let _timer = QueryTimer::start("get_parent_order_id");
+        let row = sqlx::query("SELECT parent_order_id FROM orders WHERE id = ?")
+            .bind(order_id)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(row.and_then(|row| row.try_get("parent_order_id").ok().flatten()))
+    }
+
+    /// IDs of the orders split off from an order
+    pub async fn get_child_order_ids(pool: &SqlitePool, order_id: &str) -> Result<Vec<String>> {
+        let _timer = QueryTimer::start("get_child_order_ids");
+        let rows = sqlx::query("SELECT id FROM orders WHERE parent_order_id = ? ORDER BY id")
+            .bind(order_id)
+            .fetch_all(pool)
+            .await?;
+
+        Ok(rows.iter().filter_map(|row| row.try_get("id").ok()).collect())
+    }
+
+    /// The order waiting for a deposit with this reference, if any
+    #[instrument(skip_all, fields(db.system = "sqlite", deposit_reference = %reference))]
+    pub async fn get_order_by_deposit_reference(pool: &SqlitePool, reference: &str) -> Result<Option<Order>> {
+        let _timer = QueryTimer::start("get_order_by_deposit_reference");
+        inject(FaultTarget::Database).await?;
+
+        let row = sqlx::query(
+            "SELECT id, order_type, status, from_address, to_address, token_id, amount, bank_account, bank_service, banking_hash, filler_id, locked_amount, batch_id, created_at, updated_at FROM orders WHERE deposit_reference = ?"
+        )
+        .bind(reference)
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(|row| row_to_order(&row)).transpose()
+    }
+
+    /// Attach an on-chain deposit to a pending order that has not received one yet
+    /// Returns false if the order already has a deposit or has moved on from Pending
+    #[instrument(skip_all, fields(db.system = "sqlite", order_id = %order_id))]
+    pub async fn attach_deposit(pool: &SqlitePool, order_id: &str, banking_hash: &str) -> Result<bool> {
+        let _timer = QueryTimer::start("attach_deposit");
+        let result = sqlx::query(
+            "UPDATE orders SET banking_hash = ?1, updated_at = ?2 WHERE id = ?3 AND status = ?4 AND banking_hash IS NULL"
+        )
+        .bind(banking_hash)
+        .bind(Utc::now())
+        .bind(order_id)
+        .bind(OrderStatus::Pending as i32)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Number of orders a filler currently holds locked and their total locked amount
+    pub async fn get_filler_lock_usage(pool: &SqlitePool, filler_id: &str) -> Result<(u32, u64)> {
+        let locks = get_filler_locks(pool, filler_id).await?;
+        let locked_value = sum_locked_amounts(locks.iter().map(|(_, amount)| amount.as_str()));
+        Ok((locks.len() as u32, u64::try_from(locked_value).unwrap_or(u64::MAX)))
+    }
+
+    /// A filler's locked orders as (order id, locked amount), in order id order
+    pub async fn get_filler_locks(pool: &SqlitePool, filler_id: &str) -> Result<Vec<(String, String)>> {
+        let _timer = QueryTimer::start("get_filler_locks");
+        let rows = sqlx::query("SELECT id, COALESCE(locked_amount, '0') AS locked_amount FROM orders WHERE filler_id = ? AND status = ? ORDER BY id")
+            .bind(filler_id)
+            .bind(OrderStatus::Locked as i32)
+            .fetch_all(pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| Ok((row.try_get("id")?, row.try_get("locked_amount")?)))
+            .collect()
+    }
+
+    /// Total of decimal locked amounts, summed as U256 because SQLite's integer SUM overflows
+    /// past i64; amounts that do not parse count as zero
+    pub fn sum_locked_amounts<'a>(amounts: impl IntoIterator<Item = &'a str>) -> U256 {
+        amounts.into_iter()
+            .filter_map(|amount| U256::from_dec_str(amount).ok())
+            .fold(U256::zero(), |total, amount| total.saturating_add(amount))
+    }
+
     /// Update account balance
     pub async fn upsert_account_balance(
         pool: &SqlitePool, 
@@ -224,6 +1378,7 @@ pub mod helpers {
         token_id: u32, 
         balance: &str
     ) -> Result<()> {
+        let _timer = QueryTimer::start("upsert_account_balance");
         sqlx::query(
             r#"
             INSERT INTO account_balances (address, token_id, balance, updated_at)
@@ -242,10 +1397,37 @@ pub mod helpers {
         Ok(())
     }
     
-    /// Get account balances for an address
+    /// Persist the balances of accounts, e.g. as of a finalized batch
+    pub async fn store_account_balances(pool: &SqlitePool, accounts: &[AccountState]) -> Result<()> {
+        let _timer = QueryTimer::start("store_account_balances");
+        let mut tx = pool.begin().await?;
+        for account in accounts {
+            for balance in &account.balances {
+                sqlx::query(
+                    r#"
+                    INSERT INTO account_balances (address, token_id, balance, updated_at)
+                    VALUES (?1, ?2, ?3, ?4)
+                    ON CONFLICT(address, token_id)
+                    DO UPDATE SET balance = ?3, updated_at = ?4
+                    "#,
+                )
+                .bind(&account.address)
+                .bind(balance.token_id as i32)
+                .bind(&balance.balance)
+                .bind(account.updated_at)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Get account balances for an address, matched case-insensitively
     pub async fn get_account_balances(pool: &SqlitePool, address: &str) -> Result<Vec<TokenBalance>> {
+        let _timer = QueryTimer::start("get_account_balances");
         let rows = sqlx::query(
-            "SELECT token_id, balance FROM account_balances WHERE address = ?"
+            "SELECT token_id, balance FROM account_balances WHERE LOWER(address) = LOWER(?) ORDER BY token_id"
         )
         .bind(address)
         .fetch_all(pool)
@@ -262,8 +1444,89 @@ pub mod helpers {
         Ok(balances)
     }
     
+    /// Get a page of accounts with persisted balances, by address
+    ///
+    /// Returns up to `limit` accounts whose address sorts after `after`; with `token_id`, only
+    /// accounts holding a balance of that token.
+    pub async fn list_accounts(
+        pool: &SqlitePool,
+        token_id: Option<u32>,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<AccountState>> {
+        let _timer = QueryTimer::start("list_accounts");
+        // System accounts are read through GET /accounts/system and by address
+        let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new("SELECT DISTINCT address FROM account_balances WHERE address NOT LIKE ");
+        query.push_bind(format!("{}%", crate::services::system_accounts::SYSTEM_ACCOUNT_PREFIX));
+        if let Some(token_id) = token_id {
+            query.push(" AND token_id = ").push_bind(token_id as i32);
+        }
+        if let Some(after) = after {
+            query.push(" AND address > ").push_bind(after.to_string());
+        }
+        query.push(" ORDER BY address LIMIT ").push_bind(limit as i64);
+        let addresses: Vec<String> = query.build_query_scalar().fetch_all(pool).await?;
+        if addresses.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query = format!(
+            "SELECT address, token_id, balance, updated_at FROM account_balances WHERE address IN ({}) ORDER BY address, token_id",
+            placeholders(addresses.len())
+        );
+        let mut query = sqlx::query(&query);
+        for address in &addresses {
+            query = query.bind(address);
+        }
+
+        let mut accounts: Vec<AccountState> = Vec::with_capacity(addresses.len());
+        for row in query.fetch_all(pool).await? {
+            let address: String = row.try_get("address")?;
+            let balance = TokenBalance {
+                token_id: row.try_get::<i32, _>("token_id")? as u32,
+                balance: row.try_get("balance")?,
+            };
+            let updated_at = row.try_get("updated_at")?;
+            match accounts.last_mut() {
+                Some(account) if account.address == address => {
+                    account.balances.push(balance);
+                    account.updated_at = account.updated_at.max(updated_at);
+                }
+                _ => accounts.push(AccountState { address, balances: vec![balance], updated_at }),
+            }
+        }
+        Ok(accounts)
+    }
+
+    /// State root of the latest batch with a submitted proof, from its hot or archived snapshot
+    pub async fn latest_proven_root(pool: &SqlitePool) -> Result<Option<ProvenRoot>> {
+        let _timer = QueryTimer::start("latest_proven_root");
+        let row = sqlx::query(
+            r#"
+            SELECT batch_id, state_root FROM (
+                SELECT batch_id, state_root FROM batch_snapshots
+                UNION ALL
+                SELECT batch_id, state_root FROM batch_snapshot_archive
+            )
+            WHERE batch_id IN (SELECT batch_id FROM proof_submissions)
+            ORDER BY batch_id DESC LIMIT 1
+            "#,
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(|row| {
+            Ok(ProvenRoot {
+                batch_id: row.try_get::<i64, _>("batch_id")? as u64,
+                state_root: row.try_get("state_root")?,
+            })
+        })
+        .transpose()
+    }
+
     /// Count orders by status
     pub async fn count_orders_by_status(pool: &SqlitePool, status: OrderStatus) -> Result<i64> {
+        let _timer = QueryTimer::start("count_orders_by_status");
         let row = sqlx::query(
             "SELECT COUNT(*) as count FROM orders WHERE status = ?"
         )
@@ -276,6 +1539,7 @@ pub mod helpers {
     
     /// Clean up all test data
     pub async fn cleanup_test_data(pool: &SqlitePool) -> Result<()> {
+        let _timer = QueryTimer::start("cleanup_test_data");
         sqlx::query("DELETE FROM orders").execute(pool).await?;
         sqlx::query("DELETE FROM batches").execute(pool).await?;
         sqlx::query("DELETE FROM account_balances").execute(pool).await?;
@@ -284,6 +1548,7 @@ pub mod helpers {
 
     /// Get filler balance by filler ID
     pub async fn get_filler_balance(pool: &SqlitePool, filler_id: &str) -> Result<Option<FillerBalance>> {
+        let _timer = QueryTimer::start("get_filler_balance");
         // Get main balance info
         let balance_row = sqlx::query(
             "SELECT filler_id, total_balance, locked_balance, completed_jobs FROM filler_balances WHERE filler_id = ?"
@@ -344,6 +1609,7 @@ pub mod helpers {
 
     /// Create or update filler balance
     pub async fn upsert_filler_balance(pool: &SqlitePool, filler_id: &str, total_balance: &str) -> Result<()> {
+        let _timer = QueryTimer::start("upsert_filler_balance");
         sqlx::query(
             r#"
             INSERT INTO filler_balances (filler_id, total_balance) 
@@ -364,6 +1630,7 @@ pub mod helpers {
 
     /// Add wallet to filler
     pub async fn add_filler_wallet(pool: &SqlitePool, filler_id: &str, wallet_address: &str, balance: &str) -> Result<()> {
+        let _timer = QueryTimer::start("add_filler_wallet");
         sqlx::query(
             r#"
             INSERT INTO filler_wallets (filler_id, wallet_address, balance) 
@@ -385,6 +1652,7 @@ pub mod helpers {
 
     /// Update filler locked balance
     pub async fn update_filler_locked_balance(pool: &SqlitePool, filler_id: &str, locked_balance: &str) -> Result<()> {
+        let _timer = QueryTimer::start("update_filler_locked_balance");
         sqlx::query(
             "UPDATE filler_balances SET locked_balance = ?, updated_at = CURRENT_TIMESTAMP WHERE filler_id = ?"
         )
@@ -396,33 +1664,241 @@ pub mod helpers {
         Ok(())
     }
 
-    /// Record a claim
-    pub async fn insert_claim(pool: &SqlitePool, claim_id: &str, filler_id: &str, wallet_address: &str, 
-                            destination_address: &str, amount: &str, batch_id: Option<u32>) -> Result<()> {
-        sqlx::query(
+    /// Record a claim, with the conversion applied when it is paid out in another token
+    pub async fn insert_claim(pool: &SqlitePool, claim: &ClaimRecord) -> Result<()> {
+        let _timer = QueryTimer::start("insert_claim");
+        insert_claims(pool, std::slice::from_ref(claim)).await
+    }
+
+    /// Record several claims in one transaction: either all of them are stored or none is
+    ///
+    /// Fails with a unique violation if a claim redeems an order another claim already has.
+    pub async fn insert_claims(pool: &SqlitePool, claims: &[ClaimRecord]) -> Result<()> {
+        let _timer = QueryTimer::start("insert_claims");
+        let mut tx = pool.begin().await?;
+        for claim in claims {
+            // Claims reference filler_balances, which is not populated for every filler yet
+            sqlx::query("INSERT OR IGNORE INTO filler_balances (filler_id) VALUES (?)")
+                .bind(&claim.filler_id)
+                .execute(&mut *tx)
+                .await?;
+
+            let conversion = claim.conversion.as_ref();
+            sqlx::query(
+                r#"
+                INSERT INTO claims (id, filler_id, wallet_address, destination_address, amount, token_id,
+                                    payout_token_id, payout_amount, conversion_rate, rate_source, rate_quoted_at, batch_id,
+                                    order_id, status, transaction_hash, created_at, updated_at, fee_amount, net_amount)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?16, ?17, ?18)
+                "#
+            )
+            .bind(&claim.id)
+            .bind(&claim.filler_id)
+            .bind(&claim.wallet_address)
+            .bind(&claim.destination_address)
+            .bind(&claim.amount)
+            .bind(claim.token_id as i64)
+            .bind(claim.payout_token_id as i64)
+            .bind(&claim.payout_amount)
+            .bind(conversion.map(|c| &c.rate))
+            .bind(conversion.map(|c| &c.rate_source))
+            .bind(conversion.map(|c| c.quoted_at))
+            .bind(claim.batch_id.map(|id| id as i32))
+            .bind(claim.order_id.map(|id| id as i64))
+            .bind(&claim.status)
+            .bind(&claim.transaction_hash)
+            .bind(claim.created_at)
+            .bind(&claim.fee_amount)
+            .bind(&claim.net_amount)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Ids of the given on-chain orders that already back a claim
+    pub async fn claimed_order_ids(pool: &SqlitePool, order_ids: &[u32]) -> Result<Vec<u32>> {
+        let _timer = QueryTimer::start("claimed_order_ids");
+        if order_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query = format!(
+            "SELECT order_id FROM claims WHERE order_id IN ({}) ORDER BY order_id",
+            placeholders(order_ids.len())
+        );
+        let mut query = sqlx::query_scalar::<_, i64>(&query);
+        for order_id in order_ids {
+            query = query.bind(*order_id as i64);
+        }
+
+        Ok(query.fetch_all(pool).await?.into_iter().map(|id| id as u32).collect())
+    }
+
+    /// Get the claim redeeming an on-chain order
+    pub async fn get_claim_by_order(pool: &SqlitePool, order_id: u32) -> Result<Option<ClaimRecord>> {
+        let _timer = QueryTimer::start("get_claim_by_order");
+        let row = sqlx::query(
             r#"
-            INSERT INTO claims (id, filler_id, wallet_address, destination_address, amount, batch_id) 
-            VALUES (?, ?, ?, ?, ?, ?)
+            SELECT id, filler_id, wallet_address, destination_address, amount, token_id, payout_token_id,
+                   payout_amount, conversion_rate, rate_source, rate_quoted_at, batch_id, order_id, status,
+                   transaction_hash, created_at, fee_amount, net_amount
+            FROM claims WHERE order_id = ?
             "#
         )
+        .bind(order_id as i64)
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(|row| row_to_claim(&row)).transpose()
+    }
+
+    /// Mark a claim as settled on-chain by the given transaction
+    ///
+    /// Confirming again with the same transaction changes nothing and logs no event.
+    pub async fn confirm_claim(pool: &SqlitePool, claim_id: &str, transaction_hash: &str) -> Result<()> {
+        let _timer = QueryTimer::start("confirm_claim");
+        let result = sqlx::query(
+            "UPDATE claims SET status = 'confirmed', transaction_hash = ?1, updated_at = ?2 WHERE id = ?3 AND (status != 'confirmed' OR transaction_hash IS NOT ?1)"
+        )
+        .bind(transaction_hash)
+        .bind(Utc::now())
         .bind(claim_id)
-        .bind(filler_id)
-        .bind(wallet_address)
-        .bind(destination_address)
-        .bind(amount)
-        .bind(batch_id.map(|id| id as i32))
         .execute(pool)
         .await?;
 
+        if result.rows_affected() > 0 {
+            event_log::append(pool, &DomainEvent::ClaimPaid {
+                claim_id: claim_id.to_string(),
+                transaction_hash: transaction_hash.to_string(),
+            })
+            .await?;
+        }
         Ok(())
     }
+
+    /// Get a recorded claim
+    pub async fn get_claim(pool: &SqlitePool, claim_id: &str) -> Result<Option<ClaimRecord>> {
+        let _timer = QueryTimer::start("get_claim");
+        let row = sqlx::query(
+            r#"
+            SELECT id, filler_id, wallet_address, destination_address, amount, token_id, payout_token_id,
+                   payout_amount, conversion_rate, rate_source, rate_quoted_at, batch_id, order_id, status,
+                   transaction_hash, created_at, fee_amount, net_amount
+            FROM claims WHERE id = ?
+            "#
+        )
+        .bind(claim_id)
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(|row| row_to_claim(&row)).transpose()
+    }
+
+    /// Get the claims of several fillers, newest first
+    pub async fn get_claims_by_fillers(pool: &SqlitePool, filler_ids: &[String]) -> Result<Vec<ClaimRecord>> {
+        let _timer = QueryTimer::start("get_claims_by_fillers");
+        if filler_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query = format!(
+            r#"
+            SELECT id, filler_id, wallet_address, destination_address, amount, token_id, payout_token_id,
+                   payout_amount, conversion_rate, rate_source, rate_quoted_at, batch_id, order_id, status,
+                   transaction_hash, created_at, fee_amount, net_amount
+            FROM claims WHERE filler_id IN ({}) ORDER BY created_at DESC, id
+            "#,
+            placeholders(filler_ids.len())
+        );
+        let mut query = sqlx::query(&query);
+        for filler_id in filler_ids {
+            query = query.bind(filler_id);
+        }
+
+        query.fetch_all(pool).await?.iter().map(row_to_claim).collect()
+    }
+
+    /// Get a page of claims, newest first, optionally of a single filler
+    ///
+    /// Returns up to `limit` claims created before `after` in that order.
+    pub async fn list_claims(
+        pool: &SqlitePool,
+        filler_id: Option<&str>,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Vec<ClaimRecord>> {
+        let _timer = QueryTimer::start("list_claims");
+        let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+            r#"
+            SELECT id, filler_id, wallet_address, destination_address, amount, token_id, payout_token_id,
+                   payout_amount, conversion_rate, rate_source, rate_quoted_at, batch_id, order_id, status,
+                   transaction_hash, created_at, fee_amount, net_amount
+            FROM claims WHERE 1 = 1
+            "#,
+        );
+        if let Some(filler_id) = filler_id {
+            query.push(" AND filler_id = ").push_bind(filler_id.to_string());
+        }
+        if let Some(after) = after {
+            query.push(" AND (created_at, id) < (").push_bind(after.created_at);
+            query.push(", ").push_bind(after.id.clone()).push(")");
+        }
+        query.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(limit as i64);
+
+        query.build().fetch_all(pool).await?.iter().map(row_to_claim).collect()
+    }
+
+    fn row_to_claim(row: &sqlx::sqlite::SqliteRow) -> Result<ClaimRecord> {
+        let amount: String = row.try_get("amount")?;
+        let token_id = row.try_get::<i64, _>("token_id")? as u32;
+        // Claims recorded before payout tokens existed were paid out as claimed
+        let payout_token_id = row.try_get::<Option<i64>, _>("payout_token_id")?.map_or(token_id, |id| id as u32);
+        let payout_amount = row.try_get::<Option<String>, _>("payout_amount")?.unwrap_or_else(|| amount.clone());
+        // and claims recorded before claim fees transferred their whole amount
+        let fee_amount = row.try_get::<Option<String>, _>("fee_amount")?.unwrap_or_else(|| "0".to_string());
+        let net_amount = row.try_get::<Option<String>, _>("net_amount")?.unwrap_or_else(|| amount.clone());
+
+        let conversion = match row.try_get::<Option<String>, _>("conversion_rate")? {
+            Some(rate) => Some(TokenConversion {
+                from_token_id: token_id,
+                to_token_id: payout_token_id,
+                source_amount: net_amount.clone(),
+                converted_amount: payout_amount.clone(),
+                rate,
+                rate_source: row.try_get::<Option<String>, _>("rate_source")?.unwrap_or_default(),
+                quoted_at: row.try_get("rate_quoted_at")?,
+            }),
+            None => None,
+        };
+
+        Ok(ClaimRecord {
+            id: row.try_get("id")?,
+            filler_id: row.try_get("filler_id")?,
+            wallet_address: row.try_get("wallet_address")?,
+            destination_address: row.try_get("destination_address")?,
+            amount,
+            fee_amount,
+            net_amount,
+            token_id,
+            payout_token_id,
+            payout_amount,
+            conversion,
+            batch_id: row.try_get::<Option<i64>, _>("batch_id")?.map(|id| id as u64),
+            order_id: row.try_get::<Option<i64>, _>("order_id")?.map(|id| id as u32),
+            status: row.try_get("status")?,
+            transaction_hash: row.try_get("transaction_hash")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use super::helpers::*;
-    use crate::models::{Order, OrderType, OrderStatus, TokenBalance};
+    use crate::models::{Order, OrderType, OrderStatus, TokenBalance, ClaimRecord, Cursor};
     use chrono::Utc;
     use uuid::Uuid;
 
@@ -515,6 +1991,80 @@ mod tests {
         assert_eq!(retrieved_order.token_id, order.token_id);
     }
 
+    #[tokio::test]
+    async fn test_order_permit_roundtrip() {
+        let pool = setup_test_db().await;
+        let order = create_test_order("permit_order", OrderType::BridgeIn, OrderStatus::Pending, "1000000");
+        insert_order(&pool, &order).await.unwrap();
+
+        assert!(get_order_permit(&pool, &order.id).await.unwrap().is_none());
+
+        let permit = crate::models::PermitData {
+            owner: "0x1234567890123456789012345678901234567890".to_string(),
+            spender: "0x0000000000000000000000000000000000000000".to_string(),
+            value: "1000000".to_string(),
+            nonce: Some("0".to_string()),
+            deadline: 1_900_000_000,
+            signature: format!("0x{}1b", "11".repeat(64)),
+        };
+        insert_order_permit(&pool, &order.id, &permit).await.unwrap();
+
+        let stored = get_order_permit(&pool, &order.id).await.unwrap().unwrap();
+        assert_eq!(stored, permit);
+    }
+
+    #[tokio::test]
+    async fn test_claims_page_in_stable_order() {
+        let pool = setup_test_db().await;
+        let created_at = Utc::now();
+        let claim = |id: &str, filler_id: &str| ClaimRecord {
+            id: id.to_string(),
+            filler_id: filler_id.to_string(),
+            wallet_address: "0x0000000000000000000000000000000000000000".to_string(),
+            destination_address: "0x1234567890123456789012345678901234567890".to_string(),
+            amount: "100".to_string(),
+            fee_amount: "0".to_string(),
+            net_amount: "100".to_string(),
+            token_id: 1,
+            payout_token_id: 1,
+            payout_amount: "100".to_string(),
+            conversion: None,
+            batch_id: None,
+            order_id: None,
+            status: "pending".to_string(),
+            transaction_hash: None,
+            created_at,
+        };
+        // Same timestamp throughout, so only the id orders them
+        for id in ["c1", "c2", "c3", "c4", "c5"] {
+            insert_claim(&pool, &claim(id, if id == "c3" { "filler_2" } else { "filler_1" })).await.unwrap();
+        }
+
+        let mut listed = Vec::new();
+        let mut after = None;
+        loop {
+            let page = list_claims(&pool, None, after.as_ref(), 2).await.unwrap();
+            let Some(last) = page.last() else { break };
+            after = Some(Cursor::new(last.created_at, &last.id));
+            listed.extend(page.into_iter().map(|claim| claim.id));
+        }
+        assert_eq!(listed, vec!["c5", "c4", "c3", "c2", "c1"]);
+
+        let filler_1: Vec<String> = list_claims(&pool, Some("filler_1"), None, 10).await.unwrap()
+            .into_iter().map(|claim| claim.id).collect();
+        assert_eq!(filler_1, vec!["c5", "c4", "c2", "c1"]);
+
+        // Claims stored with CURRENT_TIMESTAMP are migrated to sort among newer ones
+        sqlx::query("INSERT INTO claims (id, filler_id, wallet_address, destination_address, amount, created_at) VALUES ('legacy', 'filler_1', '0x0', '0x0', '1', '2020-01-01 00:00:00')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        run_migrations(&pool).await.unwrap();
+        let oldest = list_claims(&pool, None, Some(&Cursor::new(created_at, "c1")), 10).await.unwrap();
+        assert_eq!(oldest.len(), 1);
+        assert_eq!(oldest[0].created_at, "2020-01-01T00:00:00Z".parse::<chrono::DateTime<Utc>>().unwrap());
+    }
+
     #[tokio::test]
     async fn test_order_status_mapping() {
         let pool = setup_test_db().await;
@@ -525,6 +2075,8 @@ mod tests {
             OrderStatus::MarkPaid,
             OrderStatus::Settled,
             OrderStatus::Failed,
+            OrderStatus::Disputed,
+            OrderStatus::ReviewPending,
         ];
         
         for status in test_cases {
@@ -708,5 +2260,24 @@ mod tests {
         assert_eq!(balances[0].balance, large_amount, "Should preserve large balance precision");
     }
 
+    #[tokio::test]
+    async fn test_filler_lock_usage_sums_amounts_past_i64() {
+        let pool = setup_test_db().await;
+
+        for (i, amount) in ["9223372036854775807", "9223372036854775807", "999999999999999999999999999999999999"].iter().enumerate() {
+            let mut order = create_test_order(&format!("lock_{}", i), OrderType::BridgeIn, OrderStatus::Locked, amount);
+            order.filler_id = Some("filler_1".to_string());
+            order.locked_amount = Some(amount.to_string());
+            insert_order(&pool, &order).await.unwrap();
+        }
+
+        let locks = get_filler_locks(&pool, "filler_1").await.unwrap();
+        assert_eq!(locks.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), ["lock_0", "lock_1", "lock_2"]);
+        let total = sum_locked_amounts(locks.iter().map(|(_, amount)| amount.as_str()));
+        assert_eq!(total, web3::types::U256::from_dec_str("1000000000000000018446744073709551613").unwrap());
+        // Past u64 the usage saturates, which is over any configured limit
+        assert_eq!(get_filler_lock_usage(&pool, "filler_1").await.unwrap(), (3, u64::MAX));
+    }
+
 
 }
\ No newline at end of file
@@ -1,58 +1,84 @@
 use axum::{
-    routing::{get, post},
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{delete, get, post, put},
     Router,
 };
+use clap::Parser;
 use std::net::SocketAddr;
 
-use tower_http::cors::CorsLayer;
+use tower_http::trace::{DefaultMakeSpan, TraceLayer};
 use tracing::{info, error, warn, Level};
-use chrono;
-use tracing_subscriber::FmtSubscriber;
 
 mod api;
 mod config;
 mod database;
 mod models;
 mod services;
-mod blockchain;
 mod merkle;
-
-// Library modules
-mod lib {
-    pub mod sparse_merkle_tree;
-    
-    pub use sparse_merkle_tree::{
-        SparseMerkleTree, 
-        SparseMerkleLeaf, 
-        MerkleProof, 
-        ethereum_address_to_path, 
-        index_to_path
-    };
-}
+mod signer;
+mod telemetry;
 
 use config::Config;
 
+#[derive(Parser)]
+#[command(name = "vapor-server", about = "Vapor backend server")]
+struct Cli {
+    /// Run the order-to-proof pipeline against a temporary database, print a report and exit
+    /// (non-zero if any stage fails)
+    #[arg(long)]
+    self_test: bool,
+
+    /// Discard the relayer checkpoint even if it was taken on another chain (e.g. after an
+    /// anvil reset or a network switch), and scan the current chain from scratch
+    #[arg(long)]
+    reset_relayer_checkpoint: bool,
+
+    /// Restore the database from this backup (see GET /api/v1/admin/backups) before starting;
+    /// the backup is verified first and the current database is backed up
+    #[arg(long, value_name = "BACKUP_ID")]
+    restore_backup: Option<i64>,
+
+    /// Replay the event log (see GET /api/v1/events) against the database, print a report and
+    /// exit (non-zero if the replayed state diverges from the database)
+    #[arg(long)]
+    replay_events: bool,
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize tracing
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber)?;
+    let cli = Cli::parse();
+    if cli.self_test {
+        // Hermetic: needs no configuration, database or chain
+        let report = services::self_test::run().await;
+        println!("{}", report);
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
 
     // Load configuration
     dotenv::dotenv().ok();
     let config = Config::from_env()?;
+    config.check_profile()?;
+
+    // Initialize tracing, exporting spans when OTEL_EXPORTER_OTLP_ENDPOINT is set
+    let tracer_provider = telemetry::init(&config.telemetry)?;
     
-    info!("Starting Vapor Backend Server...");
+    info!("Starting Vapor Backend Server ({} profile)...", config.profile.as_str());
     info!("Contract address: {}", config.blockchain.contract_address);
 
-    // Initialize database
+    // Initialize database, logging data-access operations slower than DB_SLOW_QUERY_MS
+    services::query_metrics::global().set_slow_query_ms(config.diagnostics.slow_query_ms);
     let db = database::init_db(&config.database.url).await?;
     
     // Run database migrations
     database::run_migrations(&db).await?;
 
+    if cli.replay_events {
+        let report = services::event_log::replay(&db).await?;
+        println!("{}", report);
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
+
     // Store port before moving config
     let port = config.api.port;
 
@@ -68,7 +94,7 @@ async fn main() -> anyhow::Result<()> {
     let usdc_address = config.blockchain.usdc_address.parse()
         .map_err(|_| anyhow::anyhow!("Invalid USDC_CONTRACT format"))?;
     
-    let blockchain_client = crate::blockchain::BlockchainClient::new(
+    let blockchain_client = vapor_chain::BlockchainClient::new(
         config.blockchain.rpc_url.clone(),
         bridge_address,
         proof_verifier_address,
@@ -76,19 +102,57 @@ async fn main() -> anyhow::Result<()> {
         1, // Chain ID (anvil default)
     ).await?;
     
+    let tx_signer = signer::signer_from_config(&config.signer, &config.blockchain)?;
     let mut app_state = api::AppState::new(config, db);
-    app_state = app_state.with_blockchain_client(blockchain_client);
+    let blockchain_client = blockchain_client
+        .with_signer(tx_signer.clone())
+        .with_event_metrics(app_state.relayer_metrics.deposit_abi())
+        .with_read_cache(app_state.chain_reads.clone());
+    
+    app_state = app_state
+        .with_blockchain_client(blockchain_client)
+        .with_receipt_signer(tx_signer.clone())
+        .with_manifest_signer(tx_signer.clone())
+        .with_attestation_signer(tx_signer.clone())
+        .with_quote_signer(tx_signer);
+    if let Some(backup_id) = cli.restore_backup {
+        let report = app_state.backups.restore(backup_id, app_state.clock.now()).await?;
+        info!("Restored database backup {} ({} tables); pre-restore backup is {}", backup_id, report.tables.len(), report.pre_restore_backup_id);
+    }
+    app_state.maintenance.restore().await?;
+
+    // Only the lease holder loads the writer state; a standby loads it when it takes over
+    let role = app_state.writer_lease.refresh(app_state.clock.now()).await?;
+    if role.accepts_writes() {
+        // Continue the batch sequence from the last finalized batch rather than from batch 1
+        let report = api::handover::assume_writer(&app_state, &[]).await?;
+        info!("Resuming after batch {}", report.batch_id);
+    } else {
+        warn!("Starting on standby ({}), waiting for a handover or for the lease to lapse", role);
+    }
 
     // Initialize and start relayer service
     if let Some(blockchain_client) = &app_state.blockchain_client {
-        let relayer_config = services::relayer::RelayerConfig::default();
+        let relayer_config = services::relayer::RelayerConfig {
+            scan_range_blocks: app_state.config.relayer.range_blocks,
+            max_concurrent_ranges: app_state.config.relayer.max_concurrent_ranges,
+            reset_checkpoint: cli.reset_relayer_checkpoint,
+            finality: app_state.config.relayer.finality.clone(),
+            confirmation_depth: app_state.config.relayer.confirmation_depth,
+            ..Default::default()
+        };
         let relayer = services::relayer::RelayerService::new(
             blockchain_client.clone(),
             app_state.db.clone(),
             app_state.matching_engine.clone(),
             app_state.batch_processor.clone(),
             relayer_config.clone(),
-        ).await?;
+        ).await?
+        .with_event_bus(app_state.event_bus.clone())
+        .with_maintenance(app_state.maintenance.clone())
+        .with_metrics(app_state.relayer_metrics.clone())
+        .with_intent_revival(app_state.config.sla.intent_revival_seconds)
+        .with_order_review(app_state.order_review.clone());
         
         app_state = app_state.with_relayer_service(relayer).await;
         
@@ -112,27 +176,53 @@ async fn main() -> anyhow::Result<()> {
         warn!("Blockchain client not configured, relayer service disabled");
     }
 
+    // Writer lease: renew it, or take over writing once the writer's lease lapses
+    if app_state.writer_lease.enabled() {
+        let lease_state = app_state.clone();
+        tokio::spawn(async move {
+            let lease = &lease_state.writer_lease;
+            loop {
+                tokio::time::sleep(lease.renew_interval()).await;
+                let _transition = lease.lock_transitions().await;
+                let was_writing = lease.role().accepts_writes();
+                match lease.refresh(lease_state.clock.now()).await {
+                    Ok(role) if role.accepts_writes() && !was_writing => {
+                        warn!("Writer lease lapsed without a handover, taking over");
+                        if let Err(e) = api::handover::assume_writer(&lease_state, &[]).await {
+                            error!("Failed to take over as writer, writes stay paused: {}", e);
+                            lease.freeze();
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        error!("Writer lease refresh failed: {}", e);
+                        // Another instance may take the lease once it expires, so stop writing first
+                        if lease.check_lapsed(lease_state.clock.now()) {
+                            error!("Writer lease expired before it could be renewed, writes are paused");
+                        }
+                    }
+                }
+            }
+        });
+    }
+
     // Auto-discovery service: Automatically move Pending orders to Discovery
     let discovery_db = app_state.db.clone();
+    let discovery_events = app_state.event_bus.clone();
+    let discovery_maintenance = app_state.maintenance.clone();
     tokio::spawn(async move {
         loop {
             // Wait 5 seconds between checks
             tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            if discovery_maintenance.is_enabled() {
+                continue;
+            }
             
-            // Get all pending BridgeIn orders and move them to discovery
-            // Exclude Transfer orders as they should be processed by batch processor
-            let query = "UPDATE orders SET status = $1, updated_at = $2 WHERE status = $3 AND order_type = $4";
-            match sqlx::query(query)
-                .bind(crate::models::OrderStatus::Discovery as i32)
-                .bind(chrono::Utc::now())
-                .bind(crate::models::OrderStatus::Pending as i32)
-                .bind(crate::models::OrderType::BridgeIn as i32)
-                .execute(&discovery_db)
-                .await
-            {
-                Ok(result) => {
-                    if result.rows_affected() > 0 {
-                        info!("Auto-discovery: Moved {} BridgeIn orders from Pending to Discovery", result.rows_affected());
+            // Move pending BridgeIn orders to discovery and notify subscribed fillers
+            match services::discovery::promote_pending_orders(&discovery_db, &discovery_events).await {
+                Ok(promoted) => {
+                    if promoted > 0 {
+                        info!("Auto-discovery: Moved {} BridgeIn orders from Pending to Discovery", promoted);
                     }
                 }
                 Err(e) => {
@@ -144,6 +234,278 @@ async fn main() -> anyhow::Result<()> {
     
     info!("Auto-discovery service started - will move Pending BridgeIn orders to Discovery every 5 seconds");
 
+    // Lock expiry watcher: warn fillers before their order locks lapse
+    let mut lock_watcher = services::discovery::LockExpiryWatcher::new(
+        app_state.config.filler.lock_ttl_seconds,
+        app_state.config.filler.lock_expiry_warning_seconds,
+    )
+    .with_clock(app_state.clock.clone());
+    let lock_watcher_db = app_state.db.clone();
+    let lock_watcher_events = app_state.event_bus.clone();
+    let lock_watcher_lease = app_state.writer_lease.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(15)).await;
+            // Only the writer warns, or fillers would hear once per instance
+            if !lock_watcher_lease.role().accepts_writes() {
+                continue;
+            }
+            
+            if let Err(e) = lock_watcher.sweep(&lock_watcher_db, &lock_watcher_events).await {
+                error!("Lock expiry watcher failed: {}", e);
+            }
+        }
+    });
+
+    // SLA sweeper: fail or dispute orders stuck in a state past their SLA
+    let sla_policy = services::sla::SlaPolicy::from_config(&app_state.config)
+        .with_clock(app_state.clock.clone());
+    let sla_interval = app_state.config.sla.sweep_interval_seconds.max(1);
+    let sla_db = app_state.db.clone();
+    let sla_engine = app_state.matching_engine.clone();
+    let sla_metrics = app_state.sla_metrics.clone();
+    let sla_saga = app_state.settlement_saga.clone();
+    let sla_collateral = app_state.collateral.clone();
+    let sla_maintenance = app_state.maintenance.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(sla_interval)).await;
+            // Orders cannot progress while intake is paused, so don't fail them for it
+            if sla_maintenance.is_enabled() {
+                continue;
+            }
+            
+            let breaches = match services::sla::sweep_overdue_orders(&sla_db, &sla_policy, &sla_engine, &sla_metrics).await {
+                Ok(breaches) => breaches,
+                Err(e) => {
+                    error!("SLA sweeper failed: {}", e);
+                    continue;
+                }
+            };
+            // Expired locks end their settlement sagas; the sweeper already released the lock
+            for breach in breaches.iter().filter(|breach| breach.from_status == models::OrderStatus::Locked) {
+                if let Err(e) = sla_collateral.record_expiry(&breach.order_id).await {
+                    error!("Failed to record lock expiry against filler collateral for order {}: {}", breach.order_id, e);
+                }
+                match sla_saga.fail(&breach.order_id, breach.reason.as_str()).await {
+                    Ok(_) | Err(services::settlement_saga::SagaError::NotFound(_)) => {}
+                    Err(e) => error!("Failed to compensate settlement saga for order {}: {}", breach.order_id, e),
+                }
+            }
+        }
+    });
+
+    // Intent expiry: fail API-created BridgeIn orders whose deposit never arrived
+    let intent_expiry = services::intent_expiry::IntentExpiry::from_config(&app_state.config.sla)
+        .with_clock(app_state.clock.clone());
+    if intent_expiry.enabled() {
+        let intent_state = app_state.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(sla_interval)).await;
+                if intent_state.maintenance.is_enabled() {
+                    continue;
+                }
+                if let Err(e) = intent_expiry.sweep(&intent_state.db, &intent_state.matching_engine, &intent_state.event_bus).await {
+                    error!("Intent expiry sweep failed: {}", e);
+                }
+            }
+        });
+    }
+
+    // Delayed jobs: payment verification retries, settlement checks and lock expiry
+    let jobs_state = app_state.clone();
+    let jobs_interval = app_state.config.jobs.poll_interval_seconds.max(1);
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(jobs_interval)).await;
+            // Like the SLA sweeper, leave orders alone while intake is paused
+            if jobs_state.maintenance.is_enabled() {
+                continue;
+            }
+            if let Err(e) = api::jobs::run_due_jobs(&jobs_state).await {
+                error!("Scheduled jobs failed: {}", e);
+            }
+        }
+    });
+
+    // Nightly order book reconciliation between the matching engine, the database and the bridge
+    let reconciler = app_state.order_reconciler.clone();
+    let reconcile_chain = app_state.blockchain_client.clone();
+    let reconcile_lease = app_state.writer_lease.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(reconciler.until_next_run()).await;
+            if !reconcile_lease.role().accepts_writes() {
+                continue;
+            }
+            if let Err(e) = reconciler.run(reconcile_chain.as_deref(), services::order_reconciliation::ReconciliationTrigger::Scheduled).await {
+                error!("Order book reconciliation failed: {}", e);
+            }
+        }
+    });
+
+    // Bank details retention: scrub account details of orders settled or failed long enough ago
+    let retention_db = app_state.db.clone();
+    let retention_clock = app_state.clock.clone();
+    let retention = app_state.config.retention.clone();
+    let retention_lease = app_state.writer_lease.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(retention.sweep_interval_seconds.max(1))).await;
+            if !retention_lease.role().accepts_writes() {
+                continue;
+            }
+            if let Err(e) = services::retention::scrub_expired(&retention_db, &retention, retention_clock.now()).await {
+                error!("Bank details retention sweep failed: {}", e);
+            }
+        }
+    });
+
+    // Database backups: snapshot into the blob store on a schedule, keeping the newest BACKUP_RETAIN
+    if app_state.config.backup.interval_seconds > 0 {
+        let backups = app_state.backups.clone();
+        let backup_clock = app_state.clock.clone();
+        let backup_interval = app_state.config.backup.interval_seconds;
+        let backup_lease = app_state.writer_lease.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(backup_interval)).await;
+                if !backup_lease.role().accepts_writes() {
+                    continue;
+                }
+                if let Err(e) = backups.take(services::backups::BackupTrigger::Scheduled, backup_clock.now()).await {
+                    error!("Scheduled database backup failed: {}", e);
+                }
+            }
+        });
+        info!("Database backups scheduled every {} seconds", backup_interval);
+    }
+
+    // Analytics and notifications: relay the event log onto the event bus; anonymized order
+    // events and user notifications are derived from it
+    if app_state.config.analytics.enabled {
+        let emitter = services::analytics::AnalyticsEmitter::new(
+            app_state.db.clone(),
+            app_state.registry.clone(),
+            &app_state.config.analytics,
+        );
+        tokio::spawn(emitter.run(app_state.event_bus.subscribe_logged_events()));
+        info!("Analytics events enabled, sampling {} of orders", app_state.config.analytics.sample_rate);
+    }
+    if app_state.notifications.is_enabled() {
+        tokio::spawn(app_state.notifications.clone().run(app_state.event_bus.subscribe_logged_events(), app_state.scheduler.clone()));
+        info!("Order lifecycle notifications enabled");
+    }
+    if app_state.config.analytics.enabled || app_state.notifications.is_enabled() {
+        let relay_db = app_state.db.clone();
+        let relay_events = app_state.event_bus.clone();
+        let relay_lease = app_state.writer_lease.clone();
+        let mut relayed = services::event_log::last_seq(&relay_db).await?;
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                // Followers skip ahead so events are emitted once, by whichever instance writes
+                if !relay_lease.role().accepts_writes() {
+                    match services::event_log::last_seq(&relay_db).await {
+                        Ok(seq) => relayed = seq,
+                        Err(e) => error!("Event log relay failed: {}", e),
+                    }
+                    continue;
+                }
+                match services::event_log::relay(&relay_db, &relay_events, relayed).await {
+                    Ok(seq) => relayed = seq,
+                    Err(e) => error!("Event log relay failed: {}", e),
+                }
+            }
+        });
+    }
+
+    // Market summary refresher: public summary requests are served from this cache
+    let market_summary = app_state.market_summary.clone();
+    let market_refresh = app_state.config.market.refresh_seconds.max(1);
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = market_summary.refresh().await {
+                error!("Market summary refresh failed: {}", e);
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(market_refresh)).await;
+        }
+    });
+
+    // SLO refresher: per-phase latency percentiles are recomputed from the status history
+    let slo = app_state.slo.clone();
+    let slo_refresh = app_state.config.slo.refresh_seconds.max(1);
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = slo.refresh().await {
+                error!("SLO refresh failed: {}", e);
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(slo_refresh)).await;
+        }
+    });
+
+    // Order queue consumer: orders pushed to a Redis stream take the same path as POST /orders
+    if app_state.config.order_queue.redis_url.is_some() {
+        let queue_state = app_state.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = api::order_queue::run(&queue_state).await {
+                    error!("Order queue consumer failed: {:#}", e);
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+            }
+        });
+        info!("Order queue consumer started on stream {}", app_state.config.order_queue.stream);
+    }
+
+    // Balance alerts: warn operators when the bridge, a filler or the submission signer runs low
+    let watch_signer = app_state.config.balance_alerts.signer_min_submissions > 0
+        && app_state.blockchain_client.as_ref().is_some_and(|client| client.signer.is_some());
+    if !app_state.config.balance_alerts.rules.is_empty() || watch_signer {
+        let alerts_state = app_state.clone();
+        let alerts_interval = app_state.config.balance_alerts.interval_seconds.max(1);
+        tokio::spawn(async move {
+            loop {
+                if alerts_state.writer_lease.role().accepts_writes() {
+                    alerts_state.balance_alerts.evaluate(
+                        alerts_state.blockchain_client.as_deref(),
+                        &alerts_state.batch_processor,
+                        &alerts_state.config.blockchain.contract_address,
+                    ).await;
+                    if let Some(client) = alerts_state.blockchain_client.as_deref().filter(|_| watch_signer) {
+                        alerts_state.balance_alerts.evaluate_signer(client, &alerts_state.db).await;
+                    }
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(alerts_interval)).await;
+            }
+        });
+        info!("Balance alerts enabled with {} rules", app_state.config.balance_alerts.rules.len());
+    }
+
+    // Claim reconciliation: compare on-chain ClaimEvents with the claims table to catch drift
+    if let Some(chain) = app_state.blockchain_client.clone() {
+        let reconcile_state = app_state.clone();
+        let reconcile_interval = app_state.config.claims.reconcile_interval_seconds.max(1);
+        tokio::spawn(async move {
+            loop {
+                if !reconcile_state.writer_lease.role().accepts_writes() {
+                    tokio::time::sleep(tokio::time::Duration::from_secs(reconcile_interval)).await;
+                    continue;
+                }
+                match reconcile_state.claim_reconciler.run_once(&reconcile_state.db, &chain).await {
+                    Ok(report) if report.has_drift() => error!(
+                        "Claim drift in blocks {}-{}: {} orders claimed on-chain without a local claim, {} mismatched",
+                        report.from_block, report.to_block, report.missing_locally.len(), report.mismatched.len()
+                    ),
+                    Ok(_) => {}
+                    Err(e) => error!("Claim reconciliation failed: {:#}", e),
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(reconcile_interval)).await;
+            }
+        });
+    }
+
     // Build our application with routes
     let app = Router::new()
         // Health endpoints
@@ -153,42 +515,153 @@ async fn main() -> anyhow::Result<()> {
         // Order management endpoints
         .route("/api/v1/orders", post(api::orders::create_order))
         .route("/api/v1/orders", get(api::orders::list_orders))
+        .route("/api/v1/orders/quote", post(api::orders::quote_order))
+        .route("/api/v1/transfers", post(api::orders::create_transfer))
         .route("/api/v1/orders/:order_id", get(api::orders::get_order))
         .route("/api/v1/orders/:order_id/status", get(api::orders::get_order_status))
+        .route("/api/v1/orders/:order_id/permit", get(api::orders::get_order_permit))
+        .route("/api/v1/orders/:order_id/history", get(api::orders::get_order_history))
+        .route("/api/v1/orders/:order_id/receipt", get(api::orders::get_order_receipt))
+        .route("/api/v1/receipts/verify", post(api::orders::verify_receipt))
         .route("/api/v1/orders/:order_id/mark-paid", post(api::orders::mark_paid))
         .route("/api/v1/orders/:order_id/mark-discovery", post(api::orders::mark_discovery))
+        .route("/api/v1/orders/:order_id/settle-partial", post(api::orders::settle_partial))
         .route("/api/v1/orders/match", post(api::orders::match_orders))
+        .route("/api/v1/orders/sla-metrics", get(api::orders::get_sla_metrics))
+        .route("/api/v1/stats/slo", get(api::orders::get_slo_report))
+        .route("/api/v1/stats/matching", get(api::orders::get_matching_stats))
+        .route("/api/v1/events", get(api::events::list_events))
+        .route("/api/v1/order-queue/messages/:message_id", get(api::order_queue::get_queued_message))
+        
+        // Public market data
+        .route("/api/v1/market/summary", get(api::market::get_market_summary))
+        .route("/api/v1/explorer/state/:batch_id/leaves", get(api::explorer::get_state_leaves))
+        .route("/api/v1/explorer/batches/:batch_id/manifest", get(api::explorer::get_batch_manifest))
+        .route("/api/v1/explorer/manifests/verify", post(api::explorer::verify_manifest))
+        
+        // Read-only GraphQL queries over orders, batches, accounts, fillers and proofs
+        .route("/api/v1/graphql", post(api::graphql::graphql_handler))
         
         // Filler endpoints
         .route("/api/v1/fillers/discovery", get(api::fillers::get_discovery_orders))
+        .route("/api/v1/fillers/:filler_id/capabilities", get(api::fillers::get_capabilities).put(api::fillers::register_capabilities))
+        .route("/api/v1/fillers/:filler_id/claim-relay", get(api::fillers::get_claim_relay).put(api::fillers::set_claim_relay))
+        .route("/api/v1/fillers/:filler_id/collateral", get(api::fillers::get_collateral))
+        .route("/api/v1/bank-services", get(api::fillers::get_bank_services))
+        .route("/api/v1/fillers/ws", get(api::fillers::filler_feed))
         .route("/api/v1/fillers/orders/:order_id/lock", post(api::fillers::lock_order))
         .route("/api/v1/fillers/orders/:order_id/payment-proof", post(api::fillers::submit_payment_proof))
+        .route("/api/v1/fillers/payment-proof/validate", post(api::fillers::validate_payment_proof))
         .route("/api/v1/fillers/:filler_id/balance", get(api::fillers::get_filler_balance_api))
         .route("/api/v1/fillers/:filler_id/wallets", post(api::fillers::add_wallet_to_filler))
         .route("/api/v1/fillers/claim", post(api::fillers::claim_tokens))
+        .route("/api/v1/fillers/claim/preview", post(api::fillers::preview_claim))
+        .route("/api/v1/fillers/claims", get(api::fillers::list_claims))
+        .route("/api/v1/fillers/claims/:claim_id", get(api::fillers::get_claim))
         
         // Batch processing endpoints
         .route("/api/v1/batch/start", post(api::batch::start_batch))
         .route("/api/v1/batch/finalize", post(api::batch::finalize_batch))
+        .route("/api/v1/batch/abort", post(api::batch::abort_batch))
+        .route("/api/v1/batch/aborted", get(api::batch::list_aborted_batches))
+        .route("/api/v1/batch/dry-run", post(api::batch::dry_run_batch))
         .route("/api/v1/batch/prove", post(api::batch::prove_batch))
         .route("/api/v1/batch/stats", get(api::batch::get_batch_stats))
+        .route("/api/v1/batch/archive", get(api::batch::get_archive_stats))
+        .route("/api/v1/batches/by-root/:root", get(api::batch::get_batches_by_root))
+        .route("/api/v1/batch/submissions", get(api::batch::get_submission_sizes))
+        .route("/api/v1/batch/:batch_id/costs", get(api::batch::get_batch_costs))
+        .route("/api/v1/stats/costs", get(api::batch::get_cost_history))
         .route("/api/v1/batch/current", get(api::batch::get_current_batch))
-        .route("/api/v1/batch/init-account", post(api::batch::init_account))
+        // Deprecated alias of POST /api/v1/accounts
+        .route("/api/v1/batch/init-account", post(api::accounts::init_account_deprecated))
+        
+        // Account endpoints
+        .route("/api/v1/accounts", get(api::accounts::list_accounts).post(api::accounts::init_account))
+        .route("/api/v1/accounts/system", get(api::accounts::get_system_ledger))
+        .route("/api/v1/accounts/:address", get(api::accounts::get_account))
+        .route("/api/v1/admin/accounts/bulk", post(api::accounts::init_accounts_bulk))
         
         // Proof endpoints
         .route("/api/v1/proofs/order/:batch_id/:order_id", get(api::proofs::get_order_proof))
         .route("/api/v1/proofs/account/:address", get(api::proofs::get_account_proof))
+        .route("/api/v1/proofs/account/:address/attestation", get(api::proofs::get_balance_attestation))
+        .route("/api/v1/proofs/attestations/verify", post(api::proofs::verify_balance_attestation))
+        .route("/api/v1/sync/diff", get(api::sync::get_state_diff))
+        .route("/api/v1/notifications/:address", get(api::notifications::get_notifications))
+        .route("/api/v1/notifications/:address/preferences", get(api::notifications::get_notification_preferences).put(api::notifications::set_notification_preferences))
         .route("/api/v1/proofs/verify", post(api::proofs::verify_proof))
+        .route("/api/v1/proofs/verify/cache", get(api::proofs::get_verification_cache_metrics))
+        .route("/api/v1/proofs/multiproof", post(api::proofs::get_order_multiproof))
         .route("/api/v1/proofs/batch/:batch_id", get(api::proofs::get_batch_proofs))
         .route("/api/v1/proofs/stats", get(api::proofs::get_proof_stats))
         
         // Relayer endpoints
         .route("/api/v1/relayer/status", get(api::relayer::get_relayer_status))
+        .route("/api/v1/relayer/metrics", get(api::relayer::get_relayer_metrics))
+        .route("/api/v1/relayer/read-cache", get(api::relayer::get_read_cache_metrics))
         .route("/api/v1/relayer/process-events", post(api::relayer::process_events_manually))
         .route("/api/v1/relayer/config", post(api::relayer::update_relayer_config))
         .route("/api/v1/relayer/blockchain", get(api::relayer::get_blockchain_status))
+        .route("/api/v1/prover/config", get(api::batch::get_prover_config).post(api::batch::update_prover_config))
         
-        .layer(CorsLayer::permissive())
+        // Admin endpoints
+        .route("/api/v1/admin/overview", get(api::overview::get_overview))
+        .route("/api/v1/admin/config", get(api::admin::get_config))
+        .route("/api/v1/admin/maintenance", get(api::admin::get_maintenance).put(api::admin::set_maintenance))
+        .route("/api/v1/admin/withdrawals/lists", get(api::admin::get_withdrawal_lists))
+        .route("/api/v1/admin/withdrawals/lists/:address", put(api::admin::set_withdrawal_list_entry)
+            .delete(api::admin::remove_withdrawal_list_entry))
+        .route("/api/v1/admin/withdrawals/usage/:address", get(api::admin::get_withdrawal_usage))
+        .route("/api/v1/admin/fixtures", get(api::admin::get_verification_fixtures))
+        .route("/api/v1/admin/batch-caps", get(api::admin::get_batch_caps))
+        .route("/api/v1/admin/batch-caps/:token_id", put(api::admin::set_batch_cap).delete(api::admin::clear_batch_cap))
+        .route("/api/v1/admin/rates/:token_id", put(api::admin::set_token_rate))
+        .route("/api/v1/admin/registry", get(api::admin::get_registry))
+        .route("/api/v1/admin/registry/metrics", get(api::admin::get_registry_metrics))
+        .route("/api/v1/admin/registry/tokens/:token_id", put(api::admin::set_registry_token))
+        .route("/api/v1/admin/registry/bank-services/:name", put(api::admin::set_registry_bank_service))
+        .route("/api/v1/admin/balance-alerts", get(api::admin::get_balance_alerts))
+        .route("/api/v1/admin/claims/reconciliation", get(api::admin::get_claim_reconciliation))
+        .route("/api/v1/admin/sagas", get(api::admin::list_sagas))
+        .route("/api/v1/admin/sagas/:order_id", get(api::admin::get_saga))
+        .route("/api/v1/admin/sagas/:order_id/abort", post(api::admin::abort_saga))
+        .route("/api/v1/admin/jobs", get(api::admin::list_jobs))
+        .route("/api/v1/admin/fillers/:filler_id/collateral/flag", delete(api::admin::clear_collateral_flag))
+        .route("/api/v1/admin/reconciliation/reports", get(api::admin::list_reconciliation_reports))
+        .route("/api/v1/admin/reconciliation/reports/latest", get(api::admin::get_latest_reconciliation_report))
+        .route("/api/v1/admin/reconciliation/reports/:id", get(api::admin::get_reconciliation_report))
+        .route("/api/v1/admin/reconciliation/run", post(api::admin::run_reconciliation))
+        .route("/api/v1/admin/retention/audit", get(api::admin::get_retention_audit))
+        .route("/api/v1/admin/retention/run", post(api::admin::run_retention))
+        .route("/api/v1/admin/screening/reviews", get(api::admin::list_screening_reviews))
+        .route("/api/v1/admin/screening/reviews/:id", post(api::admin::review_screening_result))
+        .route("/api/v1/admin/order-reviews", get(api::admin::list_order_reviews))
+        .route("/api/v1/admin/order-reviews/:order_id", post(api::admin::decide_order_review))
+        .route("/api/v1/admin/diagnostics/queries", get(api::admin::get_query_diagnostics))
+        .route("/api/v1/admin/backups", get(api::admin::list_backups).post(api::admin::take_backup))
+        .route("/api/v1/admin/backups/:id/verify", post(api::admin::verify_backup))
+        .route("/api/v1/admin/backups/:id/restore", post(api::admin::restore_backup))
+        .route("/api/v1/admin/handover", get(api::handover::get_handover))
+        .route("/api/v1/admin/handover/export", post(api::handover::export_handover))
+        .route("/api/v1/admin/handover/import", post(api::handover::import_handover));
+
+    // Fault injection controls, only compiled into fault-injection builds
+    #[cfg(feature = "fault-injection")]
+    let app = app
+        .route("/api/v1/admin/faults", get(api::faults::get_faults)
+            .put(api::faults::set_faults)
+            .delete(api::faults::reset_faults));
+
+    let batch_journal = app_state.batch_processor.lock().await.journal.clone();
+    let app = app
+        .layer(middleware::from_fn_with_state(app_state.clone(), api::caching::conditional_get))
+        .layer(middleware::from_fn_with_state(app_state.clone(), api::admin::maintenance_guard))
+        .layer(middleware::from_fn_with_state(app_state.clone(), api::admin::admin_guard))
+        .layer(middleware::from_fn_with_state(app_state.clone(), api::limits::enforce_request_limits))
+        .layer(DefaultBodyLimit::max(app_state.config.request_limits.max_body_bytes))
+        .layer(api::cors_layer(&app_state.config))
+        .layer(TraceLayer::new_for_http().make_span_with(DefaultMakeSpan::new().level(Level::INFO)))
         .with_state(app_state);
 
     // Run the server
@@ -196,7 +669,13 @@ async fn main() -> anyhow::Result<()> {
     info!("Server listening on {}", addr);
     
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // Peer addresses feed per-client rate limits
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
+    // Make sure the open batch is fully journaled before exiting
+    if let Some(journal) = batch_journal {
+        journal.flush().await;
+    }
+    telemetry::shutdown(tracer_provider);
     Ok(())
 }
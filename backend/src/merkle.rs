@@ -1,6 +1,7 @@
 use crate::models::{Order, AccountState, TokenBalance};
-use crate::lib::{SparseMerkleTree, SparseMerkleLeaf, MerkleProof, ethereum_address_to_path, index_to_path};
-use crate::lib::sparse_merkle_tree::TreeStats;
+use vapor_core::{SparseMerkleTree, SparseMerkleLeaf, MerkleProof};
+use vapor_core::sparse_merkle_tree::{NodeCacheStats, TreeStats};
+use vapor_core::leaf_encoding::order_leaf_preimage;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
@@ -16,13 +17,13 @@ pub struct MerkleTreeManager {
     /// Sparse order tree (20 levels, ~1M max orders per batch)
     pub order_tree: OrderMerkleTree,
     /// Current batch ID for order tree context
-    pub current_batch_id: u32,
+    pub current_batch_id: u64,
 }
 
 /// Specialized Order Merkle Tree that handles batch_id context
 pub struct OrderMerkleTree {
     inner: SparseMerkleTree<Order>,
-    current_batch_id: Option<u32>,
+    current_batch_id: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +49,28 @@ pub struct OrderMerkleProof {
     pub root: String,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleCacheStats {
+    pub account_tree: NodeCacheStats,
+    pub order_tree: NodeCacheStats,
+}
+
+/// Proof that several orders are in the tree, sharing the siblings their paths have in common
+///
+/// Laid out for an OpenZeppelin-style `processMultiProof` queue: leaves in ascending tree
+/// index, then one flag per hash from the bottom level up, left to right. A set flag pairs
+/// the next two queued nodes; a clear one pairs the next node with the next proof hash.
+/// Hashing is positional, so the verifier queues each node with its index (halved at every
+/// level) to tell left from right.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderMultiProof {
+    pub leaf_indices: Vec<usize>,
+    pub leaves: Vec<String>,
+    pub proof: Vec<String>,
+    pub proof_flags: Vec<bool>,
+    pub root: String,
+}
+
 impl MerkleTreeManager {
     pub fn new() -> Self {
         Self {
@@ -89,14 +112,15 @@ impl MerkleTreeManager {
             .map(|acc| (acc.address.clone(), acc.clone()))
             .collect();
         
-        self.account_tree = SparseMerkleTree::build_from_items(items)?;
+        let capacity = self.account_tree.cached_nodes.capacity();
+        self.account_tree = SparseMerkleTree::build_from_items(items)?.with_cache_capacity(capacity);
         let root = self.account_tree.compute_root()?;
         Ok(hex::encode(root))
     }
 
     /// Build sparse order tree (optimized for batch size)
     /// Uses Solidity-compatible hashing to match smart contract verification
-    pub fn build_orders_tree(&mut self, orders: &[Order], batch_id: u32) -> Result<String> {
+    pub fn build_orders_tree(&mut self, orders: &[Order], batch_id: u64) -> Result<String> {
         if orders.is_empty() {
             return Ok("0x".to_string());
         }
@@ -119,7 +143,7 @@ impl MerkleTreeManager {
     }
     
     /// Build orders tree from scratch (most efficient for new batches)
-    pub fn build_orders_tree_from_scratch(&mut self, orders: &[Order], batch_id: u32) -> Result<String> {
+    pub fn build_orders_tree_from_scratch(&mut self, orders: &[Order], batch_id: u64) -> Result<String> {
         if orders.is_empty() {
             return Ok("0x".to_string());
         }
@@ -128,7 +152,8 @@ impl MerkleTreeManager {
             .map(|(index, order)| (index.to_string(), order.clone()))
             .collect();
         
-        self.order_tree.inner = SparseMerkleTree::build_from_items(items)?;
+        let capacity = self.order_tree.inner.cached_nodes.capacity();
+        self.order_tree.inner = SparseMerkleTree::build_from_items(items)?.with_cache_capacity(capacity);
         self.current_batch_id = batch_id;
         self.order_tree.set_batch_id(batch_id);
         
@@ -166,11 +191,30 @@ impl MerkleTreeManager {
         Ok(proofs)
     }
     
+    /// Generate one multiproof covering several orders (for claiming them in one call)
+    pub fn generate_order_multiproof(&mut self, order_indices: &[usize]) -> Result<OrderMultiProof> {
+        self.order_tree.generate_multiproof(order_indices)
+    }
+
     /// Get tree statistics for monitoring and optimization
     pub fn get_tree_stats(&self) -> (TreeStats, TreeStats) {
         (self.account_tree.get_stats(), self.order_tree.inner.get_stats())
     }
     
+    /// Bound each tree's node cache to `capacity` nodes
+    pub fn set_node_cache_capacity(&mut self, capacity: usize) {
+        self.account_tree.cached_nodes.set_capacity(capacity);
+        self.order_tree.inner.cached_nodes.set_capacity(capacity);
+    }
+
+    /// Node cache usage of the account and order trees
+    pub fn node_cache_stats(&self) -> MerkleCacheStats {
+        MerkleCacheStats {
+            account_tree: self.account_tree.cached_nodes.stats(),
+            order_tree: self.order_tree.inner.cached_nodes.stats(),
+        }
+    }
+    
     /// Optimize both trees based on current data
     pub fn optimize_trees(&mut self) -> Result<()> {
         self.account_tree.optimize()?;
@@ -214,84 +258,6 @@ impl MerkleTreeManager {
     }
 }
 
-// Trait implementations for AccountState
-impl SparseMerkleLeaf for AccountState {
-    fn hash_leaf(&self, _key: &str) -> Result<[u8; 32]> {
-        let mut hasher = Keccak256::new();
-        
-        // Hash address
-        hasher.update(self.address.as_bytes());
-        
-        // Hash balances in deterministic order
-        let mut sorted_balances = self.balances.clone();
-        sorted_balances.sort_by_key(|b| b.token_id);
-        
-        for balance in sorted_balances {
-            hasher.update(balance.token_id.to_be_bytes());
-            hasher.update(balance.balance.as_bytes());
-        }
-        
-        Ok(hasher.finalize().into())
-    }
-    
-    fn key_to_path(&self, key: &str, depth: usize) -> String {
-        ethereum_address_to_path(key, depth)
-    }
-}
-
-// Trait implementations for Order (basic version, specialized tree handles batch_id)
-impl SparseMerkleLeaf for Order {
-    fn hash_leaf(&self, _key: &str) -> Result<[u8; 32]> {
-        // This should not be called directly - use OrderMerkleTree instead
-        Err(anyhow::anyhow!("Order hash_leaf requires batch context - use OrderMerkleTree instead"))
-    }
-    
-    fn key_to_path(&self, key: &str, depth: usize) -> String {
-        index_to_path(key, depth)
-    }
-}
-
-impl Order {
-    /// Hash leaf with batch ID context
-    pub fn hash_leaf_with_batch_id(&self, batch_id: u32) -> Result<[u8; 32]> {
-        // Determine source and destination addresses based on order type
-        let (source_addr, dest_addr) = match self.order_type {
-            crate::models::OrderType::BridgeIn => {
-                // Bridge-in: source and destination are the same (user's wallet address)
-                let user_addr = self.from_address.clone().unwrap_or_default();
-                (user_addr.clone(), user_addr)
-            },
-            crate::models::OrderType::BridgeOut => {
-                // Bridge-out: source is zero address, destination is specified address
-                let zero_addr = "0x0000000000000000000000000000000000000000".to_string();
-                let dest_addr = self.to_address.clone().unwrap_or_default();
-                (zero_addr, dest_addr)
-            },
-            crate::models::OrderType::Transfer => {
-                // Transfer: use addresses as specified
-                (
-                    self.from_address.clone().unwrap_or_default(),
-                    self.to_address.clone().unwrap_or_default()
-                )
-            },
-        };
-
-        let leaf_hash = solidity_order_leaf_hash(
-            batch_id,
-            &self.id,
-            self.order_type as u8,
-            &source_addr,
-            &dest_addr,
-            self.token_id,
-            &self.amount,
-        );
-        
-        let mut hash_array = [0u8; 32];
-        hash_array.copy_from_slice(&leaf_hash[..32]);
-        Ok(hash_array)
-    }
-}
-
 impl OrderMerkleTree {
     pub fn new(depth: usize) -> Self {
         Self {
@@ -314,7 +280,11 @@ impl OrderMerkleTree {
         }
     }
     
-    pub fn set_batch_id(&mut self, batch_id: u32) {
+    pub fn depth(&self) -> usize {
+        self.inner.depth
+    }
+
+    pub fn set_batch_id(&mut self, batch_id: u64) {
         self.current_batch_id = Some(batch_id);
         // Clear cache when batch ID changes
         self.inner.cached_nodes.clear();
@@ -383,10 +353,64 @@ impl OrderMerkleTree {
         })
     }
     
+    /// Multiproof for the leaves at `indices`; duplicates are dropped and the rest sorted
+    pub fn generate_multiproof(&mut self, indices: &[usize]) -> Result<OrderMultiProof> {
+        let batch_id = self.current_batch_id
+            .ok_or_else(|| anyhow::anyhow!("Batch ID not set for order tree"))?;
+        let depth = self.inner.depth;
+
+        let mut known: Vec<usize> = indices.to_vec();
+        known.sort_unstable();
+        known.dedup();
+        if known.is_empty() {
+            return Err(anyhow::anyhow!("Multiproof needs at least one leaf"));
+        }
+        if let Some(index) = known.iter().find(|index| (**index >> depth) != 0) {
+            return Err(anyhow::anyhow!("Leaf index {} does not fit a tree of depth {}", index, depth));
+        }
+
+        let root = self.compute_root()?;
+        let leaf_indices = known.clone();
+        let mut leaves = Vec::with_capacity(known.len());
+        for index in &known {
+            leaves.push(hex::encode(self.compute_node_hash(format!("{:0width$b}", index, width = depth), depth, batch_id)?));
+        }
+
+        let mut proof = Vec::new();
+        let mut proof_flags = Vec::new();
+        for level in (1..=depth).rev() {
+            let mut parents = Vec::with_capacity(known.len());
+            let mut i = 0;
+            while i < known.len() {
+                let index = known[i];
+                if index & 1 == 0 && known.get(i + 1) == Some(&(index + 1)) {
+                    // Both children are known, so the shared sibling is not repeated
+                    proof_flags.push(true);
+                    i += 2;
+                } else {
+                    let sibling_path = format!("{:0width$b}", index ^ 1, width = level);
+                    proof.push(hex::encode(self.compute_node_hash(sibling_path, level, batch_id)?));
+                    proof_flags.push(false);
+                    i += 1;
+                }
+                parents.push(index / 2);
+            }
+            known = parents;
+        }
+
+        Ok(OrderMultiProof {
+            leaf_indices,
+            leaves,
+            proof,
+            proof_flags,
+            root: hex::encode(root),
+        })
+    }
+
     /// Recursively compute node hash with batch_id context
-    fn compute_node_hash(&mut self, path: String, level: usize, batch_id: u32) -> Result<[u8; 32]> {
+    fn compute_node_hash(&mut self, path: String, level: usize, batch_id: u64) -> Result<[u8; 32]> {
         if let Some(cached) = self.inner.cached_nodes.get(&path) {
-            return Ok(*cached);
+            return Ok(cached);
         }
         
         if level == self.inner.depth {
@@ -397,7 +421,7 @@ impl OrderMerkleTree {
                 self.inner.zero_hashes[0] // Empty leaf
             };
             
-            self.inner.cached_nodes.insert(path, hash);
+            self.inner.cached_nodes.insert(&path, hash);
             return Ok(hash);
         }
         
@@ -414,7 +438,7 @@ impl OrderMerkleTree {
         hasher.update(right_hash);
         let hash = hasher.finalize().into();
         
-        self.inner.cached_nodes.insert(path, hash);
+        self.inner.cached_nodes.insert(&path, hash);
         Ok(hash)
     }
     
@@ -431,7 +455,7 @@ impl OrderMerkleTree {
 
 /// Solidity-compatible order leaf hash
 fn solidity_order_leaf_hash(
-    batch_id: u32,
+    batch_id: u64,
     order_id: &str,
     order_type: u8,
     from: &str,
@@ -440,24 +464,14 @@ fn solidity_order_leaf_hash(
     amount: &str,
 ) -> Vec<u8> {
     // This should match the keccak256(abi.encode(...)) in the smart contract
-    let mut hasher = Keccak256::new();
-    
-    hasher.update(batch_id.to_be_bytes()); // Solidity uses big-endian
-    hasher.update(order_id.as_bytes());
-    hasher.update([order_type]);
-    hasher.update(from.as_bytes());
-    hasher.update(to.as_bytes());
-    hasher.update(token_id.to_be_bytes());
-    hasher.update(amount.as_bytes());
-    
-    hasher.finalize().to_vec()
+    Keccak256::digest(order_leaf_preimage(batch_id, order_id, order_type, from, to, token_id, amount)).to_vec()
 }
 
 /// Utility functions for Solidity compatibility
 impl MerkleTreeManager {
     /// Convert order to Solidity-compatible leaf hash (matches smart contract)
     pub fn solidity_order_leaf_hash(
-        batch_id: u32,
+        batch_id: u64,
         order_id: &str,
         order_type: u8,
         from: &str,
@@ -472,6 +486,7 @@ impl MerkleTreeManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use vapor_core::proof_format::{parse_hash32, process_multi_proof};
     use crate::models::{Order, OrderType, OrderStatus, AccountState, TokenBalance};
     use chrono::Utc;
     use uuid::Uuid;
@@ -675,6 +690,69 @@ mod tests {
         assert_ne!(proof.root, MerkleTreeManager::empty_orders_root());
     }
 
+    #[test]
+    fn test_order_multiproof_generation() {
+        let mut manager = MerkleTreeManager::new();
+        let orders: Vec<Order> = (0..6)
+            .map(|i| create_test_order(&format!("order-{}", i), OrderType::BridgeOut))
+            .collect();
+        manager.build_orders_tree(&orders, 42).unwrap();
+
+        let multiproof = manager.generate_order_multiproof(&[4, 1, 0, 4]).unwrap();
+        assert_eq!(multiproof.leaf_indices, vec![0, 1, 4]);
+        assert_eq!(multiproof.root, manager.get_orders_root().unwrap());
+
+        let hashes = |values: &[String]| values.iter().map(|value| parse_hash32(value).unwrap()).collect::<Vec<_>>();
+        let root = process_multi_proof(
+            &hashes(&multiproof.leaves),
+            &multiproof.leaf_indices,
+            &hashes(&multiproof.proof),
+            &multiproof.proof_flags,
+        ).unwrap();
+        assert_eq!(hex::encode(root), multiproof.root);
+
+        // Shared siblings are sent once instead of once per order
+        let separate: usize = multiproof.leaf_indices.iter()
+            .map(|index| manager.generate_order_proof(*index).unwrap().proof.len())
+            .sum();
+        assert!(multiproof.proof.len() < separate);
+
+        let single = manager.generate_order_multiproof(&[3]).unwrap();
+        assert_eq!(single.proof, manager.generate_order_proof(3).unwrap().proof);
+        assert!(single.proof_flags.iter().all(|flag| !flag));
+
+        assert!(manager.generate_order_multiproof(&[]).is_err());
+        assert!(manager.generate_order_multiproof(&[1 << 20]).is_err());
+    }
+
+    #[test]
+    fn test_bounded_node_cache_keeps_roots_and_proofs() {
+        let orders: Vec<Order> = (0..12).map(|i| create_test_order(&format!("order-{}", i), OrderType::BridgeIn)).collect();
+        let accounts: Vec<AccountState> = (1..6).map(|i| create_test_account(&format!("0x{:040x}", i * 7919), vec![(1, "1000")])).collect();
+
+        let mut unbounded = MerkleTreeManager::new();
+        let mut bounded = MerkleTreeManager::new();
+        bounded.set_node_cache_capacity(8);
+
+        assert_eq!(bounded.build_state_tree(&accounts).unwrap(), unbounded.build_state_tree(&accounts).unwrap());
+        assert_eq!(bounded.build_orders_tree(&orders, 9).unwrap(), unbounded.build_orders_tree(&orders, 9).unwrap());
+        for index in 0..orders.len() {
+            assert_eq!(bounded.generate_order_proof(index).unwrap().proof, unbounded.generate_order_proof(index).unwrap().proof);
+        }
+        for account in &accounts {
+            assert_eq!(
+                bounded.generate_account_proof(&account.address).unwrap().proof,
+                unbounded.generate_account_proof(&account.address).unwrap().proof
+            );
+        }
+
+        // Rebuilding from scratch keeps the configured bound
+        bounded.build_state_tree_from_scratch(&accounts).unwrap();
+        let stats = bounded.node_cache_stats();
+        assert_eq!((stats.account_tree.capacity, stats.order_tree.capacity), (8, 8));
+        assert!(stats.account_tree.entries <= 8 && stats.order_tree.evictions > 0);
+    }
+
     #[test]
     fn test_order_hash_with_batch_id() {
         let order = create_test_order("test-order", OrderType::BridgeIn);
@@ -906,4 +984,158 @@ mod tests {
         
         assert_eq!(hash1, hash2, "Token balance order should not affect hash (deterministic sorting)");
     }
-}
\ No newline at end of file
+
+    /// Browser verifiers use vapor-merkle on its own, from the fields the proofs API publishes
+    #[test]
+    fn test_vapor_merkle_verifies_server_proofs() {
+        use vapor_merkle::leaf::{AccountLeafFields, LeafBalance, OrderLeafFields};
+        use vapor_merkle::proof::{bit_path_to_path_bits, index_to_path_bits, process_raw_proof};
+
+        let hashes = |proof: &[String]| -> Vec<[u8; 32]> { proof.iter().map(|hash| parse_hash32(hash).unwrap()).collect() };
+
+        let mut manager = MerkleTreeManager::new();
+        let mut orders = vec![
+            create_test_order("order-0", OrderType::BridgeIn),
+            create_test_order("order-1", OrderType::BridgeOut),
+            create_test_order("order-2", OrderType::Transfer),
+        ];
+        orders[2].to_address = None;
+        manager.build_orders_tree(&orders, 4_294_967_296).unwrap();
+
+        for (index, order) in orders.iter().enumerate() {
+            let proof = manager.generate_order_proof(index).unwrap();
+            let fields = OrderLeafFields {
+                order_id: order.id.clone(),
+                order_type: order.order_type as u8,
+                from_address: order.from_address.clone(),
+                to_address: order.to_address.clone(),
+                token_id: order.token_id,
+                amount: order.amount.clone(),
+            };
+            let leaf = fields.leaf_hash(4_294_967_296);
+            assert_eq!(leaf, parse_hash32(&proof.leaf_hash).unwrap(), "{}", order.id);
+            let root = process_raw_proof(leaf, &hashes(&proof.proof), &index_to_path_bits(index, proof.proof.len())).unwrap();
+            assert_eq!(root, parse_hash32(&proof.root).unwrap(), "{}", order.id);
+        }
+
+        let mut manager = MerkleTreeManager {
+            account_tree: SparseMerkleTree::new_with_bounds(8, 8, 8),
+            order_tree: OrderMerkleTree::new(ORDER_TREE_DEPTH),
+            current_batch_id: 0,
+        };
+        let account = create_test_account("0x5a", vec![(2, "7"), (1, "1000000")]);
+        manager.build_state_tree(&[account, create_test_account("0x12", vec![(1, "5")])]).unwrap();
+        let proof = manager.generate_account_proof("0x5a").unwrap();
+        let fields = AccountLeafFields {
+            address: "0x5a".to_string(),
+            balances: vec![
+                LeafBalance { token_id: 2, balance: "7".to_string() },
+                LeafBalance { token_id: 1, balance: "1000000".to_string() },
+            ],
+        };
+        let leaf = fields.leaf_hash();
+        assert_eq!(leaf, parse_hash32(&proof.leaf_hash).unwrap());
+        let path_bits = bit_path_to_path_bits(&vapor_merkle::leaf::ethereum_address_to_path("0x5a", 8));
+        assert_eq!(process_raw_proof(leaf, &hashes(&proof.proof), &path_bits).unwrap(), parse_hash32(&proof.root).unwrap());
+    }
+
+    mod properties {
+        use super::*;
+        use vapor_core::proof_format::{bit_path_to_path_bits, process_raw_proof};
+        use vapor_core::{ethereum_address_to_path, index_to_path};
+        use proptest::collection::{btree_set, vec};
+        use proptest::prelude::*;
+
+        fn to_hash(value: &str) -> [u8; 32] {
+            hex::decode(value).unwrap().try_into().unwrap()
+        }
+
+        /// Fold a proof back up along `path` (root to leaf bits) and compare with its root
+        fn verifies(leaf_hash: &str, proof: &[String], path: &str, root: &str) -> bool {
+            let siblings: Vec<[u8; 32]> = proof.iter().map(|sibling| to_hash(sibling)).collect();
+            process_raw_proof(to_hash(leaf_hash), &siblings, &bit_path_to_path_bits(path)).unwrap() == to_hash(root)
+        }
+
+        fn orders() -> impl Strategy<Value = Vec<Order>> {
+            vec((0u8..3, 1u64..1_000_000_000), 1..40).prop_map(|specs| {
+                specs.into_iter().enumerate().map(|(i, (kind, amount))| {
+                    let order_type = [OrderType::BridgeIn, OrderType::BridgeOut, OrderType::Transfer][kind as usize];
+                    let mut order = create_test_order(&format!("order_{}", i), order_type);
+                    order.amount = amount.to_string();
+                    order
+                }).collect()
+            })
+        }
+
+        fn accounts() -> impl Strategy<Value = Vec<AccountState>> {
+            btree_set(any::<[u8; 20]>(), 1..24).prop_flat_map(|addresses| {
+                let count = addresses.len();
+                (Just(addresses), vec(1u64..1_000_000_000, count))
+            }).prop_map(|(addresses, balances)| {
+                addresses.into_iter().zip(balances).map(|(address, balance)| {
+                    create_test_account(&format!("0x{}", hex::encode(address)), vec![(1, &balance.to_string())])
+                }).collect()
+            })
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(48))]
+
+            #[test]
+            fn every_order_has_a_verifying_proof(orders in orders(), batch_id in 1u64..1000) {
+                let mut manager = MerkleTreeManager::new();
+                let root = manager.build_orders_tree(&orders, batch_id).unwrap();
+
+                for (index, order) in orders.iter().enumerate() {
+                    let proof = manager.generate_order_proof(index).unwrap();
+                    prop_assert_eq!(&proof.root, &root);
+                    prop_assert_eq!(&proof.leaf_hash, &hex::encode(order.hash_leaf_with_batch_id(batch_id).unwrap()));
+                    let path = index_to_path(&index.to_string(), proof.proof.len());
+                    prop_assert!(verifies(&proof.leaf_hash, &proof.proof, &path, &proof.root));
+                }
+
+                // An index past the last order proves an empty leaf
+                let proof = manager.generate_order_proof(orders.len()).unwrap();
+                prop_assert_eq!(&proof.leaf_hash, &hex::encode([0u8; 32]));
+                let path = index_to_path(&orders.len().to_string(), proof.proof.len());
+                prop_assert!(verifies(&proof.leaf_hash, &proof.proof, &path, &proof.root));
+            }
+
+            #[test]
+            fn reused_order_tree_matches_a_fresh_one(first in orders(), second in orders()) {
+                // The same manager builds one batch after another, with its caches and depth reused
+                let mut reused = MerkleTreeManager::new();
+                reused.build_orders_tree(&first, 1).unwrap();
+                let root = reused.build_orders_tree(&second, 2).unwrap();
+
+                prop_assert_eq!(&root, &MerkleTreeManager::new().build_orders_tree(&second, 2).unwrap());
+                prop_assert_eq!(&root, &MerkleTreeManager::new().build_orders_tree_from_scratch(&second, 2).unwrap());
+            }
+
+            #[test]
+            fn every_account_has_a_verifying_proof(
+                (accounts, shuffled) in accounts().prop_flat_map(|accounts| (Just(accounts.clone()), Just(accounts).prop_shuffle())),
+                absent in any::<[u8; 20]>(),
+            ) {
+                let mut manager = MerkleTreeManager::new();
+                let root = manager.build_state_tree(&accounts).unwrap();
+                prop_assert_eq!(&root, &MerkleTreeManager::new().build_state_tree(&shuffled).unwrap());
+
+                for account in &accounts {
+                    let proof = manager.generate_account_proof(&account.address).unwrap();
+                    prop_assert_eq!(&proof.root, &root);
+                    prop_assert_eq!(&proof.leaf_hash, &hex::encode(SparseMerkleLeaf::hash_leaf(account, "").unwrap()));
+                    let path = ethereum_address_to_path(&account.address, proof.proof.len());
+                    prop_assert!(verifies(&proof.leaf_hash, &proof.proof, &path, &proof.root));
+                }
+
+                let absent = format!("0x{}", hex::encode(absent));
+                prop_assume!(accounts.iter().all(|account| account.address != absent));
+                let proof = manager.generate_account_proof(&absent).unwrap();
+                let path = ethereum_address_to_path(&absent, proof.proof.len());
+                prop_assert!(verifies(&proof.leaf_hash, &proof.proof, &path, &proof.root));
+            }
+        }
+    }
+}
+
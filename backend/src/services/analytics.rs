@@ -0,0 +1,307 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use sha3::{Digest, Keccak256};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, warn};
+
+use crate::config::{AnalyticsConfig, AnalyticsSinkConfig};
+use crate::database::helpers;
+use crate::models::OrderStatus;
+use crate::services::event_log::{DomainEvent, LoggedEvent};
+use crate::services::market::UNSPECIFIED_BANK_SERVICE;
+use crate::services::registry::RegistryCache;
+use vapor_core::services::analytics::{AnalyticsEvent, AnalyticsSink, Corridor, StdoutSink};
+
+/// Decimals assumed for tokens missing from the registry
+const DEFAULT_TOKEN_DECIMALS: u8 = 18;
+
+/// Upper bounds, in whole tokens, of the amount buckets below the open-ended top one
+const AMOUNT_BUCKETS: [(u128, &str); 6] = [
+    (1, "0-1"),
+    (10, "1-10"),
+    (100, "10-100"),
+    (1_000, "100-1k"),
+    (10_000, "1k-10k"),
+    (100_000, "10k-100k"),
+];
+
+/// POSTs each event as JSON to a collector
+pub struct HttpSink {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl HttpSink {
+    pub fn new(url: &str) -> Self {
+        Self { url: url.to_string(), client: reqwest::Client::new() }
+    }
+}
+
+impl AnalyticsSink for HttpSink {
+    fn send<'a>(&'a self, event: &'a AnalyticsEvent) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.client.post(&self.url).json(event).send().await?.error_for_status()?;
+            Ok(())
+        })
+    }
+}
+
+pub fn analytics_sink_from_config(config: &AnalyticsSinkConfig) -> Arc<dyn AnalyticsSink> {
+    match config {
+        AnalyticsSinkConfig::Stdout => Arc::new(StdoutSink),
+        AnalyticsSinkConfig::Http { url } => Arc::new(HttpSink::new(url)),
+    }
+}
+
+/// Turns order events relayed from the event log into anonymized analytics events
+///
+/// Delivery is best effort: events a sink refuses, or that a lagging subscriber missed, are
+/// dropped with a warning rather than retried.
+#[derive(Clone)]
+pub struct AnalyticsEmitter {
+    db: SqlitePool,
+    registry: RegistryCache,
+    sample_rate: f64,
+    hash_salt: String,
+    sink: Arc<dyn AnalyticsSink>,
+}
+
+impl AnalyticsEmitter {
+    pub fn new(db: SqlitePool, registry: RegistryCache, config: &AnalyticsConfig) -> Self {
+        Self {
+            db,
+            registry,
+            sample_rate: config.sample_rate,
+            hash_salt: config.hash_salt.clone(),
+            sink: analytics_sink_from_config(&config.sink),
+        }
+    }
+
+    /// Salted Keccak of an address or id, lowercased first so checksummed addresses hash alike
+    fn hash(&self, value: &str) -> String {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.hash_salt.as_bytes());
+        hasher.update(b":");
+        hasher.update(value.to_lowercase().as_bytes());
+        hex::encode(&hasher.finalize()[..16])
+    }
+
+    /// Whether an order's events are emitted; decided by its id so an order is never half sampled
+    fn sampled(&self, order_id: &str) -> bool {
+        if self.sample_rate >= 1.0 {
+            return true;
+        }
+        let digest = Keccak256::digest(order_id.as_bytes());
+        let position = u64::from_be_bytes(digest[..8].try_into().expect("8 bytes")) as f64 / u64::MAX as f64;
+        position < self.sample_rate
+    }
+
+    /// The analytics event for a logged event, if it is about a sampled order
+    pub async fn anonymize(&self, logged: &LoggedEvent) -> Result<Option<AnalyticsEvent>> {
+        let Ok(event) = serde_json::from_value::<DomainEvent>(logged.payload.clone()) else {
+            return Ok(None);
+        };
+        let (order_id, status) = match &event {
+            DomainEvent::OrderCreated { order_id, status, .. } => (order_id, Some(*status)),
+            DomainEvent::OrderLocked { order_id, .. } => (order_id, Some(OrderStatus::Locked)),
+            DomainEvent::OrderPaid { order_id, .. } => (order_id, Some(OrderStatus::MarkPaid)),
+            DomainEvent::OrderSettled { order_id, .. } => (order_id, Some(OrderStatus::Settled)),
+            DomainEvent::OrderStatusChanged { order_id, to_status, .. } => (order_id, Some(*to_status)),
+            _ => return Ok(None),
+        };
+        if !self.sampled(order_id) {
+            return Ok(None);
+        }
+        let Some(order) = helpers::get_order_by_id(&self.db, order_id).await? else {
+            return Ok(None);
+        };
+
+        let decimals = self.registry.token(order.token_id).await?
+            .map(|token| token.decimals)
+            .unwrap_or(DEFAULT_TOKEN_DECIMALS);
+        let filler = match &event {
+            DomainEvent::OrderLocked { filler_id, .. } => Some(filler_id.as_str()),
+            _ => order.filler_id.as_deref(),
+        };
+        Ok(Some(AnalyticsEvent {
+            event: logged.kind.clone(),
+            order: self.hash(&order.id),
+            order_type: order.order_type,
+            status,
+            corridor: Corridor {
+                token_id: order.token_id,
+                bank_service: order.bank_service.clone()
+                    .filter(|service| !service.is_empty())
+                    .unwrap_or_else(|| UNSPECIFIED_BANK_SERVICE.to_string()),
+            },
+            amount_bucket: amount_bucket(&order.amount, decimals),
+            from_address: order.from_address.as_deref().map(|address| self.hash(address)),
+            to_address: order.to_address.as_deref().map(|address| self.hash(address)),
+            filler: filler.map(|filler_id| self.hash(filler_id)),
+            occurred_at: logged.recorded_at,
+            order_age_ms: (logged.recorded_at - order.created_at).num_milliseconds().max(0),
+        }))
+    }
+
+    /// Anonymize and send one logged event, returning whether anything was sent
+    pub async fn emit(&self, logged: &LoggedEvent) -> Result<bool> {
+        let Some(event) = self.anonymize(logged).await? else {
+            return Ok(false);
+        };
+        self.sink.send(&event).await?;
+        Ok(true)
+    }
+
+    /// Emit events from the event bus until it closes
+    pub async fn run(self, mut receiver: broadcast::Receiver<LoggedEvent>) {
+        loop {
+            match receiver.recv().await {
+                Ok(logged) => {
+                    if let Err(e) = self.emit(&logged).await {
+                        warn!("Dropped analytics event for event log entry {}: {}", logged.seq, e);
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => warn!("Analytics fell behind, dropped {} events", skipped),
+                Err(RecvError::Closed) => {
+                    debug!("Event bus closed, stopping analytics");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Bucket of an amount in token base units, by whole tokens
+fn amount_bucket(amount: &str, decimals: u8) -> &'static str {
+    let Ok(amount) = amount.parse::<u128>() else {
+        return "unknown";
+    };
+    let unit = 10u128.checked_pow(decimals as u32).unwrap_or(u128::MAX);
+    AMOUNT_BUCKETS.iter()
+        .find(|(max, _)| amount < max.saturating_mul(unit))
+        .map(|(_, label)| *label)
+        .unwrap_or("100k+")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::database::run_migrations;
+    use crate::models::{Order, OrderType};
+    use crate::services::event_bus::EventBus;
+    use crate::services::event_log;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    const FROM: &str = "0x1234567890AbcdEF1234567890aBcdef12345678";
+
+    #[derive(Default)]
+    struct MemorySink(Mutex<Vec<AnalyticsEvent>>);
+
+    impl AnalyticsSink for MemorySink {
+        fn send<'a>(&'a self, event: &'a AnalyticsEvent) -> BoxFuture<'a, Result<()>> {
+            self.0.lock().unwrap().push(event.clone());
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    async fn test_emitter(sample_rate: f64) -> (AnalyticsEmitter, Arc<MemorySink>) {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let registry = RegistryCache::new(db.clone(), Duration::from_secs(60), &EventBus::default());
+        let config = AnalyticsConfig { enabled: true, sample_rate, hash_salt: "salt".to_string(), ..Default::default() };
+        let sink = Arc::new(MemorySink::default());
+        (AnalyticsEmitter { sink: sink.clone(), ..AnalyticsEmitter::new(db, registry, &config) }, sink)
+    }
+
+    fn order(id: &str) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::BridgeIn,
+            from_address: Some(FROM.to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "2500000000000000000000".to_string(),
+            bank_account: Some("84127312".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            status: OrderStatus::Pending,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relayed_order_events_are_anonymized() {
+        let (emitter, sink) = test_emitter(1.0).await;
+        let bus = EventBus::default();
+        let receiver = bus.subscribe_logged_events();
+
+        helpers::insert_order(&emitter.db, &order("order_1")).await.unwrap();
+        event_log::append(&emitter.db, &DomainEvent::OrderLocked {
+            order_id: "order_1".to_string(),
+            filler_id: "filler_1".to_string(),
+            locked_amount: "2500000000000000000000".to_string(),
+        }).await.unwrap();
+        event_log::append(&emitter.db, &DomainEvent::BatchAborted { batch_id: 1, order_ids: vec![] }).await.unwrap();
+        assert_eq!(event_log::relay(&emitter.db, &bus, 0).await.unwrap(), 3);
+
+        drop(bus);
+        emitter.clone().run(receiver).await;
+        let events = sink.0.lock().unwrap().clone();
+        assert_eq!(events.iter().map(|event| event.event.as_str()).collect::<Vec<_>>(), ["order_created", "order_locked"]);
+        assert_eq!(events[1].status, Some(OrderStatus::Locked));
+        assert_eq!(events[1].amount_bucket, "1k-10k");
+        assert_eq!(events[1].corridor, Corridor { token_id: 1, bank_service: "PayPal Hong Kong".to_string() });
+        // Both events of the order and its address join up without revealing them
+        assert_eq!(events[0].order, events[1].order);
+        assert_eq!(events[0].from_address, Some(emitter.hash(&FROM.to_lowercase())));
+        assert_eq!(events[1].filler, Some(emitter.hash("filler_1")));
+        let json = serde_json::to_string(&events).unwrap();
+        for pii in ["order_1", "filler_1", "84127312", &FROM.to_lowercase()[2..], "2500000000000000000000"] {
+            assert!(!json.to_lowercase().contains(&pii.to_lowercase()), "{} leaked", pii);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_orders_are_sampled_whole() {
+        let (emitter, sink) = test_emitter(0.5).await;
+        for i in 0..40 {
+            let order_id = format!("order_{}", i);
+            helpers::insert_order(&emitter.db, &order(&order_id)).await.unwrap();
+            event_log::append(&emitter.db, &DomainEvent::status_change(&order_id, OrderStatus::Pending, OrderStatus::Failed, None))
+                .await
+                .unwrap();
+        }
+        for logged in event_log::read(&emitter.db, 0, 100).await.unwrap() {
+            emitter.emit(&logged).await.unwrap();
+        }
+
+        let events = sink.0.lock().unwrap().clone();
+        assert!(!events.is_empty() && events.len() < 80, "{} events", events.len());
+        for pair in events.chunks(2) {
+            assert_eq!(pair[0].order, pair[1].order);
+        }
+
+        let (none, sink) = test_emitter(0.0).await;
+        helpers::insert_order(&none.db, &order("order_1")).await.unwrap();
+        let logged = event_log::read(&none.db, 0, 10).await.unwrap();
+        assert!(!none.emit(&logged[0]).await.unwrap());
+        assert!(sink.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_amount_buckets() {
+        assert_eq!(amount_bucket("999999", 6), "0-1");
+        assert_eq!(amount_bucket("1000000", 6), "1-10");
+        assert_eq!(amount_bucket("100000000000", 6), "100k+");
+        assert_eq!(amount_bucket("5", 0), "1-10");
+        assert_eq!(amount_bucket("lots", 6), "unknown");
+    }
+}
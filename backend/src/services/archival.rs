@@ -0,0 +1,500 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use sqlx::sqlite::SqliteRow;
+use std::io::{Read, Write};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::config::ArchiveConfig;
+use crate::models::{AccountState, Order};
+use vapor_core::services::blob_store::BlobStore;
+
+/// Account and order tree contents as of a finalized batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSnapshot {
+    pub batch_id: u64,
+    pub state_root: String,
+    pub orders_root: String,
+    pub accounts: Vec<AccountState>,
+    pub orders: Vec<Order>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Which of a batch's roots a looked-up root is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RootKind {
+    State,
+    Orders,
+}
+
+/// A finalized batch one of whose roots is a looked-up root
+#[derive(Debug, Clone, Serialize)]
+pub struct RootMatch {
+    pub batch_id: u64,
+    pub root_kind: RootKind,
+    pub state_root: String,
+    pub orders_root: String,
+    pub finalized_at: DateTime<Utc>,
+    /// Whether the batch's snapshot has moved to the compressed archive
+    pub archived: bool,
+    pub order_count: u64,
+}
+
+/// A known root close to one that is not known, by the hex digits they start with
+#[derive(Debug, Clone, Serialize)]
+pub struct NearbyRoot {
+    pub root: String,
+    pub root_kind: RootKind,
+    pub batch_id: u64,
+    /// Leading hex digits shared with the looked-up root
+    pub common_prefix: usize,
+}
+
+/// Lowercase hex without `0x`, as roots are stored
+fn stored_root(root: &str) -> String {
+    root.trim().trim_start_matches("0x").to_lowercase()
+}
+
+/// Outcome of a retention pass
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArchiveReport {
+    pub archived: usize,
+    pub raw_bytes: u64,
+    pub compressed_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveStats {
+    pub retain_batches: u32,
+    pub hot_snapshots: i64,
+    pub archived_snapshots: i64,
+    pub archived_raw_bytes: i64,
+    pub archived_compressed_bytes: i64,
+}
+
+/// Keeps the most recent batch snapshots hot and moves older ones into the
+/// compressed archive table. Archived snapshots are restored on demand.
+///
+/// Snapshot contents live in the blob store; the tables only hold their reference and checksum.
+#[derive(Clone)]
+pub struct ArchiveService {
+    db: SqlitePool,
+    blobs: Arc<dyn BlobStore>,
+    retain_batches: u32,
+}
+
+impl ArchiveService {
+    pub fn new(db: SqlitePool, blobs: Arc<dyn BlobStore>, config: &ArchiveConfig) -> Self {
+        Self {
+            db,
+            blobs,
+            retain_batches: config.retain_batches,
+        }
+    }
+
+    /// Contents of a snapshot row, from the blob store or inline for rows written before it
+    async fn row_data(&self, row: &SqliteRow) -> Result<Vec<u8>> {
+        let Some(reference) = row.try_get::<Option<String>, _>("blob_ref")? else {
+            return Ok(row.try_get("data")?);
+        };
+        let checksum: String = row.try_get("checksum")?;
+        self.blobs.get(&reference, &checksum).await?
+            .ok_or_else(|| anyhow::anyhow!("Snapshot blob {} is missing from the {} blob store", reference, self.blobs.kind()))
+    }
+
+    /// Put the hot copy of a snapshot, whose JSON is `data`
+    async fn store_hot(&self, snapshot: &BatchSnapshot, data: &[u8], replace: bool) -> Result<()> {
+        let blob = self.blobs.put(data).await?;
+        // `data` stays empty for blob-backed rows
+        sqlx::query(&format!(
+            r#"
+            INSERT OR {} INTO batch_snapshots (batch_id, state_root, orders_root, data, blob_ref, checksum, created_at)
+            VALUES (?1, ?2, ?3, '', ?4, ?5, ?6)
+            "#,
+            if replace { "REPLACE" } else { "IGNORE" }
+        ))
+        .bind(snapshot.batch_id as i64)
+        .bind(&snapshot.state_root)
+        .bind(&snapshot.orders_root)
+        .bind(&blob.reference)
+        .bind(&blob.checksum)
+        .bind(snapshot.created_at)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Store a finalized batch snapshot and archive anything that fell out of the retention window
+    pub async fn store_snapshot(&self, snapshot: &BatchSnapshot) -> Result<ArchiveReport> {
+        self.store_hot(snapshot, &serde_json::to_vec(snapshot)?, true).await?;
+
+        info!("Stored state snapshot for batch {}", snapshot.batch_id);
+        self.enforce_retention().await
+    }
+
+    /// Move hot snapshots older than the last `retain_batches` batches into the archive
+    pub async fn enforce_retention(&self) -> Result<ArchiveReport> {
+        let mut report = ArchiveReport::default();
+        if self.retain_batches == 0 {
+            return Ok(report);
+        }
+
+        let latest: Option<i64> = sqlx::query("SELECT MAX(batch_id) AS latest FROM batch_snapshots")
+            .fetch_one(&self.db)
+            .await?
+            .try_get("latest")?;
+        let Some(latest) = latest else {
+            return Ok(report);
+        };
+        let cutoff = latest - self.retain_batches as i64;
+
+        let rows = sqlx::query("SELECT batch_id, state_root, orders_root, data, blob_ref, checksum, created_at FROM batch_snapshots WHERE batch_id <= ? ORDER BY batch_id")
+            .bind(cutoff)
+            .fetch_all(&self.db)
+            .await?;
+
+        for row in rows {
+            let batch_id: i64 = row.try_get("batch_id")?;
+            let data = self.row_data(&row).await?;
+            let compressed = compress(&data)?;
+            let blob = self.blobs.put(&compressed).await?;
+
+            // Snapshots restored for a historical proof are already archived, so keep the existing copy
+            let mut tx = self.db.begin().await?;
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO batch_snapshot_archive
+                    (batch_id, state_root, orders_root, data, blob_ref, checksum, raw_size, compressed_size, created_at, archived_at)
+                VALUES (?1, ?2, ?3, X'', ?4, ?5, ?6, ?7, ?8, ?9)
+                "#,
+            )
+            .bind(batch_id)
+            .bind(row.try_get::<String, _>("state_root")?)
+            .bind(row.try_get::<String, _>("orders_root")?)
+            .bind(&blob.reference)
+            .bind(&blob.checksum)
+            .bind(data.len() as i64)
+            .bind(compressed.len() as i64)
+            .bind(row.try_get::<DateTime<Utc>, _>("created_at")?)
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query("DELETE FROM batch_snapshots WHERE batch_id = ?")
+                .bind(batch_id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+
+            // The hot blob is unreferenced once its row is gone
+            if let Some(reference) = row.try_get::<Option<String>, _>("blob_ref")? {
+                if let Err(e) = self.blobs.delete(&reference).await {
+                    warn!("Failed to delete hot snapshot blob {} for batch {}: {}", reference, batch_id, e);
+                }
+            }
+
+            report.archived += 1;
+            report.raw_bytes += data.len() as u64;
+            report.compressed_bytes += compressed.len() as u64;
+        }
+
+        if report.archived > 0 {
+            info!(
+                "Archived {} state snapshots ({} -> {} bytes)",
+                report.archived, report.raw_bytes, report.compressed_bytes
+            );
+        }
+        Ok(report)
+    }
+
+    /// Load a batch snapshot, restoring it from the archive into the hot table if needed
+    pub async fn load_snapshot(&self, batch_id: u64) -> Result<Option<BatchSnapshot>> {
+        let hot = sqlx::query("SELECT data, blob_ref, checksum FROM batch_snapshots WHERE batch_id = ?")
+            .bind(batch_id as i64)
+            .fetch_optional(&self.db)
+            .await?;
+        if let Some(row) = hot {
+            return Ok(Some(serde_json::from_slice(&self.row_data(&row).await?)?));
+        }
+
+        let archived = sqlx::query("SELECT data, blob_ref, checksum FROM batch_snapshot_archive WHERE batch_id = ?")
+            .bind(batch_id as i64)
+            .fetch_optional(&self.db)
+            .await?;
+        let Some(row) = archived else {
+            return Ok(None);
+        };
+
+        let data = decompress(&self.row_data(&row).await?)?;
+        let snapshot: BatchSnapshot = serde_json::from_slice(&data)?;
+
+        // Keep the restored copy hot until the next retention pass
+        self.store_hot(&snapshot, &data, false).await?;
+
+        info!("Restored archived state snapshot for batch {}", batch_id);
+        Ok(Some(snapshot))
+    }
+
+    /// State and orders roots of a finalized batch and when it was finalized, without loading
+    /// or restoring its snapshot
+    pub async fn snapshot_roots(&self, batch_id: u64) -> Result<Option<(String, String, DateTime<Utc>)>> {
+        let row = sqlx::query(
+            r#"
+            SELECT state_root, orders_root, created_at FROM batch_snapshots WHERE batch_id = ?1
+            UNION ALL
+            SELECT state_root, orders_root, created_at FROM batch_snapshot_archive WHERE batch_id = ?1
+            LIMIT 1
+            "#,
+        )
+        .bind(batch_id as i64)
+        .fetch_optional(&self.db)
+        .await?;
+        row.map(|row| Ok((row.try_get("state_root")?, row.try_get("orders_root")?, row.try_get("created_at")?)))
+            .transpose()
+    }
+
+    /// The newest finalized batch with a snapshot, hot or archived
+    pub async fn latest_batch_id(&self) -> Result<Option<u64>> {
+        let latest: Option<i64> = sqlx::query(
+            "SELECT MAX(batch_id) AS latest FROM (SELECT batch_id FROM batch_snapshots UNION ALL SELECT batch_id FROM batch_snapshot_archive)"
+        )
+        .fetch_one(&self.db)
+        .await?
+        .try_get("latest")?;
+        Ok(latest.map(|batch_id| batch_id as u64))
+    }
+
+    /// Finalized batches with `root` as their state or orders root, hot or archived, by batch id
+    ///
+    /// A state root can belong to several batches when a batch left every balance unchanged.
+    pub async fn batches_by_root(&self, root: &str) -> Result<Vec<RootMatch>> {
+        let root = stored_root(root);
+        let prefixed = format!("0x{}", root);
+        let rows = sqlx::query(
+            r#"
+            SELECT matched.*, (SELECT COUNT(*) FROM orders WHERE orders.batch_id = matched.batch_id) AS order_count
+            FROM (
+                SELECT batch_id, state_root, orders_root, created_at, 0 AS archived FROM batch_snapshots
+                WHERE state_root IN (?1, ?2) OR orders_root IN (?1, ?2)
+                UNION ALL
+                SELECT batch_id, state_root, orders_root, created_at, 1 AS archived FROM batch_snapshot_archive
+                WHERE state_root IN (?1, ?2) OR orders_root IN (?1, ?2)
+            ) AS matched
+            ORDER BY batch_id
+            "#,
+        )
+        .bind(&root)
+        .bind(&prefixed)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut matches = Vec::new();
+        for row in &rows {
+            let state_root: String = row.try_get("state_root")?;
+            let orders_root: String = row.try_get("orders_root")?;
+            let kinds = [(RootKind::State, &state_root), (RootKind::Orders, &orders_root)];
+            for (root_kind, _) in kinds.iter().filter(|(_, candidate)| stored_root(candidate) == root) {
+                matches.push(RootMatch {
+                    batch_id: row.try_get::<i64, _>("batch_id")? as u64,
+                    root_kind: *root_kind,
+                    state_root: state_root.clone(),
+                    orders_root: orders_root.clone(),
+                    finalized_at: row.try_get("created_at")?,
+                    archived: row.try_get::<i64, _>("archived")? != 0,
+                    order_count: row.try_get::<i64, _>("order_count")? as u64,
+                });
+            }
+        }
+        Ok(matches)
+    }
+
+    /// The `limit` known roots sharing the longest hex prefix with `root`, newest batch first
+    /// among equally close ones
+    pub async fn nearest_roots(&self, root: &str, limit: usize) -> Result<Vec<NearbyRoot>> {
+        let root = stored_root(root);
+        let rows = sqlx::query(
+            "SELECT batch_id, state_root, orders_root FROM batch_snapshots UNION ALL SELECT batch_id, state_root, orders_root FROM batch_snapshot_archive"
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut nearby = Vec::with_capacity(rows.len() * 2);
+        for row in &rows {
+            let batch_id = row.try_get::<i64, _>("batch_id")? as u64;
+            for (root_kind, column) in [(RootKind::State, "state_root"), (RootKind::Orders, "orders_root")] {
+                let known = stored_root(&row.try_get::<String, _>(column)?);
+                if known.is_empty() {
+                    continue;
+                }
+                let common_prefix = known.chars().zip(root.chars()).take_while(|(a, b)| a == b).count();
+                nearby.push(NearbyRoot { root: format!("0x{}", known), root_kind, batch_id, common_prefix });
+            }
+        }
+        nearby.sort_by(|a, b| b.common_prefix.cmp(&a.common_prefix).then(b.batch_id.cmp(&a.batch_id)));
+        nearby.truncate(limit);
+        Ok(nearby)
+    }
+
+    pub async fn stats(&self) -> Result<ArchiveStats> {
+        let hot_snapshots: i64 = sqlx::query("SELECT COUNT(*) AS count FROM batch_snapshots")
+            .fetch_one(&self.db)
+            .await?
+            .try_get("count")?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT COUNT(*) AS count,
+                   COALESCE(SUM(raw_size), 0) AS raw_bytes,
+                   COALESCE(SUM(compressed_size), 0) AS compressed_bytes
+            FROM batch_snapshot_archive
+            "#,
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(ArchiveStats {
+            retain_batches: self.retain_batches,
+            hot_snapshots,
+            archived_snapshots: row.try_get("count")?,
+            archived_raw_bytes: row.try_get("raw_bytes")?,
+            archived_compressed_bytes: row.try_get("compressed_bytes")?,
+        })
+    }
+}
+
+pub(crate) fn compress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+pub(crate) fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    GzDecoder::new(data).read_to_end(&mut decoded)?;
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TokenBalance;
+    use vapor_core::services::blob_store::{BlobRef, MemoryBlobStore};
+
+    fn snapshot(batch_id: u64) -> BatchSnapshot {
+        BatchSnapshot {
+            batch_id,
+            state_root: format!("{:064x}", batch_id),
+            orders_root: format!("{:064x}", batch_id * 2),
+            accounts: vec![AccountState {
+                address: "0x1234567890123456789012345678901234567890".to_string(),
+                balances: vec![TokenBalance { token_id: 1, balance: (batch_id * 1000).to_string() }],
+                updated_at: Utc::now(),
+            }],
+            orders: Vec::new(),
+            created_at: Utc::now(),
+        }
+    }
+
+    async fn service(retain_batches: u32) -> ArchiveService {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        ArchiveService::new(db, Arc::new(MemoryBlobStore::default()), &ArchiveConfig { retain_batches })
+    }
+
+    #[test]
+    fn test_compression_round_trip() {
+        let data = serde_json::to_vec(&snapshot(3)).unwrap();
+        let compressed = compress(&data).unwrap();
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_old_snapshots_are_archived() {
+        let archive = service(2).await;
+        for batch_id in 1..=5 {
+            archive.store_snapshot(&snapshot(batch_id)).await.unwrap();
+        }
+
+        let stats = archive.stats().await.unwrap();
+        assert_eq!(stats.hot_snapshots, 2);
+        assert_eq!(stats.archived_snapshots, 3);
+        assert!(stats.archived_compressed_bytes > 0);
+    }
+
+    #[tokio::test]
+    async fn test_archived_snapshot_is_restored_lazily() {
+        let archive = service(1).await;
+        for batch_id in 1..=3 {
+            archive.store_snapshot(&snapshot(batch_id)).await.unwrap();
+        }
+        assert_eq!(archive.stats().await.unwrap().hot_snapshots, 1);
+
+        let restored = archive.load_snapshot(1).await.unwrap().unwrap();
+        assert_eq!(restored.batch_id, 1);
+        assert_eq!(restored.accounts[0].balances[0].balance, "1000");
+        assert_eq!(archive.stats().await.unwrap().hot_snapshots, 2);
+
+        // The next retention pass drops the restored copy without archiving it twice
+        let report = archive.enforce_retention().await.unwrap();
+        assert_eq!(report.archived, 1);
+        let stats = archive.stats().await.unwrap();
+        assert_eq!(stats.hot_snapshots, 1);
+        assert_eq!(stats.archived_snapshots, 2);
+
+        assert!(archive.load_snapshot(9).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_zero_retention_keeps_everything_hot() {
+        let archive = service(0).await;
+        for batch_id in 1..=3 {
+            archive.store_snapshot(&snapshot(batch_id)).await.unwrap();
+        }
+        assert_eq!(archive.stats().await.unwrap().archived_snapshots, 0);
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_are_kept_in_the_blob_store() {
+        let blobs = Arc::new(MemoryBlobStore::default());
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let archive = ArchiveService::new(db.clone(), blobs.clone(), &ArchiveConfig { retain_batches: 1 });
+
+        let hot = snapshot(1);
+        archive.store_snapshot(&hot).await.unwrap();
+        let row = sqlx::query("SELECT data, blob_ref, checksum FROM batch_snapshots WHERE batch_id = 1")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        let expected = BlobRef::for_content(&serde_json::to_vec(&hot).unwrap());
+        assert_eq!(row.try_get::<String, _>("data").unwrap(), "");
+        assert_eq!(row.try_get::<String, _>("blob_ref").unwrap(), expected.reference);
+        assert_eq!(row.try_get::<String, _>("checksum").unwrap(), expected.checksum);
+
+        // Archiving replaces the hot blob with the compressed one
+        archive.store_snapshot(&snapshot(2)).await.unwrap();
+        assert!(blobs.get(&expected.reference, &expected.checksum).await.unwrap().is_none());
+        assert_eq!(archive.load_snapshot(1).await.unwrap().unwrap().state_root, hot.state_root);
+    }
+
+    #[tokio::test]
+    async fn test_inline_snapshots_stay_readable() {
+        let archive = service(1).await;
+        let legacy = snapshot(1);
+        sqlx::query("INSERT INTO batch_snapshots (batch_id, state_root, orders_root, data, created_at) VALUES (1, ?1, ?2, ?3, ?4)")
+            .bind(&legacy.state_root)
+            .bind(&legacy.orders_root)
+            .bind(serde_json::to_string(&legacy).unwrap())
+            .bind(legacy.created_at)
+            .execute(&archive.db)
+            .await
+            .unwrap();
+
+        assert_eq!(archive.load_snapshot(1).await.unwrap().unwrap().state_root, legacy.state_root);
+        archive.store_snapshot(&snapshot(2)).await.unwrap();
+        assert_eq!(archive.load_snapshot(1).await.unwrap().unwrap().state_root, legacy.state_root);
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
+use thiserror::Error;
+use web3::types::{Address, H256, U256};
+
+use crate::config::AttestationConfig;
+use vapor_core::proof_format::parse_hash32;
+use vapor_core::sparse_merkle_tree::solidity_keccak256_hash;
+use vapor_chain::signer::{recover_signer, signature_to_hex, uint256, Signer};
+
+/// Domain tag hashed into every attestation digest, so an attestation signature cannot be
+/// passed off as a signature over anything else
+pub const BALANCE_ATTESTATION_DOMAIN: &[u8] = b"VAPOR_BALANCE_ATTESTATION_V1";
+
+/// Operator-signed statement that `address` held `balance` of `token_id` in the state tree of
+/// a proven batch, which third parties may rely on until `expires_at`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceAttestation {
+    pub address: String,
+    pub token_id: u32,
+    pub balance: String,
+    pub batch_id: u64,
+    pub state_root: String,
+    pub expires_at: DateTime<Utc>,
+    /// `keccak256(abi.encodePacked(BALANCE_ATTESTATION_DOMAIN, address, uint256(token_id), uint256(balance), uint256(batch_id), state_root, uint256(expires_at)))`
+    /// with `expires_at` in unix seconds
+    pub digest: String,
+    pub signer: String,
+    /// 65-byte r || s || v over `digest`, without an EIP-191 prefix (v is 27 or 28)
+    pub signature: String,
+    pub issued_at: DateTime<Utc>,
+}
+
+/// Digest signed for a balance attestation
+pub fn attestation_digest(
+    address: &Address,
+    token_id: u32,
+    balance: U256,
+    batch_id: u64,
+    state_root: &[u8; 32],
+    expires_at: DateTime<Utc>,
+) -> [u8; 32] {
+    let mut balance_word = [0u8; 32];
+    balance.to_big_endian(&mut balance_word);
+    solidity_keccak256_hash(&[
+        BALANCE_ATTESTATION_DOMAIN,
+        address.as_bytes(),
+        &uint256(token_id as u64),
+        &balance_word,
+        &uint256(batch_id),
+        state_root,
+        &uint256(expires_at.timestamp().max(0) as u64),
+    ])
+}
+
+fn parse_fields(address: &str, balance: &str, state_root: &str) -> Result<(Address, U256, [u8; 32])> {
+    let address = Address::from_str(address.trim_start_matches("0x"))
+        .map_err(|e| anyhow::anyhow!("Invalid address {}: {}", address, e))?;
+    let balance = U256::from_dec_str(balance)
+        .map_err(|e| anyhow::anyhow!("Invalid balance {}: {:?}", balance, e))?;
+    Ok((address, balance, parse_hash32(state_root)?))
+}
+
+impl BalanceAttestation {
+    /// Recompute the digest from the attestation's fields and recover the address that signed it
+    ///
+    /// The attestation is genuine if this is the operator's address; whether it is still
+    /// current is up to `is_expired`.
+    pub fn recover_signer(&self) -> Result<Address> {
+        let (address, balance, state_root) = parse_fields(&self.address, &self.balance, &self.state_root)?;
+        let digest = attestation_digest(&address, self.token_id, balance, self.batch_id, &state_root, self.expires_at);
+        if format!("0x{}", hex::encode(digest)) != self.digest {
+            return Err(anyhow::anyhow!("Attestation digest does not match its fields"));
+        }
+
+        recover_signer(&digest, &self.signature)
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires_at
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AttestationError {
+    #[error("No operator key is configured to sign attestations")]
+    Disabled,
+    #[error("Attestation expired at {0}")]
+    Expired(DateTime<Utc>),
+    #[error("Attestation is not signed by the operator: {0}")]
+    InvalidSignature(String),
+}
+
+/// Signs balance attestations with the operator key
+///
+/// Without a signer no attestations are issued.
+#[derive(Clone)]
+pub struct BalanceAttester {
+    ttl: Duration,
+    signer: Option<Arc<dyn Signer>>,
+}
+
+impl BalanceAttester {
+    pub fn new(config: &AttestationConfig) -> Self {
+        Self {
+            ttl: Duration::seconds(config.ttl_seconds as i64),
+            signer: None,
+        }
+    }
+
+    pub fn with_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.signer.is_some()
+    }
+
+    /// Accept an attestation presented back to the operator, returning its signer
+    ///
+    /// It must be unaltered, signed by this operator's key and not yet expired at `now`.
+    pub fn accept(&self, attestation: &BalanceAttestation, now: DateTime<Utc>) -> Result<Address, AttestationError> {
+        let operator = self.signer.as_ref().ok_or(AttestationError::Disabled)?.address();
+        let signer = attestation.recover_signer().map_err(|e| AttestationError::InvalidSignature(e.to_string()))?;
+        if signer != operator {
+            return Err(AttestationError::InvalidSignature(format!("signed by {:?}", signer)));
+        }
+        if attestation.is_expired(now) {
+            return Err(AttestationError::Expired(attestation.expires_at));
+        }
+        Ok(signer)
+    }
+
+    /// Sign an account's balance of a token at a batch's state root, valid for the configured TTL from `now`
+    pub async fn attest(
+        &self,
+        address: &str,
+        token_id: u32,
+        balance: &str,
+        batch_id: u64,
+        state_root: &str,
+        now: DateTime<Utc>,
+    ) -> Result<BalanceAttestation> {
+        let Some(signer) = &self.signer else {
+            return Err(anyhow::anyhow!("No operator key is configured to sign attestations"));
+        };
+
+        let (parsed_address, parsed_balance, root) = parse_fields(address, balance, state_root)?;
+        // Whole seconds, so the expiry in the response is exactly the one that was signed
+        let expires_at = DateTime::<Utc>::from_timestamp((now + self.ttl).timestamp(), 0)
+            .ok_or_else(|| anyhow::anyhow!("Attestation expiry is out of range"))?;
+        let digest = attestation_digest(&parsed_address, token_id, parsed_balance, batch_id, &root, expires_at);
+        let signature = signer.sign_digest(H256::from(digest), None).await?;
+
+        Ok(BalanceAttestation {
+            address: format!("{:?}", parsed_address),
+            token_id,
+            balance: parsed_balance.to_string(),
+            batch_id,
+            state_root: format!("0x{}", hex::encode(root)),
+            expires_at,
+            digest: format!("0x{}", hex::encode(digest)),
+            signer: format!("{:?}", signer.address()),
+            signature: signature_to_hex(&signature)?,
+            issued_at: now,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vapor_chain::signer::LocalKeySigner;
+
+    // Anvil's first default account
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const ROOT: &str = "0x1111111111111111111111111111111111111111111111111111111111111111";
+
+    #[tokio::test]
+    async fn test_attestations_recover_to_operator_until_they_expire() {
+        let signer = Arc::new(LocalKeySigner::from_hex(TEST_KEY).unwrap());
+        let operator = signer.address();
+        let attester = BalanceAttester::new(&AttestationConfig { ttl_seconds: 600 }).with_signer(signer);
+        let now = Utc::now();
+
+        let attestation = attester
+            .attest("0x1234567890ABCDEF1234567890abcdef12345678", 1, "1000000000000000000000000", 4, ROOT, now)
+            .await
+            .unwrap();
+        assert_eq!(attestation.address, "0x1234567890abcdef1234567890abcdef12345678");
+        assert_eq!(attestation.recover_signer().unwrap(), operator);
+        assert!(!attestation.is_expired(now + Duration::seconds(599)));
+        assert!(attestation.is_expired(now + Duration::seconds(600)));
+
+        // Any field changed after signing breaks the attestation
+        let inflated = BalanceAttestation { balance: "2000000000000000000000000".to_string(), ..attestation.clone() };
+        assert!(inflated.recover_signer().is_err());
+        let extended = BalanceAttestation { expires_at: attestation.expires_at + Duration::days(1), ..attestation };
+        assert!(extended.recover_signer().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_only_current_operator_attestations_are_accepted() {
+        let attester = BalanceAttester::new(&AttestationConfig { ttl_seconds: 600 })
+            .with_signer(Arc::new(LocalKeySigner::from_hex(TEST_KEY).unwrap()));
+        let now = Utc::now();
+        let attestation = attester.attest("0x1234567890123456789012345678901234567890", 1, "5", 4, ROOT, now).await.unwrap();
+        assert_eq!(format!("{:?}", attester.accept(&attestation, now).unwrap()), attestation.signer);
+
+        let expiry = now + Duration::seconds(600);
+        assert!(matches!(attester.accept(&attestation, expiry), Err(AttestationError::Expired(at)) if at == attestation.expires_at));
+        let inflated = BalanceAttestation { balance: "6".to_string(), ..attestation.clone() };
+        assert!(matches!(attester.accept(&inflated, now), Err(AttestationError::InvalidSignature(_))));
+
+        // Anvil's second default account
+        let other_key = "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+        let impostor = BalanceAttester::new(&AttestationConfig { ttl_seconds: 600 })
+            .with_signer(Arc::new(LocalKeySigner::from_hex(other_key).unwrap()));
+        let counterfeit = impostor.attest("0x1234567890123456789012345678901234567890", 1, "5", 4, ROOT, now).await.unwrap();
+        assert!(matches!(attester.accept(&counterfeit, now), Err(AttestationError::InvalidSignature(_))));
+        assert!(matches!(BalanceAttester::new(&AttestationConfig::default()).accept(&attestation, now), Err(AttestationError::Disabled)));
+    }
+
+    #[tokio::test]
+    async fn test_no_attestations_without_signer() {
+        let attester = BalanceAttester::new(&AttestationConfig::default());
+        assert!(!attester.is_enabled());
+        assert!(attester.attest("0x1234567890123456789012345678901234567890", 1, "5", 1, ROOT, Utc::now()).await.is_err());
+    }
+}
@@ -0,0 +1,513 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha3::{Digest, Keccak256};
+use sqlx::pool::PoolConnection;
+use sqlx::{Row, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::config::BackupConfig;
+use crate::services::archival::{compress, decompress};
+use vapor_core::services::blob_store::BlobStore;
+use crate::services::event_log::{self, DomainEvent};
+
+/// Tables a restore leaves alone: the backup catalogue itself, the maintenance flag the
+/// operator set for the restore, the append-only event log and the writer lease
+const PRESERVED_TABLES: [&str; 4] = ["db_backups", "maintenance_mode", "events", "writer_lease"];
+
+/// Why a backup was taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupTrigger {
+    Scheduled,
+    Manual,
+    /// Taken automatically just before a restore overwrote the database
+    PreRestore,
+}
+
+impl BackupTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Manual => "manual",
+            Self::PreRestore => "pre_restore",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "scheduled" => Self::Scheduled,
+            "pre_restore" => Self::PreRestore,
+            _ => Self::Manual,
+        }
+    }
+}
+
+/// A database snapshot kept in the blob store
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupRecord {
+    pub id: i64,
+    pub blob_ref: String,
+    /// Checksum of the compressed snapshot
+    pub checksum: String,
+    pub raw_size: u64,
+    pub compressed_size: u64,
+    /// Fingerprint of the tables and columns the snapshot was taken with
+    pub schema_fingerprint: String,
+    pub trigger: BackupTrigger,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A snapshot that passed every check and can be restored
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupVerification {
+    pub backup: BackupRecord,
+    /// Rows per table in the snapshot
+    pub tables: BTreeMap<String, i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreReport {
+    pub restored: BackupRecord,
+    /// Backup of the database as it was before the restore
+    pub pre_restore_backup_id: i64,
+    pub tables: BTreeMap<String, i64>,
+    /// In-memory state (open batch, matching engine) is only reloaded on startup
+    pub restart_required: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("no backup {0}")]
+    NotFound(i64),
+    #[error("backup {id} is corrupt: {reason}")]
+    Corrupt { id: i64, reason: String },
+    #[error("backup {id} was taken with a different schema (tables differ: {})", tables.join(", "))]
+    SchemaMismatch { id: i64, expected: String, found: String, tables: Vec<String> },
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for BackupError {
+    fn from(e: sqlx::Error) -> Self {
+        BackupError::Other(e.into())
+    }
+}
+
+/// Point-in-time copies of the database
+///
+/// A backup is a `VACUUM INTO` copy of the SQLite database, gzipped into the blob store and
+/// catalogued in `db_backups`. Restores check the blob checksum, SQLite's integrity check and
+/// the schema fingerprint before any row is replaced, and take a backup of the current
+/// database first.
+#[derive(Clone)]
+pub struct BackupService {
+    db: SqlitePool,
+    blobs: Arc<dyn BlobStore>,
+    retain: u32,
+}
+
+impl BackupService {
+    pub fn new(db: SqlitePool, blobs: Arc<dyn BlobStore>, config: &BackupConfig) -> Self {
+        Self { db, blobs, retain: config.retain }
+    }
+
+    /// Snapshot the database into the blob store, then drop backups beyond the retention
+    pub async fn take(&self, trigger: BackupTrigger, now: DateTime<Utc>) -> Result<BackupRecord> {
+        let path = scratch_path();
+        let result = self.take_into(&path, trigger, now).await;
+        remove_scratch(&path).await;
+        let record = result?;
+
+        info!(
+            "Took {} database backup {} ({} -> {} bytes)",
+            record.trigger.as_str(), record.id, record.raw_size, record.compressed_size
+        );
+        self.rotate().await?;
+        Ok(record)
+    }
+
+    async fn take_into(&self, path: &Path, trigger: BackupTrigger, now: DateTime<Utc>) -> Result<BackupRecord> {
+        // VACUUM INTO and ATTACH inherit the in-memory flag, so such a database never reaches disk
+        let file: String = sqlx::query("PRAGMA database_list")
+            .fetch_one(&self.db)
+            .await?
+            .try_get("file")?;
+        if file.is_empty() {
+            return Err(anyhow::anyhow!("In-memory databases cannot be backed up"));
+        }
+        let schema_fingerprint = schema_fingerprint(&mut *self.db.acquire().await?, "main").await?.0;
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().into_owned())
+            .execute(&self.db)
+            .await?;
+        let data = tokio::fs::read(path).await?;
+        let compressed = compress(&data)?;
+        let blob = self.blobs.put(&compressed).await?;
+
+        let id = sqlx::query(
+            r#"
+            INSERT INTO db_backups (blob_ref, checksum, raw_size, compressed_size, schema_fingerprint, trigger, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&blob.reference)
+        .bind(&blob.checksum)
+        .bind(data.len() as i64)
+        .bind(compressed.len() as i64)
+        .bind(&schema_fingerprint)
+        .bind(trigger.as_str())
+        .bind(now)
+        .execute(&self.db)
+        .await?
+        .last_insert_rowid();
+
+        Ok(BackupRecord {
+            id,
+            blob_ref: blob.reference,
+            checksum: blob.checksum,
+            raw_size: data.len() as u64,
+            compressed_size: compressed.len() as u64,
+            schema_fingerprint,
+            trigger,
+            created_at: now,
+        })
+    }
+
+    /// Backups, newest first
+    pub async fn list(&self) -> Result<Vec<BackupRecord>> {
+        let rows = sqlx::query("SELECT * FROM db_backups ORDER BY id DESC")
+            .fetch_all(&self.db)
+            .await?;
+        rows.iter().map(record_from_row).collect()
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Option<BackupRecord>> {
+        let row = sqlx::query("SELECT * FROM db_backups WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?;
+        row.as_ref().map(record_from_row).transpose()
+    }
+
+    /// Keep the newest `retain` backups (0 keeps them all)
+    pub async fn rotate(&self) -> Result<usize> {
+        if self.retain == 0 {
+            return Ok(0);
+        }
+        let expired = sqlx::query("SELECT id, blob_ref FROM db_backups ORDER BY id DESC LIMIT -1 OFFSET ?")
+            .bind(self.retain as i64)
+            .fetch_all(&self.db)
+            .await?;
+
+        for row in &expired {
+            let (id, blob_ref): (i64, String) = (row.try_get("id")?, row.try_get("blob_ref")?);
+            sqlx::query("DELETE FROM db_backups WHERE id = ?").bind(id).execute(&self.db).await?;
+            // Blobs are content-addressed, so an identical later snapshot may share this one
+            let shared: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM db_backups WHERE blob_ref = ?")
+                .bind(&blob_ref)
+                .fetch_one(&self.db)
+                .await?;
+            if shared == 0 {
+                if let Err(e) = self.blobs.delete(&blob_ref).await {
+                    warn!("Failed to delete blob {} of rotated backup {}: {}", blob_ref, id, e);
+                }
+            }
+        }
+        if !expired.is_empty() {
+            info!("Rotated out {} database backups", expired.len());
+        }
+        Ok(expired.len())
+    }
+
+    /// Check a backup can be restored without touching the database
+    pub async fn verify(&self, id: i64) -> Result<BackupVerification, BackupError> {
+        let (record, path) = self.stage(id).await?;
+        let result = async {
+            let mut conn = attach(&self.db, &path).await?;
+            let tables = check_snapshot(&mut conn, &record).await;
+            detach(&mut conn).await?;
+            tables
+        }
+        .await;
+        remove_scratch(&path).await;
+        Ok(BackupVerification { backup: record, tables: result? })
+    }
+
+    /// Replace the database contents with a backup's, after verifying it
+    ///
+    /// Only the database is restored; the server has to be restarted to reload in-memory
+    /// state from it.
+    pub async fn restore(&self, id: i64, now: DateTime<Utc>) -> Result<RestoreReport, BackupError> {
+        let (record, path) = self.stage(id).await?;
+        let result = async {
+            let mut conn = attach(&self.db, &path).await?;
+            let checked = check_snapshot(&mut conn, &record).await;
+            detach(&mut conn).await?;
+            drop(conn);
+            let tables = checked?;
+
+            let pre_restore = self.take(BackupTrigger::PreRestore, now).await?;
+            let mut conn = attach(&self.db, &path).await?;
+            let copied = copy_tables(&mut conn, &tables).await;
+            // The log itself is kept, so replays need to know where the restored state stands in it
+            let through_seq = sqlx::query_scalar::<_, Option<i64>>("SELECT MAX(seq) FROM snapshot.events")
+                .fetch_one(&mut *conn)
+                .await;
+            detach(&mut conn).await?;
+            copied?;
+            Ok::<_, BackupError>((pre_restore, tables, through_seq?.unwrap_or(0) as u64))
+        }
+        .await;
+        remove_scratch(&path).await;
+
+        let (pre_restore, tables, through_seq) = result?;
+        event_log::append(&self.db, &DomainEvent::BackupRestored { backup_id: record.id, through_seq }).await?;
+        warn!("Restored database backup {} taken at {}; restart the server to reload state", record.id, record.created_at);
+        Ok(RestoreReport { restored: record, pre_restore_backup_id: pre_restore.id, tables, restart_required: true })
+    }
+
+    /// Fetch a backup's snapshot into a scratch file
+    async fn stage(&self, id: i64) -> Result<(BackupRecord, PathBuf), BackupError> {
+        let record = self.get(id).await?.ok_or(BackupError::NotFound(id))?;
+        let corrupt = |reason: String| BackupError::Corrupt { id, reason };
+
+        let compressed = self.blobs.get(&record.blob_ref, &record.checksum).await
+            .map_err(|e| corrupt(e.to_string()))?
+            .ok_or_else(|| corrupt(format!("blob {} is missing from the {} blob store", record.blob_ref, self.blobs.kind())))?;
+        let data = decompress(&compressed).map_err(|e| corrupt(format!("snapshot does not decompress: {}", e)))?;
+        if data.len() as u64 != record.raw_size {
+            return Err(corrupt(format!("snapshot is {} bytes, expected {}", data.len(), record.raw_size)));
+        }
+
+        let path = scratch_path();
+        tokio::fs::write(&path, &data).await.map_err(anyhow::Error::from)?;
+        Ok((record, path))
+    }
+}
+
+/// Integrity and schema checks of the attached snapshot; the row counts of its tables on success
+async fn check_snapshot(conn: &mut SqliteConnection, record: &BackupRecord) -> Result<BTreeMap<String, i64>, BackupError> {
+    let corrupt = |reason: String| BackupError::Corrupt { id: record.id, reason };
+
+    let integrity: Vec<String> = sqlx::query_scalar("PRAGMA snapshot.integrity_check")
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| corrupt(format!("not a readable SQLite database: {}", e)))?;
+    if integrity != ["ok"] {
+        return Err(corrupt(format!("integrity check failed: {}", integrity.join("; "))));
+    }
+
+    let (found, snapshot_tables) = schema_fingerprint(conn, "snapshot").await?;
+    if found != record.schema_fingerprint {
+        return Err(corrupt(format!("schema fingerprint {} does not match the recorded {}", found, record.schema_fingerprint)));
+    }
+    let (expected, current_tables) = schema_fingerprint(conn, "main").await?;
+    if found != expected {
+        let mut tables: Vec<String> = current_tables.keys().chain(snapshot_tables.keys())
+            .filter(|table| current_tables.get(*table) != snapshot_tables.get(*table))
+            .cloned()
+            .collect();
+        tables.dedup();
+        return Err(BackupError::SchemaMismatch { id: record.id, expected, found, tables });
+    }
+
+    let mut counts = BTreeMap::new();
+    for table in snapshot_tables.keys().filter(|table| !PRESERVED_TABLES.contains(&table.as_str())) {
+        let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM snapshot.\"{}\"", table))
+            .fetch_one(&mut *conn)
+            .await?;
+        counts.insert(table.clone(), count);
+    }
+    Ok(counts)
+}
+
+/// Replace every table's rows with the snapshot's, in one transaction
+async fn copy_tables(conn: &mut SqliteConnection, tables: &BTreeMap<String, i64>) -> Result<(), BackupError> {
+    sqlx::query("BEGIN IMMEDIATE").execute(&mut *conn).await?;
+    let result = async {
+        for table in tables.keys() {
+            let columns: Vec<String> = sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}', 'main')", table))
+                .fetch_all(&mut *conn)
+                .await?;
+            let columns = columns.iter().map(|column| format!("\"{}\"", column)).collect::<Vec<_>>().join(", ");
+            sqlx::query(&format!("DELETE FROM main.\"{}\"", table)).execute(&mut *conn).await?;
+            sqlx::query(&format!("INSERT INTO main.\"{0}\" ({1}) SELECT {1} FROM snapshot.\"{0}\"", table, columns))
+                .execute(&mut *conn)
+                .await?;
+        }
+        Ok::<_, sqlx::Error>(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            sqlx::query("COMMIT").execute(&mut *conn).await?;
+            Ok(())
+        }
+        Err(e) => {
+            sqlx::query("ROLLBACK").execute(&mut *conn).await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Keccak of every table's sorted column names and types in `schema`, with the columns by table
+///
+/// Column order is left out, so a database upgraded with `ALTER TABLE` matches a fresh one.
+async fn schema_fingerprint(conn: &mut SqliteConnection, schema: &str) -> Result<(String, BTreeMap<String, Vec<String>>)> {
+    let tables: Vec<String> = sqlx::query_scalar(&format!(
+        "SELECT name FROM {}.sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+        schema
+    ))
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut columns_by_table = BTreeMap::new();
+    for table in tables {
+        let rows = sqlx::query(&format!("SELECT name, type FROM pragma_table_info('{}', '{}')", table, schema))
+            .fetch_all(&mut *conn)
+            .await?;
+        let mut columns = rows.iter()
+            .map(|row| Ok(format!("{} {}", row.try_get::<String, _>("name")?, row.try_get::<String, _>("type")?)))
+            .collect::<Result<Vec<_>>>()?;
+        columns.sort();
+        columns_by_table.insert(table, columns);
+    }
+
+    let mut hasher = Keccak256::new();
+    for (table, columns) in &columns_by_table {
+        hasher.update(format!("{}({});", table, columns.join(",")));
+    }
+    Ok((format!("0x{}", hex::encode(hasher.finalize())), columns_by_table))
+}
+
+async fn attach(db: &SqlitePool, path: &Path) -> Result<PoolConnection<Sqlite>> {
+    let mut conn = db.acquire().await?;
+    sqlx::query("ATTACH DATABASE ? AS snapshot")
+        .bind(path.to_string_lossy().into_owned())
+        .execute(&mut *conn)
+        .await?;
+    Ok(conn)
+}
+
+async fn detach(conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query("DETACH DATABASE snapshot").execute(&mut *conn).await?;
+    Ok(())
+}
+
+fn scratch_path() -> PathBuf {
+    std::env::temp_dir().join(format!("vapor-backup-{}.db", uuid::Uuid::new_v4()))
+}
+
+async fn remove_scratch(path: &Path) {
+    if let Err(e) = tokio::fs::remove_file(path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove backup scratch file {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn record_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<BackupRecord> {
+    Ok(BackupRecord {
+        id: row.try_get("id")?,
+        blob_ref: row.try_get("blob_ref")?,
+        checksum: row.try_get("checksum")?,
+        raw_size: row.try_get::<i64, _>("raw_size")? as u64,
+        compressed_size: row.try_get::<i64, _>("compressed_size")? as u64,
+        schema_fingerprint: row.try_get("schema_fingerprint")?,
+        trigger: BackupTrigger::parse(&row.try_get::<String, _>("trigger")?),
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vapor_core::services::blob_store::MemoryBlobStore;
+
+    async fn setup(retain: u32) -> (SqlitePool, Arc<MemoryBlobStore>, BackupService) {
+        // Backups need a database on disk
+        let path = scratch_path();
+        let db = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let blobs = Arc::new(MemoryBlobStore::default());
+        let service = BackupService::new(db.clone(), blobs.clone(), &BackupConfig { interval_seconds: 0, retain });
+        (db, blobs, service)
+    }
+
+    async fn set_balance(db: &SqlitePool, address: &str, balance: &str) {
+        sqlx::query("INSERT OR REPLACE INTO account_balances (address, token_id, balance, updated_at) VALUES (?, 1, ?, ?)")
+            .bind(address)
+            .bind(balance)
+            .bind(Utc::now())
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    async fn balances(db: &SqlitePool) -> Vec<(String, String)> {
+        sqlx::query_as("SELECT address, balance FROM account_balances ORDER BY address").fetch_all(db).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_restore_brings_back_the_snapshot_and_keeps_the_catalogue() {
+        let (db, _, service) = setup(0).await;
+        set_balance(&db, "0xaaa", "100").await;
+        let backup = service.take(BackupTrigger::Manual, Utc::now()).await.unwrap();
+
+        set_balance(&db, "0xaaa", "5").await;
+        set_balance(&db, "0xbbb", "70").await;
+        sqlx::query("INSERT INTO maintenance_mode (id, enabled, message, updated_at) VALUES (1, 1, 'restoring', ?)")
+            .bind(Utc::now())
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let report = service.restore(backup.id, Utc::now()).await.unwrap();
+        assert_eq!(balances(&db).await, vec![("0xaaa".to_string(), "100".to_string())]);
+        assert_eq!(report.tables["account_balances"], 1);
+        assert!(!report.tables.contains_key("db_backups"));
+
+        // The catalogue and the maintenance flag are not rolled back
+        let catalogue: Vec<_> = service.list().await.unwrap().into_iter().map(|backup| (backup.id, backup.trigger)).collect();
+        assert_eq!(catalogue, vec![(report.pre_restore_backup_id, BackupTrigger::PreRestore), (backup.id, BackupTrigger::Manual)]);
+        let enabled: bool = sqlx::query_scalar("SELECT enabled FROM maintenance_mode WHERE id = 1").fetch_one(&db).await.unwrap();
+        assert!(enabled);
+
+        // The pre-restore backup undoes the restore
+        service.restore(report.pre_restore_backup_id, Utc::now()).await.unwrap();
+        assert_eq!(balances(&db).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_or_mismatched_backups_are_refused() {
+        let (db, blobs, service) = setup(2).await;
+        set_balance(&db, "0xaaa", "100").await;
+        let first = service.take(BackupTrigger::Scheduled, Utc::now()).await.unwrap();
+        let second = service.take(BackupTrigger::Scheduled, Utc::now()).await.unwrap();
+        assert_eq!(service.verify(second.id).await.unwrap().tables["account_balances"], 1);
+
+        // A blob that no longer matches its checksum
+        blobs.write(&first.blob_ref, b"not a snapshot").await.unwrap();
+        assert!(matches!(service.verify(first.id).await, Err(BackupError::Corrupt { .. })));
+        assert!(matches!(service.restore(first.id, Utc::now()).await, Err(BackupError::Corrupt { .. })));
+        assert_eq!(balances(&db).await.len(), 1);
+
+        // A snapshot of an older schema
+        sqlx::query("CREATE TABLE added_later (id INTEGER PRIMARY KEY)").execute(&db).await.unwrap();
+        match service.restore(second.id, Utc::now()).await {
+            Err(BackupError::SchemaMismatch { tables, .. }) => assert_eq!(tables, vec!["added_later"]),
+            other => panic!("expected a schema mismatch, got {:?}", other.map(|report| report.restored.id)),
+        }
+
+        // Only the newest two are kept
+        let third = service.take(BackupTrigger::Scheduled, Utc::now()).await.unwrap();
+        let ids: Vec<_> = service.list().await.unwrap().into_iter().map(|backup| backup.id).collect();
+        assert_eq!(ids, vec![third.id, second.id]);
+        assert!(matches!(service.verify(first.id).await, Err(BackupError::NotFound(_))));
+    }
+}
@@ -0,0 +1,376 @@
+use vapor_chain::BlockchainClient;
+use crate::config::{BalanceAlertConfig, BalanceAlertRule, BalanceSource};
+use crate::models::resolve_account_address;
+use crate::services::batch_processor::BatchProcessor;
+use crate::services::signer_funds::{self, SignerFunds};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn, error};
+use web3::types::{Address, U256};
+
+/// Token id of USDC, the only token whose on-chain balance the client can read
+const USDC_TOKEN_ID: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    /// Not evaluated yet, or the balance could not be read
+    Unknown,
+    Firing,
+    Resolved,
+}
+
+/// Current state of one alert rule (GET /admin/balance-alerts)
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertState {
+    pub rule: BalanceAlertRule,
+    pub status: AlertStatus,
+    /// Last balance read, in token base units
+    pub balance: Option<String>,
+    /// When the rule last started or stopped firing
+    pub since: Option<DateTime<Utc>>,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// A rule that started or stopped firing, as delivered to the webhook
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertEvent {
+    pub rule: BalanceAlertRule,
+    pub status: AlertStatus,
+    pub balance: String,
+    pub at: DateTime<Utc>,
+}
+
+/// The submission signer started or stopped running low on gas, as delivered to the webhook
+#[derive(Debug, Clone, Serialize)]
+pub struct SignerFundsEvent {
+    pub alert: &'static str,
+    pub status: AlertStatus,
+    pub funds: SignerFunds,
+}
+
+/// Balance threshold alerts with their firing/resolved state, and the submission signer's
+/// gas balance
+///
+/// State is kept in memory, so rules below their threshold fire again after a restart.
+#[derive(Clone)]
+pub struct BalanceAlerts {
+    states: Arc<Mutex<Vec<AlertState>>>,
+    /// Last reading of the signer's funds
+    signer: Arc<Mutex<Option<SignerFunds>>>,
+    signer_min_submissions: u64,
+    webhook_url: Option<String>,
+    email_to: Option<String>,
+    client: reqwest::Client,
+}
+
+impl BalanceAlerts {
+    pub fn new(config: &BalanceAlertConfig) -> Self {
+        let states = config.rules.iter()
+            .map(|rule| AlertState {
+                rule: rule.clone(),
+                status: AlertStatus::Unknown,
+                balance: None,
+                since: None,
+                last_checked: None,
+                last_error: None,
+            })
+            .collect();
+        Self {
+            states: Arc::new(Mutex::new(states)),
+            signer: Arc::new(Mutex::new(None)),
+            signer_min_submissions: config.signer_min_submissions,
+            webhook_url: config.webhook_url.clone(),
+            email_to: config.email_to.clone(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn states(&self) -> Vec<AlertState> {
+        self.states.lock().unwrap().clone()
+    }
+
+    /// Read every rule's balance and notify about rules that started or stopped firing
+    pub async fn evaluate(
+        &self,
+        chain: Option<&BlockchainClient>,
+        processor: &tokio::sync::Mutex<BatchProcessor>,
+        bridge_address: &str,
+    ) {
+        let rules: Vec<BalanceAlertRule> = self.states().into_iter().map(|state| state.rule).collect();
+        for (index, rule) in rules.iter().enumerate() {
+            let balance = match rule.source {
+                BalanceSource::Chain => chain_balance(chain, rule, bridge_address).await,
+                BalanceSource::Ledger => Ok(ledger_balance(&*processor.lock().await, rule, bridge_address)),
+            };
+
+            match balance {
+                Ok(balance) => {
+                    if let Some(event) = self.observe(index, balance, Utc::now()) {
+                        self.notify(&event).await;
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to read balance of {} for token {}: {:#}", rule.target, rule.token_id, e);
+                    let mut states = self.states.lock().unwrap();
+                    states[index].last_checked = Some(Utc::now());
+                    states[index].last_error = Some(format!("{:#}", e));
+                }
+            }
+        }
+    }
+
+    /// Read the submission signer's funds, alerting when they start or stop running low
+    ///
+    /// Returns the reading, or None without a signer or when the balance could not be read.
+    pub async fn evaluate_signer(&self, chain: &BlockchainClient, db: &SqlitePool) -> Option<SignerFunds> {
+        let funds = match signer_funds::check(chain, db, self.signer_min_submissions, Utc::now()).await {
+            Ok(funds) => funds?,
+            Err(e) => {
+                warn!("Failed to read the signer's funds: {:#}", e);
+                return None;
+            }
+        };
+
+        if let Some(event) = self.observe_signer(funds.clone()) {
+            self.notify_signer(&event).await;
+        }
+        Some(funds)
+    }
+
+    /// Record a signer funds reading; returns the event to deliver if it became or stopped being low
+    fn observe_signer(&self, funds: SignerFunds) -> Option<SignerFundsEvent> {
+        let previous = self.signer.lock().unwrap().replace(funds.clone());
+        if self.signer_min_submissions == 0 {
+            return None;
+        }
+        // Like rules, funds that were fine from the first reading have nothing to resolve
+        let was_low = previous.map(|previous| previous.low).unwrap_or(false);
+        if funds.low == was_low {
+            return None;
+        }
+        let status = if funds.low { AlertStatus::Firing } else { AlertStatus::Resolved };
+        Some(SignerFundsEvent { alert: "signer_funds_low", status, funds })
+    }
+
+    async fn notify_signer(&self, event: &SignerFundsEvent) {
+        let funds = &event.funds;
+        let runway = funds.runway_days.map_or_else(|| "unknown".to_string(), |days| format!("{} days", days));
+        if event.status == AlertStatus::Firing {
+            let summary = format!(
+                "Signer {} holds {} wei, enough for {} submissions (runway {})",
+                funds.address, funds.balance_wei, funds.submissions_left, runway
+            );
+            error!(alert = "signer_funds_low", signer = %funds.address, "{}", summary);
+            self.deliver(event, &summary).await;
+        } else {
+            let summary = format!("Signer {} topped up to {} wei", funds.address, funds.balance_wei);
+            info!(alert = "signer_funds_low_resolved", signer = %funds.address, "{}", summary);
+            self.deliver(event, &summary).await;
+        }
+    }
+
+    /// Record a balance reading; returns the event to deliver if the rule changed state
+    fn observe(&self, index: usize, balance: u64, now: DateTime<Utc>) -> Option<AlertEvent> {
+        let mut states = self.states.lock().unwrap();
+        let state = &mut states[index];
+        let status = if balance < state.rule.threshold { AlertStatus::Firing } else { AlertStatus::Resolved };
+        let previous = state.status;
+
+        state.balance = Some(balance.to_string());
+        state.last_checked = Some(now);
+        state.last_error = None;
+        if status == previous {
+            return None;
+        }
+        state.status = status;
+        state.since = Some(now);
+
+        // A balance that was fine from the first check has nothing to resolve
+        if previous == AlertStatus::Unknown && status == AlertStatus::Resolved {
+            return None;
+        }
+        Some(AlertEvent { rule: state.rule.clone(), status, balance: balance.to_string(), at: now })
+    }
+
+    async fn notify(&self, event: &AlertEvent) {
+        let rule = &event.rule;
+        let summary = match event.status {
+            AlertStatus::Firing => format!(
+                "Balance of {} ({:?}) for token {} is {}, below {}",
+                rule.target, rule.source, rule.token_id, event.balance, rule.threshold
+            ),
+            _ => format!(
+                "Balance of {} ({:?}) for token {} recovered to {}",
+                rule.target, rule.source, rule.token_id, event.balance
+            ),
+        };
+        if event.status == AlertStatus::Firing {
+            error!(alert = "balance_low", target = %rule.target, token_id = rule.token_id, "{}", summary);
+        } else {
+            info!(alert = "balance_low_resolved", target = %rule.target, token_id = rule.token_id, "{}", summary);
+        }
+        self.deliver(event, &summary).await;
+    }
+
+    /// Deliver an alert to the webhook and the email stub; failures are only logged
+    async fn deliver<T: Serialize>(&self, event: &T, summary: &str) {
+        if let Some(url) = &self.webhook_url {
+            let delivered = self.client.post(url).json(event).send().await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = delivered {
+                warn!("Failed to deliver balance alert webhook to {}: {}", url, e);
+            }
+        }
+
+        if let Some(to) = &self.email_to {
+            info!("Email stub: would send balance alert to {}: {}", to, summary);
+        }
+    }
+}
+
+/// Address a rule reads, with `bridge` and `filler:<filler_id>` resolved
+fn rule_address(rule: &BalanceAlertRule, bridge_address: &str) -> String {
+    if rule.target.eq_ignore_ascii_case("bridge") {
+        bridge_address.to_string()
+    } else {
+        resolve_account_address(&rule.target)
+    }
+}
+
+async fn chain_balance(chain: Option<&BlockchainClient>, rule: &BalanceAlertRule, bridge_address: &str) -> Result<u64> {
+    let Some(chain) = chain else {
+        bail!("no blockchain client configured");
+    };
+    if rule.token_id != USDC_TOKEN_ID {
+        bail!("on-chain balances can only be read for USDC (token {})", USDC_TOKEN_ID);
+    }
+
+    let address: Address = rule_address(rule, bridge_address).parse()
+        .map_err(|_| anyhow!("invalid address {}", rule.target))?;
+    let balance = chain.get_usdc_balance(address).await?;
+    Ok(balance.min(U256::from(u64::MAX)).as_u64())
+}
+
+/// Rollup balance of the rule's account; accounts the ledger does not know hold nothing
+fn ledger_balance(processor: &BatchProcessor, rule: &BalanceAlertRule, bridge_address: &str) -> u64 {
+    let address = rule_address(rule, bridge_address);
+    processor.accounts.values()
+        .find(|account| account.address.eq_ignore_ascii_case(&address))
+        .and_then(|account| account.get_balance(rule.token_id))
+        .and_then(|balance| balance.parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use serde_json::Value;
+
+    const ALICE: &str = "0x1234567890123456789012345678901234567890";
+
+    fn rule(source: BalanceSource, target: &str, threshold: u64) -> BalanceAlertRule {
+        BalanceAlertRule { source, target: target.to_string(), token_id: 1, threshold }
+    }
+
+    fn alerts(rules: Vec<BalanceAlertRule>, webhook_url: Option<String>) -> BalanceAlerts {
+        BalanceAlerts::new(&BalanceAlertConfig { rules, webhook_url, ..Default::default() })
+    }
+
+    #[test]
+    fn test_alerts_fire_and_resolve_on_transitions() {
+        let alerts = alerts(vec![rule(BalanceSource::Ledger, ALICE, 100)], None);
+        let now = Utc::now();
+
+        // Healthy on the first check: nothing to report
+        assert!(alerts.observe(0, 150, now).is_none());
+        assert_eq!(alerts.states()[0].status, AlertStatus::Resolved);
+
+        let fired = alerts.observe(0, 99, now).unwrap();
+        assert_eq!(fired.status, AlertStatus::Firing);
+        assert_eq!(fired.balance, "99");
+        // Still low: already firing, no repeat
+        assert!(alerts.observe(0, 50, now).is_none());
+
+        let resolved = alerts.observe(0, 100, now).unwrap();
+        assert_eq!(resolved.status, AlertStatus::Resolved);
+        let state = &alerts.states()[0];
+        assert_eq!(state.balance.as_deref(), Some("100"));
+        assert_eq!(state.since, Some(now));
+    }
+
+    #[test]
+    fn test_signer_funds_alert_on_becoming_low() {
+        let alerts = alerts(Vec::new(), None);
+        let cost = U256::from(1_000u64);
+        let funds = |balance: u64| signer_funds::assess(Address::repeat_byte(0x11), U256::from(balance), cost, None, 10, Utc::now());
+
+        assert!(alerts.observe_signer(funds(50_000)).is_none());
+        let fired = alerts.observe_signer(funds(9_000)).unwrap();
+        assert_eq!((fired.status, fired.funds.submissions_left), (AlertStatus::Firing, 9));
+        assert!(alerts.observe_signer(funds(500)).is_none());
+        assert_eq!(alerts.observe_signer(funds(10_000)).unwrap().status, AlertStatus::Resolved);
+
+        // A minimum of 0 turns the alert off
+        let silent = BalanceAlerts::new(&BalanceAlertConfig { signer_min_submissions: 0, ..Default::default() });
+        assert!(silent.observe_signer(funds(500)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ledger_rules_read_processor_accounts() {
+        let mut processor = BatchProcessor::new();
+        processor.init_account(ALICE.to_string(), 1, "500".to_string()).unwrap();
+        let processor = tokio::sync::Mutex::new(processor);
+
+        let alerts = alerts(vec![
+            rule(BalanceSource::Ledger, ALICE, 1000),
+            rule(BalanceSource::Ledger, "filler:filler_1", 1),
+            rule(BalanceSource::Chain, "bridge", 1),
+        ], None);
+        alerts.evaluate(None, &processor, "0x0000000000000000000000000000000000000000").await;
+
+        let states = alerts.states();
+        assert_eq!(states[0].status, AlertStatus::Firing);
+        assert_eq!(states[0].balance.as_deref(), Some("500"));
+        // Unknown settlement accounts hold nothing
+        assert_eq!(states[1].status, AlertStatus::Firing);
+        assert_eq!(states[1].balance.as_deref(), Some("0"));
+        // Without a chain client the rule cannot be evaluated
+        assert_eq!(states[2].status, AlertStatus::Unknown);
+        assert!(states[2].last_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_transitions_are_posted_to_webhook() {
+        let received: Arc<Mutex<Vec<Value>>> = Arc::default();
+        let sink = received.clone();
+        let app = Router::new().route("/alerts", post(move |Json(body): Json<Value>| async move {
+            sink.lock().unwrap().push(body);
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/alerts", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut processor = BatchProcessor::new();
+        processor.init_account(ALICE.to_string(), 1, "50".to_string()).unwrap();
+        let processor = tokio::sync::Mutex::new(processor);
+        let alerts = alerts(vec![rule(BalanceSource::Ledger, ALICE, 100)], Some(url));
+
+        alerts.evaluate(None, &processor, "").await;
+        alerts.evaluate(None, &processor, "").await;
+        processor.lock().await.accounts.get_mut(ALICE).unwrap().balances[0].balance = "100".to_string();
+        alerts.evaluate(None, &processor, "").await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0]["status"], "firing");
+        assert_eq!(received[0]["balance"], "50");
+        assert_eq!(received[0]["rule"]["target"], ALICE);
+        assert_eq!(received[1]["status"], "resolved");
+    }
+}
@@ -0,0 +1,211 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{Order, OrderType};
+
+/// A BridgeOut that would take a token's volume in the batch over its cap
+///
+/// `used` is the volume already in the batch; once a token's cap trips, later
+/// BridgeOuts of it are held back too, so deferred orders keep their relative order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("batch {batch_id} already bridges out {used} of token {token_id}, {requested} more would exceed the {max} per-batch cap")]
+pub struct BatchCapExceeded {
+    pub batch_id: u64,
+    pub token_id: u32,
+    pub used: u64,
+    pub requested: u64,
+    pub max: u64,
+}
+
+/// A BridgeOut order held back by the caps, to be retried when the next batch starts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeferredOrder {
+    pub order: Order,
+    /// Batch the order was last deferred from
+    pub batch_id: u64,
+    pub cap: BatchCapExceeded,
+    pub deferred_at: DateTime<Utc>,
+}
+
+/// Cap state of one token, as shown to admins
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TokenCapStatus {
+    pub token_id: u32,
+    pub configured_max: Option<u64>,
+    /// Admin override in place of the configured cap, 0 meaning uncapped
+    pub override_max: Option<u64>,
+    /// Cap enforced for the current batch
+    pub effective_max: Option<u64>,
+    pub batch_volume: u64,
+    pub tripped: bool,
+}
+
+/// Per-token BridgeOut volume of the current batch, checked against the caps
+#[derive(Debug, Clone, Default)]
+pub struct BatchVolumeCaps {
+    configured: HashMap<u32, u64>,
+    overrides: HashMap<u32, u64>,
+    volume: HashMap<u32, u64>,
+    tripped: HashSet<u32>,
+}
+
+impl BatchVolumeCaps {
+    pub fn new(configured: HashMap<u32, u64>) -> Self {
+        Self {
+            configured,
+            ..Default::default()
+        }
+    }
+
+    /// Cap enforced for a token, if any
+    pub fn max_for(&self, token_id: u32) -> Option<u64> {
+        match self.overrides.get(&token_id) {
+            Some(0) => None,
+            Some(max) => Some(*max),
+            None => self.configured.get(&token_id).copied(),
+        }
+    }
+
+    /// Check that an order fits in its token's remaining volume for `batch_id`, tripping the cap if not
+    ///
+    /// Returns whether the cap tripped on this order alongside the error, so the caller can alert once.
+    pub fn check(&mut self, batch_id: u64, order: &Order) -> Result<(), (BatchCapExceeded, bool)> {
+        if order.order_type != OrderType::BridgeOut {
+            return Ok(());
+        }
+        let Some(max) = self.max_for(order.token_id) else {
+            return Ok(());
+        };
+
+        let used = self.volume.get(&order.token_id).copied().unwrap_or(0);
+        let requested: u64 = order.amount.parse().unwrap_or(0);
+        if !self.tripped.contains(&order.token_id) && used.saturating_add(requested) <= max {
+            return Ok(());
+        }
+
+        let newly_tripped = self.tripped.insert(order.token_id);
+        Err((BatchCapExceeded { batch_id, token_id: order.token_id, used, requested, max }, newly_tripped))
+    }
+
+    /// Count an included BridgeOut order towards its token's batch volume
+    pub fn record(&mut self, order: &Order) {
+        if order.order_type != OrderType::BridgeOut {
+            return;
+        }
+        let used = self.volume.entry(order.token_id).or_insert(0);
+        *used = used.saturating_add(order.amount.parse().unwrap_or(0));
+    }
+
+    /// Start counting a new batch
+    pub fn reset(&mut self) {
+        self.volume.clear();
+        self.tripped.clear();
+    }
+
+    /// Replace a token's cap until cleared (0 lifts it) and reset its breaker
+    pub fn set_override(&mut self, token_id: u32, max: u64) {
+        self.overrides.insert(token_id, max);
+        self.tripped.remove(&token_id);
+    }
+
+    /// Go back to the configured cap; returns false if there was no override
+    pub fn clear_override(&mut self, token_id: u32) -> bool {
+        self.overrides.remove(&token_id).is_some()
+    }
+
+    /// Status of every token that has a cap, an override or volume in the batch
+    pub fn status(&self) -> Vec<TokenCapStatus> {
+        let mut token_ids: Vec<u32> = self.configured.keys()
+            .chain(self.overrides.keys())
+            .chain(self.volume.keys())
+            .copied()
+            .collect();
+        token_ids.sort_unstable();
+        token_ids.dedup();
+
+        token_ids.into_iter().map(|token_id| self.status_for(token_id)).collect()
+    }
+
+    pub fn status_for(&self, token_id: u32) -> TokenCapStatus {
+        TokenCapStatus {
+            token_id,
+            configured_max: self.configured.get(&token_id).copied(),
+            override_max: self.overrides.get(&token_id).copied(),
+            effective_max: self.max_for(token_id),
+            batch_volume: self.volume.get(&token_id).copied().unwrap_or(0),
+            tripped: self.tripped.contains(&token_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OrderStatus;
+
+    fn order(order_type: OrderType, token_id: u32, amount: &str) -> Order {
+        Order {
+            id: ulid::Ulid::new().to_string(),
+            order_type,
+            from_address: Some("0x1111111111111111111111111111111111111111".to_string()),
+            to_address: Some("0x1111111111111111111111111111111111111111".to_string()),
+            token_id,
+            amount: amount.to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            status: OrderStatus::Pending,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_cap_trips_and_holds_back_later_orders() {
+        let mut caps = BatchVolumeCaps::new(HashMap::from([(1, 1000)]));
+
+        let first = order(OrderType::BridgeOut, 1, "700");
+        assert!(caps.check(1, &first).is_ok());
+        caps.record(&first);
+
+        let (exceeded, tripped) = caps.check(1, &order(OrderType::BridgeOut, 1, "400")).unwrap_err();
+        assert_eq!(exceeded, BatchCapExceeded { batch_id: 1, token_id: 1, used: 700, requested: 400, max: 1000 });
+        assert!(tripped);
+
+        // Fits, but the breaker is open for the rest of the batch
+        let (_, tripped) = caps.check(1, &order(OrderType::BridgeOut, 1, "100")).unwrap_err();
+        assert!(!tripped);
+
+        // Other tokens and order types are not capped
+        assert!(caps.check(1, &order(OrderType::BridgeOut, 2, "1000000")).is_ok());
+        assert!(caps.check(1, &order(OrderType::Transfer, 1, "1000000")).is_ok());
+
+        caps.reset();
+        assert!(caps.check(2, &order(OrderType::BridgeOut, 1, "1000")).is_ok());
+    }
+
+    #[test]
+    fn test_override_replaces_the_configured_cap() {
+        let mut caps = BatchVolumeCaps::new(HashMap::from([(1, 1000)]));
+        assert!(caps.check(1, &order(OrderType::BridgeOut, 1, "5000")).is_err());
+
+        caps.set_override(1, 0);
+        assert!(caps.check(1, &order(OrderType::BridgeOut, 1, "5000")).is_ok());
+        assert_eq!(caps.status_for(1), TokenCapStatus {
+            token_id: 1,
+            configured_max: Some(1000),
+            override_max: Some(0),
+            effective_max: None,
+            batch_volume: 0,
+            tripped: false,
+        });
+
+        assert!(caps.clear_override(1));
+        assert!(!caps.clear_override(1));
+        assert_eq!(caps.max_for(1), Some(1000));
+    }
+}
@@ -0,0 +1,274 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::BTreeMap;
+
+use crate::services::order_costs::SubmissionCost;
+use crate::services::rates::format_rate;
+
+const WEI_PER_ETH: u128 = 1_000_000_000_000_000_000;
+const WEI_PER_GWEI: f64 = 1_000_000_000.0;
+
+/// Proving time and submission cost of one proven batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchCostSample {
+    pub batch_id: u64,
+    pub orders_count: u64,
+    pub proof_time_ms: u64,
+    pub gas_used: u64,
+    pub gas_price_wei: String,
+    pub gas_cost_wei: String,
+    /// Gas cost at the ETH price of the time, in micro-dollars
+    pub usd_cost_micros: u64,
+    /// No on-chain receipt; `gas_used` is estimated from the calldata
+    pub estimated: bool,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl BatchCostSample {
+    /// `eth_usd_price` is in micro-dollars
+    pub fn new(batch_id: u64, orders_count: u64, proof_time_ms: u64, cost: SubmissionCost, eth_usd_price: u64, recorded_at: DateTime<Utc>) -> Self {
+        let gas_cost_wei = cost.gas_used as u128 * cost.gas_price_wei;
+        Self {
+            batch_id,
+            orders_count,
+            proof_time_ms,
+            gas_used: cost.gas_used,
+            gas_price_wei: cost.gas_price_wei.to_string(),
+            gas_cost_wei: gas_cost_wei.to_string(),
+            usd_cost_micros: u64::try_from(gas_cost_wei * eth_usd_price as u128 / WEI_PER_ETH).unwrap_or(u64::MAX),
+            estimated: cost.estimated,
+            recorded_at,
+        }
+    }
+}
+
+/// Store a batch's sample, replacing an earlier one if the batch was proven again
+pub async fn record(db: &SqlitePool, sample: &BatchCostSample) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT OR REPLACE INTO batch_costs
+            (batch_id, orders_count, proof_time_ms, gas_used, gas_price_wei, gas_cost_wei, usd_cost_micros, estimated, recorded_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        "#,
+    )
+    .bind(sample.batch_id as i64)
+    .bind(sample.orders_count as i64)
+    .bind(i64::try_from(sample.proof_time_ms).unwrap_or(i64::MAX))
+    .bind(sample.gas_used as i64)
+    .bind(&sample.gas_price_wei)
+    .bind(&sample.gas_cost_wei)
+    .bind(i64::try_from(sample.usd_cost_micros).unwrap_or(i64::MAX))
+    .bind(sample.estimated)
+    .bind(sample.recorded_at)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Width of the buckets samples are aggregated into
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostPeriod {
+    #[default]
+    Daily,
+    /// ISO weeks, starting on Monday
+    Weekly,
+}
+
+impl CostPeriod {
+    fn start_of(self, at: DateTime<Utc>) -> NaiveDate {
+        let day = at.date_naive();
+        match self {
+            CostPeriod::Daily => day,
+            CostPeriod::Weekly => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+        }
+    }
+
+    fn length(self) -> Duration {
+        match self {
+            CostPeriod::Daily => Duration::days(1),
+            CostPeriod::Weekly => Duration::weeks(1),
+        }
+    }
+}
+
+/// Batches proven in one period and what they cost
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostBucket {
+    pub period_start: NaiveDate,
+    pub batches: u64,
+    pub orders: u64,
+    /// Batches whose gas was estimated rather than read from a receipt
+    pub estimated_batches: u64,
+    pub avg_proof_time_ms: u64,
+    pub avg_orders_per_batch: f64,
+    pub gas_used: u64,
+    pub avg_gas_per_order: u64,
+    /// Gas-weighted average price
+    pub avg_gas_price_gwei: f64,
+    pub gas_cost_wei: String,
+    pub usd_cost: String,
+    pub usd_cost_per_order: String,
+    #[serde(skip)]
+    usd_cost_micros: u64,
+}
+
+impl CostBucket {
+    fn from_samples(period_start: NaiveDate, samples: &[BatchCostSample]) -> Self {
+        let batches = samples.len() as u64;
+        let orders: u64 = samples.iter().map(|sample| sample.orders_count).sum();
+        let gas_used: u64 = samples.iter().map(|sample| sample.gas_used).sum();
+        let gas_cost_wei: u128 = samples.iter().map(|sample| sample.gas_cost_wei.parse::<u128>().unwrap_or(0)).sum();
+        let usd_cost_micros: u64 = samples.iter().map(|sample| sample.usd_cost_micros).sum();
+        Self {
+            period_start,
+            batches,
+            orders,
+            estimated_batches: samples.iter().filter(|sample| sample.estimated).count() as u64,
+            avg_proof_time_ms: samples.iter().map(|sample| sample.proof_time_ms).sum::<u64>() / batches.max(1),
+            avg_orders_per_batch: orders as f64 / batches.max(1) as f64,
+            gas_used,
+            avg_gas_per_order: gas_used / orders.max(1),
+            avg_gas_price_gwei: if gas_used == 0 { 0.0 } else { gas_cost_wei as f64 / gas_used as f64 / WEI_PER_GWEI },
+            gas_cost_wei: gas_cost_wei.to_string(),
+            usd_cost: format_rate(usd_cost_micros),
+            usd_cost_per_order: format_rate(usd_cost_micros / orders.max(1)),
+            usd_cost_micros,
+        }
+    }
+
+    fn usd_cost_per_order_micros(&self) -> u64 {
+        self.usd_cost_micros / self.orders.max(1)
+    }
+}
+
+/// Change from the previous bucket with batches to the latest one, in percent; `None` where the
+/// previous value was zero
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostTrend {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub avg_proof_time_ms_pct: Option<f64>,
+    pub avg_orders_per_batch_pct: Option<f64>,
+    pub avg_gas_per_order_pct: Option<f64>,
+    pub avg_gas_price_pct: Option<f64>,
+    pub usd_cost_per_order_pct: Option<f64>,
+}
+
+/// Cost history for GET /stats/costs, oldest bucket first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostHistory {
+    pub period: CostPeriod,
+    pub since: NaiveDate,
+    /// Periods without proven batches are left out
+    pub buckets: Vec<CostBucket>,
+    pub trend: Option<CostTrend>,
+}
+
+fn pct_change(previous: f64, current: f64) -> Option<f64> {
+    if previous == 0.0 {
+        return None;
+    }
+    Some(((current - previous) / previous * 10_000.0).round() / 100.0)
+}
+
+/// Aggregate the samples of the last `periods` periods, including the current one
+pub async fn history(db: &SqlitePool, period: CostPeriod, periods: u32, now: DateTime<Utc>) -> Result<CostHistory> {
+    let until = period.start_of(now) + period.length();
+    let since = until - period.length() * periods.max(1) as i32;
+    let midnight = |day: NaiveDate| day.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let rows = sqlx::query(
+        r#"
+        SELECT batch_id, orders_count, proof_time_ms, gas_used, gas_price_wei, gas_cost_wei, usd_cost_micros, estimated, recorded_at
+        FROM batch_costs WHERE recorded_at >= ?1 AND recorded_at < ?2 ORDER BY recorded_at
+        "#,
+    )
+    .bind(midnight(since))
+    .bind(midnight(until))
+    .fetch_all(db)
+    .await?;
+
+    let mut by_period: BTreeMap<NaiveDate, Vec<BatchCostSample>> = BTreeMap::new();
+    for row in &rows {
+        let sample = sample(row)?;
+        by_period.entry(period.start_of(sample.recorded_at)).or_default().push(sample);
+    }
+    let buckets: Vec<CostBucket> = by_period.iter()
+        .map(|(start, samples)| CostBucket::from_samples(*start, samples))
+        .collect();
+
+    let trend = match buckets.as_slice() {
+        [.., previous, latest] => Some(CostTrend {
+            from: previous.period_start,
+            to: latest.period_start,
+            avg_proof_time_ms_pct: pct_change(previous.avg_proof_time_ms as f64, latest.avg_proof_time_ms as f64),
+            avg_orders_per_batch_pct: pct_change(previous.avg_orders_per_batch, latest.avg_orders_per_batch),
+            avg_gas_per_order_pct: pct_change(previous.avg_gas_per_order as f64, latest.avg_gas_per_order as f64),
+            avg_gas_price_pct: pct_change(previous.avg_gas_price_gwei, latest.avg_gas_price_gwei),
+            usd_cost_per_order_pct: pct_change(previous.usd_cost_per_order_micros() as f64, latest.usd_cost_per_order_micros() as f64),
+        }),
+        _ => None,
+    };
+
+    Ok(CostHistory { period, since, buckets, trend })
+}
+
+fn sample(row: &sqlx::sqlite::SqliteRow) -> Result<BatchCostSample> {
+    Ok(BatchCostSample {
+        batch_id: row.try_get::<i64, _>("batch_id")? as u64,
+        orders_count: row.try_get::<i64, _>("orders_count")? as u64,
+        proof_time_ms: row.try_get::<i64, _>("proof_time_ms")? as u64,
+        gas_used: row.try_get::<i64, _>("gas_used")? as u64,
+        gas_price_wei: row.try_get("gas_price_wei")?,
+        gas_cost_wei: row.try_get("gas_cost_wei")?,
+        usd_cost_micros: row.try_get::<i64, _>("usd_cost_micros")? as u64,
+        estimated: row.try_get("estimated")?,
+        recorded_at: row.try_get("recorded_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_daily_and_weekly_history_with_trend() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        // Wednesday
+        let now = Utc.with_ymd_and_hms(2026, 3, 11, 12, 0, 0).unwrap();
+        let cost = |gas_used, gwei: u128| SubmissionCost { gas_used, gas_price_wei: gwei * 1_000_000_000, estimated: false };
+
+        // 100k gas at 10 gwei is 0.001 ETH, $3 at $3000
+        let monday = BatchCostSample::new(1, 10, 2_000, cost(100_000, 10), 3_000_000_000, now - Duration::days(2));
+        assert_eq!((monday.gas_cost_wei.as_str(), monday.usd_cost_micros), ("1000000000000000", 3_000_000));
+        record(&db, &monday).await.unwrap();
+        record(&db, &BatchCostSample::new(2, 20, 3_000, cost(150_000, 20), 3_000_000_000, now)).await.unwrap();
+        record(&db, &BatchCostSample::new(3, 20, 5_000, cost(150_000, 20), 3_000_000_000, now + Duration::hours(1))).await.unwrap();
+        // Outside a 3-day window
+        record(&db, &BatchCostSample::new(0, 1, 1_000, cost(50_000, 5), 3_000_000_000, now - Duration::days(5))).await.unwrap();
+
+        let daily = history(&db, CostPeriod::Daily, 3, now).await.unwrap();
+        assert_eq!(daily.since, NaiveDate::from_ymd_opt(2026, 3, 9).unwrap());
+        assert_eq!(daily.buckets.len(), 2);
+        let today = &daily.buckets[1];
+        assert_eq!((today.batches, today.orders, today.avg_proof_time_ms, today.avg_gas_per_order), (2, 40, 4_000, 7_500));
+        assert_eq!((today.avg_gas_price_gwei, today.usd_cost.as_str(), today.usd_cost_per_order.as_str()), (20.0, "18.000000", "0.450000"));
+
+        let trend = daily.trend.unwrap();
+        assert_eq!((trend.from, trend.to), (monday.recorded_at.date_naive(), now.date_naive()));
+        assert_eq!(trend.avg_proof_time_ms_pct, Some(100.0));
+        assert_eq!(trend.avg_gas_per_order_pct, Some(-25.0));
+        assert_eq!(trend.avg_gas_price_pct, Some(100.0));
+        assert_eq!(trend.usd_cost_per_order_pct, Some(50.0));
+
+        // The earlier sample falls in the previous ISO week
+        let weekly = history(&db, CostPeriod::Weekly, 2, now).await.unwrap();
+        assert_eq!(weekly.buckets.iter().map(|bucket| bucket.batches).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(weekly.buckets[1].period_start, NaiveDate::from_ymd_opt(2026, 3, 9).unwrap());
+        assert!(history(&db, CostPeriod::Weekly, 1, now - Duration::weeks(4)).await.unwrap().trend.is_none());
+    }
+}
@@ -0,0 +1,568 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info, warn};
+
+use crate::config::BatchRecoveryPolicy;
+use crate::database::helpers;
+use crate::models::{AccountState, Order, OrderStatus};
+use crate::services::batch_caps::DeferredOrder;
+use crate::services::batch_processor::{BatchProcessor, ProcessingBatch};
+use crate::services::event_log::{self, DomainEvent};
+
+enum JournalEntry {
+    Started {
+        batch_id: u64,
+        prev_batch_id: u64,
+        prev_state_root: String,
+        prev_orders_root: String,
+        start_accounts: String,
+        created_at: DateTime<Utc>,
+    },
+    OrderAdded {
+        batch_id: u64,
+        position: usize,
+        order: String,
+    },
+    BaselineUpdated {
+        batch_id: u64,
+        start_accounts: String,
+    },
+    Closed {
+        batch_id: u64,
+    },
+    OrderDeferred {
+        order_id: String,
+        deferred: String,
+    },
+    DeferralReleased {
+        order_id: String,
+    },
+    OrderRequeued {
+        order_id: String,
+        order: String,
+        requeued_at: DateTime<Utc>,
+    },
+    RequeueReleased {
+        order_id: String,
+    },
+    Event(DomainEvent),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Write-behind log of the open batch, so its orders survive a restart
+///
+/// The batch processor is synchronous, so entries are queued and written in order by a
+/// background task. The journal holds the account states as of the batch start plus every
+/// order added since, which is enough to deterministically rebuild the batch on startup.
+/// Batch events for the event log go through the same queue.
+#[derive(Clone)]
+pub struct BatchJournal {
+    sender: mpsc::UnboundedSender<JournalEntry>,
+}
+
+impl BatchJournal {
+    /// Start the writer task; must be called from within the Tokio runtime
+    pub fn spawn(db: SqlitePool) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(entry) = receiver.recv().await {
+                if let Err(e) = write_entry(&db, entry).await {
+                    error!("Failed to journal open batch: {}", e);
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    pub fn started(&self, batch: &ProcessingBatch, start_accounts: Vec<&AccountState>) {
+        let Ok(start_accounts) = serde_json::to_string(&start_accounts) else {
+            return;
+        };
+        self.send(JournalEntry::Started {
+            batch_id: batch.batch_id,
+            prev_batch_id: batch.prev_batch_id,
+            prev_state_root: batch.prev_state_root.clone(),
+            prev_orders_root: batch.prev_orders_root.clone(),
+            start_accounts,
+            created_at: batch.created_at,
+        });
+    }
+
+    pub fn order_added(&self, batch_id: u64, position: usize, order: &Order) {
+        let Ok(order) = serde_json::to_string(order) else {
+            return;
+        };
+        self.send(JournalEntry::OrderAdded { batch_id, position, order });
+    }
+
+    pub fn baseline_updated(&self, batch_id: u64, start_accounts: Vec<&AccountState>) {
+        let Ok(start_accounts) = serde_json::to_string(&start_accounts) else {
+            return;
+        };
+        self.send(JournalEntry::BaselineUpdated { batch_id, start_accounts });
+    }
+
+    pub fn closed(&self, batch_id: u64) {
+        self.send(JournalEntry::Closed { batch_id });
+    }
+
+    pub fn order_deferred(&self, deferred: &DeferredOrder) {
+        let Ok(data) = serde_json::to_string(deferred) else {
+            return;
+        };
+        self.send(JournalEntry::OrderDeferred { order_id: deferred.order.id.clone(), deferred: data });
+    }
+
+    pub fn deferral_released(&self, order_id: &str) {
+        self.send(JournalEntry::DeferralReleased { order_id: order_id.to_string() });
+    }
+
+    pub fn order_requeued(&self, order: &Order, requeued_at: DateTime<Utc>) {
+        let Ok(data) = serde_json::to_string(order) else {
+            return;
+        };
+        self.send(JournalEntry::OrderRequeued { order_id: order.id.clone(), order: data, requeued_at });
+    }
+
+    pub fn requeue_released(&self, order_id: &str) {
+        self.send(JournalEntry::RequeueReleased { order_id: order_id.to_string() });
+    }
+
+    /// Append a batch event to the event log, in order with the journal entries
+    pub fn event(&self, event: DomainEvent) {
+        self.send(JournalEntry::Event(event));
+    }
+
+    /// Wait until everything queued so far is written
+    pub async fn flush(&self) {
+        let (done, written) = oneshot::channel();
+        self.send(JournalEntry::Flush(done));
+        let _ = written.await;
+    }
+
+    fn send(&self, entry: JournalEntry) {
+        if self.sender.send(entry).is_err() {
+            warn!("Batch journal writer has stopped, open batch is not persisted");
+        }
+    }
+}
+
+async fn write_entry(db: &SqlitePool, entry: JournalEntry) -> Result<()> {
+    match entry {
+        JournalEntry::Started { batch_id, prev_batch_id, prev_state_root, prev_orders_root, start_accounts, created_at } => {
+            // Only one batch is open at a time, anything left over is stale
+            let mut tx = db.begin().await?;
+            sqlx::query("DELETE FROM open_batch_orders").execute(&mut *tx).await?;
+            sqlx::query("DELETE FROM open_batch").execute(&mut *tx).await?;
+            sqlx::query(
+                r#"
+                INSERT INTO open_batch (batch_id, prev_batch_id, prev_state_root, prev_orders_root, start_accounts, created_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                "#,
+            )
+            .bind(batch_id as i64)
+            .bind(prev_batch_id as i64)
+            .bind(prev_state_root)
+            .bind(prev_orders_root)
+            .bind(start_accounts)
+            .bind(created_at)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+        }
+        JournalEntry::OrderAdded { batch_id, position, order } => {
+            sqlx::query("INSERT OR REPLACE INTO open_batch_orders (batch_id, position, order_data) VALUES (?1, ?2, ?3)")
+                .bind(batch_id as i64)
+                .bind(position as i64)
+                .bind(order)
+                .execute(db)
+                .await?;
+        }
+        JournalEntry::BaselineUpdated { batch_id, start_accounts } => {
+            sqlx::query("UPDATE open_batch SET start_accounts = ?1 WHERE batch_id = ?2")
+                .bind(start_accounts)
+                .bind(batch_id as i64)
+                .execute(db)
+                .await?;
+        }
+        JournalEntry::Closed { batch_id } => {
+            let mut tx = db.begin().await?;
+            sqlx::query("DELETE FROM open_batch_orders WHERE batch_id = ?1")
+                .bind(batch_id as i64)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM open_batch WHERE batch_id = ?1")
+                .bind(batch_id as i64)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+        }
+        JournalEntry::OrderDeferred { order_id, deferred } => {
+            sqlx::query("INSERT OR REPLACE INTO deferred_batch_orders (order_id, deferred_data) VALUES (?1, ?2)")
+                .bind(order_id)
+                .bind(deferred)
+                .execute(db)
+                .await?;
+        }
+        JournalEntry::DeferralReleased { order_id } => {
+            sqlx::query("DELETE FROM deferred_batch_orders WHERE order_id = ?1")
+                .bind(order_id)
+                .execute(db)
+                .await?;
+        }
+        JournalEntry::OrderRequeued { order_id, order, requeued_at } => {
+            sqlx::query("INSERT OR REPLACE INTO requeued_batch_orders (order_id, order_data, requeued_at) VALUES (?1, ?2, ?3)")
+                .bind(order_id)
+                .bind(order)
+                .bind(requeued_at)
+                .execute(db)
+                .await?;
+        }
+        JournalEntry::RequeueReleased { order_id } => {
+            sqlx::query("DELETE FROM requeued_batch_orders WHERE order_id = ?1")
+                .bind(order_id)
+                .execute(db)
+                .await?;
+        }
+        JournalEntry::Event(event) => {
+            event_log::append(db, &event).await?;
+        }
+        JournalEntry::Flush(done) => {
+            let _ = done.send(());
+        }
+    }
+    Ok(())
+}
+
+/// Load the batch that was open when the server stopped, with the account states it started from
+pub async fn load_open_batch(db: &SqlitePool) -> Result<Option<(ProcessingBatch, Vec<AccountState>)>> {
+    let Some(row) = sqlx::query(
+        "SELECT batch_id, prev_batch_id, prev_state_root, prev_orders_root, start_accounts, created_at FROM open_batch",
+    )
+    .fetch_optional(db)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let batch_id: i64 = row.try_get("batch_id")?;
+    let prev_batch_id: i64 = row.try_get("prev_batch_id")?;
+    let start_accounts: String = row.try_get("start_accounts")?;
+
+    let orders = sqlx::query("SELECT order_data FROM open_batch_orders WHERE batch_id = ?1 ORDER BY position")
+        .bind(batch_id)
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(|row| -> Result<Order> {
+            let data: String = row.try_get("order_data")?;
+            Ok(serde_json::from_str(&data)?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let batch = ProcessingBatch {
+        batch_id: batch_id as u64,
+        prev_batch_id: prev_batch_id as u64,
+        prev_state_root: row.try_get("prev_state_root")?,
+        prev_orders_root: row.try_get("prev_orders_root")?,
+        orders,
+        new_state_root: String::new(),
+        new_orders_root: String::new(),
+        created_at: row.try_get("created_at")?,
+        is_finalized: false,
+    };
+
+    Ok(Some((batch, serde_json::from_str(&start_accounts)?)))
+}
+
+/// Load the orders the batch caps were holding back when the server stopped, oldest first
+pub async fn load_deferred_orders(db: &SqlitePool) -> Result<Vec<DeferredOrder>> {
+    let mut deferred = sqlx::query("SELECT deferred_data FROM deferred_batch_orders")
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(|row| -> Result<DeferredOrder> {
+            let data: String = row.try_get("deferred_data")?;
+            Ok(serde_json::from_str(&data)?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    deferred.sort_by(|a, b| a.order.created_at.cmp(&b.order.created_at).then_with(|| a.order.id.cmp(&b.order.id)));
+    Ok(deferred)
+}
+
+/// Load the orders of an aborted batch that were not re-added to a batch before the server stopped
+pub async fn load_requeued_orders(db: &SqlitePool) -> Result<Vec<Order>> {
+    sqlx::query("SELECT order_data FROM requeued_batch_orders ORDER BY requeued_at, rowid")
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(|row| -> Result<Order> {
+            let data: String = row.try_get("order_data")?;
+            Ok(serde_json::from_str(&data)?)
+        })
+        .collect()
+}
+
+/// A batch abandoned by an admin before it was finalized
+#[derive(Debug, Clone, Serialize)]
+pub struct AbortedBatch {
+    pub batch_id: u64,
+    pub reason: String,
+    /// Orders taken out of the batch and requeued for the next one
+    pub order_ids: Vec<String>,
+    pub aborted_at: DateTime<Utc>,
+}
+
+pub async fn record_aborted_batch(db: &SqlitePool, aborted: &AbortedBatch) -> Result<()> {
+    sqlx::query("INSERT INTO aborted_batches (batch_id, reason, order_ids, aborted_at) VALUES (?1, ?2, ?3, ?4)")
+        .bind(aborted.batch_id as i64)
+        .bind(&aborted.reason)
+        .bind(serde_json::to_string(&aborted.order_ids)?)
+        .bind(aborted.aborted_at)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Aborted batches, most recent first
+pub async fn list_aborted_batches(db: &SqlitePool, limit: u32) -> Result<Vec<AbortedBatch>> {
+    sqlx::query("SELECT batch_id, reason, order_ids, aborted_at FROM aborted_batches ORDER BY id DESC LIMIT ?1")
+        .bind(limit as i64)
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(|row| -> Result<AbortedBatch> {
+            let order_ids: String = row.try_get("order_ids")?;
+            Ok(AbortedBatch {
+                batch_id: row.try_get::<i64, _>("batch_id")? as u64,
+                reason: row.try_get("reason")?,
+                order_ids: serde_json::from_str(&order_ids)?,
+                aborted_at: row.try_get("aborted_at")?,
+            })
+        })
+        .collect()
+}
+
+/// What startup recovery did with an orphaned batch
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryReport {
+    pub batch_id: u64,
+    pub policy: BatchRecoveryPolicy,
+    /// Orders still in the reopened batch
+    pub resumed_orders: usize,
+    /// Orders dropped from the batch and marked Failed
+    pub failed_orders: Vec<String>,
+}
+
+/// Reopen a batch left unfinalized by a restart, according to `policy`, and requeue deferred orders
+///
+/// Orders dropped from the batch (all of them on rollback, those that no longer replay on
+/// resume) are marked Failed so they are not silently lost.
+pub async fn recover_open_batch(
+    db: &SqlitePool,
+    processor: &mut BatchProcessor,
+    policy: BatchRecoveryPolicy,
+) -> Result<Option<RecoveryReport>> {
+    processor.restore_deferred(load_deferred_orders(db).await?);
+    processor.restore_requeued(load_requeued_orders(db).await?);
+
+    let Some((batch, start_accounts)) = load_open_batch(db).await? else {
+        return Ok(None);
+    };
+
+    let batch_id = batch.batch_id;
+    warn!("Batch {} was not finalized before shutdown, recovering with policy {:?}", batch_id, policy);
+
+    let dropped = processor.reopen_batch(batch, start_accounts, policy);
+    let reason = match policy {
+        BatchRecoveryPolicy::Resume => "batch_replay_failed",
+        BatchRecoveryPolicy::Rollback => "batch_rolled_back",
+    };
+
+    let mut failed_orders = Vec::with_capacity(dropped.len());
+    for order in dropped {
+        fail_order(db, &order.id, reason).await?;
+        failed_orders.push(order.id);
+    }
+
+    let report = RecoveryReport {
+        batch_id,
+        policy,
+        resumed_orders: processor.get_current_batch().map_or(0, |batch| batch.orders.len()),
+        failed_orders,
+    };
+    info!(
+        "Recovered batch {}: {} orders resumed, {} failed",
+        report.batch_id, report.resumed_orders, report.failed_orders.len()
+    );
+    Ok(Some(report))
+}
+
+async fn fail_order(db: &SqlitePool, order_id: &str, reason: &str) -> Result<()> {
+    let Some(order) = helpers::get_order_by_id(db, order_id).await? else {
+        warn!("Order {} from the recovered batch is not in the database", order_id);
+        return Ok(());
+    };
+
+    sqlx::query("UPDATE orders SET status = ?1, failure_reason = ?2, updated_at = ?3 WHERE id = ?4")
+        .bind(OrderStatus::Failed as i32)
+        .bind(reason)
+        .bind(Utc::now())
+        .bind(order_id)
+        .execute(db)
+        .await?;
+
+    helpers::record_status_transition(db, order_id, order.status, OrderStatus::Failed, Some(reason)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OrderType;
+
+    const ALICE: &str = "0x1111111111111111111111111111111111111111";
+    const BOB: &str = "0x2222222222222222222222222222222222222222";
+
+    fn transfer(id: &str, amount: &str) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::Transfer,
+            from_address: Some(ALICE.to_string()),
+            to_address: Some(BOB.to_string()),
+            token_id: 1,
+            amount: amount.to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            status: OrderStatus::Pending,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Open a batch with two transfers, as a server would have before stopping
+    async fn interrupted_batch() -> (SqlitePool, BatchProcessor) {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+
+        let journal = BatchJournal::spawn(db.clone());
+        let mut processor = BatchProcessor::new().with_journal(journal.clone());
+        processor.init_account(ALICE.to_string(), 1, "1000".to_string()).unwrap();
+        processor.start_batch().unwrap();
+        for order in [transfer("t1", "300"), transfer("t2", "200")] {
+            helpers::insert_order(&db, &order).await.unwrap();
+            processor.add_order_to_batch(order).unwrap();
+        }
+        journal.flush().await;
+
+        (db, processor)
+    }
+
+    #[tokio::test]
+    async fn test_resume_rebuilds_the_open_batch() {
+        let (db, mut original) = interrupted_batch().await;
+
+        let mut restarted = BatchProcessor::new().with_journal(BatchJournal::spawn(db.clone()));
+        let report = recover_open_batch(&db, &mut restarted, BatchRecoveryPolicy::Resume).await.unwrap().unwrap();
+        assert_eq!(report.batch_id, 1);
+        assert_eq!(report.resumed_orders, 2);
+        assert!(report.failed_orders.is_empty());
+        assert_eq!(restarted.next_batch_id, 2);
+
+        // The rebuilt batch finalizes to the same roots the original would have
+        let expected = original.finalize_batch().unwrap();
+        let recovered = restarted.finalize_batch().unwrap();
+        assert_eq!(recovered.prev_state_root, expected.prev_state_root);
+        assert_eq!(recovered.new_state_root, expected.new_state_root);
+        assert_eq!(recovered.new_orders_root, expected.new_orders_root);
+
+        // Finalizing clears the journal, so the next restart has nothing to recover
+        restarted.journal.as_ref().unwrap().flush().await;
+        assert!(load_open_batch(&db).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rollback_reopens_empty_batch_and_fails_orders() {
+        let (db, _original) = interrupted_batch().await;
+
+        let mut restarted = BatchProcessor::new().with_journal(BatchJournal::spawn(db.clone()));
+        let report = recover_open_batch(&db, &mut restarted, BatchRecoveryPolicy::Rollback).await.unwrap().unwrap();
+        assert_eq!(report.resumed_orders, 0);
+        assert_eq!(report.failed_orders, vec!["t1".to_string(), "t2".to_string()]);
+
+        let batch = restarted.get_current_batch().unwrap();
+        assert_eq!(batch.batch_id, 1);
+        assert!(batch.orders.is_empty());
+        assert_eq!(restarted.accounts.get(ALICE).unwrap().balances[0].balance, "1000");
+        assert!(!restarted.accounts.contains_key(BOB));
+
+        let order = helpers::get_order_by_id(&db, "t1").await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Failed);
+
+        // The journal now matches the reopened, empty batch
+        restarted.journal.as_ref().unwrap().flush().await;
+        let (journaled, _) = load_open_batch(&db).await.unwrap().unwrap();
+        assert!(journaled.orders.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deferred_orders_survive_restart() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+
+        let journal = BatchJournal::spawn(db.clone());
+        let mut processor = BatchProcessor::new()
+            .with_journal(journal.clone())
+            .with_batch_caps(std::collections::HashMap::from([(1, 100)]));
+        processor.init_account(ALICE.to_string(), 1, "1000".to_string()).unwrap();
+        processor.start_batch().unwrap();
+        let mut bridge_out = transfer("out", "300");
+        bridge_out.order_type = OrderType::BridgeOut;
+        assert!(processor.add_order_to_batch(bridge_out).is_err());
+        processor.finalize_batch().unwrap();
+        journal.flush().await;
+
+        let mut restarted = BatchProcessor::new().with_journal(BatchJournal::spawn(db.clone()));
+        restarted.init_account(ALICE.to_string(), 1, "1000".to_string()).unwrap();
+        assert!(recover_open_batch(&db, &mut restarted, BatchRecoveryPolicy::Resume).await.unwrap().is_none());
+        assert_eq!(restarted.deferred_orders.len(), 1);
+
+        // Without the cap the order goes into the next batch and leaves the journal
+        restarted.start_batch().unwrap();
+        assert_eq!(restarted.get_current_batch().unwrap().orders[0].id, "out");
+        restarted.journal.as_ref().unwrap().flush().await;
+        assert!(load_deferred_orders(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_aborted_batch_orders_are_requeued_across_restart() {
+        let (db, mut original) = interrupted_batch().await;
+
+        let aborted = original.abort_batch().unwrap();
+        assert_eq!((aborted.batch_id, aborted.orders.len()), (1, 2));
+        assert!(original.get_current_batch().is_none());
+        assert_eq!(original.next_batch_id, 1);
+        assert_eq!(original.accounts.get(ALICE).unwrap().balances[0].balance, "1000");
+        original.journal.as_ref().unwrap().flush().await;
+        assert!(load_open_batch(&db).await.unwrap().is_none());
+
+        let mut restarted = BatchProcessor::new().with_journal(BatchJournal::spawn(db.clone()));
+        restarted.init_account(ALICE.to_string(), 1, "1000".to_string()).unwrap();
+        assert!(recover_open_batch(&db, &mut restarted, BatchRecoveryPolicy::Resume).await.unwrap().is_none());
+        assert_eq!(restarted.requeued_orders.len(), 2);
+
+        // The next batch reuses the aborted batch's id and takes its orders back, in order
+        assert_eq!(restarted.start_batch().unwrap(), 1);
+        let order_ids: Vec<&str> = restarted.get_current_batch().unwrap().orders.iter().map(|order| order.id.as_str()).collect();
+        assert_eq!(order_ids, vec!["t1", "t2"]);
+        restarted.journal.as_ref().unwrap().flush().await;
+        assert!(load_requeued_orders(&db).await.unwrap().is_empty());
+    }
+}
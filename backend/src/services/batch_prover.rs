@@ -0,0 +1,507 @@
+use crate::services::batch_processor::{BatchError, ProcessingBatch};
+use crate::services::batch_sequence::{self, BatchLink, SequenceTip};
+use crate::services::mvp_prover::{MvpProverConfig, MvpProverService, ProofGenerationResult, ProverStats};
+use crate::services::proof_compression::{self, PreparedSubmission};
+use crate::services::stats;
+use crate::config::{CalldataCompression, ProofSubmissionConfig};
+use vapor_chain::{BlockchainClient, ProofSubmissionResult};
+use sqlx::SqlitePool;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn, error, instrument};
+
+type Result<T> = std::result::Result<T, BatchError>;
+
+/// Finalized batches waiting for a proof, oldest first
+///
+/// Shared by the building stage, which pushes batches as they are finalized, and the proving
+/// stage, which drains them in order. The lock is only held for queue operations, never while
+/// proving, so finalizing never waits on a proof.
+#[derive(Clone, Default)]
+pub struct ProvingQueue {
+    batches: Arc<Mutex<VecDeque<ProcessingBatch>>>,
+}
+
+impl ProvingQueue {
+    pub fn push(&self, batch: ProcessingBatch) -> Result<()> {
+        if !batch.is_finalized {
+            return Err(BatchError::NotFinalized(batch.batch_id));
+        }
+        info!("Batch {} queued for proving", batch.batch_id);
+        self.batches.lock().unwrap().push_back(batch);
+        Ok(())
+    }
+
+    /// Ids of the queued batches, oldest first
+    pub fn batch_ids(&self) -> Vec<u64> {
+        self.batches.lock().unwrap().iter().map(|batch| batch.batch_id).collect()
+    }
+
+    fn front(&self) -> Option<ProcessingBatch> {
+        self.batches.lock().unwrap().front().cloned()
+    }
+
+    fn pop_front(&self) {
+        self.batches.lock().unwrap().pop_front();
+    }
+}
+
+/// Outcome of proving one queued batch
+#[derive(Debug)]
+pub struct ProvenBatch {
+    pub batch_id: u64,
+    pub result: ProofGenerationResult,
+    /// Encoded proof, waiting for its sizes to be recorded
+    pub submission: Option<PreparedSubmission>,
+    /// Gas of the on-chain submission, when it was submitted
+    pub gas_used: Option<u64>,
+}
+
+/// Proving stage of the batch pipeline
+///
+/// Generates and submits proofs for finalized batches while the `BatchProcessor` keeps
+/// building the next one. Batches are proven strictly in order, since each proof starts
+/// from the roots of the previous batch.
+pub struct BatchProver {
+    /// MVP prover service for generating mock proofs
+    pub prover: MvpProverService,
+    /// Optional blockchain client for submitting proofs
+    pub blockchain_client: Option<Arc<BlockchainClient>>,
+    /// Verifier contract proofs are submitted to, and how their calldata is compressed
+    pub submission_target: String,
+    pub calldata_compression: CalldataCompression,
+    pub queue: ProvingQueue,
+    /// Where proof totals are recorded; without it stats only show the configuration
+    stats_db: Option<SqlitePool>,
+}
+
+impl BatchProver {
+    pub fn new(queue: ProvingQueue) -> Self {
+        Self {
+            prover: MvpProverService::new(MvpProverConfig::default()),
+            blockchain_client: None,
+            submission_target: String::new(),
+            calldata_compression: CalldataCompression::None,
+            queue,
+            stats_db: None,
+        }
+    }
+
+    /// Submit proofs to `verifier_address`, compressed as configured for it
+    pub fn with_proof_submission(mut self, config: &ProofSubmissionConfig, verifier_address: &str) -> Self {
+        self.submission_target = verifier_address.to_string();
+        self.calldata_compression = config.compression_for(verifier_address);
+        self
+    }
+
+    pub fn with_blockchain_client(mut self, client: Arc<BlockchainClient>) -> Self {
+        self.blockchain_client = Some(client);
+        self
+    }
+
+    /// Persist proof totals in the stats table
+    pub fn with_stats(mut self, db: SqlitePool) -> Self {
+        self.stats_db = Some(db);
+        self
+    }
+
+    /// Prove queued batches in order up to and including `batch_id`
+    ///
+    /// Stops at the first failed proof, which stays queued to be retried; the returned
+    /// list then ends with that failure instead of `batch_id`.
+    #[instrument(skip_all, fields(batch_id = batch_id))]
+    pub async fn prove_through(&mut self, batch_id: u64) -> Result<Vec<ProvenBatch>> {
+        if !self.queue.batch_ids().contains(&batch_id) {
+            return Err(BatchError::NotQueued(batch_id));
+        }
+
+        let mut proven = Vec::new();
+        while let Some(batch) = self.queue.front() {
+            let outcome = self.prove(&batch).await?;
+            let success = outcome.result.success;
+            proven.push(outcome);
+            if !success {
+                break;
+            }
+            self.queue.pop_front();
+            if batch.batch_id == batch_id {
+                break;
+            }
+        }
+
+        Ok(proven)
+    }
+
+    /// Generate a proof for a finalized batch and optionally submit it to the blockchain
+    async fn prove(&self, batch: &ProcessingBatch) -> Result<ProvenBatch> {
+        let batch_id = batch.batch_id;
+        info!("Starting proof generation and submission for batch {}", batch_id);
+
+        // Generate proof using MVP prover
+        let proof_result = self.prover.generate_proof_for_batch(
+            batch.batch_id,
+            &batch.prev_state_root,
+            &batch.prev_orders_root,
+            &batch.new_state_root,
+            &batch.new_orders_root,
+            &batch.orders,
+        ).await?;
+
+        if let Some(db) = &self.stats_db {
+            if let Err(e) = stats::record_proof(db, proof_result.success, proof_result.generation_time_ms).await {
+                error!("Failed to record proof stats for batch {}: {}", batch_id, e);
+            }
+        }
+
+        let mut submission = None;
+        let mut gas_used = None;
+        if proof_result.success {
+            if let Some(ref proof) = proof_result.proof {
+                info!("Proof generated successfully for batch {}", batch_id);
+
+                let prepared = proof_compression::prepare(
+                    batch_id,
+                    &self.submission_target,
+                    &proof.to_submission_bytes(),
+                    self.calldata_compression,
+                )?;
+
+                // Submit proof to blockchain if client is available
+                if self.blockchain_client.is_some() {
+                    match self.submit_proof_to_blockchain(&prepared, batch).await {
+                        Ok(result) => {
+                            info!("Proof submitted to blockchain successfully for batch {}", batch_id);
+                            gas_used = result.gas_used.map(|gas| gas.low_u64());
+                        }
+                        // The batch stays queued; submitting it out of sequence would never verify
+                        Err(BatchError::Sequence(e)) => return Err(e.into()),
+                        Err(e) => {
+                            error!("Failed to submit proof to blockchain for batch {}: {}", batch_id, e);
+                            // Don't fail the entire operation, just log the error
+                        }
+                    }
+                } else {
+                    warn!("No blockchain client available, skipping on-chain submission for batch {}", batch_id);
+                }
+                submission = Some(prepared);
+            } else {
+                error!("Proof generation succeeded but no proof returned for batch {}", batch_id);
+            }
+        } else {
+            error!("Proof generation failed for batch {}: {:?}", batch_id, proof_result.error_message);
+        }
+
+        Ok(ProvenBatch { batch_id, result: proof_result, submission, gas_used })
+    }
+
+    /// Submit proof to blockchain via smart contract
+    async fn submit_proof_to_blockchain(&self, submission: &PreparedSubmission, batch: &ProcessingBatch) -> Result<ProofSubmissionResult> {
+        if let Some(ref blockchain_client) = self.blockchain_client {
+            // The verifier only accepts the batch after its latest one, starting from that batch's roots
+            let latest_batch_id = blockchain_client.get_latest_batch_id().await?;
+            let on_chain = if latest_batch_id == 0 {
+                SequenceTip::genesis()
+            } else {
+                let (state_root, orders_root) = blockchain_client.get_batch_roots(latest_batch_id).await?;
+                SequenceTip {
+                    batch_id: latest_batch_id,
+                    state_root: hex::encode(state_root),
+                    orders_root: hex::encode(orders_root),
+                }
+            };
+            batch_sequence::check_follows(&on_chain, &BatchLink::from(batch))?;
+
+            let prev_state_root = vapor_chain::hex_to_h256(&batch.prev_state_root)?;
+            let prev_orders_root = vapor_chain::hex_to_h256(&batch.prev_orders_root)?;
+            let new_state_root = vapor_chain::hex_to_h256(&batch.new_state_root)?;
+            let new_orders_root = vapor_chain::hex_to_h256(&batch.new_orders_root)?;
+            let proof_bytes = web3::types::Bytes(submission.calldata.clone());
+
+            let result = blockchain_client.submit_proof(
+                batch.batch_id,
+                batch.prev_batch_id,
+                prev_state_root,
+                prev_orders_root,
+                new_state_root,
+                new_orders_root,
+                proof_bytes,
+            ).await?;
+
+            info!("Proof submission result: {:?}", result);
+            Ok(result)
+        } else {
+            Err(BatchError::NoBlockchainClient)
+        }
+    }
+
+    /// Update MVP prover configuration
+    pub fn update_prover_config(&mut self, config: MvpProverConfig) {
+        self.prover.update_config(config);
+        info!("Updated MVP prover configuration");
+    }
+
+    pub fn prover_config(&self) -> &MvpProverConfig {
+        self.prover.config()
+    }
+
+    /// Get prover statistics, with the recorded proof totals
+    pub async fn get_prover_stats(&self) -> ProverStats {
+        let totals = match &self.stats_db {
+            Some(db) => stats::proof_totals(db).await.unwrap_or_else(|e| {
+                error!("Failed to read proof stats: {}", e);
+                Default::default()
+            }),
+            None => Default::default(),
+        };
+        self.prover.get_stats(&totals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Order, OrderStatus, OrderType};
+    use crate::services::batch_processor::BatchProcessor;
+    use crate::services::mvp_prover::FailureScenario;
+    use vapor_chain::chain_reads::{ChainReadCache, ReadKey, ReadValue};
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    fn bridge_in(id: &str, amount: &str) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::BridgeIn,
+            status: OrderStatus::Pending,
+            from_address: None,
+            to_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            token_id: 1,
+            amount: amount.to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn fast_config() -> MvpProverConfig {
+        MvpProverConfig {
+            generation_delay_ms: 1,
+            simulate_failures: false,
+            failure_rate: 0.0,
+            scenarios: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_batch_prover_creation() {
+        let prover = BatchProver::new(ProvingQueue::default());
+        assert!(prover.blockchain_client.is_none());
+        assert!(prover.queue.batch_ids().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_proof_generation_success() {
+        let mut processor = BatchProcessor::new();
+        let mut prover = BatchProver::new(processor.proving_queue.clone());
+
+        // Update prover config for fast testing
+        prover.update_prover_config(fast_config());
+
+        processor.start_batch().unwrap();
+        processor.add_order_to_batch(bridge_in("proof_test", "1000")).unwrap();
+        let batch_id = processor.finalize_batch().unwrap().batch_id;
+
+        let stats = prover.get_prover_stats().await;
+        assert!(stats.is_mock);
+        assert_eq!(stats.generation_delay_ms, 1);
+
+        // Finalizing queues the batch for proving
+        assert_eq!(prover.queue.batch_ids(), vec![batch_id]);
+        let proven = prover.prove_through(batch_id).await.unwrap();
+        assert_eq!(proven.len(), 1);
+        assert!(proven[0].result.success);
+        let submission = proven[0].submission.as_ref().unwrap();
+        assert_eq!(submission.batch_id, batch_id);
+        assert_eq!(submission.calldata, proven[0].result.proof.as_ref().unwrap().to_submission_bytes());
+        assert!(prover.queue.batch_ids().is_empty());
+
+        assert!(matches!(prover.prove_through(batch_id + 1).await, Err(BatchError::NotQueued(_))));
+    }
+
+    #[tokio::test]
+    async fn test_proof_calldata_compression_per_target() {
+        let verifier = "0x00000000000000000000000000000000000000Aa";
+        let config = ProofSubmissionConfig {
+            default_compression: CalldataCompression::None,
+            target_compression: HashMap::from([(verifier.to_lowercase(), CalldataCompression::ZstdArtifact)]),
+            ..ProofSubmissionConfig::default()
+        };
+        let mut processor = BatchProcessor::new();
+        let mut prover = BatchProver::new(processor.proving_queue.clone()).with_proof_submission(&config, verifier);
+        prover.update_prover_config(fast_config());
+
+        let batch_id = processor.start_batch().unwrap();
+        processor.finalize_batch().unwrap();
+        let mut proven = prover.prove_through(batch_id).await.unwrap();
+
+        let submission = proven.pop().unwrap().submission.unwrap();
+        assert_eq!(submission.target, verifier);
+        assert_eq!(submission.compression, CalldataCompression::ZstdArtifact);
+        assert_eq!(submission.calldata.len(), 32);
+        assert!(submission.artifact.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_proof_totals_survive_restart() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+
+        let mut processor = BatchProcessor::new();
+        let mut prover = BatchProver::new(processor.proving_queue.clone()).with_stats(db.clone());
+        prover.update_prover_config(MvpProverConfig {
+            scenarios: vec![FailureScenario::FailAttempts { batch_id: None, attempts: 1 }],
+            ..fast_config()
+        });
+        let batch_id = processor.start_batch().unwrap();
+        processor.add_order_to_batch(bridge_in("stats", "100")).unwrap();
+        processor.finalize_batch().unwrap();
+        assert!(!prover.prove_through(batch_id).await.unwrap()[0].result.success);
+        assert!(prover.prove_through(batch_id).await.unwrap()[0].result.success);
+
+        // A new prover over the same database reports the same totals
+        let restarted = BatchProver::new(ProvingQueue::default()).with_stats(db);
+        let stats = restarted.get_prover_stats().await;
+        assert_eq!(stats.total_proofs_generated, 1);
+        assert_eq!(stats.total_failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_proof_generation_failure() {
+        let mut processor = BatchProcessor::new();
+        let mut prover = BatchProver::new(processor.proving_queue.clone());
+
+        // Configure prover to always fail
+        prover.update_prover_config(MvpProverConfig {
+            generation_delay_ms: 1,
+            simulate_failures: true,
+            failure_rate: 1.0, // Always fail
+            scenarios: Vec::new(),
+        });
+
+        let stats = prover.get_prover_stats().await;
+        assert!(stats.simulate_failures);
+        assert_eq!(stats.failure_rate, 1.0);
+
+        // A failed batch stays queued, and blocks later batches until it is proven
+        let first = processor.start_batch().unwrap();
+        processor.add_order_to_batch(bridge_in("first", "100")).unwrap();
+        processor.finalize_batch().unwrap();
+        let second = processor.start_batch().unwrap();
+        processor.add_order_to_batch(bridge_in("second", "100")).unwrap();
+        processor.finalize_batch().unwrap();
+        let proven = prover.prove_through(second).await.unwrap();
+        assert_eq!(proven.len(), 1);
+        assert_eq!(proven[0].batch_id, first);
+        assert!(!proven[0].result.success);
+        assert_eq!(prover.queue.batch_ids(), vec![first, second]);
+    }
+
+    #[tokio::test]
+    async fn test_next_batch_builds_while_previous_is_proving() {
+        let processor = Arc::new(tokio::sync::Mutex::new(BatchProcessor::new()));
+        let mut prover = BatchProver::new(processor.lock().await.proving_queue.clone());
+        prover.update_prover_config(MvpProverConfig { generation_delay_ms: 200, ..fast_config() });
+
+        let first = {
+            let mut processor = processor.lock().await;
+            let batch_id = processor.start_batch().unwrap();
+            processor.add_order_to_batch(bridge_in("batch_1", "1000")).unwrap();
+            processor.finalize_batch().unwrap();
+            batch_id
+        };
+
+        let proving = tokio::spawn(async move { prover.prove_through(first).await });
+
+        // Batch N+1 takes orders and is finalized while batch N is still being proven
+        let second = {
+            let mut processor = processor.lock().await;
+            let batch_id = processor.start_batch().unwrap();
+            processor.add_order_to_batch(bridge_in("batch_2", "500")).unwrap();
+            processor.finalize_batch().unwrap();
+            batch_id
+        };
+        assert!(!proving.is_finished());
+
+        let proven = proving.await.unwrap().unwrap();
+        assert_eq!(proven.iter().map(|p| p.batch_id).collect::<Vec<_>>(), vec![first]);
+        assert_eq!(processor.lock().await.proving_queue.batch_ids(), vec![second]);
+    }
+
+    #[tokio::test]
+    async fn test_batches_are_proven_in_order() {
+        let mut processor = BatchProcessor::new();
+        let mut prover = BatchProver::new(processor.proving_queue.clone());
+        prover.update_prover_config(fast_config());
+
+        let mut batch_ids = Vec::new();
+        for amount in ["100", "200", "300"] {
+            batch_ids.push(processor.start_batch().unwrap());
+            processor.add_order_to_batch(bridge_in(&format!("order_{}", amount), amount)).unwrap();
+            processor.finalize_batch().unwrap();
+        }
+
+        // Proving a later batch proves the earlier ones first
+        let proven = prover.prove_through(batch_ids[1]).await.unwrap();
+        assert_eq!(proven.iter().map(|p| p.batch_id).collect::<Vec<_>>(), batch_ids[..2].to_vec());
+        assert!(proven.iter().all(|p| p.result.success));
+        assert_eq!(prover.queue.batch_ids(), vec![batch_ids[2]]);
+    }
+
+    #[tokio::test]
+    async fn test_scripted_failures_are_retried() {
+        let mut processor = BatchProcessor::new();
+        let mut prover = BatchProver::new(processor.proving_queue.clone());
+        prover.update_prover_config(MvpProverConfig {
+            scenarios: vec![FailureScenario::FailAttempts { batch_id: None, attempts: 2 }],
+            ..fast_config()
+        });
+
+        let batch_id = processor.start_batch().unwrap();
+        processor.add_order_to_batch(bridge_in("retried", "100")).unwrap();
+        processor.finalize_batch().unwrap();
+
+        // Each retry proves the batch again until the scripted failures run out
+        for _ in 0..2 {
+            let proven = prover.prove_through(batch_id).await.unwrap();
+            assert!(!proven[0].result.success);
+            assert_eq!(prover.queue.batch_ids(), vec![batch_id]);
+        }
+        let proven = prover.prove_through(batch_id).await.unwrap();
+        assert!(proven[0].result.success);
+        assert!(prover.queue.batch_ids().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_proof_is_submitted_for_the_finalized_batch() {
+        let read_cache = ChainReadCache::new(60);
+        read_cache.put(ReadKey::LatestBatchId, ReadValue::BatchId(0));
+        let client = vapor_chain::testing::funded_client(read_cache).await;
+
+        let mut processor = BatchProcessor::new();
+        let mut prover = BatchProver::new(processor.proving_queue.clone()).with_blockchain_client(Arc::new(client));
+        prover.update_prover_config(fast_config());
+        let batch_id = processor.start_batch().unwrap();
+        processor.add_order_to_batch(bridge_in("submitted", "100")).unwrap();
+        processor.finalize_batch().unwrap();
+        let batch = prover.queue.batches.lock().unwrap().front().cloned().unwrap();
+
+        let submission = proof_compression::prepare(batch_id, "", &[0xab; 32], CalldataCompression::None).unwrap();
+        let result = prover.submit_proof_to_blockchain(&submission, &batch).await.unwrap();
+        assert_eq!((batch.batch_id, batch.prev_batch_id), (1, 0));
+        assert_eq!(result.batch_id, batch.batch_id);
+    }
+}
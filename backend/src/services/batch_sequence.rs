@@ -0,0 +1,278 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+use crate::merkle::MerkleTreeManager;
+use crate::services::batch_processor::{BatchResult, ProcessingBatch};
+use crate::services::handover;
+
+/// Why a batch cannot be the next one in the batch sequence
+#[derive(Debug, thiserror::Error)]
+pub enum BatchSequenceError {
+    /// Batches were skipped or repeated
+    #[error("batch {found} is out of sequence, expected batch {expected}")]
+    Gap { expected: u64, found: u64 },
+    /// The batch starts from other roots than the batch it follows finalized to
+    #[error("batch {batch_id} starts from {root} root {found}, but batch {prev_batch_id} finalized to {expected}")]
+    Fork { batch_id: u64, prev_batch_id: u64, root: &'static str, expected: String, found: String },
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for BatchSequenceError {
+    fn from(e: sqlx::Error) -> Self {
+        BatchSequenceError::Other(e.into())
+    }
+}
+
+type Result<T> = std::result::Result<T, BatchSequenceError>;
+
+/// A finalized batch the next one must continue from; batch 0 with the empty roots before the first batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SequenceTip {
+    pub batch_id: u64,
+    pub state_root: String,
+    pub orders_root: String,
+}
+
+impl SequenceTip {
+    pub fn genesis() -> Self {
+        Self {
+            batch_id: 0,
+            state_root: MerkleTreeManager::empty_state_root(),
+            orders_root: MerkleTreeManager::empty_orders_root(),
+        }
+    }
+}
+
+/// Where a batch says it continues from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchLink {
+    pub batch_id: u64,
+    pub prev_batch_id: u64,
+    pub prev_state_root: String,
+    pub prev_orders_root: String,
+}
+
+impl From<&ProcessingBatch> for BatchLink {
+    fn from(batch: &ProcessingBatch) -> Self {
+        Self {
+            batch_id: batch.batch_id,
+            prev_batch_id: batch.prev_batch_id,
+            prev_state_root: batch.prev_state_root.clone(),
+            prev_orders_root: batch.prev_orders_root.clone(),
+        }
+    }
+}
+
+impl From<&BatchResult> for BatchLink {
+    fn from(result: &BatchResult) -> Self {
+        Self {
+            batch_id: result.batch_id,
+            prev_batch_id: result.prev_batch_id,
+            prev_state_root: result.prev_state_root.clone(),
+            prev_orders_root: result.prev_orders_root.clone(),
+        }
+    }
+}
+
+/// A batch as recorded in the sequence
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecordedBatch {
+    pub batch_id: u64,
+    pub prev_batch_id: u64,
+    pub prev_state_root: String,
+    pub prev_orders_root: String,
+    pub state_root: String,
+    pub orders_root: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Check that `link` is the batch directly after `tip` and starts from its roots
+pub fn check_follows(tip: &SequenceTip, link: &BatchLink) -> Result<()> {
+    let expected = tip.batch_id + 1;
+    if link.batch_id != expected || link.prev_batch_id != tip.batch_id {
+        return Err(BatchSequenceError::Gap { expected, found: link.batch_id });
+    }
+    let fork = |root, expected: &str, found: &str| BatchSequenceError::Fork {
+        batch_id: link.batch_id,
+        prev_batch_id: link.prev_batch_id,
+        root,
+        expected: expected.to_string(),
+        found: found.to_string(),
+    };
+    if link.prev_state_root != tip.state_root {
+        return Err(fork("state", &tip.state_root, &link.prev_state_root));
+    }
+    if link.prev_orders_root != tip.orders_root {
+        return Err(fork("orders", &tip.orders_root, &link.prev_orders_root));
+    }
+    Ok(())
+}
+
+/// The batch the next finalized batch must continue from
+///
+/// A database finalized batches before the sequence was recorded continues from its last snapshot.
+pub async fn tip(db: &SqlitePool) -> Result<SequenceTip> {
+    let row = sqlx::query("SELECT batch_id, state_root, orders_root FROM batch_sequence ORDER BY batch_id DESC LIMIT 1")
+        .fetch_optional(db)
+        .await?;
+    if let Some(row) = row {
+        return Ok(SequenceTip {
+            batch_id: row.try_get::<i64, _>("batch_id")? as u64,
+            state_root: row.try_get("state_root")?,
+            orders_root: row.try_get("orders_root")?,
+        });
+    }
+
+    Ok(match handover::latest_batch(db).await? {
+        Some((batch_id, state_root, orders_root)) => SequenceTip { batch_id, state_root, orders_root },
+        None => SequenceTip::genesis(),
+    })
+}
+
+/// Check that `link` may be finalized as the next batch of the sequence
+pub async fn check_next(db: &SqlitePool, link: &BatchLink) -> Result<()> {
+    check_follows(&tip(db).await?, link)
+}
+
+/// Record a just-finalized batch as the next one of the sequence
+pub async fn record(db: &SqlitePool, result: &BatchResult, now: DateTime<Utc>) -> Result<()> {
+    check_next(db, &BatchLink::from(result)).await?;
+
+    // The table's constraints catch a concurrent writer recording the same or another batch
+    sqlx::query(
+        r#"
+        INSERT INTO batch_sequence
+            (batch_id, prev_batch_id, prev_state_root, prev_orders_root, state_root, orders_root, recorded_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#,
+    )
+    .bind(result.batch_id as i64)
+    .bind(result.prev_batch_id as i64)
+    .bind(&result.prev_state_root)
+    .bind(&result.prev_orders_root)
+    .bind(&result.new_state_root)
+    .bind(&result.new_orders_root)
+    .bind(now)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// A recorded batch, if it was finalized since the sequence is recorded
+pub async fn get(db: &SqlitePool, batch_id: u64) -> Result<Option<RecordedBatch>> {
+    let row = sqlx::query("SELECT * FROM batch_sequence WHERE batch_id = ?1")
+        .bind(batch_id as i64)
+        .fetch_optional(db)
+        .await?;
+
+    row.map(|row| {
+        Ok(RecordedBatch {
+            batch_id: row.try_get::<i64, _>("batch_id")? as u64,
+            prev_batch_id: row.try_get::<i64, _>("prev_batch_id")? as u64,
+            prev_state_root: row.try_get("prev_state_root")?,
+            prev_orders_root: row.try_get("prev_orders_root")?,
+            state_root: row.try_get("state_root")?,
+            orders_root: row.try_get("orders_root")?,
+            recorded_at: row.try_get("recorded_at")?,
+        })
+    })
+    .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::run_migrations;
+
+    fn result(batch_id: u64, prev_state_root: &str, new_state_root: &str) -> BatchResult {
+        BatchResult {
+            batch_id,
+            prev_batch_id: batch_id - 1,
+            orders_count: 0,
+            prev_state_root: prev_state_root.to_string(),
+            new_state_root: new_state_root.to_string(),
+            prev_orders_root: MerkleTreeManager::empty_orders_root(),
+            new_orders_root: MerkleTreeManager::empty_orders_root(),
+            ready_for_proof: true,
+            deferred_orders: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sequence_only_grows_by_the_next_batch() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let now = Utc::now();
+        let genesis = MerkleTreeManager::empty_state_root();
+
+        assert_eq!(tip(&db).await.unwrap(), SequenceTip::genesis());
+        record(&db, &result(1, &genesis, "0x01"), now).await.unwrap();
+        record(&db, &result(2, "0x01", "0x02"), now).await.unwrap();
+        assert_eq!(tip(&db).await.unwrap().batch_id, 2);
+        assert_eq!(get(&db, 2).await.unwrap().unwrap().prev_state_root, "0x01");
+
+        // Skipping or repeating a batch is a gap, starting from other roots a fork
+        assert!(matches!(
+            record(&db, &result(4, "0x02", "0x04"), now).await,
+            Err(BatchSequenceError::Gap { expected: 3, found: 4 })
+        ));
+        assert!(matches!(
+            record(&db, &result(2, "0x01", "0x02"), now).await,
+            Err(BatchSequenceError::Gap { expected: 3, found: 2 })
+        ));
+        assert!(matches!(
+            record(&db, &result(3, "0x01", "0x03"), now).await,
+            Err(BatchSequenceError::Fork { root: "state", prev_batch_id: 2, .. })
+        ));
+
+        // The table refuses them on its own too
+        let insert = |batch_id: i64, prev_state_root: &'static str| {
+            sqlx::query(
+                "INSERT INTO batch_sequence VALUES (?1, ?1 - 1, ?2, ?3, '0x09', ?3, CURRENT_TIMESTAMP)",
+            )
+            .bind(batch_id)
+            .bind(prev_state_root)
+            .bind(MerkleTreeManager::empty_orders_root())
+            .execute(&db)
+        };
+        assert!(insert(4, "0x02").await.is_err());
+        assert!(insert(3, "0x01").await.is_err());
+        insert(3, "0x02").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sequence_continues_from_the_last_snapshot() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        sqlx::query("INSERT INTO batch_snapshots (batch_id, state_root, orders_root, data, created_at) VALUES (7, '0x07', ?1, '{}', CURRENT_TIMESTAMP)")
+            .bind(MerkleTreeManager::empty_orders_root())
+            .execute(&db)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            record(&db, &result(1, &MerkleTreeManager::empty_state_root(), "0x01"), Utc::now()).await,
+            Err(BatchSequenceError::Gap { expected: 8, found: 1 })
+        ));
+        record(&db, &result(8, "0x07", "0x08"), Utc::now()).await.unwrap();
+    }
+
+    #[test]
+    fn test_check_follows_genesis() {
+        let link = BatchLink {
+            batch_id: 1,
+            prev_batch_id: 0,
+            prev_state_root: MerkleTreeManager::empty_state_root(),
+            prev_orders_root: MerkleTreeManager::empty_orders_root(),
+        };
+        check_follows(&SequenceTip::genesis(), &link).unwrap();
+
+        let forked = BatchLink { prev_orders_root: "0x01".to_string(), ..link };
+        assert!(matches!(
+            check_follows(&SequenceTip::genesis(), &forked),
+            Err(BatchSequenceError::Fork { root: "orders", .. })
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::de::{self, DeserializeSeed, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use crate::services::system_accounts;
+
+/// One balance to set, from a JSON array element or a CSV line `address,token_id,balance`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BulkAccountEntry {
+    pub address: String,
+    pub token_id: u32,
+    #[serde(alias = "initial_balance")]
+    pub balance: String,
+}
+
+/// A bulk upload that cannot be applied; nothing is written when any entry is rejected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum BulkAccountError {
+    #[error("at most {max} balances can be set at once")]
+    TooManyEntries { max: usize },
+    /// `entry` is the CSV line or JSON array element, from 1
+    #[error("entry {entry}: {message}")]
+    InvalidEntry { entry: usize, message: String },
+    #[error("invalid JSON: {message}")]
+    InvalidJson { message: String },
+    #[error("no balances to set")]
+    Empty,
+}
+
+/// What a bulk initialization changed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BulkAccountSummary {
+    pub balances_set: usize,
+    pub accounts_created: usize,
+    pub accounts_updated: usize,
+    /// Sum of the balances set, per token
+    pub totals: BTreeMap<u32, String>,
+}
+
+/// Parses CSV as it arrives, so an oversized upload is refused without buffering all of it
+///
+/// A first line starting with `address` is taken as a header; blank lines are skipped.
+pub struct CsvParser {
+    max_entries: usize,
+    pending: Vec<u8>,
+    line: usize,
+    entries: Vec<BulkAccountEntry>,
+}
+
+impl CsvParser {
+    pub fn new(max_entries: usize) -> Self {
+        Self { max_entries, pending: Vec::new(), line: 0, entries: Vec::new() }
+    }
+
+    /// Parse every complete line in `chunk`, keeping a trailing partial line for the next one
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), BulkAccountError> {
+        self.pending.extend_from_slice(chunk);
+        while let Some(end) = self.pending.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.parse_line(&line)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<Vec<BulkAccountEntry>, BulkAccountError> {
+        let rest = std::mem::take(&mut self.pending);
+        self.parse_line(&rest)?;
+        Ok(self.entries)
+    }
+
+    fn parse_line(&mut self, raw: &[u8]) -> Result<(), BulkAccountError> {
+        self.line += 1;
+        let invalid = |message: String| BulkAccountError::InvalidEntry { entry: self.line, message };
+        let line = std::str::from_utf8(raw).map_err(|_| invalid("not UTF-8".to_string()))?.trim();
+        if line.is_empty() || (self.line == 1 && line.to_ascii_lowercase().starts_with("address")) {
+            return Ok(());
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [address, token_id, balance] = fields[..] else {
+            return Err(invalid(format!("expected address,token_id,balance, got {} fields", fields.len())));
+        };
+        let token_id = token_id.parse().map_err(|_| invalid(format!("invalid token_id '{}'", token_id)))?;
+
+        if self.entries.len() == self.max_entries {
+            return Err(BulkAccountError::TooManyEntries { max: self.max_entries });
+        }
+        self.entries.push(BulkAccountEntry { address: address.to_string(), token_id, balance: balance.to_string() });
+        Ok(())
+    }
+}
+
+/// Deserializes a JSON array, failing as soon as it holds more than `max_entries` elements
+struct CappedEntries<'a> {
+    max_entries: usize,
+    exceeded: &'a Cell<bool>,
+}
+
+impl<'de> DeserializeSeed<'de> for CappedEntries<'_> {
+    type Value = Vec<BulkAccountEntry>;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for CappedEntries<'_> {
+    type Value = Vec<BulkAccountEntry>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of {address, token_id, balance}")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = seq.next_element()? {
+            if entries.len() == self.max_entries {
+                self.exceeded.set(true);
+                return Err(de::Error::custom("too many entries"));
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
+
+pub fn parse_json(body: &[u8], max_entries: usize) -> Result<Vec<BulkAccountEntry>, BulkAccountError> {
+    let exceeded = Cell::new(false);
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let entries = CappedEntries { max_entries, exceeded: &exceeded }
+        .deserialize(&mut deserializer)
+        .and_then(|entries| deserializer.end().map(|()| entries));
+
+    entries.map_err(|e| match exceeded.get() {
+        true => BulkAccountError::TooManyEntries { max: max_entries },
+        false => BulkAccountError::InvalidJson { message: e.to_string() },
+    })
+}
+
+/// Reject malformed entries and balances set twice, numbering entries from 1
+pub fn validate(entries: &[BulkAccountEntry]) -> Result<(), BulkAccountError> {
+    if entries.is_empty() {
+        return Err(BulkAccountError::Empty);
+    }
+
+    let mut seen = HashSet::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let invalid = |message: String| BulkAccountError::InvalidEntry { entry: index + 1, message };
+        if entry.address.trim().is_empty() {
+            return Err(invalid("address is empty".to_string()));
+        }
+        if let Err(e) = system_accounts::check_user_address(&entry.address) {
+            return Err(invalid(e.to_string()));
+        }
+        if entry.token_id == 0 {
+            return Err(invalid("token_id must be greater than 0".to_string()));
+        }
+        if entry.balance.parse::<u64>().is_err() {
+            return Err(invalid(format!("invalid balance '{}'", entry.balance)));
+        }
+        if !seen.insert((entry.address.to_lowercase(), entry.token_id)) {
+            return Err(invalid(format!("token {} of {} is set twice", entry.token_id, entry.address)));
+        }
+    }
+    Ok(())
+}
+
+/// Write validated balances in one transaction, so a failure leaves the database untouched
+pub async fn store(db: &SqlitePool, entries: &[BulkAccountEntry], now: DateTime<Utc>) -> Result<()> {
+    let mut tx = db.begin().await?;
+    for entry in entries {
+        sqlx::query(
+            r#"
+            INSERT INTO account_balances (address, token_id, balance, updated_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(address, token_id)
+            DO UPDATE SET balance = ?3, updated_at = ?4
+            "#,
+        )
+        .bind(&entry.address)
+        .bind(entry.token_id as i32)
+        .bind(&entry.balance)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(address: &str, token_id: u32, balance: &str) -> BulkAccountEntry {
+        BulkAccountEntry { address: address.to_string(), token_id, balance: balance.to_string() }
+    }
+
+    #[test]
+    fn test_csv_parses_across_chunks() {
+        let mut parser = CsvParser::new(10);
+        parser.push(b"address,token_id,balance\n0xaaaa, 1, 100\n0xbb").unwrap();
+        parser.push(b"bb,2,200\n\n0xcccc,1,300").unwrap();
+        assert_eq!(parser.finish().unwrap(), vec![entry("0xaaaa", 1, "100"), entry("0xbbbb", 2, "200"), entry("0xcccc", 1, "300")]);
+
+        let mut parser = CsvParser::new(10);
+        assert_eq!(
+            parser.push(b"0xaaaa,1,100\n0xbbbb,x,1\n"),
+            Err(BulkAccountError::InvalidEntry { entry: 2, message: "invalid token_id 'x'".to_string() })
+        );
+        assert!(matches!(CsvParser::new(10).push(b"0xaaaa,1\n"), Err(BulkAccountError::InvalidEntry { entry: 1, .. })));
+    }
+
+    #[test]
+    fn test_uploads_are_capped() {
+        let mut parser = CsvParser::new(2);
+        assert_eq!(parser.push(b"0xa,1,1\n0xb,1,1\n0xc,1,1\n"), Err(BulkAccountError::TooManyEntries { max: 2 }));
+
+        let json = br#"[{"address":"0xa","token_id":1,"balance":"1"},{"address":"0xb","token_id":1,"initial_balance":"2"}]"#;
+        assert_eq!(parse_json(json, 2).unwrap()[1], entry("0xb", 1, "2"));
+        assert_eq!(parse_json(json, 1), Err(BulkAccountError::TooManyEntries { max: 1 }));
+        assert!(matches!(parse_json(b"[{\"address\":1}]", 5), Err(BulkAccountError::InvalidJson { .. })));
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_and_duplicate_entries() {
+        assert!(validate(&[entry("0xa", 1, "1"), entry("0xa", 2, "1")]).is_ok());
+        assert_eq!(validate(&[]), Err(BulkAccountError::Empty));
+        assert!(matches!(validate(&[entry("0xa", 1, "1"), entry("0xA", 1, "2")]), Err(BulkAccountError::InvalidEntry { entry: 2, .. })));
+        assert!(matches!(validate(&[entry("0xa", 0, "1")]), Err(BulkAccountError::InvalidEntry { entry: 1, .. })));
+        assert!(matches!(validate(&[entry("0xa", 1, "-5")]), Err(BulkAccountError::InvalidEntry { entry: 1, .. })));
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+use vapor_chain::ChainIdentity;
+
+/// Last block the relayer applied, with the chain it was read from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RelayerCheckpoint {
+    pub chain_id: u64,
+    pub genesis_hash: String,
+    pub block_number: u64,
+    /// Hash of `block_number` when it was scanned, to catch a fork after the genesis block
+    pub block_hash: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Why a stored checkpoint cannot be resumed on the current chain
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CheckpointMismatch {
+    #[error("checkpoint is for chain id {stored}, but the RPC serves chain id {current}")]
+    ChainId { stored: u64, current: u64 },
+    #[error("checkpoint is for genesis block {stored}, but the chain's genesis block is {current}")]
+    Genesis { stored: String, current: String },
+    #[error("checkpoint block {block_number} was {stored}, but the chain has {current}")]
+    Block { block_number: u64, stored: String, current: String },
+}
+
+impl RelayerCheckpoint {
+    /// Check that the checkpoint was taken on `current`, whose block at the checkpoint height
+    /// has hash `current_block_hash` (None if the chain is not that long)
+    pub fn verify(&self, current: &ChainIdentity, current_block_hash: Option<&str>) -> Result<(), CheckpointMismatch> {
+        if self.chain_id != current.chain_id {
+            return Err(CheckpointMismatch::ChainId { stored: self.chain_id, current: current.chain_id });
+        }
+        if self.genesis_hash != current.genesis_hash {
+            return Err(CheckpointMismatch::Genesis { stored: self.genesis_hash.clone(), current: current.genesis_hash.clone() });
+        }
+        if current_block_hash != Some(self.block_hash.as_str()) {
+            return Err(CheckpointMismatch::Block {
+                block_number: self.block_number,
+                stored: self.block_hash.clone(),
+                current: current_block_hash.unwrap_or("no block").to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+pub async fn load(db: &SqlitePool) -> Result<Option<RelayerCheckpoint>> {
+    let row = sqlx::query(
+        "SELECT chain_id, genesis_hash, block_number, block_hash, updated_at FROM relayer_checkpoint WHERE id = 1"
+    )
+    .fetch_optional(db)
+    .await?;
+
+    row.map(|row| {
+        Ok(RelayerCheckpoint {
+            chain_id: row.try_get::<i64, _>("chain_id")? as u64,
+            genesis_hash: row.try_get("genesis_hash")?,
+            block_number: row.try_get::<i64, _>("block_number")? as u64,
+            block_hash: row.try_get("block_hash")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    })
+    .transpose()
+}
+
+pub async fn save(db: &SqlitePool, chain: &ChainIdentity, block_number: u64, block_hash: &str) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO relayer_checkpoint (id, chain_id, genesis_hash, block_number, block_hash, updated_at)
+        VALUES (1, ?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT(id) DO UPDATE SET
+            chain_id = ?1, genesis_hash = ?2, block_number = ?3, block_hash = ?4, updated_at = ?5
+        "#,
+    )
+    .bind(chain.chain_id as i64)
+    .bind(&chain.genesis_hash)
+    .bind(block_number as i64)
+    .bind(block_hash)
+    .bind(Utc::now())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn clear(db: &SqlitePool) -> Result<()> {
+    sqlx::query("DELETE FROM relayer_checkpoint").execute(db).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(chain_id: u64, genesis: &str) -> ChainIdentity {
+        ChainIdentity { chain_id, genesis_hash: genesis.to_string() }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_roundtrip() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        assert!(load(&db).await.unwrap().is_none());
+
+        save(&db, &chain(31337, "0xaa"), 100, "0x100").await.unwrap();
+        save(&db, &chain(31337, "0xaa"), 250, "0x250").await.unwrap();
+        let checkpoint = load(&db).await.unwrap().unwrap();
+        assert_eq!((checkpoint.block_number, checkpoint.block_hash.as_str()), (250, "0x250"));
+
+        clear(&db).await.unwrap();
+        assert!(load(&db).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_verify_detects_other_chains() {
+        let checkpoint = RelayerCheckpoint {
+            chain_id: 31337,
+            genesis_hash: "0xaa".to_string(),
+            block_number: 250,
+            block_hash: "0x250".to_string(),
+            updated_at: Utc::now(),
+        };
+
+        assert_eq!(checkpoint.verify(&chain(31337, "0xaa"), Some("0x250")), Ok(()));
+        assert!(matches!(checkpoint.verify(&chain(11155111, "0xaa"), Some("0x250")), Err(CheckpointMismatch::ChainId { .. })));
+        // Anvil restarted: same chain id, new genesis
+        assert!(matches!(checkpoint.verify(&chain(31337, "0xbb"), Some("0x250")), Err(CheckpointMismatch::Genesis { .. })));
+        // Forked after genesis, or reset to a shorter chain
+        assert!(matches!(checkpoint.verify(&chain(31337, "0xaa"), Some("0x999")), Err(CheckpointMismatch::Block { .. })));
+        assert!(matches!(checkpoint.verify(&chain(31337, "0xaa"), None), Err(CheckpointMismatch::Block { .. })));
+    }
+}
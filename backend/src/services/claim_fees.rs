@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::ClaimConfig;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClaimFeeError {
+    #[error("Claim fee {fee} is not covered by the claimed amount ({gross})")]
+    FeeExceedsAmount { gross: u64, fee: u64 },
+    #[error("Invalid claim amount: {0}")]
+    InvalidAmount(String),
+}
+
+/// What a claim is charged and what is left to transfer, all in the earned token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimFee {
+    pub gross_amount: String,
+    /// `fee_bps` of the gross amount, rounded down
+    pub protocol_fee: String,
+    pub gas_surcharge: String,
+    /// Share of a relayed claim transaction's fee; only the largest claim of a request pays it
+    pub relay_fee: String,
+    /// Everything deducted from the gross amount
+    pub fee_amount: String,
+    /// Transferred on-chain, before any payout conversion
+    pub net_amount: String,
+}
+
+impl ClaimFee {
+    pub fn net(&self) -> u64 {
+        self.net_amount.parse().unwrap_or(0)
+    }
+
+    pub fn fee(&self) -> u64 {
+        self.fee_amount.parse().unwrap_or(0)
+    }
+
+    /// Add a relay fee already taken out of this claim's net amount
+    pub fn charge_relay_fee(&mut self, relay_fee: u64) {
+        let net = self.net().saturating_sub(relay_fee);
+        self.relay_fee = relay_fee.to_string();
+        self.fee_amount = (self.fee() + relay_fee).to_string();
+        self.net_amount = net.to_string();
+    }
+}
+
+/// Fee of a claim of `gross`: the protocol fee plus the fixed gas surcharge
+///
+/// A claim whose fee would leave nothing to transfer is refused; without fees configured the
+/// whole amount is transferred.
+pub fn compute(config: &ClaimConfig, gross: u64) -> Result<ClaimFee, ClaimFeeError> {
+    let protocol_fee = (gross as u128 * config.fee_bps as u128 / 10_000) as u64;
+    let fee = protocol_fee.saturating_add(config.fee_gas_surcharge);
+    if fee > 0 && fee >= gross {
+        return Err(ClaimFeeError::FeeExceedsAmount { gross, fee });
+    }
+
+    Ok(ClaimFee {
+        gross_amount: gross.to_string(),
+        protocol_fee: protocol_fee.to_string(),
+        gas_surcharge: config.fee_gas_surcharge.to_string(),
+        relay_fee: "0".to_string(),
+        fee_amount: fee.to_string(),
+        net_amount: (gross - fee).to_string(),
+    })
+}
+
+/// Fees of several claims given as decimal strings
+pub fn compute_all(config: &ClaimConfig, amounts: &[&str]) -> Result<Vec<ClaimFee>, ClaimFeeError> {
+    amounts.iter()
+        .map(|amount| {
+            let gross = amount.parse::<u64>().map_err(|_| ClaimFeeError::InvalidAmount(amount.to_string()))?;
+            compute(config, gross)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_is_bps_of_gross_plus_surcharge() {
+        let config = ClaimConfig { fee_bps: 25, fee_gas_surcharge: 50_000, ..ClaimConfig::default() };
+
+        // 0.25% of 10 USDC is 0.025, plus the 0.05 surcharge
+        let fee = compute(&config, 10_000_000).unwrap();
+        assert_eq!(fee.protocol_fee, "25000");
+        assert_eq!(fee.fee_amount, "75000");
+        assert_eq!(fee.net_amount, "9925000");
+
+        // Rounded down in the filler's favour
+        assert_eq!(compute(&config, 50_399).unwrap().protocol_fee, "125");
+
+        let err = compute(&config, 50_000).unwrap_err();
+        assert!(matches!(err, ClaimFeeError::FeeExceedsAmount { gross: 50_000, fee: 50_125 }));
+        assert!(matches!(compute_all(&config, &["10", "abc"]).unwrap_err(), ClaimFeeError::FeeExceedsAmount { .. }));
+        assert!(matches!(compute_all(&ClaimConfig::default(), &["abc"]).unwrap_err(), ClaimFeeError::InvalidAmount(_)));
+    }
+
+    #[test]
+    fn test_relay_fee_adds_to_the_fee() {
+        let config = ClaimConfig { fee_bps: 100, ..ClaimConfig::default() };
+        let mut fee = compute(&config, 1_000_000).unwrap();
+        fee.charge_relay_fee(200_000);
+        assert_eq!(fee.relay_fee, "200000");
+        assert_eq!(fee.fee_amount, "210000");
+        assert_eq!(fee.net_amount, "790000");
+
+        // Without fees configured the whole claim is transferred
+        assert_eq!(compute(&ClaimConfig::default(), 1).unwrap().net(), 1);
+        assert_eq!(compute(&ClaimConfig::default(), 0).unwrap().fee(), 0);
+    }
+}
@@ -0,0 +1,329 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use tracing::warn;
+
+use vapor_chain::BlockchainClient;
+use crate::config::ClaimConfig;
+use crate::services::rates::{RateError, RateService};
+
+/// Decimals of the token fillers earn (USDC), in which relay fees are charged
+const EARNED_TOKEN_DECIMALS: u32 = 6;
+const WEI_PER_ETH: u128 = 1_000_000_000_000_000_000;
+const WEI_PER_GWEI: u128 = 1_000_000_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RelayError {
+    #[error("Claim relaying is not offered by this backend")]
+    Unavailable,
+    #[error("Relay fee {fee} is not covered by the largest claim ({largest_claim})")]
+    FeeExceedsClaim { fee: u64, largest_claim: u64 },
+    #[error("Relay fee {fee} is above the filler's maximum of {max_fee}")]
+    FeeAboveMaximum { fee: u64, max_fee: u64 },
+    #[error(transparent)]
+    Rate(#[from] RateError),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// A filler's opt-in to having its claims relayed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelaySettings {
+    pub enabled: bool,
+    /// Highest fee the filler accepts per relayed claim transaction, in the earned token
+    #[serde(default)]
+    pub max_fee: Option<String>,
+}
+
+impl RelaySettings {
+    /// Refuse a fee above the filler's maximum, rather than silently charging it
+    pub fn check_fee(&self, fee: u64) -> Result<(), RelayError> {
+        match self.max_fee.as_deref().and_then(|max| max.parse::<u64>().ok()) {
+            Some(max_fee) if fee > max_fee => Err(RelayError::FeeAboveMaximum { fee, max_fee }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Estimated gas of one relayed claim transaction and the fee charged for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayQuote {
+    pub claims: usize,
+    pub estimated_gas: u64,
+    pub gas_price_wei: String,
+    pub gas_cost_wei: String,
+    /// Charged in the earned token, out of the largest claim
+    pub fee_amount: String,
+    pub fee_token_id: u32,
+}
+
+impl RelayQuote {
+    pub fn fee(&self) -> u64 {
+        self.fee_amount.parse().unwrap_or(0)
+    }
+}
+
+/// Price a relayed claim: estimated gas at `gas_price_wei`, converted to the earned token
+/// through the ETH price and the token's rate, plus the configured markup
+pub fn price(config: &ClaimConfig, rates: &RateService, token_id: u32, claims: usize, gas_price_wei: u128) -> Result<RelayQuote, RelayError> {
+    let estimated_gas = config.relay_base_gas + config.relay_gas_per_claim * claims as u64;
+    let gas_cost_wei = estimated_gas as u128 * gas_price_wei;
+
+    let token_price = rates.usd_price(token_id)? as u128;
+    let micro_usd = gas_cost_wei
+        .checked_mul(config.relay_eth_usd_price as u128)
+        .map(|cost| cost / WEI_PER_ETH)
+        .ok_or(RateError::Overflow)?;
+    let with_markup = micro_usd * (10_000 + config.relay_fee_markup_bps as u128) / 10_000;
+    // Round up so relaying never costs the operator more than it charges
+    let fee = (with_markup * 10u128.pow(EARNED_TOKEN_DECIMALS)).div_ceil(token_price);
+
+    Ok(RelayQuote {
+        claims,
+        estimated_gas,
+        gas_price_wei: gas_price_wei.to_string(),
+        gas_cost_wei: gas_cost_wei.to_string(),
+        fee_amount: u64::try_from(fee).map_err(|_| RateError::Overflow)?.to_string(),
+        fee_token_id: token_id,
+    })
+}
+
+/// Quote a relayed claim at the network's gas price, or the configured one without a client
+pub async fn quote(
+    config: &ClaimConfig,
+    rates: &RateService,
+    chain: Option<&BlockchainClient>,
+    token_id: u32,
+    claims: usize,
+) -> Result<RelayQuote, RelayError> {
+    let configured = config.relay_gas_price_gwei as u128 * WEI_PER_GWEI;
+    let gas_price_wei = match chain {
+        Some(client) => match client.get_gas_price().await {
+            Ok(gas_price) => gas_price.as_u128(),
+            Err(e) => {
+                warn!("Using configured gas price for claim relay, network price unavailable: {}", e);
+                configured
+            }
+        },
+        None => configured,
+    };
+    price(config, rates, token_id, claims, gas_price_wei)
+}
+
+/// Take the fee out of the largest claim; returns the index of the claim charged
+pub fn deduct_fee(amounts: &mut [u64], fee: u64) -> Result<usize, RelayError> {
+    let (index, largest) = amounts.iter()
+        .copied()
+        .enumerate()
+        .fold(None, |best: Option<(usize, u64)>, (index, amount)| match best {
+            Some((_, largest)) if largest >= amount => best,
+            _ => Some((index, amount)),
+        })
+        .unwrap_or((0, 0));
+    if largest <= fee {
+        return Err(RelayError::FeeExceedsClaim { fee, largest_claim: largest });
+    }
+    amounts[index] -= fee;
+    Ok(index)
+}
+
+pub async fn load_settings(db: &SqlitePool, filler_id: &str) -> Result<Option<RelaySettings>> {
+    let row = sqlx::query("SELECT enabled, max_fee FROM filler_relay_settings WHERE filler_id = ?")
+        .bind(filler_id)
+        .fetch_optional(db)
+        .await?;
+
+    row.map(|row| Ok(RelaySettings { enabled: row.try_get("enabled")?, max_fee: row.try_get("max_fee")? }))
+        .transpose()
+}
+
+pub async fn save_settings(db: &SqlitePool, filler_id: &str, settings: &RelaySettings) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO filler_relay_settings (filler_id, enabled, max_fee, updated_at)
+        VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT(filler_id) DO UPDATE SET enabled = ?2, max_fee = ?3, updated_at = ?4
+        "#,
+    )
+    .bind(filler_id)
+    .bind(settings.enabled)
+    .bind(&settings.max_fee)
+    .bind(Utc::now())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// One claim transaction the backend submitted for a filler
+#[derive(Debug, Clone, Serialize)]
+pub struct RelayRecord {
+    pub id: String,
+    pub transaction_hash: Option<String>,
+    pub claim_ids: Vec<String>,
+    pub gas_used: String,
+    pub gas_price_wei: String,
+    pub gas_cost_wei: String,
+    pub fee_amount: String,
+    pub fee_token_id: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Record a relayed claim transaction; gas used is the estimate until receipts are tracked
+pub async fn record(
+    db: &SqlitePool,
+    filler_id: &str,
+    transaction_hash: Option<&str>,
+    quote: &RelayQuote,
+    claim_ids: &[String],
+) -> Result<RelayRecord> {
+    let relay = RelayRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        transaction_hash: transaction_hash.map(str::to_string),
+        claim_ids: claim_ids.to_vec(),
+        gas_used: quote.estimated_gas.to_string(),
+        gas_price_wei: quote.gas_price_wei.clone(),
+        gas_cost_wei: quote.gas_cost_wei.clone(),
+        fee_amount: quote.fee_amount.clone(),
+        fee_token_id: quote.fee_token_id,
+        created_at: Utc::now(),
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO claim_relays (id, filler_id, transaction_hash, claim_ids, gas_used, gas_price_wei, gas_cost_wei, fee_amount, fee_token_id, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+        "#,
+    )
+    .bind(&relay.id)
+    .bind(filler_id)
+    .bind(&relay.transaction_hash)
+    .bind(serde_json::to_string(&relay.claim_ids)?)
+    .bind(&relay.gas_used)
+    .bind(&relay.gas_price_wei)
+    .bind(&relay.gas_cost_wei)
+    .bind(&relay.fee_amount)
+    .bind(relay.fee_token_id as i64)
+    .bind(relay.created_at)
+    .execute(db)
+    .await?;
+    Ok(relay)
+}
+
+/// A filler's relay settings with the gas spent and fees charged on its behalf
+#[derive(Debug, Clone, Serialize)]
+pub struct RelayAccounting {
+    pub filler_id: String,
+    /// Whether this backend relays claims at all
+    pub relay_available: bool,
+    pub settings: RelaySettings,
+    pub transactions: u64,
+    pub claims_relayed: u64,
+    pub gas_used: String,
+    pub gas_cost_wei: String,
+    pub fees_charged: String,
+    /// Most recent relayed transactions first
+    pub relays: Vec<RelayRecord>,
+}
+
+pub async fn accounting(db: &SqlitePool, config: &ClaimConfig, filler_id: &str) -> Result<RelayAccounting> {
+    let settings = load_settings(db, filler_id).await?
+        .unwrap_or(RelaySettings { enabled: false, max_fee: None });
+    let rows = sqlx::query("SELECT * FROM claim_relays WHERE filler_id = ? ORDER BY created_at DESC, id")
+        .bind(filler_id)
+        .fetch_all(db)
+        .await?;
+
+    let relays = rows.iter()
+        .map(|row| {
+            Ok(RelayRecord {
+                id: row.try_get("id")?,
+                transaction_hash: row.try_get("transaction_hash")?,
+                claim_ids: serde_json::from_str(&row.try_get::<String, _>("claim_ids")?)?,
+                gas_used: row.try_get("gas_used")?,
+                gas_price_wei: row.try_get("gas_price_wei")?,
+                gas_cost_wei: row.try_get("gas_cost_wei")?,
+                fee_amount: row.try_get("fee_amount")?,
+                fee_token_id: row.try_get::<i64, _>("fee_token_id")? as u32,
+                created_at: row.try_get("created_at")?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let sum = |field: fn(&RelayRecord) -> &str| -> String {
+        relays.iter().map(|relay| field(relay).parse::<u128>().unwrap_or(0)).sum::<u128>().to_string()
+    };
+    Ok(RelayAccounting {
+        filler_id: filler_id.to_string(),
+        relay_available: config.relay_enabled,
+        settings,
+        transactions: relays.len() as u64,
+        claims_relayed: relays.iter().map(|relay| relay.claim_ids.len() as u64).sum(),
+        gas_used: sum(|relay| &relay.gas_used),
+        gas_cost_wei: sum(|relay| &relay.gas_cost_wei),
+        fees_charged: sum(|relay| &relay.fee_amount),
+        relays,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RateConfig;
+
+    #[test]
+    fn test_fee_covers_gas_cost_with_markup() {
+        let config = ClaimConfig::default();
+        let rates = RateService::new(&RateConfig::default());
+
+        // Two claims: 150k gas at 20 gwei is 0.003 ETH, $9 at $3000, $9.90 with the 10% markup
+        let quote = price(&config, &rates, 1, 2, 20 * WEI_PER_GWEI).unwrap();
+        assert_eq!(quote.estimated_gas, 150_000);
+        assert_eq!(quote.gas_cost_wei, "3000000000000000");
+        assert_eq!(quote.fee_amount, "9900000");
+
+        rates.set_usd_price(1, 500_000);
+        assert_eq!(price(&config, &rates, 1, 2, 20 * WEI_PER_GWEI).unwrap().fee(), 19_800_000);
+        assert!(matches!(price(&config, &rates, 99, 1, 1).unwrap_err(), RelayError::Rate(RateError::UnknownToken(99))));
+    }
+
+    #[test]
+    fn test_fee_is_deducted_from_the_largest_claim() {
+        let mut amounts = vec![5_000_000, 20_000_000, 20_000_000];
+        assert_eq!(deduct_fee(&mut amounts, 9_900_000).unwrap(), 1);
+        assert_eq!(amounts, vec![5_000_000, 10_100_000, 20_000_000]);
+
+        let err = deduct_fee(&mut [1_000, 2_000], 2_000).unwrap_err();
+        assert!(matches!(err, RelayError::FeeExceedsClaim { fee: 2_000, largest_claim: 2_000 }));
+
+        let settings = RelaySettings { enabled: true, max_fee: Some("5000000".to_string()) };
+        assert!(settings.check_fee(5_000_000).is_ok());
+        assert!(matches!(settings.check_fee(9_900_000), Err(RelayError::FeeAboveMaximum { max_fee: 5_000_000, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_accounting_totals_relayed_claims() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let config = ClaimConfig { relay_enabled: true, ..ClaimConfig::default() };
+        let rates = RateService::new(&RateConfig::default());
+
+        let settings = RelaySettings { enabled: true, max_fee: None };
+        save_settings(&db, "filler_1", &settings).await.unwrap();
+        let quote = price(&config, &rates, 1, 2, 20 * WEI_PER_GWEI).unwrap();
+        record(&db, "filler_1", Some("0xabc"), &quote, &["c1".to_string(), "c2".to_string()]).await.unwrap();
+        record(&db, "filler_1", None, &quote, &["c3".to_string(), "c4".to_string()]).await.unwrap();
+        record(&db, "filler_2", None, &quote, &["c5".to_string()]).await.unwrap();
+
+        let report = accounting(&db, &config, "filler_1").await.unwrap();
+        assert!(report.relay_available);
+        assert_eq!(report.settings, settings);
+        assert_eq!((report.transactions, report.claims_relayed), (2, 4));
+        assert_eq!(report.gas_used, "300000");
+        assert_eq!(report.gas_cost_wei, "6000000000000000");
+        assert_eq!(report.fees_charged, "19800000");
+
+        let report = accounting(&db, &config, "filler_3").await.unwrap();
+        assert!(!report.settings.enabled);
+        assert_eq!(report.transactions, 0);
+    }
+}
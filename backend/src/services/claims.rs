@@ -0,0 +1,277 @@
+use vapor_chain::{BlockchainClient, ChainError, ClaimEvent};
+use crate::database::helpers;
+use crate::models::ClaimRecord;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+use web3::types::U256;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClaimError {
+    #[error("Claims must reference an on-chain order with both batch_id and order_id, or neither")]
+    IncompleteReference,
+    #[error("Order {0} is claimed more than once in the request")]
+    DuplicateInRequest(u32),
+    #[error("Orders already claimed: {0:?}")]
+    AlreadyClaimed(Vec<u32>),
+    #[error(transparent)]
+    Chain(#[from] ChainError),
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// Record a set of claims, refusing any that redeem an order claimed before
+///
+/// Orders are checked against the claims table and, when a client is configured, the bridge
+/// contract; a contract that cannot be reached rejects the claims rather than risk a double
+/// payout. The claims are stored in one transaction, and the unique index on `order_id` catches
+/// a concurrent request that passed the same checks.
+pub async fn consume(db: &SqlitePool, chain: Option<&BlockchainClient>, claims: &[ClaimRecord]) -> Result<(), ClaimError> {
+    let mut order_ids = Vec::new();
+    for claim in claims {
+        match (claim.batch_id, claim.order_id) {
+            (Some(_), Some(order_id)) => order_ids.push(order_id),
+            (None, None) => {}
+            _ => return Err(ClaimError::IncompleteReference),
+        }
+    }
+    let mut seen = HashSet::new();
+    if let Some(&duplicate) = order_ids.iter().find(|&&order_id| !seen.insert(order_id)) {
+        return Err(ClaimError::DuplicateInRequest(duplicate));
+    }
+
+    let claimed = helpers::claimed_order_ids(db, &order_ids).await?;
+    if !claimed.is_empty() {
+        return Err(ClaimError::AlreadyClaimed(claimed));
+    }
+
+    if let Some(chain) = chain {
+        let mut claimed = Vec::new();
+        for &order_id in &order_ids {
+            if chain.is_order_claimed(order_id).await? {
+                claimed.push(order_id);
+            }
+        }
+        if !claimed.is_empty() {
+            return Err(ClaimError::AlreadyClaimed(claimed));
+        }
+    }
+
+    if let Err(e) = helpers::insert_claims(db, claims).await {
+        if is_unique_violation(&e) {
+            return Err(ClaimError::AlreadyClaimed(helpers::claimed_order_ids(db, &order_ids).await?));
+        }
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+fn is_unique_violation(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<sqlx::Error>()
+        .and_then(|e| e.as_database_error())
+        .is_some_and(|e| e.is_unique_violation())
+}
+
+/// Outcome of comparing on-chain ClaimEvents with the claims table
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReconcileReport {
+    pub from_block: u64,
+    pub to_block: u64,
+    pub scanned_events: usize,
+    /// Claims now marked confirmed with their transaction hash
+    pub confirmed: usize,
+    /// Orders claimed on-chain with no local claim row
+    pub missing_locally: Vec<u32>,
+    /// Orders whose local claim disagrees with the event on batch or amount
+    pub mismatched: Vec<u32>,
+    pub checked_at: Option<DateTime<Utc>>,
+}
+
+impl ReconcileReport {
+    pub fn has_drift(&self) -> bool {
+        !self.missing_locally.is_empty() || !self.mismatched.is_empty()
+    }
+}
+
+/// Match ClaimEvents to local claims, confirming the ones that agree and reporting the rest
+pub async fn reconcile_events(db: &SqlitePool, events: &[ClaimEvent]) -> anyhow::Result<ReconcileReport> {
+    let mut report = ReconcileReport {
+        scanned_events: events.len(),
+        checked_at: Some(Utc::now()),
+        ..Default::default()
+    };
+
+    for event in events {
+        let Some(claim) = helpers::get_claim_by_order(db, event.order_id).await? else {
+            warn!("Order {} was claimed on-chain in batch {} but has no local claim", event.order_id, event.batch_id);
+            report.missing_locally.push(event.order_id);
+            continue;
+        };
+
+        let amount = U256::from_dec_str(&claim.payout_amount).ok();
+        if claim.batch_id != Some(event.batch_id) || amount != Some(event.amount) {
+            warn!(
+                "Claim {} of order {} records batch {:?} and amount {}, chain has batch {} and amount {}",
+                claim.id, event.order_id, claim.batch_id, claim.payout_amount, event.batch_id, event.amount
+            );
+            report.mismatched.push(event.order_id);
+            continue;
+        }
+
+        helpers::confirm_claim(db, &claim.id, &format!("{:?}", event.transaction_hash)).await?;
+        report.confirmed += 1;
+    }
+
+    Ok(report)
+}
+
+struct ReconcilerState {
+    next_block: u64,
+    last_report: Option<ReconcileReport>,
+}
+
+/// Periodic claim reconciliation, resuming after the last block it scanned
+///
+/// Progress is kept in memory, so a restart scans again from the configured start block;
+/// confirming a claim twice is harmless.
+#[derive(Clone)]
+pub struct ClaimReconciler {
+    state: Arc<Mutex<ReconcilerState>>,
+}
+
+impl ClaimReconciler {
+    pub fn new(from_block: u64) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ReconcilerState { next_block: from_block, last_report: None })),
+        }
+    }
+
+    pub fn last_report(&self) -> Option<ReconcileReport> {
+        self.state.lock().unwrap().last_report.clone()
+    }
+
+    /// Reconcile the claim events of the blocks mined since the previous run
+    pub async fn run_once(&self, db: &SqlitePool, chain: &BlockchainClient) -> anyhow::Result<ReconcileReport> {
+        let from_block = self.state.lock().unwrap().next_block;
+        let to_block = chain.get_block_number().await?;
+        if to_block < from_block {
+            return Ok(self.last_report().unwrap_or_default());
+        }
+
+        let events = chain.get_claim_events(from_block, Some(to_block)).await?;
+        let mut report = reconcile_events(db, &events).await?;
+        report.from_block = from_block;
+        report.to_block = to_block;
+        if !report.has_drift() {
+            info!("Reconciled {} claim events in blocks {}-{}", report.scanned_events, from_block, to_block);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.next_block = to_block + 1;
+        state.last_report = Some(report.clone());
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use web3::types::{Address, H256};
+
+    async fn setup_db() -> SqlitePool {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        db
+    }
+
+    fn claim(id: &str, reference: Option<(u64, u32)>) -> ClaimRecord {
+        ClaimRecord {
+            id: id.to_string(),
+            filler_id: "filler_1".to_string(),
+            wallet_address: "0x0000000000000000000000000000000000000000".to_string(),
+            destination_address: "0x1234567890123456789012345678901234567890".to_string(),
+            amount: "100".to_string(),
+            fee_amount: "0".to_string(),
+            net_amount: "100".to_string(),
+            token_id: 1,
+            payout_token_id: 1,
+            payout_amount: "100".to_string(),
+            conversion: None,
+            batch_id: reference.map(|(batch_id, _)| batch_id),
+            order_id: reference.map(|(_, order_id)| order_id),
+            status: "pending".to_string(),
+            transaction_hash: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn event(batch_id: u64, order_id: u32, amount: u64) -> ClaimEvent {
+        ClaimEvent {
+            user: Address::zero(),
+            batch_id,
+            order_id,
+            amount: U256::from(amount),
+            block_number: 10,
+            transaction_hash: H256::from_low_u64_be(order_id as u64),
+        }
+    }
+
+    async fn claim_count(db: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM claims").fetch_one(db).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_orders_are_claimed_once_across_batches() {
+        let db = setup_db().await;
+        consume(&db, None, &[claim("c1", Some((1, 7))), claim("c2", None)]).await.unwrap();
+
+        // The contract tracks claimed orders globally, so another batch does not make it new
+        let err = consume(&db, None, &[claim("c3", Some((2, 7)))]).await.unwrap_err();
+        assert!(matches!(err, ClaimError::AlreadyClaimed(ref ids) if ids == &vec![7]));
+
+        // Unreferenced claims create fresh orders and never conflict
+        consume(&db, None, &[claim("c4", None)]).await.unwrap();
+        assert_eq!(claim_count(&db).await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_requests_store_nothing() {
+        let db = setup_db().await;
+        consume(&db, None, &[claim("c1", Some((1, 7)))]).await.unwrap();
+
+        let err = consume(&db, None, &[claim("c2", Some((1, 8))), claim("c3", Some((1, 8)))]).await.unwrap_err();
+        assert!(matches!(err, ClaimError::DuplicateInRequest(8)));
+
+        let mut incomplete = claim("c4", Some((1, 9)));
+        incomplete.batch_id = None;
+        let err = consume(&db, None, &[incomplete]).await.unwrap_err();
+        assert!(matches!(err, ClaimError::IncompleteReference));
+
+        // A conflict reaching the insert rolls back the whole request
+        let err = helpers::insert_claims(&db, &[claim("c5", Some((1, 10))), claim("c6", Some((1, 7)))]).await.unwrap_err();
+        assert!(is_unique_violation(&err));
+        assert_eq!(claim_count(&db).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_confirms_matches_and_reports_drift() {
+        let db = setup_db().await;
+        consume(&db, None, &[claim("c1", Some((1, 7))), claim("c2", Some((1, 8)))]).await.unwrap();
+
+        let report = reconcile_events(&db, &[event(1, 7, 100), event(1, 8, 999), event(2, 9, 100)]).await.unwrap();
+        assert_eq!(report.scanned_events, 3);
+        assert_eq!(report.confirmed, 1);
+        assert_eq!(report.mismatched, vec![8]);
+        assert_eq!(report.missing_locally, vec![9]);
+        assert!(report.has_drift());
+
+        let confirmed = helpers::get_claim(&db, "c1").await.unwrap().unwrap();
+        assert_eq!(confirmed.status, "confirmed");
+        assert_eq!(confirmed.transaction_hash, Some(format!("{:?}", H256::from_low_u64_be(7))));
+        let drifted = helpers::get_claim(&db, "c2").await.unwrap().unwrap();
+        assert_eq!(drifted.status, "pending");
+    }
+}
@@ -0,0 +1,391 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use tracing::{info, warn};
+use web3::types::{Address, U256};
+
+use vapor_chain::{hex_to_address, BlockchainClient, DepositEvent};
+use crate::config::CollateralConfig;
+use crate::models::{OrderStatus, OrderType};
+use vapor_core::services::clock::SharedClock;
+
+/// A lock refused for want of collateral
+#[derive(Debug, Serialize, thiserror::Error)]
+#[serde(tag = "collateral", rename_all = "snake_case")]
+pub enum CollateralError {
+    #[error("filler {filler_id} needs {required} collateral for its open locks, has {available}")]
+    Insufficient { filler_id: String, required: String, available: String },
+    #[error("filler {filler_id} is flagged after {expiries} expired locks and may only lock up to {lock_threshold}")]
+    Flagged { filler_id: String, expiries: u32, lock_threshold: u64 },
+    #[error(transparent)]
+    #[serde(skip)]
+    Other(#[from] anyhow::Error),
+}
+
+/// A filler's collateral against what its open locks require
+#[derive(Debug, Clone, Serialize)]
+pub struct CollateralStatus {
+    pub filler_id: String,
+    /// Deposited to the bridge from the filler's registered wallets, less what they withdrew
+    pub deposited: String,
+    pub slashed: String,
+    pub available: String,
+    /// Required by the filler's open locks at their corridors' ratios
+    pub required: String,
+    pub open_locks: u32,
+    pub expiries: u32,
+    pub flagged: bool,
+    pub flagged_at: Option<DateTime<Utc>>,
+    /// When `deposited` was last read from the chain
+    pub refreshed_at: Option<DateTime<Utc>>,
+}
+
+/// Sum of the deposits made from any of `wallets`
+pub fn deposited_by(events: &[DepositEvent], wallets: &[Address]) -> U256 {
+    events.iter()
+        .filter(|event| wallets.contains(&event.user))
+        .fold(U256::zero(), |sum, event| sum.saturating_add(event.amount))
+}
+
+/// Sum of the BridgeOut orders, other than failed ones, withdrawing from `wallet`
+async fn withdrawn_by(db: &SqlitePool, wallet: &str) -> Result<U256> {
+    let amounts: Vec<String> = sqlx::query_scalar(
+        "SELECT amount FROM orders WHERE order_type = ? AND status != ? AND LOWER(from_address) = LOWER(?)",
+    )
+    .bind(OrderType::BridgeOut as i32)
+    .bind(OrderStatus::Failed as i32)
+    .bind(wallet)
+    .fetch_all(db)
+    .await?;
+    Ok(amounts.iter()
+        .filter_map(|amount| U256::from_dec_str(amount).ok())
+        .fold(U256::zero(), |sum, amount| sum.saturating_add(amount)))
+}
+
+/// Filler collateral: bridge deposits from the filler's wallets less their withdrawals, and
+/// less what expired locks slashed
+///
+/// The bridge holds no separate collateral, so slashing and flagging are recorded here and
+/// enforced when the filler locks; nothing moves on-chain.
+#[derive(Debug, Clone)]
+pub struct CollateralService {
+    db: SqlitePool,
+    config: CollateralConfig,
+    clock: SharedClock,
+}
+
+impl CollateralService {
+    pub fn new(db: SqlitePool, config: &CollateralConfig, clock: SharedClock) -> Self {
+        Self { db, config: config.clone(), clock }
+    }
+
+    /// Refuse a lock of `lock_amount` whose collateral requirement the filler cannot cover
+    ///
+    /// The deposited balance is re-read from the chain once older than `cache_seconds`; when
+    /// that fails, or there is no client, the last balance read is used.
+    pub async fn check_lock(
+        &self,
+        filler_id: &str,
+        chain: Option<&BlockchainClient>,
+        token_id: u32,
+        bank_service: Option<&str>,
+        lock_amount: u64,
+    ) -> Result<(), CollateralError> {
+        let ratio_bps = self.config.ratio_for(token_id, bank_service);
+        if ratio_bps == 0 || lock_amount <= self.config.lock_threshold {
+            return Ok(());
+        }
+
+        let mut status = self.status(filler_id).await?;
+        if status.flagged {
+            return Err(CollateralError::Flagged {
+                filler_id: filler_id.to_string(),
+                expiries: status.expiries,
+                lock_threshold: self.config.lock_threshold,
+            });
+        }
+
+        let stale = status.refreshed_at
+            .is_none_or(|at| self.clock.now() - at >= Duration::seconds(self.config.cache_seconds as i64));
+        if let (true, Some(chain)) = (stale, chain) {
+            match self.refresh(filler_id, chain).await {
+                Ok(refreshed) => status = refreshed,
+                Err(e) => warn!("Using last known collateral of filler {}, chain read failed: {}", filler_id, e),
+            }
+        }
+
+        let required = amount(&status.required) + lock_amount as u128 * ratio_bps as u128 / 10_000;
+        if required > amount(&status.available) {
+            return Err(CollateralError::Insufficient {
+                filler_id: filler_id.to_string(),
+                required: required.to_string(),
+                available: status.available,
+            });
+        }
+        Ok(())
+    }
+
+    /// Re-read the filler's bridge deposits from the chain, net of its withdrawals
+    pub async fn refresh(&self, filler_id: &str, chain: &BlockchainClient) -> Result<CollateralStatus> {
+        let wallets: Vec<String> = sqlx::query_scalar("SELECT wallet_address FROM filler_wallets WHERE filler_id = ?")
+            .bind(filler_id)
+            .fetch_all(&self.db)
+            .await?;
+        let addresses = wallets.iter()
+            .filter_map(|wallet| hex_to_address(wallet).ok())
+            .collect::<Vec<_>>();
+        let mut deposited = if addresses.is_empty() {
+            U256::zero()
+        } else {
+            deposited_by(&chain.get_deposit_events(self.config.from_block, None).await?, &addresses)
+        };
+        // Collateral is what the wallets still hold at the bridge, not what they ever put in
+        for wallet in &wallets {
+            deposited = deposited.saturating_sub(withdrawn_by(&self.db, wallet).await?);
+        }
+        // Stored amounts are read back as u128
+        let deposited = if deposited > U256::from(u128::MAX) { u128::MAX } else { deposited.as_u128() };
+
+        let now = self.clock.now();
+        sqlx::query(
+            r#"
+            INSERT INTO filler_collateral (filler_id, deposited, refreshed_at, updated_at) VALUES (?1, ?2, ?3, ?3)
+            ON CONFLICT(filler_id) DO UPDATE SET deposited = excluded.deposited, refreshed_at = excluded.refreshed_at, updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(filler_id)
+        .bind(deposited.to_string())
+        .bind(now)
+        .execute(&self.db)
+        .await?;
+
+        info!("Filler {} has {} collateral deposited from {} wallets", filler_id, deposited, wallets.len());
+        self.status(filler_id).await
+    }
+
+    /// Slash the filler of an expired lock and flag it once it has let too many expire
+    ///
+    /// Call before the lock is released, while the order still names its filler.
+    pub async fn record_expiry(&self, order_id: &str) -> Result<Option<CollateralStatus>> {
+        let row = sqlx::query("SELECT filler_id, locked_amount FROM orders WHERE id = ?")
+            .bind(order_id)
+            .fetch_optional(&self.db)
+            .await?;
+        let Some(filler_id) = row.as_ref().and_then(|row| row.get::<Option<String>, _>("filler_id")) else {
+            return Ok(None);
+        };
+        let locked_amount = row.and_then(|row| row.get::<Option<String>, _>("locked_amount"))
+            .map_or(0, |locked| amount(&locked));
+
+        let before = self.status(&filler_id).await?;
+        let slashed = amount(&before.slashed) + locked_amount * self.config.slash_bps as u128 / 10_000;
+        let expiries = before.expiries + 1;
+        let now = self.clock.now();
+        let flag = self.config.flag_after_expiries > 0 && expiries >= self.config.flag_after_expiries && !before.flagged;
+
+        sqlx::query(
+            r#"
+            INSERT INTO filler_collateral (filler_id, slashed, expiries, flagged_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(filler_id) DO UPDATE SET slashed = excluded.slashed, expiries = excluded.expiries,
+                flagged_at = COALESCE(filler_collateral.flagged_at, excluded.flagged_at), updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&filler_id)
+        .bind(slashed.to_string())
+        .bind(expiries as i64)
+        .bind(flag.then_some(now))
+        .bind(now)
+        .execute(&self.db)
+        .await?;
+
+        if flag {
+            warn!("Filler {} flagged after {} expired locks", filler_id, expiries);
+        } else {
+            info!("Filler {} let the lock on order {} expire ({} so far)", filler_id, order_id, expiries);
+        }
+        self.status(&filler_id).await.map(Some)
+    }
+
+    /// Lift a filler's flag and reset its expiry count; slashed collateral stays slashed
+    pub async fn clear_flag(&self, filler_id: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE filler_collateral SET flagged_at = NULL, expiries = 0, updated_at = ?1 WHERE filler_id = ?2 AND flagged_at IS NOT NULL")
+            .bind(self.clock.now())
+            .bind(filler_id)
+            .execute(&self.db)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The filler's recorded collateral and what its open locks require
+    pub async fn status(&self, filler_id: &str) -> Result<CollateralStatus> {
+        let row = sqlx::query("SELECT deposited, slashed, expiries, flagged_at, refreshed_at FROM filler_collateral WHERE filler_id = ?")
+            .bind(filler_id)
+            .fetch_optional(&self.db)
+            .await?;
+        let locks = sqlx::query("SELECT token_id, bank_service, locked_amount FROM orders WHERE filler_id = ? AND status = ?")
+            .bind(filler_id)
+            .bind(OrderStatus::Locked as i32)
+            .fetch_all(&self.db)
+            .await?;
+
+        let required: u128 = locks.iter()
+            .map(|lock| {
+                let ratio_bps = self.config.ratio_for(lock.get::<i64, _>("token_id") as u32, lock.get::<Option<String>, _>("bank_service").as_deref());
+                let locked = lock.get::<Option<String>, _>("locked_amount").map_or(0, |locked| amount(&locked));
+                locked * ratio_bps as u128 / 10_000
+            })
+            .sum();
+        let deposited = row.as_ref().map_or(0, |row| amount(&row.get::<String, _>("deposited")));
+        let slashed = row.as_ref().map_or(0, |row| amount(&row.get::<String, _>("slashed")));
+        let flagged_at: Option<DateTime<Utc>> = row.as_ref().and_then(|row| row.get("flagged_at"));
+
+        Ok(CollateralStatus {
+            filler_id: filler_id.to_string(),
+            deposited: deposited.to_string(),
+            slashed: slashed.to_string(),
+            available: deposited.saturating_sub(slashed).to_string(),
+            required: required.to_string(),
+            open_locks: locks.len() as u32,
+            expiries: row.as_ref().map_or(0, |row| row.get::<i64, _>("expiries") as u32),
+            flagged: flagged_at.is_some(),
+            flagged_at,
+            refreshed_at: row.and_then(|row| row.get("refreshed_at")),
+        })
+    }
+}
+
+fn amount(raw: &str) -> u128 {
+    raw.parse().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::helpers;
+    use crate::models::Order;
+    use vapor_core::services::clock::system_clock;
+    use web3::types::H256;
+
+    fn locked_order(id: &str, bank_service: &str, locked_amount: &str) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::BridgeIn,
+            from_address: None,
+            to_address: None,
+            token_id: 1,
+            amount: locked_amount.to_string(),
+            bank_account: None,
+            bank_service: Some(bank_service.to_string()),
+            banking_hash: None,
+            filler_id: Some("filler_1".to_string()),
+            locked_amount: Some(locked_amount.to_string()),
+            status: OrderStatus::Locked,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    async fn setup(config: CollateralConfig, deposited: u64) -> CollateralService {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        sqlx::query("INSERT INTO filler_collateral (filler_id, deposited, refreshed_at, updated_at) VALUES ('filler_1', ?1, ?2, ?2)")
+            .bind(deposited.to_string())
+            .bind(Utc::now())
+            .execute(&db)
+            .await
+            .unwrap();
+        CollateralService::new(db, &config, system_clock())
+    }
+
+    #[test]
+    fn test_deposits_are_summed_per_wallet() {
+        let wallet = Address::from_low_u64_be(7);
+        let deposit = |user: Address, amount: u64| DepositEvent {
+            user,
+            token: Address::zero(),
+            amount: U256::from(amount),
+            banking_hash: H256::zero(),
+            block_number: 1,
+            transaction_hash: H256::zero(),
+        };
+        let events = vec![deposit(wallet, 500), deposit(Address::from_low_u64_be(8), 900), deposit(wallet, 250)];
+        assert_eq!(deposited_by(&events, &[wallet]), U256::from(750));
+        assert_eq!(deposited_by(&events, &[]), U256::zero());
+
+        // Amounts past u128 are kept whole rather than truncated
+        let large = vec![deposit(wallet, 1), DepositEvent { amount: U256::from(u128::MAX), ..deposit(wallet, 0) }];
+        assert_eq!(deposited_by(&large, &[wallet]), U256::from(u128::MAX) + 1);
+    }
+
+    #[tokio::test]
+    async fn test_withdrawals_are_summed_per_wallet() {
+        let collateral = setup(CollateralConfig::default(), 0).await;
+        let wallet = "0x00000000000000000000000000000000000000AA";
+        let bridge_out = |id: &str, from: &str, amount: &str, status: OrderStatus| Order {
+            order_type: OrderType::BridgeOut,
+            from_address: Some(from.to_string()),
+            filler_id: None,
+            locked_amount: None,
+            status,
+            ..locked_order(id, "PayPal Hong Kong", amount)
+        };
+        for order in [
+            bridge_out("out_1", wallet, "300", OrderStatus::Pending),
+            bridge_out("out_2", &wallet.to_lowercase(), "200", OrderStatus::Settled),
+            bridge_out("out_failed", wallet, "1000", OrderStatus::Failed),
+            bridge_out("out_other", "0x00000000000000000000000000000000000000bb", "1000", OrderStatus::Settled),
+        ] {
+            helpers::insert_order(&collateral.db, &order).await.unwrap();
+        }
+
+        assert_eq!(withdrawn_by(&collateral.db, wallet).await.unwrap(), U256::from(500));
+    }
+
+    #[tokio::test]
+    async fn test_locks_need_collateral_at_their_corridor_ratio() {
+        let config = CollateralConfig {
+            lock_threshold: 100,
+            ratio_bps: 5_000,
+            corridor_ratios: vec![crate::config::CorridorRatio { token_id: 1, bank_service: "Wise".to_string(), ratio_bps: 0 }],
+            ..CollateralConfig::default()
+        };
+        let collateral = setup(config, 1_000).await;
+        helpers::insert_order(&collateral.db, &locked_order("open", "PayPal Hong Kong", "1200")).await.unwrap();
+
+        let status = collateral.status("filler_1").await.unwrap();
+        assert_eq!((status.required.as_str(), status.open_locks), ("600", 1));
+
+        // Under the threshold, or in a corridor without a ratio, no collateral is needed
+        assert!(collateral.check_lock("filler_1", None, 1, Some("PayPal Hong Kong"), 100).await.is_ok());
+        assert!(collateral.check_lock("filler_1", None, 1, Some("wise"), 50_000).await.is_ok());
+        assert!(collateral.check_lock("filler_1", None, 1, Some("PayPal Hong Kong"), 800).await.is_ok());
+        let err = collateral.check_lock("filler_1", None, 1, Some("PayPal Hong Kong"), 802).await.unwrap_err();
+        assert!(matches!(err, CollateralError::Insufficient { ref required, ref available, .. } if required == "1001" && available == "1000"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_locks_slash_and_flag_the_filler() {
+        let config = CollateralConfig { ratio_bps: 10_000, slash_bps: 1_000, flag_after_expiries: 2, ..CollateralConfig::default() };
+        let collateral = setup(config, 1_000).await;
+        for id in ["expired_1", "expired_2"] {
+            helpers::insert_order(&collateral.db, &locked_order(id, "PayPal Hong Kong", "500")).await.unwrap();
+        }
+
+        let status = collateral.record_expiry("expired_1").await.unwrap().unwrap();
+        assert_eq!((status.slashed.as_str(), status.available.as_str(), status.flagged), ("50", "950", false));
+        let status = collateral.record_expiry("expired_2").await.unwrap().unwrap();
+        assert_eq!((status.expiries, status.flagged), (2, true));
+        assert!(matches!(
+            collateral.check_lock("filler_1", None, 1, None, 1).await,
+            Err(CollateralError::Flagged { expiries: 2, .. })
+        ));
+
+        assert!(collateral.clear_flag("filler_1").await.unwrap());
+        assert!(!collateral.clear_flag("filler_1").await.unwrap());
+        let status = collateral.status("filler_1").await.unwrap();
+        assert_eq!((status.expiries, status.flagged, status.slashed.as_str()), (0, false, "100"));
+        assert!(collateral.record_expiry("missing").await.unwrap().is_none());
+    }
+}
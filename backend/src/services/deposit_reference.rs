@@ -0,0 +1,194 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+use web3::types::{H256, U256};
+
+use vapor_chain::DepositEvent;
+use crate::database::helpers;
+use crate::models::{Order, OrderStatus, OrderType, PermitData};
+
+/// Deterministic deposit reference for a pre-created BridgeIn order
+///
+/// The user passes it as the `bankingHash` argument of `VaporBridge.deposit`, so the
+/// resulting Deposit event can be attributed to the order it was made for.
+/// reference = keccak256(salt || order_id)
+pub fn derive_deposit_reference(salt: &str, order_id: &str) -> H256 {
+    let mut preimage = Vec::with_capacity(salt.len() + order_id.len());
+    preimage.extend_from_slice(salt.as_bytes());
+    preimage.extend_from_slice(order_id.as_bytes());
+    H256::from(web3::signing::keccak256(&preimage))
+}
+
+/// 0x-prefixed hex form, as stored on orders and compared against deposit events
+pub fn format_reference(reference: &H256) -> String {
+    format!("{:?}", reference)
+}
+
+/// Attach a deposit to the pending order it references, if there is one
+///
+/// Returns None when the deposit does not carry a known reference, or when it does not fit
+/// the referenced order (wrong type, amount or depositor, a stored permit whose owner or value
+/// does not match the deposit, or the order already has a deposit); the caller then treats it
+/// as a standalone deposit.
+pub async fn claim_referenced_order(db: &SqlitePool, event: &DepositEvent) -> Result<Option<Order>> {
+    let reference = format_reference(&event.banking_hash);
+    let Some(mut order) = helpers::get_order_by_deposit_reference(db, &reference).await? else {
+        return Ok(None);
+    };
+
+    let depositor = format!("{:?}", event.user);
+    // A deposit for an order created with a permit must be the one the permit authorised
+    let permit_covers = |permit: &PermitData| {
+        permit.owner.eq_ignore_ascii_case(&depositor)
+            && U256::from_dec_str(&permit.value).is_ok_and(|value| value >= event.amount)
+    };
+    let permit = helpers::get_order_permit(db, &order.id).await?;
+    // An order held for review takes its deposit now and enters Discovery once approved
+    let mismatch = if order.order_type != OrderType::BridgeIn || !matches!(order.status, OrderStatus::Pending | OrderStatus::ReviewPending) {
+        Some("order is not a pending BridgeIn order")
+    } else if order.amount != event.amount.to_string() {
+        Some("amount differs")
+    } else if order.from_address.as_deref().is_some_and(|from| !from.eq_ignore_ascii_case(&depositor)) {
+        Some("depositor differs")
+    } else if permit.as_ref().is_some_and(|permit| !permit_covers(permit)) {
+        Some("its permit owner or value does not match the deposit")
+    } else {
+        None
+    };
+
+    if let Some(reason) = mismatch {
+        warn!("Deposit {:?} references order {} but {}", event.transaction_hash, order.id, reason);
+        return Ok(None);
+    }
+
+    if !helpers::attach_deposit(db, &order.id, &reference).await? {
+        warn!("Order {} already received a deposit, ignoring reference on {:?}", order.id, event.transaction_hash);
+        return Ok(None);
+    }
+
+    info!("Attributed deposit {:?} to pre-created order {}", event.transaction_hash, order.id);
+    order.banking_hash = Some(reference);
+    Ok(Some(order))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use web3::types::{Address, U256};
+
+    const SALT: &str = "test-salt";
+
+    fn order(id: &str, from: &str, amount: &str) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::BridgeIn,
+            from_address: Some(from.to_string()),
+            to_address: Some(from.to_string()),
+            token_id: 1,
+            amount: amount.to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            status: OrderStatus::Pending,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn deposit(user: Address, amount: u64, reference: H256) -> DepositEvent {
+        DepositEvent {
+            user,
+            token: Address::from_low_u64_be(3),
+            amount: U256::from(amount),
+            banking_hash: reference,
+            block_number: 100,
+            transaction_hash: H256::from_low_u64_be(1),
+        }
+    }
+
+    async fn setup(order: &Order) -> (SqlitePool, H256) {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+
+        helpers::insert_order(&db, order).await.unwrap();
+        let reference = derive_deposit_reference(SALT, &order.id);
+        sqlx::query("UPDATE orders SET deposit_reference = ? WHERE id = ?")
+            .bind(format_reference(&reference))
+            .bind(&order.id)
+            .execute(&db)
+            .await
+            .unwrap();
+        (db, reference)
+    }
+
+    #[test]
+    fn test_reference_is_deterministic_and_salted() {
+        assert_eq!(derive_deposit_reference(SALT, "order-1"), derive_deposit_reference(SALT, "order-1"));
+        assert_ne!(derive_deposit_reference(SALT, "order-1"), derive_deposit_reference(SALT, "order-2"));
+        assert_ne!(derive_deposit_reference(SALT, "order-1"), derive_deposit_reference("other-salt", "order-1"));
+        assert_eq!(format_reference(&derive_deposit_reference(SALT, "order-1")).len(), 66);
+    }
+
+    #[tokio::test]
+    async fn test_deposit_is_attributed_to_referenced_order() {
+        let user = Address::from_low_u64_be(7);
+        let (db, reference) = setup(&order("order-1", &format!("{:?}", user), "1000")).await;
+
+        let claimed = claim_referenced_order(&db, &deposit(user, 1000, reference)).await.unwrap().unwrap();
+        assert_eq!(claimed.id, "order-1");
+        assert_eq!(claimed.banking_hash, Some(format_reference(&reference)));
+
+        let stored = helpers::get_order_by_id(&db, "order-1").await.unwrap().unwrap();
+        assert_eq!(stored.banking_hash, Some(format_reference(&reference)));
+
+        // A second deposit with the same reference is not attributed again
+        assert!(claim_referenced_order(&db, &deposit(user, 1000, reference)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_or_unknown_deposits_are_not_attributed() {
+        let user = Address::from_low_u64_be(7);
+        let (db, reference) = setup(&order("order-1", &format!("{:?}", user), "1000")).await;
+
+        assert!(claim_referenced_order(&db, &deposit(user, 999, reference)).await.unwrap().is_none());
+        assert!(claim_referenced_order(&db, &deposit(Address::from_low_u64_be(8), 1000, reference)).await.unwrap().is_none());
+        assert!(claim_referenced_order(&db, &deposit(user, 1000, H256::from_low_u64_be(42))).await.unwrap().is_none());
+
+        let stored = helpers::get_order_by_id(&db, "order-1").await.unwrap().unwrap();
+        assert_eq!(stored.banking_hash, None);
+    }
+
+    #[tokio::test]
+    async fn test_deposit_must_match_the_orders_permit() {
+        let user = Address::from_low_u64_be(7);
+        let (db, reference) = setup(&order("order-1", &format!("{:?}", user), "1000")).await;
+        let mut permit = PermitData {
+            owner: format!("{:?}", Address::from_low_u64_be(8)),
+            spender: "0x0000000000000000000000000000000000000001".to_string(),
+            value: "1000".to_string(),
+            nonce: Some("0".to_string()),
+            deadline: 1_900_000_000,
+            signature: format!("0x{}1b", "11".repeat(64)),
+        };
+        helpers::insert_order_permit(&db, "order-1", &permit).await.unwrap();
+
+        // The permit was signed by someone other than the depositor
+        assert!(claim_referenced_order(&db, &deposit(user, 1000, reference)).await.unwrap().is_none());
+
+        permit.owner = format!("{:?}", user);
+        permit.value = "999".to_string();
+        sqlx::query("DELETE FROM order_permits").execute(&db).await.unwrap();
+        helpers::insert_order_permit(&db, "order-1", &permit).await.unwrap();
+        assert!(claim_referenced_order(&db, &deposit(user, 1000, reference)).await.unwrap().is_none());
+
+        permit.value = "1000".to_string();
+        sqlx::query("DELETE FROM order_permits").execute(&db).await.unwrap();
+        helpers::insert_order_permit(&db, "order-1", &permit).await.unwrap();
+        let claimed = claim_referenced_order(&db, &deposit(user, 1000, reference)).await.unwrap().unwrap();
+        assert_eq!(claimed.id, "order-1");
+    }
+}
@@ -0,0 +1,274 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashSet;
+use tracing::{info, warn};
+
+use crate::models::{OrderResponse, OrderStatus, OrderType};
+use vapor_core::services::clock::{system_clock, SharedClock};
+use crate::services::event_bus::{EventBus, OrderEvent};
+use crate::services::query_metrics::QueryTimer;
+
+/// Orders that are filled by a filler paying out fiat go through Discovery;
+/// Transfer and BridgeOut orders are processed by the batch processor instead
+pub fn is_discovery_eligible(order_type: OrderType) -> bool {
+    order_type == OrderType::BridgeIn
+}
+
+/// Move one Pending order to Discovery, record the transition and announce it on the event bus
+/// Returns false when the order is not eligible or has already left Pending
+pub async fn advance_to_discovery(db: &SqlitePool, event_bus: &EventBus, order_id: &str, reason: &str) -> Result<bool> {
+    let Some(order) = crate::database::helpers::get_order_by_id(db, order_id).await? else {
+        return Ok(false);
+    };
+    if !is_discovery_eligible(order.order_type) {
+        return Ok(false);
+    }
+
+    // Guard on status so concurrent transitions are not overwritten
+    let result = sqlx::query("UPDATE orders SET status = $1, updated_at = $2 WHERE id = $3 AND status = $4")
+        .bind(OrderStatus::Discovery as i32)
+        .bind(Utc::now())
+        .bind(order_id)
+        .bind(OrderStatus::Pending as i32)
+        .execute(db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    crate::database::helpers::record_status_transition(
+        db, order_id, OrderStatus::Pending, OrderStatus::Discovery, Some(reason),
+    ).await?;
+    publish_discovered(db, event_bus, order_id).await?;
+
+    Ok(true)
+}
+
+/// Move Pending BridgeIn orders to Discovery and announce each one on the event bus
+/// Transfer orders are excluded as they are processed by the batch processor
+pub async fn promote_pending_orders(db: &SqlitePool, event_bus: &EventBus) -> Result<usize> {
+    let _timer = QueryTimer::start("promote_pending_orders");
+    let rows = sqlx::query("SELECT id FROM orders WHERE status = $1 AND order_type = $2")
+        .bind(OrderStatus::Pending as i32)
+        .bind(OrderType::BridgeIn as i32)
+        .fetch_all(db)
+        .await?;
+
+    let mut promoted = 0;
+    for row in rows {
+        let order_id: String = row.try_get("id")?;
+        if advance_to_discovery(db, event_bus, &order_id, "auto_discovery").await? {
+            promoted += 1;
+        }
+    }
+
+    Ok(promoted)
+}
+
+/// Publish a Discovered event for an order that is now in the Discovery phase
+pub async fn publish_discovered(db: &SqlitePool, event_bus: &EventBus, order_id: &str) -> Result<()> {
+    match crate::database::helpers::get_order_by_id(db, order_id).await? {
+        Some(order) => {
+            event_bus.publish(OrderEvent::Discovered {
+                order: Box::new(OrderResponse::from(&order)),
+            });
+        }
+        None => warn!("Order {} disappeared before discovery event was published", order_id),
+    }
+    Ok(())
+}
+
+/// Notifies lock holders shortly before their lock on an order expires
+pub struct LockExpiryWatcher {
+    lock_ttl: Duration,
+    warning_window: Duration,
+    /// Orders already notified for their current lock
+    notified: HashSet<String>,
+    clock: SharedClock,
+}
+
+impl LockExpiryWatcher {
+    pub fn new(lock_ttl_seconds: u64, warning_seconds: u64) -> Self {
+        Self {
+            lock_ttl: Duration::seconds(lock_ttl_seconds as i64),
+            warning_window: Duration::seconds(warning_seconds as i64),
+            notified: HashSet::new(),
+            clock: system_clock(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Scan locked orders and publish LockExpiring for those inside the warning window
+    pub async fn sweep(&mut self, db: &SqlitePool, event_bus: &EventBus) -> Result<usize> {
+        let rows = sqlx::query("SELECT id, filler_id, updated_at FROM orders WHERE status = $1")
+            .bind(OrderStatus::Locked as i32)
+            .fetch_all(db)
+            .await?;
+
+        let now = self.clock.now();
+        let mut still_locked = HashSet::new();
+        let mut published = 0;
+
+        for row in rows {
+            let order_id: String = row.try_get("id")?;
+            let filler_id: Option<String> = row.try_get("filler_id")?;
+            let locked_at: DateTime<Utc> = row.try_get("updated_at")?;
+            still_locked.insert(order_id.clone());
+
+            let Some(filler_id) = filler_id else { continue };
+            if self.notified.contains(&order_id) {
+                continue;
+            }
+
+            let expires_at = locked_at + self.lock_ttl;
+            if now >= expires_at - self.warning_window {
+                info!("Lock on order {} held by {} expires at {}", order_id, filler_id, expires_at);
+                event_bus.publish(OrderEvent::LockExpiring {
+                    order_id: order_id.clone(),
+                    filler_id,
+                    expires_at,
+                });
+                self.notified.insert(order_id);
+                published += 1;
+            }
+        }
+
+        // Forget orders that left the Locked state so a future lock is notified again
+        self.notified.retain(|order_id| still_locked.contains(order_id));
+
+        Ok(published)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Order;
+
+    async fn setup_db() -> SqlitePool {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        db
+    }
+
+    fn bridge_in_order(id: &str) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::BridgeIn,
+            status: OrderStatus::Pending,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal".to_string()),
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_promote_pending_orders_publishes_events() {
+        let db = setup_db().await;
+        let bus = EventBus::new(16);
+        let mut receiver = bus.subscribe();
+
+        crate::database::helpers::insert_order(&db, &bridge_in_order("order_1")).await.unwrap();
+        let mut transfer = bridge_in_order("order_2");
+        transfer.order_type = OrderType::Transfer;
+        crate::database::helpers::insert_order(&db, &transfer).await.unwrap();
+
+        let promoted = promote_pending_orders(&db, &bus).await.unwrap();
+        assert_eq!(promoted, 1);
+
+        match receiver.try_recv().unwrap() {
+            OrderEvent::Discovered { order } => {
+                assert_eq!(order.id, "order_1");
+                assert_eq!(order.status, OrderStatus::Discovery);
+            }
+            other => panic!("Unexpected event: {:?}", other),
+        }
+        assert!(receiver.try_recv().is_err());
+
+        // Already promoted orders are not announced again
+        assert_eq!(promote_pending_orders(&db, &bus).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_advance_to_discovery_records_history() {
+        let db = setup_db().await;
+        let bus = EventBus::new(16);
+
+        crate::database::helpers::insert_order(&db, &bridge_in_order("order_1")).await.unwrap();
+        let mut bridge_out = bridge_in_order("order_2");
+        bridge_out.order_type = OrderType::BridgeOut;
+        crate::database::helpers::insert_order(&db, &bridge_out).await.unwrap();
+
+        assert!(advance_to_discovery(&db, &bus, "order_1", "deposit_confirmed").await.unwrap());
+        assert!(!advance_to_discovery(&db, &bus, "order_1", "deposit_confirmed").await.unwrap());
+        assert!(!advance_to_discovery(&db, &bus, "order_2", "deposit_confirmed").await.unwrap());
+        assert!(!advance_to_discovery(&db, &bus, "missing", "deposit_confirmed").await.unwrap());
+
+        let history = crate::database::helpers::get_order_history(&db, "order_1").await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].from_status, OrderStatus::Pending);
+        assert_eq!(history[0].to_status, OrderStatus::Discovery);
+        assert_eq!(history[0].reason.as_deref(), Some("deposit_confirmed"));
+
+        assert!(crate::database::helpers::get_order_history(&db, "order_2").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lock_expiry_watcher_notifies_once() {
+        use vapor_core::services::clock::MockClock;
+        use chrono::TimeZone;
+
+        let db = setup_db().await;
+        let bus = EventBus::new(16);
+        let mut receiver = bus.subscribe();
+        let locked_at = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+        let clock = MockClock::new(locked_at);
+
+        let mut order = bridge_in_order("order_1");
+        order.status = OrderStatus::Locked;
+        order.filler_id = Some("filler_1".to_string());
+        order.locked_amount = Some("1000".to_string());
+        order.updated_at = locked_at;
+        crate::database::helpers::insert_order(&db, &order).await.unwrap();
+
+        let mut fresh = bridge_in_order("order_2");
+        fresh.status = OrderStatus::Locked;
+        fresh.filler_id = Some("filler_2".to_string());
+        fresh.updated_at = locked_at + Duration::minutes(10);
+        crate::database::helpers::insert_order(&db, &fresh).await.unwrap();
+
+        // 30 minute locks with a 5 minute warning window
+        let mut watcher = LockExpiryWatcher::new(1800, 300).with_clock(clock.shared());
+        clock.advance(Duration::minutes(24));
+        assert_eq!(watcher.sweep(&db, &bus).await.unwrap(), 0);
+
+        clock.advance(Duration::minutes(1));
+        assert_eq!(watcher.sweep(&db, &bus).await.unwrap(), 1);
+
+        match receiver.try_recv().unwrap() {
+            OrderEvent::LockExpiring { order_id, filler_id, expires_at } => {
+                assert_eq!(order_id, "order_1");
+                assert_eq!(filler_id, "filler_1");
+                assert_eq!(expires_at, locked_at + Duration::minutes(30));
+            }
+            other => panic!("Unexpected event: {:?}", other),
+        }
+
+        assert_eq!(watcher.sweep(&db, &bus).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use crate::config::{DuplicateConfig, DuplicateMode};
+use crate::models::{Order, OrderStatus};
+
+/// A recent order a new one looks like a resubmission of
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuspectedDuplicate {
+    pub original_order_id: String,
+    pub original_created_at: DateTime<Utc>,
+    pub window_seconds: u64,
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("order looks like a duplicate of order {} created at {}; resubmit with confirm_duplicate to create it anyway", .0.original_order_id, .0.original_created_at)]
+pub struct DuplicateOrderError(pub SuspectedDuplicate);
+
+/// The latest order of the same type from the same address, for the same token, amount and
+/// bank account, created within the window before `order`
+///
+/// Failed orders are left out, since resubmitting one is how a seller retries it.
+pub async fn find_suspected_duplicate(db: &SqlitePool, config: &DuplicateConfig, order: &Order) -> Result<Option<SuspectedDuplicate>> {
+    if config.mode == DuplicateMode::Off || config.window_seconds == 0 {
+        return Ok(None);
+    }
+    let Some(from_address) = &order.from_address else {
+        return Ok(None);
+    };
+
+    let since = order.created_at - Duration::seconds(config.window_seconds as i64);
+    let row = sqlx::query(
+        r#"
+        SELECT id, created_at FROM orders
+        WHERE LOWER(from_address) = LOWER(?) AND order_type = ? AND token_id = ? AND amount = ?
+          AND bank_account IS ? AND status != ? AND created_at >= ? AND id != ?
+        ORDER BY created_at DESC LIMIT 1
+        "#,
+    )
+    .bind(from_address)
+    .bind(order.order_type as i32)
+    .bind(order.token_id as i64)
+    .bind(&order.amount)
+    .bind(&order.bank_account)
+    .bind(OrderStatus::Failed as i32)
+    .bind(since)
+    .bind(&order.id)
+    .fetch_optional(db)
+    .await?;
+
+    row.map(|row| {
+        Ok(SuspectedDuplicate {
+            original_order_id: row.try_get("id")?,
+            original_created_at: row.try_get("created_at")?,
+            window_seconds: config.window_seconds,
+        })
+    })
+    .transpose()
+}
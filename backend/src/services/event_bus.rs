@@ -0,0 +1,255 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tracing::debug;
+
+use crate::models::OrderResponse;
+use crate::services::batch_journal::AbortedBatch;
+use crate::services::event_log::LoggedEvent;
+use crate::services::registry::Registry;
+
+/// Order lifecycle events published to in-process subscribers (e.g. filler feeds)
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OrderEvent {
+    /// Order entered the Discovery phase and can be locked by fillers
+    Discovered { order: Box<OrderResponse> },
+    /// A filler's lock on an order is about to expire
+    LockExpiring {
+        order_id: String,
+        filler_id: String,
+        expires_at: DateTime<Utc>,
+    },
+    /// A BridgeIn order's deposit never arrived, so it failed and left the Discovery phase
+    IntentExpired {
+        order_id: String,
+        created_at: DateTime<Utc>,
+        expired_at: DateTime<Utc>,
+    },
+}
+
+impl OrderEvent {
+    pub fn order_id(&self) -> &str {
+        match self {
+            OrderEvent::Discovered { order } => &order.id,
+            OrderEvent::LockExpiring { order_id, .. } => order_id,
+            OrderEvent::IntentExpired { order_id, .. } => order_id,
+        }
+    }
+}
+
+/// Batch lifecycle events for monitors
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchEvent {
+    /// An admin abandoned the open batch; its orders were requeued for the next one
+    Aborted(AbortedBatch),
+}
+
+/// Broadcast bus for order events, for admin changes to the registries, and for events
+/// relayed from the event log
+/// Slow subscribers lag and drop the oldest events instead of blocking publishers
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<OrderEvent>,
+    registry_changes: broadcast::Sender<Registry>,
+    batch_events: broadcast::Sender<BatchEvent>,
+    logged_events: broadcast::Sender<LoggedEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        let (registry_changes, _) = broadcast::channel(capacity);
+        let (batch_events, _) = broadcast::channel(capacity);
+        let (logged_events, _) = broadcast::channel(capacity);
+        Self { sender, registry_changes, batch_events, logged_events }
+    }
+
+    /// Publish an event, returning how many subscribers received it
+    pub fn publish(&self, event: OrderEvent) -> usize {
+        let order_id = event.order_id().to_string();
+        match self.sender.send(event) {
+            Ok(receivers) => receivers,
+            Err(_) => {
+                debug!("No subscribers for event on order {}", order_id);
+                0
+            }
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<OrderEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Tell registry caches that a registry was changed, returning how many were told
+    pub fn publish_registry_change(&self, registry: Registry) -> usize {
+        self.registry_changes.send(registry).unwrap_or(0)
+    }
+
+    pub fn subscribe_registry_changes(&self) -> broadcast::Receiver<Registry> {
+        self.registry_changes.subscribe()
+    }
+
+    /// Publish a batch event, returning how many subscribers received it
+    pub fn publish_batch_event(&self, event: BatchEvent) -> usize {
+        self.batch_events.send(event).unwrap_or(0)
+    }
+
+    pub fn subscribe_batch_events(&self) -> broadcast::Receiver<BatchEvent> {
+        self.batch_events.subscribe()
+    }
+
+    /// Publish an event read from the event log, returning how many subscribers received it
+    pub fn publish_logged_event(&self, event: LoggedEvent) -> usize {
+        self.logged_events.send(event).unwrap_or(0)
+    }
+
+    pub fn subscribe_logged_events(&self) -> broadcast::Receiver<LoggedEvent> {
+        self.logged_events.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+/// What a single filler wants to receive from the event bus
+#[derive(Debug, Clone)]
+pub struct FillerSubscription {
+    pub filler_id: String,
+    /// Only forward orders for this bank service
+    pub bank_service: Option<String>,
+    /// Only forward orders up to this amount
+    pub max_amount: Option<u64>,
+}
+
+impl FillerSubscription {
+    /// Check whether an event should be delivered to this filler
+    /// `capacity` is the filler's remaining capacity in the matching engine, if registered
+    pub fn accepts(&self, event: &OrderEvent, capacity: Option<u64>) -> bool {
+        match event {
+            OrderEvent::Discovered { order } => {
+                let amount: u64 = match order.amount.parse() {
+                    Ok(amount) => amount,
+                    Err(_) => return false,
+                };
+
+                if let Some(bank_service) = &self.bank_service {
+                    match &order.bank_service {
+                        Some(service) if service.eq_ignore_ascii_case(bank_service) => {}
+                        _ => return false,
+                    }
+                }
+
+                if self.max_amount.is_some_and(|max| amount > max) {
+                    return false;
+                }
+
+                capacity.is_none_or(|capacity| amount <= capacity)
+            }
+            OrderEvent::LockExpiring { filler_id, .. } => filler_id == &self.filler_id,
+            // Every feed may have been offered the order
+            OrderEvent::IntentExpired { .. } => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{OrderStatus, OrderType};
+
+    fn discovered(amount: &str, bank_service: Option<&str>) -> OrderEvent {
+        OrderEvent::Discovered {
+            order: Box::new(OrderResponse {
+                id: "order_1".to_string(),
+                order_type: OrderType::BridgeIn,
+                status: OrderStatus::Discovery,
+                amount: amount.to_string(),
+                bank_account: Some("12345678".to_string()),
+                bank_service: bank_service.map(|s| s.to_string()),
+                filler_id: None,
+                locked_amount: None,
+                created_at: Utc::now(),
+                deposit_reference: None,
+                payment_proof: None,
+                quoted_rate: None,
+                duplicate_warning: None,
+                settlement_cost: None,
+            }),
+        }
+    }
+
+    fn subscription() -> FillerSubscription {
+        FillerSubscription {
+            filler_id: "filler_1".to_string(),
+            bank_service: None,
+            max_amount: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish_and_subscribe() {
+        let bus = EventBus::new(16);
+        assert_eq!(bus.publish(discovered("100", None)), 0);
+
+        let mut receiver = bus.subscribe();
+        assert_eq!(bus.publish(discovered("100", None)), 1);
+
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.order_id(), "order_1");
+    }
+
+    #[test]
+    fn test_subscription_filters_discovered_orders() {
+        let mut sub = subscription();
+        assert!(sub.accepts(&discovered("100", Some("PayPal")), None));
+        assert!(!sub.accepts(&discovered("not-a-number", None), None));
+
+        // Capacity from the matching engine caps what a filler sees
+        assert!(sub.accepts(&discovered("100", None), Some(100)));
+        assert!(!sub.accepts(&discovered("101", None), Some(100)));
+
+        sub.bank_service = Some("paypal".to_string());
+        assert!(sub.accepts(&discovered("100", Some("PayPal")), None));
+        assert!(!sub.accepts(&discovered("100", Some("Wise")), None));
+        assert!(!sub.accepts(&discovered("100", None), None));
+
+        sub.max_amount = Some(50);
+        assert!(!sub.accepts(&discovered("100", Some("PayPal")), None));
+    }
+
+    #[test]
+    fn test_subscription_only_receives_own_lock_expiry() {
+        let sub = subscription();
+        let own = OrderEvent::LockExpiring {
+            order_id: "order_1".to_string(),
+            filler_id: "filler_1".to_string(),
+            expires_at: Utc::now(),
+        };
+        let other = OrderEvent::LockExpiring {
+            order_id: "order_2".to_string(),
+            filler_id: "filler_2".to_string(),
+            expires_at: Utc::now(),
+        };
+
+        assert!(sub.accepts(&own, None));
+        assert!(!sub.accepts(&other, None));
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let event = OrderEvent::LockExpiring {
+            order_id: "order_1".to_string(),
+            filler_id: "filler_1".to_string(),
+            expires_at: Utc::now(),
+        };
+        let json = serde_json::to_value(&event).unwrap();
+
+        assert_eq!(json["type"], "lock_expiring");
+        assert_eq!(json["order_id"], "order_1");
+    }
+}
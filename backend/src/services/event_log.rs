@@ -0,0 +1,531 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use sqlx::{Row, SqlitePool};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::models::{Order, OrderStatus, OrderType};
+use crate::services::event_bus::EventBus;
+use crate::services::query_metrics::QueryTimer;
+
+/// Events read per query while replaying
+const REPLAY_PAGE_SIZE: u32 = 1000;
+
+/// Domain events of the append-only event log
+///
+/// Every state mutation the rollup's audit trail depends on is appended as one of these, in
+/// the order it happened, so the log can be tailed by consumers and replayed to rebuild state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    OrderCreated {
+        order_id: String,
+        order_type: OrderType,
+        status: OrderStatus,
+        token_id: u32,
+        amount: String,
+    },
+    OrderLocked {
+        order_id: String,
+        filler_id: String,
+        locked_amount: String,
+    },
+    /// The filler submitted its payment proof (the order is MarkPaid)
+    OrderPaid {
+        order_id: String,
+        banking_hash: Option<String>,
+    },
+    OrderSettled {
+        order_id: String,
+        from_status: OrderStatus,
+        reason: Option<String>,
+    },
+    /// Any other status change
+    OrderStatusChanged {
+        order_id: String,
+        from_status: OrderStatus,
+        to_status: OrderStatus,
+        reason: Option<String>,
+    },
+    BatchStarted {
+        batch_id: u64,
+        prev_state_root: String,
+        prev_orders_root: String,
+    },
+    BatchFinalized {
+        batch_id: u64,
+        state_root: String,
+        orders_root: String,
+        order_ids: Vec<String>,
+    },
+    /// The open batch was abandoned; its orders go into the next batch, which reuses its id
+    BatchAborted {
+        batch_id: u64,
+        order_ids: Vec<String>,
+    },
+    ProofSubmitted {
+        batch_id: u64,
+        /// Verifier contract the proof went to
+        target: String,
+        artifact_hash: Option<String>,
+        gas_used: Option<u64>,
+    },
+    /// A claim's payout was confirmed on-chain
+    ClaimPaid {
+        claim_id: String,
+        transaction_hash: String,
+    },
+    /// A backup was restored; state is as of event `through_seq` again, and later events
+    /// before this one no longer apply
+    BackupRestored {
+        backup_id: i64,
+        through_seq: u64,
+    },
+}
+
+impl DomainEvent {
+    pub fn order_created(order: &Order) -> Self {
+        DomainEvent::OrderCreated {
+            order_id: order.id.clone(),
+            order_type: order.order_type,
+            status: order.status,
+            token_id: order.token_id,
+            amount: order.amount.clone(),
+        }
+    }
+
+    /// The event for an order moving from `from_status` to `to_status`
+    pub fn status_change(order_id: &str, from_status: OrderStatus, to_status: OrderStatus, reason: Option<&str>) -> Self {
+        let order_id = order_id.to_string();
+        let reason = reason.map(str::to_string);
+        match to_status {
+            OrderStatus::Settled => DomainEvent::OrderSettled { order_id, from_status, reason },
+            _ => DomainEvent::OrderStatusChanged { order_id, from_status, to_status, reason },
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DomainEvent::OrderCreated { .. } => "order_created",
+            DomainEvent::OrderLocked { .. } => "order_locked",
+            DomainEvent::OrderPaid { .. } => "order_paid",
+            DomainEvent::OrderSettled { .. } => "order_settled",
+            DomainEvent::OrderStatusChanged { .. } => "order_status_changed",
+            DomainEvent::BatchStarted { .. } => "batch_started",
+            DomainEvent::BatchFinalized { .. } => "batch_finalized",
+            DomainEvent::BatchAborted { .. } => "batch_aborted",
+            DomainEvent::ProofSubmitted { .. } => "proof_submitted",
+            DomainEvent::ClaimPaid { .. } => "claim_paid",
+            DomainEvent::BackupRestored { .. } => "backup_restored",
+        }
+    }
+
+    /// Order, batch, claim or backup the event is about
+    pub fn subject(&self) -> String {
+        match self {
+            DomainEvent::OrderCreated { order_id, .. }
+            | DomainEvent::OrderLocked { order_id, .. }
+            | DomainEvent::OrderPaid { order_id, .. }
+            | DomainEvent::OrderSettled { order_id, .. }
+            | DomainEvent::OrderStatusChanged { order_id, .. } => order_id.clone(),
+            DomainEvent::BatchStarted { batch_id, .. }
+            | DomainEvent::BatchFinalized { batch_id, .. }
+            | DomainEvent::BatchAborted { batch_id, .. }
+            | DomainEvent::ProofSubmitted { batch_id, .. } => batch_id.to_string(),
+            DomainEvent::ClaimPaid { claim_id, .. } => claim_id.clone(),
+            DomainEvent::BackupRestored { backup_id, .. } => backup_id.to_string(),
+        }
+    }
+}
+
+/// An event as stored in the log
+#[derive(Debug, Clone, Serialize)]
+pub struct LoggedEvent {
+    pub seq: u64,
+    pub kind: String,
+    pub subject: String,
+    /// The event as appended, kept raw so consumers can read kinds they do not know yet
+    pub payload: serde_json::Value,
+    /// Keccak of the payload's compact JSON, keys sorted, as stored
+    pub payload_hash: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// `0x`-prefixed Keccak of a payload's compact JSON text
+pub fn payload_hash(payload: &str) -> String {
+    format!("0x{}", hex::encode(Keccak256::digest(payload.as_bytes())))
+}
+
+/// Append an event to the log, returning its sequence number
+///
+/// The payload is stored with its keys sorted, so a consumer can re-serialize what it read
+/// the same way and check the hash.
+pub async fn append<'e>(db: impl sqlx::SqliteExecutor<'e>, event: &DomainEvent) -> Result<u64> {
+    let _timer = QueryTimer::start("append_event");
+    let payload = serde_json::to_value(event)?.to_string();
+    let result = sqlx::query(
+        "INSERT INTO events (kind, subject, payload, payload_hash, recorded_at) VALUES (?1, ?2, ?3, ?4, ?5)"
+    )
+    .bind(event.kind())
+    .bind(event.subject())
+    .bind(&payload)
+    .bind(payload_hash(&payload))
+    .bind(Utc::now())
+    .execute(db)
+    .await?;
+    Ok(result.last_insert_rowid() as u64)
+}
+
+/// Events after sequence number `after_seq`, oldest first
+pub async fn read(db: &SqlitePool, after_seq: u64, limit: u32) -> Result<Vec<LoggedEvent>> {
+    let rows = sqlx::query(
+        "SELECT seq, kind, subject, payload, payload_hash, recorded_at FROM events WHERE seq > ?1 ORDER BY seq LIMIT ?2"
+    )
+    .bind(after_seq as i64)
+    .bind(limit as i64)
+    .fetch_all(db)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            let payload: String = row.try_get("payload")?;
+            Ok(LoggedEvent {
+                seq: row.try_get::<i64, _>("seq")? as u64,
+                kind: row.try_get("kind")?,
+                subject: row.try_get("subject")?,
+                payload: serde_json::from_str(&payload)?,
+                payload_hash: row.try_get("payload_hash")?,
+                recorded_at: row.try_get("recorded_at")?,
+            })
+        })
+        .collect()
+}
+
+/// Sequence number of the last event, 0 for an empty log
+pub async fn last_seq(db: &SqlitePool) -> Result<u64> {
+    let seq: Option<i64> = sqlx::query_scalar("SELECT MAX(seq) FROM events").fetch_one(db).await?;
+    Ok(seq.unwrap_or(0) as u64)
+}
+
+/// Publish the events appended after `after_seq` on the event bus, returning the last seq published
+pub async fn relay(db: &SqlitePool, event_bus: &EventBus, after_seq: u64) -> Result<u64> {
+    let mut last_seq = after_seq;
+    loop {
+        let events = read(db, last_seq, REPLAY_PAGE_SIZE).await?;
+        let Some(last) = events.last() else {
+            return Ok(last_seq);
+        };
+        last_seq = last.seq;
+        for event in events {
+            event_bus.publish_logged_event(event);
+        }
+    }
+}
+
+/// Where replayed state disagrees with the database
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Divergence {
+    pub subject: String,
+    pub field: &'static str,
+    pub replayed: String,
+    pub stored: String,
+}
+
+/// Outcome of replaying the event log against the database
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplayReport {
+    pub events: u64,
+    pub last_seq: u64,
+    pub orders: usize,
+    pub finalized_batches: usize,
+    pub paid_claims: usize,
+    /// Events whose stored payload no longer matches its hash, or is not a known event
+    pub corrupt_events: Vec<u64>,
+    pub divergences: Vec<Divergence>,
+}
+
+impl ReplayReport {
+    pub fn passed(&self) -> bool {
+        self.corrupt_events.is_empty() && self.divergences.is_empty()
+    }
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Vapor event log replay")?;
+        writeln!(f, "  events replayed     {} (through seq {})", self.events, self.last_seq)?;
+        writeln!(f, "  orders              {}", self.orders)?;
+        writeln!(f, "  finalized batches   {}", self.finalized_batches)?;
+        writeln!(f, "  paid claims         {}", self.paid_claims)?;
+        for seq in &self.corrupt_events {
+            writeln!(f, "  [CORRUPT] event {} does not match its payload hash", seq)?;
+        }
+        for divergence in &self.divergences {
+            writeln!(
+                f,
+                "  [DIVERGED] {} {}: replayed {}, stored {}",
+                divergence.subject, divergence.field, divergence.replayed, divergence.stored
+            )?;
+        }
+        if self.passed() {
+            write!(f, "Replay matches the database")
+        } else {
+            write!(f, "Replay FAILED: {} corrupt events, {} divergences", self.corrupt_events.len(), self.divergences.len())
+        }
+    }
+}
+
+/// State rebuilt from the log
+#[derive(Debug, Default)]
+struct ReplayedState {
+    order_statuses: BTreeMap<String, OrderStatus>,
+    /// State and orders root of each finalized batch
+    batch_roots: BTreeMap<u64, (String, String)>,
+    paid_claims: BTreeMap<String, String>,
+}
+
+impl ReplayedState {
+    fn apply(&mut self, event: &DomainEvent) {
+        match event {
+            DomainEvent::OrderCreated { order_id, status, .. } => {
+                self.order_statuses.insert(order_id.clone(), *status);
+            }
+            DomainEvent::OrderLocked { order_id, .. } => {
+                self.order_statuses.insert(order_id.clone(), OrderStatus::Locked);
+            }
+            DomainEvent::OrderPaid { order_id, .. } => {
+                self.order_statuses.insert(order_id.clone(), OrderStatus::MarkPaid);
+            }
+            DomainEvent::OrderSettled { order_id, .. } => {
+                self.order_statuses.insert(order_id.clone(), OrderStatus::Settled);
+            }
+            DomainEvent::OrderStatusChanged { order_id, to_status, .. } => {
+                self.order_statuses.insert(order_id.clone(), *to_status);
+            }
+            DomainEvent::BatchFinalized { batch_id, state_root, orders_root, .. } => {
+                self.batch_roots.insert(*batch_id, (state_root.clone(), orders_root.clone()));
+            }
+            DomainEvent::ClaimPaid { claim_id, transaction_hash } => {
+                self.paid_claims.insert(claim_id.clone(), transaction_hash.clone());
+            }
+            DomainEvent::BatchStarted { .. }
+            | DomainEvent::BatchAborted { .. }
+            | DomainEvent::ProofSubmitted { .. }
+            | DomainEvent::BackupRestored { .. } => {}
+        }
+    }
+}
+
+/// Rebuild order statuses, batch roots and claim payouts from the event log, and compare them
+/// with the database
+///
+/// A `BackupRestored` event rewinds the replay to the restored point, so events the restore
+/// rolled back do not count. Orders created before the log existed have no events and are
+/// not checked.
+pub async fn replay(db: &SqlitePool) -> Result<ReplayReport> {
+    let mut report = ReplayReport::default();
+    let mut applied: Vec<(u64, DomainEvent)> = Vec::new();
+
+    loop {
+        let page = read(db, report.last_seq, REPLAY_PAGE_SIZE).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        report.last_seq = last.seq;
+
+        for logged in page {
+            report.events += 1;
+            let event = Some(&logged)
+                .filter(|logged| payload_hash(&logged.payload.to_string()) == logged.payload_hash)
+                .and_then(|logged| serde_json::from_value::<DomainEvent>(logged.payload.clone()).ok());
+            let Some(event) = event else {
+                report.corrupt_events.push(logged.seq);
+                continue;
+            };
+            if let DomainEvent::BackupRestored { through_seq, .. } = event {
+                applied.retain(|(seq, _)| *seq <= through_seq);
+            }
+            applied.push((logged.seq, event));
+        }
+    }
+
+    let mut state = ReplayedState::default();
+    for (_, event) in &applied {
+        state.apply(event);
+    }
+    report.orders = state.order_statuses.len();
+    report.finalized_batches = state.batch_roots.len();
+    report.paid_claims = state.paid_claims.len();
+    report.divergences = compare(db, &state).await?;
+    Ok(report)
+}
+
+async fn compare(db: &SqlitePool, state: &ReplayedState) -> Result<Vec<Divergence>> {
+    let mut divergences = Vec::new();
+    let mut diverged = |subject: String, field: &'static str, replayed: String, stored: String| {
+        if replayed != stored {
+            divergences.push(Divergence { subject, field, replayed, stored });
+        }
+    };
+
+    let stored_statuses: HashMap<String, i32> = sqlx::query("SELECT id, status FROM orders")
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(|row| Ok((row.try_get("id")?, row.try_get("status")?)))
+        .collect::<Result<_>>()?;
+    for (order_id, status) in &state.order_statuses {
+        let stored = stored_statuses.get(order_id)
+            .map_or_else(|| "missing".to_string(), |status| format!("{:?}", OrderStatus::from(*status)));
+        diverged(order_id.clone(), "status", format!("{:?}", status), stored);
+    }
+
+    for (batch_id, (state_root, orders_root)) in &state.batch_roots {
+        let row = sqlx::query(
+            r#"
+            SELECT state_root, orders_root FROM batch_snapshots WHERE batch_id = ?1
+            UNION ALL
+            SELECT state_root, orders_root FROM batch_snapshot_archive WHERE batch_id = ?1
+            "#,
+        )
+        .bind(*batch_id as i64)
+        .fetch_optional(db)
+        .await?;
+        let (stored_state_root, stored_orders_root) = match row {
+            Some(row) => (row.try_get("state_root")?, row.try_get("orders_root")?),
+            None => ("missing".to_string(), "missing".to_string()),
+        };
+        diverged(format!("batch {}", batch_id), "state_root", state_root.clone(), stored_state_root);
+        diverged(format!("batch {}", batch_id), "orders_root", orders_root.clone(), stored_orders_root);
+    }
+
+    for (claim_id, transaction_hash) in &state.paid_claims {
+        let stored: Option<String> = sqlx::query_scalar("SELECT transaction_hash FROM claims WHERE id = ?1 AND status = 'confirmed'")
+            .bind(claim_id)
+            .fetch_optional(db)
+            .await?
+            .flatten();
+        diverged(claim_id.clone(), "transaction_hash", transaction_hash.clone(), stored.unwrap_or_else(|| "unconfirmed".to_string()));
+    }
+
+    Ok(divergences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{self, helpers};
+
+    async fn setup_db() -> SqlitePool {
+        let db = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        database::run_migrations(&db).await.unwrap();
+        db
+    }
+
+    fn bridge_in_order(id: &str, status: OrderStatus) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::BridgeIn,
+            status,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_append_assigns_sequence_numbers_and_hashes() {
+        let db = setup_db().await;
+        let locked = DomainEvent::OrderLocked {
+            order_id: "order_1".to_string(),
+            filler_id: "filler_1".to_string(),
+            locked_amount: "1000".to_string(),
+        };
+        let first = append(&db, &DomainEvent::status_change("order_1", OrderStatus::Pending, OrderStatus::Discovery, None)).await.unwrap();
+        let second = append(&db, &locked).await.unwrap();
+        assert!(second > first);
+        assert_eq!(last_seq(&db).await.unwrap(), second);
+
+        let events = read(&db, first, 10).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].seq, second);
+        assert_eq!(events[0].kind, "order_locked");
+        assert_eq!(events[0].subject, "order_1");
+        assert_eq!(events[0].payload_hash, payload_hash(&serde_json::to_value(&locked).unwrap().to_string()));
+        assert_eq!(serde_json::from_value::<DomainEvent>(events[0].payload.clone()).unwrap(), locked);
+    }
+
+    #[tokio::test]
+    async fn test_events_cannot_be_rewritten() {
+        let db = setup_db().await;
+        append(&db, &DomainEvent::ClaimPaid { claim_id: "claim_1".to_string(), transaction_hash: "0x01".to_string() }).await.unwrap();
+
+        assert!(sqlx::query("UPDATE events SET payload = '{}'").execute(&db).await.is_err());
+        assert!(sqlx::query("DELETE FROM events").execute(&db).await.is_err());
+        assert_eq!(read(&db, 0, 10).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_detects_unlogged_changes() {
+        let db = setup_db().await;
+        helpers::insert_order(&db, &bridge_in_order("order_1", OrderStatus::Pending)).await.unwrap();
+        sqlx::query("UPDATE orders SET status = ? WHERE id = 'order_1'")
+            .bind(OrderStatus::Discovery as i32)
+            .execute(&db)
+            .await
+            .unwrap();
+        helpers::record_status_transition(&db, "order_1", OrderStatus::Pending, OrderStatus::Discovery, None).await.unwrap();
+
+        let report = replay(&db).await.unwrap();
+        assert!(report.passed(), "{}", report);
+        assert_eq!(report.orders, 1);
+        assert_eq!(report.events, 2);
+
+        // A change that bypassed the log
+        sqlx::query("UPDATE orders SET status = ? WHERE id = 'order_1'")
+            .bind(OrderStatus::Failed as i32)
+            .execute(&db)
+            .await
+            .unwrap();
+        let report = replay(&db).await.unwrap();
+        assert!(!report.passed());
+        assert_eq!(report.divergences, vec![Divergence {
+            subject: "order_1".to_string(),
+            field: "status",
+            replayed: "Discovery".to_string(),
+            stored: "Failed".to_string(),
+        }]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_rewinds_to_restored_backups() {
+        let db = setup_db().await;
+        helpers::insert_order(&db, &bridge_in_order("order_1", OrderStatus::Discovery)).await.unwrap();
+        let through_seq = last_seq(&db).await.unwrap();
+
+        // Locked after the backup, then the backup was restored
+        append(&db, &DomainEvent::OrderLocked {
+            order_id: "order_1".to_string(),
+            filler_id: "filler_1".to_string(),
+            locked_amount: "1".to_string(),
+        })
+        .await
+        .unwrap();
+        append(&db, &DomainEvent::BackupRestored { backup_id: 1, through_seq }).await.unwrap();
+
+        let report = replay(&db).await.unwrap();
+        assert!(report.passed(), "{}", report);
+        assert_eq!(report.events, 3);
+    }
+}
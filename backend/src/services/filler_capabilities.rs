@@ -0,0 +1,273 @@
+use anyhow::Result;
+use chrono::{DateTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+
+use crate::services::payment_proofs::PaymentRail;
+
+/// What a filler declared it will fill, at registration
+///
+/// Empty lists and unset bounds accept anything, so a filler that never registered is unrestricted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FillerCapabilities {
+    /// Bank services the filler pays out through, e.g. "PayPal Hong Kong"
+    #[serde(default)]
+    pub bank_services: Vec<String>,
+    /// Tokens the filler takes, per payment rail
+    #[serde(default)]
+    pub corridors: Vec<Corridor>,
+    #[serde(default)]
+    pub min_order_amount: Option<u64>,
+    #[serde(default)]
+    pub max_order_amount: Option<u64>,
+    /// When the filler locks orders; unset means around the clock
+    #[serde(default)]
+    pub operating_hours: Option<OperatingHours>,
+    /// Orders the filler takes without reviewing them, ranked first by the matching engine
+    #[serde(default)]
+    pub auto_accept: Vec<AutoAcceptRule>,
+}
+
+/// A token paid out over a payment rail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Corridor {
+    pub token_id: u32,
+    pub rail: PaymentRail,
+}
+
+/// UTC hours `[start_hour, end_hour)`, wrapping past midnight when `start_hour > end_hour`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperatingHours {
+    pub start_hour: u8,
+    pub end_hour: u8,
+}
+
+impl OperatingHours {
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let hour = at.hour() as u8;
+        if self.start_hour <= self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+/// Orders matching every set condition are auto-accepted
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoAcceptRule {
+    #[serde(default)]
+    pub max_amount: Option<u64>,
+    /// Empty matches any bank service
+    #[serde(default)]
+    pub bank_services: Vec<String>,
+}
+
+/// An order, or a lock on it, outside what the filler registered for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(tag = "capability", rename_all = "snake_case")]
+pub enum CapabilityError {
+    #[error("filler {filler_id} does not serve bank service {bank_service:?}")]
+    BankService { filler_id: String, bank_service: Option<String> },
+    #[error("filler {filler_id} has no corridor for token {token_id} over bank service {bank_service:?}")]
+    Corridor { filler_id: String, token_id: u32, bank_service: Option<String> },
+    #[error("filler {filler_id} takes orders from {min}, order is {amount}")]
+    BelowMinimum { filler_id: String, amount: u64, min: u64 },
+    #[error("filler {filler_id} takes orders up to {max}, order is {amount}")]
+    AboveMaximum { filler_id: String, amount: u64, max: u64 },
+    #[error("filler {filler_id} operates from {start_hour}:00 to {end_hour}:00 UTC")]
+    OutsideOperatingHours { filler_id: String, start_hour: u8, end_hour: u8 },
+}
+
+fn serves_bank_service(services: &[String], bank_service: Option<&str>) -> bool {
+    services.is_empty()
+        || bank_service.is_some_and(|bank_service| services.iter().any(|service| service.eq_ignore_ascii_case(bank_service)))
+}
+
+impl FillerCapabilities {
+    /// Reject declarations that could never match an order
+    pub fn validate(&self) -> Result<(), String> {
+        if let (Some(min), Some(max)) = (self.min_order_amount, self.max_order_amount) {
+            if min > max {
+                return Err(format!("min_order_amount {} is above max_order_amount {}", min, max));
+            }
+        }
+        if let Some(hours) = self.operating_hours {
+            if hours.start_hour > 23 || hours.end_hour > 23 || hours.start_hour == hours.end_hour {
+                return Err("operating_hours must be two different hours from 0 to 23".to_string());
+            }
+        }
+        if self.bank_services.iter().chain(self.auto_accept.iter().flat_map(|rule| &rule.bank_services)).any(|service| service.trim().is_empty()) {
+            return Err("bank services cannot be empty".to_string());
+        }
+        Ok(())
+    }
+
+    /// Check an order's token, amount and bank service against the declaration
+    pub fn check_order(&self, filler_id: &str, token_id: u32, amount: u64, bank_service: Option<&str>) -> Result<(), CapabilityError> {
+        if !serves_bank_service(&self.bank_services, bank_service) {
+            return Err(CapabilityError::BankService {
+                filler_id: filler_id.to_string(),
+                bank_service: bank_service.map(str::to_string),
+            });
+        }
+
+        if !self.corridors.is_empty() {
+            let rail = bank_service.and_then(PaymentRail::for_bank_service);
+            if !self.corridors.iter().any(|corridor| corridor.token_id == token_id && Some(corridor.rail) == rail) {
+                return Err(CapabilityError::Corridor {
+                    filler_id: filler_id.to_string(),
+                    token_id,
+                    bank_service: bank_service.map(str::to_string),
+                });
+            }
+        }
+
+        if let Some(min) = self.min_order_amount.filter(|min| amount < *min) {
+            return Err(CapabilityError::BelowMinimum { filler_id: filler_id.to_string(), amount, min });
+        }
+        if let Some(max) = self.max_order_amount.filter(|max| amount > *max) {
+            return Err(CapabilityError::AboveMaximum { filler_id: filler_id.to_string(), amount, max });
+        }
+        Ok(())
+    }
+
+    /// Whether the filler takes orders for this token over this bank service at some amount
+    pub fn serves(&self, token_id: u32, bank_service: Option<&str>) -> bool {
+        !matches!(
+            self.check_order("", token_id, self.min_order_amount.unwrap_or(0), bank_service),
+            Err(CapabilityError::BankService { .. } | CapabilityError::Corridor { .. })
+        )
+    }
+
+    pub fn check_hours(&self, filler_id: &str, at: DateTime<Utc>) -> Result<(), CapabilityError> {
+        match self.operating_hours {
+            Some(hours) if !hours.contains(at) => Err(CapabilityError::OutsideOperatingHours {
+                filler_id: filler_id.to_string(),
+                start_hour: hours.start_hour,
+                end_hour: hours.end_hour,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn auto_accepts(&self, amount: u64, bank_service: Option<&str>) -> bool {
+        self.auto_accept.iter().any(|rule| {
+            rule.max_amount.is_none_or(|max| amount <= max) && serves_bank_service(&rule.bank_services, bank_service)
+        })
+    }
+}
+
+/// Register or replace a filler's capabilities
+pub async fn save(db: &SqlitePool, filler_id: &str, capabilities: &FillerCapabilities) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO filler_capabilities (filler_id, capabilities, updated_at)
+        VALUES (?1, ?2, ?3)
+        ON CONFLICT(filler_id) DO UPDATE SET capabilities = ?2, updated_at = ?3
+        "#,
+    )
+    .bind(filler_id)
+    .bind(serde_json::to_string(capabilities)?)
+    .bind(Utc::now())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn load(db: &SqlitePool, filler_id: &str) -> Result<Option<FillerCapabilities>> {
+    let row = sqlx::query("SELECT capabilities FROM filler_capabilities WHERE filler_id = ?")
+        .bind(filler_id)
+        .fetch_optional(db)
+        .await?;
+
+    row.map(|row| Ok(serde_json::from_str(&row.try_get::<String, _>("capabilities")?)?))
+        .transpose()
+}
+
+/// Every registered filler's capabilities, to seed the matching engine on startup
+pub async fn load_all(db: &SqlitePool) -> Result<HashMap<String, FillerCapabilities>> {
+    let rows = sqlx::query("SELECT filler_id, capabilities FROM filler_capabilities")
+        .fetch_all(db)
+        .await?;
+
+    rows.iter()
+        .map(|row| {
+            let capabilities = serde_json::from_str(&row.try_get::<String, _>("capabilities")?)?;
+            Ok((row.try_get("filler_id")?, capabilities))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn capabilities() -> FillerCapabilities {
+        FillerCapabilities {
+            bank_services: vec!["PayPal Hong Kong".to_string(), "Wise".to_string()],
+            corridors: vec![Corridor { token_id: 1, rail: PaymentRail::Paypal }, Corridor { token_id: 2, rail: PaymentRail::Wise }],
+            min_order_amount: Some(100),
+            max_order_amount: Some(10_000),
+            operating_hours: Some(OperatingHours { start_hour: 22, end_hour: 6 }),
+            auto_accept: vec![AutoAcceptRule { max_amount: Some(500), bank_services: vec!["Wise".to_string()] }],
+        }
+    }
+
+    #[test]
+    fn test_check_order() {
+        let caps = capabilities();
+        assert_eq!(caps.check_order("f", 1, 100, Some("paypal hong kong")), Ok(()));
+        assert_eq!(caps.check_order("f", 2, 10_000, Some("Wise")), Ok(()));
+
+        assert!(matches!(caps.check_order("f", 1, 500, Some("ACH")), Err(CapabilityError::BankService { .. })));
+        assert!(matches!(caps.check_order("f", 1, 500, None), Err(CapabilityError::BankService { .. })));
+        assert!(matches!(caps.check_order("f", 2, 500, Some("PayPal Hong Kong")), Err(CapabilityError::Corridor { .. })));
+        assert!(matches!(caps.check_order("f", 1, 99, Some("PayPal Hong Kong")), Err(CapabilityError::BelowMinimum { min: 100, .. })));
+        assert!(matches!(caps.check_order("f", 2, 10_001, Some("Wise")), Err(CapabilityError::AboveMaximum { max: 10_000, .. })));
+
+        // Nothing declared accepts anything
+        assert_eq!(FillerCapabilities::default().check_order("f", 7, 1, None), Ok(()));
+    }
+
+    #[test]
+    fn test_operating_hours_wrap_midnight() {
+        let caps = capabilities();
+        let at = |hour| Utc.with_ymd_and_hms(2026, 1, 1, hour, 30, 0).unwrap();
+        assert!(caps.check_hours("f", at(23)).is_ok());
+        assert!(caps.check_hours("f", at(5)).is_ok());
+        assert!(matches!(caps.check_hours("f", at(6)), Err(CapabilityError::OutsideOperatingHours { .. })));
+        assert!(caps.check_hours("f", at(12)).is_err());
+
+        let office = OperatingHours { start_hour: 9, end_hour: 17 };
+        assert!(office.contains(at(9)) && office.contains(at(16)) && !office.contains(at(17)));
+    }
+
+    #[test]
+    fn test_auto_accept_and_validation() {
+        let caps = capabilities();
+        assert!(caps.auto_accepts(500, Some("wise")));
+        assert!(!caps.auto_accepts(501, Some("Wise")));
+        assert!(!caps.auto_accepts(100, Some("PayPal Hong Kong")));
+        assert!(caps.validate().is_ok());
+
+        let inverted = FillerCapabilities { min_order_amount: Some(10), max_order_amount: Some(1), ..Default::default() };
+        assert!(inverted.validate().is_err());
+        let no_hours = FillerCapabilities { operating_hours: Some(OperatingHours { start_hour: 8, end_hour: 8 }), ..Default::default() };
+        assert!(no_hours.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_are_persisted() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        assert!(load(&db, "filler_1").await.unwrap().is_none());
+
+        save(&db, "filler_1", &FillerCapabilities::default()).await.unwrap();
+        save(&db, "filler_1", &capabilities()).await.unwrap();
+        assert_eq!(load(&db, "filler_1").await.unwrap(), Some(capabilities()));
+        assert_eq!(load_all(&db).await.unwrap().len(), 1);
+    }
+}
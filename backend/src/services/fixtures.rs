@@ -0,0 +1,251 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use vapor_core::proof_format::{parse_hash32, to_hex32, FormattedProof, ProofError, ProofFormat};
+use crate::merkle::MerkleTreeManager;
+use crate::models::{AccountState, Order, OrderStatus, OrderType, TokenBalance};
+use crate::services::proof_cache::{build_account_proof, build_order_proofs};
+
+/// Batch id the fixtures are generated for unless another is requested
+pub const DEFAULT_FIXTURE_BATCH_ID: u64 = 1;
+
+/// Hash test vectors for the contracts' Foundry tests
+///
+/// Built from a fixed set of orders and accounts, so the output only changes when the
+/// backend's hashing or tree layout does. Every leaf carries its preimage, so the Solidity
+/// side can check `keccak256(preimage) == leaf_hash` as well as the proofs.
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationFixtures {
+    pub batch_id: u64,
+    pub order_tree: OrderTreeFixture,
+    pub account_tree: AccountTreeFixture,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderTreeFixture {
+    /// Root of the positional (sparse) order tree
+    pub root: String,
+    /// Root of the sorted-pair tree over the same leaves, as VaporBridge verifies claims
+    pub sorted_pairs_root: String,
+    pub orders: Vec<OrderFixture>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderFixture {
+    pub index: usize,
+    pub order_id: String,
+    pub order_type: u8,
+    pub from_address: Option<String>,
+    pub to_address: Option<String>,
+    pub token_id: u32,
+    pub amount: String,
+    pub leaf_preimage: String,
+    pub leaf_hash: String,
+    /// Positional proof with path bits (leaf to root)
+    pub proof: FormattedProof,
+    pub sorted_pairs_proof: FormattedProof,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountTreeFixture {
+    pub root: String,
+    pub accounts: Vec<AccountFixture>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountFixture {
+    pub address: String,
+    pub balances: Vec<TokenBalance>,
+    pub leaf_preimage: String,
+    pub leaf_hash: String,
+    /// Positional proof with path bits (leaf to root)
+    pub proof: FormattedProof,
+}
+
+/// Build the fixtures for the sample orders and accounts
+pub fn generate(batch_id: u64) -> Result<VerificationFixtures, ProofError> {
+    let orders = sample_orders();
+    let indices: Vec<usize> = (0..orders.len()).collect();
+    let proofs = build_order_proofs(&orders, batch_id, &indices, ProofFormat::Raw)?;
+    let sorted_pairs_proofs = build_order_proofs(&orders, batch_id, &indices, ProofFormat::SortedPairs)?;
+
+    let order_tree = OrderTreeFixture {
+        root: proofs[0].root.clone(),
+        sorted_pairs_root: sorted_pairs_proofs[0].root.clone(),
+        orders: orders.iter()
+            .zip(proofs.into_iter().zip(sorted_pairs_proofs))
+            .enumerate()
+            .map(|(index, (order, (proof, sorted_pairs_proof)))| {
+                Ok(OrderFixture {
+                    index,
+                    order_id: order.id.clone(),
+                    order_type: order.order_type as u8,
+                    from_address: order.from_address.clone(),
+                    to_address: order.to_address.clone(),
+                    token_id: order.token_id,
+                    amount: order.amount.clone(),
+                    leaf_preimage: format!("0x{}", hex::encode(order.leaf_preimage_with_batch_id(batch_id))),
+                    leaf_hash: to_hex32(&order.hash_leaf_with_batch_id(batch_id).map_err(ProofError::Tree)?),
+                    proof,
+                    sorted_pairs_proof,
+                })
+            })
+            .collect::<Result<_, ProofError>>()?,
+    };
+
+    let accounts = sample_accounts();
+    let mut manager = MerkleTreeManager::new();
+    let state_root = manager.build_state_tree(&accounts).map_err(ProofError::Tree)?;
+    let account_tree = AccountTreeFixture {
+        root: to_hex32(&parse_hash32(&state_root)?),
+        accounts: accounts.iter()
+            .map(|account| {
+                let proof = with_hex_prefix(build_account_proof(&mut manager, &account.address, ProofFormat::Raw)?)?;
+                Ok(AccountFixture {
+                    address: account.address.clone(),
+                    balances: account.balances.clone(),
+                    leaf_preimage: format!("0x{}", hex::encode(account.leaf_preimage())),
+                    leaf_hash: proof.leaf_hash.clone(),
+                    proof,
+                })
+            })
+            .collect::<Result<_, ProofError>>()?,
+    };
+
+    Ok(VerificationFixtures { batch_id, order_tree, account_tree })
+}
+
+/// Account proofs come out of the sparse tree without the 0x prefix that order proofs carry
+fn with_hex_prefix(proof: FormattedProof) -> Result<FormattedProof, ProofError> {
+    Ok(FormattedProof {
+        leaf_hash: to_hex32(&parse_hash32(&proof.leaf_hash)?),
+        proof: proof.proof.iter()
+            .map(|sibling| Ok(to_hex32(&parse_hash32(sibling)?)))
+            .collect::<Result<_, ProofError>>()?,
+        root: to_hex32(&parse_hash32(&proof.root)?),
+        ..proof
+    })
+}
+
+const ALICE: &str = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+const BOB: &str = "0x3c44cdddb6a900fa2b585dd299e03d12fa4293bc";
+const CAROL: &str = "0x90f79bf6eb2c4f870365e785982e1f101e93b906";
+
+/// One order of each type, with fixed ids so leaf hashes are stable
+fn sample_orders() -> Vec<Order> {
+    let order = |id: &str, order_type, from: Option<&str>, to: Option<&str>, token_id, amount: &str| Order {
+        id: id.to_string(),
+        order_type,
+        from_address: from.map(str::to_string),
+        to_address: to.map(str::to_string),
+        token_id,
+        amount: amount.to_string(),
+        bank_account: None,
+        bank_service: None,
+        banking_hash: None,
+        filler_id: None,
+        locked_amount: None,
+        status: OrderStatus::Settled,
+        batch_id: None,
+        created_at: fixed_time(),
+        updated_at: fixed_time(),
+    };
+
+    vec![
+        order("01J0000000000000000000000A", OrderType::BridgeIn, Some(ALICE), Some(ALICE), 1, "1000000"),
+        order("01J0000000000000000000000B", OrderType::Transfer, Some(ALICE), Some(BOB), 1, "250000"),
+        order("01J0000000000000000000000C", OrderType::BridgeOut, Some(BOB), Some(CAROL), 1, "100000"),
+        order("01J0000000000000000000000D", OrderType::BridgeIn, Some(CAROL), Some(CAROL), 2, "5000000"),
+        order("01J0000000000000000000000E", OrderType::Transfer, Some(CAROL), Some(ALICE), 2, "1"),
+    ]
+}
+
+/// Balances after the sample orders
+fn sample_accounts() -> Vec<AccountState> {
+    let account = |address: &str, balances: &[(u32, &str)]| AccountState {
+        address: address.to_string(),
+        balances: balances.iter()
+            .map(|&(token_id, balance)| TokenBalance { token_id, balance: balance.to_string() })
+            .collect(),
+        updated_at: fixed_time(),
+    };
+
+    vec![
+        account(ALICE, &[(1, "750000"), (2, "1")]),
+        account(BOB, &[(1, "150000")]),
+        account(CAROL, &[(2, "4999999")]),
+    ]
+}
+
+fn fixed_time() -> DateTime<Utc> {
+    DateTime::<Utc>::UNIX_EPOCH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vapor_core::leaf_encoding::{AccountLeafFields, OrderLeafFields};
+    use vapor_core::proof_format::{process_raw_proof, process_sorted_proof};
+    use sha3::{Digest, Keccak256};
+
+    fn fold(proof: &FormattedProof) -> [u8; 32] {
+        let leaf = parse_hash32(&proof.leaf_hash).unwrap();
+        let siblings: Vec<[u8; 32]> = proof.proof.iter().map(|sibling| parse_hash32(sibling).unwrap()).collect();
+        match proof.format {
+            ProofFormat::SortedPairs => process_sorted_proof(leaf, &siblings),
+            _ => process_raw_proof(leaf, &siblings, proof.path_bits.as_ref().unwrap()).unwrap(),
+        }
+    }
+
+    fn keccak_hex(preimage: &str) -> String {
+        let bytes = hex::decode(preimage.trim_start_matches("0x")).unwrap();
+        to_hex32(&Keccak256::digest(bytes).into())
+    }
+
+    #[test]
+    fn test_fixtures_verify_against_their_roots() {
+        let fixtures = generate(DEFAULT_FIXTURE_BATCH_ID).unwrap();
+
+        assert_eq!(fixtures.order_tree.orders.len(), 5);
+        for order in &fixtures.order_tree.orders {
+            assert_eq!(keccak_hex(&order.leaf_preimage), order.leaf_hash);
+            assert_eq!(order.proof.leaf_hash, order.leaf_hash);
+            assert_eq!(to_hex32(&fold(&order.proof)), fixtures.order_tree.root);
+            assert_eq!(to_hex32(&fold(&order.sorted_pairs_proof)), fixtures.order_tree.sorted_pairs_root);
+        }
+
+        assert_eq!(fixtures.account_tree.accounts.len(), 3);
+        for account in &fixtures.account_tree.accounts {
+            assert_eq!(keccak_hex(&account.leaf_preimage), account.leaf_hash);
+            assert_eq!(to_hex32(&fold(&account.proof)), fixtures.account_tree.root);
+        }
+
+    }
+
+    #[test]
+    fn test_published_fields_recompute_the_leaves() {
+        // vapor-verify recomputes leaves from these fields rather than from the models
+        let fixtures = serde_json::to_value(generate(DEFAULT_FIXTURE_BATCH_ID).unwrap()).unwrap();
+
+        for order in fixtures["order_tree"]["orders"].as_array().unwrap() {
+            let fields: OrderLeafFields = serde_json::from_value(order.clone()).unwrap();
+            assert_eq!(to_hex32(&fields.leaf_hash(DEFAULT_FIXTURE_BATCH_ID)), order["leaf_hash"]);
+        }
+        for account in fixtures["account_tree"]["accounts"].as_array().unwrap() {
+            let fields: AccountLeafFields = serde_json::from_value(account.clone()).unwrap();
+            assert_eq!(to_hex32(&fields.leaf_hash()), account["leaf_hash"]);
+        }
+    }
+
+    #[test]
+    fn test_fixtures_are_deterministic_per_batch() {
+        let first = serde_json::to_value(generate(7).unwrap()).unwrap();
+        let second = serde_json::to_value(generate(7).unwrap()).unwrap();
+        assert_eq!(first, second);
+
+        // Order leaves commit to the batch id, account leaves do not
+        let other = generate(8).unwrap();
+        assert_ne!(first["order_tree"]["root"], serde_json::json!(other.order_tree.root));
+        assert_eq!(first["account_tree"]["root"], serde_json::json!(other.account_tree.root));
+    }
+}
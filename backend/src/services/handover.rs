@@ -0,0 +1,532 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+
+use crate::config::HandoverConfig;
+use crate::merkle::MerkleTreeManager;
+use crate::models::OrderStatus;
+use crate::services::{chain_checkpoint, event_log, maintenance::MaintenanceMode};
+
+/// Manifests of another format are refused
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// What a new backend instance needs to take over writing from the one it replaces
+///
+/// The old instance exports it once frozen. The new instance re-reads the same facts from the
+/// shared database and only takes the writer lease if they are unchanged, so nothing written
+/// after the freeze can be lost or forked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandoverManifest {
+    pub version: u32,
+    /// Instance that froze and exported
+    pub from_instance: String,
+    pub created_at: DateTime<Utc>,
+    /// Last finalized batch and its roots; batch 0 with the empty roots before the first batch
+    pub batch_id: u64,
+    pub state_root: String,
+    pub orders_root: String,
+    /// Batch left open, recovered from the batch journal by the new instance
+    pub open_batch_id: Option<u64>,
+    /// Finalized batches still waiting for their proof, oldest first
+    pub queued_for_proof: Vec<u64>,
+    /// Last block the relayer applied
+    pub relayer_block: Option<u64>,
+    pub last_event_seq: u64,
+    /// Orders not yet settled or failed, by id
+    pub open_orders: Vec<OpenOrder>,
+    /// Keccak-256 of the manifest's JSON with an empty digest
+    pub digest: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenOrder {
+    pub order_id: String,
+    pub status: OrderStatus,
+}
+
+impl HandoverManifest {
+    /// Read the state to hand over from the database, as of now
+    pub async fn capture(
+        db: &SqlitePool,
+        from_instance: &str,
+        open_batch_id: Option<u64>,
+        queued_for_proof: Vec<u64>,
+        now: DateTime<Utc>,
+    ) -> Result<Self> {
+        let (batch_id, state_root, orders_root) = latest_batch(db).await?
+            .unwrap_or_else(|| (0, MerkleTreeManager::empty_state_root(), MerkleTreeManager::empty_orders_root()));
+
+        let open_orders = sqlx::query("SELECT id, status FROM orders WHERE status NOT IN (?1, ?2) ORDER BY id")
+            .bind(OrderStatus::Settled as i32)
+            .bind(OrderStatus::Failed as i32)
+            .fetch_all(db)
+            .await?
+            .into_iter()
+            .map(|row| Ok(OpenOrder {
+                order_id: row.try_get("id")?,
+                status: OrderStatus::from(row.try_get::<i32, _>("status")?),
+            }))
+            .collect::<Result<_>>()?;
+
+        let mut manifest = Self {
+            version: MANIFEST_VERSION,
+            from_instance: from_instance.to_string(),
+            created_at: now,
+            batch_id,
+            state_root,
+            orders_root,
+            open_batch_id,
+            queued_for_proof,
+            relayer_block: chain_checkpoint::load(db).await?.map(|checkpoint| checkpoint.block_number),
+            last_event_seq: event_log::last_seq(db).await?,
+            open_orders,
+            digest: String::new(),
+        };
+        manifest.digest = manifest.compute_digest();
+        Ok(manifest)
+    }
+
+    pub fn compute_digest(&self) -> String {
+        let unsigned = Self { digest: String::new(), ..self.clone() };
+        event_log::payload_hash(&serde_json::json!(unsigned).to_string())
+    }
+
+    /// Check the manifest is intact and still describes the database
+    pub async fn validate(&self, db: &SqlitePool) -> Result<(), HandoverError> {
+        if self.version != MANIFEST_VERSION {
+            return Err(HandoverError::Version(self.version));
+        }
+        if self.digest != self.compute_digest() {
+            return Err(HandoverError::Digest);
+        }
+
+        let current = Self::capture(db, &self.from_instance, self.open_batch_id, self.queued_for_proof.clone(), self.created_at).await?;
+        let diverged = |field: &'static str, manifest: String, current: String| {
+            Err(HandoverError::Diverged { field, manifest, current })
+        };
+        if (self.batch_id, &self.state_root, &self.orders_root) != (current.batch_id, &current.state_root, &current.orders_root) {
+            return diverged("batch", format!("{} ({})", self.batch_id, self.state_root), format!("{} ({})", current.batch_id, current.state_root));
+        }
+        if self.relayer_block != current.relayer_block {
+            return diverged("relayer_block", format!("{:?}", self.relayer_block), format!("{:?}", current.relayer_block));
+        }
+        if self.last_event_seq != current.last_event_seq {
+            return diverged("last_event_seq", self.last_event_seq.to_string(), current.last_event_seq.to_string());
+        }
+        if self.open_orders != current.open_orders {
+            return diverged("open_orders", format!("{} orders", self.open_orders.len()), format!("{} orders", current.open_orders.len()));
+        }
+        Ok(())
+    }
+}
+
+/// Last finalized batch with its roots, hot or archived
+pub async fn latest_batch(db: &SqlitePool) -> Result<Option<(u64, String, String)>> {
+    let row = sqlx::query(
+        r#"
+        SELECT batch_id, state_root, orders_root FROM batch_snapshots
+        UNION ALL
+        SELECT batch_id, state_root, orders_root FROM batch_snapshot_archive
+        ORDER BY batch_id DESC LIMIT 1
+        "#,
+    )
+    .fetch_optional(db)
+    .await?;
+
+    row.map(|row| Ok((row.try_get::<i64, _>("batch_id")? as u64, row.try_get("state_root")?, row.try_get("orders_root")?)))
+        .transpose()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HandoverError {
+    #[error("this instance is not the writer ({0})")]
+    NotWriter(WriterRole),
+    #[error("this instance is not on standby ({0})")]
+    NotStandby(WriterRole),
+    #[error("unsupported handover manifest version {0}")]
+    Version(u32),
+    #[error("handover manifest digest does not match its contents")]
+    Digest,
+    #[error("no handover is pending for manifest {0}")]
+    NotPending(String),
+    #[error("{field} changed since the handover manifest was taken: {manifest} in the manifest, {current} now")]
+    Diverged { field: &'static str, manifest: String, current: String },
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for HandoverError {
+    fn from(e: sqlx::Error) -> Self {
+        HandoverError::Other(e.into())
+    }
+}
+
+/// Whether this instance may write, as decided by the writer lease
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "role", rename_all = "snake_case")]
+pub enum WriterRole {
+    /// The lease is not in use (`WRITER_LEASE_SECONDS=0`) or not taken yet; the instance writes
+    Unleased,
+    Writer,
+    /// Another instance holds the lease
+    Standby { holder: String },
+    /// This instance exported a handover manifest and no longer writes
+    HandedOver { digest: String },
+    /// This instance held the lease but could not renew it before it expired, so another
+    /// instance may already be writing
+    Lapsed,
+}
+
+impl WriterRole {
+    pub fn accepts_writes(&self) -> bool {
+        matches!(self, WriterRole::Unleased | WriterRole::Writer)
+    }
+}
+
+impl std::fmt::Display for WriterRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriterRole::Unleased => write!(f, "unleased"),
+            WriterRole::Writer => write!(f, "writer"),
+            WriterRole::Standby { holder } => write!(f, "standby, {} holds the lease", holder),
+            WriterRole::HandedOver { digest } => write!(f, "handed over with manifest {}", digest),
+            WriterRole::Lapsed => write!(f, "lapsed, the writer lease could not be renewed in time"),
+        }
+    }
+}
+
+/// The writer lease as recorded in the shared database, as reported by /health
+#[derive(Debug, Clone, Serialize)]
+pub struct LeaseStatus {
+    pub instance_id: String,
+    #[serde(flatten)]
+    pub role: WriterRole,
+    pub lease_seconds: i64,
+    /// Instance holding the lease and when it lapses unless renewed; `None` until first taken
+    pub leader: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Last renewal (heartbeat) by the leader
+    pub heartbeat_at: Option<DateTime<Utc>>,
+}
+
+/// Lease on the single writer role, kept in the shared database
+///
+/// The writer renews it every third of `WRITER_LEASE_SECONDS`. Another instance only takes it
+/// once it lapses, or by importing the handover manifest the writer exported; while a handover
+/// is pending, lapsing is not enough. Instances without the lease hold maintenance locally, so
+/// writes are refused and background processing idles. A writer that cannot renew in time
+/// fences itself off as `Lapsed` rather than keep writing past its lease.
+#[derive(Clone)]
+pub struct WriterLease {
+    db: SqlitePool,
+    instance_id: String,
+    lease: Duration,
+    maintenance: MaintenanceMode,
+    role: Arc<RwLock<WriterRole>>,
+    /// When our own lease lapses, as of the last successful renewal
+    expires_at: Arc<RwLock<Option<DateTime<Utc>>>>,
+    /// Serializes taking up the writer role, by import or by the lease lapsing
+    transitions: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl WriterLease {
+    pub fn new(db: SqlitePool, config: &HandoverConfig, maintenance: MaintenanceMode) -> Self {
+        Self {
+            db,
+            instance_id: config.instance_id.clone(),
+            lease: Duration::seconds(config.lease_seconds as i64),
+            maintenance,
+            role: Arc::new(RwLock::new(WriterRole::Unleased)),
+            expires_at: Arc::new(RwLock::new(None)),
+            transitions: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.lease > Duration::zero()
+    }
+
+    /// How often `refresh` should run
+    pub fn renew_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs((self.lease.num_seconds() as u64 / 3).max(1))
+    }
+
+    pub fn role(&self) -> WriterRole {
+        self.role.read().unwrap().clone()
+    }
+
+    /// Held while an instance checks its role and takes up writing
+    pub async fn lock_transitions(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.transitions.lock().await
+    }
+
+    /// Take or renew the lease, e.g. on startup and then every `renew_interval`
+    pub async fn refresh(&self, now: DateTime<Utc>) -> Result<WriterRole> {
+        if !self.enabled() {
+            return Ok(WriterRole::Unleased);
+        }
+        if let role @ WriterRole::HandedOver { .. } = self.role() {
+            return Ok(role);
+        }
+
+        // Taken when free, held by us, or lapsed, unless a handover is waiting for its import
+        let taken = sqlx::query(
+            r#"
+            INSERT INTO writer_lease (id, holder, expires_at, handover_digest, handover_manifest, updated_at)
+            VALUES (1, ?1, ?2, NULL, NULL, ?3)
+            ON CONFLICT(id) DO UPDATE SET holder = ?1, expires_at = ?2, updated_at = ?3
+            WHERE writer_lease.handover_digest IS NULL
+              AND (writer_lease.holder = ?1 OR writer_lease.expires_at < ?3)
+            "#,
+        )
+        .bind(&self.instance_id)
+        .bind(now + self.lease)
+        .bind(now)
+        .execute(&self.db)
+        .await?
+        .rows_affected() > 0;
+
+        let role = if taken {
+            *self.expires_at.write().unwrap() = Some(now + self.lease);
+            WriterRole::Writer
+        } else {
+            let holder: String = sqlx::query_scalar("SELECT holder FROM writer_lease WHERE id = 1")
+                .fetch_one(&self.db)
+                .await?;
+            WriterRole::Standby { holder }
+        };
+        self.set_role(role.clone());
+        Ok(role)
+    }
+
+    /// Stop writing once our lease has expired without a successful `refresh`, e.g. while the
+    /// database is unreachable; returns whether it did
+    pub fn check_lapsed(&self, now: DateTime<Utc>) -> bool {
+        let expired = self.expires_at.read().unwrap().is_some_and(|expires_at| expires_at <= now);
+        if !expired || self.role() != WriterRole::Writer {
+            return false;
+        }
+        self.set_role(WriterRole::Lapsed);
+        true
+    }
+
+    /// The lease row with this instance's role, for health checks
+    pub async fn status(&self) -> Result<LeaseStatus> {
+        let row = sqlx::query("SELECT holder, expires_at, updated_at FROM writer_lease WHERE id = 1")
+            .fetch_optional(&self.db)
+            .await?;
+        let (leader, expires_at, heartbeat_at) = match row {
+            Some(row) => (Some(row.try_get("holder")?), Some(row.try_get("expires_at")?), Some(row.try_get("updated_at")?)),
+            None => (None, None, None),
+        };
+        Ok(LeaseStatus {
+            instance_id: self.instance_id.clone(),
+            role: self.role(),
+            lease_seconds: self.lease.num_seconds(),
+            leader,
+            expires_at,
+            heartbeat_at,
+        })
+    }
+
+    /// Record `manifest` as the pending handover and stop writing for good
+    pub async fn hand_over(&self, manifest: &HandoverManifest, now: DateTime<Utc>) -> Result<(), HandoverError> {
+        let role = self.role();
+        if role != WriterRole::Writer {
+            return Err(HandoverError::NotWriter(role));
+        }
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE writer_lease SET handover_digest = ?1, handover_manifest = ?2, updated_at = ?3
+            WHERE id = 1 AND holder = ?4 AND handover_digest IS NULL
+            "#,
+        )
+        .bind(&manifest.digest)
+        .bind(serde_json::to_string(manifest).map_err(anyhow::Error::from)?)
+        .bind(now)
+        .bind(&self.instance_id)
+        .execute(&self.db)
+        .await?
+        .rows_affected();
+        if updated == 0 {
+            return Err(HandoverError::NotWriter(role));
+        }
+
+        self.set_role(WriterRole::HandedOver { digest: manifest.digest.clone() });
+        Ok(())
+    }
+
+    /// The handover waiting to be imported, if any
+    pub async fn pending_manifest(&self) -> Result<Option<HandoverManifest>> {
+        let manifest: Option<Option<String>> = sqlx::query_scalar("SELECT handover_manifest FROM writer_lease WHERE id = 1")
+            .fetch_optional(&self.db)
+            .await?;
+        manifest.flatten().map(|manifest| Ok(serde_json::from_str(&manifest)?)).transpose()
+    }
+
+    /// Take the lease from the instance that exported `manifest`
+    pub async fn take_over(&self, manifest: &HandoverManifest, now: DateTime<Utc>) -> Result<(), HandoverError> {
+        let role = self.role();
+        if !matches!(role, WriterRole::Standby { .. }) {
+            return Err(HandoverError::NotStandby(role));
+        }
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE writer_lease SET holder = ?1, expires_at = ?2, handover_digest = NULL, handover_manifest = NULL, updated_at = ?3
+            WHERE id = 1 AND handover_digest = ?4
+            "#,
+        )
+        .bind(&self.instance_id)
+        .bind(now + self.lease)
+        .bind(now)
+        .bind(&manifest.digest)
+        .execute(&self.db)
+        .await?
+        .rows_affected();
+        if updated == 0 {
+            return Err(HandoverError::NotPending(manifest.digest.clone()));
+        }
+
+        *self.expires_at.write().unwrap() = Some(now + self.lease);
+        self.set_role(WriterRole::Writer);
+        Ok(())
+    }
+
+    /// Refuse writes while the writer prepares a handover; `thaw` undoes it if the export fails
+    pub fn freeze(&self) {
+        self.maintenance.hold(Some("Handing over to a new deployment, writes are paused".to_string()));
+    }
+
+    pub fn thaw(&self) {
+        if self.role().accepts_writes() {
+            self.maintenance.hold(None);
+        }
+    }
+
+    /// Switch roles, holding maintenance while the new role refuses writes; renewing the same
+    /// role leaves a freeze in place
+    fn set_role(&self, role: WriterRole) {
+        let previous = std::mem::replace(&mut *self.role.write().unwrap(), role.clone());
+        if previous == role {
+            return;
+        }
+        match &role {
+            WriterRole::Writer => info!("Instance {} holds the writer lease", self.instance_id),
+            role => warn!("Instance {} does not write: {}", self.instance_id, role),
+        }
+
+        self.maintenance.hold(match &role {
+            WriterRole::Unleased | WriterRole::Writer => None,
+            WriterRole::Standby { holder } => Some(format!("Instance {} holds the writer lease, this instance is on standby", holder)),
+            WriterRole::HandedOver { .. } => Some("This instance handed over to a new deployment and no longer accepts writes".to_string()),
+            WriterRole::Lapsed => Some("This instance could not renew the writer lease, writes are paused until it is re-elected".to_string()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup_db() -> SqlitePool {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        db
+    }
+
+    fn lease(db: &SqlitePool, instance_id: &str) -> WriterLease {
+        let config = HandoverConfig { lease_seconds: 30, instance_id: instance_id.to_string() };
+        WriterLease::new(db.clone(), &config, MaintenanceMode::new(db.clone()))
+    }
+
+    #[tokio::test]
+    async fn test_only_one_instance_holds_the_lease() {
+        let db = setup_db().await;
+        let (blue, green) = (lease(&db, "blue"), lease(&db, "green"));
+        let now = Utc::now();
+
+        assert_eq!(blue.refresh(now).await.unwrap(), WriterRole::Writer);
+        assert_eq!(green.refresh(now).await.unwrap(), WriterRole::Standby { holder: "blue".to_string() });
+        assert!(green.maintenance.is_enabled());
+        assert!(!blue.maintenance.is_enabled());
+
+        // Renewing keeps it; green only gets it once blue stops renewing
+        assert_eq!(blue.refresh(now + Duration::seconds(20)).await.unwrap(), WriterRole::Writer);
+        assert!(!green.refresh(now + Duration::seconds(40)).await.unwrap().accepts_writes());
+        assert_eq!(green.refresh(now + Duration::seconds(60)).await.unwrap(), WriterRole::Writer);
+        assert_eq!(blue.refresh(now + Duration::seconds(61)).await.unwrap(), WriterRole::Standby { holder: "green".to_string() });
+    }
+
+    #[tokio::test]
+    async fn test_writer_fences_itself_when_it_cannot_renew() {
+        let db = setup_db().await;
+        let (blue, green) = (lease(&db, "blue"), lease(&db, "green"));
+        let now = Utc::now();
+        assert!(blue.status().await.unwrap().leader.is_none());
+        blue.refresh(now).await.unwrap();
+        green.refresh(now).await.unwrap();
+
+        // Renewals failing before expiry change nothing; past it, blue stops writing on its own
+        assert!(!blue.check_lapsed(now + Duration::seconds(29)));
+        assert!(blue.check_lapsed(now + Duration::seconds(30)));
+        assert_eq!(blue.role(), WriterRole::Lapsed);
+        assert!(blue.maintenance.is_enabled());
+        assert!(!green.check_lapsed(now + Duration::seconds(30)));
+
+        assert_eq!(green.refresh(now + Duration::seconds(31)).await.unwrap(), WriterRole::Writer);
+        let status = green.status().await.unwrap();
+        assert_eq!(status.leader.as_deref(), Some("green"));
+        assert_eq!(status.expires_at, Some(now + Duration::seconds(61)));
+        assert_eq!(status.heartbeat_at, Some(now + Duration::seconds(31)));
+        assert_eq!(blue.refresh(now + Duration::seconds(32)).await.unwrap(), WriterRole::Standby { holder: "green".to_string() });
+        assert!(matches!(blue.status().await.unwrap().role, WriterRole::Standby { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_handover_moves_the_lease_only_with_a_valid_manifest() {
+        let db = setup_db().await;
+        let (blue, green) = (lease(&db, "blue"), lease(&db, "green"));
+        let now = Utc::now();
+        blue.refresh(now).await.unwrap();
+        green.refresh(now).await.unwrap();
+
+        let manifest = HandoverManifest::capture(&db, "blue", None, vec![], now).await.unwrap();
+        assert_eq!((manifest.batch_id, manifest.last_event_seq), (0, 0));
+        assert_eq!(manifest.state_root, MerkleTreeManager::empty_state_root());
+        blue.hand_over(&manifest, now).await.unwrap();
+        assert!(matches!(blue.role(), WriterRole::HandedOver { .. }));
+        assert_eq!(blue.pending_manifest().await.unwrap(), Some(manifest.clone()));
+
+        // A pending handover cannot be bypassed by letting the lease lapse
+        assert!(!green.refresh(now + Duration::seconds(120)).await.unwrap().accepts_writes());
+
+        let mut tampered = manifest.clone();
+        tampered.batch_id = 7;
+        assert!(matches!(tampered.validate(&db).await, Err(HandoverError::Digest)));
+
+        // Writes after the export invalidate the manifest
+        event_log::append(&db, &event_log::DomainEvent::BatchAborted { batch_id: 1, order_ids: vec![] }).await.unwrap();
+        assert!(matches!(manifest.validate(&db).await, Err(HandoverError::Diverged { field: "last_event_seq", .. })));
+
+        let manifest = HandoverManifest::capture(&db, "blue", None, vec![], now).await.unwrap();
+        manifest.validate(&db).await.unwrap();
+        assert!(matches!(green.take_over(&manifest, now).await, Err(HandoverError::NotPending(_))));
+
+        sqlx::query("UPDATE writer_lease SET handover_digest = ?1").bind(&manifest.digest).execute(&db).await.unwrap();
+        green.take_over(&manifest, now).await.unwrap();
+        assert_eq!(green.role(), WriterRole::Writer);
+        assert!(!green.maintenance.is_enabled());
+        assert!(green.pending_manifest().await.unwrap().is_none());
+        // The old instance stays out
+        assert!(!blue.refresh(now + Duration::seconds(300)).await.unwrap().accepts_writes());
+    }
+}
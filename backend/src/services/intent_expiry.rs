@@ -0,0 +1,252 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::config::SlaConfig;
+use crate::database::helpers;
+use crate::models::{OrderStatus, OrderType};
+use vapor_core::services::clock::{system_clock, SharedClock};
+use crate::services::event_bus::{EventBus, OrderEvent};
+use crate::services::matching_engine::MatchingEngine;
+
+/// Failure reason of BridgeIn orders whose deposit never arrived
+pub const INTENT_EXPIRED: &str = "intent_expired";
+/// History reason of an expired order revived by its late deposit
+pub const DEPOSIT_AFTER_EXPIRY: &str = "deposit_after_expiry";
+
+/// A BridgeIn order failed for lack of a deposit, as delivered to the webhook
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExpiredIntent {
+    pub order_id: String,
+    pub from_status: OrderStatus,
+    pub created_at: DateTime<Utc>,
+    pub expired_at: DateTime<Utc>,
+}
+
+/// Fails API-created BridgeIn orders that saw no deposit within the intent TTL
+///
+/// Only orders carrying a deposit reference and no attributed deposit are considered, while
+/// they are still Pending or in Discovery; a filler holding a lock keeps the order.
+#[derive(Clone)]
+pub struct IntentExpiry {
+    ttl: Duration,
+    webhook_url: Option<String>,
+    client: reqwest::Client,
+    clock: SharedClock,
+}
+
+impl IntentExpiry {
+    pub fn from_config(config: &SlaConfig) -> Self {
+        Self {
+            ttl: Duration::seconds(config.intent_seconds as i64),
+            webhook_url: config.intent_webhook_url.clone(),
+            client: reqwest::Client::new(),
+            clock: system_clock(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.ttl > Duration::zero()
+    }
+
+    /// Fail the orders past the TTL, take them out of matching and notify about each one
+    pub async fn sweep(&self, db: &SqlitePool, matching_engine: &Arc<Mutex<MatchingEngine>>, event_bus: &EventBus) -> Result<Vec<ExpiredIntent>> {
+        if !self.enabled() {
+            return Ok(Vec::new());
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, status, created_at FROM orders
+            WHERE order_type = ?1 AND status IN (?2, ?3) AND deposit_reference IS NOT NULL AND banking_hash IS NULL
+            "#,
+        )
+        .bind(OrderType::BridgeIn as i32)
+        .bind(OrderStatus::Pending as i32)
+        .bind(OrderStatus::Discovery as i32)
+        .fetch_all(db)
+        .await?;
+
+        let now = self.clock.now();
+        let mut expired = Vec::new();
+        for row in rows {
+            let created_at: DateTime<Utc> = row.try_get("created_at")?;
+            if now < created_at + self.ttl {
+                continue;
+            }
+            let intent = ExpiredIntent {
+                order_id: row.try_get("id")?,
+                from_status: OrderStatus::from(row.try_get::<i32, _>("status")?),
+                created_at,
+                expired_at: now,
+            };
+
+            // Guard on status and deposit so an order that moved on concurrently is left alone
+            let result = sqlx::query(
+                "UPDATE orders SET status = ?1, failure_reason = ?2, updated_at = ?3 WHERE id = ?4 AND status = ?5 AND banking_hash IS NULL"
+            )
+            .bind(OrderStatus::Failed as i32)
+            .bind(INTENT_EXPIRED)
+            .bind(now)
+            .bind(&intent.order_id)
+            .bind(intent.from_status as i32)
+            .execute(db)
+            .await?;
+            if result.rows_affected() == 0 {
+                continue;
+            }
+            helpers::record_status_transition(db, &intent.order_id, intent.from_status, OrderStatus::Failed, Some(INTENT_EXPIRED)).await?;
+            matching_engine.lock().await.withdraw_order(&intent.order_id);
+
+            warn!("BridgeIn order {} saw no deposit within {}s, failed as {}", intent.order_id, self.ttl.num_seconds(), INTENT_EXPIRED);
+            event_bus.publish(OrderEvent::IntentExpired {
+                order_id: intent.order_id.clone(),
+                created_at: intent.created_at,
+                expired_at: intent.expired_at,
+            });
+            self.notify(&intent).await;
+            expired.push(intent);
+        }
+
+        if !expired.is_empty() {
+            info!("Intent expiry failed {} BridgeIn orders without a deposit", expired.len());
+        }
+        Ok(expired)
+    }
+
+    /// Deliver an expired intent to the webhook; failures are only logged
+    async fn notify(&self, intent: &ExpiredIntent) {
+        let Some(url) = &self.webhook_url else {
+            return;
+        };
+        let delivered = self.client.post(url).json(intent).send().await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = delivered {
+            warn!("Failed to deliver expired intent of order {} to webhook: {}", intent.order_id, e);
+        }
+    }
+}
+
+/// Put the order with `deposit_reference` back to Pending if it expired at most `window` ago,
+/// so its late deposit can still be attributed to it; returns the revived order's id
+pub async fn revive(db: &SqlitePool, deposit_reference: &str, window: Duration, now: DateTime<Utc>) -> Result<Option<String>> {
+    let row = sqlx::query(
+        "SELECT id, updated_at FROM orders WHERE deposit_reference = ?1 AND status = ?2 AND failure_reason = ?3 AND banking_hash IS NULL"
+    )
+    .bind(deposit_reference)
+    .bind(OrderStatus::Failed as i32)
+    .bind(INTENT_EXPIRED)
+    .fetch_optional(db)
+    .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let order_id: String = row.try_get("id")?;
+    let expired_at: DateTime<Utc> = row.try_get("updated_at")?;
+    if now > expired_at + window {
+        warn!("Deposit for order {} arrived {}s after its intent expired, too late to revive it", order_id, (now - expired_at).num_seconds());
+        return Ok(None);
+    }
+
+    let result = sqlx::query(
+        "UPDATE orders SET status = ?1, failure_reason = NULL, updated_at = ?2 WHERE id = ?3 AND status = ?4 AND failure_reason = ?5"
+    )
+    .bind(OrderStatus::Pending as i32)
+    .bind(now)
+    .bind(&order_id)
+    .bind(OrderStatus::Failed as i32)
+    .bind(INTENT_EXPIRED)
+    .execute(db)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+    helpers::record_status_transition(db, &order_id, OrderStatus::Failed, OrderStatus::Pending, Some(DEPOSIT_AFTER_EXPIRY)).await?;
+
+    info!("Revived order {} for its deposit, {}s after its intent expired", order_id, (now - expired_at).num_seconds());
+    Ok(Some(order_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Order;
+    use vapor_core::services::clock::MockClock;
+
+    fn intent(id: &str, status: OrderStatus, created_at: DateTime<Utc>) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::BridgeIn,
+            status,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unconfirmed_intents_expire_and_late_deposits_revive_them() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let start = Utc::now();
+        let clock = MockClock::new(start);
+        let engine = Arc::new(Mutex::new(MatchingEngine::new()));
+        let bus = EventBus::new(16);
+        let mut events = bus.subscribe();
+
+        let pending = intent("pending", OrderStatus::Pending, start);
+        let discovered = intent("discovered", OrderStatus::Discovery, start);
+        let locked = intent("locked", OrderStatus::Locked, start);
+        let mut deposited = intent("deposited", OrderStatus::Pending, start);
+        deposited.banking_hash = Some("0xdeposit".to_string());
+        for order in [&pending, &discovered, &locked, &deposited] {
+            helpers::insert_order(&db, order).await.unwrap();
+            sqlx::query("UPDATE orders SET deposit_reference = ?1 WHERE id = ?1").bind(&order.id).execute(&db).await.unwrap();
+        }
+        engine.lock().await.add_order(discovered.clone()).unwrap();
+
+        let mut config = crate::config::Config::default().sla;
+        config.intent_seconds = 600;
+        let expiry = IntentExpiry::from_config(&config).with_clock(clock.shared());
+        clock.advance(Duration::seconds(599));
+        assert!(expiry.sweep(&db, &engine, &bus).await.unwrap().is_empty());
+
+        clock.advance(Duration::seconds(1));
+        let mut expired: Vec<String> = expiry.sweep(&db, &engine, &bus).await.unwrap().into_iter().map(|intent| intent.order_id).collect();
+        expired.sort();
+        assert_eq!(expired, vec!["discovered", "pending"]);
+        assert!(engine.lock().await.pending_orders.is_empty());
+        assert!(matches!(events.try_recv().unwrap(), OrderEvent::IntentExpired { .. }));
+        let order = helpers::get_order_by_id(&db, "discovered").await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Failed);
+        assert_eq!(helpers::get_order_history(&db, "discovered").await.unwrap()[0].reason.as_deref(), Some(INTENT_EXPIRED));
+        assert!(expiry.sweep(&db, &engine, &bus).await.unwrap().is_empty());
+
+        // A deposit within the revival window puts the order back; later ones do not
+        let now = start + Duration::seconds(600);
+        assert_eq!(revive(&db, "pending", Duration::seconds(60), now + Duration::seconds(60)).await.unwrap().as_deref(), Some("pending"));
+        assert_eq!(helpers::get_order_by_id(&db, "pending").await.unwrap().unwrap().status, OrderStatus::Pending);
+        assert!(revive(&db, "pending", Duration::seconds(60), now).await.unwrap().is_none());
+        assert!(revive(&db, "discovered", Duration::seconds(60), now + Duration::seconds(61)).await.unwrap().is_none());
+        assert!(revive(&db, "locked", Duration::seconds(60), now).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,233 @@
+//! Token-balance invariants asserted after every batch is applied
+//!
+//! With `INVARIANT_CHECKS` on (the default in debug builds and on staging), each finalized
+//! batch is checked for negative balances, for value created or destroyed outside the bridge
+//! account, and for state tree leaves that disagree with the account ledger. A violation
+//! panics outside prod, so a divergence stops the node where it happened; in prod it is
+//! counted, logged as an alert and the batch goes ahead.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+use tracing::error;
+
+use crate::config::DeploymentProfile;
+use vapor_core::SparseMerkleLeaf;
+use crate::merkle::MerkleTreeManager;
+use crate::models::AccountState;
+use crate::services::system_accounts::SystemAccount;
+
+/// What happens when a finalized batch breaks an invariant
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvariantMode {
+    /// Nothing is checked
+    #[default]
+    Off,
+    /// Violations are counted and logged as alerts
+    Alert,
+    /// The first violation panics
+    Panic,
+}
+
+impl InvariantMode {
+    pub fn for_profile(enabled: bool, profile: DeploymentProfile) -> Self {
+        match (enabled, profile) {
+            (false, _) => Self::Off,
+            (true, DeploymentProfile::Prod) => Self::Alert,
+            (true, _) => Self::Panic,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InvariantViolation {
+    #[error("{address} holds {balance} of token {token_id}, which is not a non-negative amount")]
+    NegativeBalance { address: String, token_id: u32, balance: String },
+    /// Accounts other than the bridge changed by `held_delta` while the bridge changed by `bridge_delta`
+    #[error("token {token_id} changed by {held_delta} outside the bridge account but by {bridge_delta} in it")]
+    UnbalancedDeltas { token_id: u32, held_delta: i128, bridge_delta: i128 },
+    #[error("state tree leaf of {address} does not match its balances")]
+    LeafMismatch { address: String },
+    #[error("state tree holds {leaves} leaves for {accounts} accounts")]
+    LeafCount { accounts: usize, leaves: usize },
+}
+
+impl InvariantViolation {
+    pub fn invariant(&self) -> &'static str {
+        match self {
+            Self::NegativeBalance { .. } => "negative_balance",
+            Self::UnbalancedDeltas { .. } => "unbalanced_deltas",
+            Self::LeafMismatch { .. } | Self::LeafCount { .. } => "leaf_mismatch",
+        }
+    }
+}
+
+/// Check the accounts after a batch against the accounts before it and the state tree just built from them
+///
+/// Per token, the balances of every account but the bridge must move by exactly what the
+/// bridge account moves: value only enters or leaves the rollup through the bridge.
+pub fn check(
+    before: &HashMap<String, AccountState>,
+    after: &HashMap<String, AccountState>,
+    tree: &mut MerkleTreeManager,
+) -> Vec<InvariantViolation> {
+    let mut violations = Vec::new();
+
+    let mut deltas: BTreeMap<u32, [i128; 2]> = BTreeMap::new();
+    for (accounts, sign) in [(after, 1), (before, -1)] {
+        for account in accounts.values() {
+            let slot = usize::from(SystemAccount::of(&account.address) == Some(SystemAccount::Bridge));
+            for balance in &account.balances {
+                let amount = match balance.balance.parse::<u128>() {
+                    Ok(amount) => amount as i128,
+                    Err(_) => {
+                        if sign > 0 {
+                            violations.push(InvariantViolation::NegativeBalance {
+                                address: account.address.clone(),
+                                token_id: balance.token_id,
+                                balance: balance.balance.clone(),
+                            });
+                        }
+                        continue;
+                    }
+                };
+                deltas.entry(balance.token_id).or_default()[slot] += sign * amount;
+            }
+        }
+    }
+    for (token_id, [held_delta, bridge_delta]) in deltas {
+        if held_delta != bridge_delta {
+            violations.push(InvariantViolation::UnbalancedDeltas { token_id, held_delta, bridge_delta });
+        }
+    }
+
+    let leaves = tree.get_tree_stats().0.item_count;
+    if leaves != after.len() {
+        violations.push(InvariantViolation::LeafCount { accounts: after.len(), leaves });
+    }
+    let mut addresses: Vec<&String> = after.keys().collect();
+    addresses.sort();
+    for address in addresses {
+        let expected = SparseMerkleLeaf::hash_leaf(&after[address], address).map(hex::encode).ok();
+        let leaf = tree.generate_account_proof(address).ok().map(|proof| proof.leaf_hash);
+        if expected.is_none() || expected != leaf {
+            violations.push(InvariantViolation::LeafMismatch { address: address.clone() });
+        }
+    }
+
+    violations
+}
+
+/// Most recent violation seen
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedViolation {
+    pub batch_id: u64,
+    pub invariant: &'static str,
+    pub detail: String,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct InvariantMetrics {
+    pub mode: InvariantMode,
+    /// Batches checked
+    pub checks: u64,
+    /// Violations per invariant
+    pub violations: BTreeMap<&'static str, u64>,
+    pub last_violation: Option<RecordedViolation>,
+}
+
+/// Runs the checks for the batch processor in its configured mode and keeps their counts
+#[derive(Debug, Clone, Default)]
+pub struct InvariantChecker {
+    metrics: InvariantMetrics,
+}
+
+impl InvariantChecker {
+    pub fn new(mode: InvariantMode) -> Self {
+        Self { metrics: InvariantMetrics { mode, ..InvariantMetrics::default() } }
+    }
+
+    /// Check a finalized batch, panicking on a violation in panic mode
+    pub fn enforce(
+        &mut self,
+        batch_id: u64,
+        before: &HashMap<String, AccountState>,
+        after: &HashMap<String, AccountState>,
+        tree: &mut MerkleTreeManager,
+        now: DateTime<Utc>,
+    ) {
+        if self.metrics.mode == InvariantMode::Off {
+            return;
+        }
+
+        let violations = check(before, after, tree);
+        self.metrics.checks += 1;
+        for violation in &violations {
+            *self.metrics.violations.entry(violation.invariant()).or_default() += 1;
+            error!(alert = "invariant_violation", invariant = violation.invariant(), batch_id, "Batch {} breaks an invariant: {}", batch_id, violation);
+            self.metrics.last_violation = Some(RecordedViolation {
+                batch_id,
+                invariant: violation.invariant(),
+                detail: violation.to_string(),
+                at: now,
+            });
+        }
+        if let (InvariantMode::Panic, Some(violation)) = (self.metrics.mode, violations.first()) {
+            panic!("Batch {} breaks an invariant: {}", batch_id, violation);
+        }
+    }
+
+    pub fn metrics(&self) -> InvariantMetrics {
+        self.metrics.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TokenBalance;
+
+    fn account(address: &str, balance: &str) -> (String, AccountState) {
+        let state = AccountState {
+            address: address.to_string(),
+            balances: vec![TokenBalance { token_id: 1, balance: balance.to_string() }],
+            updated_at: Utc::now(),
+        };
+        (address.to_string(), state)
+    }
+
+    #[test]
+    fn test_violations_are_found_and_alerted_or_fatal_by_mode() {
+        let bridge = SystemAccount::Bridge.address();
+        let alice = "0x1111111111111111111111111111111111111111";
+        let bob = "0x2222222222222222222222222222222222222222";
+        let before = HashMap::from([account(&bridge, "100"), account(alice, "100")]);
+        let after = HashMap::from([account(&bridge, "150"), account(alice, "120"), account(bob, "30")]);
+        let mut tree = MerkleTreeManager::new();
+        tree.build_state_tree(&after.values().cloned().collect::<Vec<_>>()).unwrap();
+        assert!(check(&before, &after, &mut tree).is_empty());
+
+        // Minting outside the bridge, a negative balance and a stale tree
+        let broken = HashMap::from([account(&bridge, "150"), account(alice, "180"), account(bob, "-30")]);
+        let violations = check(&before, &broken, &mut tree);
+        let invariants: Vec<_> = violations.iter().map(InvariantViolation::invariant).collect();
+        assert_eq!(invariants, vec!["negative_balance", "unbalanced_deltas", "leaf_mismatch", "leaf_mismatch"]);
+        assert_eq!(violations[1], InvariantViolation::UnbalancedDeltas { token_id: 1, held_delta: 80, bridge_delta: 50 });
+
+        let mut alerting = InvariantChecker::new(InvariantMode::for_profile(true, DeploymentProfile::Prod));
+        alerting.enforce(7, &before, &broken, &mut tree, Utc::now());
+        let metrics = alerting.metrics();
+        assert_eq!((metrics.checks, metrics.violations["leaf_mismatch"]), (1, 2));
+        assert_eq!(metrics.last_violation.unwrap().batch_id, 7);
+
+        let mut panicking = InvariantChecker::new(InvariantMode::for_profile(true, DeploymentProfile::Staging));
+        let fatal = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            panicking.enforce(7, &before, &broken, &mut tree, Utc::now())
+        }));
+        assert!(fatal.is_err());
+        assert_eq!(InvariantMode::for_profile(false, DeploymentProfile::Staging), InvariantMode::Off);
+    }
+}
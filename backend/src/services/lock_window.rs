@@ -0,0 +1,322 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use sqlx::{Row, SqlitePool};
+use tracing::info;
+
+use crate::config::{FillerConfig, LockWindowPolicy};
+use crate::models::OrderStatus;
+use vapor_core::services::clock::SharedClock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockBidStatus {
+    /// Waiting for the window to close
+    Pending,
+    /// Locked the order
+    Won,
+    /// Another bid won, or the order left discovery before the window closed
+    Lost,
+}
+
+impl LockBidStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Won => "won",
+            Self::Lost => "lost",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "pending" => Some(Self::Pending),
+            "won" => Some(Self::Won),
+            "lost" => Some(Self::Lost),
+            _ => None,
+        }
+    }
+}
+
+/// A lock attempt collected during an order's lock window
+#[derive(Debug, Clone, Serialize)]
+pub struct LockBid {
+    pub order_id: String,
+    pub filler_id: String,
+    pub amount: String,
+    pub fee_bps: Option<u32>,
+    /// The filler's settlement record when it bid, see `filler_reputation`
+    pub reputation_bps: u32,
+    /// Rate the lock takes if this bid wins, when the filler accepted a re-quote
+    #[serde(skip)]
+    pub requoted_usd_price: Option<u64>,
+    pub status: LockBidStatus,
+    pub window_closes_at: DateTime<Utc>,
+    pub placed_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+/// Order in which bids try to lock their order: the first that still can wins
+///
+/// Under `Reputation` bids are drawn without replacement, each with a chance proportional
+/// to its filler's reputation. The draw is seeded from the order and its bidders, so it can
+/// be replayed from the recorded bids but not predicted before the window closes.
+pub fn rank_bids(policy: LockWindowPolicy, order_id: &str, bids: &[LockBid]) -> Vec<usize> {
+    let mut remaining: Vec<usize> = (0..bids.len()).collect();
+    // A stable base order, so neither policy depends on how the bids were loaded
+    remaining.sort_by(|&a, &b| (bids[a].placed_at, &bids[a].filler_id).cmp(&(bids[b].placed_at, &bids[b].filler_id)));
+
+    match policy {
+        LockWindowPolicy::BestFee => {
+            // Bids without a fee rank after every bid with one
+            remaining.sort_by_key(|&index| bids[index].fee_bps.map_or(u64::MAX, u64::from));
+            remaining
+        }
+        LockWindowPolicy::Reputation => {
+            let mut seed = Keccak256::new();
+            seed.update(order_id.as_bytes());
+            for &index in &remaining {
+                seed.update(bids[index].filler_id.as_bytes());
+                seed.update(bids[index].placed_at.timestamp_micros().to_be_bytes());
+            }
+            let mut rng = StdRng::from_seed(seed.finalize().into());
+
+            let mut ranked = Vec::with_capacity(remaining.len());
+            while !remaining.is_empty() {
+                // Every filler keeps some chance, however poor its record
+                let weights: Vec<u64> = remaining.iter().map(|&index| u64::from(bids[index].reputation_bps.max(1))).collect();
+                let mut draw = rng.gen_range(0..weights.iter().sum::<u64>());
+                let position = weights.iter()
+                    .position(|&weight| {
+                        if draw < weight {
+                            return true;
+                        }
+                        draw -= weight;
+                        false
+                    })
+                    .unwrap_or(0);
+                ranked.push(remaining.remove(position));
+            }
+            ranked
+        }
+    }
+}
+
+/// A filler's share of settled orders among settled orders and expired locks, in basis
+/// points, counting one of each up front so new fillers start at 5000
+pub async fn filler_reputation(db: &SqlitePool, filler_id: &str) -> Result<u32> {
+    let settled: i64 = sqlx::query("SELECT COUNT(*) AS count FROM orders WHERE filler_id = ? AND status = ?")
+        .bind(filler_id)
+        .bind(OrderStatus::Settled as i32)
+        .fetch_one(db)
+        .await?
+        .try_get("count")?;
+    let expired: i64 = sqlx::query("SELECT expiries FROM filler_collateral WHERE filler_id = ?")
+        .bind(filler_id)
+        .fetch_optional(db)
+        .await?
+        .map(|row| row.try_get("expiries"))
+        .transpose()?
+        .unwrap_or(0);
+
+    let (settled, expired) = (settled.max(0) as u64, expired.max(0) as u64);
+    Ok(((settled + 1) * 10_000 / (settled + expired + 2)) as u32)
+}
+
+/// Collects lock attempts on an order for a configured window before one wins, so the
+/// fastest bot does not take every order
+///
+/// The first attempt opens the window; a filler bidding again replaces its bid. The
+/// scheduler closes the window (see api::jobs), and bids are kept in the DB so a restart
+/// does not lose them. With a window of 0 locks are first come, first served.
+#[derive(Clone)]
+pub struct LockWindow {
+    db: SqlitePool,
+    window: Duration,
+    policy: LockWindowPolicy,
+    clock: SharedClock,
+}
+
+impl LockWindow {
+    pub fn new(db: SqlitePool, config: &FillerConfig, clock: SharedClock) -> Self {
+        Self {
+            db,
+            window: Duration::seconds(config.lock_window_seconds as i64),
+            policy: config.lock_window_policy,
+            clock,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.window > Duration::zero()
+    }
+
+    /// Record a filler's bid on an order; returns the bid and whether it opened the window
+    pub async fn bid(
+        &self,
+        order_id: &str,
+        filler_id: &str,
+        amount: &str,
+        fee_bps: Option<u32>,
+        requoted_usd_price: Option<u64>,
+    ) -> Result<(LockBid, bool)> {
+        let now = self.clock.now();
+        let reputation_bps = filler_reputation(&self.db, filler_id).await?;
+
+        let mut tx = self.db.begin().await?;
+        let open: Option<DateTime<Utc>> = sqlx::query("SELECT MIN(window_closes_at) AS closes_at FROM lock_bids WHERE order_id = ? AND status = ?")
+            .bind(order_id)
+            .bind(LockBidStatus::Pending.as_str())
+            .fetch_one(&mut *tx)
+            .await?
+            .try_get("closes_at")?;
+        let opened = open.is_none();
+        let window_closes_at = match open {
+            Some(closes_at) => closes_at,
+            None => {
+                // Bids of an earlier window, from before the order went back to discovery
+                sqlx::query("DELETE FROM lock_bids WHERE order_id = ?")
+                    .bind(order_id)
+                    .execute(&mut *tx)
+                    .await?;
+                now + self.window
+            }
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO lock_bids (order_id, filler_id, amount, fee_bps, reputation_bps, requoted_usd_price, status, window_closes_at, placed_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT(order_id, filler_id) DO UPDATE SET
+                amount = excluded.amount, fee_bps = excluded.fee_bps, reputation_bps = excluded.reputation_bps,
+                requoted_usd_price = excluded.requoted_usd_price, placed_at = excluded.placed_at
+            "#,
+        )
+        .bind(order_id)
+        .bind(filler_id)
+        .bind(amount)
+        .bind(fee_bps.map(i64::from))
+        .bind(reputation_bps as i64)
+        .bind(requoted_usd_price.map(|price| price as i64))
+        .bind(LockBidStatus::Pending.as_str())
+        .bind(window_closes_at)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        if opened {
+            info!("Lock window for order {} opened by filler {}, closing at {}", order_id, filler_id, window_closes_at);
+        }
+        let bid = LockBid {
+            order_id: order_id.to_string(),
+            filler_id: filler_id.to_string(),
+            amount: amount.to_string(),
+            fee_bps,
+            reputation_bps,
+            requoted_usd_price,
+            status: LockBidStatus::Pending,
+            window_closes_at,
+            placed_at: now,
+            decided_at: None,
+        };
+        Ok((bid, opened))
+    }
+
+    /// Bids on an order, earliest first
+    pub async fn bids(&self, order_id: &str) -> Result<Vec<LockBid>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT order_id, filler_id, amount, fee_bps, reputation_bps, requoted_usd_price, status, window_closes_at, placed_at, decided_at
+            FROM lock_bids WHERE order_id = ? ORDER BY placed_at, filler_id
+            "#,
+        )
+        .bind(order_id)
+        .fetch_all(&self.db)
+        .await?;
+        rows.iter().map(bid_from_row).collect()
+    }
+
+    /// The pending bids on an order in the order they try to lock it
+    pub async fn ranked_bids(&self, order_id: &str) -> Result<Vec<LockBid>> {
+        let pending: Vec<LockBid> = self.bids(order_id).await?
+            .into_iter()
+            .filter(|bid| bid.status == LockBidStatus::Pending)
+            .collect();
+        Ok(rank_bids(self.policy, order_id, &pending).into_iter().map(|index| pending[index].clone()).collect())
+    }
+
+    /// Close an order's window: `winner`'s bid won and every other pending bid lost
+    pub async fn decide(&self, order_id: &str, winner: Option<&str>) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE lock_bids SET status = CASE WHEN filler_id = ?1 THEN ?2 ELSE ?3 END, decided_at = ?4 WHERE order_id = ?5 AND status = ?6",
+        )
+        .bind(winner)
+        .bind(LockBidStatus::Won.as_str())
+        .bind(LockBidStatus::Lost.as_str())
+        .bind(self.clock.now())
+        .bind(order_id)
+        .bind(LockBidStatus::Pending.as_str())
+        .execute(&self.db)
+        .await?;
+        info!("Lock window for order {} closed with {} bids, won by {:?}", order_id, result.rows_affected(), winner);
+        Ok(())
+    }
+}
+
+fn bid_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<LockBid> {
+    let status: String = row.try_get("status")?;
+    Ok(LockBid {
+        order_id: row.try_get("order_id")?,
+        filler_id: row.try_get("filler_id")?,
+        amount: row.try_get("amount")?,
+        fee_bps: row.try_get::<Option<i64>, _>("fee_bps")?.map(|fee| fee as u32),
+        reputation_bps: row.try_get::<i64, _>("reputation_bps")? as u32,
+        requoted_usd_price: row.try_get::<Option<i64>, _>("requoted_usd_price")?.map(|price| price as u64),
+        status: LockBidStatus::parse(&status).ok_or_else(|| anyhow!("unknown lock bid status '{}'", status))?,
+        window_closes_at: row.try_get("window_closes_at")?,
+        placed_at: row.try_get("placed_at")?,
+        decided_at: row.try_get("decided_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bid(filler_id: &str, fee_bps: Option<u32>, reputation_bps: u32, placed_at: i64) -> LockBid {
+        let placed_at = DateTime::<Utc>::from_timestamp(placed_at, 0).unwrap();
+        LockBid {
+            order_id: "order_1".to_string(),
+            filler_id: filler_id.to_string(),
+            amount: "1000".to_string(),
+            fee_bps,
+            reputation_bps,
+            requoted_usd_price: None,
+            status: LockBidStatus::Pending,
+            window_closes_at: placed_at,
+            placed_at,
+            decided_at: None,
+        }
+    }
+
+    #[test]
+    fn test_best_fee_ranks_lowest_fee_then_earliest() {
+        let bids = vec![bid("fast", Some(50), 5_000, 0), bid("no_fee", None, 9_000, 1), bid("cheap", Some(20), 5_000, 3), bid("cheap_late", Some(20), 5_000, 4)];
+        assert_eq!(rank_bids(LockWindowPolicy::BestFee, "order_1", &bids), vec![2, 3, 0, 1]);
+    }
+
+    #[test]
+    fn test_reputation_draw_is_weighted_and_replayable() {
+        let bids = vec![bid("fast", None, 1_000, 0), bid("trusted", None, 9_000, 1)];
+        assert_eq!(rank_bids(LockWindowPolicy::Reputation, "order_1", &bids), rank_bids(LockWindowPolicy::Reputation, "order_1", &bids));
+
+        // Over many orders the better record wins about nine times in ten, not every time
+        let trusted_wins = (0..1_000)
+            .filter(|order| rank_bids(LockWindowPolicy::Reputation, &format!("order_{}", order), &bids)[0] == 1)
+            .count();
+        assert!((850..950).contains(&trusted_wins), "trusted filler won {} of 1000", trusted_wins);
+    }
+}
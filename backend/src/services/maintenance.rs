@@ -0,0 +1,154 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+
+/// Message returned to clients when maintenance is enabled without one
+pub const DEFAULT_MAINTENANCE_MESSAGE: &str = "Vapor is undergoing maintenance, new orders are temporarily not accepted";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    pub message: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl MaintenanceStatus {
+    pub fn message(&self) -> &str {
+        self.message.as_deref().unwrap_or(DEFAULT_MAINTENANCE_MESSAGE)
+    }
+}
+
+/// Admin pause switch for order intake
+///
+/// While enabled, write endpoints answer 503 and the background scheduler, relayer and
+/// matching stay idle; reads keep serving. The flag is persisted so a restart during an
+/// incident does not silently reopen intake. A hold pauses this instance only, without
+/// persisting anything, e.g. while another instance holds the writer lease.
+#[derive(Clone)]
+pub struct MaintenanceMode {
+    db: SqlitePool,
+    status: Arc<RwLock<MaintenanceStatus>>,
+    hold: Arc<RwLock<Option<MaintenanceStatus>>>,
+}
+
+impl MaintenanceMode {
+    pub fn new(db: SqlitePool) -> Self {
+        Self {
+            db,
+            status: Arc::new(RwLock::new(MaintenanceStatus::default())),
+            hold: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Load the persisted flag, e.g. on startup
+    pub async fn restore(&self) -> Result<MaintenanceStatus> {
+        let row = sqlx::query("SELECT enabled, message, updated_at FROM maintenance_mode WHERE id = 1")
+            .fetch_optional(&self.db)
+            .await?;
+
+        let status = match row {
+            Some(row) => MaintenanceStatus {
+                enabled: row.try_get("enabled")?,
+                message: row.try_get("message")?,
+                updated_at: row.try_get("updated_at")?,
+            },
+            None => MaintenanceStatus::default(),
+        };
+
+        if status.enabled {
+            warn!("Maintenance mode is enabled: {}", status.message());
+        }
+        *self.status.write().unwrap() = status.clone();
+        Ok(status)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.status.read().unwrap().enabled || self.hold.read().unwrap().is_some()
+    }
+
+    /// The persisted status, unless only a hold pauses this instance
+    pub fn status(&self) -> MaintenanceStatus {
+        let status = self.status.read().unwrap().clone();
+        match &*self.hold.read().unwrap() {
+            Some(hold) if !status.enabled => hold.clone(),
+            _ => status,
+        }
+    }
+
+    /// Pause this instance with `reason`, or lift the hold with None; the persisted flag is untouched
+    pub fn hold(&self, reason: Option<String>) {
+        let mut hold = self.hold.write().unwrap();
+        match reason {
+            Some(reason) if hold.as_ref().and_then(|hold| hold.message.as_deref()) != Some(reason.as_str()) => {
+                *hold = Some(MaintenanceStatus { enabled: true, message: Some(reason), updated_at: Some(Utc::now()) });
+            }
+            Some(_) => {}
+            None => *hold = None,
+        }
+    }
+
+    /// Turn maintenance on or off and persist the change
+    pub async fn set(&self, enabled: bool, message: Option<String>) -> Result<MaintenanceStatus> {
+        let status = MaintenanceStatus {
+            enabled,
+            message,
+            updated_at: Some(Utc::now()),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO maintenance_mode (id, enabled, message, updated_at)
+            VALUES (1, ?1, ?2, ?3)
+            ON CONFLICT(id) DO UPDATE SET enabled = ?1, message = ?2, updated_at = ?3
+            "#,
+        )
+        .bind(status.enabled)
+        .bind(&status.message)
+        .bind(status.updated_at)
+        .execute(&self.db)
+        .await?;
+
+        info!("Maintenance mode {}", if enabled { "enabled" } else { "disabled" });
+        *self.status.write().unwrap() = status.clone();
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_maintenance_flag_is_persisted() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+
+        let maintenance = MaintenanceMode::new(db.clone());
+        assert!(!maintenance.restore().await.unwrap().enabled);
+
+        maintenance.set(true, Some("Rotating keys".to_string())).await.unwrap();
+        assert!(maintenance.is_enabled());
+
+        // A fresh instance (e.g. after a restart) picks the flag back up
+        let restarted = MaintenanceMode::new(db);
+        assert!(!restarted.is_enabled());
+        let status = restarted.restore().await.unwrap();
+        assert!(status.enabled);
+        assert_eq!(status.message(), "Rotating keys");
+
+        let status = restarted.set(false, None).await.unwrap();
+        assert!(!restarted.is_enabled());
+        assert_eq!(status.message(), DEFAULT_MAINTENANCE_MESSAGE);
+
+        // A hold pauses this instance without touching the persisted flag
+        restarted.hold(Some("On standby".to_string()));
+        assert!(restarted.is_enabled());
+        assert_eq!(restarted.status().message(), "On standby");
+        assert!(!MaintenanceMode::new(restarted.db.clone()).restore().await.unwrap().enabled);
+        restarted.hold(None);
+        assert!(!restarted.is_enabled());
+    }
+}
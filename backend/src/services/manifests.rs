@@ -0,0 +1,399 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+use tracing::{info, instrument};
+use web3::types::{Address, H256};
+
+use crate::config::{ManifestConfig, ManifestPushKind};
+use vapor_core::proof_format::parse_hash32;
+use vapor_core::sparse_merkle_tree::solidity_keccak256_hash;
+use crate::merkle::MerkleTreeManager;
+use crate::services::archival::BatchSnapshot;
+use crate::services::proof_compression::PreparedSubmission;
+use vapor_chain::signer::{recover_signer, signature_to_hex, uint256, SignatureVerification, Signer};
+
+/// Domain tag hashed into every manifest digest, keeping manifest signatures apart from
+/// inclusion receipts and transactions signed with the same key
+pub const MANIFEST_DOMAIN: &[u8] = b"VAPOR_BATCH_MANIFEST_V1";
+
+/// Operator-signed summary of a submitted batch, enough for a third party to mirror rollup
+/// history and check it against the chain without trusting the backend's database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchManifest {
+    pub batch_id: u64,
+    pub state_root: String,
+    pub orders_root: String,
+    pub order_count: usize,
+    /// When the batch was finalized, to the second
+    pub timestamp: DateTime<Utc>,
+    /// keccak256 of the off-chain proof artifact, or of the proof calldata when there is none
+    pub proof_artifact_hash: String,
+    /// `keccak256(abi.encodePacked(MANIFEST_DOMAIN, uint256(batch_id), state_root, orders_root, uint256(order_count), uint256(timestamp), proof_artifact_hash))`
+    pub digest: String,
+    pub signer: String,
+    /// 65-byte r || s || v over `digest`, without an EIP-191 prefix (v is 27 or 28)
+    pub signature: String,
+}
+
+/// A stored manifest with where it was pushed
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestRecord {
+    #[serde(flatten)]
+    pub manifest: BatchManifest,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pushed_to: Option<String>,
+    /// e.g. the IPFS CID of the manifest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub push_reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub push_error: Option<String>,
+}
+
+/// Digest signed for a batch's manifest
+pub fn manifest_digest(
+    batch_id: u64,
+    state_root: &[u8; 32],
+    orders_root: &[u8; 32],
+    order_count: usize,
+    timestamp: i64,
+    proof_artifact_hash: &[u8; 32],
+) -> [u8; 32] {
+    solidity_keccak256_hash(&[
+        MANIFEST_DOMAIN,
+        &uint256(batch_id),
+        state_root,
+        orders_root,
+        &uint256(order_count as u64),
+        &uint256(timestamp.max(0) as u64),
+        proof_artifact_hash,
+    ])
+}
+
+/// Hash a manifest commits to for a submitted proof
+pub fn proof_artifact_hash(submission: &PreparedSubmission) -> String {
+    submission.artifact_hash()
+        .unwrap_or_else(|| format!("0x{}", hex::encode(Keccak256::digest(&submission.calldata))))
+}
+
+impl BatchManifest {
+    /// Recompute the digest from the manifest's fields and recover the address that signed it
+    ///
+    /// The manifest is genuine if this is the operator's address.
+    pub fn recover_signer(&self) -> Result<Address> {
+        let digest = manifest_digest(
+            self.batch_id,
+            &parse_hash32(&self.state_root)?,
+            &parse_hash32(&self.orders_root)?,
+            self.order_count,
+            self.timestamp.timestamp(),
+            &parse_hash32(&self.proof_artifact_hash)?,
+        );
+        if format!("0x{}", hex::encode(digest)) != self.digest {
+            return Err(anyhow::anyhow!("Manifest digest does not match its fields"));
+        }
+
+        recover_signer(&digest, &self.signature)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IpfsAddResponse {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+/// Signs, stores and pushes a manifest for every submitted batch, with the operator key
+///
+/// Without a signer no manifests are published.
+#[derive(Clone)]
+pub struct ManifestPublisher {
+    db: SqlitePool,
+    signer: Option<Arc<dyn Signer>>,
+    config: ManifestConfig,
+    client: reqwest::Client,
+}
+
+impl ManifestPublisher {
+    pub fn new(db: SqlitePool, config: &ManifestConfig) -> Self {
+        Self { db, signer: None, config: config.clone(), client: reqwest::Client::new() }
+    }
+
+    pub fn with_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.signer.is_some()
+    }
+
+    /// Check that a manifest is unaltered and was signed by this operator
+    ///
+    /// Returns `None` without a signer, as there is no operator address to check against.
+    pub fn verify(&self, manifest: &BatchManifest) -> Option<SignatureVerification> {
+        let operator = self.signer.as_ref()?.address();
+        Some(SignatureVerification::against(operator, manifest.recover_signer()))
+    }
+
+    /// Whether published manifests are also pushed to an external endpoint
+    pub fn pushes(&self) -> bool {
+        self.config.push_url.is_some()
+    }
+
+    /// Sign and store the manifest of a submitted batch
+    #[instrument(skip_all, fields(batch_id = snapshot.batch_id))]
+    pub async fn publish(&self, snapshot: &BatchSnapshot, proof_artifact_hash: &str) -> Result<Option<BatchManifest>> {
+        let Some(signer) = &self.signer else {
+            return Ok(None);
+        };
+
+        // An empty batch's orders tree has no root; sign the all-zero empty root instead
+        let orders_root = match snapshot.orders_root.trim_start_matches("0x") {
+            "" => MerkleTreeManager::empty_orders_root(),
+            _ => snapshot.orders_root.clone(),
+        };
+        let timestamp = DateTime::from_timestamp(snapshot.created_at.timestamp(), 0).unwrap_or(snapshot.created_at);
+        let digest = manifest_digest(
+            snapshot.batch_id,
+            &parse_hash32(&snapshot.state_root)?,
+            &parse_hash32(&orders_root)?,
+            snapshot.orders.len(),
+            timestamp.timestamp(),
+            &parse_hash32(proof_artifact_hash)?,
+        );
+        let signature = signer.sign_digest(H256::from(digest), None).await?;
+        let manifest = BatchManifest {
+            batch_id: snapshot.batch_id,
+            state_root: snapshot.state_root.clone(),
+            orders_root,
+            order_count: snapshot.orders.len(),
+            timestamp,
+            proof_artifact_hash: proof_artifact_hash.to_string(),
+            digest: format!("0x{}", hex::encode(digest)),
+            signer: format!("{:?}", signer.address()),
+            signature: signature_to_hex(&signature)?,
+        };
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO batch_manifests
+                (batch_id, state_root, orders_root, order_count, timestamp, proof_artifact_hash, digest, signer, signature)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+        )
+        .bind(manifest.batch_id as i64)
+        .bind(&manifest.state_root)
+        .bind(&manifest.orders_root)
+        .bind(manifest.order_count as i64)
+        .bind(manifest.timestamp)
+        .bind(&manifest.proof_artifact_hash)
+        .bind(&manifest.digest)
+        .bind(&manifest.signer)
+        .bind(&manifest.signature)
+        .execute(&self.db)
+        .await?;
+
+        info!("Published manifest of batch {}", manifest.batch_id);
+        Ok(Some(manifest))
+    }
+
+    /// Push a manifest to the configured endpoint and record the outcome
+    ///
+    /// Returns the push reference (the CID for IPFS); a failed push is recorded on the
+    /// manifest and returned as the error.
+    pub async fn push(&self, manifest: &BatchManifest) -> Result<Option<String>> {
+        let Some(url) = &self.config.push_url else {
+            return Ok(None);
+        };
+
+        let pushed = match self.config.push_kind {
+            ManifestPushKind::Http => self.push_http(url, manifest).await,
+            ManifestPushKind::Ipfs => self.push_ipfs(url, manifest).await,
+        };
+        let (reference, error) = match &pushed {
+            Ok(reference) => (reference.clone(), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        sqlx::query("UPDATE batch_manifests SET pushed_to = ?, push_reference = ?, push_error = ? WHERE batch_id = ?")
+            .bind(url)
+            .bind(&reference)
+            .bind(&error)
+            .bind(manifest.batch_id as i64)
+            .execute(&self.db)
+            .await?;
+
+        pushed
+    }
+
+    async fn push_http(&self, url: &str, manifest: &BatchManifest) -> Result<Option<String>> {
+        self.client.post(url).json(manifest).send().await?.error_for_status()?;
+        Ok(None)
+    }
+
+    /// Add the manifest through an IPFS node's HTTP API, pinned, and return its CID
+    async fn push_ipfs(&self, url: &str, manifest: &BatchManifest) -> Result<Option<String>> {
+        const BOUNDARY: &str = "vapor-manifest-boundary";
+        let mut body = format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"batch-{}.json\"\r\nContent-Type: application/json\r\n\r\n",
+            manifest.batch_id
+        ).into_bytes();
+        body.extend_from_slice(&serde_json::to_vec(manifest)?);
+        body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+        let added: IpfsAddResponse = self.client
+            .post(format!("{}/api/v0/add?pin=true", url.trim_end_matches('/')))
+            .header(reqwest::header::CONTENT_TYPE, format!("multipart/form-data; boundary={BOUNDARY}"))
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(Some(added.hash))
+    }
+
+    pub async fn get(&self, batch_id: u64) -> Result<Option<ManifestRecord>> {
+        let row = sqlx::query(
+            r#"
+            SELECT batch_id, state_root, orders_root, order_count, timestamp, proof_artifact_hash, digest, signer,
+                   signature, pushed_to, push_reference, push_error
+            FROM batch_manifests WHERE batch_id = ?
+            "#,
+        )
+        .bind(batch_id as i64)
+        .fetch_optional(&self.db)
+        .await?;
+
+        row.map(|row| {
+            Ok(ManifestRecord {
+                manifest: BatchManifest {
+                    batch_id: row.try_get::<i64, _>("batch_id")? as u64,
+                    state_root: row.try_get("state_root")?,
+                    orders_root: row.try_get("orders_root")?,
+                    order_count: row.try_get::<i64, _>("order_count")? as usize,
+                    timestamp: row.try_get("timestamp")?,
+                    proof_artifact_hash: row.try_get("proof_artifact_hash")?,
+                    digest: row.try_get("digest")?,
+                    signer: row.try_get("signer")?,
+                    signature: row.try_get("signature")?,
+                },
+                pushed_to: row.try_get("pushed_to")?,
+                push_reference: row.try_get("push_reference")?,
+                push_error: row.try_get("push_error")?,
+            })
+        })
+        .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vapor_chain::signer::LocalKeySigner;
+    use axum::{body::Bytes, routing::post, Json, Router};
+    use serde_json::{json, Value};
+    use std::sync::Mutex;
+
+    // Anvil's first default account
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    async fn signed_publisher(config: ManifestConfig) -> (ManifestPublisher, Address) {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let signer = Arc::new(LocalKeySigner::from_hex(TEST_KEY).unwrap());
+        let operator = signer.address();
+        (ManifestPublisher::new(db, &config).with_signer(signer), operator)
+    }
+
+    fn snapshot(batch_id: u64) -> BatchSnapshot {
+        BatchSnapshot {
+            batch_id,
+            state_root: format!("0x{}", "11".repeat(32)),
+            orders_root: format!("0x{}", "22".repeat(32)),
+            accounts: Vec::new(),
+            orders: Vec::new(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_manifests_recover_to_operator() {
+        let (publisher, operator) = signed_publisher(ManifestConfig::default()).await;
+        let artifact_hash = format!("0x{}", "33".repeat(32));
+        let published = publisher.publish(&snapshot(4), &artifact_hash).await.unwrap().unwrap();
+        assert_eq!(published.recover_signer().unwrap(), operator);
+
+        // The stored copy verifies too, and pushing is a no-op without an endpoint
+        let stored = publisher.get(4).await.unwrap().unwrap();
+        assert_eq!(stored.manifest, published);
+        assert_eq!(stored.manifest.recover_signer().unwrap(), operator);
+        assert_eq!(publisher.push(&published).await.unwrap(), None);
+        assert!(publisher.get(5).await.unwrap().is_none());
+
+        assert!(publisher.verify(&published).unwrap().valid);
+
+        let forged = BatchManifest { order_count: 9, ..published.clone() };
+        assert!(forged.recover_signer().is_err());
+        let verification = publisher.verify(&forged).unwrap();
+        assert_eq!((verification.valid, verification.recovered_signer), (false, None));
+
+        // Signed by another key: recoverable, but not the operator
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let other_key = "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+        let impostor = ManifestPublisher::new(db, &ManifestConfig::default())
+            .with_signer(Arc::new(LocalKeySigner::from_hex(other_key).unwrap()));
+        let counterfeit = impostor.publish(&snapshot(4), &artifact_hash).await.unwrap().unwrap();
+        let verification = publisher.verify(&counterfeit).unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.recovered_signer, Some(counterfeit.signer));
+
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        let unsigned = ManifestPublisher::new(db, &ManifestConfig::default());
+        assert!(unsigned.publish(&snapshot(4), &artifact_hash).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_manifests_are_pushed_over_http_and_ipfs() {
+        let received: Arc<Mutex<Vec<Value>>> = Arc::default();
+        let sink = received.clone();
+        let ipfs_sink = received.clone();
+        let app = Router::new()
+            .route("/manifests", post(move |Json(body): Json<Value>| async move {
+                sink.lock().unwrap().push(body);
+            }))
+            .route("/api/v0/add", post(move |body: Bytes| async move {
+                let body = String::from_utf8_lossy(&body).to_string();
+                ipfs_sink.lock().unwrap().push(json!(body.contains("\"batch_id\":5")));
+                Json(json!({ "Name": "batch-5.json", "Hash": "QmManifest", "Size": "512" }))
+            }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let artifact_hash = format!("0x{}", "33".repeat(32));
+        let http = ManifestConfig { push_url: Some(format!("{}/manifests", base)), push_kind: ManifestPushKind::Http };
+        let (publisher, _) = signed_publisher(http).await;
+        let manifest = publisher.publish(&snapshot(5), &artifact_hash).await.unwrap().unwrap();
+        assert_eq!(publisher.push(&manifest).await.unwrap(), None);
+        assert_eq!(received.lock().unwrap()[0]["signature"], manifest.signature.as_str());
+        assert!(publisher.get(5).await.unwrap().unwrap().push_error.is_none());
+
+        let ipfs = ManifestConfig { push_url: Some(base.clone()), push_kind: ManifestPushKind::Ipfs };
+        let (publisher, _) = signed_publisher(ipfs).await;
+        let manifest = publisher.publish(&snapshot(5), &artifact_hash).await.unwrap().unwrap();
+        assert_eq!(publisher.push(&manifest).await.unwrap().as_deref(), Some("QmManifest"));
+        assert_eq!(received.lock().unwrap()[1], json!(true));
+        assert_eq!(publisher.get(5).await.unwrap().unwrap().push_reference.as_deref(), Some("QmManifest"));
+
+        // A failed push is kept on the manifest
+        let missing = ManifestConfig { push_url: Some(format!("{}/missing", base)), push_kind: ManifestPushKind::Http };
+        let (publisher, _) = signed_publisher(missing).await;
+        let manifest = publisher.publish(&snapshot(6), &artifact_hash).await.unwrap().unwrap();
+        assert!(publisher.push(&manifest).await.is_err());
+        assert!(publisher.get(6).await.unwrap().unwrap().push_error.unwrap().contains("404"));
+    }
+}
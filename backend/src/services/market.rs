@@ -0,0 +1,434 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use tracing::{info, instrument};
+use web3::types::U256;
+
+use crate::config::FillerConfig;
+use crate::database::helpers;
+use crate::models::{OrderStatus, OrderType};
+use crate::services::filler_capabilities;
+
+/// Window for the matched and filled aggregates
+pub const SUMMARY_WINDOW_HOURS: i64 = 24;
+
+/// Bank service reported for orders that did not name one
+pub const UNSPECIFIED_BANK_SERVICE: &str = "unspecified";
+
+/// Aggregates for one corridor (token paid in, bank service paid out)
+///
+/// Volumes are in the token's smallest unit; they are never summed across tokens.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct CorridorSummary {
+    pub token_id: u32,
+    pub bank_service: String,
+    /// Orders currently in Discovery
+    pub open_orders: u64,
+    pub open_volume: String,
+    /// Orders taken by a filler whose last update falls in the window
+    pub matched_orders: u64,
+    pub matched_volume: String,
+    /// Orders whose filler submitted payment proof in the window
+    pub filled_orders: u64,
+    /// Mean time from Discovery to payment proof
+    pub avg_fill_seconds: Option<u64>,
+    /// Registered fillers serving the corridor within their operating hours
+    pub fillers: u64,
+    /// What those fillers can still lock under their limits; unset when one of them has no
+    /// locked-value limit
+    pub available_liquidity: Option<String>,
+    /// Open volume exceeds the corridor's available liquidity (or no filler serves it)
+    pub saturated: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct MarketTotals {
+    pub open_orders: u64,
+    pub matched_orders: u64,
+    pub filled_orders: u64,
+    pub avg_fill_seconds: Option<u64>,
+    pub saturated_corridors: u64,
+}
+
+/// Anonymized order book aggregates: no addresses, bank accounts or order ids
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketSummary {
+    pub generated_at: DateTime<Utc>,
+    pub window_hours: i64,
+    pub corridors: Vec<CorridorSummary>,
+    pub totals: MarketTotals,
+}
+
+#[derive(Default)]
+struct CorridorTally {
+    open_orders: u64,
+    open_volume: u128,
+    matched_orders: u64,
+    matched_volume: u128,
+    fill_seconds: Vec<i64>,
+}
+
+/// A new order refused because its corridor's fillers cannot take the volume already open
+#[derive(Debug, Clone, Serialize, thiserror::Error)]
+#[error("Token {token_id} over {bank_service} is saturated ({open_volume} open, {} available from {fillers} fillers), retry in {retry_after_seconds}s", available_liquidity.as_deref().unwrap_or("unlimited"))]
+pub struct CorridorSaturated {
+    pub token_id: u32,
+    pub bank_service: String,
+    pub open_volume: String,
+    pub available_liquidity: Option<String>,
+    pub fillers: u64,
+    /// Expected wait for liquidity to free up: the corridor's mean fill time, or the lock TTL
+    pub retry_after_seconds: u64,
+}
+
+/// How much more a registered filler can lock right now; `None` is unlimited
+struct FillerCapacity {
+    capabilities: filler_capabilities::FillerCapabilities,
+    headroom: Option<u128>,
+}
+
+/// Registered fillers within their operating hours at `now`, with their lock headroom
+async fn filler_capacities(db: &SqlitePool, filler: &FillerConfig, now: DateTime<Utc>) -> Result<Vec<FillerCapacity>> {
+    let rows = sqlx::query("SELECT filler_id, locked_amount FROM orders WHERE status = ?1 AND filler_id IS NOT NULL")
+        .bind(OrderStatus::Locked as i32)
+        .fetch_all(db)
+        .await?;
+    let mut amounts: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for row in &rows {
+        let locked_amount: Option<String> = row.try_get("locked_amount")?;
+        amounts.entry(row.try_get("filler_id")?).or_default().push(locked_amount.unwrap_or_default());
+    }
+    let usage: BTreeMap<String, (u64, U256)> = amounts.into_iter()
+        .map(|(filler_id, amounts)| {
+            let locked = helpers::sum_locked_amounts(amounts.iter().map(String::as_str));
+            (filler_id, (amounts.len() as u64, locked))
+        })
+        .collect();
+
+    Ok(filler_capabilities::load_all(db).await?
+        .into_iter()
+        .filter(|(filler_id, capabilities)| capabilities.check_hours(filler_id, now).is_ok())
+        .map(|(filler_id, capabilities)| {
+            let limits = filler.limits_for(&filler_id);
+            let (locks, locked) = usage.get(&filler_id).copied().unwrap_or_default();
+            let headroom = if limits.max_concurrent_locks > 0 && locks >= limits.max_concurrent_locks as u64 {
+                Some(0)
+            } else if limits.max_locked_value > 0 {
+                Some(U256::from(limits.max_locked_value).saturating_sub(locked).as_u128())
+            } else {
+                None
+            };
+            FillerCapacity { capabilities, headroom }
+        })
+        .collect())
+}
+
+/// Aggregate BridgeIn orders into per-corridor market data as of `now`
+///
+/// Orders split by a partial settlement are counted through their child orders. A corridor's
+/// liquidity is the lock headroom of the registered fillers serving it; a filler serving
+/// several corridors counts towards each of them.
+#[instrument(skip_all, fields(db.system = "sqlite"))]
+pub async fn compute_summary(db: &SqlitePool, filler: &FillerConfig, now: DateTime<Utc>) -> Result<MarketSummary> {
+    let rows = sqlx::query(
+        r#"
+        SELECT o.token_id, o.bank_service, o.amount, o.locked_amount, o.filler_id, o.status, o.created_at, o.updated_at,
+            (SELECT MIN(h.created_at) FROM order_status_history h WHERE h.order_id = o.id AND h.to_status = ?1) AS discovered_at,
+            (SELECT MIN(h.created_at) FROM order_status_history h WHERE h.order_id = o.id AND h.to_status = ?2) AS paid_at
+        FROM orders o
+        WHERE o.order_type = ?3 AND o.status IN (?1, ?4, ?2, ?5)
+            AND o.id NOT IN (SELECT parent_order_id FROM orders WHERE parent_order_id IS NOT NULL)
+        "#,
+    )
+    .bind(OrderStatus::Discovery as i32)
+    .bind(OrderStatus::MarkPaid as i32)
+    .bind(OrderType::BridgeIn as i32)
+    .bind(OrderStatus::Locked as i32)
+    .bind(OrderStatus::Settled as i32)
+    .fetch_all(db)
+    .await?;
+
+    let since = now - Duration::hours(SUMMARY_WINDOW_HOURS);
+    let mut tallies: BTreeMap<(u32, String), CorridorTally> = BTreeMap::new();
+
+    for row in rows {
+        let token_id = row.try_get::<i64, _>("token_id")? as u32;
+        let bank_service = row.try_get::<Option<String>, _>("bank_service")?
+            .filter(|service| !service.is_empty())
+            .unwrap_or_else(|| UNSPECIFIED_BANK_SERVICE.to_string());
+        let amount = parse_amount(&row.try_get::<String, _>("amount")?);
+        let status = OrderStatus::from(row.try_get::<i32, _>("status")?);
+        let updated_at: DateTime<Utc> = row.try_get("updated_at")?;
+        let tally = tallies.entry((token_id, bank_service)).or_default();
+
+        if status == OrderStatus::Discovery {
+            tally.open_orders += 1;
+            tally.open_volume += amount;
+            continue;
+        }
+
+        if row.try_get::<Option<String>, _>("filler_id")?.is_some() && updated_at >= since {
+            let locked = row.try_get::<Option<String>, _>("locked_amount")?
+                .map(|locked| parse_amount(&locked))
+                .filter(|locked| *locked > 0);
+            tally.matched_orders += 1;
+            tally.matched_volume += locked.unwrap_or(amount);
+        }
+
+        if matches!(status, OrderStatus::MarkPaid | OrderStatus::Settled) {
+            let discovered_at: DateTime<Utc> = row.try_get::<Option<DateTime<Utc>>, _>("discovered_at")?
+                .unwrap_or(row.try_get("created_at")?);
+            let paid_at = row.try_get::<Option<DateTime<Utc>>, _>("paid_at")?.unwrap_or(updated_at);
+            if paid_at >= since {
+                tally.fill_seconds.push((paid_at - discovered_at).num_seconds().max(0));
+            }
+        }
+    }
+
+    let capacities = filler_capacities(db, filler, now).await?;
+    let corridors: Vec<CorridorSummary> = tallies.iter()
+        .map(|((token_id, bank_service), tally)| {
+            let service = (bank_service != UNSPECIFIED_BANK_SERVICE).then_some(bank_service.as_str());
+            let serving: Vec<&FillerCapacity> = capacities.iter()
+                .filter(|capacity| capacity.capabilities.serves(*token_id, service))
+                .collect();
+            let liquidity = serving.iter()
+                .try_fold(0u128, |total, capacity| capacity.headroom.map(|headroom| total + headroom));
+            let saturated = tally.open_volume > 0
+                && (serving.is_empty() || liquidity.is_some_and(|liquidity| liquidity < tally.open_volume));
+            CorridorSummary {
+                token_id: *token_id,
+                bank_service: bank_service.clone(),
+                open_orders: tally.open_orders,
+                open_volume: tally.open_volume.to_string(),
+                matched_orders: tally.matched_orders,
+                matched_volume: tally.matched_volume.to_string(),
+                filled_orders: tally.fill_seconds.len() as u64,
+                avg_fill_seconds: mean_seconds(&tally.fill_seconds),
+                fillers: serving.len() as u64,
+                available_liquidity: liquidity.map(|liquidity| liquidity.to_string()),
+                saturated,
+            }
+        })
+        .collect();
+
+    let all_fill_seconds: Vec<i64> = tallies.values().flat_map(|tally| tally.fill_seconds.iter().copied()).collect();
+    let totals = MarketTotals {
+        open_orders: corridors.iter().map(|corridor| corridor.open_orders).sum(),
+        matched_orders: corridors.iter().map(|corridor| corridor.matched_orders).sum(),
+        filled_orders: all_fill_seconds.len() as u64,
+        avg_fill_seconds: mean_seconds(&all_fill_seconds),
+        saturated_corridors: corridors.iter().filter(|corridor| corridor.saturated).count() as u64,
+    };
+
+    Ok(MarketSummary {
+        generated_at: now,
+        window_hours: SUMMARY_WINDOW_HOURS,
+        corridors,
+        totals,
+    })
+}
+
+fn parse_amount(amount: &str) -> u128 {
+    amount.trim().parse().unwrap_or(0)
+}
+
+fn mean_seconds(samples: &[i64]) -> Option<u64> {
+    (!samples.is_empty()).then(|| (samples.iter().sum::<i64>() / samples.len() as i64) as u64)
+}
+
+/// Last computed market summary, refreshed on a timer so public requests never hit the orders table
+#[derive(Clone)]
+pub struct MarketSummaryCache {
+    db: SqlitePool,
+    filler: FillerConfig,
+    summary: Arc<RwLock<Option<MarketSummary>>>,
+}
+
+impl MarketSummaryCache {
+    pub fn new(db: SqlitePool, filler: FillerConfig) -> Self {
+        Self {
+            db,
+            filler,
+            summary: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Recompute the summary and replace the cached one
+    pub async fn refresh(&self) -> Result<MarketSummary> {
+        let summary = compute_summary(&self.db, &self.filler, Utc::now()).await?;
+        *self.summary.write().unwrap_or_else(|e| e.into_inner()) = Some(summary.clone());
+
+        info!("Refreshed market summary: {} corridors, {} open orders", summary.corridors.len(), summary.totals.open_orders);
+        Ok(summary)
+    }
+
+    /// The cached summary, computed on first use if the refresh timer has not run yet
+    pub async fn get(&self) -> Result<MarketSummary> {
+        let cached = self.summary.read().unwrap_or_else(|e| e.into_inner()).clone();
+        match cached {
+            Some(summary) => Ok(summary),
+            None => self.refresh().await,
+        }
+    }
+
+    /// Refuse a new order in a corridor the last refresh found saturated
+    ///
+    /// Only the cached summary is consulted, so intake is never slowed by the orders query;
+    /// before the first refresh every corridor is open.
+    pub fn check_intake(&self, token_id: u32, bank_service: Option<&str>) -> Result<(), CorridorSaturated> {
+        let bank_service = bank_service.filter(|service| !service.is_empty()).unwrap_or(UNSPECIFIED_BANK_SERVICE);
+        let summary = self.summary.read().unwrap_or_else(|e| e.into_inner());
+        let Some(corridor) = summary.iter()
+            .flat_map(|summary| &summary.corridors)
+            .find(|corridor| corridor.saturated && corridor.token_id == token_id && corridor.bank_service == bank_service)
+        else {
+            return Ok(());
+        };
+
+        Err(CorridorSaturated {
+            token_id,
+            bank_service: corridor.bank_service.clone(),
+            open_volume: corridor.open_volume.clone(),
+            available_liquidity: corridor.available_liquidity.clone(),
+            fillers: corridor.fillers,
+            retry_after_seconds: corridor.avg_fill_seconds.unwrap_or(self.filler.lock_ttl_seconds).max(1),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, FillerLimits};
+    use crate::database::{helpers, run_migrations};
+    use crate::models::{CreateOrderRequest, Order};
+    use crate::services::filler_capabilities::FillerCapabilities;
+
+    async fn setup_db() -> SqlitePool {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        db
+    }
+
+    async fn insert(db: &SqlitePool, bank_service: Option<&str>, token_id: u32, amount: &str, status: OrderStatus, filler: Option<&str>, age: Duration) -> Order {
+        let mut order = Order::new(CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id,
+            amount: amount.to_string(),
+            bank_account: Some("secret-account".to_string()),
+            bank_service: bank_service.map(str::to_string),
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        });
+        order.status = status;
+        order.filler_id = filler.map(str::to_string);
+        order.created_at = Utc::now() - age;
+        order.updated_at = order.created_at;
+        helpers::insert_order(db, &order).await.unwrap();
+        order
+    }
+
+    #[tokio::test]
+    async fn test_summary_aggregates_per_corridor() {
+        let db = setup_db().await;
+
+        insert(&db, Some("PayPal Hong Kong"), 1, "1000", OrderStatus::Discovery, None, Duration::minutes(5)).await;
+        insert(&db, Some("PayPal Hong Kong"), 1, "500", OrderStatus::Discovery, None, Duration::minutes(1)).await;
+        insert(&db, None, 2, "70", OrderStatus::Discovery, None, Duration::minutes(1)).await;
+        insert(&db, Some("PayPal Hong Kong"), 1, "300", OrderStatus::Locked, Some("filler_1"), Duration::minutes(10)).await;
+        // Filled 10 minutes after entering discovery
+        let filled = insert(&db, Some("PayPal Hong Kong"), 1, "200", OrderStatus::MarkPaid, Some("filler_2"), Duration::minutes(30)).await;
+        sqlx::query("INSERT INTO order_status_history (order_id, from_status, to_status, reason, created_at) VALUES (?1, 0, 1, NULL, ?2), (?1, 2, 3, NULL, ?3)")
+            .bind(&filled.id)
+            .bind(Utc::now() - Duration::minutes(30))
+            .bind(Utc::now() - Duration::minutes(20))
+            .execute(&db)
+            .await
+            .unwrap();
+        // Outside the window
+        insert(&db, Some("PayPal Hong Kong"), 1, "999", OrderStatus::Settled, Some("filler_1"), Duration::hours(30)).await;
+
+        let summary = compute_summary(&db, &Config::default().filler, Utc::now()).await.unwrap();
+
+        assert_eq!(summary.corridors.len(), 2);
+        let paypal = &summary.corridors[0];
+        assert_eq!((paypal.token_id, paypal.bank_service.as_str()), (1, "PayPal Hong Kong"));
+        assert_eq!(paypal.open_orders, 2);
+        assert_eq!(paypal.open_volume, "1500");
+        assert_eq!(paypal.matched_orders, 2);
+        assert_eq!(paypal.matched_volume, "500");
+        assert_eq!(paypal.filled_orders, 1);
+        assert_eq!(paypal.avg_fill_seconds, Some(600));
+
+        let unspecified = &summary.corridors[1];
+        assert_eq!((unspecified.token_id, unspecified.bank_service.as_str()), (2, "unspecified"));
+        assert_eq!(unspecified.open_volume, "70");
+
+        assert_eq!(summary.totals, MarketTotals { open_orders: 3, matched_orders: 2, filled_orders: 1, avg_fill_seconds: Some(600), saturated_corridors: 2 });
+
+        // Nothing identifying leaks into the output
+        let json = serde_json::to_string(&summary).unwrap();
+        assert!(!json.contains("0x1234") && !json.contains("secret-account") && !json.contains(&filled.id));
+    }
+
+    #[tokio::test]
+    async fn test_cache_serves_last_refresh() {
+        let db = setup_db().await;
+        let cache = MarketSummaryCache::new(db.clone(), Config::default().filler);
+
+        assert_eq!(cache.get().await.unwrap().totals.open_orders, 0);
+
+        insert(&db, Some("PayPal Hong Kong"), 1, "1000", OrderStatus::Discovery, None, Duration::minutes(1)).await;
+        assert_eq!(cache.get().await.unwrap().totals.open_orders, 0);
+
+        cache.refresh().await.unwrap();
+        assert_eq!(cache.get().await.unwrap().totals.open_orders, 1);
+    }
+
+    #[tokio::test]
+    async fn test_corridor_saturates_when_fillers_are_spoken_for() {
+        let db = setup_db().await;
+        let mut filler = Config::default().filler;
+        filler.default_limits = FillerLimits { max_concurrent_locks: 5, max_locked_value: 1000 };
+
+        let paypal = FillerCapabilities { bank_services: vec!["PayPal Hong Kong".to_string()], ..Default::default() };
+        filler_capabilities::save(&db, "filler_1", &paypal).await.unwrap();
+        insert(&db, Some("PayPal Hong Kong"), 1, "800", OrderStatus::Discovery, None, Duration::minutes(5)).await;
+        let locked = insert(&db, Some("PayPal Hong Kong"), 1, "400", OrderStatus::Locked, Some("filler_1"), Duration::minutes(5)).await;
+        sqlx::query("UPDATE orders SET locked_amount = '400' WHERE id = ?1").bind(&locked.id).execute(&db).await.unwrap();
+        insert(&db, Some("Wise"), 1, "50", OrderStatus::Discovery, None, Duration::minutes(5)).await;
+
+        let cache = MarketSummaryCache::new(db.clone(), filler.clone());
+        let summary = cache.refresh().await.unwrap();
+        let corridor = &summary.corridors[0];
+        assert_eq!((corridor.bank_service.as_str(), corridor.fillers), ("PayPal Hong Kong", 1));
+        assert_eq!(corridor.available_liquidity.as_deref(), Some("600"));
+        assert!(corridor.saturated);
+        // Nobody pays out over Wise
+        assert_eq!((summary.corridors[1].fillers, summary.corridors[1].saturated), (0, true));
+        assert_eq!(summary.totals.saturated_corridors, 2);
+
+        let err = cache.check_intake(1, Some("PayPal Hong Kong")).unwrap_err();
+        assert_eq!(err.open_volume, "800");
+        assert_eq!(err.retry_after_seconds, filler.lock_ttl_seconds);
+        assert!(cache.check_intake(2, Some("PayPal Hong Kong")).is_ok());
+
+        // A second filler without a locked-value limit covers anything
+        filler_capabilities::save(&db, "filler_2", &FillerCapabilities::default()).await.unwrap();
+        filler.limits.insert("filler_2".to_string(), FillerLimits { max_concurrent_locks: 5, max_locked_value: 0 });
+        let cache = MarketSummaryCache::new(db.clone(), filler);
+        let summary = cache.refresh().await.unwrap();
+        assert_eq!((summary.corridors[0].fillers, summary.corridors[0].available_liquidity.as_deref()), (2, None));
+        assert_eq!(summary.totals.saturated_corridors, 0);
+        assert!(cache.check_intake(1, Some("Wise")).is_ok());
+    }
+}
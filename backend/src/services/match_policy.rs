@@ -0,0 +1,272 @@
+use crate::config::MatchPolicyKind;
+use crate::models::Order;
+use crate::services::matching_engine::Filler;
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashMap;
+
+/// A pending order as a policy sees it
+#[derive(Debug, Clone, Copy)]
+pub struct QueuedOrder<'a> {
+    pub order: &'a Order,
+    pub amount: u64,
+    pub priority_fee: u64,
+    /// Position in the arrival queue, 0 being the oldest
+    pub position: usize,
+}
+
+/// Volume matched to each filler so far in one matching round
+#[derive(Debug, Clone, Default)]
+pub struct RoundAllocation {
+    /// Capacity each filler had when the round started
+    pub starting_capacity: HashMap<String, u64>,
+    pub allocated: HashMap<String, u64>,
+}
+
+impl RoundAllocation {
+    pub fn start<'a>(fillers: impl IntoIterator<Item = &'a Filler>) -> Self {
+        Self {
+            starting_capacity: fillers.into_iter().map(|filler| (filler.id.clone(), filler.capacity_usd)).collect(),
+            allocated: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, filler_id: &str, amount: u64) {
+        *self.allocated.entry(filler_id.to_string()).or_default() += amount;
+    }
+
+    pub fn allocated(&self, filler_id: &str) -> u64 {
+        self.allocated.get(filler_id).copied().unwrap_or(0)
+    }
+
+    pub fn starting_capacity(&self, filler_id: &str) -> u64 {
+        self.starting_capacity.get(filler_id).copied().unwrap_or(0)
+    }
+}
+
+/// Decides which pending order is offered to fillers next, and which filler takes it
+pub trait MatchPolicy: Send + Sync {
+    fn kind(&self) -> MatchPolicyKind;
+
+    /// Queue positions in the order they are offered to fillers
+    fn sequence(&self, queue: &[QueuedOrder]) -> Vec<usize>;
+
+    /// Whether an order no filler can take ends the round instead of being skipped
+    fn blocks_on_unmatched(&self) -> bool {
+        false
+    }
+
+    /// The filler that takes `order`, among `candidates` that are all able to; None if empty
+    fn choose_filler(&self, order: &QueuedOrder, candidates: &[&Filler], _round: &RoundAllocation) -> Option<String> {
+        candidates.iter().copied().max_by(|a, b| rank(order, a, b)).map(|filler| filler.id.clone())
+    }
+}
+
+/// Default filler ranking: auto-accepting fillers first, then the most remaining capacity,
+/// then by id so the choice is deterministic
+fn rank(order: &QueuedOrder, a: &Filler, b: &Filler) -> Ordering {
+    let bank_service = order.order.bank_service.as_deref();
+    a.capabilities.auto_accepts(order.amount, bank_service)
+        .cmp(&b.capabilities.auto_accepts(order.amount, bank_service))
+        .then(a.capacity_usd.cmp(&b.capacity_usd))
+        .then(b.id.cmp(&a.id))
+}
+
+pub fn policy_for(kind: MatchPolicyKind, priority_fee_tiers: &[u64]) -> Box<dyn MatchPolicy> {
+    match kind {
+        MatchPolicyKind::Fifo => Box::new(Fifo),
+        MatchPolicyKind::SmallestFirst => Box::new(SmallestFirst),
+        MatchPolicyKind::ProRata => Box::new(ProRata),
+        MatchPolicyKind::PriorityFee => Box::new(PriorityFeeTiers { tiers: priority_fee_tiers.to_vec() }),
+    }
+}
+
+/// Oldest order first; the round stops at the first order no filler can take
+pub struct Fifo;
+
+impl MatchPolicy for Fifo {
+    fn kind(&self) -> MatchPolicyKind {
+        MatchPolicyKind::Fifo
+    }
+
+    fn sequence(&self, queue: &[QueuedOrder]) -> Vec<usize> {
+        (0..queue.len()).collect()
+    }
+
+    fn blocks_on_unmatched(&self) -> bool {
+        true
+    }
+}
+
+/// Smallest amount first, oldest first among equal amounts; orders no filler can take are skipped
+pub struct SmallestFirst;
+
+impl MatchPolicy for SmallestFirst {
+    fn kind(&self) -> MatchPolicyKind {
+        MatchPolicyKind::SmallestFirst
+    }
+
+    fn sequence(&self, queue: &[QueuedOrder]) -> Vec<usize> {
+        let mut sequence: Vec<_> = (0..queue.len()).collect();
+        sequence.sort_by_key(|&index| (queue[index].amount, queue[index].position));
+        sequence
+    }
+}
+
+/// Oldest order first, each to the filler whose share of the round's matched volume stays
+/// closest to its share of capacity, so fillers end up with volume in proportion to capacity
+pub struct ProRata;
+
+impl MatchPolicy for ProRata {
+    fn kind(&self) -> MatchPolicyKind {
+        MatchPolicyKind::ProRata
+    }
+
+    fn sequence(&self, queue: &[QueuedOrder]) -> Vec<usize> {
+        (0..queue.len()).collect()
+    }
+
+    fn choose_filler(&self, order: &QueuedOrder, candidates: &[&Filler], round: &RoundAllocation) -> Option<String> {
+        // Lowest (allocated + amount) / starting capacity, compared without division
+        let load = |filler: &Filler| (round.allocated(&filler.id) as u128 + order.amount as u128, round.starting_capacity(&filler.id).max(1) as u128);
+        candidates.iter().copied()
+            .max_by(|a, b| {
+                let ((a_volume, a_capacity), (b_volume, b_capacity)) = (load(a), load(b));
+                (b_volume * a_capacity).cmp(&(a_volume * b_capacity)).then_with(|| rank(order, a, b))
+            })
+            .map(|filler| filler.id.clone())
+    }
+}
+
+/// Highest priority fee tier first, oldest first within a tier; orders no filler can take are
+/// skipped. `tiers` are the ascending minimum fees of each tier above the base tier, so fees
+/// within a tier do not outbid each other.
+pub struct PriorityFeeTiers {
+    pub tiers: Vec<u64>,
+}
+
+impl PriorityFeeTiers {
+    pub fn tier(&self, priority_fee: u64) -> usize {
+        self.tiers.iter().take_while(|minimum| priority_fee >= **minimum).count()
+    }
+}
+
+impl MatchPolicy for PriorityFeeTiers {
+    fn kind(&self) -> MatchPolicyKind {
+        MatchPolicyKind::PriorityFee
+    }
+
+    fn sequence(&self, queue: &[QueuedOrder]) -> Vec<usize> {
+        let mut sequence: Vec<_> = (0..queue.len()).collect();
+        sequence.sort_by_key(|&index| (Reverse(self.tier(queue[index].priority_fee)), queue[index].position));
+        sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{OrderStatus, OrderType};
+    use crate::services::matching_engine::MatchingEngine;
+    use chrono::Utc;
+
+    fn order(id: &str, amount: u64) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::BridgeIn,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: amount.to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            status: OrderStatus::Discovery,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn engine(kind: MatchPolicyKind, tiers: &[u64], fillers: &[(&str, u64)]) -> MatchingEngine {
+        let mut engine = MatchingEngine::new().with_policy(policy_for(kind, tiers));
+        for (id, capacity) in fillers {
+            engine.add_filler(id.to_string(), format!("0x{}", id), *capacity).unwrap();
+        }
+        engine
+    }
+
+    fn matched(engine: &mut MatchingEngine) -> Vec<(String, String)> {
+        engine.match_orders().unwrap().into_iter().map(|m| (m.order_id, m.filler_id)).collect()
+    }
+
+    fn pending(engine: &MatchingEngine) -> Vec<&str> {
+        engine.pending_orders.iter().map(|order| order.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_fifo_and_smallest_first_with_an_unfillable_head() {
+        for kind in [MatchPolicyKind::Fifo, MatchPolicyKind::SmallestFirst] {
+            let mut engine = engine(kind, &[], &[("filler_a", 100)]);
+            for (id, amount) in [("large", 500), ("small_1", 60), ("small_2", 40)] {
+                engine.add_order(order(id, amount)).unwrap();
+            }
+
+            let matches = matched(&mut engine);
+            match kind {
+                // The large order holds up everything behind it
+                MatchPolicyKind::Fifo => {
+                    assert!(matches.is_empty());
+                    assert_eq!(pending(&engine), vec!["large", "small_1", "small_2"]);
+                }
+                // Smallest first, skipping the order nobody can take
+                _ => {
+                    let ids: Vec<_> = matches.iter().map(|(order_id, _)| order_id.as_str()).collect();
+                    assert_eq!(ids, vec!["small_2", "small_1"]);
+                    assert_eq!(pending(&engine), vec!["large"]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pro_rata_splits_volume_by_capacity() {
+        let volume = |kind| {
+            let mut engine = engine(kind, &[], &[("filler_a", 300), ("filler_b", 100)]);
+            for i in 0..4 {
+                engine.add_order(order(&format!("order_{}", i), 50)).unwrap();
+            }
+            let mut volume = HashMap::new();
+            for (_, filler_id) in matched(&mut engine) {
+                *volume.entry(filler_id).or_insert(0) += 50;
+            }
+            volume
+        };
+
+        // 200 of volume against 300:100 of capacity goes 150:50
+        let pro_rata = volume(MatchPolicyKind::ProRata);
+        assert_eq!((pro_rata["filler_a"], pro_rata["filler_b"]), (150, 50));
+        // The default ranking keeps picking the filler with the most capacity left
+        let fifo = volume(MatchPolicyKind::Fifo);
+        assert_eq!((fifo["filler_a"], fifo.get("filler_b").copied()), (200, None));
+    }
+
+    #[test]
+    fn test_priority_fee_tiers_go_first_in_arrival_order() {
+        let mut engine = engine(MatchPolicyKind::PriorityFee, &[10, 100], &[("filler_a", 300)]);
+        for (id, fee) in [("no_fee", 0), ("tier_1_old", 20), ("tier_2_old", 500), ("tier_1_new", 99), ("tier_2_new", 150)] {
+            engine.add_order_with_priority_fee(order(id, 100), fee).unwrap();
+        }
+
+        let ids: Vec<_> = matched(&mut engine).into_iter().map(|(order_id, _)| order_id).collect();
+        // A higher fee within the same tier does not jump the queue
+        assert_eq!(ids, vec!["tier_2_old", "tier_2_new", "tier_1_old"]);
+        // Unmatched orders keep their place in the arrival queue
+        assert_eq!(pending(&engine), vec!["no_fee", "tier_1_new"]);
+        assert_eq!(engine.priority_fees.len(), 1);
+
+        let tiers = PriorityFeeTiers { tiers: vec![10, 100] };
+        assert_eq!((tiers.tier(0), tiers.tier(10), tiers.tier(99), tiers.tier(100)), (0, 1, 1, 2));
+    }
+}
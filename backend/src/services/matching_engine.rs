@@ -1,17 +1,48 @@
+use crate::config::FillerLimits;
 use crate::models::{Order, OrderType};
-use anyhow::Result;
-use std::collections::{HashMap, VecDeque};
-use tracing::{info, warn};
+use vapor_core::services::clock::{system_clock, SharedClock};
+use crate::services::filler_capabilities::{CapabilityError, FillerCapabilities};
+use crate::services::match_policy::{Fifo, MatchPolicy, QueuedOrder, RoundAllocation};
+use std::collections::{HashMap, HashSet, VecDeque};
+use tracing::{info, warn, info_span, instrument, Span};
 use chrono::{DateTime, Utc};
 use serde::Serialize;
 
 /// Simple P2P Offramp Matching Engine
-/// FIFO order matching with basic filler capacity management
+/// Orders are offered to fillers in the sequence of the configured [`MatchPolicy`] (FIFO by
+/// default); each goes to the filler the policy picks among those whose capabilities,
+/// capacity and limits allow it. Orders re-queued after their lock fell through are offered
+/// first, oldest first, for up to `max_boosts` re-queues each.
 pub struct MatchingEngine {
-    /// FIFO queue of sell orders waiting for fillers
+    /// Sell orders waiting for fillers, in arrival order
     pub pending_orders: VecDeque<Order>,
+    /// When each pending order entered the queue, and whether it is in the priority lane
+    pub queue_entries: HashMap<String, QueueEntry>,
+    /// Times each order known to the engine was re-queued
+    pub requeue_counts: HashMap<String, u32>,
+    /// Re-queues of one order that go to the priority lane; later ones join the back of the queue
+    pub max_boosts: u32,
+    /// Time from entering the queue to a match, for fresh and re-queued orders
+    pub fresh_waits: LaneWaits,
+    pub requeued_waits: LaneWaits,
+    /// Priority fees offered by pending orders, by order id
+    pub priority_fees: HashMap<String, u64>,
+    /// Ordering policy for matching rounds
+    pub policy: Box<dyn MatchPolicy>,
     /// Available fillers by ID
     pub fillers: HashMap<String, Filler>,
+    /// Lock limits for fillers without an override
+    pub default_limits: FillerLimits,
+    /// Per-filler lock limit overrides
+    pub filler_limits: HashMap<String, FillerLimits>,
+    /// Registered filler capabilities
+    pub filler_capabilities: HashMap<String, FillerCapabilities>,
+    /// Matches waiting for their filler to lock the order through the API
+    pub matched_orders: HashMap<String, MatchResult>,
+    /// Orders locked through the API and awaiting payment, with their filler
+    pub locked_orders: HashMap<String, String>,
+    /// Time source for operating hours and lock expiry
+    pub clock: SharedClock,
 }
 
 /// Simplified filler info
@@ -21,6 +52,104 @@ pub struct Filler {
     pub address: String,
     pub capacity_usd: u64,      // How much USD they can provide
     pub is_active: bool,
+    pub limits: FillerLimits,
+    pub capabilities: FillerCapabilities,
+    pub open_locks: u32,        // Orders currently locked by this filler
+    pub locked_value: u64,      // Sum of those orders' amounts
+}
+
+impl Filler {
+    /// Check that the filler's capabilities and lock limits let it take `amount` of `order` at `at`
+    pub fn check_order(&self, order: &Order, amount: u64, at: DateTime<Utc>) -> Result<(), MatchError> {
+        self.capabilities.check_order(&self.id, order.token_id, amount, order.bank_service.as_deref())?;
+        self.capabilities.check_hours(&self.id, at)?;
+        check_filler_limits(&self.id, &self.limits, self.open_locks, self.locked_value, amount)?;
+        Ok(())
+    }
+}
+
+/// A lock that would take a filler over one of its limits
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(tag = "limit", rename_all = "snake_case")]
+pub enum FillerLimitError {
+    #[error("filler {filler_id} already holds {current} of {max} allowed locks")]
+    MaxConcurrentLocks { filler_id: String, current: u32, max: u32 },
+    #[error("filler {filler_id} has {current} locked, {requested} more would exceed the {max} limit")]
+    MaxLockedValue { filler_id: String, current: u64, requested: u64, max: u64 },
+}
+
+/// Errors from queueing and matching orders
+#[derive(Debug, thiserror::Error)]
+pub enum MatchError {
+    #[error("Only BridgeIn orders supported, got {0:?}")]
+    UnsupportedOrderType(OrderType),
+    #[error(transparent)]
+    LimitExceeded(#[from] FillerLimitError),
+    #[error(transparent)]
+    Capability(#[from] CapabilityError),
+}
+
+/// Check whether a filler holding `open_locks` orders worth `locked_value` may lock `amount` more
+pub fn check_filler_limits(
+    filler_id: &str,
+    limits: &FillerLimits,
+    open_locks: u32,
+    locked_value: u64,
+    amount: u64,
+) -> Result<(), FillerLimitError> {
+    if limits.max_concurrent_locks > 0 && open_locks >= limits.max_concurrent_locks {
+        return Err(FillerLimitError::MaxConcurrentLocks {
+            filler_id: filler_id.to_string(),
+            current: open_locks,
+            max: limits.max_concurrent_locks,
+        });
+    }
+
+    if limits.max_locked_value > 0 && locked_value.saturating_add(amount) > limits.max_locked_value {
+        return Err(FillerLimitError::MaxLockedValue {
+            filler_id: filler_id.to_string(),
+            current: locked_value,
+            requested: amount,
+            max: limits.max_locked_value,
+        });
+    }
+
+    Ok(())
+}
+
+/// A pending order's place in the queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueEntry {
+    pub enqueued_at: DateTime<Utc>,
+    /// Times the order was re-queued before this entry, 0 for a fresh order
+    pub requeues: u32,
+    /// Offered ahead of the policy's sequence
+    pub boosted: bool,
+}
+
+/// Waits of the orders matched from one lane
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LaneWaits {
+    pub matched: u64,
+    pub total_wait_seconds: i64,
+    pub max_wait_seconds: i64,
+}
+
+impl LaneWaits {
+    pub fn record(&mut self, waited: chrono::Duration) {
+        let seconds = waited.num_seconds().max(0);
+        self.matched += 1;
+        self.total_wait_seconds += seconds;
+        self.max_wait_seconds = self.max_wait_seconds.max(seconds);
+    }
+
+    fn stats(&self) -> LaneWaitStats {
+        LaneWaitStats {
+            matched: self.matched,
+            average_wait_seconds: if self.matched == 0 { 0 } else { self.total_wait_seconds / self.matched as i64 },
+            max_wait_seconds: self.max_wait_seconds,
+        }
+    }
 }
 
 /// Simple match result
@@ -36,17 +165,69 @@ impl MatchingEngine {
     pub fn new() -> Self {
         Self {
             pending_orders: VecDeque::new(),
+            queue_entries: HashMap::new(),
+            requeue_counts: HashMap::new(),
+            max_boosts: 3,
+            fresh_waits: LaneWaits::default(),
+            requeued_waits: LaneWaits::default(),
+            priority_fees: HashMap::new(),
+            policy: Box::new(Fifo),
             fillers: HashMap::new(),
+            default_limits: FillerLimits::default(),
+            filler_limits: HashMap::new(),
+            filler_capabilities: HashMap::new(),
+            matched_orders: HashMap::new(),
+            locked_orders: HashMap::new(),
+            clock: system_clock(),
         }
     }
 
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: Box<dyn MatchPolicy>) -> Self {
+        info!("Matching orders with the {:?} policy", policy.kind());
+        self.policy = policy;
+        self
+    }
+
+    /// Re-queues of one order that go to the priority lane (0 sends every re-queue to the back)
+    pub fn with_max_boosts(mut self, max_boosts: u32) -> Self {
+        self.max_boosts = max_boosts;
+        self
+    }
+
+    /// Apply lock limits to fillers (existing and future)
+    pub fn with_filler_limits(mut self, default_limits: FillerLimits, filler_limits: HashMap<String, FillerLimits>) -> Self {
+        self.default_limits = default_limits;
+        self.filler_limits = filler_limits;
+        for filler in self.fillers.values_mut() {
+            filler.limits = self.filler_limits.get(&filler.id).copied().unwrap_or(self.default_limits);
+        }
+        self
+    }
+
+    /// Register a filler's capabilities, for it and any later `add_filler` with its id
+    pub fn set_capabilities(&mut self, filler_id: &str, capabilities: FillerCapabilities) {
+        if let Some(filler) = self.fillers.get_mut(filler_id) {
+            filler.capabilities = capabilities.clone();
+        }
+        self.filler_capabilities.insert(filler_id.to_string(), capabilities);
+    }
+
     /// Add a filler to the system
-    pub fn add_filler(&mut self, id: String, address: String, capacity_usd: u64) -> Result<()> {
+    pub fn add_filler(&mut self, id: String, address: String, capacity_usd: u64) -> Result<(), MatchError> {
         let filler = Filler {
             id: id.clone(),
             address,
             capacity_usd,
             is_active: true,
+            limits: self.filler_limits.get(&id).copied().unwrap_or(self.default_limits),
+            capabilities: self.filler_capabilities.get(&id).cloned().unwrap_or_default(),
+            open_locks: 0,
+            locked_value: 0,
         };
         
         self.fillers.insert(id.clone(), filler);
@@ -55,62 +236,148 @@ impl MatchingEngine {
     }
 
     /// Remove a filler
-    pub fn remove_filler(&mut self, filler_id: &str) -> Result<()> {
+    pub fn remove_filler(&mut self, filler_id: &str) -> Result<(), MatchError> {
         self.fillers.remove(filler_id);
         info!("Removed filler {}", filler_id);
         Ok(())
     }
 
     /// Add a sell order to the queue
-    pub fn add_order(&mut self, order: Order) -> Result<()> {
+    pub fn add_order(&mut self, order: Order) -> Result<(), MatchError> {
+        self.add_order_with_priority_fee(order, 0)
+    }
+
+    /// Add a sell order offering `priority_fee` for earlier matching under the priority_fee policy
+    #[instrument(skip_all, fields(order_id = %order.id))]
+    pub fn add_order_with_priority_fee(&mut self, order: Order, priority_fee: u64) -> Result<(), MatchError> {
         if order.order_type != OrderType::BridgeIn {
-            return Err(anyhow::anyhow!("Only BridgeIn orders supported"));
+            return Err(MatchError::UnsupportedOrderType(order.order_type));
         }
 
+        if priority_fee > 0 {
+            self.priority_fees.insert(order.id.clone(), priority_fee);
+        }
+        let entry = QueueEntry { enqueued_at: self.clock.now(), requeues: 0, boosted: false };
+        self.queue_entries.insert(order.id.clone(), entry);
         self.pending_orders.push_back(order.clone());
         info!("Added order {} for ${} to queue", order.id, order.amount);
         Ok(())
     }
 
-    /// Match orders with fillers (FIFO)
-    pub fn match_orders(&mut self) -> Result<Vec<MatchResult>> {
-        let mut matches = Vec::new();
+    /// Put an order whose lock fell through back in the queue
+    ///
+    /// Its first `max_boosts` re-queues go to the priority lane, offered ahead of fresh orders;
+    /// after that it joins the back of the queue like a new order, so an order no filler
+    /// completes cannot hold the head of the queue forever.
+    #[instrument(skip_all, fields(order_id = %order.id))]
+    pub fn requeue_order(&mut self, order: Order) -> Result<(), MatchError> {
+        if order.order_type != OrderType::BridgeIn {
+            return Err(MatchError::UnsupportedOrderType(order.order_type));
+        }
+        if self.queue_entries.contains_key(&order.id) {
+            return Ok(());
+        }
+
+        let requeues = self.requeue_counts.entry(order.id.clone()).or_default();
+        *requeues += 1;
+        let entry = QueueEntry { enqueued_at: self.clock.now(), requeues: *requeues, boosted: *requeues <= self.max_boosts };
+        if entry.boosted {
+            info!("Re-queued order {} in the priority lane (re-queue {} of {})", order.id, entry.requeues, self.max_boosts);
+        } else {
+            info!("Re-queued order {} at the back of the queue after {} re-queues", order.id, entry.requeues);
+        }
+        self.queue_entries.insert(order.id.clone(), entry);
+        self.pending_orders.push_back(order);
+        Ok(())
+    }
 
-        // Process orders in FIFO order
-        while let Some(order) = self.pending_orders.front() {
-            let order_amount: u64 = order.amount.parse().unwrap_or(0);
-            
-            // Find any active filler with enough capacity
-            let mut matched_filler = None;
-            for filler in self.fillers.values_mut() {
-                if filler.is_active && filler.capacity_usd >= order_amount {
-                    matched_filler = Some(filler.id.clone());
-                    filler.capacity_usd -= order_amount; // Reduce capacity
+    /// Match orders with fillers, in the sequence of the configured policy
+    #[instrument(skip_all, fields(pending_orders = self.pending_orders.len(), policy = ?self.policy.kind(), matched = tracing::field::Empty))]
+    pub fn match_orders(&mut self) -> Result<Vec<MatchResult>, MatchError> {
+        let mut matches = Vec::new();
+        let mut matched_positions = HashSet::new();
+        let mut round = RoundAllocation::start(self.fillers.values().filter(|filler| filler.is_active));
+
+        let queue: Vec<QueuedOrder> = self.pending_orders.iter().enumerate()
+            .map(|(position, order)| QueuedOrder {
+                order,
+                amount: order.amount.parse().unwrap_or(0),
+                priority_fee: self.priority_fees.get(&order.id).copied().unwrap_or(0),
+                position,
+            })
+            .collect();
+
+        // The priority lane goes first, longest-lived order first; the policy orders the rest
+        let (mut sequence, rest): (Vec<usize>, Vec<usize>) = self.policy.sequence(&queue).into_iter()
+            .partition(|&index| self.queue_entries.get(&queue[index].order.id).is_some_and(|entry| entry.boosted));
+        sequence.sort_by_key(|&index| (queue[index].order.created_at, queue[index].position));
+        sequence.extend(rest);
+
+        for index in sequence {
+            let queued = &queue[index];
+            let order = queued.order;
+            let _span = info_span!("match_order", order_id = %order.id).entered();
+            let order_amount = queued.amount;
+
+            let now = self.clock.now();
+            let candidates: Vec<&Filler> = self.fillers.values()
+                .filter(|filler| filler.is_active && filler.capacity_usd >= order_amount)
+                .filter(|filler| match filler.check_order(order, order_amount, now) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        warn!("Skipping filler for order {}: {}", order.id, e);
+                        false
+                    }
+                })
+                .collect();
+            let matched_filler = self.policy.choose_filler(queued, &candidates, &round);
+
+            let Some(filler_id) = matched_filler else {
+                if self.policy.blocks_on_unmatched() {
+                    // No filler available, stop processing
                     break;
                 }
+                continue;
+            };
+
+            if let Some(filler) = self.fillers.get_mut(&filler_id) {
+                filler.capacity_usd -= order_amount; // Reduce capacity
+                filler.open_locks += 1;
+                filler.locked_value += order_amount;
             }
+            round.record(&filler_id, order_amount);
+
+            let lock_until = self.clock.now() + chrono::Duration::minutes(30); // 30 min lock
+            let match_result = MatchResult {
+                order_id: order.id.clone(),
+                filler_id: filler_id.clone(),
+                amount_usd: order_amount,
+                locked_until: lock_until,
+            };
+
+            info!("Matched order {} with filler {} for ${}", 
+                order.id, filler_id, order_amount);
+
+            self.matched_orders.insert(order.id.clone(), match_result.clone());
+            matched_positions.insert(queued.position);
+            matches.push(match_result);
+        }
 
-            if let Some(filler_id) = matched_filler {
-                let order = self.pending_orders.pop_front().unwrap();
-                let lock_until = Utc::now() + chrono::Duration::minutes(30); // 30 min lock
-                
-                let match_result = MatchResult {
-                    order_id: order.id.clone(),
-                    filler_id: filler_id.clone(),
-                    amount_usd: order_amount,
-                    locked_until: lock_until,
-                };
-
-                info!("Matched order {} with filler {} for ${}", 
-                    order.id, filler_id, order_amount);
-                
-                matches.push(match_result);
-            } else {
-                // No filler available, stop processing
-                break;
+        let mut position = 0;
+        self.pending_orders.retain(|_| {
+            position += 1;
+            !matched_positions.contains(&(position - 1))
+        });
+        let now = self.clock.now();
+        for matched in &matches {
+            self.priority_fees.remove(&matched.order_id);
+            if let Some(entry) = self.queue_entries.remove(&matched.order_id) {
+                let lane = if entry.requeues == 0 { &mut self.fresh_waits } else { &mut self.requeued_waits };
+                lane.record(now - entry.enqueued_at);
             }
         }
 
+        Span::current().record("matched", matches.len());
         Ok(matches)
     }
 
@@ -123,14 +390,47 @@ impl MatchingEngine {
                 .filter(|f| f.is_active)
                 .map(|f| f.capacity_usd)
                 .sum(),
+            priority_lane_orders: self.queue_entries.values().filter(|entry| entry.boosted).count(),
+            max_boosts: self.max_boosts,
+            fresh: self.fresh_waits.stats(),
+            requeued: self.requeued_waits.stats(),
         }
     }
 
+    /// Take an order that can no longer be filled out of the queue; returns whether it was queued
+    /// or matched
+    pub fn withdraw_order(&mut self, order_id: &str) -> bool {
+        let queued = self.pending_orders.len();
+        self.pending_orders.retain(|order| order.id != order_id);
+        self.queue_entries.remove(order_id);
+        self.priority_fees.remove(order_id);
+        self.requeue_counts.remove(order_id);
+        let matched = self.matched_orders.remove(order_id).is_some();
+        queued != self.pending_orders.len() || matched
+    }
+
+    /// Note that a filler locked an order through the API, completing any match for it
+    pub fn record_lock(&mut self, order_id: &str, filler_id: &str) {
+        self.matched_orders.remove(order_id);
+        self.locked_orders.insert(order_id.to_string(), filler_id.to_string());
+    }
+
+    /// The order's payment was submitted, so it no longer holds a lock
+    pub fn clear_lock(&mut self, order_id: &str) {
+        self.locked_orders.remove(order_id);
+        self.requeue_counts.remove(order_id);
+    }
+
     /// Release a locked order back to queue (if payment fails)
-    pub fn release_order(&mut self, order_id: &str, filler_id: &str, amount: u64) -> Result<()> {
+    #[instrument(skip(self))]
+    pub fn release_order(&mut self, order_id: &str, filler_id: &str, amount: u64) -> Result<(), MatchError> {
+        self.matched_orders.remove(order_id);
+        self.locked_orders.remove(order_id);
         // Restore filler capacity
         if let Some(filler) = self.fillers.get_mut(filler_id) {
             filler.capacity_usd += amount;
+            filler.open_locks = filler.open_locks.saturating_sub(1);
+            filler.locked_value = filler.locked_value.saturating_sub(amount);
             info!("Released order {} and restored ${} to filler {}", 
                 order_id, amount, filler_id);
         }
@@ -143,6 +443,20 @@ pub struct MatchingStats {
     pub pending_orders: usize,
     pub active_fillers: usize,
     pub total_capacity: u64,
+    /// Pending orders in the priority lane
+    pub priority_lane_orders: usize,
+    pub max_boosts: u32,
+    /// How long orders waited for a match, fresh ones against re-queued ones
+    pub fresh: LaneWaitStats,
+    pub requeued: LaneWaitStats,
+}
+
+/// Waits of the orders matched from one lane since startup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LaneWaitStats {
+    pub matched: u64,
+    pub average_wait_seconds: i64,
+    pub max_wait_seconds: i64,
 }
 
 #[cfg(test)]
@@ -247,7 +561,7 @@ mod tests {
         order.order_type = OrderType::BridgeOut; // Invalid type
         
         let result = engine.add_order(order);
-        assert!(result.is_err());
+        assert!(matches!(result, Err(MatchError::UnsupportedOrderType(OrderType::BridgeOut))));
         assert_eq!(engine.pending_orders.len(), 0);
     }
 
@@ -416,6 +730,52 @@ mod tests {
         assert_eq!(engine.fillers.get("filler1").unwrap().capacity_usd, 1000);
     }
 
+    #[test]
+    fn test_filler_lock_limits() {
+        let mut limits = HashMap::new();
+        limits.insert("capped".to_string(), FillerLimits { max_concurrent_locks: 2, max_locked_value: 0 });
+        let mut engine = MatchingEngine::new()
+            .with_filler_limits(FillerLimits { max_concurrent_locks: 0, max_locked_value: 150 }, limits);
+
+        engine.add_filler("capped".to_string(), "0x1111".to_string(), 10_000).unwrap();
+        for i in 0..3 {
+            engine.add_order(create_test_order(&format!("order{}", i), 100)).unwrap();
+        }
+
+        // Third order exceeds the two-lock limit and stays queued
+        let matches = engine.match_orders().unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(engine.pending_orders.len(), 1);
+        assert_eq!(engine.fillers["capped"].open_locks, 2);
+        assert_eq!(engine.fillers["capped"].locked_value, 200);
+
+        // Releasing a lock frees a slot
+        engine.release_order("order0", "capped", 100).unwrap();
+        assert_eq!(engine.match_orders().unwrap().len(), 1);
+
+        // Fillers without an override use the default value limit
+        engine.add_filler("default".to_string(), "0x2222".to_string(), 10_000).unwrap();
+        assert_eq!(engine.fillers["default"].limits.max_locked_value, 150);
+    }
+
+    #[test]
+    fn test_check_filler_limits() {
+        let limits = FillerLimits { max_concurrent_locks: 3, max_locked_value: 1000 };
+
+        assert!(check_filler_limits("f", &limits, 2, 500, 500).is_ok());
+        assert_eq!(
+            check_filler_limits("f", &limits, 3, 0, 1),
+            Err(FillerLimitError::MaxConcurrentLocks { filler_id: "f".to_string(), current: 3, max: 3 })
+        );
+        assert!(matches!(
+            check_filler_limits("f", &limits, 0, 900, 101),
+            Err(FillerLimitError::MaxLockedValue { current: 900, requested: 101, max: 1000, .. })
+        ));
+
+        // Zero disables a limit
+        assert!(check_filler_limits("f", &FillerLimits::default(), 100, u64::MAX, 1).is_ok());
+    }
+
     #[test]
     fn test_release_order_unknown_filler() {
         let mut engine = MatchingEngine::new();
@@ -427,26 +787,32 @@ mod tests {
 
     #[test]
     fn test_match_result_lock_time() {
-        let mut engine = MatchingEngine::new();
+        use vapor_core::services::clock::MockClock;
+        use crate::services::filler_capabilities::OperatingHours;
+        use chrono::TimeZone;
+
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 1, 1, 8, 0, 0).unwrap());
+        let mut engine = MatchingEngine::new().with_clock(clock.shared());
         
         engine.add_filler("filler1".to_string(), "0x1111".to_string(), 1000).unwrap();
+        engine.set_capabilities("filler1", FillerCapabilities {
+            operating_hours: Some(OperatingHours { start_hour: 9, end_hour: 17 }),
+            ..Default::default()
+        });
         let order = create_test_order("order1", 100);
         engine.add_order(order).unwrap();
         
-        let before_match = Utc::now();
+        // Outside the filler's hours nothing matches
+        assert!(engine.match_orders().unwrap().is_empty());
+
+        clock.advance(chrono::Duration::hours(1));
         let matches = engine.match_orders().unwrap();
-        let after_match = Utc::now();
         
         assert_eq!(matches.len(), 1);
         let match_result = &matches[0];
         
-        // Check that lock time is approximately 30 minutes from now
-        let lock_duration = match_result.locked_until - before_match;
-        assert!(lock_duration.num_minutes() >= 29);
-        assert!(lock_duration.num_minutes() <= 31);
-        
-        // Lock time should be after match time
-        assert!(match_result.locked_until > after_match);
+        // Locks last 30 minutes from the match
+        assert_eq!(match_result.locked_until, Utc.with_ymd_and_hms(2026, 1, 1, 9, 30, 0).unwrap());
     }
 
     #[test]
@@ -521,4 +887,64 @@ mod tests {
         assert_eq!(stats.active_fillers, 5);
         assert_eq!(stats.pending_orders + matches.len(), 10);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_ranking_follows_capabilities() {
+        use crate::services::filler_capabilities::AutoAcceptRule;
+
+        let mut engine = MatchingEngine::new();
+        engine.add_filler("big".to_string(), "0x1111".to_string(), 10_000).unwrap();
+        engine.add_filler("wise_only".to_string(), "0x2222".to_string(), 20_000).unwrap();
+        engine.add_filler("auto".to_string(), "0x3333".to_string(), 1_000).unwrap();
+        engine.set_capabilities("wise_only", FillerCapabilities { bank_services: vec!["Wise".to_string()], ..Default::default() });
+        engine.set_capabilities("auto", FillerCapabilities {
+            auto_accept: vec![AutoAcceptRule { max_amount: Some(200), bank_services: Vec::new() }],
+            ..Default::default()
+        });
+
+        // PayPal orders skip the Wise-only filler; the auto-accepting filler is preferred while
+        // its rule covers the order, then the one with the most capacity
+        engine.add_order(create_test_order("small", 100)).unwrap();
+        engine.add_order(create_test_order("large", 500)).unwrap();
+        let matches = engine.match_orders().unwrap();
+        assert_eq!(matches[0].filler_id, "auto");
+        assert_eq!(matches[1].filler_id, "big");
+
+        // Capabilities registered before the filler joins still apply
+        engine.set_capabilities("late", FillerCapabilities { max_order_amount: Some(10), ..Default::default() });
+        engine.add_filler("late".to_string(), "0x4444".to_string(), 50_000).unwrap();
+        assert_eq!(engine.fillers["late"].capabilities.max_order_amount, Some(10));
+    }
+
+    #[test]
+    fn test_requeued_orders_take_the_priority_lane() {
+        use vapor_core::services::clock::MockClock;
+
+        let clock = MockClock::new(Utc::now());
+        let mut engine = MatchingEngine::new().with_clock(clock.shared()).with_max_boosts(1);
+        engine.add_filler("filler1".to_string(), "0x1111".to_string(), 100).unwrap();
+        engine.add_order(create_test_order("retried", 100)).unwrap();
+        assert_eq!(engine.match_orders().unwrap()[0].order_id, "retried");
+
+        // The lock falls through while a fresh order waits; the re-queued order goes first
+        clock.advance(chrono::Duration::seconds(60));
+        engine.add_order(create_test_order("fresh", 100)).unwrap();
+        engine.release_order("retried", "filler1", 100).unwrap();
+        engine.requeue_order(create_test_order("retried", 100)).unwrap();
+        assert_eq!(engine.get_stats().priority_lane_orders, 1);
+        clock.advance(chrono::Duration::seconds(30));
+        assert_eq!(engine.match_orders().unwrap()[0].order_id, "retried");
+
+        // Past max_boosts it joins the back of the queue
+        engine.release_order("retried", "filler1", 100).unwrap();
+        engine.requeue_order(create_test_order("retried", 100)).unwrap();
+        assert_eq!(engine.get_stats().priority_lane_orders, 0);
+        let matches = engine.match_orders().unwrap();
+        assert_eq!(matches.iter().map(|m| m.order_id.as_str()).collect::<Vec<_>>(), vec!["fresh"]);
+
+        let stats = engine.get_stats();
+        assert_eq!(stats.fresh, LaneWaitStats { matched: 2, average_wait_seconds: 15, max_wait_seconds: 30 });
+        assert_eq!(stats.requeued, LaneWaitStats { matched: 1, average_wait_seconds: 30, max_wait_seconds: 30 });
+        assert!(engine.requeue_order(Order { order_type: OrderType::Transfer, ..create_test_order("transfer", 1) }).is_err());
+    }
+}
@@ -1,5 +1,66 @@
 pub mod order_service;
 pub mod matching_engine;
+pub mod match_policy;
 pub mod batch_processor;
+pub mod batch_prover;
 pub mod relayer;
 pub mod mvp_prover;
+pub mod event_bus;
+pub mod discovery;
+pub mod sla;
+pub mod archival;
+pub mod settlement;
+pub mod proof_cache;
+pub mod deposit_reference;
+pub mod maintenance;
+pub mod withdrawal_limits;
+pub mod order_limits;
+pub mod duplicates;
+pub mod batch_journal;
+pub mod rates;
+pub mod fixtures;
+pub mod market;
+pub mod request_limiter;
+pub mod proof_compression;
+pub mod self_test;
+pub mod batch_caps;
+pub mod payment_proofs;
+pub mod balance_alerts;
+pub mod claims;
+pub mod stats;
+pub mod receipts;
+pub mod manifests;
+pub mod order_costs;
+pub mod scheduler;
+pub mod chain_checkpoint;
+pub mod blob_store;
+pub mod filler_capabilities;
+pub mod collateral;
+pub mod registry;
+pub mod slo;
+pub mod bulk_accounts;
+pub mod settlement_saga;
+pub mod claim_relay;
+pub mod claim_fees;
+pub mod order_reconciliation;
+pub mod transfers;
+pub mod retention;
+pub mod backups;
+pub mod quotes;
+pub mod system_accounts;
+pub mod event_log;
+pub mod handover;
+pub mod batch_sequence;
+pub mod screening;
+pub mod analytics;
+pub mod batch_costs;
+pub mod intent_expiry;
+pub mod attestations;
+pub mod order_review;
+pub mod query_metrics;
+pub mod state_sync;
+pub mod lock_window;
+pub mod signer_funds;
+pub mod verification_cache;
+pub mod notifications;
+pub mod invariants;
@@ -1,17 +1,21 @@
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
-use tracing::{info, warn};
+use tracing::{info, warn, instrument};
 use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Mutex;
 use std::time::Duration;
 use tokio::time::sleep;
 
+use vapor_core::proof_format::ProofError;
 use crate::models::Order;
+use vapor_core::fault_injection::{self, FaultTarget};
+use crate::services::stats::ProofTotals;
 
 /// Mock proof data structure for MVP
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MockProof {
-    pub batch_id: u32,
+    pub batch_id: u64,
     pub prev_state_root: String,
     pub prev_orders_root: String,
     pub new_state_root: String,
@@ -32,7 +36,7 @@ pub struct ProofGenerationResult {
 }
 
 /// Configuration for MVP prover
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MvpProverConfig {
     /// Simulated proof generation time (for realism)
     pub generation_delay_ms: u64,
@@ -40,6 +44,40 @@ pub struct MvpProverConfig {
     pub simulate_failures: bool,
     /// Failure rate (0.0 to 1.0)
     pub failure_rate: f64,
+    /// Scripted failures for staging; the first one matching an attempt applies
+    #[serde(default)]
+    pub scenarios: Vec<FailureScenario>,
+}
+
+/// Deterministic prover misbehaviour, for one batch or (without `batch_id`) every batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FailureScenario {
+    /// Fail the first `attempts` proof attempts, then prove normally
+    FailAttempts {
+        batch_id: Option<u64>,
+        attempts: u32,
+    },
+    /// Report a timeout after `after_ms` instead of a proof
+    Timeout {
+        batch_id: Option<u64>,
+        after_ms: u64,
+    },
+    /// Return a proof whose bytes do not commit to the batch, for the verifier to reject
+    CorruptProof {
+        batch_id: Option<u64>,
+    },
+}
+
+impl FailureScenario {
+    /// Whether the scenario applies to the given (1-based) attempt at proving a batch
+    fn applies_to(&self, batch_id: u64, attempt: u32) -> bool {
+        let (target, applies) = match self {
+            FailureScenario::FailAttempts { batch_id, attempts } => (batch_id, attempt <= *attempts),
+            FailureScenario::Timeout { batch_id, .. } | FailureScenario::CorruptProof { batch_id } => (batch_id, true),
+        };
+        applies && target.is_none_or(|target| target == batch_id)
+    }
 }
 
 impl Default for MvpProverConfig {
@@ -48,6 +86,7 @@ impl Default for MvpProverConfig {
             generation_delay_ms: 2000, // 2 seconds simulated proof time
             simulate_failures: false,   // No failures for MVP
             failure_rate: 0.1,         // 10% failure rate if enabled
+            scenarios: Vec::new(),
         }
     }
 }
@@ -55,24 +94,27 @@ impl Default for MvpProverConfig {
 /// MVP Prover service that mocks SP1 proof generation
 pub struct MvpProverService {
     config: MvpProverConfig,
+    /// Proof attempts per batch since the config was last set, for the scripted scenarios
+    attempts: Mutex<HashMap<u64, u32>>,
 }
 
 impl MvpProverService {
     /// Create a new MVP prover service
     pub fn new(config: MvpProverConfig) -> Self {
-        Self { config }
+        Self { config, attempts: Mutex::default() }
     }
 
     /// Generate a mock proof for a batch
+    #[instrument(skip_all, fields(batch_id = batch_id, orders_count = orders.len()))]
     pub async fn generate_proof_for_batch(
         &self,
-        batch_id: u32,
+        batch_id: u64,
         prev_state_root: &str,
         prev_orders_root: &str,
         new_state_root: &str,
         new_orders_root: &str,
         orders: &[Order],
-    ) -> Result<ProofGenerationResult> {
+    ) -> Result<ProofGenerationResult, ProofError> {
         let start_time = std::time::Instant::now();
         
         info!(
@@ -80,9 +122,40 @@ impl MvpProverService {
             batch_id, orders.len()
         );
 
-        // Simulate proof generation time
-        if self.config.generation_delay_ms > 0 {
-            sleep(Duration::from_millis(self.config.generation_delay_ms)).await;
+        let attempt = {
+            let mut attempts = self.attempts.lock().unwrap();
+            let attempt = attempts.entry(batch_id).or_insert(0);
+            *attempt += 1;
+            *attempt
+        };
+        let scenario = self.config.scenarios.iter().find(|scenario| scenario.applies_to(batch_id, attempt));
+
+        match scenario {
+            Some(FailureScenario::Timeout { after_ms, .. }) => {
+                sleep(Duration::from_millis(*after_ms)).await;
+                warn!("Scripted proof generation timeout for batch {}", batch_id);
+                return Ok(ProofGenerationResult {
+                    success: false,
+                    proof: None,
+                    error_message: Some(format!("Proof generation timed out after {} ms", after_ms)),
+                    generation_time_ms: start_time.elapsed().as_millis() as u64,
+                });
+            }
+            // Simulate proof generation time
+            _ if self.config.generation_delay_ms > 0 => {
+                sleep(Duration::from_millis(self.config.generation_delay_ms)).await;
+            }
+            _ => {}
+        }
+
+        if let Some(FailureScenario::FailAttempts { attempts, .. }) = scenario {
+            warn!("Scripted proof generation failure for batch {} (attempt {} of {})", batch_id, attempt, attempts);
+            return Ok(ProofGenerationResult {
+                success: false,
+                proof: None,
+                error_message: Some(format!("Scripted failure of attempt {} of {}", attempt, attempts)),
+                generation_time_ms: start_time.elapsed().as_millis() as u64,
+            });
         }
 
         // Simulate occasional failures if enabled
@@ -98,8 +171,17 @@ impl MvpProverService {
             }
         }
 
+        if let Err(e) = fault_injection::inject(FaultTarget::Prover).await {
+            return Ok(ProofGenerationResult {
+                success: false,
+                proof: None,
+                error_message: Some(e.to_string()),
+                generation_time_ms: start_time.elapsed().as_millis() as u64,
+            });
+        }
+
         // Generate mock proof
-        let proof = self.create_mock_proof(
+        let mut proof = self.create_mock_proof(
             batch_id,
             prev_state_root,
             prev_orders_root,
@@ -108,6 +190,11 @@ impl MvpProverService {
             orders,
         );
 
+        if let Some(FailureScenario::CorruptProof { .. }) = scenario {
+            warn!("Scripted corrupt proof for batch {}", batch_id);
+            proof.proof_data.iter_mut().for_each(|byte| *byte = !*byte);
+        }
+
         let generation_time = start_time.elapsed().as_millis() as u64;
 
         info!(
@@ -126,7 +213,7 @@ impl MvpProverService {
     /// Create a mock proof with deterministic but realistic-looking data
     fn create_mock_proof(
         &self,
-        batch_id: u32,
+        batch_id: u64,
         prev_state_root: &str,
         prev_orders_root: &str,
         new_state_root: &str,
@@ -200,23 +287,30 @@ impl MvpProverService {
         true
     }
 
-    /// Get prover statistics
-    pub fn get_stats(&self) -> ProverStats {
+    /// Get prover statistics, with the totals recorded so far
+    ///
+    /// Until a proof has been generated, the average is the configured delay.
+    pub fn get_stats(&self, totals: &ProofTotals) -> ProverStats {
         ProverStats {
             is_mock: true,
             generation_delay_ms: self.config.generation_delay_ms,
             simulate_failures: self.config.simulate_failures,
             failure_rate: self.config.failure_rate,
-            total_proofs_generated: 0, // TODO: Track this
-            total_failures: 0,         // TODO: Track this
-            average_generation_time_ms: self.config.generation_delay_ms,
+            total_proofs_generated: totals.generated,
+            total_failures: totals.failures,
+            average_generation_time_ms: totals.average_generation_time_ms().unwrap_or(self.config.generation_delay_ms),
         }
     }
 
-    /// Update prover configuration
+    pub fn config(&self) -> &MvpProverConfig {
+        &self.config
+    }
+
+    /// Update prover configuration, restarting the scripted scenarios' attempt counts
     pub fn update_config(&mut self, new_config: MvpProverConfig) {
         info!("Updating MVP prover configuration: {:?}", new_config);
         self.config = new_config;
+        self.attempts.lock().unwrap().clear();
     }
 }
 
@@ -286,6 +380,7 @@ mod tests {
             generation_delay_ms: 100,
             simulate_failures: false,
             failure_rate: 0.0,
+            scenarios: Vec::new(),
         };
         
         let prover = MvpProverService::new(config.clone());
@@ -299,6 +394,7 @@ mod tests {
             generation_delay_ms: 1, // Very fast for testing
             simulate_failures: false,
             failure_rate: 0.0,
+            scenarios: Vec::new(),
         };
         
         let prover = MvpProverService::new(config);
@@ -332,6 +428,7 @@ mod tests {
             generation_delay_ms: 1,
             simulate_failures: false,
             failure_rate: 0.0,
+            scenarios: Vec::new(),
         };
         
         let prover = MvpProverService::new(config);
@@ -359,6 +456,7 @@ mod tests {
             generation_delay_ms: 1,
             simulate_failures: false,
             failure_rate: 0.0,
+            scenarios: Vec::new(),
         };
         
         let prover = MvpProverService::new(config);
@@ -391,6 +489,7 @@ mod tests {
             generation_delay_ms: delay_ms,
             simulate_failures: false,
             failure_rate: 0.0,
+            scenarios: Vec::new(),
         };
         
         let prover = MvpProverService::new(config);
@@ -418,6 +517,7 @@ mod tests {
             generation_delay_ms: 1,
             simulate_failures: true,
             failure_rate: 1.0, // Always fail
+            scenarios: Vec::new(),
         };
         
         let prover = MvpProverService::new(config);
@@ -437,6 +537,84 @@ mod tests {
         assert_eq!(result.error_message.unwrap(), "Simulated proof generation failure");
     }
 
+    async fn prove(prover: &MvpProverService, batch_id: u64) -> ProofGenerationResult {
+        prover.generate_proof_for_batch(
+            batch_id,
+            "0x1111111111111111111111111111111111111111111111111111111111111111",
+            "0x2222222222222222222222222222222222222222222222222222222222222222",
+            "0x3333333333333333333333333333333333333333333333333333333333333333",
+            "0x4444444444444444444444444444444444444444444444444444444444444444",
+            &[],
+        ).await.unwrap()
+    }
+
+    fn scripted(scenarios: Vec<FailureScenario>) -> MvpProverService {
+        MvpProverService::new(MvpProverConfig {
+            generation_delay_ms: 0,
+            simulate_failures: false,
+            failure_rate: 0.0,
+            scenarios,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_scenario_fails_first_attempts_of_batch() {
+        let mut prover = scripted(vec![FailureScenario::FailAttempts { batch_id: Some(2), attempts: 2 }]);
+
+        assert!(prove(&prover, 1).await.success);
+        for attempt in 1..=2 {
+            let result = prove(&prover, 2).await;
+            assert!(!result.success);
+            assert_eq!(result.error_message.unwrap(), format!("Scripted failure of attempt {} of 2", attempt));
+        }
+        assert!(prove(&prover, 2).await.success);
+
+        // Setting the config again replays the script
+        prover.update_config(prover.config().clone());
+        assert!(!prove(&prover, 2).await.success);
+    }
+
+    #[tokio::test]
+    async fn test_scenario_timeout() {
+        let prover = scripted(vec![FailureScenario::Timeout { batch_id: None, after_ms: 20 }]);
+
+        let result = prove(&prover, 1).await;
+        assert!(!result.success);
+        assert!(result.proof.is_none());
+        assert!(result.generation_time_ms >= 20);
+        assert_eq!(result.error_message.unwrap(), "Proof generation timed out after 20 ms");
+    }
+
+    #[tokio::test]
+    async fn test_scenario_corrupt_proof() {
+        let honest = prove(&scripted(Vec::new()), 3).await.proof.unwrap();
+        let corrupt = prove(&scripted(vec![FailureScenario::CorruptProof { batch_id: Some(3) }]), 3).await;
+
+        // The prover reports success; the bytes are what a verifier must reject
+        assert!(corrupt.success);
+        let corrupt = corrupt.proof.unwrap();
+        assert_eq!(corrupt.proof_data.len(), honest.proof_data.len());
+        assert_ne!(corrupt.proof_data, honest.proof_data);
+        assert_eq!(corrupt.new_state_root, honest.new_state_root);
+    }
+
+    #[test]
+    fn test_scenarios_deserialize_from_config_json() {
+        let config: MvpProverConfig = serde_json::from_value(serde_json::json!({
+            "generation_delay_ms": 10,
+            "simulate_failures": false,
+            "failure_rate": 0.0,
+            "scenarios": [
+                { "kind": "fail_attempts", "batch_id": 4, "attempts": 3 },
+                { "kind": "timeout", "after_ms": 5000 },
+            ],
+        })).unwrap();
+        assert_eq!(config.scenarios, vec![
+            FailureScenario::FailAttempts { batch_id: Some(4), attempts: 3 },
+            FailureScenario::Timeout { batch_id: None, after_ms: 5000 },
+        ]);
+    }
+
     #[test]
     fn test_proof_validation_success() {
         let config = MvpProverConfig::default();
@@ -495,6 +673,7 @@ mod tests {
             generation_delay_ms: 0,
             simulate_failures: false,
             failure_rate: 0.0,
+            scenarios: Vec::new(),
         };
         let prover = MvpProverService::new(config);
 
@@ -566,15 +745,21 @@ mod tests {
             generation_delay_ms: 500,
             simulate_failures: true,
             failure_rate: 0.25,
+            scenarios: Vec::new(),
         };
         let prover = MvpProverService::new(config);
 
-        let stats = prover.get_stats();
+        let stats = prover.get_stats(&ProofTotals::default());
         assert!(stats.is_mock);
         assert_eq!(stats.generation_delay_ms, 500);
         assert!(stats.simulate_failures);
         assert_eq!(stats.failure_rate, 0.25);
         assert_eq!(stats.average_generation_time_ms, 500);
+
+        let stats = prover.get_stats(&ProofTotals { generated: 4, failures: 1, generation_ms: 1000 });
+        assert_eq!(stats.total_proofs_generated, 4);
+        assert_eq!(stats.total_failures, 1);
+        assert_eq!(stats.average_generation_time_ms, 250);
     }
 
     #[test]
@@ -583,6 +768,7 @@ mod tests {
             generation_delay_ms: 100,
             simulate_failures: false,
             failure_rate: 0.0,
+            scenarios: Vec::new(),
         };
         let mut prover = MvpProverService::new(initial_config);
 
@@ -590,6 +776,7 @@ mod tests {
             generation_delay_ms: 200,
             simulate_failures: true,
             failure_rate: 0.5,
+            scenarios: Vec::new(),
         };
 
         prover.update_config(new_config.clone());
@@ -604,6 +791,7 @@ mod tests {
             generation_delay_ms: 1,
             simulate_failures: false,
             failure_rate: 0.0,
+            scenarios: Vec::new(),
         };
         
         let prover = MvpProverService::new(config);
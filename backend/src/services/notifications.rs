@@ -0,0 +1,491 @@
+//! Order lifecycle notifications to users off-platform
+//!
+//! Order events relayed from the event log are matched against the preferences of the
+//! order's owner (its `from_address`, or `to_address` without one), rendered from a template
+//! per event and stored, one notification per channel. Each is then delivered by a
+//! `deliver_notification` job, so failed deliveries are retried with the scheduler's backoff,
+//! and the attempts and outcome are kept on the notification.
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::config::NotificationConfig;
+use crate::database::helpers;
+use crate::models::Order;
+use vapor_core::services::clock::SharedClock;
+use crate::services::event_log::{DomainEvent, LoggedEvent};
+use crate::services::query_metrics::QueryTimer;
+use crate::services::scheduler::{JobKind, Scheduler};
+use vapor_core::services::notifications::{
+    ConsoleTransport, DeliveryStatus, Notification, NotificationChannel, NotificationEvent, NotificationPreference,
+    NotificationTransport,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotificationError {
+    #[error("Invalid notification preference: {0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// The notification event for a domain event, if it is one users are notified of
+fn notification_event(event: &DomainEvent) -> Option<(NotificationEvent, &str)> {
+    match event {
+        DomainEvent::OrderLocked { order_id, .. } => Some((NotificationEvent::OrderLocked, order_id)),
+        DomainEvent::OrderPaid { order_id, .. } => Some((NotificationEvent::PaymentReceived, order_id)),
+        DomainEvent::OrderSettled { order_id, .. } => Some((NotificationEvent::OrderSettled, order_id)),
+        _ => None,
+    }
+}
+
+/// Fill a template's `{name}` placeholders; unknown names are left as they are
+pub fn render(template: &str, values: &HashMap<&str, String>) -> String {
+    values.iter().fold(template.to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+}
+
+fn template_values(order: &Order) -> HashMap<&'static str, String> {
+    HashMap::from([
+        ("order_id", order.id.clone()),
+        ("amount", order.amount.clone()),
+        ("token_id", order.token_id.to_string()),
+        ("filler_id", order.filler_id.clone().unwrap_or_else(|| "unknown".to_string())),
+        ("bank_service", order.bank_service.clone().filter(|service| !service.is_empty()).unwrap_or_else(|| "bank".to_string())),
+    ])
+}
+
+/// Stands in for an SMTP relay: logs the message it would send and accepts it
+pub struct SmtpStub {
+    from: String,
+}
+
+impl NotificationTransport for SmtpStub {
+    fn send<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            info!(
+                from = %self.from,
+                to = %notification.destination,
+                subject = %notification.subject,
+                "Email notification {} (SMTP stub, not sent)", notification.id
+            );
+            Ok(())
+        })
+    }
+}
+
+/// POSTs the notification as JSON to the user's URL; any non-2xx answer is a failed delivery
+pub struct WebhookTransport {
+    client: reqwest::Client,
+}
+
+impl NotificationTransport for WebhookTransport {
+    fn send<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.client.post(&notification.destination).json(notification).send().await?.error_for_status()?;
+            Ok(())
+        })
+    }
+}
+
+/// The transport of every channel, registered with the service at startup
+pub fn notification_transports_from_config(config: &NotificationConfig) -> Vec<(NotificationChannel, Arc<dyn NotificationTransport>)> {
+    vec![
+        (NotificationChannel::Email, Arc::new(SmtpStub { from: config.email_from.clone() })),
+        (NotificationChannel::Webhook, Arc::new(WebhookTransport { client: reqwest::Client::new() })),
+        (NotificationChannel::Console, Arc::new(ConsoleTransport)),
+    ]
+}
+
+fn row_to_notification(row: &sqlx::sqlite::SqliteRow) -> Result<Notification> {
+    let event: String = row.try_get("event")?;
+    let channel: String = row.try_get("channel")?;
+    let status: String = row.try_get("status")?;
+    Ok(Notification {
+        id: row.try_get("id")?,
+        address: row.try_get("address")?,
+        order_id: row.try_get("order_id")?,
+        event: NotificationEvent::parse(&event).ok_or_else(|| anyhow::anyhow!("Unknown notification event {}", event))?,
+        channel: NotificationChannel::parse(&channel).ok_or_else(|| anyhow::anyhow!("Unknown notification channel {}", channel))?,
+        destination: row.try_get("destination")?,
+        subject: row.try_get("subject")?,
+        body: row.try_get("body")?,
+        status: DeliveryStatus::parse(&status).ok_or_else(|| anyhow::anyhow!("Unknown delivery status {}", status))?,
+        attempts: row.try_get::<i64, _>("attempts")? as u32,
+        last_error: row.try_get("last_error")?,
+        created_at: row.try_get("created_at")?,
+        delivered_at: row.try_get("delivered_at")?,
+    })
+}
+
+#[derive(Clone)]
+pub struct NotificationService {
+    db: SqlitePool,
+    clock: SharedClock,
+    enabled: bool,
+    transports: HashMap<NotificationChannel, Arc<dyn NotificationTransport>>,
+}
+
+impl NotificationService {
+    /// A service with no transports; notifications on a channel without one fail to deliver
+    pub fn new(db: SqlitePool, config: &NotificationConfig, clock: SharedClock) -> Self {
+        Self { db, clock, enabled: config.enabled, transports: HashMap::new() }
+    }
+
+    pub fn with_transport(mut self, channel: NotificationChannel, transport: Arc<dyn NotificationTransport>) -> Self {
+        self.transports.insert(channel, transport);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// A user's preferences, by channel
+    pub async fn preferences(&self, address: &str) -> Result<Vec<NotificationPreference>, NotificationError> {
+        let _timer = QueryTimer::start("get_notification_preferences");
+        let rows = sqlx::query("SELECT channel, destination, events FROM notification_preferences WHERE address = ? ORDER BY channel")
+            .bind(address.to_lowercase())
+            .fetch_all(&self.db)
+            .await?;
+        Ok(rows.iter()
+            .filter_map(|row| {
+                let channel = NotificationChannel::parse(row.get::<String, _>("channel").as_str())?;
+                let events = row.get::<String, _>("events").split(',').filter_map(NotificationEvent::parse).collect();
+                Some(NotificationPreference { channel, destination: row.get("destination"), events })
+            })
+            .collect())
+    }
+
+    /// Replace a user's preferences; channels left out are no longer notified on
+    pub async fn set_preferences(&self, address: &str, preferences: &[NotificationPreference]) -> Result<Vec<NotificationPreference>, NotificationError> {
+        for (i, preference) in preferences.iter().enumerate() {
+            if !preference.channel.accepts_destination(&preference.destination) {
+                return Err(NotificationError::Invalid(format!("'{}' is not a {} destination", preference.destination, preference.channel.as_str())));
+            }
+            if preferences[..i].iter().any(|earlier| earlier.channel == preference.channel) {
+                return Err(NotificationError::Invalid(format!("{} is listed twice", preference.channel.as_str())));
+            }
+        }
+
+        let _timer = QueryTimer::start("set_notification_preferences");
+        let address = address.to_lowercase();
+        let now = self.clock.now();
+        let mut tx = self.db.begin().await?;
+        sqlx::query("DELETE FROM notification_preferences WHERE address = ?")
+            .bind(&address)
+            .execute(&mut *tx)
+            .await?;
+        for preference in preferences.iter().filter(|preference| !preference.events.is_empty()) {
+            let mut events: Vec<&str> = preference.events.iter().map(NotificationEvent::as_str).collect();
+            events.sort();
+            events.dedup();
+            sqlx::query("INSERT INTO notification_preferences (address, channel, destination, events, updated_at) VALUES (?, ?, ?, ?, ?)")
+                .bind(&address)
+                .bind(preference.channel.as_str())
+                .bind(&preference.destination)
+                .bind(events.join(","))
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        info!("Updated notification preferences of {}", address);
+        self.preferences(&address).await
+    }
+
+    /// Render and store the notifications a logged event calls for, returning the new ones
+    ///
+    /// Storing is idempotent per order, event, channel and user, so an event relayed twice
+    /// notifies once.
+    pub async fn enqueue(&self, logged: &LoggedEvent) -> Result<Vec<Notification>> {
+        let Ok(event) = serde_json::from_value::<DomainEvent>(logged.payload.clone()) else {
+            return Ok(Vec::new());
+        };
+        let Some((event, order_id)) = notification_event(&event) else {
+            return Ok(Vec::new());
+        };
+        let Some(order) = helpers::get_order_by_id(&self.db, order_id).await? else {
+            return Ok(Vec::new());
+        };
+        let Some(address) = order.from_address.as_deref().or(order.to_address.as_deref()).map(str::to_lowercase) else {
+            return Ok(Vec::new());
+        };
+
+        let values = template_values(&order);
+        let (subject, body) = event.template();
+        let mut queued = Vec::new();
+        for preference in self.preferences(&address).await? {
+            if !preference.events.contains(&event) {
+                continue;
+            }
+            let notification = Notification {
+                id: Uuid::new_v4().to_string(),
+                address: address.clone(),
+                order_id: order.id.clone(),
+                event,
+                channel: preference.channel,
+                destination: preference.destination,
+                subject: render(subject, &values),
+                body: render(body, &values),
+                status: DeliveryStatus::Pending,
+                attempts: 0,
+                last_error: None,
+                created_at: self.clock.now(),
+                delivered_at: None,
+            };
+
+            let _timer = QueryTimer::start("insert_notification");
+            let inserted = sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO notifications
+                    (id, address, order_id, event, channel, destination, subject, body, status, attempts, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?)
+                "#,
+            )
+            .bind(&notification.id)
+            .bind(&notification.address)
+            .bind(&notification.order_id)
+            .bind(notification.event.as_str())
+            .bind(notification.channel.as_str())
+            .bind(&notification.destination)
+            .bind(&notification.subject)
+            .bind(&notification.body)
+            .bind(notification.status.as_str())
+            .bind(notification.created_at)
+            .execute(&self.db)
+            .await?;
+            if inserted.rows_affected() > 0 {
+                queued.push(notification);
+            }
+        }
+        Ok(queued)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<Notification>> {
+        let _timer = QueryTimer::start("get_notification");
+        let row = sqlx::query("SELECT * FROM notifications WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?;
+        row.as_ref().map(row_to_notification).transpose()
+    }
+
+    /// A user's notifications, newest first
+    pub async fn history(&self, address: &str, limit: u32) -> Result<Vec<Notification>> {
+        let _timer = QueryTimer::start("list_notifications");
+        let rows = sqlx::query("SELECT * FROM notifications WHERE address = ? ORDER BY created_at DESC, id LIMIT ?")
+            .bind(address.to_lowercase())
+            .bind(limit as i64)
+            .fetch_all(&self.db)
+            .await?;
+        rows.iter().map(row_to_notification).collect()
+    }
+
+    /// Attempt one delivery, recording its outcome
+    ///
+    /// A failed attempt is returned as an error for the job to retry; on the last of
+    /// `max_attempts` the notification is marked failed instead.
+    pub async fn deliver(&self, id: &str, max_attempts: u32) -> Result<Option<Notification>> {
+        let Some(mut notification) = self.get(id).await? else {
+            return Ok(None);
+        };
+        if notification.status != DeliveryStatus::Pending {
+            return Ok(Some(notification));
+        }
+        let transport = self.transports.get(&notification.channel)
+            .ok_or_else(|| anyhow::anyhow!("No transport for the {} channel", notification.channel.as_str()))?;
+
+        let sent = transport.send(&notification).await;
+        notification.attempts += 1;
+        match &sent {
+            Ok(()) => {
+                notification.status = DeliveryStatus::Delivered;
+                notification.delivered_at = Some(self.clock.now());
+                notification.last_error = None;
+            }
+            Err(e) => {
+                warn!("Delivering notification {} over {} failed: {}", id, notification.channel.as_str(), e);
+                notification.last_error = Some(e.to_string());
+                if notification.attempts >= max_attempts {
+                    notification.status = DeliveryStatus::Failed;
+                }
+            }
+        }
+
+        let _timer = QueryTimer::start("update_notification_delivery");
+        sqlx::query("UPDATE notifications SET status = ?, attempts = ?, last_error = ?, delivered_at = ? WHERE id = ?")
+            .bind(notification.status.as_str())
+            .bind(notification.attempts as i64)
+            .bind(&notification.last_error)
+            .bind(notification.delivered_at)
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+
+        match sent {
+            Err(e) if notification.status == DeliveryStatus::Pending => Err(e),
+            _ => Ok(Some(notification)),
+        }
+    }
+
+    /// Queue notifications for events from the event bus, each delivered by its own job,
+    /// until the bus closes
+    pub async fn run(self, mut receiver: broadcast::Receiver<LoggedEvent>, scheduler: Scheduler) {
+        loop {
+            match receiver.recv().await {
+                Ok(logged) => match self.enqueue(&logged).await {
+                    Ok(queued) => {
+                        for notification in queued {
+                            if let Err(e) = scheduler.schedule(JobKind::DeliverNotification, &notification.id, None, chrono::Duration::zero()).await {
+                                error!("Failed to schedule delivery of notification {}: {}", notification.id, e);
+                            }
+                        }
+                    }
+                    Err(e) => warn!("Failed to queue notifications for event log entry {}: {}", logged.seq, e),
+                },
+                Err(RecvError::Lagged(skipped)) => warn!("Notifications fell behind, dropped {} events", skipped),
+                Err(RecvError::Closed) => {
+                    debug!("Event bus closed, stopping notifications");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::database::run_migrations;
+    use crate::models::{OrderStatus, OrderType};
+    use vapor_core::services::clock::system_clock;
+    use crate::services::event_log;
+    use std::sync::Mutex;
+
+    const SELLER: &str = "0x1234567890AbcdEF1234567890aBcdef12345678";
+
+    /// Fails the first `failures` sends, then records the rest
+    #[derive(Default)]
+    struct FlakyTransport {
+        failures: Mutex<u32>,
+        sent: Mutex<Vec<Notification>>,
+    }
+
+    impl NotificationTransport for FlakyTransport {
+        fn send<'a>(&'a self, notification: &'a Notification) -> BoxFuture<'a, Result<()>> {
+            Box::pin(async move {
+                let mut failures = self.failures.lock().unwrap();
+                if *failures > 0 {
+                    *failures -= 1;
+                    anyhow::bail!("connection refused");
+                }
+                self.sent.lock().unwrap().push(notification.clone());
+                Ok(())
+            })
+        }
+    }
+
+    fn order() -> Order {
+        Order {
+            id: "order_1".to_string(),
+            order_type: OrderType::BridgeOut,
+            from_address: Some(SELLER.to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "250".to_string(),
+            bank_account: Some("84127312".to_string()),
+            bank_service: Some("Wise".to_string()),
+            banking_hash: None,
+            filler_id: Some("filler_1".to_string()),
+            locked_amount: None,
+            status: OrderStatus::Locked,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_notify_subscribed_channels_and_retry_failed_deliveries() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let webhook = Arc::new(FlakyTransport { failures: Mutex::new(1), ..Default::default() });
+        let service = NotificationService::new(db.clone(), &NotificationConfig::default(), system_clock())
+            .with_transport(NotificationChannel::Webhook, webhook.clone());
+
+        let invalid = service.set_preferences(SELLER, &[NotificationPreference {
+            channel: NotificationChannel::Email,
+            destination: "not-an-email".to_string(),
+            events: vec![NotificationEvent::OrderSettled],
+        }]).await;
+        assert!(matches!(invalid, Err(NotificationError::Invalid(_))));
+
+        service.set_preferences(SELLER, &[
+            NotificationPreference {
+                channel: NotificationChannel::Webhook,
+                destination: "https://seller.example/hooks".to_string(),
+                events: vec![NotificationEvent::OrderLocked, NotificationEvent::OrderSettled],
+            },
+            NotificationPreference {
+                channel: NotificationChannel::Email,
+                destination: "seller@example.com".to_string(),
+                events: vec![NotificationEvent::OrderSettled],
+            },
+        ]).await.unwrap();
+
+        helpers::insert_order(&db, &order()).await.unwrap();
+        event_log::append(&db, &DomainEvent::OrderLocked {
+            order_id: "order_1".to_string(),
+            filler_id: "filler_1".to_string(),
+            locked_amount: "250".to_string(),
+        }).await.unwrap();
+        let logged = event_log::read(&db, 0, 10).await.unwrap();
+        let locked = logged.iter().find(|event| event.kind == "order_locked").unwrap();
+
+        let queued = service.enqueue(locked).await.unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].channel, NotificationChannel::Webhook);
+        assert_eq!(queued[0].address, SELLER.to_lowercase());
+        assert_eq!(queued[0].subject, "Order order_1 was picked up");
+        assert!(queued[0].body.contains("Filler filler_1 locked your order order_1 for 250 of token 1"));
+        assert!(queued[0].body.contains("Wise"));
+        // Relayed again, the event is not notified twice
+        assert!(service.enqueue(locked).await.unwrap().is_empty());
+
+        let id = &queued[0].id;
+        assert!(service.deliver(id, 3).await.is_err());
+        let delivered = service.deliver(id, 3).await.unwrap().unwrap();
+        assert_eq!((delivered.status, delivered.attempts), (DeliveryStatus::Delivered, 2));
+        assert_eq!(webhook.sent.lock().unwrap().len(), 1);
+
+        // Out of attempts, the delivery is given up
+        *webhook.failures.lock().unwrap() = 5;
+        event_log::append(&db, &DomainEvent::status_change("order_1", OrderStatus::MarkPaid, OrderStatus::Settled, None)).await.unwrap();
+        let settled = event_log::read(&db, 0, 10).await.unwrap().into_iter().find(|event| event.kind == "order_settled").unwrap();
+        let queued = service.enqueue(&settled).await.unwrap();
+        assert_eq!(queued.len(), 2);
+        let hook = queued.iter().find(|notification| notification.channel == NotificationChannel::Webhook).unwrap();
+        assert!(service.deliver(&hook.id, 1).await.unwrap().is_some_and(|notification| notification.status == DeliveryStatus::Failed));
+
+        let history = service.history(SELLER, 10).await.unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.iter().filter(|notification| notification.status == DeliveryStatus::Pending).count(), 1);
+
+        // Email has no transport here until the ones registered at startup are
+        let email = queued.iter().find(|notification| notification.channel == NotificationChannel::Email).unwrap();
+        assert!(service.deliver(&email.id, 3).await.is_err());
+        let service = notification_transports_from_config(&NotificationConfig::default())
+            .into_iter()
+            .fold(service, |service, (channel, transport)| service.with_transport(channel, transport));
+        let delivered = service.deliver(&email.id, 3).await.unwrap().unwrap();
+        assert_eq!((delivered.status, delivered.attempts), (DeliveryStatus::Delivered, 1));
+    }
+}
@@ -0,0 +1,274 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use tracing::{info, warn};
+
+use vapor_chain::BlockchainClient;
+use crate::config::{CostWeighting, ProofSubmissionConfig};
+use crate::models::Order;
+use crate::services::proof_compression::PreparedSubmission;
+
+/// Intrinsic gas of any transaction, added to the calldata gas of an estimate
+const BASE_TX_GAS: u64 = 21_000;
+const WEI_PER_GWEI: u128 = 1_000_000_000;
+
+/// Gas a batch's proof submission used and the price it was paid at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubmissionCost {
+    pub gas_used: u64,
+    pub gas_price_wei: u128,
+    /// No on-chain receipt; `gas_used` is estimated from the calldata
+    pub estimated: bool,
+}
+
+impl SubmissionCost {
+    /// Estimate of a submission that was not sent on-chain
+    pub fn estimate(calldata_gas: u64, gas_price_wei: u128) -> Self {
+        Self { gas_used: BASE_TX_GAS + calldata_gas, gas_price_wei, estimated: true }
+    }
+}
+
+/// Cost of a batch's submission: the receipt's gas when it went on-chain, else the calldata estimate
+///
+/// Priced at the network's gas price, or the configured one without a client.
+pub async fn submission_cost(
+    config: &ProofSubmissionConfig,
+    chain: Option<&BlockchainClient>,
+    submission: &PreparedSubmission,
+    gas_used: Option<u64>,
+) -> SubmissionCost {
+    let configured = config.gas_price_gwei as u128 * WEI_PER_GWEI;
+    let gas_price_wei = match chain {
+        Some(client) => match client.get_gas_price().await {
+            Ok(gas_price) => gas_price.as_u128(),
+            Err(e) => {
+                warn!("Using configured gas price for batch {} costs, network price unavailable: {}", submission.batch_id, e);
+                configured
+            }
+        },
+        None => configured,
+    };
+
+    match gas_used {
+        Some(gas_used) => SubmissionCost { gas_used, gas_price_wei, estimated: false },
+        None => SubmissionCost::estimate(submission.calldata_gas(), gas_price_wei),
+    }
+}
+
+/// One order's share of its batch's submission cost
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderCost {
+    pub order_id: String,
+    pub batch_id: u64,
+    pub weighting: CostWeighting,
+    pub gas_used: u64,
+    pub gas_price_wei: String,
+    pub gas_cost_wei: String,
+    pub estimated: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A batch's submission cost and how it was attributed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchCostReport {
+    pub batch_id: u64,
+    pub gas_used: u64,
+    pub gas_cost_wei: String,
+    pub estimated: bool,
+    pub orders: Vec<OrderCost>,
+}
+
+/// Split a batch's submission cost across its orders
+///
+/// With `Value` weighting shares follow the raw order amounts, falling back to equal shares
+/// when none of them parse. Rounding leftovers go to the heaviest order, so the shares always
+/// add up to the batch's cost.
+pub fn apportion(batch_id: u64, orders: &[Order], cost: SubmissionCost, weighting: CostWeighting) -> Vec<OrderCost> {
+    let mut weights: Vec<u128> = match weighting {
+        CostWeighting::Count => vec![1; orders.len()],
+        CostWeighting::Value => orders.iter().map(|order| order.amount.parse().unwrap_or(0)).collect(),
+    };
+    if weights.iter().all(|weight| *weight == 0) {
+        weights = vec![1; orders.len()];
+    }
+
+    let gas = split(cost.gas_used as u128, &weights);
+    let wei = split(cost.gas_used as u128 * cost.gas_price_wei, &weights);
+    let now = Utc::now();
+    orders.iter()
+        .zip(gas.into_iter().zip(wei))
+        .map(|(order, (gas_used, gas_cost_wei))| OrderCost {
+            order_id: order.id.clone(),
+            batch_id,
+            weighting,
+            gas_used: gas_used as u64,
+            gas_price_wei: cost.gas_price_wei.to_string(),
+            gas_cost_wei: gas_cost_wei.to_string(),
+            estimated: cost.estimated,
+            created_at: now,
+        })
+        .collect()
+}
+
+fn split(total: u128, weights: &[u128]) -> Vec<u128> {
+    let sum: u128 = weights.iter().sum();
+    if sum == 0 {
+        return vec![0; weights.len()];
+    }
+    let mut shares: Vec<u128> = weights.iter().map(|weight| total * weight / sum).collect();
+    let heaviest = weights.iter()
+        .enumerate()
+        .fold(0, |best, (index, weight)| if *weight > weights[best] { index } else { best });
+    shares[heaviest] += total - shares.iter().sum::<u128>();
+    shares
+}
+
+/// Store the attributed costs, replacing any earlier attribution of the same batch
+pub async fn record(db: &SqlitePool, costs: &[OrderCost]) -> Result<()> {
+    let mut tx = db.begin().await?;
+    for cost in costs {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO order_costs
+                (batch_id, order_id, weighting, gas_used, gas_price_wei, gas_cost_wei, estimated, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+        )
+        .bind(cost.batch_id as i64)
+        .bind(&cost.order_id)
+        .bind(cost.weighting.as_str())
+        .bind(cost.gas_used as i64)
+        .bind(&cost.gas_price_wei)
+        .bind(&cost.gas_cost_wei)
+        .bind(cost.estimated)
+        .bind(cost.created_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    if let Some(cost) = costs.first() {
+        info!("Attributed batch {} submission cost to {} orders", cost.batch_id, costs.len());
+    }
+    Ok(())
+}
+
+/// The cost attributed to an order, from the latest batch it settled in
+pub async fn for_order(db: &SqlitePool, order_id: &str) -> Result<Option<OrderCost>> {
+    let row = sqlx::query(
+        r#"
+        SELECT batch_id, order_id, weighting, gas_used, gas_price_wei, gas_cost_wei, estimated, created_at
+        FROM order_costs WHERE order_id = ?1 ORDER BY batch_id DESC LIMIT 1
+        "#,
+    )
+    .bind(order_id)
+    .fetch_optional(db)
+    .await?;
+
+    row.map(|row| order_cost(&row)).transpose()
+}
+
+/// A batch's attributed costs, or `None` before its proof was submitted
+pub async fn for_batch(db: &SqlitePool, batch_id: u64) -> Result<Option<BatchCostReport>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT batch_id, order_id, weighting, gas_used, gas_price_wei, gas_cost_wei, estimated, created_at
+        FROM order_costs WHERE batch_id = ?1 ORDER BY order_id
+        "#,
+    )
+    .bind(batch_id as i64)
+    .fetch_all(db)
+    .await?;
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let orders = rows.iter().map(order_cost).collect::<Result<Vec<_>>>()?;
+    Ok(Some(BatchCostReport {
+        batch_id,
+        gas_used: orders.iter().map(|cost| cost.gas_used).sum(),
+        gas_cost_wei: orders.iter()
+            .map(|cost| cost.gas_cost_wei.parse::<u128>().unwrap_or(0))
+            .sum::<u128>()
+            .to_string(),
+        estimated: orders.iter().any(|cost| cost.estimated),
+        orders,
+    }))
+}
+
+fn order_cost(row: &sqlx::sqlite::SqliteRow) -> Result<OrderCost> {
+    Ok(OrderCost {
+        order_id: row.try_get("order_id")?,
+        batch_id: row.try_get::<i64, _>("batch_id")? as u64,
+        weighting: CostWeighting::parse(row.try_get("weighting")?),
+        gas_used: row.try_get::<i64, _>("gas_used")? as u64,
+        gas_price_wei: row.try_get("gas_price_wei")?,
+        gas_cost_wei: row.try_get("gas_cost_wei")?,
+        estimated: row.try_get("estimated")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{OrderStatus, OrderType};
+
+    fn order(id: &str, amount: &str) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::Transfer,
+            from_address: None,
+            to_address: None,
+            token_id: 1,
+            amount: amount.to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            status: OrderStatus::Pending,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_costs_are_split_by_count_or_value() {
+        let orders = vec![order("a", "100"), order("b", "300"), order("c", "0")];
+        let cost = SubmissionCost { gas_used: 200_000, gas_price_wei: 10, estimated: false };
+
+        let by_count = apportion(7, &orders, cost, CostWeighting::Count);
+        let gas: Vec<u64> = by_count.iter().map(|cost| cost.gas_used).collect();
+        // Leftover units go to the first of the equally heavy orders
+        assert_eq!(gas, vec![66_668, 66_666, 66_666]);
+        assert_eq!(by_count[0].gas_cost_wei, "666668");
+
+        let by_value = apportion(7, &orders, cost, CostWeighting::Value);
+        let wei: Vec<&str> = by_value.iter().map(|cost| cost.gas_cost_wei.as_str()).collect();
+        assert_eq!(wei, vec!["500000", "1500000", "0"]);
+
+        // Unparseable amounts fall back to equal shares
+        let unpriced = apportion(7, &[order("a", "x"), order("b", "")], cost, CostWeighting::Value);
+        assert_eq!(unpriced[0].gas_used, 100_000);
+        assert!(apportion(7, &[], cost, CostWeighting::Count).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recorded_costs_by_order_and_batch() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+
+        let cost = SubmissionCost::estimate(1_000, 2);
+        assert_eq!(cost.gas_used, 22_000);
+        record(&db, &apportion(3, &[order("a", "1"), order("b", "3")], cost, CostWeighting::Value)).await.unwrap();
+
+        let report = for_batch(&db, 3).await.unwrap().unwrap();
+        assert_eq!((report.gas_used, report.gas_cost_wei.as_str(), report.estimated), (22_000, "44000", true));
+        assert_eq!(for_order(&db, "b").await.unwrap().unwrap().gas_cost_wei, "33000");
+        assert!(for_batch(&db, 4).await.unwrap().is_none());
+        assert!(for_order(&db, "c").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,96 @@
+use serde::Serialize;
+
+use crate::config::{AmountRange, OrderAmountConfig};
+use crate::models::{Order, OrderType};
+
+/// An order amount outside the range configured for its token and order type
+///
+/// Both bounds are reported so clients can show the allowed range; an unset bound is None.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(tag = "limit", rename_all = "snake_case")]
+pub enum OrderAmountError {
+    #[error("{order_type:?} amount {amount} of token {token_id} is below the minimum of {}", min.unwrap_or_default())]
+    BelowMinimum { token_id: u32, order_type: OrderType, amount: String, min: Option<u64>, max: Option<u64> },
+    #[error("{order_type:?} amount {amount} of token {token_id} is above the maximum of {}", max.unwrap_or_default())]
+    AboveMaximum { token_id: u32, order_type: OrderType, amount: String, min: Option<u64>, max: Option<u64> },
+}
+
+/// Check an order's amount against the range for its token and type
+///
+/// Amounts that are not integers are left to the order's own validation.
+pub fn check(config: &OrderAmountConfig, order: &Order) -> Result<(), OrderAmountError> {
+    let AmountRange { min, max } = config.range_for(order.token_id, order.order_type);
+    let Ok(amount) = order.amount.parse::<u128>() else {
+        return Ok(());
+    };
+
+    let bound = |value: u64| (value > 0).then_some(value);
+    let (token_id, order_type, amount_text) = (order.token_id, order.order_type, order.amount.clone());
+    if min > 0 && amount < min as u128 {
+        return Err(OrderAmountError::BelowMinimum { token_id, order_type, amount: amount_text, min: bound(min), max: bound(max) });
+    }
+    if max > 0 && amount > max as u128 {
+        return Err(OrderAmountError::AboveMaximum { token_id, order_type, amount: amount_text, min: bound(min), max: bound(max) });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OrderTypeAmounts;
+    use crate::models::OrderStatus;
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    fn order(order_type: OrderType, token_id: u32, amount: &str) -> Order {
+        Order {
+            id: "order".to_string(),
+            order_type,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id,
+            amount: amount.to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            status: OrderStatus::Pending,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_amounts_are_checked_per_token_and_type() {
+        let config = OrderAmountConfig {
+            default_limits: OrderTypeAmounts { bridge_in: AmountRange { min: 1_000, max: 0 }, ..Default::default() },
+            token_limits: HashMap::from([(
+                1,
+                OrderTypeAmounts {
+                    bridge_out: AmountRange { min: 0, max: 10_000_000 },
+                    ..Default::default()
+                },
+            )]),
+        };
+
+        assert!(check(&config, &order(OrderType::BridgeIn, 2, "1000")).is_ok());
+        let dust = check(&config, &order(OrderType::BridgeIn, 2, "1")).unwrap_err();
+        assert_eq!(dust, OrderAmountError::BelowMinimum {
+            token_id: 2,
+            order_type: OrderType::BridgeIn,
+            amount: "1".to_string(),
+            min: Some(1_000),
+            max: None,
+        });
+
+        // Token 1 overrides the default, so its BridgeIn orders are not bounded
+        assert!(check(&config, &order(OrderType::BridgeIn, 1, "1")).is_ok());
+        assert!(check(&config, &order(OrderType::BridgeOut, 1, "10000000")).is_ok());
+        let whale = check(&config, &order(OrderType::BridgeOut, 1, "99999999999999999999999")).unwrap_err();
+        assert!(matches!(whale, OrderAmountError::AboveMaximum { max: Some(10_000_000), .. }));
+        assert!(check(&config, &order(OrderType::Transfer, 1, "not a number")).is_ok());
+    }
+}
@@ -0,0 +1,394 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use vapor_chain::BlockchainClient;
+use crate::config::ReconciliationConfig;
+use crate::database::helpers;
+use crate::models::OrderStatus;
+use vapor_core::services::clock::SharedClock;
+use crate::services::matching_engine::MatchingEngine;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationTrigger {
+    Scheduled,
+    Manual,
+}
+
+impl ReconciliationTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReconciliationTrigger::Scheduled => "scheduled",
+            ReconciliationTrigger::Manual => "manual",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedOrder {
+    pub order_id: String,
+    pub filler_id: Option<String>,
+}
+
+/// A match whose lock window passed without the filler locking the order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnlockedMatch {
+    pub order_id: String,
+    pub filler_id: String,
+    pub locked_until: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettledOrder {
+    pub order_id: String,
+    /// The batch the order names, when it has one but that batch was never finalized
+    pub batch_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainClaim {
+    pub order_id: u32,
+    pub batch_id: u64,
+    pub transaction_hash: String,
+}
+
+/// Mismatches between the matching engine, the database and the bridge contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    /// Set once the report is stored
+    pub id: Option<i64>,
+    pub trigger: ReconciliationTrigger,
+    pub checked_at: DateTime<Utc>,
+    /// Orders Locked in the database that the matching engine does not hold
+    pub locked_unknown_to_engine: Vec<LockedOrder>,
+    /// Locks the engine holds for orders no longer Locked in the database
+    pub engine_locks_not_in_db: Vec<LockedOrder>,
+    /// Orders the engine matched that were never locked through the API
+    pub matched_never_locked: Vec<UnlockedMatch>,
+    /// Settled orders that are not in any finalized batch
+    pub settled_without_batch: Vec<SettledOrder>,
+    /// Claim events scanned, or None without a blockchain client
+    pub chain_claims_scanned: Option<usize>,
+    /// Orders claimed on-chain with no local claim record
+    pub claims_missing_locally: Vec<ChainClaim>,
+}
+
+impl ReconciliationReport {
+    pub fn mismatches(&self) -> usize {
+        self.locked_unknown_to_engine.len()
+            + self.engine_locks_not_in_db.len()
+            + self.matched_never_locked.len()
+            + self.settled_without_batch.len()
+            + self.claims_missing_locally.len()
+    }
+}
+
+/// A stored report without its mismatch lists
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportSummary {
+    pub id: i64,
+    pub trigger: String,
+    pub mismatches: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Orders Locked in the database, with the filler holding each
+pub async fn locked_orders(db: &SqlitePool) -> Result<Vec<LockedOrder>> {
+    let rows = sqlx::query("SELECT id, filler_id FROM orders WHERE status = ? ORDER BY created_at, id")
+        .bind(OrderStatus::Locked as i32)
+        .fetch_all(db)
+        .await?;
+    rows.iter()
+        .map(|row| Ok(LockedOrder { order_id: row.try_get("id")?, filler_id: row.try_get("filler_id")? }))
+        .collect()
+}
+
+/// Settled orders without a batch, or naming a batch that has no finalized snapshot
+async fn settled_without_batch(db: &SqlitePool) -> Result<Vec<SettledOrder>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, batch_id FROM orders
+        WHERE status = ? AND (batch_id IS NULL OR batch_id NOT IN (SELECT batch_id FROM batch_snapshots))
+        ORDER BY created_at, id
+        "#,
+    )
+    .bind(OrderStatus::Settled as i32)
+    .fetch_all(db)
+    .await?;
+    rows.iter()
+        .map(|row| {
+            Ok(SettledOrder {
+                order_id: row.try_get("id")?,
+                batch_id: row.try_get::<Option<i64>, _>("batch_id")?.map(|id| id as u64),
+            })
+        })
+        .collect()
+}
+
+/// Produces and stores order book reconciliation reports
+#[derive(Clone)]
+pub struct OrderReconciler {
+    db: SqlitePool,
+    matching_engine: Arc<Mutex<MatchingEngine>>,
+    clock: SharedClock,
+    config: ReconciliationConfig,
+    /// First block scanned for claim events
+    from_block: u64,
+}
+
+impl OrderReconciler {
+    pub fn new(
+        db: SqlitePool,
+        matching_engine: Arc<Mutex<MatchingEngine>>,
+        clock: SharedClock,
+        config: ReconciliationConfig,
+        from_block: u64,
+    ) -> Self {
+        Self { db, matching_engine, clock, config, from_block }
+    }
+
+    /// Compare the engine, database and chain, then store the report
+    pub async fn run(&self, chain: Option<&BlockchainClient>, trigger: ReconciliationTrigger) -> Result<ReconciliationReport> {
+        let now = self.clock.now();
+        let db_locks = locked_orders(&self.db).await?;
+
+        // Copy the engine's view out so matching is not held up by the queries below
+        let (engine_locks, matches) = {
+            let engine = self.matching_engine.lock().await;
+            (engine.locked_orders.clone(), engine.matched_orders.values().cloned().collect::<Vec<_>>())
+        };
+
+        let db_locked: HashMap<&str, &LockedOrder> = db_locks.iter().map(|lock| (lock.order_id.as_str(), lock)).collect();
+        let locked_unknown_to_engine = db_locks.iter()
+            .filter(|lock| !engine_locks.contains_key(&lock.order_id))
+            .cloned()
+            .collect();
+        let mut engine_locks_not_in_db: Vec<_> = engine_locks.iter()
+            .filter(|(order_id, _)| !db_locked.contains_key(order_id.as_str()))
+            .map(|(order_id, filler_id)| LockedOrder { order_id: order_id.clone(), filler_id: Some(filler_id.clone()) })
+            .collect();
+        engine_locks_not_in_db.sort_by(|a, b| a.order_id.cmp(&b.order_id));
+
+        let mut matched_never_locked: Vec<_> = matches.into_iter()
+            .filter(|matched| matched.locked_until < now)
+            .map(|matched| UnlockedMatch { order_id: matched.order_id, filler_id: matched.filler_id, locked_until: matched.locked_until })
+            .collect();
+        matched_never_locked.sort_by(|a, b| a.locked_until.cmp(&b.locked_until).then(a.order_id.cmp(&b.order_id)));
+
+        let (chain_claims_scanned, claims_missing_locally) = match chain {
+            Some(chain) => {
+                let events = chain.get_claim_events(self.from_block, None).await?;
+                let mut missing = Vec::new();
+                for event in &events {
+                    if helpers::get_claim_by_order(&self.db, event.order_id).await?.is_none() {
+                        missing.push(ChainClaim {
+                            order_id: event.order_id,
+                            batch_id: event.batch_id,
+                            transaction_hash: format!("{:?}", event.transaction_hash),
+                        });
+                    }
+                }
+                (Some(events.len()), missing)
+            }
+            None => (None, Vec::new()),
+        };
+
+        let mut report = ReconciliationReport {
+            id: None,
+            trigger,
+            checked_at: now,
+            locked_unknown_to_engine,
+            engine_locks_not_in_db,
+            matched_never_locked,
+            settled_without_batch: settled_without_batch(&self.db).await?,
+            chain_claims_scanned,
+            claims_missing_locally,
+        };
+        report.id = Some(self.store(&report).await?);
+
+        match report.mismatches() {
+            0 => info!("Order book reconciliation found no mismatches"),
+            mismatches => warn!(
+                "Order book reconciliation found {} mismatches: {} locked unknown to engine, {} stale engine locks, {} matches never locked, {} settled without batch, {} on-chain claims missing locally",
+                mismatches,
+                report.locked_unknown_to_engine.len(),
+                report.engine_locks_not_in_db.len(),
+                report.matched_never_locked.len(),
+                report.settled_without_batch.len(),
+                report.claims_missing_locally.len(),
+            ),
+        }
+        Ok(report)
+    }
+
+    async fn store(&self, report: &ReconciliationReport) -> Result<i64> {
+        let id = sqlx::query("INSERT INTO reconciliation_reports (trigger, mismatches, report, created_at) VALUES (?, ?, ?, ?)")
+            .bind(report.trigger.as_str())
+            .bind(report.mismatches() as i64)
+            .bind(serde_json::to_string(report)?)
+            .bind(report.checked_at)
+            .execute(&self.db)
+            .await?
+            .last_insert_rowid();
+
+        sqlx::query("DELETE FROM reconciliation_reports WHERE id NOT IN (SELECT id FROM reconciliation_reports ORDER BY id DESC LIMIT ?)")
+            .bind(self.config.retain_reports.max(1) as i64)
+            .execute(&self.db)
+            .await?;
+        Ok(id)
+    }
+
+    /// A stored report; the latest one when `id` is None
+    pub async fn get(&self, id: Option<i64>) -> Result<Option<ReconciliationReport>> {
+        let row = match id {
+            Some(id) => sqlx::query("SELECT id, report FROM reconciliation_reports WHERE id = ?").bind(id).fetch_optional(&self.db).await?,
+            None => sqlx::query("SELECT id, report FROM reconciliation_reports ORDER BY id DESC LIMIT 1").fetch_optional(&self.db).await?,
+        };
+        row.map(|row| {
+            let mut report: ReconciliationReport = serde_json::from_str(&row.try_get::<String, _>("report")?)?;
+            report.id = Some(row.try_get("id")?);
+            Ok(report)
+        })
+        .transpose()
+    }
+
+    /// Stored reports, newest first
+    pub async fn list(&self) -> Result<Vec<ReportSummary>> {
+        let rows = sqlx::query("SELECT id, trigger, mismatches, created_at FROM reconciliation_reports ORDER BY id DESC")
+            .fetch_all(&self.db)
+            .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(ReportSummary {
+                    id: row.try_get("id")?,
+                    trigger: row.try_get("trigger")?,
+                    mismatches: row.try_get("mismatches")?,
+                    created_at: row.try_get("created_at")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Time until the next scheduled run, at `hour_utc`
+    pub fn until_next_run(&self) -> std::time::Duration {
+        let now = self.clock.now();
+        let at = NaiveTime::from_hms_opt(self.config.hour_utc, 0, 0).unwrap_or_default();
+        let mut next = now.date_naive().and_time(at).and_utc();
+        if next <= now {
+            next += Duration::days(1);
+        }
+        (next - now).to_std().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Order, OrderType};
+    use vapor_core::services::clock::MockClock;
+    use chrono::TimeZone;
+
+    async fn insert(db: &SqlitePool, id: &str, status: OrderStatus, filler_id: Option<&str>, batch_id: Option<u64>) {
+        let order = Order {
+            id: id.to_string(),
+            order_type: OrderType::BridgeIn,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: filler_id.map(str::to_string),
+            locked_amount: filler_id.map(|_| "1000".to_string()),
+            status,
+            batch_id,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        helpers::insert_order(db, &order).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_report_lists_each_kind_of_mismatch() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap());
+
+        insert(&db, "locked_known", OrderStatus::Locked, Some("filler_1"), None).await;
+        insert(&db, "locked_unknown", OrderStatus::Locked, Some("filler_2"), None).await;
+        insert(&db, "paid", OrderStatus::MarkPaid, Some("filler_1"), None).await;
+        insert(&db, "settled_in_batch", OrderStatus::Settled, None, Some(1)).await;
+        insert(&db, "settled_unfinalized", OrderStatus::Settled, None, Some(2)).await;
+        insert(&db, "settled_no_batch", OrderStatus::Settled, None, None).await;
+        sqlx::query("INSERT INTO batch_snapshots (batch_id, state_root, orders_root, data, created_at) VALUES (1, '0x', '0x', '{}', ?)")
+            .bind(Utc::now())
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let mut engine = MatchingEngine::new().with_clock(clock.shared());
+        engine.add_filler("filler_1".to_string(), "0xf1".to_string(), 10_000).unwrap();
+        let template = helpers::get_order_by_id(&db, "paid").await.unwrap().unwrap();
+        for (id, then) in [("matched_expired", Duration::minutes(45)), ("matched_recent", Duration::minutes(5))] {
+            engine.add_order(Order { id: id.to_string(), amount: "100".to_string(), ..template.clone() }).unwrap();
+            engine.match_orders().unwrap();
+            clock.advance(then);
+        }
+        engine.record_lock("locked_known", "filler_1");
+        // Payment never cleared this lock
+        engine.record_lock("paid", "filler_1");
+
+        let reconciler = OrderReconciler::new(
+            db,
+            Arc::new(Mutex::new(engine)),
+            clock.shared(),
+            ReconciliationConfig { hour_utc: 3, retain_reports: 2 },
+            0,
+        );
+        let report = reconciler.run(None, ReconciliationTrigger::Manual).await.unwrap();
+
+        assert_eq!(report.locked_unknown_to_engine, vec![LockedOrder { order_id: "locked_unknown".to_string(), filler_id: Some("filler_2".to_string()) }]);
+        assert_eq!(report.engine_locks_not_in_db.iter().map(|lock| lock.order_id.as_str()).collect::<Vec<_>>(), vec!["paid"]);
+        // The second match's lock window has not passed yet
+        assert_eq!(report.matched_never_locked.iter().map(|m| m.order_id.as_str()).collect::<Vec<_>>(), vec!["matched_expired"]);
+        assert_eq!(
+            report.settled_without_batch,
+            vec![
+                SettledOrder { order_id: "settled_unfinalized".to_string(), batch_id: Some(2) },
+                SettledOrder { order_id: "settled_no_batch".to_string(), batch_id: None },
+            ]
+        );
+        assert_eq!(report.chain_claims_scanned, None);
+        assert_eq!(report.mismatches(), 5);
+
+        let stored = reconciler.get(None).await.unwrap().unwrap();
+        assert_eq!(stored.id, report.id);
+        assert_eq!(stored.settled_without_batch, report.settled_without_batch);
+
+        // Only the newest reports are kept
+        reconciler.run(None, ReconciliationTrigger::Scheduled).await.unwrap();
+        reconciler.run(None, ReconciliationTrigger::Scheduled).await.unwrap();
+        let summaries = reconciler.list().await.unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].trigger, "scheduled");
+        assert!(reconciler.get(report.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_next_run_is_at_the_configured_hour() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 3, 1, 2, 30, 0).unwrap());
+        let reconciler = OrderReconciler::new(db, Arc::new(Mutex::new(MatchingEngine::new())), clock.shared(), ReconciliationConfig::default(), 0);
+
+        assert_eq!(reconciler.until_next_run(), std::time::Duration::from_secs(30 * 60));
+        clock.advance(Duration::hours(1));
+        assert_eq!(reconciler.until_next_run(), std::time::Duration::from_secs(23 * 3600 + 30 * 60));
+    }
+}
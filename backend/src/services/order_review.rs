@@ -0,0 +1,340 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::config::OrderReviewConfig;
+use crate::database::helpers;
+use crate::models::{Order, OrderStatus};
+use vapor_core::services::clock::SharedClock;
+use crate::services::discovery::{advance_to_discovery, is_discovery_eligible};
+use crate::services::event_bus::EventBus;
+use crate::services::matching_engine::MatchingEngine;
+
+/// History reason of an order held for review
+pub const REVIEW_REQUIRED: &str = "review_required";
+/// History reason, and prefix of the reviewer's reason, of an approved order
+pub const REVIEW_APPROVED: &str = "review_approved";
+/// Failure reason, and history prefix of the reviewer's reason, of a rejected order
+pub const REVIEW_REJECTED: &str = "review_rejected";
+
+#[derive(Debug, Serialize, thiserror::Error)]
+#[serde(tag = "review", rename_all = "snake_case")]
+pub enum OrderReviewError {
+    #[error("order {order_id} was never held for review")]
+    NotFound { order_id: String },
+    #[error("order {order_id} is not waiting for review")]
+    NotPending { order_id: String },
+    #[error("rejecting order {order_id} needs a reason")]
+    ReasonRequired { order_id: String },
+    #[error(transparent)]
+    #[serde(skip)]
+    Other(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for OrderReviewError {
+    fn from(e: sqlx::Error) -> Self {
+        OrderReviewError::Other(e.into())
+    }
+}
+
+/// An admin's decision on a held order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderReviewDecision {
+    /// Release the order to matching
+    Approved,
+    /// Fail the order
+    Rejected,
+}
+
+impl OrderReviewDecision {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "approved" => Some(Self::Approved),
+            "rejected" => Some(Self::Rejected),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderReviewEntry {
+    pub order_id: String,
+    pub token_id: u32,
+    pub amount: String,
+    /// The token's threshold when the order was held
+    pub threshold: u64,
+    pub requested_at: DateTime<Utc>,
+    pub decision: Option<OrderReviewDecision>,
+    pub reason: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+/// Holds BridgeIn orders above their token's threshold in ReviewPending until an admin
+/// approves them into Discovery or rejects them
+///
+/// Tokens without a threshold are never reviewed.
+#[derive(Clone)]
+pub struct OrderReview {
+    db: SqlitePool,
+    thresholds: HashMap<u32, u64>,
+    clock: SharedClock,
+}
+
+impl OrderReview {
+    pub fn new(db: SqlitePool, config: &OrderReviewConfig, clock: SharedClock) -> Self {
+        Self { db, thresholds: config.thresholds.clone(), clock }
+    }
+
+    /// The threshold an order is above, if it needs review
+    pub fn threshold_for(&self, order: &Order) -> Option<u64> {
+        if !is_discovery_eligible(order.order_type) {
+            return None;
+        }
+        let threshold = *self.thresholds.get(&order.token_id)?;
+        // Amounts too large for u128 are certainly above any threshold
+        let above = order.amount.parse::<u128>().map_or(true, |amount| amount > threshold as u128);
+        above.then_some(threshold)
+    }
+
+    /// Move a Pending order above its token's threshold to ReviewPending and queue it;
+    /// returns whether the order is now held
+    pub async fn hold_if_required(&self, order: &Order) -> Result<bool> {
+        let Some(threshold) = self.threshold_for(order) else {
+            return Ok(false);
+        };
+
+        let now = self.clock.now();
+        // Guard on status so an order that already moved on is left alone
+        let result = sqlx::query("UPDATE orders SET status = ?1, updated_at = ?2 WHERE id = ?3 AND status = ?4")
+            .bind(OrderStatus::ReviewPending as i32)
+            .bind(now)
+            .bind(&order.id)
+            .bind(OrderStatus::Pending as i32)
+            .execute(&self.db)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        helpers::record_status_transition(&self.db, &order.id, OrderStatus::Pending, OrderStatus::ReviewPending, Some(REVIEW_REQUIRED)).await?;
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO order_reviews (order_id, token_id, amount, threshold, requested_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+        )
+        .bind(&order.id)
+        .bind(order.token_id as i32)
+        .bind(&order.amount)
+        .bind(threshold as i64)
+        .bind(now)
+        .execute(&self.db)
+        .await?;
+
+        info!("Order {} of {} (token {}) is above the review threshold {}, held for review", order.id, order.amount, order.token_id, threshold);
+        Ok(true)
+    }
+
+    /// Held orders, oldest first; decided ones too when `include_decided`
+    pub async fn queue(&self, include_decided: bool, limit: u32) -> Result<Vec<OrderReviewEntry>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM order_reviews
+            WHERE ?1 OR decision IS NULL
+            ORDER BY requested_at, order_id
+            LIMIT ?2
+            "#,
+        )
+        .bind(include_decided)
+        .bind(limit as i64)
+        .fetch_all(&self.db)
+        .await?;
+
+        rows.iter().map(entry_from_row).collect()
+    }
+
+    /// Approve a held order into Discovery and matching, or reject it as Failed
+    ///
+    /// The decision and the reviewer's reason go into the order's status history.
+    pub async fn decide(
+        &self,
+        order_id: &str,
+        decision: OrderReviewDecision,
+        reason: Option<&str>,
+        matching_engine: &Arc<Mutex<MatchingEngine>>,
+        event_bus: &EventBus,
+    ) -> Result<OrderReviewEntry, OrderReviewError> {
+        let reason = reason.map(str::trim).filter(|reason| !reason.is_empty());
+        if decision == OrderReviewDecision::Rejected && reason.is_none() {
+            return Err(OrderReviewError::ReasonRequired { order_id: order_id.to_string() });
+        }
+
+        let now = self.clock.now();
+        let updated = sqlx::query(
+            "UPDATE order_reviews SET decision = ?2, reason = ?3, decided_at = ?4 WHERE order_id = ?1 AND decision IS NULL"
+        )
+        .bind(order_id)
+        .bind(decision.as_str())
+        .bind(reason)
+        .bind(now)
+        .execute(&self.db)
+        .await?;
+
+        let row = sqlx::query("SELECT * FROM order_reviews WHERE order_id = ?1")
+            .bind(order_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| OrderReviewError::NotFound { order_id: order_id.to_string() })?;
+        if updated.rows_affected() == 0 {
+            return Err(OrderReviewError::NotPending { order_id: order_id.to_string() });
+        }
+
+        let (to_status, tag) = match decision {
+            OrderReviewDecision::Approved => (OrderStatus::Pending, REVIEW_APPROVED),
+            OrderReviewDecision::Rejected => (OrderStatus::Failed, REVIEW_REJECTED),
+        };
+        let failure_reason = (decision == OrderReviewDecision::Rejected).then_some(REVIEW_REJECTED);
+        let moved = sqlx::query(
+            "UPDATE orders SET status = ?1, failure_reason = COALESCE(?2, failure_reason), updated_at = ?3 WHERE id = ?4 AND status = ?5"
+        )
+        .bind(to_status as i32)
+        .bind(failure_reason)
+        .bind(now)
+        .bind(order_id)
+        .bind(OrderStatus::ReviewPending as i32)
+        .execute(&self.db)
+        .await?;
+        if moved.rows_affected() == 0 {
+            warn!("Order {} left ReviewPending before its review was decided", order_id);
+            return Ok(entry_from_row(&row)?);
+        }
+
+        let history_reason = match reason {
+            Some(reason) => format!("{}: {}", tag, reason),
+            None => tag.to_string(),
+        };
+        helpers::record_status_transition(&self.db, order_id, OrderStatus::ReviewPending, to_status, Some(&history_reason)).await?;
+
+        if decision == OrderReviewDecision::Approved {
+            if let Some(order) = helpers::get_order_by_id(&self.db, order_id).await? {
+                if let Err(e) = matching_engine.lock().await.add_order(order) {
+                    warn!("Failed to add approved order {} to the matching engine: {}", order_id, e);
+                }
+            }
+            advance_to_discovery(&self.db, event_bus, order_id, REVIEW_APPROVED).await?;
+        }
+
+        info!("Review of order {} {}", order_id, decision.as_str());
+        Ok(entry_from_row(&row)?)
+    }
+}
+
+fn entry_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<OrderReviewEntry> {
+    let decision: Option<String> = row.try_get("decision")?;
+    Ok(OrderReviewEntry {
+        order_id: row.try_get("order_id")?,
+        token_id: row.try_get::<i32, _>("token_id")? as u32,
+        amount: row.try_get("amount")?,
+        threshold: row.try_get::<i64, _>("threshold")? as u64,
+        requested_at: row.try_get("requested_at")?,
+        decision: decision.as_deref().and_then(OrderReviewDecision::parse),
+        reason: row.try_get("reason")?,
+        decided_at: row.try_get("decided_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OrderType;
+    use vapor_core::services::clock::system_clock;
+
+    fn order(id: &str, order_type: OrderType, amount: &str) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type,
+            status: OrderStatus::Pending,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            token_id: 1,
+            amount: amount.to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_orders_above_threshold_wait_for_a_decision() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let config = OrderReviewConfig { thresholds: HashMap::from([(1, 10_000)]) };
+        let review = OrderReview::new(db.clone(), &config, system_clock());
+        let engine = Arc::new(Mutex::new(MatchingEngine::new()));
+        let bus = EventBus::new(16);
+
+        let small = order("small", OrderType::BridgeIn, "10000");
+        let large = order("large", OrderType::BridgeIn, "10001");
+        let huge = order("huge", OrderType::BridgeIn, "1000000000000000000000000000000000000000");
+        let transfer = order("transfer", OrderType::Transfer, "99999");
+        for order in [&small, &large, &huge, &transfer] {
+            helpers::insert_order(&db, order).await.unwrap();
+        }
+        assert_eq!(review.threshold_for(&small), None);
+        assert_eq!(review.threshold_for(&transfer), None);
+        assert!(!review.hold_if_required(&small).await.unwrap());
+        assert!(review.hold_if_required(&large).await.unwrap());
+        assert!(review.hold_if_required(&huge).await.unwrap());
+        assert_eq!(helpers::get_order_by_id(&db, "large").await.unwrap().unwrap().status, OrderStatus::ReviewPending);
+
+        let queued: Vec<String> = review.queue(false, 10).await.unwrap().into_iter().map(|entry| entry.order_id).collect();
+        assert_eq!(queued.len(), 2);
+        assert!(queued.contains(&"large".to_string()));
+
+        // Rejections need a reason; approvals release the order to Discovery and matching
+        assert!(matches!(
+            review.decide("huge", OrderReviewDecision::Rejected, Some(" "), &engine, &bus).await,
+            Err(OrderReviewError::ReasonRequired { .. })
+        ));
+        let rejected = review.decide("huge", OrderReviewDecision::Rejected, Some("source of funds unclear"), &engine, &bus).await.unwrap();
+        assert_eq!(rejected.decision, Some(OrderReviewDecision::Rejected));
+        assert_eq!(helpers::get_order_by_id(&db, "huge").await.unwrap().unwrap().status, OrderStatus::Failed);
+
+        review.decide("large", OrderReviewDecision::Approved, None, &engine, &bus).await.unwrap();
+        assert_eq!(helpers::get_order_by_id(&db, "large").await.unwrap().unwrap().status, OrderStatus::Discovery);
+        assert_eq!(engine.lock().await.pending_orders.len(), 1);
+        let reasons: Vec<Option<String>> = helpers::get_order_history(&db, "large").await.unwrap().into_iter().map(|t| t.reason).collect();
+        assert_eq!(reasons, vec![Some(REVIEW_REQUIRED.to_string()), Some(REVIEW_APPROVED.to_string()), Some(REVIEW_APPROVED.to_string())]);
+        let history = helpers::get_order_history(&db, "huge").await.unwrap();
+        assert_eq!(history[1].reason.as_deref(), Some("review_rejected: source of funds unclear"));
+
+        assert!(review.queue(false, 10).await.unwrap().is_empty());
+        assert_eq!(review.queue(true, 10).await.unwrap().len(), 2);
+        assert!(matches!(
+            review.decide("large", OrderReviewDecision::Rejected, Some("late"), &engine, &bus).await,
+            Err(OrderReviewError::NotPending { .. })
+        ));
+        assert!(matches!(
+            review.decide("small", OrderReviewDecision::Approved, None, &engine, &bus).await,
+            Err(OrderReviewError::NotFound { .. })
+        ));
+    }
+}
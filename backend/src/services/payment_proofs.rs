@@ -0,0 +1,402 @@
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+
+/// Payment rails with a structured proof schema
+///
+/// Bank services are free-form names such as "PayPal Hong Kong"; their first word picks the rail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentRail {
+    Paypal,
+    Wise,
+    Ach,
+}
+
+impl PaymentRail {
+    pub const ALL: [PaymentRail; 3] = [PaymentRail::Paypal, PaymentRail::Wise, PaymentRail::Ach];
+
+    /// Rail of a bank service, or None for services still using an opaque banking hash
+    pub fn for_bank_service(bank_service: &str) -> Option<Self> {
+        let first_word = bank_service.split_whitespace().next()?;
+        Self::ALL.into_iter().find(|rail| rail.service_prefix().eq_ignore_ascii_case(first_word))
+    }
+
+    /// Leading word of the bank services on this rail
+    pub fn service_prefix(self) -> &'static str {
+        match self {
+            PaymentRail::Paypal => "PayPal",
+            PaymentRail::Wise => "Wise",
+            PaymentRail::Ach => "ACH",
+        }
+    }
+
+    /// Parse and validate a proof payload for this rail
+    pub fn parse_proof(self, payload: Value) -> Result<PaymentProof, PaymentProofError> {
+        let invalid = |e: serde_json::Error| PaymentProofError::Invalid { rail: self, message: e.to_string() };
+        Ok(match self {
+            PaymentRail::Paypal => PaymentProof::Paypal(serde_json::from_value(payload).map_err(invalid)?),
+            PaymentRail::Wise => PaymentProof::Wise(serde_json::from_value(payload).map_err(invalid)?),
+            PaymentRail::Ach => PaymentProof::Ach(serde_json::from_value(payload).map_err(invalid)?),
+        })
+    }
+
+    /// Fields of this rail's proofs, with what each must be and the check it must pass
+    fn fields(self) -> &'static [FieldCheck] {
+        match self {
+            PaymentRail::Paypal => &[
+                ("transaction_id", PAYPAL_TRANSACTION_ID, is_paypal_transaction_id),
+                ("payer_email_hash", HASH32, is_hash32),
+            ],
+            PaymentRail::Wise => &[("transfer_id", WISE_TRANSFER_ID, is_wise_transfer_id)],
+            PaymentRail::Ach => &[("trace_number", ACH_TRACE_NUMBER, is_ach_trace_number)],
+        }
+    }
+
+    /// Check every field of a proof payload for this rail without stopping at the first problem
+    ///
+    /// A payload has no invalid diagnostic exactly when `parse_proof` accepts it.
+    pub fn diagnose(self, payload: &Value) -> Vec<FieldDiagnostic> {
+        let Some(object) = payload.as_object() else {
+            return vec![FieldDiagnostic::invalid("payment_proof", format!("{} payment proofs are JSON objects", self))];
+        };
+
+        let mut diagnostics: Vec<FieldDiagnostic> = self.fields().iter()
+            .map(|(name, expected, check)| {
+                let field = format!("payment_proof.{}", name);
+                match object.get(*name) {
+                    None => FieldDiagnostic::invalid(field, "is required".to_string()),
+                    Some(Value::String(value)) if check(value) => FieldDiagnostic::valid(field),
+                    Some(Value::String(value)) => FieldDiagnostic::invalid(field, format!("'{}' is not {}", value, expected)),
+                    Some(_) => FieldDiagnostic::invalid(field, format!("must be a string, {}", expected)),
+                }
+            })
+            .collect();
+        diagnostics.extend(object.keys()
+            .filter(|key| self.fields().iter().all(|(name, _, _)| name != key))
+            .map(|key| FieldDiagnostic::invalid(format!("payment_proof.{}", key), format!("is not a field of {} payment proofs", self))));
+        diagnostics
+    }
+
+    /// JSON Schema of the proof payload, as served by the bank services registry
+    pub fn proof_schema(self) -> Value {
+        let (properties, required) = match self {
+            PaymentRail::Paypal => (
+                json!({
+                    "transaction_id": {
+                        "type": "string",
+                        "pattern": "^[A-Z0-9]{17}$",
+                        "description": "PayPal transaction id from the payment receipt",
+                    },
+                    "payer_email_hash": {
+                        "type": "string",
+                        "pattern": "^0x[0-9a-f]{64}$",
+                        "description": "keccak256 of the payer's lowercased PayPal email",
+                    },
+                }),
+                json!(["transaction_id", "payer_email_hash"]),
+            ),
+            PaymentRail::Wise => (
+                json!({
+                    "transfer_id": {
+                        "type": "string",
+                        "pattern": "^[0-9]{1,20}$",
+                        "description": "Wise transfer id",
+                    },
+                }),
+                json!(["transfer_id"]),
+            ),
+            PaymentRail::Ach => (
+                json!({
+                    "trace_number": {
+                        "type": "string",
+                        "pattern": "^[0-9]{15}$",
+                        "description": "15-digit ACH trace number",
+                    },
+                }),
+                json!(["trace_number"]),
+            ),
+        };
+
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        })
+    }
+}
+
+impl std::fmt::Display for PaymentRail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.service_prefix())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PaymentProofError {
+    #[error("Invalid {rail} payment proof: {message}")]
+    Invalid { rail: PaymentRail, message: String },
+    #[error("Bank service '{0}' has no payment proof schema, submit a banking_hash instead")]
+    UnsupportedService(String),
+    #[error("A banking_hash or payment_proof is required")]
+    Missing,
+}
+
+/// Outcome of checking one field of a payment proof submission
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldDiagnostic {
+    pub field: String,
+    pub valid: bool,
+    /// Why the field would be refused, None when it is valid
+    pub message: Option<String>,
+}
+
+impl FieldDiagnostic {
+    pub fn valid(field: impl Into<String>) -> Self {
+        Self { field: field.into(), valid: true, message: None }
+    }
+
+    pub fn invalid(field: impl Into<String>, message: String) -> Self {
+        Self { field: field.into(), valid: false, message: Some(message) }
+    }
+}
+
+/// Validated payment proof, stored as typed JSON on the order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rail", rename_all = "snake_case")]
+pub enum PaymentProof {
+    Paypal(PaypalProof),
+    Wise(WiseProof),
+    Ach(AchProof),
+}
+
+impl PaymentProof {
+    /// 0x-prefixed keccak256 of the stored JSON, used as the banking hash when none is given
+    pub fn digest(&self) -> String {
+        let json = serde_json::to_vec(self).expect("payment proofs serialize");
+        format!("0x{}", hex::encode(web3::signing::keccak256(&json)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaypalProof {
+    #[serde(deserialize_with = "paypal_transaction_id")]
+    pub transaction_id: String,
+    #[serde(deserialize_with = "hash32")]
+    pub payer_email_hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WiseProof {
+    #[serde(deserialize_with = "wise_transfer_id")]
+    pub transfer_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AchProof {
+    #[serde(deserialize_with = "ach_trace_number")]
+    pub trace_number: String,
+}
+
+/// A proof field's name, a description of what it must be, and its check
+type FieldCheck = (&'static str, &'static str, fn(&str) -> bool);
+
+const PAYPAL_TRANSACTION_ID: &str = "a 17 character PayPal transaction id";
+const HASH32: &str = "a 0x-prefixed lowercase 32-byte hex hash";
+const WISE_TRANSFER_ID: &str = "a numeric Wise transfer id";
+const ACH_TRACE_NUMBER: &str = "a 15-digit ACH trace number";
+
+fn validated<'de, D: Deserializer<'de>>(deserializer: D, expected: &str, check: impl Fn(&str) -> bool) -> Result<String, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    if check(&value) {
+        Ok(value)
+    } else {
+        Err(D::Error::custom(format!("'{}' is not {}", value, expected)))
+    }
+}
+
+fn is_digits(value: &str, lengths: std::ops::RangeInclusive<usize>) -> bool {
+    lengths.contains(&value.len()) && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_paypal_transaction_id(value: &str) -> bool {
+    value.len() == 17 && value.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+}
+
+fn is_hash32(value: &str) -> bool {
+    value.strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)))
+}
+
+fn is_wise_transfer_id(value: &str) -> bool {
+    is_digits(value, 1..=20)
+}
+
+fn is_ach_trace_number(value: &str) -> bool {
+    is_digits(value, 15..=15)
+}
+
+fn paypal_transaction_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    validated(deserializer, PAYPAL_TRANSACTION_ID, is_paypal_transaction_id)
+}
+
+fn hash32<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    validated(deserializer, HASH32, is_hash32)
+}
+
+fn wise_transfer_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    validated(deserializer, WISE_TRANSFER_ID, is_wise_transfer_id)
+}
+
+fn ach_trace_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    validated(deserializer, ACH_TRACE_NUMBER, is_ach_trace_number)
+}
+
+/// Entry of the bank services registry
+#[derive(Debug, Clone, Serialize)]
+pub struct BankServiceSchema {
+    pub rail: PaymentRail,
+    /// Bank services whose name starts with this word use the rail
+    pub service_prefix: &'static str,
+    pub proof_schema: Value,
+}
+
+/// Every rail with its required proof schema
+pub fn registry() -> Vec<BankServiceSchema> {
+    PaymentRail::ALL.into_iter()
+        .map(|rail| BankServiceSchema {
+            rail,
+            service_prefix: rail.service_prefix(),
+            proof_schema: rail.proof_schema(),
+        })
+        .collect()
+}
+
+/// What the payment verification provider made of a proof
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Verification {
+    Verified,
+    /// Not visible to the provider yet; asked again later
+    Pending,
+    Rejected { reason: Option<String> },
+}
+
+/// Checks submitted payment proofs with an external provider (`PAYMENT_VERIFIER_URL`)
+///
+/// Providers are eventually consistent, so a `Pending` answer is expected right after a
+/// payment. Without a provider every proof that passed its schema is accepted.
+#[derive(Debug, Clone)]
+pub struct PaymentVerifier {
+    url: Option<String>,
+    client: reqwest::Client,
+}
+
+impl PaymentVerifier {
+    pub fn new(url: Option<String>) -> Self {
+        Self { url, client: reqwest::Client::new() }
+    }
+
+    pub async fn verify(
+        &self,
+        order_id: &str,
+        bank_service: Option<&str>,
+        banking_hash: Option<&str>,
+        payment_proof: Option<&Value>,
+    ) -> anyhow::Result<Verification> {
+        let Some(url) = &self.url else {
+            return Ok(Verification::Verified);
+        };
+        let verification = self.client
+            .post(url)
+            .json(&json!({
+                "order_id": order_id,
+                "bank_service": bank_service,
+                "banking_hash": banking_hash,
+                "payment_proof": payment_proof,
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(verification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMAIL_HASH: &str = "0x1c8aff950685c2ed4bc3174f3472287b56d9517b9c948127319a09a7a36deac8";
+
+    #[test]
+    fn test_bank_service_resolves_to_rail() {
+        assert_eq!(PaymentRail::for_bank_service("PayPal Hong Kong"), Some(PaymentRail::Paypal));
+        assert_eq!(PaymentRail::for_bank_service("paypal"), Some(PaymentRail::Paypal));
+        assert_eq!(PaymentRail::for_bank_service("Wise"), Some(PaymentRail::Wise));
+        assert_eq!(PaymentRail::for_bank_service("ACH"), Some(PaymentRail::Ach));
+        assert_eq!(PaymentRail::for_bank_service("Venmo"), None);
+        assert_eq!(PaymentRail::for_bank_service(""), None);
+    }
+
+    #[test]
+    fn test_proofs_are_validated_per_rail() {
+        let proof = PaymentRail::Paypal
+            .parse_proof(json!({ "transaction_id": "8MC585209K746392H", "payer_email_hash": EMAIL_HASH }))
+            .unwrap();
+        assert!(matches!(proof, PaymentProof::Paypal(_)));
+        assert_eq!(
+            serde_json::to_value(&proof).unwrap(),
+            json!({ "rail": "paypal", "transaction_id": "8MC585209K746392H", "payer_email_hash": EMAIL_HASH })
+        );
+        // Stored proofs read back as the same type
+        assert_eq!(serde_json::from_value::<PaymentProof>(serde_json::to_value(&proof).unwrap()).unwrap(), proof);
+
+        for (rail, payload) in [
+            (PaymentRail::Paypal, json!({ "transaction_id": "8mc585209k746392h", "payer_email_hash": EMAIL_HASH })),
+            (PaymentRail::Paypal, json!({ "transaction_id": "8MC585209K746392H" })),
+            (PaymentRail::Wise, json!({ "transfer_id": "12ab" })),
+            (PaymentRail::Wise, json!({ "transfer_id": "123", "note": "extra" })),
+            (PaymentRail::Ach, json!({ "trace_number": "12345" })),
+        ] {
+            assert!(matches!(rail.parse_proof(payload), Err(PaymentProofError::Invalid { .. })));
+        }
+
+        assert!(PaymentRail::Ach.parse_proof(json!({ "trace_number": "091000019876543" })).is_ok());
+    }
+
+    #[test]
+    fn test_digest_depends_on_payload() {
+        let wise = |id: &str| PaymentRail::Wise.parse_proof(json!({ "transfer_id": id })).unwrap();
+        assert_eq!(wise("123456789").digest(), wise("123456789").digest());
+        assert_ne!(wise("123456789").digest(), wise("123456780").digest());
+        assert_eq!(wise("123456789").digest().len(), 66);
+    }
+
+    #[test]
+    fn test_diagnostics_report_every_field_and_agree_with_parsing() {
+        let diagnostics = PaymentRail::Paypal.diagnose(&json!({ "transaction_id": "8mc585209k746392h", "memo": "rent" }));
+        let by_field: Vec<_> = diagnostics.iter().map(|d| (d.field.as_str(), d.valid)).collect();
+        assert_eq!(by_field, vec![
+            ("payment_proof.transaction_id", false),
+            ("payment_proof.payer_email_hash", false),
+            ("payment_proof.memo", false),
+        ]);
+        assert_eq!(diagnostics[1].message.as_deref(), Some("is required"));
+
+        for (rail, payload) in [
+            (PaymentRail::Paypal, json!({ "transaction_id": "8MC585209K746392H", "payer_email_hash": EMAIL_HASH })),
+            (PaymentRail::Wise, json!({ "transfer_id": 123 })),
+            (PaymentRail::Wise, json!({ "transfer_id": "123" })),
+            (PaymentRail::Ach, json!({ "trace_number": "12345" })),
+            (PaymentRail::Ach, json!("091000019876543")),
+        ] {
+            let valid = rail.diagnose(&payload).iter().all(|d| d.valid);
+            assert_eq!(valid, rail.parse_proof(payload).is_ok());
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use anyhow::Result;
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::BTreeSet;
+use tracing::{info, warn, instrument};
+
+use vapor_core::ethereum_address_to_path;
+use vapor_core::proof_format::{
+    bit_path_to_path_bits, index_to_path_bits, parse_hash32, FormattedProof, ProofError, ProofFormat, SortedPairTree,
+};
+use crate::merkle::MerkleTreeManager;
+use crate::models::{AccountState, Order};
+use crate::services::archival::BatchSnapshot;
+
+/// Formats precomputed for order proofs
+pub const ORDER_PROOF_FORMATS: [ProofFormat; 3] = [ProofFormat::Siblings, ProofFormat::Raw, ProofFormat::SortedPairs];
+/// Formats precomputed for account proofs (the account tree is positional, see get_account_proof)
+pub const ACCOUNT_PROOF_FORMATS: [ProofFormat; 2] = [ProofFormat::Siblings, ProofFormat::Raw];
+
+const ORDER_KIND: &str = "order";
+const ACCOUNT_KIND: &str = "account";
+
+/// Build proofs for the orders at `indices` of a batch, building the tree only once
+pub fn build_order_proofs(orders: &[Order], batch_id: u64, indices: &[usize], format: ProofFormat) -> Result<Vec<FormattedProof>, ProofError> {
+    match format {
+        ProofFormat::SortedPairs => {
+            let leaves = orders.iter()
+                .map(|order| order.hash_leaf_with_batch_id(batch_id))
+                .collect::<Result<Vec<_>>>()
+                .map_err(ProofError::Tree)?;
+            let tree = SortedPairTree::from_leaves(leaves)?;
+            indices.iter()
+                .map(|&index| {
+                    let leaf = tree.leaf(index)
+                        .ok_or(ProofError::LeafIndexOutOfRange(index))?;
+                    Ok(FormattedProof::sorted_pairs(leaf, &tree.proof(index)?, tree.root()))
+                })
+                .collect()
+        }
+        ProofFormat::Siblings | ProofFormat::Raw => {
+            let mut manager = MerkleTreeManager::new();
+            manager.build_orders_tree(orders, batch_id).map_err(ProofError::Tree)?;
+            indices.iter()
+                .map(|&index| {
+                    let proof = manager.generate_order_proof(index).map_err(ProofError::Tree)?;
+                    let siblings = proof.proof.iter()
+                        .map(|sibling| parse_hash32(sibling))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let path_bits = index_to_path_bits(index, siblings.len());
+                    Ok(FormattedProof::positional(
+                        format,
+                        parse_hash32(&proof.leaf_hash)?,
+                        &siblings,
+                        path_bits,
+                        parse_hash32(&proof.root)?,
+                    ))
+                })
+                .collect()
+        }
+    }
+}
+
+/// Build an account proof from a state tree that has already been built
+pub fn build_account_proof(manager: &mut MerkleTreeManager, address: &str, format: ProofFormat) -> Result<FormattedProof, ProofError> {
+    if format == ProofFormat::SortedPairs {
+        return Err(ProofError::SortedPairsForAccount);
+    }
+
+    let proof = manager.generate_account_proof(address).map_err(ProofError::Tree)?;
+    let path_bits = (format == ProofFormat::Raw)
+        .then(|| bit_path_to_path_bits(&ethereum_address_to_path(address, proof.proof.len())));
+
+    Ok(FormattedProof {
+        format,
+        leaf_hash: proof.leaf_hash,
+        proof: proof.proof,
+        root: proof.root,
+        path_bits,
+    })
+}
+
+/// Accounts of a snapshot that the batch's orders touched
+fn touched_accounts(snapshot: &BatchSnapshot) -> Vec<&AccountState> {
+    let touched: BTreeSet<String> = snapshot.orders.iter()
+        .flat_map(|order| [order.from_address.as_deref(), order.to_address.as_deref()])
+        .flatten()
+        .map(str::to_lowercase)
+        .collect();
+
+    snapshot.accounts.iter()
+        .filter(|account| touched.contains(&account.address.to_lowercase()))
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PrecomputeReport {
+    pub order_proofs: usize,
+    pub account_proofs: usize,
+}
+
+/// Proofs precomputed when a batch is finalized, so proof requests for recent
+/// batches are a single row lookup instead of a tree walk
+#[derive(Clone)]
+pub struct ProofCache {
+    db: SqlitePool,
+    /// Number of recent batches to keep proofs for (0 disables precomputation)
+    keep_batches: u32,
+}
+
+impl ProofCache {
+    pub fn new(db: SqlitePool, keep_batches: u32) -> Self {
+        Self { db, keep_batches }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.keep_batches > 0
+    }
+
+    /// Precompute and store proofs for every order and touched account of a finalized batch
+    #[instrument(skip_all, fields(batch_id = snapshot.batch_id))]
+    pub async fn precompute(&self, snapshot: &BatchSnapshot) -> Result<PrecomputeReport> {
+        let mut report = PrecomputeReport::default();
+        if !self.is_enabled() {
+            return Ok(report);
+        }
+
+        let batch_id = snapshot.batch_id;
+        let indices: Vec<usize> = (0..snapshot.orders.len()).collect();
+        let mut rows: Vec<(&str, String, ProofFormat, Option<usize>, FormattedProof)> = Vec::new();
+
+        if !snapshot.orders.is_empty() {
+            for format in ORDER_PROOF_FORMATS {
+                let proofs = build_order_proofs(&snapshot.orders, batch_id, &indices, format)?;
+                for (index, proof) in proofs.into_iter().enumerate() {
+                    rows.push((ORDER_KIND, snapshot.orders[index].id.clone(), format, Some(index), proof));
+                    report.order_proofs += 1;
+                }
+            }
+        }
+
+        let touched = touched_accounts(snapshot);
+        if !touched.is_empty() {
+            let mut manager = MerkleTreeManager::new();
+            manager.build_state_tree(&snapshot.accounts)?;
+            for account in touched {
+                for format in ACCOUNT_PROOF_FORMATS {
+                    match build_account_proof(&mut manager, &account.address, format) {
+                        Ok(proof) => {
+                            rows.push((ACCOUNT_KIND, account.address.to_lowercase(), format, None, proof));
+                            report.account_proofs += 1;
+                        }
+                        Err(e) => warn!("Skipping account proof for {} in batch {}: {}", account.address, batch_id, e),
+                    }
+                }
+            }
+        }
+
+        let mut tx = self.db.begin().await?;
+        for (kind, key, format, leaf_index, proof) in rows {
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO precomputed_proofs (batch_id, kind, proof_key, format, leaf_index, proof, created_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                "#,
+            )
+            .bind(batch_id as i64)
+            .bind(kind)
+            .bind(key)
+            .bind(format.as_str())
+            .bind(leaf_index.map(|index| index as i64))
+            .bind(serde_json::to_string(&proof)?)
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Older batches fall back to computing proofs on request
+        sqlx::query("DELETE FROM precomputed_proofs WHERE batch_id <= ?")
+            .bind(batch_id as i64 - self.keep_batches as i64)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        info!(
+            "Precomputed {} order and {} account proofs for batch {}",
+            report.order_proofs, report.account_proofs, batch_id
+        );
+        Ok(report)
+    }
+
+    /// A precomputed order proof and the order's leaf index
+    pub async fn get_order_proof(&self, batch_id: u64, order_id: &str, format: ProofFormat) -> Result<Option<(FormattedProof, Option<usize>)>> {
+        let row = self.lookup(batch_id, ORDER_KIND, order_id, format).await?;
+        row.map(|row| {
+            let proof: String = row.try_get("proof")?;
+            let leaf_index: Option<i64> = row.try_get("leaf_index")?;
+            Ok((serde_json::from_str(&proof)?, leaf_index.map(|index| index as usize)))
+        })
+        .transpose()
+    }
+
+    pub async fn get_account_proof(&self, batch_id: u64, address: &str, format: ProofFormat) -> Result<Option<FormattedProof>> {
+        let row = self.lookup(batch_id, ACCOUNT_KIND, &address.to_lowercase(), format).await?;
+        row.map(|row| Ok(serde_json::from_str(&row.try_get::<String, _>("proof")?)?))
+            .transpose()
+    }
+
+    async fn lookup(&self, batch_id: u64, kind: &str, key: &str, format: ProofFormat) -> Result<Option<sqlx::sqlite::SqliteRow>> {
+        Ok(sqlx::query(
+            "SELECT proof, leaf_index FROM precomputed_proofs WHERE batch_id = ? AND kind = ? AND proof_key = ? AND format = ?"
+        )
+        .bind(batch_id as i64)
+        .bind(kind)
+        .bind(key)
+        .bind(format.as_str())
+        .fetch_optional(&self.db)
+        .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vapor_core::proof_format::{process_raw_proof, process_sorted_proof};
+    use crate::models::{OrderStatus, OrderType, TokenBalance};
+
+    const ALICE: &str = "0x1234567890123456789012345678901234567890";
+    const BOB: &str = "0x0987654321098765432109876543210987654321";
+    const CAROL: &str = "0x1111111111111111111111111111111111111111";
+
+    fn account(address: &str) -> AccountState {
+        AccountState {
+            address: address.to_string(),
+            balances: vec![TokenBalance { token_id: 1, balance: "1000".to_string() }],
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn snapshot(batch_id: u64) -> BatchSnapshot {
+        let orders = (0..3).map(|i| Order {
+            id: format!("order_{}", i),
+            order_type: OrderType::Transfer,
+            status: OrderStatus::Settled,
+            from_address: Some(ALICE.to_string()),
+            to_address: Some(BOB.to_string()),
+            token_id: 1,
+            amount: format!("{}00", i + 1),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: Some(batch_id),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }).collect();
+
+        BatchSnapshot {
+            batch_id,
+            state_root: String::new(),
+            orders_root: String::new(),
+            accounts: vec![account(ALICE), account(BOB), account(CAROL)],
+            orders,
+            created_at: Utc::now(),
+        }
+    }
+
+    async fn cache(keep_batches: u32) -> ProofCache {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        ProofCache::new(db, keep_batches)
+    }
+
+    #[test]
+    fn test_touched_accounts() {
+        let snapshot = snapshot(1);
+        let touched: Vec<&str> = touched_accounts(&snapshot).iter().map(|a| a.address.as_str()).collect();
+        assert_eq!(touched, vec![ALICE, BOB]);
+    }
+
+    #[tokio::test]
+    async fn test_precomputed_proofs_verify() {
+        let cache = cache(5).await;
+        let report = cache.precompute(&snapshot(1)).await.unwrap();
+        assert_eq!(report.order_proofs, 9);
+        assert_eq!(report.account_proofs, 4);
+
+        let (raw, leaf_index) = cache.get_order_proof(1, "order_2", ProofFormat::Raw).await.unwrap().unwrap();
+        assert_eq!(leaf_index, Some(2));
+        let siblings: Vec<_> = raw.proof.iter().map(|s| parse_hash32(s).unwrap()).collect();
+        let root = process_raw_proof(parse_hash32(&raw.leaf_hash).unwrap(), &siblings, raw.path_bits.as_ref().unwrap()).unwrap();
+        assert_eq!(root, parse_hash32(&raw.root).unwrap());
+
+        let (sorted, _) = cache.get_order_proof(1, "order_0", ProofFormat::SortedPairs).await.unwrap().unwrap();
+        let siblings: Vec<_> = sorted.proof.iter().map(|s| parse_hash32(s).unwrap()).collect();
+        assert_eq!(process_sorted_proof(parse_hash32(&sorted.leaf_hash).unwrap(), &siblings), parse_hash32(&sorted.root).unwrap());
+
+        // Matches what the on-demand path computes
+        let on_demand = build_order_proofs(&snapshot(1).orders, 1, &[2], ProofFormat::Raw).unwrap();
+        assert_eq!(on_demand[0].root, raw.root);
+
+        assert!(cache.get_account_proof(1, &BOB.to_uppercase(), ProofFormat::Raw).await.unwrap().is_some());
+        assert!(cache.get_account_proof(1, CAROL, ProofFormat::Siblings).await.unwrap().is_none());
+        assert!(cache.get_order_proof(2, "order_0", ProofFormat::Raw).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_old_batches_are_pruned() {
+        let cache = cache(2).await;
+        for batch_id in 1..=3 {
+            cache.precompute(&snapshot(batch_id)).await.unwrap();
+        }
+
+        assert!(cache.get_order_proof(1, "order_0", ProofFormat::Siblings).await.unwrap().is_none());
+        assert!(cache.get_order_proof(2, "order_0", ProofFormat::Siblings).await.unwrap().is_some());
+        assert!(cache.get_order_proof(3, "order_0", ProofFormat::Siblings).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_cache_stores_nothing() {
+        let cache = cache(0).await;
+        let report = cache.precompute(&snapshot(1)).await.unwrap();
+        assert_eq!(report.order_proofs, 0);
+        assert!(cache.get_order_proof(1, "order_0", ProofFormat::Siblings).await.unwrap().is_none());
+    }
+}
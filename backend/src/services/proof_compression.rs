@@ -0,0 +1,228 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use flate2::{write::ZlibEncoder, Compression};
+use serde::Serialize;
+use sha3::{Digest, Keccak256};
+use sqlx::{Row, SqlitePool};
+use std::io::Write;
+use tracing::info;
+
+use crate::config::CalldataCompression;
+use vapor_core::services::blob_store::BlobStore;
+
+/// zstd level for off-chain artifacts; proofs are compressed once per batch, so favour size
+const ZSTD_LEVEL: i32 = 19;
+
+/// Calldata gas per byte (EIP-2028)
+const ZERO_BYTE_GAS: u64 = 4;
+const NONZERO_BYTE_GAS: u64 = 16;
+
+/// Proof bytes encoded for one submission target
+#[derive(Debug, Clone)]
+pub struct PreparedSubmission {
+    pub batch_id: u64,
+    /// Verifier contract the proof is submitted to
+    pub target: String,
+    pub compression: CalldataCompression,
+    /// Bytes passed as the proof argument
+    pub calldata: Vec<u8>,
+    /// zstd-compressed proof stored off-chain, for `ZstdArtifact`
+    pub artifact: Option<Vec<u8>>,
+    pub raw_size: usize,
+    pub raw_calldata_gas: u64,
+}
+
+impl PreparedSubmission {
+    pub fn calldata_gas(&self) -> u64 {
+        calldata_gas(&self.calldata)
+    }
+
+    pub fn artifact_hash(&self) -> Option<String> {
+        self.artifact.as_ref().map(|artifact| format!("0x{}", hex::encode(Keccak256::digest(artifact))))
+    }
+}
+
+/// Raw vs submitted sizes of a batch's proof, as recorded
+#[derive(Debug, Clone, Serialize)]
+pub struct SubmissionSizes {
+    pub batch_id: u64,
+    pub target: String,
+    pub compression: CalldataCompression,
+    pub raw_size: i64,
+    pub calldata_size: i64,
+    pub raw_calldata_gas: i64,
+    pub calldata_gas: i64,
+    pub artifact_size: Option<i64>,
+    pub artifact_hash: Option<String>,
+    /// Blob store key of the artifact
+    pub artifact_ref: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Encode a batch proof for submission with the given compression
+pub fn prepare(batch_id: u64, target: &str, proof: &[u8], compression: CalldataCompression) -> std::io::Result<PreparedSubmission> {
+    let (calldata, artifact) = match compression {
+        CalldataCompression::None => (proof.to_vec(), None),
+        CalldataCompression::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+            encoder.write_all(proof)?;
+            (encoder.finish()?, None)
+        }
+        CalldataCompression::ZstdArtifact => {
+            let artifact = zstd::encode_all(proof, ZSTD_LEVEL)?;
+            (Keccak256::digest(&artifact).to_vec(), Some(artifact))
+        }
+    };
+
+    Ok(PreparedSubmission {
+        batch_id,
+        target: target.to_string(),
+        compression,
+        calldata,
+        artifact,
+        raw_size: proof.len(),
+        raw_calldata_gas: calldata_gas(proof),
+    })
+}
+
+/// Intrinsic gas of passing `data` as calldata
+pub fn calldata_gas(data: &[u8]) -> u64 {
+    data.iter()
+        .map(|byte| if *byte == 0 { ZERO_BYTE_GAS } else { NONZERO_BYTE_GAS })
+        .sum()
+}
+
+/// Record the sizes of a prepared submission, keeping its artifact in the blob store when there is one
+///
+/// The artifact's keccak256 hash is both its on-chain commitment and its blob checksum.
+pub async fn record(db: &SqlitePool, blobs: &dyn BlobStore, submission: &PreparedSubmission) -> Result<()> {
+    let artifact = match &submission.artifact {
+        Some(artifact) => Some(blobs.put(artifact).await?),
+        None => None,
+    };
+
+    sqlx::query(
+        r#"
+        INSERT OR REPLACE INTO proof_submissions
+            (batch_id, target, compression, raw_size, calldata_size, raw_calldata_gas, calldata_gas,
+             artifact_ref, artifact_size, artifact_hash, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        "#,
+    )
+    .bind(submission.batch_id as i64)
+    .bind(&submission.target)
+    .bind(submission.compression.as_str())
+    .bind(submission.raw_size as i64)
+    .bind(submission.calldata.len() as i64)
+    .bind(submission.raw_calldata_gas as i64)
+    .bind(submission.calldata_gas() as i64)
+    .bind(artifact.as_ref().map(|blob| blob.reference.clone()))
+    .bind(artifact.as_ref().map(|blob| blob.size as i64))
+    .bind(artifact.map(|blob| blob.checksum))
+    .bind(Utc::now())
+    .execute(db)
+    .await?;
+
+    info!(
+        "Batch {} proof for {}: {} bytes raw, {} bytes calldata ({:?})",
+        submission.batch_id, submission.target, submission.raw_size, submission.calldata.len(), submission.compression
+    );
+    Ok(())
+}
+
+/// Recorded submission sizes, most recent batches first
+pub async fn list(db: &SqlitePool, limit: u32) -> Result<Vec<SubmissionSizes>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT batch_id, target, compression, raw_size, calldata_size, raw_calldata_gas, calldata_gas,
+               COALESCE(artifact_size, LENGTH(artifact)) AS artifact_size, artifact_hash, artifact_ref, created_at
+        FROM proof_submissions ORDER BY batch_id DESC, target LIMIT ?1
+        "#,
+    )
+    .bind(limit as i64)
+    .fetch_all(db)
+    .await?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(SubmissionSizes {
+                batch_id: row.try_get::<i64, _>("batch_id")? as u64,
+                target: row.try_get("target")?,
+                compression: CalldataCompression::parse(&row.try_get::<String, _>("compression")?).unwrap_or_default(),
+                raw_size: row.try_get("raw_size")?,
+                calldata_size: row.try_get("calldata_size")?,
+                raw_calldata_gas: row.try_get("raw_calldata_gas")?,
+                calldata_gas: row.try_get("calldata_gas")?,
+                artifact_size: row.try_get("artifact_size")?,
+                artifact_hash: row.try_get("artifact_hash")?,
+                artifact_ref: row.try_get("artifact_ref")?,
+                created_at: row.try_get("created_at")?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::run_migrations;
+    use vapor_core::services::blob_store::MemoryBlobStore;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    const VERIFIER: &str = "0x0000000000000000000000000000000000000001";
+
+    /// Proof-like bytes: mostly structured, so they compress
+    fn sample_proof() -> Vec<u8> {
+        (0..4096u32).flat_map(|i| (i / 64).to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn test_compressed_calldata_round_trips() {
+        let proof = sample_proof();
+
+        let raw = prepare(1, VERIFIER, &proof, CalldataCompression::None).unwrap();
+        assert_eq!(raw.calldata, proof);
+        assert_eq!(raw.calldata_gas(), raw.raw_calldata_gas);
+
+        let zlib = prepare(1, VERIFIER, &proof, CalldataCompression::Zlib).unwrap();
+        assert!(zlib.calldata.len() < proof.len());
+        assert!(zlib.calldata_gas() < zlib.raw_calldata_gas);
+        let mut inflated = Vec::new();
+        ZlibDecoder::new(zlib.calldata.as_slice()).read_to_end(&mut inflated).unwrap();
+        assert_eq!(inflated, proof);
+
+        // Only the artifact's hash goes on-chain
+        let zstd = prepare(1, VERIFIER, &proof, CalldataCompression::ZstdArtifact).unwrap();
+        let artifact = zstd.artifact.as_ref().unwrap();
+        assert_eq!(zstd.calldata.len(), 32);
+        assert_eq!(zstd.artifact_hash().unwrap(), format!("0x{}", hex::encode(&zstd.calldata)));
+        assert_eq!(zstd::decode_all(artifact.as_slice()).unwrap(), proof);
+    }
+
+    #[tokio::test]
+    async fn test_sizes_are_recorded_per_batch() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let blobs = MemoryBlobStore::default();
+        let proof = sample_proof();
+
+        record(&db, &blobs, &prepare(1, VERIFIER, &proof, CalldataCompression::None).unwrap()).await.unwrap();
+        record(&db, &blobs, &prepare(2, VERIFIER, &proof, CalldataCompression::ZstdArtifact).unwrap()).await.unwrap();
+
+        let sizes = list(&db, 10).await.unwrap();
+        assert_eq!(sizes.len(), 2);
+        assert_eq!((sizes[0].batch_id, sizes[0].compression), (2, CalldataCompression::ZstdArtifact));
+        assert_eq!(sizes[0].raw_size, proof.len() as i64);
+        assert_eq!(sizes[0].calldata_size, 32);
+        assert!(sizes[0].artifact_size.unwrap() < proof.len() as i64);
+        assert_eq!((sizes[1].batch_id, sizes[1].calldata_size), (1, proof.len() as i64));
+        assert_eq!(sizes[1].artifact_size, None);
+
+        // Only the artifact's reference and hash are in the database
+        let reference = sizes[0].artifact_ref.as_deref().unwrap();
+        let artifact = blobs.get(reference, sizes[0].artifact_hash.as_deref().unwrap()).await.unwrap().unwrap();
+        assert_eq!(zstd::decode_all(artifact.as_slice()).unwrap(), proof);
+        assert_eq!(sizes[1].artifact_ref, None);
+    }
+}
@@ -0,0 +1,221 @@
+//! Per-operation database latency
+//!
+//! Data-access functions start a [`QueryTimer`] named after their logical operation; its
+//! latency lands in a fixed-bucket histogram, and runs above the slow-query threshold are
+//! logged and kept for the admin diagnostics endpoint. The registry is process-wide so
+//! timers need no handle threaded through every caller.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Upper bounds of the latency buckets in milliseconds; slower runs fall in the overflow bucket
+pub const BUCKET_BOUNDS_MS: [u64; 10] = [1, 2, 5, 10, 25, 50, 100, 250, 500, 1000];
+
+/// Slow runs kept for the diagnostics endpoint, newest last
+const RECENT_SLOW_QUERIES: usize = 50;
+
+/// Latency histogram of one operation
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationStats {
+    pub operation: &'static str,
+    pub count: u64,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+    /// Upper bound of the bucket holding the 95th percentile, None when it is the overflow bucket
+    pub p95_ms: Option<u64>,
+    pub slow_count: u64,
+    /// Counts per bucket of `BUCKET_BOUNDS_MS`, then the overflow bucket
+    pub buckets: Vec<u64>,
+}
+
+impl OperationStats {
+    fn new(operation: &'static str) -> Self {
+        Self {
+            operation,
+            count: 0,
+            total_ms: 0.0,
+            mean_ms: 0.0,
+            max_ms: 0.0,
+            p95_ms: None,
+            slow_count: 0,
+            buckets: vec![0; BUCKET_BOUNDS_MS.len() + 1],
+        }
+    }
+
+    fn record(&mut self, elapsed_ms: f64, slow: bool) {
+        let bucket = BUCKET_BOUNDS_MS.iter().position(|bound| elapsed_ms <= *bound as f64).unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.total_ms += elapsed_ms;
+        self.mean_ms = self.total_ms / self.count as f64;
+        self.max_ms = self.max_ms.max(elapsed_ms);
+        if slow {
+            self.slow_count += 1;
+        }
+
+        let target = (self.count * 95).div_ceil(100);
+        let mut seen = 0;
+        self.p95_ms = None;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                self.p95_ms = BUCKET_BOUNDS_MS.get(bucket).copied();
+                break;
+            }
+        }
+    }
+
+    /// Sort key for "slowest first": the p95 bucket (overflow above all), then the worst run
+    fn slowness(&self) -> (u64, f64) {
+        (self.p95_ms.unwrap_or(u64::MAX), self.max_ms)
+    }
+}
+
+/// One run above the slow-query threshold
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowQuery {
+    pub operation: &'static str,
+    pub elapsed_ms: f64,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryDiagnostics {
+    pub slow_query_ms: u64,
+    /// Slowest operations first
+    pub operations: Vec<OperationStats>,
+    /// Newest first
+    pub recent_slow_queries: Vec<SlowQuery>,
+}
+
+#[derive(Default)]
+struct Recorded {
+    operations: HashMap<&'static str, OperationStats>,
+    slow: VecDeque<SlowQuery>,
+}
+
+pub struct QueryMetrics {
+    slow_query_ms: AtomicU64,
+    recorded: RwLock<Recorded>,
+}
+
+impl QueryMetrics {
+    pub fn new(slow_query_ms: u64) -> Self {
+        Self {
+            slow_query_ms: AtomicU64::new(slow_query_ms),
+            recorded: RwLock::new(Recorded::default()),
+        }
+    }
+
+    pub fn set_slow_query_ms(&self, slow_query_ms: u64) {
+        self.slow_query_ms.store(slow_query_ms, Ordering::Relaxed);
+    }
+
+    /// Add one run of `operation`, logging it when it exceeded the slow-query threshold
+    ///
+    /// A threshold of 0 turns the slow-query log off.
+    pub fn record(&self, operation: &'static str, elapsed: Duration) {
+        let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+        let threshold = self.slow_query_ms.load(Ordering::Relaxed);
+        let slow = threshold > 0 && elapsed_ms > threshold as f64;
+        if slow {
+            warn!(operation, elapsed_ms, threshold_ms = threshold, "Slow query");
+        }
+
+        let mut recorded = self.recorded.write().unwrap_or_else(|e| e.into_inner());
+        recorded.operations.entry(operation).or_insert_with(|| OperationStats::new(operation)).record(elapsed_ms, slow);
+        if slow {
+            if recorded.slow.len() == RECENT_SLOW_QUERIES {
+                recorded.slow.pop_front();
+            }
+            recorded.slow.push_back(SlowQuery { operation, elapsed_ms, at: Utc::now() });
+        }
+    }
+
+    /// The `top` slowest operations and the recent slow runs
+    pub fn diagnostics(&self, top: usize) -> QueryDiagnostics {
+        let recorded = self.recorded.read().unwrap_or_else(|e| e.into_inner());
+        let mut operations: Vec<OperationStats> = recorded.operations.values().cloned().collect();
+        operations.sort_by(|a, b| b.slowness().partial_cmp(&a.slowness()).unwrap_or(std::cmp::Ordering::Equal));
+        operations.truncate(top);
+
+        QueryDiagnostics {
+            slow_query_ms: self.slow_query_ms.load(Ordering::Relaxed),
+            operations,
+            recent_slow_queries: recorded.slow.iter().rev().cloned().collect(),
+        }
+    }
+}
+
+/// The process-wide registry timers record into
+pub fn global() -> &'static QueryMetrics {
+    static METRICS: OnceLock<QueryMetrics> = OnceLock::new();
+    METRICS.get_or_init(|| QueryMetrics::new(crate::config::DiagnosticsConfig::default().slow_query_ms))
+}
+
+/// Records the time from `start` until it is dropped under `operation`
+///
+/// Hold it for the length of the data-access function, including on early returns:
+/// `let _timer = QueryTimer::start("get_order_by_id");`
+pub struct QueryTimer {
+    operation: &'static str,
+    started: Instant,
+}
+
+impl QueryTimer {
+    pub fn start(operation: &'static str) -> Self {
+        Self { operation, started: Instant::now() }
+    }
+}
+
+impl Drop for QueryTimer {
+    fn drop(&mut self) {
+        global().record(self.operation, self.started.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histograms_rank_operations_and_keep_slow_runs() {
+        let metrics = QueryMetrics::new(100);
+        for _ in 0..19 {
+            metrics.record("get_order_by_id", Duration::from_millis(1));
+        }
+        metrics.record("get_order_by_id", Duration::from_millis(40));
+        for ms in [20, 30, 150] {
+            metrics.record("find_orders", Duration::from_millis(ms));
+        }
+        metrics.record("insert_order", Duration::from_millis(3));
+
+        let diagnostics = metrics.diagnostics(2);
+        let ranked: Vec<&str> = diagnostics.operations.iter().map(|stats| stats.operation).collect();
+        assert_eq!(ranked, vec!["find_orders", "insert_order"]);
+
+        let find = &diagnostics.operations[0];
+        assert_eq!((find.count, find.slow_count, find.p95_ms), (3, 1, Some(250)));
+        assert_eq!(find.max_ms, 150.0);
+        assert_eq!(find.buckets.iter().sum::<u64>(), 3);
+
+        // One outlier in twenty stays out of the 95th percentile
+        let lookups = &metrics.diagnostics(3).operations[2];
+        assert_eq!((lookups.operation, lookups.p95_ms), ("get_order_by_id", Some(1)));
+
+        assert_eq!(diagnostics.recent_slow_queries.len(), 1);
+        assert_eq!(diagnostics.recent_slow_queries[0].operation, "find_orders");
+
+        // A threshold of 0 logs nothing
+        metrics.set_slow_query_ms(0);
+        metrics.record("find_orders", Duration::from_secs(5));
+        assert_eq!(metrics.diagnostics(1).recent_slow_queries.len(), 1);
+        assert_eq!(metrics.diagnostics(1).operations[0].p95_ms, None);
+    }
+}
@@ -0,0 +1,320 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+use tracing::info;
+use web3::types::H256;
+
+use crate::config::QuoteConfig;
+use vapor_core::sparse_merkle_tree::solidity_keccak256_hash;
+use crate::models::{CreateOrderRequest, OrderType};
+use crate::services::claim_fees::ClaimFee;
+use vapor_core::services::clock::SharedClock;
+use crate::services::rates::format_rate;
+use vapor_chain::signer::{signature_to_hex, uint256, Signer};
+
+/// Domain tag hashed into every quote digest
+pub const QUOTE_DOMAIN: &[u8] = b"VAPOR_ORDER_QUOTE_V1";
+
+/// A quote that cannot be committed to
+#[derive(Debug, Serialize, thiserror::Error)]
+#[serde(tag = "quote", rename_all = "snake_case")]
+pub enum QuoteError {
+    #[error("quote {quote_id} not found")]
+    NotFound { quote_id: String },
+    #[error("quote {quote_id} expired at {expires_at}")]
+    Expired { quote_id: String, expires_at: DateTime<Utc> },
+    #[error("quote {quote_id} was already used by order {order_id}")]
+    AlreadyUsed { quote_id: String, order_id: String },
+    #[error("order {field} does not match quote {quote_id}")]
+    Mismatch { quote_id: String, field: &'static str },
+    #[error(transparent)]
+    #[serde(skip)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Order parameters a quote is reserved for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuoteTerms {
+    pub order_type: OrderType,
+    pub token_id: u32,
+    pub amount: String,
+    pub bank_service: Option<String>,
+}
+
+impl QuoteTerms {
+    pub fn of(req: &CreateOrderRequest) -> Self {
+        Self {
+            order_type: req.order_type,
+            token_id: req.token_id,
+            amount: req.amount.clone(),
+            bank_service: req.bank_service.clone(),
+        }
+    }
+
+    /// First field of `other` that differs, bank services compared case-insensitively
+    fn mismatch(&self, other: &QuoteTerms) -> Option<&'static str> {
+        if self.order_type != other.order_type {
+            return Some("order_type");
+        }
+        if self.token_id != other.token_id {
+            return Some("token_id");
+        }
+        if self.amount != other.amount {
+            return Some("amount");
+        }
+        let same_service = match (&self.bank_service, &other.bank_service) {
+            (Some(ours), Some(theirs)) => ours.eq_ignore_ascii_case(theirs),
+            (ours, theirs) => ours == theirs,
+        };
+        (!same_service).then_some("bank_service")
+    }
+}
+
+/// Binding terms for an order not yet committed, held by the server until `expires_at`
+#[derive(Debug, Clone, Serialize)]
+pub struct Quote {
+    pub quote_id: String,
+    #[serde(flatten)]
+    pub terms: QuoteTerms,
+    /// USD price of the token the order is held to, in micro-dollars; None for unpriced tokens
+    #[serde(skip)]
+    pub usd_price: Option<u64>,
+    pub rate: Option<String>,
+    /// What the filler taking the order is charged when it claims the tokens
+    pub claim_fee: Option<ClaimFee>,
+    /// Median creation-to-settlement time of the corridor's recent orders
+    pub eta_seconds: Option<u64>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// `keccak256(abi.encodePacked(QUOTE_DOMAIN, keccak256(quote_id), uint256(order_type), uint256(token_id), keccak256(amount), keccak256(bank_service), uint256(usd_price), uint256(expires_at)))`
+    pub digest: String,
+    /// Operator address and its signature over `digest`, when an operator signer is configured
+    pub signer: Option<String>,
+    pub signature: Option<String>,
+}
+
+/// Digest signed for a quote; `expires_at` is in Unix seconds
+pub fn quote_digest(quote_id: &str, terms: &QuoteTerms, usd_price: Option<u64>, expires_at: i64) -> [u8; 32] {
+    solidity_keccak256_hash(&[
+        QUOTE_DOMAIN,
+        &solidity_keccak256_hash(&[quote_id.as_bytes()]),
+        &uint256(terms.order_type as u64),
+        &uint256(terms.token_id as u64),
+        &solidity_keccak256_hash(&[terms.amount.as_bytes()]),
+        &solidity_keccak256_hash(&[terms.bank_service.as_deref().unwrap_or_default().as_bytes()]),
+        &uint256(usd_price.unwrap_or(0)),
+        &uint256(expires_at.max(0) as u64),
+    ])
+}
+
+/// Issues quotes and holds their terms until an order commits to them or they expire
+#[derive(Clone)]
+pub struct QuoteService {
+    db: SqlitePool,
+    config: QuoteConfig,
+    clock: SharedClock,
+    signer: Option<Arc<dyn Signer>>,
+}
+
+impl QuoteService {
+    pub fn new(db: SqlitePool, config: &QuoteConfig, clock: SharedClock) -> Self {
+        Self { db, config: config.clone(), clock, signer: None }
+    }
+
+    pub fn with_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Store and sign a quote for `terms` at the given price, fee and ETA
+    pub async fn issue(
+        &self,
+        terms: QuoteTerms,
+        usd_price: Option<u64>,
+        claim_fee: Option<ClaimFee>,
+        eta_seconds: Option<u64>,
+    ) -> Result<Quote> {
+        let quote_id = format!("quote_{}", uuid::Uuid::new_v4().simple());
+        let created_at = self.clock.now();
+        let expires_at = created_at + Duration::seconds(self.config.ttl_seconds as i64);
+        let digest = quote_digest(&quote_id, &terms, usd_price, expires_at.timestamp());
+        let (signer, signature) = match &self.signer {
+            Some(signer) => {
+                let signature = signer.sign_digest(H256::from(digest), None).await?;
+                (Some(format!("{:?}", signer.address())), Some(signature_to_hex(&signature)?))
+            }
+            None => (None, None),
+        };
+
+        let quote = Quote {
+            quote_id,
+            terms,
+            usd_price,
+            rate: usd_price.map(format_rate),
+            claim_fee,
+            eta_seconds,
+            created_at,
+            expires_at,
+            digest: format!("0x{}", hex::encode(digest)),
+            signer,
+            signature,
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO order_quotes
+                (quote_id, order_type, token_id, amount, bank_service, usd_price, claim_fee, eta_seconds,
+                 digest, signer, signature, created_at, expires_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+            "#,
+        )
+        .bind(&quote.quote_id)
+        .bind(quote.terms.order_type as i32)
+        .bind(quote.terms.token_id as i64)
+        .bind(&quote.terms.amount)
+        .bind(&quote.terms.bank_service)
+        .bind(quote.usd_price.map(|price| price as i64))
+        .bind(quote.claim_fee.as_ref().map(serde_json::to_string).transpose()?)
+        .bind(quote.eta_seconds.map(|seconds| seconds as i64))
+        .bind(&quote.digest)
+        .bind(&quote.signer)
+        .bind(&quote.signature)
+        .bind(quote.created_at)
+        .bind(quote.expires_at)
+        .execute(&self.db)
+        .await?;
+
+        info!("Issued quote {} expiring at {}", quote.quote_id, quote.expires_at);
+        Ok(quote)
+    }
+
+    pub async fn get(&self, quote_id: &str) -> Result<Option<(Quote, Option<String>)>> {
+        let Some(row) = sqlx::query("SELECT * FROM order_quotes WHERE quote_id = ?")
+            .bind(quote_id)
+            .fetch_optional(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let claim_fee: Option<String> = row.try_get("claim_fee")?;
+        let quote = Quote {
+            quote_id: row.try_get("quote_id")?,
+            terms: QuoteTerms {
+                order_type: OrderType::from(row.try_get::<i32, _>("order_type")?),
+                token_id: row.try_get::<i64, _>("token_id")? as u32,
+                amount: row.try_get("amount")?,
+                bank_service: row.try_get("bank_service")?,
+            },
+            usd_price: row.try_get::<Option<i64>, _>("usd_price")?.map(|price| price as u64),
+            rate: row.try_get::<Option<i64>, _>("usd_price")?.map(|price| format_rate(price as u64)),
+            claim_fee: claim_fee.map(|fee| serde_json::from_str(&fee)).transpose()?,
+            eta_seconds: row.try_get::<Option<i64>, _>("eta_seconds")?.map(|seconds| seconds as u64),
+            created_at: row.try_get("created_at")?,
+            expires_at: row.try_get("expires_at")?,
+            digest: row.try_get("digest")?,
+            signer: row.try_get("signer")?,
+            signature: row.try_get("signature")?,
+        };
+        Ok(Some((quote, row.try_get("order_id")?)))
+    }
+
+    /// Hold `order_id` to a quote: refused when the quote is unknown, expired, used, or its
+    /// terms differ from the order's
+    pub async fn commit(&self, quote_id: &str, terms: &QuoteTerms, order_id: &str) -> Result<Quote, QuoteError> {
+        let Some((quote, used_by)) = self.get(quote_id).await? else {
+            return Err(QuoteError::NotFound { quote_id: quote_id.to_string() });
+        };
+        if let Some(order_id) = used_by {
+            return Err(QuoteError::AlreadyUsed { quote_id: quote.quote_id, order_id });
+        }
+        if quote.expires_at <= self.clock.now() {
+            return Err(QuoteError::Expired { quote_id: quote.quote_id, expires_at: quote.expires_at });
+        }
+        if let Some(field) = quote.terms.mismatch(terms) {
+            return Err(QuoteError::Mismatch { quote_id: quote.quote_id, field });
+        }
+
+        // Two orders racing for the same quote: only one update finds it unused
+        let result = sqlx::query("UPDATE order_quotes SET order_id = ?1 WHERE quote_id = ?2 AND order_id IS NULL")
+            .bind(order_id)
+            .bind(quote_id)
+            .execute(&self.db)
+            .await
+            .map_err(anyhow::Error::from)?;
+        if result.rows_affected() == 0 {
+            let used_by = self.get(quote_id).await?.and_then(|(_, order_id)| order_id).unwrap_or_default();
+            return Err(QuoteError::AlreadyUsed { quote_id: quote.quote_id, order_id: used_by });
+        }
+        Ok(quote)
+    }
+
+    /// Free a quote again when the order committed to it could not be stored
+    pub async fn release(&self, quote_id: &str, order_id: &str) -> Result<()> {
+        sqlx::query("UPDATE order_quotes SET order_id = NULL WHERE quote_id = ?1 AND order_id = ?2")
+            .bind(quote_id)
+            .bind(order_id)
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vapor_core::services::clock::MockClock;
+
+    fn terms(amount: &str) -> QuoteTerms {
+        QuoteTerms {
+            order_type: OrderType::BridgeIn,
+            token_id: 1,
+            amount: amount.to_string(),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+        }
+    }
+
+    async fn service(clock: &MockClock) -> QuoteService {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        QuoteService::new(db, &QuoteConfig { ttl_seconds: 60 }, clock.shared())
+    }
+
+    #[tokio::test]
+    async fn test_quote_is_committed_once_with_matching_terms() {
+        let clock = MockClock::new(Utc::now());
+        let quotes = service(&clock).await;
+        let quote = quotes.issue(terms("1000"), Some(1_000_200), None, Some(900)).await.unwrap();
+        assert_eq!(quote.rate.as_deref(), Some("1.000200"));
+        assert!(quote.signature.is_none());
+
+        let mut other_service = terms("1000");
+        other_service.bank_service = Some("paypal hong kong".to_string());
+        let committed = quotes.commit(&quote.quote_id, &other_service, "order_1").await.unwrap();
+        assert_eq!((committed.usd_price, committed.eta_seconds), (Some(1_000_200), Some(900)));
+
+        let err = quotes.commit(&quote.quote_id, &terms("1000"), "order_2").await.unwrap_err();
+        assert!(matches!(err, QuoteError::AlreadyUsed { order_id, .. } if order_id == "order_1"));
+
+        // A released quote can be committed to again
+        quotes.release(&quote.quote_id, "order_1").await.unwrap();
+        quotes.commit(&quote.quote_id, &terms("1000"), "order_2").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_and_mismatched_quotes_are_refused() {
+        let clock = MockClock::new(Utc::now());
+        let quotes = service(&clock).await;
+        let quote = quotes.issue(terms("1000"), None, None, None).await.unwrap();
+
+        let err = quotes.commit(&quote.quote_id, &terms("2000"), "order_1").await.unwrap_err();
+        assert!(matches!(err, QuoteError::Mismatch { field: "amount", .. }));
+        let err = quotes.commit("quote_missing", &terms("1000"), "order_1").await.unwrap_err();
+        assert!(matches!(err, QuoteError::NotFound { .. }));
+
+        clock.advance(Duration::seconds(60));
+        let err = quotes.commit(&quote.quote_id, &terms("1000"), "order_1").await.unwrap_err();
+        assert!(matches!(err, QuoteError::Expired { .. }));
+    }
+}
@@ -0,0 +1,172 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::config::RateConfig;
+use crate::models::TokenConversion;
+
+/// Errors from converting an amount between tokens
+#[derive(Debug, thiserror::Error)]
+pub enum RateError {
+    #[error("No conversion rate for token {0}")]
+    UnknownToken(u32),
+    #[error("Converted amount overflows")]
+    Overflow,
+    #[error("Rate of token {} moved from {} to {}, beyond the {} bps tolerance",
+            .0.token_id, .0.quoted_rate, .0.current_rate, .0.max_slippage_bps)]
+    RequoteRequired(Requote),
+}
+
+/// New rate offered when the quoted one slipped too far; lock again with `accepted_rate` set to
+/// `current_rate` to take it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Requote {
+    pub token_id: u32,
+    pub quoted_rate: String,
+    pub current_rate: String,
+    pub slippage_bps: u64,
+    pub max_slippage_bps: u32,
+}
+
+/// Quotes conversions between tokens from their USD prices
+///
+/// Prices come from configuration for now and can be moved at runtime (PUT /admin/rates) to
+/// simulate market moves; every quote records its rate and source so payouts can be audited
+/// after the fact.
+#[derive(Debug, Clone)]
+pub struct RateService {
+    usd_prices: Arc<RwLock<HashMap<u32, u64>>>,
+    max_slippage_bps: u32,
+}
+
+const RATE_SOURCE: &str = "config";
+const MICROS: u128 = 1_000_000;
+const BPS: u128 = 10_000;
+
+/// Render a micro-dollar price or micro-unit rate as a decimal, e.g. "1.000200"
+pub fn format_rate(micros: u64) -> String {
+    format!("{}.{:06}", micros / MICROS as u64, micros % MICROS as u64)
+}
+
+impl RateService {
+    pub fn new(config: &RateConfig) -> Self {
+        Self {
+            usd_prices: Arc::new(RwLock::new(config.usd_prices.clone())),
+            max_slippage_bps: config.max_slippage_bps,
+        }
+    }
+
+    fn price(&self, token_id: u32) -> Result<u128, RateError> {
+        self.usd_price(token_id).map(|price| price as u128)
+    }
+
+    /// Current USD price of a token, in micro-dollars
+    pub fn usd_price(&self, token_id: u32) -> Result<u64, RateError> {
+        self.usd_prices.read().unwrap()
+            .get(&token_id)
+            .copied()
+            .ok_or(RateError::UnknownToken(token_id))
+    }
+
+    /// Replace a token's USD price (micro-dollars); later quotes and slippage checks use it
+    pub fn set_usd_price(&self, token_id: u32, price: u64) {
+        self.usd_prices.write().unwrap().insert(token_id, price);
+    }
+
+    /// Check a price quoted earlier against the current one
+    ///
+    /// Returns None while the price is within the slippage tolerance, so the quote stands. Beyond
+    /// it the caller must have accepted the current price (`accepted`), which is then returned
+    /// as the new quote; otherwise a re-quote is required.
+    pub fn check_slippage(&self, token_id: u32, quoted: u64, accepted: Option<u64>) -> Result<Option<u64>, RateError> {
+        let current = self.usd_price(token_id)?;
+        let slippage_bps = (quoted.abs_diff(current) as u128 * BPS).div_ceil((quoted as u128).max(1));
+        if slippage_bps <= self.max_slippage_bps as u128 {
+            return Ok(None);
+        }
+        if accepted == Some(current) {
+            return Ok(Some(current));
+        }
+
+        Err(RateError::RequoteRequired(Requote {
+            token_id,
+            quoted_rate: format_rate(quoted),
+            current_rate: format_rate(current),
+            slippage_bps: u64::try_from(slippage_bps).unwrap_or(u64::MAX),
+            max_slippage_bps: self.max_slippage_bps,
+        }))
+    }
+
+    /// Convert `amount` of `from_token_id` into `to_token_id`, rounding down
+    ///
+    /// Both tokens are assumed to use the same number of decimals (true for USDC and PYUSD).
+    pub fn convert(&self, from_token_id: u32, to_token_id: u32, amount: u64) -> Result<TokenConversion, RateError> {
+        let from_price = self.price(from_token_id)?;
+        let to_price = self.price(to_token_id)?;
+
+        let converted = u64::try_from(amount as u128 * from_price / to_price).map_err(|_| RateError::Overflow)?;
+        let rate = from_price * MICROS / to_price;
+
+        Ok(TokenConversion {
+            from_token_id,
+            to_token_id,
+            source_amount: amount.to_string(),
+            converted_amount: converted.to_string(),
+            rate: format_rate(u64::try_from(rate).map_err(|_| RateError::Overflow)?),
+            rate_source: RATE_SOURCE.to_string(),
+            quoted_at: Utc::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates() -> RateService {
+        RateService::new(&RateConfig {
+            usd_prices: HashMap::from([(1, 1_000_000), (2, 999_800), (3, 2_000_000)]),
+            max_slippage_bps: 50,
+        })
+    }
+
+    #[test]
+    fn test_convert_between_tokens() {
+        let conversion = rates().convert(1, 2, 1_000_000).unwrap();
+        assert_eq!(conversion.converted_amount, "1000200");
+        assert_eq!(conversion.rate, "1.000200");
+        assert_eq!(conversion.rate_source, "config");
+
+        let conversion = rates().convert(1, 3, 1_000_001).unwrap();
+        assert_eq!(conversion.converted_amount, "500000"); // rounded down
+        assert_eq!(conversion.rate, "0.500000");
+    }
+
+    #[test]
+    fn test_unknown_token_has_no_rate() {
+        assert!(matches!(rates().convert(1, 9, 100), Err(RateError::UnknownToken(9))));
+        assert!(matches!(rates().convert(9, 1, 100), Err(RateError::UnknownToken(9))));
+    }
+
+    #[test]
+    fn test_slippage_beyond_tolerance_needs_requote() {
+        let rates = rates();
+        // 0.02% off the quote: within 50 bps, the quote stands
+        assert_eq!(rates.check_slippage(2, 1_000_000, None).unwrap(), None);
+
+        rates.set_usd_price(2, 990_000);
+        let Err(RateError::RequoteRequired(requote)) = rates.check_slippage(2, 1_000_000, None) else {
+            panic!("expected a re-quote");
+        };
+        assert_eq!(requote.quoted_rate, "1.000000");
+        assert_eq!(requote.current_rate, "0.990000");
+        assert_eq!(requote.slippage_bps, 100);
+
+        // Accepting a stale re-quote is not enough, only the current price is
+        assert!(rates.check_slippage(2, 1_000_000, Some(995_000)).is_err());
+        assert_eq!(rates.check_slippage(2, 1_000_000, Some(990_000)).unwrap(), Some(990_000));
+        // Conversions use the moved price too
+        assert_eq!(rates.convert(1, 2, 990_000).unwrap().converted_amount, "1000000");
+    }
+}
@@ -0,0 +1,292 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+use tracing::{info, instrument};
+use web3::types::{Address, H256};
+
+use vapor_core::proof_format::parse_hash32;
+use vapor_core::services::clock::SharedClock;
+use vapor_core::sparse_merkle_tree::solidity_keccak256_hash;
+use crate::services::archival::BatchSnapshot;
+use vapor_chain::signer::{recover_signer, signature_to_hex, uint256, SignatureVerification, Signer};
+
+/// Domain tag hashed into every receipt digest, so a receipt signature cannot be passed off
+/// as a signature over anything else
+pub const RECEIPT_DOMAIN: &[u8] = b"VAPOR_INCLUSION_RECEIPT_V1";
+
+/// Operator-signed acknowledgment that an order is leaf `leaf_index` of a finalized batch's
+/// orders tree, available before the batch's proof lands on-chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionReceipt {
+    pub order_id: String,
+    pub batch_id: u64,
+    pub orders_root: String,
+    pub leaf_index: usize,
+    /// `keccak256(abi.encodePacked(RECEIPT_DOMAIN, keccak256(bytes(order_id)), uint256(batch_id), orders_root, uint256(leaf_index)))`
+    pub digest: String,
+    pub signer: String,
+    /// 65-byte r || s || v over `digest`, without an EIP-191 prefix (v is 27 or 28)
+    pub signature: String,
+    pub issued_at: DateTime<Utc>,
+}
+
+/// Digest signed for an order's receipt
+pub fn receipt_digest(order_id: &str, batch_id: u64, orders_root: &[u8; 32], leaf_index: usize) -> [u8; 32] {
+    let order_hash = solidity_keccak256_hash(&[order_id.as_bytes()]);
+    solidity_keccak256_hash(&[
+        RECEIPT_DOMAIN,
+        &order_hash,
+        &uint256(batch_id),
+        orders_root,
+        &uint256(leaf_index as u64),
+    ])
+}
+
+impl InclusionReceipt {
+    /// Recompute the digest from the receipt's fields and recover the address that signed it
+    ///
+    /// The receipt is genuine if this is the operator's address.
+    pub fn recover_signer(&self) -> Result<Address> {
+        let digest = receipt_digest(&self.order_id, self.batch_id, &parse_hash32(&self.orders_root)?, self.leaf_index);
+        if format!("0x{}", hex::encode(digest)) != self.digest {
+            return Err(anyhow::anyhow!("Receipt digest does not match its fields"));
+        }
+
+        recover_signer(&digest, &self.signature)
+    }
+}
+
+/// Issues and stores inclusion receipts for finalized batches, signed with the operator key
+///
+/// Without a signer no receipts are issued.
+#[derive(Clone)]
+pub struct ReceiptIssuer {
+    db: SqlitePool,
+    clock: SharedClock,
+    signer: Option<Arc<dyn Signer>>,
+}
+
+impl ReceiptIssuer {
+    pub fn new(db: SqlitePool, clock: SharedClock) -> Self {
+        Self { db, clock, signer: None }
+    }
+
+    pub fn with_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.signer.is_some()
+    }
+
+    /// Check that a receipt is unaltered and was signed by this operator
+    ///
+    /// Returns `None` without a signer, as there is no operator address to check against.
+    pub fn verify(&self, receipt: &InclusionReceipt) -> Option<SignatureVerification> {
+        let operator = self.signer.as_ref()?.address();
+        Some(SignatureVerification::against(operator, receipt.recover_signer()))
+    }
+
+    /// Sign and store a receipt for every order of a finalized batch
+    #[instrument(skip_all, fields(batch_id = snapshot.batch_id))]
+    pub async fn issue(&self, snapshot: &BatchSnapshot) -> Result<usize> {
+        let Some(signer) = &self.signer else {
+            return Ok(0);
+        };
+
+        let orders_root = parse_hash32(&snapshot.orders_root)?;
+        let issued_at = self.clock.now();
+        let mut receipts = Vec::with_capacity(snapshot.orders.len());
+        for (leaf_index, order) in snapshot.orders.iter().enumerate() {
+            let digest = receipt_digest(&order.id, snapshot.batch_id, &orders_root, leaf_index);
+            let signature = signer.sign_digest(H256::from(digest), None).await?;
+            receipts.push(InclusionReceipt {
+                order_id: order.id.clone(),
+                batch_id: snapshot.batch_id,
+                orders_root: snapshot.orders_root.clone(),
+                leaf_index,
+                digest: format!("0x{}", hex::encode(digest)),
+                signer: format!("{:?}", signer.address()),
+                signature: signature_to_hex(&signature)?,
+                issued_at,
+            });
+        }
+
+        let mut tx = self.db.begin().await?;
+        for receipt in &receipts {
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO inclusion_receipts
+                    (order_id, batch_id, orders_root, leaf_index, digest, signer, signature, issued_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                "#,
+            )
+            .bind(&receipt.order_id)
+            .bind(receipt.batch_id as i64)
+            .bind(&receipt.orders_root)
+            .bind(receipt.leaf_index as i64)
+            .bind(&receipt.digest)
+            .bind(&receipt.signer)
+            .bind(&receipt.signature)
+            .bind(receipt.issued_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        info!("Issued {} inclusion receipts for batch {}", receipts.len(), snapshot.batch_id);
+        Ok(receipts.len())
+    }
+
+    pub async fn get(&self, order_id: &str) -> Result<Option<InclusionReceipt>> {
+        let row = sqlx::query(
+            "SELECT order_id, batch_id, orders_root, leaf_index, digest, signer, signature, issued_at FROM inclusion_receipts WHERE order_id = ?"
+        )
+        .bind(order_id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        row.map(|row| {
+            Ok(InclusionReceipt {
+                order_id: row.try_get("order_id")?,
+                batch_id: row.try_get::<i64, _>("batch_id")? as u64,
+                orders_root: row.try_get("orders_root")?,
+                leaf_index: row.try_get::<i64, _>("leaf_index")? as usize,
+                digest: row.try_get("digest")?,
+                signer: row.try_get("signer")?,
+                signature: row.try_get("signature")?,
+                issued_at: row.try_get("issued_at")?,
+            })
+        })
+        .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::MerkleTreeManager;
+    use crate::models::{Order, OrderStatus, OrderType};
+    use vapor_chain::signer::LocalKeySigner;
+    use vapor_core::services::clock::{system_clock, MockClock};
+
+    // Anvil's first default account
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn order(id: &str) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::Transfer,
+            status: OrderStatus::Settled,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: Some("0x0987654321098765432109876543210987654321".to_string()),
+            token_id: 1,
+            amount: "100".to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: Some(3),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_issued_receipts_recover_to_operator() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let signer = Arc::new(LocalKeySigner::from_hex(TEST_KEY).unwrap());
+        let operator = signer.address();
+        let issued_at = Utc::now() - chrono::Duration::hours(1);
+        let clock = MockClock::new(issued_at);
+        let issuer = ReceiptIssuer::new(db, clock.shared()).with_signer(signer);
+
+        let orders = vec![order("order_a"), order("order_b")];
+        let orders_root = MerkleTreeManager::new().build_orders_tree(&orders, 3).unwrap();
+        let snapshot = BatchSnapshot {
+            batch_id: 3,
+            state_root: orders_root.clone(),
+            orders_root,
+            accounts: Vec::new(),
+            orders,
+            created_at: Utc::now(),
+        };
+        assert_eq!(issuer.issue(&snapshot).await.unwrap(), 2);
+
+        let receipt = issuer.get("order_b").await.unwrap().unwrap();
+        assert_eq!(receipt.batch_id, 3);
+        assert_eq!(receipt.leaf_index, 1);
+        assert_eq!(receipt.issued_at, issued_at);
+        assert_eq!(receipt.recover_signer().unwrap(), operator);
+        assert!(issuer.get("order_c").await.unwrap().is_none());
+
+        // Any field changed after signing breaks the receipt
+        let forged = InclusionReceipt { leaf_index: 0, ..receipt.clone() };
+        assert!(forged.recover_signer().is_err());
+        let resigned = InclusionReceipt {
+            leaf_index: 0,
+            digest: format!("0x{}", hex::encode(receipt_digest("order_b", 3, &parse_hash32(&receipt.orders_root).unwrap(), 0))),
+            ..receipt
+        };
+        assert_ne!(resigned.recover_signer().ok(), Some(operator));
+    }
+
+    #[tokio::test]
+    async fn test_receipts_verify_only_against_the_operator_key() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let issuer = ReceiptIssuer::new(db.clone(), system_clock()).with_signer(Arc::new(LocalKeySigner::from_hex(TEST_KEY).unwrap()));
+
+        let orders = vec![order("order_a")];
+        let orders_root = MerkleTreeManager::new().build_orders_tree(&orders, 3).unwrap();
+        let snapshot = BatchSnapshot {
+            batch_id: 3,
+            state_root: orders_root.clone(),
+            orders_root,
+            accounts: Vec::new(),
+            orders,
+            created_at: Utc::now(),
+        };
+        issuer.issue(&snapshot).await.unwrap();
+        let receipt = issuer.get("order_a").await.unwrap().unwrap();
+
+        let verification = issuer.verify(&receipt).unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.recovered_signer.as_deref(), Some(receipt.signer.as_str()));
+
+        let tampered = InclusionReceipt { batch_id: 4, ..receipt.clone() };
+        let verification = issuer.verify(&tampered).unwrap();
+        assert_eq!((verification.valid, verification.recovered_signer), (false, None));
+
+        // A well-formed receipt signed by any other key is rejected
+        let other_key = "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+        let impostor = ReceiptIssuer::new(db, system_clock()).with_signer(Arc::new(LocalKeySigner::from_hex(other_key).unwrap()));
+        let verification = impostor.verify(&receipt).unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.recovered_signer.as_deref(), Some(receipt.signer.as_str()));
+        assert!(ReceiptIssuer::new(SqlitePool::connect(":memory:").await.unwrap(), system_clock()).verify(&receipt).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_no_receipts_without_signer() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let issuer = ReceiptIssuer::new(db, system_clock());
+
+        let snapshot = BatchSnapshot {
+            batch_id: 1,
+            state_root: String::new(),
+            orders_root: String::new(),
+            accounts: Vec::new(),
+            orders: vec![order("order_a")],
+            created_at: Utc::now(),
+        };
+        assert!(!issuer.is_enabled());
+        assert_eq!(issuer.issue(&snapshot).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,364 @@
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use tracing::{debug, warn};
+
+use crate::services::event_bus::EventBus;
+
+/// Registries kept in memory by [`RegistryCache`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Registry {
+    Tokens,
+    BankServices,
+}
+
+/// A token orders can be placed in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenEntry {
+    pub token_id: u32,
+    pub symbol: String,
+    pub decimals: u8,
+    pub enabled: bool,
+}
+
+/// A bank service orders can be paid out through, e.g. "PayPal Hong Kong"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BankServiceEntry {
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// An order for something the registries have switched off
+///
+/// Tokens and bank services that were never registered are not restricted.
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+    #[error("Token {0} is disabled")]
+    TokenDisabled(u32),
+    #[error("Bank service '{0}' is disabled")]
+    BankServiceDisabled(String),
+    #[error("Registry database error: {0}")]
+    Storage(#[from] anyhow::Error),
+}
+
+/// Register or replace a token
+pub async fn save_token(db: &SqlitePool, token: &TokenEntry) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO token_registry (token_id, symbol, decimals, enabled, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT(token_id) DO UPDATE SET symbol = ?2, decimals = ?3, enabled = ?4, updated_at = ?5
+        "#,
+    )
+    .bind(token.token_id as i64)
+    .bind(&token.symbol)
+    .bind(token.decimals as i64)
+    .bind(token.enabled)
+    .bind(Utc::now())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Register or replace a bank service; names are matched case-insensitively
+pub async fn save_bank_service(db: &SqlitePool, service: &BankServiceEntry) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO bank_service_registry (name, enabled, updated_at)
+        VALUES (?1, ?2, ?3)
+        ON CONFLICT(name) DO UPDATE SET enabled = ?2, updated_at = ?3
+        "#,
+    )
+    .bind(&service.name)
+    .bind(service.enabled)
+    .bind(Utc::now())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+async fn load_tokens(db: &SqlitePool) -> Result<HashMap<u32, TokenEntry>> {
+    let rows = sqlx::query("SELECT token_id, symbol, decimals, enabled FROM token_registry")
+        .fetch_all(db)
+        .await?;
+
+    rows.iter()
+        .map(|row| {
+            let token = TokenEntry {
+                token_id: row.try_get::<i64, _>("token_id")? as u32,
+                symbol: row.try_get("symbol")?,
+                decimals: row.try_get::<i64, _>("decimals")? as u8,
+                enabled: row.try_get("enabled")?,
+            };
+            Ok((token.token_id, token))
+        })
+        .collect()
+}
+
+async fn load_bank_services(db: &SqlitePool) -> Result<HashMap<String, BankServiceEntry>> {
+    let rows = sqlx::query("SELECT name, enabled FROM bank_service_registry")
+        .fetch_all(db)
+        .await?;
+
+    rows.iter()
+        .map(|row| {
+            let service = BankServiceEntry {
+                name: row.try_get("name")?,
+                enabled: row.try_get("enabled")?,
+            };
+            Ok((service.name.to_lowercase(), service))
+        })
+        .collect()
+}
+
+/// Hit/miss counters of one cached registry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    /// Lookups that reloaded the registry because it was expired or invalidated
+    pub misses: u64,
+    pub invalidations: u64,
+    pub entries: usize,
+    /// Seconds since the registry was loaded, unset while it is not cached
+    pub age_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryCacheStats {
+    pub ttl_seconds: u64,
+    pub tokens: CacheStats,
+    pub bank_services: CacheStats,
+}
+
+/// Entries of a registry with when they were loaded
+type Loaded<K, V> = Option<(Instant, Arc<HashMap<K, V>>)>;
+
+/// One registry as loaded from the database, with its counters
+struct Slot<K, V> {
+    loaded: RwLock<Loaded<K, V>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl<K: Eq + Hash, V> Slot<K, V> {
+    fn new() -> Self {
+        Self {
+            loaded: RwLock::new(None),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    /// The cached entries, unless they are older than `ttl`
+    fn fresh(&self, ttl: Duration) -> Option<Arc<HashMap<K, V>>> {
+        let loaded = self.loaded.read().unwrap_or_else(|e| e.into_inner());
+        let entries = loaded.as_ref()
+            .filter(|(loaded_at, _)| loaded_at.elapsed() < ttl)
+            .map(|(_, entries)| entries.clone())?;
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(entries)
+    }
+
+    fn store(&self, entries: HashMap<K, V>) -> Arc<HashMap<K, V>> {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let entries = Arc::new(entries);
+        *self.loaded.write().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), entries.clone()));
+        entries
+    }
+
+    fn invalidate(&self) {
+        self.invalidations.fetch_add(1, Ordering::Relaxed);
+        *self.loaded.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    fn stats(&self) -> CacheStats {
+        let loaded = self.loaded.read().unwrap_or_else(|e| e.into_inner());
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            entries: loaded.as_ref().map_or(0, |(_, entries)| entries.len()),
+            age_seconds: loaded.as_ref().map(|(loaded_at, _)| loaded_at.elapsed().as_secs()),
+        }
+    }
+}
+
+/// Token and bank-service registries, kept in memory for validation and matching
+///
+/// Each registry is reloaded from the database at most once per `ttl`. Admin mutations publish
+/// a change on the event bus, and pending changes are applied before the next lookup, so an
+/// update is seen right away instead of after the TTL.
+#[derive(Clone)]
+pub struct RegistryCache {
+    db: SqlitePool,
+    ttl: Duration,
+    tokens: Arc<Slot<u32, TokenEntry>>,
+    bank_services: Arc<Slot<String, BankServiceEntry>>,
+    changes: Arc<Mutex<broadcast::Receiver<Registry>>>,
+}
+
+impl RegistryCache {
+    pub fn new(db: SqlitePool, ttl: Duration, event_bus: &EventBus) -> Self {
+        Self {
+            db,
+            ttl,
+            tokens: Arc::new(Slot::new()),
+            bank_services: Arc::new(Slot::new()),
+            changes: Arc::new(Mutex::new(event_bus.subscribe_registry_changes())),
+        }
+    }
+
+    pub fn invalidate(&self, registry: Registry) {
+        debug!("Invalidating cached {:?} registry", registry);
+        match registry {
+            Registry::Tokens => self.tokens.invalidate(),
+            Registry::BankServices => self.bank_services.invalidate(),
+        }
+    }
+
+    /// Apply the changes published since the last lookup
+    fn apply_changes(&self) {
+        let mut changes = self.changes.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            match changes.try_recv() {
+                Ok(registry) => self.invalidate(registry),
+                Err(TryRecvError::Lagged(skipped)) => {
+                    warn!("Missed {} registry changes, dropping both cached registries", skipped);
+                    self.invalidate(Registry::Tokens);
+                    self.invalidate(Registry::BankServices);
+                }
+                Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+            }
+        }
+    }
+
+    async fn token_entries(&self) -> Result<Arc<HashMap<u32, TokenEntry>>> {
+        self.apply_changes();
+        if let Some(entries) = self.tokens.fresh(self.ttl) {
+            return Ok(entries);
+        }
+        Ok(self.tokens.store(load_tokens(&self.db).await?))
+    }
+
+    async fn bank_service_entries(&self) -> Result<Arc<HashMap<String, BankServiceEntry>>> {
+        self.apply_changes();
+        if let Some(entries) = self.bank_services.fresh(self.ttl) {
+            return Ok(entries);
+        }
+        Ok(self.bank_services.store(load_bank_services(&self.db).await?))
+    }
+
+    pub async fn token(&self, token_id: u32) -> Result<Option<TokenEntry>> {
+        Ok(self.token_entries().await?.get(&token_id).cloned())
+    }
+
+    pub async fn bank_service(&self, name: &str) -> Result<Option<BankServiceEntry>> {
+        Ok(self.bank_service_entries().await?.get(&name.to_lowercase()).cloned())
+    }
+
+    /// Every registered token, by id
+    pub async fn tokens(&self) -> Result<Vec<TokenEntry>> {
+        let mut tokens: Vec<_> = self.token_entries().await?.values().cloned().collect();
+        tokens.sort_by_key(|token| token.token_id);
+        Ok(tokens)
+    }
+
+    /// Every registered bank service, by name
+    pub async fn bank_services(&self) -> Result<Vec<BankServiceEntry>> {
+        let mut services: Vec<_> = self.bank_service_entries().await?.values().cloned().collect();
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(services)
+    }
+
+    /// Reject orders in a disabled token or paid out through a disabled bank service
+    pub async fn check_order(&self, token_id: u32, bank_service: Option<&str>) -> Result<(), RegistryError> {
+        if self.token(token_id).await?.is_some_and(|token| !token.enabled) {
+            return Err(RegistryError::TokenDisabled(token_id));
+        }
+        if let Some(name) = bank_service {
+            if self.bank_service(name).await?.is_some_and(|service| !service.enabled) {
+                return Err(RegistryError::BankServiceDisabled(name.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self) -> RegistryCacheStats {
+        RegistryCacheStats {
+            ttl_seconds: self.ttl.as_secs(),
+            tokens: self.tokens.stats(),
+            bank_services: self.bank_services.stats(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::run_migrations;
+
+    async fn setup() -> (SqlitePool, EventBus) {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        (db, EventBus::default())
+    }
+
+    fn usdc(enabled: bool) -> TokenEntry {
+        TokenEntry { token_id: 1, symbol: "USDC".to_string(), decimals: 6, enabled }
+    }
+
+    #[tokio::test]
+    async fn test_lookups_are_cached_until_invalidated() {
+        let (db, bus) = setup().await;
+        let cache = RegistryCache::new(db.clone(), Duration::from_secs(3600), &bus);
+        save_token(&db, &usdc(true)).await.unwrap();
+
+        assert_eq!(cache.token(1).await.unwrap(), Some(usdc(true)));
+        assert_eq!(cache.token(2).await.unwrap(), None);
+
+        // Changes that skip the event bus wait for the TTL
+        save_token(&db, &usdc(false)).await.unwrap();
+        assert_eq!(cache.token(1).await.unwrap(), Some(usdc(true)));
+
+        assert_eq!(bus.publish_registry_change(Registry::Tokens), 1);
+        assert_eq!(cache.token(1).await.unwrap(), Some(usdc(false)));
+
+        let stats = cache.stats().tokens;
+        assert_eq!((stats.hits, stats.misses, stats.invalidations, stats.entries), (2, 2, 1, 1));
+        assert_eq!(cache.stats().bank_services, CacheStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_expired_registry_is_reloaded() {
+        let (db, bus) = setup().await;
+        let cache = RegistryCache::new(db.clone(), Duration::ZERO, &bus);
+
+        assert!(cache.bank_services().await.unwrap().is_empty());
+        save_bank_service(&db, &BankServiceEntry { name: "PayPal Hong Kong".to_string(), enabled: true }).await.unwrap();
+        assert_eq!(cache.bank_service("paypal hong kong").await.unwrap().unwrap().name, "PayPal Hong Kong");
+        assert_eq!(cache.stats().bank_services.misses, 2);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_entries_reject_orders() {
+        let (db, bus) = setup().await;
+        let cache = RegistryCache::new(db.clone(), Duration::from_secs(3600), &bus);
+        save_token(&db, &usdc(false)).await.unwrap();
+        save_bank_service(&db, &BankServiceEntry { name: "Wise".to_string(), enabled: false }).await.unwrap();
+
+        assert!(matches!(cache.check_order(1, None).await, Err(RegistryError::TokenDisabled(1))));
+        assert!(matches!(cache.check_order(2, Some("WISE")).await, Err(RegistryError::BankServiceDisabled(_))));
+        // Unregistered tokens and services are not restricted
+        assert!(cache.check_order(2, Some("ACH")).await.is_ok());
+    }
+}
@@ -1,18 +1,36 @@
 use anyhow::Result;
-use std::sync::Arc;
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 use tokio::sync::Mutex;
 use tokio::time::{interval, Duration};
-use tracing::{info, warn, error, debug};
-use uuid::Uuid;
+use tracing::{info, warn, error, debug, instrument, Span};
 use chrono::Utc;
 use sqlx::{SqlitePool, Row};
 
-use crate::blockchain::{BlockchainClient, DepositEvent};
-use crate::models::{Order, OrderType, OrderStatus};
+use web3::types::BlockNumber;
+
+use vapor_chain::{BlockchainClient, ChainIdentity, DepositEvent};
+use crate::config::FinalityMode;
+use crate::models::{new_order_id, Order, OrderType, OrderStatus};
+use vapor_core::fault_injection::{self, FaultTarget};
 use crate::services::{
     matching_engine::MatchingEngine,
     batch_processor::BatchProcessor,
+    event_bus::EventBus,
+    event_log::{self, DomainEvent},
+    discovery,
+    deposit_reference,
+    intent_expiry,
+    maintenance::MaintenanceMode,
+    order_review::OrderReview,
+    stats::{self, Counter},
+    chain_checkpoint,
 };
+use vapor_chain::event_abi::{AbiVersionMetrics, DepositAbiMetrics};
 
 /// Relayer service that monitors blockchain events and creates orders
 pub struct RelayerService {
@@ -26,10 +44,25 @@ pub struct RelayerService {
     batch_processor: Arc<Mutex<BatchProcessor>>,
     /// Last processed block number
     last_processed_block: u64,
+    /// Chain the relayer is scanning, recorded with its checkpoint
+    chain: ChainIdentity,
     /// Polling interval in seconds
     poll_interval_seconds: u64,
     /// Whether the relayer is running
     is_running: bool,
+    /// Event bus for announcing orders that enter discovery
+    event_bus: EventBus,
+    /// Admin pause switch; polling is skipped while maintenance is enabled
+    maintenance: Option<MaintenanceMode>,
+    /// Catch-up scan throughput, shared with the API
+    metrics: RelayerMetrics,
+    /// Finality mode configured for the chain
+    finality: FinalityMode,
+    confirmation_depth: u64,
+    /// How long after its intent expired a BridgeIn order is revived by its deposit
+    intent_revival: chrono::Duration,
+    /// Holds standalone deposits above their token's review threshold
+    order_review: Option<OrderReview>,
 }
 
 /// Configuration for the relayer service
@@ -39,6 +72,16 @@ pub struct RelayerConfig {
     pub start_block: Option<u64>,
     pub auto_match_orders: bool,
     pub auto_batch_orders: bool,
+    /// Blocks per log query when scanning
+    pub scan_range_blocks: u64,
+    /// Log queries in flight at once; results are still applied in block order
+    pub max_concurrent_ranges: usize,
+    /// Discard a checkpoint taken on another chain instead of refusing to start
+    pub reset_checkpoint: bool,
+    /// Finality mode per chain id; chains not listed use `depth`
+    pub finality: HashMap<u64, FinalityMode>,
+    /// Blocks behind latest treated as final in `depth` mode
+    pub confirmation_depth: u64,
 }
 
 impl Default for RelayerConfig {
@@ -48,8 +91,134 @@ impl Default for RelayerConfig {
             start_block: None, // Start from latest block
             auto_match_orders: true,
             auto_batch_orders: true,
+            scan_range_blocks: 500,
+            max_concurrent_ranges: 10,
+            reset_checkpoint: false,
+            finality: HashMap::new(),
+            confirmation_depth: 0,
+        }
+    }
+}
+
+/// Throughput of one scan over a block range
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanThroughput {
+    pub from_block: u64,
+    pub to_block: u64,
+    pub ranges: usize,
+    pub blocks: u64,
+    pub events: u64,
+    pub duration_ms: u64,
+    pub blocks_per_second: f64,
+    pub events_per_second: f64,
+    /// False when a range failed; blocks after it are rescanned on the next poll
+    pub completed: bool,
+}
+
+/// Cumulative scan counters and rates
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScanMetrics {
+    pub blocks_scanned: u64,
+    pub events_fetched: u64,
+    pub scan_seconds: f64,
+    pub blocks_per_second: f64,
+    pub events_per_second: f64,
+    pub last_scan: Option<ScanThroughput>,
+    /// Deposits decoded per bridge ABI version, and logs skipped as unknown
+    pub deposit_abi: DepositAbiMetrics,
+}
+
+/// Finality the relayer scanned up to on its last poll
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FinalityStatus {
+    /// Mode configured for the chain
+    pub configured: FinalityMode,
+    /// Mode of the last poll: `depth` when the node did not serve the configured tag
+    pub active: FinalityMode,
+    pub confirmation_depth: u64,
+    /// Highest block considered final
+    pub final_block: u64,
+    /// Why the configured tag was not used, when it was not
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_reason: Option<String>,
+}
+
+/// Shared handle to the relayer's scan metrics, readable while the relayer loop holds its lock
+#[derive(Clone, Default)]
+pub struct RelayerMetrics {
+    inner: Arc<RwLock<ScanMetrics>>,
+    deposit_abi: AbiVersionMetrics,
+    finality: Arc<RwLock<Option<FinalityStatus>>>,
+}
+
+impl RelayerMetrics {
+    pub fn snapshot(&self) -> ScanMetrics {
+        let mut metrics = self.inner.read().unwrap_or_else(|e| e.into_inner()).clone();
+        metrics.deposit_abi = self.deposit_abi.snapshot();
+        metrics
+    }
+
+    /// Handle for the chain client to count decoded deposits on
+    pub fn deposit_abi(&self) -> AbiVersionMetrics {
+        self.deposit_abi.clone()
+    }
+
+    /// Finality of the last poll, None before the relayer first polled
+    pub fn finality(&self) -> Option<FinalityStatus> {
+        self.finality.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn record_finality(&self, status: FinalityStatus) {
+        *self.finality.write().unwrap_or_else(|e| e.into_inner()) = Some(status);
+    }
+
+    fn record(&self, scan: ScanThroughput) {
+        let mut metrics = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        metrics.blocks_scanned += scan.blocks;
+        metrics.events_fetched += scan.events;
+        metrics.scan_seconds += scan.duration_ms as f64 / 1000.0;
+        metrics.blocks_per_second = per_second(metrics.blocks_scanned, metrics.scan_seconds);
+        metrics.events_per_second = per_second(metrics.events_fetched, metrics.scan_seconds);
+        metrics.last_scan = Some(scan);
+    }
+}
+
+fn per_second(count: u64, seconds: f64) -> f64 {
+    if seconds > 0.0 { count as f64 / seconds } else { 0.0 }
+}
+
+/// Split an inclusive block range into consecutive inclusive sub-ranges of at most `range_blocks` blocks
+pub fn split_block_range(from: u64, to: u64, range_blocks: u64) -> Vec<(u64, u64)> {
+    let range_blocks = range_blocks.max(1);
+    let mut ranges = Vec::new();
+    let mut start = from;
+    while start <= to {
+        let end = start.saturating_add(range_blocks - 1).min(to);
+        ranges.push((start, end));
+        if end == u64::MAX {
+            break;
         }
+        start = end + 1;
     }
+    ranges
+}
+
+/// Fetch sub-ranges with up to `max_concurrent` requests in flight, yielding results in range order
+pub fn fetch_ranges_ordered<T, F, Fut>(
+    ranges: Vec<(u64, u64)>,
+    max_concurrent: usize,
+    fetch: F,
+) -> impl Stream<Item = ((u64, u64), Result<Vec<T>>)>
+where
+    F: Fn(u64, u64) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    futures::stream::iter(ranges)
+        .map(move |(start, end)| {
+            let events = fetch(start, end);
+            async move { ((start, end), events.await) }
+        })
+        .buffered(max_concurrent.max(1))
 }
 
 /// Statistics for the relayer service
@@ -71,15 +240,45 @@ impl RelayerService {
         batch_processor: Arc<Mutex<BatchProcessor>>,
         config: RelayerConfig,
     ) -> Result<Self> {
+        // Resume from the checkpoint, unless it was taken on another chain
+        let chain = blockchain_client.get_chain_identity().await?;
+        let resume_from = match chain_checkpoint::load(&db).await? {
+            Some(checkpoint) if config.reset_checkpoint => {
+                warn!(
+                    "Discarding relayer checkpoint at block {} of chain {} (genesis {})",
+                    checkpoint.block_number, checkpoint.chain_id, checkpoint.genesis_hash
+                );
+                chain_checkpoint::clear(&db).await?;
+                None
+            }
+            Some(checkpoint) => {
+                let block_hash = blockchain_client.get_block_hash(checkpoint.block_number).await?
+                    .map(|hash| format!("{:?}", hash));
+                checkpoint.verify(&chain, block_hash.as_deref()).map_err(|e| anyhow::anyhow!(
+                    "Relayer checkpoint does not match the chain at the RPC endpoint: {}. \
+                     Restart with --reset-relayer-checkpoint to scan this chain from scratch",
+                    e
+                ))?;
+                Some(checkpoint.block_number)
+            }
+            None => None,
+        };
+
         // Get starting block number
-        let last_processed_block = if let Some(start_block) = config.start_block {
+        let last_processed_block = if let Some(block) = resume_from {
+            block
+        } else if let Some(start_block) = config.start_block {
             start_block
         } else {
             // Start from current block - 100 blocks for safety
             blockchain_client.get_block_number().await?.saturating_sub(100)
         };
 
-        info!("Initializing relayer service from block {}", last_processed_block);
+        let finality = config.finality.get(&chain.chain_id).copied().unwrap_or_default();
+        info!(
+            "Initializing relayer service from block {} ({:?} finality, confirmation depth {})",
+            last_processed_block, finality, config.confirmation_depth
+        );
 
         Ok(Self {
             blockchain_client,
@@ -87,11 +286,49 @@ impl RelayerService {
             matching_engine,
             batch_processor,
             last_processed_block,
+            chain,
             poll_interval_seconds: config.poll_interval_seconds,
             is_running: false,
+            event_bus: EventBus::default(),
+            maintenance: None,
+            metrics: RelayerMetrics::default(),
+            finality,
+            confirmation_depth: config.confirmation_depth,
+            intent_revival: chrono::Duration::zero(),
+            order_review: None,
         })
     }
 
+    /// Announce orders entering discovery on the given bus (shared with the API's filler feeds)
+    pub fn with_event_bus(mut self, event_bus: EventBus) -> Self {
+        self.event_bus = event_bus;
+        self
+    }
+
+    /// Pause event polling while the given maintenance switch is enabled
+    pub fn with_maintenance(mut self, maintenance: MaintenanceMode) -> Self {
+        self.maintenance = Some(maintenance);
+        self
+    }
+
+    /// Revive BridgeIn orders whose deposit arrives up to `seconds` after their intent expired
+    pub fn with_intent_revival(mut self, seconds: u64) -> Self {
+        self.intent_revival = chrono::Duration::seconds(seconds as i64);
+        self
+    }
+
+    /// Hold standalone deposits above their token's review threshold for an admin's approval
+    pub fn with_order_review(mut self, order_review: OrderReview) -> Self {
+        self.order_review = Some(order_review);
+        self
+    }
+
+    /// Record scan throughput on the given handle (shared with the API)
+    pub fn with_metrics(mut self, metrics: RelayerMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
     /// Start the relayer service as a background task
     pub async fn start(&mut self, config: RelayerConfig) -> Result<()> {
         if self.is_running {
@@ -112,6 +349,12 @@ impl RelayerService {
                 break;
             }
 
+            // Leave last_processed_block untouched so deposits are picked up once maintenance ends
+            if self.maintenance.as_ref().is_some_and(|m| m.is_enabled()) {
+                debug!("Maintenance mode enabled, skipping event polling");
+                continue;
+            }
+
             // Process new events
             match self.process_new_events(&config).await {
                 Ok(events_processed) => {
@@ -139,9 +382,13 @@ impl RelayerService {
 
     /// Process new blockchain events since last check
     async fn process_new_events(&mut self, config: &RelayerConfig) -> Result<usize> {
-        // Get current block number
-        let current_block = self.blockchain_client.get_block_number().await?;
-        
+        if let Err(e) = stats::increment(&self.db, &[(Counter::RelayerPolls, 1)]).await {
+            error!("Failed to record relayer poll: {}", e);
+        }
+
+        // Only blocks the chain considers final are processed
+        let current_block = self.final_block().await?;
+
         if current_block <= self.last_processed_block {
             // No new blocks to process
             return Ok(0);
@@ -149,33 +396,139 @@ impl RelayerService {
 
         debug!("Checking blocks {} to {}", self.last_processed_block + 1, current_block);
 
-        // Get deposit events from last processed block to current block
-        let deposit_events = self.blockchain_client
-            .get_deposit_events(self.last_processed_block + 1, Some(current_block))
-            .await?;
+        self.scan_blocks(self.last_processed_block + 1, current_block, config).await
+    }
 
-        let mut events_processed = 0;
+    /// Highest final block: the node's `finalized` or `safe` block when configured and served,
+    /// otherwise `confirmation_depth` blocks behind the latest block
+    async fn final_block(&self) -> Result<u64> {
+        let tag = match self.finality {
+            FinalityMode::Finalized => Some(BlockNumber::Finalized),
+            FinalityMode::Safe => Some(BlockNumber::Safe),
+            FinalityMode::Depth => None,
+        };
 
-        for event in deposit_events {
-            match self.process_deposit_event(&event, config).await {
-                Ok(_) => {
-                    events_processed += 1;
-                    info!("Processed deposit event: {:?} -> {} {}", 
-                        event.user, event.amount, event.token);
+        let mut fallback_reason = None;
+        if let Some(tag) = tag {
+            match self.blockchain_client.get_tagged_block_number(tag).await {
+                Ok(Some(block)) => {
+                    self.metrics.record_finality(FinalityStatus {
+                        configured: self.finality,
+                        active: self.finality,
+                        confirmation_depth: self.confirmation_depth,
+                        final_block: block,
+                        fallback_reason: None,
+                    });
+                    return Ok(block);
                 }
+                Ok(None) => fallback_reason = Some(format!("Node returned no {:?} block", self.finality)),
+                Err(e) => fallback_reason = Some(e.to_string()),
+            }
+            // Warn once when falling back, not on every poll
+            let was_active = self.metrics.finality().is_none_or(|status| status.active == self.finality);
+            if was_active {
+                warn!(
+                    "{:?} block tag unavailable ({}), falling back to a confirmation depth of {}",
+                    self.finality, fallback_reason.as_deref().unwrap_or_default(), self.confirmation_depth
+                );
+            }
+        }
+
+        let block = self.blockchain_client.get_block_number().await?.saturating_sub(self.confirmation_depth);
+        self.metrics.record_finality(FinalityStatus {
+            configured: self.finality,
+            active: FinalityMode::Depth,
+            confirmation_depth: self.confirmation_depth,
+            final_block: block,
+            fallback_reason,
+        });
+        Ok(block)
+    }
+
+    /// Fetch deposit events for a block range and process them in block order
+    ///
+    /// Sub-ranges are fetched concurrently but applied strictly in order, so the
+    /// already-processed check sees every earlier deposit. `last_processed_block`
+    /// advances past each applied sub-range; if a fetch fails, scanning stops there
+    /// and the remaining blocks are picked up by the next poll.
+    async fn scan_blocks(&mut self, from: u64, to: u64, config: &RelayerConfig) -> Result<usize> {
+        let started = Instant::now();
+        let ranges = split_block_range(from, to, config.scan_range_blocks);
+        let range_count = ranges.len();
+
+        let client = self.blockchain_client.clone();
+        let mut results = fetch_ranges_ordered(ranges, config.max_concurrent_ranges, move |start, end| {
+            let client = client.clone();
+            async move { Ok(client.get_deposit_events(start, Some(end)).await?) }
+        });
+
+        let mut events_processed = 0;
+        let mut events_fetched = 0u64;
+        let mut scanned_to = None;
+        let mut failure = None;
+
+        while let Some(((start, end), deposit_events)) = results.next().await {
+            let deposit_events = match deposit_events {
+                Ok(deposit_events) => deposit_events,
                 Err(e) => {
-                    error!("Failed to process deposit event {:?}: {}", event, e);
+                    failure = Some(e.context(format!("fetching deposit events for blocks {}-{}", start, end)));
+                    break;
+                }
+            };
+            events_fetched += deposit_events.len() as u64;
+
+            for event in deposit_events {
+                match self.process_deposit_event(&event, config).await {
+                    Ok(_) => {
+                        events_processed += 1;
+                        info!("Processed deposit event: {:?} -> {} {}", 
+                            event.user, event.amount, event.token);
+                    }
+                    Err(e) => {
+                        error!("Failed to process deposit event {:?}: {}", event, e);
+                    }
                 }
             }
+
+            scanned_to = Some(end);
+            self.last_processed_block = self.last_processed_block.max(end);
+        }
+
+        let elapsed = started.elapsed();
+        let blocks = scanned_to.map_or(0, |end| end - from + 1);
+        self.metrics.record(ScanThroughput {
+            from_block: from,
+            to_block: to,
+            ranges: range_count,
+            blocks,
+            events: events_fetched,
+            duration_ms: elapsed.as_millis() as u64,
+            blocks_per_second: per_second(blocks, elapsed.as_secs_f64()),
+            events_per_second: per_second(events_fetched, elapsed.as_secs_f64()),
+            completed: failure.is_none(),
+        });
+
+        if scanned_to.is_some() {
+            if let Err(e) = self.save_checkpoint().await {
+                error!("Failed to save relayer checkpoint at block {}: {}", self.last_processed_block, e);
+            }
         }
 
-        // Update last processed block
-        self.last_processed_block = current_block;
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(events_processed),
+        }
+    }
 
-        Ok(events_processed)
+    /// Record the last processed block and its hash, so a restart resumes from it on the same chain only
+    async fn save_checkpoint(&self) -> Result<()> {
+        let block_hash = self.blockchain_client.get_block_hash(self.last_processed_block).await?
+            .ok_or_else(|| anyhow::anyhow!("Block {} not found", self.last_processed_block))?;
+        chain_checkpoint::save(&self.db, &self.chain, self.last_processed_block, &format!("{:?}", block_hash)).await
     }
 
-    /// Process a single deposit event and create corresponding BridgeIn order
+    /// Process a single deposit event and attribute it to a BridgeIn order
+    #[instrument(skip_all, fields(tx_hash = ?event.transaction_hash, order_id = tracing::field::Empty))]
     async fn process_deposit_event(&self, event: &DepositEvent, config: &RelayerConfig) -> Result<()> {
         info!("Processing deposit event: user={:?}, amount={}, token={:?}", 
             event.user, event.amount, event.token);
@@ -186,32 +539,46 @@ impl RelayerService {
             return Ok(());
         }
 
-        // Create BridgeIn order from deposit event
-        let bridge_in_order = Order {
-            id: Uuid::new_v4().to_string(),
-            order_type: OrderType::BridgeIn,
-            status: OrderStatus::Pending,
-            from_address: Some(format!("{:?}", event.user)),
-            to_address: Some(format!("{:?}", event.user)), // User receives to same address
-            token_id: self.token_address_to_id(&event.token),
-            amount: event.amount.to_string(),
-            bank_account: None, // Will be set when order is created from frontend
-            bank_service: None, // Will be set when order is created from frontend
-            banking_hash: Some(format!("{:?}", event.banking_hash)),
-            filler_id: None,
-            locked_amount: None,
-            batch_id: None,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
+        // A deposit shortly after its order's intent expired still goes to that order
+        let revived = intent_expiry::revive(
+            &self.db,
+            &deposit_reference::format_reference(&event.banking_hash),
+            self.intent_revival,
+            Utc::now(),
+        ).await?.is_some();
+
+        // Deposits made for a pre-created order carry its reference; anything else becomes a standalone order
+        let pre_created = deposit_reference::claim_referenced_order(&self.db, event).await?;
+        let is_standalone = pre_created.is_none();
+        let mut bridge_in_order = match pre_created {
+            Some(order) => order,
+            None => self.create_standalone_order(event).await?,
         };
+        Span::current().record("order_id", bridge_in_order.id.as_str());
 
-        // Save order to database
-        self.save_order_to_database(&bridge_in_order).await?;
+        // Standalone deposits above the review threshold wait for an admin like API-created orders
+        let held = match &self.order_review {
+            Some(order_review) if is_standalone => order_review.hold_if_required(&bridge_in_order).await?,
+            _ => false,
+        };
+        if held {
+            bridge_in_order.status = OrderStatus::ReviewPending;
+        }
+
+        // The deposit is confirmed on-chain, so the order can go straight to fillers
+        if discovery::advance_to_discovery(&self.db, &self.event_bus, &bridge_in_order.id, "deposit_confirmed").await? {
+            bridge_in_order.status = OrderStatus::Discovery;
+            info!("Order {} moved to Discovery after deposit confirmation", bridge_in_order.id);
+        }
 
         // Add to matching engine if auto-matching is enabled
         if config.auto_match_orders {
             let mut engine = self.matching_engine.lock().await;
-            engine.add_order(bridge_in_order.clone())?;
+            // Pre-created orders were queued when they were created, and withdrawn if they expired;
+            // held orders are queued once approved
+            if (is_standalone && !held) || revived {
+                engine.add_order(bridge_in_order.clone())?;
+            }
             
             // Trigger matching
             let matches = engine.match_orders()?;
@@ -236,10 +603,43 @@ impl RelayerService {
             info!("Added BridgeIn order to batch");
         }
 
-        info!("Successfully processed deposit event and created BridgeIn order: {}", order_id);
+        // The deposit is applied whether or not its totals can be recorded
+        let orders_created = if is_standalone { 1 } else { 0 };
+        if let Err(e) = stats::increment(&self.db, &[(Counter::DepositsProcessed, 1), (Counter::OrdersCreated, orders_created)]).await {
+            error!("Failed to record relayer stats for order {}: {}", order_id, e);
+        }
+
+        info!("Successfully processed deposit event for BridgeIn order: {}", order_id);
         Ok(())
     }
 
+    /// Create and save a BridgeIn order for a deposit that does not reference a pre-created order
+    async fn create_standalone_order(&self, event: &DepositEvent) -> Result<Order> {
+        let bridge_in_order = Order {
+            id: new_order_id(),
+            order_type: OrderType::BridgeIn,
+            status: OrderStatus::Pending,
+            from_address: Some(format!("{:?}", event.user)),
+            to_address: Some(format!("{:?}", event.user)), // User receives to same address
+            token_id: self.token_address_to_id(&event.token),
+            amount: event.amount.to_string(),
+            bank_account: None, // Will be set when order is created from frontend
+            bank_service: None, // Will be set when order is created from frontend
+            banking_hash: Some(format!("{:?}", event.banking_hash)),
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        // Save order to database
+        self.save_order_to_database(&bridge_in_order).await?;
+        info!("Created standalone BridgeIn order {} from deposit", bridge_in_order.id);
+
+        Ok(bridge_in_order)
+    }
+
     /// Check if a deposit event has already been processed
     async fn is_deposit_already_processed(&self, event: &DepositEvent) -> Result<bool> {
         let query = "SELECT COUNT(*) as count FROM orders WHERE banking_hash = ?";
@@ -256,6 +656,8 @@ impl RelayerService {
 
     /// Save order to database
     async fn save_order_to_database(&self, order: &Order) -> Result<()> {
+        fault_injection::inject(FaultTarget::Database).await?;
+
         let query = r#"
             INSERT INTO orders (id, order_type, status, from_address, to_address, token_id, amount, banking_hash, created_at, updated_at)
             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
@@ -274,6 +676,7 @@ impl RelayerService {
             .bind(order.updated_at)
             .execute(&self.db)
             .await?;
+        event_log::append(&self.db, &DomainEvent::order_created(order)).await?;
 
         Ok(())
     }
@@ -293,15 +696,24 @@ impl RelayerService {
         }
     }
 
-    /// Get relayer statistics
-    pub fn get_stats(&self) -> RelayerStats {
-        RelayerStats {
+    pub fn is_running(&self) -> bool {
+        self.is_running
+    }
+
+    pub fn last_processed_block(&self) -> u64 {
+        self.last_processed_block
+    }
+
+    /// Get relayer statistics, with the totals recorded in the stats table
+    pub async fn get_stats(&self) -> Result<RelayerStats> {
+        let totals = stats::relayer_totals(&self.db).await?;
+        Ok(RelayerStats {
             is_running: self.is_running,
             last_processed_block: self.last_processed_block,
-            total_deposits_processed: 0, // TODO: Track this in database
-            total_orders_created: 0, // TODO: Track this in database
-            last_poll_time: Some(Utc::now()), // TODO: Track actual last poll time
-        }
+            total_deposits_processed: totals.deposits_processed,
+            total_orders_created: totals.orders_created,
+            last_poll_time: totals.last_poll_time,
+        })
     }
 
     /// Manual trigger to process events (useful for testing)
@@ -313,29 +725,12 @@ impl RelayerService {
         
         info!("Manually processing events from block {} to {}", from, to);
         
-        let deposit_events = self.blockchain_client
-            .get_deposit_events(from, Some(to))
-            .await?;
-
-        let mut events_processed = 0;
-        
-        for event in deposit_events {
-            match self.process_deposit_event(&event, &config).await {
-                Ok(_) => events_processed += 1,
-                Err(e) => error!("Failed to process event: {}", e),
-            }
-        }
-
-        if to > self.last_processed_block {
-            self.last_processed_block = to;
-        }
-
-        Ok(events_processed)
+        self.scan_blocks(from, to, &config).await
     }
 
     /// Get the current block number from blockchain
     pub async fn get_current_block(&self) -> Result<u64> {
-        self.blockchain_client.get_block_number().await
+        Ok(self.blockchain_client.get_block_number().await?)
     }
 
     /// Update relayer configuration
@@ -367,7 +762,7 @@ pub async fn start_relayer_service(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::blockchain::{ContractAddresses, ChainConfig, DepositEvent, BlockchainClient};
+    use vapor_chain::{ContractAddresses, ChainConfig, DepositEvent, BlockchainClient};
     use crate::models::{Order, OrderType, OrderStatus};
     use crate::services::{
         matching_engine::MatchingEngine, 
@@ -457,6 +852,8 @@ mod tests {
         assert!(config.start_block.is_none());
         assert!(config.auto_match_orders);
         assert!(config.auto_batch_orders);
+        assert!(config.finality.is_empty());
+        assert_eq!(config.confirmation_depth, 0);
     }
 
     #[test]
@@ -466,6 +863,7 @@ mod tests {
             start_block: Some(1000),
             auto_match_orders: false,
             auto_batch_orders: true,
+            ..RelayerConfig::default()
         };
         
         assert_eq!(config.poll_interval_seconds, 30);
@@ -474,6 +872,92 @@ mod tests {
         assert!(config.auto_batch_orders);
     }
 
+    #[test]
+    fn test_finality_status_is_shared_with_the_api() {
+        let metrics = RelayerMetrics::default();
+        assert!(metrics.finality().is_none());
+
+        metrics.clone().record_finality(FinalityStatus {
+            configured: FinalityMode::Finalized,
+            active: FinalityMode::Depth,
+            confirmation_depth: 12,
+            final_block: 988,
+            fallback_reason: Some("Node returned no Finalized block".to_string()),
+        });
+        let status = serde_json::to_value(metrics.finality()).unwrap();
+        assert_eq!(status["configured"], "finalized");
+        assert_eq!(status["active"], "depth");
+        assert_eq!(status["final_block"], 988);
+    }
+
+    #[test]
+    fn test_split_block_range() {
+        assert_eq!(split_block_range(1, 10, 4), vec![(1, 4), (5, 8), (9, 10)]);
+        assert_eq!(split_block_range(5, 5, 100), vec![(5, 5)]);
+        assert_eq!(split_block_range(1, 3, 0), vec![(1, 1), (2, 2), (3, 3)]);
+        assert!(split_block_range(10, 9, 4).is_empty());
+        assert_eq!(split_block_range(u64::MAX - 1, u64::MAX, 4), vec![(u64::MAX - 1, u64::MAX)]);
+    }
+
+    #[tokio::test]
+    async fn test_ranges_fetched_concurrently_and_yielded_in_order() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let ranges = split_block_range(1, 100, 10);
+
+        let (in_flight_fetch, peak_fetch) = (in_flight.clone(), peak.clone());
+        let results: Vec<_> = fetch_ranges_ordered(ranges.clone(), 3, move |start, end| {
+            let (in_flight, peak) = (in_flight_fetch.clone(), peak_fetch.clone());
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                // Earlier ranges finish last
+                tokio::time::sleep(Duration::from_millis(200 - start)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                if start == 61 {
+                    anyhow::bail!("rpc timeout");
+                }
+                Ok(vec![start, end])
+            }
+        })
+        .collect()
+        .await;
+
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        let yielded: Vec<(u64, u64)> = results.iter().map(|(range, _)| *range).collect();
+        assert_eq!(yielded, ranges);
+        assert_eq!(results[0].1.as_ref().unwrap(), &vec![1, 10]);
+        assert!(results[6].1.is_err());
+    }
+
+    #[test]
+    fn test_relayer_metrics_accumulate_throughput() {
+        let metrics = RelayerMetrics::default();
+        let scan = |blocks, events, duration_ms| ScanThroughput {
+            from_block: 1,
+            to_block: blocks,
+            ranges: 1,
+            blocks,
+            events,
+            duration_ms,
+            blocks_per_second: 0.0,
+            events_per_second: 0.0,
+            completed: true,
+        };
+
+        metrics.record(scan(1000, 10, 500));
+        metrics.record(scan(1000, 30, 1500));
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.blocks_scanned, 2000);
+        assert_eq!(snapshot.events_fetched, 40);
+        assert_eq!(snapshot.blocks_per_second, 1000.0);
+        assert_eq!(snapshot.events_per_second, 20.0);
+        assert_eq!(snapshot.last_scan.unwrap().duration_ms, 1500);
+    }
+
     #[tokio::test]
     async fn test_relayer_service_creation() {
         // Skip blockchain client tests for now as they require network connection
@@ -0,0 +1,96 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Windows are pruned once this many clients are tracked
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// A client went over its per-minute request allowance
+#[derive(Debug, Clone, thiserror::Error, Serialize)]
+#[error("Rate limit of {limit} requests per minute exceeded, retry in {retry_after_seconds}s")]
+pub struct RateLimited {
+    pub limit: u32,
+    pub retry_after_seconds: u64,
+}
+
+/// Fixed-window request limiter keyed by client (usually the IP address)
+#[derive(Clone)]
+pub struct ClientRateLimiter {
+    per_minute: u32,
+    windows: Arc<Mutex<HashMap<String, (Instant, u32)>>>,
+}
+
+impl ClientRateLimiter {
+    /// Allow `per_minute` requests per client and minute; 0 disables the limit
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            windows: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Count a request from `client`
+    pub fn check(&self, client: &str) -> Result<(), RateLimited> {
+        self.check_at(client, Instant::now())
+    }
+
+    fn check_at(&self, client: &str, now: Instant) -> Result<(), RateLimited> {
+        if self.per_minute == 0 {
+            return Ok(());
+        }
+
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.len() >= PRUNE_THRESHOLD {
+            windows.retain(|_, (started, _)| now.duration_since(*started) < WINDOW);
+        }
+
+        let (started, count) = windows.entry(client.to_string()).or_insert((now, 0));
+        if now.duration_since(*started) >= WINDOW {
+            *started = now;
+            *count = 0;
+        }
+
+        if *count >= self.per_minute {
+            let remaining = WINDOW.saturating_sub(now.duration_since(*started));
+            return Err(RateLimited {
+                limit: self.per_minute,
+                retry_after_seconds: remaining.as_secs_f64().ceil() as u64,
+            });
+        }
+
+        *count += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_each_client_per_window() {
+        let limiter = ClientRateLimiter::new(2);
+        let start = Instant::now();
+
+        assert!(limiter.check_at("1.2.3.4", start).is_ok());
+        assert!(limiter.check_at("1.2.3.4", start + Duration::from_secs(1)).is_ok());
+        let limited = limiter.check_at("1.2.3.4", start + Duration::from_secs(20)).unwrap_err();
+        assert_eq!(limited.limit, 2);
+        assert_eq!(limited.retry_after_seconds, 40);
+
+        // Other clients have their own allowance
+        assert!(limiter.check_at("5.6.7.8", start + Duration::from_secs(20)).is_ok());
+
+        // A new window starts a minute after the first request
+        assert!(limiter.check_at("1.2.3.4", start + Duration::from_secs(60)).is_ok());
+    }
+
+    #[test]
+    fn test_zero_disables_the_limit() {
+        let limiter = ClientRateLimiter::new(0);
+        assert!((0..1000).all(|_| limiter.check("1.2.3.4").is_ok()));
+    }
+}
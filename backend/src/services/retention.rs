@@ -0,0 +1,206 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use tracing::info;
+
+use crate::config::RetentionConfig;
+use crate::models::OrderStatus;
+
+/// Order columns holding bank details; `banking_hash` stays, it is the proof digest the
+/// batch proofs commit to and reveals nothing about the account
+const BANK_DETAIL_COLUMNS: [&str; 3] = ["bank_account", "bank_service", "payment_proof"];
+
+/// One order's bank details removed once its retention ran out
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrubRecord {
+    pub order_id: String,
+    /// Bank service the retention was looked up for, recorded before it was scrubbed
+    pub bank_service: Option<String>,
+    /// Columns that held data
+    pub fields: Vec<String>,
+    pub retention_days: u32,
+    pub terminal_at: DateTime<Utc>,
+    pub scrubbed_at: DateTime<Utc>,
+}
+
+/// Remove the bank details of settled and failed orders whose retention has run out
+///
+/// The retention starts when the order reaches its terminal state. Every scrub is written to
+/// the audit log in the same transaction.
+pub async fn scrub_expired(db: &SqlitePool, config: &RetentionConfig, now: DateTime<Utc>) -> Result<Vec<ScrubRecord>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT o.id, o.bank_account, o.bank_service, o.payment_proof,
+               COALESCE((SELECT MAX(h.created_at) FROM order_status_history h WHERE h.order_id = o.id AND h.to_status = o.status), o.updated_at) AS terminal_at
+        FROM orders o
+        WHERE o.status IN (?, ?) AND (o.bank_account IS NOT NULL OR o.bank_service IS NOT NULL OR o.payment_proof IS NOT NULL)
+        ORDER BY o.id
+        "#,
+    )
+    .bind(OrderStatus::Settled as i32)
+    .bind(OrderStatus::Failed as i32)
+    .fetch_all(db)
+    .await?;
+
+    let mut scrubbed = Vec::new();
+    for row in rows {
+        let bank_service: Option<String> = row.try_get("bank_service")?;
+        let retention_days = config.days_for(bank_service.as_deref());
+        let terminal_at: DateTime<Utc> = row.try_get("terminal_at")?;
+        if retention_days == 0 || terminal_at + Duration::days(retention_days as i64) > now {
+            continue;
+        }
+
+        let mut fields = Vec::new();
+        for column in BANK_DETAIL_COLUMNS {
+            if row.try_get::<Option<String>, _>(column)?.is_some() {
+                fields.push(column.to_string());
+            }
+        }
+        let record = ScrubRecord {
+            order_id: row.try_get("id")?,
+            bank_service,
+            fields,
+            retention_days,
+            terminal_at,
+            scrubbed_at: now,
+        };
+
+        let mut tx = db.begin().await?;
+        sqlx::query("UPDATE orders SET bank_account = NULL, bank_service = NULL, payment_proof = NULL WHERE id = ?")
+            .bind(&record.order_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO retention_audit (order_id, bank_service, fields, retention_days, terminal_at, scrubbed_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&record.order_id)
+        .bind(&record.bank_service)
+        .bind(record.fields.join(","))
+        .bind(record.retention_days as i64)
+        .bind(record.terminal_at)
+        .bind(record.scrubbed_at)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        scrubbed.push(record);
+    }
+
+    if !scrubbed.is_empty() {
+        info!("Scrubbed bank details of {} orders past their retention", scrubbed.len());
+    }
+    Ok(scrubbed)
+}
+
+/// Scrub audit entries, newest first, optionally for a single order
+pub async fn audit_log(db: &SqlitePool, order_id: Option<&str>, limit: u32) -> Result<Vec<ScrubRecord>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT order_id, bank_service, fields, retention_days, terminal_at, scrubbed_at FROM retention_audit
+        WHERE ?1 IS NULL OR order_id = ?1
+        ORDER BY id DESC LIMIT ?2
+        "#,
+    )
+    .bind(order_id)
+    .bind(limit as i64)
+    .fetch_all(db)
+    .await?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(ScrubRecord {
+                order_id: row.try_get("order_id")?,
+                bank_service: row.try_get("bank_service")?,
+                fields: row.try_get::<String, _>("fields")?.split(',').filter(|field| !field.is_empty()).map(str::to_string).collect(),
+                retention_days: row.try_get::<i64, _>("retention_days")? as u32,
+                terminal_at: row.try_get("terminal_at")?,
+                scrubbed_at: row.try_get("scrubbed_at")?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::helpers;
+    use crate::models::{Order, OrderType};
+    use std::collections::HashMap;
+
+    async fn insert(db: &SqlitePool, id: &str, status: OrderStatus, bank_service: &str, updated_at: DateTime<Utc>) {
+        let order = Order {
+            id: id.to_string(),
+            order_type: OrderType::BridgeIn,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some(bank_service.to_string()),
+            banking_hash: Some("0xabcdef".to_string()),
+            filler_id: None,
+            locked_amount: None,
+            status,
+            batch_id: None,
+            created_at: updated_at,
+            updated_at,
+        };
+        helpers::insert_order(db, &order).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scrubs_terminal_orders_past_their_tenants_retention() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let now = Utc::now();
+        let days_ago = |days| now - Duration::days(days);
+
+        insert(&db, "settled_old", OrderStatus::Settled, "PayPal Hong Kong", days_ago(31)).await;
+        insert(&db, "settled_recent", OrderStatus::Settled, "PayPal Hong Kong", days_ago(5)).await;
+        insert(&db, "failed_short_tenant", OrderStatus::Failed, "Wise", days_ago(8)).await;
+        insert(&db, "kept_forever", OrderStatus::Settled, "ACH", days_ago(400)).await;
+        insert(&db, "still_open", OrderStatus::Locked, "PayPal Hong Kong", days_ago(90)).await;
+        sqlx::query("UPDATE orders SET payment_proof = '{}' WHERE id = 'settled_old'").execute(&db).await.unwrap();
+
+        let config = RetentionConfig {
+            bank_details_days: 30,
+            bank_service_days: HashMap::from([("wise".to_string(), 7), ("ach".to_string(), 0)]),
+            sweep_interval_seconds: 3600,
+        };
+        let scrubbed = scrub_expired(&db, &config, now).await.unwrap();
+        let ids: Vec<_> = scrubbed.iter().map(|record| record.order_id.as_str()).collect();
+        assert_eq!(ids, vec!["failed_short_tenant", "settled_old"]);
+        assert_eq!(scrubbed[1].fields, vec!["bank_account", "bank_service", "payment_proof"]);
+        assert_eq!(scrubbed[0].retention_days, 7);
+
+        let old = helpers::get_order_by_id(&db, "settled_old").await.unwrap().unwrap();
+        assert_eq!((old.bank_account, old.bank_service), (None, None));
+        // The deposit hash is kept
+        assert_eq!(old.banking_hash.as_deref(), Some("0xabcdef"));
+        let recent = helpers::get_order_by_id(&db, "settled_recent").await.unwrap().unwrap();
+        assert_eq!(recent.bank_account.as_deref(), Some("12345678"));
+
+        // Scrubbed orders are not scrubbed again
+        assert!(scrub_expired(&db, &config, now).await.unwrap().is_empty());
+        let audit = audit_log(&db, None, 10).await.unwrap();
+        assert_eq!(audit.len(), 2);
+        let audit = audit_log(&db, Some("settled_old"), 10).await.unwrap();
+        assert_eq!((audit[0].bank_service.as_deref(), audit[0].fields.len()), (Some("PayPal Hong Kong"), 3));
+    }
+
+    #[tokio::test]
+    async fn test_retention_starts_when_the_order_became_terminal() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+
+        // Created long ago, but only settled now
+        insert(&db, "late_settle", OrderStatus::Settled, "PayPal Hong Kong", Utc::now() - Duration::days(60)).await;
+        helpers::record_status_transition(&db, "late_settle", OrderStatus::MarkPaid, OrderStatus::Settled, None).await.unwrap();
+
+        let config = RetentionConfig::default();
+        assert!(scrub_expired(&db, &config, Utc::now()).await.unwrap().is_empty());
+        assert_eq!(scrub_expired(&db, &config, Utc::now() + Duration::days(31)).await.unwrap().len(), 1);
+    }
+}
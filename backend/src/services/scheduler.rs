@@ -0,0 +1,317 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use tracing::{info, warn};
+
+use crate::config::JobsConfig;
+use vapor_core::services::clock::SharedClock;
+
+/// Delayed order transitions the scheduler runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Ask the payment provider about a submitted proof, retried while it is pending
+    VerifyPayment,
+    /// Settle a paid order once its escrow transfer's batch proof was submitted
+    CheckSettlement,
+    /// Fail a lock the filler did not pay within the lock TTL
+    ExpireLock,
+    /// Lock an order for the winning bid of its lock window
+    CloseLockWindow,
+    /// Send a notification; keyed by the notification id rather than an order id
+    DeliverNotification,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    Done,
+    /// Out of attempts, or given up on by the job itself
+    Failed,
+    Cancelled,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::VerifyPayment => "verify_payment",
+            JobKind::CheckSettlement => "check_settlement",
+            JobKind::ExpireLock => "expire_lock",
+            JobKind::CloseLockWindow => "close_lock_window",
+            JobKind::DeliverNotification => "deliver_notification",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "verify_payment" => Some(JobKind::VerifyPayment),
+            "check_settlement" => Some(JobKind::CheckSettlement),
+            "expire_lock" => Some(JobKind::ExpireLock),
+            "close_lock_window" => Some(JobKind::CloseLockWindow),
+            "deliver_notification" => Some(JobKind::DeliverNotification),
+            _ => None,
+        }
+    }
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "pending" => Some(JobStatus::Pending),
+            "done" => Some(JobStatus::Done),
+            "failed" => Some(JobStatus::Failed),
+            "cancelled" => Some(JobStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+/// A job as persisted; an order has at most one job of each kind
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledJob {
+    pub id: String,
+    pub kind: JobKind,
+    pub order_id: String,
+    pub payload: Option<String>,
+    pub run_at: DateTime<Utc>,
+    pub attempts: u32,
+    pub status: JobStatus,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What running a job came to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobOutcome {
+    Done,
+    /// The attempt failed; retried with backoff until `max_attempts`
+    Retry(String),
+    /// Nothing to do yet; run again at the given time without counting an attempt
+    RunAt(DateTime<Utc>),
+    /// Give up without retrying
+    Fail(String),
+}
+
+/// DB-backed delayed jobs, picked up by the jobs loop once due (see api::jobs)
+///
+/// Jobs survive restarts, and rescheduling a job of the same kind for an order replaces it.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    db: SqlitePool,
+    clock: SharedClock,
+    config: JobsConfig,
+}
+
+impl Scheduler {
+    pub fn new(db: SqlitePool, clock: SharedClock, config: &JobsConfig) -> Self {
+        Self { db, clock, config: config.clone() }
+    }
+
+    /// Run `kind` for `order_id` after `delay`, replacing a job of the same kind
+    pub async fn schedule(&self, kind: JobKind, order_id: &str, payload: Option<&str>, delay: Duration) -> Result<ScheduledJob> {
+        let now = self.clock.now();
+        let job = ScheduledJob {
+            id: format!("{}:{}", kind.as_str(), order_id),
+            kind,
+            order_id: order_id.to_string(),
+            payload: payload.map(str::to_string),
+            run_at: now + delay,
+            attempts: 0,
+            status: JobStatus::Pending,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        };
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO scheduled_jobs
+                (id, kind, order_id, payload, run_at, attempts, status, last_error, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "#,
+        )
+        .bind(&job.id)
+        .bind(job.kind.as_str())
+        .bind(&job.order_id)
+        .bind(&job.payload)
+        .bind(job.run_at)
+        .bind(job.attempts as i64)
+        .bind(job.status.as_str())
+        .bind(&job.last_error)
+        .bind(job.created_at)
+        .bind(job.updated_at)
+        .execute(&self.db)
+        .await?;
+
+        info!("Scheduled {} for order {} at {}", kind.as_str(), order_id, job.run_at);
+        Ok(job)
+    }
+
+    /// Cancel the pending job of `kind` for an order; false when there was none
+    pub async fn cancel(&self, kind: JobKind, order_id: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE scheduled_jobs SET status = ?1, updated_at = ?2 WHERE id = ?3 AND status = ?4")
+            .bind(JobStatus::Cancelled.as_str())
+            .bind(self.clock.now())
+            .bind(format!("{}:{}", kind.as_str(), order_id))
+            .bind(JobStatus::Pending.as_str())
+            .execute(&self.db)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Pending jobs whose time has come, earliest first
+    pub async fn due(&self, limit: u32) -> Result<Vec<ScheduledJob>> {
+        let rows = sqlx::query("SELECT * FROM scheduled_jobs WHERE status = ?1 AND run_at <= ?2 ORDER BY run_at, id LIMIT ?3")
+            .bind(JobStatus::Pending.as_str())
+            .bind(self.clock.now())
+            .bind(limit as i64)
+            .fetch_all(&self.db)
+            .await?;
+        rows.iter().map(row_to_job).collect()
+    }
+
+    /// Record a run's outcome, rescheduling retries at `retry_base_seconds · 2^(attempts-1)`
+    /// capped at `retry_max_seconds`
+    pub async fn finish(&self, job: &ScheduledJob, outcome: JobOutcome) -> Result<ScheduledJob> {
+        let now = self.clock.now();
+        let mut job = job.clone();
+        match outcome {
+            JobOutcome::Done => job.status = JobStatus::Done,
+            JobOutcome::RunAt(run_at) => job.run_at = run_at,
+            JobOutcome::Fail(error) => {
+                job.status = JobStatus::Failed;
+                job.last_error = Some(error);
+            }
+            JobOutcome::Retry(error) => {
+                job.attempts += 1;
+                if job.attempts >= self.config.max_attempts {
+                    warn!("Giving up {} after {} attempts: {}", job.id, job.attempts, error);
+                    job.status = JobStatus::Failed;
+                } else {
+                    job.run_at = now + self.backoff(job.attempts);
+                }
+                job.last_error = Some(error);
+            }
+        }
+        job.updated_at = now;
+
+        sqlx::query("UPDATE scheduled_jobs SET run_at = ?1, attempts = ?2, status = ?3, last_error = ?4, updated_at = ?5 WHERE id = ?6")
+            .bind(job.run_at)
+            .bind(job.attempts as i64)
+            .bind(job.status.as_str())
+            .bind(&job.last_error)
+            .bind(job.updated_at)
+            .bind(&job.id)
+            .execute(&self.db)
+            .await?;
+        Ok(job)
+    }
+
+    /// Jobs in `status`, or every job, most recently updated first
+    pub async fn list(&self, status: Option<JobStatus>) -> Result<Vec<ScheduledJob>> {
+        let rows = sqlx::query("SELECT * FROM scheduled_jobs WHERE ?1 IS NULL OR status = ?1 ORDER BY updated_at DESC, id")
+            .bind(status.map(|status| status.as_str()))
+            .fetch_all(&self.db)
+            .await?;
+        rows.iter().map(row_to_job).collect()
+    }
+
+    fn backoff(&self, attempts: u32) -> Duration {
+        let seconds = self.config.retry_base_seconds
+            .saturating_mul(1u64.checked_shl(attempts.saturating_sub(1)).unwrap_or(u64::MAX))
+            .min(self.config.retry_max_seconds);
+        Duration::seconds(seconds as i64)
+    }
+}
+
+fn row_to_job(row: &sqlx::sqlite::SqliteRow) -> Result<ScheduledJob> {
+    let kind: String = row.try_get("kind")?;
+    let status: String = row.try_get("status")?;
+    Ok(ScheduledJob {
+        id: row.try_get("id")?,
+        kind: JobKind::parse(&kind).ok_or_else(|| anyhow!("unknown job kind '{}'", kind))?,
+        order_id: row.try_get("order_id")?,
+        payload: row.try_get("payload")?,
+        run_at: row.try_get("run_at")?,
+        attempts: row.try_get::<i64, _>("attempts")? as u32,
+        status: JobStatus::parse(&status).ok_or_else(|| anyhow!("unknown job status '{}'", status))?,
+        last_error: row.try_get("last_error")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vapor_core::services::clock::{Clock, MockClock};
+    use chrono::TimeZone;
+
+    async fn setup(config: JobsConfig) -> (Scheduler, MockClock) {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap());
+        (Scheduler::new(db, clock.shared(), &config), clock)
+    }
+
+    #[tokio::test]
+    async fn test_jobs_run_once_due_and_replace_earlier_ones() {
+        let (scheduler, clock) = setup(JobsConfig::default()).await;
+        scheduler.schedule(JobKind::ExpireLock, "order_1", None, Duration::seconds(60)).await.unwrap();
+        scheduler.schedule(JobKind::VerifyPayment, "order_1", None, Duration::zero()).await.unwrap();
+        // Relocking replaces the earlier expiry
+        scheduler.schedule(JobKind::ExpireLock, "order_1", Some("again"), Duration::seconds(120)).await.unwrap();
+
+        let due = scheduler.due(10).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].kind, JobKind::VerifyPayment);
+
+        clock.advance(Duration::seconds(120));
+        let due = scheduler.due(10).await.unwrap();
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[1].payload.as_deref(), Some("again"));
+
+        scheduler.finish(&due[0], JobOutcome::Done).await.unwrap();
+        assert!(scheduler.cancel(JobKind::ExpireLock, "order_1").await.unwrap());
+        assert!(!scheduler.cancel(JobKind::ExpireLock, "order_1").await.unwrap());
+        assert!(scheduler.due(10).await.unwrap().is_empty());
+        assert_eq!(scheduler.list(Some(JobStatus::Cancelled)).await.unwrap().len(), 1);
+        assert_eq!(scheduler.list(None).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retries_back_off_until_out_of_attempts() {
+        let config = JobsConfig { retry_base_seconds: 30, retry_max_seconds: 100, max_attempts: 4, ..JobsConfig::default() };
+        let (scheduler, clock) = setup(config).await;
+        let start = clock.now();
+        let mut job = scheduler.schedule(JobKind::VerifyPayment, "order_1", None, Duration::zero()).await.unwrap();
+
+        let mut delays = Vec::new();
+        for _ in 0..3 {
+            job = scheduler.finish(&job, JobOutcome::Retry("pending".to_string())).await.unwrap();
+            delays.push((job.run_at - start).num_seconds());
+        }
+        assert_eq!(delays, vec![30, 60, 100]);
+        assert_eq!(job.status, JobStatus::Pending);
+
+        // Checking back later is not an attempt
+        job = scheduler.finish(&job, JobOutcome::RunAt(start + Duration::seconds(500))).await.unwrap();
+        assert_eq!((job.attempts, job.run_at), (3, start + Duration::seconds(500)));
+
+        job = scheduler.finish(&job, JobOutcome::Retry("still pending".to_string())).await.unwrap();
+        assert_eq!(job.status, JobStatus::Failed);
+        assert_eq!(job.last_error.as_deref(), Some("still pending"));
+        assert!(scheduler.due(10).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,456 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::config::{ScreeningAction, ScreeningConfig, ScreeningProviderConfig};
+use vapor_core::services::clock::SharedClock;
+use vapor_core::services::screening::{Identification, NoScreening, ScreeningProvider};
+
+/// A fixed denylist of addresses, compared case-insensitively
+pub struct StaticScreening {
+    addresses: HashSet<String>,
+}
+
+impl StaticScreening {
+    pub fn new<'a>(addresses: impl IntoIterator<Item = &'a str>) -> Self {
+        Self { addresses: addresses.into_iter().map(str::to_lowercase).collect() }
+    }
+}
+
+impl ScreeningProvider for StaticScreening {
+    fn kind(&self) -> &'static str {
+        "static"
+    }
+
+    fn screen<'a>(&'a self, address: &'a str) -> BoxFuture<'a, Result<Vec<Identification>>> {
+        let listed = self.addresses.contains(&address.to_lowercase());
+        Box::pin(async move {
+            Ok(listed.then(|| Identification {
+                category: "sanctions".to_string(),
+                name: "Static denylist".to_string(),
+                description: None,
+                url: None,
+            }).into_iter().collect())
+        })
+    }
+}
+
+/// A Chainalysis-style sanctions API: `GET {url}/api/v1/address/{address}` with the key in
+/// `X-API-Key`, answering `{"identifications": [...]}`
+pub struct HttpScreening {
+    url: String,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct HttpScreeningResponse {
+    identifications: Vec<Identification>,
+}
+
+impl HttpScreening {
+    pub fn new(url: &str, api_key: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl ScreeningProvider for HttpScreening {
+    fn kind(&self) -> &'static str {
+        "http"
+    }
+
+    fn screen<'a>(&'a self, address: &'a str) -> BoxFuture<'a, Result<Vec<Identification>>> {
+        Box::pin(async move {
+            let response: HttpScreeningResponse = self.client
+                .get(format!("{}/api/v1/address/{}", self.url, address))
+                .header("X-API-Key", &self.api_key)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            Ok(response.identifications)
+        })
+    }
+}
+
+pub fn screening_provider_from_config(config: &ScreeningProviderConfig) -> Arc<dyn ScreeningProvider> {
+    match config {
+        ScreeningProviderConfig::None => Arc::new(NoScreening),
+        ScreeningProviderConfig::Static { addresses } => Arc::new(StaticScreening::new(addresses.iter().map(String::as_str))),
+        ScreeningProviderConfig::Http { url, api_key } => Arc::new(HttpScreening::new(url, api_key)),
+    }
+}
+
+/// What is being screened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreeningContext {
+    /// A new order's from and to addresses
+    Order,
+    /// The wallets of a filler locking an order
+    Lock,
+    /// The destination addresses of a claim
+    Claim,
+}
+
+impl ScreeningContext {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Order => "order",
+            Self::Lock => "lock",
+            Self::Claim => "claim",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "order" => Some(Self::Order),
+            "lock" => Some(Self::Lock),
+            "claim" => Some(Self::Claim),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, thiserror::Error)]
+#[serde(tag = "screening", rename_all = "snake_case")]
+pub enum ScreeningError {
+    #[error("address {address} failed sanctions screening")]
+    Blocked { context: ScreeningContext, address: String, identifications: Vec<Identification> },
+    /// Addresses are not let through unscreened
+    #[error("address screening is unavailable: {reason}")]
+    Unavailable { reason: String },
+    #[error("screening result {id} not found")]
+    NotFound { id: String },
+    #[error("screening result {id} is not waiting for review")]
+    NotPending { id: String },
+    #[error(transparent)]
+    #[serde(skip)]
+    Other(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for ScreeningError {
+    fn from(e: sqlx::Error) -> Self {
+        ScreeningError::Other(e.into())
+    }
+}
+
+/// Outcome recorded for a screened address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreeningOutcome {
+    Clear,
+    Blocked,
+    /// Let through, waiting for an admin's review
+    Flagged,
+}
+
+impl ScreeningOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Clear => "clear",
+            Self::Blocked => "blocked",
+            Self::Flagged => "flagged",
+        }
+    }
+
+    fn parse(raw: &str) -> Self {
+        match raw {
+            "blocked" => Self::Blocked,
+            "flagged" => Self::Flagged,
+            _ => Self::Clear,
+        }
+    }
+}
+
+/// An admin's decision on a flagged result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewDecision {
+    /// A false positive
+    Cleared,
+    /// A true match, to be handled outside the backend
+    Confirmed,
+}
+
+impl ReviewDecision {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Cleared => "cleared",
+            Self::Confirmed => "confirmed",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "cleared" => Some(Self::Cleared),
+            "confirmed" => Some(Self::Confirmed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreeningResult {
+    pub id: String,
+    pub context: ScreeningContext,
+    /// Order id for orders and locks, filler id for claims
+    pub subject_id: String,
+    pub address: String,
+    pub provider: String,
+    pub outcome: ScreeningOutcome,
+    pub identifications: Vec<Identification>,
+    pub screened_at: DateTime<Utc>,
+    pub decision: Option<ReviewDecision>,
+    pub review_note: Option<String>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+/// Screens the addresses of orders, locks and claims and records every result
+///
+/// A hit is refused or flagged for review depending on `SCREENING_ACTION`. When the provider
+/// cannot be reached the request is refused either way.
+#[derive(Clone)]
+pub struct Screener {
+    db: SqlitePool,
+    provider: Arc<dyn ScreeningProvider>,
+    action: ScreeningAction,
+    clock: SharedClock,
+}
+
+impl Screener {
+    pub fn new(db: SqlitePool, config: &ScreeningConfig, clock: SharedClock) -> Self {
+        Self {
+            db,
+            provider: screening_provider_from_config(&config.provider),
+            action: config.action,
+            clock,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.provider.kind() != "none"
+    }
+
+    /// Screen `addresses` for `subject_id`, failing on the first hit when hits are blocked
+    pub async fn screen(&self, context: ScreeningContext, subject_id: &str, addresses: &[&str]) -> Result<(), ScreeningError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let mut seen = HashSet::new();
+        for &address in addresses.iter().filter(|address| seen.insert(address.to_lowercase())) {
+            let identifications = self.provider.screen(address).await.map_err(|e| {
+                warn!("Screening {} for {} {} failed: {}", address, context.as_str(), subject_id, e);
+                ScreeningError::Unavailable { reason: e.to_string() }
+            })?;
+            let outcome = match (identifications.is_empty(), self.action) {
+                (true, _) => ScreeningOutcome::Clear,
+                (false, ScreeningAction::Block) => ScreeningOutcome::Blocked,
+                (false, ScreeningAction::Flag) => ScreeningOutcome::Flagged,
+            };
+            self.record(context, subject_id, address, outcome, &identifications).await?;
+
+            match outcome {
+                ScreeningOutcome::Blocked => {
+                    warn!("Blocked {} {}: address {} failed screening", context.as_str(), subject_id, address);
+                    return Err(ScreeningError::Blocked { context, address: address.to_string(), identifications });
+                }
+                ScreeningOutcome::Flagged => {
+                    warn!("Flagged {} {} for review: address {} failed screening", context.as_str(), subject_id, address);
+                }
+                ScreeningOutcome::Clear => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Screen the registered wallets of a filler locking `order_id`
+    pub async fn screen_lock(&self, order_id: &str, filler_id: &str) -> Result<(), ScreeningError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let wallets: Vec<String> = sqlx::query_scalar("SELECT wallet_address FROM filler_wallets WHERE filler_id = ?")
+            .bind(filler_id)
+            .fetch_all(&self.db)
+            .await?;
+        let wallets: Vec<&str> = wallets.iter().map(String::as_str).collect();
+        self.screen(ScreeningContext::Lock, order_id, &wallets).await
+    }
+
+    async fn record(
+        &self,
+        context: ScreeningContext,
+        subject_id: &str,
+        address: &str,
+        outcome: ScreeningOutcome,
+        identifications: &[Identification],
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO screening_results
+                (id, context, subject_id, address, provider, outcome, identifications, screened_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(context.as_str())
+        .bind(subject_id)
+        .bind(address.to_lowercase())
+        .bind(self.provider.kind())
+        .bind(outcome.as_str())
+        .bind(serde_json::to_string(identifications)?)
+        .bind(self.clock.now())
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Flagged results, oldest first; only those still waiting for a decision unless `include_reviewed`
+    pub async fn review_queue(&self, include_reviewed: bool, limit: u32) -> Result<Vec<ScreeningResult>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM screening_results
+            WHERE outcome = 'flagged' AND (?1 OR decision IS NULL)
+            ORDER BY screened_at, id
+            LIMIT ?2
+            "#,
+        )
+        .bind(include_reviewed)
+        .bind(limit as i64)
+        .fetch_all(&self.db)
+        .await?;
+        rows.iter().map(result_from_row).collect()
+    }
+
+    /// Record an admin's decision on a flagged result
+    pub async fn review(&self, id: &str, decision: ReviewDecision, note: Option<&str>) -> Result<ScreeningResult, ScreeningError> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE screening_results SET decision = ?2, review_note = ?3, reviewed_at = ?4
+            WHERE id = ?1 AND outcome = 'flagged' AND decision IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(decision.as_str())
+        .bind(note)
+        .bind(self.clock.now())
+        .execute(&self.db)
+        .await?;
+
+        let row = sqlx::query("SELECT * FROM screening_results WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| ScreeningError::NotFound { id: id.to_string() })?;
+        if updated.rows_affected() == 0 {
+            return Err(ScreeningError::NotPending { id: id.to_string() });
+        }
+        Ok(result_from_row(&row)?)
+    }
+}
+
+fn result_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<ScreeningResult> {
+    let context: String = row.try_get("context")?;
+    let decision: Option<String> = row.try_get("decision")?;
+    Ok(ScreeningResult {
+        id: row.try_get("id")?,
+        context: ScreeningContext::parse(&context)
+            .ok_or_else(|| anyhow::anyhow!("unknown screening context '{}'", context))?,
+        subject_id: row.try_get("subject_id")?,
+        address: row.try_get("address")?,
+        provider: row.try_get("provider")?,
+        outcome: ScreeningOutcome::parse(&row.try_get::<String, _>("outcome")?),
+        identifications: serde_json::from_str(&row.try_get::<String, _>("identifications")?)?,
+        screened_at: row.try_get("screened_at")?,
+        decision: decision.as_deref().and_then(ReviewDecision::parse),
+        review_note: row.try_get("review_note")?,
+        reviewed_at: row.try_get("reviewed_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::run_migrations;
+    use vapor_core::services::clock::system_clock;
+
+    const LISTED: &str = "0x8589427373D6D84E98730D7795D8f6f8731FDA16";
+
+    async fn screener(action: ScreeningAction) -> Screener {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let config = ScreeningConfig {
+            provider: ScreeningProviderConfig::Static { addresses: vec![LISTED.to_lowercase()] },
+            action,
+        };
+        Screener::new(db, &config, system_clock())
+    }
+
+    #[tokio::test]
+    async fn test_blocked_hits_are_refused_and_recorded() {
+        let screener = screener(ScreeningAction::Block).await;
+        let clean = "0x1234567890123456789012345678901234567890";
+        screener.screen(ScreeningContext::Order, "order-1", &[clean]).await.unwrap();
+
+        let err = screener.screen(ScreeningContext::Claim, "filler-1", &[clean, LISTED]).await.unwrap_err();
+        assert!(matches!(&err, ScreeningError::Blocked { context: ScreeningContext::Claim, address, .. } if address == LISTED));
+
+        let outcomes: Vec<(String, String)> = sqlx::query_as("SELECT address, outcome FROM screening_results ORDER BY rowid")
+            .fetch_all(&screener.db)
+            .await
+            .unwrap();
+        assert_eq!(outcomes, vec![
+            (clean.to_string(), "clear".to_string()),
+            (clean.to_string(), "clear".to_string()),
+            (LISTED.to_lowercase(), "blocked".to_string()),
+        ]);
+        // Blocked hits need no review
+        assert!(screener.review_queue(true, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_flagged_hits_wait_for_review() {
+        let screener = screener(ScreeningAction::Flag).await;
+        screener.screen(ScreeningContext::Lock, "order-1", &[LISTED, &LISTED.to_lowercase()]).await.unwrap();
+
+        let queue = screener.review_queue(false, 10).await.unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!((queue[0].context, queue[0].outcome), (ScreeningContext::Lock, ScreeningOutcome::Flagged));
+        assert_eq!(queue[0].identifications[0].category, "sanctions");
+
+        let reviewed = screener.review(&queue[0].id, ReviewDecision::Cleared, Some("different entity")).await.unwrap();
+        assert_eq!(reviewed.decision, Some(ReviewDecision::Cleared));
+        assert!(screener.review_queue(false, 10).await.unwrap().is_empty());
+        assert_eq!(screener.review_queue(true, 10).await.unwrap().len(), 1);
+        assert!(matches!(
+            screener.review(&queue[0].id, ReviewDecision::Confirmed, None).await,
+            Err(ScreeningError::NotPending { .. })
+        ));
+        assert!(matches!(screener.review("missing", ReviewDecision::Confirmed, None).await, Err(ScreeningError::NotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_provider_refuses_even_when_flagging() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let config = ScreeningConfig {
+            provider: ScreeningProviderConfig::Http { url: "http://127.0.0.1:1".to_string(), api_key: "key".to_string() },
+            action: ScreeningAction::Flag,
+        };
+        let screener = Screener::new(db, &config, system_clock());
+
+        let err = screener.screen(ScreeningContext::Order, "order-1", &[LISTED]).await.unwrap_err();
+        assert!(matches!(err, ScreeningError::Unavailable { .. }));
+    }
+}
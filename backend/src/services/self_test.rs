@@ -0,0 +1,303 @@
+use anyhow::{anyhow, ensure, Result};
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+use ulid::Ulid;
+
+use crate::config::ArchiveConfig;
+use crate::database::{self, helpers};
+use vapor_core::proof_format::{parse_hash32, process_raw_proof, process_sorted_proof, FormattedProof, ProofFormat};
+use crate::merkle::MerkleTreeManager;
+use crate::models::{CreateOrderRequest, Order, OrderType};
+use crate::services::archival::ArchiveService;
+use crate::services::batch_processor::BatchProcessor;
+use crate::services::batch_prover::BatchProver;
+use vapor_core::services::blob_store::MemoryBlobStore;
+use crate::services::mvp_prover::MvpProverConfig;
+use crate::services::proof_cache::{build_account_proof, build_order_proofs};
+
+/// Pipeline stages, in the order they run
+const STAGES: [&str; 7] = ["database", "accounts", "orders", "batch", "mock_proof", "snapshot", "merkle_proofs"];
+
+const ALICE: &str = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+const BOB: &str = "0x3c44cdddb6a900fa2b585dd299e03d12fa4293bc";
+const TOKEN_ID: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StageStatus {
+    Passed,
+    Failed,
+    /// Not run because an earlier stage failed
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StageReport {
+    pub stage: &'static str,
+    pub status: StageStatus,
+    pub duration_ms: u64,
+    pub detail: String,
+}
+
+/// Outcome of a self-test run, one entry per stage
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SelfTestReport {
+    pub stages: Vec<StageReport>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.stages.iter().all(|stage| stage.status == StageStatus::Passed)
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Vapor self-test")?;
+        for stage in &self.stages {
+            let status = match stage.status {
+                StageStatus::Passed => "PASS",
+                StageStatus::Failed => "FAIL",
+                StageStatus::Skipped => "SKIP",
+            };
+            writeln!(f, "  [{}] {:<14} {:>6} ms  {}", status, stage.stage, stage.duration_ms, stage.detail)?;
+        }
+        match self.stages.iter().find(|stage| stage.status == StageStatus::Failed) {
+            Some(failed) => write!(f, "Self-test FAILED at stage {}", failed.stage),
+            None => write!(f, "Self-test passed"),
+        }
+    }
+}
+
+/// Tracks the running stage so a failure is reported against it
+struct Run {
+    report: SelfTestReport,
+    stage: &'static str,
+    started: Instant,
+}
+
+impl Run {
+    fn start(&mut self, stage: &'static str) {
+        self.stage = stage;
+        self.started = Instant::now();
+    }
+
+    fn finish(&mut self, status: StageStatus, detail: String) {
+        self.report.stages.push(StageReport {
+            stage: self.stage,
+            status,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            detail,
+        });
+    }
+
+    fn pass(&mut self, detail: impl Into<String>) {
+        self.finish(StageStatus::Passed, detail.into());
+    }
+}
+
+/// Run the whole pipeline against a temporary database: accounts, one order of each type,
+/// a batch, a mock proof, snapshot archival and Merkle proof verification
+///
+/// Nothing outside the temporary database is touched, so this is safe to run on a deploy
+/// target as a smoke test.
+pub async fn run() -> SelfTestReport {
+    let path = std::env::temp_dir().join(format!("vapor-self-test-{}.db", Ulid::new()));
+    let report = run_with_database(&format!("sqlite://{}?mode=rwc", path.display())).await;
+    let _ = std::fs::remove_file(&path);
+    report
+}
+
+async fn run_with_database(database_url: &str) -> SelfTestReport {
+    let mut run = Run {
+        report: SelfTestReport::default(),
+        stage: STAGES[0],
+        started: Instant::now(),
+    };
+
+    if let Err(e) = pipeline(&mut run, database_url).await {
+        run.finish(StageStatus::Failed, format!("{:#}", e));
+        let reached = run.report.stages.len();
+        for stage in &STAGES[reached..] {
+            run.report.stages.push(StageReport {
+                stage,
+                status: StageStatus::Skipped,
+                duration_ms: 0,
+                detail: String::new(),
+            });
+        }
+    }
+
+    run.report
+}
+
+async fn pipeline(run: &mut Run, database_url: &str) -> Result<()> {
+    run.start("database");
+    let db = database::init_db(database_url).await?;
+    database::run_migrations(&db).await?;
+    run.pass("temporary database created and migrated");
+
+    run.start("accounts");
+    let mut processor = BatchProcessor::new();
+    let mut prover = BatchProver::new(processor.proving_queue.clone());
+    prover.update_prover_config(MvpProverConfig {
+        generation_delay_ms: 1,
+        simulate_failures: false,
+        failure_rate: 0.0,
+        scenarios: Vec::new(),
+    });
+    processor.init_account(ALICE.to_string(), TOKEN_ID, "1000".to_string())?;
+    run.pass(format!("initialized {} with 1000 of token {}", ALICE, TOKEN_ID));
+
+    run.start("orders");
+    let orders = vec![
+        sample_order(OrderType::BridgeIn, None, Some(ALICE), "500"),
+        sample_order(OrderType::Transfer, Some(ALICE), Some(BOB), "300"),
+        sample_order(OrderType::BridgeOut, Some(BOB), Some(BOB), "100"),
+    ];
+    for order in &orders {
+        helpers::insert_order(&db, order).await?;
+        let stored = helpers::get_order_by_id(&db, &order.id).await?
+            .ok_or_else(|| anyhow!("order {} missing after insert", order.id))?;
+        ensure!(
+            stored.order_type == order.order_type && stored.amount == order.amount,
+            "order {} read back differently than written",
+            order.id
+        );
+    }
+    run.pass(format!("{} orders stored and read back, one of each type", orders.len()));
+
+    run.start("batch");
+    let batch_id = processor.start_batch()?;
+    for order in &orders {
+        processor.add_order_to_batch(order.clone())?;
+    }
+    let result = processor.finalize_batch()?;
+    for (address, expected) in [(ALICE, "1200"), (BOB, "200")] {
+        let balance = processor.accounts.get(address).and_then(|account| account.get_balance(TOKEN_ID));
+        ensure!(balance == Some(expected), "{} holds {:?} after the batch, expected {}", address, balance, expected);
+    }
+    run.pass(format!("batch {} finalized with {} orders, state root 0x{}", batch_id, result.orders_count, result.new_state_root));
+
+    run.start("mock_proof");
+    let proof_result = prover.prove_through(batch_id).await?
+        .pop()
+        .ok_or_else(|| anyhow!("batch {} was not queued for proving", batch_id))?
+        .result;
+    let proof = match proof_result.proof {
+        Some(proof) if proof_result.success => proof,
+        _ => return Err(anyhow!(proof_result.error_message.unwrap_or_else(|| "prover returned no proof".to_string()))),
+    };
+    ensure!(
+        proof.batch_id == batch_id
+            && proof.prev_state_root == result.prev_state_root
+            && proof.new_state_root == result.new_state_root
+            && proof.new_orders_root == result.new_orders_root
+            && proof.orders_count == orders.len()
+            && !proof.proof_data.is_empty(),
+        "mock proof does not commit to batch {}",
+        batch_id
+    );
+    run.pass(format!("{} byte proof in {} ms", proof.proof_data.len(), proof_result.generation_time_ms));
+
+    run.start("snapshot");
+    let snapshot = processor.take_snapshot()
+        .ok_or_else(|| anyhow!("no snapshot kept for batch {}", batch_id))?;
+    let archive = ArchiveService::new(db.clone(), Arc::new(MemoryBlobStore::default()), &ArchiveConfig { retain_batches: 1 });
+    archive.store_snapshot(&snapshot).await?;
+    let snapshot = archive.load_snapshot(batch_id).await?
+        .ok_or_else(|| anyhow!("snapshot of batch {} missing after store", batch_id))?;
+    ensure!(snapshot.state_root == result.new_state_root, "stored snapshot has a different state root");
+    run.pass(format!("snapshot of {} accounts and {} orders stored and reloaded", snapshot.accounts.len(), snapshot.orders.len()));
+
+    // Proofs are built from the reloaded snapshot, as historical proofs are
+    run.start("merkle_proofs");
+    let indices: Vec<usize> = (0..snapshot.orders.len()).collect();
+    let mut verified = 0;
+    for format in [ProofFormat::Raw, ProofFormat::SortedPairs] {
+        for proof in build_order_proofs(&snapshot.orders, batch_id, &indices, format)? {
+            verify(&proof)?;
+            verified += 1;
+        }
+    }
+    let raw_root = &build_order_proofs(&snapshot.orders, batch_id, &[0], ProofFormat::Raw)?[0].root;
+    ensure!(parse_hash32(raw_root)? == parse_hash32(&result.new_orders_root)?, "order proofs do not prove against the batch's orders root");
+
+    let mut manager = MerkleTreeManager::new();
+    let state_root = manager.build_state_tree(&snapshot.accounts)?;
+    ensure!(state_root == result.new_state_root, "rebuilt state root 0x{} differs from the batch's", state_root);
+    for account in &snapshot.accounts {
+        let proof = build_account_proof(&mut manager, &account.address, ProofFormat::Raw)?;
+        verify(&proof)?;
+        ensure!(parse_hash32(&proof.root)? == parse_hash32(&state_root)?, "account proof for {} has a stale root", account.address);
+        verified += 1;
+    }
+    run.pass(format!("{} order and account proofs verified", verified));
+
+    db.close().await;
+    Ok(())
+}
+
+fn sample_order(order_type: OrderType, from: Option<&str>, to: Option<&str>, amount: &str) -> Order {
+    Order::new(CreateOrderRequest {
+        order_type,
+        from_address: from.map(str::to_string),
+        to_address: to.map(str::to_string),
+        token_id: TOKEN_ID,
+        amount: amount.to_string(),
+        bank_account: None,
+        bank_service: None,
+        banking_hash: None,
+        permit: None,
+        priority_fee: None,
+        confirm_duplicate: false,
+        quote_id: None,
+    })
+}
+
+/// Fold a proof up to its root
+fn verify(proof: &FormattedProof) -> Result<()> {
+    let leaf = parse_hash32(&proof.leaf_hash)?;
+    let siblings = proof.proof.iter()
+        .map(|sibling| parse_hash32(sibling))
+        .collect::<Result<Vec<_>, _>>()?;
+    let root = match proof.format {
+        ProofFormat::SortedPairs => process_sorted_proof(leaf, &siblings),
+        _ => {
+            let path_bits = proof.path_bits.as_deref().ok_or_else(|| anyhow!("raw proof without path bits"))?;
+            process_raw_proof(leaf, &siblings, path_bits)?
+        }
+    };
+
+    ensure!(root == parse_hash32(&proof.root)?, "proof of leaf {} does not fold to root {}", proof.leaf_hash, proof.root);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_self_test_passes() {
+        let report = run().await;
+
+        assert!(report.passed(), "{}", report);
+        let stages: Vec<&str> = report.stages.iter().map(|stage| stage.stage).collect();
+        assert_eq!(stages, STAGES);
+        assert!(report.to_string().ends_with("Self-test passed"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_stage_skips_the_rest() {
+        let report = run_with_database("sqlite:///nonexistent-dir/vapor-self-test.db").await;
+
+        assert!(!report.passed());
+        assert_eq!(report.stages.len(), STAGES.len());
+        assert_eq!(report.stages[0].status, StageStatus::Failed);
+        assert!(!report.stages[0].detail.is_empty());
+        assert!(report.stages[1..].iter().all(|stage| stage.status == StageStatus::Skipped));
+        assert!(report.to_string().ends_with("Self-test FAILED at stage database"));
+    }
+}
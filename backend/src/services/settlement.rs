@@ -0,0 +1,248 @@
+use anyhow::Result;
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tracing::{info, instrument};
+
+use crate::database::helpers;
+use crate::models::{Order, OrderStatus, OrderType, RemainderAction};
+use crate::services::discovery::{is_discovery_eligible, publish_discovered};
+use crate::services::event_bus::EventBus;
+
+/// Why an order could not be partially settled
+#[derive(Debug, thiserror::Error)]
+pub enum SettlementError {
+    #[error("order {0} not found")]
+    NotFound(String),
+    #[error("order {order_id} is {status:?}, only Locked or MarkPaid orders can be settled")]
+    InvalidStatus { order_id: String, status: OrderStatus },
+    #[error("order {0} has no unfilled remainder to split off")]
+    NothingToSplit(String),
+    #[error("{order_type:?} order {order_id} cannot go back to discovery")]
+    CannotRediscover { order_id: String, order_type: OrderType },
+    #[error("order {0} changed status while it was being settled")]
+    Conflict(String),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// The two orders a partially filled order is split into
+#[derive(Debug, Clone, Serialize)]
+pub struct PartialSettlement {
+    pub parent_order_id: String,
+    /// The locked portion, settled with the filler
+    pub settled: Order,
+    /// The unfilled portion, back in discovery or refunded
+    pub remainder: Order,
+    pub remainder_action: RemainderAction,
+}
+
+/// Split an order amount into its settled (locked) and remaining parts
+/// Returns None unless the lock covers some but not all of the order
+pub fn split_amounts(amount: &str, locked_amount: Option<&str>) -> Option<(u64, u64)> {
+    let amount: u64 = amount.parse().ok()?;
+    let locked: u64 = locked_amount?.parse().ok()?;
+    (locked > 0 && locked < amount).then(|| (locked, amount - locked))
+}
+
+/// Settle the locked portion of an order and split the rest off as a separate order
+///
+/// The parent order is marked Settled and linked to two child orders: one for the locked
+/// amount (which takes the parent's place in its batch's Merkle leaves) and one for the remainder.
+#[instrument(skip_all, fields(order_id = %order_id, action = ?action))]
+pub async fn settle_partial(
+    db: &SqlitePool,
+    event_bus: &EventBus,
+    order_id: &str,
+    action: RemainderAction,
+) -> Result<PartialSettlement, SettlementError> {
+    let parent = helpers::get_order_by_id(db, order_id)
+        .await?
+        .ok_or_else(|| SettlementError::NotFound(order_id.to_string()))?;
+
+    if !matches!(parent.status, OrderStatus::Locked | OrderStatus::MarkPaid) {
+        return Err(SettlementError::InvalidStatus { order_id: parent.id, status: parent.status });
+    }
+
+    let (settled_amount, remainder_amount) = split_amounts(&parent.amount, parent.locked_amount.as_deref())
+        .ok_or_else(|| SettlementError::NothingToSplit(parent.id.clone()))?;
+
+    if action == RemainderAction::Rediscover && !is_discovery_eligible(parent.order_type) {
+        return Err(SettlementError::CannotRediscover { order_id: parent.id, order_type: parent.order_type });
+    }
+
+    // Guard on status so a concurrent transition is not overwritten
+    let now = Utc::now();
+    let result = sqlx::query("UPDATE orders SET status = $1, updated_at = $2 WHERE id = $3 AND status = $4")
+        .bind(OrderStatus::Settled as i32)
+        .bind(now)
+        .bind(&parent.id)
+        .bind(parent.status as i32)
+        .execute(db)
+        .await
+        .map_err(anyhow::Error::from)?;
+
+    if result.rows_affected() == 0 {
+        return Err(SettlementError::Conflict(parent.id));
+    }
+
+    let settled = Order {
+        id: format!("{}-settled", parent.id),
+        amount: settled_amount.to_string(),
+        locked_amount: Some(settled_amount.to_string()),
+        status: OrderStatus::Settled,
+        created_at: now,
+        updated_at: now,
+        ..parent.clone()
+    };
+
+    let remainder_status = match action {
+        RemainderAction::Rediscover => OrderStatus::Discovery,
+        RemainderAction::Refund => OrderStatus::Failed,
+    };
+    let remainder = Order {
+        id: format!("{}-remainder", parent.id),
+        amount: remainder_amount.to_string(),
+        banking_hash: None,
+        filler_id: None,
+        locked_amount: None,
+        status: remainder_status,
+        batch_id: None,
+        created_at: now,
+        updated_at: now,
+        ..parent.clone()
+    };
+
+    helpers::insert_child_order(db, &settled, &parent.id).await?;
+    helpers::insert_child_order(db, &remainder, &parent.id).await?;
+
+    if action == RemainderAction::Refund {
+        sqlx::query("UPDATE orders SET failure_reason = 'refunded' WHERE id = ?")
+            .bind(&remainder.id)
+            .execute(db)
+            .await
+            .map_err(anyhow::Error::from)?;
+    }
+
+    helpers::record_status_transition(db, &parent.id, parent.status, OrderStatus::Settled, Some("partial_settlement")).await?;
+    let remainder_reason = match action {
+        RemainderAction::Rediscover => "partial_remainder",
+        RemainderAction::Refund => "refunded",
+    };
+    helpers::record_status_transition(db, &remainder.id, parent.status, remainder_status, Some(remainder_reason)).await?;
+
+    if action == RemainderAction::Rediscover {
+        publish_discovered(db, event_bus, &remainder.id).await?;
+    }
+
+    info!(
+        "Partially settled order {}: {} settled, {} {:?}",
+        parent.id, settled_amount, remainder_amount, action
+    );
+
+    Ok(PartialSettlement {
+        parent_order_id: parent.id,
+        settled,
+        remainder,
+        remainder_action: action,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup_with_order(order_type: OrderType, status: OrderStatus, locked_amount: Option<&str>) -> SqlitePool {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+
+        let order = Order {
+            id: "parent".to_string(),
+            order_type,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: Some("0xreceipt".to_string()),
+            filler_id: Some("filler_1".to_string()),
+            locked_amount: locked_amount.map(str::to_string),
+            status,
+            batch_id: Some(3),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        helpers::insert_order(&db, &order).await.unwrap();
+        db
+    }
+
+    #[test]
+    fn test_split_amounts() {
+        assert_eq!(split_amounts("1000", Some("500")), Some((500, 500)));
+        assert_eq!(split_amounts("1000", Some("1")), Some((1, 999)));
+        assert_eq!(split_amounts("1000", Some("1000")), None);
+        assert_eq!(split_amounts("1000", Some("0")), None);
+        assert_eq!(split_amounts("1000", None), None);
+        assert_eq!(split_amounts("abc", Some("500")), None);
+    }
+
+    #[tokio::test]
+    async fn test_partial_settlement_rediscovers_remainder() {
+        let db = setup_with_order(OrderType::BridgeIn, OrderStatus::MarkPaid, Some("400")).await;
+        let bus = EventBus::default();
+
+        let split = settle_partial(&db, &bus, "parent", RemainderAction::Rediscover).await.unwrap();
+        assert_eq!(split.settled.amount, "400");
+        assert_eq!(split.settled.status, OrderStatus::Settled);
+        assert_eq!(split.settled.batch_id, Some(3));
+        assert_eq!(split.remainder.amount, "600");
+        assert_eq!(split.remainder.status, OrderStatus::Discovery);
+        assert_eq!(split.remainder.filler_id, None);
+
+        let parent = helpers::get_order_by_id(&db, "parent").await.unwrap().unwrap();
+        assert_eq!(parent.status, OrderStatus::Settled);
+        assert_eq!(
+            helpers::get_child_order_ids(&db, "parent").await.unwrap(),
+            vec!["parent-remainder".to_string(), "parent-settled".to_string()]
+        );
+        assert_eq!(helpers::get_parent_order_id(&db, "parent-settled").await.unwrap(), Some("parent".to_string()));
+
+        // The settled child replaces the parent in its batch's leaves
+        let batch_orders = helpers::get_orders_by_batch(&db, 3).await.unwrap();
+        assert_eq!(batch_orders.len(), 1);
+        assert_eq!(batch_orders[0].id, "parent-settled");
+
+        // Settling again is rejected now that the parent is Settled
+        let err = settle_partial(&db, &bus, "parent", RemainderAction::Rediscover).await.unwrap_err();
+        assert!(matches!(err, SettlementError::InvalidStatus { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_partial_settlement_refund() {
+        let db = setup_with_order(OrderType::BridgeOut, OrderStatus::Locked, Some("250")).await;
+        let bus = EventBus::default();
+
+        // Only discovery-eligible orders can be offered to fillers again
+        let err = settle_partial(&db, &bus, "parent", RemainderAction::Rediscover).await.unwrap_err();
+        assert!(matches!(err, SettlementError::CannotRediscover { .. }));
+
+        let split = settle_partial(&db, &bus, "parent", RemainderAction::Refund).await.unwrap();
+        assert_eq!(split.remainder.amount, "750");
+        assert_eq!(split.remainder.status, OrderStatus::Failed);
+
+        let history = helpers::get_order_history(&db, "parent-remainder").await.unwrap();
+        assert_eq!(history[0].reason.as_deref(), Some("refunded"));
+    }
+
+    #[tokio::test]
+    async fn test_fully_locked_order_has_nothing_to_split() {
+        let db = setup_with_order(OrderType::BridgeIn, OrderStatus::MarkPaid, Some("1000")).await;
+
+        let err = settle_partial(&db, &EventBus::default(), "parent", RemainderAction::Rediscover).await.unwrap_err();
+        assert!(matches!(err, SettlementError::NothingToSplit(_)));
+
+        let err = settle_partial(&db, &EventBus::default(), "missing", RemainderAction::Rediscover).await.unwrap_err();
+        assert!(matches!(err, SettlementError::NotFound(_)));
+    }
+}
@@ -0,0 +1,594 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{info, instrument, warn};
+
+use crate::database::helpers;
+use crate::models::{filler_settlement_address, Order, OrderStatus, OrderType};
+use crate::services::batch_processor::BatchProcessor;
+use vapor_core::services::clock::SharedClock;
+use crate::services::discovery::publish_discovered;
+use crate::services::event_bus::EventBus;
+use crate::services::matching_engine::MatchingEngine;
+
+/// The cross-service steps a locked order goes through, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaStep {
+    /// A filler locked the order
+    Matched,
+    /// The filler's payment was recorded; the escrow transfer may exist
+    Paid,
+    /// The escrow transfer was included in a finalized batch
+    Batched,
+    /// The filler claimed the settled funds
+    Claimed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaState {
+    Running,
+    Completed,
+    Compensating,
+    Compensated,
+    /// A compensation failed; aborting again retries the ones not yet done
+    CompensationFailed,
+}
+
+/// Undo actions, run newest step first when a saga fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compensation {
+    /// Transfer the escrowed amount back from the filler's settlement account
+    RefundEscrow,
+    /// Put the order back into discovery and restore the filler's capacity
+    ReleaseLock,
+}
+
+macro_rules! text_enum {
+    ($ty:ty { $($variant:ident => $text:literal),+ $(,)? }) => {
+        impl $ty {
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $text),+
+                }
+            }
+
+            pub fn parse(text: &str) -> Option<Self> {
+                match text {
+                    $($text => Some(Self::$variant),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+text_enum!(SagaStep { Matched => "matched", Paid => "paid", Batched => "batched", Claimed => "claimed" });
+text_enum!(SagaState {
+    Running => "running",
+    Completed => "completed",
+    Compensating => "compensating",
+    Compensated => "compensated",
+    CompensationFailed => "compensation_failed",
+});
+
+impl SagaState {
+    /// Sagas an operator may still need to act on
+    pub fn in_flight(&self) -> bool {
+        matches!(self, SagaState::Running | SagaState::Compensating | SagaState::CompensationFailed)
+    }
+}
+
+impl Compensation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Compensation::RefundEscrow => "refund_escrow",
+            Compensation::ReleaseLock => "release_lock",
+        }
+    }
+}
+
+/// Persisted progress of one order's settlement
+#[derive(Debug, Clone, Serialize)]
+pub struct SagaRecord {
+    pub order_id: String,
+    pub filler_id: String,
+    pub locked_amount: String,
+    pub step: SagaStep,
+    pub state: SagaState,
+    /// Transfer order moving the locked amount to the filler's settlement account
+    pub escrow_order_id: Option<String>,
+    pub batch_id: Option<u64>,
+    /// Transfer order reversing the escrow, once refunded
+    pub refund_order_id: Option<String>,
+    /// Compensations that have completed
+    pub compensations: Vec<Compensation>,
+    pub failure_reason: Option<String>,
+    pub last_error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SagaRecord {
+    /// Compensations this saga needs, in the order they run
+    fn compensation_plan(&self) -> Vec<Compensation> {
+        let mut plan = Vec::new();
+        if self.escrow_order_id.is_some() {
+            plan.push(Compensation::RefundEscrow);
+        }
+        plan.push(Compensation::ReleaseLock);
+        plan
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SagaError {
+    #[error("no settlement saga for order {0}")]
+    NotFound(String),
+    #[error("settlement saga for order {order_id} is already {}", state.as_str())]
+    AlreadyFinished { order_id: String, state: SagaState },
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for SagaError {
+    fn from(e: sqlx::Error) -> Self {
+        SagaError::Other(e.into())
+    }
+}
+
+/// Drives each locked order through match → payment → batch → claim
+///
+/// Every step is persisted in `settlement_sagas`, so a failure at any point (a lock that
+/// expired, an escrow the batch refused, an operator abort) can be undone by running the
+/// compensations for the steps already taken. Completed compensations are recorded, so a
+/// saga whose compensation failed can be aborted again without repeating them.
+#[derive(Clone)]
+pub struct SettlementSaga {
+    db: SqlitePool,
+    matching_engine: Arc<Mutex<MatchingEngine>>,
+    batch_processor: Arc<Mutex<BatchProcessor>>,
+    event_bus: EventBus,
+    clock: SharedClock,
+}
+
+impl SettlementSaga {
+    pub fn new(
+        db: SqlitePool,
+        matching_engine: Arc<Mutex<MatchingEngine>>,
+        batch_processor: Arc<Mutex<BatchProcessor>>,
+        event_bus: EventBus,
+        clock: SharedClock,
+    ) -> Self {
+        Self { db, matching_engine, batch_processor, event_bus, clock }
+    }
+
+    /// Start the saga for an order a filler has just locked
+    ///
+    /// Relocking an order whose earlier saga was compensated starts it over.
+    pub async fn begin(&self, order: &Order) -> Result<(), SagaError> {
+        let filler_id = order.filler_id.as_deref()
+            .ok_or_else(|| anyhow!("order {} has no filler", order.id))?;
+        let now = self.clock.now();
+        sqlx::query(
+            r#"
+            INSERT INTO settlement_sagas (order_id, filler_id, locked_amount, step, state, started_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+            ON CONFLICT(order_id) DO UPDATE SET
+                filler_id = excluded.filler_id, locked_amount = excluded.locked_amount,
+                step = excluded.step, state = excluded.state, escrow_order_id = NULL, batch_id = NULL,
+                refund_order_id = NULL, compensations = '[]', failure_reason = NULL, last_error = NULL,
+                started_at = excluded.started_at, updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&order.id)
+        .bind(filler_id)
+        .bind(order.locked_amount.as_deref().unwrap_or(&order.amount))
+        .bind(SagaStep::Matched.as_str())
+        .bind(SagaState::Running.as_str())
+        .bind(now)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Record the filler's payment, and the escrow transfer once it has been created
+    pub async fn record_payment(&self, order_id: &str, escrow_order_id: Option<&str>) -> Result<(), SagaError> {
+        let result = sqlx::query(
+            "UPDATE settlement_sagas SET step = ?1, escrow_order_id = COALESCE(?2, escrow_order_id), updated_at = ?3 WHERE order_id = ?4 AND state = ?5",
+        )
+        .bind(SagaStep::Paid.as_str())
+        .bind(escrow_order_id)
+        .bind(self.clock.now())
+        .bind(order_id)
+        .bind(SagaState::Running.as_str())
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(SagaError::NotFound(order_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Advance the paid sagas whose escrow transfers made it into a finalized batch
+    pub async fn record_batched(&self, batch_id: u64, order_ids: &[String]) -> Result<u64, SagaError> {
+        let mut advanced = 0;
+        for order_id in order_ids {
+            let result = sqlx::query(
+                "UPDATE settlement_sagas SET step = ?1, batch_id = ?2, updated_at = ?3 WHERE escrow_order_id = ?4 AND step = ?5 AND state = ?6",
+            )
+            .bind(SagaStep::Batched.as_str())
+            .bind(batch_id as i64)
+            .bind(self.clock.now())
+            .bind(order_id)
+            .bind(SagaStep::Paid.as_str())
+            .bind(SagaState::Running.as_str())
+            .execute(&self.db)
+            .await?;
+            advanced += result.rows_affected();
+        }
+        Ok(advanced)
+    }
+
+    /// Complete the filler's batched sagas covered by a claim, oldest first
+    pub async fn record_claims(&self, filler_id: &str, claimed_amount: u64) -> Result<Vec<String>, SagaError> {
+        let rows = sqlx::query(
+            "SELECT order_id, locked_amount FROM settlement_sagas WHERE filler_id = ?1 AND step = ?2 AND state = ?3 ORDER BY started_at, order_id",
+        )
+        .bind(filler_id)
+        .bind(SagaStep::Batched.as_str())
+        .bind(SagaState::Running.as_str())
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut remaining = claimed_amount;
+        let mut completed = Vec::new();
+        for row in rows {
+            let amount: u64 = row.try_get::<String, _>("locked_amount")?.parse().unwrap_or(0);
+            if amount > remaining {
+                break;
+            }
+            remaining -= amount;
+
+            let order_id: String = row.try_get("order_id")?;
+            sqlx::query("UPDATE settlement_sagas SET step = ?1, state = ?2, updated_at = ?3 WHERE order_id = ?4")
+                .bind(SagaStep::Claimed.as_str())
+                .bind(SagaState::Completed.as_str())
+                .bind(self.clock.now())
+                .bind(&order_id)
+                .execute(&self.db)
+                .await?;
+            completed.push(order_id);
+        }
+        Ok(completed)
+    }
+
+    /// Fail a saga and run its outstanding compensations, newest step first
+    #[instrument(skip_all, fields(order_id = %order_id))]
+    pub async fn fail(&self, order_id: &str, reason: &str) -> Result<SagaRecord, SagaError> {
+        let saga = self.get(order_id).await?;
+        if !saga.state.in_flight() {
+            return Err(SagaError::AlreadyFinished { order_id: saga.order_id, state: saga.state });
+        }
+
+        sqlx::query("UPDATE settlement_sagas SET state = ?1, failure_reason = COALESCE(failure_reason, ?2), updated_at = ?3 WHERE order_id = ?4")
+            .bind(SagaState::Compensating.as_str())
+            .bind(reason)
+            .bind(self.clock.now())
+            .bind(order_id)
+            .execute(&self.db)
+            .await?;
+
+        let mut done = saga.compensations.clone();
+        for compensation in saga.compensation_plan() {
+            if done.contains(&compensation) {
+                continue;
+            }
+            let outcome = match compensation {
+                Compensation::RefundEscrow => self.refund_escrow(&saga).await,
+                Compensation::ReleaseLock => self.release_lock(&saga).await,
+            };
+            if let Err(e) = outcome {
+                warn!("Compensation {:?} failed for order {}: {:#}", compensation, order_id, e);
+                sqlx::query("UPDATE settlement_sagas SET state = ?1, last_error = ?2, updated_at = ?3 WHERE order_id = ?4")
+                    .bind(SagaState::CompensationFailed.as_str())
+                    .bind(format!("{}: {:#}", compensation.as_str(), e))
+                    .bind(self.clock.now())
+                    .bind(order_id)
+                    .execute(&self.db)
+                    .await?;
+                return self.get(order_id).await;
+            }
+
+            done.push(compensation);
+            sqlx::query("UPDATE settlement_sagas SET compensations = ?1, updated_at = ?2 WHERE order_id = ?3")
+                .bind(serde_json::to_string(&done).map_err(anyhow::Error::from)?)
+                .bind(self.clock.now())
+                .bind(order_id)
+                .execute(&self.db)
+                .await?;
+        }
+
+        sqlx::query("UPDATE settlement_sagas SET state = ?1, last_error = NULL, updated_at = ?2 WHERE order_id = ?3")
+            .bind(SagaState::Compensated.as_str())
+            .bind(self.clock.now())
+            .bind(order_id)
+            .execute(&self.db)
+            .await?;
+        info!("Settlement saga for order {} compensated: {}", order_id, reason);
+        self.get(order_id).await
+    }
+
+    /// Reverse the escrow transfer with one from the filler's settlement account
+    async fn refund_escrow(&self, saga: &SagaRecord) -> anyhow::Result<()> {
+        let escrow_id = saga.escrow_order_id.as_deref().unwrap_or_default();
+        let escrow = helpers::get_order_by_id(&self.db, escrow_id)
+            .await?
+            .ok_or_else(|| anyhow!("escrow order {} not found", escrow_id))?;
+
+        // An escrow that never left this node has nothing to reverse
+        if escrow.status == OrderStatus::Failed {
+            return Ok(());
+        }
+
+        let now = self.clock.now();
+        let refund = Order {
+            id: format!("{}-refund", escrow.id),
+            order_type: OrderType::Transfer,
+            status: OrderStatus::Pending,
+            from_address: Some(filler_settlement_address(&saga.filler_id)),
+            to_address: escrow.from_address.clone(),
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at: now,
+            updated_at: now,
+            ..escrow
+        };
+        if helpers::get_order_by_id(&self.db, &refund.id).await?.is_none() {
+            helpers::insert_order(&self.db, &refund).await?;
+        }
+
+        let mut processor = self.batch_processor.lock().await;
+        if processor.get_current_batch().is_none() {
+            processor.start_batch()?;
+        }
+        processor.add_order_to_batch(refund.clone())?;
+        drop(processor);
+
+        sqlx::query("UPDATE settlement_sagas SET refund_order_id = ?1 WHERE order_id = ?2")
+            .bind(&refund.id)
+            .bind(&saga.order_id)
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Return the order to discovery, unless something else already moved it on
+    async fn release_lock(&self, saga: &SagaRecord) -> anyhow::Result<()> {
+        let Some(order) = helpers::get_order_by_id(&self.db, &saga.order_id).await? else {
+            return Ok(());
+        };
+
+        // The SLA sweeper (or another transition) already took the order out of the lock
+        if !matches!(order.status, OrderStatus::Locked | OrderStatus::MarkPaid) {
+            return Ok(());
+        }
+
+        let result = sqlx::query(
+            "UPDATE orders SET status = ?1, filler_id = NULL, locked_amount = NULL, updated_at = ?2 WHERE id = ?3 AND status = ?4",
+        )
+        .bind(OrderStatus::Discovery as i32)
+        .bind(self.clock.now())
+        .bind(&order.id)
+        .bind(order.status as i32)
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(());
+        }
+
+        helpers::record_status_transition(&self.db, &order.id, order.status, OrderStatus::Discovery, Some("saga_compensation")).await?;
+        let amount = saga.locked_amount.parse().unwrap_or(0);
+        let mut engine = self.matching_engine.lock().await;
+        engine.release_order(&order.id, &saga.filler_id, amount)?;
+        if order.order_type == OrderType::BridgeIn {
+            engine.requeue_order(Order { status: OrderStatus::Discovery, filler_id: None, locked_amount: None, ..order.clone() })?;
+        }
+        drop(engine);
+        publish_discovered(&self.db, &self.event_bus, &order.id).await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, order_id: &str) -> Result<SagaRecord, SagaError> {
+        let row = sqlx::query("SELECT * FROM settlement_sagas WHERE order_id = ?")
+            .bind(order_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| SagaError::NotFound(order_id.to_string()))?;
+        Ok(row_to_saga(&row)?)
+    }
+
+    /// Sagas in `state`, or every in-flight saga when no state is given, oldest first
+    pub async fn list(&self, state: Option<SagaState>) -> Result<Vec<SagaRecord>, SagaError> {
+        let rows = sqlx::query("SELECT * FROM settlement_sagas ORDER BY started_at, order_id")
+            .fetch_all(&self.db)
+            .await?;
+        let sagas = rows.iter()
+            .map(row_to_saga)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(sagas.into_iter()
+            .filter(|saga| state.map_or(saga.state.in_flight(), |state| saga.state == state))
+            .collect())
+    }
+}
+
+fn row_to_saga(row: &sqlx::sqlite::SqliteRow) -> anyhow::Result<SagaRecord> {
+    let step: String = row.try_get("step")?;
+    let state: String = row.try_get("state")?;
+    let compensations: String = row.try_get("compensations")?;
+    Ok(SagaRecord {
+        order_id: row.try_get("order_id")?,
+        filler_id: row.try_get("filler_id")?,
+        locked_amount: row.try_get("locked_amount")?,
+        step: SagaStep::parse(&step).ok_or_else(|| anyhow!("unknown saga step '{}'", step))?,
+        state: SagaState::parse(&state).ok_or_else(|| anyhow!("unknown saga state '{}'", state))?,
+        escrow_order_id: row.try_get("escrow_order_id")?,
+        batch_id: row.try_get::<Option<i64>, _>("batch_id")?.map(|id| id as u64),
+        refund_order_id: row.try_get("refund_order_id")?,
+        compensations: serde_json::from_str(&compensations)?,
+        failure_reason: row.try_get("failure_reason")?,
+        last_error: row.try_get("last_error")?,
+        started_at: row.try_get("started_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vapor_core::services::clock::system_clock;
+
+    const SELLER: &str = "0x1234567890123456789012345678901234567890";
+
+    async fn setup() -> (SettlementSaga, Order) {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+
+        let order = Order {
+            id: "order_1".to_string(),
+            order_type: OrderType::BridgeIn,
+            from_address: Some(SELLER.to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            filler_id: Some("filler_1".to_string()),
+            locked_amount: Some("1000".to_string()),
+            status: OrderStatus::Locked,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        helpers::insert_order(&db, &order).await.unwrap();
+
+        let mut processor = BatchProcessor::new();
+        processor.init_account(SELLER.to_string(), 1, "5000".to_string()).unwrap();
+        processor.start_batch().unwrap();
+
+        let saga = SettlementSaga::new(
+            db,
+            Arc::new(Mutex::new(MatchingEngine::new())),
+            Arc::new(Mutex::new(processor)),
+            EventBus::default(),
+            system_clock(),
+        );
+        saga.begin(&order).await.unwrap();
+        (saga, order)
+    }
+
+    /// Escrow the locked amount from the seller to the filler, as marking the order paid does
+    async fn escrow(saga: &SettlementSaga, order: &Order) -> Order {
+        let escrow = Order {
+            id: "escrow_1".to_string(),
+            order_type: OrderType::Transfer,
+            status: OrderStatus::Pending,
+            from_address: Some(SELLER.to_string()),
+            to_address: Some(filler_settlement_address("filler_1")),
+            bank_account: None,
+            bank_service: None,
+            filler_id: None,
+            locked_amount: None,
+            ..order.clone()
+        };
+        helpers::insert_order(&saga.db, &escrow).await.unwrap();
+        saga.batch_processor.lock().await.add_order_to_batch(escrow.clone()).unwrap();
+        sqlx::query("UPDATE orders SET status = ? WHERE id = ?")
+            .bind(OrderStatus::MarkPaid as i32)
+            .bind(&order.id)
+            .execute(&saga.db)
+            .await
+            .unwrap();
+        saga.record_payment(&order.id, Some(&escrow.id)).await.unwrap();
+        escrow
+    }
+
+    #[tokio::test]
+    async fn test_saga_completes_through_claim() {
+        let (saga, order) = setup().await;
+        let escrow = escrow(&saga, &order).await;
+        assert_eq!(saga.get(&order.id).await.unwrap().step, SagaStep::Paid);
+
+        assert_eq!(saga.record_batched(4, std::slice::from_ref(&escrow.id)).await.unwrap(), 1);
+        let batched = saga.get(&order.id).await.unwrap();
+        assert_eq!((batched.step, batched.batch_id), (SagaStep::Batched, Some(4)));
+
+        // A claim that does not cover the locked amount leaves the saga waiting
+        assert!(saga.record_claims("filler_1", 999).await.unwrap().is_empty());
+        assert_eq!(saga.record_claims("filler_1", 1000).await.unwrap(), vec![order.id.clone()]);
+
+        let completed = saga.get(&order.id).await.unwrap();
+        assert_eq!((completed.step, completed.state), (SagaStep::Claimed, SagaState::Completed));
+        assert!(saga.list(None).await.unwrap().is_empty());
+
+        let err = saga.fail(&order.id, "late_abort").await.unwrap_err();
+        assert!(matches!(err, SagaError::AlreadyFinished { state: SagaState::Completed, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_failure_after_payment_refunds_escrow_and_releases_lock() {
+        let (saga, order) = setup().await;
+        let escrow = escrow(&saga, &order).await;
+
+        let failed = saga.fail(&order.id, "payment_reversed").await.unwrap();
+        assert_eq!(failed.state, SagaState::Compensated);
+        assert_eq!(failed.compensations, vec![Compensation::RefundEscrow, Compensation::ReleaseLock]);
+        assert_eq!(failed.failure_reason.as_deref(), Some("payment_reversed"));
+
+        // The refund reverses the escrow in the same batch
+        let refund = helpers::get_order_by_id(&saga.db, failed.refund_order_id.as_deref().unwrap()).await.unwrap().unwrap();
+        assert_eq!(refund.from_address, escrow.to_address);
+        assert_eq!(refund.to_address.as_deref(), Some(SELLER));
+        assert_eq!(refund.amount, "1000");
+        let processor = saga.batch_processor.lock().await;
+        let batch_orders: Vec<_> = processor.get_current_batch().unwrap().orders.iter().map(|order| order.id.clone()).collect();
+        assert_eq!(batch_orders, vec![escrow.id.clone(), refund.id.clone()]);
+        drop(processor);
+
+        let released = helpers::get_order_by_id(&saga.db, &order.id).await.unwrap().unwrap();
+        assert_eq!((released.status, released.filler_id, released.locked_amount), (OrderStatus::Discovery, None, None));
+
+        // Back in the matching queue, in the priority lane
+        let engine = saga.matching_engine.lock().await;
+        assert!(engine.pending_orders.iter().any(|pending| pending.id == order.id));
+        assert!(engine.queue_entries[&order.id].boosted);
+    }
+
+    #[tokio::test]
+    async fn test_release_skips_orders_already_moved_on() {
+        let (saga, order) = setup().await;
+
+        // The SLA sweeper failed the order (and released the filler) before the saga heard of it
+        sqlx::query("UPDATE orders SET status = ? WHERE id = ?")
+            .bind(OrderStatus::Failed as i32)
+            .bind(&order.id)
+            .execute(&saga.db)
+            .await
+            .unwrap();
+
+        let failed = saga.fail(&order.id, "lock_expired").await.unwrap();
+        assert_eq!(failed.state, SagaState::Compensated);
+        assert_eq!(failed.refund_order_id, None);
+        let order = helpers::get_order_by_id(&saga.db, &order.id).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Failed);
+    }
+}
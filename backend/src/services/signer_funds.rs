@@ -0,0 +1,117 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use web3::types::{Address, U256};
+
+use vapor_chain::BlockchainClient;
+
+/// Days of recorded batch costs the daily spend is averaged over
+const SPEND_WINDOW_DAYS: i64 = 7;
+
+/// The submission signer's ETH against what its submissions cost
+#[derive(Debug, Clone, Serialize)]
+pub struct SignerFunds {
+    pub address: String,
+    pub balance_wei: String,
+    /// Gas limit at the submission gas price: the most one proof or claim submission can cost
+    pub submission_cost_wei: String,
+    /// Submissions the balance still covers
+    pub submissions_left: u64,
+    /// Average daily proof submission spend over the last 7 days, None without recorded costs
+    pub daily_spend_wei: Option<String>,
+    /// Days the balance lasts at the daily spend
+    pub runway_days: Option<f64>,
+    /// Covers fewer submissions than the configured minimum, or not even one
+    pub low: bool,
+    pub checked_at: DateTime<Utc>,
+}
+
+fn wei_as_f64(wei: U256) -> f64 {
+    wei.to_string().parse().unwrap_or(f64::MAX)
+}
+
+/// Size up a signer's balance against the cost of one submission and the recent daily spend
+pub fn assess(
+    address: Address,
+    balance: U256,
+    submission_cost: U256,
+    daily_spend: Option<U256>,
+    min_submissions: u64,
+    now: DateTime<Utc>,
+) -> SignerFunds {
+    let submissions_left = if submission_cost.is_zero() {
+        u64::MAX
+    } else {
+        (balance / submission_cost).min(U256::from(u64::MAX)).as_u64()
+    };
+    let runway_days = daily_spend
+        .filter(|spend| !spend.is_zero())
+        .map(|spend| (wei_as_f64(balance) / wei_as_f64(spend) * 100.0).floor() / 100.0);
+
+    SignerFunds {
+        address: format!("{:?}", address),
+        balance_wei: balance.to_string(),
+        submission_cost_wei: submission_cost.to_string(),
+        submissions_left,
+        daily_spend_wei: daily_spend.map(|spend| spend.to_string()),
+        runway_days,
+        low: balance < submission_cost || submissions_left < min_submissions,
+        checked_at: now,
+    }
+}
+
+/// Average daily gas spent on proof submissions over the last week, from `batch_costs`
+pub async fn daily_spend(db: &SqlitePool, now: DateTime<Utc>) -> Result<Option<U256>> {
+    let rows = sqlx::query("SELECT gas_cost_wei FROM batch_costs WHERE recorded_at >= ?")
+        .bind(now - Duration::days(SPEND_WINDOW_DAYS))
+        .fetch_all(db)
+        .await?;
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let mut total = U256::zero();
+    for row in &rows {
+        let cost: String = row.try_get("gas_cost_wei")?;
+        total = total.saturating_add(U256::from_dec_str(&cost).unwrap_or_default());
+    }
+    Ok(Some(total / U256::from(SPEND_WINDOW_DAYS)))
+}
+
+/// Read the chain client's signer balance and size it up; None without a signer
+pub async fn check(chain: &BlockchainClient, db: &SqlitePool, min_submissions: u64, now: DateTime<Utc>) -> Result<Option<SignerFunds>> {
+    let Some(signer) = &chain.signer else {
+        return Ok(None);
+    };
+    let address = signer.address();
+    let balance = chain.get_eth_balance(address).await?;
+    let submission_cost = chain.estimated_submission_cost().await?;
+    let daily_spend = daily_spend(db, now).await?;
+    Ok(Some(assess(address, balance, submission_cost, daily_spend, min_submissions, now)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_funds_count_submissions_and_runway() {
+        let signer = Address::repeat_byte(0x11);
+        let cost = U256::from(500_000u64 * 20 * GWEI); // 0.01 ETH
+        let now = Utc::now();
+
+        let funds = assess(signer, cost * 25, cost, Some(cost * 10), 10, now);
+        assert_eq!((funds.submissions_left, funds.runway_days, funds.low), (25, Some(2.5), false));
+
+        // Below the minimum number of submissions is low before it is refused
+        let funds = assess(signer, cost * 9 + 1, cost, None, 10, now);
+        assert_eq!((funds.submissions_left, funds.runway_days, funds.low), (9, None, true));
+
+        // Not even one submission is low whatever the minimum
+        let funds = assess(signer, cost - 1, cost, Some(U256::zero()), 0, now);
+        assert_eq!((funds.submissions_left, funds.runway_days, funds.low), (0, None, true));
+    }
+}
@@ -0,0 +1,407 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::config::Config;
+use crate::models::OrderStatus;
+use vapor_core::services::clock::{system_clock, SharedClock};
+use crate::services::event_log::{self, DomainEvent};
+use crate::services::matching_engine::MatchingEngine;
+
+/// Reason codes recorded on orders that breached their SLA
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaReason {
+    /// No filler locked the order while it was in discovery
+    DiscoveryTimeout,
+    /// Filler did not submit payment proof before the lock expired
+    LockExpired,
+    /// Payment proof was submitted but never verified
+    PaymentVerificationTimeout,
+}
+
+impl SlaReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SlaReason::DiscoveryTimeout => "discovery_timeout",
+            SlaReason::LockExpired => "lock_expired",
+            SlaReason::PaymentVerificationTimeout => "payment_verification_timeout",
+        }
+    }
+}
+
+/// What happens to an order that stays in `status` longer than `limit`
+#[derive(Debug, Clone)]
+pub struct SlaRule {
+    pub status: OrderStatus,
+    pub limit: Duration,
+    pub target: OrderStatus,
+    pub reason: SlaReason,
+}
+
+/// Per-state SLA timers
+#[derive(Debug, Clone)]
+pub struct SlaPolicy {
+    pub rules: Vec<SlaRule>,
+    /// Time the timers are measured against
+    pub clock: SharedClock,
+}
+
+impl SlaPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        let candidates = [
+            (OrderStatus::Discovery, config.sla.discovery_seconds, OrderStatus::Failed, SlaReason::DiscoveryTimeout),
+            (OrderStatus::Locked, config.filler.lock_ttl_seconds, OrderStatus::Failed, SlaReason::LockExpired),
+            (OrderStatus::MarkPaid, config.sla.mark_paid_seconds, OrderStatus::Disputed, SlaReason::PaymentVerificationTimeout),
+        ];
+
+        let rules = candidates.into_iter()
+            .filter(|(_, seconds, _, _)| *seconds > 0)
+            .map(|(status, seconds, target, reason)| SlaRule {
+                status,
+                limit: Duration::seconds(seconds as i64),
+                target,
+                reason,
+            })
+            .collect();
+
+        Self { rules, clock: system_clock() }
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+/// An order moved out of its state by the sweeper
+#[derive(Debug, Clone, Serialize)]
+pub struct SlaBreach {
+    pub order_id: String,
+    pub from_status: OrderStatus,
+    pub to_status: OrderStatus,
+    pub reason: SlaReason,
+    pub bank_service: String,
+    pub overdue_seconds: i64,
+}
+
+/// SLA breach counters, per bank service and reason code
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SlaMetrics {
+    pub total_breaches: u64,
+    pub breaches_by_bank_service: HashMap<String, HashMap<String, u64>>,
+    pub last_breach_at: Option<DateTime<Utc>>,
+}
+
+impl SlaMetrics {
+    pub fn record(&mut self, breach: &SlaBreach, at: DateTime<Utc>) {
+        self.total_breaches += 1;
+        *self.breaches_by_bank_service
+            .entry(breach.bank_service.clone())
+            .or_default()
+            .entry(breach.reason.as_str().to_string())
+            .or_insert(0) += 1;
+        self.last_breach_at = Some(at);
+    }
+}
+
+/// Moves orders that overstayed their SLA into Failed/Disputed and releases filler capacity
+pub async fn sweep_overdue_orders(
+    db: &SqlitePool,
+    policy: &SlaPolicy,
+    matching_engine: &Arc<Mutex<MatchingEngine>>,
+    metrics: &Arc<Mutex<SlaMetrics>>,
+) -> Result<Vec<SlaBreach>> {
+    let now = policy.clock.now();
+    let mut breaches = Vec::new();
+
+    for rule in &policy.rules {
+        let rows = sqlx::query("SELECT id, bank_service, filler_id, locked_amount, updated_at FROM orders WHERE status = $1")
+            .bind(rule.status as i32)
+            .fetch_all(db)
+            .await?;
+
+        for row in rows {
+            let entered_at: DateTime<Utc> = row.try_get("updated_at")?;
+            let overdue = now - (entered_at + rule.limit);
+            if overdue < Duration::zero() {
+                continue;
+            }
+            if let Some(breach) = breach_order(db, rule, &row, overdue, now, matching_engine, metrics).await? {
+                breaches.push(breach);
+            }
+        }
+    }
+
+    if !breaches.is_empty() {
+        info!("SLA sweeper moved {} overdue orders", breaches.len());
+    }
+
+    Ok(breaches)
+}
+
+/// Where one order's lock stands against the Locked timer
+#[derive(Debug, Clone)]
+pub enum LockExpiry {
+    Expired(SlaBreach),
+    /// Still within the lock TTL until then
+    Until(DateTime<Utc>),
+    /// Paid, released or never locked, or lock expiry is disabled
+    NotLocked,
+}
+
+/// Expire a single lock once it is past the lock TTL, as the sweeper would
+pub async fn expire_lock(
+    db: &SqlitePool,
+    policy: &SlaPolicy,
+    matching_engine: &Arc<Mutex<MatchingEngine>>,
+    metrics: &Arc<Mutex<SlaMetrics>>,
+    order_id: &str,
+) -> Result<LockExpiry> {
+    let Some(rule) = policy.rules.iter().find(|rule| rule.status == OrderStatus::Locked) else {
+        return Ok(LockExpiry::NotLocked);
+    };
+    let row = sqlx::query("SELECT id, bank_service, filler_id, locked_amount, updated_at FROM orders WHERE id = $1 AND status = $2")
+        .bind(order_id)
+        .bind(rule.status as i32)
+        .fetch_optional(db)
+        .await?;
+    let Some(row) = row else {
+        return Ok(LockExpiry::NotLocked);
+    };
+
+    let now = policy.clock.now();
+    let expires_at = row.try_get::<DateTime<Utc>, _>("updated_at")? + rule.limit;
+    if now < expires_at {
+        return Ok(LockExpiry::Until(expires_at));
+    }
+    Ok(breach_order(db, rule, &row, now - expires_at, now, matching_engine, metrics).await?
+        .map_or(LockExpiry::NotLocked, LockExpiry::Expired))
+}
+
+/// Move one overdue order to the rule's target, or None if it moved on concurrently
+async fn breach_order(
+    db: &SqlitePool,
+    rule: &SlaRule,
+    row: &sqlx::sqlite::SqliteRow,
+    overdue: Duration,
+    now: DateTime<Utc>,
+    matching_engine: &Arc<Mutex<MatchingEngine>>,
+    metrics: &Arc<Mutex<SlaMetrics>>,
+) -> Result<Option<SlaBreach>> {
+    let order_id: String = row.try_get("id")?;
+
+    // Guard on status so an order that moved on concurrently is left alone; the move is
+    // recorded in the order's history and the event log with it
+    let mut tx = db.begin().await?;
+    let result = sqlx::query("UPDATE orders SET status = $1, failure_reason = $2, updated_at = $3 WHERE id = $4 AND status = $5")
+        .bind(rule.target as i32)
+        .bind(rule.reason.as_str())
+        .bind(now)
+        .bind(&order_id)
+        .bind(rule.status as i32)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+    sqlx::query("INSERT INTO order_status_history (order_id, from_status, to_status, reason, created_at) VALUES (?1, ?2, ?3, ?4, ?5)")
+        .bind(&order_id)
+        .bind(rule.status as i32)
+        .bind(rule.target as i32)
+        .bind(rule.reason.as_str())
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    event_log::append(&mut *tx, &DomainEvent::status_change(&order_id, rule.status, rule.target, Some(rule.reason.as_str()))).await?;
+    tx.commit().await?;
+
+    // The order has moved on, so failing to restore the filler's capacity must not stop the sweep
+    if rule.status == OrderStatus::Locked {
+        let filler_id: Option<String> = row.try_get("filler_id")?;
+        let locked_amount: Option<String> = row.try_get("locked_amount")?;
+        if let Some(filler_id) = filler_id {
+            let amount = locked_amount.and_then(|a| a.parse::<u64>().ok()).unwrap_or(0);
+            if let Err(e) = matching_engine.lock().await.release_order(&order_id, &filler_id, amount) {
+                error!("Failed to release expired lock on order {} for filler {}: {:?}", order_id, filler_id, e);
+            }
+        }
+    }
+
+    let breach = SlaBreach {
+        order_id,
+        from_status: rule.status,
+        to_status: rule.target,
+        reason: rule.reason,
+        bank_service: row.try_get::<Option<String>, _>("bank_service")?
+            .unwrap_or_else(|| "unknown".to_string()),
+        overdue_seconds: overdue.num_seconds(),
+    };
+
+    warn!("SLA breach: order {} moved from {:?} to {:?} ({}, bank service {}, {}s overdue)",
+        breach.order_id, breach.from_status, breach.to_status,
+        breach.reason.as_str(), breach.bank_service, breach.overdue_seconds);
+
+    metrics.lock().await.record(&breach, now);
+    Ok(Some(breach))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Order, OrderType};
+
+    async fn setup_db() -> SqlitePool {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        db
+    }
+
+    fn order_in_state(id: &str, status: OrderStatus, age: Duration) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::BridgeIn,
+            status,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some("PayPal Hong Kong".to_string()),
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at: Utc::now() - age,
+            updated_at: Utc::now() - age,
+        }
+    }
+
+    #[test]
+    fn test_policy_skips_disabled_timers() {
+        let mut config = Config::default();
+        config.sla.discovery_seconds = 0;
+
+        let policy = SlaPolicy::from_config(&config);
+        assert_eq!(policy.rules.len(), 2);
+        assert!(policy.rules.iter().all(|rule| rule.status != OrderStatus::Discovery));
+    }
+
+    #[tokio::test]
+    async fn test_sweep_moves_overdue_orders() {
+        let db = setup_db().await;
+        let engine = Arc::new(Mutex::new(MatchingEngine::new()));
+        let metrics = Arc::new(Mutex::new(SlaMetrics::default()));
+        engine.lock().await.add_filler("filler_1".to_string(), "0xfiller".to_string(), 4000).unwrap();
+
+        let mut locked = order_in_state("locked", OrderStatus::Locked, Duration::hours(1));
+        locked.filler_id = Some("filler_1".to_string());
+        locked.locked_amount = Some("1000".to_string());
+        let paid = order_in_state("paid", OrderStatus::MarkPaid, Duration::hours(3));
+        let fresh = order_in_state("fresh", OrderStatus::MarkPaid, Duration::minutes(1));
+
+        for order in [&locked, &paid, &fresh] {
+            crate::database::helpers::insert_order(&db, order).await.unwrap();
+        }
+
+        let policy = SlaPolicy::from_config(&Config::default());
+        let breaches = sweep_overdue_orders(&db, &policy, &engine, &metrics).await.unwrap();
+        assert_eq!(breaches.len(), 2);
+
+        let locked = crate::database::helpers::get_order_by_id(&db, "locked").await.unwrap().unwrap();
+        assert_eq!(locked.status, OrderStatus::Failed);
+        let paid = crate::database::helpers::get_order_by_id(&db, "paid").await.unwrap().unwrap();
+        assert_eq!(paid.status, OrderStatus::Disputed);
+        let fresh = crate::database::helpers::get_order_by_id(&db, "fresh").await.unwrap().unwrap();
+        assert_eq!(fresh.status, OrderStatus::MarkPaid);
+
+        let reason: String = sqlx::query("SELECT failure_reason FROM orders WHERE id = 'paid'")
+            .fetch_one(&db).await.unwrap()
+            .try_get("failure_reason").unwrap();
+        assert_eq!(reason, "payment_verification_timeout");
+
+        // Each move is in the order's history with its reason
+        let history = crate::database::helpers::get_order_history(&db, "paid").await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!((history[0].from_status, history[0].to_status), (OrderStatus::MarkPaid, OrderStatus::Disputed));
+        assert_eq!(history[0].reason.as_deref(), Some("payment_verification_timeout"));
+
+        // Filler capacity is restored for the expired lock
+        assert_eq!(engine.lock().await.fillers["filler_1"].capacity_usd, 5000);
+
+        let metrics = metrics.lock().await;
+        assert_eq!(metrics.total_breaches, 2);
+        assert_eq!(metrics.breaches_by_bank_service["PayPal Hong Kong"]["lock_expired"], 1);
+
+        // Second sweep finds nothing new
+        drop(metrics);
+        let metrics = Arc::new(Mutex::new(SlaMetrics::default()));
+        assert!(sweep_overdue_orders(&db, &policy, &engine, &metrics).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sweep_measures_against_injected_clock() {
+        use vapor_core::services::clock::MockClock;
+        use chrono::TimeZone;
+
+        let db = setup_db().await;
+        let engine = Arc::new(Mutex::new(MatchingEngine::new()));
+        let metrics = Arc::new(Mutex::new(SlaMetrics::default()));
+        let discovered_at = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+        let clock = MockClock::new(discovered_at);
+
+        let mut order = order_in_state("waiting", OrderStatus::Discovery, Duration::zero());
+        order.updated_at = discovered_at;
+        crate::database::helpers::insert_order(&db, &order).await.unwrap();
+
+        let mut config = Config::default();
+        config.sla.discovery_seconds = 600;
+        let policy = SlaPolicy::from_config(&config).with_clock(clock.shared());
+
+        clock.advance(Duration::seconds(599));
+        assert!(sweep_overdue_orders(&db, &policy, &engine, &metrics).await.unwrap().is_empty());
+
+        clock.advance(Duration::seconds(61));
+        let breaches = sweep_overdue_orders(&db, &policy, &engine, &metrics).await.unwrap();
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].overdue_seconds, 60);
+        assert_eq!(metrics.lock().await.last_breach_at, Some(discovered_at + Duration::seconds(660)));
+    }
+
+    #[tokio::test]
+    async fn test_single_lock_expires_once_past_its_ttl() {
+        use vapor_core::services::clock::MockClock;
+
+        let db = setup_db().await;
+        let engine = Arc::new(Mutex::new(MatchingEngine::new()));
+        let metrics = Arc::new(Mutex::new(SlaMetrics::default()));
+        engine.lock().await.add_filler("filler_1".to_string(), "0xfiller".to_string(), 4000).unwrap();
+
+        let mut order = order_in_state("locked", OrderStatus::Locked, Duration::zero());
+        order.filler_id = Some("filler_1".to_string());
+        order.locked_amount = Some("1000".to_string());
+        crate::database::helpers::insert_order(&db, &order).await.unwrap();
+
+        let clock = MockClock::new(order.updated_at);
+        let config = Config::default();
+        let policy = SlaPolicy::from_config(&config).with_clock(clock.shared());
+        let expires_at = order.updated_at + Duration::seconds(config.filler.lock_ttl_seconds as i64);
+
+        let expiry = expire_lock(&db, &policy, &engine, &metrics, "locked").await.unwrap();
+        assert!(matches!(expiry, LockExpiry::Until(at) if at == expires_at));
+
+        clock.set(expires_at);
+        let LockExpiry::Expired(breach) = expire_lock(&db, &policy, &engine, &metrics, "locked").await.unwrap() else {
+            panic!("expected the lock to expire");
+        };
+        assert_eq!((breach.to_status, breach.reason), (OrderStatus::Failed, SlaReason::LockExpired));
+        assert_eq!(engine.lock().await.fillers["filler_1"].capacity_usd, 5000);
+        assert!(matches!(expire_lock(&db, &policy, &engine, &metrics, "locked").await.unwrap(), LockExpiry::NotLocked));
+    }
+}
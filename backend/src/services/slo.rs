@@ -0,0 +1,403 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use tracing::{info, instrument, warn};
+
+use crate::config::{SloConfig, SloTargets};
+use crate::models::{Order, OrderStatus};
+use crate::services::market::UNSPECIFIED_BANK_SERVICE;
+
+/// Stretches of an order's life that latency objectives are tracked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SloPhase {
+    /// Waiting for a filler
+    Discovery,
+    /// Locked by a filler, waiting for payment proof
+    Locked,
+    /// Payment proof submitted, waiting for settlement
+    MarkPaid,
+    /// Creation to settlement
+    Settle,
+}
+
+impl SloPhase {
+    pub const ALL: [SloPhase; 4] = [SloPhase::Discovery, SloPhase::Locked, SloPhase::MarkPaid, SloPhase::Settle];
+
+    /// Phase an order in this status is currently in
+    fn of_status(status: OrderStatus) -> Option<Self> {
+        match status {
+            OrderStatus::Discovery => Some(SloPhase::Discovery),
+            OrderStatus::Locked => Some(SloPhase::Locked),
+            OrderStatus::MarkPaid => Some(SloPhase::MarkPaid),
+            OrderStatus::Settled => Some(SloPhase::Settle),
+            _ => None,
+        }
+    }
+
+    fn target(self, targets: &SloTargets) -> Option<u64> {
+        match self {
+            SloPhase::Discovery => targets.discovery_seconds,
+            SloPhase::Locked => targets.locked_seconds,
+            SloPhase::MarkPaid => targets.mark_paid_seconds,
+            SloPhase::Settle => targets.settle_seconds,
+        }
+    }
+}
+
+/// Percentiles of a phase's durations in the window, in seconds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PhaseStats {
+    pub samples: usize,
+    pub p50_seconds: u64,
+    pub p95_seconds: u64,
+    pub p99_seconds: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_p95_seconds: Option<u64>,
+    /// Whether p95 is within the target, when there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub met: Option<bool>,
+}
+
+/// Phase latencies of one corridor (token paid in, bank service paid out)
+#[derive(Debug, Clone, Serialize)]
+pub struct CorridorSlo {
+    pub token_id: u32,
+    pub bank_service: String,
+    pub phases: BTreeMap<SloPhase, PhaseStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SloReport {
+    pub generated_at: DateTime<Utc>,
+    pub window_hours: i64,
+    pub corridors: Vec<CorridorSlo>,
+    /// Every corridor together
+    pub overall: BTreeMap<SloPhase, PhaseStats>,
+}
+
+/// Where an order's time in its current phase falls among the corridor's recent orders
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SloPosition {
+    pub phase: SloPhase,
+    pub elapsed_seconds: u64,
+    /// Share of the corridor's orders in the window that took no longer, 0-100
+    pub percentile: u8,
+    pub p95_seconds: u64,
+}
+
+type Corridor = (u32, String);
+
+/// Sorted durations in seconds, per corridor and phase
+type Samples = BTreeMap<Corridor, BTreeMap<SloPhase, Vec<u64>>>;
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[u64], p: u64) -> u64 {
+    let rank = (sorted.len() as u64 * p).div_ceil(100).max(1) as usize;
+    sorted[rank.min(sorted.len()) - 1]
+}
+
+fn phase_stats(phase: SloPhase, sorted: &[u64], targets: &SloTargets) -> PhaseStats {
+    let p95 = percentile(sorted, 95);
+    let target = phase.target(targets);
+    PhaseStats {
+        samples: sorted.len(),
+        p50_seconds: percentile(sorted, 50),
+        p95_seconds: p95,
+        p99_seconds: percentile(sorted, 99),
+        target_p95_seconds: target,
+        met: target.map(|target| p95 <= target),
+    }
+}
+
+/// Durations of the phases orders left in the window, read from the status history
+///
+/// A phase lasts from the transition into a status to the next transition out of it;
+/// Settle runs from the order's creation to its transition into Settled.
+#[instrument(skip_all, fields(db.system = "sqlite"))]
+async fn collect_samples(db: &SqlitePool, since: DateTime<Utc>) -> Result<Samples> {
+    let rows = sqlx::query(
+        r#"
+        SELECT h.order_id, h.from_status, h.to_status, h.created_at, o.token_id, o.bank_service, o.created_at AS order_created_at
+        FROM order_status_history h JOIN orders o ON o.id = h.order_id
+        WHERE h.order_id IN (SELECT order_id FROM order_status_history WHERE created_at >= ?1)
+        ORDER BY h.order_id, h.id
+        "#,
+    )
+    .bind(since)
+    .fetch_all(db)
+    .await?;
+
+    let mut samples = Samples::new();
+    let mut current_order = String::new();
+    let mut entered: HashMap<OrderStatus, DateTime<Utc>> = HashMap::new();
+    for row in rows {
+        let order_id: String = row.try_get("order_id")?;
+        if order_id != current_order {
+            current_order = order_id;
+            entered.clear();
+        }
+
+        let from_status = OrderStatus::from(row.try_get::<i32, _>("from_status")?);
+        let to_status = OrderStatus::from(row.try_get::<i32, _>("to_status")?);
+        let at: DateTime<Utc> = row.try_get("created_at")?;
+        let corridor = || -> Result<Corridor> {
+            let bank_service = row.try_get::<Option<String>, _>("bank_service")?
+                .filter(|service| !service.is_empty())
+                .unwrap_or_else(|| UNSPECIFIED_BANK_SERVICE.to_string());
+            Ok((row.try_get::<i64, _>("token_id")? as u32, bank_service))
+        };
+
+        if at >= since {
+            let mut finished = Vec::new();
+            if let (Some(phase), Some(started)) = (SloPhase::of_status(from_status), entered.get(&from_status)) {
+                finished.push((phase, at - *started));
+            }
+            if to_status == OrderStatus::Settled {
+                finished.push((SloPhase::Settle, at - row.try_get::<DateTime<Utc>, _>("order_created_at")?));
+            }
+            for (phase, duration) in finished {
+                samples.entry(corridor()?).or_default()
+                    .entry(phase).or_default()
+                    .push(duration.num_seconds().max(0) as u64);
+            }
+        }
+        entered.insert(to_status, at);
+    }
+
+    for phases in samples.values_mut() {
+        for durations in phases.values_mut() {
+            durations.sort_unstable();
+        }
+    }
+    Ok(samples)
+}
+
+/// Compute the SLO report for the window ending at `now`, with the samples it was built from
+pub async fn compute_report(db: &SqlitePool, config: &SloConfig, now: DateTime<Utc>) -> Result<(SloReport, Samples)> {
+    let samples = collect_samples(db, now - Duration::hours(config.window_hours)).await?;
+
+    let corridors = samples.iter()
+        .map(|((token_id, bank_service), phases)| CorridorSlo {
+            token_id: *token_id,
+            bank_service: bank_service.clone(),
+            phases: phases.iter()
+                .map(|(phase, durations)| (*phase, phase_stats(*phase, durations, &config.p95_targets)))
+                .collect(),
+        })
+        .collect();
+
+    let overall = SloPhase::ALL.into_iter()
+        .filter_map(|phase| {
+            let mut durations: Vec<u64> = samples.values()
+                .filter_map(|phases| phases.get(&phase))
+                .flatten()
+                .copied()
+                .collect();
+            durations.sort_unstable();
+            (!durations.is_empty()).then(|| (phase, phase_stats(phase, &durations, &config.p95_targets)))
+        })
+        .collect();
+
+    let report = SloReport { generated_at: now, window_hours: config.window_hours, corridors, overall };
+    Ok((report, samples))
+}
+
+struct Snapshot {
+    report: SloReport,
+    samples: Samples,
+}
+
+/// Last computed SLO report, refreshed on a timer like the market summary
+#[derive(Clone)]
+pub struct SloTracker {
+    db: SqlitePool,
+    config: SloConfig,
+    snapshot: Arc<RwLock<Option<Arc<Snapshot>>>>,
+}
+
+impl SloTracker {
+    pub fn new(db: SqlitePool, config: SloConfig) -> Self {
+        Self {
+            db,
+            config,
+            snapshot: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Recompute the report and replace the cached one, warning about missed targets
+    pub async fn refresh(&self) -> Result<SloReport> {
+        let (report, samples) = compute_report(&self.db, &self.config, Utc::now()).await?;
+        for (phase, stats) in &report.overall {
+            if stats.met == Some(false) {
+                warn!("{:?} p95 is {}s, above its {}s target", phase, stats.p95_seconds, stats.target_p95_seconds.unwrap_or_default());
+            }
+        }
+        info!("Refreshed SLO report: {} corridors", report.corridors.len());
+
+        *self.snapshot.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(Snapshot { report: report.clone(), samples }));
+        Ok(report)
+    }
+
+    async fn snapshot(&self) -> Result<Arc<Snapshot>> {
+        let cached = self.snapshot.read().unwrap_or_else(|e| e.into_inner()).clone();
+        match cached {
+            Some(snapshot) => Ok(snapshot),
+            None => {
+                self.refresh().await?;
+                Ok(self.snapshot.read().unwrap_or_else(|e| e.into_inner()).clone().expect("refreshed above"))
+            }
+        }
+    }
+
+    /// The cached report, computed on first use if the refresh timer has not run yet
+    pub async fn report(&self) -> Result<SloReport> {
+        Ok(self.snapshot().await?.report.clone())
+    }
+
+    /// Position of an order in its corridor's distribution for its current phase
+    ///
+    /// `entered_at` is when the order entered its current status; a settled order is measured
+    /// from creation to settlement.
+    pub async fn position(&self, order: &Order, entered_at: DateTime<Utc>, now: DateTime<Utc>) -> Result<Option<SloPosition>> {
+        let Some(phase) = SloPhase::of_status(order.status) else {
+            return Ok(None);
+        };
+        let elapsed = match phase {
+            SloPhase::Settle => entered_at - order.created_at,
+            _ => now - entered_at,
+        };
+        let elapsed = elapsed.num_seconds().max(0) as u64;
+
+        let bank_service = order.bank_service.clone()
+            .filter(|service| !service.is_empty())
+            .unwrap_or_else(|| UNSPECIFIED_BANK_SERVICE.to_string());
+        let snapshot = self.snapshot().await?;
+        let Some(durations) = snapshot.samples.get(&(order.token_id, bank_service)).and_then(|phases| phases.get(&phase)) else {
+            return Ok(None);
+        };
+
+        let within = durations.partition_point(|duration| *duration <= elapsed);
+        Ok(Some(SloPosition {
+            phase,
+            elapsed_seconds: elapsed,
+            percentile: (within * 100 / durations.len()) as u8,
+            p95_seconds: percentile(durations, 95),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{helpers, run_migrations};
+    use crate::models::{CreateOrderRequest, OrderType};
+
+    async fn setup_db() -> SqlitePool {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        run_migrations(&db).await.unwrap();
+        db
+    }
+
+    /// Insert an order that went through `steps` (status, minutes after creation)
+    async fn order_with_history(db: &SqlitePool, bank_service: &str, created_minutes_ago: i64, steps: &[(OrderStatus, i64)]) -> Order {
+        let mut order = Order::new(CreateOrderRequest {
+            order_type: OrderType::BridgeIn,
+            from_address: Some("0x1234567890123456789012345678901234567890".to_string()),
+            to_address: None,
+            token_id: 1,
+            amount: "1000".to_string(),
+            bank_account: Some("12345678".to_string()),
+            bank_service: Some(bank_service.to_string()),
+            banking_hash: None,
+            permit: None,
+            priority_fee: None,
+            confirm_duplicate: false,
+            quote_id: None,
+        });
+        order.created_at = Utc::now() - Duration::minutes(created_minutes_ago);
+        order.status = steps.last().map_or(OrderStatus::Pending, |(status, _)| *status);
+        helpers::insert_order(db, &order).await.unwrap();
+
+        let mut from = OrderStatus::Pending;
+        for (status, minute) in steps {
+            sqlx::query("INSERT INTO order_status_history (order_id, from_status, to_status, reason, created_at) VALUES (?1, ?2, ?3, NULL, ?4)")
+                .bind(&order.id)
+                .bind(from as i32)
+                .bind(*status as i32)
+                .bind(order.created_at + Duration::minutes(*minute))
+                .execute(db)
+                .await
+                .unwrap();
+            from = *status;
+        }
+        order
+    }
+
+    fn config(targets: SloTargets) -> SloConfig {
+        SloConfig { window_hours: 24, refresh_seconds: 60, p95_targets: targets }
+    }
+
+    #[test]
+    fn test_nearest_rank_percentiles() {
+        let samples: Vec<u64> = (1..=20).collect();
+        assert_eq!(percentile(&samples, 50), 10);
+        assert_eq!(percentile(&samples, 95), 19);
+        assert_eq!(percentile(&samples, 99), 20);
+        assert_eq!(percentile(&[7], 95), 7);
+    }
+
+    #[tokio::test]
+    async fn test_phase_durations_per_corridor() {
+        let db = setup_db().await;
+        let full = [(OrderStatus::Discovery, 0), (OrderStatus::Locked, 10), (OrderStatus::MarkPaid, 15), (OrderStatus::Settled, 45)];
+        order_with_history(&db, "PayPal Hong Kong", 120, &full).await;
+        order_with_history(&db, "PayPal Hong Kong", 120, &[(OrderStatus::Discovery, 0), (OrderStatus::Locked, 30)]).await;
+        order_with_history(&db, "Wise", 120, &[(OrderStatus::Discovery, 0), (OrderStatus::Locked, 2)]).await;
+        // Phases that ended before the window are left out
+        order_with_history(&db, "Wise", 60 * 48, &[(OrderStatus::Discovery, 0), (OrderStatus::Locked, 600)]).await;
+
+        let targets = SloTargets { discovery_seconds: Some(20 * 60), ..Default::default() };
+        let (report, _) = compute_report(&db, &config(targets), Utc::now()).await.unwrap();
+
+        assert_eq!(report.corridors.len(), 2);
+        let paypal = &report.corridors[0];
+        assert_eq!(paypal.bank_service, "PayPal Hong Kong");
+        assert_eq!(paypal.phases[&SloPhase::Discovery].samples, 2);
+        assert_eq!(paypal.phases[&SloPhase::Discovery].p50_seconds, 600);
+        assert_eq!(paypal.phases[&SloPhase::Discovery].p95_seconds, 1800);
+        assert_eq!(paypal.phases[&SloPhase::Discovery].met, Some(false));
+        assert_eq!(paypal.phases[&SloPhase::Locked].p95_seconds, 300);
+        assert_eq!(paypal.phases[&SloPhase::MarkPaid].p95_seconds, 1800);
+        assert_eq!(paypal.phases[&SloPhase::Settle].p95_seconds, 45 * 60);
+        assert_eq!(paypal.phases[&SloPhase::Settle].met, None);
+        assert_eq!(report.corridors[1].phases[&SloPhase::Discovery].samples, 1);
+
+        assert_eq!(report.overall[&SloPhase::Discovery].samples, 3);
+        assert_eq!(report.overall[&SloPhase::Discovery].p50_seconds, 600);
+    }
+
+    #[tokio::test]
+    async fn test_position_of_order_in_current_phase() {
+        let db = setup_db().await;
+        for minutes in [5, 10, 20, 40] {
+            order_with_history(&db, "Wise", 120, &[(OrderStatus::Discovery, 0), (OrderStatus::Locked, minutes)]).await;
+        }
+        let tracker = SloTracker::new(db.clone(), config(SloTargets::default()));
+
+        let waiting = order_with_history(&db, "Wise", 15, &[(OrderStatus::Discovery, 0)]).await;
+        let entered_at = waiting.created_at;
+        let position = tracker.position(&waiting, entered_at, entered_at + Duration::minutes(15)).await.unwrap().unwrap();
+        assert_eq!(position.phase, SloPhase::Discovery);
+        assert_eq!(position.elapsed_seconds, 900);
+        assert_eq!(position.percentile, 50);
+        assert_eq!(position.p95_seconds, 2400);
+
+        // No samples for the corridor's phase yet
+        let other = order_with_history(&db, "ACH", 15, &[(OrderStatus::Discovery, 0)]).await;
+        assert_eq!(tracker.position(&other, other.created_at, Utc::now()).await.unwrap(), None);
+    }
+}
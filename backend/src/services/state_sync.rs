@@ -0,0 +1,223 @@
+use anyhow::Result;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use web3::types::{Address, U256};
+
+use vapor_core::proof_format::{parse_hash32, FormattedProof, ProofFormat};
+use vapor_core::sparse_merkle_tree::solidity_keccak256_hash;
+use crate::merkle::MerkleTreeManager;
+use crate::models::{AccountState, TokenBalance};
+use crate::services::archival::BatchSnapshot;
+use crate::services::proof_cache::build_account_proof;
+
+/// Field order of an encoded delta
+pub const DELTA_ENCODING: &str = "address,token_id,old_balance,new_balance";
+
+/// One token balance of one account that changed between two batches
+///
+/// Encoded as the array `[address, token_id, old_balance, new_balance]` to keep diffs small.
+/// A balance the account did not hold is "0" on that side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountDelta {
+    /// Lowercase hex
+    pub address: String,
+    pub token_id: u32,
+    pub old_balance: String,
+    pub new_balance: String,
+}
+
+impl Serialize for AccountDelta {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (&self.address, self.token_id, &self.old_balance, &self.new_balance).serialize(serializer)
+    }
+}
+
+/// Proof that an account's balances as of the target batch are a leaf of its state root
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountInclusion {
+    pub address: String,
+    /// Every balance of the account's leaf, in token order, so the leaf hash can be recomputed
+    pub balances: Vec<TokenBalance>,
+    pub proof: FormattedProof,
+}
+
+/// Balance changes taking a mirror from one finalized batch to another
+#[derive(Debug, Clone, Serialize)]
+pub struct StateDiff {
+    pub from_batch: u64,
+    pub to_batch: u64,
+    pub from_root: String,
+    /// State root after applying every delta
+    pub to_root: String,
+    pub encoding: &'static str,
+    /// Ordered by address, then token
+    pub deltas: Vec<AccountDelta>,
+    /// `keccak256(uint256(from_batch) || uint256(to_batch) || to_root || delta...)`, each delta
+    /// packed as `address (20 bytes) || uint256(token_id) || uint256(old_balance) || uint256(new_balance)`
+    pub checksum: String,
+    /// Inclusion proofs of the changed accounts against `to_root`, by address, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proofs: Option<Vec<AccountInclusion>>,
+}
+
+fn balances_by_token(accounts: &[AccountState]) -> BTreeMap<(String, u32), &str> {
+    accounts.iter()
+        .flat_map(|account| {
+            let address = account.address.to_lowercase();
+            account.balances.iter().map(move |balance| ((address.clone(), balance.token_id), balance.balance.as_str()))
+        })
+        .collect()
+}
+
+/// Balances equal in value are unchanged, whatever their formatting
+fn same_balance(old: &str, new: &str) -> bool {
+    match (U256::from_dec_str(old), U256::from_dec_str(new)) {
+        (Ok(old), Ok(new)) => old == new,
+        _ => old == new,
+    }
+}
+
+/// Every balance that differs between two account sets, ordered by address then token
+pub fn account_deltas(from: &[AccountState], to: &[AccountState]) -> Vec<AccountDelta> {
+    let old = balances_by_token(from);
+    let new = balances_by_token(to);
+    let mut keys: Vec<&(String, u32)> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let old_balance = old.get(key).copied().unwrap_or("0");
+            let new_balance = new.get(key).copied().unwrap_or("0");
+            (!same_balance(old_balance, new_balance)).then(|| AccountDelta {
+                address: key.0.clone(),
+                token_id: key.1,
+                old_balance: old_balance.to_string(),
+                new_balance: new_balance.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn uint256(value: U256) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    word
+}
+
+fn parse_balance(balance: &str) -> Result<U256> {
+    U256::from_dec_str(balance).map_err(|e| anyhow::anyhow!("Invalid balance {}: {:?}", balance, e))
+}
+
+/// Checksum binding the ordered deltas to the batches and the resulting root
+pub fn diff_checksum(from_batch: u64, to_batch: u64, to_root: &str, deltas: &[AccountDelta]) -> Result<[u8; 32]> {
+    let mut packed = Vec::with_capacity(96 + deltas.len() * 116);
+    packed.extend_from_slice(&uint256(U256::from(from_batch)));
+    packed.extend_from_slice(&uint256(U256::from(to_batch)));
+    packed.extend_from_slice(&parse_hash32(to_root)?);
+    for delta in deltas {
+        let address = Address::from_str(delta.address.trim_start_matches("0x"))
+            .map_err(|e| anyhow::anyhow!("Invalid address {}: {}", delta.address, e))?;
+        packed.extend_from_slice(address.as_bytes());
+        packed.extend_from_slice(&uint256(U256::from(delta.token_id)));
+        packed.extend_from_slice(&uint256(parse_balance(&delta.old_balance)?));
+        packed.extend_from_slice(&uint256(parse_balance(&delta.new_balance)?));
+    }
+    Ok(solidity_keccak256_hash(&[&packed]))
+}
+
+/// Diff a mirror at `from` (None for an empty mirror) up to `to`, optionally proving every
+/// changed account against `to`'s state root
+pub fn diff(from: Option<&BatchSnapshot>, to: &BatchSnapshot, with_proofs: bool) -> Result<StateDiff> {
+    let (from_batch, from_root, from_accounts) = match from {
+        Some(snapshot) => (snapshot.batch_id, snapshot.state_root.clone(), snapshot.accounts.as_slice()),
+        None => (0, MerkleTreeManager::empty_state_root(), &[][..]),
+    };
+    let deltas = account_deltas(from_accounts, &to.accounts);
+    let checksum = diff_checksum(from_batch, to.batch_id, &to.state_root, &deltas)?;
+
+    let proofs = if with_proofs {
+        let mut manager = MerkleTreeManager::new();
+        manager.build_state_tree(&to.accounts)?;
+        let mut addresses: Vec<&str> = deltas.iter().map(|delta| delta.address.as_str()).collect();
+        addresses.dedup();
+
+        let mut proofs = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let Some(account) = to.accounts.iter().find(|account| account.address.eq_ignore_ascii_case(address)) else {
+                continue;
+            };
+            proofs.push(AccountInclusion {
+                address: address.to_string(),
+                balances: account.balances.clone(),
+                proof: build_account_proof(&mut manager, &account.address, ProofFormat::Raw)?,
+            });
+        }
+        Some(proofs)
+    } else {
+        None
+    };
+
+    Ok(StateDiff {
+        from_batch,
+        to_batch: to.batch_id,
+        from_root,
+        to_root: to.state_root.clone(),
+        encoding: DELTA_ENCODING,
+        deltas,
+        checksum: format!("0x{}", hex::encode(checksum)),
+        proofs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn account(address: &str, balances: &[(u32, &str)]) -> AccountState {
+        AccountState {
+            address: address.to_string(),
+            balances: balances.iter().map(|(token_id, balance)| TokenBalance { token_id: *token_id, balance: balance.to_string() }).collect(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn snapshot(batch_id: u64, accounts: Vec<AccountState>) -> BatchSnapshot {
+        let state_root = MerkleTreeManager::new().build_state_tree(&accounts).unwrap();
+        BatchSnapshot { batch_id, state_root, orders_root: String::new(), accounts, orders: Vec::new(), created_at: Utc::now() }
+    }
+
+    #[test]
+    fn test_diffs_are_ordered_and_bound_to_the_resulting_root() {
+        let alice = "0x00000000000000000000000000000000000000aa";
+        let bob = "0x00000000000000000000000000000000000000BB";
+        let carol = "0x00000000000000000000000000000000000000cc";
+        let from = snapshot(1, vec![account(bob, &[(1, "10"), (2, "5")]), account(alice, &[(1, "7")])]);
+        let to = snapshot(3, vec![account(alice, &[(1, "7")]), account(bob, &[(1, "4"), (3, "6")]), account(carol, &[(1, "9")])]);
+
+        let diff = diff(Some(&from), &to, true).unwrap();
+        assert_eq!((diff.from_batch, diff.to_batch), (1, 3));
+        let encoded = serde_json::to_value(&diff.deltas).unwrap();
+        assert_eq!(encoded, serde_json::json!([
+            [bob.to_lowercase(), 1, "10", "4"],
+            [bob.to_lowercase(), 2, "5", "0"],
+            [bob.to_lowercase(), 3, "0", "6"],
+            [carol, 1, "0", "9"],
+        ]));
+        assert_eq!(diff.checksum, format!("0x{}", hex::encode(diff_checksum(1, 3, &to.state_root, &diff.deltas).unwrap())));
+        let reordered: Vec<AccountDelta> = diff.deltas.iter().rev().cloned().collect();
+        assert_ne!(diff_checksum(1, 3, &to.state_root, &reordered).unwrap(), diff_checksum(1, 3, &to.state_root, &diff.deltas).unwrap());
+
+        // Unchanged accounts are neither listed nor proven
+        let proven: Vec<&str> = diff.proofs.as_ref().unwrap().iter().map(|inclusion| inclusion.address.as_str()).collect();
+        assert_eq!(proven, vec![bob.to_lowercase().as_str(), carol]);
+        assert!(diff.proofs.unwrap().iter().all(|inclusion| inclusion.proof.root.trim_start_matches("0x") == to.state_root.trim_start_matches("0x")));
+
+        // From an empty mirror every balance is a delta
+        let bootstrap = super::diff(None, &to, false).unwrap();
+        assert_eq!((bootstrap.from_batch, bootstrap.deltas.len()), (0, 4));
+        assert!(bootstrap.proofs.is_none());
+    }
+}
@@ -0,0 +1,153 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqlitePool};
+
+/// Counters persisted in the `service_stats` table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    ProofsGenerated,
+    ProofFailures,
+    /// Total generation time of the successful proofs
+    ProofGenerationMs,
+    DepositsProcessed,
+    /// Standalone BridgeIn orders the relayer created for deposits without a pre-created order
+    OrdersCreated,
+    /// Relayer polls; its `updated_at` is the last poll time
+    RelayerPolls,
+}
+
+impl Counter {
+    fn name(self) -> &'static str {
+        match self {
+            Counter::ProofsGenerated => "proofs_generated",
+            Counter::ProofFailures => "proof_failures",
+            Counter::ProofGenerationMs => "proof_generation_ms",
+            Counter::DepositsProcessed => "deposits_processed",
+            Counter::OrdersCreated => "orders_created",
+            Counter::RelayerPolls => "relayer_polls",
+        }
+    }
+}
+
+/// Add to several counters in one transaction, so readers never see half of an update
+pub async fn increment(db: &SqlitePool, increments: &[(Counter, u64)]) -> Result<()> {
+    let now = Utc::now();
+    let mut tx = db.begin().await?;
+    for &(counter, by) in increments {
+        sqlx::query(
+            r#"
+            INSERT INTO service_stats (name, value, updated_at) VALUES (?1, ?2, ?3)
+            ON CONFLICT(name) DO UPDATE SET value = value + ?2, updated_at = ?3
+            "#,
+        )
+        .bind(counter.name())
+        .bind(i64::try_from(by).unwrap_or(i64::MAX))
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Current value of a counter and when it last changed; counters never incremented are zero
+pub async fn get(db: &SqlitePool, counter: Counter) -> Result<(u64, Option<DateTime<Utc>>)> {
+    let row = sqlx::query("SELECT value, updated_at FROM service_stats WHERE name = ?")
+        .bind(counter.name())
+        .fetch_optional(db)
+        .await?;
+
+    match row {
+        Some(row) => Ok((row.try_get::<i64, _>("value")? as u64, Some(row.try_get("updated_at")?))),
+        None => Ok((0, None)),
+    }
+}
+
+/// Proof generation totals since the database was created
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofTotals {
+    pub generated: u64,
+    pub failures: u64,
+    pub generation_ms: u64,
+}
+
+impl ProofTotals {
+    /// Mean generation time of the successful proofs, once there is one
+    pub fn average_generation_time_ms(&self) -> Option<u64> {
+        (self.generated > 0).then(|| self.generation_ms / self.generated)
+    }
+}
+
+pub async fn record_proof(db: &SqlitePool, success: bool, generation_time_ms: u64) -> Result<()> {
+    if success {
+        increment(db, &[(Counter::ProofsGenerated, 1), (Counter::ProofGenerationMs, generation_time_ms)]).await
+    } else {
+        increment(db, &[(Counter::ProofFailures, 1)]).await
+    }
+}
+
+pub async fn proof_totals(db: &SqlitePool) -> Result<ProofTotals> {
+    Ok(ProofTotals {
+        generated: get(db, Counter::ProofsGenerated).await?.0,
+        failures: get(db, Counter::ProofFailures).await?.0,
+        generation_ms: get(db, Counter::ProofGenerationMs).await?.0,
+    })
+}
+
+/// Relayer totals since the database was created
+#[derive(Debug, Clone, Default)]
+pub struct RelayerTotals {
+    pub deposits_processed: u64,
+    pub orders_created: u64,
+    pub last_poll_time: Option<DateTime<Utc>>,
+}
+
+pub async fn relayer_totals(db: &SqlitePool) -> Result<RelayerTotals> {
+    Ok(RelayerTotals {
+        deposits_processed: get(db, Counter::DepositsProcessed).await?.0,
+        orders_created: get(db, Counter::OrdersCreated).await?.0,
+        last_poll_time: get(db, Counter::RelayerPolls).await?.1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup_db() -> SqlitePool {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_proof_totals_accumulate() {
+        let db = setup_db().await;
+        assert_eq!(proof_totals(&db).await.unwrap(), ProofTotals::default());
+        assert_eq!(proof_totals(&db).await.unwrap().average_generation_time_ms(), None);
+
+        record_proof(&db, true, 100).await.unwrap();
+        record_proof(&db, true, 300).await.unwrap();
+        record_proof(&db, false, 50).await.unwrap();
+
+        let totals = proof_totals(&db).await.unwrap();
+        assert_eq!(totals, ProofTotals { generated: 2, failures: 1, generation_ms: 400 });
+        assert_eq!(totals.average_generation_time_ms(), Some(200));
+    }
+
+    #[tokio::test]
+    async fn test_relayer_totals_track_last_poll() {
+        let db = setup_db().await;
+        assert!(relayer_totals(&db).await.unwrap().last_poll_time.is_none());
+
+        let before = Utc::now();
+        increment(&db, &[(Counter::RelayerPolls, 1)]).await.unwrap();
+        increment(&db, &[(Counter::DepositsProcessed, 1), (Counter::OrdersCreated, 1)]).await.unwrap();
+        increment(&db, &[(Counter::DepositsProcessed, 1)]).await.unwrap();
+
+        let totals = relayer_totals(&db).await.unwrap();
+        assert_eq!(totals.deposits_processed, 2);
+        assert_eq!(totals.orders_created, 1);
+        assert!(totals.last_poll_time.unwrap() >= before);
+    }
+}
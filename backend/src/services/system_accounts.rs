@@ -0,0 +1,251 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::{AccountState, Order, OrderType, TokenBalance};
+
+/// Leading 19 bytes of the system accounts' addresses; the last byte names the account
+pub const SYSTEM_ACCOUNT_PREFIX: &str = "0x000000000000000000000000000000005e5e00";
+
+/// Operator-held leaves of the state tree that mirror every token entering or leaving user balances
+///
+/// The bridge account holds, per token, what the bridge contract holds for the rollup, so it
+/// always equals the sum of every other account: users, fee pool and escrow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemAccount {
+    /// Liquidity locked in the bridge contract; credited by deposits, debited by withdrawals
+    Bridge = 1,
+    /// Fees collected by the operator, which leave only by withdrawal
+    FeePool = 2,
+    /// Funds held for an order until it is released
+    Escrow = 3,
+}
+
+impl SystemAccount {
+    pub const ALL: [SystemAccount; 3] = [SystemAccount::Bridge, SystemAccount::FeePool, SystemAccount::Escrow];
+
+    pub fn address(self) -> String {
+        format!("{}{:02x}", SYSTEM_ACCOUNT_PREFIX, self as u8)
+    }
+
+    /// System account an address is reserved for
+    pub fn of(address: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|account| account.address().eq_ignore_ascii_case(address))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bridge => "bridge",
+            Self::FeePool => "fee_pool",
+            Self::Escrow => "escrow",
+        }
+    }
+}
+
+/// Which side of a transfer a system account is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Leg {
+    Credit,
+    Debit,
+}
+
+/// A state change the system accounts do not allow
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum SystemAccountError {
+    #[error("a {order_type:?} order cannot {leg:?} the {} system account (order {order_id})", account.as_str())]
+    ForbiddenTransition { order_id: String, order_type: OrderType, account: SystemAccount, leg: Leg },
+    #[error("{address} is reserved for the {} system account", account.as_str())]
+    Reserved { address: String, account: SystemAccount },
+    #[error("token {token_id} does not balance: the bridge holds {bridge} but accounts hold {held}")]
+    Unbalanced { token_id: u32, bridge: String, held: String },
+}
+
+/// Refuse addresses reserved for system accounts where a user account is expected
+pub fn check_user_address(address: &str) -> Result<(), SystemAccountError> {
+    match SystemAccount::of(address) {
+        Some(account) => Err(SystemAccountError::Reserved { address: address.to_string(), account }),
+        None => Ok(()),
+    }
+}
+
+/// Transition rules of the system accounts: the bridge moves only with deposits and
+/// withdrawals, the fee pool only takes transfers in and withdrawals out, and nothing is
+/// deposited straight into a system account or withdrawn from escrow
+pub fn check_order(order: &Order) -> Result<(), SystemAccountError> {
+    let from = order.from_address.as_deref().and_then(SystemAccount::of);
+    let to = order.to_address.as_deref().and_then(SystemAccount::of);
+    let forbidden = |account: SystemAccount, leg: Leg| SystemAccountError::ForbiddenTransition {
+        order_id: order.id.clone(),
+        order_type: order.order_type,
+        account,
+        leg,
+    };
+
+    match order.order_type {
+        OrderType::BridgeIn => match to {
+            Some(account) => Err(forbidden(account, Leg::Credit)),
+            None => Ok(()),
+        },
+        OrderType::BridgeOut => match from {
+            Some(account @ (SystemAccount::Bridge | SystemAccount::Escrow)) => Err(forbidden(account, Leg::Debit)),
+            _ => Ok(()),
+        },
+        OrderType::Transfer => match (from, to) {
+            (Some(account @ (SystemAccount::Bridge | SystemAccount::FeePool)), _) => Err(forbidden(account, Leg::Debit)),
+            (_, Some(SystemAccount::Bridge)) => Err(forbidden(SystemAccount::Bridge, Leg::Credit)),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Per-token totals of the state tree, split by holder
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TokenLedger {
+    pub token_id: u32,
+    pub bridge: String,
+    pub users: String,
+    pub fee_pool: String,
+    pub escrow: String,
+    /// Signed: everything held outside the bridge account less the bridge account; "0" when balanced
+    pub net: String,
+}
+
+/// Ledger of every token in `accounts`, by token id
+pub fn ledger(accounts: &HashMap<String, AccountState>) -> Vec<TokenLedger> {
+    let mut totals: BTreeMap<u32, [u128; 4]> = BTreeMap::new();
+    for account in accounts.values() {
+        let slot = match SystemAccount::of(&account.address) {
+            Some(SystemAccount::Bridge) => 0,
+            None => 1,
+            Some(SystemAccount::FeePool) => 2,
+            Some(SystemAccount::Escrow) => 3,
+        };
+        for balance in &account.balances {
+            totals.entry(balance.token_id).or_default()[slot] += balance.balance.parse::<u128>().unwrap_or(0);
+        }
+    }
+
+    totals.into_iter()
+        .map(|(token_id, [bridge, users, fee_pool, escrow])| {
+            let held = users + fee_pool + escrow;
+            let net = if held >= bridge { (held - bridge).to_string() } else { format!("-{}", bridge - held) };
+            TokenLedger {
+                token_id,
+                bridge: bridge.to_string(),
+                users: users.to_string(),
+                fee_pool: fee_pool.to_string(),
+                escrow: escrow.to_string(),
+                net,
+            }
+        })
+        .collect()
+}
+
+/// Check that the bridge account mirrors the rest of the tree for every token
+pub fn check_balanced(accounts: &HashMap<String, AccountState>) -> Result<(), SystemAccountError> {
+    match ledger(accounts).into_iter().find(|token| token.net != "0") {
+        Some(token) => {
+            let held = token.bridge.parse::<u128>().unwrap_or(0) as i128 + token.net.parse::<i128>().unwrap_or(0);
+            Err(SystemAccountError::Unbalanced { token_id: token.token_id, bridge: token.bridge, held: held.to_string() })
+        }
+        None => Ok(()),
+    }
+}
+
+/// Give state from before the system accounts a bridge account holding what the other
+/// accounts hold, so it balances; returns whether one was added
+pub fn seed_bridge(accounts: &mut HashMap<String, AccountState>, now: DateTime<Utc>) -> bool {
+    let address = SystemAccount::Bridge.address();
+    if accounts.contains_key(&address) {
+        return false;
+    }
+    let balances: Vec<TokenBalance> = ledger(accounts).into_iter()
+        .filter(|token| token.net != "0")
+        .map(|token| TokenBalance { token_id: token.token_id, balance: token.net })
+        .collect();
+    if balances.is_empty() {
+        return false;
+    }
+    accounts.insert(address.clone(), AccountState { address, balances, updated_at: now });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OrderStatus;
+
+    fn order(order_type: OrderType, from: Option<String>, to: Option<String>) -> Order {
+        Order {
+            id: "order_1".to_string(),
+            order_type,
+            status: OrderStatus::Pending,
+            from_address: from,
+            to_address: to,
+            token_id: 1,
+            amount: "100".to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_system_addresses() {
+        for account in SystemAccount::ALL {
+            assert_eq!(account.address().len(), 42);
+            assert_eq!(SystemAccount::of(&account.address().to_uppercase().replace("0X", "0x")), Some(account));
+        }
+        assert_eq!(SystemAccount::of("0x1111111111111111111111111111111111111111"), None);
+        assert!(check_user_address(&SystemAccount::Escrow.address()).is_err());
+    }
+
+    #[test]
+    fn test_transition_rules() {
+        let user = Some("0x1111111111111111111111111111111111111111".to_string());
+        let system = |account: SystemAccount| Some(account.address());
+        let forbidden = |order: Order| matches!(check_order(&order), Err(SystemAccountError::ForbiddenTransition { .. }));
+
+        assert!(!forbidden(order(OrderType::BridgeIn, None, user.clone())));
+        assert!(forbidden(order(OrderType::BridgeIn, None, system(SystemAccount::FeePool))));
+        assert!(!forbidden(order(OrderType::BridgeOut, system(SystemAccount::FeePool), None)));
+        assert!(forbidden(order(OrderType::BridgeOut, system(SystemAccount::Escrow), None)));
+        assert!(forbidden(order(OrderType::BridgeOut, system(SystemAccount::Bridge), None)));
+        assert!(!forbidden(order(OrderType::Transfer, user.clone(), system(SystemAccount::Escrow))));
+        assert!(!forbidden(order(OrderType::Transfer, system(SystemAccount::Escrow), user.clone())));
+        assert!(!forbidden(order(OrderType::Transfer, user.clone(), system(SystemAccount::FeePool))));
+        assert!(forbidden(order(OrderType::Transfer, system(SystemAccount::FeePool), user.clone())));
+        assert!(forbidden(order(OrderType::Transfer, user, system(SystemAccount::Bridge))));
+    }
+
+    #[test]
+    fn test_seeded_bridge_balances_the_ledger() {
+        let account = |address: String, balances: &[(u32, &str)]| {
+            let balances = balances.iter().map(|(token_id, balance)| TokenBalance { token_id: *token_id, balance: balance.to_string() }).collect();
+            (address.clone(), AccountState { address, balances, updated_at: Utc::now() })
+        };
+        let mut accounts = HashMap::from([
+            account("0x1111111111111111111111111111111111111111".to_string(), &[(1, "700"), (2, "5")]),
+            account(SystemAccount::Escrow.address(), &[(1, "300")]),
+        ]);
+        assert_eq!(
+            check_balanced(&accounts),
+            Err(SystemAccountError::Unbalanced { token_id: 1, bridge: "0".to_string(), held: "1000".to_string() })
+        );
+
+        assert!(seed_bridge(&mut accounts, Utc::now()));
+        assert!(!seed_bridge(&mut accounts, Utc::now()));
+        assert_eq!(check_balanced(&accounts), Ok(()));
+        let ledger = ledger(&accounts);
+        assert_eq!((ledger[0].bridge.as_str(), ledger[0].users.as_str(), ledger[0].escrow.as_str()), ("1000", "700", "300"));
+        assert_eq!((ledger[1].token_id, ledger[1].bridge.as_str()), (2, "5"));
+    }
+}
@@ -0,0 +1,227 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use web3::signing::{self, hash_message};
+use web3::types::Address;
+
+use vapor_chain::hex_to_address;
+use crate::database::helpers;
+use crate::models::{new_order_id, Order, OrderStatus, OrderType};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TransferError {
+    #[error("Invalid transfer: {0}")]
+    Invalid(String),
+    #[error("Transfer signature is not a 65-byte r || s || v signature")]
+    MalformedSignature,
+    #[error("Transfer is signed by {signer:?}, not by its from_address {from_address}")]
+    WrongSigner { signer: Address, from_address: String },
+    #[error("Nonce {nonce} was already used by {from_address}")]
+    NonceUsed { from_address: String, nonce: u64 },
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// An account-to-account transfer, signed by the sender with `personal_sign` over [`TransferRequest::message`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferRequest {
+    pub from_address: String,
+    pub to_address: String,
+    pub token_id: u32,
+    pub amount: String,
+    /// Any number the sender has not signed a transfer with before
+    pub nonce: u64,
+    /// 65-byte r || s || v signature, hex encoded
+    pub signature: String,
+}
+
+impl TransferRequest {
+    pub fn validate(&self) -> Result<(), TransferError> {
+        let from = hex_to_address(&self.from_address).map_err(|_| TransferError::Invalid("from_address is not an address".to_string()))?;
+        let to = hex_to_address(&self.to_address).map_err(|_| TransferError::Invalid("to_address is not an address".to_string()))?;
+        if from == to {
+            return Err(TransferError::Invalid("from_address and to_address are the same".to_string()));
+        }
+        if self.token_id == 0 {
+            return Err(TransferError::Invalid("token_id must be greater than 0".to_string()));
+        }
+        if !self.amount.parse::<u64>().is_ok_and(|amount| amount > 0) {
+            return Err(TransferError::Invalid("amount must be a positive integer".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The text the sender signs; naming the bridge keeps signatures from replaying on other deployments
+    pub fn message(&self, bridge_address: &str) -> String {
+        format!(
+            "Vapor transfer\nbridge: {}\nfrom: {}\nto: {}\ntoken: {}\namount: {}\nnonce: {}",
+            bridge_address.to_lowercase(),
+            self.from_address.to_lowercase(),
+            self.to_address.to_lowercase(),
+            self.token_id,
+            self.amount,
+            self.nonce,
+        )
+    }
+
+    /// Check the request is well formed and signed by its `from_address`
+    pub fn verify(&self, bridge_address: &str) -> Result<(), TransferError> {
+        self.validate()?;
+
+        let signature = hex::decode(self.signature.trim_start_matches("0x")).map_err(|_| TransferError::MalformedSignature)?;
+        if signature.len() != 65 {
+            return Err(TransferError::MalformedSignature);
+        }
+        // Wallets produce v as 27/28; some signers use the bare recovery id
+        let recovery_id = match signature[64] {
+            0 | 1 => signature[64] as i32,
+            27 | 28 => signature[64] as i32 - 27,
+            _ => return Err(TransferError::MalformedSignature),
+        };
+        let digest = hash_message(self.message(bridge_address).as_bytes());
+        let signer = signing::recover(digest.as_bytes(), &signature[..64], recovery_id).map_err(|_| TransferError::MalformedSignature)?;
+
+        if Some(signer) != hex_to_address(&self.from_address).ok() {
+            return Err(TransferError::WrongSigner { signer, from_address: self.from_address.clone() });
+        }
+        Ok(())
+    }
+
+    pub fn to_order(&self, now: DateTime<Utc>) -> Order {
+        Order {
+            id: new_order_id(),
+            order_type: OrderType::Transfer,
+            from_address: Some(self.from_address.to_lowercase()),
+            to_address: Some(self.to_address.to_lowercase()),
+            token_id: self.token_id,
+            amount: self.amount.clone(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            status: OrderStatus::Pending,
+            batch_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Claim the sender's nonce for `order_id`; each nonce is accepted once, even if the transfer then fails
+pub async fn reserve_nonce(db: &SqlitePool, from_address: &str, nonce: u64, order_id: &str, now: DateTime<Utc>) -> Result<(), TransferError> {
+    let result = sqlx::query("INSERT INTO transfer_nonces (from_address, nonce, order_id, created_at) VALUES (?, ?, ?, ?)")
+        .bind(from_address.to_lowercase())
+        .bind(nonce as i64)
+        .bind(order_id)
+        .bind(now)
+        .execute(db)
+        .await;
+    match result {
+        Ok(_) => Ok(()),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            Err(TransferError::NonceUsed { from_address: from_address.to_string(), nonce })
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Record which batch its orders landed in, and settle the signed transfers among them
+///
+/// Transfers move balances inside the batch itself, so there is nothing left to claim once
+/// the batch is finalized. Returns the settled transfer ids.
+pub async fn settle_batch(db: &SqlitePool, batch_id: u64, orders: &[Order], now: DateTime<Utc>) -> anyhow::Result<Vec<String>> {
+    let mut settled = Vec::new();
+    for order in orders {
+        sqlx::query("UPDATE orders SET batch_id = ? WHERE id = ? AND batch_id IS NULL")
+            .bind(batch_id as i64)
+            .bind(&order.id)
+            .execute(db)
+            .await?;
+
+        if order.order_type != OrderType::Transfer {
+            continue;
+        }
+        let result = sqlx::query(
+            "UPDATE orders SET status = ?1, updated_at = ?2 WHERE id = ?3 AND status = ?4 AND id IN (SELECT order_id FROM transfer_nonces)",
+        )
+        .bind(OrderStatus::Settled as i32)
+        .bind(now)
+        .bind(&order.id)
+        .bind(OrderStatus::Pending as i32)
+        .execute(db)
+        .await?;
+        if result.rows_affected() > 0 {
+            helpers::record_status_transition(db, &order.id, OrderStatus::Pending, OrderStatus::Settled, Some("batch_finalized")).await?;
+            settled.push(order.id.clone());
+        }
+    }
+    Ok(settled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vapor_chain::signer::{LocalKeySigner, Signer};
+
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const BRIDGE: &str = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+
+    async fn signed(signer: &LocalKeySigner, mut request: TransferRequest) -> TransferRequest {
+        let digest = hash_message(request.message(BRIDGE).as_bytes());
+        let signature = signer.sign_digest(digest, None).await.unwrap();
+        request.signature = format!(
+            "0x{}{}{:02x}",
+            hex::encode(signature.r.as_bytes()),
+            hex::encode(signature.s.as_bytes()),
+            signature.v
+        );
+        request
+    }
+
+    fn request(from_address: String) -> TransferRequest {
+        TransferRequest {
+            from_address,
+            to_address: "0x2222222222222222222222222222222222222222".to_string(),
+            token_id: 1,
+            amount: "300".to_string(),
+            nonce: 7,
+            signature: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_the_sender_can_sign() {
+        let signer = LocalKeySigner::from_hex(TEST_KEY).unwrap();
+        let sender = format!("{:?}", signer.address());
+        let transfer = signed(&signer, request(sender.clone())).await;
+        transfer.verify(BRIDGE).unwrap();
+        // Checksummed or not, it is the same address
+        TransferRequest { from_address: sender.to_uppercase().replace("0X", "0x"), ..transfer.clone() }.verify(BRIDGE).unwrap();
+
+        // Any signed field changed, or another deployment, breaks the signature
+        let tampered = TransferRequest { amount: "3000".to_string(), ..transfer.clone() };
+        assert!(matches!(tampered.verify(BRIDGE), Err(TransferError::WrongSigner { .. })));
+        assert!(matches!(transfer.verify("0x0000000000000000000000000000000000000001"), Err(TransferError::WrongSigner { .. })));
+
+        let someone_else = signed(&signer, request("0x1111111111111111111111111111111111111111".to_string())).await;
+        assert!(matches!(someone_else.verify(BRIDGE), Err(TransferError::WrongSigner { .. })));
+        let truncated = TransferRequest { signature: transfer.signature[..20].to_string(), ..transfer.clone() };
+        assert!(matches!(truncated.verify(BRIDGE), Err(TransferError::MalformedSignature)));
+        let to_self = TransferRequest { to_address: sender, ..transfer };
+        assert!(matches!(to_self.verify(BRIDGE), Err(TransferError::Invalid(_))));
+    }
+
+    #[tokio::test]
+    async fn test_nonces_are_single_use_per_sender() {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        let alice = "0x1111111111111111111111111111111111111111";
+
+        reserve_nonce(&db, alice, 1, "order_a", Utc::now()).await.unwrap();
+        let reused = reserve_nonce(&db, &alice.to_uppercase().replace("0X", "0x"), 1, "order_b", Utc::now()).await;
+        assert!(matches!(reused, Err(TransferError::NonceUsed { nonce: 1, .. })));
+        reserve_nonce(&db, alice, 2, "order_b", Utc::now()).await.unwrap();
+        reserve_nonce(&db, "0x2222222222222222222222222222222222222222", 1, "order_c", Utc::now()).await.unwrap();
+    }
+}
@@ -0,0 +1,261 @@
+//! Reuse of proof verification results
+//!
+//! Around settlement many fillers verify the same proofs against the same batch. Results are
+//! kept for a TTL keyed by a hash of the proof and a hash of its public inputs, and the public
+//! inputs of a batch (its id and finalized roots, read from the archive) are encoded once per
+//! batch rather than once per request.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use vapor_core::proof_format::{parse_hash32, ProofError, ProofFormat};
+use vapor_core::sparse_merkle_tree::solidity_keccak256_hash;
+
+/// Batches whose public inputs stay memoized; the oldest batch is dropped past it
+const MEMOIZED_BATCHES: usize = 256;
+
+/// What a cached verification result is looked up by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VerificationKey {
+    pub proof_hash: [u8; 32],
+    pub public_inputs_hash: [u8; 32],
+}
+
+/// A finalized batch's public inputs, encoded as
+/// `keccak256(uint256(batch_id) || state_root || orders_root)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchPublicInputs {
+    pub batch_id: u64,
+    pub state_root: [u8; 32],
+    pub orders_root: [u8; 32],
+    pub hash: [u8; 32],
+}
+
+impl BatchPublicInputs {
+    pub fn encode(batch_id: u64, state_root: &str, orders_root: &str) -> Result<Self, ProofError> {
+        let state_root = parse_hash32(state_root)?;
+        let orders_root = parse_hash32(orders_root)?;
+        let mut id = [0u8; 32];
+        id[24..].copy_from_slice(&batch_id.to_be_bytes());
+        let hash = solidity_keccak256_hash(&[&id, &state_root, &orders_root]);
+        Ok(Self { batch_id, state_root, orders_root, hash })
+    }
+
+    /// Whether `root` is one of the batch's roots
+    pub fn commits_to(&self, root: &[u8; 32]) -> bool {
+        *root == self.state_root || *root == self.orders_root
+    }
+}
+
+fn normalized(hash: &str) -> String {
+    hash.trim().trim_start_matches("0x").to_lowercase()
+}
+
+/// Hash of a proof's sibling list, insensitive to `0x` prefixes and hex case
+pub fn proof_hash(siblings: &[String]) -> [u8; 32] {
+    let joined = siblings.iter().map(|sibling| normalized(sibling)).collect::<Vec<_>>().join(",");
+    solidity_keccak256_hash(&[joined.as_bytes()])
+}
+
+/// Hash of everything a proof is verified against besides its siblings: the leaf, the root,
+/// the hash scheme, the leaf position and, when given, the batch
+pub fn public_inputs_hash(
+    leaf_hash: &str,
+    root: &str,
+    format: ProofFormat,
+    path_bits: Option<&[u8]>,
+    batch: Option<&BatchPublicInputs>,
+) -> [u8; 32] {
+    let scheme = [format as u8];
+    let position = match path_bits {
+        Some(path_bits) => [&[1u8][..], path_bits].concat(),
+        None => vec![0u8],
+    };
+    let batch = batch.map_or([0u8; 32], |batch| batch.hash);
+    solidity_keccak256_hash(&[
+        normalized(leaf_hash).as_bytes(),
+        b",",
+        normalized(root).as_bytes(),
+        &scheme,
+        &position,
+        &batch,
+    ])
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerificationCacheMetrics {
+    pub ttl_seconds: u64,
+    pub capacity: usize,
+    /// Results that have not expired yet
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Results dropped before expiring to stay within the capacity
+    pub evictions: u64,
+    /// Batches whose public inputs are memoized
+    pub memoized_batches: usize,
+    pub batch_input_hits: u64,
+    pub batch_input_misses: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<VerificationKey, (Instant, bool)>,
+    batch_inputs: BTreeMap<u64, BatchPublicInputs>,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    batch_input_hits: u64,
+    batch_input_misses: u64,
+}
+
+/// Shared TTL cache of proof verification results and memoized batch public inputs
+///
+/// A TTL of 0 disables the result cache; batch public inputs are memoized regardless, as
+/// finalized roots never change.
+#[derive(Clone, Default)]
+pub struct VerificationCache {
+    ttl: Duration,
+    capacity: usize,
+    inner: Arc<RwLock<CacheState>>,
+}
+
+impl VerificationCache {
+    pub fn new(ttl_seconds: u64, capacity: usize) -> Self {
+        Self { ttl: Duration::from_secs(ttl_seconds), capacity, inner: Arc::default() }
+    }
+
+    /// Cached result for a proof and its public inputs, if still fresh
+    pub fn get(&self, key: &VerificationKey) -> Option<bool> {
+        self.get_at(key, Instant::now())
+    }
+
+    /// Remember whether a proof verified against its public inputs
+    pub fn put(&self, key: VerificationKey, valid: bool) {
+        self.put_at(key, valid, Instant::now())
+    }
+
+    /// Memoized public inputs of a batch
+    pub fn batch_inputs(&self, batch_id: u64) -> Option<BatchPublicInputs> {
+        let mut state = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        let inputs = state.batch_inputs.get(&batch_id).copied();
+        match inputs {
+            Some(_) => state.batch_input_hits += 1,
+            None => state.batch_input_misses += 1,
+        }
+        inputs
+    }
+
+    pub fn remember_batch_inputs(&self, inputs: BatchPublicInputs) {
+        let mut state = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        state.batch_inputs.insert(inputs.batch_id, inputs);
+        while state.batch_inputs.len() > MEMOIZED_BATCHES {
+            state.batch_inputs.pop_first();
+        }
+    }
+
+    pub fn snapshot(&self) -> VerificationCacheMetrics {
+        let state = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        VerificationCacheMetrics {
+            ttl_seconds: self.ttl.as_secs(),
+            capacity: self.capacity,
+            entries: state.entries.values().filter(|(expires_at, _)| *expires_at > now).count(),
+            hits: state.hits,
+            misses: state.misses,
+            evictions: state.evictions,
+            memoized_batches: state.batch_inputs.len(),
+            batch_input_hits: state.batch_input_hits,
+            batch_input_misses: state.batch_input_misses,
+        }
+    }
+
+    fn get_at(&self, key: &VerificationKey, now: Instant) -> Option<bool> {
+        let mut state = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        let valid = match state.entries.get(key) {
+            Some((expires_at, valid)) if *expires_at > now => Some(*valid),
+            Some(_) => {
+                state.entries.remove(key);
+                None
+            }
+            None => None,
+        };
+        match valid {
+            Some(_) => state.hits += 1,
+            None => state.misses += 1,
+        }
+        valid
+    }
+
+    fn put_at(&self, key: VerificationKey, valid: bool, now: Instant) {
+        if self.ttl.is_zero() || self.capacity == 0 {
+            return;
+        }
+        let mut state = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.capacity {
+            state.entries.retain(|_, (expires_at, _)| *expires_at > now);
+            if state.entries.len() >= self.capacity {
+                let soonest = state.entries.iter().min_by_key(|(_, (expires_at, _))| *expires_at).map(|(key, _)| *key);
+                if let Some(soonest) = soonest {
+                    state.entries.remove(&soonest);
+                    state.evictions += 1;
+                }
+            }
+        }
+        state.entries.insert(key, (now + self.ttl, valid));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(seed: u8) -> VerificationKey {
+        VerificationKey { proof_hash: [seed; 32], public_inputs_hash: [0; 32] }
+    }
+
+    #[test]
+    fn test_results_are_reused_until_their_ttl_within_the_capacity() {
+        let cache = VerificationCache::new(60, 2);
+        let start = Instant::now();
+        assert_eq!(cache.get_at(&key(1), start), None);
+        cache.put_at(key(1), true, start);
+        cache.put_at(key(2), false, start + Duration::from_secs(1));
+        assert_eq!(cache.get_at(&key(1), start + Duration::from_secs(59)), Some(true));
+        assert_eq!(cache.get_at(&key(2), start + Duration::from_secs(59)), Some(false));
+        assert_eq!(cache.get_at(&key(1), start + Duration::from_secs(60)), None);
+
+        // Past the capacity the result closest to expiring goes first
+        cache.put_at(key(1), true, start + Duration::from_secs(2));
+        cache.put_at(key(3), true, start + Duration::from_secs(3));
+        assert_eq!(cache.get_at(&key(2), start + Duration::from_secs(3)), None);
+        assert_eq!(cache.get_at(&key(1), start + Duration::from_secs(3)), Some(true));
+
+        let metrics = cache.snapshot();
+        assert_eq!((metrics.hits, metrics.misses, metrics.evictions), (3, 3, 1));
+    }
+
+    #[test]
+    fn test_public_inputs_bind_the_batch_and_position() {
+        let root = format!("0x{}", "ab".repeat(32));
+        let batch = BatchPublicInputs::encode(7, &root, &format!("0x{}", "cd".repeat(32))).unwrap();
+        assert!(batch.commits_to(&parse_hash32(&root).unwrap()));
+        assert_ne!(batch.hash, BatchPublicInputs::encode(8, &root, &format!("0x{}", "cd".repeat(32))).unwrap().hash);
+
+        let leaf = format!("0x{}", "11".repeat(32));
+        let plain = public_inputs_hash(&leaf, &root, ProofFormat::Raw, Some(&[0, 1]), None);
+        assert_eq!(plain, public_inputs_hash(&leaf.to_uppercase().replace("0X", ""), &root, ProofFormat::Raw, Some(&[0, 1]), None));
+        assert_ne!(plain, public_inputs_hash(&leaf, &root, ProofFormat::Raw, Some(&[1, 0]), None));
+        assert_ne!(plain, public_inputs_hash(&leaf, &root, ProofFormat::SortedPairs, Some(&[0, 1]), None));
+        assert_ne!(plain, public_inputs_hash(&leaf, &root, ProofFormat::Raw, Some(&[0, 1]), Some(&batch)));
+
+        let cache = VerificationCache::new(0, 10);
+        assert_eq!(cache.batch_inputs(7), None);
+        cache.remember_batch_inputs(batch);
+        assert_eq!(cache.batch_inputs(7), Some(batch));
+        let metrics = cache.snapshot();
+        assert_eq!((metrics.memoized_batches, metrics.batch_input_hits, metrics.batch_input_misses), (1, 1, 1));
+    }
+}
@@ -0,0 +1,468 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use tracing::info;
+
+use crate::config::{WithdrawalConfig, WithdrawalLimits};
+use crate::models::{Order, OrderStatus, OrderType};
+use vapor_core::services::clock::{system_clock, SharedClock};
+
+/// A BridgeOut that is not permitted or would take an address or token over its daily cap
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(tag = "limit", rename_all = "snake_case")]
+pub enum WithdrawalLimitError {
+    #[error("address {address} is denylisted for bridge-out")]
+    Denylisted { address: String, reason: Option<String> },
+    #[error("address {address} is not on the bridge-out allowlist")]
+    NotAllowlisted { address: String },
+    #[error("address {address} has withdrawn {used} of token {token_id} today, {requested} more would exceed the {max} daily limit")]
+    PerAddressDaily { address: String, token_id: u32, used: u64, requested: u64, max: u64, remaining: u64 },
+    #[error("{used} of token {token_id} has been withdrawn today, {requested} more would exceed the {max} global daily limit")]
+    GlobalDaily { token_id: u32, used: u64, requested: u64, max: u64, remaining: u64 },
+}
+
+/// Errors from checking a BridgeOut order against the lists and limits
+#[derive(Debug, thiserror::Error)]
+pub enum WithdrawalError {
+    #[error(transparent)]
+    Limit(#[from] WithdrawalLimitError),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressList {
+    Allow,
+    Deny,
+}
+
+impl AddressList {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AddressList::Allow => "allow",
+            AddressList::Deny => "deny",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "allow" => Some(AddressList::Allow),
+            "deny" => Some(AddressList::Deny),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressListEntry {
+    pub address: String,
+    pub list: AddressList,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Today's BridgeOut usage of a token, for an address and overall
+#[derive(Debug, Clone, Serialize)]
+pub struct WithdrawalUsage {
+    pub address: String,
+    pub token_id: u32,
+    pub list: Option<AddressList>,
+    pub limits: WithdrawalLimits,
+    pub address_used: u64,
+    pub global_used: u64,
+}
+
+/// Address a BridgeOut is attributed to: the on-chain recipient, lowercased
+pub fn withdrawal_address(order: &Order) -> Option<String> {
+    order.to_address.as_deref().map(|address| address.to_lowercase())
+}
+
+/// Check whether `amount` more can be withdrawn given today's usage (0 limits are unlimited)
+pub fn check_withdrawal_limits(
+    address: &str,
+    token_id: u32,
+    limits: &WithdrawalLimits,
+    address_used: u64,
+    global_used: u64,
+    amount: u64,
+) -> Result<(), WithdrawalLimitError> {
+    if limits.per_address_daily > 0 && address_used.saturating_add(amount) > limits.per_address_daily {
+        return Err(WithdrawalLimitError::PerAddressDaily {
+            address: address.to_string(),
+            token_id,
+            used: address_used,
+            requested: amount,
+            max: limits.per_address_daily,
+            remaining: limits.per_address_daily.saturating_sub(address_used),
+        });
+    }
+
+    if limits.global_daily > 0 && global_used.saturating_add(amount) > limits.global_daily {
+        return Err(WithdrawalLimitError::GlobalDaily {
+            token_id,
+            used: global_used,
+            requested: amount,
+            max: limits.global_daily,
+            remaining: limits.global_daily.saturating_sub(global_used),
+        });
+    }
+
+    Ok(())
+}
+
+/// Check a new BridgeOut order against the allow/deny lists and today's created BridgeOut volume
+pub async fn check_bridge_out(db: &SqlitePool, config: &WithdrawalConfig, order: &Order, now: DateTime<Utc>) -> Result<(), WithdrawalError> {
+    let Some(address) = withdrawal_address(order) else {
+        return Ok(());
+    };
+
+    // The debited account is screened too, the limits follow the recipient
+    let mut screened = vec![address.clone()];
+    screened.extend(order.from_address.as_deref().map(|from| from.to_lowercase()).filter(|from| *from != address));
+    for candidate in &screened {
+        if let Some(entry) = get_list_entry(db, candidate).await? {
+            if entry.list == AddressList::Deny {
+                return Err(WithdrawalLimitError::Denylisted { address: entry.address, reason: entry.reason }.into());
+            }
+        }
+    }
+
+    if config.allowlist_only {
+        let allowlisted = get_list_entry(db, &address).await?.is_some_and(|entry| entry.list == AddressList::Allow);
+        if !allowlisted {
+            return Err(WithdrawalLimitError::NotAllowlisted { address }.into());
+        }
+    }
+
+    let usage = daily_usage(db, config, &address, order.token_id, now).await?;
+    let amount = order.amount.parse().unwrap_or(0);
+    check_withdrawal_limits(&address, order.token_id, &usage.limits, usage.address_used, usage.global_used, amount)?;
+    Ok(())
+}
+
+/// BridgeOut volume of a token created since the start of `now`'s day (UTC), excluding failed orders
+pub async fn daily_usage(
+    db: &SqlitePool,
+    config: &WithdrawalConfig,
+    address: &str,
+    token_id: u32,
+    now: DateTime<Utc>,
+) -> Result<WithdrawalUsage, sqlx::Error> {
+    let address = address.to_lowercase();
+    let start_of_day = now.date_naive().and_time(NaiveTime::MIN).and_utc();
+
+    let row = sqlx::query(
+        r#"
+        SELECT COALESCE(SUM(CAST(amount AS INTEGER)), 0) AS global_used,
+               COALESCE(SUM(CASE WHEN LOWER(to_address) = ?1 THEN CAST(amount AS INTEGER) ELSE 0 END), 0) AS address_used
+        FROM orders
+        WHERE order_type = ?2 AND token_id = ?3 AND status != ?4 AND created_at >= ?5
+        "#,
+    )
+    .bind(&address)
+    .bind(OrderType::BridgeOut as i32)
+    .bind(token_id as i32)
+    .bind(OrderStatus::Failed as i32)
+    .bind(start_of_day)
+    .fetch_one(db)
+    .await?;
+
+    let address_used = u64::try_from(row.try_get::<i64, _>("address_used")?).unwrap_or(0);
+    let global_used = u64::try_from(row.try_get::<i64, _>("global_used")?).unwrap_or(0);
+
+    let list = get_list_entry(db, &address).await?.map(|entry| entry.list);
+    Ok(WithdrawalUsage {
+        address,
+        token_id,
+        list,
+        limits: config.limits_for(token_id),
+        address_used,
+        global_used,
+    })
+}
+
+pub async fn get_list_entry(db: &SqlitePool, address: &str) -> Result<Option<AddressListEntry>, sqlx::Error> {
+    let row = sqlx::query("SELECT address, list, reason, created_at FROM withdrawal_address_lists WHERE address = ?")
+        .bind(address.to_lowercase())
+        .fetch_optional(db)
+        .await?;
+
+    row.map(|row| entry_from_row(&row)).transpose()
+}
+
+pub async fn list_entries(db: &SqlitePool) -> Result<Vec<AddressListEntry>, sqlx::Error> {
+    let rows = sqlx::query("SELECT address, list, reason, created_at FROM withdrawal_address_lists ORDER BY created_at")
+        .fetch_all(db)
+        .await?;
+
+    rows.iter().map(entry_from_row).collect()
+}
+
+/// Put an address on the allowlist or denylist, replacing any previous entry
+pub async fn set_list_entry(db: &SqlitePool, address: &str, list: AddressList, reason: Option<String>) -> Result<AddressListEntry, sqlx::Error> {
+    let entry = AddressListEntry {
+        address: address.to_lowercase(),
+        list,
+        reason,
+        created_at: Utc::now(),
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO withdrawal_address_lists (address, list, reason, created_at)
+        VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT(address) DO UPDATE SET list = ?2, reason = ?3, created_at = ?4
+        "#,
+    )
+    .bind(&entry.address)
+    .bind(entry.list.as_str())
+    .bind(&entry.reason)
+    .bind(entry.created_at)
+    .execute(db)
+    .await?;
+
+    info!("Added {} to the bridge-out {}list", entry.address, entry.list.as_str());
+    Ok(entry)
+}
+
+/// Remove an address from whichever list it is on; returns false if it was on none
+pub async fn remove_list_entry(db: &SqlitePool, address: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM withdrawal_address_lists WHERE address = ?")
+        .bind(address.to_lowercase())
+        .execute(db)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+fn entry_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<AddressListEntry, sqlx::Error> {
+    let list: String = row.try_get("list")?;
+    Ok(AddressListEntry {
+        address: row.try_get("address")?,
+        list: AddressList::parse(&list).ok_or_else(|| sqlx::Error::Decode(format!("unknown address list {}", list).into()))?,
+        reason: row.try_get("reason")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+/// BridgeOut volume already included in batches today, enforced again at batch inclusion
+///
+/// Creation-time checks read committed orders, so concurrent requests can each pass them;
+/// this keeps the batch itself within the daily limits.
+#[derive(Debug, Clone)]
+pub struct WithdrawalTracker {
+    config: WithdrawalConfig,
+    clock: SharedClock,
+    day: NaiveDate,
+    per_address: HashMap<(u32, String), u64>,
+    global: HashMap<u32, u64>,
+}
+
+impl WithdrawalTracker {
+    pub fn new(config: WithdrawalConfig) -> Self {
+        Self {
+            config,
+            clock: system_clock(),
+            day: Utc::now().date_naive(),
+            per_address: HashMap::new(),
+            global: HashMap::new(),
+        }
+    }
+
+    /// Read the day the limits apply to from `clock`
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.day = clock.now().date_naive();
+        self.clock = clock;
+        self
+    }
+
+    /// Check that a BridgeOut order fits in today's remaining limits
+    pub fn check(&mut self, order: &Order) -> Result<(), WithdrawalLimitError> {
+        self.roll_over(self.clock.now().date_naive());
+        let Some(address) = withdrawal_address(order) else {
+            return Ok(());
+        };
+
+        let limits = self.config.limits_for(order.token_id);
+        let address_used = self.per_address.get(&(order.token_id, address.clone())).copied().unwrap_or(0);
+        let global_used = self.global.get(&order.token_id).copied().unwrap_or(0);
+        check_withdrawal_limits(&address, order.token_id, &limits, address_used, global_used, order.amount.parse().unwrap_or(0))
+    }
+
+    /// Count an included BridgeOut order towards today's usage
+    pub fn record(&mut self, order: &Order) {
+        let Some(address) = withdrawal_address(order) else {
+            return;
+        };
+
+        let amount: u64 = order.amount.parse().unwrap_or(0);
+        let used = self.per_address.entry((order.token_id, address)).or_insert(0);
+        *used = used.saturating_add(amount);
+        let used = self.global.entry(order.token_id).or_insert(0);
+        *used = used.saturating_add(amount);
+    }
+
+    /// Take back an order's usage when the batch that included it is aborted
+    pub fn forget(&mut self, order: &Order) {
+        let Some(address) = withdrawal_address(order) else {
+            return;
+        };
+
+        let amount: u64 = order.amount.parse().unwrap_or(0);
+        if let Some(used) = self.per_address.get_mut(&(order.token_id, address)) {
+            *used = used.saturating_sub(amount);
+        }
+        if let Some(used) = self.global.get_mut(&order.token_id) {
+            *used = used.saturating_sub(amount);
+        }
+    }
+
+    fn roll_over(&mut self, today: NaiveDate) {
+        if today != self.day {
+            self.day = today;
+            self.per_address.clear();
+            self.global.clear();
+        }
+    }
+}
+
+impl Default for WithdrawalTracker {
+    fn default() -> Self {
+        Self::new(WithdrawalConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::helpers;
+
+    fn bridge_out(id: &str, to: &str, amount: &str) -> Order {
+        Order {
+            id: id.to_string(),
+            order_type: OrderType::BridgeOut,
+            from_address: None,
+            to_address: Some(to.to_string()),
+            token_id: 1,
+            amount: amount.to_string(),
+            bank_account: None,
+            bank_service: None,
+            banking_hash: None,
+            filler_id: None,
+            locked_amount: None,
+            status: OrderStatus::Pending,
+            batch_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn config(per_address_daily: u64, global_daily: u64) -> WithdrawalConfig {
+        WithdrawalConfig {
+            default_limits: WithdrawalLimits { per_address_daily, global_daily },
+            token_limits: HashMap::new(),
+            allowlist_only: false,
+        }
+    }
+
+    async fn setup() -> SqlitePool {
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+        db
+    }
+
+    #[test]
+    fn test_check_withdrawal_limits() {
+        let limits = WithdrawalLimits { per_address_daily: 100, global_daily: 250 };
+
+        assert!(check_withdrawal_limits("0xa", 1, &limits, 60, 60, 40).is_ok());
+        assert!(check_withdrawal_limits("0xa", 1, &WithdrawalLimits::default(), u64::MAX, u64::MAX, 1).is_ok());
+
+        let err = check_withdrawal_limits("0xa", 1, &limits, 60, 60, 41).unwrap_err();
+        assert!(matches!(err, WithdrawalLimitError::PerAddressDaily { remaining: 40, .. }));
+
+        let err = check_withdrawal_limits("0xa", 1, &limits, 0, 200, 51).unwrap_err();
+        assert!(matches!(err, WithdrawalLimitError::GlobalDaily { remaining: 50, .. }));
+        assert_eq!(serde_json::to_value(&err).unwrap()["limit"], "global_daily");
+    }
+
+    #[tokio::test]
+    async fn test_daily_limits_count_todays_bridge_outs() {
+        let db = setup().await;
+        let config = config(100, 150);
+
+        helpers::insert_order(&db, &bridge_out("out-1", "0xAAAA", "80")).await.unwrap();
+        helpers::insert_order(&db, &bridge_out("out-2", "0xbbbb", "50")).await.unwrap();
+        let mut failed = bridge_out("out-3", "0xaaaa", "1000");
+        failed.status = OrderStatus::Failed;
+        helpers::insert_order(&db, &failed).await.unwrap();
+
+        let usage = daily_usage(&db, &config, "0xaaaa", 1, Utc::now()).await.unwrap();
+        assert_eq!((usage.address_used, usage.global_used), (80, 130));
+
+        // The day comes from the caller's clock, so tomorrow starts from zero
+        let usage = daily_usage(&db, &config, "0xaaaa", 1, Utc::now() + chrono::Duration::days(1)).await.unwrap();
+        assert_eq!((usage.address_used, usage.global_used), (0, 0));
+
+        assert!(check_bridge_out(&db, &config, &bridge_out("new", "0xaaaa", "20"), Utc::now()).await.is_ok());
+        assert!(matches!(
+            check_bridge_out(&db, &config, &bridge_out("new", "0xaaaa", "21"), Utc::now()).await,
+            Err(WithdrawalError::Limit(WithdrawalLimitError::PerAddressDaily { .. }))
+        ));
+        assert!(matches!(
+            check_bridge_out(&db, &config, &bridge_out("new", "0xcccc", "21"), Utc::now()).await,
+            Err(WithdrawalError::Limit(WithdrawalLimitError::GlobalDaily { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_allow_and_deny_lists() {
+        let db = setup().await;
+        let mut config = config(0, 0);
+
+        set_list_entry(&db, "0xDDDD", AddressList::Deny, Some("sanctions".to_string())).await.unwrap();
+        assert!(matches!(
+            check_bridge_out(&db, &config, &bridge_out("new", "0xdddd", "1"), Utc::now()).await,
+            Err(WithdrawalError::Limit(WithdrawalLimitError::Denylisted { .. }))
+        ));
+
+        // A denylisted source account cannot withdraw to a clean address either
+        let mut order = bridge_out("new", "0xeeee", "1");
+        order.from_address = Some("0xdddd".to_string());
+        assert!(check_bridge_out(&db, &config, &order, Utc::now()).await.is_err());
+
+        config.allowlist_only = true;
+        assert!(matches!(
+            check_bridge_out(&db, &config, &bridge_out("new", "0xeeee", "1"), Utc::now()).await,
+            Err(WithdrawalError::Limit(WithdrawalLimitError::NotAllowlisted { .. }))
+        ));
+        set_list_entry(&db, "0xeeee", AddressList::Allow, None).await.unwrap();
+        assert!(check_bridge_out(&db, &config, &bridge_out("new", "0xeeee", "1"), Utc::now()).await.is_ok());
+
+        assert_eq!(list_entries(&db).await.unwrap().len(), 2);
+        assert!(remove_list_entry(&db, "0xEEEE").await.unwrap());
+        assert!(!remove_list_entry(&db, "0xeeee").await.unwrap());
+    }
+
+    #[test]
+    fn test_tracker_enforces_limits_on_included_orders() {
+        use vapor_core::services::clock::MockClock;
+        use chrono::TimeZone;
+
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 1, 1, 23, 59, 0).unwrap());
+        let mut tracker = WithdrawalTracker::new(config(100, 0)).with_clock(clock.shared());
+
+        let first = bridge_out("out-1", "0xaaaa", "70");
+        tracker.check(&first).unwrap();
+        tracker.record(&first);
+
+        assert!(tracker.check(&bridge_out("out-2", "0xAAAA", "31")).is_err());
+        assert!(tracker.check(&bridge_out("out-2", "0xbbbb", "31")).is_ok());
+
+        // Usage resets at the start of the next UTC day
+        clock.advance(chrono::Duration::minutes(1));
+        assert!(tracker.check(&bridge_out("out-2", "0xaaaa", "31")).is_ok());
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::Result;
+use std::sync::Arc;
+use tracing::info;
+use vapor_chain::signer::{KeystoreSigner, LocalKeySigner, RemoteSigner, Signer};
+
+use crate::config::{BlockchainConfig, SignerConfig};
+
+/// Build the signer selected by SIGNER_TYPE
+pub fn signer_from_config(signer: &SignerConfig, blockchain: &BlockchainConfig) -> Result<Arc<dyn Signer>> {
+    let signer: Arc<dyn Signer> = match signer.kind.as_str() {
+        "env" => {
+            if blockchain.private_key.is_empty() {
+                return Err(anyhow::anyhow!("PRIVATE_KEY environment variable required for env signer"));
+            }
+            Arc::new(LocalKeySigner::from_hex(&blockchain.private_key)?)
+        }
+        "keystore" => {
+            let path = signer.keystore_path.as_deref()
+                .ok_or_else(|| anyhow::anyhow!("SIGNER_KEYSTORE_PATH required for keystore signer"))?;
+            let password = signer.keystore_password.as_deref()
+                .ok_or_else(|| anyhow::anyhow!("SIGNER_KEYSTORE_PASSWORD required for keystore signer"))?;
+            Arc::new(KeystoreSigner::open(path, password)?)
+        }
+        "remote" => {
+            let url = signer.remote_url.clone()
+                .ok_or_else(|| anyhow::anyhow!("SIGNER_REMOTE_URL required for remote signer"))?;
+            let key_id = signer.remote_key_id.clone()
+                .ok_or_else(|| anyhow::anyhow!("SIGNER_REMOTE_KEY_ID required for remote signer"))?;
+            let address = signer.remote_address.as_deref()
+                .ok_or_else(|| anyhow::anyhow!("SIGNER_REMOTE_ADDRESS required for remote signer"))?;
+            let address = vapor_chain::hex_to_address(address)?;
+            Arc::new(RemoteSigner::new(url, key_id, signer.remote_auth_token.clone(), address))
+        }
+        other => return Err(anyhow::anyhow!("Unknown SIGNER_TYPE '{}', expected env, keystore or remote", other)),
+    };
+
+    info!("Using {} signer with address {:?}", signer.kind(), signer.address());
+    Ok(signer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    // Anvil's first default account
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const TEST_ADDRESS: &str = "f39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+
+    #[test]
+    fn test_signer_from_config() {
+        let mut config = Config::default();
+        config.blockchain.private_key = TEST_KEY.to_string();
+
+        let signer = signer_from_config(&config.signer, &config.blockchain).unwrap();
+        assert_eq!(signer.kind(), "env");
+
+        config.signer.kind = "keystore".to_string();
+        assert!(signer_from_config(&config.signer, &config.blockchain).is_err());
+
+        config.signer.kind = "remote".to_string();
+        config.signer.remote_url = Some("http://localhost:9000".to_string());
+        config.signer.remote_key_id = Some("relayer".to_string());
+        config.signer.remote_address = Some(format!("0x{}", TEST_ADDRESS));
+        let signer = signer_from_config(&config.signer, &config.blockchain).unwrap();
+        assert_eq!(signer.kind(), "remote");
+        assert_eq!(hex::encode(signer.address().as_bytes()), TEST_ADDRESS);
+
+        config.signer.kind = "plaintext".to_string();
+        assert!(signer_from_config(&config.signer, &config.blockchain).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use opentelemetry::{trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+use tracing::{info, level_filters::LevelFilter, warn};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::config::TelemetryConfig;
+
+/// Install the global tracing subscriber
+///
+/// Logs always go to stdout. When an OTLP endpoint is configured, spans are also exported
+/// so an order's journey (HTTP handling, DB queries, matching, batching, proving and chain
+/// submission) can be followed by its `order_id` / `batch_id` attributes in Jaeger or Tempo.
+/// The returned provider must be shut down on exit to flush pending spans.
+pub fn init(config: &TelemetryConfig) -> Result<Option<TracerProvider>> {
+    let provider = config.otlp_endpoint.as_deref()
+        .map(|endpoint| build_provider(endpoint, &config.service_name))
+        .transpose()?;
+
+    let otel_layer = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer("vapor-backend"))
+    });
+
+    tracing_subscriber::registry()
+        .with(LevelFilter::INFO)
+        .with(tracing_subscriber::fmt::layer())
+        .with(otel_layer)
+        .try_init()?;
+
+    if let Some(endpoint) = &config.otlp_endpoint {
+        info!("Exporting traces to {} as {}", endpoint, config.service_name);
+    }
+
+    Ok(provider)
+}
+
+/// Flush and stop the exporter
+pub fn shutdown(provider: Option<TracerProvider>) {
+    if let Some(provider) = provider {
+        if let Err(e) = provider.shutdown() {
+            warn!("Failed to flush traces: {}", e);
+        }
+    }
+}
+
+fn build_provider(endpoint: &str, service_name: &str) -> Result<TracerProvider> {
+    let exporter = SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()?;
+
+    Ok(TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new(vec![KeyValue::new("service.name", service_name.to_string())]))
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use opentelemetry_sdk::export::trace::{ExportResult, SpanData};
+    use sqlx::SqlitePool;
+    use std::sync::{Arc, Mutex};
+    use tracing::Instrument;
+
+    /// Keeps exported spans in memory
+    #[derive(Debug, Clone, Default)]
+    struct CollectingExporter {
+        spans: Arc<Mutex<Vec<SpanData>>>,
+    }
+
+    impl opentelemetry_sdk::export::trace::SpanExporter for CollectingExporter {
+        fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+            self.spans.lock().unwrap().extend(batch);
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_db_spans_carry_order_id_and_parent() {
+        let exporter = CollectingExporter::default();
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let db = SqlitePool::connect(":memory:").await.unwrap();
+        crate::database::run_migrations(&db).await.unwrap();
+
+        crate::database::helpers::get_order_by_id(&db, "order-1")
+            .instrument(tracing::info_span!("request"))
+            .await
+            .unwrap();
+
+        let spans = exporter.spans.lock().unwrap();
+        let request = spans.iter().find(|span| span.name == "request").unwrap();
+        let query = spans.iter().find(|span| span.name == "get_order_by_id").unwrap();
+
+        assert_eq!(query.parent_span_id, request.span_context.span_id());
+        assert_eq!(query.span_context.trace_id(), request.span_context.trace_id());
+        let attribute = |key: &str| query.attributes.iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| kv.value.as_str().to_string());
+        assert_eq!(attribute("order_id").as_deref(), Some("order-1"));
+        assert_eq!(attribute("db.system").as_deref(), Some("sqlite"));
+    }
+}